@@ -0,0 +1,265 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Keeps the node's advertised public address up to date for nodes on dynamic IPs.
+//!
+//! The monitor periodically requests a configured URL that echoes the caller's IP address as plain text over HTTP
+//! (e.g. `http://api.ipify.org/`). When the reported IP differs from the one in the node's public address, the
+//! address is updated, the node identity is saved and the node re-announces itself with a DHT join. Addresses that do
+//! not contain an IP (e.g. onion addresses when using tor) are never changed.
+
+use crate::builder::save_identity;
+use futures::StreamExt;
+use log::*;
+use std::{net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
+use tari_comms::{
+    multiaddr::{Multiaddr, Protocol},
+    NodeIdentity,
+};
+use tari_comms_dht::DhtRequester;
+use tari_shutdown::ShutdownSignal;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time,
+};
+
+const LOG_TARGET: &str = "base_node::address_monitor";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// IP echo responses are tiny, anything bigger than this is not what we asked for
+const MAX_RESPONSE_SIZE: u64 = 4096;
+
+pub struct PublicAddressMonitor {
+    node_identity: Arc<NodeIdentity>,
+    identity_file: PathBuf,
+    identity_passphrase: Option<String>,
+    dht_requester: DhtRequester,
+    check_url: String,
+    check_interval: Duration,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl PublicAddressMonitor {
+    pub fn new(
+        node_identity: Arc<NodeIdentity>,
+        identity_file: PathBuf,
+        identity_passphrase: Option<String>,
+        dht_requester: DhtRequester,
+        check_url: String,
+        check_interval: Duration,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self
+    {
+        Self {
+            node_identity,
+            identity_file,
+            identity_passphrase,
+            dht_requester,
+            check_url,
+            check_interval,
+            shutdown_signal,
+        }
+    }
+
+    pub async fn run(mut self) {
+        info!(
+            target: LOG_TARGET,
+            "Public address monitor started. Checking '{}' every {:.0?}", self.check_url, self.check_interval
+        );
+        let mut interval = time::interval(self.check_interval).fuse();
+        let mut shutdown_signal = self.shutdown_signal.clone();
+        loop {
+            futures::select! {
+                _ = interval.select_next_some() => {
+                    if let Err(err) = self.check_public_address().await {
+                        warn!(target: LOG_TARGET, "Public address check failed: {}", err);
+                    }
+                },
+                _ = shutdown_signal => {
+                    info!(target: LOG_TARGET, "Public address monitor shutting down because the shutdown signal was received");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn check_public_address(&mut self) -> Result<(), String> {
+        let ip = match time::timeout(REQUEST_TIMEOUT, fetch_public_ip(&self.check_url)).await {
+            Ok(result) => result?,
+            Err(_) => return Err("Timed out waiting for public IP".to_string()),
+        };
+        let current = self.node_identity.public_address();
+        let new_address = match replace_ip(&current, ip) {
+            Some(addr) if addr != current => addr,
+            _ => return Ok(()),
+        };
+
+        info!(
+            target: LOG_TARGET,
+            "Public IP changed. Updating public address from '{}' to '{}'", current, new_address
+        );
+        self.node_identity
+            .set_public_address(new_address)
+            .map_err(|err| format!("Failed to set public address: {:?}", err))?;
+        save_identity(
+            &self.identity_file,
+            &*self.node_identity,
+            self.identity_passphrase.as_deref(),
+        )?;
+        self.dht_requester
+            .send_join()
+            .await
+            .map_err(|err| format!("Failed to re-announce node: {:?}", err))
+    }
+}
+
+/// Replace the IP component of `address` with `ip`, keeping the rest of the address (e.g. the TCP port). Returns None
+/// if the address has no IP component.
+fn replace_ip(address: &Multiaddr, ip: IpAddr) -> Option<Multiaddr> {
+    let mut replaced = false;
+    let new_address = address
+        .iter()
+        .map(|protocol| match protocol {
+            Protocol::Ip4(_) | Protocol::Ip6(_) if !replaced => {
+                replaced = true;
+                match ip {
+                    IpAddr::V4(ip) => Protocol::Ip4(ip),
+                    IpAddr::V6(ip) => Protocol::Ip6(ip),
+                }
+            },
+            p => p,
+        })
+        .collect::<Multiaddr>();
+    if replaced {
+        Some(new_address)
+    } else {
+        None
+    }
+}
+
+/// Issue a plain HTTP GET to `url` and parse the response body as an IP address
+async fn fetch_public_ip(url: &str) -> Result<IpAddr, String> {
+    let (host, port, path) = parse_http_url(url)?;
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .map_err(|err| format!("Could not connect to '{}': {}", url, err))?;
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: tari_base_node\r\nConnection: close\r\n\r\n",
+        path, host
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|err| err.to_string())?;
+    let mut response = Vec::new();
+    stream
+        .take(MAX_RESPONSE_SIZE)
+        .read_to_end(&mut response)
+        .await
+        .map_err(|err| err.to_string())?;
+    parse_http_response(&String::from_utf8_lossy(&response))
+}
+
+/// Split a `http://host[:port][/path]` URL into its parts
+pub(crate) fn parse_http_url(url: &str) -> Result<(&str, u16, &str), String> {
+    const SCHEME: &str = "http://";
+    if !url.starts_with(SCHEME) {
+        return Err(format!(
+            "Only http:// URLs are supported for public address checks, got '{}'",
+            url
+        ));
+    }
+    let rest = &url[SCHEME.len()..];
+    let (authority, path) = match rest.find('/') {
+        Some(pos) => (&rest[..pos], &rest[pos..]),
+        None => (rest, "/"),
+    };
+    match authority.rfind(':') {
+        Some(pos) => {
+            let port = authority[pos + 1..]
+                .parse()
+                .map_err(|_| format!("Invalid port in '{}'", url))?;
+            Ok((&authority[..pos], port, path))
+        },
+        None => Ok((authority, 80, path)),
+    }
+}
+
+fn parse_http_response(response: &str) -> Result<IpAddr, String> {
+    let status_ok = response
+        .lines()
+        .next()
+        .and_then(|status_line| status_line.split_whitespace().nth(1))
+        .map(|code| code == "200")
+        .unwrap_or(false);
+    if !status_ok {
+        return Err(format!(
+            "Unexpected response '{}'",
+            response.lines().next().unwrap_or_default()
+        ));
+    }
+    let body = response.splitn(2, "\r\n\r\n").nth(1).unwrap_or_default();
+    body.trim()
+        .parse()
+        .map_err(|_| format!("Response body '{}' is not an IP address", body.trim()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn replace_ip_keeps_port() {
+        let address = "/ip4/1.2.3.4/tcp/18189".parse::<Multiaddr>().unwrap();
+        let new_address = replace_ip(&address, "5.6.7.8".parse().unwrap()).unwrap();
+        assert_eq!(new_address, "/ip4/5.6.7.8/tcp/18189".parse::<Multiaddr>().unwrap());
+
+        let new_address = replace_ip(&address, "::1".parse().unwrap()).unwrap();
+        assert_eq!(new_address, "/ip6/::1/tcp/18189".parse::<Multiaddr>().unwrap());
+
+        let onion = "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"
+            .parse::<Multiaddr>()
+            .unwrap();
+        assert!(replace_ip(&onion, "5.6.7.8".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn parse_url() {
+        assert_eq!(
+            parse_http_url("http://api.ipify.org").unwrap(),
+            ("api.ipify.org", 80, "/")
+        );
+        assert_eq!(
+            parse_http_url("http://127.0.0.1:8080/ip").unwrap(),
+            ("127.0.0.1", 8080, "/ip")
+        );
+        assert!(parse_http_url("https://api.ipify.org").is_err());
+    }
+
+    #[test]
+    fn parse_response() {
+        let ip = parse_http_response("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n5.6.7.8\n").unwrap();
+        assert_eq!(ip, "5.6.7.8".parse::<IpAddr>().unwrap());
+        assert!(parse_http_response("HTTP/1.1 500 Internal Server Error\r\n\r\n5.6.7.8").is_err());
+        assert!(parse_http_response("HTTP/1.1 200 OK\r\n\r\n<html></html>").is_err());
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! User-defined command aliases for the base node CLI.
+//!
+//! Aliases are read from `cli_aliases.toml` in the base path (`~/.tari` by default). Each key is an alias name and its
+//! value is the command line it expands to. Several commands can be run in sequence by separating them with `;`, and
+//! aliases may refer to other aliases:
+//!
+//! ```toml
+//! st = "get-network-status"
+//! bans = "list-peers --banned"
+//! checkup = "st; get-mempool-stats; bans"
+//! ```
+//!
+//! Any arguments typed after an alias are appended to the last command of its expansion. Aliases cannot replace the
+//! built-in commands.
+
+use config::{Config, File, FileFormat};
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+/// The name of the aliases file in the base path
+pub const ALIASES_FILE_NAME: &str = "cli_aliases.toml";
+/// Aliases that expand to other aliases are followed at most this many levels deep
+const MAX_EXPANSION_DEPTH: usize = 8;
+
+#[derive(Debug, PartialEq)]
+pub enum AliasError {
+    /// The aliases file could not be read or is not valid TOML
+    InvalidFile(String),
+    /// The alias expands to itself, directly or through other aliases
+    Recursive(String),
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AliasError::InvalidFile(e) => write!(f, "Invalid aliases file: {}", e),
+            AliasError::Recursive(name) => write!(f, "The alias '{}' expands to itself", name),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CommandAliases {
+    aliases: HashMap<String, String>,
+}
+
+impl CommandAliases {
+    /// Load the aliases from the file at `path`. A missing file defines no aliases. Aliases named after one of
+    /// `builtin_commands` are ignored, and their names are returned alongside the aliases.
+    pub fn load(path: &Path, builtin_commands: &[String]) -> Result<(Self, Vec<String>), AliasError> {
+        if !path.exists() {
+            return Ok((Self::default(), Vec::new()));
+        }
+        let contents = fs::read_to_string(path).map_err(|e| AliasError::InvalidFile(e.to_string()))?;
+        Self::from_toml(&contents, builtin_commands)
+    }
+
+    /// Parse the aliases from the contents of an aliases file
+    pub fn from_toml(contents: &str, builtin_commands: &[String]) -> Result<(Self, Vec<String>), AliasError> {
+        let mut cfg = Config::new();
+        cfg.merge(File::from_str(contents, FileFormat::Toml))
+            .map_err(|e| AliasError::InvalidFile(e.to_string()))?;
+        let values = cfg.collect().map_err(|e| AliasError::InvalidFile(e.to_string()))?;
+
+        let mut aliases = HashMap::new();
+        let mut ignored = Vec::new();
+        for (name, value) in values {
+            let expansion = value
+                .into_str()
+                .map_err(|e| AliasError::InvalidFile(format!("{}: {}", name, e)))?;
+            if builtin_commands.contains(&name) {
+                ignored.push(name);
+            } else {
+                aliases.insert(name, expansion);
+            }
+        }
+        ignored.sort();
+        Ok((Self { aliases }, ignored))
+    }
+
+    /// The names of all aliases in alphabetical order
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.aliases.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Expand the aliases in a command line, returning the commands to run in order. A line that does not start with
+    /// an alias is returned unchanged.
+    pub fn expand(&self, line: &str) -> Result<Vec<String>, AliasError> {
+        let mut commands = Vec::new();
+        self.expand_command(line.trim(), 0, &mut commands)?;
+        Ok(commands)
+    }
+
+    fn expand_command(&self, command: &str, depth: usize, commands: &mut Vec<String>) -> Result<(), AliasError> {
+        let mut words = command.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let expansion = match self.aliases.get(name) {
+            Some(expansion) => expansion,
+            None => {
+                commands.push(command.to_string());
+                return Ok(());
+            },
+        };
+        if depth >= MAX_EXPANSION_DEPTH {
+            return Err(AliasError::Recursive(name.to_string()));
+        }
+
+        let args = words.collect::<Vec<_>>();
+        let mut expanded = expansion
+            .split(';')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<_>>();
+        if let Some(last) = expanded.last_mut() {
+            for arg in args {
+                last.push(' ');
+                last.push_str(arg);
+            }
+        }
+        for command in expanded {
+            self.expand_command(&command, depth + 1, commands)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn builtins() -> Vec<String> {
+        vec![
+            "get-network-status".to_string(),
+            "list-peers".to_string(),
+            "unban-peer".to_string(),
+        ]
+    }
+
+    #[test]
+    fn expand_aliases() {
+        let (aliases, ignored) = CommandAliases::from_toml(
+            r#"
+            st = "get-network-status"
+            bans = "list-peers --banned"
+            checkup = "st; get-mempool-stats ;bans"
+            list-peers = "quit"
+            "#,
+            &builtins(),
+        )
+        .unwrap();
+        assert_eq!(ignored, vec!["list-peers".to_string()]);
+        assert_eq!(aliases.names(), vec!["bans", "checkup", "st"]);
+
+        assert_eq!(aliases.expand("st").unwrap(), vec!["get-network-status"]);
+        assert_eq!(aliases.expand("list-peers").unwrap(), vec!["list-peers"]);
+        assert_eq!(aliases.expand("bans 10").unwrap(), vec!["list-peers --banned 10"]);
+        assert_eq!(aliases.expand("checkup 10").unwrap(), vec![
+            "get-network-status",
+            "get-mempool-stats",
+            "list-peers --banned 10"
+        ]);
+        // Only alias definitions are split into several commands
+        assert_eq!(aliases.expand("sign-message a;b").unwrap(), vec!["sign-message a;b"]);
+    }
+
+    #[test]
+    fn recursive_alias() {
+        let (aliases, _) = CommandAliases::from_toml("a = \"b\"\nb = \"whoami; a\"", &builtins()).unwrap();
+        assert_eq!(aliases.expand("a"), Err(AliasError::Recursive("a".to_string())));
+    }
+
+    #[test]
+    fn invalid_file() {
+        assert!(CommandAliases::from_toml("st = [", &builtins()).is_err());
+        assert!(CommandAliases::from_toml("[st]\nx = \"whoami\"", &builtins()).is_err());
+    }
+}
@@ -20,7 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::miner;
+use crate::{
+    address_monitor::PublicAddressMonitor,
+    miner,
+    notifications::{NotificationConfig, NotificationKind, NotificationMonitor, Notifier},
+};
 use futures::future;
 use log::*;
 use rand::rngs::OsRng;
@@ -35,6 +39,7 @@ use std::{
 };
 use tari_common::{CommsTransport, DatabaseType, GlobalConfig, Network, SocksAuthentication, TorControlAuthentication};
 use tari_comms::{
+    connection_manager::PeerAllowList,
     multiaddr::{Multiaddr, Protocol},
     peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerFlags},
     socks,
@@ -46,16 +51,29 @@ use tari_comms::{
     ConnectionManagerEvent,
     PeerManager,
 };
-use tari_comms_dht::Dht;
+use tari_comms_dht::{Dht, DhtConfig};
 use tari_core::{
     base_node::{
         chain_metadata_service::{ChainMetadataHandle, ChainMetadataServiceInitializer},
+        chain_stats_service::{ChainStatsConfig, ChainStatsHandle, ChainStatsServiceInitializer},
+        confirmation_service::{ConfirmationServiceConfig, ConfirmationServiceInitializer},
+        header_subscription_service::{HeaderSubscriptionServiceConfig, HeaderSubscriptionServiceInitializer},
+        partition_detection_service::{
+            PartitionDetectionConfig,
+            PartitionDetectionHandle,
+            PartitionDetectionServiceInitializer,
+        },
+        protocol_version::ProtocolFeatures,
         service::{BaseNodeServiceConfig, BaseNodeServiceInitializer},
+        states::SyncProgressHandle,
+        time_sync_service::{TimeSyncConfig, TimeSyncHandle, TimeSyncServiceInitializer},
+        version_handshake_service::VersionHandshakeServiceInitializer,
         BaseNodeStateMachine,
         BaseNodeStateMachineConfig,
         LocalNodeCommsInterface,
         OutboundNodeCommsInterface,
     },
+    blocks::genesis_block_generator::load_genesis_block,
     chain_storage::{
         create_lmdb_database,
         BlockchainBackend,
@@ -64,7 +82,7 @@ use tari_core::{
         MemoryDatabase,
         Validators,
     },
-    consensus::{ConsensusManager, ConsensusManagerBuilder, Network as NetworkType},
+    consensus::{ConsensusConstantsBuilder, ConsensusManager, ConsensusManagerBuilder, Network as NetworkType},
     mempool::{
         service::LocalMempoolService,
         Mempool,
@@ -73,7 +91,7 @@ use tari_core::{
         MempoolServiceInitializer,
         MempoolValidators,
     },
-    mining::Miner,
+    mining::{Miner, MinerMetrics},
     proof_of_work::DiffAdjManager,
     tari_utilities::{hex::Hex, message_format::MessageFormat},
     transactions::{
@@ -88,8 +106,10 @@ use tari_core::{
 use tari_mmr::MmrCacheConfig;
 use tari_p2p::{
     comms_connector::{pubsub_connector, PubsubDomainConnector, SubscriptionFactory},
+    identity_file::{load_identity_file, save_identity_file},
     initialization::{initialize_comms, CommsConfig},
     services::{
+        blocklist::{BlocklistConfig, BlocklistInitializer},
         comms_outbound::CommsOutboundServiceInitializer,
         liveness::{LivenessConfig, LivenessInitializer},
     },
@@ -106,7 +126,7 @@ use tari_wallet::{
     },
     storage::connection_manager::{run_migration_and_create_sqlite_connection, WalletDbConnection},
     transaction_service::{
-        config::TransactionServiceConfig,
+        config::{TransactionServiceConfig, ZeroConfPolicy},
         handle::TransactionServiceHandle,
         storage::sqlite_db::TransactionServiceSqliteDatabase,
         TransactionServiceInitializer,
@@ -158,6 +178,50 @@ impl NodeContainer {
         using_backend!(self, ctx, ctx.local_mempool())
     }
 
+    /// Returns a handle to the time sync service. This function panics if it has not been registered with the comms
+    /// service
+    pub fn time_sync(&self) -> TimeSyncHandle {
+        using_backend!(self, ctx, ctx.time_sync())
+    }
+
+    /// Returns a handle to the chain stats service. This function panics if it has not been registered with the
+    /// comms service
+    pub fn chain_stats(&self) -> ChainStatsHandle {
+        using_backend!(self, ctx, ctx.chain_stats())
+    }
+
+    /// Returns a handle to the partition detection service. This function panics if it has not been registered with
+    /// the comms service
+    pub fn partition_detection(&self) -> PartitionDetectionHandle {
+        using_backend!(self, ctx, ctx.partition_detection())
+    }
+
+    /// Returns a handle to the chain metadata service. This function panics if it has not been registered with the
+    /// comms service
+    pub fn chain_metadata(&self) -> ChainMetadataHandle {
+        using_backend!(self, ctx, ctx.chain_metadata())
+    }
+
+    /// Returns a handle to query and subscribe to the block sync progress
+    pub fn sync_progress(&self) -> SyncProgressHandle {
+        using_backend!(self, ctx, ctx.node.get_sync_progress_handle())
+    }
+
+    /// Returns the flag used to pause block sync on the base node state machine
+    pub fn block_sync_pause_flag(&self) -> Arc<AtomicBool> {
+        using_backend!(self, ctx, ctx.node.get_block_sync_pause_flag())
+    }
+
+    /// Returns the notifier used to deliver operator notifications
+    pub fn notifier(&self) -> Notifier {
+        using_backend!(self, ctx, ctx.notifier.clone())
+    }
+
+    /// Returns the consensus rules this node is running with
+    pub fn consensus_rules(&self) -> ConsensusManager {
+        using_backend!(self, ctx, ctx.consensus_rules.clone())
+    }
+
     /// Returns the CommsNode.
     pub fn base_node_comms(&self) -> &CommsNode {
         using_backend!(self, ctx, &ctx.base_node_comms)
@@ -183,6 +247,17 @@ impl NodeContainer {
         using_backend!(self, ctx, ctx.miner_enabled.clone())
     }
 
+    /// Returns this node's miner metrics.
+    pub fn miner_metrics(&self) -> MinerMetrics {
+        using_backend!(self, ctx, ctx.miner_metrics.clone())
+    }
+
+    /// Returns the flag that puts this node into maintenance mode. While it is set, new mempool transactions are
+    /// refused and blocks are not relayed, but requests are still served.
+    pub fn maintenance_mode(&self) -> Arc<AtomicBool> {
+        using_backend!(self, ctx, ctx.maintenance_mode.clone())
+    }
+
     /// Returns a handle to the wallet transaction service. This function panics if it has not been registered
     /// with the comms service
     pub fn wallet_transaction_service(&self) -> TransactionServiceHandle {
@@ -192,17 +267,34 @@ impl NodeContainer {
     async fn run_impl<B: BlockchainBackend + 'static>(mut ctx: BaseNodeContext<B>, rt: runtime::Handle) {
         info!(target: LOG_TARGET, "Tari base node has STARTED");
         let mut wallet_output_handle = ctx.output_manager();
+        let notification_monitor = NotificationMonitor::new(
+            ctx.notifier.clone(),
+            ctx.node.get_state_change_event_stream(),
+            ctx.local_node().get_block_event_stream(),
+            ctx.base_node_comms.connectivity(),
+            ctx.wallet_transaction_service(),
+            ctx.node.get_interrupt_signal(),
+        );
+        rt.spawn(notification_monitor.run());
+        let notifier = ctx.notifier.clone();
         // Start wallet & miner
         let mut miner = ctx.miner.take().expect("Miner was not constructed");
         let mut rx = miner.get_utxo_receiver_channel();
         rt.spawn(async move {
             debug!(target: LOG_TARGET, "Mining wallet ready to receive coins.");
             while let Some(utxo) = rx.next().await {
+                let value = utxo.value;
                 match wallet_output_handle.add_output(utxo).await {
-                    Ok(_) => info!(
-                        target: LOG_TARGET,
-                        "🤑💰🤑 Newly mined coinbase output added to wallet 🤑💰🤑"
-                    ),
+                    Ok(_) => {
+                        info!(
+                            target: LOG_TARGET,
+                            "🤑💰🤑 Newly mined coinbase output added to wallet 🤑💰🤑"
+                        );
+                        notifier.notify(
+                            NotificationKind::BlockMined,
+                            format!("Mined a new block with a coinbase of {}", value),
+                        );
+                    },
                     Err(e) => warn!(target: LOG_TARGET, "Error adding output: {}", e),
                 }
             }
@@ -232,6 +324,10 @@ pub struct BaseNodeContext<B: BlockchainBackend> {
     pub node: BaseNodeStateMachine<B>,
     pub miner: Option<Miner>,
     pub miner_enabled: Arc<AtomicBool>,
+    pub miner_metrics: MinerMetrics,
+    pub maintenance_mode: Arc<AtomicBool>,
+    pub notifier: Notifier,
+    pub consensus_rules: ConsensusManager,
 }
 
 impl<B: BlockchainBackend> BaseNodeContext<B> {
@@ -253,6 +349,30 @@ impl<B: BlockchainBackend> BaseNodeContext<B> {
             .expect("Could not get local mempool interface handle")
     }
 
+    pub fn time_sync(&self) -> TimeSyncHandle {
+        self.base_node_handles
+            .get_handle::<TimeSyncHandle>()
+            .expect("Could not get time sync service handle")
+    }
+
+    pub fn chain_stats(&self) -> ChainStatsHandle {
+        self.base_node_handles
+            .get_handle::<ChainStatsHandle>()
+            .expect("Could not get chain stats service handle")
+    }
+
+    pub fn partition_detection(&self) -> PartitionDetectionHandle {
+        self.base_node_handles
+            .get_handle::<PartitionDetectionHandle>()
+            .expect("Could not get partition detection service handle")
+    }
+
+    pub fn chain_metadata(&self) -> ChainMetadataHandle {
+        self.base_node_handles
+            .get_handle::<ChainMetadataHandle>()
+            .expect("Could not get chain metadata service handle")
+    }
+
     pub fn wallet_transaction_service(&self) -> TransactionServiceHandle {
         self.wallet_handles
             .get_handle::<TransactionServiceHandle>()
@@ -261,24 +381,18 @@ impl<B: BlockchainBackend> BaseNodeContext<B> {
 }
 
 /// Tries to construct a node identity by loading the secret key and other metadata from disk and calculating the
-/// missing fields from that information.
-pub fn load_identity(path: &Path) -> Result<NodeIdentity, String> {
+/// missing fields from that information. The secret key is decrypted with the passphrase if it is encrypted, and
+/// identity files in an older format are upgraded.
+pub fn load_identity(path: &Path, passphrase: Option<&str>) -> Result<NodeIdentity, String> {
     if !path.exists() {
         return Err(format!("Identity file, {}, does not exist.", path.to_str().unwrap()));
     }
 
-    let id_str = std::fs::read_to_string(path).map_err(|e| {
+    let id = load_identity_file(path, passphrase).map_err(|e| {
         format!(
-            "The node identity file, {}, could not be read. {}",
+            "The node identity file, {}, could not be loaded. {:?}",
             path.to_str().unwrap_or("?"),
-            e.to_string()
-        )
-    })?;
-    let id = NodeIdentity::from_json(&id_str).map_err(|e| {
-        format!(
-            "The node identity file, {}, has an error. {}",
-            path.to_str().unwrap_or("?"),
-            e.to_string()
+            e
         )
     })?;
     info!(
@@ -289,20 +403,37 @@ pub fn load_identity(path: &Path) -> Result<NodeIdentity, String> {
     Ok(id)
 }
 
-/// Create a new node id and save it to disk
+/// Create a new node id and save it to disk, encrypting the secret key if a passphrase is given
 pub fn create_new_base_node_identity<P: AsRef<Path>>(
     path: P,
     public_addr: Multiaddr,
     features: PeerFeatures,
+    passphrase: Option<&str>,
 ) -> Result<NodeIdentity, String>
 {
     let private_key = PrivateKey::random(&mut OsRng);
     let node_identity = NodeIdentity::new(private_key, public_addr, features)
         .map_err(|e| format!("We were unable to construct a node identity. {}", e.to_string()))?;
-    save_as_json(path, &node_identity)?;
+    save_identity(path, &node_identity, passphrase)?;
     Ok(node_identity)
 }
 
+/// Save the node identity to disk, encrypting the secret key if a passphrase is given
+pub fn save_identity<P: AsRef<Path>>(
+    path: P,
+    node_identity: &NodeIdentity,
+    passphrase: Option<&str>,
+) -> Result<(), String>
+{
+    save_identity_file(path.as_ref(), node_identity, passphrase).map_err(|e| {
+        format!(
+            "Error writing identity file, {}. {:?}",
+            path.as_ref().to_str().unwrap_or("<invalid UTF-8>"),
+            e
+        )
+    })
+}
+
 pub fn load_from_json<P: AsRef<Path>, T: MessageFormat>(path: P) -> Result<T, String> {
     if !path.as_ref().exists() {
         return Err(format!(
@@ -344,6 +475,7 @@ pub async fn configure_and_initialize_node(
     let network = match &config.network {
         Network::MainNet => NetworkType::MainNet,
         Network::Rincewind => NetworkType::Rincewind,
+        Network::LocalNet => NetworkType::LocalNet,
     };
     let result = match &config.db_type {
         DatabaseType::Memory => {
@@ -389,28 +521,57 @@ where
 {
     //---------------------------------- Blockchain --------------------------------------------//
 
-    let rules = ConsensusManagerBuilder::new(network).build();
+    let mut rules = ConsensusManagerBuilder::new(network);
+    if let Some(schedule) = config.max_block_weight_schedule.clone() {
+        if let NetworkType::MainNet = network {
+            return Err("The maximum block weight schedule cannot be changed on mainnet".to_string());
+        }
+        info!(target: LOG_TARGET, "Using maximum block weight schedule {:?}", schedule);
+        rules = rules.with_consensus_constants(
+            ConsensusConstantsBuilder::new(network)
+                .with_max_block_weight_schedule(schedule)
+                .build(),
+        );
+    }
+    if let Some(path) = &config.genesis_block_file {
+        if let NetworkType::MainNet | NetworkType::Rincewind = network {
+            return Err("A custom genesis block file can only be used on localnet".to_string());
+        }
+        let genesis_block = load_genesis_block(path)
+            .map_err(|e| format!("Could not load the genesis block from '{}': {}", path.display(), e))?;
+        info!(target: LOG_TARGET, "Using the genesis block from '{}'", path.display());
+        rules = rules.with_block(genesis_block);
+    }
+    let rules = rules.build();
     let factories = CryptoFactories::default();
     let validators = Validators::new(
         FullConsensusValidator::new(rules.clone(), factories.clone()),
         StatelessBlockValidator::new(&rules.consensus_constants()),
     );
-    let db = BlockchainDatabase::new(backend, &rules, validators).map_err(|e| e.to_string())?;
+    let db = BlockchainDatabase::new(backend, &rules, validators)
+        .map_err(|e| e.to_string())?
+        .with_validation_log_threshold(config.block_validation_log_threshold_ms.map(Duration::from_millis));
     let mempool_validator =
         MempoolValidators::new(FullTxValidator::new(factories.clone()), TxInputAndMaturityValidator {});
-    let mempool = Mempool::new(db.clone(), MempoolConfig::default(), mempool_validator);
+    let mempool = Mempool::new(db.clone(), setup_mempool_config(config), mempool_validator);
     let diff_adj_manager = DiffAdjManager::new(&rules.consensus_constants()).map_err(|e| e.to_string())?;
     rules.set_diff_manager(diff_adj_manager).map_err(|e| e.to_string())?;
     let handle = runtime::Handle::current();
 
+    // The base node and its wallet always have to be able to connect to each other
+    let base_node_allow_list = setup_peer_allow_list(config, vec![wallet_node_identity.public_key().clone()])?;
+    let wallet_allow_list = setup_peer_allow_list(config, vec![base_node_identity.public_key().clone()])?;
+
     //---------------------------------- Base Node --------------------------------------------//
 
     let (publisher, base_node_subscriptions) = pubsub_connector(handle.clone(), 100);
     let base_node_subscriptions = Arc::new(base_node_subscriptions);
     create_peer_db_folder(&config.peer_db_path)?;
-    let (base_node_comms, base_node_dht) = setup_base_node_comms(base_node_identity, config, publisher).await?;
+    let (base_node_comms, base_node_dht) =
+        setup_base_node_comms(base_node_identity, config, publisher, base_node_allow_list).await?;
 
     debug!(target: LOG_TARGET, "Registering base node services");
+    let maintenance_mode = Arc::new(AtomicBool::new(false));
     let base_node_handles = register_base_node_services(
         &base_node_comms,
         &base_node_dht,
@@ -418,6 +579,10 @@ where
         base_node_subscriptions.clone(),
         mempool,
         rules.clone(),
+        setup_blocklist_config(config),
+        setup_chain_stats_config(config, &rules),
+        setup_base_node_service_config(config),
+        maintenance_mode.clone(),
     )
     .await;
     debug!(target: LOG_TARGET, "Base node service registration complete.");
@@ -431,6 +596,7 @@ where
         config,
         publisher,
         base_node_comms.node_identity().to_peer(),
+        wallet_allow_list,
     )
     .await?;
 
@@ -440,6 +606,19 @@ where
         wallet_comms.peer_manager(),
     ));
 
+    if let Some(check_url) = config.public_address_check_url.clone() {
+        let monitor = PublicAddressMonitor::new(
+            base_node_comms.node_identity(),
+            config.identity_file.clone(),
+            config.identity_passphrase.clone(),
+            base_node_dht.dht_requester(),
+            check_url,
+            Duration::from_secs(config.public_address_check_interval),
+            interrupt_signal.clone(),
+        );
+        task::spawn(monitor.run());
+    }
+
     create_wallet_folder(
         &config
             .wallet_db_file
@@ -449,15 +628,57 @@ where
     let wallet_conn = run_migration_and_create_sqlite_connection(&config.wallet_db_file)
         .map_err(|e| format!("Could not create wallet: {:?}", e))?;
 
+    let mut output_manager_service_config = OutputManagerServiceConfig::default();
+    if let Some(dust_threshold) = config.wallet_dust_threshold {
+        output_manager_service_config.dust_threshold = dust_threshold.into();
+    }
+    if let Some(recovery_byte) = config.wallet_output_recovery_byte {
+        output_manager_service_config.recovery_byte = recovery_byte;
+    }
+    if let Some(period) = config.wallet_spend_unlock_period {
+        output_manager_service_config.spend_unlock_period = Duration::from_secs(period);
+    }
+    let mut transaction_service_config = TransactionServiceConfig::default();
+    if let Some(num_confirmations_required) = config.wallet_num_confirmations_required {
+        transaction_service_config.num_confirmations_required = num_confirmations_required;
+    }
+    if let Some(min_inbound_amount) = config.wallet_min_inbound_amount {
+        transaction_service_config.min_inbound_amount = min_inbound_amount.into();
+    }
+    if let Some(max_inbound) = config.wallet_max_inbound_transactions_per_peer {
+        transaction_service_config.max_inbound_transactions_per_peer = max_inbound;
+    }
+    if let Some(period) = config.wallet_inbound_throttle_period {
+        transaction_service_config.inbound_throttle_period = Duration::from_secs(period);
+    }
+    if let Some(min_fee) = config.wallet_zero_conf_min_fee {
+        transaction_service_config.zero_conf_policy = ZeroConfPolicy::MempoolAccepted {
+            min_fee: min_fee.into(),
+        };
+    }
     let wallet_handles = register_wallet_services(
         &wallet_comms,
         &wallet_dht,
         &wallet_conn,
         wallet_subscriptions,
         factories,
+        output_manager_service_config,
+        transaction_service_config,
     )
     .await;
 
+    debug!(target: LOG_TARGET, "Waiting for base node and wallet services to become ready");
+    base_node_handles
+        .readiness()
+        .wait_all_ready()
+        .await
+        .map_err(|e| format!("Base node services failed to start: {}", e))?;
+    wallet_handles
+        .readiness()
+        .wait_all_ready()
+        .await
+        .map_err(|e| format!("Wallet services failed to start: {}", e))?;
+
     // Set the base node for the wallet to the 'local' base node
     let base_node_public_key = base_node_comms.node_identity().public_key().clone();
     wallet_handles
@@ -486,6 +707,10 @@ where
         .block_sync_strategy
         .parse()
         .expect("Problem reading block sync strategy from config");
+    if let Some(threads) = config.header_verification_threads {
+        state_machine_config.block_sync_config.header_verification_threads = threads;
+    }
+    state_machine_config.block_sync_config.min_header_difficulty = rules.consensus_constants().min_pow_difficulty();
 
     let node = BaseNodeStateMachine::new(
         &db,
@@ -504,8 +729,9 @@ where
         &base_node_handles,
         node.get_interrupt_signal(),
         event_stream,
-        rules,
+        rules.clone(),
         config.num_mining_threads,
+        config.coinbase_extra.clone(),
     );
     if config.enable_mining {
         debug!(target: LOG_TARGET, "Enabling solo miner");
@@ -518,6 +744,8 @@ where
     };
 
     let miner_enabled = miner.enable_mining_flag();
+    let miner_metrics = miner.metrics();
+    let notifier = Notifier::new(NotificationConfig::from_global_config(config)?);
     Ok(BaseNodeContext {
         base_node_comms,
         base_node_dht,
@@ -528,6 +756,10 @@ where
         node,
         miner: Some(miner),
         miner_enabled,
+        miner_metrics,
+        maintenance_mode,
+        notifier,
+        consensus_rules: rules,
     })
 }
 
@@ -556,6 +788,142 @@ async fn sync_peers(
     }
 }
 
+/// Returns the mempool configuration with the relay policy overridden by any values set in the `[relay]` section
+fn setup_mempool_config(config: &GlobalConfig) -> MempoolConfig {
+    let mut mempool_config = MempoolConfig::default();
+    let relay_config = &mut mempool_config.relay_config;
+    if let Some(min_fee_per_gram) = config.relay_min_fee_per_gram {
+        relay_config.min_fee_per_gram = min_fee_per_gram.into();
+    }
+    if let Some(max_tx_weight) = config.relay_max_tx_weight {
+        relay_config.max_tx_weight = max_tx_weight;
+    }
+    if let Some(relay_non_standard_features) = config.relay_non_standard_features {
+        relay_config.relay_non_standard_features = relay_non_standard_features;
+    }
+    if let Some(max_list_page_size) = config.mempool_max_list_page_size {
+        mempool_config.max_list_page_size = max_list_page_size;
+    }
+    mempool_config
+}
+
+/// Returns the base node service configuration with the response limits overridden by any configured values
+fn setup_base_node_service_config(config: &GlobalConfig) -> BaseNodeServiceConfig {
+    let mut node_config = BaseNodeServiceConfig::default();
+    let limits = &mut node_config.response_limits;
+    if let Some(max_headers) = config.max_headers_per_response {
+        limits.max_headers = max_headers;
+    }
+    if let Some(max_blocks) = config.max_blocks_per_response {
+        limits.max_blocks = max_blocks;
+    }
+    if let Some(max_kernels) = config.max_kernels_per_response {
+        limits.max_kernels = max_kernels;
+    }
+    if let Some(max_utxos) = config.max_utxos_per_response {
+        limits.max_utxos = max_utxos;
+    }
+    node_config
+}
+
+/// Returns the chain stats configuration, persisting block stats in the data directory
+fn setup_chain_stats_config(config: &GlobalConfig, rules: &ConsensusManager) -> ChainStatsConfig {
+    ChainStatsConfig {
+        storage_path: Some(config.data_dir.join("chain_stats.json")),
+        target_block_interval: rules.consensus_constants().get_target_block_interval(),
+        ..Default::default()
+    }
+}
+
+/// Returns the peer allow list if allow list only mode is enabled, otherwise None. The `always_allowed` public keys are
+/// added to the configured allow list. Invalid public keys or an unreadable allow list file are an error rather than
+/// being skipped, so that a misconfigured private network node does not start with a different set of peers than
+/// intended.
+/// Applies the configured DHT join policy to the DHT config
+fn setup_dht_join_policy(config: &GlobalConfig, mut dht_config: DhtConfig) -> DhtConfig {
+    if let Some(delay) = config.dht_join_startup_delay {
+        dht_config.join_startup_delay = Duration::from_secs(delay);
+    }
+    if let Some(interval) = config.dht_join_min_interval {
+        dht_config.join_min_interval = Duration::from_secs(interval);
+    }
+    if let Some(interval) = config.dht_join_reannounce_interval {
+        dht_config.join_reannounce_interval = Some(interval).filter(|i| *i > 0).map(Duration::from_secs);
+    }
+    dht_config
+}
+
+fn setup_peer_allow_list(
+    config: &GlobalConfig,
+    always_allowed: Vec<PublicKey>,
+) -> Result<Option<PeerAllowList>, String>
+{
+    if !config.peer_allow_list_only {
+        return Ok(None);
+    }
+    let mut public_keys = config
+        .peer_allow_list
+        .iter()
+        .map(|s| PublicKey::from_hex(s).map_err(|e| format!("{} is not a valid allow list public key. {}", s, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    public_keys.extend(always_allowed);
+    let mut allow_list = PeerAllowList::new(public_keys);
+    if let Some(path) = config.peer_allow_list_file.as_ref() {
+        allow_list = allow_list
+            .with_file(path)
+            .map_err(|e| format!("Could not read the allow list file '{}': {}", path.display(), e))?;
+    }
+    info!(
+        target: LOG_TARGET,
+        "Allow list only mode is enabled. Only peers in the allow list will be dialed or accepted."
+    );
+    Ok(Some(allow_list))
+}
+
+/// Returns the blocklist feed configuration if a feed has been configured, otherwise None
+fn setup_blocklist_config(config: &GlobalConfig) -> Option<BlocklistConfig> {
+    let feed_path = config.blocklist_feed_path.clone()?;
+    let feed_public_key = match config.blocklist_feed_public_key.as_ref() {
+        Some(public_key) => match PublicKey::from_hex(public_key) {
+            Ok(pk) => pk,
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Blocklist feed disabled. The feed public key is invalid. {}",
+                    e.to_string()
+                );
+                return None;
+            },
+        },
+        None => {
+            warn!(
+                target: LOG_TARGET,
+                "Blocklist feed disabled. A feed path was given without a feed public key."
+            );
+            return None;
+        },
+    };
+
+    let mut blocklist_config = BlocklistConfig::new(feed_path, feed_public_key);
+    blocklist_config.allowlist = config
+        .blocklist_allowlist
+        .iter()
+        .filter_map(|s| match NodeId::from_hex(s) {
+            Ok(node_id) => Some(node_id),
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "{} is not a valid blocklist allowlist node id. {}",
+                    s,
+                    e.to_string()
+                );
+                None
+            },
+        })
+        .collect();
+    Some(blocklist_config)
+}
+
 fn parse_peer_seeds(seeds: &[String]) -> Vec<Peer> {
     info!("Adding {} peers to the peer database", seeds.len());
     let mut result = Vec::with_capacity(seeds.len());
@@ -635,6 +1003,7 @@ fn setup_transport_type(config: &GlobalConfig) -> TransportType {
             forward_address,
             auth,
             onion_port,
+            socks_auth,
         } => {
             let tor_identity_path = Path::new(&config.tor_identity_file);
             let identity = if tor_identity_path.exists() {
@@ -665,9 +1034,8 @@ fn setup_transport_type(config: &GlobalConfig) -> TransportType {
                 },
                 identity: identity.map(Box::new),
                 port_mapping: (onion_port, forward_addr).into(),
-                // TODO: make configurable
                 socks_address_override,
-                socks_auth: socks::Authentication::None,
+                socks_auth: into_socks_authentication(socks_auth),
             })
         },
         CommsTransport::Socks5 {
@@ -717,6 +1085,7 @@ fn setup_wallet_transport_type(config: &GlobalConfig) -> TransportType {
             forward_address,
             auth,
             onion_port,
+            socks_auth,
         } => {
             let tor_identity_path = Path::new(&config.wallet_tor_identity_file);
             let identity = if tor_identity_path.exists() {
@@ -749,9 +1118,8 @@ fn setup_wallet_transport_type(config: &GlobalConfig) -> TransportType {
                 identity: identity.map(Box::new),
 
                 port_mapping: (onion_port.get() + 1, forward_addr).into(),
-                // TODO: make configurable
                 socks_address_override,
-                socks_auth: socks::Authentication::None,
+                socks_auth: into_socks_authentication(socks_auth),
             })
         },
         CommsTransport::Socks5 {
@@ -774,6 +1142,7 @@ fn into_socks_authentication(auth: SocksAuthentication) -> socks::Authentication
         SocksAuthentication::UsernamePassword(username, password) => {
             socks::Authentication::Password(username, password)
         },
+        SocksAuthentication::Isolated => socks::Authentication::Isolated,
     }
 }
 
@@ -823,6 +1192,7 @@ async fn setup_base_node_comms(
     node_identity: Arc<NodeIdentity>,
     config: &GlobalConfig,
     publisher: PubsubDomainConnector,
+    peer_allow_list: Option<PeerAllowList>,
 ) -> Result<(CommsNode, Dht), String>
 {
     let comms_config = CommsConfig {
@@ -833,11 +1203,15 @@ async fn setup_base_node_comms(
         max_concurrent_inbound_tasks: 100,
         outbound_buffer_size: 100,
         // TODO - make this configurable
-        dht: Default::default(),
+        dht: setup_dht_join_policy(&config, DhtConfig {
+            routing_table_path: Some(config.data_dir.join("dht_routing_table.json")),
+            ..Default::default()
+        }),
         // TODO: This should be false unless testing locally - make this configurable
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: config.listener_liveness_whitelist_cidrs.clone(),
         listener_liveness_max_sessions: config.listnener_liveness_max_sessions,
+        peer_allow_list,
     };
     let (comms, dht) = initialize_comms(comms_config, publisher)
         .await
@@ -845,8 +1219,12 @@ async fn setup_base_node_comms(
 
     // Save final node identity after comms has initialized. This is required because the public_address can be changed
     // by comms during initialization when using tor.
-    save_as_json(&config.identity_file, &*comms.node_identity())
-        .map_err(|e| format!("Failed to save node identity: {:?}", e))?;
+    save_identity(
+        &config.identity_file,
+        &*comms.node_identity(),
+        config.identity_passphrase.as_deref(),
+    )
+    .map_err(|e| format!("Failed to save node identity: {:?}", e))?;
     if let Some(hs) = comms.hidden_service() {
         save_as_json(&config.tor_identity_file, hs.tor_identity())
             .map_err(|e| format!("Failed to save tor identity: {:?}", e))?;
@@ -862,6 +1240,7 @@ async fn setup_wallet_comms(
     config: &GlobalConfig,
     publisher: PubsubDomainConnector,
     base_node_peer: Peer,
+    peer_allow_list: Option<PeerAllowList>,
 ) -> Result<(CommsNode, Dht), String>
 {
     let comms_config = CommsConfig {
@@ -872,11 +1251,12 @@ async fn setup_wallet_comms(
         max_concurrent_inbound_tasks: 100,
         outbound_buffer_size: 100,
         // TODO - make this configurable
-        dht: Default::default(),
+        dht: setup_dht_join_policy(&config, Default::default()),
         // TODO: This should be false unless testing locally - make this configurable
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        peer_allow_list,
     };
     let (comms, dht) = initialize_comms(comms_config, publisher)
         .await
@@ -884,8 +1264,12 @@ async fn setup_wallet_comms(
 
     // Save final node identity after comms has initialized. This is required because the public_address can be changed
     // by comms during initialization when using tor.
-    save_as_json(&config.wallet_identity_file, &*comms.node_identity())
-        .map_err(|e| format!("Failed to save node identity: {:?}", e))?;
+    save_identity(
+        &config.wallet_identity_file,
+        &*comms.node_identity(),
+        config.identity_passphrase.as_deref(),
+    )
+    .map_err(|e| format!("Failed to save node identity: {:?}", e))?;
     if let Some(hs) = comms.hidden_service() {
         save_as_json(&config.wallet_tor_identity_file, hs.tor_identity())
             .map_err(|e| format!("Failed to save tor identity: {:?}", e))?;
@@ -916,37 +1300,68 @@ async fn register_base_node_services<B>(
     subscription_factory: Arc<SubscriptionFactory>,
     mempool: Mempool<B>,
     consensus_manager: ConsensusManager,
+    blocklist_config: Option<BlocklistConfig>,
+    chain_stats_config: ChainStatsConfig,
+    node_config: BaseNodeServiceConfig,
+    maintenance_mode: Arc<AtomicBool>,
 ) -> Arc<ServiceHandles>
 where
     B: BlockchainBackend + 'static,
 {
-    let node_config = BaseNodeServiceConfig::default(); // TODO - make this configurable
     let mempool_config = MempoolServiceConfig::default(); // TODO - make this configurable
-    StackBuilder::new(runtime::Handle::current(), comms.shutdown_signal())
+    let partition_detection_config = PartitionDetectionConfig {
+        target_block_interval: consensus_manager.consensus_constants().get_target_block_interval(),
+        ..Default::default()
+    };
+    let mut stack = StackBuilder::new(runtime::Handle::current(), comms.shutdown_signal());
+    if let Some(blocklist_config) = blocklist_config {
+        stack = stack.add_initializer(BlocklistInitializer::new(blocklist_config, comms.peer_manager()));
+    }
+    stack
         .add_initializer(CommsOutboundServiceInitializer::new(dht.outbound_requester()))
-        .add_initializer(BaseNodeServiceInitializer::new(
-            subscription_factory.clone(),
-            db,
-            mempool.clone(),
-            consensus_manager,
-            node_config,
-        ))
-        .add_initializer(MempoolServiceInitializer::new(
-            subscription_factory.clone(),
-            mempool,
-            mempool_config,
-        ))
+        .add_initializer(
+            BaseNodeServiceInitializer::new(
+                subscription_factory.clone(),
+                db,
+                mempool.clone(),
+                consensus_manager,
+                node_config,
+            )
+            .with_maintenance_flag(maintenance_mode.clone()),
+        )
+        .add_initializer(
+            MempoolServiceInitializer::new(subscription_factory.clone(), mempool, mempool_config)
+                .with_maintenance_flag(maintenance_mode),
+        )
         .add_initializer(LivenessInitializer::new(
             LivenessConfig {
                 auto_ping_interval: Some(Duration::from_secs(30)),
                 enable_auto_join: true,
-                enable_auto_stored_message_request: true,
                 refresh_neighbours_interval: Duration::from_secs(3 * 60),
             },
-            subscription_factory,
+            subscription_factory.clone(),
             dht.dht_requester(),
         ))
         .add_initializer(ChainMetadataServiceInitializer)
+        .add_initializer(TimeSyncServiceInitializer::new(TimeSyncConfig::default()))
+        .add_initializer(ChainStatsServiceInitializer::new(chain_stats_config))
+        .add_initializer(PartitionDetectionServiceInitializer::new(partition_detection_config))
+        .add_initializer(ConfirmationServiceInitializer::new(
+            ConfirmationServiceConfig::default(),
+            subscription_factory.clone(),
+            comms.node_identity(),
+        ))
+        .add_initializer(HeaderSubscriptionServiceInitializer::new(
+            HeaderSubscriptionServiceConfig::default(),
+            subscription_factory.clone(),
+        ))
+        .add_initializer(VersionHandshakeServiceInitializer::new(
+            env!("CARGO_PKG_VERSION")
+                .parse()
+                .expect("The base node crate version must be a semantic version"),
+            ProtocolFeatures::all(),
+            subscription_factory,
+        ))
         .finish()
         .await
         .expect("Service initialization failed")
@@ -958,6 +1373,8 @@ async fn register_wallet_services(
     wallet_db_conn: &WalletDbConnection,
     subscription_factory: Arc<SubscriptionFactory>,
     factories: CryptoFactories,
+    output_manager_service_config: OutputManagerServiceConfig,
+    transaction_service_config: TransactionServiceConfig,
 ) -> Arc<ServiceHandles>
 {
     StackBuilder::new(runtime::Handle::current(), wallet_comms.shutdown_signal())
@@ -966,7 +1383,6 @@ async fn register_wallet_services(
             LivenessConfig{
                 auto_ping_interval: None,
                 enable_auto_join: true,
-                enable_auto_stored_message_request: true,
                 ..Default::default()
             },
             subscription_factory.clone(),
@@ -975,17 +1391,18 @@ async fn register_wallet_services(
         ))
         // Wallet services
         .add_initializer(OutputManagerServiceInitializer::new(
-            OutputManagerServiceConfig::default(),
+            output_manager_service_config,
             subscription_factory.clone(),
             OutputManagerSqliteDatabase::new(wallet_db_conn.clone()),
             factories.clone(),
         ))
         .add_initializer(TransactionServiceInitializer::new(
-            TransactionServiceConfig::default(),
+            transaction_service_config,
             subscription_factory,
             wallet_comms.subscribe_messaging_events(),
             TransactionServiceSqliteDatabase::new(wallet_db_conn.clone()),
             wallet_comms.node_identity(),
+            wallet_comms.connectivity(),
             factories,
         ))
         .finish()
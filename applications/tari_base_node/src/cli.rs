@@ -23,6 +23,7 @@
 
 use crate::consts;
 use clap::clap_app;
+use std::path::PathBuf;
 use tari_common::{bootstrap_config_from_cli, ConfigBootstrap};
 
 /// Prints a pretty banner on the console
@@ -41,6 +42,14 @@ pub struct Arguments {
     pub bootstrap: ConfigBootstrap,
     pub create_id: bool,
     pub init: bool,
+    pub self_test: bool,
+    pub validate_config: bool,
+    /// The name of the secret to add to the secrets file with `config set_secret`
+    pub set_secret: Option<String>,
+    pub generate_genesis: Option<PathBuf>,
+    pub genesis_seed: String,
+    pub genesis_utxos: Option<String>,
+    pub genesis_coinbase_lock_height: Option<u64>,
 }
 
 /// Parse the command-line args and populate the minimal bootstrap config object
@@ -52,18 +61,58 @@ pub fn parse_cli_args() -> Arguments {
         (@arg base_dir: -b --base_dir +takes_value "A path to a directory to store your files")
         (@arg config: -c --config +takes_value "A path to the configuration file to use (config.toml)")
         (@arg log_config: -l --log_config +takes_value "A path to the logfile configuration (log4rs.yml))")
+        (@arg log_format: --log_format +takes_value "Write log lines as text (the default) or json")
+        (@arg secrets_file: --secrets_file +takes_value "A path to the encrypted secrets file (secrets.enc)")
         (@arg init: --init "Create a default configuration file if it doesn't exist")
+        (@arg network: --network +takes_value "The network to run on (mainnet, rincewind or localnet). Overrides base_node.network in the config file")
         (@arg create_id: --create_id "Create and save new node identity if one doesn't exist ")
+        (@arg self_test: --("self-test") "Check crypto, the database and the transport, print a report and exit")
+        (@arg generate_genesis: --generate_genesis +takes_value "Write a localnet genesis block to the given directory and exit")
+        (@arg genesis_seed: --genesis_seed +takes_value "The seed the genesis block keys are derived from (default: localnet)")
+        (@arg genesis_utxos: --genesis_utxos +takes_value "A comma separated list of µT values of spendable genesis UTXOs")
+        (@arg genesis_coinbase_lock_height: --genesis_coinbase_lock_height +takes_value "The coinbase lock height of the localnet")
+        (@subcommand config =>
+            (about: "Configuration file tools")
+            (@subcommand validate =>
+                (about: "Check the configuration file, print every problem found and exit")
+            )
+            (@subcommand set_secret =>
+                (about: "Add a secret to the encrypted secrets file, so that it can be referred to as secret:<name>")
+                (@arg name: +required "The name of the secret")
+            )
+        )
     )
     .get_matches();
 
     let bootstrap = bootstrap_config_from_cli(&matches);
     let create_id = matches.is_present("create_id");
     let init = matches.is_present("init");
+    let self_test = matches.is_present("self_test");
+    let validate_config = matches
+        .subcommand_matches("config")
+        .map_or(false, |config| config.subcommand_matches("validate").is_some());
+    let set_secret = matches
+        .subcommand_matches("config")
+        .and_then(|config| config.subcommand_matches("set_secret"))
+        .and_then(|set_secret| set_secret.value_of("name"))
+        .map(ToString::to_string);
+    let generate_genesis = matches.value_of("generate_genesis").map(PathBuf::from);
+    let genesis_seed = matches.value_of("genesis_seed").unwrap_or("localnet").to_string();
+    let genesis_utxos = matches.value_of("genesis_utxos").map(ToString::to_string);
+    let genesis_coinbase_lock_height = matches
+        .value_of("genesis_coinbase_lock_height")
+        .and_then(|height| height.parse().ok());
 
     Arguments {
         bootstrap,
         create_id,
         init,
+        self_test,
+        validate_config,
+        set_secret,
+        generate_genesis,
+        genesis_seed,
+        genesis_utxos,
+        genesis_coinbase_lock_height,
     }
 }
@@ -0,0 +1,201 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Collects the information maintainers need for a bug report into a single `tar.gz` bundle. Secrets in the
+//! configuration file are redacted before they are written to the bundle.
+
+use crate::consts;
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Only the tail of each log file is included in the bundle
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+const REDACTED: &str = "\"[REDACTED]\"";
+/// Config keys containing any of these fragments have their values redacted
+const SECRET_KEY_FRAGMENTS: &[&str] = &["password", "secret", "auth", "private", "passphrase", "seed"];
+
+/// A set of named text entries that are written to a `tar.gz` archive
+#[derive(Default)]
+pub struct DiagnosticsBundle {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl DiagnosticsBundle {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add<T: Into<Vec<u8>>>(&mut self, name: &str, contents: T) {
+        self.entries.push((name.to_string(), contents.into()));
+    }
+
+    /// Add the version and platform the node was built for
+    pub fn add_version_info(&mut self) {
+        self.add(
+            "version.txt",
+            format!(
+                "Version: {}\nOS: {}\nArchitecture: {}\n",
+                consts::VERSION,
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ),
+        );
+    }
+
+    /// Add the configuration file with all secret values redacted
+    pub fn add_config(&mut self, config_path: &Path) {
+        let contents = match fs::read_to_string(config_path) {
+            Ok(contents) => redact_config(&contents),
+            Err(err) => format!("Could not read '{}': {}", config_path.to_string_lossy(), err),
+        };
+        self.add("config.toml", contents);
+    }
+
+    /// Add the tail of every `.log` file in `log_dir`
+    pub fn add_logs(&mut self, log_dir: &Path) {
+        let dir = match fs::read_dir(log_dir) {
+            Ok(dir) => dir,
+            Err(err) => {
+                self.add(
+                    "logs/README.txt",
+                    format!("Could not read log directory '{}': {}", log_dir.to_string_lossy(), err),
+                );
+                return;
+            },
+        };
+        for path in dir.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.extension().map(|ext| ext != "log").unwrap_or(true) {
+                continue;
+            }
+            let name = format!("logs/{}", path.file_name().unwrap_or_default().to_string_lossy());
+            match read_tail(&path, MAX_LOG_BYTES) {
+                Ok(contents) => self.add(&name, contents),
+                Err(err) => self.add(&name, format!("Could not read log file: {}", err)),
+            }
+        }
+    }
+
+    /// Write the bundle to `path` as a gzipped tarball
+    pub fn write_tar_gz(&self, path: &Path) -> io::Result<()> {
+        let mtime = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let encoder = GzEncoder::new(File::create(path)?, Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+        for (name, contents) in &self.entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            header.set_cksum();
+            archive.append_data(&mut header, name, contents.as_slice())?;
+        }
+        archive.into_inner()?.finish()?;
+        Ok(())
+    }
+}
+
+/// The default location of a diagnostics bundle, e.g. `<base_path>/diagnostics-1588000000.tar.gz`
+pub fn default_bundle_path(base_path: &Path) -> PathBuf {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    base_path.join(format!("diagnostics-{}.tar.gz", now))
+}
+
+/// Replace the value of every `key = value` line whose key looks like it holds a secret
+pub fn redact_config(contents: &str) -> String {
+    contents
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('#') {
+                return line.to_string();
+            }
+            match line.find('=') {
+                Some(pos) if is_secret_key(&line[..pos]) => format!("{}= {}", &line[..pos], REDACTED),
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.trim().to_lowercase();
+    SECRET_KEY_FRAGMENTS.iter().any(|fragment| key.contains(fragment))
+}
+
+fn read_tail(path: &Path, max_bytes: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len > max_bytes {
+        file.seek(SeekFrom::Start(len - max_bytes))?;
+    }
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use tempdir::TempDir;
+
+    #[test]
+    fn redact_secrets() {
+        let config = "[base_node.rincewind]\n# tor_control_auth = \"password=abc\"\ntor_control_auth = \
+                      \"password=hunter2\"\nwallet_passphrase=\"abc\"\npublic_address = \"/ip4/1.2.3.4/tcp/18141\"";
+        let redacted = redact_config(config);
+        assert!(!redacted.contains("hunter2"));
+        assert!(redacted.contains("tor_control_auth = \"[REDACTED]\""));
+        assert!(redacted.contains("wallet_passphrase= \"[REDACTED]\""));
+        assert!(redacted.contains("# tor_control_auth = \"password=abc\""));
+        assert!(redacted.contains("public_address = \"/ip4/1.2.3.4/tcp/18141\""));
+    }
+
+    #[test]
+    fn write_bundle() {
+        let dir = TempDir::new("diagnostics").unwrap();
+        let path = dir.path().join("bundle.tar.gz");
+        let mut bundle = DiagnosticsBundle::new();
+        bundle.add_version_info();
+        bundle.add("chain_metadata.txt", "Height of longest chain: 10");
+        bundle.write_tar_gz(&path).unwrap();
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&path).unwrap()));
+        let names = archive
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["version.txt", "chain_metadata.txt"]);
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! The `--generate_genesis` command, which writes a genesis block for a private localnet deployment.
+//!
+//! Three files are written to the output directory:
+//! * `genesis_block.json` - the genesis block, loaded by nodes that set `genesis_block_file` in their
+//!   `[base_node.localnet]` config section,
+//! * `genesis_outputs.json` - the values and spending keys of the genesis outputs, and
+//! * `genesis_block.rs` - the genesis block as a Rust function, for hard-coding it in the `genesis_block` module.
+
+use log::*;
+use std::{fs, path::Path};
+use tari_core::{
+    blocks::genesis_block_generator::GenesisBlockGenerator,
+    consensus::{ConsensusConstantsBuilder, Network},
+    tari_utilities::{hex::Hex, Hashable},
+    transactions::{tari_amount::MicroTari, types::CryptoFactories},
+};
+
+const LOG_TARGET: &str = "base_node::genesis";
+
+pub struct GenesisArgs<'a> {
+    pub output_dir: &'a Path,
+    pub seed: &'a str,
+    pub utxo_values: Vec<MicroTari>,
+    pub coinbase_lock_height: Option<u64>,
+}
+
+/// Parse a comma separated list of µT values
+pub fn parse_utxo_values(values: &str) -> Result<Vec<MicroTari>, String> {
+    values
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse::<u64>()
+                .map(MicroTari::from)
+                .map_err(|_| format!("Invalid genesis UTXO value '{}'", v))
+        })
+        .collect()
+}
+
+pub fn generate_genesis(args: GenesisArgs) -> Result<(), String> {
+    let mut constants = ConsensusConstantsBuilder::new(Network::LocalNet);
+    if let Some(height) = args.coinbase_lock_height {
+        constants = constants.with_coinbase_lockheight(height);
+    }
+    let genesis = GenesisBlockGenerator::new(constants.build(), args.seed.as_bytes())
+        .with_utxos(&args.utxo_values)
+        .generate(&CryptoFactories::default())
+        .map_err(|e| format!("Could not generate the genesis block: {}", e))?;
+
+    fs::create_dir_all(args.output_dir)
+        .map_err(|e| format!("Could not create '{}': {}", args.output_dir.display(), e))?;
+    let files = [
+        ("genesis_block.json", genesis.to_json().map_err(|e| e.to_string())?),
+        (
+            "genesis_outputs.json",
+            genesis.outputs_to_json().map_err(|e| e.to_string())?,
+        ),
+        (
+            "genesis_block.rs",
+            genesis.to_rust_snippet("get_localnet_genesis_block"),
+        ),
+    ];
+    for (name, contents) in files.iter() {
+        let path = args.output_dir.join(name);
+        fs::write(&path, contents).map_err(|e| format!("Could not write '{}': {}", path.display(), e))?;
+        info!(target: LOG_TARGET, "Wrote '{}'", path.display());
+    }
+    println!(
+        "Genesis block {} written to '{}'. Keep genesis_outputs.json secret, it contains the spending keys.",
+        genesis.block.hash().to_hex(),
+        args.output_dir.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(parse_utxo_values("1000, 2000,").unwrap(), vec![
+            MicroTari::from(1000),
+            MicroTari::from(2000)
+        ]);
+        assert!(parse_utxo_values("1000,abc").unwrap_err().contains("abc"));
+    }
+}
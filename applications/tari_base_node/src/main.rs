@@ -20,33 +20,53 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+/// Keeps the advertised public address up to date on dynamic IPs
+mod address_monitor;
+/// User-defined command aliases for the CLI
+mod aliases;
 /// Utilities and helpers for building the base node instance
 mod builder;
 /// The command line interface definition and configuration
 mod cli;
 /// Application-specific constants
 mod consts;
+/// Diagnostics bundle export for bug reports
+mod diagnostics;
+/// Localnet genesis block generation
+mod genesis;
 /// Miner lib Todo hide behind feature flag
 mod miner;
+/// Webhook and shell hook notifications for node operators
+mod notifications;
 /// Parser module used to control user commands
 mod parser;
+/// Pauses block sync or shuts the node down when it runs low on disk space, file descriptors or memory
+mod resource_monitor;
+/// The `--self-test` preflight checks
+mod self_test;
 mod utils;
 
 use crate::builder::{create_new_base_node_identity, load_identity};
 use log::*;
 use parser::Parser;
+use resource_monitor::{ResourceLimits, ResourceMonitor};
 use rustyline::{config::OutputStreamType, error::ReadlineError, CompletionType, Config, EditMode, Editor};
-use std::{path::PathBuf, sync::Arc};
-use tari_common::{load_configuration, GlobalConfig};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tari_common::{load_configuration, secrets, ConfigBootstrap, ConfigWatcher, GlobalConfig};
 use tari_comms::{multiaddr::Multiaddr, peer_manager::PeerFeatures, NodeIdentity};
-use tari_shutdown::Shutdown;
+use tari_shutdown::{Shutdown, ShutdownListener, ShutdownPhase};
 use tokio::runtime::Runtime;
 
 pub const LOG_TARGET: &str = "base_node::app";
+/// The maximum time to wait for the node to shut down before exiting
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the config file is checked for changes
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(10);
 
 enum ExitCodes {
     ConfigError = 101,
     UnknownError = 102,
+    SelfTestFailed = 103,
 }
 
 fn main() {
@@ -62,16 +82,57 @@ fn main_inner() -> Result<(), ExitCodes> {
     let arguments = cli::parse_cli_args();
 
     // Initialise the logger
-    if !tari_common::initialize_logging(&arguments.bootstrap.log_config) {
+    if !tari_common::initialize_logging(&arguments.bootstrap.log_config, arguments.bootstrap.log_format) {
         return Err(ExitCodes::ConfigError);
     }
 
+    if let Some(output_dir) = &arguments.generate_genesis {
+        let utxo_values =
+            genesis::parse_utxo_values(arguments.genesis_utxos.as_deref().unwrap_or_default()).map_err(|err| {
+                error!(target: LOG_TARGET, "{}", err);
+                ExitCodes::ConfigError
+            })?;
+        return genesis::generate_genesis(genesis::GenesisArgs {
+            output_dir,
+            seed: &arguments.genesis_seed,
+            utxo_values,
+            coinbase_lock_height: arguments.genesis_coinbase_lock_height,
+        })
+        .map_err(|err| {
+            error!(target: LOG_TARGET, "{}", err);
+            ExitCodes::UnknownError
+        });
+    }
+
+    if let Some(name) = &arguments.set_secret {
+        return set_secret(&arguments.bootstrap, name).map_err(|err| {
+            error!(target: LOG_TARGET, "{}", err);
+            ExitCodes::ConfigError
+        });
+    }
+
     // Load and apply configuration file
     let cfg = load_configuration(&arguments.bootstrap).map_err(|err| {
         error!(target: LOG_TARGET, "{}", err);
         ExitCodes::ConfigError
     })?;
 
+    if arguments.validate_config {
+        return match tari_common::validate_configuration(cfg) {
+            Ok(_) => {
+                println!("The configuration is valid");
+                Ok(())
+            },
+            Err(errors) => {
+                println!("The configuration has {} error(s):", errors.len());
+                for err in errors {
+                    println!("  {}", err);
+                }
+                Err(ExitCodes::ConfigError)
+            },
+        };
+    }
+
     // Populate the configuration struct
     let node_config = GlobalConfig::convert_from(cfg).map_err(|err| {
         error!(target: LOG_TARGET, "The configuration file has an error. {}", err);
@@ -86,9 +147,21 @@ fn main_inner() -> Result<(), ExitCodes> {
         ExitCodes::UnknownError
     })?;
 
+    if arguments.self_test {
+        let report = rt.block_on(self_test::run(&node_config));
+        info!(target: LOG_TARGET, "Self-test report:\n{}", report);
+        println!("{}", report);
+        return if report.passed() {
+            Ok(())
+        } else {
+            Err(ExitCodes::SelfTestFailed)
+        };
+    }
+
     // Load or create the Node identity
     let wallet_identity = setup_node_identity(
         &node_config.wallet_identity_file,
+        node_config.identity_passphrase.as_deref(),
         &node_config.public_address,
         arguments.create_id ||
             // If the base node identity exists, we want to be sure that the wallet identity exists
@@ -97,19 +170,23 @@ fn main_inner() -> Result<(), ExitCodes> {
     )?;
     let node_identity = setup_node_identity(
         &node_config.identity_file,
+        node_config.identity_passphrase.as_deref(),
         &node_config.public_address,
         arguments.create_id,
         PeerFeatures::COMMUNICATION_NODE,
     )?;
 
     // Build, node, build!
-    let shutdown = Shutdown::new();
+    let mut shutdown = Shutdown::new();
+    // The node can also be shut down on its own, e.g. by the resource monitor when disk space runs out
+    let node_shutdown = shutdown.new_child();
+    let node_shutdown_listener = node_shutdown.to_listener();
     let ctx = rt
         .block_on(builder::configure_and_initialize_node(
             &node_config,
             node_identity,
             wallet_identity,
-            shutdown.to_signal(),
+            node_shutdown.to_signal(),
         ))
         .map_err(|err| {
             error!(target: LOG_TARGET, "{}", err);
@@ -132,15 +209,49 @@ fn main_inner() -> Result<(), ExitCodes> {
     }
 
     // Run, node, run!
-    let parser = Parser::new(rt.handle().clone(), &ctx);
-    let base_node_handle = rt.spawn(ctx.run(rt.handle().clone()));
+    // Settings that are safe to change live are applied when the config file changes
+    let config_watcher = ConfigWatcher::new(arguments.bootstrap.clone(), node_config, CONFIG_WATCH_INTERVAL);
+    let node_config = config_watcher.current();
+    let resource_monitor = ResourceMonitor::new(
+        node_config.data_dir.clone(),
+        ResourceLimits::from_global_config(&node_config),
+        Duration::from_secs(node_config.resource_check_interval),
+        ctx.block_sync_pause_flag(),
+        ctx.notifier(),
+        config_watcher.subscribe(),
+        node_shutdown,
+    );
+    rt.spawn(resource_monitor.run());
+    rt.spawn(ctx.notifier().follow_config_updates(config_watcher.subscribe()));
+    rt.spawn(config_watcher.run());
+    let parser = Parser::new(rt.handle().clone(), &ctx, &arguments.bootstrap);
+    let base_node_guard = shutdown
+        .to_listener()
+        .named_guard(ShutdownPhase::Terminate, "base node");
+    let base_node_handle = rt.spawn({
+        let handle = rt.handle().clone();
+        async move {
+            ctx.run(handle).await;
+            base_node_guard.release();
+        }
+    });
 
     info!(
         target: LOG_TARGET,
         "Node has been successfully configured and initialized. Starting CLI loop."
     );
 
-    cli_loop(parser, shutdown);
+    cli_loop(parser, &mut shutdown, &node_shutdown_listener);
+
+    match rt.block_on(shutdown.trigger_with_timeout(SHUTDOWN_TIMEOUT)) {
+        Ok(report) if !report.is_complete() => {
+            error!(target: LOG_TARGET, "{}", report);
+            println!("{}", report);
+            return Err(ExitCodes::UnknownError);
+        },
+        Ok(_) => {},
+        Err(_) => error!(target: LOG_TARGET, "Shutdown signal failed to trigger"),
+    }
 
     match rt.block_on(base_node_handle) {
         Ok(_) => info!(target: LOG_TARGET, "Node shutdown successfully."),
@@ -172,7 +283,28 @@ fn setup_runtime(config: &GlobalConfig) -> Result<Runtime, String> {
         .map_err(|e| format!("There was an error while building the node runtime. {}", e.to_string()))
 }
 
-fn cli_loop(parser: Parser, mut shutdown: Shutdown) {
+/// Add a secret to the encrypted secrets file, creating the file if it does not exist yet
+fn set_secret(bootstrap: &ConfigBootstrap, name: &str) -> Result<(), String> {
+    let mut secrets = if bootstrap.secrets.is_unlocked() {
+        bootstrap.secrets.clone()
+    } else {
+        println!("Creating a new secrets file at {:?}", bootstrap.secrets_file);
+        secrets::unlock_secrets_file(&bootstrap.secrets_file).map_err(|e| e.to_string())?
+    };
+    let value = rpassword::read_password_from_tty(Some(&format!("Enter the value of '{}': ", name)))
+        .map_err(|e| format!("Could not read the secret. {}", e))?;
+    secrets.insert(name, value).map_err(|e| e.to_string())?;
+    secrets
+        .save()
+        .map_err(|e| format!("Could not save the secrets file. {}", e))?;
+    println!(
+        "Saved '{}'. Use \"secret:{}\" in the configuration file to refer to it.",
+        name, name
+    );
+    Ok(())
+}
+
+fn cli_loop(parser: Parser, shutdown: &mut Shutdown, node_shutdown: &ShutdownListener) {
     let cli_config = Config::builder()
         .history_ignore_space(true)
         .completion_type(CompletionType::List)
@@ -183,11 +315,14 @@ fn cli_loop(parser: Parser, mut shutdown: Shutdown) {
     rustyline.set_helper(Some(parser));
     loop {
         let readline = rustyline.readline(">> ");
+        if node_shutdown.is_triggered() {
+            break;
+        }
         match readline {
             Ok(line) => {
                 rustyline.add_history_entry(line.as_str());
                 if let Some(p) = rustyline.helper_mut().as_deref_mut() {
-                    p.handle_command(&line, &mut shutdown)
+                    p.handle_command(&line, shutdown)
                 }
             },
             Err(ReadlineError::Interrupted) => {
@@ -215,12 +350,13 @@ fn cli_loop(parser: Parser, mut shutdown: Shutdown) {
 
 fn setup_node_identity(
     identity_file: &PathBuf,
+    passphrase: Option<&str>,
     public_address: &Multiaddr,
     create_id: bool,
     peer_features: PeerFeatures,
 ) -> Result<Arc<NodeIdentity>, ExitCodes>
 {
-    match load_identity(identity_file) {
+    match load_identity(identity_file, passphrase) {
         Ok(id) => Ok(Arc::new(id)),
         Err(e) => {
             if !create_id {
@@ -235,7 +371,7 @@ fn setup_node_identity(
 
             debug!(target: LOG_TARGET, "Node id not found. {}. Creating new ID", e);
 
-            match create_new_base_node_identity(identity_file, public_address.clone(), peer_features) {
+            match create_new_base_node_identity(identity_file, public_address.clone(), peer_features, passphrase) {
                 Ok(id) => {
                     info!(
                         target: LOG_TARGET,
@@ -36,11 +36,15 @@ pub fn build_miner<H: AsRef<ServiceHandles>>(
     event_stream: Subscriber<StateEvent>,
     consensus_manager: ConsensusManager,
     num_threads: usize,
+    coinbase_extra: Option<String>,
 ) -> Miner
 {
     let handles = handles.as_ref();
     let node_local_interface = handles.get_handle::<LocalNodeCommsInterface>().unwrap();
     let mut miner = Miner::new(kill_signal, consensus_manager, &node_local_interface, num_threads);
+    if let Some(extra) = coinbase_extra {
+        miner.set_coinbase_extra(extra.into_bytes());
+    }
     miner.subscribe_to_state_change(event_stream);
     miner
 }
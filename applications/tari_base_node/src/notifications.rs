@@ -0,0 +1,402 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Operator notifications for noteworthy node events.
+//!
+//! The [NotificationMonitor] subscribes to the state machine, block, connectivity and wallet event streams and passes
+//! the events selected in the `[notifications]` config section to the [Notifier]. The notifier POSTs a JSON object
+//! `{ "event": ..., "message": ... }` to each configured webhook URL and runs the configured hook command with the
+//! event and message as arguments (also available in the `TARI_EVENT` and `TARI_MESSAGE` environment variables).
+//!
+//! Changes to the `[notifications]` section are picked up while the node is running, but notifications must be
+//! enabled when the node starts for the monitor to run at all.
+
+use crate::address_monitor::parse_http_url;
+use futures::StreamExt;
+use log::*;
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use strum_macros::{Display, EnumString};
+use tari_broadcast_channel::Subscriber;
+use tari_common::{ConfigUpdateReceiver, GlobalConfig};
+use tari_comms::connectivity::{ConnectivityEvent, ConnectivityRequester};
+use tari_core::{
+    base_node::{
+        comms_interface::BlockEvent,
+        states::{StateEvent, SyncStatus},
+    },
+    chain_storage::BlockAddResult,
+};
+use tari_shutdown::ShutdownSignal;
+use tari_wallet::transaction_service::handle::{TransactionEvent, TransactionServiceHandle};
+use tokio::{io::AsyncWriteExt, net::TcpStream, process::Command, sync::broadcast, time};
+
+const LOG_TARGET: &str = "base_node::notifications";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_REORG_DEPTH_THRESHOLD: u64 = 3;
+const DEFAULT_MIN_PEER_COUNT: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum NotificationKind {
+    NodeSynced,
+    Reorg,
+    LowPeerCount,
+    BlockMined,
+    PaymentReceived,
+    ResourceWarning,
+}
+
+#[derive(Clone, Debug)]
+pub struct NotificationConfig {
+    pub webhook_urls: Vec<String>,
+    pub hook_command: Option<PathBuf>,
+    /// The events to notify on. All events are notified if this is empty.
+    pub events: Vec<NotificationKind>,
+    pub reorg_depth_threshold: u64,
+    pub min_peer_count: usize,
+}
+
+impl NotificationConfig {
+    pub fn from_global_config(config: &GlobalConfig) -> Result<Self, String> {
+        let events = config
+            .notification_events
+            .iter()
+            .map(|event| {
+                event
+                    .parse()
+                    .map_err(|_| format!("Invalid notification event '{}'", event))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            webhook_urls: config.notification_webhook_urls.clone(),
+            hook_command: config.notification_hook_command.clone(),
+            events,
+            reorg_depth_threshold: config
+                .notification_reorg_depth_threshold
+                .unwrap_or(DEFAULT_REORG_DEPTH_THRESHOLD),
+            min_peer_count: config.notification_min_peer_count.unwrap_or(DEFAULT_MIN_PEER_COUNT),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.webhook_urls.is_empty() || self.hook_command.is_some()
+    }
+
+    pub fn is_selected(&self, kind: NotificationKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// Delivers notifications to the configured webhooks and hook command. Delivery happens in the background and
+/// failures are only logged, so notifying never blocks or fails the caller.
+#[derive(Clone)]
+pub struct Notifier {
+    config: Arc<RwLock<Arc<NotificationConfig>>>,
+}
+
+impl Notifier {
+    pub fn new(config: NotificationConfig) -> Self {
+        Self {
+            config: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    pub fn config(&self) -> Arc<NotificationConfig> {
+        self.config
+            .read()
+            .expect("Notifier config lock should not be poisoned")
+            .clone()
+    }
+
+    pub fn set_config(&self, config: NotificationConfig) {
+        *self
+            .config
+            .write()
+            .expect("Notifier config lock should not be poisoned") = Arc::new(config);
+    }
+
+    /// Apply changes to the `[notifications]` config section until the config watcher stops
+    pub async fn follow_config_updates(self, mut config_updates: ConfigUpdateReceiver) {
+        while let Some(update) = config_updates.next().await {
+            // A lagged receiver is skipped, the next update contains the latest configuration
+            let update = match update {
+                Ok(update) => update,
+                Err(_) => continue,
+            };
+            match NotificationConfig::from_global_config(&update.current) {
+                Ok(config) => {
+                    debug!(target: LOG_TARGET, "Notification config updated");
+                    self.set_config(config);
+                },
+                Err(err) => warn!(
+                    target: LOG_TARGET,
+                    "Keeping the current notification config. The new one is invalid: {}", err
+                ),
+            }
+        }
+    }
+
+    pub fn notify(&self, kind: NotificationKind, message: String) {
+        let config = self.config();
+        if !config.is_enabled() || !config.is_selected(kind) {
+            return;
+        }
+        debug!(target: LOG_TARGET, "Sending '{}' notification: {}", kind, message);
+        let body = serde_json::json!({ "event": kind.to_string(), "message": message }).to_string();
+        for url in config.webhook_urls.clone() {
+            let body = body.clone();
+            tokio::spawn(async move {
+                let result = match time::timeout(REQUEST_TIMEOUT, post_webhook(&url, &body)).await {
+                    Ok(result) => result,
+                    Err(_) => Err("Timed out".to_string()),
+                };
+                if let Err(err) = result {
+                    warn!(target: LOG_TARGET, "Failed to notify webhook '{}': {}", url, err);
+                }
+            });
+        }
+        if let Some(command) = config.hook_command.clone() {
+            tokio::spawn(async move {
+                let result = Command::new(&command)
+                    .arg(kind.to_string())
+                    .arg(&message)
+                    .env("TARI_EVENT", kind.to_string())
+                    .env("TARI_MESSAGE", &message)
+                    .status()
+                    .await;
+                match result {
+                    Ok(status) if status.success() => {},
+                    Ok(status) => warn!(
+                        target: LOG_TARGET,
+                        "Notification hook '{}' exited with {}",
+                        command.display(),
+                        status
+                    ),
+                    Err(err) => warn!(
+                        target: LOG_TARGET,
+                        "Failed to run notification hook '{}': {}",
+                        command.display(),
+                        err
+                    ),
+                }
+            });
+        }
+    }
+}
+
+/// POST `body` as JSON to a `http://` webhook URL
+async fn post_webhook(url: &str, body: &str) -> Result<(), String> {
+    let (host, port, path) = parse_http_url(url)?;
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .map_err(|err| format!("Could not connect: {}", err))?;
+    let request = format!(
+        "POST {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: tari_base_node\r\nContent-Type: \
+         application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|err| err.to_string())
+}
+
+/// Turns events from the node's event streams into notifications
+pub struct NotificationMonitor {
+    notifier: Notifier,
+    state_events: Subscriber<StateEvent>,
+    block_events: Subscriber<BlockEvent>,
+    connectivity: ConnectivityRequester,
+    transaction_service: TransactionServiceHandle,
+    shutdown_signal: ShutdownSignal,
+    is_synced: bool,
+    is_peer_count_low: bool,
+}
+
+impl NotificationMonitor {
+    pub fn new(
+        notifier: Notifier,
+        state_events: Subscriber<StateEvent>,
+        block_events: Subscriber<BlockEvent>,
+        connectivity: ConnectivityRequester,
+        transaction_service: TransactionServiceHandle,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self
+    {
+        Self {
+            notifier,
+            state_events,
+            block_events,
+            connectivity,
+            transaction_service,
+            shutdown_signal,
+            is_synced: false,
+            is_peer_count_low: false,
+        }
+    }
+
+    pub async fn run(mut self) {
+        if !self.notifier.config().is_enabled() {
+            debug!(target: LOG_TARGET, "No notification webhooks or hook command configured");
+            return;
+        }
+        info!(target: LOG_TARGET, "Notification monitor started");
+        let mut state_events = self.state_events.clone().fuse();
+        let mut block_events = self.block_events.clone().fuse();
+        let mut connectivity_events = self.connectivity.subscribe_event_stream().fuse();
+        let mut transaction_events = self.transaction_service.get_event_stream_fused();
+        let mut shutdown_signal = self.shutdown_signal.clone();
+        loop {
+            futures::select! {
+                event = state_events.select_next_some() => self.handle_state_event(&event),
+                event = block_events.select_next_some() => self.handle_block_event(&event),
+                event = connectivity_events.select_next_some() => self.handle_connectivity_event(event).await,
+                event = transaction_events.select_next_some() => self.handle_transaction_event(&event).await,
+                _ = shutdown_signal => {
+                    info!(target: LOG_TARGET, "Notification monitor shutting down because the shutdown signal was received");
+                    break;
+                },
+            }
+        }
+    }
+
+    fn handle_state_event(&mut self, event: &StateEvent) {
+        match event {
+            StateEvent::BlocksSynchronized if !self.is_synced => {
+                self.is_synced = true;
+                self.notifier.notify(
+                    NotificationKind::NodeSynced,
+                    "The node has synchronised with the network".to_string(),
+                );
+            },
+            StateEvent::FallenBehind(SyncStatus::Lagging(_, _)) => {
+                self.is_synced = false;
+            },
+            _ => {},
+        }
+    }
+
+    fn handle_block_event(&self, event: &BlockEvent) {
+        if let BlockEvent::Verified((block, BlockAddResult::ChainReorg((removed, added)))) = event {
+            let depth = removed.len() as u64;
+            if depth >= self.notifier.config().reorg_depth_threshold {
+                self.notifier.notify(
+                    NotificationKind::Reorg,
+                    format!(
+                        "Chain reorg removed {} block(s) and added {} block(s). New tip is at height {}",
+                        depth,
+                        added.len(),
+                        block.header.height
+                    ),
+                );
+            }
+        }
+    }
+
+    async fn handle_connectivity_event(&mut self, event: Result<Arc<ConnectivityEvent>, broadcast::RecvError>) {
+        match event {
+            Ok(event) => match *event {
+                ConnectivityEvent::PeerConnected(_) |
+                ConnectivityEvent::PeerDisconnected { .. } |
+                ConnectivityEvent::Banned(_) => {},
+                _ => return,
+            },
+            Err(broadcast::RecvError::Lagged(_)) => {},
+            Err(broadcast::RecvError::Closed) => return,
+        }
+        let num_peers = match self.connectivity.get_connectivity_status().await {
+            Ok(status) => status.num_connected_peers(),
+            Err(err) => {
+                warn!(target: LOG_TARGET, "Failed to get connectivity status: {}", err);
+                return;
+            },
+        };
+        let min_peer_count = self.notifier.config().min_peer_count;
+        if num_peers < min_peer_count && !self.is_peer_count_low {
+            self.is_peer_count_low = true;
+            self.notifier.notify(
+                NotificationKind::LowPeerCount,
+                format!(
+                    "Connected peer count dropped to {} (minimum {})",
+                    num_peers, min_peer_count
+                ),
+            );
+        } else if num_peers >= min_peer_count {
+            self.is_peer_count_low = false;
+        }
+    }
+
+    async fn handle_transaction_event(&mut self, event: &TransactionEvent) {
+        if let TransactionEvent::ReceivedFinalizedTransaction(tx_id) = event {
+            let amount = match self.transaction_service.get_completed_transactions().await {
+                Ok(mut transactions) => transactions.remove(tx_id).map(|tx| tx.amount),
+                Err(err) => {
+                    warn!(target: LOG_TARGET, "Failed to get completed transactions: {}", err);
+                    None
+                },
+            };
+            let message = match amount {
+                Some(amount) => format!("Received a payment of {} (TxId: {})", amount, tx_id),
+                None => format!("Received a payment (TxId: {})", tx_id),
+            };
+            self.notifier.notify(NotificationKind::PaymentReceived, message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn notification_kind_parse() {
+        assert_eq!(
+            "payment_received".parse::<NotificationKind>().unwrap(),
+            NotificationKind::PaymentReceived
+        );
+        assert_eq!(NotificationKind::LowPeerCount.to_string(), "low_peer_count");
+        assert!("synced".parse::<NotificationKind>().is_err());
+    }
+
+    #[test]
+    fn is_selected() {
+        let mut config = NotificationConfig {
+            webhook_urls: vec![],
+            hook_command: None,
+            events: vec![],
+            reorg_depth_threshold: DEFAULT_REORG_DEPTH_THRESHOLD,
+            min_peer_count: DEFAULT_MIN_PEER_COUNT,
+        };
+        assert!(!config.is_enabled());
+        assert!(config.is_selected(NotificationKind::Reorg));
+        config.events = vec![NotificationKind::BlockMined];
+        assert!(config.is_selected(NotificationKind::BlockMined));
+        assert!(!config.is_selected(NotificationKind::Reorg));
+    }
+}
@@ -21,9 +21,15 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::LOG_TARGET;
-use crate::{builder::NodeContainer, utils};
+use crate::{
+    aliases::{CommandAliases, ALIASES_FILE_NAME},
+    builder::NodeContainer,
+    diagnostics::{self, DiagnosticsBundle},
+    utils,
+};
 use log::*;
 use qrcode::{render::unicode, QrCode};
+use rand::rngs::OsRng;
 use rustyline::{
     completion::Completer,
     error::ReadlineError,
@@ -33,7 +39,9 @@ use rustyline::{
 };
 use rustyline_derive::{Helper, Highlighter, Validator};
 use std::{
+    fs,
     io::{self, Write},
+    path::{Path, PathBuf},
     str::FromStr,
     string::ToString,
     sync::{
@@ -44,19 +52,35 @@ use std::{
 };
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
+use tari_common::ConfigBootstrap;
 use tari_comms::{
     connection_manager::ConnectionManagerRequester,
-    peer_manager::{PeerFeatures, PeerManager, PeerQuery},
+    connectivity::ConnectivityRequester,
+    peer_manager::{Peer, PeerFeatures, PeerManager, PeerQuery},
     types::CommsPublicKey,
+    utils::signature,
     NodeIdentity,
 };
-use tari_comms_dht::{envelope::NodeDestination, DhtDiscoveryRequester};
+use tari_comms_dht::{envelope::NodeDestination, DhtDiscoveryRequester, DhtRequester};
 use tari_core::{
-    base_node::LocalNodeCommsInterface,
+    base_node::{
+        chain_metadata_service::ChainMetadataHandle,
+        chain_stats_service::ChainStatsHandle,
+        partition_detection_service::PartitionDetectionHandle,
+        states::SyncProgressHandle,
+        time_sync_service::TimeSyncHandle,
+        LocalNodeCommsInterface,
+    },
     blocks::BlockHeader,
-    mempool::service::LocalMempoolService,
+    consensus::ConsensusManager,
+    mempool::{service::LocalMempoolService, MempoolServiceError, MempoolTxFilter, TxSortOrder},
+    mining::MinerMetrics,
     tari_utilities::{hex::Hex, Hashable},
-    transactions::tari_amount::{uT, MicroTari},
+    transactions::{
+        tari_amount::{uT, MicroTari},
+        transaction::Transaction,
+        types::{PrivateKey, PublicKey, Signature},
+    },
 };
 use tari_shutdown::Shutdown;
 use tari_wallet::{
@@ -66,6 +90,11 @@ use tari_wallet::{
 };
 use tokio::{runtime, time};
 
+/// The number of blocks this node may be behind the network tip while still being considered ready
+const NETWORK_TIP_READY_THRESHOLD: u64 = 2;
+/// The number of recent blocks summarised by the chain-stats command if no number is given
+const DEFAULT_CHAIN_STATS_BLOCKS: usize = 720;
+
 /// Enum representing commands used by the basenode
 #[derive(Clone, PartialEq, Debug, Display, EnumIter, EnumString)]
 #[strum(serialize_all = "kebab_case")]
@@ -73,8 +102,11 @@ pub enum BaseNodeCommand {
     Help,
     GetBalance,
     SendTari,
+    CoinSplit,
     GetChainMetadata,
+    GetNetworkStatus,
     ListPeers,
+    Peers,
     BanPeer,
     UnbanPeer,
     ListConnections,
@@ -85,8 +117,17 @@ pub enum BaseNodeCommand {
     GetBlock,
     GetMempoolStats,
     GetMempoolState,
+    Mempool,
+    SubmitTransaction,
     Whoami,
+    SignMessage,
+    VerifyMessage,
+    CheckClock,
+    ChainStats,
+    DhtStatus,
     ToggleMining,
+    Maintenance,
+    Diagnostics,
     Quit,
     Exit,
 }
@@ -97,16 +138,30 @@ pub struct Parser {
     executor: runtime::Handle,
     wallet_node_identity: Arc<NodeIdentity>,
     discovery_service: DhtDiscoveryRequester,
+    dht_requester: DhtRequester,
     base_node_identity: Arc<NodeIdentity>,
     peer_manager: Arc<PeerManager>,
     connection_manager: ConnectionManagerRequester,
+    connectivity: ConnectivityRequester,
     commands: Vec<String>,
+    aliases: CommandAliases,
     hinter: HistoryHinter,
     wallet_output_service: OutputManagerHandle,
     node_service: LocalNodeCommsInterface,
     mempool_service: LocalMempoolService,
+    time_sync: TimeSyncHandle,
+    chain_stats: ChainStatsHandle,
+    partition_detection: PartitionDetectionHandle,
+    chain_metadata: ChainMetadataHandle,
+    sync_progress: SyncProgressHandle,
+    consensus_rules: ConsensusManager,
     wallet_transaction_service: TransactionServiceHandle,
     enable_miner: Arc<AtomicBool>,
+    miner_metrics: MinerMetrics,
+    maintenance_mode: Arc<AtomicBool>,
+    mining_before_maintenance: bool,
+    base_path: PathBuf,
+    config_path: PathBuf,
 }
 
 // This will go through all instructions and look for potential matches
@@ -114,9 +169,11 @@ impl Completer for Parser {
     type Candidate = String;
 
     fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Result<(usize, Vec<String>), ReadlineError> {
+        let aliases = self.aliases.names();
         let completions = self
             .commands
             .iter()
+            .chain(aliases.iter())
             .filter(|cmd| cmd.starts_with(line))
             .cloned()
             .collect();
@@ -138,29 +195,62 @@ impl Hinter for Parser {
 
 impl Parser {
     /// creates a new parser struct
-    pub fn new(executor: runtime::Handle, ctx: &NodeContainer) -> Self {
+    pub fn new(executor: runtime::Handle, ctx: &NodeContainer, bootstrap: &ConfigBootstrap) -> Self {
+        let commands = BaseNodeCommand::iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        let aliases = load_aliases(&bootstrap.base_path.join(ALIASES_FILE_NAME), &commands);
         Parser {
             executor,
             wallet_node_identity: ctx.wallet_node_identity(),
             discovery_service: ctx.base_node_dht().discovery_service_requester(),
+            dht_requester: ctx.base_node_dht().dht_requester(),
             base_node_identity: ctx.base_node_identity(),
             peer_manager: ctx.base_node_comms().peer_manager(),
             connection_manager: ctx.base_node_comms().connection_manager(),
-            commands: BaseNodeCommand::iter().map(|x| x.to_string()).collect(),
+            connectivity: ctx.base_node_comms().connectivity(),
+            commands,
+            aliases,
             hinter: HistoryHinter {},
             wallet_output_service: ctx.output_manager(),
             node_service: ctx.local_node(),
             mempool_service: ctx.local_mempool(),
+            time_sync: ctx.time_sync(),
+            chain_stats: ctx.chain_stats(),
+            partition_detection: ctx.partition_detection(),
+            chain_metadata: ctx.chain_metadata(),
+            sync_progress: ctx.sync_progress(),
+            consensus_rules: ctx.consensus_rules(),
             wallet_transaction_service: ctx.wallet_transaction_service(),
             enable_miner: ctx.miner_enabled(),
+            miner_metrics: ctx.miner_metrics(),
+            maintenance_mode: ctx.maintenance_mode(),
+            mining_before_maintenance: false,
+            base_path: bootstrap.base_path.clone(),
+            config_path: bootstrap.config.clone(),
         }
     }
 
-    /// This will parse the provided command and execute the task
+    /// This will expand any alias in the provided command line and execute the resulting commands in order
     pub fn handle_command(&mut self, command_str: &str, shutdown: &mut Shutdown) {
         if command_str.trim().is_empty() {
             return;
         }
+        let commands = match self.aliases.expand(command_str) {
+            Ok(commands) => commands,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            },
+        };
+        for command in commands {
+            if shutdown.is_triggered() {
+                break;
+            }
+            self.handle_single_command(&command, shutdown);
+        }
+    }
+
+    /// This will parse the provided command and execute the task
+    fn handle_single_command(&mut self, command_str: &str, shutdown: &mut Shutdown) {
         let mut args = command_str.split_whitespace();
         let command = BaseNodeCommand::from_str(args.next().unwrap_or(&"help"));
         if command.is_err() {
@@ -191,15 +281,24 @@ impl Parser {
             SendTari => {
                 self.process_send_tari(args);
             },
+            CoinSplit => {
+                self.process_coin_split(args);
+            },
             GetChainMetadata => {
                 self.process_get_chain_meta();
             },
+            GetNetworkStatus => {
+                self.process_get_network_status();
+            },
             DiscoverPeer => {
                 self.process_discover_peer(args);
             },
             ListPeers => {
                 self.process_list_peers(args);
             },
+            Peers => {
+                self.process_peers(args);
+            },
             CheckDb => {
                 self.process_check_db();
             },
@@ -221,6 +320,9 @@ impl Parser {
             ToggleMining => {
                 self.process_toggle_mining();
             },
+            Maintenance => {
+                self.process_maintenance(args);
+            },
             GetBlock => {
                 self.process_get_block(args);
             },
@@ -230,9 +332,33 @@ impl Parser {
             GetMempoolState => {
                 self.process_get_mempool_state();
             },
+            Mempool => {
+                self.process_mempool(args);
+            },
+            SubmitTransaction => {
+                self.process_submit_transaction(args);
+            },
             Whoami => {
                 self.process_whoami();
             },
+            SignMessage => {
+                self.process_sign_message(args);
+            },
+            VerifyMessage => {
+                self.process_verify_message(args);
+            },
+            CheckClock => {
+                self.process_check_clock();
+            },
+            ChainStats => {
+                self.process_chain_stats(args);
+            },
+            DhtStatus => {
+                self.process_dht_status();
+            },
+            Diagnostics => {
+                self.process_diagnostics(args);
+            },
             Exit | Quit => {
                 println!("Shutting down...");
                 info!(
@@ -252,6 +378,13 @@ impl Parser {
                 println!("Available commands are: ");
                 let joined = self.commands.join(", ");
                 println!("{}", joined);
+                if !self.aliases.is_empty() {
+                    println!(
+                        "Aliases defined in {}: {}",
+                        ALIASES_FILE_NAME,
+                        self.aliases.names().join(", ")
+                    );
+                }
             },
             GetBalance => {
                 println!("Gets your balance");
@@ -260,15 +393,39 @@ impl Parser {
                 println!("Sends an amount of Tari to a address call this command via:");
                 println!("send-tari [amount of tari to send] [destination public key or emoji id] [optional: msg]");
             },
+            CoinSplit => {
+                println!(
+                    "Splits your wallet's funds into a number of outputs of the same value, so that several \
+                     transactions can be sent at the same time, call this command via:"
+                );
+                println!("coin-split [amount of tari per split] [number of splits]");
+            },
             GetChainMetadata => {
                 println!("Gets your base node chain meta data");
             },
+            GetNetworkStatus => {
+                println!(
+                    "Shows this node's peer connectivity, the network tip as reported by neighbouring peers, the \
+                     block sync progress, whether this node is within {} blocks of it, the miner's hashrate and \
+                     whether this node may be partitioned from the network",
+                    NETWORK_TIP_READY_THRESHOLD
+                );
+            },
             DiscoverPeer => {
                 println!("Attempt to discover a peer on the Tari network");
             },
             ListPeers => {
                 println!("Lists the peers that this node knows about");
             },
+            Peers => {
+                println!(
+                    "Exports the peer database, including addresses, connection stats and bans, to a JSON file or \
+                     imports peers from such a file. Imported peers are merged with the known peers and imported bans \
+                     are applied:"
+                );
+                println!("peers export [output file] [optional: --banned]");
+                println!("peers import [input file]");
+            },
             BanPeer => {
                 println!("Bans a peer");
             },
@@ -292,6 +449,13 @@ impl Parser {
             ToggleMining => {
                 println!("Enable or disable the miner on this node, calling this command will toggle the state");
             },
+            Maintenance => {
+                println!(
+                    "Puts the node into maintenance mode, where new mempool transactions are refused and block relay \
+                     and mining are paused while requests from other nodes are still served:"
+                );
+                println!("maintenance [on|off]");
+            },
             GetBlock => {
                 println!("View a block of a height, call this command via:");
                 println!("get-block [height of the block]");
@@ -302,12 +466,66 @@ impl Parser {
             GetMempoolState => {
                 println!("Retrieves your mempools state");
             },
+            Mempool => {
+                println!(
+                    "Lists the unconfirmed, orphaned and time-locked transactions in your mempool, shows a single \
+                     transaction or exports all of them to a JSON file:"
+                );
+                println!(
+                    "mempool list [optional: --min-fee [fee per gram in uT]] [optional: --sort fee|age] [optional: \
+                     --limit [count]]"
+                );
+                println!("mempool get [excess sig public nonce] [excess sig signature]");
+                println!("mempool export [output file]");
+            },
+            SubmitTransaction => {
+                println!(
+                    "Runs full validation of a JSON encoded transaction against the mempool and reports the fee per \
+                     gram, weight, maturity issues and conflicting mempool transactions. The transaction is only \
+                     submitted to the mempool and broadcast when --broadcast is given:"
+                );
+                println!("submit-transaction [optional: --broadcast] [transaction json file]");
+            },
             Whoami => {
                 println!(
                     "Display identity information about this node, including: public key, node ID and the public \
                      address"
                 );
             },
+            SignMessage => {
+                println!("Signs a message with this node's identity key to prove ownership, call this command via:");
+                println!("sign-message [optional: --wallet to sign with the wallet identity] [message]");
+            },
+            VerifyMessage => {
+                println!("Verifies a message signed with sign-message, call this command via:");
+                println!("verify-message [public key] [signature] [message]");
+            },
+            CheckClock => {
+                println!("Compares the local clock against the clocks of connected peers and received blocks");
+            },
+            ChainStats => {
+                println!(
+                    "Summarises the block intervals, propagation delays and difficulties of the blocks received by \
+                     this node"
+                );
+                println!(
+                    "chain-stats [optional: --last [number of blocks, default {}]]",
+                    DEFAULT_CHAIN_STATS_BLOCKS
+                );
+            },
+            DhtStatus => {
+                println!(
+                    "Displays the number of known, online and cached neighbourhood peers in each routing table \
+                     bucket. Higher bucket numbers are closer to this node"
+                );
+            },
+            Diagnostics => {
+                println!(
+                    "Exports a tar.gz bundle with the config (secrets redacted), recent logs, chain metadata, peer \
+                     statistics, mempool stats and version info for bug reports:"
+                );
+                println!("diagnostics export [optional: output file]");
+            },
             Exit | Quit => {
                 println!("Exits the base node");
             },
@@ -335,6 +553,7 @@ impl Parser {
     // Function to process  the get chain meta data
     fn process_get_chain_meta(&mut self) {
         let mut handler = self.node_service.clone();
+        let consensus_rules = self.consensus_rules.clone();
         self.executor.spawn(async move {
             match handler.get_metadata().await {
                 Err(err) => {
@@ -342,11 +561,80 @@ impl Parser {
                     warn!(target: LOG_TARGET, "Error communicating with base node: {:?}", err);
                     return;
                 },
-                Ok(data) => println!("{}", data),
+                Ok(data) => {
+                    println!("{}", data);
+                    let next_height = data.height_of_longest_chain.map(|h| h + 1).unwrap_or(0);
+                    let constants = consensus_rules.consensus_constants();
+                    println!(
+                        "Max block weight: {}",
+                        constants.get_max_block_transaction_weight(next_height)
+                    );
+                    if let Some((height, weight)) = constants.next_max_block_weight_change(next_height) {
+                        println!("Max block weight changes to {} at height {}", weight, height);
+                    }
+                },
             };
         });
     }
 
+    // Function to process the get network status command
+    fn process_get_network_status(&mut self) {
+        let mut chain_metadata = self.chain_metadata.clone();
+        let mut node = self.node_service.clone();
+        let mut connectivity = self.connectivity.clone();
+        let mut partition_detection = self.partition_detection.clone();
+        let sync_progress = self.sync_progress.get_progress();
+        let miner_stats = self.miner_metrics.stats();
+        self.executor.spawn(async move {
+            println!("Mining: {}", miner_stats);
+            match connectivity.get_connectivity_status().await {
+                Ok(status) => println!("Connectivity: {}", status),
+                Err(err) => {
+                    println!("Failed to retrieve connectivity status: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with connectivity manager: {:?}", err);
+                },
+            }
+            let network_state = match chain_metadata.get_network_state().await {
+                Ok(state) => state,
+                Err(err) => {
+                    println!("Failed to retrieve network status: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with chain metadata service: {:?}", err);
+                    return;
+                },
+            };
+            println!("{}", network_state);
+            println!("Block sync: {}", sync_progress);
+            match node.get_metadata().await {
+                Ok(local) => {
+                    let local_height = local.height_of_longest_chain.unwrap_or(0);
+                    println!("Local chain height: {}", local_height);
+                    if let Some(behind) = network_state.blocks_behind_tip(local_height) {
+                        println!("Blocks behind network tip: {}", behind);
+                    }
+                    println!(
+                        "Ready: {}",
+                        if network_state.is_within_tip(local_height, NETWORK_TIP_READY_THRESHOLD) {
+                            "Yes"
+                        } else {
+                            "No"
+                        }
+                    );
+                },
+                Err(err) => {
+                    println!("Failed to retrieve local chain metadata: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with base node: {:?}", err);
+                },
+            }
+            match partition_detection.get_status().await {
+                Ok(status) => println!("Health: {}", status),
+                Err(err) => {
+                    println!("Failed to retrieve partition status: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with partition detection service: {:?}", err);
+                },
+            }
+        });
+    }
+
     fn process_get_block<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
         let command_arg = args.take(4).collect::<Vec<&str>>();
         let height = if command_arg.len() == 1 {
@@ -382,6 +670,62 @@ impl Parser {
         });
     }
 
+    // Function to process the check-clock command
+    fn process_check_clock(&mut self) {
+        let mut handler = self.time_sync.clone();
+        self.executor.spawn(async move {
+            match handler.get_clock_drift_status().await {
+                Ok(status) => println!("{}", status),
+                Err(err) => {
+                    println!("Failed to retrieve clock drift status: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with time sync service: {:?}", err);
+                },
+            };
+        });
+    }
+
+    // Function to process the chain-stats command
+    fn process_chain_stats<'a, I: Iterator<Item = &'a str>>(&mut self, args: I) {
+        let mut args = args.peekable();
+        let num_blocks = if args.peek() == Some(&"--last") {
+            args.next();
+            match args.next().map(usize::from_str) {
+                Some(Ok(n)) if n > 0 => n,
+                _ => {
+                    println!("Please enter a valid number of blocks");
+                    println!("chain-stats [optional: --last [number of blocks]]");
+                    return;
+                },
+            }
+        } else {
+            DEFAULT_CHAIN_STATS_BLOCKS
+        };
+        let mut handler = self.chain_stats.clone();
+        self.executor.spawn(async move {
+            match handler.get_summary(num_blocks).await {
+                Ok(summary) => println!("{}", summary),
+                Err(err) => {
+                    println!("Failed to retrieve chain stats: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with chain stats service: {:?}", err);
+                },
+            };
+        });
+    }
+
+    // Function to process the dht-status command
+    fn process_dht_status(&mut self) {
+        let mut dht = self.dht_requester.clone();
+        self.executor.spawn(async move {
+            match dht.get_routing_table_status().await {
+                Ok(status) => println!("{}", status),
+                Err(err) => {
+                    println!("Failed to retrieve DHT status: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with DHT actor: {:?}", err);
+                },
+            };
+        });
+    }
+
     fn process_get_mempool_stats(&mut self) {
         let mut handler = self.mempool_service.clone();
         self.executor.spawn(async move {
@@ -410,6 +754,264 @@ impl Parser {
         });
     }
 
+    fn process_mempool<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        match args.next() {
+            Some("list") => self.process_mempool_list(args),
+            Some("get") => self.process_mempool_get(args),
+            Some("export") => self.process_mempool_export(args),
+            _ => {
+                println!("Invalid command, please enter as follows:");
+                println!(
+                    "mempool list [optional: --min-fee [fee per gram in uT]] [optional: --sort fee|age] [optional: \
+                     --limit [count]]"
+                );
+                println!("mempool get [excess sig public nonce] [excess sig signature]");
+                println!("mempool export [output file]");
+            },
+        }
+    }
+
+    fn process_mempool_list<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let mut filter = MempoolTxFilter::default();
+        while let Some(arg) = args.next() {
+            match (arg, args.next()) {
+                ("--min-fee", Some(fee)) => match u64::from_str(fee) {
+                    Ok(fee) => filter.min_fee_per_gram = MicroTari(fee),
+                    Err(_) => {
+                        println!("Please enter a valid fee per gram in uT");
+                        return;
+                    },
+                },
+                ("--sort", Some("fee")) => filter.sort = TxSortOrder::Fee,
+                ("--sort", Some("age")) => filter.sort = TxSortOrder::Age,
+                ("--limit", Some(limit)) => match usize::from_str(limit) {
+                    Ok(limit) => filter.limit = Some(limit),
+                    Err(_) => {
+                        println!("Please enter a valid number of transactions to list");
+                        return;
+                    },
+                },
+                _ => {
+                    println!("Invalid command, please enter as follows:");
+                    println!(
+                        "mempool list [optional: --min-fee [fee per gram in uT]] [optional: --sort fee|age] \
+                         [optional: --limit [count]]"
+                    );
+                    return;
+                },
+            }
+        }
+        let mut handler = self.mempool_service.clone();
+        self.executor.spawn(async move {
+            match handler.list_transactions(filter).await {
+                Ok(txs) => {
+                    for tx in &txs {
+                        println!("{}", tx);
+                    }
+                    println!("{} transaction(s)", txs.len());
+                },
+                Err(err) => {
+                    println!("Failed to list mempool transactions: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with local mempool: {:?}", err,);
+                },
+            };
+        });
+    }
+
+    fn process_mempool_get<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let public_nonce = args.next().and_then(|nonce| PublicKey::from_hex(nonce).ok());
+        let signature = args.next().and_then(|sig| PrivateKey::from_hex(sig).ok());
+        let excess_sig = match (public_nonce, signature) {
+            (Some(public_nonce), Some(signature)) => Signature::new(public_nonce, signature),
+            _ => {
+                println!("Please enter a valid excess signature, as listed by mempool list");
+                println!("mempool get [excess sig public nonce] [excess sig signature]");
+                return;
+            },
+        };
+        let mut handler = self.mempool_service.clone();
+        self.executor.spawn(async move {
+            match handler.get_transaction(excess_sig).await {
+                Ok(Some(tx)) => {
+                    println!("{}", tx);
+                    if let Some(transaction) = tx.transaction {
+                        println!("{}", transaction);
+                    }
+                },
+                Ok(None) => println!("Transaction not found in the mempool"),
+                Err(err) => {
+                    println!("Failed to retrieve mempool transaction: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with local mempool: {:?}", err,);
+                },
+            };
+        });
+    }
+
+    fn process_mempool_export<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let path = match args.next() {
+            Some(path) => PathBuf::from(path),
+            None => {
+                println!("Please enter the path of the file to export to");
+                println!("mempool export [output file]");
+                return;
+            },
+        };
+        let mut filter = MempoolTxFilter {
+            include_transactions: true,
+            ..Default::default()
+        };
+        let mut handler = self.mempool_service.clone();
+        self.executor.spawn(async move {
+            // The mempool returns the transactions a page at a time
+            let mut txs = Vec::new();
+            loop {
+                let page = match handler.list_transactions(filter.clone()).await {
+                    Ok(page) => page,
+                    Err(err) => {
+                        println!("Failed to list mempool transactions: {:?}", err);
+                        warn!(target: LOG_TARGET, "Error communicating with local mempool: {:?}", err,);
+                        return;
+                    },
+                };
+                match page.last() {
+                    Some(last) => filter.after = Some(last.excess_sig.clone()),
+                    None => break,
+                }
+                txs.extend(page);
+            }
+            match serde_json::to_string_pretty(&txs)
+                .map_err(|e| e.to_string())
+                .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()))
+            {
+                Ok(()) => println!("Exported {} transaction(s) to {}", txs.len(), path.display()),
+                Err(err) => println!("Failed to export mempool transactions to {}: {}", path.display(), err),
+            }
+        });
+    }
+
+    fn process_submit_transaction<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
+        let mut args = args.peekable();
+        let broadcast = if args.peek() == Some(&"--broadcast") {
+            args.next();
+            true
+        } else {
+            false
+        };
+        let path = match args.next() {
+            Some(path) => PathBuf::from(path),
+            None => {
+                println!("Please enter the path to a JSON encoded transaction");
+                println!("submit-transaction [optional: --broadcast] [transaction json file]");
+                return;
+            },
+        };
+        let transaction = match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Transaction>(&json).map_err(|e| e.to_string()))
+        {
+            Ok(tx) => tx,
+            Err(err) => {
+                println!("Failed to read transaction from {}: {}", path.display(), err);
+                return;
+            },
+        };
+        let mut handler = self.mempool_service.clone();
+        self.executor.spawn(async move {
+            let report = match handler.validate_transaction(transaction.clone()).await {
+                Ok(report) => report,
+                Err(err) => {
+                    println!("Failed to validate transaction: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with local mempool: {:?}", err,);
+                    return;
+                },
+            };
+            print!("{}", report);
+            if !broadcast {
+                return;
+            }
+            if !report.is_accepted() {
+                println!("Transaction was not submitted because the mempool would not accept it");
+                return;
+            }
+            match handler.submit_transaction(transaction).await {
+                Ok(storage) => println!("Transaction submitted: {}", storage),
+                Err(MempoolServiceError::TransactionRejected(rejection)) => {
+                    println!("Transaction rejected by the mempool: {}", rejection)
+                },
+                Err(err) => {
+                    println!("Failed to submit transaction: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with local mempool: {:?}", err,);
+                },
+            };
+        });
+    }
+
+    fn process_diagnostics<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        if args.next() != Some("export") {
+            println!("Invalid command, please enter as follows:");
+            println!("diagnostics export [optional: output file]");
+            return;
+        }
+        let output_path = args
+            .next()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| diagnostics::default_bundle_path(&self.base_path));
+
+        let mut bundle = DiagnosticsBundle::new();
+        bundle.add_version_info();
+        bundle.add_config(&self.config_path);
+        // The default log4rs configuration writes log files relative to the working directory
+        bundle.add_logs(&PathBuf::from("log"));
+
+        let mut node_service = self.node_service.clone();
+        let mut mempool_service = self.mempool_service.clone();
+        let peer_manager = self.peer_manager.clone();
+        let mut connection_manager = self.connection_manager.clone();
+        self.executor.spawn(async move {
+            let chain_metadata = match node_service.get_metadata().await {
+                Ok(metadata) => metadata.to_string(),
+                Err(err) => format!("Failed to retrieve chain metadata: {:?}", err),
+            };
+            bundle.add("chain_metadata.txt", chain_metadata);
+
+            let mempool = match mempool_service.get_mempool_stats().await {
+                Ok(stats) => stats.to_string(),
+                Err(err) => format!("Failed to retrieve mempool stats: {:?}", err),
+            };
+            bundle.add("mempool.txt", mempool);
+
+            let mut peer_stats = match peer_manager.perform_query(PeerQuery::new()).await {
+                Ok(peers) => format!(
+                    "Known peers: {}\nBase nodes: {}\nWallets: {}\nBanned: {}\n",
+                    peers.len(),
+                    peers
+                        .iter()
+                        .filter(|p| p.features.contains(PeerFeatures::MESSAGE_PROPAGATION))
+                        .count(),
+                    peers
+                        .iter()
+                        .filter(|p| p.features == PeerFeatures::COMMUNICATION_CLIENT)
+                        .count(),
+                    peers.iter().filter(|p| p.is_banned()).count()
+                ),
+                Err(err) => format!("Failed to query peers: {:?}", err),
+            };
+            match connection_manager.get_active_connections().await {
+                Ok(conns) => peer_stats.push_str(&format!("Active connections: {}\n", conns.len())),
+                Err(err) => peer_stats.push_str(&format!("Failed to list connections: {:?}\n", err)),
+            }
+            bundle.add("peers.txt", peer_stats);
+
+            match bundle.write_tar_gz(&output_path) {
+                Ok(_) => println!("Diagnostics bundle written to {}", output_path.to_string_lossy()),
+                Err(err) => {
+                    println!("Failed to write diagnostics bundle: {}", err);
+                    error!(target: LOG_TARGET, "Could not write diagnostics bundle: {}", err);
+                },
+            }
+        });
+    }
+
     fn process_discover_peer<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
         let mut dht = self.discovery_service.clone();
 
@@ -439,6 +1041,104 @@ impl Parser {
         });
     }
 
+    fn process_peers<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        match args.next() {
+            Some("export") => self.process_peers_export(args),
+            Some("import") => self.process_peers_import(args),
+            _ => {
+                println!("Invalid command, please enter as follows:");
+                println!("peers export [output file] [optional: --banned]");
+                println!("peers import [input file]");
+            },
+        }
+    }
+
+    fn process_peers_export<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let path = match args.next() {
+            Some(path) => PathBuf::from(path),
+            None => {
+                println!("Please enter the path of the file to export to");
+                println!("peers export [output file] [optional: --banned]");
+                return;
+            },
+        };
+        let banned_only = match args.next() {
+            Some("--banned") => true,
+            None => false,
+            _ => {
+                println!("Invalid command, please enter as follows:");
+                println!("peers export [output file] [optional: --banned]");
+                return;
+            },
+        };
+        let peer_manager = self.peer_manager.clone();
+        self.executor.spawn(async move {
+            let mut query = PeerQuery::new();
+            if banned_only {
+                query = query.select_where(|p| p.is_banned());
+            }
+            let peers = match peer_manager.perform_query(query).await {
+                Ok(peers) => peers,
+                Err(err) => {
+                    println!("Failed to query peers: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error querying the peer manager: {:?}", err);
+                    return;
+                },
+            };
+            match serde_json::to_string_pretty(&peers)
+                .map_err(|e| e.to_string())
+                .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()))
+            {
+                Ok(()) => println!("Exported {} peer(s) to {}", peers.len(), path.display()),
+                Err(err) => println!("Failed to export peers to {}: {}", path.display(), err),
+            }
+        });
+    }
+
+    fn process_peers_import<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let path = match args.next() {
+            Some(path) => PathBuf::from(path),
+            None => {
+                println!("Please enter the path of the file to import from");
+                println!("peers import [input file]");
+                return;
+            },
+        };
+        let peers = match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Vec<Peer>>(&json).map_err(|e| e.to_string()))
+        {
+            Ok(peers) => peers,
+            Err(err) => {
+                println!("Failed to read peers from {}: {}", path.display(), err);
+                return;
+            },
+        };
+        let peer_manager = self.peer_manager.clone();
+        self.executor.spawn(async move {
+            match peer_manager.import_peers(peers).await {
+                Ok(summary) => {
+                    println!(
+                        "Imported peers from {}: {} added, {} updated",
+                        path.display(),
+                        summary.added,
+                        summary.updated
+                    );
+                    if summary.skipped > 0 {
+                        println!(
+                            "{} peer(s) were skipped because their node id does not match their public key",
+                            summary.skipped
+                        );
+                    }
+                },
+                Err(err) => {
+                    println!("Failed to import peers: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error importing peers: {:?}", err);
+                },
+            }
+        });
+    }
+
     fn process_list_peers<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
         let peer_manager = self.peer_manager.clone();
         let filter = args.next().map(ToOwned::to_owned);
@@ -449,7 +1149,7 @@ impl Parser {
                 let filter = f.to_lowercase();
                 query = query.select_where(move |p| match filter.as_str() {
                     "basenode" | "basenodes" | "base_node" | "base-node" | "bn" => {
-                        p.features == PeerFeatures::COMMUNICATION_NODE
+                        p.features.contains(PeerFeatures::MESSAGE_PROPAGATION)
                     },
                     "wallet" | "wallets" | "w" => p.features == PeerFeatures::COMMUNICATION_CLIENT,
                     _ => false,
@@ -477,7 +1177,7 @@ impl Parser {
 
     fn process_ban_peer<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I, is_banned: bool) {
         let peer_manager = self.peer_manager.clone();
-        let mut connection_manager = self.connection_manager.clone();
+        let mut connectivity = self.connectivity.clone();
 
         let public_key = match args.next().and_then(parse_emoji_id_or_public_key) {
             Some(v) => Box::new(v),
@@ -489,29 +1189,30 @@ impl Parser {
         };
 
         self.executor.spawn(async move {
-            match peer_manager.set_banned(&public_key, is_banned).await {
-                Ok(node_id) => {
-                    if is_banned {
-                        match connection_manager.disconnect_peer(node_id).await {
-                            Ok(_) => {
-                                println!("Peer was banned.");
-                            },
-                            Err(err) => {
-                                println!(
-                                    "Peer was banned but an error occurred when disconnecting them: {:?}",
-                                    err
-                                );
-                            },
-                        }
-                    } else {
+            if is_banned {
+                let result = match peer_manager.find_by_public_key(&public_key).await {
+                    Ok(peer) => connectivity.ban_peer(peer.node_id).await,
+                    Err(err) => Err(err.into()),
+                };
+                match result {
+                    Ok(_) => {
+                        println!("Peer was banned.");
+                    },
+                    Err(err) => {
+                        println!("Failed to ban peer: {:?}", err);
+                        error!(target: LOG_TARGET, "Could not ban peer: {:?}", err);
+                    },
+                }
+            } else {
+                match peer_manager.set_banned(&public_key, false).await {
+                    Ok(_) => {
                         println!("Peer ban was removed.");
-                    }
-                },
-                Err(err) => {
-                    println!("Failed to ban/unban peer: {:?}", err);
-                    error!(target: LOG_TARGET, "Could not ban/unban peer: {:?}", err);
-                    return;
-                },
+                    },
+                    Err(err) => {
+                        println!("Failed to unban peer: {:?}", err);
+                        error!(target: LOG_TARGET, "Could not unban peer: {:?}", err);
+                    },
+                }
             }
         });
     }
@@ -543,6 +1244,10 @@ impl Parser {
     }
 
     fn process_toggle_mining(&mut self) {
+        if self.maintenance_mode.load(Ordering::SeqCst) {
+            println!("Mining is paused while the node is in maintenance mode. Use `maintenance off` first.");
+            return;
+        }
         let new_state = !self.enable_miner.load(Ordering::SeqCst);
         self.enable_miner.store(new_state, Ordering::SeqCst);
         if new_state {
@@ -553,6 +1258,36 @@ impl Parser {
         debug!(target: LOG_TARGET, "Mining state is now switched to {}", new_state);
     }
 
+    fn process_maintenance<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let enabled = self.maintenance_mode.load(Ordering::SeqCst);
+        match args.next() {
+            None => {
+                println!("Maintenance mode is {}", if enabled { "ON" } else { "OFF" });
+            },
+            Some("on") if enabled => println!("Maintenance mode is already ON"),
+            Some("on") => {
+                self.mining_before_maintenance = self.enable_miner.swap(false, Ordering::SeqCst);
+                self.maintenance_mode.store(true, Ordering::SeqCst);
+                println!("Maintenance mode is ON. New transactions are refused, block relay and mining are paused.");
+                info!(target: LOG_TARGET, "Maintenance mode enabled");
+            },
+            Some("off") if !enabled => println!("Maintenance mode is already OFF"),
+            Some("off") => {
+                self.maintenance_mode.store(false, Ordering::SeqCst);
+                if self.mining_before_maintenance {
+                    self.enable_miner.store(true, Ordering::SeqCst);
+                    println!("Mining is ON");
+                }
+                println!("Maintenance mode is OFF");
+                info!(target: LOG_TARGET, "Maintenance mode disabled");
+            },
+            Some(arg) => {
+                println!("{} is not a valid option. Please use the following format:", arg);
+                println!("maintenance [on|off]");
+            },
+        }
+    }
+
     fn process_list_headers<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
         let command_arg = args.map(|arg| arg.to_string()).take(4).collect::<Vec<String>>();
         if (command_arg.is_empty()) || (command_arg.len() > 2) {
@@ -704,7 +1439,122 @@ impl Parser {
         println!("{}", self.base_node_identity);
     }
 
+    fn process_sign_message<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
+        let mut args = args.peekable();
+        let identity = if args.peek() == Some(&"--wallet") {
+            args.next();
+            &self.wallet_node_identity
+        } else {
+            &self.base_node_identity
+        };
+        let message = args.collect::<Vec<_>>().join(" ");
+        if message.is_empty() {
+            println!("Please enter a message to sign");
+            println!("sign-message [optional: --wallet to sign with the wallet identity] [message]");
+            return;
+        }
+        match signature::sign_message(&mut OsRng, identity.secret_key().clone(), &message) {
+            Ok(sig) => {
+                println!("Public key: {}", identity.public_key().to_hex());
+                println!("Signature: {}", signature::encode_message_signature(&sig));
+                println!("Message: {}", message);
+            },
+            Err(err) => {
+                println!("Failed to sign message: {:?}", err);
+                error!(target: LOG_TARGET, "Could not sign message: {:?}", err);
+            },
+        }
+    }
+
+    fn process_verify_message<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let (public_key, sig) = match (args.next(), args.next()) {
+            (Some(public_key), Some(sig)) => (public_key, sig),
+            _ => {
+                println!("Invalid command, please enter as follows:");
+                println!("verify-message [public key] [signature] [message]");
+                return;
+            },
+        };
+        let message = args.collect::<Vec<_>>().join(" ");
+        let public_key = match CommsPublicKey::from_hex(public_key) {
+            Ok(public_key) => public_key,
+            Err(_) => {
+                println!("Invalid public key provided");
+                return;
+            },
+        };
+        let sig = match signature::decode_message_signature(sig) {
+            Some(sig) => sig,
+            None => {
+                println!("Invalid signature provided");
+                return;
+            },
+        };
+        if signature::verify_message(&public_key, &sig, &message) {
+            println!("Signature is VALID");
+        } else {
+            println!("Signature is INVALID");
+        }
+    }
+
     // Function to process  the send transaction function
+    // Function to split the wallet's funds into a number of equal valued outputs
+    fn process_coin_split<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let amount_per_split: MicroTari = match args.next().and_then(|v| v.parse::<u64>().ok()) {
+            Some(v) => v.into(),
+            None => {
+                println!("Please enter a valid amount of tari per split");
+                println!("coin-split [amount of tari per split] [number of splits]");
+                return;
+            },
+        };
+        let split_count = match args.next().and_then(|v| v.parse::<usize>().ok()) {
+            Some(v) if v > 0 => v,
+            _ => {
+                println!("Please enter a valid number of splits");
+                println!("coin-split [amount of tari per split] [number of splits]");
+                return;
+            },
+        };
+
+        let fee_per_gram = 25 * uT;
+        let mut txn_service = self.wallet_transaction_service.clone();
+        let mut oms_handle = self.wallet_output_service.clone();
+        self.executor.spawn(async move {
+            // TODO perform this function more intelligently in the Output Manager
+            let _ = oms_handle.sync_with_base_node().await;
+
+            let (tx_id, tx, fee, amount) = match oms_handle
+                .create_coin_split(amount_per_split, split_count, fee_per_gram, None)
+                .await
+            {
+                Ok(v) => v,
+                Err(OutputManagerError::NotEnoughFunds) => {
+                    println!("Not enough funds to fulfill the coin split.");
+                    return;
+                },
+                Err(e) => {
+                    println!("Something went wrong creating the coin split");
+                    warn!(target: LOG_TARGET, "Error creating coin split: {:?}", e);
+                    return;
+                },
+            };
+            match txn_service
+                .submit_transaction(tx_id, tx, fee, amount, "Coin split".to_string())
+                .await
+            {
+                Ok(_) => println!(
+                    "Coin split into {} outputs of {} submitted (TxId: {}, fee {})",
+                    split_count, amount_per_split, tx_id, fee
+                ),
+                Err(e) => {
+                    println!("Something went wrong submitting the coin split");
+                    warn!(target: LOG_TARGET, "Error submitting coin split: {:?}", e);
+                },
+            }
+        });
+    }
+
     fn process_send_tari<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
         let amount = args.next().and_then(|v| v.parse::<u64>().ok());
         if amount.is_none() {
@@ -811,6 +1661,27 @@ impl Parser {
     }
 }
 
+/// Load the CLI aliases, reporting any problem with the aliases file without preventing the node from starting
+fn load_aliases(path: &Path, commands: &[String]) -> CommandAliases {
+    match CommandAliases::load(path, commands) {
+        Ok((aliases, ignored)) => {
+            if !ignored.is_empty() {
+                println!(
+                    "The aliases {} in {} are ignored because they are built-in commands",
+                    ignored.join(", "),
+                    path.display()
+                );
+            }
+            aliases
+        },
+        Err(e) => {
+            println!("Could not load the aliases in {}: {}", path.display(), e);
+            warn!(target: LOG_TARGET, "Could not load the aliases in {}: {}", path.display(), e);
+            CommandAliases::default()
+        },
+    }
+}
+
 fn parse_emoji_id_or_public_key(key: &str) -> Option<CommsPublicKey> {
     EmojiId::str_to_pubkey(&key.trim().replace('|', ""))
         .or_else(|_| CommsPublicKey::from_hex(key))
@@ -0,0 +1,398 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Watches the resources the node needs to keep running safely.
+//!
+//! The [ResourceMonitor] periodically measures the free disk space on the data directory, the number of open file
+//! descriptors and the node's memory usage. While any of them crosses its warning threshold, block sync is paused and
+//! the operator is warned. If free disk space falls below the critical threshold, the node is shut down cleanly so that
+//! LMDB writes do not start failing part way through a transaction.
+//!
+//! The thresholds and the check interval are re-read whenever the configuration file changes.
+
+use crate::notifications::{NotificationKind, Notifier};
+use futures::{stream::Fuse, StreamExt};
+use log::*;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tari_common::{ConfigUpdate, ConfigUpdateReceiver, GlobalConfig};
+use tari_shutdown::Shutdown;
+use tokio::time;
+
+const LOG_TARGET: &str = "base_node::resource_monitor";
+const MIB: u64 = 1024 * 1024;
+const DEFAULT_MIN_FREE_DISK_SPACE_MB: u64 = 1024;
+const DEFAULT_CRITICAL_FREE_DISK_SPACE_MB: u64 = 256;
+/// The fraction of the open file limit at which block sync is paused
+const MAX_OPEN_FILES_RATIO: f64 = 0.9;
+
+/// The thresholds at which the [ResourceMonitor] takes action. All sizes are in bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceLimits {
+    pub min_free_disk_space: u64,
+    pub critical_free_disk_space: u64,
+    pub max_memory_usage: Option<u64>,
+    pub max_open_files_ratio: f64,
+}
+
+impl ResourceLimits {
+    pub fn from_global_config(config: &GlobalConfig) -> Self {
+        Self {
+            min_free_disk_space: config.min_free_disk_space_mb.unwrap_or(DEFAULT_MIN_FREE_DISK_SPACE_MB) * MIB,
+            critical_free_disk_space: config
+                .critical_free_disk_space_mb
+                .unwrap_or(DEFAULT_CRITICAL_FREE_DISK_SPACE_MB) *
+                MIB,
+            max_memory_usage: config.max_memory_usage_mb.map(|mb| mb * MIB),
+            max_open_files_ratio: MAX_OPEN_FILES_RATIO,
+        }
+    }
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            min_free_disk_space: DEFAULT_MIN_FREE_DISK_SPACE_MB * MIB,
+            critical_free_disk_space: DEFAULT_CRITICAL_FREE_DISK_SPACE_MB * MIB,
+            max_memory_usage: None,
+            max_open_files_ratio: MAX_OPEN_FILES_RATIO,
+        }
+    }
+}
+
+/// A snapshot of the node's resource usage. A value is None if it could not be measured on this platform.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub free_disk_space: Option<u64>,
+    pub open_files: Option<u64>,
+    pub max_open_files: Option<u64>,
+    pub memory_usage: Option<u64>,
+}
+
+impl ResourceUsage {
+    /// Measure the current resource usage, with free disk space measured on the file system containing `data_dir`
+    pub fn measure(data_dir: &Path) -> Self {
+        Self {
+            free_disk_space: sys::free_disk_space(data_dir),
+            open_files: sys::open_files(),
+            max_open_files: sys::max_open_files(),
+            memory_usage: sys::memory_usage(),
+        }
+    }
+
+    /// Compare this usage against the given limits
+    pub fn check(&self, limits: &ResourceLimits) -> ResourceStatus {
+        if let Some(free) = self.free_disk_space {
+            if free < limits.critical_free_disk_space {
+                return ResourceStatus::Critical(format!(
+                    "only {} MiB of disk space is left on the data directory",
+                    free / MIB
+                ));
+            }
+        }
+
+        let mut warnings = Vec::new();
+        if let Some(free) = self.free_disk_space {
+            if free < limits.min_free_disk_space {
+                warnings.push(format!("{} MiB of free disk space left", free / MIB));
+            }
+        }
+        if let (Some(open), Some(max)) = (self.open_files, self.max_open_files) {
+            if open as f64 >= max as f64 * limits.max_open_files_ratio {
+                warnings.push(format!("{} of {} file descriptors in use", open, max));
+            }
+        }
+        if let (Some(used), Some(max)) = (self.memory_usage, limits.max_memory_usage) {
+            if used > max {
+                warnings.push(format!("{} MiB of memory in use", used / MIB));
+            }
+        }
+
+        if warnings.is_empty() {
+            ResourceStatus::Ok
+        } else {
+            ResourceStatus::Warning(warnings)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ResourceStatus {
+    Ok,
+    /// One or more resources are running low. Block sync should be paused.
+    Warning(Vec<String>),
+    /// The node must shut down to protect its database
+    Critical(String),
+}
+
+impl fmt::Display for ResourceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceStatus::Ok => write!(f, "Ok"),
+            ResourceStatus::Warning(warnings) => write!(f, "Low on resources: {}", warnings.join(", ")),
+            ResourceStatus::Critical(reason) => write!(f, "Critically low on resources: {}", reason),
+        }
+    }
+}
+
+pub struct ResourceMonitor {
+    data_dir: PathBuf,
+    limits: ResourceLimits,
+    check_interval: Duration,
+    block_sync_paused: Arc<AtomicBool>,
+    notifier: Notifier,
+    config_updates: Fuse<ConfigUpdateReceiver>,
+    node_shutdown: Shutdown,
+}
+
+impl ResourceMonitor {
+    pub fn new(
+        data_dir: PathBuf,
+        limits: ResourceLimits,
+        check_interval: Duration,
+        block_sync_paused: Arc<AtomicBool>,
+        notifier: Notifier,
+        config_updates: ConfigUpdateReceiver,
+        node_shutdown: Shutdown,
+    ) -> Self
+    {
+        Self {
+            data_dir,
+            limits,
+            check_interval,
+            block_sync_paused,
+            notifier,
+            config_updates: config_updates.fuse(),
+            node_shutdown,
+        }
+    }
+
+    pub async fn run(mut self) {
+        info!(
+            target: LOG_TARGET,
+            "Resource monitor started. Checking '{}' every {:.0?}",
+            self.data_dir.to_string_lossy(),
+            self.check_interval
+        );
+        let mut interval = time::interval(self.check_interval).fuse();
+        let mut shutdown_signal = self.node_shutdown.to_signal();
+        loop {
+            futures::select! {
+                _ = interval.select_next_some() => {
+                    if self.check_resources() {
+                        break;
+                    }
+                },
+                update = self.config_updates.select_next_some() => {
+                    // A lagged receiver is skipped, the next update contains the latest configuration
+                    if let Ok(update) = update {
+                        let check_interval = self.check_interval;
+                        self.apply_config_update(&update);
+                        if self.check_interval != check_interval {
+                            interval = time::interval(self.check_interval).fuse();
+                        }
+                    }
+                },
+                _ = shutdown_signal => {
+                    info!(target: LOG_TARGET, "Resource monitor shutting down because the shutdown signal was received");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn apply_config_update(&mut self, update: &ConfigUpdate) {
+        let limits = ResourceLimits::from_global_config(&update.current);
+        if limits != self.limits {
+            info!(target: LOG_TARGET, "Resource limits changed to {:?}", limits);
+            self.limits = limits;
+        }
+        if update.changed(|c| c.resource_check_interval) {
+            self.check_interval = Duration::from_secs(update.current.resource_check_interval);
+            info!(target: LOG_TARGET, "Checking resources every {:.0?}", self.check_interval);
+        }
+    }
+
+    /// Measure the resource usage and act on it. Returns true if the node has been shut down.
+    fn check_resources(&mut self) -> bool {
+        let usage = ResourceUsage::measure(&self.data_dir);
+        trace!(target: LOG_TARGET, "Resource usage: {:?}", usage);
+        let status = usage.check(&self.limits);
+        let was_paused = self.block_sync_paused.load(Ordering::Relaxed);
+        match status {
+            ResourceStatus::Ok => {
+                if was_paused {
+                    info!(target: LOG_TARGET, "Resources have recovered. Resuming block sync.");
+                    println!("Resources have recovered. Resuming block sync.");
+                    self.block_sync_paused.store(false, Ordering::Relaxed);
+                }
+                false
+            },
+            ResourceStatus::Warning(_) => {
+                if !was_paused {
+                    let message = format!("{}. Block sync is paused until this is resolved.", status);
+                    warn!(target: LOG_TARGET, "{}", message);
+                    println!("{}", message);
+                    self.notifier.notify(NotificationKind::ResourceWarning, message);
+                    self.block_sync_paused.store(true, Ordering::Relaxed);
+                }
+                false
+            },
+            ResourceStatus::Critical(_) => {
+                self.block_sync_paused.store(true, Ordering::Relaxed);
+                let message = format!("{}. The node is shutting down to protect its database.", status);
+                error!(target: LOG_TARGET, "{}", message);
+                println!("{} Press Enter to exit.", message);
+                self.notifier.notify(NotificationKind::ResourceWarning, message);
+                if self.node_shutdown.trigger().is_err() {
+                    error!(target: LOG_TARGET, "Shutdown signal failed to trigger");
+                }
+                true
+            },
+        }
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::{ffi::CString, fs, mem, os::unix::ffi::OsStrExt, path::Path};
+
+    pub fn free_disk_space(path: &Path) -> Option<u64> {
+        let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statvfs = unsafe { mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+
+    pub fn open_files() -> Option<u64> {
+        // Reading the directory itself opens one descriptor, which is not counted
+        fs::read_dir("/dev/fd")
+            .ok()
+            .map(|entries| (entries.count() as u64).saturating_sub(1))
+    }
+
+    pub fn max_open_files() -> Option<u64> {
+        let mut limit: libc::rlimit = unsafe { mem::zeroed() };
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 || limit.rlim_cur == libc::RLIM_INFINITY {
+            return None;
+        }
+        Some(limit.rlim_cur as u64)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn memory_usage() -> Option<u64> {
+        // The second field of statm is the resident set size in pages
+        let statm = fs::read_to_string("/proc/self/statm").ok()?;
+        let resident_pages = statm.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if page_size <= 0 {
+            return None;
+        }
+        Some(resident_pages * page_size as u64)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn memory_usage() -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use std::path::Path;
+
+    pub fn free_disk_space(_path: &Path) -> Option<u64> {
+        None
+    }
+
+    pub fn open_files() -> Option<u64> {
+        None
+    }
+
+    pub fn max_open_files() -> Option<u64> {
+        None
+    }
+
+    pub fn memory_usage() -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_usage() {
+        let limits = ResourceLimits {
+            max_memory_usage: Some(512 * MIB),
+            ..Default::default()
+        };
+        let usage = ResourceUsage {
+            free_disk_space: Some(10 * 1024 * MIB),
+            open_files: Some(100),
+            max_open_files: Some(1024),
+            memory_usage: Some(100 * MIB),
+        };
+        assert_eq!(usage.check(&limits), ResourceStatus::Ok);
+        // Unmeasured resources are ignored
+        assert_eq!(ResourceUsage::default().check(&limits), ResourceStatus::Ok);
+
+        let low = ResourceUsage {
+            free_disk_space: Some(512 * MIB),
+            open_files: Some(1000),
+            memory_usage: Some(1024 * MIB),
+            ..usage.clone()
+        };
+        match low.check(&limits) {
+            ResourceStatus::Warning(warnings) => assert_eq!(warnings.len(), 3),
+            status => panic!("Unexpected status {}", status),
+        }
+
+        let critical = ResourceUsage {
+            free_disk_space: Some(100 * MIB),
+            ..usage
+        };
+        match critical.check(&limits) {
+            ResourceStatus::Critical(_) => {},
+            status => panic!("Unexpected status {}", status),
+        }
+    }
+
+    #[test]
+    fn measure_usage() {
+        let usage = ResourceUsage::measure(&std::env::temp_dir());
+        if cfg!(unix) {
+            assert!(usage.free_disk_space.is_some());
+            assert!(usage.open_files.unwrap() > 0);
+        }
+    }
+}
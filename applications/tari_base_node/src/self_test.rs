@@ -0,0 +1,370 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! The `--self-test` startup mode.
+//!
+//! Runs a quick preflight of the things the node depends on and prints a report with one line per check, without
+//! starting the node:
+//! - the crypto primitives against known-answer tests,
+//! - that the blockchain database can be opened and read,
+//! - that the configured transport can bind its listener and dial its proxies, and
+//! - that the Tor control port accepts the configured authentication.
+
+use digest::Digest;
+use std::{
+    fmt,
+    future::Future,
+    net::{SocketAddr, TcpListener},
+    time::Duration,
+};
+use tari_common::{CommsTransport, DatabaseType, GlobalConfig, TorControlAuthentication};
+use tari_comms::{multiaddr::Multiaddr, tor, utils::multiaddr::multiaddr_to_socketaddr};
+use tari_core::{
+    chain_storage::{create_lmdb_database, BlockchainBackend},
+    crypto::{
+        commitment::HomomorphicCommitmentFactory,
+        common::Blake256,
+        keys::PublicKey as PublicKeyTrait,
+        range_proof::RangeProofService,
+    },
+    tari_utilities::{hex::to_hex, ByteArray},
+    transactions::types::{CryptoFactories, PrivateKey, PublicKey, Signature},
+};
+use tari_mmr::MmrCacheConfig;
+use tokio::{net::TcpStream, sync::broadcast, time};
+
+/// The longest any single network check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Blake2b-256 of "abc"
+const BLAKE256_ABC: &str = "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319";
+/// The compressed encodings of 1·G and 2·G on Ristretto255
+const RISTRETTO_MULTIPLES: &[(u64, &str)] = &[
+    (1, "e2f2ae0a6abc4e71a884a961c500515f58e30b6aa582dd8db6a65945e08d2d76"),
+    (2, "6a493210f7499cd17fecb510ae0cea23a110e8d5b901f8acadd3095c73a3b919"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    Skipped,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+            CheckStatus::Skipped => write!(f, "SKIP"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new<T: Into<String>>(name: T, result: Result<String, String>) -> Self {
+        let (status, detail) = match result {
+            Ok(detail) => (CheckStatus::Pass, detail),
+            Err(detail) => (CheckStatus::Fail, detail),
+        };
+        Self {
+            name: name.into(),
+            status,
+            detail,
+        }
+    }
+
+    fn skipped<T: Into<String>>(name: T, reason: String) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Skipped,
+            detail: reason,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SelfTestReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    /// True if no check failed. Skipped checks do not count as failures.
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.status != CheckStatus::Fail)
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.results.iter().map(|r| r.name.len()).max().unwrap_or(0);
+        for result in &self.results {
+            writeln!(
+                f,
+                "[{}] {:width$}  {}",
+                result.status,
+                result.name,
+                result.detail,
+                width = width
+            )?;
+        }
+        let failed = self.results.iter().filter(|r| r.status == CheckStatus::Fail).count();
+        if failed == 0 {
+            write!(f, "Self-test passed.")
+        } else {
+            write!(
+                f,
+                "Self-test failed: {} of {} checks failed.",
+                failed,
+                self.results.len()
+            )
+        }
+    }
+}
+
+/// Run every check that applies to the given configuration
+pub async fn run(config: &GlobalConfig) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+    report
+        .results
+        .push(CheckResult::new("Crypto primitives", check_crypto()));
+    report.results.push(check_database(&config.db_type));
+    report.results.extend(check_transport(&config.comms_transport).await);
+    report
+}
+
+fn check_crypto() -> Result<String, String> {
+    let hash = to_hex(&Blake256::digest(b"abc"));
+    if hash != BLAKE256_ABC {
+        return Err(format!("Blake256 known-answer test failed, got {}", hash));
+    }
+    for (scalar, expected) in RISTRETTO_MULTIPLES {
+        let point = to_hex(PublicKey::from_secret_key(&PrivateKey::from(*scalar)).as_bytes());
+        if point != *expected {
+            return Err(format!(
+                "Ristretto {}·G known-answer test failed, got {}",
+                scalar, point
+            ));
+        }
+    }
+
+    let key = PrivateKey::from(42u64);
+    let public_key = PublicKey::from_secret_key(&key);
+    let challenge = Blake256::digest(b"tari self-test");
+    let signature = Signature::sign(key.clone(), PrivateKey::from(7u64), &challenge).map_err(|e| e.to_string())?;
+    if !signature.verify_challenge(&public_key, &challenge) {
+        return Err("A valid Schnorr signature did not verify".to_string());
+    }
+    if signature.verify_challenge(&public_key, &Blake256::digest(b"tampered")) {
+        return Err("A Schnorr signature verified against the wrong challenge".to_string());
+    }
+
+    let factories = CryptoFactories::default();
+    let value = 1_000_000u64;
+    let commitment = factories.commitment.commit(&key, &PrivateKey::from(value));
+    if !factories.commitment.open_value(&key, value, &commitment) {
+        return Err("A Pedersen commitment did not open to its value".to_string());
+    }
+    let proof = factories
+        .range_proof
+        .construct_proof(&key, value)
+        .map_err(|e| e.to_string())?;
+    if !factories.range_proof.verify(&proof, &commitment) {
+        return Err("A valid range proof did not verify".to_string());
+    }
+    Ok("Blake256, Ristretto, Schnorr signatures, commitments and range proofs".to_string())
+}
+
+fn check_database(db_type: &DatabaseType) -> CheckResult {
+    const NAME: &str = "Blockchain database";
+    let path = match db_type {
+        DatabaseType::Memory => return CheckResult::skipped(NAME, "The node uses an in-memory database".to_string()),
+        DatabaseType::LMDB(path) => path,
+    };
+    if !path.exists() {
+        return CheckResult::skipped(
+            NAME,
+            format!("No database at '{}' yet. It is created on first start.", path.display()),
+        );
+    }
+    let result = create_lmdb_database(path, MmrCacheConfig::default())
+        .and_then(|db| db.fetch_last_header())
+        .map(|header| match header {
+            Some(header) => format!("'{}' is readable, tip at height {}", path.display(), header.height),
+            None => format!("'{}' is readable and empty", path.display()),
+        })
+        .map_err(|e| format!("Could not read '{}': {}", path.display(), e));
+    CheckResult::new(NAME, result)
+}
+
+async fn check_transport(transport: &CommsTransport) -> Vec<CheckResult> {
+    match transport {
+        CommsTransport::Tcp {
+            listener_address,
+            tor_socks_address,
+            ..
+        } => {
+            let mut results = vec![CheckResult::new("TCP listener", check_bind(listener_address))];
+            if let Some(address) = tor_socks_address {
+                results.push(CheckResult::new("Tor SOCKS proxy", check_dial(address).await));
+            }
+            results
+        },
+        CommsTransport::TorHiddenService {
+            control_server_address,
+            socks_address_override,
+            forward_address,
+            auth,
+            ..
+        } => {
+            let mut results = vec![CheckResult::new("Tor forward address", check_bind(forward_address))];
+            let (control_result, socks_listeners) = check_tor_control(control_server_address, auth).await;
+            results.push(CheckResult::new("Tor control port", control_result));
+            let socks_result = match socks_address_override {
+                Some(address) => Some(check_dial(address).await),
+                None => match socks_listeners.first() {
+                    Some(address) => Some(dial(*address).await.map(|_| format!("{} accepts connections", address))),
+                    None => None,
+                },
+            };
+            match socks_result {
+                Some(result) => results.push(CheckResult::new("Tor SOCKS proxy", result)),
+                None => results.push(CheckResult::skipped(
+                    "Tor SOCKS proxy",
+                    "Tor did not report a SOCKS listener".to_string(),
+                )),
+            }
+            results
+        },
+        CommsTransport::Socks5 {
+            proxy_address,
+            listener_address,
+            ..
+        } => vec![
+            CheckResult::new("SOCKS5 listener", check_bind(listener_address)),
+            CheckResult::new("SOCKS5 proxy", check_dial(proxy_address).await),
+        ],
+    }
+}
+
+/// Bind to the address and release it straight away
+fn check_bind(address: &Multiaddr) -> Result<String, String> {
+    let socket_addr = multiaddr_to_socketaddr(address).map_err(|e| format!("Invalid address '{}': {}", address, e))?;
+    TcpListener::bind(socket_addr)
+        .map(|_| format!("Can bind {}", address))
+        .map_err(|e| format!("Cannot bind {}: {}", address, e))
+}
+
+async fn check_dial(address: &Multiaddr) -> Result<String, String> {
+    let socket_addr = multiaddr_to_socketaddr(address).map_err(|e| format!("Invalid address '{}': {}", address, e))?;
+    dial(socket_addr)
+        .await
+        .map(|_| format!("{} accepts connections", address))
+}
+
+async fn dial(address: SocketAddr) -> Result<(), String> {
+    with_timeout(TcpStream::connect(address))
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Cannot connect to {}: {}", address, e))
+}
+
+/// Authenticate with the Tor control port. Also returns the SOCKS listeners Tor reports, so that the proxy can be
+/// checked when no SOCKS address is configured.
+async fn check_tor_control(
+    address: &Multiaddr,
+    auth: &TorControlAuthentication,
+) -> (Result<String, String>, Vec<SocketAddr>)
+{
+    let authentication = match auth {
+        TorControlAuthentication::None => tor::Authentication::None,
+        TorControlAuthentication::Password(password) => tor::Authentication::HashedPassword(password.clone()),
+    };
+    let (event_tx, _) = broadcast::channel(1);
+    let mut client = match with_timeout(tor::TorControlPortClient::connect(address.clone(), event_tx)).await {
+        Ok(client) => client,
+        Err(e) => return (Err(format!("Cannot connect to {}: {}", address, e)), Vec::new()),
+    };
+    if let Err(e) = with_timeout(client.authenticate(&authentication)).await {
+        return (
+            Err(format!("Authentication with {} failed: {}", address, e)),
+            Vec::new(),
+        );
+    }
+    let socks_listeners = with_timeout(client.get_info("net/listeners/socks"))
+        .await
+        .map(|listeners| listeners.iter().filter_map(|l| l.parse().ok()).collect())
+        .unwrap_or_default();
+    (Ok(format!("Authenticated with {}", address)), socks_listeners)
+}
+
+async fn with_timeout<F, T, E>(future: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, E>>,
+    E: fmt::Display,
+{
+    match time::timeout(CHECK_TIMEOUT, future).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("Timed out after {:.0?}", CHECK_TIMEOUT)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crypto_known_answer_tests_pass() {
+        assert!(check_crypto().is_ok(), "{:?}", check_crypto());
+    }
+
+    #[test]
+    fn report_fails_only_on_failed_checks() {
+        let mut report = SelfTestReport::default();
+        report.results.push(CheckResult::new("a", Ok("fine".to_string())));
+        report
+            .results
+            .push(CheckResult::skipped("b", "not configured".to_string()));
+        assert!(report.passed());
+        assert!(report.to_string().ends_with("Self-test passed."));
+
+        report.results.push(CheckResult::new("c", Err("broken".to_string())));
+        assert!(!report.passed());
+        assert!(report.to_string().contains("[FAIL] c  broken"));
+    }
+
+    #[test]
+    fn bind_check_fails_when_the_port_is_taken() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let address: Multiaddr = format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap();
+        assert!(check_bind(&address).is_err());
+        drop(listener);
+        assert!(check_bind(&address).is_ok());
+    }
+}
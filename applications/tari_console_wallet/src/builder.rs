@@ -0,0 +1,492 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use futures::StreamExt;
+use log::*;
+use rand::rngs::OsRng;
+use std::{fs, path::Path, sync::Arc, time::Duration};
+use tari_common::{CommsTransport, GlobalConfig, SocksAuthentication, TorControlAuthentication};
+use tari_comms::{
+    connection_manager::PeerAllowList,
+    multiaddr::{Multiaddr, Protocol},
+    peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerFlags},
+    socks,
+    tor,
+    tor::TorIdentity,
+    transports::SocksConfig,
+    utils::multiaddr::multiaddr_to_socketaddr,
+};
+use tari_comms_dht::DhtConfig;
+use tari_core::{
+    tari_utilities::{hex::Hex, message_format::MessageFormat},
+    transactions::{
+        crypto::keys::SecretKey as SK,
+        types::{CryptoFactories, PrivateKey, PublicKey},
+    },
+};
+use tari_p2p::{
+    identity_file::{load_identity_file, save_identity_file},
+    initialization::CommsConfig,
+    transport::{TorConfig, TransportType},
+};
+use tari_wallet::{
+    backup::{BackupTarget, WalletBackupConfig, WalletBackupEvent},
+    contacts_service::storage::sqlite_db::ContactsServiceSqliteDatabase,
+    localization::LocaleConfig,
+    output_manager_service::{config::OutputManagerServiceConfig, storage::sqlite_db::OutputManagerSqliteDatabase},
+    storage::{
+        connection_manager::run_migration_and_create_sqlite_connection,
+        database::WalletDatabase,
+        sqlite_db::WalletSqliteDatabase,
+    },
+    transaction_service::{
+        config::{TransactionServiceConfig, ZeroConfPolicy},
+        storage::sqlite_db::TransactionServiceSqliteDatabase,
+    },
+    wallet::WalletConfig,
+    Wallet,
+};
+use tokio::runtime::Runtime;
+
+const LOG_TARGET: &str = "wallet::console_wallet::initialization";
+
+/// The wallet instance used by the console wallet, backed by the sqlite databases
+pub type WalletSqlite = Wallet<
+    WalletSqliteDatabase,
+    TransactionServiceSqliteDatabase,
+    OutputManagerSqliteDatabase,
+    ContactsServiceSqliteDatabase,
+>;
+
+/// The running wallet along with a handle to its database, which is needed to persist the selected base node
+pub struct WalletContext {
+    pub wallet: WalletSqlite,
+    pub wallet_db: WalletDatabase<WalletSqliteDatabase>,
+}
+
+/// Tries to construct a node identity by loading the secret key and other metadata from disk and calculating the
+/// missing fields from that information. The secret key is decrypted with the passphrase if it is encrypted, and
+/// identity files in an older format are upgraded.
+pub fn load_identity(path: &Path, passphrase: Option<&str>) -> Result<NodeIdentity, String> {
+    if !path.exists() {
+        return Err(format!("Identity file, {}, does not exist.", path.to_str().unwrap()));
+    }
+
+    let id = load_identity_file(path, passphrase).map_err(|e| {
+        format!(
+            "The wallet identity file, {}, could not be loaded. {:?}",
+            path.to_str().unwrap_or("?"),
+            e
+        )
+    })?;
+    info!(
+        target: LOG_TARGET,
+        "Wallet ID loaded with public key {} and Node id {}",
+        id.public_key().to_hex(),
+        id.node_id().to_hex()
+    );
+    Ok(id)
+}
+
+/// Create a new wallet identity and save it to disk, encrypting the secret key if a passphrase is given
+pub fn create_new_identity<P: AsRef<Path>>(
+    path: P,
+    public_addr: Multiaddr,
+    passphrase: Option<&str>,
+) -> Result<NodeIdentity, String>
+{
+    let private_key = PrivateKey::random(&mut OsRng);
+    let node_identity = NodeIdentity::new(private_key, public_addr, PeerFeatures::COMMUNICATION_CLIENT)
+        .map_err(|e| format!("We were unable to construct a node identity. {}", e.to_string()))?;
+    save_identity(path, &node_identity, passphrase)?;
+    Ok(node_identity)
+}
+
+/// Save the wallet identity to disk, encrypting the secret key if a passphrase is given
+pub fn save_identity<P: AsRef<Path>>(
+    path: P,
+    node_identity: &NodeIdentity,
+    passphrase: Option<&str>,
+) -> Result<(), String>
+{
+    save_identity_file(path.as_ref(), node_identity, passphrase).map_err(|e| {
+        format!(
+            "Error writing identity file, {}. {:?}",
+            path.as_ref().to_str().unwrap_or("<invalid UTF-8>"),
+            e
+        )
+    })
+}
+
+pub fn load_from_json<P: AsRef<Path>, T: MessageFormat>(path: P) -> Result<T, String> {
+    if !path.as_ref().exists() {
+        return Err(format!(
+            "Identity file, {}, does not exist.",
+            path.as_ref().to_str().unwrap()
+        ));
+    }
+
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let object = T::from_json(&contents).map_err(|err| err.to_string())?;
+    Ok(object)
+}
+
+pub fn save_as_json<P: AsRef<Path>, T: MessageFormat>(path: P, object: &T) -> Result<(), String> {
+    let json = object.to_json().unwrap();
+    if let Some(p) = path.as_ref().parent() {
+        if !p.exists() {
+            fs::create_dir_all(p).map_err(|e| format!("Could not save json to data folder. {}", e.to_string()))?;
+        }
+    }
+    fs::write(path.as_ref(), json.as_bytes()).map_err(|e| {
+        format!(
+            "Error writing json file, {}. {}",
+            path.as_ref().to_str().unwrap_or("<invalid UTF-8>"),
+            e.to_string()
+        )
+    })?;
+
+    Ok(())
+}
+
+/// Sets up the wallet databases, comms and services. The wallet uses the same identity, database and transport
+/// settings as the wallet that is embedded in the base node, so the two cannot run at the same time.
+pub fn create_wallet(
+    config: &GlobalConfig,
+    node_identity: Arc<NodeIdentity>,
+    runtime: Runtime,
+) -> Result<WalletContext, String>
+{
+    let wallet_folder = config
+        .wallet_db_file
+        .parent()
+        .expect("wallet_db_file cannot be set to a root directory");
+    fs::create_dir_all(wallet_folder).map_err(|e| format!("Could not create wallet directory: {}", e))?;
+    fs::create_dir_all(&config.wallet_peer_db_path).map_err(|e| format!("Could not create peer db path: {}", e))?;
+
+    let connection = run_migration_and_create_sqlite_connection(&config.wallet_db_file)
+        .map_err(|e| format!("Could not open the wallet database: {:?}", e))?;
+    let wallet_db = WalletDatabase::new(WalletSqliteDatabase::new(connection.clone()));
+
+    let comms_config = CommsConfig {
+        node_identity,
+        transport_type: setup_transport_type(config),
+        datastore_path: config.wallet_peer_db_path.clone(),
+        peer_database_name: "peers".to_string(),
+        max_concurrent_inbound_tasks: 100,
+        outbound_buffer_size: 100,
+        dht: setup_dht_join_policy(config),
+        // TODO: This should be false unless testing locally - make this configurable
+        allow_test_addresses: true,
+        listener_liveness_whitelist_cidrs: Vec::new(),
+        listener_liveness_max_sessions: 0,
+        peer_allow_list: setup_peer_allow_list(config)?,
+    };
+
+    let mut transaction_service_config = TransactionServiceConfig::default();
+    if let Some(num_confirmations_required) = config.wallet_num_confirmations_required {
+        transaction_service_config.num_confirmations_required = num_confirmations_required;
+    }
+    if let Some(min_inbound_amount) = config.wallet_min_inbound_amount {
+        transaction_service_config.min_inbound_amount = min_inbound_amount.into();
+    }
+    if let Some(max_inbound) = config.wallet_max_inbound_transactions_per_peer {
+        transaction_service_config.max_inbound_transactions_per_peer = max_inbound;
+    }
+    if let Some(period) = config.wallet_inbound_throttle_period {
+        transaction_service_config.inbound_throttle_period = Duration::from_secs(period);
+    }
+    if let Some(min_fee) = config.wallet_zero_conf_min_fee {
+        transaction_service_config.zero_conf_policy = ZeroConfPolicy::MempoolAccepted {
+            min_fee: min_fee.into(),
+        };
+    }
+
+    let mut output_manager_service_config = OutputManagerServiceConfig::default();
+    if let Some(dust_threshold) = config.wallet_dust_threshold {
+        output_manager_service_config.dust_threshold = dust_threshold.into();
+    }
+    if let Some(recovery_byte) = config.wallet_output_recovery_byte {
+        output_manager_service_config.recovery_byte = recovery_byte;
+    }
+    if let Some(period) = config.wallet_spend_unlock_period {
+        output_manager_service_config.spend_unlock_period = Duration::from_secs(period);
+    }
+
+    let mut wallet = Wallet::new(
+        WalletConfig {
+            comms_config,
+            factories: CryptoFactories::default(),
+            transaction_service_config: Some(transaction_service_config),
+            output_manager_service_config: Some(output_manager_service_config),
+            locale_config: config.wallet_locale.clone().map(|locale| LocaleConfig {
+                locale,
+                catalogue_dir: config.wallet_locale_dir.clone(),
+            }),
+        },
+        runtime,
+        WalletSqliteDatabase::new(connection.clone()),
+        TransactionServiceSqliteDatabase::new(connection.clone()),
+        OutputManagerSqliteDatabase::new(connection.clone()),
+        ContactsServiceSqliteDatabase::new(connection),
+    )
+    .map_err(|e| format!("Could not create the wallet: {:?}", e))?;
+
+    // Save final node identity after comms has initialized. This is required because the public_address can be changed
+    // by comms during initialization when using tor.
+    save_identity(
+        &config.wallet_identity_file,
+        &*wallet.comms.node_identity(),
+        config.identity_passphrase.as_deref(),
+    )
+    .map_err(|e| format!("Failed to save node identity: {:?}", e))?;
+    if let Some(hs) = wallet.comms.hidden_service() {
+        save_as_json(&config.wallet_tor_identity_file, hs.tor_identity())
+            .map_err(|e| format!("Failed to save tor identity: {:?}", e))?;
+    }
+
+    let peer_manager = wallet.comms.peer_manager();
+    for peer in parse_peer_seeds(&config.peer_seeds) {
+        wallet
+            .runtime
+            .block_on(peer_manager.add_peer(peer))
+            .map_err(|e| format!("Could not add peer to the peer database: {:?}", e))?;
+    }
+
+    setup_wallet_backups(config, &mut wallet)?;
+
+    Ok(WalletContext { wallet, wallet_db })
+}
+
+/// Starts the automatic wallet backups if a backup target is configured. Backup failures are reported on the console.
+fn setup_wallet_backups(config: &GlobalConfig, wallet: &mut WalletSqlite) -> Result<(), String> {
+    let target = match config.wallet_backup_target.as_ref() {
+        Some(target) => target
+            .parse::<BackupTarget>()
+            .map_err(|e| format!("Invalid wallet backup target: {:?}", e))?
+            .with_s3_endpoint(config.wallet_backup_s3_endpoint.clone()),
+        None => return Ok(()),
+    };
+    let passphrase = config
+        .wallet_backup_passphrase
+        .as_ref()
+        .or_else(|| config.identity_passphrase.as_ref())
+        .ok_or_else(|| "A backup passphrase or identity passphrase is required for wallet backups".to_string())?;
+
+    let mut backup_config = WalletBackupConfig::new(target, passphrase.clone());
+    if let Some(interval) = config.wallet_backup_interval {
+        backup_config.interval = Duration::from_secs(interval);
+    }
+    if let Some(retention) = config.wallet_backup_retention {
+        backup_config.retention = retention;
+    }
+    if let Some(incremental_backups) = config.wallet_backup_incremental_count {
+        backup_config.incremental_backups = incremental_backups;
+    }
+
+    let mut events = wallet.start_backup_scheduler(backup_config);
+    wallet.runtime.spawn(async move {
+        while let Some(event) = events.next().await {
+            if let WalletBackupEvent::BackupFailed(err) = &*event {
+                println!("Wallet backup failed: {}", err);
+            }
+        }
+    });
+    info!(target: LOG_TARGET, "Automatic wallet backups enabled");
+    Ok(())
+}
+
+/// Parses a base node peer in the form `public_key::address`
+pub fn parse_peer(seed: &str) -> Result<Peer, String> {
+    let parts: Vec<&str> = seed.split("::").map(|s| s.trim()).collect();
+    if parts.len() != 2 {
+        return Err(format!(
+            "Invalid peer '{}', it should be in the form public_key::address",
+            seed
+        ));
+    }
+    let pub_key =
+        PublicKey::from_hex(parts[0]).map_err(|e| format!("The public key of '{}' is incorrect. {}", seed, e))?;
+    let addr = parts[1]
+        .parse::<Multiaddr>()
+        .map_err(|e| format!("The address of '{}' is incorrect. {}", seed, e))?;
+    let node_id = NodeId::from_key(&pub_key)
+        .map_err(|e| format!("A node id couldn't be derived from the public key of '{}'. {}", seed, e))?;
+    Ok(Peer::new(
+        pub_key,
+        node_id,
+        addr.into(),
+        PeerFlags::default(),
+        PeerFeatures::COMMUNICATION_NODE,
+        &[],
+    ))
+}
+
+fn parse_peer_seeds(seeds: &[String]) -> Vec<Peer> {
+    info!(target: LOG_TARGET, "Adding {} peers to the peer database", seeds.len());
+    seeds
+        .iter()
+        .filter_map(|s| match parse_peer(s) {
+            Ok(peer) => Some(peer),
+            Err(e) => {
+                warn!(target: LOG_TARGET, "{} is not a valid peer seed. {}", s, e);
+                None
+            },
+        })
+        .collect()
+}
+
+/// Returns the peer allow list if allow list only mode is enabled, otherwise None. Note that the wallet's base node
+/// must be in the allow list.
+/// The DHT config with the configured join policy applied
+fn setup_dht_join_policy(config: &GlobalConfig) -> DhtConfig {
+    let mut dht_config = DhtConfig::default();
+    if let Some(delay) = config.dht_join_startup_delay {
+        dht_config.join_startup_delay = Duration::from_secs(delay);
+    }
+    if let Some(interval) = config.dht_join_min_interval {
+        dht_config.join_min_interval = Duration::from_secs(interval);
+    }
+    if let Some(interval) = config.dht_join_reannounce_interval {
+        dht_config.join_reannounce_interval = Some(interval).filter(|i| *i > 0).map(Duration::from_secs);
+    }
+    dht_config
+}
+
+fn setup_peer_allow_list(config: &GlobalConfig) -> Result<Option<PeerAllowList>, String> {
+    if !config.peer_allow_list_only {
+        return Ok(None);
+    }
+    let public_keys = config
+        .peer_allow_list
+        .iter()
+        .map(|s| PublicKey::from_hex(s).map_err(|e| format!("{} is not a valid allow list public key. {}", s, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut allow_list = PeerAllowList::new(public_keys);
+    if let Some(path) = config.peer_allow_list_file.as_ref() {
+        allow_list = allow_list
+            .with_file(path)
+            .map_err(|e| format!("Could not read the allow list file '{}': {}", path.display(), e))?;
+    }
+    Ok(Some(allow_list))
+}
+
+fn setup_transport_type(config: &GlobalConfig) -> TransportType {
+    debug!(
+        target: LOG_TARGET,
+        "Wallet transport is set to '{:?}'", config.comms_transport
+    );
+
+    let add_to_port = |addr: Multiaddr, n| -> Multiaddr {
+        addr.iter()
+            .map(|p| match p {
+                Protocol::Tcp(port) => Protocol::Tcp(port + n),
+                p => p,
+            })
+            .collect()
+    };
+
+    match config.comms_transport.clone() {
+        CommsTransport::Tcp {
+            listener_address,
+            tor_socks_address,
+            tor_socks_auth,
+        } => TransportType::Tcp {
+            listener_address: add_to_port(listener_address, 1),
+            tor_socks_config: tor_socks_address.map(|proxy_address| SocksConfig {
+                proxy_address,
+                authentication: tor_socks_auth.map(into_socks_authentication).unwrap_or_default(),
+            }),
+        },
+        CommsTransport::TorHiddenService {
+            control_server_address,
+            socks_address_override,
+            forward_address,
+            auth,
+            onion_port,
+            socks_auth,
+        } => {
+            let tor_identity_path = Path::new(&config.wallet_tor_identity_file);
+            let identity = if tor_identity_path.exists() {
+                // If this fails, we can just use another address
+                load_from_json::<_, TorIdentity>(&tor_identity_path).ok()
+            } else {
+                None
+            };
+
+            let mut forward_addr = multiaddr_to_socketaddr(&forward_address).expect("Invalid tor forward address");
+            forward_addr.set_port(forward_addr.port() + 1);
+            TransportType::Tor(TorConfig {
+                control_server_addr: control_server_address,
+                control_server_auth: {
+                    match auth {
+                        TorControlAuthentication::None => tor::Authentication::None,
+                        TorControlAuthentication::Password(password) => tor::Authentication::HashedPassword(password),
+                    }
+                },
+                identity: identity.map(Box::new),
+                port_mapping: (onion_port.get() + 1, forward_addr).into(),
+                socks_address_override,
+                socks_auth: into_socks_authentication(socks_auth),
+            })
+        },
+        CommsTransport::Socks5 {
+            proxy_address,
+            listener_address,
+            auth,
+        } => TransportType::Socks {
+            socks_config: SocksConfig {
+                proxy_address,
+                authentication: into_socks_authentication(auth),
+            },
+            listener_address: add_to_port(listener_address, 1),
+        },
+    }
+}
+
+fn into_socks_authentication(auth: SocksAuthentication) -> socks::Authentication {
+    match auth {
+        SocksAuthentication::None => socks::Authentication::None,
+        SocksAuthentication::UsernamePassword(username, password) => {
+            socks::Authentication::Password(username, password)
+        },
+        SocksAuthentication::Isolated => socks::Authentication::Isolated,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_peer;
+
+    #[test]
+    fn parse_base_node_peer() {
+        let peer =
+            parse_peer("06e98e9c5eb52bd504836edec1878eccf12eb9f26a5fe5ec0e279423156e657a::/ip4/127.0.0.1/tcp/18189")
+                .unwrap();
+        assert_eq!(peer.addresses.len(), 1);
+        assert!(parse_peer("06e98e9c5eb52bd504836edec1878eccf12eb9f26a5fe5ec0e279423156e657a").is_err());
+        assert!(parse_peer("not_a_key::/ip4/127.0.0.1/tcp/18189").is_err());
+        assert!(
+            parse_peer("06e98e9c5eb52bd504836edec1878eccf12eb9f26a5fe5ec0e279423156e657a::not_an_address").is_err()
+        );
+    }
+}
@@ -0,0 +1,1166 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::LOG_TARGET;
+use crate::{builder::parse_peer, utils};
+use chrono::Utc;
+use log::*;
+use rustyline::{
+    completion::Completer,
+    error::ReadlineError,
+    hint::{Hinter, HistoryHinter},
+    line_buffer::LineBuffer,
+    Context,
+};
+use rustyline_derive::{Helper, Highlighter, Validator};
+use std::{
+    fs,
+    str::FromStr,
+    string::ToString,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+use tari_common::Network;
+use tari_comms::{peer_manager::PeerManager, types::CommsPublicKey, NodeIdentity};
+use tari_core::{
+    tari_utilities::hex::Hex,
+    transactions::{
+        tari_amount::MicroTari,
+        transaction::UnblindedOutput,
+        types::{CommitmentFactory, PrivateKey},
+    },
+};
+use tari_shutdown::Shutdown;
+use tari_wallet::{
+    output_manager_service::{
+        error::OutputManagerError,
+        faucet::parse_faucet_outputs,
+        handle::OutputManagerHandle,
+        TxId,
+    },
+    storage::{database::WalletDatabase, sqlite_db::WalletSqliteDatabase},
+    transaction_service::{error::TransactionServiceError, handle::TransactionServiceHandle},
+    util::{emoji::EmojiId, payment_uri::PaymentUri},
+};
+use tokio::{runtime, time};
+
+/// The fee per gram used for all transactions created by the console wallet
+const FEE_PER_GRAM: MicroTari = MicroTari(25);
+/// The number of outputs migrated per transaction after a master key rotation, unless specified
+const DEFAULT_MIGRATION_BATCH_SIZE: usize = 10;
+
+/// Enum representing commands used by the console wallet
+#[derive(Clone, PartialEq, Debug, Display, EnumIter, EnumString)]
+#[strum(serialize_all = "kebab_case")]
+pub enum WalletCommand {
+    Help,
+    GetBalance,
+    SendTari,
+    Claim,
+    ImportOutputs,
+    History,
+    ListPending,
+    CancelTx,
+    RetryTx,
+    CoinSplit,
+    SeedWords,
+    RotateMasterKey,
+    MigrateOutputs,
+    CreateInvoice,
+    ListInvoices,
+    ReceiveOnly,
+    UnlockSpending,
+    LockSpending,
+    SetBaseNode,
+    Whoami,
+    Quit,
+    Exit,
+}
+
+/// This is used to parse commands from the user and execute them
+#[derive(Helper, Validator, Highlighter)]
+pub struct Parser {
+    executor: runtime::Handle,
+    network: Network,
+    node_identity: Arc<NodeIdentity>,
+    peer_manager: Arc<PeerManager>,
+    wallet_db: Arc<WalletDatabase<WalletSqliteDatabase>>,
+    output_manager_service: OutputManagerHandle,
+    transaction_service: TransactionServiceHandle,
+    commands: Vec<String>,
+    hinter: HistoryHinter,
+}
+
+// This will go through all instructions and look for potential matches
+impl Completer for Parser {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Result<(usize, Vec<String>), ReadlineError> {
+        let completions = self
+            .commands
+            .iter()
+            .filter(|cmd| cmd.starts_with(line))
+            .cloned()
+            .collect();
+
+        Ok((pos, completions))
+    }
+
+    fn update(&self, line: &mut LineBuffer, _: usize, elected: &str) {
+        line.update(elected, elected.len());
+    }
+}
+
+// This allows us to make hints based on historic inputs
+impl Hinter for Parser {
+    fn hint(&self, line: &str, pos: usize, ctx: &rustyline::Context<'_>) -> Option<String> {
+        self.hinter.hint(line, pos, ctx)
+    }
+}
+
+impl Parser {
+    /// creates a new parser struct
+    pub fn new(
+        executor: runtime::Handle,
+        network: Network,
+        node_identity: Arc<NodeIdentity>,
+        peer_manager: Arc<PeerManager>,
+        wallet_db: WalletDatabase<WalletSqliteDatabase>,
+        output_manager_service: OutputManagerHandle,
+        transaction_service: TransactionServiceHandle,
+    ) -> Self
+    {
+        Parser {
+            executor,
+            network,
+            node_identity,
+            peer_manager,
+            wallet_db: Arc::new(wallet_db),
+            output_manager_service,
+            transaction_service,
+            commands: WalletCommand::iter().map(|x| x.to_string()).collect(),
+            hinter: HistoryHinter {},
+        }
+    }
+
+    /// This will parse the provided command and execute the task
+    pub fn handle_command(&mut self, command_str: &str, shutdown: &mut Shutdown) {
+        if command_str.trim().is_empty() {
+            return;
+        }
+        let mut args = command_str.split_whitespace();
+        let command = WalletCommand::from_str(args.next().unwrap_or(&"help"));
+        if command.is_err() {
+            println!("{} is not a valid command, please enter a valid command", command_str);
+            println!("Enter help or press tab for available commands");
+            return;
+        }
+        let command = command.unwrap();
+        self.process_command(command, args, shutdown);
+    }
+
+    // Function to process commands
+    fn process_command<'a, I: Iterator<Item = &'a str>>(
+        &mut self,
+        command: WalletCommand,
+        args: I,
+        shutdown: &mut Shutdown,
+    )
+    {
+        use WalletCommand::*;
+        match command {
+            Help => {
+                self.print_help(args);
+            },
+            GetBalance => {
+                self.process_get_balance();
+            },
+            SendTari => {
+                self.process_send_tari(args);
+            },
+            Claim => {
+                self.process_claim(args);
+            },
+            ImportOutputs => {
+                self.process_import_outputs(args);
+            },
+            History => {
+                self.process_history();
+            },
+            ListPending => {
+                self.process_list_pending();
+            },
+            CancelTx => {
+                self.process_cancel_tx(args);
+            },
+            RetryTx => {
+                self.process_retry_tx(args);
+            },
+            CoinSplit => {
+                self.process_coin_split(args);
+            },
+            SeedWords => {
+                self.process_seed_words();
+            },
+            RotateMasterKey => {
+                self.process_rotate_master_key(args);
+            },
+            MigrateOutputs => {
+                self.process_migrate_outputs();
+            },
+            CreateInvoice => {
+                self.process_create_invoice(args);
+            },
+            ListInvoices => {
+                self.process_list_invoices();
+            },
+            ReceiveOnly => {
+                self.process_receive_only(args);
+            },
+            UnlockSpending => {
+                self.process_unlock_spending(args);
+            },
+            LockSpending => {
+                self.process_lock_spending();
+            },
+            SetBaseNode => {
+                self.process_set_base_node(args);
+            },
+            Whoami => {
+                self.process_whoami();
+            },
+            Exit | Quit => {
+                println!("Shutting down...");
+                info!(
+                    target: LOG_TARGET,
+                    "Termination signal received from user. Shutting wallet down."
+                );
+                let _ = shutdown.trigger();
+            },
+        }
+    }
+
+    fn print_help<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let help_for = WalletCommand::from_str(args.next().unwrap_or_default()).unwrap_or(WalletCommand::Help);
+        use WalletCommand::*;
+        match help_for {
+            Help => {
+                println!("Available commands are: ");
+                let joined = self.commands.join(", ");
+                println!("{}", joined);
+            },
+            GetBalance => {
+                println!("Gets your balance");
+            },
+            SendTari => {
+                println!("Sends an amount of Tari to a address call this command via:");
+                println!("send-tari [amount of tari to send] [destination public key or emoji id] [optional: msg]");
+                println!("or with a payment URI, which may carry the amount and message:");
+                println!("send-tari [payment uri] [amount of tari to send, if not in the uri] [optional: msg]");
+            },
+            Claim => {
+                println!("Imports a spendable UTXO, e.g. from a faucet, into the wallet, call this command via:");
+                println!("claim [spending key hex] [amount in uT] [optional: msg]");
+            },
+            ImportOutputs => {
+                println!("Imports the spendable UTXOs in a faucet file into the wallet, call this command via:");
+                println!("import-outputs [file path]");
+                println!("The file holds a JSON array, or one JSON object per line, of outputs with a spending key");
+                println!("hex, a value in uT and optionally a maturity height and commitment hex.");
+            },
+            History => {
+                println!("Lists the pending and completed transactions of this wallet");
+            },
+            ListPending => {
+                println!("Lists the pending transactions of this wallet with the stage their negotiation has reached,");
+                println!("how long ago they were last updated and how often their last message was resent");
+            },
+            CancelTx => {
+                println!("Cancels a pending transaction and releases its funds, call this command via:");
+                println!("cancel-tx [tx id]");
+            },
+            RetryTx => {
+                println!("Resends the last message of a pending transaction to the other party, call via:");
+                println!("retry-tx [tx id]");
+            },
+            CoinSplit => {
+                println!("Splits your funds into a number of outputs of the same value, call this command via:");
+                println!("coin-split [amount of tari per split] [number of splits]");
+            },
+            SeedWords => {
+                println!("Displays the seed words that can be used to recover this wallet. Keep them secret!");
+            },
+            RotateMasterKey => {
+                println!("Replaces the wallet's master key if you fear it has been compromised, call via:");
+                println!("rotate-master-key [optional: outputs migrated per transaction]");
+                println!("Your seed words change, so back them up again. Then move your funds to the new key by");
+                println!("running migrate-outputs once each previous migration transaction has been mined.");
+            },
+            MigrateOutputs => {
+                println!("Moves the next batch of outputs to the wallet's current master key after a key rotation");
+            },
+            CreateInvoice => {
+                println!("Creates an invoice paid to a new key of its own, call this command via:");
+                println!("create-invoice [invoice id] [optional: amount in uT]");
+                println!("The payment is matched to the invoice when the sender uses the invoice id as the message.");
+                println!("A payment URI that carries the invoice id as its memo is printed for the customer.");
+            },
+            ListInvoices => {
+                println!("Lists the invoices of this wallet and the transactions that paid them");
+            },
+            ReceiveOnly => {
+                println!("Turns receive-only mode on or off, call this command via:");
+                println!("receive-only [on|off] [spend passphrase]");
+                println!("In receive-only mode the wallet refuses to spend until unlock-spending is run.");
+            },
+            UnlockSpending => {
+                println!("Allows a wallet in receive-only mode to spend for a while, call this command via:");
+                println!("unlock-spending [spend passphrase]");
+            },
+            LockSpending => {
+                println!("Locks spending again in receive-only mode before the unlock period is over");
+            },
+            SetBaseNode => {
+                println!("Sets the base node used to broadcast transactions and monitor the chain, call via:");
+                println!("set-base-node [public key] [address]");
+            },
+            Whoami => {
+                println!("Display identity information about this wallet, including: public key and emoji ID");
+            },
+            Exit | Quit => {
+                println!("Exits the wallet");
+            },
+        }
+    }
+
+    // Function to process  the get balance command
+    fn process_get_balance(&mut self) {
+        let mut handler = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            // TODO perform this function more intelligently in the Output Manager
+            let _ = handler.sync_with_base_node().await;
+
+            match handler.get_balance().await {
+                Err(e) => {
+                    println!("Something went wrong");
+                    warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                    return;
+                },
+                Ok(data) => println!("Balances:\n{}", data),
+            };
+        });
+    }
+
+    // Function to process  the send transaction function
+    fn process_send_tari<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let first = args.next();
+        if let Some(uri) = first.filter(|a| PaymentUri::is_payment_uri(a)) {
+            self.process_send_tari_to_uri(uri, args);
+            return;
+        }
+        let amount = first.and_then(|v| v.parse::<u64>().ok());
+        if amount.is_none() {
+            println!("Please enter a valid amount of tari");
+            return;
+        }
+        let amount: MicroTari = amount.unwrap().into();
+
+        let key = match args.next() {
+            Some(k) => k.to_string(),
+            None => {
+                println!("Command entered incorrectly, please use the following format: ");
+                println!("send-tari [amount of tari to send] [public key or emoji id to send to]");
+                return;
+            },
+        };
+
+        let dest_pubkey = match parse_emoji_id_or_public_key(&key) {
+            Some(v) => v,
+            None => {
+                println!("Please enter a valid destination public key or emoji id");
+                return;
+            },
+        };
+
+        // Use the rest of the command line as my message
+        let msg = args.collect::<Vec<&str>>().join(" ");
+        self.send_tari(dest_pubkey, amount, msg);
+    }
+
+    // Function to send to a payment URI. The amount and message are taken from the command line if the URI does not
+    // have them.
+    fn process_send_tari_to_uri<'a, I: Iterator<Item = &'a str>>(&mut self, uri: &str, mut args: I) {
+        let uri = match uri.parse::<PaymentUri>() {
+            Ok(uri) => uri,
+            Err(e) => {
+                println!("Please enter a valid payment URI: {}", e);
+                return;
+            },
+        };
+        if uri.network != self.network {
+            println!(
+                "The payment URI is for the {} network, but this wallet is on the {} network",
+                uri.network, self.network
+            );
+            return;
+        }
+        let amount = match uri
+            .amount
+            .or_else(|| args.next().and_then(|v| v.parse::<u64>().ok()).map(MicroTari::from))
+        {
+            Some(amount) => amount,
+            None => {
+                println!("The payment URI has no amount, please enter a valid amount of tari");
+                return;
+            },
+        };
+        let msg = match uri.memo {
+            Some(memo) => memo,
+            None => args.collect::<Vec<&str>>().join(" "),
+        };
+        self.send_tari(uri.public_key, amount, msg);
+    }
+
+    fn send_tari(&mut self, dest_pubkey: CommsPublicKey, amount: MicroTari, msg: String) {
+        let mut txn_service = self.transaction_service.clone();
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            // TODO perform this function more intelligently in the Output Manager
+            let _ = oms_handle.sync_with_base_node().await;
+
+            let event_stream = txn_service.get_event_stream_fused();
+            match txn_service
+                .send_transaction(dest_pubkey.clone(), amount, FEE_PER_GRAM, msg)
+                .await
+            {
+                Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id)) => {
+                    println!(
+                        "No peer found matching that public key. Attempting to discover the peer on the network. 🌎"
+                    );
+                    let start = Instant::now();
+                    match time::timeout(
+                        Duration::from_secs(120),
+                        utils::wait_for_discovery_transaction_event(event_stream, tx_id),
+                    )
+                    .await
+                    {
+                        Ok(true) => {
+                            let end = Instant::now();
+                            println!(
+                                "Discovery succeeded for peer {} after {}ms",
+                                dest_pubkey,
+                                (end - start).as_millis()
+                            );
+                            debug!(
+                                target: LOG_TARGET,
+                                "Discovery succeeded for peer {} after {}ms",
+                                dest_pubkey,
+                                (end - start).as_millis()
+                            );
+                        },
+                        Ok(false) => {
+                            let end = Instant::now();
+                            println!(
+                                "Discovery failed for peer {} after {}ms",
+                                dest_pubkey,
+                                (end - start).as_millis()
+                            );
+                            println!("The peer may be offline. Please try again later.");
+
+                            debug!(
+                                target: LOG_TARGET,
+                                "Discovery failed for peer {} after {}ms",
+                                dest_pubkey,
+                                (end - start).as_millis()
+                            );
+                        },
+                        Err(_) => {
+                            debug!(
+                                target: LOG_TARGET,
+                                "Discovery timed out before the node was discovered."
+                            );
+                            println!("Discovery timed out before the node was discovered.");
+                            println!("The peer may be offline. Please try again later.");
+                        },
+                    }
+                },
+                Err(TransactionServiceError::OutputManagerError(OutputManagerError::NotEnoughFunds)) => {
+                    println!("Not enough funds to fulfill the transaction.");
+                },
+                Err(TransactionServiceError::OutputManagerError(OutputManagerError::SpendingLocked)) => {
+                    println!("The wallet is in receive-only mode, run unlock-spending first.");
+                },
+                Err(e) => {
+                    println!("Something went wrong sending funds");
+                    println!("{:?}", e);
+                    warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                    return;
+                },
+                Ok(_) => println!("Sending {} Tari to {} ", amount, dest_pubkey),
+            };
+        });
+    }
+
+    // Function to import a spendable UTXO, such as the ones handed out by the test faucet
+    fn process_claim<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let spending_key = match args.next().and_then(|k| PrivateKey::from_hex(k).ok()) {
+            Some(k) => k,
+            None => {
+                println!("Please enter a valid spending key, in hex");
+                println!("claim [spending key hex] [amount in uT] [optional: msg]");
+                return;
+            },
+        };
+        let amount: MicroTari = match args.next().and_then(|v| v.parse::<u64>().ok()) {
+            Some(v) => v.into(),
+            None => {
+                println!("Please enter a valid amount of tari");
+                return;
+            },
+        };
+        let msg = args.collect::<Vec<&str>>().join(" ");
+        let msg = if msg.is_empty() { "Claimed UTXO".to_string() } else { msg };
+
+        let source_public_key = self.node_identity.public_key().clone();
+        let mut txn_service = self.transaction_service.clone();
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            let output = UnblindedOutput::new(amount, spending_key, None);
+            match oms_handle.import_outputs(vec![output]).await {
+                Ok(result) if result.duplicates > 0 => {
+                    println!("This UTXO has already been added to the wallet");
+                    return;
+                },
+                Ok(result) if result.imported.is_empty() => {
+                    println!("This UTXO is not valid and could not be claimed");
+                    return;
+                },
+                Ok(_) => (),
+                Err(e) => {
+                    println!("Could not claim the UTXO: {:?}", e);
+                    warn!(target: LOG_TARGET, "Error adding claimed output to the wallet: {:?}", e);
+                    return;
+                },
+            }
+            match txn_service.import_utxo(amount, source_public_key, msg).await {
+                Ok(tx_id) => println!("Claimed {} (TxId: {})", amount, tx_id),
+                Err(e) => {
+                    println!("The UTXO was added but could not be recorded in the transaction history");
+                    warn!(target: LOG_TARGET, "Error recording imported UTXO: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to import the spendable UTXOs in a faucet file
+    fn process_import_outputs<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let path = match args.next() {
+            Some(p) => p,
+            None => {
+                println!("Please enter the path of the file to import");
+                println!("import-outputs [file path]");
+                return;
+            },
+        };
+        let outputs = match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                parse_faucet_outputs(&contents, &CommitmentFactory::default()).map_err(|e| format!("{:?}", e))
+            }) {
+            Ok(outputs) => outputs,
+            Err(e) => {
+                println!("Could not read the outputs from {}: {}", path, e);
+                return;
+            },
+        };
+
+        let source_public_key = self.node_identity.public_key().clone();
+        let mut txn_service = self.transaction_service.clone();
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            let result = match oms_handle.import_outputs(outputs).await {
+                Ok(r) => r,
+                Err(e) => {
+                    println!("Could not import the outputs: {:?}", e);
+                    warn!(target: LOG_TARGET, "Error importing outputs: {:?}", e);
+                    return;
+                },
+            };
+            for output in result.imported.iter() {
+                if let Err(e) = txn_service
+                    .import_utxo(output.value, source_public_key.clone(), "Imported UTXO".to_string())
+                    .await
+                {
+                    warn!(target: LOG_TARGET, "Error recording imported UTXO: {:?}", e);
+                }
+            }
+            println!(
+                "Imported {} outputs worth {} ({} already in the wallet, {} invalid)",
+                result.imported.len(),
+                result.imported_value(),
+                result.duplicates,
+                result.rejected
+            );
+            if result.validation_request_key.is_some() {
+                println!("The imported outputs are being checked against the base node");
+            } else if !result.imported.is_empty() {
+                println!("No base node is set, so the imported outputs could not be checked against the chain yet");
+            }
+        });
+    }
+
+    // Function to list the pending and completed transactions
+    fn process_history(&mut self) {
+        let mut txn_service = self.transaction_service.clone();
+        self.executor.spawn(async move {
+            let inbound = txn_service.get_pending_inbound_transactions().await;
+            let outbound = txn_service.get_pending_outbound_transactions().await;
+            let completed = txn_service.get_completed_transactions().await;
+            let (inbound, outbound, completed) = match (inbound, outbound, completed) {
+                (Ok(i), Ok(o), Ok(c)) => (i, o, c),
+                (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                    println!("Something went wrong");
+                    warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                    return;
+                },
+            };
+
+            let mut inbound = inbound.values().collect::<Vec<_>>();
+            inbound.sort_by_key(|tx| tx.timestamp);
+            println!("======== Pending Inbound ({}) ==========", inbound.len());
+            for tx in inbound {
+                println!(
+                    "{} | TxId: {} | {} from {} | {}",
+                    tx.timestamp, tx.tx_id, tx.amount, tx.source_public_key, tx.message
+                );
+            }
+
+            let mut outbound = outbound.values().collect::<Vec<_>>();
+            outbound.sort_by_key(|tx| tx.timestamp);
+            println!("======== Pending Outbound ({}) ==========", outbound.len());
+            for tx in outbound {
+                println!(
+                    "{} | TxId: {} | {} (fee {}) to {} | {}",
+                    tx.timestamp, tx.tx_id, tx.amount, tx.fee, tx.destination_public_key, tx.message
+                );
+            }
+
+            let mut completed = completed.values().collect::<Vec<_>>();
+            completed.sort_by_key(|tx| tx.timestamp);
+            println!("======== Completed ({}) ==========", completed.len());
+            for tx in completed {
+                println!(
+                    "{} | TxId: {} | {:?} | {} (fee {}) from {} to {} | {}",
+                    tx.timestamp,
+                    tx.tx_id,
+                    tx.status,
+                    tx.amount,
+                    tx.fee,
+                    tx.source_public_key,
+                    tx.destination_public_key,
+                    tx.message
+                );
+            }
+        });
+    }
+
+    // Function to list the pending transactions with the progress of their negotiation
+    fn process_list_pending(&mut self) {
+        let mut txn_service = self.transaction_service.clone();
+        self.executor.spawn(async move {
+            let inbound = txn_service.get_pending_inbound_transactions().await;
+            let outbound = txn_service.get_pending_outbound_transactions().await;
+            let (inbound, outbound) = match (inbound, outbound) {
+                (Ok(i), Ok(o)) => (i, o),
+                (Err(e), _) | (_, Err(e)) => {
+                    println!("Something went wrong");
+                    warn!(target: LOG_TARGET, "Error communicating with wallet: {:?}", e);
+                    return;
+                },
+            };
+            if inbound.is_empty() && outbound.is_empty() {
+                println!("No pending transactions");
+                return;
+            }
+
+            let now = Utc::now().naive_utc();
+            let mut inbound = inbound.values().collect::<Vec<_>>();
+            inbound.sort_by_key(|tx| tx.timestamp);
+            for tx in inbound {
+                println!(
+                    "Inbound  | TxId: {} | {} from {} | {:?} | age {}s | retries {}",
+                    tx.tx_id,
+                    tx.amount,
+                    tx.source_public_key,
+                    tx.stage,
+                    (now - tx.timestamp).num_seconds(),
+                    tx.retry_count
+                );
+            }
+
+            let mut outbound = outbound.values().collect::<Vec<_>>();
+            outbound.sort_by_key(|tx| tx.timestamp);
+            for tx in outbound {
+                println!(
+                    "Outbound | TxId: {} | {} to {} | {:?} | age {}s | retries {}",
+                    tx.tx_id,
+                    tx.amount,
+                    tx.destination_public_key,
+                    tx.stage,
+                    (now - tx.timestamp).num_seconds(),
+                    tx.retry_count
+                );
+            }
+        });
+    }
+
+    // Function to cancel a pending transaction
+    fn process_cancel_tx<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let tx_id = match args.next().and_then(|v| v.parse::<TxId>().ok()) {
+            Some(id) => id,
+            None => {
+                println!("Please enter a valid transaction id");
+                println!("cancel-tx [tx id]");
+                return;
+            },
+        };
+
+        let mut txn_service = self.transaction_service.clone();
+        self.executor.spawn(async move {
+            match txn_service.cancel_transaction(tx_id).await {
+                Ok(_) => println!("Transaction {} cancelled", tx_id),
+                Err(TransactionServiceError::PendingTransactionNotFound) => {
+                    println!("There is no pending transaction with id {}", tx_id);
+                },
+                Err(e) => {
+                    println!("Something went wrong cancelling the transaction");
+                    warn!(target: LOG_TARGET, "Error cancelling transaction: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to resend the last message of a pending transaction
+    fn process_retry_tx<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let tx_id = match args.next().and_then(|v| v.parse::<TxId>().ok()) {
+            Some(id) => id,
+            None => {
+                println!("Please enter a valid transaction id");
+                println!("retry-tx [tx id]");
+                return;
+            },
+        };
+
+        let mut txn_service = self.transaction_service.clone();
+        self.executor.spawn(async move {
+            match txn_service.retry_transaction(tx_id).await {
+                Ok(_) => println!("Transaction {} resent", tx_id),
+                Err(TransactionServiceError::PendingTransactionNotFound) => {
+                    println!("There is no pending transaction with id {}", tx_id);
+                },
+                Err(e) => {
+                    println!("Something went wrong resending the transaction");
+                    warn!(target: LOG_TARGET, "Error resending transaction: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to split the wallet's funds into a number of equal valued outputs
+    fn process_coin_split<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let amount_per_split: MicroTari = match args.next().and_then(|v| v.parse::<u64>().ok()) {
+            Some(v) => v.into(),
+            None => {
+                println!("Please enter a valid amount of tari per split");
+                println!("coin-split [amount of tari per split] [number of splits]");
+                return;
+            },
+        };
+        let split_count = match args.next().and_then(|v| v.parse::<usize>().ok()) {
+            Some(v) if v > 0 => v,
+            _ => {
+                println!("Please enter a valid number of splits");
+                println!("coin-split [amount of tari per split] [number of splits]");
+                return;
+            },
+        };
+
+        let mut txn_service = self.transaction_service.clone();
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            // TODO perform this function more intelligently in the Output Manager
+            let _ = oms_handle.sync_with_base_node().await;
+
+            let (tx_id, tx, fee, amount) = match oms_handle
+                .create_coin_split(amount_per_split, split_count, FEE_PER_GRAM, None)
+                .await
+            {
+                Ok(v) => v,
+                Err(OutputManagerError::NotEnoughFunds) => {
+                    println!("Not enough funds to fulfill the coin split.");
+                    return;
+                },
+                Err(OutputManagerError::SpendingLocked) => {
+                    println!("The wallet is in receive-only mode, run unlock-spending first.");
+                    return;
+                },
+                Err(e) => {
+                    println!("Something went wrong creating the coin split");
+                    warn!(target: LOG_TARGET, "Error creating coin split: {:?}", e);
+                    return;
+                },
+            };
+            match txn_service
+                .submit_transaction(tx_id, tx, fee, amount, "Coin split".to_string())
+                .await
+            {
+                Ok(_) => println!(
+                    "Coin split into {} outputs of {} submitted (TxId: {}, fee {})",
+                    split_count, amount_per_split, tx_id, fee
+                ),
+                Err(e) => {
+                    println!("Something went wrong submitting the coin split");
+                    warn!(target: LOG_TARGET, "Error submitting coin split: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to display the wallet's seed words
+    fn process_seed_words(&mut self) {
+        let mut handler = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            match handler.get_seed_words().await {
+                Ok(words) => {
+                    println!("Keep these words somewhere safe, they can be used to recover your funds:");
+                    println!("{}", words.join(" "));
+                },
+                Err(e) => {
+                    println!("Something went wrong");
+                    warn!(target: LOG_TARGET, "Error retrieving seed words: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to replace the wallet's master key and plan the migration of its outputs to the new key
+    fn process_rotate_master_key<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let batch_size = match args.next() {
+            None => DEFAULT_MIGRATION_BATCH_SIZE,
+            Some(v) => match v.parse::<usize>() {
+                Ok(v) if v > 0 => v,
+                _ => {
+                    println!("Please enter a valid number of outputs per migration transaction");
+                    println!("rotate-master-key [optional: outputs migrated per transaction]");
+                    return;
+                },
+            },
+        };
+
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            match oms_handle.rotate_master_key(FEE_PER_GRAM, batch_size).await {
+                Ok(plan) => {
+                    println!(
+                        "Master key rotated. {} output(s) will be migrated to the new key, run migrate-outputs to \
+                         start.",
+                        plan.outputs_to_migrate.len()
+                    );
+                    println!("Your seed words have changed, run seed-words and back them up again.");
+                },
+                Err(OutputManagerError::KeyRotationInProgress) => {
+                    println!("The outputs of the previous key rotation have not all been migrated yet.");
+                },
+                Err(OutputManagerError::TransactionsPending) => {
+                    println!("The master key cannot be rotated while transactions are pending.");
+                },
+                Err(e) => {
+                    println!("Something went wrong rotating the master key");
+                    warn!(target: LOG_TARGET, "Error rotating master key: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to migrate the next batch of outputs to the wallet's current master key
+    fn process_migrate_outputs(&mut self) {
+        let mut txn_service = self.transaction_service.clone();
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            // TODO perform this function more intelligently in the Output Manager
+            let _ = oms_handle.sync_with_base_node().await;
+
+            let (tx_id, tx, fee, amount) = match oms_handle.migrate_outputs().await {
+                Ok(Some(v)) => v,
+                Ok(None) => {
+                    match oms_handle.get_output_migration_plan().await {
+                        Ok(Some(plan)) => println!("Output migration complete: {}", plan),
+                        _ => println!("Output migration complete"),
+                    }
+                    return;
+                },
+                Err(OutputManagerError::NoOutputMigrationPlan) => {
+                    println!("There is nothing to migrate, the master key has not been rotated.");
+                    return;
+                },
+                Err(OutputManagerError::MigrationBatchPending) => {
+                    println!("The previous migration transaction has not been mined yet, try again later.");
+                    return;
+                },
+                Err(OutputManagerError::MigrationOutputsEncumbered) => {
+                    println!("The remaining outputs are used by pending transactions, try again later.");
+                    return;
+                },
+                Err(e) => {
+                    println!("Something went wrong migrating outputs");
+                    warn!(target: LOG_TARGET, "Error migrating outputs: {:?}", e);
+                    return;
+                },
+            };
+            match txn_service
+                .submit_transaction(tx_id, tx, fee, amount, "Output migration".to_string())
+                .await
+            {
+                Ok(_) => println!(
+                    "Migration of {} submitted (TxId: {}, fee {}). Run migrate-outputs again once it is mined.",
+                    amount, tx_id, fee
+                ),
+                Err(e) => {
+                    println!("Something went wrong submitting the migration transaction");
+                    warn!(target: LOG_TARGET, "Error submitting output migration: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to select the base node the wallet uses to broadcast transactions and monitor the blockchain
+    fn process_set_base_node<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let peer = match (args.next(), args.next()) {
+            (Some(public_key), Some(address)) => parse_peer(&format!("{}::{}", public_key, address)),
+            _ => Err("Command entered incorrectly".to_string()),
+        };
+        let peer = match peer {
+            Ok(peer) => peer,
+            Err(e) => {
+                println!("{}, please use the following format:", e);
+                println!("set-base-node [public key] [address]");
+                return;
+            },
+        };
+
+        let peer_manager = self.peer_manager.clone();
+        let wallet_db = self.wallet_db.clone();
+        let mut txn_service = self.transaction_service.clone();
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            let public_key = peer.public_key.clone();
+            if let Err(e) = peer_manager.add_peer(peer.clone()).await {
+                println!("Could not add the base node to the peer database");
+                warn!(target: LOG_TARGET, "Error adding base node peer: {:?}", e);
+                return;
+            }
+            // Remove any peers in db to only persist a single base node at a time.
+            if let Ok(existing_peers) = wallet_db.get_peers().await {
+                for p in existing_peers {
+                    let _ = wallet_db.remove_peer(p.public_key).await;
+                }
+            }
+            if let Err(e) = wallet_db.save_peer(peer).await {
+                warn!(target: LOG_TARGET, "Error persisting the base node peer: {:?}", e);
+            }
+            let result = match txn_service.set_base_node_public_key(public_key.clone()).await {
+                Ok(_) => oms_handle
+                    .set_base_node_public_key(public_key.clone())
+                    .await
+                    .map_err(|e| format!("{:?}", e)),
+                Err(e) => Err(format!("{:?}", e)),
+            };
+            match result {
+                Ok(_) => println!("Base node set to {}", public_key),
+                Err(e) => {
+                    println!("Something went wrong setting the base node");
+                    warn!(target: LOG_TARGET, "Error setting base node: {}", e);
+                },
+            }
+        });
+    }
+
+    // Function to create an invoice that is paid to a key of its own
+    fn process_create_invoice<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let invoice_id = match args.next() {
+            Some(id) => id.to_string(),
+            None => {
+                println!("Please enter an invoice id");
+                println!("create-invoice [invoice id] [optional: amount in uT]");
+                return;
+            },
+        };
+        let mut payment_uri = PaymentUri::new(self.network.clone(), self.node_identity.public_key().clone())
+            .with_memo(invoice_id.clone());
+        if let Some(amount) = args.next() {
+            match amount.parse::<u64>() {
+                Ok(amount) => payment_uri = payment_uri.with_amount(amount.into()),
+                Err(_) => {
+                    println!("Please enter a valid amount in uT");
+                    return;
+                },
+            }
+        }
+
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            match oms_handle.create_invoice(invoice_id.clone()).await {
+                Ok(public_key) => {
+                    println!("Invoice {} created, paid to key {}", invoice_id, public_key.to_hex());
+                    println!("Ask the customer to send the payment with the message: {}", invoice_id);
+                    println!("Payment URI: {}", payment_uri);
+                },
+                Err(OutputManagerError::DuplicateInvoice) => {
+                    println!("An invoice with id {} already exists.", invoice_id);
+                },
+                Err(e) => {
+                    println!("Something went wrong creating the invoice");
+                    warn!(target: LOG_TARGET, "Error creating invoice: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to list the wallet's invoices
+    fn process_list_invoices(&mut self) {
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            let invoices = match oms_handle.get_invoices().await {
+                Ok(i) => i,
+                Err(e) => {
+                    println!("Something went wrong");
+                    warn!(target: LOG_TARGET, "Error retrieving invoices: {:?}", e);
+                    return;
+                },
+            };
+            if invoices.is_empty() {
+                println!("No invoices");
+            }
+            for invoice in invoices {
+                match (invoice.tx_id, invoice.amount) {
+                    (Some(tx_id), Some(amount)) => println!(
+                        "{} {}: paid {} (TxId: {})",
+                        invoice.timestamp, invoice.invoice_id, amount, tx_id
+                    ),
+                    _ => println!("{} {}: unpaid", invoice.timestamp, invoice.invoice_id),
+                }
+            }
+        });
+    }
+
+    // Function to turn receive-only mode on or off
+    fn process_receive_only<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let enable = match args.next() {
+            Some("on") => true,
+            Some("off") => false,
+            _ => {
+                println!("Please enter on or off");
+                println!("receive-only [on|off] [spend passphrase]");
+                return;
+            },
+        };
+        let passphrase = args.collect::<Vec<_>>().join(" ");
+        if passphrase.is_empty() {
+            println!("Please enter the spend passphrase");
+            println!("receive-only [on|off] [spend passphrase]");
+            return;
+        }
+
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            let result = if enable {
+                oms_handle.enable_receive_only_mode(passphrase).await
+            } else {
+                oms_handle.disable_receive_only_mode(passphrase).await
+            };
+            match result {
+                Ok(_) if enable => println!("Receive-only mode on, spending is locked."),
+                Ok(_) => println!("Receive-only mode off."),
+                Err(OutputManagerError::ReceiveOnlyModeEnabled) => println!("Receive-only mode is already on."),
+                Err(OutputManagerError::ReceiveOnlyModeNotEnabled) => println!("Receive-only mode is already off."),
+                Err(OutputManagerError::InvalidSpendPassphrase) => println!("Incorrect spend passphrase."),
+                Err(e) => {
+                    println!("Something went wrong changing receive-only mode");
+                    warn!(target: LOG_TARGET, "Error changing receive-only mode: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to unlock spending in receive-only mode
+    fn process_unlock_spending<'a, I: Iterator<Item = &'a str>>(&mut self, args: I) {
+        let passphrase = args.collect::<Vec<_>>().join(" ");
+        if passphrase.is_empty() {
+            println!("Please enter the spend passphrase");
+            println!("unlock-spending [spend passphrase]");
+            return;
+        }
+
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            match oms_handle.unlock_spending(passphrase).await {
+                Ok(_) => println!("Spending unlocked."),
+                Err(OutputManagerError::ReceiveOnlyModeNotEnabled) => {
+                    println!("The wallet is not in receive-only mode, spending is not locked.")
+                },
+                Err(OutputManagerError::InvalidSpendPassphrase) => println!("Incorrect spend passphrase."),
+                Err(e) => {
+                    println!("Something went wrong unlocking spending");
+                    warn!(target: LOG_TARGET, "Error unlocking spending: {:?}", e);
+                },
+            }
+        });
+    }
+
+    // Function to lock spending in receive-only mode
+    fn process_lock_spending(&mut self) {
+        let mut oms_handle = self.output_manager_service.clone();
+        self.executor.spawn(async move {
+            match oms_handle.lock_spending().await {
+                Ok(_) => println!("Spending locked."),
+                Err(e) => {
+                    println!("Something went wrong locking spending");
+                    warn!(target: LOG_TARGET, "Error locking spending: {:?}", e);
+                },
+            }
+        });
+    }
+
+    fn process_whoami(&self) {
+        println!("{}", self.node_identity);
+        let emoji_id = EmojiId::from_pubkey(&self.node_identity.public_key());
+        println!("Emoji ID: {}", emoji_id);
+    }
+}
+
+fn parse_emoji_id_or_public_key(key: &str) -> Option<CommsPublicKey> {
+    EmojiId::str_to_pubkey(&key.trim().replace('|', ""))
+        .or_else(|_| CommsPublicKey::from_hex(key))
+        .ok()
+}
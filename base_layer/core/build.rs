@@ -28,6 +28,7 @@ fn main() {
             "src/base_node/proto",
             "src/transactions/transaction_protocol/proto",
         ])
+        .derive_serde(&["tari.types", "tari.core"], "crate::transactions::proto::json")
         .compile()
         .unwrap();
 }
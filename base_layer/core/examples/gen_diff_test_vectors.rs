@@ -0,0 +1,40 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+/// Generates the difficulty adjustment test vectors. The expected difficulties are calculated by the current
+/// implementation, so this should only be run to regenerate the golden file after an intentional change to the
+/// algorithm:
+///
+/// ```bash
+/// cargo run --example gen_diff_test_vectors -- tests/data/lwma_diff_vectors.json
+/// ```
+use std::{env, fs};
+use tari_core::proof_of_work::test_vectors::lwma_test_vectors;
+
+fn main() {
+    let out_path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "tests/data/lwma_diff_vectors.json".to_string());
+    let json = serde_json::to_string_pretty(&lwma_test_vectors()).expect("Test vectors can be serialized");
+    fs::write(&out_path, json).expect("Could not write the test vectors");
+    println!("Wrote LWMA test vectors to {}", out_path);
+}
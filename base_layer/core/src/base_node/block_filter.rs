@@ -0,0 +1,256 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Compact block filters in the style of [BIP158](https://github.com/bitcoin/bips/blob/master/bip-0158.mediawiki).
+//!
+//! A block filter is a Golomb-coded set of the output commitments, spent input commitments and kernel excesses of a
+//! block. Light clients can test whether any of their commitments or kernels appear in a block using only the filter,
+//! without downloading the block. False positives happen at a rate of roughly 1 in [BLOCK_FILTER_M], false negatives
+//! never happen.
+
+#[cfg(feature = "base_node")]
+use crate::blocks::Block;
+use crate::transactions::types::{HashDigest, HashOutput};
+use digest::Digest;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "base_node")]
+use tari_crypto::tari_utilities::{ByteArray, Hashable};
+
+/// The Golomb-Rice coding parameter, i.e. the number of bits of each encoded delta stored verbatim
+pub const BLOCK_FILTER_P: u8 = 19;
+/// The inverse of the false positive rate
+pub const BLOCK_FILTER_M: u64 = 784_931;
+
+/// A compact filter of the commitments and kernel excesses in a block
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockFilter {
+    /// The hash of the block the filter was built from, used as the key for hashing the filter items
+    pub block_hash: HashOutput,
+    /// The height of the block the filter was built from
+    pub height: u64,
+    /// The number of distinct items in the filter
+    pub n: u64,
+    /// The Golomb-Rice coded, sorted deltas of the hashed items
+    pub filter: Vec<u8>,
+}
+
+impl BlockFilter {
+    /// Build a filter of the given items for the block with the given hash and height
+    pub fn new<I, T>(block_hash: HashOutput, height: u64, items: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        let mut items = items.into_iter().map(|i| i.as_ref().to_vec()).collect::<Vec<_>>();
+        items.sort();
+        items.dedup();
+        let n = items.len() as u64;
+        let range = n * BLOCK_FILTER_M;
+        let mut values = items
+            .iter()
+            .map(|item| hash_to_range(&block_hash, item, range))
+            .collect::<Vec<_>>();
+        values.sort();
+
+        let mut writer = BitWriter::default();
+        let mut last = 0;
+        for value in values {
+            golomb_encode(&mut writer, value - last);
+            last = value;
+        }
+
+        Self {
+            block_hash,
+            height,
+            n,
+            filter: writer.into_bytes(),
+        }
+    }
+
+    /// Build the filter of the output commitments, spent input commitments and kernel excesses of the block
+    #[cfg(feature = "base_node")]
+    pub fn from_block(block: &Block) -> Self {
+        let items = block
+            .body
+            .outputs()
+            .iter()
+            .map(|o| o.commitment.as_bytes().to_vec())
+            .chain(block.body.inputs().iter().map(|i| i.commitment.as_bytes().to_vec()))
+            .chain(block.body.kernels().iter().map(|k| k.excess.as_bytes().to_vec()));
+        Self::new(block.hash(), block.header.height, items)
+    }
+
+    /// Returns true if the item is probably in the filter, and false if it is definitely not
+    pub fn matches<T: AsRef<[u8]>>(&self, item: T) -> bool {
+        self.matches_any(&[item])
+    }
+
+    /// Returns true if any of the items are probably in the filter, and false if none of them are
+    pub fn matches_any<T: AsRef<[u8]>>(&self, items: &[T]) -> bool {
+        if self.n == 0 || items.is_empty() {
+            return false;
+        }
+        let range = self.n * BLOCK_FILTER_M;
+        let mut queries = items
+            .iter()
+            .map(|item| hash_to_range(&self.block_hash, item.as_ref(), range))
+            .collect::<Vec<_>>();
+        queries.sort();
+
+        let mut reader = BitReader::new(&self.filter);
+        let mut queries = queries.into_iter().peekable();
+        let mut value = 0;
+        for _ in 0..self.n {
+            match golomb_decode(&mut reader) {
+                Some(delta) => value += delta,
+                None => return false,
+            }
+            while let Some(query) = queries.peek() {
+                if *query == value {
+                    return true;
+                }
+                if *query > value {
+                    break;
+                }
+                queries.next();
+            }
+            if queries.peek().is_none() {
+                return false;
+            }
+        }
+        false
+    }
+}
+
+// Hash the item keyed by the block hash and map it uniformly onto [0, range)
+fn hash_to_range(key: &[u8], item: &[u8], range: u64) -> u64 {
+    let hash = HashDigest::new().chain(key).chain(item).result();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    ((u128::from(u64::from_le_bytes(bytes)) * u128::from(range)) >> 64) as u64
+}
+
+fn golomb_encode(writer: &mut BitWriter, value: u64) {
+    let quotient = value >> BLOCK_FILTER_P;
+    for _ in 0..quotient {
+        writer.write_bit(true);
+    }
+    writer.write_bit(false);
+    writer.write_bits(value, BLOCK_FILTER_P);
+}
+
+fn golomb_decode(reader: &mut BitReader) -> Option<u64> {
+    let mut quotient = 0;
+    while reader.read_bit()? {
+        quotient += 1;
+    }
+    let remainder = reader.read_bits(BLOCK_FILTER_P)?;
+    Some((quotient << BLOCK_FILTER_P) + remainder)
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit_pos: u8,
+}
+
+impl BitWriter {
+    fn write_bit(&mut self, bit: bool) {
+        if self.bit_pos == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().expect("a byte was pushed above") |= 0x80 >> self.bit_pos;
+        }
+        self.bit_pos = (self.bit_pos + 1) % 8;
+    }
+
+    // Writes the lowest `count` bits of the value, most significant bit first
+    fn write_bits(&mut self, value: u64, count: u8) {
+        for i in (0..count).rev() {
+            self.write_bit((value >> i) & 1 == 1);
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.bytes.get(self.pos / 8)?;
+        let bit = byte & (0x80 >> (self.pos % 8)) != 0;
+        self.pos += 1;
+        Some(bit)
+    }
+
+    fn read_bits(&mut self, count: u8) -> Option<u64> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | u64::from(self.read_bit()?);
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn matches_items_in_filter() {
+        let items = (0u32..100).map(|i| i.to_le_bytes().to_vec()).collect::<Vec<_>>();
+        let filter = BlockFilter::new(vec![1u8; 32], 10, &items);
+        assert_eq!(filter.n, 100);
+        assert!(items.iter().all(|item| filter.matches(item)));
+        assert!(filter.matches_any(&[b"not in filter".to_vec(), items[42].clone()]));
+    }
+
+    #[test]
+    fn rarely_matches_items_not_in_filter() {
+        let items = (0u32..100).map(|i| i.to_le_bytes().to_vec()).collect::<Vec<_>>();
+        let filter = BlockFilter::new(vec![1u8; 32], 10, &items);
+        let false_positives = (100u32..10_100).filter(|i| filter.matches(i.to_le_bytes())).count();
+        assert!(false_positives <= 2);
+        // The same items keyed by a different block hash hash differently
+        let other = BlockFilter::new(vec![2u8; 32], 10, &items);
+        assert_ne!(filter.filter, other.filter);
+    }
+
+    #[test]
+    fn empty_filter() {
+        let filter = BlockFilter::new(vec![1u8; 32], 0, Vec::<Vec<u8>>::new());
+        assert_eq!(filter.n, 0);
+        assert!(filter.filter.is_empty());
+        assert!(!filter.matches(b"anything"));
+    }
+}
@@ -25,6 +25,7 @@ use derive_error::Error;
 use prost::DecodeError;
 use tari_comms::message::MessageError;
 use tari_p2p::services::liveness::error::LivenessError;
+use tari_service_framework::reply_channel::TransportChannelError;
 
 #[derive(Debug, Error)]
 pub enum ChainMetadataSyncError {
@@ -37,4 +38,5 @@ pub enum ChainMetadataSyncError {
     MessageError(MessageError),
     /// Failed to publish `ChainMetadataEvent`
     EventPublishFailed,
+    TransportChannelError(TransportChannelError),
 }
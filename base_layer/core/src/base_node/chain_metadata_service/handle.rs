@@ -20,10 +20,13 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use super::{error::ChainMetadataSyncError, network_state::NetworkState};
 use crate::chain_storage::ChainMetadata;
 use futures::{stream::Fuse, StreamExt};
 use tari_broadcast_channel::Subscriber;
 use tari_comms::peer_manager::NodeId;
+use tari_service_framework::reply_channel::SenderService;
+use tower_service::Service;
 
 #[derive(Debug, Clone)]
 pub struct PeerChainMetadata {
@@ -42,17 +45,43 @@ impl PeerChainMetadata {
 
 #[derive(Debug)]
 pub enum ChainMetadataEvent {
+    /// The chain metadata of the peers that make up the current `NetworkState`, published after each ping round
     PeerChainMetadataReceived(Vec<PeerChainMetadata>),
 }
 
+/// Request types made through the `ChainMetadataHandle` and handled by the `ChainMetadataService`
+#[derive(Debug, Clone)]
+pub enum ChainMetadataRequest {
+    /// Retrieve the aggregated view of the chain metadata of the network
+    GetNetworkState,
+}
+
+/// Response type for `ChainMetadataService`
+#[derive(Debug)]
+pub enum ChainMetadataResponse {
+    NetworkState(NetworkState),
+}
+
 #[derive(Clone)]
 pub struct ChainMetadataHandle {
+    handle: SenderService<ChainMetadataRequest, Result<ChainMetadataResponse, ChainMetadataSyncError>>,
     event_stream: Subscriber<ChainMetadataEvent>,
 }
 
 impl ChainMetadataHandle {
-    pub fn new(event_stream: Subscriber<ChainMetadataEvent>) -> Self {
-        Self { event_stream }
+    pub fn new(
+        handle: SenderService<ChainMetadataRequest, Result<ChainMetadataResponse, ChainMetadataSyncError>>,
+        event_stream: Subscriber<ChainMetadataEvent>,
+    ) -> Self
+    {
+        Self { handle, event_stream }
+    }
+
+    /// Returns the aggregated view of the chain metadata gossiped by neighbouring peers
+    pub async fn get_network_state(&mut self) -> Result<NetworkState, ChainMetadataSyncError> {
+        match self.handle.call(ChainMetadataRequest::GetNetworkState).await?? {
+            ChainMetadataResponse::NetworkState(state) => Ok(state),
+        }
     }
 
     pub fn get_event_stream(&self) -> Subscriber<ChainMetadataEvent> {
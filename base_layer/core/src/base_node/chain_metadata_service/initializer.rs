@@ -27,7 +27,12 @@ use log::*;
 use std::future::Future;
 use tari_broadcast_channel as broadcast_channel;
 use tari_p2p::services::liveness::LivenessHandle;
-use tari_service_framework::{handles::ServiceHandlesFuture, ServiceInitializationError, ServiceInitializer};
+use tari_service_framework::{
+    handles::ServiceHandlesFuture,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+};
 use tari_shutdown::ShutdownSignal;
 use tokio::runtime;
 
@@ -47,21 +52,26 @@ impl ServiceInitializer for ChainMetadataServiceInitializer {
     ) -> Self::Future
     {
         let (publisher, subscriber) = broadcast_channel::bounded(BROADCAST_EVENT_BUFFER_SIZE);
-        let handle = ChainMetadataHandle::new(subscriber);
+        let (sender, receiver) = reply_channel::unbounded();
+        let handle = ChainMetadataHandle::new(sender, subscriber);
         handles_fut.register(handle);
+        let ready_signal = handles_fut.ready_signal::<ChainMetadataHandle>();
 
         executor.spawn(async move {
             let handles = handles_fut.await;
 
             let liveness = handles
-                .get_handle::<LivenessHandle>()
+                .wait_for_handle::<LivenessHandle>()
+                .await
                 .expect("Liveness service required to initialize ChainStateSyncService");
 
             let base_node = handles
-                .get_handle::<LocalNodeCommsInterface>()
+                .wait_for_handle::<LocalNodeCommsInterface>()
+                .await
                 .expect("LocalNodeCommsInterface required to initialize ChainStateSyncService");
 
-            let service_run = ChainMetadataService::new(liveness, base_node, publisher).run();
+            let service_run = ChainMetadataService::new(liveness, base_node, publisher, receiver).run();
+            ready_signal.set_ready();
             pin_mut!(service_run);
             select(service_run, shutdown).await;
             info!(target: LOG_TARGET, "ChainMetadataService has shut down");
@@ -25,8 +25,13 @@ const LOG_TARGET: &str = "c::bn::chain_state_sync_service";
 mod error;
 mod handle;
 mod initializer;
+mod network_state;
 mod service;
 
 // Public re-exports
+pub use error::ChainMetadataSyncError;
 pub use handle::{ChainMetadataEvent, ChainMetadataHandle, PeerChainMetadata};
 pub use initializer::ChainMetadataServiceInitializer;
+pub use network_state::NetworkState;
+
+pub(crate) use network_state::best_chain_metadata;
@@ -0,0 +1,235 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::handle::PeerChainMetadata;
+use crate::{chain_storage::ChainMetadata, proof_of_work::Difficulty};
+use chrono::{NaiveDateTime, Utc};
+use std::{
+    collections::HashMap,
+    fmt,
+    fmt::{Display, Formatter},
+};
+use tari_comms::peer_manager::NodeId;
+
+#[derive(Debug, Clone)]
+struct NetworkStateEntry {
+    chain_metadata: ChainMetadata,
+    round: u64,
+}
+
+/// An aggregated view of the chain metadata that has been gossiped by neighbouring peers. Peer metadata is retained
+/// across ping rounds so that a peer that misses a round does not disappear from the view, and is discarded once the
+/// peer has not responded for `max_missed_rounds` rounds.
+#[derive(Debug, Clone)]
+pub struct NetworkState {
+    peers: HashMap<NodeId, NetworkStateEntry>,
+    round: u64,
+    max_missed_rounds: u64,
+    last_updated: Option<NaiveDateTime>,
+}
+
+impl NetworkState {
+    pub fn new(max_missed_rounds: u64) -> Self {
+        Self {
+            peers: HashMap::new(),
+            round: 0,
+            max_missed_rounds,
+            last_updated: None,
+        }
+    }
+
+    /// Add or replace the chain metadata received from peers in the current round
+    pub fn update(&mut self, peer_metadata: &[PeerChainMetadata]) {
+        for peer in peer_metadata {
+            self.peers.insert(peer.node_id.clone(), NetworkStateEntry {
+                chain_metadata: peer.chain_metadata.clone(),
+                round: self.round,
+            });
+        }
+        self.last_updated = Some(Utc::now().naive_utc());
+    }
+
+    /// Start a new round, discarding the metadata of peers that have not responded in the last `max_missed_rounds`
+    /// rounds
+    pub fn next_round(&mut self) {
+        self.round += 1;
+        let (round, max_missed_rounds) = (self.round, self.max_missed_rounds);
+        self.peers
+            .retain(|_, entry| round.saturating_sub(entry.round) <= max_missed_rounds);
+    }
+
+    /// The number of peers that make up this view of the network
+    pub fn num_peers(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// The time that peer metadata was last received, if any has been received
+    pub fn last_updated(&self) -> Option<NaiveDateTime> {
+        self.last_updated
+    }
+
+    /// The chain metadata of all peers in this view
+    pub fn peer_metadata(&self) -> Vec<PeerChainMetadata> {
+        self.peers
+            .iter()
+            .map(|(node_id, entry)| PeerChainMetadata::new(node_id.clone(), entry.chain_metadata.clone()))
+            .collect()
+    }
+
+    /// The chain metadata of the network tip, i.e. the peer chain with the highest accumulated difficulty, or `None`
+    /// if no peer has reported any chain data
+    pub fn best_metadata(&self) -> Option<ChainMetadata> {
+        let metadata = self.peers.values().map(|entry| &entry.chain_metadata);
+        best_chain_metadata(metadata).filter(|best| best.accumulated_difficulty.is_some())
+    }
+
+    /// The peers that have the network tip on their main chain, these are the candidates to sync from
+    pub fn sync_peers(&self) -> Vec<NodeId> {
+        match self.best_metadata() {
+            Some(best) => self
+                .peers
+                .iter()
+                .filter(|(_, entry)| entry.chain_metadata == best)
+                .map(|(node_id, _)| node_id.clone())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The height of the network tip, or `None` if no peer has reported any chain data
+    pub fn tip_height(&self) -> Option<u64> {
+        self.best_metadata().and_then(|m| m.height_of_longest_chain)
+    }
+
+    /// The number of blocks that the given local height is behind the network tip. Zero is returned if the local
+    /// chain is at or ahead of the network tip, and `None` if the network tip is unknown.
+    pub fn blocks_behind_tip(&self, local_height: u64) -> Option<u64> {
+        self.tip_height().map(|tip| tip.saturating_sub(local_height))
+    }
+
+    /// Returns true if the given local height is within `max_blocks_behind` blocks of the network tip. As in the
+    /// listening state, a node is assumed to be at the tip if the rest of the network has not reported any chain data.
+    pub fn is_within_tip(&self, local_height: u64, max_blocks_behind: u64) -> bool {
+        self.blocks_behind_tip(local_height)
+            .map(|behind| behind <= max_blocks_behind)
+            .unwrap_or(true)
+    }
+}
+
+impl Display for NetworkState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Peers reporting chain metadata: {}", self.num_peers())?;
+        match self.best_metadata() {
+            Some(best) => {
+                writeln!(
+                    f,
+                    "Network tip height: {}",
+                    best.height_of_longest_chain
+                        .map(|h| h.to_string())
+                        .unwrap_or_else(|| "Empty Database".into())
+                )?;
+                writeln!(
+                    f,
+                    "Network tip accumulated difficulty: {}",
+                    best.accumulated_difficulty.unwrap_or_else(Difficulty::min)
+                )?;
+                writeln!(f, "Network tip pruning horizon: {}", best.pruning_horizon)?;
+                writeln!(f, "Peers at network tip: {}", self.sync_peers().len())?;
+            },
+            None => writeln!(f, "Network tip: unknown")?,
+        }
+        match self.last_updated {
+            Some(t) => write!(f, "Last updated: {}", t),
+            None => write!(f, "Last updated: never"),
+        }
+    }
+}
+
+/// Determine the best metadata, i.e. the chain with the highest accumulated difficulty, from a set of chain metadata
+/// received from the network.
+pub(crate) fn best_chain_metadata<'a, I: IntoIterator<Item = &'a ChainMetadata>>(metadata: I) -> Option<ChainMetadata> {
+    // TODO: Use heuristics to weed out outliers / dishonest nodes.
+    metadata
+        .into_iter()
+        .fold(None, |best: Option<&ChainMetadata>, current| match best {
+            Some(best)
+                if current.accumulated_difficulty.unwrap_or_else(Difficulty::min) <
+                    best.accumulated_difficulty.unwrap_or_else(|| 0.into()) =>
+            {
+                Some(best)
+            },
+            _ => Some(current),
+        })
+        .cloned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn peer_metadata(height: u64, difficulty: u64) -> PeerChainMetadata {
+        PeerChainMetadata::new(
+            NodeId::new(),
+            ChainMetadata::new(height, vec![height as u8], 0, difficulty.into()),
+        )
+    }
+
+    #[test]
+    fn best_metadata_and_sync_peers() {
+        let mut state = NetworkState::new(2);
+        assert!(state.best_metadata().is_none());
+        assert!(state.sync_peers().is_empty());
+        assert!(state.is_within_tip(0, 0));
+
+        let peers = vec![peer_metadata(10, 100), peer_metadata(12, 120), peer_metadata(12, 120)];
+        state.update(&peers);
+        assert_eq!(state.num_peers(), 3);
+        assert_eq!(state.tip_height(), Some(12));
+        let sync_peers = state.sync_peers();
+        assert_eq!(sync_peers.len(), 2);
+        assert!(sync_peers.contains(&peers[1].node_id));
+        assert!(sync_peers.contains(&peers[2].node_id));
+
+        assert_eq!(state.blocks_behind_tip(9), Some(3));
+        assert_eq!(state.blocks_behind_tip(13), Some(0));
+        assert!(state.is_within_tip(10, 2));
+        assert!(!state.is_within_tip(9, 2));
+    }
+
+    #[test]
+    fn stale_peers_are_discarded() {
+        let mut state = NetworkState::new(1);
+        let stale = peer_metadata(20, 200);
+        let fresh = peer_metadata(10, 100);
+        state.update(&[stale.clone(), fresh.clone()]);
+        assert_eq!(state.tip_height(), Some(20));
+
+        state.next_round();
+        state.update(&[fresh.clone()]);
+        assert_eq!(state.num_peers(), 2);
+
+        state.next_round();
+        assert_eq!(state.num_peers(), 1);
+        assert_eq!(state.tip_height(), Some(10));
+        assert_eq!(state.sync_peers(), vec![fresh.node_id]);
+    }
+}
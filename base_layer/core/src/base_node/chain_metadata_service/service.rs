@@ -20,10 +20,15 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::{error::ChainMetadataSyncError, LOG_TARGET};
+use super::{error::ChainMetadataSyncError, network_state::NetworkState, LOG_TARGET};
 use crate::{
     base_node::{
-        chain_metadata_service::handle::{ChainMetadataEvent, PeerChainMetadata},
+        chain_metadata_service::handle::{
+            ChainMetadataEvent,
+            ChainMetadataRequest,
+            ChainMetadataResponse,
+            PeerChainMetadata,
+        },
         comms_interface::{BlockEvent, LocalNodeCommsInterface},
         proto,
     },
@@ -37,13 +42,20 @@ use tari_broadcast_channel::Publisher;
 use tari_common::log_if_error;
 use tari_comms::{message::MessageExt, peer_manager::NodeId};
 use tari_p2p::services::liveness::{LivenessEvent, LivenessHandle, Metadata, MetadataKey};
+use tari_service_framework::reply_channel::Receiver;
+
+/// The number of consecutive ping rounds a peer may fail to respond in before its chain metadata is removed from the
+/// `NetworkState`
+const NETWORK_STATE_MAX_MISSED_ROUNDS: u64 = 3;
 
 pub(super) struct ChainMetadataService {
     liveness: LivenessHandle,
     base_node: LocalNodeCommsInterface,
     peer_chain_metadata: Vec<PeerChainMetadata>,
+    network_state: NetworkState,
     last_chainstate_flushed_at: NaiveDateTime,
     event_publisher: Publisher<ChainMetadataEvent>,
+    request_stream: Option<Receiver<ChainMetadataRequest, Result<ChainMetadataResponse, ChainMetadataSyncError>>>,
 }
 
 impl ChainMetadataService {
@@ -52,23 +64,33 @@ impl ChainMetadataService {
     /// ## Arguments
     /// `liveness` - the liveness service handle
     /// `base_node` - the base node service handle
+    /// `event_publisher` - publisher for `ChainMetadataEvent`s
+    /// `request_stream` - requests made through the `ChainMetadataHandle`
     pub fn new(
         liveness: LivenessHandle,
         base_node: LocalNodeCommsInterface,
         event_publisher: Publisher<ChainMetadataEvent>,
+        request_stream: Receiver<ChainMetadataRequest, Result<ChainMetadataResponse, ChainMetadataSyncError>>,
     ) -> Self
     {
         Self {
             liveness,
             base_node,
             peer_chain_metadata: Vec::new(),
+            network_state: NetworkState::new(NETWORK_STATE_MAX_MISSED_ROUNDS),
             last_chainstate_flushed_at: Utc::now().naive_utc(),
             event_publisher,
+            request_stream: Some(request_stream),
         }
     }
 
     /// Run the service
     pub async fn run(mut self) {
+        let mut request_stream = self
+            .request_stream
+            .take()
+            .expect("ChainMetadataService initialized without request_stream")
+            .fuse();
         let mut liveness_event_stream = self.liveness.get_event_stream_fused();
         let mut base_node_event_stream = self.base_node.get_block_event_stream_fused();
 
@@ -80,6 +102,11 @@ impl ChainMetadataService {
 
         loop {
             futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(Ok(self.handle_request(request)));
+                },
+
                 event = base_node_event_stream.select_next_some() => {
                     log_if_error!(
                         level: debug,
@@ -105,6 +132,12 @@ impl ChainMetadataService {
         }
     }
 
+    fn handle_request(&self, request: ChainMetadataRequest) -> ChainMetadataResponse {
+        match request {
+            ChainMetadataRequest::GetNetworkState => ChainMetadataResponse::NetworkState(self.network_state.clone()),
+        }
+    }
+
     /// Handle BlockEvents
     async fn handle_block_event(&mut self, event: &BlockEvent) -> Result<(), ChainMetadataSyncError> {
         match event {
@@ -163,6 +196,7 @@ impl ChainMetadataService {
                 if !self.peer_chain_metadata.is_empty() {
                     self.flush_chain_metadata_to_event_publisher().await?;
                 }
+                self.network_state.next_round();
                 // Ensure that we're waiting for the correct amount of peers to respond
                 // and have allocated space for their replies
                 self.resize_chainstate_buffer(*num_peers);
@@ -175,9 +209,13 @@ impl ChainMetadataService {
 
     async fn flush_chain_metadata_to_event_publisher(&mut self) -> Result<(), ChainMetadataSyncError> {
         let chain_metadata = self.peer_chain_metadata.drain(..).collect::<Vec<_>>();
+        self.network_state.update(&chain_metadata);
 
+        // Publish the aggregated view so that peers that did not respond in this round are still considered
         self.event_publisher
-            .send(ChainMetadataEvent::PeerChainMetadataReceived(chain_metadata))
+            .send(ChainMetadataEvent::PeerChainMetadataReceived(
+                self.network_state.peer_metadata(),
+            ))
             .await
             .map_err(|_| ChainMetadataSyncError::EventPublishFailed)?;
 
@@ -269,7 +307,8 @@ mod test {
             let (base_node, mut base_node_receiver) = create_base_node_nci();
 
             let (publisher, _subscriber) = broadcast_channel::bounded(1);
-            let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+            let (_, request_stream) = reply_channel::unbounded();
+            let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher, request_stream);
 
             let mut proto_chain_metadata = create_sample_proto_chain_metadata();
             proto_chain_metadata.height_of_longest_chain = Some(123);
@@ -317,7 +356,8 @@ mod test {
         let (base_node, _) = create_base_node_nci();
 
         let (publisher, _subscriber) = broadcast_channel::bounded(1);
-        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+        let (_, request_stream) = reply_channel::unbounded();
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher, request_stream);
 
         // To prevent the chain metadata buffer being flushed after receiving a single pong event,
         // extend it's capacity to 2
@@ -348,7 +388,8 @@ mod test {
 
         let (base_node, _) = create_base_node_nci();
         let (publisher, _subscriber) = broadcast_channel::bounded(1);
-        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+        let (_, request_stream) = reply_channel::unbounded();
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher, request_stream);
 
         let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
         let err = service.handle_liveness_event(&sample_event).await.unwrap_err();
@@ -371,7 +412,8 @@ mod test {
 
         let (base_node, _) = create_base_node_nci();
         let (publisher, _subscriber) = broadcast_channel::bounded(1);
-        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+        let (_, request_stream) = reply_channel::unbounded();
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher, request_stream);
 
         let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
         service.handle_liveness_event(&sample_event).await.unwrap();
@@ -394,7 +436,8 @@ mod test {
 
         let (base_node, _) = create_base_node_nci();
         let (publisher, _subscriber) = broadcast_channel::bounded(1);
-        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher);
+        let (_, request_stream) = reply_channel::unbounded();
+        let mut service = ChainMetadataService::new(liveness_handle, base_node, publisher, request_stream);
 
         let sample_event = LivenessEvent::ReceivedPong(Box::new(pong_event));
         let err = service.handle_liveness_event(&sample_event).await.unwrap_err();
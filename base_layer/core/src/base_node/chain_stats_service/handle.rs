@@ -0,0 +1,235 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::error::ChainStatsError;
+use crate::{blocks::BlockHash, proof_of_work::PowAlgorithm};
+use serde::{Deserialize, Serialize};
+use std::{fmt, fmt::Display};
+use tari_crypto::tari_utilities::hex::Hex;
+use tari_service_framework::reply_channel::SenderService;
+use tower_service::Service;
+
+/// The upper bounds, in seconds, of the block interval histogram buckets
+pub(super) const BLOCK_INTERVAL_BUCKETS: [f64; 8] = [15.0, 30.0, 60.0, 90.0, 120.0, 180.0, 300.0, 600.0];
+/// The upper bounds, in seconds, of the block propagation delay histogram buckets
+pub(super) const PROPAGATION_DELAY_BUCKETS: [f64; 8] = [0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0];
+
+/// Request types made through the `ChainStatsHandle` and handled by the `ChainStatsService`
+#[derive(Debug, Clone)]
+pub enum ChainStatsRequest {
+    /// Retrieve the records of (up to) the given number of most recent blocks
+    GetBlockStats(usize),
+    /// Retrieve a summary of (up to) the given number of most recent blocks
+    GetSummary(usize),
+}
+
+/// Response type for `ChainStatsService`
+#[derive(Debug)]
+pub enum ChainStatsResponse {
+    BlockStats(Vec<BlockStats>),
+    Summary(ChainStatsSummary),
+}
+
+/// The statistics recorded for a block received by this node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockStats {
+    pub height: u64,
+    pub hash: BlockHash,
+    pub pow_algo: PowAlgorithm,
+    /// The block timestamp in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Milliseconds between the block timestamp and the time that this node received the block. This is negative if
+    /// the block timestamp is ahead of the local clock.
+    pub propagation_delay: i64,
+    /// Seconds between the timestamps of the parent block and this block, if the parent header is known
+    pub interval: Option<i64>,
+    /// The difficulty achieved by the proof of work of this block
+    pub difficulty: u64,
+}
+
+/// A summary of the block times, propagation delays and difficulties of a range of blocks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainStatsSummary {
+    pub num_blocks: usize,
+    /// The lowest and highest block heights included in the summary
+    pub heights: Option<(u64, u64)>,
+    /// The target time between blocks in seconds
+    pub target_block_interval: u64,
+    /// Mean and median time between blocks in seconds
+    pub mean_block_interval: Option<f64>,
+    pub median_block_interval: Option<i64>,
+    /// Mean, median and maximum propagation delay in milliseconds
+    pub mean_propagation_delay: Option<f64>,
+    pub median_propagation_delay: Option<i64>,
+    pub max_propagation_delay: Option<i64>,
+    pub min_difficulty: Option<u64>,
+    pub max_difficulty: Option<u64>,
+    /// The number of block intervals in each of the `BLOCK_INTERVAL_BUCKETS`, followed by the number that exceeded
+    /// the last bucket
+    pub block_interval_histogram: Vec<usize>,
+    /// The number of propagation delays in each of the `PROPAGATION_DELAY_BUCKETS`, followed by the number that
+    /// exceeded the last bucket
+    pub propagation_delay_histogram: Vec<usize>,
+}
+
+impl ChainStatsSummary {
+    pub fn new(blocks: &[BlockStats], target_block_interval: u64) -> Self {
+        let mut intervals = blocks.iter().filter_map(|b| b.interval).collect::<Vec<_>>();
+        intervals.sort_unstable();
+        let mut delays = blocks.iter().map(|b| b.propagation_delay).collect::<Vec<_>>();
+        delays.sort_unstable();
+        let min_height = blocks.iter().map(|b| b.height).min();
+        let max_height = blocks.iter().map(|b| b.height).max();
+
+        Self {
+            num_blocks: blocks.len(),
+            heights: min_height.and_then(|min| max_height.map(|max| (min, max))),
+            target_block_interval,
+            mean_block_interval: mean(&intervals),
+            median_block_interval: intervals.get(intervals.len() / 2).copied(),
+            mean_propagation_delay: mean(&delays),
+            median_propagation_delay: delays.get(delays.len() / 2).copied(),
+            max_propagation_delay: delays.last().copied(),
+            min_difficulty: blocks.iter().map(|b| b.difficulty).min(),
+            max_difficulty: blocks.iter().map(|b| b.difficulty).max(),
+            block_interval_histogram: histogram(intervals.iter().map(|i| *i as f64), &BLOCK_INTERVAL_BUCKETS),
+            propagation_delay_histogram: histogram(
+                delays.iter().map(|d| *d as f64 / 1000.0),
+                &PROPAGATION_DELAY_BUCKETS,
+            ),
+        }
+    }
+}
+
+fn mean(values: &[i64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().map(|v| *v as f64).sum::<f64>() / values.len() as f64)
+}
+
+/// Counts the values less than or equal to each bucket upper bound (and greater than the previous one). The last
+/// count is the number of values greater than the last bucket.
+fn histogram<I: Iterator<Item = f64>>(values: I, buckets: &[f64]) -> Vec<usize> {
+    let mut counts = vec![0; buckets.len() + 1];
+    for value in values {
+        let i = buckets
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(buckets.len());
+        counts[i] += 1;
+    }
+    counts
+}
+
+fn fmt_histogram(f: &mut fmt::Formatter<'_>, counts: &[usize], buckets: &[f64]) -> fmt::Result {
+    const MAX_BAR_WIDTH: usize = 40;
+    let max_count = counts.iter().copied().max().unwrap_or(0).max(1);
+    for (i, count) in counts.iter().enumerate() {
+        let label = match buckets.get(i) {
+            Some(bound) => format!("<= {}s", bound),
+            None => format!("> {}s", buckets.last().copied().unwrap_or(0.0)),
+        };
+        let bar = "#".repeat((count * MAX_BAR_WIDTH + max_count - 1) / max_count);
+        writeln!(f, "  {:>9} | {} {}", label, bar, count)?;
+    }
+    Ok(())
+}
+
+fn fmt_optional<T: Display>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string())
+}
+
+impl Display for ChainStatsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.heights {
+            Some((first, last)) => writeln!(f, "Statistics of {} blocks (#{} - #{})", self.num_blocks, first, last)?,
+            None => return write!(f, "No blocks have been recorded"),
+        }
+        writeln!(
+            f,
+            "Block interval: mean {}s, median {}s, target {}s",
+            fmt_optional(self.mean_block_interval.map(|i| format!("{:.1}", i))),
+            fmt_optional(self.median_block_interval),
+            self.target_block_interval
+        )?;
+        writeln!(
+            f,
+            "Propagation delay: mean {}ms, median {}ms, max {}ms",
+            fmt_optional(self.mean_propagation_delay.map(|d| format!("{:.0}", d))),
+            fmt_optional(self.median_propagation_delay),
+            fmt_optional(self.max_propagation_delay)
+        )?;
+        writeln!(
+            f,
+            "Difficulty: min {}, max {}",
+            fmt_optional(self.min_difficulty),
+            fmt_optional(self.max_difficulty)
+        )?;
+        writeln!(f, "Block interval histogram:")?;
+        fmt_histogram(f, &self.block_interval_histogram, &BLOCK_INTERVAL_BUCKETS)?;
+        writeln!(f, "Propagation delay histogram:")?;
+        fmt_histogram(f, &self.propagation_delay_histogram, &PROPAGATION_DELAY_BUCKETS)
+    }
+}
+
+impl Display for BlockStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} ({}): interval {}s, propagation delay {}ms, difficulty {}",
+            self.height,
+            self.hash.to_hex(),
+            self.pow_algo,
+            fmt_optional(self.interval),
+            self.propagation_delay,
+            self.difficulty
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ChainStatsHandle {
+    handle: SenderService<ChainStatsRequest, Result<ChainStatsResponse, ChainStatsError>>,
+}
+
+impl ChainStatsHandle {
+    pub fn new(handle: SenderService<ChainStatsRequest, Result<ChainStatsResponse, ChainStatsError>>) -> Self {
+        Self { handle }
+    }
+
+    /// Returns the records of (up to) the `n` most recent blocks, oldest first
+    pub async fn get_block_stats(&mut self, n: usize) -> Result<Vec<BlockStats>, ChainStatsError> {
+        match self.handle.call(ChainStatsRequest::GetBlockStats(n)).await?? {
+            ChainStatsResponse::BlockStats(stats) => Ok(stats),
+            _ => Err(ChainStatsError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns a summary of (up to) the `n` most recent blocks
+    pub async fn get_summary(&mut self, n: usize) -> Result<ChainStatsSummary, ChainStatsError> {
+        match self.handle.call(ChainStatsRequest::GetSummary(n)).await?? {
+            ChainStatsResponse::Summary(summary) => Ok(summary),
+            _ => Err(ChainStatsError::UnexpectedApiResponse),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{config::ChainStatsConfig, handle::ChainStatsHandle, service::ChainStatsService, LOG_TARGET};
+use crate::base_node::comms_interface::LocalNodeCommsInterface;
+use futures::{future, future::select, pin_mut};
+use log::*;
+use std::future::Future;
+use tari_service_framework::{
+    handles::ServiceHandlesFuture,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+};
+use tari_shutdown::ShutdownSignal;
+use tokio::runtime;
+
+pub struct ChainStatsServiceInitializer {
+    config: ChainStatsConfig,
+}
+
+impl ChainStatsServiceInitializer {
+    pub fn new(config: ChainStatsConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl ServiceInitializer for ChainStatsServiceInitializer {
+    type Future = impl Future<Output = Result<(), ServiceInitializationError>>;
+
+    fn initialize(
+        &mut self,
+        executor: runtime::Handle,
+        handles_fut: ServiceHandlesFuture,
+        shutdown: ShutdownSignal,
+    ) -> Self::Future
+    {
+        let (sender, receiver) = reply_channel::unbounded();
+        handles_fut.register(ChainStatsHandle::new(sender));
+        let ready_signal = handles_fut.ready_signal::<ChainStatsHandle>();
+
+        let config = self.config.clone();
+        executor.spawn(async move {
+            let handles = handles_fut.await;
+
+            let base_node = handles
+                .wait_for_handle::<LocalNodeCommsInterface>()
+                .await
+                .expect("LocalNodeCommsInterface required to initialize ChainStatsService");
+
+            let service_run = ChainStatsService::new(config, base_node, receiver).run();
+            ready_signal.set_ready();
+            pin_mut!(service_run);
+            select(service_run, shutdown).await;
+            info!(target: LOG_TARGET, "ChainStatsService has shut down");
+        });
+
+        future::ready(Ok(()))
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! The chain stats service records statistics for every block received by this node: the delay between the block
+//! timestamp and its arrival (the propagation delay), the time since its parent block and its difficulty. The records
+//! are kept in a ring buffer which is persisted to disk, and every observation is exported to the prometheus
+//! `tari_base_node_block_*` metrics. Operators can use these to see whether the network is hitting the target block
+//! time.
+
+const LOG_TARGET: &str = "c::bn::chain_stats_service";
+
+mod config;
+mod error;
+mod handle;
+mod initializer;
+mod service;
+
+// Public re-exports
+pub use config::ChainStatsConfig;
+pub use error::ChainStatsError;
+pub use handle::{BlockStats, ChainStatsHandle, ChainStatsRequest, ChainStatsResponse, ChainStatsSummary};
+pub use initializer::ChainStatsServiceInitializer;
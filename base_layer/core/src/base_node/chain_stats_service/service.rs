@@ -0,0 +1,360 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{
+    config::ChainStatsConfig,
+    error::ChainStatsError,
+    handle::{
+        BlockStats,
+        ChainStatsRequest,
+        ChainStatsResponse,
+        ChainStatsSummary,
+        BLOCK_INTERVAL_BUCKETS,
+        PROPAGATION_DELAY_BUCKETS,
+    },
+    LOG_TARGET,
+};
+use crate::{
+    base_node::comms_interface::{BlockEvent, LocalNodeCommsInterface},
+    blocks::Block,
+    chain_storage::BlockAddResult,
+};
+use chrono::Utc;
+use futures::stream::StreamExt;
+use lazy_static::lazy_static;
+use log::*;
+use prometheus::{register_gauge_vec, register_histogram, GaugeVec, Histogram};
+use std::{collections::VecDeque, fs, path::Path};
+use tari_common::log_if_error;
+use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+use tari_service_framework::reply_channel::Receiver;
+
+lazy_static! {
+    static ref BLOCK_INTERVAL: Histogram = register_histogram!(
+        "tari_base_node_block_interval_seconds",
+        "Time between the timestamps of received blocks and their parent blocks",
+        BLOCK_INTERVAL_BUCKETS.to_vec()
+    )
+    .expect("Block interval metric registered more than once");
+    static ref BLOCK_PROPAGATION_DELAY: Histogram = register_histogram!(
+        "tari_base_node_block_propagation_delay_seconds",
+        "Time between the timestamps of received blocks and their arrival at this node",
+        PROPAGATION_DELAY_BUCKETS.to_vec()
+    )
+    .expect("Block propagation delay metric registered more than once");
+    static ref BLOCK_DIFFICULTY: GaugeVec = register_gauge_vec!(
+        "tari_base_node_block_difficulty",
+        "Difficulty achieved by the last received block of each proof of work algorithm",
+        &["pow_algo"]
+    )
+    .expect("Block difficulty metric registered more than once");
+}
+
+pub(super) struct ChainStatsService {
+    config: ChainStatsConfig,
+    base_node: LocalNodeCommsInterface,
+    request_stream: Option<Receiver<ChainStatsRequest, Result<ChainStatsResponse, ChainStatsError>>>,
+    records: VecDeque<BlockStats>,
+}
+
+impl ChainStatsService {
+    pub fn new(
+        config: ChainStatsConfig,
+        base_node: LocalNodeCommsInterface,
+        request_stream: Receiver<ChainStatsRequest, Result<ChainStatsResponse, ChainStatsError>>,
+    ) -> Self
+    {
+        let mut records = match config.storage_path.as_ref().filter(|path| path.exists()) {
+            Some(path) => load_records(path).unwrap_or_else(|err| {
+                warn!(
+                    target: LOG_TARGET,
+                    "Discarding block stats because '{}' could not be read: {}",
+                    path.display(),
+                    err
+                );
+                VecDeque::new()
+            }),
+            None => VecDeque::new(),
+        };
+        while records.len() > config.capacity {
+            records.pop_front();
+        }
+        Self {
+            config,
+            base_node,
+            request_stream: Some(request_stream),
+            records,
+        }
+    }
+
+    /// Run the service
+    pub async fn run(mut self) {
+        let mut request_stream = self
+            .request_stream
+            .take()
+            .expect("ChainStatsService initialized without request_stream")
+            .fuse();
+        let mut base_node_event_stream = self.base_node.get_block_event_stream_fused();
+
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(Ok(self.handle_request(request)));
+                },
+
+                event = base_node_event_stream.select_next_some() => {
+                    log_if_error!(
+                        level: warn,
+                        target: LOG_TARGET,
+                        "Failed to record block stats because '{:?}'",
+                        self.handle_block_event(&event).await
+                    );
+                },
+
+                complete => {
+                    info!(target: LOG_TARGET, "ChainStatsService is exiting because all tasks have completed");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_request(&self, request: ChainStatsRequest) -> ChainStatsResponse {
+        match request {
+            ChainStatsRequest::GetBlockStats(n) => ChainStatsResponse::BlockStats(self.last_records(n)),
+            ChainStatsRequest::GetSummary(n) => ChainStatsResponse::Summary(ChainStatsSummary::new(
+                &self.last_records(n),
+                self.config.target_block_interval,
+            )),
+        }
+    }
+
+    async fn handle_block_event(&mut self, event: &BlockEvent) -> Result<(), ChainStatsError> {
+        match event {
+            // Only the received block arrived now, so the other blocks added by a reorg are not recorded
+            BlockEvent::Verified((block, BlockAddResult::Ok)) |
+            BlockEvent::Verified((block, BlockAddResult::ChainReorg(_))) => {
+                let parent_timestamp = self.parent_timestamp(block).await;
+                self.record_block(block, parent_timestamp, Utc::now().timestamp_millis());
+                self.persist()
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the timestamp of the parent of the block, from the previous record if it is the parent, otherwise from
+    /// the local chain
+    async fn parent_timestamp(&mut self, block: &Block) -> Option<u64> {
+        if block.header.height == 0 {
+            return None;
+        }
+        if let Some(record) = self.records.back().filter(|r| r.hash == block.header.prev_hash) {
+            return Some(record.timestamp);
+        }
+        match self.base_node.get_headers(vec![block.header.height - 1]).await {
+            Ok(mut headers) => headers
+                .pop()
+                .filter(|header| header.hash() == block.header.prev_hash)
+                .map(|header| header.timestamp.as_u64()),
+            Err(err) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Could not fetch the parent header of block {}: {:?}",
+                    block.hash().to_hex(),
+                    err
+                );
+                None
+            },
+        }
+    }
+
+    fn record_block(&mut self, block: &Block, parent_timestamp: Option<u64>, received_at: i64) {
+        let timestamp = block.header.timestamp.as_u64();
+        let stats = BlockStats {
+            height: block.header.height,
+            hash: block.hash(),
+            pow_algo: block.header.pow.pow_algo,
+            timestamp,
+            propagation_delay: received_at - timestamp as i64 * 1000,
+            interval: parent_timestamp.map(|parent| timestamp as i64 - parent as i64),
+            difficulty: block.header.achieved_difficulty().as_u64(),
+        };
+        trace!(target: LOG_TARGET, "Block stats: {}", stats);
+
+        if let Some(interval) = stats.interval {
+            BLOCK_INTERVAL.observe(interval as f64);
+        }
+        BLOCK_PROPAGATION_DELAY.observe(stats.propagation_delay.max(0) as f64 / 1000.0);
+        BLOCK_DIFFICULTY
+            .with_label_values(&[&stats.pow_algo.to_string()])
+            .set(stats.difficulty as f64);
+
+        // Records of blocks that were replaced by a reorg no longer describe the chain
+        self.records.retain(|r| r.height < stats.height);
+        if self.records.len() >= self.config.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(stats);
+    }
+
+    fn last_records(&self, n: usize) -> Vec<BlockStats> {
+        self.records
+            .iter()
+            .skip(self.records.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+
+    fn persist(&self) -> Result<(), ChainStatsError> {
+        if let Some(path) = self.config.storage_path.as_ref() {
+            let json = serde_json::to_string(&self.records)?;
+            fs::write(path, json)?;
+        }
+        Ok(())
+    }
+}
+
+fn load_records(path: &Path) -> Result<VecDeque<BlockStats>, ChainStatsError> {
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        blocks::{BlockBuilder, BlockHeader},
+        proof_of_work::PowAlgorithm,
+    };
+    use tari_broadcast_channel as broadcast_channel;
+    use tari_crypto::tari_utilities::epoch_time::EpochTime;
+    use tari_service_framework::reply_channel;
+    use tari_test_utils::{random, unpack_enum};
+    use tempdir::TempDir;
+
+    fn create_service(config: ChainStatsConfig) -> ChainStatsService {
+        let (base_node_sender, _) = reply_channel::unbounded();
+        let (block_sender, _) = reply_channel::unbounded();
+        let (_, subscriber) = broadcast_channel::bounded(1);
+        let base_node = LocalNodeCommsInterface::new(base_node_sender, block_sender, subscriber);
+        let (_, request_stream) = reply_channel::unbounded();
+        ChainStatsService::new(config, base_node, request_stream)
+    }
+
+    fn create_block(height: u64, timestamp: u64, prev_hash: Vec<u8>) -> Block {
+        let mut header = BlockHeader::new(0);
+        header.height = height;
+        header.timestamp = EpochTime::from(timestamp);
+        header.prev_hash = prev_hash;
+        BlockBuilder::new(0).with_header(header).build()
+    }
+
+    #[test]
+    fn record_blocks() {
+        let mut service = create_service(ChainStatsConfig {
+            capacity: 3,
+            ..Default::default()
+        });
+        let mut prev_hash = Vec::new();
+        for height in 0..4 {
+            let block = create_block(height, 1000 + height * 100, prev_hash);
+            let parent_timestamp = if height == 0 { None } else { Some(1000 + (height - 1) * 100) };
+            service.record_block(&block, parent_timestamp, (1001 + height as i64 * 100) * 1000);
+            prev_hash = block.hash();
+        }
+
+        let records = service.last_records(10);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].height, 1);
+        assert_eq!(records[2].height, 3);
+        assert!(records.iter().all(|r| r.interval == Some(100)));
+        assert!(records.iter().all(|r| r.propagation_delay == 1000));
+
+        // A reorg to height 2 replaces the records of heights 2 and 3
+        let block = create_block(2, 1150, records[0].hash.clone());
+        service.record_block(&block, Some(1100), 1150 * 1000);
+        let records = service.last_records(10);
+        assert_eq!(records.iter().map(|r| r.height).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(records[1].interval, Some(50));
+
+        let response = service.handle_request(ChainStatsRequest::GetSummary(1));
+        unpack_enum!(ChainStatsResponse::Summary(summary) = response);
+        assert_eq!(summary.num_blocks, 1);
+        assert_eq!(summary.heights, Some((2, 2)));
+        assert_eq!(summary.median_block_interval, Some(50));
+    }
+
+    #[test]
+    fn persist_records() {
+        let temp_dir = TempDir::new(random::string(8).as_str()).unwrap();
+        let config = ChainStatsConfig {
+            storage_path: Some(temp_dir.path().join("chain_stats.json")),
+            ..Default::default()
+        };
+        let mut service = create_service(config.clone());
+        let block = create_block(0, 1000, Vec::new());
+        service.record_block(&block, None, 1_000_500);
+        service.persist().unwrap();
+
+        let service = create_service(config);
+        let records = service.last_records(10);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].hash, block.hash());
+        assert_eq!(records[0].propagation_delay, 500);
+        assert_eq!(records[0].interval, None);
+    }
+
+    #[test]
+    fn summary() {
+        let blocks = [(60, 500), (120, 1500), (400, 12_000)]
+            .iter()
+            .enumerate()
+            .map(|(i, (interval, delay))| BlockStats {
+                height: i as u64,
+                hash: Vec::new(),
+                pow_algo: PowAlgorithm::Blake,
+                timestamp: 0,
+                propagation_delay: *delay,
+                interval: Some(*interval),
+                difficulty: 10 + i as u64,
+            })
+            .collect::<Vec<_>>();
+        let summary = ChainStatsSummary::new(&blocks, 120);
+        assert_eq!(summary.heights, Some((0, 2)));
+        assert_eq!(summary.mean_block_interval, Some(580.0 / 3.0));
+        assert_eq!(summary.median_block_interval, Some(120));
+        assert_eq!(summary.max_propagation_delay, Some(12_000));
+        assert_eq!(summary.min_difficulty, Some(10));
+        assert_eq!(summary.max_difficulty, Some(12));
+        assert_eq!(summary.block_interval_histogram, vec![0, 0, 1, 0, 1, 0, 0, 1, 0]);
+        assert_eq!(summary.propagation_delay_histogram, vec![0, 1, 0, 1, 0, 0, 1, 0, 0]);
+        assert!(summary
+            .to_string()
+            .contains("Block interval: mean 193.3s, median 120s, target 120s"));
+
+        assert_eq!(
+            ChainStatsSummary::new(&[], 120).to_string(),
+            "No blocks have been recorded"
+        );
+    }
+}
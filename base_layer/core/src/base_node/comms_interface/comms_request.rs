@@ -24,7 +24,7 @@ use crate::{
     blocks::NewBlockTemplate,
     chain_storage::MmrTree,
     proof_of_work::PowAlgorithm,
-    transactions::types::HashOutput,
+    transactions::types::{HashOutput, Signature},
 };
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Error, Formatter};
@@ -42,12 +42,14 @@ pub struct MmrStateRequest {
 pub enum NodeCommsRequest {
     GetChainMetadata,
     FetchKernels(Vec<HashOutput>),
+    SearchKernels(Vec<Signature>),
     FetchHeaders(Vec<u64>),
     FetchHeadersWithHashes(Vec<HashOutput>),
     FetchHeadersAfter(Vec<HashOutput>, HashOutput),
     FetchUtxos(Vec<HashOutput>),
     FetchBlocks(Vec<u64>),
     FetchBlocksWithHashes(Vec<HashOutput>),
+    FetchBlockFilters(Vec<u64>),
     GetNewBlockTemplate,
     GetNewBlock(NewBlockTemplate),
     GetTargetDifficulty(PowAlgorithm),
@@ -58,12 +60,14 @@ impl Display for NodeCommsRequest {
         match self {
             NodeCommsRequest::GetChainMetadata => f.write_str("GetChainMetadata"),
             NodeCommsRequest::FetchKernels(v) => f.write_str(&format!("FetchKernels (n={})", v.len())),
+            NodeCommsRequest::SearchKernels(v) => f.write_str(&format!("SearchKernels (n={})", v.len())),
             NodeCommsRequest::FetchHeaders(v) => f.write_str(&format!("FetchHeaders (n={})", v.len())),
             NodeCommsRequest::FetchHeadersWithHashes(v) => f.write_str(&format!("FetchHeaders (n={})", v.len())),
             NodeCommsRequest::FetchHeadersAfter(v, _hash) => f.write_str(&format!("FetchHeadersAfter (n={})", v.len())),
             NodeCommsRequest::FetchUtxos(v) => f.write_str(&format!("FetchUtxos (n={})", v.len())),
             NodeCommsRequest::FetchBlocks(v) => f.write_str(&format!("FetchBlocks (n={})", v.len())),
             NodeCommsRequest::FetchBlocksWithHashes(v) => f.write_str(&format!("FetchBlocks (n={})", v.len())),
+            NodeCommsRequest::FetchBlockFilters(v) => f.write_str(&format!("FetchBlockFilters (n={})", v.len())),
             NodeCommsRequest::GetNewBlockTemplate => f.write_str("GetNewBlockTemplate"),
             NodeCommsRequest::GetNewBlock(b) => f.write_str(&format!("GetNewBlock (Block Height={})", b.header.height)),
             NodeCommsRequest::GetTargetDifficulty(algo) => f.write_str(&format!("GetTargetDifficulty ({})", algo)),
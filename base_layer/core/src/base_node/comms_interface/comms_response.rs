@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    base_node::BlockFilter,
     blocks::{blockheader::BlockHeader, Block, NewBlockTemplate},
     chain_storage::{ChainMetadata, HistoricalBlock},
     proof_of_work::Difficulty,
@@ -33,9 +34,12 @@ use serde::{Deserialize, Serialize};
 pub enum NodeCommsResponse {
     ChainMetadata(ChainMetadata),
     TransactionKernels(Vec<TransactionKernel>),
+    /// Kernels found by excess signature, each with the header of the block that contains it
+    KernelSearchResults(Vec<(TransactionKernel, BlockHeader)>),
     BlockHeaders(Vec<BlockHeader>),
     TransactionOutputs(Vec<TransactionOutput>),
     HistoricalBlocks(Vec<HistoricalBlock>),
+    BlockFilters(Vec<BlockFilter>),
     NewBlockTemplate(NewBlockTemplate),
     NewBlock(Block),
     TargetDifficulty(Difficulty),
@@ -40,7 +40,10 @@ use crate::{
 };
 use futures::SinkExt;
 use log::*;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use strum_macros::Display;
 use tari_broadcast_channel::Publisher;
 use tari_comms::types::CommsPublicKey;
@@ -48,7 +51,35 @@ use tari_crypto::tari_utilities::{hash::Hashable, hex::Hex};
 use tokio::sync::RwLock;
 
 const LOG_TARGET: &str = "c::bn::comms_interface::inbound_handler";
-const MAX_HEADERS_PER_RESPONSE: u32 = 100;
+const MAX_HEADERS_PER_RESPONSE: usize = 100;
+const MAX_BLOCKS_PER_RESPONSE: usize = 50;
+const MAX_KERNELS_PER_RESPONSE: usize = 1000;
+const MAX_UTXOS_PER_RESPONSE: usize = 1000;
+const MAX_BLOCK_FILTERS_PER_RESPONSE: usize = 1000;
+
+/// The maximum number of items in the response to a single request, so that one request can not make the node load
+/// and serialize an unbounded amount of data. Requests for more items are truncated to the first items requested, and
+/// the remaining items can be requested in a follow-up request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResponseLimits {
+    pub max_headers: usize,
+    pub max_blocks: usize,
+    pub max_kernels: usize,
+    pub max_utxos: usize,
+    pub max_block_filters: usize,
+}
+
+impl Default for ResponseLimits {
+    fn default() -> Self {
+        Self {
+            max_headers: MAX_HEADERS_PER_RESPONSE,
+            max_blocks: MAX_BLOCKS_PER_RESPONSE,
+            max_kernels: MAX_KERNELS_PER_RESPONSE,
+            max_utxos: MAX_UTXOS_PER_RESPONSE,
+            max_block_filters: MAX_BLOCK_FILTERS_PER_RESPONSE,
+        }
+    }
+}
 
 /// Events that can be published on the Validated Block Event Stream
 #[derive(Debug, Clone, Display)]
@@ -66,6 +97,8 @@ where T: BlockchainBackend + 'static
     mempool: Mempool<T>,
     consensus_manager: ConsensusManager,
     outbound_nci: OutboundNodeCommsInterface,
+    response_limits: ResponseLimits,
+    maintenance_flag: Arc<AtomicBool>,
 }
 
 impl<T> InboundNodeCommsHandlers<T>
@@ -86,9 +119,23 @@ where T: BlockchainBackend + 'static
             mempool,
             consensus_manager,
             outbound_nci,
+            response_limits: ResponseLimits::default(),
+            maintenance_flag: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Set the maximum number of items returned in response to a single request
+    pub fn with_response_limits(mut self, response_limits: ResponseLimits) -> Self {
+        self.response_limits = response_limits;
+        self
+    }
+
+    /// Blocks are still added to the chain but are not propagated to other nodes while the given flag is set
+    pub fn with_maintenance_flag(mut self, maintenance_flag: Arc<AtomicBool>) -> Self {
+        self.maintenance_flag = maintenance_flag;
+        self
+    }
+
     /// Handle inbound node comms requests from remote nodes and local services.
     pub async fn handle_request(&self, request: &NodeCommsRequest) -> Result<NodeCommsResponse, CommsInterfaceError> {
         debug!(target: LOG_TARGET, "Handling remote request: {}", request);
@@ -98,16 +145,25 @@ where T: BlockchainBackend + 'static
             )),
             NodeCommsRequest::FetchKernels(kernel_hashes) => {
                 let mut kernels = Vec::<TransactionKernel>::new();
-                for hash in kernel_hashes {
+                for hash in kernel_hashes.iter().take(self.response_limits.max_kernels) {
                     if let Ok(kernel) = async_db::fetch_kernel(self.blockchain_db.clone(), hash.clone()).await {
                         kernels.push(kernel);
                     }
                 }
                 Ok(NodeCommsResponse::TransactionKernels(kernels))
             },
+            NodeCommsRequest::SearchKernels(signatures) => {
+                let signatures = signatures
+                    .iter()
+                    .take(self.response_limits.max_kernels)
+                    .cloned()
+                    .collect();
+                let results = async_db::search_kernels(self.blockchain_db.clone(), signatures).await?;
+                Ok(NodeCommsResponse::KernelSearchResults(results))
+            },
             NodeCommsRequest::FetchHeaders(block_nums) => {
                 let mut block_headers = Vec::<BlockHeader>::new();
-                for block_num in block_nums {
+                for block_num in block_nums.iter().take(self.response_limits.max_headers) {
                     if let Ok(block_header) = async_db::fetch_header(self.blockchain_db.clone(), *block_num).await {
                         block_headers.push(block_header);
                     }
@@ -116,7 +172,7 @@ where T: BlockchainBackend + 'static
             },
             NodeCommsRequest::FetchHeadersWithHashes(block_hashes) => {
                 let mut block_headers = Vec::<BlockHeader>::new();
-                for block_hash in block_hashes {
+                for block_hash in block_hashes.iter().take(self.response_limits.max_headers) {
                     if let Ok(block_header) =
                         async_db::fetch_header_with_block_hash(self.blockchain_db.clone(), block_hash.clone()).await
                     {
@@ -138,7 +194,7 @@ where T: BlockchainBackend + 'static
                     }
                 }
                 let mut headers = vec![];
-                for i in 1..MAX_HEADERS_PER_RESPONSE {
+                for i in 1..=self.response_limits.max_headers {
                     if let Ok(header) =
                         async_db::fetch_header(self.blockchain_db.clone(), starting_block.height + i as u64).await
                     {
@@ -154,7 +210,7 @@ where T: BlockchainBackend + 'static
             },
             NodeCommsRequest::FetchUtxos(utxo_hashes) => {
                 let mut utxos = Vec::<TransactionOutput>::new();
-                for hash in utxo_hashes {
+                for hash in utxo_hashes.iter().take(self.response_limits.max_utxos) {
                     if let Ok(utxo) = async_db::fetch_utxo(self.blockchain_db.clone(), hash.clone()).await {
                         utxos.push(utxo);
                     }
@@ -162,8 +218,9 @@ where T: BlockchainBackend + 'static
                 Ok(NodeCommsResponse::TransactionOutputs(utxos))
             },
             NodeCommsRequest::FetchBlocks(block_nums) => {
-                let mut blocks = Vec::<HistoricalBlock>::with_capacity(block_nums.len());
-                for block_num in block_nums {
+                let max_blocks = self.response_limits.max_blocks;
+                let mut blocks = Vec::<HistoricalBlock>::with_capacity(block_nums.len().min(max_blocks));
+                for block_num in block_nums.iter().take(max_blocks) {
                     debug!(target: LOG_TARGET, "A peer has requested block {}", block_num);
                     match async_db::fetch_block(self.blockchain_db.clone(), *block_num).await {
                         Ok(block) => blocks.push(block),
@@ -178,8 +235,9 @@ where T: BlockchainBackend + 'static
                 Ok(NodeCommsResponse::HistoricalBlocks(blocks))
             },
             NodeCommsRequest::FetchBlocksWithHashes(block_hashes) => {
-                let mut blocks = Vec::<HistoricalBlock>::with_capacity(block_hashes.len());
-                for block_hash in block_hashes {
+                let max_blocks = self.response_limits.max_blocks;
+                let mut blocks = Vec::<HistoricalBlock>::with_capacity(block_hashes.len().min(max_blocks));
+                for block_hash in block_hashes.iter().take(max_blocks) {
                     debug!(
                         target: LOG_TARGET,
                         "A peer has requested a block with hash {}",
@@ -202,6 +260,20 @@ where T: BlockchainBackend + 'static
                 }
                 Ok(NodeCommsResponse::HistoricalBlocks(blocks))
             },
+            NodeCommsRequest::FetchBlockFilters(block_nums) => {
+                let max_filters = self.response_limits.max_block_filters;
+                let mut filters = Vec::with_capacity(block_nums.len().min(max_filters));
+                for block_num in block_nums.iter().take(max_filters) {
+                    match async_db::fetch_block_filter(self.blockchain_db.clone(), *block_num).await {
+                        Ok(filter) => filters.push(filter),
+                        Err(e) => debug!(
+                            target: LOG_TARGET,
+                            "Could not provide requested block filter #{} to peer because: {}", block_num, e
+                        ),
+                    }
+                }
+                Ok(NodeCommsResponse::BlockFilters(filters))
+            },
             NodeCommsRequest::GetNewBlockTemplate => {
                 let metadata = async_db::get_metadata(self.blockchain_db.clone()).await?;
                 let best_block_hash = metadata
@@ -216,7 +288,7 @@ where T: BlockchainBackend + 'static
                     self.mempool.clone(),
                     self.consensus_manager
                         .consensus_constants()
-                        .get_max_block_transaction_weight(),
+                        .get_max_block_transaction_weight(header.height),
                 )
                 .await
                 .map_err(|e| CommsInterfaceError::MempoolError(e.to_string()))?
@@ -230,6 +302,13 @@ where T: BlockchainBackend + 'static
                 Ok(NodeCommsResponse::NewBlockTemplate(block_template))
             },
             NodeCommsRequest::GetNewBlock(block_template) => {
+                let max_weight = self
+                    .consensus_manager
+                    .consensus_constants()
+                    .get_max_block_transaction_weight(block_template.header.height);
+                if block_template.body.calculate_weight() > max_weight {
+                    return Err(CommsInterfaceError::BlockTemplateTooLarge);
+                }
                 let block = async_db::calculate_mmr_roots(self.blockchain_db.clone(), block_template.clone()).await?;
                 Ok(NodeCommsResponse::NewBlock(block))
             },
@@ -285,7 +364,13 @@ where T: BlockchainBackend + 'static
                 BlockAddResult::OrphanBlock => false,
                 BlockAddResult::ChainReorg(_) => true,
             };
-            if propagate {
+            if propagate && self.maintenance_flag.load(Ordering::SeqCst) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Block ({}) not propagated, the node is in maintenance mode.",
+                    block.hash().to_hex()
+                );
+            } else if propagate {
                 debug!(
                     target: LOG_TARGET,
                     "Propagate block ({}) to network.",
@@ -310,6 +395,8 @@ where T: BlockchainBackend + 'static
             mempool: self.mempool.clone(),
             consensus_manager: self.consensus_manager.clone(),
             outbound_nci: self.outbound_nci.clone(),
+            response_limits: self.response_limits,
+            maintenance_flag: self.maintenance_flag.clone(),
         }
     }
 }
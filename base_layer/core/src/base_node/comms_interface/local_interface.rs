@@ -21,10 +21,17 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    base_node::comms_interface::{error::CommsInterfaceError, BlockEvent, NodeCommsRequest, NodeCommsResponse},
+    base_node::{
+        comms_interface::{error::CommsInterfaceError, BlockEvent, NodeCommsRequest, NodeCommsResponse},
+        BlockFilter,
+    },
     blocks::{Block, BlockHeader, NewBlockTemplate},
     chain_storage::{ChainMetadata, HistoricalBlock},
     proof_of_work::{Difficulty, PowAlgorithm},
+    transactions::{
+        transaction::TransactionKernel,
+        types::{HashOutput, Signature},
+    },
 };
 use futures::{stream::Fuse, StreamExt};
 use tari_broadcast_channel::Subscriber;
@@ -95,6 +102,57 @@ impl LocalNodeCommsInterface {
         }
     }
 
+    /// Request the main chain headers that follow the first of the given header hashes which is in the main chain, up
+    /// to and including the header with the stopping hash. Headers are returned from the genesis block if none of the
+    /// hashes are in the main chain.
+    pub async fn get_headers_after(
+        &mut self,
+        header_hashes: Vec<HashOutput>,
+        stopping_hash: HashOutput,
+    ) -> Result<Vec<BlockHeader>, CommsInterfaceError>
+    {
+        match self
+            .request_sender
+            .call(NodeCommsRequest::FetchHeadersAfter(header_hashes, stopping_hash))
+            .await??
+        {
+            NodeCommsResponse::FetchHeadersAfterResponse(headers) => Ok(headers),
+            _ => Err(CommsInterfaceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Request the compact block filters of the blocks at the given heights. Heights without a filter are skipped.
+    pub async fn get_block_filters(
+        &mut self,
+        block_heights: Vec<u64>,
+    ) -> Result<Vec<BlockFilter>, CommsInterfaceError> {
+        match self
+            .request_sender
+            .call(NodeCommsRequest::FetchBlockFilters(block_heights))
+            .await??
+        {
+            NodeCommsResponse::BlockFilters(filters) => Ok(filters),
+            _ => Err(CommsInterfaceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Find the main chain kernels with the given excess signatures, e.g. to verify the payment proofs of a batch of
+    /// payments. Each kernel that is found is returned with the header of the block that contains it.
+    pub async fn search_kernels(
+        &mut self,
+        signatures: Vec<Signature>,
+    ) -> Result<Vec<(TransactionKernel, BlockHeader)>, CommsInterfaceError>
+    {
+        match self
+            .request_sender
+            .call(NodeCommsRequest::SearchKernels(signatures))
+            .await??
+        {
+            NodeCommsResponse::KernelSearchResults(results) => Ok(results),
+            _ => Err(CommsInterfaceError::UnexpectedApiResponse),
+        }
+    }
+
     /// Request the construction of a new mineable block template from the base node service.
     pub async fn get_new_block_template(&mut self) -> Result<NewBlockTemplate, CommsInterfaceError> {
         match self
@@ -21,12 +21,15 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    base_node::comms_interface::{error::CommsInterfaceError, NodeCommsRequest, NodeCommsResponse},
+    base_node::{
+        comms_interface::{error::CommsInterfaceError, NodeCommsRequest, NodeCommsResponse},
+        BlockFilter,
+    },
     blocks::{blockheader::BlockHeader, Block},
     chain_storage::{ChainMetadata, HistoricalBlock},
     transactions::{
         transaction::{TransactionKernel, TransactionOutput},
-        types::HashOutput,
+        types::{HashOutput, Signature},
     },
 };
 use futures::channel::mpsc::UnboundedSender;
@@ -113,6 +116,59 @@ impl OutboundNodeCommsInterface {
         }
     }
 
+    /// Search remote base nodes for the main chain kernels with the provided excess signatures. Each kernel that is
+    /// found is returned with the header of the block that contains it.
+    pub async fn search_kernels(
+        &mut self,
+        signatures: Vec<Signature>,
+    ) -> Result<Vec<(TransactionKernel, BlockHeader)>, CommsInterfaceError>
+    {
+        self.search_kernels_on_peer(signatures, None).await
+    }
+
+    /// Search a specific base node for the main chain kernels with the provided excess signatures, if None is
+    /// provided as a node_id then a random base node will be queried.
+    pub async fn search_kernels_on_peer(
+        &mut self,
+        signatures: Vec<Signature>,
+        node_id: Option<NodeId>,
+    ) -> Result<Vec<(TransactionKernel, BlockHeader)>, CommsInterfaceError>
+    {
+        if let NodeCommsResponse::KernelSearchResults(results) = self
+            .request_sender
+            .call((NodeCommsRequest::SearchKernels(signatures), node_id))
+            .await??
+        {
+            Ok(results)
+        } else {
+            Err(CommsInterfaceError::UnexpectedApiResponse)
+        }
+    }
+
+    /// Fetch the compact block filters of the blocks at the provided heights from remote base nodes.
+    pub async fn fetch_block_filters(&mut self, block_nums: Vec<u64>) -> Result<Vec<BlockFilter>, CommsInterfaceError> {
+        self.request_block_filters_from_peer(block_nums, None).await
+    }
+
+    /// Fetch the compact block filters of the blocks at the provided heights from a specific base node, if None is
+    /// provided as a node_id then a random base node will be queried.
+    pub async fn request_block_filters_from_peer(
+        &mut self,
+        block_nums: Vec<u64>,
+        node_id: Option<NodeId>,
+    ) -> Result<Vec<BlockFilter>, CommsInterfaceError>
+    {
+        if let NodeCommsResponse::BlockFilters(filters) = self
+            .request_sender
+            .call((NodeCommsRequest::FetchBlockFilters(block_nums), node_id))
+            .await??
+        {
+            Ok(filters)
+        } else {
+            Err(CommsInterfaceError::UnexpectedApiResponse)
+        }
+    }
+
     /// Fetch the block headers corresponding to the provided block numbers from remote base nodes.
     pub async fn fetch_headers(&mut self, block_nums: Vec<u64>) -> Result<Vec<BlockHeader>, CommsInterfaceError> {
         self.request_headers_from_peer(block_nums, None).await
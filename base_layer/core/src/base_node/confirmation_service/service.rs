@@ -0,0 +1,458 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{config::ConfirmationServiceConfig, error::ConfirmationServiceError, LOG_TARGET};
+use crate::{
+    base_node::{
+        comms_interface::{BlockEvent, LocalNodeCommsInterface},
+        proto::base_node::{confirmation_notification::Watched, ConfirmationNotification, ConfirmationWatchRequest},
+    },
+    blocks::{Block, BlockHash},
+    chain_storage::BlockAddResult,
+};
+use futures::{pin_mut, stream::StreamExt, Stream};
+use log::*;
+use rand::rngs::OsRng;
+use std::{cmp, collections::HashSet, sync::Arc};
+use tari_common::log_if_error;
+use tari_comms::{peer_manager::NodeIdentity, types::CommsPublicKey, utils::signature};
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester},
+};
+use tari_crypto::tari_utilities::{message_format::MessageFormat, Hashable};
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+
+/// A kernel or output which a peer is waiting to see confirmed
+struct Watch {
+    peer: CommsPublicKey,
+    key: Vec<u8>,
+    watched: Watched,
+    required_confirmations: u64,
+    /// The hash and height of the block which contains the watched item, if it has been mined
+    mined_in: Option<(BlockHash, u64)>,
+}
+
+impl Watch {
+    fn confirmations_at(&self, tip_height: u64) -> Option<u64> {
+        self.mined_in
+            .as_ref()
+            .filter(|(_, height)| *height <= tip_height)
+            .map(|(_, height)| tip_height - height + 1)
+    }
+}
+
+pub(super) struct ConfirmationService<SWatch> {
+    config: ConfirmationServiceConfig,
+    node_identity: Arc<NodeIdentity>,
+    outbound_message_service: OutboundMessageRequester,
+    base_node: LocalNodeCommsInterface,
+    watch_request_stream: Option<SWatch>,
+    watches: Vec<Watch>,
+}
+
+impl<SWatch> ConfirmationService<SWatch>
+where SWatch: Stream<Item = DomainMessage<ConfirmationWatchRequest>>
+{
+    pub fn new(
+        config: ConfirmationServiceConfig,
+        node_identity: Arc<NodeIdentity>,
+        outbound_message_service: OutboundMessageRequester,
+        base_node: LocalNodeCommsInterface,
+        watch_request_stream: SWatch,
+    ) -> Self
+    {
+        Self {
+            config,
+            node_identity,
+            outbound_message_service,
+            base_node,
+            watch_request_stream: Some(watch_request_stream),
+            watches: Vec::new(),
+        }
+    }
+
+    /// Run the service
+    pub async fn run(mut self) {
+        let watch_request_stream = self
+            .watch_request_stream
+            .take()
+            .expect("ConfirmationService initialized without watch_request_stream")
+            .fuse();
+        pin_mut!(watch_request_stream);
+        let mut base_node_event_stream = self.base_node.get_block_event_stream_fused();
+
+        loop {
+            futures::select! {
+                msg = watch_request_stream.select_next_some() => {
+                    let (origin_public_key, request) = msg.into_origin_and_inner();
+                    log_if_error!(
+                        level: debug,
+                        target: LOG_TARGET,
+                        "Failed to handle confirmation watch request because '{:?}'",
+                        self.handle_watch_request(origin_public_key, request)
+                    );
+                },
+
+                event = base_node_event_stream.select_next_some() => {
+                    for (peer, notification) in self.handle_block_event(&event) {
+                        log_if_error!(
+                            level: warn,
+                            target: LOG_TARGET,
+                            "Failed to send confirmation notification because '{:?}'",
+                            self.send_notification(peer, notification).await
+                        );
+                    }
+                },
+
+                complete => {
+                    info!(target: LOG_TARGET, "ConfirmationService is exiting because all tasks have completed");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_watch_request(
+        &mut self,
+        peer: CommsPublicKey,
+        request: ConfirmationWatchRequest,
+    ) -> Result<(), ConfirmationServiceError>
+    {
+        let required_confirmations = cmp::min(
+            cmp::max(request.required_confirmations, 1),
+            self.config.max_required_confirmations,
+        );
+        let watched_items = request
+            .excess_sigs
+            .into_iter()
+            .map(Watched::ExcessSig)
+            .chain(request.commitments.into_iter().map(Watched::Commitment));
+
+        for watched in watched_items {
+            let key = watched.to_bytes();
+            if key.is_empty() {
+                continue;
+            }
+            // Repeated requests update the existing watch
+            if let Some(watch) = self.watches.iter_mut().find(|w| w.peer == peer && w.key == key) {
+                watch.required_confirmations = required_confirmations;
+                continue;
+            }
+            if self.watches.iter().filter(|w| w.peer == peer).count() >= self.config.max_watches_per_peer {
+                return Err(ConfirmationServiceError::MaxWatchesExceeded);
+            }
+            trace!(target: LOG_TARGET, "Peer '{}' is watching for '{:?}'", peer, watched);
+            self.watches.push(Watch {
+                peer: peer.clone(),
+                key,
+                watched,
+                required_confirmations,
+                mined_in: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Updates the watches for the new chain tip and returns the (unsigned) notifications which should be sent to the
+    /// watching peers
+    fn handle_block_event(&mut self, event: &BlockEvent) -> Vec<(CommsPublicKey, ConfirmationNotification)> {
+        if self.watches.is_empty() {
+            return Vec::new();
+        }
+        match event {
+            BlockEvent::Verified((block, BlockAddResult::Ok)) => {
+                self.apply_block(block);
+                self.update_confirmations(block.header.height)
+            },
+            BlockEvent::Verified((_, BlockAddResult::ChainReorg((removed, added)))) => {
+                let removed_hashes = removed.iter().map(Hashable::hash).collect::<HashSet<_>>();
+                let mut unmined = Vec::new();
+                for (i, watch) in self.watches.iter_mut().enumerate() {
+                    let is_removed = watch
+                        .mined_in
+                        .as_ref()
+                        .map(|(hash, _)| removed_hashes.contains(hash))
+                        .unwrap_or(false);
+                    if is_removed {
+                        unmined.push((i, watch.mined_in.take()));
+                    }
+                }
+                added.iter().for_each(|block| self.apply_block(block));
+                // Watches which were not mined again in the new chain are reported with zero confirmations, so that the
+                // peer knows that the item is no longer in the longest chain
+                let mut notifications = unmined
+                    .into_iter()
+                    .filter_map(|(i, mined_in)| {
+                        let watch = &self.watches[i];
+                        if watch.mined_in.is_some() {
+                            return None;
+                        }
+                        let (block_hash, height) = mined_in?;
+                        let notification = ConfirmationNotification {
+                            watched: Some(watch.watched.clone()),
+                            block_hash,
+                            height,
+                            confirmations: 0,
+                            signature: Vec::new(),
+                        };
+                        Some((watch.peer.clone(), notification))
+                    })
+                    .collect::<Vec<_>>();
+                if let Some(tip_height) = added.iter().map(|block| block.header.height).max() {
+                    notifications.extend(self.update_confirmations(tip_height));
+                }
+                notifications
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Marks the unmined watches which are included in the block as mined
+    fn apply_block(&mut self, block: &Block) {
+        let keys = block
+            .body
+            .kernels()
+            .iter()
+            .map(|kernel| Watched::ExcessSig(kernel.excess_sig.clone().into()).to_bytes())
+            .chain(
+                block
+                    .body
+                    .outputs()
+                    .iter()
+                    .map(|output| Watched::Commitment(output.commitment.clone().into()).to_bytes()),
+            )
+            .collect::<HashSet<_>>();
+        let hash = block.hash();
+        for watch in self.watches.iter_mut().filter(|w| w.mined_in.is_none()) {
+            if keys.contains(&watch.key) {
+                watch.mined_in = Some((hash.clone(), block.header.height));
+            }
+        }
+    }
+
+    /// Creates a notification for every mined watch and drops the watches which have reached their required
+    /// confirmations
+    fn update_confirmations(&mut self, tip_height: u64) -> Vec<(CommsPublicKey, ConfirmationNotification)> {
+        let notifications = self
+            .watches
+            .iter()
+            .filter_map(|watch| {
+                let confirmations = watch.confirmations_at(tip_height)?;
+                let (block_hash, height) = watch.mined_in.clone()?;
+                let notification = ConfirmationNotification {
+                    watched: Some(watch.watched.clone()),
+                    block_hash,
+                    height,
+                    confirmations,
+                    signature: Vec::new(),
+                };
+                Some((watch.peer.clone(), notification))
+            })
+            .collect();
+
+        self.watches.retain(|watch| {
+            watch
+                .confirmations_at(tip_height)
+                .map(|confirmations| confirmations < watch.required_confirmations)
+                .unwrap_or(true)
+        });
+
+        notifications
+    }
+
+    async fn send_notification(
+        &mut self,
+        peer: CommsPublicKey,
+        mut notification: ConfirmationNotification,
+    ) -> Result<(), ConfirmationServiceError>
+    {
+        let signature = signature::sign(
+            &mut OsRng,
+            self.node_identity.secret_key().clone(),
+            notification.signature_body(),
+        )?;
+        notification.signature = signature.to_binary()?;
+        self.outbound_message_service
+            .send_direct(
+                peer,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::ConfirmationNotification, notification),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        blocks::{BlockBuilder, BlockHeader},
+        transactions::{helpers::create_test_kernel, tari_amount::MicroTari},
+    };
+    use futures::{channel::mpsc, stream};
+    use tari_broadcast_channel as broadcast_channel;
+    use tari_comms::peer_manager::PeerFeatures;
+    use tari_crypto::keys::PublicKey;
+    use tari_service_framework::reply_channel;
+    use tari_test_utils::unpack_enum;
+
+    type TestService = ConfirmationService<stream::Empty<DomainMessage<ConfirmationWatchRequest>>>;
+
+    fn create_service(config: ConfirmationServiceConfig) -> TestService {
+        let node_identity = Arc::new(
+            NodeIdentity::random(
+                &mut OsRng,
+                "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+                PeerFeatures::COMMUNICATION_NODE,
+            )
+            .unwrap(),
+        );
+        let (outbound_tx, _) = mpsc::channel(1);
+        let (base_node_sender, _) = reply_channel::unbounded();
+        let (block_sender, _) = reply_channel::unbounded();
+        let (_, subscriber) = broadcast_channel::bounded(1);
+        let base_node = LocalNodeCommsInterface::new(base_node_sender, block_sender, subscriber);
+        ConfirmationService::new(
+            config,
+            node_identity,
+            OutboundMessageRequester::new(outbound_tx),
+            base_node,
+            stream::empty(),
+        )
+    }
+
+    fn create_block(height: u64) -> Block {
+        let mut header = BlockHeader::new(0);
+        header.height = height;
+        BlockBuilder::new(0)
+            .with_header(header)
+            .add_kernels(vec![create_test_kernel(MicroTari(100), height)])
+            .build()
+    }
+
+    fn watch_request(block: &Block, required_confirmations: u64) -> ConfirmationWatchRequest {
+        ConfirmationWatchRequest {
+            excess_sigs: vec![block.body.kernels()[0].excess_sig.clone().into()],
+            commitments: Vec::new(),
+            required_confirmations,
+        }
+    }
+
+    fn block_added(block: &Block) -> BlockEvent {
+        BlockEvent::Verified((Box::new(block.clone()), BlockAddResult::Ok))
+    }
+
+    #[test]
+    fn notify_until_confirmed() {
+        let mut service = create_service(ConfirmationServiceConfig::default());
+        let (_, peer) = CommsPublicKey::random_keypair(&mut OsRng);
+        let block1 = create_block(1);
+        service
+            .handle_watch_request(peer.clone(), watch_request(&block1, 2))
+            .unwrap();
+
+        let notifications = service.handle_block_event(&block_added(&create_block(0)));
+        assert!(notifications.is_empty());
+
+        let notifications = service.handle_block_event(&block_added(&block1));
+        assert_eq!(notifications.len(), 1);
+        let (notified_peer, notification) = &notifications[0];
+        assert_eq!(notified_peer, &peer);
+        assert_eq!(notification.block_hash, block1.hash());
+        assert_eq!(notification.height, 1);
+        assert_eq!(notification.confirmations, 1);
+
+        let notifications = service.handle_block_event(&block_added(&create_block(2)));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].1.confirmations, 2);
+        // The required confirmations have been reached
+        assert!(service.watches.is_empty());
+        let notifications = service.handle_block_event(&block_added(&create_block(3)));
+        assert!(notifications.is_empty());
+    }
+
+    #[test]
+    fn reorg_unmines_watch() {
+        let mut service = create_service(ConfirmationServiceConfig::default());
+        let (_, peer) = CommsPublicKey::random_keypair(&mut OsRng);
+        let block1 = create_block(1);
+        service
+            .handle_watch_request(peer.clone(), watch_request(&block1, 3))
+            .unwrap();
+        service.handle_block_event(&block_added(&block1));
+
+        let mut fork_header = BlockHeader::new(0);
+        fork_header.height = 1;
+        fork_header.nonce = 1;
+        let fork_block = BlockBuilder::new(0).with_header(fork_header).build();
+        let event = BlockEvent::Verified((
+            Box::new(fork_block.clone()),
+            BlockAddResult::ChainReorg((Box::new(vec![block1.clone()]), Box::new(vec![fork_block]))),
+        ));
+        let notifications = service.handle_block_event(&event);
+        assert_eq!(notifications.len(), 1);
+        let (notified_peer, notification) = &notifications[0];
+        assert_eq!(notified_peer, &peer);
+        assert_eq!(notification.block_hash, block1.hash());
+        assert_eq!(notification.confirmations, 0);
+        assert!(service.watches[0].mined_in.is_none());
+
+        // The kernel is mined again in a later block
+        let mut header = BlockHeader::new(0);
+        header.height = 2;
+        let block2 = BlockBuilder::new(0)
+            .with_header(header)
+            .add_kernels(block1.body.kernels().clone())
+            .build();
+        let notifications = service.handle_block_event(&block_added(&block2));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].1.block_hash, block2.hash());
+        assert_eq!(notifications[0].1.confirmations, 1);
+    }
+
+    #[test]
+    fn max_watches_per_peer() {
+        let mut service = create_service(ConfirmationServiceConfig {
+            max_watches_per_peer: 1,
+            ..Default::default()
+        });
+        let (_, peer) = CommsPublicKey::random_keypair(&mut OsRng);
+        let block = create_block(1);
+        service
+            .handle_watch_request(peer.clone(), watch_request(&block, 1))
+            .unwrap();
+        // Watching the same item again does not count towards the limit
+        service
+            .handle_watch_request(peer.clone(), watch_request(&block, 5))
+            .unwrap();
+        assert_eq!(service.watches.len(), 1);
+        assert_eq!(service.watches[0].required_confirmations, 5);
+
+        let err = service
+            .handle_watch_request(peer, watch_request(&create_block(2), 1))
+            .unwrap_err();
+        unpack_enum!(ConfirmationServiceError::MaxWatchesExceeded = err);
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{config::HeaderSubscriptionServiceConfig, service::HeaderSubscriptionService, LOG_TARGET};
+use crate::base_node::{comms_interface::LocalNodeCommsInterface, proto::base_node::HeaderSubscriptionRequest};
+use futures::{future, future::select, pin_mut, Stream, StreamExt};
+use log::*;
+use std::{future::Future, sync::Arc};
+use tari_comms_dht::outbound::OutboundMessageRequester;
+use tari_p2p::{
+    comms_connector::PeerMessage,
+    domain_message::DomainMessage,
+    services::utils::{map_decode, ok_or_skip_result},
+    tari_message::TariMessageType,
+};
+use tari_pubsub::TopicSubscriptionFactory;
+use tari_service_framework::{handles::ServiceHandlesFuture, ServiceInitializationError, ServiceInitializer};
+use tari_shutdown::ShutdownSignal;
+use tokio::runtime;
+
+pub struct HeaderSubscriptionServiceInitializer {
+    config: HeaderSubscriptionServiceConfig,
+    inbound_message_subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+}
+
+impl HeaderSubscriptionServiceInitializer {
+    pub fn new(
+        config: HeaderSubscriptionServiceConfig,
+        inbound_message_subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+    ) -> Self
+    {
+        Self {
+            config,
+            inbound_message_subscription_factory,
+        }
+    }
+
+    /// Get a stream for inbound header subscription requests
+    fn subscription_request_stream(&self) -> impl Stream<Item = DomainMessage<HeaderSubscriptionRequest>> {
+        self.inbound_message_subscription_factory
+            .get_subscription(TariMessageType::HeaderSubscriptionRequest)
+            .map(map_decode::<HeaderSubscriptionRequest>)
+            .filter_map(ok_or_skip_result)
+    }
+}
+
+impl ServiceInitializer for HeaderSubscriptionServiceInitializer {
+    type Future = impl Future<Output = Result<(), ServiceInitializationError>>;
+
+    fn initialize(
+        &mut self,
+        executor: runtime::Handle,
+        handles_fut: ServiceHandlesFuture,
+        shutdown: ShutdownSignal,
+    ) -> Self::Future
+    {
+        let subscription_request_stream = self.subscription_request_stream();
+        let config = self.config.clone();
+
+        executor.spawn(async move {
+            let handles = handles_fut.await;
+
+            let outbound_message_service = handles
+                .wait_for_handle::<OutboundMessageRequester>()
+                .await
+                .expect("OutboundMessageRequester handle required for HeaderSubscriptionService");
+
+            let base_node = handles
+                .wait_for_handle::<LocalNodeCommsInterface>()
+                .await
+                .expect("LocalNodeCommsInterface required to initialize HeaderSubscriptionService");
+
+            let service_run = HeaderSubscriptionService::new(
+                config,
+                outbound_message_service,
+                base_node,
+                subscription_request_stream,
+            )
+            .run();
+            pin_mut!(service_run);
+            select(service_run, shutdown).await;
+            info!(target: LOG_TARGET, "HeaderSubscriptionService has shut down");
+        });
+
+        future::ready(Ok(()))
+    }
+}
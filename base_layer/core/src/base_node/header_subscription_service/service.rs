@@ -0,0 +1,329 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{config::HeaderSubscriptionServiceConfig, error::HeaderSubscriptionServiceError, LOG_TARGET};
+use crate::{
+    base_node::{
+        comms_interface::{BlockEvent, LocalNodeCommsInterface},
+        proto::base_node::{HeaderChainUpdate, HeaderSubscriptionRequest},
+    },
+    chain_storage::BlockAddResult,
+};
+use futures::{pin_mut, stream::StreamExt, Stream};
+use log::*;
+use std::{collections::HashMap, time::Instant};
+use tari_common::log_if_error;
+use tari_comms::types::CommsPublicKey;
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester},
+};
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+
+pub(super) struct HeaderSubscriptionService<SRequest> {
+    config: HeaderSubscriptionServiceConfig,
+    outbound_message_service: OutboundMessageRequester,
+    base_node: LocalNodeCommsInterface,
+    subscription_request_stream: Option<SRequest>,
+    /// The subscribed peers and the time at which their subscription expires
+    subscriptions: HashMap<CommsPublicKey, Instant>,
+}
+
+impl<SRequest> HeaderSubscriptionService<SRequest>
+where SRequest: Stream<Item = DomainMessage<HeaderSubscriptionRequest>>
+{
+    pub fn new(
+        config: HeaderSubscriptionServiceConfig,
+        outbound_message_service: OutboundMessageRequester,
+        base_node: LocalNodeCommsInterface,
+        subscription_request_stream: SRequest,
+    ) -> Self
+    {
+        Self {
+            config,
+            outbound_message_service,
+            base_node,
+            subscription_request_stream: Some(subscription_request_stream),
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    /// Run the service
+    pub async fn run(mut self) {
+        let subscription_request_stream = self
+            .subscription_request_stream
+            .take()
+            .expect("HeaderSubscriptionService initialized without subscription_request_stream")
+            .fuse();
+        pin_mut!(subscription_request_stream);
+        let mut base_node_event_stream = self.base_node.get_block_event_stream_fused();
+
+        loop {
+            futures::select! {
+                msg = subscription_request_stream.select_next_some() => {
+                    let (origin_public_key, request) = msg.into_origin_and_inner();
+                    log_if_error!(
+                        level: debug,
+                        target: LOG_TARGET,
+                        "Failed to handle header subscription request because '{:?}'",
+                        self.handle_subscription_request(origin_public_key, request).await
+                    );
+                },
+
+                event = base_node_event_stream.select_next_some() => {
+                    if let Some(update) = self.handle_block_event(&event) {
+                        let peers = self.subscriptions.keys().cloned().collect::<Vec<_>>();
+                        for peer in peers {
+                            log_if_error!(
+                                level: warn,
+                                target: LOG_TARGET,
+                                "Failed to send header chain update because '{:?}'",
+                                self.send_update(peer, update.clone()).await
+                            );
+                        }
+                    }
+                },
+
+                complete => {
+                    info!(target: LOG_TARGET, "HeaderSubscriptionService is exiting because all tasks have completed");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Subscribes the peer and sends it the headers which follow its last known header
+    async fn handle_subscription_request(
+        &mut self,
+        peer: CommsPublicKey,
+        request: HeaderSubscriptionRequest,
+    ) -> Result<(), HeaderSubscriptionServiceError>
+    {
+        if !self.subscribe(peer.clone(), &request)? {
+            return Ok(());
+        }
+
+        let headers = self
+            .base_node
+            .get_headers_after(request.last_known_hashes, Vec::new())
+            .await?;
+        let (anchor_hash, anchor_height, last_height) = match (headers.first(), headers.last()) {
+            (Some(first), Some(last)) => (first.prev_hash.clone(), first.height.saturating_sub(1), last.height),
+            // The peer already has the tip
+            _ => return Ok(()),
+        };
+        let tip_height = self
+            .base_node
+            .get_metadata()
+            .await?
+            .height_of_longest_chain
+            .unwrap_or_default();
+        let mut update = HeaderChainUpdate::from_headers(anchor_hash, anchor_height, headers);
+        update.has_more = last_height < tip_height;
+        self.send_update(peer, update).await
+    }
+
+    /// Adds or renews the subscription of the peer, or cancels it if the request contains no hashes. Returns true if
+    /// the peer is subscribed.
+    fn subscribe(
+        &mut self,
+        peer: CommsPublicKey,
+        request: &HeaderSubscriptionRequest,
+    ) -> Result<bool, HeaderSubscriptionServiceError>
+    {
+        if request.last_known_hashes.is_empty() {
+            trace!(target: LOG_TARGET, "Peer '{}' cancelled its header subscription", peer);
+            self.subscriptions.remove(&peer);
+            return Ok(false);
+        }
+        self.remove_expired_subscriptions();
+        if !self.subscriptions.contains_key(&peer) && self.subscriptions.len() >= self.config.max_subscriptions {
+            return Err(HeaderSubscriptionServiceError::MaxSubscriptionsExceeded);
+        }
+        trace!(target: LOG_TARGET, "Peer '{}' subscribed to headers", peer);
+        self.subscriptions
+            .insert(peer, Instant::now() + self.config.subscription_expiry);
+        Ok(true)
+    }
+
+    fn remove_expired_subscriptions(&mut self) {
+        let now = Instant::now();
+        self.subscriptions.retain(|_, expires_at| *expires_at > now);
+    }
+
+    /// Returns the update which should be sent to the subscribed peers for the block event, if any
+    fn handle_block_event(&mut self, event: &BlockEvent) -> Option<HeaderChainUpdate> {
+        self.remove_expired_subscriptions();
+        if self.subscriptions.is_empty() {
+            return None;
+        }
+        match event {
+            BlockEvent::Verified((block, BlockAddResult::Ok)) => Some(HeaderChainUpdate::from_headers(
+                block.header.prev_hash.clone(),
+                block.header.height.saturating_sub(1),
+                vec![block.header.clone()],
+            )),
+            BlockEvent::Verified((_, BlockAddResult::ChainReorg((_, added)))) => {
+                let mut headers = added.iter().map(|block| block.header.clone()).collect::<Vec<_>>();
+                headers.sort_by_key(|header| header.height);
+                // The new headers are anchored at the fork point, so that peers roll back the removed headers
+                let (anchor_hash, anchor_height) = headers
+                    .first()
+                    .map(|header| (header.prev_hash.clone(), header.height.saturating_sub(1)))?;
+                let mut update = HeaderChainUpdate::from_headers(anchor_hash, anchor_height, headers);
+                update.is_reorg = true;
+                Some(update)
+            },
+            _ => None,
+        }
+    }
+
+    async fn send_update(
+        &mut self,
+        peer: CommsPublicKey,
+        update: HeaderChainUpdate,
+    ) -> Result<(), HeaderSubscriptionServiceError>
+    {
+        // Headers are public, so they are not encrypted to keep the updates small
+        self.outbound_message_service
+            .send_direct(
+                peer,
+                OutboundEncryption::None,
+                OutboundDomainMessage::new(TariMessageType::HeaderChainUpdate, update),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::blocks::{Block, BlockBuilder, BlockHeader};
+    use futures::{channel::mpsc, stream};
+    use rand::rngs::OsRng;
+    use std::time::Duration;
+    use tari_broadcast_channel as broadcast_channel;
+    use tari_crypto::{keys::PublicKey, tari_utilities::Hashable};
+    use tari_service_framework::reply_channel;
+    use tari_test_utils::unpack_enum;
+
+    type TestService = HeaderSubscriptionService<stream::Empty<DomainMessage<HeaderSubscriptionRequest>>>;
+
+    fn create_service(config: HeaderSubscriptionServiceConfig) -> TestService {
+        let (outbound_tx, _) = mpsc::channel(1);
+        let (base_node_sender, _) = reply_channel::unbounded();
+        let (block_sender, _) = reply_channel::unbounded();
+        let (_, subscriber) = broadcast_channel::bounded(1);
+        let base_node = LocalNodeCommsInterface::new(base_node_sender, block_sender, subscriber);
+        HeaderSubscriptionService::new(
+            config,
+            OutboundMessageRequester::new(outbound_tx),
+            base_node,
+            stream::empty(),
+        )
+    }
+
+    fn create_block(prev: Option<&Block>, nonce: u64) -> Block {
+        let mut header = match prev {
+            Some(prev) => BlockHeader::from_previous(&prev.header),
+            None => BlockHeader::new(0),
+        };
+        header.nonce = nonce;
+        BlockBuilder::new(0).with_header(header).build()
+    }
+
+    fn subscription_request() -> HeaderSubscriptionRequest {
+        HeaderSubscriptionRequest {
+            last_known_hashes: vec![vec![0; 32]],
+        }
+    }
+
+    #[test]
+    fn new_block_update() {
+        let mut service = create_service(HeaderSubscriptionServiceConfig::default());
+        let genesis = create_block(None, 0);
+        let block1 = create_block(Some(&genesis), 0);
+        let event = BlockEvent::Verified((Box::new(block1.clone()), BlockAddResult::Ok));
+        // There are no subscribers
+        assert!(service.handle_block_event(&event).is_none());
+
+        let (_, peer) = CommsPublicKey::random_keypair(&mut OsRng);
+        assert!(service.subscribe(peer, &subscription_request()).unwrap());
+        let update = service.handle_block_event(&event).unwrap();
+        assert!(!update.is_reorg);
+        assert_eq!(update.anchor_hash, genesis.hash());
+        assert_eq!(update.anchor_height, 0);
+        // The derived fields are not sent
+        assert_eq!(update.headers[0].height, 0);
+        assert!(update.headers[0].prev_hash.is_empty());
+        assert_eq!(update.into_headers().unwrap(), vec![block1.header]);
+    }
+
+    #[test]
+    fn reorg_update_is_anchored_at_fork() {
+        let mut service = create_service(HeaderSubscriptionServiceConfig::default());
+        let (_, peer) = CommsPublicKey::random_keypair(&mut OsRng);
+        service.subscribe(peer, &subscription_request()).unwrap();
+
+        let genesis = create_block(None, 0);
+        let block1 = create_block(Some(&genesis), 0);
+        let fork1 = create_block(Some(&genesis), 1);
+        let fork2 = create_block(Some(&fork1), 1);
+        let event = BlockEvent::Verified((
+            Box::new(fork2.clone()),
+            BlockAddResult::ChainReorg((Box::new(vec![block1]), Box::new(vec![fork2.clone(), fork1.clone()]))),
+        ));
+        let update = service.handle_block_event(&event).unwrap();
+        assert!(update.is_reorg);
+        assert_eq!(update.anchor_hash, genesis.hash());
+        assert_eq!(update.anchor_height, 0);
+        assert_eq!(update.into_headers().unwrap(), vec![fork1.header, fork2.header]);
+    }
+
+    #[test]
+    fn subscription_limits() {
+        let mut service = create_service(HeaderSubscriptionServiceConfig {
+            max_subscriptions: 1,
+            ..Default::default()
+        });
+        let (_, peer1) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (_, peer2) = CommsPublicKey::random_keypair(&mut OsRng);
+        assert!(service.subscribe(peer1.clone(), &subscription_request()).unwrap());
+        // Renewing a subscription does not count towards the limit
+        assert!(service.subscribe(peer1.clone(), &subscription_request()).unwrap());
+        let err = service.subscribe(peer2.clone(), &subscription_request()).unwrap_err();
+        unpack_enum!(HeaderSubscriptionServiceError::MaxSubscriptionsExceeded = err);
+
+        // An empty request cancels the subscription
+        let cancel = HeaderSubscriptionRequest::default();
+        assert!(!service.subscribe(peer1, &cancel).unwrap());
+        assert!(service.subscribe(peer2, &subscription_request()).unwrap());
+
+        // Subscriptions which are not renewed in time are dropped
+        service.config.subscription_expiry = Duration::from_secs(0);
+        service.subscribe(peer2, &subscription_request()).unwrap();
+        let event = BlockEvent::Verified((Box::new(create_block(None, 0)), BlockAddResult::Ok));
+        assert!(service.handle_block_event(&event).is_none());
+        assert!(service.subscriptions.is_empty());
+    }
+}
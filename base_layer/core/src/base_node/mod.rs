@@ -32,19 +32,37 @@
 //! More details about the implementation are presented in
 //! [RFC-0111](https://rfc.tari.com/RFC-0111_BaseNodeArchitecture.html).
 
+#[cfg(any(feature = "base_node", feature = "base_node_proto"))]
+pub mod block_filter;
 #[cfg(feature = "base_node")]
 pub mod chain_metadata_service;
 #[cfg(feature = "base_node")]
+pub mod chain_stats_service;
+#[cfg(feature = "base_node")]
 pub mod comms_interface;
 #[cfg(feature = "base_node")]
+pub mod confirmation_service;
+#[cfg(feature = "base_node")]
 pub mod consts;
 #[cfg(feature = "base_node")]
+pub mod header_subscription_service;
+#[cfg(feature = "base_node")]
+pub mod partition_detection_service;
+#[cfg(any(feature = "base_node", feature = "base_node_proto"))]
+pub mod protocol_version;
+#[cfg(feature = "base_node")]
 pub mod service;
 #[cfg(feature = "base_node")]
 mod state_machine;
 #[cfg(feature = "base_node")]
 pub mod states;
+#[cfg(feature = "base_node")]
+pub mod time_sync_service;
+#[cfg(feature = "base_node")]
+pub mod version_handshake_service;
 // Public re-exports
+#[cfg(any(feature = "base_node", feature = "base_node_proto"))]
+pub use block_filter::BlockFilter;
 #[cfg(feature = "base_node")]
 pub use comms_interface::{LocalNodeCommsInterface, OutboundNodeCommsInterface};
 #[cfg(feature = "base_node")]
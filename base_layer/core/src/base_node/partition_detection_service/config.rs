@@ -0,0 +1,55 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct PartitionDetectionConfig {
+    /// The time between checks of the local and network chain tips
+    pub check_interval: Duration,
+    /// The target time between blocks in seconds
+    pub target_block_interval: u64,
+    /// The number of target block intervals that may pass without the local chain tip advancing before the chain is
+    /// considered to have stalled
+    pub max_stalled_intervals: u64,
+    /// The number of blocks that a peer must report ahead of the local chain tip to count as evidence that blocks are
+    /// being produced that this node is not receiving
+    pub min_blocks_ahead: u64,
+}
+
+impl Default for PartitionDetectionConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(60),
+            target_block_interval: 120,
+            max_stalled_intervals: 5,
+            min_blocks_ahead: 2,
+        }
+    }
+}
+
+impl PartitionDetectionConfig {
+    /// The time without the local chain tip advancing after which the chain is considered to have stalled
+    pub fn stall_threshold(&self) -> Duration {
+        Duration::from_secs(self.target_block_interval.saturating_mul(self.max_stalled_intervals))
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::error::PartitionDetectionError;
+use futures::{stream::Fuse, StreamExt};
+use std::{fmt, fmt::Display};
+use tari_broadcast_channel::Subscriber;
+use tari_service_framework::reply_channel::SenderService;
+use tower_service::Service;
+
+/// The evidence for a possible partition
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartitionAlert {
+    /// The height of the local chain tip
+    pub local_height: u64,
+    /// The highest tip height reported by peers
+    pub network_tip_height: u64,
+    /// The number of peers reporting a tip ahead of the local chain tip
+    pub peers_ahead: usize,
+    /// The number of peers reporting chain metadata
+    pub num_peers: usize,
+    /// Seconds since the local chain tip last advanced
+    pub stalled_for: u64,
+}
+
+impl Display for PartitionAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no new blocks for {}s at height {} while {} of {} peer(s) report a tip at height {}",
+            self.stalled_for, self.local_height, self.peers_ahead, self.num_peers, self.network_tip_height
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartitionStatus {
+    /// No check has been completed yet
+    Unknown,
+    /// The local chain tip is advancing, or no peer reports a higher tip
+    Healthy,
+    /// The local chain has stalled while peers report a higher tip
+    PossiblePartition(PartitionAlert),
+}
+
+impl PartitionStatus {
+    pub fn is_healthy(&self) -> bool {
+        match self {
+            PartitionStatus::PossiblePartition(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl Display for PartitionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionStatus::Unknown => write!(f, "Unknown"),
+            PartitionStatus::Healthy => write!(f, "Healthy"),
+            PartitionStatus::PossiblePartition(alert) => {
+                write!(f, "Degraded, possible partition or eclipse: {}", alert)
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartitionEvent {
+    /// The local chain has stalled while peers report a higher tip. This is published once when the condition is first
+    /// detected.
+    PossiblePartition(PartitionAlert),
+    /// The local chain tip is advancing again after a possible partition was detected
+    Recovered,
+}
+
+#[derive(Debug, Clone)]
+pub enum PartitionDetectionRequest {
+    /// Retrieve the result of the last check
+    GetStatus,
+}
+
+#[derive(Debug)]
+pub enum PartitionDetectionResponse {
+    Status(PartitionStatus),
+}
+
+#[derive(Clone)]
+pub struct PartitionDetectionHandle {
+    handle: SenderService<PartitionDetectionRequest, Result<PartitionDetectionResponse, PartitionDetectionError>>,
+    event_stream: Subscriber<PartitionEvent>,
+}
+
+impl PartitionDetectionHandle {
+    pub fn new(
+        handle: SenderService<PartitionDetectionRequest, Result<PartitionDetectionResponse, PartitionDetectionError>>,
+        event_stream: Subscriber<PartitionEvent>,
+    ) -> Self
+    {
+        Self { handle, event_stream }
+    }
+
+    /// Returns the result of the last partition check
+    pub async fn get_status(&mut self) -> Result<PartitionStatus, PartitionDetectionError> {
+        match self.handle.call(PartitionDetectionRequest::GetStatus).await?? {
+            PartitionDetectionResponse::Status(status) => Ok(status),
+        }
+    }
+
+    pub fn get_event_stream(&self) -> Subscriber<PartitionEvent> {
+        self.event_stream.clone()
+    }
+
+    pub fn get_event_stream_fused(&self) -> Fuse<Subscriber<PartitionEvent>> {
+        self.get_event_stream().fuse()
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! The partition detection service watches for signs that this node has been cut off from the rest of the network,
+//! either by a network partition or by an eclipse attack where all of its peers are controlled by an attacker. It
+//! periodically compares how long it has been since the local chain tip last advanced against the target block
+//! interval, and the local tip against the tips reported by neighbouring peers. If the local chain has stalled while
+//! peers claim a higher tip, blocks are evidently being produced but are not reaching this node, and a
+//! [PartitionEvent::PossiblePartition] alert is raised. The alert is cleared once the local tip advances again.
+
+const LOG_TARGET: &str = "c::bn::partition_detection_service";
+
+mod config;
+mod error;
+mod handle;
+mod initializer;
+mod service;
+
+pub use config::PartitionDetectionConfig;
+pub use error::PartitionDetectionError;
+pub use handle::{PartitionAlert, PartitionDetectionHandle, PartitionEvent, PartitionStatus};
+pub use initializer::PartitionDetectionServiceInitializer;
@@ -0,0 +1,281 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{
+    config::PartitionDetectionConfig,
+    error::PartitionDetectionError,
+    handle::{PartitionAlert, PartitionDetectionRequest, PartitionDetectionResponse, PartitionEvent, PartitionStatus},
+    LOG_TARGET,
+};
+use crate::base_node::{
+    chain_metadata_service::{ChainMetadataHandle, NetworkState},
+    comms_interface::LocalNodeCommsInterface,
+};
+use futures::{stream::StreamExt, SinkExt};
+use log::*;
+use std::time::Instant;
+use tari_broadcast_channel::Publisher;
+use tari_service_framework::reply_channel::Receiver;
+use tokio::time;
+
+pub(super) struct PartitionDetectionService {
+    config: PartitionDetectionConfig,
+    base_node: LocalNodeCommsInterface,
+    chain_metadata: ChainMetadataHandle,
+    event_publisher: Publisher<PartitionEvent>,
+    request_stream:
+        Option<Receiver<PartitionDetectionRequest, Result<PartitionDetectionResponse, PartitionDetectionError>>>,
+    detector: PartitionDetector,
+}
+
+impl PartitionDetectionService {
+    pub fn new(
+        config: PartitionDetectionConfig,
+        base_node: LocalNodeCommsInterface,
+        chain_metadata: ChainMetadataHandle,
+        event_publisher: Publisher<PartitionEvent>,
+        request_stream: Receiver<
+            PartitionDetectionRequest,
+            Result<PartitionDetectionResponse, PartitionDetectionError>,
+        >,
+    ) -> Self
+    {
+        let detector = PartitionDetector::new(config.clone());
+        Self {
+            config,
+            base_node,
+            chain_metadata,
+            event_publisher,
+            request_stream: Some(request_stream),
+            detector,
+        }
+    }
+
+    /// Run the service
+    pub async fn run(mut self) {
+        let mut request_stream = self
+            .request_stream
+            .take()
+            .expect("PartitionDetectionService initialized without request_stream")
+            .fuse();
+        let mut check_tick = time::interval(self.config.check_interval).fuse();
+
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(Ok(self.handle_request(request)));
+                },
+
+                _ = check_tick.select_next_some() => {
+                    if let Err(err) = self.check().await {
+                        warn!(target: LOG_TARGET, "Partition check failed because '{:?}'", err);
+                    }
+                },
+
+                complete => {
+                    info!(target: LOG_TARGET, "PartitionDetectionService is exiting because all tasks have completed");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_request(&self, request: PartitionDetectionRequest) -> PartitionDetectionResponse {
+        match request {
+            PartitionDetectionRequest::GetStatus => PartitionDetectionResponse::Status(self.detector.status().clone()),
+        }
+    }
+
+    async fn check(&mut self) -> Result<(), PartitionDetectionError> {
+        let local_height = self
+            .base_node
+            .get_metadata()
+            .await?
+            .height_of_longest_chain
+            .unwrap_or(0);
+        let network_state = self.chain_metadata.get_network_state().await?;
+        if let Some(event) = self.detector.check(local_height, &network_state, Instant::now()) {
+            match &event {
+                PartitionEvent::PossiblePartition(alert) => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Possible network partition or eclipse attack: {}. This node may be isolated from the \
+                         honest network.",
+                        alert
+                    );
+                },
+                PartitionEvent::Recovered => {
+                    info!(
+                        target: LOG_TARGET,
+                        "Local chain tip is advancing again at height {}, partition alert cleared", local_height
+                    );
+                },
+            }
+            if self.event_publisher.send(event).await.is_err() {
+                debug!(target: LOG_TARGET, "No subscribers for partition events");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tracks when the local chain tip last advanced and decides whether the node may be partitioned from the network
+pub(super) struct PartitionDetector {
+    config: PartitionDetectionConfig,
+    last_tip: Option<(u64, Instant)>,
+    status: PartitionStatus,
+}
+
+impl PartitionDetector {
+    pub fn new(config: PartitionDetectionConfig) -> Self {
+        Self {
+            config,
+            last_tip: None,
+            status: PartitionStatus::Unknown,
+        }
+    }
+
+    pub fn status(&self) -> &PartitionStatus {
+        &self.status
+    }
+
+    /// Updates the status from the local chain height and the peer view of the network at time `now`, returning an
+    /// event if a possible partition was detected or cleared by this check.
+    pub fn check(&mut self, local_height: u64, network_state: &NetworkState, now: Instant) -> Option<PartitionEvent> {
+        let last_advanced = match self.last_tip {
+            Some((height, at)) if height == local_height => at,
+            _ => {
+                self.last_tip = Some((local_height, now));
+                now
+            },
+        };
+        let stalled_for = now.duration_since(last_advanced);
+
+        let peer_metadata = network_state.peer_metadata();
+        let ahead_threshold = local_height.saturating_add(self.config.min_blocks_ahead);
+        let peer_heights = peer_metadata
+            .iter()
+            .filter_map(|p| p.chain_metadata.height_of_longest_chain)
+            .filter(|height| *height >= ahead_threshold)
+            .collect::<Vec<_>>();
+
+        let status = if stalled_for >= self.config.stall_threshold() && !peer_heights.is_empty() {
+            PartitionStatus::PossiblePartition(PartitionAlert {
+                local_height,
+                network_tip_height: peer_heights.iter().copied().max().unwrap_or(local_height),
+                peers_ahead: peer_heights.len(),
+                num_peers: peer_metadata.len(),
+                stalled_for: stalled_for.as_secs(),
+            })
+        } else {
+            PartitionStatus::Healthy
+        };
+
+        let event = match (&self.status, &status) {
+            (PartitionStatus::PossiblePartition(_), PartitionStatus::Healthy) => Some(PartitionEvent::Recovered),
+            (PartitionStatus::PossiblePartition(_), PartitionStatus::PossiblePartition(_)) => None,
+            (_, PartitionStatus::PossiblePartition(alert)) => Some(PartitionEvent::PossiblePartition(alert.clone())),
+            _ => None,
+        };
+        self.status = status;
+        event
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{base_node::chain_metadata_service::PeerChainMetadata, chain_storage::ChainMetadata};
+    use std::time::Duration;
+    use tari_comms::peer_manager::NodeId;
+
+    fn network_state(heights: &[u64]) -> NetworkState {
+        let mut state = NetworkState::new(1);
+        let peers = heights
+            .iter()
+            .map(|h| PeerChainMetadata::new(NodeId::new(), ChainMetadata::new(*h, vec![*h as u8], 0, (*h).into())))
+            .collect::<Vec<_>>();
+        state.update(&peers);
+        state
+    }
+
+    #[test]
+    fn detects_stalled_chain_with_peers_ahead() {
+        let config = PartitionDetectionConfig {
+            target_block_interval: 60,
+            max_stalled_intervals: 2,
+            min_blocks_ahead: 2,
+            ..Default::default()
+        };
+        let mut detector = PartitionDetector::new(config);
+        let start = Instant::now();
+        let peers_ahead = network_state(&[10, 15, 16]);
+
+        assert_eq!(detector.check(10, &peers_ahead, start), None);
+        assert_eq!(detector.status(), &PartitionStatus::Healthy);
+        // Still within the stall threshold
+        assert_eq!(detector.check(10, &peers_ahead, start + Duration::from_secs(119)), None);
+
+        let event = detector.check(10, &peers_ahead, start + Duration::from_secs(120));
+        let alert = PartitionAlert {
+            local_height: 10,
+            network_tip_height: 16,
+            peers_ahead: 2,
+            num_peers: 3,
+            stalled_for: 120,
+        };
+        assert_eq!(event, Some(PartitionEvent::PossiblePartition(alert.clone())));
+        assert_eq!(detector.status(), &PartitionStatus::PossiblePartition(alert));
+        assert!(!detector.status().is_healthy());
+        // The alert is only raised once
+        assert_eq!(detector.check(10, &peers_ahead, start + Duration::from_secs(180)), None);
+
+        let event = detector.check(11, &peers_ahead, start + Duration::from_secs(240));
+        assert_eq!(event, Some(PartitionEvent::Recovered));
+        assert!(detector.status().is_healthy());
+    }
+
+    #[test]
+    fn stalled_network_is_not_a_partition() {
+        let config = PartitionDetectionConfig {
+            target_block_interval: 60,
+            max_stalled_intervals: 2,
+            ..Default::default()
+        };
+        let mut detector = PartitionDetector::new(config);
+        let start = Instant::now();
+
+        detector.check(10, &network_state(&[10, 11]), start);
+        // Peers are not far enough ahead, so the whole network may simply not be producing blocks
+        assert_eq!(
+            detector.check(10, &network_state(&[10, 11]), start + Duration::from_secs(600)),
+            None
+        );
+        // No peers reporting chain metadata
+        assert_eq!(
+            detector.check(10, &network_state(&[]), start + Duration::from_secs(600)),
+            None
+        );
+        assert_eq!(detector.status(), &PartitionStatus::Healthy);
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::base_node::{BlockFilter as ProtoBlockFilter, BlockFilters as ProtoBlockFilters};
+use crate::base_node::BlockFilter;
+use std::iter::FromIterator;
+
+impl From<ProtoBlockFilter> for BlockFilter {
+    fn from(filter: ProtoBlockFilter) -> Self {
+        Self {
+            block_hash: filter.block_hash,
+            height: filter.height,
+            n: filter.n,
+            filter: filter.filter,
+        }
+    }
+}
+
+impl From<BlockFilter> for ProtoBlockFilter {
+    fn from(filter: BlockFilter) -> Self {
+        Self {
+            block_hash: filter.block_hash,
+            height: filter.height,
+            n: filter.n,
+            filter: filter.filter,
+        }
+    }
+}
+
+impl FromIterator<ProtoBlockFilter> for ProtoBlockFilters {
+    fn from_iter<T: IntoIterator<Item = ProtoBlockFilter>>(iter: T) -> Self {
+        Self {
+            filters: iter.into_iter().collect(),
+        }
+    }
+}
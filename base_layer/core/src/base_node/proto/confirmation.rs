@@ -0,0 +1,44 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::base_node::{confirmation_notification::Watched, ConfirmationNotification};
+
+impl Watched {
+    /// Returns the bytes which identify the watched kernel or output
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Watched::ExcessSig(sig) => [sig.public_nonce.as_slice(), sig.signature.as_slice()].concat(),
+            Watched::Commitment(commitment) => commitment.data.clone(),
+        }
+    }
+}
+
+impl ConfirmationNotification {
+    /// The message which is signed by the base node. It commits to every field except the signature.
+    pub fn signature_body(&self) -> Vec<u8> {
+        let mut body = self.watched.as_ref().map(Watched::to_bytes).unwrap_or_default();
+        body.extend_from_slice(&self.block_hash);
+        body.extend_from_slice(&self.height.to_le_bytes());
+        body.extend_from_slice(&self.confirmations.to_le_bytes());
+        body
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{base_node::HeaderChainUpdate, core};
+use crate::blocks::{BlockHash, BlockHeader};
+use std::convert::TryFrom;
+use tari_crypto::tari_utilities::Hashable;
+
+impl HeaderChainUpdate {
+    /// Create an update from consecutive headers which follow the anchor. The header fields that can be derived from
+    /// the anchor and the previous header are cleared.
+    pub fn from_headers(anchor_hash: BlockHash, anchor_height: u64, headers: Vec<BlockHeader>) -> Self {
+        let headers = headers
+            .into_iter()
+            .map(|header| core::BlockHeader {
+                height: 0,
+                prev_hash: Vec::new(),
+                ..core::BlockHeader::from(header)
+            })
+            .collect();
+        Self {
+            anchor_hash,
+            anchor_height,
+            headers,
+            is_reorg: false,
+            has_more: false,
+        }
+    }
+
+    /// Restore the full headers from the anchor. The restored headers link to the anchor by construction, so only
+    /// their proof of work still needs to be validated.
+    pub fn into_headers(self) -> Result<Vec<BlockHeader>, String> {
+        let mut prev_hash = self.anchor_hash;
+        let mut height = self.anchor_height;
+        let mut headers = Vec::with_capacity(self.headers.len());
+        for compact in self.headers {
+            height += 1;
+            let header = BlockHeader::try_from(core::BlockHeader {
+                height,
+                prev_hash,
+                ..compact
+            })?;
+            prev_hash = header.hash();
+            headers.push(header);
+        }
+        Ok(headers)
+    }
+}
@@ -27,8 +27,12 @@ use crate::proto::core;
 // Required for `super::types` used in generated files
 use crate::transactions::proto::types;
 
+mod block_filter;
 #[cfg(feature = "base_node")]
 pub mod chain_metadata;
+mod confirmation;
+#[cfg(feature = "base_node")]
+mod header_subscription;
 #[cfg(feature = "base_node")]
 pub mod mmr_tree;
 #[cfg(feature = "base_node")]
@@ -37,3 +41,12 @@ pub mod request;
 pub mod response;
 #[cfg(feature = "base_node")]
 pub use base_node::{BaseNodeServiceRequest, BaseNodeServiceResponse, ChainMetadata};
+pub use base_node::{
+    BlockFilter,
+    BlockFilters,
+    ConfirmationNotification,
+    ConfirmationWatchRequest,
+    HeaderChainUpdate,
+    HeaderSubscriptionRequest,
+    VersionHandshake,
+};
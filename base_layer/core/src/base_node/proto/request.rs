@@ -25,8 +25,16 @@ use super::base_node::{
     BlockHeights,
     FetchHeadersAfter as ProtoFetchHeadersAfter,
     HashOutputs,
+    Signatures as ProtoSignatures,
+};
+use crate::{
+    base_node::comms_interface as ci,
+    proof_of_work::PowAlgorithm,
+    transactions::{
+        proto::utils::try_convert_all,
+        types::{HashOutput, Signature},
+    },
 };
-use crate::{base_node::comms_interface as ci, proof_of_work::PowAlgorithm, transactions::types::HashOutput};
 use std::convert::{TryFrom, TryInto};
 
 //---------------------------------- BaseNodeRequest --------------------------------------------//
@@ -39,6 +47,10 @@ impl TryInto<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             // Field was not specified
             GetChainMetadata(_) => ci::NodeCommsRequest::GetChainMetadata,
             FetchKernels(hash_outputs) => ci::NodeCommsRequest::FetchKernels(hash_outputs.outputs),
+            SearchKernels(signatures) => {
+                let signatures = try_convert_all(signatures.sigs).map_err(|err| err.to_string())?;
+                ci::NodeCommsRequest::SearchKernels(signatures)
+            },
             FetchHeaders(block_heights) => ci::NodeCommsRequest::FetchHeaders(block_heights.heights),
             FetchHeadersWithHashes(block_hashes) => ci::NodeCommsRequest::FetchHeadersWithHashes(block_hashes.outputs),
             FetchHeadersAfter(request) => {
@@ -47,6 +59,7 @@ impl TryInto<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             FetchUtxos(hash_outputs) => ci::NodeCommsRequest::FetchUtxos(hash_outputs.outputs),
             FetchBlocks(block_heights) => ci::NodeCommsRequest::FetchBlocks(block_heights.heights),
             FetchBlocksWithHashes(block_hashes) => ci::NodeCommsRequest::FetchBlocksWithHashes(block_hashes.outputs),
+            FetchBlockFilters(block_heights) => ci::NodeCommsRequest::FetchBlockFilters(block_heights.heights),
             GetNewBlockTemplate(_) => ci::NodeCommsRequest::GetNewBlockTemplate,
             GetNewBlock(block_template) => ci::NodeCommsRequest::GetNewBlock(block_template.try_into()?),
             GetTargetDifficulty(pow_algo) => {
@@ -63,6 +76,7 @@ impl From<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
         match request {
             GetChainMetadata => ProtoNodeCommsRequest::GetChainMetadata(true),
             FetchKernels(hash_outputs) => ProtoNodeCommsRequest::FetchKernels(hash_outputs.into()),
+            SearchKernels(signatures) => ProtoNodeCommsRequest::SearchKernels(signatures.into()),
             FetchHeaders(block_heights) => ProtoNodeCommsRequest::FetchHeaders(block_heights.into()),
             FetchHeadersWithHashes(block_hashes) => ProtoNodeCommsRequest::FetchHeadersWithHashes(block_hashes.into()),
             FetchHeadersAfter(hashes, stopping_hash) => {
@@ -71,6 +85,7 @@ impl From<ci::NodeCommsRequest> for ProtoNodeCommsRequest {
             FetchUtxos(hash_outputs) => ProtoNodeCommsRequest::FetchUtxos(hash_outputs.into()),
             FetchBlocks(block_heights) => ProtoNodeCommsRequest::FetchBlocks(block_heights.into()),
             FetchBlocksWithHashes(block_hashes) => ProtoNodeCommsRequest::FetchBlocksWithHashes(block_hashes.into()),
+            FetchBlockFilters(block_heights) => ProtoNodeCommsRequest::FetchBlockFilters(block_heights.into()),
             GetNewBlockTemplate => ProtoNodeCommsRequest::GetNewBlockTemplate(true),
             GetNewBlock(block_template) => ProtoNodeCommsRequest::GetNewBlock(block_template.into()),
             GetTargetDifficulty(pow_algo) => ProtoNodeCommsRequest::GetTargetDifficulty(pow_algo as u64),
@@ -86,6 +101,14 @@ impl From<Vec<HashOutput>> for HashOutputs {
     }
 }
 
+impl From<Vec<Signature>> for ProtoSignatures {
+    fn from(signatures: Vec<Signature>) -> Self {
+        Self {
+            sigs: signatures.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 impl From<Vec<u64>> for BlockHeights {
     fn from(heights: Vec<u64>) -> Self {
         Self { heights }
@@ -24,17 +24,23 @@ pub use super::base_node::base_node_service_response::Response as ProtoNodeComms
 use super::base_node::{
     BlockHeaders as ProtoBlockHeaders,
     HistoricalBlocks as ProtoHistoricalBlocks,
+    KernelSearchResult as ProtoKernelSearchResult,
+    KernelSearchResults as ProtoKernelSearchResults,
     TransactionKernels as ProtoTransactionKernels,
     TransactionOutputs as ProtoTransactionOutputs,
 };
 use crate::{
     base_node::comms_interface as ci,
+    blocks::BlockHeader,
     proof_of_work::Difficulty,
     proto::core as core_proto_types,
-    transactions::proto::{types as transactions_proto, utils::try_convert_all},
+    transactions::{
+        proto::{types as transactions_proto, utils::try_convert_all},
+        transaction::TransactionKernel,
+    },
 };
 use std::{
-    convert::TryInto,
+    convert::{TryFrom, TryInto},
     iter::{FromIterator, Iterator},
 };
 
@@ -49,6 +55,10 @@ impl TryInto<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
                 let kernels = try_convert_all(kernels.kernels)?;
                 ci::NodeCommsResponse::TransactionKernels(kernels)
             },
+            KernelSearchResults(results) => {
+                let results = try_convert_all(results.results)?;
+                ci::NodeCommsResponse::KernelSearchResults(results)
+            },
             BlockHeaders(headers) => {
                 let headers = try_convert_all(headers.headers)?;
                 ci::NodeCommsResponse::BlockHeaders(headers)
@@ -65,6 +75,10 @@ impl TryInto<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
                 let blocks = try_convert_all(blocks.blocks)?;
                 ci::NodeCommsResponse::HistoricalBlocks(blocks)
             },
+            BlockFilters(filters) => {
+                let filters = filters.filters.into_iter().map(Into::into).collect();
+                ci::NodeCommsResponse::BlockFilters(filters)
+            },
             NewBlockTemplate(block_template) => ci::NodeCommsResponse::NewBlockTemplate(block_template.try_into()?),
             NewBlock(block) => ci::NodeCommsResponse::NewBlock(block.try_into()?),
             TargetDifficulty(difficulty) => ci::NodeCommsResponse::TargetDifficulty(Difficulty::from(difficulty)),
@@ -83,6 +97,10 @@ impl From<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
                 let kernels = kernels.into_iter().map(Into::into).collect();
                 ProtoNodeCommsResponse::TransactionKernels(kernels)
             },
+            KernelSearchResults(results) => {
+                let results = results.into_iter().map(Into::into).collect();
+                ProtoNodeCommsResponse::KernelSearchResults(results)
+            },
             BlockHeaders(headers) => {
                 let block_headers = headers.into_iter().map(Into::into).collect();
                 ProtoNodeCommsResponse::BlockHeaders(block_headers)
@@ -99,6 +117,10 @@ impl From<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
                 let historical_blocks = historical_blocks.into_iter().map(Into::into).collect();
                 ProtoNodeCommsResponse::HistoricalBlocks(historical_blocks)
             },
+            BlockFilters(filters) => {
+                let filters = filters.into_iter().map(Into::into).collect();
+                ProtoNodeCommsResponse::BlockFilters(filters)
+            },
             NewBlockTemplate(block_template) => ProtoNodeCommsResponse::NewBlockTemplate(block_template.into()),
             NewBlock(block) => ProtoNodeCommsResponse::NewBlock(block.into()),
             TargetDifficulty(difficulty) => ProtoNodeCommsResponse::TargetDifficulty(difficulty.as_u64()),
@@ -106,6 +128,33 @@ impl From<ci::NodeCommsResponse> for ProtoNodeCommsResponse {
     }
 }
 
+//---------------------------------- KernelSearchResult --------------------------------------------//
+
+impl TryFrom<ProtoKernelSearchResult> for (TransactionKernel, BlockHeader) {
+    type Error = String;
+
+    fn try_from(result: ProtoKernelSearchResult) -> Result<Self, Self::Error> {
+        let kernel = result
+            .kernel
+            .ok_or_else(|| "Kernel search result is missing the kernel".to_string())?
+            .try_into()?;
+        let header = result
+            .header
+            .ok_or_else(|| "Kernel search result is missing the block header".to_string())?
+            .try_into()?;
+        Ok((kernel, header))
+    }
+}
+
+impl From<(TransactionKernel, BlockHeader)> for ProtoKernelSearchResult {
+    fn from((kernel, header): (TransactionKernel, BlockHeader)) -> Self {
+        Self {
+            kernel: Some(kernel.into()),
+            header: Some(header.into()),
+        }
+    }
+}
+
 //---------------------------------- Collection impls --------------------------------------------//
 
 // The following allow `Iterator::collect` to collect into these repeated types
@@ -118,6 +167,14 @@ impl FromIterator<transactions_proto::TransactionKernel> for ProtoTransactionKer
     }
 }
 
+impl FromIterator<ProtoKernelSearchResult> for ProtoKernelSearchResults {
+    fn from_iter<T: IntoIterator<Item = ProtoKernelSearchResult>>(iter: T) -> Self {
+        Self {
+            results: iter.into_iter().collect(),
+        }
+    }
+}
+
 impl FromIterator<core_proto_types::BlockHeader> for ProtoBlockHeaders {
     fn from_iter<T: IntoIterator<Item = core_proto_types::BlockHeader>>(iter: T) -> Self {
         Self {
@@ -0,0 +1,218 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+//! Versions and feature bits exchanged by wallets and base nodes in a version handshake.
+//!
+//! When a wallet starts using a base node it sends its version and the protocol features it supports in a
+//! `VersionHandshake` request and the base node answers with its own. Either side refuses a counterpart that is older
+//! than [MIN_COMPATIBLE_VERSION]. Otherwise the capabilities that both sides support are used, so that a newer wallet
+//! can fall back to older behaviour, e.g. polling for mined transactions instead of waiting for confirmation
+//! notifications, when its base node does not support a feature.
+
+use crate::base_node::proto::base_node::VersionHandshake;
+use bitflags::bitflags;
+use derive_error::Error;
+use std::{fmt, str::FromStr};
+
+/// The oldest version of a wallet or base node that a version handshake will be accepted from
+pub const MIN_COMPATIBLE_VERSION: NodeVersion = NodeVersion::new(0, 0, 10);
+
+bitflags! {
+    /// The wallet and base node protocol features that a node supports
+    pub struct ProtocolFeatures: u64 {
+        const NONE = 0b0000_0000;
+        /// Confirmation notifications are pushed for watched transaction kernels and outputs
+        const CONFIRMATION_NOTIFICATIONS = 0b0000_0001;
+        /// Compact block filters can be requested
+        const BLOCK_FILTERS = 0b0000_0010;
+        /// Transactions can be validated by the mempool without being submitted
+        const TRANSACTION_VALIDATION = 0b0000_0100;
+    }
+}
+
+impl Default for ProtocolFeatures {
+    fn default() -> Self {
+        ProtocolFeatures::NONE
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ProtocolVersionError {
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidVersion(String),
+    /// The version of the peer is older than the minimum compatible version
+    IncompatibleVersion,
+    /// The peer refused the version handshake
+    HandshakeRejected,
+}
+
+/// A semantic version of the form `major.minor.patch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl NodeVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self { major, minor, patch }
+    }
+}
+
+impl FromStr for NodeVersion {
+    type Err = ProtocolVersionError;
+
+    /// Parses a `major.minor.patch` version. Pre-release and build metadata suffixes are ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let core = s.split(|c| c == '-' || c == '+').next().unwrap_or("");
+        let parts = core
+            .split('.')
+            .map(|p| p.parse::<u16>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProtocolVersionError::InvalidVersion(format!("Invalid version '{}'", s)))?;
+        match parts.as_slice() {
+            [major, minor, patch] => Ok(Self::new(*major, *minor, *patch)),
+            _ => Err(ProtocolVersionError::InvalidVersion(format!(
+                "Version '{}' is not of the form major.minor.patch",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The version of a peer and the protocol features supported by both this node and the peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegotiatedCapabilities {
+    pub peer_version: NodeVersion,
+    pub features: ProtocolFeatures,
+}
+
+impl NegotiatedCapabilities {
+    /// Returns true if both sides of the handshake support all of the given features
+    pub fn supports(&self, features: ProtocolFeatures) -> bool {
+        self.features.contains(features)
+    }
+}
+
+impl fmt::Display for NegotiatedCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "version {} with features {:?}", self.peer_version, self.features)
+    }
+}
+
+impl VersionHandshake {
+    /// Create a handshake message which advertises the given version and features
+    pub fn new(request_key: u64, version: NodeVersion, features: ProtocolFeatures) -> Self {
+        Self {
+            request_key,
+            version: version.to_string(),
+            features: features.bits(),
+            rejected: false,
+        }
+    }
+
+    /// Create a handshake response which refuses the handshake with the given request key
+    pub fn rejection(request_key: u64, version: NodeVersion) -> Self {
+        Self {
+            request_key,
+            version: version.to_string(),
+            features: ProtocolFeatures::NONE.bits(),
+            rejected: true,
+        }
+    }
+
+    /// Check the handshake of a peer against this node's features. The peer is refused if it rejected the handshake or
+    /// if its version is older than `min_version`. Feature bits which are unknown to this node are ignored.
+    pub fn negotiate(
+        &self,
+        our_features: ProtocolFeatures,
+        min_version: NodeVersion,
+    ) -> Result<NegotiatedCapabilities, ProtocolVersionError>
+    {
+        if self.rejected {
+            return Err(ProtocolVersionError::HandshakeRejected);
+        }
+        let peer_version = self.version.parse::<NodeVersion>()?;
+        if peer_version < min_version {
+            return Err(ProtocolVersionError::IncompatibleVersion);
+        }
+        Ok(NegotiatedCapabilities {
+            peer_version,
+            features: our_features & ProtocolFeatures::from_bits_truncate(self.features),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_version() {
+        assert_eq!("0.0.10".parse::<NodeVersion>(), Ok(NodeVersion::new(0, 0, 10)));
+        assert_eq!("1.2.3-rc.1".parse::<NodeVersion>(), Ok(NodeVersion::new(1, 2, 3)));
+        assert!("1.2".parse::<NodeVersion>().is_err());
+        assert!("one.two.three".parse::<NodeVersion>().is_err());
+        assert!(NodeVersion::new(0, 1, 0) > NodeVersion::new(0, 0, 10));
+        assert_eq!(NodeVersion::new(1, 2, 3).to_string(), "1.2.3");
+    }
+
+    #[test]
+    fn negotiate() {
+        let ours = ProtocolFeatures::CONFIRMATION_NOTIFICATIONS | ProtocolFeatures::BLOCK_FILTERS;
+        let min_version = NodeVersion::new(0, 1, 0);
+
+        let handshake = VersionHandshake::new(
+            1,
+            NodeVersion::new(0, 2, 0),
+            ProtocolFeatures::CONFIRMATION_NOTIFICATIONS | ProtocolFeatures::TRANSACTION_VALIDATION,
+        );
+        let capabilities = handshake.negotiate(ours, min_version).unwrap();
+        assert_eq!(capabilities.peer_version, NodeVersion::new(0, 2, 0));
+        assert!(capabilities.supports(ProtocolFeatures::CONFIRMATION_NOTIFICATIONS));
+        assert!(!capabilities.supports(ProtocolFeatures::BLOCK_FILTERS));
+        assert!(!capabilities.supports(ProtocolFeatures::TRANSACTION_VALIDATION));
+
+        // Unknown feature bits from newer peers are ignored
+        let mut handshake = VersionHandshake::new(2, NodeVersion::new(0, 3, 0), ours);
+        handshake.features |= 1 << 63;
+        assert_eq!(handshake.negotiate(ours, min_version).unwrap().features, ours);
+
+        let handshake = VersionHandshake::new(3, NodeVersion::new(0, 0, 9), ours);
+        assert_eq!(
+            handshake.negotiate(ours, min_version),
+            Err(ProtocolVersionError::IncompatibleVersion)
+        );
+
+        let handshake = VersionHandshake::rejection(4, NodeVersion::new(0, 2, 0));
+        assert_eq!(
+            handshake.negotiate(ours, min_version),
+            Err(ProtocolVersionError::HandshakeRejected)
+        );
+    }
+}
@@ -34,7 +34,10 @@ use crate::{
 };
 use futures::{channel::mpsc::unbounded as futures_mpsc_channel_unbounded, future, Future, Stream, StreamExt};
 use log::*;
-use std::{convert::TryFrom, sync::Arc};
+use std::{
+    convert::TryFrom,
+    sync::{atomic::AtomicBool, Arc},
+};
 use tari_broadcast_channel::bounded;
 use tari_comms_dht::outbound::OutboundMessageRequester;
 use tari_p2p::{
@@ -64,6 +67,7 @@ where T: BlockchainBackend
     mempool: Mempool<T>,
     consensus_manager: ConsensusManager,
     config: BaseNodeServiceConfig,
+    maintenance_flag: Arc<AtomicBool>,
 }
 
 impl<T> BaseNodeServiceInitializer<T>
@@ -84,9 +88,16 @@ where T: BlockchainBackend
             mempool,
             consensus_manager,
             config,
+            maintenance_flag: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Stop propagating blocks while the given flag is set
+    pub fn with_maintenance_flag(mut self, maintenance_flag: Arc<AtomicBool>) -> Self {
+        self.maintenance_flag = maintenance_flag;
+        self
+    }
+
     /// Get a stream for inbound Base Node request messages
     fn inbound_request_stream(&self) -> impl Stream<Item = DomainMessage<proto::BaseNodeServiceRequest>> {
         self.inbound_message_subscription_factory
@@ -136,6 +147,7 @@ async fn extract_block(msg: Arc<PeerMessage>) -> Option<DomainMessage<Block>> {
             Some(DomainMessage {
                 source_peer: msg.source_peer.clone(),
                 dht_header: msg.dht_header.clone(),
+                reply_block: msg.reply_block.clone(),
                 inner: block,
             })
         },
@@ -177,18 +189,22 @@ where T: BlockchainBackend + 'static
             self.mempool.clone(),
             self.consensus_manager.clone(),
             outbound_nci.clone(),
-        );
+        )
+        .with_response_limits(self.config.response_limits)
+        .with_maintenance_flag(self.maintenance_flag.clone());
         let config = self.config;
 
         // Register handle to OutboundNodeCommsInterface before waiting for handles to be ready
         handles_fut.register(outbound_nci);
         handles_fut.register(local_nci);
+        let ready_signal = handles_fut.ready_signal::<LocalNodeCommsInterface>();
 
         executor.spawn(async move {
             let handles = handles_fut.await;
 
             let outbound_message_service = handles
-                .get_handle::<OutboundMessageRequester>()
+                .wait_for_handle::<OutboundMessageRequester>()
+                .await
                 .expect("OutboundMessageRequester handle required for BaseNodeService");
 
             let streams = BaseNodeStreams::new(
@@ -201,6 +217,7 @@ where T: BlockchainBackend + 'static
                 local_block_stream,
             );
             let service = BaseNodeService::new(outbound_message_service, inbound_nch, config).start(streams);
+            ready_signal.set_ready();
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
             info!(target: LOG_TARGET, "Base Node Service shutdown");
@@ -22,7 +22,13 @@
 
 use crate::{
     base_node::{
-        comms_interface::{CommsInterfaceError, InboundNodeCommsHandlers, NodeCommsRequest, NodeCommsResponse},
+        comms_interface::{
+            CommsInterfaceError,
+            InboundNodeCommsHandlers,
+            NodeCommsRequest,
+            NodeCommsResponse,
+            ResponseLimits,
+        },
         consts::{BASE_NODE_SERVICE_DESIRED_RESPONSE_FRACTION, BASE_NODE_SERVICE_REQUEST_TIMEOUT},
         generate_request_key,
         proto,
@@ -51,7 +57,7 @@ use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
 use tari_comms_dht::{
     domain_message::OutboundDomainMessage,
     envelope::NodeDestination,
-    outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageParams},
+    outbound::{DhtMessagePriority, OutboundEncryption, OutboundMessageRequester, SendMessageParams},
 };
 use tari_crypto::ristretto::RistrettoPublicKey;
 use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
@@ -67,6 +73,8 @@ pub struct BaseNodeServiceConfig {
     pub request_timeout: Duration,
     /// The fraction of responses that need to be received for a corresponding service request to be finalize.
     pub desired_response_fraction: f32,
+    /// The maximum number of items returned in response to a single request from a remote node
+    pub response_limits: ResponseLimits,
 }
 
 impl Default for BaseNodeServiceConfig {
@@ -74,6 +82,7 @@ impl Default for BaseNodeServiceConfig {
         Self {
             request_timeout: BASE_NODE_SERVICE_REQUEST_TIMEOUT,
             desired_response_fraction: BASE_NODE_SERVICE_DESIRED_RESPONSE_FRACTION,
+            response_limits: ResponseLimits::default(),
         }
     }
 }
@@ -450,7 +459,10 @@ async fn handle_outbound_request(
 
     let mut send_msg_params = SendMessageParams::new();
     match node_id {
-        Some(node_id) => send_msg_params.direct_node_id(node_id),
+        // Requests to a specific peer (e.g. a sync peer) are waited on, so the peer is dialed with high priority
+        Some(node_id) => send_msg_params
+            .direct_node_id(node_id)
+            .with_priority(DhtMessagePriority::High),
         None => send_msg_params.random(1),
     };
 
@@ -504,10 +516,13 @@ async fn handle_outbound_block(
 ) -> Result<(), CommsInterfaceError>
 {
     outbound_message_service
-        .propagate(
-            NodeDestination::Unknown,
-            OutboundEncryption::EncryptForPeer,
-            exclude_peers,
+        .send_message(
+            SendMessageParams::new()
+                .neighbours(exclude_peers)
+                .with_encryption(OutboundEncryption::EncryptForPeer)
+                .with_destination(NodeDestination::Unknown)
+                .with_priority(DhtMessagePriority::High)
+                .finish(),
             OutboundDomainMessage::new(TariMessageType::NewBlock, ProtoBlock::from(block)),
         )
         .await
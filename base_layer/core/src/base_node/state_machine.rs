@@ -25,13 +25,27 @@ use crate::{
         chain_metadata_service::ChainMetadataEvent,
         comms_interface::OutboundNodeCommsInterface,
         states,
-        states::{BaseNodeState, BlockSyncConfig, StateEvent},
+        states::{
+            BaseNodeState,
+            BlockSyncConfig,
+            HeaderVerifier,
+            StateEvent,
+            SyncPhase,
+            SyncProgressHandle,
+            SyncProgressReporter,
+        },
     },
     chain_storage::{BlockchainBackend, BlockchainDatabase},
 };
 use futures::{future, future::Either, SinkExt};
 use log::*;
-use std::{future::Future, sync::Arc};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use tari_broadcast_channel::{bounded, Publisher, Subscriber};
 use tari_comms::{connection_manager::ConnectionManagerRequester, PeerManager};
 use tari_shutdown::ShutdownSignal;
@@ -66,9 +80,12 @@ pub struct BaseNodeStateMachine<B: BlockchainBackend> {
     pub(super) connection_manager: ConnectionManagerRequester,
     pub(super) metadata_event_stream: Subscriber<ChainMetadataEvent>,
     pub(super) config: BaseNodeStateMachineConfig,
+    pub(super) sync_progress: SyncProgressReporter,
+    pub(super) header_verifier: HeaderVerifier,
     event_sender: Publisher<StateEvent>,
     event_receiver: Subscriber<StateEvent>,
     interrupt_signal: ShutdownSignal,
+    block_sync_paused: Arc<AtomicBool>,
 }
 
 impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
@@ -84,6 +101,10 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
     ) -> Self
     {
         let (event_sender, event_receiver): (Publisher<_>, Subscriber<_>) = bounded(10);
+        let header_verifier = HeaderVerifier::new(
+            config.block_sync_config.header_verification_threads,
+            config.block_sync_config.min_header_difficulty,
+        );
         Self {
             db: db.clone(),
             comms: comms.clone(),
@@ -92,8 +113,11 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
             metadata_event_stream,
             interrupt_signal: shutdown_signal,
             config,
+            sync_progress: SyncProgressReporter::new(),
+            header_verifier,
             event_sender,
             event_receiver,
+            block_sync_paused: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -103,6 +127,13 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
         use crate::base_node::states::{BaseNodeState::*, StateEvent::*, SyncStatus::*};
         match (state, event) {
             (Starting(s), Initialized) => Listening(s.into()),
+            (Listening(s), FallenBehind(Lagging(_, _))) if self.is_block_sync_paused() => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Node has fallen behind the network but block sync is paused"
+                );
+                Listening(s)
+            },
             (BlockSync(s, _, _), BlocksSynchronized) => Listening(s.into()),
             (BlockSync(s, _, _), BlockSyncFailure) => Waiting(s.into()),
             (Listening(_), FallenBehind(Lagging(network_tip, sync_peers))) => {
@@ -128,6 +159,22 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
         self.event_receiver.clone()
     }
 
+    /// Returns the flag used to pause block sync. While it is set, the node does not start a block sync when it falls
+    /// behind the network and any block sync in progress is abandoned before requesting the next batch of blocks.
+    pub fn get_block_sync_pause_flag(&self) -> Arc<AtomicBool> {
+        self.block_sync_paused.clone()
+    }
+
+    /// Returns true if block sync has been paused using the flag returned by `get_block_sync_pause_flag`
+    pub fn is_block_sync_paused(&self) -> bool {
+        self.block_sync_paused.load(Ordering::Relaxed)
+    }
+
+    /// Returns a handle that can be used to query and subscribe to the block sync progress
+    pub fn get_sync_progress_handle(&self) -> SyncProgressHandle {
+        self.sync_progress.handle()
+    }
+
     /// Start the base node runtime.
     pub async fn run(mut self) {
         use crate::base_node::states::BaseNodeState::*;
@@ -148,6 +195,11 @@ impl<B: BlockchainBackend + 'static> BaseNodeStateMachine<B> {
             let next_event = select_next_state_event(interrupt_signal, next_state_future).await;
             // Publish the event on the event bus
             let _ = self.event_sender.send(next_event.clone()).await;
+            match next_event {
+                StateEvent::BlocksSynchronized => self.sync_progress.finish(SyncPhase::Synced).await,
+                StateEvent::BlockSyncFailure => self.sync_progress.finish(SyncPhase::Idle).await,
+                _ => {},
+            }
             debug!(
                 target: LOG_TARGET,
                 "=== Base Node event in State [{}]:  {}", state, next_event
@@ -24,19 +24,23 @@ use crate::{
     base_node::{
         comms_interface::CommsInterfaceError,
         state_machine::BaseNodeStateMachine,
-        states::{ForwardBlockSyncInfo, ListeningInfo, StateEvent},
+        states::{sync_peers::SyncPeers, ForwardBlockSyncInfo, HeaderVerificationError, ListeningInfo, StateEvent},
     },
     blocks::{
         blockheader::{BlockHash, BlockHeader},
         Block,
     },
     chain_storage::{async_db, BlockchainBackend, ChainMetadata, ChainStorageError},
+    proof_of_work::Difficulty,
 };
 use core::cmp::min;
 use derive_error::Error;
 use log::*;
-use rand::seq::SliceRandom;
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tari_common::retry::RetryPolicy;
 use tari_comms::{
     connection_manager::ConnectionManagerError,
     peer_manager::{NodeId, PeerManagerError},
@@ -46,8 +50,10 @@ use tari_crypto::tari_utilities::{hex::Hex, Hashable};
 const LOG_TARGET: &str = "c::bn::states::block_sync";
 
 // If more than one sync peer discovered with the correct chain, enable or disable the selection of a random sync peer
-// to query headers and blocks.
+// to query headers and blocks when the peers have the same score.
 const RANDOM_SYNC_PEER_WITH_CHAIN: bool = true;
+// The number of consecutive requests to a sync peer that can time out before the sync switches to another peer.
+const SYNC_PEER_STALL_LIMIT: usize = 2;
 // The maximum number of retry attempts a node can perform to request a particular block from remote nodes.
 const MAX_METADATA_REQUEST_RETRY_ATTEMPTS: usize = 3;
 const MAX_HEADER_REQUEST_RETRY_ATTEMPTS: usize = 5;
@@ -55,8 +61,14 @@ const MAX_BLOCK_REQUEST_RETRY_ATTEMPTS: usize = 5;
 // The maximum number of retry attempts for attempting to validly request and add the block at a specific block height
 // to the chain.
 const MAX_ADD_BLOCK_RETRY_ATTEMPTS: usize = 3;
+// The delay before the first retry of a failed sync request. The delay doubles for every subsequent retry, up to
+// `MAX_REQUEST_RETRY_DELAY`.
+const INITIAL_REQUEST_RETRY_DELAY: Duration = Duration::from_millis(100);
+const MAX_REQUEST_RETRY_DELAY: Duration = Duration::from_secs(2);
 // The number of headers that can be requested in a single query.
 const HEADER_REQUEST_SIZE: usize = 100;
+// The number of threads used to verify the proof of work of downloaded headers. Zero uses one thread per CPU.
+const HEADER_VERIFICATION_THREADS: usize = 0;
 // The number of blocks that can be requested in a single query.
 const BLOCK_REQUEST_SIZE: usize = 5;
 
@@ -69,8 +81,20 @@ pub struct BlockSyncConfig {
     pub max_header_request_retry_attempts: usize,
     pub max_block_request_retry_attempts: usize,
     pub max_add_block_retry_attempts: usize,
+    /// The backoff between retries of metadata, header and block requests. The maximum number of attempts is set by
+    /// the `max_*_retry_attempts` fields.
+    pub retry_policy: RetryPolicy,
     pub header_request_size: usize,
     pub block_request_size: usize,
+    /// The size of the thread pool used to verify the proof of work of downloaded headers. Zero uses one thread per
+    /// CPU.
+    pub header_verification_threads: usize,
+    /// The number of consecutive requests to a sync peer that can time out before requests are sent to another peer
+    /// instead.
+    pub sync_peer_stall_limit: usize,
+    /// Downloaded headers that achieve less than this difficulty are rejected. This should be set to the minimum
+    /// proof of work difficulty of the network.
+    pub min_header_difficulty: Difficulty,
 }
 
 impl Default for BlockSyncConfig {
@@ -82,8 +106,14 @@ impl Default for BlockSyncConfig {
             max_header_request_retry_attempts: MAX_HEADER_REQUEST_RETRY_ATTEMPTS,
             max_block_request_retry_attempts: MAX_BLOCK_REQUEST_RETRY_ATTEMPTS,
             max_add_block_retry_attempts: MAX_ADD_BLOCK_RETRY_ATTEMPTS,
+            retry_policy: RetryPolicy::exponential(INITIAL_REQUEST_RETRY_DELAY)
+                .with_max_delay(MAX_REQUEST_RETRY_DELAY)
+                .with_jitter(0.25),
             header_request_size: HEADER_REQUEST_SIZE,
             block_request_size: BLOCK_REQUEST_SIZE,
+            header_verification_threads: HEADER_VERIFICATION_THREADS,
+            sync_peer_stall_limit: SYNC_PEER_STALL_LIMIT,
+            min_header_difficulty: Difficulty::min(),
         }
     }
 }
@@ -154,6 +184,7 @@ pub enum BlockSyncError {
     EmptyBlockchain,
     EmptyNetworkBestBlock,
     NoSyncPeers,
+    Paused,
     ChainStorageError(ChainStorageError),
     PeerManagerError(PeerManagerError),
     ConnectionManagerError(ConnectionManagerError),
@@ -172,7 +203,14 @@ impl BestChainMetadataBlockSyncInfo {
     ) -> StateEvent
     {
         info!(target: LOG_TARGET, "Synchronizing missing blocks.");
-        match synchronize_blocks(shared, network_tip, sync_peers).await {
+        let mut peers = SyncPeers::new(
+            sync_peers.clone(),
+            network_tip.height_of_longest_chain.unwrap_or(0),
+            shared.config.block_sync_config.sync_peer_stall_limit,
+        );
+        let result = synchronize_blocks(shared, network_tip, &mut peers).await;
+        *sync_peers = peers.node_ids();
+        match result {
             Ok(()) => {
                 info!(target: LOG_TARGET, "Block sync state has synchronised.");
                 StateEvent::BlocksSynchronized
@@ -210,6 +248,10 @@ impl BestChainMetadataBlockSyncInfo {
                 warn!(target: LOG_TARGET, "An empty network best block hash was received.",);
                 StateEvent::BlockSyncFailure
             },
+            Err(BlockSyncError::Paused) => {
+                info!(target: LOG_TARGET, "Block sync has been paused.");
+                StateEvent::BlockSyncFailure
+            },
             Err(BlockSyncError::CommsInterfaceError(e)) => {
                 warn!(target: LOG_TARGET, "Unable to perform network queries: {}", e);
                 StateEvent::BlockSyncFailure
@@ -222,7 +264,7 @@ impl BestChainMetadataBlockSyncInfo {
 async fn synchronize_blocks<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
     network_metadata: &ChainMetadata,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
 ) -> Result<(), BlockSyncError>
 {
     let local_metadata = shared.db.get_metadata()?;
@@ -247,6 +289,7 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
             {
                 info!(target: LOG_TARGET, "Chain split detected, finding chain split height.");
                 let min_tip_height = min(local_tip_height, network_tip_height);
+                shared.sync_progress.start_chain_split_search(min_tip_height).await;
                 sync_height = find_chain_split_height(shared, sync_peers, min_tip_height).await?;
                 info!(target: LOG_TARGET, "Chain split found at height {}.", sync_height);
             } else {
@@ -258,17 +301,29 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
             }
 
             info!(target: LOG_TARGET, "Synchronize missing blocks.");
+            shared
+                .sync_progress
+                .start_block_download(num_blocks_between(sync_height, network_tip_height))
+                .await;
             let mut height = sync_height;
             while height <= network_tip_height {
+                if shared.is_block_sync_paused() {
+                    return Err(BlockSyncError::Paused);
+                }
                 let max_height = min(
                     height + (shared.config.block_sync_config.block_request_size - 1) as u64,
                     network_tip_height,
                 );
                 let block_nums: Vec<u64> = (height..=max_height).collect();
                 request_and_add_blocks(shared, sync_peers, block_nums.clone()).await?;
+                shared.sync_progress.add_blocks(block_nums.len() as u64).await;
                 if height == network_tip_height {
                     info!(target: LOG_TARGET, "Check if sync peer chain has been extended.");
                     network_tip_height = request_network_tip_height(shared, sync_peers).await?;
+                    shared
+                        .sync_progress
+                        .set_blocks_total(num_blocks_between(sync_height, network_tip_height))
+                        .await;
                 }
                 height += block_nums.len() as u64;
             }
@@ -279,6 +334,11 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
     Err(BlockSyncError::EmptyBlockchain)
 }
 
+// The number of blocks from `from_height` up to and including `to_height`.
+fn num_blocks_between(from_height: u64, to_height: u64) -> u64 {
+    (to_height + 1).saturating_sub(from_height)
+}
+
 // Perform a basic check to determine if a chain split has occurred between the local and network chain. The
 // determine_sync_mode from the listening state would have ensured that when we reach this code that the network tip has
 // a higher accumulated difficulty compared to the local chain. In the case when the network height is lower, but has a
@@ -286,7 +346,7 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
 // at the shared height if the local tip has a lower accumulated difficulty compared to the network tip.
 async fn check_chain_split<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
     local_tip_height: u64,
     network_tip_height: u64,
     local_block_hash: &BlockHash,
@@ -307,7 +367,7 @@ async fn check_chain_split<B: BlockchainBackend + 'static>(
 // not common between the local and network chains.
 async fn find_chain_split_height<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
     tip_height: u64,
 ) -> Result<u64, BlockSyncError>
 {
@@ -317,6 +377,7 @@ async fn find_chain_split_height<B: BlockchainBackend + 'static>(
         .chunks(shared.config.block_sync_config.header_request_size)
     {
         let (headers, sync_peer) = request_headers(shared, sync_peers, block_nums).await?;
+        shared.sync_progress.add_headers(headers.len() as u64).await;
         for header in headers {
             // Check if header is linked to local chain
             if let Ok(prev_header) =
@@ -346,18 +407,27 @@ async fn find_chain_split_height<B: BlockchainBackend + 'static>(
 // Request a block from a remote sync peer and attempt to add it to the local blockchain.
 async fn request_and_add_blocks<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
     mut block_nums: Vec<u64>,
 ) -> Result<(), BlockSyncError>
 {
     let config = shared.config.block_sync_config;
-    for attempt in 0..config.max_add_block_retry_attempts {
+    let mut attempts = config
+        .retry_policy
+        .with_max_attempts(config.max_add_block_retry_attempts)
+        .attempts();
+    while let Some(attempt) = attempts.next().await {
         let (blocks, sync_peer) = request_blocks(shared, sync_peers, block_nums.clone()).await?;
-        for block in blocks {
+        // The received blocks are group-committed, so that the whole batch only needs to be flushed to disk once
+        let results = shared
+            .db
+            .add_blocks(blocks.clone())
+            .map_err(BlockSyncError::ChainStorageError)?;
+        for (block, result) in blocks.iter().zip(results) {
             let block_hash = block.hash();
-            match shared.db.add_block(block.clone()) {
+            match result {
                 Ok(_) => {
-                    info!(
+                    debug!(
                         target: LOG_TARGET,
                         "Block #{} ({}) successfully added to database",
                         block.header.height,
@@ -406,19 +476,25 @@ async fn request_and_add_blocks<B: BlockchainBackend + 'static>(
 // Request a block from a remote sync peer.
 async fn request_blocks<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
     block_nums: Vec<u64>,
 ) -> Result<(Vec<Block>, NodeId), BlockSyncError>
 {
     let config = shared.config.block_sync_config;
-    for attempt in 1..=config.max_block_request_retry_attempts {
-        let sync_peer = select_sync_peer(&config, sync_peers)?;
+    let mut attempts = config
+        .retry_policy
+        .with_max_attempts(config.max_block_request_retry_attempts)
+        .attempts();
+    let max_height = block_nums.last().cloned().unwrap_or(0);
+    while let Some(attempt) = attempts.next().await {
+        let sync_peer = select_sync_peer(&config, sync_peers, max_height)?;
         trace!(
             target: LOG_TARGET,
             "Requesting blocks {:?} from {}.",
             block_nums,
             sync_peer
         );
+        let request_start = Instant::now();
         match shared
             .comms
             .request_blocks_from_peer(block_nums.clone(), Some(sync_peer.clone()))
@@ -428,6 +504,7 @@ async fn request_blocks<B: BlockchainBackend + 'static>(
                 debug!(target: LOG_TARGET, "Received {} blocks from peer", hist_blocks.len());
                 if block_nums.len() == hist_blocks.len() {
                     if (0..block_nums.len()).all(|i| hist_blocks[i].block().header.height == block_nums[i]) {
+                        sync_peers.record_response(&sync_peer, hist_blocks.len(), request_start.elapsed());
                         let blocks: Vec<Block> = hist_blocks
                             .into_iter()
                             .map(|hist_block| hist_block.block().clone())
@@ -466,6 +543,7 @@ async fn request_blocks<B: BlockchainBackend + 'static>(
                     "Failed to fetch blocks from peer: {:?}. Retrying.",
                     CommsInterfaceError::RequestTimedOut,
                 );
+                record_sync_peer_stall(sync_peers, &sync_peer);
             },
             Err(e) => return Err(BlockSyncError::CommsInterfaceError(e)),
         }
@@ -477,7 +555,7 @@ async fn request_blocks<B: BlockchainBackend + 'static>(
 // Request a header from a remote sync peer.
 async fn request_header<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
     height: u64,
 ) -> Result<(BlockHeader, NodeId), BlockSyncError>
 {
@@ -491,13 +569,18 @@ async fn request_header<B: BlockchainBackend + 'static>(
 // Request a set of headers from a remote sync peer.
 async fn request_headers<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
     block_nums: &[u64],
 ) -> Result<(Vec<BlockHeader>, NodeId), BlockSyncError>
 {
     let config = shared.config.block_sync_config;
-    for attempt in 1..=config.max_header_request_retry_attempts {
-        let sync_peer = select_sync_peer(&config, sync_peers)?;
+    let mut attempts = config
+        .retry_policy
+        .with_max_attempts(config.max_header_request_retry_attempts)
+        .attempts();
+    let max_height = block_nums.iter().max().cloned().unwrap_or(0);
+    while let Some(attempt) = attempts.next().await {
+        let sync_peer = select_sync_peer(&config, sync_peers, max_height)?;
         trace!(target: LOG_TARGET, "Requesting headers from {}.", sync_peer);
         match shared
             .comms
@@ -508,7 +591,21 @@ async fn request_headers<B: BlockchainBackend + 'static>(
                 debug!(target: LOG_TARGET, "Received {} headers from peer", headers.len());
                 if block_nums.len() == headers.len() {
                     if (0..block_nums.len()).all(|i| headers[i].height == block_nums[i]) {
-                        return Ok((headers, sync_peer));
+                        match shared.header_verifier.verify(headers).await {
+                            Ok(headers) => return Ok((headers, sync_peer)),
+                            Err(HeaderVerificationError::VerificationCancelled) => {
+                                warn!(target: LOG_TARGET, "Header verification was cancelled. Retrying.");
+                            },
+                            Err(e) => {
+                                warn!(
+                                    target: LOG_TARGET,
+                                    "Banning peer {} from local node, because they supplied invalid headers: {}",
+                                    sync_peer,
+                                    e
+                                );
+                                ban_sync_peer(shared, sync_peers, sync_peer.clone()).await?;
+                            },
+                        }
                     } else {
                         debug!(target: LOG_TARGET, "This was NOT the headers we were expecting.");
                         warn!(
@@ -546,6 +643,7 @@ async fn request_headers<B: BlockchainBackend + 'static>(
                     "Failed to fetch header from peer: {:?}. Retrying.",
                     CommsInterfaceError::RequestTimedOut,
                 );
+                record_sync_peer_stall(sync_peers, &sync_peer);
             },
             Err(e) => return Err(BlockSyncError::CommsInterfaceError(e)),
         }
@@ -557,20 +655,33 @@ async fn request_headers<B: BlockchainBackend + 'static>(
 // Request the updated tip height from a remote sync peer.
 async fn request_network_tip_height<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &[NodeId],
+    sync_peers: &mut SyncPeers,
 ) -> Result<u64, BlockSyncError>
 {
     let config = shared.config.block_sync_config;
-    for attempt in 1..=config.max_metadata_request_retry_attempts {
-        let sync_peer = select_sync_peer(&config, sync_peers)?;
+    let mut attempts = config
+        .retry_policy
+        .with_max_attempts(config.max_metadata_request_retry_attempts)
+        .attempts();
+    while let Some(attempt) = attempts.next().await {
+        let sync_peer = select_sync_peer(&config, sync_peers, 0)?;
         trace!(target: LOG_TARGET, "Requesting updated metadata from {}.", sync_peer);
         match shared.comms.request_metadata_from_peer(Some(sync_peer.clone())).await {
             Ok(metadata) => {
                 debug!(target: LOG_TARGET, "Received updated metadata from peer");
                 if let Some(network_tip_height) = metadata.height_of_longest_chain {
+                    sync_peers.set_tip_height(&sync_peer, network_tip_height);
                     return Ok(network_tip_height);
                 }
             },
+            Err(CommsInterfaceError::RequestTimedOut) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to fetch updated metadata from peer: {:?}. ",
+                    CommsInterfaceError::RequestTimedOut,
+                );
+                record_sync_peer_stall(sync_peers, &sync_peer);
+            },
             Err(e) => {
                 warn!(
                     target: LOG_TARGET,
@@ -586,26 +697,33 @@ async fn request_network_tip_height<B: BlockchainBackend + 'static>(
     Err(BlockSyncError::MaxRequestAttemptsReached)
 }
 
-// Selects the first sync peer or a random peer from the set of sync peers that have the current network tip depending
-// on the selected configuration.
-fn select_sync_peer(config: &BlockSyncConfig, sync_peers: &[NodeId]) -> Result<NodeId, BlockSyncError> {
-    if config.random_sync_peer_with_chain {
-        sync_peers.choose(&mut rand::thread_rng())
-    } else {
-        sync_peers.first()
+// Selects the best scoring sync peer that claims to have the given height. Peers with the same score are chosen between
+// randomly or in order depending on the selected configuration.
+fn select_sync_peer(config: &BlockSyncConfig, sync_peers: &SyncPeers, height: u64) -> Result<NodeId, BlockSyncError> {
+    sync_peers
+        .select(height, config.random_sync_peer_with_chain)
+        .ok_or(BlockSyncError::NoSyncPeers)
+}
+
+// Record a timed out request to the sync peer, so that the following requests switch to another peer once it is
+// stalling. The requested heights are kept, so the sync continues from where it was.
+fn record_sync_peer_stall(sync_peers: &mut SyncPeers, sync_peer: &NodeId) {
+    if sync_peers.record_stall(sync_peer) && sync_peers.len() > 1 {
+        info!(
+            target: LOG_TARGET,
+            "Sync peer {} is stalling, switching to another sync peer.", sync_peer
+        );
     }
-    .map(Clone::clone)
-    .ok_or(BlockSyncError::NoSyncPeers)
 }
 
-// Ban and disconnect the provided sync peer.
+// Blacklist the provided sync peer for the rest of the sync, then ban and disconnect it.
 async fn ban_sync_peer<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
     sync_peer: NodeId,
 ) -> Result<(), BlockSyncError>
 {
-    sync_peers.retain(|p| *p != sync_peer);
+    sync_peers.blacklist(&sync_peer);
     let peer = shared.peer_manager.find_by_node_id(&sync_peer).await?;
     shared.peer_manager.set_banned(&peer.public_key, true).await?;
     shared.connection_manager.disconnect_peer(sync_peer).await??;
@@ -618,12 +736,12 @@ async fn ban_sync_peer<B: BlockchainBackend + 'static>(
 // Ban and disconnect entire set of sync peers.
 async fn ban_all_sync_peers<B: BlockchainBackend + 'static>(
     shared: &mut BaseNodeStateMachine<B>,
-    sync_peers: &mut Vec<NodeId>,
+    sync_peers: &mut SyncPeers,
 ) -> Result<(), BlockSyncError>
 {
-    while !sync_peers.is_empty() {
-        warn!(target: LOG_TARGET, "Banning peer {} from local node.", sync_peers[0]);
-        ban_sync_peer(shared, sync_peers, sync_peers[0].clone()).await?;
+    while let Some(sync_peer) = sync_peers.first() {
+        warn!(target: LOG_TARGET, "Banning peer {} from local node.", sync_peer);
+        ban_sync_peer(shared, sync_peers, sync_peer).await?;
     }
     Ok(())
 }
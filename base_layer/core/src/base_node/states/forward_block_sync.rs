@@ -152,11 +152,24 @@ async fn synchronize_blocks<B: BlockchainBackend + 'static>(
                     continue;
                 }
 
-                // TODO: verify headers POW. Can't do that at present,
-                // so try to add them to the chain
+                let headers = match shared.header_verifier.verify(headers).await {
+                    Ok(headers) => headers,
+                    Err(e) => {
+                        warn!(
+                            target: LOG_TARGET,
+                            "Could not sync with node '{}': Invalid headers: {}", sync_node_string, e
+                        );
+                        sync_node = next_sync_node(&mut sync_nodes);
+                        continue;
+                    },
+                };
                 let mut page = 0;
 
                 while page < headers.len() {
+                    if shared.is_block_sync_paused() {
+                        info!(target: LOG_TARGET, "Block sync has been paused");
+                        return Ok(StateEvent::BlockSyncFailure);
+                    }
                     let curr_headers: Vec<HashOutput> = headers
                         .iter()
                         .skip(page)
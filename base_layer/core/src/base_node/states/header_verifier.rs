@@ -0,0 +1,186 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Verification of the proof of work of headers downloaded during block sync.
+//!
+//! Calculating the achieved difficulty of a header is CPU-bound (for Monero merge mined headers it requires a RandomX
+//! hash), so the headers are hashed in parallel on a dedicated rayon thread pool instead of on the async runtime. The
+//! results are then checked in height order: each header must link to the previous header and achieve at least the
+//! minimum difficulty of the network. Headers are only handed back to the sync process once the whole batch has been
+//! verified.
+
+use crate::{
+    blocks::{blockheader::BlockHash, BlockHeader},
+    proof_of_work::Difficulty,
+};
+use derive_error::Error;
+use futures::channel::oneshot;
+use log::*;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use std::sync::Arc;
+use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+
+const LOG_TARGET: &str = "c::bn::states::header_verifier";
+
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum HeaderVerificationError {
+    // The header at this height does not link to the header before it
+    #[error(no_from, non_std)]
+    InvalidChainLink(u64),
+    // The achieved difficulty of the header at this height is below the minimum difficulty of the network
+    #[error(no_from, non_std)]
+    AchievedDifficultyTooLow(u64),
+    // The verification task stopped before returning a result
+    VerificationCancelled,
+}
+
+/// Verifies the proof of work of batches of headers on a thread pool of a configurable size.
+#[derive(Clone)]
+pub struct HeaderVerifier {
+    pool: Option<Arc<ThreadPool>>,
+    min_difficulty: Difficulty,
+}
+
+impl HeaderVerifier {
+    /// Create a verifier with a thread pool of `num_threads` threads. If `num_threads` is zero, one thread is used for
+    /// every CPU. The global rayon thread pool is used if the thread pool could not be created.
+    pub fn new(num_threads: usize, min_difficulty: Difficulty) -> Self {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|i| format!("header-verifier-{}", i))
+            .build()
+            .map_err(|e| {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not create the header verification thread pool, using the global pool: {}", e
+                );
+            })
+            .ok()
+            .map(Arc::new);
+        Self { pool, min_difficulty }
+    }
+
+    /// Verify the proof of work of the headers and that consecutive headers are linked. The headers can be in any
+    /// order and are returned in the same order once they have all been verified.
+    pub async fn verify(&self, headers: Vec<BlockHeader>) -> Result<Vec<BlockHeader>, HeaderVerificationError> {
+        let (tx, rx) = oneshot::channel();
+        let task = move || {
+            let hashes = headers
+                .par_iter()
+                .map(|header| (header.hash(), header.achieved_difficulty()))
+                .collect::<Vec<_>>();
+            let _ = tx.send((headers, hashes));
+        };
+        match &self.pool {
+            Some(pool) => pool.spawn(task),
+            None => rayon::spawn(task),
+        }
+        let (headers, hashes) = rx.await.map_err(|_| HeaderVerificationError::VerificationCancelled)?;
+        check_in_height_order(&headers, &hashes, self.min_difficulty)?;
+        Ok(headers)
+    }
+}
+
+// The ordered stage of the verification, run once the hashes and achieved difficulties of all the headers are known
+fn check_in_height_order(
+    headers: &[BlockHeader],
+    hashes: &[(BlockHash, Difficulty)],
+    min_difficulty: Difficulty,
+) -> Result<(), HeaderVerificationError>
+{
+    let mut order = (0..headers.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| headers[i].height);
+    let mut prev: Option<usize> = None;
+    for i in order {
+        let header = &headers[i];
+        let (hash, achieved) = &hashes[i];
+        // The genesis block is not mined, so it has no minimum difficulty
+        if header.height > 0 && *achieved < min_difficulty {
+            warn!(
+                target: LOG_TARGET,
+                "Header {} at height {} achieved difficulty {}, below the minimum of {}",
+                hash.to_hex(),
+                header.height,
+                achieved,
+                min_difficulty
+            );
+            return Err(HeaderVerificationError::AchievedDifficultyTooLow(header.height));
+        }
+        if let Some(p) = prev {
+            if headers[p].height + 1 == header.height && hashes[p].0 != header.prev_hash {
+                warn!(
+                    target: LOG_TARGET,
+                    "Header {} at height {} does not link to the previous header {}",
+                    hash.to_hex(),
+                    header.height,
+                    hashes[p].0.to_hex()
+                );
+                return Err(HeaderVerificationError::InvalidChainLink(header.height));
+            }
+        }
+        prev = Some(i);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn chain_of_headers(len: u64) -> Vec<BlockHeader> {
+        let mut headers = vec![BlockHeader::new(0)];
+        for _ in 1..len {
+            let header = BlockHeader::from_previous(headers.last().unwrap());
+            headers.push(header);
+        }
+        headers
+    }
+
+    #[test]
+    fn verify_linked_headers() {
+        let verifier = HeaderVerifier::new(2, 1.into());
+        let headers = chain_of_headers(10);
+        assert_eq!(block_on(verifier.verify(headers.clone())), Ok(headers.clone()));
+        // The order of the headers is kept
+        let mut reversed = headers;
+        reversed.reverse();
+        assert_eq!(block_on(verifier.verify(reversed.clone())), Ok(reversed));
+    }
+
+    #[test]
+    fn reject_invalid_headers() {
+        let verifier = HeaderVerifier::new(2, 1.into());
+        let mut headers = chain_of_headers(10);
+        headers[5].prev_hash = vec![0; 32];
+        assert_eq!(
+            block_on(verifier.verify(headers)),
+            Err(HeaderVerificationError::InvalidChainLink(5))
+        );
+
+        let verifier = HeaderVerifier::new(2, Difficulty::from(u64::max_value()));
+        assert_eq!(
+            block_on(verifier.verify(chain_of_headers(3))),
+            Err(HeaderVerificationError::AchievedDifficultyTooLow(1))
+        );
+    }
+}
@@ -22,12 +22,11 @@
 
 use crate::{
     base_node::{
-        chain_metadata_service::{ChainMetadataEvent, PeerChainMetadata},
+        chain_metadata_service::{best_chain_metadata, ChainMetadataEvent, PeerChainMetadata},
         states::{StateEvent, StateEvent::FatalError, SyncStatus},
         BaseNodeStateMachine,
     },
     chain_storage::{BlockchainBackend, ChainMetadata},
-    proof_of_work::Difficulty,
 };
 use futures::stream::StreamExt;
 use log::*;
@@ -56,7 +55,8 @@ impl ListeningInfo {
                                 return FatalError(msg);
                             },
                         };
-                        // Find the best network metadata and set of sync peers with the best tip.
+                        // Find the best network metadata and set of sync peers with the best tip. The metadata list is
+                        // the aggregated network state, so it includes peers that missed the latest ping round.
                         let best_metadata = best_metadata(peer_metadata_list.as_slice());
                         let sync_peers = find_sync_peers(&best_metadata, &peer_metadata_list);
                         if let SyncStatus::Lagging(network_tip, sync_peers) =
@@ -90,19 +90,7 @@ fn find_sync_peers(best_metadata: &ChainMetadata, peer_metadata_list: &Vec<PeerC
 
 /// Determine the best metadata from a set of metadata received from the network.
 fn best_metadata(metadata_list: &[PeerChainMetadata]) -> ChainMetadata {
-    // TODO: Use heuristics to weed out outliers / dishonest nodes.
-    metadata_list.iter().fold(ChainMetadata::default(), |best, current| {
-        if current
-            .chain_metadata
-            .accumulated_difficulty
-            .unwrap_or_else(Difficulty::min) >=
-            best.accumulated_difficulty.unwrap_or_else(|| 0.into())
-        {
-            current.chain_metadata.clone()
-        } else {
-            best
-        }
-    })
+    best_chain_metadata(metadata_list.iter().map(|peer| &peer.chain_metadata)).unwrap_or_default()
 }
 
 /// Given a local and the network chain state respectively, figure out what synchronisation state we should be in.
@@ -42,8 +42,15 @@
 //! downloaded headers, this is performed in a ascending order from the lowest height to the highest block height until
 //! the tip is reached.
 //!
+//! Headers and blocks are requested from the sync peer with the best score, based on the chain tip it claims and the
+//! throughput measured so far. If a peer stops responding, the remaining heights of the batch are requested from the
+//! next best peer. Peers that serve invalid data are blacklisted for the rest of the sync.
+//!
 //! After we have caught up on the chain, switch to `Listening`.
 //!
+//! The progress of the sync (the current phase, headers and blocks downloaded, the block rate and estimated time
+//! remaining) is available from a [SyncProgressHandle].
+//!
 //! If errors occur, re-request the problematic header or block.
 //!
 //! Give up after n failures and switch back to `Listening` (if a peer gave an erroneous chain tip and cannot provide
@@ -60,15 +67,21 @@ mod block_sync;
 mod error;
 mod events_and_states;
 mod forward_block_sync;
+mod header_verifier;
 mod listening;
 mod shutdown_state;
 mod starting_state;
+mod sync_peers;
+mod sync_progress;
 mod waiting;
 
 pub use block_sync::{BestChainMetadataBlockSyncInfo, BlockSyncConfig, BlockSyncStrategy};
 pub use events_and_states::{BaseNodeState, StateEvent, SyncStatus};
 pub use forward_block_sync::ForwardBlockSyncInfo;
+pub use header_verifier::{HeaderVerificationError, HeaderVerifier};
 pub use listening::ListeningInfo;
 pub use shutdown_state::Shutdown;
 pub use starting_state::Starting;
+pub(crate) use sync_progress::SyncProgressReporter;
+pub use sync_progress::{SyncPhase, SyncProgress, SyncProgressHandle};
 pub use waiting::Waiting;
@@ -0,0 +1,246 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use rand::seq::SliceRandom;
+use std::{collections::HashSet, time::Duration};
+use tari_comms::peer_manager::NodeId;
+
+// The weight given to the most recent throughput measurement of a sync peer.
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+
+/// The download statistics of a single sync peer.
+#[derive(Clone, Debug)]
+struct SyncPeer {
+    node_id: NodeId,
+    /// The height of the chain tip the peer last claimed to have
+    tip_height: u64,
+    /// The smoothed number of blocks per second received from this peer, if any blocks have been received yet
+    throughput: Option<f64>,
+    /// The number of requests to this peer that timed out since it last responded
+    stalls: usize,
+}
+
+/// The set of peers that blocks and headers are requested from during a block sync. Peers are scored on their claimed
+/// chain tip and measured throughput, so that requests go to the fastest peer that can provide the requested heights.
+/// Peers that stop responding are switched away from and peers that serve invalid data are blacklisted for the rest of
+/// the sync.
+#[derive(Clone, Debug)]
+pub struct SyncPeers {
+    peers: Vec<SyncPeer>,
+    blacklist: HashSet<NodeId>,
+    stall_limit: usize,
+}
+
+impl SyncPeers {
+    /// Create a set of sync peers that all claim to have the chain tip at `tip_height`. A peer is considered to be
+    /// stalling once `stall_limit` consecutive requests to it have timed out.
+    pub fn new(node_ids: Vec<NodeId>, tip_height: u64, stall_limit: usize) -> Self {
+        Self {
+            peers: node_ids
+                .into_iter()
+                .map(|node_id| SyncPeer {
+                    node_id,
+                    tip_height,
+                    throughput: None,
+                    stalls: 0,
+                })
+                .collect(),
+            blacklist: HashSet::new(),
+            stall_limit,
+        }
+    }
+
+    /// Select the best peer to request blocks up to `height` from. Peers that are not stalling and claim to have the
+    /// height are preferred, then peers with the highest throughput. Peers that have not been measured yet are tried
+    /// before peers that have, so that every peer gets a chance. If `random` is set, ties are broken randomly.
+    pub fn select(&self, height: u64, random: bool) -> Option<NodeId> {
+        let best = self.peers.iter().map(|p| self.rank(p, height)).max()?;
+        let candidates = self
+            .peers
+            .iter()
+            .filter(|p| self.rank(p, height) == best)
+            .collect::<Vec<_>>();
+        let is_measured = !best.2;
+        let candidates = if is_measured {
+            // Prefer the fastest of the measured peers
+            let fastest = candidates.iter().filter_map(|p| p.throughput).fold(0.0, f64::max);
+            candidates
+                .into_iter()
+                .filter(|p| p.throughput.unwrap_or(0.0) >= fastest)
+                .collect()
+        } else {
+            candidates
+        };
+        if random {
+            candidates.choose(&mut rand::thread_rng())
+        } else {
+            candidates.first()
+        }
+        .map(|p| p.node_id.clone())
+    }
+
+    // Peers are ranked on (not stalling, has the height, not measured yet). Throughput breaks ties between measured
+    // peers.
+    fn rank(&self, peer: &SyncPeer, height: u64) -> (bool, bool, bool) {
+        (
+            peer.stalls < self.stall_limit,
+            peer.tip_height >= height,
+            peer.throughput.is_none(),
+        )
+    }
+
+    /// Record that `num_blocks` blocks were received from the peer in the given time.
+    pub fn record_response(&mut self, node_id: &NodeId, num_blocks: usize, elapsed: Duration) {
+        if let Some(peer) = self.find_mut(node_id) {
+            let secs = elapsed.as_secs_f64().max(0.001);
+            let rate = num_blocks as f64 / secs;
+            peer.throughput = Some(match peer.throughput {
+                Some(current) => current + THROUGHPUT_SMOOTHING * (rate - current),
+                None => rate,
+            });
+            peer.stalls = 0;
+        }
+    }
+
+    /// Record that a request to the peer timed out. Returns true if the peer is now considered to be stalling.
+    pub fn record_stall(&mut self, node_id: &NodeId) -> bool {
+        let stall_limit = self.stall_limit;
+        match self.find_mut(node_id) {
+            Some(peer) => {
+                peer.stalls += 1;
+                peer.stalls >= stall_limit
+            },
+            None => false,
+        }
+    }
+
+    /// Update the chain tip height that the peer claims to have.
+    pub fn set_tip_height(&mut self, node_id: &NodeId, tip_height: u64) {
+        if let Some(peer) = self.find_mut(node_id) {
+            peer.tip_height = tip_height;
+        }
+    }
+
+    /// Remove the peer from the set for the rest of the sync. A blacklisted peer cannot be added again.
+    pub fn blacklist(&mut self, node_id: &NodeId) {
+        self.peers.retain(|p| p.node_id != *node_id);
+        self.blacklist.insert(node_id.clone());
+    }
+
+    pub fn is_blacklisted(&self, node_id: &NodeId) -> bool {
+        self.blacklist.contains(node_id)
+    }
+
+    /// Add a peer that claims to have the chain tip at `tip_height`, unless it is already in the set or blacklisted.
+    pub fn add(&mut self, node_id: NodeId, tip_height: u64) {
+        if self.is_blacklisted(&node_id) || self.peers.iter().any(|p| p.node_id == node_id) {
+            return;
+        }
+        self.peers.push(SyncPeer {
+            node_id,
+            tip_height,
+            throughput: None,
+            stalls: 0,
+        });
+    }
+
+    pub fn node_ids(&self) -> Vec<NodeId> {
+        self.peers.iter().map(|p| p.node_id.clone()).collect()
+    }
+
+    pub fn first(&self) -> Option<NodeId> {
+        self.peers.first().map(|p| p.node_id.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    fn find_mut(&mut self, node_id: &NodeId) -> Option<&mut SyncPeer> {
+        self.peers.iter_mut().find(|p| p.node_id == *node_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_comms::types::CommsPublicKey;
+    use tari_crypto::keys::PublicKey;
+
+    fn random_node_id() -> NodeId {
+        let (_, pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        NodeId::from_key(&pk).unwrap()
+    }
+
+    #[test]
+    fn select_fastest_peer() {
+        let node_ids = (0..3).map(|_| random_node_id()).collect::<Vec<_>>();
+        let mut peers = SyncPeers::new(node_ids.clone(), 100, 2);
+        // Unmeasured peers are tried first
+        assert_eq!(peers.select(10, false), Some(node_ids[0].clone()));
+        peers.record_response(&node_ids[0], 10, Duration::from_secs(10));
+        assert_eq!(peers.select(10, false), Some(node_ids[1].clone()));
+        peers.record_response(&node_ids[1], 10, Duration::from_secs(1));
+        peers.record_response(&node_ids[2], 10, Duration::from_secs(5));
+        assert_eq!(peers.select(10, true), Some(node_ids[1].clone()));
+
+        // A peer that does not claim to have the height is only used if no other peer has it
+        peers.set_tip_height(&node_ids[1], 5);
+        assert_eq!(peers.select(10, false), Some(node_ids[2].clone()));
+        assert_eq!(peers.select(5, false), Some(node_ids[1].clone()));
+    }
+
+    #[test]
+    fn switch_away_from_stalling_peer() {
+        let node_ids = (0..2).map(|_| random_node_id()).collect::<Vec<_>>();
+        let mut peers = SyncPeers::new(node_ids.clone(), 100, 2);
+        peers.record_response(&node_ids[0], 10, Duration::from_secs(1));
+        peers.record_response(&node_ids[1], 10, Duration::from_secs(10));
+        assert_eq!(peers.select(10, false), Some(node_ids[0].clone()));
+        assert!(!peers.record_stall(&node_ids[0]));
+        assert_eq!(peers.select(10, false), Some(node_ids[0].clone()));
+        assert!(peers.record_stall(&node_ids[0]));
+        assert_eq!(peers.select(10, false), Some(node_ids[1].clone()));
+        // A response clears the stalls
+        peers.record_response(&node_ids[0], 10, Duration::from_secs(1));
+        assert_eq!(peers.select(10, false), Some(node_ids[0].clone()));
+    }
+
+    #[test]
+    fn blacklisted_peers_are_not_added_again() {
+        let node_ids = (0..2).map(|_| random_node_id()).collect::<Vec<_>>();
+        let mut peers = SyncPeers::new(node_ids.clone(), 100, 2);
+        peers.blacklist(&node_ids[0]);
+        assert_eq!(peers.node_ids(), vec![node_ids[1].clone()]);
+        assert!(peers.is_blacklisted(&node_ids[0]));
+        peers.add(node_ids[0].clone(), 100);
+        assert_eq!(peers.len(), 1);
+        peers.blacklist(&node_ids[1]);
+        assert!(peers.is_empty());
+        assert_eq!(peers.select(10, false), None);
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use futures::SinkExt;
+use log::*;
+use std::{
+    fmt::{Display, Error, Formatter},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tari_broadcast_channel::{bounded, Publisher, Subscriber};
+
+const LOG_TARGET: &str = "c::bn::states::sync_progress";
+const SYNC_PROGRESS_EVENT_BUFFER_SIZE: usize = 10;
+
+/// The phase of block synchronisation the node is in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SyncPhase {
+    /// No sync has been started
+    Idle,
+    /// Downloading headers to find the height where the local chain and the network chain split
+    FindingChainSplit,
+    /// Downloading and adding the missing blocks
+    DownloadingBlocks,
+    /// The last sync completed successfully
+    Synced,
+}
+
+impl Display for SyncPhase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            SyncPhase::Idle => f.write_str("Idle"),
+            SyncPhase::FindingChainSplit => f.write_str("Finding chain split"),
+            SyncPhase::DownloadingBlocks => f.write_str("Downloading blocks"),
+            SyncPhase::Synced => f.write_str("Synced"),
+        }
+    }
+}
+
+/// A snapshot of the block sync progress.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncProgress {
+    pub phase: SyncPhase,
+    pub headers_done: u64,
+    /// The maximum number of headers that may be needed to find the chain split
+    pub headers_total: u64,
+    pub blocks_done: u64,
+    pub blocks_total: u64,
+    /// The number of blocks added per second since the block download started
+    pub blocks_per_sec: f64,
+    /// The estimated time remaining until the block download completes, if it can be estimated
+    pub eta: Option<Duration>,
+}
+
+impl Default for SyncProgress {
+    fn default() -> Self {
+        Self {
+            phase: SyncPhase::Idle,
+            headers_done: 0,
+            headers_total: 0,
+            blocks_done: 0,
+            blocks_total: 0,
+            blocks_per_sec: 0.0,
+            eta: None,
+        }
+    }
+}
+
+impl Display for SyncProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.phase {
+            SyncPhase::FindingChainSplit => write!(
+                f,
+                "{} (headers {}/{})",
+                self.phase, self.headers_done, self.headers_total
+            ),
+            SyncPhase::DownloadingBlocks => {
+                write!(
+                    f,
+                    "{} (blocks {}/{}, {:.2} blocks/s",
+                    self.phase, self.blocks_done, self.blocks_total, self.blocks_per_sec
+                )?;
+                match self.eta {
+                    Some(eta) => write!(f, ", ETA {}s)", eta.as_secs()),
+                    None => f.write_str(")"),
+                }
+            },
+            _ => write!(f, "{}", self.phase),
+        }
+    }
+}
+
+/// A cloneable handle used to query the current sync progress and to subscribe to sync progress updates.
+#[derive(Clone)]
+pub struct SyncProgressHandle {
+    current: Arc<RwLock<SyncProgress>>,
+    event_stream: Subscriber<SyncProgress>,
+}
+
+impl SyncProgressHandle {
+    /// Returns the current sync progress
+    pub fn get_progress(&self) -> SyncProgress {
+        self.current.read().expect("Sync progress lock poisoned").clone()
+    }
+
+    /// Returns a stream of sync progress updates
+    pub fn get_event_stream(&self) -> Subscriber<SyncProgress> {
+        self.event_stream.clone()
+    }
+}
+
+/// Used by the block sync state to record and publish its progress.
+pub(crate) struct SyncProgressReporter {
+    current: Arc<RwLock<SyncProgress>>,
+    publisher: Publisher<SyncProgress>,
+    subscriber: Subscriber<SyncProgress>,
+    blocks_started_at: Instant,
+}
+
+impl SyncProgressReporter {
+    pub fn new() -> Self {
+        let (publisher, subscriber) = bounded(SYNC_PROGRESS_EVENT_BUFFER_SIZE);
+        Self {
+            current: Arc::new(RwLock::new(SyncProgress::default())),
+            publisher,
+            subscriber,
+            blocks_started_at: Instant::now(),
+        }
+    }
+
+    pub fn handle(&self) -> SyncProgressHandle {
+        SyncProgressHandle {
+            current: self.current.clone(),
+            event_stream: self.subscriber.clone(),
+        }
+    }
+
+    pub async fn start_chain_split_search(&mut self, headers_total: u64) {
+        self.update(|progress| {
+            *progress = SyncProgress {
+                phase: SyncPhase::FindingChainSplit,
+                headers_total,
+                ..Default::default()
+            }
+        })
+        .await;
+    }
+
+    pub async fn add_headers(&mut self, num_headers: u64) {
+        self.update(|progress| progress.headers_done += num_headers).await;
+    }
+
+    pub async fn start_block_download(&mut self, blocks_total: u64) {
+        self.blocks_started_at = Instant::now();
+        self.update(|progress| {
+            progress.phase = SyncPhase::DownloadingBlocks;
+            progress.blocks_done = 0;
+            progress.blocks_total = blocks_total;
+            progress.blocks_per_sec = 0.0;
+            progress.eta = None;
+        })
+        .await;
+    }
+
+    pub async fn set_blocks_total(&mut self, blocks_total: u64) {
+        self.update(|progress| progress.blocks_total = blocks_total).await;
+    }
+
+    pub async fn add_blocks(&mut self, num_blocks: u64) {
+        let elapsed = self.blocks_started_at.elapsed();
+        self.update(|progress| {
+            progress.blocks_done += num_blocks;
+            progress.blocks_per_sec = progress.blocks_done as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+            progress.eta = estimate_remaining(
+                progress.blocks_total.saturating_sub(progress.blocks_done),
+                progress.blocks_per_sec,
+            );
+        })
+        .await;
+    }
+
+    pub async fn finish(&mut self, phase: SyncPhase) {
+        self.update(|progress| {
+            *progress = SyncProgress {
+                phase,
+                ..Default::default()
+            }
+        })
+        .await;
+    }
+
+    async fn update<F>(&mut self, f: F)
+    where F: FnOnce(&mut SyncProgress) {
+        let progress = {
+            let mut current = self.current.write().expect("Sync progress lock poisoned");
+            f(&mut *current);
+            current.clone()
+        };
+        info!(target: LOG_TARGET, "Block sync: {}", progress);
+        let _ = self.publisher.send(progress).await;
+    }
+}
+
+fn estimate_remaining(remaining: u64, per_sec: f64) -> Option<Duration> {
+    if per_sec > 0.0 {
+        Some(Duration::from_secs_f64(remaining as f64 / per_sec))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio_macros::test]
+    async fn progress_updates() {
+        let mut reporter = SyncProgressReporter::new();
+        let handle = reporter.handle();
+        assert_eq!(handle.get_progress().phase, SyncPhase::Idle);
+
+        reporter.start_chain_split_search(100).await;
+        reporter.add_headers(50).await;
+        let progress = handle.get_progress();
+        assert_eq!(progress.phase, SyncPhase::FindingChainSplit);
+        assert_eq!((progress.headers_done, progress.headers_total), (50, 100));
+
+        reporter.start_block_download(10).await;
+        reporter.add_blocks(5).await;
+        reporter.set_blocks_total(12).await;
+        let progress = handle.get_progress();
+        assert_eq!(progress.phase, SyncPhase::DownloadingBlocks);
+        assert_eq!((progress.blocks_done, progress.blocks_total), (5, 12));
+        assert!(progress.blocks_per_sec > 0.0);
+        assert!(progress.eta.is_some());
+
+        reporter.finish(SyncPhase::Synced).await;
+        assert_eq!(handle.get_progress(), SyncProgress {
+            phase: SyncPhase::Synced,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn estimate() {
+        assert_eq!(estimate_remaining(10, 2.0), Some(Duration::from_secs(5)));
+        assert_eq!(estimate_remaining(10, 0.0), None);
+    }
+}
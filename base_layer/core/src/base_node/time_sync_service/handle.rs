@@ -0,0 +1,88 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::error::TimeSyncError;
+use std::{fmt, fmt::Display};
+use tari_service_framework::reply_channel::SenderService;
+use tower_service::Service;
+
+/// Request types made through the `TimeSyncHandle` and handled by the `TimeSyncService`
+#[derive(Debug, Clone)]
+pub enum TimeSyncRequest {
+    /// Retrieve the current clock drift status
+    GetClockDriftStatus,
+}
+
+/// Response type for `TimeSyncService`
+#[derive(Debug)]
+pub enum TimeSyncResponse {
+    ClockDriftStatus(ClockDriftStatus),
+}
+
+/// The difference between the local clock and the clocks of the network, as observed by the `TimeSyncService`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClockDriftStatus {
+    /// Median difference in milliseconds between peer clocks and the local clock. A positive value indicates that
+    /// the local clock is behind. This is `None` until enough peer samples have been collected.
+    pub peer_clock_drift: Option<i64>,
+    /// The number of peer clock samples
+    pub num_samples: usize,
+    /// Number of milliseconds that the timestamp of the last received block was ahead of the local clock, if it was
+    pub future_block_drift: Option<i64>,
+    /// True if the clock drift exceeds the configured maximum, otherwise false
+    pub is_drift_exceeded: bool,
+}
+
+impl Display for ClockDriftStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.peer_clock_drift {
+            Some(drift) => writeln!(f, "Peer clock drift: {}ms ({} samples)", drift, self.num_samples)?,
+            None => writeln!(f, "Peer clock drift: unknown ({} samples)", self.num_samples)?,
+        }
+        if let Some(drift) = self.future_block_drift {
+            writeln!(f, "Last block timestamp ahead of local clock by: {}ms", drift)?;
+        }
+        write!(
+            f,
+            "Clock status: {}",
+            if self.is_drift_exceeded { "OUT OF SYNC" } else { "OK" }
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct TimeSyncHandle {
+    handle: SenderService<TimeSyncRequest, Result<TimeSyncResponse, TimeSyncError>>,
+}
+
+impl TimeSyncHandle {
+    pub fn new(handle: SenderService<TimeSyncRequest, Result<TimeSyncResponse, TimeSyncError>>) -> Self {
+        Self { handle }
+    }
+
+    /// Returns the current clock drift status
+    pub async fn get_clock_drift_status(&mut self) -> Result<ClockDriftStatus, TimeSyncError> {
+        match self.handle.call(TimeSyncRequest::GetClockDriftStatus).await?? {
+            TimeSyncResponse::ClockDriftStatus(status) => Ok(status),
+        }
+    }
+}
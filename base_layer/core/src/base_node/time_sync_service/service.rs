@@ -0,0 +1,303 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{
+    config::TimeSyncConfig,
+    error::TimeSyncError,
+    handle::{ClockDriftStatus, TimeSyncRequest, TimeSyncResponse},
+    LOG_TARGET,
+};
+use crate::{
+    base_node::comms_interface::{BlockEvent, LocalNodeCommsInterface},
+    chain_storage::BlockAddResult,
+};
+use chrono::Utc;
+use futures::stream::StreamExt;
+use log::*;
+use std::{collections::VecDeque, convert::TryInto};
+use tari_common::log_if_error;
+use tari_p2p::services::liveness::{LivenessEvent, LivenessHandle, MetadataKey};
+use tari_service_framework::reply_channel::Receiver;
+
+pub(super) struct TimeSyncService {
+    config: TimeSyncConfig,
+    liveness: LivenessHandle,
+    base_node: LocalNodeCommsInterface,
+    request_stream: Option<Receiver<TimeSyncRequest, Result<TimeSyncResponse, TimeSyncError>>>,
+    samples: VecDeque<i64>,
+    future_block_drift: Option<i64>,
+    is_drift_exceeded: bool,
+}
+
+impl TimeSyncService {
+    pub fn new(
+        config: TimeSyncConfig,
+        liveness: LivenessHandle,
+        base_node: LocalNodeCommsInterface,
+        request_stream: Receiver<TimeSyncRequest, Result<TimeSyncResponse, TimeSyncError>>,
+    ) -> Self
+    {
+        Self {
+            samples: VecDeque::with_capacity(config.num_samples),
+            config,
+            liveness,
+            base_node,
+            request_stream: Some(request_stream),
+            future_block_drift: None,
+            is_drift_exceeded: false,
+        }
+    }
+
+    /// Run the service
+    pub async fn run(mut self) {
+        let mut request_stream = self
+            .request_stream
+            .take()
+            .expect("TimeSyncService initialized without request_stream")
+            .fuse();
+        let mut liveness_event_stream = self.liveness.get_event_stream_fused();
+        let mut base_node_event_stream = self.base_node.get_block_event_stream_fused();
+
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(Ok(self.handle_request(request)));
+                },
+
+                event = base_node_event_stream.select_next_some() => {
+                    self.handle_block_event(&event);
+                },
+
+                liveness_event = liveness_event_stream.select_next_some() => {
+                    log_if_error!(
+                        level: debug,
+                        target: LOG_TARGET,
+                        "Failed to handle liveness event because '{}'",
+                        self.handle_liveness_event(&liveness_event)
+                    );
+                },
+
+                complete => {
+                    info!(target: LOG_TARGET, "TimeSyncService is exiting because all tasks have completed");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_request(&self, request: TimeSyncRequest) -> TimeSyncResponse {
+        match request {
+            TimeSyncRequest::GetClockDriftStatus => TimeSyncResponse::ClockDriftStatus(self.status()),
+        }
+    }
+
+    fn handle_liveness_event(&mut self, event: &LivenessEvent) -> Result<(), TimeSyncError> {
+        if let LivenessEvent::ReceivedPong(event) = event {
+            let timestamp = match event.metadata.get(MetadataKey::Timestamp) {
+                Some(timestamp) => timestamp,
+                // Peers running an older version do not send a timestamp
+                None => return Ok(()),
+            };
+            let timestamp = timestamp
+                .as_slice()
+                .try_into()
+                .map(i64::from_le_bytes)
+                .map_err(|_| TimeSyncError::InvalidTimestamp)?;
+            // The pong was sent approximately half of the round trip time ago
+            let half_latency = event.latency.map(|latency| i64::from(latency) / 2).unwrap_or(0);
+            let drift = timestamp + half_latency - Utc::now().timestamp_millis();
+            trace!(
+                target: LOG_TARGET,
+                "Clock of peer '{}' differs from the local clock by {}ms",
+                event.node_id,
+                drift
+            );
+            self.add_sample(drift);
+        }
+
+        Ok(())
+    }
+
+    fn handle_block_event(&mut self, event: &BlockEvent) {
+        if let BlockEvent::Verified((block, BlockAddResult::Ok)) = event {
+            let timestamp = block.header.timestamp.as_u64() as i64 * 1000;
+            let drift = timestamp - Utc::now().timestamp_millis();
+            // Blocks are received some time after they are timestamped (and much later when syncing), so only a block
+            // timestamp that is ahead of the local clock indicates clock drift
+            self.future_block_drift = Some(drift).filter(|drift| *drift > 0);
+            self.update_drift_exceeded();
+        }
+    }
+
+    fn add_sample(&mut self, drift: i64) {
+        if self.samples.len() >= self.config.num_samples {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(drift);
+        self.update_drift_exceeded();
+    }
+
+    fn peer_clock_drift(&self) -> Option<i64> {
+        if self.samples.is_empty() || self.samples.len() < self.config.min_samples {
+            return None;
+        }
+        let mut samples = self.samples.iter().copied().collect::<Vec<_>>();
+        samples.sort_unstable();
+        Some(samples[samples.len() / 2])
+    }
+
+    fn update_drift_exceeded(&mut self) {
+        let max_drift = self.config.max_clock_drift.as_millis() as i64;
+        let is_peer_drift_exceeded = self
+            .peer_clock_drift()
+            .map(|drift| drift.abs() > max_drift)
+            .unwrap_or(false);
+        let is_block_drift_exceeded = self.future_block_drift.map(|drift| drift > max_drift).unwrap_or(false);
+        let is_drift_exceeded = is_peer_drift_exceeded || is_block_drift_exceeded;
+
+        if is_drift_exceeded && !self.is_drift_exceeded {
+            warn!(
+                target: LOG_TARGET,
+                "The local clock differs from the network by more than {}s (peer clock drift: {:?}ms, future block \
+                 drift: {:?}ms). Clock drift affects difficulty adjustment and stored message expiry. Please check \
+                 the system clock.",
+                self.config.max_clock_drift.as_secs(),
+                self.peer_clock_drift(),
+                self.future_block_drift,
+            );
+        }
+        if !is_drift_exceeded && self.is_drift_exceeded {
+            info!(
+                target: LOG_TARGET,
+                "The local clock is back within {}s of the network",
+                self.config.max_clock_drift.as_secs()
+            );
+        }
+        self.is_drift_exceeded = is_drift_exceeded;
+    }
+
+    fn status(&self) -> ClockDriftStatus {
+        ClockDriftStatus {
+            peer_clock_drift: self.peer_clock_drift(),
+            num_samples: self.samples.len(),
+            future_block_drift: self.future_block_drift,
+            is_drift_exceeded: self.is_drift_exceeded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::blocks::{BlockBuilder, BlockHeader};
+    use tari_broadcast_channel as broadcast_channel;
+    use tari_comms::peer_manager::NodeId;
+    use tari_crypto::tari_utilities::epoch_time::EpochTime;
+    use tari_p2p::services::liveness::{mock::create_p2p_liveness_mock, Metadata, PongEvent};
+    use tari_service_framework::reply_channel;
+    use tari_test_utils::unpack_enum;
+
+    fn create_service() -> TimeSyncService {
+        let (liveness_handle, _) = create_p2p_liveness_mock(1);
+        let (base_node_sender, _) = reply_channel::unbounded();
+        let (block_sender, _) = reply_channel::unbounded();
+        let (_, subscriber) = broadcast_channel::bounded(1);
+        let base_node = LocalNodeCommsInterface::new(base_node_sender, block_sender, subscriber);
+        let (_, request_stream) = reply_channel::unbounded();
+        TimeSyncService::new(TimeSyncConfig::default(), liveness_handle, base_node, request_stream)
+    }
+
+    fn create_pong_event(timestamp: i64) -> LivenessEvent {
+        let mut metadata = Metadata::new();
+        metadata.insert(MetadataKey::Timestamp, timestamp.to_le_bytes().to_vec());
+        LivenessEvent::ReceivedPong(Box::new(PongEvent {
+            is_neighbour: true,
+            metadata,
+            node_id: NodeId::new(),
+            latency: None,
+            is_monitored: false,
+        }))
+    }
+
+    #[test]
+    fn handle_liveness_event_drift() {
+        let mut service = create_service();
+        let ten_minutes = 10 * 60 * 1000;
+
+        let event = create_pong_event(Utc::now().timestamp_millis() + ten_minutes);
+        service.handle_liveness_event(&event).unwrap();
+        service.handle_liveness_event(&event).unwrap();
+        // Not enough samples
+        assert!(service.status().peer_clock_drift.is_none());
+        assert_eq!(service.status().is_drift_exceeded, false);
+
+        service.handle_liveness_event(&event).unwrap();
+        let status = service.status();
+        assert_eq!(status.num_samples, 3);
+        assert!(status.peer_clock_drift.unwrap() > ten_minutes - 1000);
+        assert_eq!(status.is_drift_exceeded, true);
+
+        // The median moves back into range once most peers agree with the local clock
+        let event = create_pong_event(Utc::now().timestamp_millis());
+        for _ in 0..4 {
+            service.handle_liveness_event(&event).unwrap();
+        }
+        let status = service.status();
+        assert!(status.peer_clock_drift.unwrap().abs() < 1000);
+        assert_eq!(status.is_drift_exceeded, false);
+    }
+
+    #[test]
+    fn handle_liveness_event_invalid_timestamp() {
+        let mut service = create_service();
+        let mut metadata = Metadata::new();
+        metadata.insert(MetadataKey::Timestamp, b"bad".to_vec());
+        let event = LivenessEvent::ReceivedPong(Box::new(PongEvent {
+            is_neighbour: true,
+            metadata,
+            node_id: NodeId::new(),
+            latency: None,
+            is_monitored: false,
+        }));
+        let err = service.handle_liveness_event(&event).unwrap_err();
+        unpack_enum!(TimeSyncError::InvalidTimestamp = err);
+        assert_eq!(service.status().num_samples, 0);
+    }
+
+    #[test]
+    fn handle_block_event_future_block() {
+        let mut service = create_service();
+
+        let mut header = BlockHeader::new(0);
+        header.timestamp = EpochTime::from(EpochTime::now().as_u64() + 10 * 60);
+        let block = BlockBuilder::new(0).with_header(header).build();
+        service.handle_block_event(&BlockEvent::Verified((Box::new(block), BlockAddResult::Ok)));
+        assert_eq!(service.status().is_drift_exceeded, true);
+
+        let block = BlockBuilder::new(0).with_header(BlockHeader::new(0)).build();
+        service.handle_block_event(&BlockEvent::Verified((Box::new(block), BlockAddResult::Ok)));
+        let status = service.status();
+        assert!(status.future_block_drift.is_none());
+        assert_eq!(status.is_drift_exceeded, false);
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use super::error::VersionHandshakeError;
+use crate::base_node::protocol_version::NegotiatedCapabilities;
+use tari_comms::types::CommsPublicKey;
+use tari_service_framework::reply_channel::SenderService;
+use tower_service::Service;
+
+/// Request types made through the `VersionHandshakeHandle` and handled by the `VersionHandshakeService`
+#[derive(Debug, Clone)]
+pub enum VersionHandshakeRequest {
+    /// Retrieve the capabilities negotiated with the given peer
+    GetPeerCapabilities(CommsPublicKey),
+}
+
+/// Response type for `VersionHandshakeService`
+#[derive(Debug)]
+pub enum VersionHandshakeResponse {
+    PeerCapabilities(Option<NegotiatedCapabilities>),
+}
+
+#[derive(Clone)]
+pub struct VersionHandshakeHandle {
+    handle: SenderService<VersionHandshakeRequest, Result<VersionHandshakeResponse, VersionHandshakeError>>,
+}
+
+impl VersionHandshakeHandle {
+    pub fn new(
+        handle: SenderService<VersionHandshakeRequest, Result<VersionHandshakeResponse, VersionHandshakeError>>,
+    ) -> Self {
+        Self { handle }
+    }
+
+    /// Returns the capabilities negotiated with the given peer, or None if the peer has not completed a version
+    /// handshake with this node
+    pub async fn get_peer_capabilities(
+        &mut self,
+        peer: CommsPublicKey,
+    ) -> Result<Option<NegotiatedCapabilities>, VersionHandshakeError>
+    {
+        match self
+            .handle
+            .call(VersionHandshakeRequest::GetPeerCapabilities(peer))
+            .await??
+        {
+            VersionHandshakeResponse::PeerCapabilities(capabilities) => Ok(capabilities),
+        }
+    }
+}
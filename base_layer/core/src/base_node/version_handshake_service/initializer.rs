@@ -0,0 +1,116 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use super::{handle::VersionHandshakeHandle, service::VersionHandshakeService, LOG_TARGET};
+use crate::base_node::{
+    proto::base_node::VersionHandshake,
+    protocol_version::{NodeVersion, ProtocolFeatures},
+};
+use futures::{future, future::select, pin_mut, Stream, StreamExt};
+use log::*;
+use std::{future::Future, sync::Arc};
+use tari_comms_dht::outbound::OutboundMessageRequester;
+use tari_p2p::{
+    comms_connector::PeerMessage,
+    domain_message::DomainMessage,
+    services::utils::{map_decode, ok_or_skip_result},
+    tari_message::TariMessageType,
+};
+use tari_pubsub::TopicSubscriptionFactory;
+use tari_service_framework::{
+    handles::ServiceHandlesFuture,
+    reply_channel,
+    ServiceInitializationError,
+    ServiceInitializer,
+};
+use tari_shutdown::ShutdownSignal;
+use tokio::runtime;
+
+pub struct VersionHandshakeServiceInitializer {
+    version: NodeVersion,
+    features: ProtocolFeatures,
+    inbound_message_subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+}
+
+impl VersionHandshakeServiceInitializer {
+    pub fn new(
+        version: NodeVersion,
+        features: ProtocolFeatures,
+        inbound_message_subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
+    ) -> Self
+    {
+        Self {
+            version,
+            features,
+            inbound_message_subscription_factory,
+        }
+    }
+
+    /// Get a stream for inbound version handshake requests
+    fn handshake_request_stream(&self) -> impl Stream<Item = DomainMessage<VersionHandshake>> {
+        self.inbound_message_subscription_factory
+            .get_subscription(TariMessageType::VersionHandshakeRequest)
+            .map(map_decode::<VersionHandshake>)
+            .filter_map(ok_or_skip_result)
+    }
+}
+
+impl ServiceInitializer for VersionHandshakeServiceInitializer {
+    type Future = impl Future<Output = Result<(), ServiceInitializationError>>;
+
+    fn initialize(
+        &mut self,
+        executor: runtime::Handle,
+        handles_fut: ServiceHandlesFuture,
+        shutdown: ShutdownSignal,
+    ) -> Self::Future
+    {
+        let (sender, receiver) = reply_channel::unbounded();
+        handles_fut.register(VersionHandshakeHandle::new(sender));
+
+        let handshake_request_stream = self.handshake_request_stream();
+        let version = self.version;
+        let features = self.features;
+
+        executor.spawn(async move {
+            let handles = handles_fut.await;
+
+            let outbound_message_service = handles
+                .wait_for_handle::<OutboundMessageRequester>()
+                .await
+                .expect("OutboundMessageRequester handle required for VersionHandshakeService");
+
+            let service_run = VersionHandshakeService::new(
+                version,
+                features,
+                outbound_message_service,
+                handshake_request_stream,
+                receiver,
+            )
+            .run();
+            pin_mut!(service_run);
+            select(service_run, shutdown).await;
+            info!(target: LOG_TARGET, "VersionHandshakeService has shut down");
+        });
+
+        future::ready(Ok(()))
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use super::{
+    error::VersionHandshakeError,
+    handle::{VersionHandshakeRequest, VersionHandshakeResponse},
+    LOG_TARGET,
+};
+use crate::base_node::{
+    proto::base_node::VersionHandshake,
+    protocol_version::{NegotiatedCapabilities, NodeVersion, ProtocolFeatures, MIN_COMPATIBLE_VERSION},
+};
+use futures::{pin_mut, stream::StreamExt, Stream};
+use log::*;
+use std::collections::HashMap;
+use tari_common::log_if_error;
+use tari_comms::types::CommsPublicKey;
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester},
+};
+use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tari_service_framework::reply_channel::Receiver;
+
+pub(super) struct VersionHandshakeService<SHandshake> {
+    version: NodeVersion,
+    features: ProtocolFeatures,
+    outbound_message_service: OutboundMessageRequester,
+    handshake_request_stream: Option<SHandshake>,
+    request_stream: Option<Receiver<VersionHandshakeRequest, Result<VersionHandshakeResponse, VersionHandshakeError>>>,
+    peer_capabilities: HashMap<CommsPublicKey, NegotiatedCapabilities>,
+}
+
+impl<SHandshake> VersionHandshakeService<SHandshake>
+where SHandshake: Stream<Item = DomainMessage<VersionHandshake>>
+{
+    pub fn new(
+        version: NodeVersion,
+        features: ProtocolFeatures,
+        outbound_message_service: OutboundMessageRequester,
+        handshake_request_stream: SHandshake,
+        request_stream: Receiver<VersionHandshakeRequest, Result<VersionHandshakeResponse, VersionHandshakeError>>,
+    ) -> Self
+    {
+        Self {
+            version,
+            features,
+            outbound_message_service,
+            handshake_request_stream: Some(handshake_request_stream),
+            request_stream: Some(request_stream),
+            peer_capabilities: HashMap::new(),
+        }
+    }
+
+    /// Run the service
+    pub async fn run(mut self) {
+        let handshake_request_stream = self
+            .handshake_request_stream
+            .take()
+            .expect("VersionHandshakeService initialized without handshake_request_stream")
+            .fuse();
+        pin_mut!(handshake_request_stream);
+        let mut request_stream = self
+            .request_stream
+            .take()
+            .expect("VersionHandshakeService initialized without request_stream")
+            .fuse();
+
+        loop {
+            futures::select! {
+                request_context = request_stream.select_next_some() => {
+                    let (request, reply_tx) = request_context.split();
+                    let _ = reply_tx.send(Ok(self.handle_request(request)));
+                },
+
+                msg = handshake_request_stream.select_next_some() => {
+                    let (origin_public_key, handshake) = msg.into_origin_and_inner();
+                    let response = self.handle_handshake(origin_public_key.clone(), handshake);
+                    log_if_error!(
+                        level: debug,
+                        target: LOG_TARGET,
+                        "Failed to send version handshake response because '{:?}'",
+                        self.send_response(origin_public_key, response).await
+                    );
+                },
+
+                complete => {
+                    info!(target: LOG_TARGET, "VersionHandshakeService is exiting because all tasks have completed");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn handle_request(&self, request: VersionHandshakeRequest) -> VersionHandshakeResponse {
+        match request {
+            VersionHandshakeRequest::GetPeerCapabilities(peer) => {
+                VersionHandshakeResponse::PeerCapabilities(self.peer_capabilities.get(&peer).cloned())
+            },
+        }
+    }
+
+    /// Negotiate the capabilities of the peer and return the handshake response. Peers older than the minimum
+    /// compatible version are refused.
+    fn handle_handshake(&mut self, peer: CommsPublicKey, handshake: VersionHandshake) -> VersionHandshake {
+        match handshake.negotiate(self.features, MIN_COMPATIBLE_VERSION) {
+            Ok(capabilities) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Version handshake with peer {} negotiated {}", peer, capabilities
+                );
+                self.peer_capabilities.insert(peer, capabilities);
+                VersionHandshake::new(handshake.request_key, self.version, self.features)
+            },
+            Err(err) => {
+                info!(
+                    target: LOG_TARGET,
+                    "Refusing version handshake with peer {} (version '{}') because '{}'", peer, handshake.version, err
+                );
+                self.peer_capabilities.remove(&peer);
+                VersionHandshake::rejection(handshake.request_key, self.version)
+            },
+        }
+    }
+
+    async fn send_response(
+        &mut self,
+        peer: CommsPublicKey,
+        response: VersionHandshake,
+    ) -> Result<(), VersionHandshakeError>
+    {
+        self.outbound_message_service
+            .send_direct(
+                peer,
+                OutboundEncryption::EncryptForPeer,
+                OutboundDomainMessage::new(TariMessageType::VersionHandshakeResponse, response),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{channel::mpsc, stream};
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::PublicKey;
+    use tari_service_framework::reply_channel;
+
+    type TestService = VersionHandshakeService<stream::Empty<DomainMessage<VersionHandshake>>>;
+
+    fn create_service() -> TestService {
+        let (outbound_tx, _) = mpsc::channel(1);
+        let (_, request_stream) = reply_channel::unbounded();
+        VersionHandshakeService::new(
+            NodeVersion::new(0, 2, 0),
+            ProtocolFeatures::CONFIRMATION_NOTIFICATIONS | ProtocolFeatures::BLOCK_FILTERS,
+            OutboundMessageRequester::new(outbound_tx),
+            stream::empty(),
+            request_stream,
+        )
+    }
+
+    #[test]
+    fn handle_handshake() {
+        let mut service = create_service();
+        let (_, peer) = CommsPublicKey::random_keypair(&mut OsRng);
+
+        let response = service.handle_handshake(
+            peer.clone(),
+            VersionHandshake::new(1, MIN_COMPATIBLE_VERSION, ProtocolFeatures::CONFIRMATION_NOTIFICATIONS),
+        );
+        assert_eq!(response.request_key, 1);
+        assert!(!response.rejected);
+        assert_eq!(response.version, "0.2.0");
+        match service.handle_request(VersionHandshakeRequest::GetPeerCapabilities(peer.clone())) {
+            VersionHandshakeResponse::PeerCapabilities(Some(capabilities)) => {
+                assert_eq!(capabilities.peer_version, MIN_COMPATIBLE_VERSION);
+                assert_eq!(capabilities.features, ProtocolFeatures::CONFIRMATION_NOTIFICATIONS);
+            },
+            _ => panic!("Expected negotiated capabilities"),
+        }
+
+        // An older peer is refused and its capabilities are forgotten
+        let response = service.handle_handshake(
+            peer.clone(),
+            VersionHandshake::new(2, NodeVersion::new(0, 0, 1), ProtocolFeatures::all()),
+        );
+        assert_eq!(response.request_key, 2);
+        assert!(response.rejected);
+        match service.handle_request(VersionHandshakeRequest::GetPeerCapabilities(peer)) {
+            VersionHandshakeResponse::PeerCapabilities(None) => {},
+            _ => panic!("Expected no capabilities"),
+        }
+    }
+}
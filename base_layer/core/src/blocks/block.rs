@@ -30,6 +30,8 @@ use crate::{
         aggregated_body::AggregateBody,
         tari_amount::MicroTari,
         transaction::{
+            KernelFeatures,
+            OutputFeaturesError,
             OutputFlags,
             Transaction,
             TransactionError,
@@ -65,6 +67,8 @@ pub enum BlockValidationError {
     NoCutThrough,
     // The block weight is above the maximum
     BlockTooLarge,
+    // An output in the block has features that are not permitted by the consensus rules
+    InvalidOutputFeatures(OutputFeaturesError),
 }
 
 /// A Tari block. Blocks are linked together into a blockchain.
@@ -94,6 +98,7 @@ impl Block {
     /// Run through the outputs of the block and check that
     /// 1. There is exactly ONE coinbase output
     /// 1. The output's maturity is correctly set
+    /// 1. The extra data on the coinbase kernel does not exceed the consensus limit
     /// NOTE this does not check the coinbase amount
     pub fn check_coinbase_output(&self, consensus_constants: &ConsensusConstants) -> Result<(), BlockValidationError> {
         let mut coinbase_counter = 0; // there should be exactly 1 coinbase
@@ -118,6 +123,39 @@ impl Block {
             );
             return Err(BlockValidationError::InvalidCoinbase);
         }
+        for kernel in self.body.kernels() {
+            if kernel.features.contains(KernelFeatures::COINBASE_KERNEL) &&
+                kernel.meta_info.as_ref().map(Vec::len).unwrap_or(0) > consensus_constants.coinbase_max_extra_size()
+            {
+                warn!(
+                    target: LOG_TARGET,
+                    "Coinbase kernel on {} carries more extra data than permitted",
+                    self.hash().to_hex()
+                );
+                return Err(BlockValidationError::InvalidCoinbase);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the feature extensions of all outputs are permitted by the consensus constants
+    pub fn check_output_features(&self, consensus_constants: &ConsensusConstants) -> Result<(), BlockValidationError> {
+        for utxo in self.body.outputs() {
+            utxo.features
+                .validate_extensions(
+                    consensus_constants.permitted_output_feature_extensions(),
+                    consensus_constants.max_output_feature_extensions_size(),
+                )
+                .map_err(|err| {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Output with invalid feature extensions found in block {}: {}",
+                        self.hash().to_hex(),
+                        err
+                    );
+                    err
+                })?;
+        }
         Ok(())
     }
 
@@ -0,0 +1,363 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Generates genesis blocks for private localnet deployments.
+//!
+//! All keys (and so the output commitments, kernel and their Merkle roots) are derived from a seed, so the same seed,
+//! consensus constants, timestamp and UTXO values always produce the same outputs and kernel. Bulletproof range proofs
+//! use their own randomness, so the range proofs (and hence the range proof root and block hash) differ between runs;
+//! generate the genesis block once and distribute the resulting JSON file or Rust snippet.
+//!
+//! The first output is the coinbase, worth the block reward at height 0 and locked for the coinbase lock height. Any
+//! additional UTXOs can be spent immediately. A single coinbase kernel covers all of the outputs.
+
+use crate::{
+    blocks::{Block, BlockHeader},
+    consensus::{emission::EmissionSchedule, ConsensusConstants},
+    proof_of_work::{PowAlgorithm, ProofOfWork},
+    transactions::{
+        aggregated_body::AggregateBody,
+        tari_amount::MicroTari,
+        transaction::{
+            KernelFeatures,
+            OutputFeatures,
+            TransactionError,
+            TransactionKernel,
+            TransactionOutput,
+            UnblindedOutput,
+        },
+        transaction_protocol::{build_challenge, TransactionMetadata},
+        types::{Commitment, CryptoFactories, HashDigest, HashOutput, PrivateKey, PublicKey, Signature},
+    },
+};
+use croaring::Bitmap;
+use derive_error::Error;
+use digest::Digest;
+use std::{fmt::Write, fs, path::Path};
+use tari_crypto::{
+    common::Blake256,
+    keys::PublicKey as PublicKeyTrait,
+    signatures::SchnorrSignatureError,
+    tari_utilities::{epoch_time::EpochTime, hex::Hex, ByteArray, ByteArrayError, Hashable},
+};
+use tari_mmr::{error::MerkleMountainRangeError, MutableMmr};
+
+#[derive(Debug, Error)]
+pub enum GenesisBlockGeneratorError {
+    /// Could not derive a key from the seed
+    KeyDerivationError(ByteArrayError),
+    /// Could not construct an output
+    TransactionError(TransactionError),
+    /// Could not sign the coinbase kernel
+    SigningError(SchnorrSignatureError),
+    /// Could not calculate a Merkle root
+    MerkleMountainRangeError(MerkleMountainRangeError),
+    /// Could not (de)serialize the genesis block
+    SerializationError(serde_json::Error),
+    /// Could not read or write a genesis block file
+    IoError(std::io::Error),
+}
+
+/// Builds a genesis block from a seed, the consensus constants and a list of initial UTXO values.
+pub struct GenesisBlockGenerator {
+    consensus_constants: ConsensusConstants,
+    seed: Vec<u8>,
+    timestamp: EpochTime,
+    utxo_values: Vec<MicroTari>,
+}
+
+impl GenesisBlockGenerator {
+    pub fn new(consensus_constants: ConsensusConstants, seed: &[u8]) -> Self {
+        Self {
+            consensus_constants,
+            seed: seed.to_vec(),
+            timestamp: EpochTime::now(),
+            utxo_values: Vec::new(),
+        }
+    }
+
+    /// Set the genesis block timestamp. Defaults to the current time.
+    pub fn with_timestamp(mut self, timestamp: EpochTime) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Add spendable UTXOs with the given values to the genesis block, in addition to the coinbase.
+    pub fn with_utxos(mut self, values: &[MicroTari]) -> Self {
+        self.utxo_values.extend_from_slice(values);
+        self
+    }
+
+    pub fn generate(&self, factories: &CryptoFactories) -> Result<GeneratedGenesisBlock, GenesisBlockGeneratorError> {
+        let constants = &self.consensus_constants;
+        let (initial, decay, tail) = constants.emission_amounts();
+        let coinbase_value = EmissionSchedule::new(initial, decay, tail).block_reward(0);
+
+        let mut unblinded = vec![UnblindedOutput::new(
+            coinbase_value,
+            self.derive_key("output", 0)?,
+            Some(OutputFeatures::create_coinbase(constants.coinbase_lock_height())),
+        )];
+        for (i, value) in self.utxo_values.iter().enumerate() {
+            unblinded.push(UnblindedOutput::new(*value, self.derive_key("output", i + 1)?, None));
+        }
+        let outputs = unblinded
+            .iter()
+            .map(|output| output.as_transaction_output(factories))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The excess of the kernel is the sum of all the output blinding factors, so the block balances
+        let excess_key = unblinded
+            .iter()
+            .skip(1)
+            .fold(unblinded[0].spending_key.reveal().clone(), |sum, output| {
+                &sum + output.spending_key.reveal()
+            });
+        let nonce = self.derive_key("kernel_nonce", 0)?;
+        let challenge = build_challenge(&PublicKey::from_secret_key(&nonce), &TransactionMetadata::default());
+        let kernel = TransactionKernel {
+            features: KernelFeatures::COINBASE_KERNEL,
+            fee: MicroTari(0),
+            lock_height: 0,
+            meta_info: None,
+            linked_kernel: None,
+            excess: Commitment::from_public_key(&PublicKey::from_secret_key(&excess_key)),
+            excess_sig: Signature::sign(excess_key, nonce, &challenge)?,
+        };
+
+        let mut body = AggregateBody::new(vec![], outputs, vec![kernel]);
+        body.sort();
+        let mut header = BlockHeader::new(constants.blockchain_version());
+        header.timestamp = self.timestamp;
+        header.kernel_mr = merkle_root(body.kernels().iter().map(Hashable::hash).collect())?;
+        header.output_mr = merkle_root(body.outputs().iter().map(Hashable::hash).collect())?;
+        header.range_proof_mr = merkle_root(body.outputs().iter().map(|o| o.proof().hash()).collect())?;
+        header.pow = ProofOfWork {
+            accumulated_monero_difficulty: 1.into(),
+            accumulated_blake_difficulty: 1.into(),
+            pow_algo: PowAlgorithm::Blake,
+            pow_data: vec![],
+        };
+
+        Ok(GeneratedGenesisBlock {
+            block: Block { header, body },
+            outputs: unblinded,
+        })
+    }
+
+    fn derive_key(&self, label: &str, index: usize) -> Result<PrivateKey, GenesisBlockGeneratorError> {
+        let hash = Blake256::new()
+            .chain(b"tari.genesis_block_generator.")
+            .chain(label.as_bytes())
+            .chain(&self.seed)
+            .chain(&(index as u64).to_le_bytes())
+            .result();
+        Ok(PrivateKey::from_bytes(hash.as_slice())?)
+    }
+}
+
+fn merkle_root(hashes: Vec<HashOutput>) -> Result<HashOutput, MerkleMountainRangeError> {
+    MutableMmr::<HashDigest, _>::new(hashes, Bitmap::create()).get_merkle_root()
+}
+
+/// A generated genesis block along with the spending keys of its outputs
+pub struct GeneratedGenesisBlock {
+    pub block: Block,
+    /// The coinbase output followed by the additional UTXOs
+    pub outputs: Vec<UnblindedOutput>,
+}
+
+impl GeneratedGenesisBlock {
+    /// The genesis block as JSON, as loaded by [load_genesis_block]
+    pub fn to_json(&self) -> Result<String, GenesisBlockGeneratorError> {
+        Ok(serde_json::to_string_pretty(&self.block)?)
+    }
+
+    /// The values and spending keys of the genesis block outputs as JSON, so that they can be imported into a wallet
+    pub fn outputs_to_json(&self) -> Result<String, GenesisBlockGeneratorError> {
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|output| {
+                serde_json::json!({
+                    "value": u64::from(output.value),
+                    "spending_key": output.spending_key.to_hex(),
+                    "maturity": output.features.maturity,
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(serde_json::to_string_pretty(&outputs)?)
+    }
+
+    /// A Rust function returning the genesis block, in the form used in the `genesis_block` module
+    pub fn to_rust_snippet(&self, fn_name: &str) -> String {
+        let header = &self.block.header;
+        let mut s = String::new();
+        let _ = writeln!(s, "pub fn {}() -> Block {{", fn_name);
+        let _ = writeln!(s, "    let mut body = AggregateBody::new(");
+        let _ = writeln!(s, "        vec![],");
+        let _ = writeln!(s, "        vec![");
+        for output in self.block.body.outputs() {
+            write_output(&mut s, output);
+        }
+        let _ = writeln!(s, "        ],");
+        let _ = writeln!(s, "        vec![");
+        for kernel in self.block.body.kernels() {
+            write_kernel(&mut s, kernel);
+        }
+        let _ = writeln!(s, "        ],");
+        let _ = writeln!(s, "    );");
+        let _ = writeln!(s, "    body.sort();");
+        let _ = writeln!(s, "    Block {{");
+        let _ = writeln!(s, "        header: BlockHeader {{");
+        let _ = writeln!(s, "            version: {},", header.version);
+        let _ = writeln!(s, "            height: {},", header.height);
+        let _ = writeln!(s, "            prev_hash: vec![0; 32],");
+        let _ = writeln!(s, "            timestamp: {}.into(),", header.timestamp.as_u64());
+        let _ = writeln!(
+            s,
+            "            output_mr: from_hex(\"{}\").unwrap(),",
+            header.output_mr.to_hex()
+        );
+        let _ = writeln!(
+            s,
+            "            range_proof_mr: from_hex(\"{}\").unwrap(),",
+            header.range_proof_mr.to_hex()
+        );
+        let _ = writeln!(
+            s,
+            "            kernel_mr: from_hex(\"{}\").unwrap(),",
+            header.kernel_mr.to_hex()
+        );
+        let _ = writeln!(
+            s,
+            "            total_kernel_offset: PrivateKey::from_hex(\"{}\").unwrap(),",
+            header.total_kernel_offset.to_hex()
+        );
+        let _ = writeln!(s, "            nonce: {},", header.nonce);
+        let _ = writeln!(s, "            pow: ProofOfWork {{");
+        let _ = writeln!(s, "                accumulated_monero_difficulty: 1.into(),");
+        let _ = writeln!(s, "                accumulated_blake_difficulty: 1.into(),");
+        let _ = writeln!(s, "                pow_algo: PowAlgorithm::Blake,");
+        let _ = writeln!(s, "                pow_data: vec![],");
+        let _ = writeln!(s, "            }},");
+        let _ = writeln!(s, "        }},");
+        let _ = writeln!(s, "        body,");
+        let _ = writeln!(s, "    }}");
+        let _ = writeln!(s, "}}");
+        s
+    }
+}
+
+fn write_output(s: &mut String, output: &TransactionOutput) {
+    let _ = writeln!(s, "            TransactionOutput {{");
+    let _ = writeln!(s, "                features: OutputFeatures {{");
+    let _ = writeln!(
+        s,
+        "                    flags: OutputFlags::from_bits({}).unwrap(),",
+        output.features.flags.bits()
+    );
+    let _ = writeln!(s, "                    maturity: {},", output.features.maturity);
+    let _ = writeln!(s, "                    ..Default::default()");
+    let _ = writeln!(s, "                }},");
+    let _ = writeln!(
+        s,
+        "                commitment: Commitment::from_hex(\"{}\").unwrap(),",
+        output.commitment.to_hex()
+    );
+    let _ = writeln!(
+        s,
+        "                proof: BulletRangeProof::from_hex(\"{}\").unwrap(),",
+        output.proof.to_hex()
+    );
+    let _ = writeln!(s, "            }},");
+}
+
+fn write_kernel(s: &mut String, kernel: &TransactionKernel) {
+    let _ = writeln!(s, "            TransactionKernel {{");
+    let _ = writeln!(s, "                features: KernelFeatures::COINBASE_KERNEL,");
+    let _ = writeln!(s, "                fee: MicroTari({}),", u64::from(kernel.fee));
+    let _ = writeln!(s, "                lock_height: {},", kernel.lock_height);
+    let _ = writeln!(s, "                meta_info: None,");
+    let _ = writeln!(s, "                linked_kernel: None,");
+    let _ = writeln!(
+        s,
+        "                excess: Commitment::from_hex(\"{}\").unwrap(),",
+        kernel.excess.to_hex()
+    );
+    let _ = writeln!(s, "                excess_sig: Signature::new(");
+    let _ = writeln!(
+        s,
+        "                    PublicKey::from_hex(\"{}\").unwrap(),",
+        kernel.excess_sig.get_public_nonce().to_hex()
+    );
+    let _ = writeln!(
+        s,
+        "                    PrivateKey::from_hex(\"{}\").unwrap(),",
+        kernel.excess_sig.get_signature().to_hex()
+    );
+    let _ = writeln!(s, "                ),");
+    let _ = writeln!(s, "            }},");
+}
+
+/// Load a genesis block from a JSON file written by [GeneratedGenesisBlock::to_json]
+pub fn load_genesis_block<P: AsRef<Path>>(path: P) -> Result<Block, GenesisBlockGeneratorError> {
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate() {
+        let factories = CryptoFactories::default();
+        let values = [MicroTari(1_000), MicroTari(2_000)];
+        let generator = GenesisBlockGenerator::new(ConsensusConstants::localnet(), b"localnet")
+            .with_timestamp(1_585_476_000.into())
+            .with_utxos(&values);
+        let genesis = generator.generate(&factories).unwrap();
+        let block = &genesis.block;
+        assert_eq!(block.header.height, 0);
+        assert_eq!(block.body.outputs().len(), 3);
+        assert_eq!(block.body.kernels().len(), 1);
+        assert_eq!(genesis.outputs.len(), 3);
+        assert_eq!(genesis.outputs[1].value, values[0]);
+        assert!(block.body.kernels()[0].verify_signature().is_ok());
+
+        // The same seed produces the same outputs and kernel
+        let again = generator.generate(&factories).unwrap();
+        assert_eq!(again.block.header.output_mr, block.header.output_mr);
+        assert_eq!(again.block.header.kernel_mr, block.header.kernel_mr);
+        let other = GenesisBlockGenerator::new(ConsensusConstants::localnet(), b"other")
+            .generate(&factories)
+            .unwrap();
+        assert_ne!(other.block.header.output_mr, block.header.output_mr);
+
+        let loaded: Block = serde_json::from_str(&genesis.to_json().unwrap()).unwrap();
+        assert_eq!(&loaded, block);
+        assert!(genesis
+            .to_rust_snippet("get_localnet_genesis_block")
+            .contains("COINBASE_KERNEL"));
+    }
+}
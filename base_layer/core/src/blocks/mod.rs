@@ -26,6 +26,7 @@ mod new_block_template;
 mod new_blockheader_template;
 
 pub mod genesis_block;
+pub mod genesis_block_generator;
 
 pub use block::{Block, BlockBuilder, BlockValidationError};
 pub use blockheader::{BlockHash, BlockHeader, BlockHeaderValidationError};
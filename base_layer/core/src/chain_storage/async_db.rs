@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    base_node::BlockFilter,
     blocks::{Block, BlockHeader, NewBlockTemplate},
     chain_storage::{
         blockchain_database::BlockAddResult,
@@ -33,7 +34,7 @@ use crate::{
     },
     transactions::{
         transaction::{TransactionKernel, TransactionOutput},
-        types::HashOutput,
+        types::{HashOutput, Signature},
     },
 };
 use log::*;
@@ -93,6 +94,7 @@ macro_rules! make_async {
 
 make_async!(get_metadata() -> ChainMetadata, "get_metadata");
 make_async!(fetch_kernel(hash: HashOutput) -> TransactionKernel, "fetch_kernel");
+make_async!(search_kernels(signatures: Vec<Signature>) -> Vec<(TransactionKernel, BlockHeader)>, "search_kernels");
 make_async!(fetch_header_with_block_hash(hash: HashOutput) -> BlockHeader, "fetch_header_with_block_hash");
 make_async!(fetch_header(block_num: u64) -> BlockHeader, "fetch_header");
 make_async!(fetch_utxo(hash: HashOutput) -> TransactionOutput, "fetch_utxo");
@@ -107,6 +109,7 @@ make_async!(calculate_mmr_roots(template: NewBlockTemplate) -> Block, "calculate
 
 // make_async!(is_new_best_block(block: &Block) -> bool);
 make_async!(fetch_block(height: u64) -> HistoricalBlock, "fetch_block");
+make_async!(fetch_block_filter(height: u64) -> BlockFilter, "fetch_block_filter");
 make_async!(fetch_block_with_hash(hash: HashOutput) -> Option<HistoricalBlock>, "fetch_block_with_hash");
 make_async!(rewind_to_height(height: u64) -> Vec<Block>, "rewind_to_height");
 make_async!(fetch_mmr_proof(tree: MmrTree, pos: usize) -> MerkleProof, "fetch_mmr_proof");
@@ -21,9 +21,10 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    base_node::BlockFilter,
     blocks::{blockheader::BlockHash, Block, BlockHeader, NewBlockTemplate},
     chain_storage::{
-        db_transaction::{DbKey, DbKeyValuePair, DbTransaction, DbValue, MetadataKey, MetadataValue, MmrTree},
+        db_transaction::{DbKey, DbTransaction, DbValue, MetadataKey, MetadataValue, MmrTree},
         error::ChainStorageError,
         ChainMetadata,
         HistoricalBlock,
@@ -32,17 +33,25 @@ use crate::{
     proof_of_work::{Difficulty, ProofOfWork},
     transactions::{
         transaction::{TransactionInput, TransactionKernel, TransactionOutput},
-        types::{BlindingFactor, Commitment, CommitmentFactory, HashOutput},
+        types::{BlindingFactor, Commitment, CommitmentFactory, HashOutput, Signature},
+    },
+    validation::{
+        timings::{time_stage, BlockValidationTimer, ValidationStage},
+        StatelessValidation,
+        StatelessValidator,
+        Validation,
+        ValidationError,
+        Validator,
     },
-    validation::{StatelessValidation, StatelessValidator, Validation, ValidationError, Validator},
 };
 use croaring::Bitmap;
 use log::*;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     ops::DerefMut,
     sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Duration,
 };
 use strum_macros::Display;
 use tari_crypto::{
@@ -50,6 +59,7 @@ use tari_crypto::{
     tari_utilities::{hex::Hex, Hashable},
 };
 use tari_mmr::{Hash, MerkleCheckPoint, MerkleProof, MutableMmrLeafNodes};
+use tracing::debug_span;
 
 const LOG_TARGET: &str = "c::cs::database";
 
@@ -105,11 +115,23 @@ impl<B: BlockchainBackend> Clone for Validators<B> {
 /// Data is passed to and from the backend via the [DbKey], [DbValue], and [DbValueKey] enums. This strategy allows
 /// us to keep the reading and writing API extremely simple. Extending the types of data that the back ends can handle
 /// will entail adding to those enums, and the back ends, while this trait can remain unchanged.
+///
+/// LMDB is the default backend, with an in-memory backend available for testing. To add another backend (e.g. RocksDB
+/// or sled), implement this trait alongside `lmdb_db` and `memory_db`, add a `DatabaseType` variant so that it can be
+/// selected with the `db_type` config setting, and invoke `backend_conformance_tests!` for it in the
+/// `chain_backend` integration tests. Nothing outside of `chain_storage` should depend on a concrete backend.
 pub trait BlockchainBackend: Send + Sync {
     /// Commit the transaction given to the backend. If there is an error, the transaction must be rolled back, and
     /// the error condition returned. On success, every operation in the transaction will have been committed, and
     /// the function will return `Ok(())`.
     fn write(&mut self, tx: DbTransaction) -> Result<(), ChainStorageError>;
+    /// Start a group commit. Transactions written before `end_group_commit` is called must still be committed
+    /// atomically, one at a time, but the backend may defer flushing them to durable storage until the group ends. A
+    /// crash during a group commit may lose the most recent transactions of the group, but must never leave a
+    /// partially written transaction behind.
+    fn begin_group_commit(&mut self) -> Result<(), ChainStorageError>;
+    /// End the current group commit and flush all of its transactions to durable storage.
+    fn end_group_commit(&mut self) -> Result<(), ChainStorageError>;
     /// Fetch a value from the back end corresponding to the given key. If the value is not found, `get` must return
     /// `Ok(None)`. It should only error if there is an access or integrity issue with the underlying back end.
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, ChainStorageError>;
@@ -219,6 +241,7 @@ where T: BlockchainBackend
     metadata: Arc<RwLock<ChainMetadata>>,
     db: Arc<RwLock<T>>,
     validators: Validators<T>,
+    validation_log_threshold: Option<Duration>,
 }
 
 impl<T> BlockchainDatabase<T>
@@ -236,22 +259,25 @@ where T: BlockchainBackend
             metadata: Arc::new(RwLock::new(metadata)),
             db: Arc::new(RwLock::new(db)),
             validators,
+            validation_log_threshold: None,
         };
         if blockchain_db.get_height()?.is_none() {
             let genesis_block = consensus_manager.get_genesis_block();
             let genesis_block_hash = genesis_block.hash();
-            let mut pow = genesis_block.header.pow.clone();
-            pow.add_difficulty(
-                &genesis_block.header.pow,
-                ProofOfWork::achieved_difficulty(&genesis_block.header),
-            );
-            let pow = pow.total_accumulated_difficulty();
+            let pow = genesis_block.header.total_accumulated_difficulty_inclusive();
             blockchain_db.store_new_block(genesis_block)?;
             blockchain_db.update_metadata(0, genesis_block_hash, pow)?;
         }
         Ok(blockchain_db)
     }
 
+    /// Log the per-stage validation timings of every block that takes longer than `threshold` to be added. The timings
+    /// are always recorded in the `tari_base_node_block_validation_duration_seconds` metric.
+    pub fn with_validation_log_threshold(mut self, threshold: Option<Duration>) -> Self {
+        self.validation_log_threshold = threshold;
+        self
+    }
+
     /// Reads the blockchain metadata (block height etc) from the underlying backend and returns it.
     /// If the metadata values aren't in the database, (e.g. when running a node for the first time),
     /// then log as much and return a reasonable default.
@@ -379,8 +405,8 @@ where T: BlockchainBackend
     ) -> Result<(), ChainStorageError>
     {
         let mut metadata = self.metadata_write_access()?;
-        let mut db = self.db_write_access()?;
-        update_metadata(&mut metadata, &mut db, new_height, new_hash, accumulated_difficulty)
+        update_metadata(&mut metadata, new_height, new_hash, accumulated_difficulty);
+        Ok(())
     }
 
     /// Returns the height of the current longest chain. This method will only fail if there's a fairly serious
@@ -412,6 +438,17 @@ where T: BlockchainBackend
         fetch_kernel(&*db, hash)
     }
 
+    /// Returns the main chain kernels with the given excess signatures, each with the header of the block that contains
+    /// it. Signatures that do not match a kernel are left out of the result.
+    pub fn search_kernels(
+        &self,
+        signatures: Vec<Signature>,
+    ) -> Result<Vec<(TransactionKernel, BlockHeader)>, ChainStorageError>
+    {
+        let db = self.db_read_access()?;
+        search_kernels(&*db, signatures)
+    }
+
     /// Returns the block header at the given block height.
     pub fn fetch_header(&self, block_num: u64) -> Result<BlockHeader, ChainStorageError> {
         let db = self.db_read_access()?;
@@ -518,6 +555,9 @@ where T: BlockchainBackend
     ///
     /// If an error does occur while writing the new block parts, all changes are reverted before returning.
     pub fn add_block(&self, block: Block) -> Result<BlockAddResult, ChainStorageError> {
+        let span = debug_span!("add_block", height = block.header.height);
+        let _enter = span.enter();
+        let _timer = BlockValidationTimer::start(&block, self.validation_log_threshold);
         // Perform orphan block validation.
         self.validators
             .orphan
@@ -529,6 +569,41 @@ where T: BlockchainBackend
         add_block(&mut metadata, &mut db, &self.validators.block, block)
     }
 
+    /// Adds a sequence of blocks, such as a batch received during block sync, to the database. The blocks are added
+    /// in order using the same rules as [BlockchainDatabase::add_block], but the write lock is held for the whole
+    /// sequence and the backend group-commits the writes: every block is still stored atomically, but flushing to
+    /// durable storage is done once for the group instead of once per block.
+    ///
+    /// Processing stops at the first block that could not be added. The returned list contains the result for every
+    /// block that was processed, so the last entry is the error if one occurred.
+    pub fn add_blocks(
+        &self,
+        blocks: Vec<Block>,
+    ) -> Result<Vec<Result<BlockAddResult, ChainStorageError>>, ChainStorageError> {
+        let mut metadata = self.metadata_write_access()?;
+        let mut db = self.db_write_access()?;
+        db.begin_group_commit()?;
+        let mut results = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let span = debug_span!("add_block", height = block.header.height);
+            let _enter = span.enter();
+            let _timer = BlockValidationTimer::start(&block, self.validation_log_threshold);
+            let result = self
+                .validators
+                .orphan
+                .validate(&block)
+                .map_err(ChainStorageError::ValidationError)
+                .and_then(|_| add_block(&mut metadata, &mut db, &self.validators.block, block));
+            let is_err = result.is_err();
+            results.push(result);
+            if is_err {
+                break;
+            }
+        }
+        db.end_group_commit()?;
+        Ok(results)
+    }
+
     fn store_new_block(&self, block: Block) -> Result<(), ChainStorageError> {
         let mut db = self.db_write_access()?;
         store_new_block(&mut db, block)
@@ -563,6 +638,13 @@ where T: BlockchainBackend
         fetch_block(&*db, height)
     }
 
+    /// Returns the compact filter of the block at the given height, which light clients use to determine whether a
+    /// block is relevant to them without downloading it.
+    pub fn fetch_block_filter(&self, height: u64) -> Result<BlockFilter, ChainStorageError> {
+        let db = self.db_read_access()?;
+        fetch_block_filter(&*db, height)
+    }
+
     /// Attempt to fetch the block corresponding to the provided hash from the main chain, if it cannot be found then
     /// the block will be searched in the orphan block pool.
     pub fn fetch_block_with_hash(&self, hash: HashOutput) -> Result<Option<HistoricalBlock>, ChainStorageError> {
@@ -612,42 +694,82 @@ fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, ChainStorageError
     Err(ChainStorageError::UnexpectedResult(msg))
 }
 
-fn update_metadata<T: BlockchainBackend>(
+// Update the cached chain metadata. The persisted metadata is written in the same transaction as the blocks that move
+// the chain tip, so this only needs to be called once those have been committed.
+fn update_metadata(
     metadata: &mut RwLockWriteGuard<ChainMetadata>,
-    db: &mut RwLockWriteGuard<T>,
     new_height: u64,
     new_hash: Vec<u8>,
     accumulated_difficulty: Difficulty,
-) -> Result<(), ChainStorageError>
+)
 {
     metadata.height_of_longest_chain = Some(new_height);
     metadata.best_block = Some(new_hash);
     metadata.accumulated_difficulty = Some(accumulated_difficulty);
-
-    let mut txn = DbTransaction::new();
-    txn.insert(DbKeyValuePair::Metadata(
-        MetadataKey::ChainHeight,
-        MetadataValue::ChainHeight(metadata.height_of_longest_chain),
-    ));
-    txn.insert(DbKeyValuePair::Metadata(
-        MetadataKey::BestBlock,
-        MetadataValue::BestBlock(metadata.best_block.clone()),
-    ));
-    txn.insert(DbKeyValuePair::Metadata(
-        MetadataKey::AccumulatedWork,
-        MetadataValue::AccumulatedWork(metadata.accumulated_difficulty),
-    ));
-    commit(db, txn)
 }
 
 fn fetch_kernel<T: BlockchainBackend>(db: &T, hash: HashOutput) -> Result<TransactionKernel, ChainStorageError> {
     fetch!(db, hash, TransactionKernel)
 }
 
+fn search_kernels<T: BlockchainBackend>(
+    db: &T,
+    signatures: Vec<Signature>,
+) -> Result<Vec<(TransactionKernel, BlockHeader)>, ChainStorageError>
+{
+    if signatures.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut kernels = HashMap::new();
+    let mut error = None;
+    db.for_each_kernel(|pair| match pair {
+        Ok((hash, kernel)) => {
+            if signatures.contains(&kernel.excess_sig) {
+                kernels.insert(hash, kernel);
+            }
+        },
+        Err(e) => {
+            error.get_or_insert(e);
+        },
+    })?;
+    if let Some(e) = error {
+        return Err(e);
+    }
+
+    // The kernel checkpoint of each block lists the kernels it added. Payments being verified are usually recent, so
+    // walk back from the tip and stop as soon as every matching kernel has been placed in a block.
+    let mut results = Vec::with_capacity(kernels.len());
+    if kernels.is_empty() {
+        return Ok(results);
+    }
+    let tip_height = fetch_tip_header(db)?.height;
+    for height in (0..=tip_height).rev() {
+        for hash in fetch_checkpoint(db, MmrTree::Kernel, height)?.nodes_added() {
+            if let Some(kernel) = kernels.remove(hash) {
+                results.push((kernel, fetch_header(db, height)?));
+            }
+        }
+        if kernels.is_empty() {
+            break;
+        }
+    }
+    Ok(results)
+}
+
 pub fn fetch_header<T: BlockchainBackend>(db: &T, block_num: u64) -> Result<BlockHeader, ChainStorageError> {
     fetch!(db, block_num, BlockHeader)
 }
 
+fn fetch_block_filter<T: BlockchainBackend>(db: &T, height: u64) -> Result<BlockFilter, ChainStorageError> {
+    match db.fetch(&DbKey::BlockFilter(height))? {
+        Some(DbValue::BlockFilter(filter)) => Ok(*filter),
+        Some(other) => unexpected_result(DbKey::BlockFilter(height), other),
+        // Blocks stored before block filters were introduced do not have one, so build it from the block if it has
+        // not been pruned
+        None => Ok(BlockFilter::from_block(fetch_block(db, height)?.block())),
+    }
+}
+
 fn fetch_header_with_block_hash<T: BlockchainBackend>(
     db: &T,
     hash: HashOutput,
@@ -731,15 +853,26 @@ fn add_block<T: BlockchainBackend>(
 }
 
 fn store_new_block<T: BlockchainBackend>(db: &mut RwLockWriteGuard<T>, block: Block) -> Result<(), ChainStorageError> {
+    commit(db, new_block_txn(block))
+}
+
+// Build all the DB queries needed to add the block as the new chain tip, so that the header, UTXO set changes, MMR
+// checkpoints and chain metadata are committed atomically.
+fn new_block_txn(block: Block) -> DbTransaction {
+    let filter = BlockFilter::from_block(&block);
+    let block_hash = block.hash();
     let (header, inputs, outputs, kernels) = block.dissolve();
-    // Build all the DB queries needed to add the block and the add it atomically
+    let height = header.height;
+    let accumulated_difficulty = header.total_accumulated_difficulty_inclusive();
     let mut txn = DbTransaction::new();
     txn.insert_header(header);
+    txn.insert_block_filter(filter);
     txn.spend_inputs(&inputs);
     outputs.iter().for_each(|utxo| txn.insert_utxo(utxo.clone(), true));
     kernels.iter().for_each(|k| txn.insert_kernel(k.clone(), true));
     txn.commit_block();
-    commit(db, txn)
+    txn.set_chain_metadata(height, block_hash, accumulated_difficulty);
+    txn
 }
 
 fn is_at_chain_tip<T: BlockchainBackend>(
@@ -890,6 +1023,7 @@ fn rewind_to_height<T: BlockchainBackend>(
 
         // Remove Header and block hash
         txn.delete(DbKey::BlockHeader(rewind_height)); // Will also delete the blockhash
+        txn.delete(DbKey::BlockFilter(rewind_height));
 
         // Remove Kernels
         fetch_checkpoint(&**db, MmrTree::Kernel, rewind_height)?
@@ -916,12 +1050,13 @@ fn rewind_to_height<T: BlockchainBackend>(
     txn.rewind_kernel_mmr(steps_back);
     txn.rewind_utxo_mmr(steps_back);
     txn.rewind_rp_mmr(steps_back);
+    // Move the chain tip back in the same transaction
+    let last_header = fetch_header(&**db, height)?;
+    let pow = last_header.total_accumulated_difficulty_inclusive();
+    txn.set_chain_metadata(height, last_header.hash(), pow);
     commit(db, txn)?;
 
-    let last_header = fetch_header(&**db, height)?;
-    let pow = ProofOfWork::new_from_difficulty(&last_header.pow, ProofOfWork::achieved_difficulty(&last_header));
-    let pow = pow.total_accumulated_difficulty();
-    update_metadata(metadata, db, height, last_header.hash(), pow)?;
+    update_metadata(metadata, height, last_header.hash(), pow);
 
     Ok(removed_blocks)
 }
@@ -1021,7 +1156,7 @@ fn handle_reorg<T: BlockchainBackend>(
             .height -
             1;
         let removed_blocks = reorganize_chain(metadata, db, block_validator, fork_height, reorg_chain)?;
-        update_metadata(metadata, db, fork_tip_header.height, fork_tip_hash, pow)?;
+        update_metadata(metadata, fork_tip_header.height, fork_tip_hash, pow);
         if removed_blocks.is_empty() {
             return Ok(BlockAddResult::Ok);
         } else {
@@ -1055,10 +1190,8 @@ fn reorganize_chain<T: BlockchainBackend>(
     let removed_blocks = rewind_to_height(metadata, db, height)?;
     trace!(target: LOG_TARGET, "Validate and add chain blocks.",);
     let mut validation_result: Result<(), ValidationError> = Ok(());
-    let mut orphan_hashes = Vec::<BlockHash>::with_capacity(chain.len());
     for block in chain {
         let block_hash = block.hash();
-        orphan_hashes.push(block_hash.clone());
         validation_result = block_validator.validate(&block, db, metadata);
         if validation_result.is_err() {
             debug!(
@@ -1069,21 +1202,14 @@ fn reorganize_chain<T: BlockchainBackend>(
             remove_orphan(db, block.hash())?;
             break;
         }
-        store_new_block(db, block)?;
+        // The block leaves the orphan pool in the same transaction that adds it to the main chain
+        let mut txn = new_block_txn(block);
+        txn.delete(DbKey::OrphanBlock(block_hash));
+        time_stage(ValidationStage::Commit, || commit(db, txn))?;
     }
 
     match validation_result {
-        Ok(_) => {
-            trace!(target: LOG_TARGET, "Removing reorged orphan blocks.",);
-            if !orphan_hashes.is_empty() {
-                let mut txn = DbTransaction::new();
-                for orphan_hash in orphan_hashes {
-                    txn.delete(DbKey::OrphanBlock(orphan_hash));
-                }
-                commit(db, txn)?;
-            }
-            Ok(removed_blocks)
-        },
+        Ok(_) => Ok(removed_blocks),
         Err(e) => {
             trace!(target: LOG_TARGET, "Restoring previous chain after failed reorg.",);
             let invalid_chain = rewind_to_height(metadata, db, height)?;
@@ -1095,12 +1221,16 @@ fn reorganize_chain<T: BlockchainBackend>(
                     .map(|block| block.hash().to_hex())
                     .collect::<Vec<_>>(),
             );
-            let mut txn = DbTransaction::new();
-            for block in removed_blocks {
-                txn.delete(DbKey::OrphanBlock(block.hash()));
-                store_new_block(db, block)?;
+            // The removed blocks are listed from the old tip downwards, so they are restored in reverse.
+            for block in removed_blocks.into_iter().rev() {
+                let block_hash = block.hash();
+                let height = block.header.height;
+                let pow = block.header.total_accumulated_difficulty_inclusive();
+                let mut txn = new_block_txn(block);
+                txn.delete(DbKey::OrphanBlock(block_hash.clone()));
+                commit(db, txn)?;
+                update_metadata(metadata, height, block_hash, pow);
             }
-            commit(db, txn)?;
             Err(ChainStorageError::ValidationError(e))
         },
     }
@@ -1110,7 +1240,7 @@ fn reorganize_chain<T: BlockchainBackend>(
 fn insert_orphan<T: BlockchainBackend>(db: &mut RwLockWriteGuard<T>, block: Block) -> Result<(), ChainStorageError> {
     let mut txn = DbTransaction::new();
     txn.insert_orphan(block);
-    commit(db, txn)
+    time_stage(ValidationStage::Commit, || commit(db, txn))
 }
 
 // Discard the the orphan block from the orphan pool that corresponds to the provided block hash.
@@ -1303,6 +1433,7 @@ where T: BlockchainBackend
             metadata: self.metadata.clone(),
             db: self.db.clone(),
             validators: self.validators.clone(),
+            validation_log_threshold: self.validation_log_threshold,
         }
     }
 }
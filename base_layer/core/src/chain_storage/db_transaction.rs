@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    base_node::BlockFilter,
     blocks::{blockheader::BlockHash, Block, BlockHeader},
     proof_of_work::Difficulty,
     transactions::{
@@ -85,6 +86,12 @@ impl DbTransaction {
         self.insert(DbKeyValuePair::BlockHeader(height, Box::new(header)));
     }
 
+    /// Inserts the compact filter of a block into the current transaction.
+    pub fn insert_block_filter(&mut self, filter: BlockFilter) {
+        let height = filter.height;
+        self.insert(DbKeyValuePair::BlockFilter(height, Box::new(filter)));
+    }
+
     /// Adds a UTXO into the current transaction and update the TXO MMR.
     pub fn insert_utxo(&mut self, utxo: TransactionOutput, update_mmr: bool) {
         let hash = utxo.hash();
@@ -150,6 +157,23 @@ impl DbTransaction {
         )));
     }
 
+    /// Sets the chain tip metadata. Adding it to the same transaction as the blocks that move the chain tip ensures
+    /// that the stored metadata can never disagree with the stored chain.
+    pub fn set_chain_metadata(&mut self, height: u64, best_block: BlockHash, accumulated_difficulty: Difficulty) {
+        self.insert(DbKeyValuePair::Metadata(
+            MetadataKey::ChainHeight,
+            MetadataValue::ChainHeight(Some(height)),
+        ));
+        self.insert(DbKeyValuePair::Metadata(
+            MetadataKey::BestBlock,
+            MetadataValue::BestBlock(Some(best_block)),
+        ));
+        self.insert(DbKeyValuePair::Metadata(
+            MetadataKey::AccumulatedWork,
+            MetadataValue::AccumulatedWork(Some(accumulated_difficulty)),
+        ));
+    }
+
     /// Rewinds the Kernel MMR state by the given number of Checkpoints.
     pub fn rewind_kernel_mmr(&mut self, steps_back: usize) {
         self.operations
@@ -187,6 +211,7 @@ pub enum DbKeyValuePair {
     UnspentOutput(HashOutput, Box<TransactionOutput>, bool),
     TransactionKernel(HashOutput, Box<TransactionKernel>, bool),
     OrphanBlock(HashOutput, Box<Block>),
+    BlockFilter(u64, Box<BlockFilter>),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -221,6 +246,7 @@ pub enum DbKey {
     SpentOutput(HashOutput),
     TransactionKernel(HashOutput),
     OrphanBlock(HashOutput),
+    BlockFilter(u64),
 }
 
 #[derive(Debug)]
@@ -232,6 +258,7 @@ pub enum DbValue {
     SpentOutput(Box<TransactionOutput>),
     TransactionKernel(Box<TransactionKernel>),
     OrphanBlock(Box<Block>),
+    BlockFilter(Box<BlockFilter>),
 }
 
 impl Display for DbValue {
@@ -247,6 +274,7 @@ impl Display for DbValue {
             DbValue::SpentOutput(_) => f.write_str("Spent output"),
             DbValue::TransactionKernel(_) => f.write_str("Transaction kernel"),
             DbValue::OrphanBlock(_) => f.write_str("Orphan block"),
+            DbValue::BlockFilter(_) => f.write_str("Block filter"),
         }
     }
 }
@@ -264,6 +292,7 @@ impl Display for DbKey {
             DbKey::SpentOutput(v) => f.write_str(&format!("Spent output ({})", to_hex(v))),
             DbKey::TransactionKernel(v) => f.write_str(&format!("Transaction kernel ({})", to_hex(v))),
             DbKey::OrphanBlock(v) => f.write_str(&format!("Orphan block hash ({})", to_hex(v))),
+            DbKey::BlockFilter(v) => f.write_str(&format!("Block filter (#{})", v)),
         }
     }
 }
@@ -25,6 +25,7 @@ use lmdb_zero::{
     error::{self, LmdbResultExt},
     put,
     ConstAccessor,
+    ConstTransaction,
     Cursor,
     CursorIter,
     Database,
@@ -126,6 +127,46 @@ where K: Serialize {
     Ok(res)
 }
 
+/// Fetch a value from within an open transaction. Unlike `lmdb_get`, a write transaction will see its own
+/// uncommitted changes.
+pub fn lmdb_txn_get<K, V>(txn: &ConstTransaction, db: &Database, key: &K) -> Result<Option<V>, ChainStorageError>
+where
+    K: Serialize,
+    V: DeserializeOwned,
+{
+    let access = txn.access();
+    let key_buf = serialize(key)?;
+    match access.get(&db, &key_buf).to_opt() {
+        Ok(None) => Ok(None),
+        Err(e) => Err(ChainStorageError::AccessError(e.to_string())),
+        Ok(Some(v)) => match deserialize(v) {
+            Ok(val) => Ok(Some(val)),
+            Err(e) => Err(ChainStorageError::AccessError(e.to_string())),
+        },
+    }
+}
+
+/// Check whether a key exists from within an open transaction.
+pub fn lmdb_txn_exists<K>(txn: &ConstTransaction, db: &Database, key: &K) -> Result<bool, ChainStorageError>
+where K: Serialize {
+    let access = txn.access();
+    let key_buf = serialize(key)?;
+    let res: error::Result<&Ignore> = access.get(&db, &key_buf);
+    let res = res
+        .to_opt()
+        .map_err(|e| ChainStorageError::AccessError(e.to_string()))?
+        .is_some();
+    Ok(res)
+}
+
+/// Return the number of entries in the database from within an open transaction.
+pub fn lmdb_txn_len(txn: &ConstTransaction, db: &Database) -> Result<usize, ChainStorageError> {
+    let stats = txn
+        .db_stat(&db)
+        .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+    Ok(stats.entries)
+}
+
 pub fn lmdb_len(env: &Environment, db: &Database) -> Result<usize, ChainStorageError> {
     let txn = ReadTransaction::new(env).map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
     let stats = txn
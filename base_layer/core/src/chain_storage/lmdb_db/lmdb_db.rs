@@ -21,14 +21,26 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    base_node::BlockFilter,
     blocks::{blockheader::BlockHeader, Block},
     chain_storage::{
         blockchain_database::BlockchainBackend,
         db_transaction::{DbKey, DbKeyValuePair, DbTransaction, DbValue, MetadataValue, MmrTree, WriteOperation},
         error::ChainStorageError,
         lmdb_db::{
-            lmdb::{lmdb_delete, lmdb_exists, lmdb_for_each, lmdb_get, lmdb_insert, lmdb_len, lmdb_replace},
+            lmdb::{
+                lmdb_delete,
+                lmdb_exists,
+                lmdb_for_each,
+                lmdb_get,
+                lmdb_insert,
+                lmdb_len,
+                lmdb_replace,
+                lmdb_txn_exists,
+                lmdb_txn_get,
+            },
             LMDBVec,
+            LMDB_DB_BLOCK_FILTERS,
             LMDB_DB_BLOCK_HASHES,
             LMDB_DB_HEADERS,
             LMDB_DB_KERNELS,
@@ -50,14 +62,13 @@ use crate::{
 };
 use croaring::Bitmap;
 use digest::Digest;
-use lmdb_zero::{Database, Environment, WriteTransaction};
+use lmdb_zero::{open, ConstTransaction, Database, Environment, WriteTransaction};
 use log::*;
 use std::{path::Path, sync::Arc};
 use tari_crypto::tari_utilities::hash::Hashable;
 use tari_mmr::{
     functions::{prune_mutable_mmr, PrunedMutableMmr},
     ArrayLike,
-    ArrayLikeExt,
     Hash as MmrHash,
     MerkleCheckPoint,
     MerkleProof,
@@ -83,6 +94,7 @@ where D: Digest
     txos_hash_to_index_db: DatabaseRef,
     kernels_db: DatabaseRef,
     orphans_db: DatabaseRef,
+    block_filters_db: DatabaseRef,
     utxo_mmr: MmrCache<D, MemDbVec<MmrHash>, LMDBVec<MerkleCheckPoint>>,
     utxo_checkpoints: LMDBVec<MerkleCheckPoint>,
     curr_utxo_checkpoint: MerkleCheckPoint,
@@ -163,6 +175,11 @@ where D: Digest + Send + Sync
                 .ok_or_else(|| ChainStorageError::CriticalError)?
                 .db()
                 .clone(),
+            block_filters_db: store
+                .get_handle(LMDB_DB_BLOCK_FILTERS)
+                .ok_or_else(|| ChainStorageError::CriticalError)?
+                .db()
+                .clone(),
             utxo_mmr: MmrCache::new(MemDbVec::new(), utxo_checkpoints.clone(), mmr_cache_config)?,
             utxo_checkpoints,
             curr_utxo_checkpoint: MerkleCheckPoint::new(Vec::new(), Bitmap::create()),
@@ -176,87 +193,46 @@ where D: Digest + Send + Sync
         })
     }
 
-    // Perform the RewindMmr and CreateMmrCheckpoint operations after MMR txns and storage txns have been applied.
-    fn commit_mmrs(&mut self, tx: DbTransaction) -> Result<(), ChainStorageError> {
-        for op in tx.operations.into_iter() {
-            match op {
-                WriteOperation::RewindMmr(tree, steps_back) => match tree {
-                    MmrTree::Kernel => {
-                        self.curr_kernel_checkpoint.clear();
-                        let cp_count = self
-                            .kernel_checkpoints
-                            .len()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.kernel_checkpoints
-                            .truncate(rewind_checkpoint_index(cp_count, steps_back))
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.kernel_mmr
-                            .update()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                    },
-                    MmrTree::Utxo => {
-                        self.curr_utxo_checkpoint.clear();
-                        let cp_count = self
-                            .utxo_checkpoints
-                            .len()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.utxo_checkpoints
-                            .truncate(rewind_checkpoint_index(cp_count, steps_back))
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.utxo_mmr
-                            .update()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                    },
-                    MmrTree::RangeProof => {
-                        self.curr_range_proof_checkpoint.clear();
-                        let cp_count = self
-                            .range_proof_checkpoints
-                            .len()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.range_proof_checkpoints
-                            .truncate(rewind_checkpoint_index(cp_count, steps_back))
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.range_proof_mmr
-                            .update()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                    },
-                },
-                WriteOperation::CreateMmrCheckpoint(tree) => match tree {
-                    MmrTree::Kernel => {
-                        let curr_checkpoint = self.curr_kernel_checkpoint.clone();
-                        self.kernel_checkpoints
-                            .push(curr_checkpoint)
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.curr_kernel_checkpoint.clear();
-
-                        self.kernel_mmr
-                            .update()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                    },
-                    MmrTree::Utxo => {
-                        let curr_checkpoint = self.curr_utxo_checkpoint.clone();
-                        self.utxo_checkpoints
-                            .push(curr_checkpoint)
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.curr_utxo_checkpoint.clear();
+    // Returns the checkpoint backend of the specified MMR tree.
+    fn checkpoints(&self, tree: &MmrTree) -> &LMDBVec<MerkleCheckPoint> {
+        match tree {
+            MmrTree::Kernel => &self.kernel_checkpoints,
+            MmrTree::Utxo => &self.utxo_checkpoints,
+            MmrTree::RangeProof => &self.range_proof_checkpoints,
+        }
+    }
 
-                        self.utxo_mmr
-                            .update()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                    },
-                    MmrTree::RangeProof => {
-                        let curr_checkpoint = self.curr_range_proof_checkpoint.clone();
-                        self.range_proof_checkpoints
-                            .push(curr_checkpoint)
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                        self.curr_range_proof_checkpoint.clear();
+    // Returns the checkpoint that is currently being built up for the specified MMR tree.
+    fn curr_checkpoint_mut(&mut self, tree: &MmrTree) -> &mut MerkleCheckPoint {
+        match tree {
+            MmrTree::Kernel => &mut self.curr_kernel_checkpoint,
+            MmrTree::Utxo => &mut self.curr_utxo_checkpoint,
+            MmrTree::RangeProof => &mut self.curr_range_proof_checkpoint,
+        }
+    }
 
-                        self.range_proof_mmr
-                            .update()
-                            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
-                    },
-                },
-                _ => {},
+    // Bring the MMR caches up to date after a transaction has been committed. The caches are rebuilt if the
+    // transaction rewound any checkpoints.
+    fn update_mmrs(&mut self, tx: &DbTransaction) -> Result<(), ChainStorageError> {
+        for tree in &[MmrTree::Kernel, MmrTree::Utxo, MmrTree::RangeProof] {
+            let mut rewound = false;
+            let mut checkpointed = false;
+            for op in tx.operations.iter() {
+                match op {
+                    WriteOperation::RewindMmr(t, _) if t == tree => rewound = true,
+                    WriteOperation::CreateMmrCheckpoint(t) if t == tree => checkpointed = true,
+                    _ => {},
+                }
+            }
+            let mmr = match tree {
+                MmrTree::Kernel => &mut self.kernel_mmr,
+                MmrTree::Utxo => &mut self.utxo_mmr,
+                MmrTree::RangeProof => &mut self.range_proof_mmr,
+            };
+            if rewound {
+                mmr.reset()?;
+            } else if checkpointed {
+                mmr.update()?;
             }
         }
         Ok(())
@@ -271,11 +247,11 @@ where D: Digest + Send + Sync
         Ok(())
     }
 
-    // Perform all the storage txns and all MMR transactions excluding CreateMmrCheckpoint and RewindMmr on the
-    // header_mmr, utxo_mmr, range_proof_mmr and kernel_mmr. Only when all the txns can successfully be applied is the
-    // changes committed to the backend databases. CreateMmrCheckpoint and RewindMmr txns will be performed after these
-    // txns have been successfully applied.
-    fn apply_mmr_and_storage_txs(&mut self, tx: &DbTransaction) -> Result<(), ChainStorageError> {
+    // Perform all the storage txns, MMR additions and deletions, and the CreateMmrCheckpoint and RewindMmr txns in a
+    // single LMDB write transaction. All reads are done through the same transaction so that later operations see the
+    // effects of earlier ones. Either every change is committed to the backend databases or, if any operation fails,
+    // the LMDB transaction is dropped and none of them are.
+    fn apply_txs(&mut self, tx: &DbTransaction) -> Result<(), ChainStorageError> {
         let txn = WriteTransaction::new(self.env.clone()).map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
         {
             for op in tx.operations.iter() {
@@ -285,7 +261,7 @@ where D: Digest + Send + Sync
                             lmdb_replace(&txn, &self.metadata_db, &(k.clone() as u32), &v)?;
                         },
                         DbKeyValuePair::BlockHeader(k, v) => {
-                            if lmdb_exists(&self.env, &self.headers_db, &k)? {
+                            if lmdb_txn_exists(&txn, &self.headers_db, &k)? {
                                 return Err(ChainStorageError::InvalidOperation("Duplicate key".to_string()));
                             }
                             let hash = v.hash();
@@ -293,7 +269,7 @@ where D: Digest + Send + Sync
                             lmdb_insert(&txn, &self.headers_db, &k, &v)?;
                         },
                         DbKeyValuePair::UnspentOutput(k, v, update_mmr) => {
-                            if lmdb_exists(&self.env, &self.utxos_db, &k)? {
+                            if lmdb_txn_exists(&txn, &self.utxos_db, &k)? {
                                 return Err(ChainStorageError::InvalidOperation("Duplicate key".to_string()));
                             }
                             let proof_hash = v.proof().hash();
@@ -301,13 +277,13 @@ where D: Digest + Send + Sync
                                 self.curr_utxo_checkpoint.push_addition(k.clone());
                                 self.curr_range_proof_checkpoint.push_addition(proof_hash.clone());
                             }
-                            if let Some(index) = self.find_range_proof_leaf_index(proof_hash)? {
+                            if let Some(index) = self.find_range_proof_leaf_index(&txn, proof_hash)? {
                                 lmdb_insert(&txn, &self.utxos_db, &k, &v)?;
                                 lmdb_insert(&txn, &self.txos_hash_to_index_db, &k, &index)?;
                             }
                         },
                         DbKeyValuePair::TransactionKernel(k, v, update_mmr) => {
-                            if lmdb_exists(&self.env, &self.kernels_db, &k)? {
+                            if lmdb_txn_exists(&txn, &self.kernels_db, &k)? {
                                 return Err(ChainStorageError::InvalidOperation("Duplicate key".to_string()));
                             }
                             if *update_mmr {
@@ -318,11 +294,14 @@ where D: Digest + Send + Sync
                         DbKeyValuePair::OrphanBlock(k, v) => {
                             lmdb_replace(&txn, &self.orphans_db, &k, &v)?;
                         },
+                        DbKeyValuePair::BlockFilter(k, v) => {
+                            lmdb_replace(&txn, &self.block_filters_db, &k, &v)?;
+                        },
                     },
                     WriteOperation::Delete(delete) => match delete {
                         DbKey::Metadata(_) => {}, // no-op
                         DbKey::BlockHeader(k) => {
-                            let val: Option<BlockHeader> = lmdb_get(&self.env, &self.headers_db, &k)?;
+                            let val: Option<BlockHeader> = lmdb_txn_get(&txn, &self.headers_db, &k)?;
                             if let Some(v) = val {
                                 let hash = v.hash();
                                 lmdb_delete(&txn, &self.block_hashes_db, &hash)?;
@@ -330,7 +309,7 @@ where D: Digest + Send + Sync
                             }
                         },
                         DbKey::BlockHash(hash) => {
-                            let result: Option<u64> = lmdb_get(&self.env, &self.block_hashes_db, &hash)?;
+                            let result: Option<u64> = lmdb_txn_get(&txn, &self.block_hashes_db, &hash)?;
                             if let Some(k) = result {
                                 lmdb_delete(&txn, &self.block_hashes_db, &hash)?;
                                 lmdb_delete(&txn, &self.headers_db, &k)?;
@@ -350,10 +329,17 @@ where D: Digest + Send + Sync
                         DbKey::OrphanBlock(k) => {
                             lmdb_delete(&txn, &self.orphans_db, &k)?;
                         },
+                        DbKey::BlockFilter(k) => {
+                            // Blocks stored before block filters were introduced do not have one
+                            if lmdb_txn_exists(&txn, &self.block_filters_db, &k)? {
+                                lmdb_delete(&txn, &self.block_filters_db, &k)?;
+                            }
+                        },
                     },
                     WriteOperation::Spend(key) => match key {
                         DbKey::UnspentOutput(hash) => {
-                            let index_result: Option<usize> = lmdb_get(&self.env, &self.txos_hash_to_index_db, &hash)?;
+                            let index_result: Option<usize> =
+                                lmdb_txn_get(&txn, &self.txos_hash_to_index_db, &hash)?;
                             match index_result {
                                 Some(index) => {
                                     self.curr_utxo_checkpoint.push_deletion(index as u32);
@@ -361,7 +347,7 @@ where D: Digest + Send + Sync
                                 None => return Err(ChainStorageError::UnspendableInput),
                             }
 
-                            let utxo_result: Option<TransactionOutput> = lmdb_get(&self.env, &self.utxos_db, &hash)?;
+                            let utxo_result: Option<TransactionOutput> = lmdb_txn_get(&txn, &self.utxos_db, &hash)?;
                             match utxo_result {
                                 Some(utxo) => {
                                     lmdb_delete(&txn, &self.utxos_db, &hash)?;
@@ -374,7 +360,7 @@ where D: Digest + Send + Sync
                     },
                     WriteOperation::UnSpend(key) => match key {
                         DbKey::SpentOutput(hash) => {
-                            let stxo_result: Option<TransactionOutput> = lmdb_get(&self.env, &self.stxos_db, &hash)?;
+                            let stxo_result: Option<TransactionOutput> = lmdb_txn_get(&txn, &self.stxos_db, &hash)?;
                             match stxo_result {
                                 Some(stxo) => {
                                     lmdb_delete(&txn, &self.stxos_db, &hash)?;
@@ -385,7 +371,17 @@ where D: Digest + Send + Sync
                         },
                         _ => return Err(ChainStorageError::InvalidOperation("Only STXOs can be unspent".into())),
                     },
-                    _ => {},
+                    WriteOperation::CreateMmrCheckpoint(tree) => {
+                        let curr_checkpoint = self.curr_checkpoint_mut(tree).clone();
+                        self.checkpoints(tree).push_with_txn(&txn, curr_checkpoint)?;
+                        self.curr_checkpoint_mut(tree).clear();
+                    },
+                    WriteOperation::RewindMmr(tree, steps_back) => {
+                        self.curr_checkpoint_mut(tree).clear();
+                        let checkpoints = self.checkpoints(tree);
+                        let cp_count = checkpoints.len_with_txn(&txn)?;
+                        checkpoints.truncate_with_txn(&txn, rewind_checkpoint_index(cp_count, *steps_back))?;
+                    },
                 }
             }
         }
@@ -394,16 +390,17 @@ where D: Digest + Send + Sync
 
     // Returns the leaf index of the hash. If the hash is in the newly added hashes it returns the future MMR index for
     // that hash, this index is only valid if the change history is Committed.
-    fn find_range_proof_leaf_index(&self, hash: HashOutput) -> Result<Option<usize>, ChainStorageError> {
+    fn find_range_proof_leaf_index(
+        &self,
+        txn: &ConstTransaction,
+        hash: HashOutput,
+    ) -> Result<Option<usize>, ChainStorageError>
+    {
         let mut accum_leaf_index = 0;
-        for cp_index in 0..self
-            .range_proof_checkpoints
-            .len()
-            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?
-        {
+        for cp_index in 0..self.range_proof_checkpoints.len_with_txn(txn)? {
             if let Some(cp) = self
                 .range_proof_checkpoints
-                .get(cp_index)
+                .get_with_txn(txn, cp_index)
                 .map_err(|e| ChainStorageError::AccessError(format!("Checkpoint error: {}", e.to_string())))?
             {
                 if let Some(leaf_index) = cp.nodes_added().iter().position(|h| *h == hash) {
@@ -474,6 +471,7 @@ pub fn create_lmdb_database(
         .add_database(LMDB_DB_TXOS_HASH_TO_INDEX, flags)
         .add_database(LMDB_DB_KERNELS, flags)
         .add_database(LMDB_DB_ORPHANS, flags)
+        .add_database(LMDB_DB_BLOCK_FILTERS, flags)
         .add_database(LMDB_DB_UTXO_MMR_CP_BACKEND, flags)
         .add_database(LMDB_DB_KERNEL_MMR_CP_BACKEND, flags)
         .add_database(LMDB_DB_RANGE_PROOF_MMR_CP_BACKEND, flags)
@@ -486,13 +484,41 @@ impl<D> BlockchainBackend for LMDBDatabase<D>
 where D: Digest + Send + Sync
 {
     fn write(&mut self, tx: DbTransaction) -> Result<(), ChainStorageError> {
-        match self.apply_mmr_and_storage_txs(&tx) {
-            Ok(_) => self.commit_mmrs(tx),
-            Err(e) => {
-                self.reset_mmrs()?;
-                Err(e)
-            },
+        let curr_checkpoints = (
+            self.curr_kernel_checkpoint.clone(),
+            self.curr_utxo_checkpoint.clone(),
+            self.curr_range_proof_checkpoint.clone(),
+        );
+        if let Err(e) = self.apply_txs(&tx) {
+            // Nothing was written to the backend databases, only the pending checkpoints need to be rolled back.
+            let (kernel_cp, utxo_cp, range_proof_cp) = curr_checkpoints;
+            self.curr_kernel_checkpoint = kernel_cp;
+            self.curr_utxo_checkpoint = utxo_cp;
+            self.curr_range_proof_checkpoint = range_proof_cp;
+            return Err(e);
+        }
+        if let Err(e) = self.update_mmrs(&tx) {
+            self.reset_mmrs()?;
+            return Err(e);
         }
+        Ok(())
+    }
+
+    fn begin_group_commit(&mut self) -> Result<(), ChainStorageError> {
+        trace!(target: LOG_TARGET, "Beginning group commit");
+        // Transactions committed while NOSYNC is set are still atomic, they are just not flushed to disk. Only this
+        // backend changes the environment flags, and it is only ever called while holding the database write lock.
+        unsafe { self.env.set_flags(open::NOSYNC, true) }.map_err(|e| ChainStorageError::AccessError(e.to_string()))
+    }
+
+    fn end_group_commit(&mut self) -> Result<(), ChainStorageError> {
+        unsafe { self.env.set_flags(open::NOSYNC, false) }
+            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+        self.env
+            .sync(true)
+            .map_err(|e| ChainStorageError::AccessError(e.to_string()))?;
+        trace!(target: LOG_TARGET, "Group commit flushed to disk");
+        Ok(())
     }
 
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, ChainStorageError> {
@@ -531,6 +557,10 @@ where D: Digest + Send + Sync
                 let val: Option<Block> = lmdb_get(&self.env, &self.orphans_db, k)?;
                 val.map(|val| DbValue::OrphanBlock(Box::new(val)))
             },
+            DbKey::BlockFilter(k) => {
+                let val: Option<BlockFilter> = lmdb_get(&self.env, &self.block_filters_db, k)?;
+                val.map(|val| DbValue::BlockFilter(Box::new(val)))
+            },
         })
     }
 
@@ -543,6 +573,7 @@ where D: Digest + Send + Sync
             DbKey::SpentOutput(k) => lmdb_exists(&self.env, &self.stxos_db, k)?,
             DbKey::TransactionKernel(k) => lmdb_exists(&self.env, &self.kernels_db, k)?,
             DbKey::OrphanBlock(k) => lmdb_exists(&self.env, &self.orphans_db, k)?,
+            DbKey::BlockFilter(k) => lmdb_exists(&self.env, &self.block_filters_db, k)?,
         })
     }
 
@@ -22,10 +22,10 @@
 
 use crate::chain_storage::{
     error::ChainStorageError,
-    lmdb_db::lmdb::{lmdb_clear_db, lmdb_delete, lmdb_get, lmdb_insert, lmdb_len},
+    lmdb_db::lmdb::{lmdb_clear_db, lmdb_delete, lmdb_get, lmdb_insert, lmdb_len, lmdb_txn_get, lmdb_txn_len},
 };
 use derive_error::Error;
-use lmdb_zero::{Database, Environment, WriteTransaction};
+use lmdb_zero::{ConstTransaction, Database, Environment, WriteTransaction};
 use std::{cmp::min, marker::PhantomData, sync::Arc};
 use tari_crypto::tari_utilities::message_format::MessageFormatError;
 use tari_mmr::{error::MerkleMountainRangeError, ArrayLike, ArrayLikeExt};
@@ -54,6 +54,39 @@ impl<T> LMDBVec<T> {
     }
 }
 
+impl<T> LMDBVec<T>
+where
+    T: serde::Serialize,
+    for<'t> T: serde::de::DeserializeOwned,
+{
+    /// Returns the number of elements as seen by the provided transaction.
+    pub fn len_with_txn(&self, txn: &ConstTransaction) -> Result<usize, ChainStorageError> {
+        lmdb_txn_len(txn, &self.db)
+    }
+
+    /// Returns the element at the given index as seen by the provided transaction.
+    pub fn get_with_txn(&self, txn: &ConstTransaction, index: usize) -> Result<Option<T>, ChainStorageError> {
+        lmdb_txn_get::<usize, T>(txn, &self.db, &index)
+    }
+
+    /// Appends an element as part of the provided write transaction. The element only becomes visible to other
+    /// readers once the transaction is committed.
+    pub fn push_with_txn(&self, txn: &WriteTransaction, item: T) -> Result<usize, ChainStorageError> {
+        let index = self.len_with_txn(txn)?;
+        lmdb_insert::<usize, T>(txn, &self.db, &index, &item)?;
+        Ok(index)
+    }
+
+    /// Shortens the vector to `len` elements as part of the provided write transaction.
+    pub fn truncate_with_txn(&self, txn: &WriteTransaction, len: usize) -> Result<(), ChainStorageError> {
+        let n_elements = self.len_with_txn(txn)?;
+        for index in len..n_elements {
+            lmdb_delete(txn, &self.db, &index)?;
+        }
+        Ok(())
+    }
+}
+
 impl<T> ArrayLike for LMDBVec<T>
 where
     T: serde::Serialize,
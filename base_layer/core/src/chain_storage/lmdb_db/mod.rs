@@ -37,6 +37,7 @@ pub const LMDB_DB_TXOS_HASH_TO_INDEX: &str = "txos_hash_to_index";
 pub const LMDB_DB_STXOS: &str = "stxos";
 pub const LMDB_DB_KERNELS: &str = "kernels";
 pub const LMDB_DB_ORPHANS: &str = "orphans";
+pub const LMDB_DB_BLOCK_FILTERS: &str = "block_filters";
 pub const LMDB_DB_UTXO_MMR_CP_BACKEND: &str = "utxo_mmr_cp_backend";
 pub const LMDB_DB_KERNEL_MMR_CP_BACKEND: &str = "kernel_mmr_cp_backend";
 pub const LMDB_DB_RANGE_PROOF_MMR_CP_BACKEND: &str = "range_proof_mmr_cp_backend";
@@ -23,6 +23,7 @@
 //! This is a memory-based blockchain database, generally only useful for testing purposes
 
 use crate::{
+    base_node::BlockFilter,
     blocks::{Block, BlockHeader},
     chain_storage::{
         blockchain_database::BlockchainBackend,
@@ -72,6 +73,7 @@ where D: Digest
     stxos: HashMap<HashOutput, MerkleNode<TransactionOutput>>,
     kernels: HashMap<HashOutput, TransactionKernel>,
     orphans: HashMap<HashOutput, Block>,
+    block_filters: HashMap<u64, BlockFilter>,
     // Define MMRs to use both a memory-backed base and a memory-backed pruned MMR
     utxo_mmr: MmrCache<D, MemDbVec<MmrHash>, MemDbVec<MerkleCheckPoint>>,
     utxo_checkpoints: MemDbVec<MerkleCheckPoint>,
@@ -115,6 +117,7 @@ where D: Digest
                 stxos: HashMap::default(),
                 kernels: HashMap::default(),
                 orphans: HashMap::default(),
+                block_filters: HashMap::default(),
                 utxo_mmr,
                 utxo_checkpoints,
                 curr_utxo_checkpoint: MerkleCheckPoint::new(Vec::new(), Bitmap::create()),
@@ -185,6 +188,9 @@ where D: Digest + Send + Sync
                     DbKeyValuePair::OrphanBlock(k, v) => {
                         db.orphans.insert(k, *v);
                     },
+                    DbKeyValuePair::BlockFilter(k, v) => {
+                        db.block_filters.insert(k, *v);
+                    },
                 },
                 WriteOperation::Delete(delete) => match delete {
                     DbKey::Metadata(_) => {}, // no-op
@@ -206,6 +212,9 @@ where D: Digest + Send + Sync
                     DbKey::OrphanBlock(k) => {
                         db.orphans.remove(&k);
                     },
+                    DbKey::BlockFilter(k) => {
+                        db.block_filters.remove(&k);
+                    },
                 },
                 WriteOperation::Spend(key) => match key {
                     DbKey::UnspentOutput(hash) => {
@@ -288,6 +297,15 @@ where D: Digest + Send + Sync
         Ok(())
     }
 
+    // Nothing is flushed to durable storage, so there is nothing to group.
+    fn begin_group_commit(&mut self) -> Result<(), ChainStorageError> {
+        Ok(())
+    }
+
+    fn end_group_commit(&mut self) -> Result<(), ChainStorageError> {
+        Ok(())
+    }
+
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, ChainStorageError> {
         let db = self.db_access()?;
         let result = match key {
@@ -311,6 +329,10 @@ where D: Digest + Send + Sync
                 .get(k)
                 .map(|v| DbValue::TransactionKernel(Box::new(v.clone()))),
             DbKey::OrphanBlock(k) => db.orphans.get(k).map(|v| DbValue::OrphanBlock(Box::new(v.clone()))),
+            DbKey::BlockFilter(k) => db
+                .block_filters
+                .get(k)
+                .map(|v| DbValue::BlockFilter(Box::new(v.clone()))),
         };
         Ok(result)
     }
@@ -325,6 +347,7 @@ where D: Digest + Send + Sync
             DbKey::SpentOutput(k) => db.stxos.contains_key(k),
             DbKey::TransactionKernel(k) => db.kernels.contains_key(k),
             DbKey::OrphanBlock(k) => db.orphans.contains_key(k),
+            DbKey::BlockFilter(k) => db.block_filters.contains_key(k),
         };
         Ok(result)
     }
@@ -482,6 +505,7 @@ where D: Digest
             stxos: HashMap::default(),
             kernels: HashMap::default(),
             orphans: HashMap::default(),
+            block_filters: HashMap::default(),
             utxo_mmr,
             utxo_checkpoints,
             curr_utxo_checkpoint: MerkleCheckPoint::new(Vec::new(), Bitmap::create()),
@@ -54,6 +54,7 @@ pub use historical_block::HistoricalBlock;
 pub use lmdb_db::{
     create_lmdb_database,
     LMDBDatabase,
+    LMDB_DB_BLOCK_FILTERS,
     LMDB_DB_BLOCK_HASHES,
     LMDB_DB_HEADERS,
     LMDB_DB_KERNELS,
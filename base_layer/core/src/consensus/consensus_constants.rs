@@ -22,7 +22,7 @@
 
 use crate::{
     consensus::network::Network,
-    proof_of_work::Difficulty,
+    proof_of_work::{Difficulty, DifficultyLimits},
     transactions::tari_amount::{uT, MicroTari, T},
 };
 use chrono::{DateTime, Duration, Utc};
@@ -45,8 +45,9 @@ pub struct ConsensusConstants {
     difficulty_block_window: u64,
     /// When doing difficulty adjustments, this is the maximum block time allowed
     difficulty_max_block_interval: u64,
-    /// Maximum transaction weight used for the construction of new blocks.
-    max_block_transaction_weight: u64,
+    /// Maximum transaction weight of blocks as (height, weight) pairs, sorted by height. Each weight applies from its
+    /// height until the height of the next entry. The first entry is at height 0.
+    max_block_weight_schedule: Vec<(u64, u64)>,
     /// The amount of PoW algorithms used by the Tari chain.
     pow_algo_count: u64,
     /// This is how many blocks we use to count towards the median timestamp to ensure the block chain moves forward
@@ -59,6 +60,16 @@ pub struct ConsensusConstants {
     pub(in crate::consensus) emission_tail: MicroTari,
     /// This is the initial min difficulty for the difficulty adjustment
     min_pow_difficulty: Difficulty,
+    /// This is the max difficulty that the difficulty adjustment can request for the pow
+    max_pow_difficulty: Difficulty,
+    /// The max factor by which the target difficulty can increase or decrease from one block to the next
+    max_difficulty_adjustment_factor: u64,
+    /// The output feature extension tags that outputs are permitted to carry
+    permitted_output_feature_extensions: Vec<u8>,
+    /// The maximum size in bytes of the encoded output feature extensions of a single output
+    max_output_feature_extensions_size: usize,
+    /// The maximum size in bytes of the extra data a miner may tag the coinbase kernel with
+    coinbase_max_extra_size: usize,
 }
 // The target time used by the difficulty adjustment algorithms, their target time is the target block interval * PoW
 // algorithm count
@@ -104,9 +115,24 @@ impl ConsensusConstants {
         self.difficulty_block_window
     }
 
-    /// Maximum transaction weight used for the construction of new blocks.
-    pub fn get_max_block_transaction_weight(&self) -> u64 {
-        self.max_block_transaction_weight
+    /// Maximum transaction weight of the block at the given height, used for the construction and validation of new
+    /// blocks.
+    pub fn get_max_block_transaction_weight(&self, height: u64) -> u64 {
+        self.max_block_weight_schedule
+            .iter()
+            .rev()
+            .find(|(from_height, _)| *from_height <= height)
+            .or_else(|| self.max_block_weight_schedule.first())
+            .map(|(_, weight)| *weight)
+            .unwrap_or(0)
+    }
+
+    /// The next scheduled change of the maximum block transaction weight after the given height, as (height, weight).
+    pub fn next_max_block_weight_change(&self, height: u64) -> Option<(u64, u64)> {
+        self.max_block_weight_schedule
+            .iter()
+            .find(|(from_height, _)| *from_height > height)
+            .cloned()
     }
 
     /// The amount of PoW algorithms used by the Tari chain.
@@ -136,6 +162,40 @@ impl ConsensusConstants {
         self.min_pow_difficulty
     }
 
+    /// This is the max difficulty that can be requested for the pow.
+    pub fn max_pow_difficulty(&self) -> Difficulty {
+        self.max_pow_difficulty
+    }
+
+    /// The max factor by which the target difficulty can increase or decrease from one block to the next.
+    pub fn max_difficulty_adjustment_factor(&self) -> u64 {
+        self.max_difficulty_adjustment_factor
+    }
+
+    /// The sanity limits applied to the target difficulty of each PoW algorithm.
+    pub fn difficulty_limits(&self) -> DifficultyLimits {
+        DifficultyLimits::new(
+            self.min_pow_difficulty,
+            self.max_pow_difficulty,
+            self.max_difficulty_adjustment_factor,
+        )
+    }
+
+    /// The output feature extension tags that outputs are permitted to carry.
+    pub fn permitted_output_feature_extensions(&self) -> &[u8] {
+        &self.permitted_output_feature_extensions
+    }
+
+    /// The maximum size in bytes of the encoded output feature extensions of a single output.
+    pub fn max_output_feature_extensions_size(&self) -> usize {
+        self.max_output_feature_extensions_size
+    }
+
+    /// The maximum size in bytes of the extra data a miner may tag the coinbase kernel with.
+    pub fn coinbase_max_extra_size(&self) -> usize {
+        self.coinbase_max_extra_size
+    }
+
     #[allow(clippy::identity_op)]
     pub fn rincewind() -> Self {
         let target_block_interval = 60;
@@ -147,13 +207,18 @@ impl ConsensusConstants {
             target_block_interval,
             difficulty_block_window,
             difficulty_max_block_interval: target_block_interval * 60,
-            max_block_transaction_weight: 6250,
+            max_block_weight_schedule: vec![(0, 6250)],
             pow_algo_count: 1,
             median_timestamp_count: 11,
             emission_initial: 5_538_846_115 * uT,
             emission_decay: 0.999_999_560_409_038_5,
             emission_tail: 1 * T,
             min_pow_difficulty: 6_000_000.into(),
+            max_pow_difficulty: 6_000_000_000_000.into(),
+            max_difficulty_adjustment_factor: 2,
+            permitted_output_feature_extensions: Vec::new(),
+            max_output_feature_extensions_size: 0,
+            coinbase_max_extra_size: 64,
         }
     }
 
@@ -167,13 +232,18 @@ impl ConsensusConstants {
             target_block_interval,
             difficulty_max_block_interval: target_block_interval * 6,
             difficulty_block_window,
-            max_block_transaction_weight: 6250,
+            max_block_weight_schedule: vec![(0, 6250)],
             pow_algo_count: 2,
             median_timestamp_count: 11,
             emission_initial: 10_000_000.into(),
             emission_decay: 0.999,
             emission_tail: 100.into(),
             min_pow_difficulty: 1.into(),
+            max_pow_difficulty: u64::MAX.into(),
+            max_difficulty_adjustment_factor: 4,
+            permitted_output_feature_extensions: Vec::new(),
+            max_output_feature_extensions_size: 0,
+            coinbase_max_extra_size: 64,
         }
     }
 
@@ -188,13 +258,18 @@ impl ConsensusConstants {
             target_block_interval,
             difficulty_max_block_interval: target_block_interval * 6,
             difficulty_block_window,
-            max_block_transaction_weight: 6250,
+            max_block_weight_schedule: vec![(0, 6250)],
             pow_algo_count: 2,
             median_timestamp_count: 11,
             emission_initial: 10_000_000.into(),
             emission_decay: 0.999,
             emission_tail: 100.into(),
             min_pow_difficulty: 500_000_000.into(),
+            max_pow_difficulty: u64::MAX.into(),
+            max_difficulty_adjustment_factor: 4,
+            permitted_output_feature_extensions: Vec::new(),
+            max_output_feature_extensions_size: 0,
+            coinbase_max_extra_size: 64,
         }
     }
 }
@@ -229,7 +304,68 @@ impl ConsensusConstantsBuilder {
         self
     }
 
+    /// Set the maximum block transaction weight schedule as (height, weight) pairs. The weight of the first entry also
+    /// applies below its height.
+    pub fn with_max_block_weight_schedule(mut self, mut schedule: Vec<(u64, u64)>) -> ConsensusConstantsBuilder {
+        schedule.sort_by_key(|(height, _)| *height);
+        self.consensus.max_block_weight_schedule = schedule;
+        self
+    }
+
+    /// Set the min and max target difficulty and the max factor by which the target difficulty can change per block.
+    pub fn with_difficulty_limits(
+        mut self,
+        min: Difficulty,
+        max: Difficulty,
+        max_adjustment_factor: u64,
+    ) -> ConsensusConstantsBuilder
+    {
+        self.consensus.min_pow_difficulty = min;
+        self.consensus.max_pow_difficulty = max;
+        self.consensus.max_difficulty_adjustment_factor = max_adjustment_factor;
+        self
+    }
+
+    pub fn with_output_feature_extensions(
+        mut self,
+        permitted_tags: Vec<u8>,
+        max_size: usize,
+    ) -> ConsensusConstantsBuilder
+    {
+        self.consensus.permitted_output_feature_extensions = permitted_tags;
+        self.consensus.max_output_feature_extensions_size = max_size;
+        self
+    }
+
+    pub fn with_coinbase_max_extra_size(mut self, max_size: usize) -> ConsensusConstantsBuilder {
+        self.consensus.coinbase_max_extra_size = max_size;
+        self
+    }
+
     pub fn build(self) -> ConsensusConstants {
         self.consensus
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn max_block_weight_schedule() {
+        let constants = ConsensusConstantsBuilder::new(Network::LocalNet)
+            .with_max_block_weight_schedule(vec![(100, 12_500), (0, 6250), (200, 3000)])
+            .build();
+        assert_eq!(constants.get_max_block_transaction_weight(0), 6250);
+        assert_eq!(constants.get_max_block_transaction_weight(99), 6250);
+        assert_eq!(constants.get_max_block_transaction_weight(100), 12_500);
+        assert_eq!(constants.get_max_block_transaction_weight(1000), 3000);
+        assert_eq!(constants.next_max_block_weight_change(0), Some((100, 12_500)));
+        assert_eq!(constants.next_max_block_weight_change(200), None);
+
+        let constants = ConsensusConstantsBuilder::new(Network::LocalNet)
+            .with_max_block_weight_schedule(vec![(10, 5000)])
+            .build();
+        assert_eq!(constants.get_max_block_transaction_weight(0), 5000);
+    }
+}
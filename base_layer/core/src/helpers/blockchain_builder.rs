@@ -0,0 +1,426 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A test utility that generates a chain of blocks from a seed, so that tests which need a populated blockchain do not
+//! have to hand-craft their own fixtures.
+//!
+//! Every key, nonce, offset, value and timestamp is drawn from a random number generator seeded with the builder's
+//! seed, so two chains built with the same parameters contain the same commitments, kernels and spends. Range proofs
+//! include randomness from the prover, so the hashes of the generated blocks do differ between runs.
+
+use crate::{
+    blocks::{Block, BlockHeader, NewBlockTemplate},
+    chain_storage::{calculate_mmr_roots, BlockAddResult, BlockchainDatabase, MemoryDatabase},
+    consensus::{ConsensusManager, ConsensusManagerBuilder, Network},
+    helpers::create_mem_db,
+    mining::CoinbaseBuilder,
+    transactions::{
+        tari_amount::{uT, MicroTari, T},
+        transaction::{KernelFeatures, OutputFeatures, Transaction, UnblindedOutput},
+        types::{CryptoFactories, HashDigest, PrivateKey},
+        SenderTransactionProtocol,
+    },
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tari_crypto::{common::Blake256, keys::SecretKey, tari_utilities::epoch_time::EpochTime};
+
+/// The timestamp of the generated genesis block. Every following block is one target block interval later.
+const GENESIS_TIMESTAMP: u64 = 1_577_836_800;
+/// Outputs worth less than this are never selected as inputs, so that every spend can pay its fee and leave change
+const MIN_INPUT_VALUE: MicroTari = MicroTari(100_000);
+
+/// The shape of a transaction that the [BlockchainBuilder] adds to every generated block
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransactionPattern {
+    /// Spend a single output, paying half of its value to a new output and returning the rest as change
+    Spend,
+    /// Split a single output into the given number of outputs of equal value, returning the rest as change
+    Split(usize),
+    /// Spend a single output to a new output that matures at the next block height. The new output is spent in the
+    /// next block, i.e. at exactly its maturity height.
+    MaturityEdge,
+}
+
+/// Builds a memory-backed blockchain of `num_blocks` blocks on top of a generated genesis block.
+///
+/// ```ignore
+/// let chain = BlockchainBuilder::new(Network::LocalNet)
+///     .with_seed(42)
+///     .with_blocks(10)
+///     .with_patterns(vec![TransactionPattern::Spend, TransactionPattern::Split(3)])
+///     .build();
+/// ```
+pub struct BlockchainBuilder {
+    network: Network,
+    seed: u64,
+    num_blocks: u64,
+    patterns: Vec<TransactionPattern>,
+    genesis_outputs: usize,
+    genesis_output_value: MicroTari,
+    fee_per_gram: MicroTari,
+}
+
+/// The result of [BlockchainBuilder::build]
+pub struct GeneratedBlockchain {
+    pub db: BlockchainDatabase<MemoryDatabase<HashDigest>>,
+    pub consensus_manager: ConsensusManager,
+    /// All blocks in the chain, starting with the genesis block
+    pub blocks: Vec<Block>,
+    /// The outputs created in each block, including its coinbase output, indexed by block height
+    pub outputs: Vec<Vec<UnblindedOutput>>,
+    /// The outputs that have not been spent at the tip of the chain
+    pub unspent: Vec<UnblindedOutput>,
+}
+
+impl BlockchainBuilder {
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            seed: 0,
+            num_blocks: 1,
+            patterns: vec![TransactionPattern::Spend],
+            genesis_outputs: 4,
+            genesis_output_value: 10 * T,
+            fee_per_gram: 25 * uT,
+        }
+    }
+
+    /// The seed for all generated keys and values
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The number of blocks to generate on top of the genesis block
+    pub fn with_blocks(mut self, num_blocks: u64) -> Self {
+        self.num_blocks = num_blocks;
+        self
+    }
+
+    /// The transactions added to every block. A pattern is skipped in a block if there is no mature output left to
+    /// spend.
+    pub fn with_patterns(mut self, patterns: Vec<TransactionPattern>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    /// The number of outputs, and the value of each, that the genesis block creates and that are immediately spendable
+    pub fn with_genesis_outputs(mut self, count: usize, value: MicroTari) -> Self {
+        self.genesis_outputs = count;
+        self.genesis_output_value = value;
+        self
+    }
+
+    pub fn with_fee_per_gram(mut self, fee_per_gram: MicroTari) -> Self {
+        self.fee_per_gram = fee_per_gram;
+        self
+    }
+
+    /// Generate the chain. This panics if a generated block is not accepted by the database.
+    pub fn build(self) -> GeneratedBlockchain {
+        let mut generator = ChainGenerator {
+            rng: StdRng::seed_from_u64(self.seed),
+            factories: CryptoFactories::default(),
+            fee_per_gram: self.fee_per_gram,
+            unspent: Vec::new(),
+            maturing: Vec::new(),
+        };
+
+        let (genesis, genesis_outputs) = generator.genesis_block(&self);
+        let consensus_manager = ConsensusManagerBuilder::new(self.network)
+            .with_block(genesis.clone())
+            .build();
+        let db = create_mem_db(&consensus_manager);
+        generator.unspent = genesis_outputs.clone();
+
+        let mut blocks = vec![genesis];
+        let mut outputs = vec![genesis_outputs];
+        for _ in 0..self.num_blocks {
+            let prev_block = blocks.last().expect("The genesis block is always present");
+            let (block, block_outputs) = generator.next_block(&self.patterns, prev_block, &consensus_manager, &db);
+            match db.add_block(block.clone()) {
+                Ok(BlockAddResult::Ok) => {},
+                result => panic!("Generated block {} was not added: {:?}", block.header.height, result),
+            }
+            blocks.push(block);
+            outputs.push(block_outputs);
+        }
+
+        GeneratedBlockchain {
+            db,
+            consensus_manager,
+            blocks,
+            outputs,
+            unspent: generator.unspent,
+        }
+    }
+}
+
+struct ChainGenerator {
+    rng: StdRng,
+    factories: CryptoFactories,
+    fee_per_gram: MicroTari,
+    unspent: Vec<UnblindedOutput>,
+    // Outputs created by the maturity edge pattern that must be spent at exactly their maturity height
+    maturing: Vec<UnblindedOutput>,
+}
+
+impl ChainGenerator {
+    fn genesis_block(&mut self, builder: &BlockchainBuilder) -> (Block, Vec<UnblindedOutput>) {
+        let constants = builder.network.create_consensus_constants();
+        let mut header = BlockHeader::new(constants.blockchain_version());
+        header.timestamp = EpochTime::from(GENESIS_TIMESTAMP);
+        let mut outputs = Vec::with_capacity(builder.genesis_outputs);
+        let mut utxos = Vec::with_capacity(builder.genesis_outputs);
+        for _ in 0..builder.genesis_outputs {
+            let output = UnblindedOutput::new(builder.genesis_output_value, self.random_key(), None);
+            let utxo = output
+                .as_transaction_output(&self.factories)
+                .expect("Genesis output values are always in range");
+            utxos.push(utxo);
+            outputs.push(output);
+        }
+        let template = NewBlockTemplate::from(header.into_builder().add_outputs(utxos).build());
+        let mut block =
+            calculate_mmr_roots(&MemoryDatabase::<HashDigest>::default(), template).expect("Empty MMRs are valid");
+        block.header.nonce = self.rng.gen();
+        (block, outputs)
+    }
+
+    fn next_block(
+        &mut self,
+        patterns: &[TransactionPattern],
+        prev_block: &Block,
+        rules: &ConsensusManager,
+        db: &BlockchainDatabase<MemoryDatabase<HashDigest>>,
+    ) -> (Block, Vec<UnblindedOutput>)
+    {
+        let constants = rules.consensus_constants();
+        let height = prev_block.header.height + 1;
+        let mut transactions = Vec::new();
+        let mut block_outputs = Vec::new();
+
+        for input in self.take_maturing(height) {
+            let value = input.value / 2u64;
+            let (tx, mut outputs) = self.spend(vec![input], vec![value], OutputFeatures::default());
+            transactions.push(tx);
+            block_outputs.append(&mut outputs);
+        }
+        for pattern in patterns {
+            let input = match self.select_input(height) {
+                Some(input) => input,
+                None => continue,
+            };
+            let (tx, mut outputs) = match pattern {
+                TransactionPattern::Spend => {
+                    let value = input.value / 2u64;
+                    self.spend(vec![input], vec![value], OutputFeatures::default())
+                },
+                TransactionPattern::Split(count) => {
+                    let count = (*count).max(1);
+                    let value = input.value / (2 * count as u64);
+                    self.spend(vec![input], vec![value; count], OutputFeatures::default())
+                },
+                TransactionPattern::MaturityEdge => {
+                    let value = input.value / 2u64;
+                    let (tx, outputs) = self.spend(vec![input], vec![value], OutputFeatures::with_maturity(height + 1));
+                    // The first output is the maturing one, the second is the change
+                    self.maturing.push(outputs[0].clone());
+                    (tx, outputs)
+                },
+            };
+            transactions.push(tx);
+            block_outputs.append(&mut outputs);
+        }
+
+        let fees = transactions.iter().map(|tx| tx.body.get_total_fee()).sum::<MicroTari>();
+        let (coinbase, coinbase_output) = CoinbaseBuilder::new(self.factories.clone())
+            .with_block_height(height)
+            .with_fees(fees)
+            .with_nonce(self.random_key())
+            .with_spend_key(self.random_key())
+            .build(rules.clone())
+            .expect("The coinbase parameters are always complete");
+        block_outputs.push(coinbase_output);
+        transactions.push(coinbase);
+
+        let mut header = BlockHeader::from_previous(&prev_block.header);
+        header.version = constants.blockchain_version();
+        let template = NewBlockTemplate::from(header.into_builder().with_transactions(transactions).build());
+        let mut block = db
+            .calculate_mmr_roots(template)
+            .expect("MMR roots could not be calculated");
+        block.header.timestamp =
+            EpochTime::from(GENESIS_TIMESTAMP + height * constants.get_target_block_interval());
+        block.header.nonce = self.rng.gen();
+
+        self.unspent.extend(block_outputs.iter().cloned());
+        (block, block_outputs)
+    }
+
+    fn random_key(&mut self) -> PrivateKey {
+        PrivateKey::random(&mut self.rng)
+    }
+
+    // Remove the outputs that mature at the given height from the unspent set
+    fn take_maturing(&mut self, height: u64) -> Vec<UnblindedOutput> {
+        let (due, maturing) = self
+            .maturing
+            .drain(..)
+            .partition::<Vec<_>, _>(|o| o.features.maturity == height);
+        self.maturing = maturing;
+        self.unspent.retain(|o| !contains(&due, o));
+        due
+    }
+
+    // Remove a random mature output from the unspent set, leaving outputs that must be spent at their maturity alone
+    fn select_input(&mut self, height: u64) -> Option<UnblindedOutput> {
+        let maturing = &self.maturing;
+        let candidates = self
+            .unspent
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.features.maturity <= height && o.value >= MIN_INPUT_VALUE && !contains(maturing, o))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        let index = candidates[self.rng.gen_range(0, candidates.len())];
+        Some(self.unspent.remove(index))
+    }
+
+    // Spend the inputs to outputs of the given values, returning the transaction with its outputs followed by change
+    fn spend(
+        &mut self,
+        inputs: Vec<UnblindedOutput>,
+        values: Vec<MicroTari>,
+        features: OutputFeatures,
+    ) -> (Transaction, Vec<UnblindedOutput>)
+    {
+        let change_key = self.random_key();
+        let mut builder = SenderTransactionProtocol::builder(0);
+        builder
+            .with_lock_height(0)
+            .with_fee_per_gram(self.fee_per_gram)
+            .with_offset(self.random_key())
+            .with_private_nonce(self.random_key())
+            .with_change_secret(change_key.clone());
+        for input in inputs {
+            let utxo = input.as_transaction_input(&self.factories.commitment, input.features.clone());
+            builder.with_input(utxo, input);
+        }
+        let mut outputs = Vec::with_capacity(values.len() + 1);
+        for value in values {
+            let output = UnblindedOutput::new(value, self.random_key(), Some(features.clone()));
+            outputs.push(output.clone());
+            builder.with_output(output);
+        }
+
+        let mut stx = builder
+            .build::<Blake256>(&self.factories)
+            .expect("Generated transactions are always complete");
+        let change = stx.get_change_amount().expect("The transaction has been built");
+        if change > MicroTari(0) {
+            outputs.push(UnblindedOutput::new(change, change_key, None));
+        }
+        match stx.finalize(KernelFeatures::empty(), &self.factories) {
+            Ok(true) => (),
+            Ok(false) => panic!("{:?}", stx.failure_reason()),
+            Err(e) => panic!("{:?}", e),
+        }
+        let tx = stx
+            .get_transaction()
+            .expect("The transaction has been finalized")
+            .clone();
+        (tx, outputs)
+    }
+}
+
+// Unblinded outputs are identified by their spending key, since every generated output has a fresh key
+fn contains(outputs: &[UnblindedOutput], output: &UnblindedOutput) -> bool {
+    outputs.iter().any(|o| o.spending_key == output.spending_key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transactions::types::Commitment;
+
+    fn commitments(chain: &GeneratedBlockchain) -> Vec<Vec<Commitment>> {
+        let factories = CryptoFactories::default();
+        chain
+            .outputs
+            .iter()
+            .map(|outputs| {
+                outputs
+                    .iter()
+                    .map(|o| {
+                        o.as_transaction_input(&factories.commitment, o.features.clone())
+                            .commitment
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_chain() {
+        let build = |seed| {
+            BlockchainBuilder::new(Network::LocalNet)
+                .with_seed(seed)
+                .with_blocks(3)
+                .with_patterns(vec![TransactionPattern::Spend, TransactionPattern::Split(3)])
+                .build()
+        };
+        let chain = build(7);
+        assert_eq!(chain.db.get_height().unwrap(), Some(3));
+        assert_eq!(chain.blocks.len(), 4);
+        // Spend: output and change, split: three outputs and change, and the coinbase
+        assert_eq!(chain.outputs[1].len(), 7);
+
+        let same = build(7);
+        assert_eq!(commitments(&chain), commitments(&same));
+        for (a, b) in chain.blocks.iter().zip(same.blocks.iter()) {
+            assert_eq!(a.body.kernels(), b.body.kernels());
+            assert_eq!(a.header.timestamp, b.header.timestamp);
+        }
+
+        let other = build(8);
+        assert_ne!(commitments(&chain), commitments(&other));
+    }
+
+    #[test]
+    fn maturity_edge_is_spent_at_maturity() {
+        let chain = BlockchainBuilder::new(Network::LocalNet)
+            .with_seed(1)
+            .with_blocks(2)
+            .with_patterns(vec![TransactionPattern::MaturityEdge])
+            .build();
+        let factories = CryptoFactories::default();
+        let maturing = &chain.outputs[1][0];
+        assert_eq!(maturing.features.maturity, 2);
+        let input = maturing.as_transaction_input(&factories.commitment, maturing.features.clone());
+        assert!(chain.blocks[2].body.inputs().contains(&input));
+        assert!(!contains(&chain.unspent, maturing));
+    }
+}
@@ -40,6 +40,14 @@ impl BlockchainBackend for MockBackend {
         unimplemented!()
     }
 
+    fn begin_group_commit(&mut self) -> Result<(), ChainStorageError> {
+        unimplemented!()
+    }
+
+    fn end_group_commit(&mut self) -> Result<(), ChainStorageError> {
+        unimplemented!()
+    }
+
     fn fetch(&self, _key: &DbKey) -> Result<Option<DbValue>, ChainStorageError> {
         unimplemented!()
     }
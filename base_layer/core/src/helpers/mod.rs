@@ -23,6 +23,7 @@
 //! Common test helper functions that are small and useful enough to be included in the main crate, rather than the
 //! integration test folder.
 
+mod blockchain_builder;
 mod mock_backend;
 
 use crate::{
@@ -33,6 +34,7 @@ use crate::{
     validation::mocks::MockValidator,
 };
 
+pub use blockchain_builder::{BlockchainBuilder, GeneratedBlockchain, TransactionPattern};
 pub use mock_backend::MockBackend;
 
 /// Create a partially constructed block using the provided set of transactions
@@ -23,7 +23,17 @@
 use crate::{
     blocks::Block,
     chain_storage::BlockchainBackend,
-    mempool::{error::MempoolError, Mempool, StateResponse, StatsResponse, TxStorageResponse},
+    mempool::{
+        error::MempoolError,
+        Mempool,
+        MempoolTransaction,
+        MempoolTxFilter,
+        StateResponse,
+        StatsResponse,
+        TxRejection,
+        TxStorageResponse,
+        TxValidationReport,
+    },
     transactions::{transaction::Transaction, types::Signature},
 };
 use std::sync::Arc;
@@ -63,6 +73,8 @@ macro_rules! make_async {
 }
 
 make_async!(insert(tx: Arc<Transaction>) -> TxStorageResponse);
+make_async!(insert_with_rejection(tx: Arc<Transaction>) -> (TxStorageResponse, Option<TxRejection>));
+make_async!(validate_transaction(tx: Arc<Transaction>) -> TxValidationReport);
 make_async!(process_published_block(published_block: Block) -> ());
 make_async!(process_reorg(removed_blocks: Vec<Block>, new_blocks: Vec<Block>) -> ());
 make_async!(snapshot() -> Vec<Arc<Transaction>>);
@@ -70,3 +82,5 @@ make_async!(retrieve(total_weight: u64) -> Vec<Arc<Transaction>>);
 make_async!(has_tx_with_excess_sig(excess_sig: Signature) -> TxStorageResponse);
 make_async!(stats() -> StatsResponse);
 make_async!(state() -> StateResponse);
+make_async!(list_transactions(filter: MempoolTxFilter) -> Vec<MempoolTransaction>);
+make_async!(get_transaction(excess_sig: Signature) -> Option<MempoolTransaction>);
@@ -20,12 +20,16 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mempool::{
-    consts,
-    orphan_pool::OrphanPoolConfig,
-    pending_pool::PendingPoolConfig,
-    reorg_pool::ReorgPoolConfig,
-    unconfirmed_pool::UnconfirmedPoolConfig,
+use crate::{
+    mempool::{
+        consts,
+        orphan_pool::OrphanPoolConfig,
+        pending_pool::PendingPoolConfig,
+        relay_policy::RelayConfig,
+        reorg_pool::ReorgPoolConfig,
+        unconfirmed_pool::UnconfirmedPoolConfig,
+    },
+    transactions::tari_amount::MicroTari,
 };
 use bitflags::_core::time::Duration;
 use config::Config;
@@ -38,6 +42,10 @@ pub struct MempoolConfig {
     pub orphan_pool_config: OrphanPoolConfig,
     pub pending_pool_config: PendingPoolConfig,
     pub reorg_pool_config: ReorgPoolConfig,
+    pub relay_config: RelayConfig,
+    /// The maximum number of transactions returned in one page when listing the mempool transactions, regardless of
+    /// the limit that was requested
+    pub max_list_page_size: usize,
 }
 
 impl Default for MempoolConfig {
@@ -47,6 +55,8 @@ impl Default for MempoolConfig {
             orphan_pool_config: OrphanPoolConfig::default(),
             pending_pool_config: PendingPoolConfig::default(),
             reorg_pool_config: ReorgPoolConfig::default(),
+            relay_config: RelayConfig::default(),
+            max_list_page_size: consts::MEMPOOL_MAX_LIST_PAGE_SIZE,
         }
     }
 }
@@ -90,6 +100,26 @@ impl ConfigExtractor for MempoolConfig {
                 default.reorg_pool_config.tx_ttl.as_secs() as i64,
             )
             .unwrap();
+            cfg.set_default(
+                &format!("relay.{}.min_fee_per_gram", network),
+                u64::from(default.relay_config.min_fee_per_gram) as i64,
+            )
+            .unwrap();
+            cfg.set_default(
+                &format!("relay.{}.max_tx_weight", network),
+                default.relay_config.max_tx_weight as i64,
+            )
+            .unwrap();
+            cfg.set_default(
+                &format!("relay.{}.relay_non_standard_features", network),
+                default.relay_config.relay_non_standard_features,
+            )
+            .unwrap();
+            cfg.set_default(
+                &format!("mempool.{}.max_list_page_size", network),
+                default.max_list_page_size as i64,
+            )
+            .unwrap();
         }
     }
 
@@ -131,6 +161,26 @@ impl ConfigExtractor for MempoolConfig {
             .get_int(&key)
             .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as u64;
         config.reorg_pool_config.tx_ttl = Duration::from_secs(val);
+        let key = format!("relay.{}.min_fee_per_gram", network);
+        let val = cfg
+            .get_int(&key)
+            .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as u64;
+        config.relay_config.min_fee_per_gram = MicroTari(val);
+        let key = format!("relay.{}.max_tx_weight", network);
+        let val = cfg
+            .get_int(&key)
+            .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as u64;
+        config.relay_config.max_tx_weight = val;
+        let key = format!("relay.{}.relay_non_standard_features", network);
+        let val = cfg
+            .get_bool(&key)
+            .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+        config.relay_config.relay_non_standard_features = val;
+        let key = format!("mempool.{}.max_list_page_size", network);
+        let val = cfg
+            .get_int(&key)
+            .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as usize;
+        config.max_list_page_size = val;
         Ok(config)
     }
 }
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //
 
+use crate::transactions::tari_amount::MicroTari;
 use std::time::Duration;
 
 /// The maximum number of transactions that can be stored in the Unconfirmed Transaction pool
@@ -42,5 +43,14 @@ pub const MEMPOOL_REORG_POOL_STORAGE_CAPACITY: usize = 5_000;
 /// The time-to-live duration used for transactions stored in the ReorgPool
 pub const MEMPOOL_REORG_POOL_CACHE_TTL: Duration = Duration::from_secs(300);
 
+/// The default minimum average fee per gram a transaction must pay to be admitted to the mempool and relayed
+pub const MEMPOOL_RELAY_MIN_FEE_PER_GRAM: MicroTari = MicroTari(0);
+/// The default maximum weight of a transaction that will be admitted to the mempool and relayed. A transaction heavier
+/// than a block can never be mined.
+pub const MEMPOOL_RELAY_MAX_TX_WEIGHT: u64 = 6250;
+
 /// The allocated waiting time for a request waiting for service responses from the mempools of remote base nodes.
 pub const MEMPOOL_SERVICE_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The maximum number of transactions returned in one page when listing the mempool transactions
+pub const MEMPOOL_MAX_LIST_PAGE_SIZE: usize = 500;
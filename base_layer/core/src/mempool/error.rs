@@ -47,4 +47,6 @@ pub enum MempoolError {
     /// A problem has been encountered with the storage backend.
     #[error(non_std, no_from)]
     BackendError(String),
+    /// The transaction the page of listed transactions should start after is no longer in the mempool
+    PageCursorNotFound,
 }
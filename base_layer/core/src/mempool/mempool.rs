@@ -26,10 +26,15 @@ use crate::{
     mempool::{
         error::MempoolError,
         mempool_storage::MempoolStorage,
+        relay_policy::RelayPolicy,
         MempoolConfig,
+        MempoolTransaction,
+        MempoolTxFilter,
         StateResponse,
         StatsResponse,
+        TxRejection,
         TxStorageResponse,
+        TxValidationReport,
     },
     transactions::{transaction::Transaction, types::Signature},
     validation::{Validation, Validator},
@@ -66,6 +71,7 @@ pub struct Mempool<T>
 where T: BlockchainBackend
 {
     pool_storage: Arc<RwLock<MempoolStorage<T>>>,
+    relay_policy: RelayPolicy,
 }
 
 impl<T> Mempool<T>
@@ -75,16 +81,50 @@ where T: BlockchainBackend
     pub fn new(blockchain_db: BlockchainDatabase<T>, config: MempoolConfig, validators: MempoolValidators<T>) -> Self {
         Self {
             pool_storage: Arc::new(RwLock::new(MempoolStorage::new(blockchain_db, config, validators))),
+            relay_policy: RelayPolicy::new(config.relay_config),
         }
     }
 
     /// Insert an unconfirmed transaction into the Mempool. The transaction *MUST* have passed through the validation
-    /// pipeline already and will thus always be internally consistent by this stage
+    /// pipeline already and will thus always be internally consistent by this stage. Transactions refused by the relay
+    /// policy are not stored, and therefore never propagated.
     pub fn insert(&self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
+        self.insert_with_rejection(tx).map(|(storage, _)| storage)
+    }
+
+    /// Insert an unconfirmed transaction into the Mempool, as with [insert](Mempool::insert), and also return the
+    /// reason the transaction was refused by the relay policy or validation when it is not stored.
+    pub fn insert_with_rejection(
+        &self,
+        tx: Arc<Transaction>,
+    ) -> Result<(TxStorageResponse, Option<TxRejection>), MempoolError>
+    {
+        if let Err(reason) = self.relay_policy.check(&tx) {
+            return Ok((TxStorageResponse::NotStored, Some(reason.into())));
+        }
         self.pool_storage
             .write()
             .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .insert(tx)
+            .insert_with_rejection(tx)
+    }
+
+    /// Produce a detailed report of the stateless, stateful and relay policy checks for the transaction, and the pool
+    /// it would be stored in, without storing or propagating it.
+    pub fn validate_transaction(&self, tx: Arc<Transaction>) -> Result<TxValidationReport, MempoolError> {
+        let pool_storage = self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?;
+        let mut report = pool_storage.validate(&tx)?;
+        if let Err(reason) = self.relay_policy.evaluate(&tx) {
+            report.relay_rejection = Some(reason.into());
+            // Transactions that are already stored stay where they are
+            let excess_sig = tx.body.kernels()[0].excess_sig.clone();
+            if pool_storage.has_tx_with_excess_sig(excess_sig)? == TxStorageResponse::NotStored {
+                report.storage = TxStorageResponse::NotStored;
+            }
+        }
+        Ok(report)
     }
 
     /// Update the Mempool based on the received published block.
@@ -131,10 +171,16 @@ where T: BlockchainBackend
 
     /// Gathers and returns the stats of the Mempool.
     pub fn stats(&self) -> Result<StatsResponse, MempoolError> {
-        self.pool_storage
+        let mut stats = self
+            .pool_storage
             .read()
             .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .stats()
+            .stats()?;
+        let rejections = self.relay_policy.rejection_counts();
+        stats.relay_rejected_low_fee = rejections.fee_too_low;
+        stats.relay_rejected_overweight = rejections.weight_too_high;
+        stats.relay_rejected_non_standard = rejections.non_standard_features;
+        Ok(stats)
     }
 
     /// Gathers and returns a breakdown of all the transaction in the Mempool.
@@ -144,6 +190,23 @@ where T: BlockchainBackend
             .map_err(|e| MempoolError::BackendError(e.to_string()))?
             .state()
     }
+
+    /// Returns summaries of the transactions in the Mempool, except the transactions stored in the ReorgPool, that
+    /// match the filter.
+    pub fn list_transactions(&self, filter: MempoolTxFilter) -> Result<Vec<MempoolTransaction>, MempoolError> {
+        self.pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .list_transactions(filter)
+    }
+
+    /// Returns the specified transaction, along with its summary, if it is stored in any of the pools.
+    pub fn get_transaction(&self, excess_sig: Signature) -> Result<Option<MempoolTransaction>, MempoolError> {
+        self.pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .get_transaction(excess_sig)
+    }
 }
 
 impl<T> Clone for Mempool<T>
@@ -152,6 +215,7 @@ where T: BlockchainBackend
     fn clone(&self) -> Self {
         Mempool {
             pool_storage: self.pool_storage.clone(),
+            relay_policy: self.relay_policy.clone(),
         }
     }
 }
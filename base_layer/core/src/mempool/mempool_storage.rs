@@ -31,15 +31,25 @@ use crate::{
         reorg_pool::ReorgPool,
         unconfirmed_pool::UnconfirmedPool,
         MempoolConfig,
+        MempoolTransaction,
+        MempoolTxFilter,
         StateResponse,
         StatsResponse,
+        TxRejection,
+        TxSortOrder,
         TxStorageResponse,
+        TxValidationReport,
     },
     transactions::{transaction::Transaction, types::Signature},
     validation::{ValidationError, Validator},
 };
 use log::*;
-use std::sync::Arc;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
 use tari_crypto::tari_utilities::{hex::Hex, Hashable};
 
 pub const LOG_TARGET: &str = "c::mp::mempool";
@@ -56,6 +66,8 @@ where T: BlockchainBackend
     pending_pool: PendingPool,
     reorg_pool: ReorgPool,
     validator: Arc<Validator<Transaction, T>>,
+    first_seen: HashMap<Signature, Instant>,
+    max_list_page_size: usize,
 }
 
 impl<T> MempoolStorage<T>
@@ -71,12 +83,24 @@ where T: BlockchainBackend
             reorg_pool: ReorgPool::new(config.reorg_pool_config),
             blockchain_db,
             validator: Arc::new(mempool_validator),
+            first_seen: HashMap::new(),
+            max_list_page_size: config.max_list_page_size,
         }
     }
 
     /// Insert an unconfirmed transaction into the Mempool. The transaction *MUST* have passed through the validation
     /// pipeline already and will thus always be internally consistent by this stage
     pub fn insert(&mut self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
+        self.insert_with_rejection(tx).map(|(storage, _)| storage)
+    }
+
+    /// Insert an unconfirmed transaction into the Mempool, as with [insert](MempoolStorage::insert), and also return
+    /// the reason the transaction was refused when it is not stored.
+    pub fn insert_with_rejection(
+        &mut self,
+        tx: Arc<Transaction>,
+    ) -> Result<(TxStorageResponse, Option<TxRejection>), MempoolError>
+    {
         debug!(
             target: LOG_TARGET,
             "Inserting tx into mempool: {}",
@@ -84,22 +108,67 @@ where T: BlockchainBackend
         );
         // The transaction is already internally consistent
         let (db, metadata) = self.blockchain_db.db_and_metadata_read_access()?;
+        let excess_sig = tx.body.kernels()[0].excess_sig.clone();
 
-        match self.validator.validate(&tx, &db, &metadata) {
+        let (storage, rejection) = match self.validator.validate(&tx, &db, &metadata) {
             Ok(()) => {
                 self.unconfirmed_pool.insert(tx)?;
-                Ok(TxStorageResponse::UnconfirmedPool)
+                (TxStorageResponse::UnconfirmedPool, None)
             },
-            Err(ValidationError::UnknownInputs) => {
+            Err(ValidationError::UnknownInput(_)) => {
                 self.orphan_pool.insert(tx)?;
-                Ok(TxStorageResponse::OrphanPool)
+                (TxStorageResponse::OrphanPool, None)
             },
             Err(ValidationError::MaturityError) => {
                 self.pending_pool.insert(tx)?;
-                Ok(TxStorageResponse::PendingPool)
+                (TxStorageResponse::PendingPool, None)
             },
-            _ => Ok(TxStorageResponse::NotStored),
+            Err(e) => (TxStorageResponse::NotStored, Some(TxRejection::from(&e))),
+        };
+        if storage != TxStorageResponse::NotStored {
+            self.first_seen.entry(excess_sig).or_insert_with(Instant::now);
         }
+        Ok((storage, rejection))
+    }
+
+    /// Run the transaction through the same validation as [insert](MempoolStorage::insert) and report where it would be
+    /// stored, without storing it.
+    pub fn validate(&self, tx: &Transaction) -> Result<TxValidationReport, MempoolError> {
+        let (db, metadata) = self.blockchain_db.db_and_metadata_read_access()?;
+        let tip_height = metadata.height_of_longest_chain.unwrap_or(0);
+        let excess_sig = tx.body.kernels()[0].excess_sig.clone();
+        let stored = self.has_tx_with_excess_sig(excess_sig.clone())?;
+
+        let (validation_error, storage) = match self.validator.validate(tx, &db, &metadata) {
+            Ok(()) => (None, TxStorageResponse::UnconfirmedPool),
+            Err(e @ ValidationError::UnknownInput(_)) => (Some(TxRejection::from(&e)), TxStorageResponse::OrphanPool),
+            Err(e @ ValidationError::MaturityError) => (Some(TxRejection::from(&e)), TxStorageResponse::PendingPool),
+            Err(e) => (Some(TxRejection::from(&e)), TxStorageResponse::NotStored),
+        };
+
+        let conflicts = self
+            .snapshot()?
+            .iter()
+            .filter(|pool_tx| pool_tx.body.kernels()[0].excess_sig != excess_sig)
+            .filter(|pool_tx| pool_tx.body.inputs().iter().any(|i| tx.body.inputs().contains(i)))
+            .map(|pool_tx| pool_tx.body.kernels()[0].excess_sig.clone())
+            .collect();
+
+        Ok(TxValidationReport {
+            fee: tx.body.get_total_fee(),
+            weight: tx.calculate_weight(),
+            fee_per_gram: tx.calculate_ave_fee_per_gram(),
+            tip_height,
+            min_spendable_height: tx.min_spendable_height(),
+            validation_error,
+            relay_rejection: None,
+            conflicts,
+            storage: if stored == TxStorageResponse::NotStored {
+                storage
+            } else {
+                stored
+            },
+        })
     }
 
     // Insert a set of new transactions into the UTxPool.
@@ -132,7 +201,7 @@ where T: BlockchainBackend
         // Move Time-locked txs that have input UTXOs that have recently become valid to PendingPool.
         self.pending_pool.insert_txs(time_locked_txs)?;
 
-        Ok(())
+        self.prune_first_seen()
     }
 
     // Update the Mempool based on the received set of published blocks.
@@ -169,6 +238,17 @@ where T: BlockchainBackend
                 .remove_reorged_txs_and_discard_double_spends(removed_blocks, &new_blocks)?,
         )?;
         self.process_published_blocks(new_blocks)?;
+        self.prune_first_seen()
+    }
+
+    // Forget when transactions that have been published, discarded or expired were first stored.
+    fn prune_first_seen(&mut self) -> Result<(), MempoolError> {
+        let stored = self
+            .snapshot()?
+            .iter()
+            .map(|tx| tx.body.kernels()[0].excess_sig.clone())
+            .collect::<HashSet<_>>();
+        self.first_seen.retain(|excess_sig, _| stored.contains(excess_sig));
         Ok(())
     }
 
@@ -181,6 +261,96 @@ where T: BlockchainBackend
         Ok(txs)
     }
 
+    // Returns the transactions stored in the Mempool, except the transactions stored in the ReorgPool, along with the
+    // pool each transaction is stored in.
+    fn stored_txs(&self) -> Result<Vec<(Arc<Transaction>, TxStorageResponse)>, MempoolError> {
+        let mut txs = Vec::new();
+        txs.extend(
+            self.unconfirmed_pool
+                .snapshot()
+                .into_iter()
+                .map(|tx| (tx, TxStorageResponse::UnconfirmedPool)),
+        );
+        txs.extend(
+            self.orphan_pool
+                .snapshot()?
+                .into_iter()
+                .map(|tx| (tx, TxStorageResponse::OrphanPool)),
+        );
+        txs.extend(
+            self.pending_pool
+                .snapshot()
+                .into_iter()
+                .map(|tx| (tx, TxStorageResponse::PendingPool)),
+        );
+        Ok(txs)
+    }
+
+    // Summarise a stored transaction, its age is measured from when it was first stored in any of the pools.
+    fn summarise(&self, tx: &Transaction, storage: TxStorageResponse, include_transaction: bool) -> MempoolTransaction {
+        let excess_sig = tx.body.kernels()[0].excess_sig.clone();
+        let age_secs = self
+            .first_seen
+            .get(&excess_sig)
+            .map(|first_seen| first_seen.elapsed().as_secs())
+            .unwrap_or(0);
+        MempoolTransaction {
+            excess_sig,
+            storage,
+            fee: tx.body.get_total_fee(),
+            weight: tx.calculate_weight(),
+            fee_per_gram: tx.calculate_ave_fee_per_gram(),
+            age_secs,
+            transaction: if include_transaction { Some(tx.clone()) } else { None },
+        }
+    }
+
+    /// Returns summaries of the transactions in the Mempool, except the transactions stored in the ReorgPool, that
+    /// match the filter.
+    pub fn list_transactions(&self, filter: MempoolTxFilter) -> Result<Vec<MempoolTransaction>, MempoolError> {
+        let min_fee_per_gram = f64::from(filter.min_fee_per_gram);
+        let mut txs = self
+            .stored_txs()?
+            .iter()
+            .filter(|(tx, _)| tx.calculate_ave_fee_per_gram() >= min_fee_per_gram)
+            .map(|(tx, storage)| self.summarise(tx, *storage, filter.include_transactions))
+            .collect::<Vec<_>>();
+        match filter.sort {
+            TxSortOrder::Fee => {
+                txs.sort_by(|a, b| b.fee_per_gram.partial_cmp(&a.fee_per_gram).unwrap_or(Ordering::Equal))
+            },
+            TxSortOrder::Age => txs.sort_by(|a, b| b.age_secs.cmp(&a.age_secs)),
+        }
+        let start = match &filter.after {
+            Some(after) => txs
+                .iter()
+                .position(|tx| &tx.excess_sig == after)
+                .map(|i| i + 1)
+                .ok_or(MempoolError::PageCursorNotFound)?,
+            None => 0,
+        };
+        let page_size = filter
+            .limit
+            .map(|limit| limit.min(self.max_list_page_size))
+            .unwrap_or(self.max_list_page_size);
+        Ok(txs.into_iter().skip(start).take(page_size).collect())
+    }
+
+    /// Returns the specified transaction, along with its summary, if it is stored in any of the pools.
+    pub fn get_transaction(&self, excess_sig: Signature) -> Result<Option<MempoolTransaction>, MempoolError> {
+        let mut txs = self.stored_txs()?;
+        txs.extend(
+            self.reorg_pool
+                .snapshot()?
+                .into_iter()
+                .map(|tx| (tx, TxStorageResponse::ReorgPool)),
+        );
+        Ok(txs
+            .iter()
+            .find(|(tx, _)| tx.body.kernels()[0].excess_sig == excess_sig)
+            .map(|(tx, storage)| self.summarise(tx, *storage, true)))
+    }
+
     /// Returns a list of transaction ranked by transaction priority up to a given weight.
     pub fn retrieve(&self, total_weight: u64) -> Result<Vec<Arc<Transaction>>, MempoolError> {
         Ok(self.unconfirmed_pool.highest_priority_txs(total_weight)?)
@@ -223,6 +393,9 @@ where T: BlockchainBackend
             timelocked_txs: self.pending_pool.len(),
             published_txs: self.reorg_pool.len()?,
             total_weight: self.calculate_weight()?,
+            relay_rejected_low_fee: 0,
+            relay_rejected_overweight: 0,
+            relay_rejected_non_standard: 0,
         })
     }
 
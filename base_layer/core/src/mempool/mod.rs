@@ -38,6 +38,8 @@ mod pending_pool;
 #[cfg(feature = "base_node")]
 mod priority;
 #[cfg(feature = "base_node")]
+mod relay_policy;
+#[cfg(feature = "base_node")]
 mod reorg_pool;
 #[cfg(feature = "base_node")]
 mod unconfirmed_pool;
@@ -54,6 +56,8 @@ pub use error::MempoolError;
 #[cfg(feature = "base_node")]
 pub use mempool::{Mempool, MempoolValidators};
 #[cfg(feature = "base_node")]
+pub use relay_policy::{RelayConfig, RelayPolicy, RelayRejection, RelayRejectionCounts};
+#[cfg(feature = "base_node")]
 pub use service::{MempoolServiceError, MempoolServiceInitializer, OutboundMempoolServiceInterface};
 
 #[cfg(any(feature = "base_node", feature = "mempool_proto"))]
@@ -61,7 +65,13 @@ pub mod proto;
 #[cfg(any(feature = "base_node", feature = "mempool_proto"))]
 pub mod service;
 
-use crate::transactions::types::Signature;
+use crate::transactions::{
+    tari_amount::MicroTari,
+    transaction::Transaction,
+    types::{Commitment, Signature},
+};
+#[cfg(feature = "base_node")]
+use crate::validation::ValidationError;
 use core::fmt::{Display, Error, Formatter};
 use serde::{Deserialize, Serialize};
 use tari_crypto::tari_utilities::hex::Hex;
@@ -74,6 +84,9 @@ pub struct StatsResponse {
     pub timelocked_txs: usize,
     pub published_txs: usize,
     pub total_weight: u64,
+    pub relay_rejected_low_fee: u64,
+    pub relay_rejected_overweight: u64,
+    pub relay_rejected_non_standard: u64,
 }
 
 impl Display for StatsResponse {
@@ -81,13 +94,16 @@ impl Display for StatsResponse {
         write!(
             fmt,
             "Mempool stats: Total transactions: {}, Unconfirmed: {}, Orphaned: {}, Time locked: {}, Published: {}, \
-             Total Weight: {}",
+             Total Weight: {}, Relay rejections: Low fee: {}, Overweight: {}, Non-standard: {}",
             self.total_txs,
             self.unconfirmed_txs,
             self.orphan_txs,
             self.timelocked_txs,
             self.published_txs,
-            self.total_weight
+            self.total_weight,
+            self.relay_rejected_low_fee,
+            self.relay_rejected_overweight,
+            self.relay_rejected_non_standard
         )
     }
 }
@@ -123,7 +139,7 @@ impl Display for StateResponse {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TxStorageResponse {
     UnconfirmedPool,
     OrphanPool,
@@ -144,3 +160,211 @@ impl Display for TxStorageResponse {
         fmt.write_str(&storage.to_string())
     }
 }
+
+/// A detailed report of how the mempool treats a transaction, produced without storing or propagating it so that
+/// transactions can be dry-run before they are submitted.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TxValidationReport {
+    /// The total fee paid by the transaction
+    pub fee: MicroTari,
+    /// The weight of the transaction
+    pub weight: u64,
+    /// The average fee per gram paid by the transaction
+    pub fee_per_gram: f64,
+    /// The height of the chain tip the transaction was validated against
+    pub tip_height: u64,
+    /// The lowest height at which all kernel lock heights and input maturities have passed
+    pub min_spendable_height: u64,
+    /// The reason the transaction failed stateless or stateful validation, if it did
+    pub validation_error: Option<TxRejection>,
+    /// The reason the relay policy refused the transaction, if it did
+    pub relay_rejection: Option<TxRejection>,
+    /// The excess signatures of transactions in the mempool that spend one or more of the same inputs
+    pub conflicts: Vec<Signature>,
+    /// The pool the transaction is stored in, or would be stored in when submitted
+    pub storage: TxStorageResponse,
+}
+
+impl TxValidationReport {
+    /// Returns true if the transaction would be accepted into one of the mempool pools when submitted
+    pub fn is_accepted(&self) -> bool {
+        self.storage != TxStorageResponse::NotStored && self.storage != TxStorageResponse::ReorgPool
+    }
+
+    /// Returns true if the transaction can only be mined once the chain has grown past the current tip
+    pub fn has_maturity_issue(&self) -> bool {
+        self.min_spendable_height > self.tip_height + 1
+    }
+}
+
+impl Display for TxValidationReport {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        writeln!(fmt, "Accepted: {} ({})", self.is_accepted(), self.storage)?;
+        writeln!(
+            fmt,
+            "Fee: {}, Weight: {}, Fee per gram: {:.2}",
+            self.fee, self.weight, self.fee_per_gram
+        )?;
+        writeln!(
+            fmt,
+            "Min spendable height: {}, Tip height: {}{}",
+            self.min_spendable_height,
+            self.tip_height,
+            if self.has_maturity_issue() {
+                " (time locked)"
+            } else {
+                ""
+            }
+        )?;
+        if let Some(err) = &self.validation_error {
+            writeln!(fmt, "Validation error: {}", err)?;
+        }
+        if let Some(reason) = &self.relay_rejection {
+            writeln!(fmt, "Relay policy rejection: {}", reason)?;
+        }
+        for excess_sig in &self.conflicts {
+            writeln!(fmt, "Conflicts with: {}", excess_sig.get_signature().to_hex())?;
+        }
+        Ok(())
+    }
+}
+
+/// The part of a transaction or block that caused it to fail validation
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ValidationComponent {
+    /// The kernel at this index in the body
+    Kernel(usize),
+    /// The input with this commitment
+    Input(Commitment),
+    /// The output with this commitment
+    Output(Commitment),
+}
+
+impl Display for ValidationComponent {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            ValidationComponent::Kernel(i) => write!(fmt, "kernel {}", i),
+            ValidationComponent::Input(c) => write!(fmt, "input {}", c.to_hex()),
+            ValidationComponent::Output(c) => write!(fmt, "output {}", c.to_hex()),
+        }
+    }
+}
+
+/// The reason a transaction was refused by the mempool. The code is stable across releases so that clients can act on
+/// the cause of a rejection without parsing the reason, which is only meant for humans.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TxRejection {
+    /// Validation error codes are listed in [ValidationError::code], relay policy rejections use 401-499
+    pub code: u32,
+    /// The kernel, input or output that caused the rejection, if it can be attributed to one
+    pub component: Option<ValidationComponent>,
+    pub reason: String,
+}
+
+impl TxRejection {
+    /// The rejection returned for transactions submitted while the base node is in maintenance mode
+    pub fn maintenance_mode() -> Self {
+        Self {
+            code: 404,
+            component: None,
+            reason: "The base node is in maintenance mode and is not accepting new transactions".to_string(),
+        }
+    }
+}
+
+impl Display for TxRejection {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(fmt, "[{}] {}", self.code, self.reason)?;
+        if let Some(component) = &self.component {
+            write!(fmt, " ({})", component)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "base_node")]
+impl From<&ValidationError> for TxRejection {
+    fn from(err: &ValidationError) -> Self {
+        Self {
+            code: err.code(),
+            component: err.component(),
+            reason: err.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "base_node")]
+impl From<RelayRejection> for TxRejection {
+    fn from(rejection: RelayRejection) -> Self {
+        let code = match rejection {
+            RelayRejection::FeeTooLow => 401,
+            RelayRejection::WeightTooHigh => 402,
+            RelayRejection::NonStandardFeatures => 403,
+        };
+        Self {
+            code,
+            component: None,
+            reason: rejection.to_string(),
+        }
+    }
+}
+
+/// The order in which [MempoolTransaction]s are listed
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TxSortOrder {
+    /// Highest fee per gram first
+    Fee,
+    /// Longest waiting first
+    Age,
+}
+
+impl Default for TxSortOrder {
+    fn default() -> Self {
+        TxSortOrder::Fee
+    }
+}
+
+/// Selects which transactions are listed from the unconfirmed, orphan and pending pools
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MempoolTxFilter {
+    /// Transactions paying a lower average fee per gram are left out
+    pub min_fee_per_gram: MicroTari,
+    pub sort: TxSortOrder,
+    /// Include the full transaction bodies, rather than only the summaries
+    pub include_transactions: bool,
+    /// Only list the transactions after the transaction with this excess signature in the sort order. Set to the
+    /// excess signature of the last transaction of the previous page to request the next page.
+    pub after: Option<Signature>,
+    /// The maximum number of transactions to list. The node limits the size of a page, so fewer transactions may be
+    /// listed even if more are available.
+    pub limit: Option<usize>,
+}
+
+/// A summary of a transaction stored in the mempool, optionally including the transaction itself
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MempoolTransaction {
+    pub excess_sig: Signature,
+    pub storage: TxStorageResponse,
+    pub fee: MicroTari,
+    pub weight: u64,
+    pub fee_per_gram: f64,
+    /// The number of seconds since the transaction was first stored by this node
+    pub age_secs: u64,
+    pub transaction: Option<Transaction>,
+}
+
+impl Display for MempoolTransaction {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            fmt,
+            "{} {} | {} | Fee: {}, Weight: {}, Fee per gram: {:.2} | Age: {}s",
+            self.excess_sig.get_public_nonce().to_hex(),
+            self.excess_sig.get_signature().to_hex(),
+            self.storage,
+            self.fee,
+            self.weight,
+            self.fee_per_gram,
+            self.age_secs
+        )
+    }
+}
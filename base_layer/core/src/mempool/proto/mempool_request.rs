@@ -41,6 +41,11 @@ impl TryInto<MempoolRequest> for ProtoMempoolRequest {
                 excess_sig.try_into().map_err(|err: ByteArrayError| err.to_string())?,
             ),
             SubmitTransaction(tx) => MempoolRequest::SubmitTransaction(tx.try_into()?),
+            ValidateTransaction(tx) => MempoolRequest::ValidateTransaction(tx.try_into()?),
+            ListTransactions(filter) => MempoolRequest::ListTransactions(filter.try_into()?),
+            GetTransaction(excess_sig) => {
+                MempoolRequest::GetTransaction(excess_sig.try_into().map_err(|err: ByteArrayError| err.to_string())?)
+            },
         };
         Ok(request)
     }
@@ -54,6 +59,9 @@ impl From<MempoolRequest> for ProtoMempoolRequest {
             GetState => ProtoMempoolRequest::GetState(true),
             GetTxStateWithExcessSig(excess_sig) => ProtoMempoolRequest::GetTxStateWithExcessSig(excess_sig.into()),
             SubmitTransaction(tx) => ProtoMempoolRequest::SubmitTransaction(tx.into()),
+            ValidateTransaction(tx) => ProtoMempoolRequest::ValidateTransaction(tx.into()),
+            ListTransactions(filter) => ProtoMempoolRequest::ListTransactions(filter.into()),
+            GetTransaction(excess_sig) => ProtoMempoolRequest::GetTransaction(excess_sig.into()),
         }
     }
 }
@@ -24,6 +24,8 @@ use super::mempool::mempool_service_response::Response as ProtoMempoolResponse;
 use crate::mempool::{
     proto::mempool::{
         MempoolServiceResponse as ProtoMempoolServiceResponse,
+        MempoolTransactionResponse as ProtoMempoolTransactionResponse,
+        MempoolTransactions as ProtoMempoolTransactions,
         TxStorageResponse as ProtoTxStorageResponse,
     },
     service::{MempoolResponse, MempoolServiceResponse},
@@ -43,6 +45,17 @@ impl TryInto<MempoolResponse> for ProtoMempoolResponse {
                     .ok_or_else(|| "Invalid or unrecognised `TxStorageResponse` enum".to_string())?;
                 MempoolResponse::TxStorage(tx_storage_response.try_into()?)
             },
+            TxValidationReport(report) => MempoolResponse::TxValidationReport(report.try_into()?),
+            Transactions(txs) => MempoolResponse::Transactions(
+                txs.transactions
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Transaction(response) => {
+                MempoolResponse::Transaction(response.transaction.map(TryInto::try_into).transpose()?)
+            },
+            TxRejected(rejection) => MempoolResponse::TxRejected(rejection.try_into()?),
         };
         Ok(response)
     }
@@ -72,6 +85,14 @@ impl From<MempoolResponse> for ProtoMempoolResponse {
                 let tx_storage_response: ProtoTxStorageResponse = tx_storage_response.into();
                 ProtoMempoolResponse::TxStorage(tx_storage_response.into())
             },
+            TxValidationReport(report) => ProtoMempoolResponse::TxValidationReport(report.into()),
+            Transactions(txs) => ProtoMempoolResponse::Transactions(ProtoMempoolTransactions {
+                transactions: txs.into_iter().map(Into::into).collect(),
+            }),
+            Transaction(tx) => ProtoMempoolResponse::Transaction(ProtoMempoolTransactionResponse {
+                transaction: tx.map(Into::into),
+            }),
+            TxRejected(rejection) => ProtoMempoolResponse::TxRejected(rejection.into()),
         }
     }
 }
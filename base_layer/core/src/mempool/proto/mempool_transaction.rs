@@ -0,0 +1,122 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use crate::mempool::{
+    proto::mempool::{
+        MempoolTransaction as ProtoMempoolTransaction,
+        MempoolTxFilter as ProtoMempoolTxFilter,
+        TxSortOrder as ProtoTxSortOrder,
+        TxStorageResponse as ProtoTxStorageResponse,
+    },
+    MempoolTransaction,
+    MempoolTxFilter,
+    TxSortOrder,
+};
+use std::convert::{TryFrom, TryInto};
+use tari_crypto::tari_utilities::ByteArrayError;
+
+impl From<ProtoTxSortOrder> for TxSortOrder {
+    fn from(sort: ProtoTxSortOrder) -> Self {
+        match sort {
+            ProtoTxSortOrder::Fee => TxSortOrder::Fee,
+            ProtoTxSortOrder::Age => TxSortOrder::Age,
+        }
+    }
+}
+
+impl From<TxSortOrder> for ProtoTxSortOrder {
+    fn from(sort: TxSortOrder) -> Self {
+        match sort {
+            TxSortOrder::Fee => ProtoTxSortOrder::Fee,
+            TxSortOrder::Age => ProtoTxSortOrder::Age,
+        }
+    }
+}
+
+impl TryFrom<ProtoMempoolTxFilter> for MempoolTxFilter {
+    type Error = String;
+
+    fn try_from(filter: ProtoMempoolTxFilter) -> Result<Self, Self::Error> {
+        let sort = ProtoTxSortOrder::from_i32(filter.sort)
+            .ok_or_else(|| "Invalid or unrecognised `TxSortOrder` enum".to_string())?;
+        Ok(Self {
+            min_fee_per_gram: filter.min_fee_per_gram.into(),
+            sort: sort.into(),
+            include_transactions: filter.include_transactions,
+            after: filter
+                .after
+                .map(TryInto::try_into)
+                .transpose()
+                .map_err(|err: ByteArrayError| err.to_string())?,
+            limit: Some(filter.limit as usize).filter(|limit| *limit > 0),
+        })
+    }
+}
+
+impl From<MempoolTxFilter> for ProtoMempoolTxFilter {
+    fn from(filter: MempoolTxFilter) -> Self {
+        let sort: ProtoTxSortOrder = filter.sort.into();
+        Self {
+            min_fee_per_gram: filter.min_fee_per_gram.into(),
+            sort: sort.into(),
+            include_transactions: filter.include_transactions,
+            after: filter.after.map(Into::into),
+            limit: filter.limit.unwrap_or_default() as u64,
+        }
+    }
+}
+
+impl TryFrom<ProtoMempoolTransaction> for MempoolTransaction {
+    type Error = String;
+
+    fn try_from(tx: ProtoMempoolTransaction) -> Result<Self, Self::Error> {
+        let storage = ProtoTxStorageResponse::from_i32(tx.storage)
+            .ok_or_else(|| "Invalid or unrecognised `TxStorageResponse` enum".to_string())?;
+        Ok(Self {
+            excess_sig: tx
+                .excess_sig
+                .ok_or_else(|| "Excess signature not provided".to_string())?
+                .try_into()
+                .map_err(|err: ByteArrayError| err.to_string())?,
+            storage: storage.try_into()?,
+            fee: tx.fee.into(),
+            weight: tx.weight,
+            fee_per_gram: tx.fee_per_gram,
+            age_secs: tx.age_secs,
+            transaction: tx.transaction.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl From<MempoolTransaction> for ProtoMempoolTransaction {
+    fn from(tx: MempoolTransaction) -> Self {
+        let storage: ProtoTxStorageResponse = tx.storage.into();
+        Self {
+            excess_sig: Some(tx.excess_sig.into()),
+            storage: storage.into(),
+            fee: tx.fee.into(),
+            weight: tx.weight,
+            fee_per_gram: tx.fee_per_gram,
+            age_secs: tx.age_secs,
+            transaction: tx.transaction.map(Into::into),
+        }
+    }
+}
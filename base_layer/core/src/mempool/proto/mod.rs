@@ -29,7 +29,10 @@ pub mod mempool {
 
 pub mod mempool_request;
 pub mod mempool_response;
+pub mod mempool_transaction;
 pub mod state_response;
 pub mod stats_response;
+pub mod tx_rejection;
 pub mod tx_storage_response;
+pub mod tx_validation_report;
 pub use mempool::{MempoolServiceRequest, MempoolServiceResponse};
@@ -34,6 +34,9 @@ impl TryFrom<ProtoStatsResponse> for StatsResponse {
             timelocked_txs: stats.timelocked_txs as usize,
             published_txs: stats.published_txs as usize,
             total_weight: stats.total_weight,
+            relay_rejected_low_fee: stats.relay_rejected_low_fee,
+            relay_rejected_overweight: stats.relay_rejected_overweight,
+            relay_rejected_non_standard: stats.relay_rejected_non_standard,
         })
     }
 }
@@ -47,6 +50,9 @@ impl From<StatsResponse> for ProtoStatsResponse {
             timelocked_txs: stats.timelocked_txs as u64,
             published_txs: stats.published_txs as u64,
             total_weight: stats.total_weight,
+            relay_rejected_low_fee: stats.relay_rejected_low_fee,
+            relay_rejected_overweight: stats.relay_rejected_overweight,
+            relay_rejected_non_standard: stats.relay_rejected_non_standard,
         }
     }
 }
@@ -0,0 +1,87 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::mempool::{
+    proto::mempool::{
+        validation_component::Component as ProtoComponent,
+        TxRejection as ProtoTxRejection,
+        ValidationComponent as ProtoValidationComponent,
+    },
+    TxRejection,
+    ValidationComponent,
+};
+use std::convert::{TryFrom, TryInto};
+use tari_crypto::tari_utilities::ByteArrayError;
+
+impl TryFrom<ProtoValidationComponent> for ValidationComponent {
+    type Error = String;
+
+    fn try_from(component: ProtoValidationComponent) -> Result<Self, Self::Error> {
+        let component = component
+            .component
+            .ok_or_else(|| "Validation component not provided".to_string())?;
+        Ok(match component {
+            ProtoComponent::Kernel(index) => ValidationComponent::Kernel(index as usize),
+            ProtoComponent::Input(commitment) => {
+                ValidationComponent::Input(commitment.try_into().map_err(|err: ByteArrayError| err.to_string())?)
+            },
+            ProtoComponent::Output(commitment) => {
+                ValidationComponent::Output(commitment.try_into().map_err(|err: ByteArrayError| err.to_string())?)
+            },
+        })
+    }
+}
+
+impl From<ValidationComponent> for ProtoValidationComponent {
+    fn from(component: ValidationComponent) -> Self {
+        let component = match component {
+            ValidationComponent::Kernel(index) => ProtoComponent::Kernel(index as u64),
+            ValidationComponent::Input(commitment) => ProtoComponent::Input(commitment.into()),
+            ValidationComponent::Output(commitment) => ProtoComponent::Output(commitment.into()),
+        };
+        Self {
+            component: Some(component),
+        }
+    }
+}
+
+impl TryFrom<ProtoTxRejection> for TxRejection {
+    type Error = String;
+
+    fn try_from(rejection: ProtoTxRejection) -> Result<Self, Self::Error> {
+        Ok(Self {
+            code: rejection.code,
+            component: rejection.component.map(TryInto::try_into).transpose()?,
+            reason: rejection.reason,
+        })
+    }
+}
+
+impl From<TxRejection> for ProtoTxRejection {
+    fn from(rejection: TxRejection) -> Self {
+        Self {
+            code: rejection.code,
+            component: rejection.component.map(Into::into),
+            reason: rejection.reason,
+        }
+    }
+}
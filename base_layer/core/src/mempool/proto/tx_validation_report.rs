@@ -0,0 +1,70 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::mempool::{
+    proto::mempool::{TxStorageResponse as ProtoTxStorageResponse, TxValidationReport as ProtoTxValidationReport},
+    TxValidationReport,
+};
+use std::convert::{TryFrom, TryInto};
+use tari_crypto::tari_utilities::ByteArrayError;
+
+impl TryFrom<ProtoTxValidationReport> for TxValidationReport {
+    type Error = String;
+
+    fn try_from(report: ProtoTxValidationReport) -> Result<Self, Self::Error> {
+        let storage = ProtoTxStorageResponse::from_i32(report.storage)
+            .ok_or_else(|| "Invalid or unrecognised `TxStorageResponse` enum".to_string())?;
+        Ok(Self {
+            fee: report.fee.into(),
+            weight: report.weight,
+            fee_per_gram: report.fee_per_gram,
+            tip_height: report.tip_height,
+            min_spendable_height: report.min_spendable_height,
+            validation_error: report.validation_error.map(TryInto::try_into).transpose()?,
+            relay_rejection: report.relay_rejection.map(TryInto::try_into).transpose()?,
+            conflicts: report
+                .conflicts
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err: ByteArrayError| err.to_string())?,
+            storage: storage.try_into()?,
+        })
+    }
+}
+
+impl From<TxValidationReport> for ProtoTxValidationReport {
+    fn from(report: TxValidationReport) -> Self {
+        let storage: ProtoTxStorageResponse = report.storage.into();
+        Self {
+            fee: report.fee.into(),
+            weight: report.weight,
+            fee_per_gram: report.fee_per_gram,
+            tip_height: report.tip_height,
+            min_spendable_height: report.min_spendable_height,
+            validation_error: report.validation_error.map(Into::into),
+            relay_rejection: report.relay_rejection.map(Into::into),
+            conflicts: report.conflicts.into_iter().map(Into::into).collect(),
+            storage: storage.into(),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    mempool::consts::{MEMPOOL_RELAY_MAX_TX_WEIGHT, MEMPOOL_RELAY_MIN_FEE_PER_GRAM},
+    transactions::{
+        tari_amount::MicroTari,
+        transaction::{KernelFeatures, OutputFlags, Transaction},
+    },
+};
+use log::*;
+use std::{
+    fmt::{Display, Error, Formatter},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tari_crypto::tari_utilities::hex::Hex;
+
+pub const LOG_TARGET: &str = "c::mp::relay_policy";
+
+/// Configuration for the relay policy that is applied to transactions before they are admitted to the mempool and
+/// propagated to peers
+#[derive(Clone, Copy)]
+pub struct RelayConfig {
+    /// Transactions paying a lower average fee per gram are not admitted or relayed
+    pub min_fee_per_gram: MicroTari,
+    /// Transactions heavier than this are not admitted or relayed
+    pub max_tx_weight: u64,
+    /// Whether to admit and relay transactions with non-standard output or kernel features, e.g. coinbase flags
+    pub relay_non_standard_features: bool,
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            min_fee_per_gram: MEMPOOL_RELAY_MIN_FEE_PER_GRAM,
+            max_tx_weight: MEMPOOL_RELAY_MAX_TX_WEIGHT,
+            relay_non_standard_features: false,
+        }
+    }
+}
+
+/// The reason a transaction was refused by the relay policy
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RelayRejection {
+    FeeTooLow,
+    WeightTooHigh,
+    NonStandardFeatures,
+}
+
+impl Display for RelayRejection {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        let reason = match self {
+            RelayRejection::FeeTooLow => "Fee per gram too low",
+            RelayRejection::WeightTooHigh => "Transaction weight too high",
+            RelayRejection::NonStandardFeatures => "Non-standard output or kernel features",
+        };
+        fmt.write_str(reason)
+    }
+}
+
+/// The number of transactions that have been refused by the relay policy, for each rejection reason
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RelayRejectionCounts {
+    pub fee_too_low: u64,
+    pub weight_too_high: u64,
+    pub non_standard_features: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    fee_too_low: AtomicU64,
+    weight_too_high: AtomicU64,
+    non_standard_features: AtomicU64,
+}
+
+/// Applies the [RelayConfig] to transactions and counts the rejections. Cloned policies share their counters.
+#[derive(Clone)]
+pub struct RelayPolicy {
+    config: RelayConfig,
+    counters: Arc<Counters>,
+}
+
+impl RelayPolicy {
+    pub fn new(config: RelayConfig) -> Self {
+        Self {
+            config,
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    /// Check the transaction against the policy, counting the rejection reason if it is refused
+    pub fn check(&self, tx: &Transaction) -> Result<(), RelayRejection> {
+        let result = self.evaluate(tx);
+        if let Err(reason) = result {
+            debug!(
+                target: LOG_TARGET,
+                "Transaction ({}) refused by relay policy: {}",
+                tx.body
+                    .kernels()
+                    .first()
+                    .map(|k| k.excess_sig.get_signature().to_hex())
+                    .unwrap_or_default(),
+                reason
+            );
+            let counter = match reason {
+                RelayRejection::FeeTooLow => &self.counters.fee_too_low,
+                RelayRejection::WeightTooHigh => &self.counters.weight_too_high,
+                RelayRejection::NonStandardFeatures => &self.counters.non_standard_features,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Returns the number of transactions refused so far for each reason
+    pub fn rejection_counts(&self) -> RelayRejectionCounts {
+        RelayRejectionCounts {
+            fee_too_low: self.counters.fee_too_low.load(Ordering::Relaxed),
+            weight_too_high: self.counters.weight_too_high.load(Ordering::Relaxed),
+            non_standard_features: self.counters.non_standard_features.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Check the transaction against the policy without counting the rejection, e.g. for dry-runs
+    pub fn evaluate(&self, tx: &Transaction) -> Result<(), RelayRejection> {
+        if tx.calculate_weight() > self.config.max_tx_weight {
+            return Err(RelayRejection::WeightTooHigh);
+        }
+        if tx.calculate_ave_fee_per_gram() < u64::from(self.config.min_fee_per_gram) as f64 {
+            return Err(RelayRejection::FeeTooLow);
+        }
+        if !self.config.relay_non_standard_features && !is_standard(tx) {
+            return Err(RelayRejection::NonStandardFeatures);
+        }
+        Ok(())
+    }
+}
+
+/// A standard transaction only uses the default output and kernel features. Coinbase features only belong in blocks
+/// and output feature extensions are reserved for features that are not active yet.
+fn is_standard(tx: &Transaction) -> bool {
+    tx.body
+        .outputs()
+        .iter()
+        .all(|o| !o.features.flags.contains(OutputFlags::COINBASE_OUTPUT) && o.features.extensions.is_empty()) &&
+        tx.body
+            .kernels()
+            .iter()
+            .all(|k| !k.features.contains(KernelFeatures::COINBASE_KERNEL))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{transactions::helpers::create_test_kernel, tx};
+
+    #[test]
+    fn relay_policy_rejections() {
+        let tx = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let policy = RelayPolicy::new(RelayConfig::default());
+        assert!(policy.check(&tx).is_ok());
+
+        let policy = RelayPolicy::new(RelayConfig {
+            min_fee_per_gram: MicroTari(tx.calculate_ave_fee_per_gram() as u64 + 1),
+            ..Default::default()
+        });
+        assert_eq!(policy.check(&tx), Err(RelayRejection::FeeTooLow));
+        assert_eq!(policy.check(&tx), Err(RelayRejection::FeeTooLow));
+        // Clones share the counters
+        assert_eq!(policy.clone().rejection_counts().fee_too_low, 2);
+
+        let policy = RelayPolicy::new(RelayConfig {
+            max_tx_weight: tx.calculate_weight() - 1,
+            ..Default::default()
+        });
+        assert_eq!(policy.check(&tx), Err(RelayRejection::WeightTooHigh));
+        assert_eq!(policy.rejection_counts(), RelayRejectionCounts {
+            weight_too_high: 1,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn non_standard_features() {
+        let mut tx = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        assert!(is_standard(&tx));
+
+        let mut outputs = tx.body.outputs().clone();
+        outputs[0].features.set_extension(3, vec![1]).unwrap();
+        let extended_tx = Transaction::new(
+            tx.body.inputs().clone(),
+            outputs,
+            tx.body.kernels().clone(),
+            tx.offset.clone(),
+        );
+        assert!(!is_standard(&extended_tx));
+
+        let mut kernel = create_test_kernel(MicroTari(0), 0);
+        kernel.features = KernelFeatures::create_coinbase();
+        tx.body.add_kernel(kernel);
+        assert!(!is_standard(&tx));
+
+        let policy = RelayPolicy::new(RelayConfig::default());
+        assert_eq!(policy.check(&tx), Err(RelayRejection::NonStandardFeatures));
+        let policy = RelayPolicy::new(RelayConfig {
+            relay_non_standard_features: true,
+            ..Default::default()
+        });
+        assert!(policy.check(&tx).is_ok());
+    }
+}
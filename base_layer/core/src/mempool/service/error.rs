@@ -20,7 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{base_node::WaitingRequestError, mempool::MempoolError};
+use crate::{
+    base_node::WaitingRequestError,
+    mempool::{MempoolError, TxRejection},
+};
 use derive_error::Error;
 use tari_comms_dht::outbound::DhtOutboundError;
 use tari_service_framework::reply_channel::TransportChannelError;
@@ -42,4 +45,7 @@ pub enum MempoolServiceError {
     /// Failed to send broadcast message
     BroadcastFailed,
     WaitingRequestError(WaitingRequestError),
+    /// The mempool refused the transaction
+    #[error(no_from, non_std)]
+    TransactionRejected(TxRejection),
 }
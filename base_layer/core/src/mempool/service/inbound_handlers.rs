@@ -27,12 +27,16 @@ use crate::{
         async_mempool,
         service::{MempoolRequest, MempoolResponse, MempoolServiceError, OutboundMempoolServiceInterface},
         Mempool,
+        TxRejection,
         TxStorageResponse,
     },
     transactions::transaction::Transaction,
 };
 use log::*;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use tari_comms::types::CommsPublicKey;
 use tari_crypto::tari_utilities::hex::Hex;
 
@@ -45,6 +49,7 @@ where T: BlockchainBackend + 'static
 {
     mempool: Mempool<T>,
     outbound_nmi: OutboundMempoolServiceInterface,
+    maintenance_flag: Arc<AtomicBool>,
 }
 
 impl<T> MempoolInboundHandlers<T>
@@ -52,7 +57,21 @@ where T: BlockchainBackend + 'static
 {
     /// Construct the MempoolInboundHandlers.
     pub fn new(mempool: Mempool<T>, outbound_nmi: OutboundMempoolServiceInterface) -> Self {
-        Self { mempool, outbound_nmi }
+        Self {
+            mempool,
+            outbound_nmi,
+            maintenance_flag: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// New transactions are refused while the given flag is set
+    pub fn with_maintenance_flag(mut self, maintenance_flag: Arc<AtomicBool>) -> Self {
+        self.maintenance_flag = maintenance_flag;
+        self
+    }
+
+    fn in_maintenance_mode(&self) -> bool {
+        self.maintenance_flag.load(Ordering::SeqCst)
     }
 
     /// Handle inbound Mempool service requests from remote nodes and local services.
@@ -74,8 +93,23 @@ where T: BlockchainBackend + 'static
                     "Transaction ({}) submitted using request.",
                     tx.body.kernels()[0].excess_sig.get_signature().to_hex(),
                 );
-                Ok(MempoolResponse::TxStorage(self.submit_transaction(tx, vec![]).await?))
+                if self.in_maintenance_mode() {
+                    return Ok(MempoolResponse::TxRejected(TxRejection::maintenance_mode()));
+                }
+                match self.submit_transaction(tx, vec![]).await? {
+                    (_, Some(rejection)) => Ok(MempoolResponse::TxRejected(rejection)),
+                    (tx_storage, None) => Ok(MempoolResponse::TxStorage(tx_storage)),
+                }
             },
+            MempoolRequest::ValidateTransaction(tx) => Ok(MempoolResponse::TxValidationReport(
+                async_mempool::validate_transaction(self.mempool.clone(), Arc::new(tx.clone())).await?,
+            )),
+            MempoolRequest::ListTransactions(filter) => Ok(MempoolResponse::Transactions(
+                async_mempool::list_transactions(self.mempool.clone(), filter.clone()).await?,
+            )),
+            MempoolRequest::GetTransaction(excess_sig) => Ok(MempoolResponse::Transaction(
+                async_mempool::get_transaction(self.mempool.clone(), excess_sig.clone()).await?,
+            )),
         }
     }
 
@@ -95,24 +129,33 @@ where T: BlockchainBackend + 'static
                 .map(|p| format!("remote peer: {}", p))
                 .unwrap_or_else(|| "local services".to_string())
         );
+        if self.in_maintenance_mode() {
+            debug!(
+                target: LOG_TARGET,
+                "Transaction ({}) ignored, the node is in maintenance mode.",
+                tx.body.kernels()[0].excess_sig.get_signature().to_hex()
+            );
+            return Ok(());
+        }
         let exclude_peers = source_peer.into_iter().collect();
         self.submit_transaction(tx, exclude_peers).await.map(|_| ())
     }
 
-    // Submits a transaction to the mempool and propagate valid transactions.
+    // Submits a transaction to the mempool and propagate valid transactions. The reason the transaction was refused is
+    // returned with the storage response when it was not stored.
     async fn submit_transaction(
         &mut self,
         tx: &Transaction,
         exclude_peers: Vec<CommsPublicKey>,
-    ) -> Result<TxStorageResponse, MempoolServiceError>
+    ) -> Result<(TxStorageResponse, Option<TxRejection>), MempoolServiceError>
     {
         trace!(target: LOG_TARGET, "Transaction: {}.", tx);
         let tx_storage =
             async_mempool::has_tx_with_excess_sig(self.mempool.clone(), tx.body.kernels()[0].excess_sig.clone())
                 .await?;
         if tx_storage == TxStorageResponse::NotStored {
-            match async_mempool::insert(self.mempool.clone(), Arc::new(tx.clone())).await {
-                Ok(tx_storage) => {
+            match async_mempool::insert_with_rejection(self.mempool.clone(), Arc::new(tx.clone())).await {
+                Ok((tx_storage, rejection)) => {
                     debug!(
                         target: LOG_TARGET,
                         "Transaction inserted into mempool: {}, pool: {}.",
@@ -134,7 +177,15 @@ where T: BlockchainBackend + 'static
                         );
                         self.outbound_nmi.propagate_tx(tx.clone(), exclude_peers).await?;
                     }
-                    return Ok(tx_storage);
+                    if let Some(rejection) = &rejection {
+                        debug!(
+                            target: LOG_TARGET,
+                            "Transaction ({}) rejected: {}",
+                            tx.body.kernels()[0].excess_sig.get_signature().to_hex(),
+                            rejection
+                        );
+                    }
+                    return Ok((tx_storage, rejection));
                 },
                 Err(e) => return Err(MempoolServiceError::MempoolError(e)),
             };
@@ -145,7 +196,7 @@ where T: BlockchainBackend + 'static
                 tx.body.kernels()[0].excess_sig.get_signature().to_hex()
             );
         }
-        Ok(tx_storage)
+        Ok((tx_storage, None))
     }
 
     /// Handle inbound block events from the local base node service.
@@ -173,6 +224,42 @@ where T: BlockchainBackend + 'static
         Self {
             mempool: self.mempool.clone(),
             outbound_nmi: self.outbound_nmi.clone(),
+            maintenance_flag: self.maintenance_flag.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        consensus::{ConsensusManagerBuilder, Network},
+        helpers::create_mem_db,
+        mempool::{MempoolConfig, MempoolValidators},
+        transactions::tari_amount::MicroTari,
+        tx,
+        validation::transaction_validators::TxInputAndMaturityValidator,
+    };
+    use futures::{channel::mpsc::unbounded as futures_mpsc_channel_unbounded, executor::block_on};
+    use tari_service_framework::reply_channel;
+
+    #[test]
+    fn submitted_transactions_are_rejected_in_maintenance_mode() {
+        let consensus_manager = ConsensusManagerBuilder::new(Network::LocalNet).build();
+        let store = create_mem_db(&consensus_manager);
+        let validators = MempoolValidators::new(TxInputAndMaturityValidator {}, TxInputAndMaturityValidator {});
+        let mempool = Mempool::new(store, MempoolConfig::default(), validators);
+        let (request_sender, _) = reply_channel::unbounded();
+        let (tx_sender, _) = futures_mpsc_channel_unbounded();
+        let outbound_nmi = OutboundMempoolServiceInterface::new(request_sender, tx_sender);
+        let maintenance_flag = Arc::new(AtomicBool::new(true));
+        let mut handlers = MempoolInboundHandlers::new(mempool, outbound_nmi).with_maintenance_flag(maintenance_flag);
+
+        let (tx, _, _) = tx!(MicroTari(5_000), fee: MicroTari(50));
+        let response = block_on(handlers.handle_request(&MempoolRequest::SubmitTransaction(tx))).unwrap();
+        match response {
+            MempoolResponse::TxRejected(rejection) => assert_eq!(rejection, TxRejection::maintenance_mode()),
+            response => panic!("Unexpected response: {:?}", response),
         }
     }
 }
@@ -38,7 +38,10 @@ use crate::{
 };
 use futures::{channel::mpsc::unbounded as futures_mpsc_channel_unbounded, future, Future, Stream, StreamExt};
 use log::*;
-use std::{convert::TryFrom, sync::Arc};
+use std::{
+    convert::TryFrom,
+    sync::{atomic::AtomicBool, Arc},
+};
 use tari_comms_dht::outbound::OutboundMessageRequester;
 use tari_p2p::{
     comms_connector::PeerMessage,
@@ -65,6 +68,7 @@ where T: BlockchainBackend
     inbound_message_subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
     mempool: Mempool<T>,
     config: MempoolServiceConfig,
+    maintenance_flag: Arc<AtomicBool>,
 }
 
 impl<T> MempoolServiceInitializer<T>
@@ -81,9 +85,16 @@ where T: BlockchainBackend
             inbound_message_subscription_factory,
             mempool,
             config,
+            maintenance_flag: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Refuse new transactions while the given flag is set
+    pub fn with_maintenance_flag(mut self, maintenance_flag: Arc<AtomicBool>) -> Self {
+        self.maintenance_flag = maintenance_flag;
+        self
+    }
+
     /// Get a stream for inbound Mempool service request messages
     fn inbound_request_stream(&self) -> impl Stream<Item = DomainMessage<proto::MempoolServiceRequest>> {
         self.inbound_message_subscription_factory
@@ -133,6 +144,7 @@ async fn extract_transaction(msg: Arc<PeerMessage>) -> Option<DomainMessage<Tran
             Some(DomainMessage {
                 source_peer: msg.source_peer.clone(),
                 dht_header: msg.dht_header.clone(),
+                reply_block: msg.reply_block.clone(),
                 inner: tx,
             })
         },
@@ -164,22 +176,26 @@ where T: BlockchainBackend + 'static
         let local_mp_interface = LocalMempoolService::new(local_request_sender_service);
         let config = self.config;
         let mempool = self.mempool.clone();
-        let inbound_handlers = MempoolInboundHandlers::new(mempool, outbound_mp_interface.clone());
+        let inbound_handlers = MempoolInboundHandlers::new(mempool, outbound_mp_interface.clone())
+            .with_maintenance_flag(self.maintenance_flag.clone());
 
         // Register handle to OutboundMempoolServiceInterface before waiting for handles to be ready
         handles_fut.register(outbound_mp_interface);
         handles_fut.register(local_mp_interface);
+        let ready_signal = handles_fut.ready_signal::<LocalMempoolService>();
 
         executor.spawn(async move {
             let handles = handles_fut.await;
 
             let outbound_message_service = handles
-                .get_handle::<OutboundMessageRequester>()
+                .wait_for_handle::<OutboundMessageRequester>()
+                .await
                 .expect("OutboundMessageRequester handle required for MempoolService");
 
             let base_node = handles
-                .get_handle::<LocalNodeCommsInterface>()
-                .expect("LocalNodeCommsInterface required to initialize ChainStateSyncService");
+                .wait_for_handle::<LocalNodeCommsInterface>()
+                .await
+                .expect("LocalNodeCommsInterface required to initialize MempoolService");
 
             let streams = MempoolStreams::new(
                 outbound_request_stream,
@@ -191,6 +207,7 @@ where T: BlockchainBackend + 'static
                 base_node.get_block_event_stream(),
             );
             let service = MempoolService::new(outbound_message_service, inbound_handlers, config).start(streams);
+            ready_signal.set_ready();
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
             info!(target: LOG_TARGET, "Mempool Service shutdown");
@@ -20,10 +20,17 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mempool::{
-    service::{MempoolRequest, MempoolResponse, MempoolServiceError},
-    StateResponse,
-    StatsResponse,
+use crate::{
+    mempool::{
+        service::{MempoolRequest, MempoolResponse, MempoolServiceError},
+        MempoolTransaction,
+        MempoolTxFilter,
+        StateResponse,
+        StatsResponse,
+        TxStorageResponse,
+        TxValidationReport,
+    },
+    transactions::{transaction::Transaction, types::Signature},
 };
 use tari_service_framework::reply_channel::{Receiver, SenderService};
 use tower_service::Service;
@@ -68,6 +75,71 @@ impl LocalMempoolService {
             _ => Err(MempoolServiceError::UnexpectedApiResponse),
         }
     }
+
+    /// Returns summaries of the unconfirmed, orphaned and time-locked transactions that match the filter
+    pub async fn list_transactions(
+        &mut self,
+        filter: MempoolTxFilter,
+    ) -> Result<Vec<MempoolTransaction>, MempoolServiceError>
+    {
+        match self
+            .request_sender
+            .call(MempoolRequest::ListTransactions(filter))
+            .await??
+        {
+            MempoolResponse::Transactions(txs) => Ok(txs),
+            _ => Err(MempoolServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns the transaction with the given excess signature, if it is stored in the mempool
+    pub async fn get_transaction(
+        &mut self,
+        excess_sig: Signature,
+    ) -> Result<Option<MempoolTransaction>, MempoolServiceError>
+    {
+        match self
+            .request_sender
+            .call(MempoolRequest::GetTransaction(excess_sig))
+            .await??
+        {
+            MempoolResponse::Transaction(tx) => Ok(tx),
+            _ => Err(MempoolServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Returns a report of the full validation of the transaction by the mempool, without submitting it
+    pub async fn validate_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<TxValidationReport, MempoolServiceError>
+    {
+        match self
+            .request_sender
+            .call(MempoolRequest::ValidateTransaction(transaction))
+            .await??
+        {
+            MempoolResponse::TxValidationReport(r) => Ok(r),
+            _ => Err(MempoolServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Submits the transaction to the mempool, which propagates it to the network if it is accepted
+    pub async fn submit_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<TxStorageResponse, MempoolServiceError>
+    {
+        match self
+            .request_sender
+            .call(MempoolRequest::SubmitTransaction(transaction))
+            .await??
+        {
+            MempoolResponse::TxStorage(s) => Ok(s),
+            MempoolResponse::TxRejected(rejection) => Err(MempoolServiceError::TransactionRejected(rejection)),
+            _ => Err(MempoolServiceError::UnexpectedApiResponse),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -93,6 +165,9 @@ mod test {
             timelocked_txs: 2,
             published_txs: 4,
             total_weight: 1000,
+            relay_rejected_low_fee: 0,
+            relay_rejected_overweight: 0,
+            relay_rejected_non_standard: 0,
         }
     }
 
@@ -22,6 +22,7 @@
 
 use crate::{
     base_node::RequestKey,
+    mempool::MempoolTxFilter,
     transactions::{transaction::Transaction, types::Signature},
 };
 use core::fmt::{Display, Error, Formatter};
@@ -35,6 +36,9 @@ pub enum MempoolRequest {
     GetState,
     GetTxStateWithExcessSig(Signature),
     SubmitTransaction(Transaction),
+    ValidateTransaction(Transaction),
+    ListTransactions(MempoolTxFilter),
+    GetTransaction(Signature),
 }
 
 impl Display for MempoolRequest {
@@ -49,6 +53,17 @@ impl Display for MempoolRequest {
                 "SubmitTransaction ({})",
                 tx.body.kernels()[0].excess_sig.get_signature().to_hex()
             )),
+            MempoolRequest::ValidateTransaction(tx) => f.write_str(&format!(
+                "ValidateTransaction ({})",
+                tx.body.kernels()[0].excess_sig.get_signature().to_hex()
+            )),
+            MempoolRequest::ListTransactions(filter) => f.write_str(&format!(
+                "ListTransactions (min fee per gram: {}, sort: {:?})",
+                filter.min_fee_per_gram, filter.sort
+            )),
+            MempoolRequest::GetTransaction(sig) => {
+                f.write_str(&format!("GetTransaction ({})", sig.get_signature().to_hex()))
+            },
         }
     }
 }
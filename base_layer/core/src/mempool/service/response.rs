@@ -22,7 +22,7 @@
 
 use crate::{
     base_node::RequestKey,
-    mempool::{StateResponse, StatsResponse, TxStorageResponse},
+    mempool::{MempoolTransaction, StateResponse, StatsResponse, TxRejection, TxStorageResponse, TxValidationReport},
 };
 use serde::{Deserialize, Serialize};
 
@@ -32,6 +32,10 @@ pub enum MempoolResponse {
     Stats(StatsResponse),
     State(StateResponse),
     TxStorage(TxStorageResponse),
+    TxValidationReport(TxValidationReport),
+    Transactions(Vec<MempoolTransaction>),
+    Transaction(Option<MempoolTransaction>),
+    TxRejected(TxRejection),
 }
 
 /// Response type for a received MempoolService requests
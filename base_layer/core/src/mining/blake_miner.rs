@@ -22,6 +22,7 @@
 
 use crate::{
     blocks::BlockHeader,
+    mining::metrics::MinerMetrics,
     proof_of_work::{Difficulty, ProofOfWork},
 };
 use log::*;
@@ -37,6 +38,11 @@ use std::{
 use tari_crypto::tari_utilities::epoch_time::EpochTime;
 pub const LOG_TARGET: &str = "c::m::blake_miner";
 
+/// How often each mining thread publishes its hashrate
+const HASHRATE_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// How often the header timestamp is refreshed while mining
+const TIMESTAMP_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
 /// A simple Blake2b-based proof of work. This is currently intended to be used for testing and perhaps Testnet until
 /// Monero merge-mining is active.
 ///
@@ -47,14 +53,17 @@ pub struct CpuBlakePow;
 
 impl CpuBlakePow {
     /// A simple miner. It starts with a random nonce and iterates until it finds a header hash that meets the desired
-    /// target
+    /// target. The hashrate of the thread is periodically recorded in `metrics` under `thread_index`.
     pub fn mine(
         target_difficulty: Difficulty,
         mut header: BlockHeader,
         stop_flag: Arc<AtomicBool>,
+        thread_index: usize,
+        metrics: MinerMetrics,
     ) -> Option<BlockHeader>
     {
         let mut start = Instant::now();
+        let mut timestamp_refreshed = start;
         let mut nonce: u64 = OsRng.next_u64();
         let mut last_measured_nonce = nonce;
         // We're mining over here!
@@ -62,7 +71,7 @@ impl CpuBlakePow {
         info!(target: LOG_TARGET, "Mining started.");
         debug!(target: LOG_TARGET, "Mining for difficulty: {:?}", target_difficulty);
         while difficulty < target_difficulty {
-            if start.elapsed() >= Duration::from_secs(60) {
+            if start.elapsed() >= HASHRATE_REPORT_INTERVAL {
                 // nonce might have wrapped around
                 let hashes = if nonce >= last_measured_nonce {
                     nonce - last_measured_nonce
@@ -70,11 +79,17 @@ impl CpuBlakePow {
                     std::u64::MAX - last_measured_nonce + nonce
                 };
                 let hash_rate = hashes as f64 / start.elapsed().as_micros() as f64;
-                info!(target: LOG_TARGET, "Mining hash rate per thread: {:.6} MH/s", hash_rate);
+                debug!(
+                    target: LOG_TARGET,
+                    "Mining hash rate of thread {}: {:.6} MH/s", thread_index, hash_rate
+                );
+                metrics.record_thread_hashrate(thread_index, hash_rate);
                 last_measured_nonce = nonce;
                 start = Instant::now();
-
+            }
+            if timestamp_refreshed.elapsed() >= TIMESTAMP_REFRESH_INTERVAL {
                 header.timestamp = EpochTime::now();
+                timestamp_refreshed = Instant::now();
             }
             if stop_flag.load(Ordering::Relaxed) {
                 info!(target: LOG_TARGET, "Mining stopped via flag");
@@ -91,6 +106,7 @@ impl CpuBlakePow {
         }
 
         debug!(target: LOG_TARGET, "Miner found nonce: {}", nonce);
+        metrics.record_solution_found();
         trace!(target: LOG_TARGET, "Mined achieved difficulty: {}", difficulty);
         Some(header)
     }
@@ -50,6 +50,8 @@ pub enum CoinbaseBuildError {
     MissingNonce,
     /// The spend key for this coinbase transaction wasn't provided
     MissingSpendKey,
+    /// The extra data is larger than the consensus rules permit
+    ExtraDataTooLarge,
     /// An error occurred building the final transaction
     #[error(msg_embedded, no_from, non_std)]
     BuildError(String),
@@ -63,6 +65,7 @@ pub struct CoinbaseBuilder {
     fees: Option<MicroTari>,
     spend_key: Option<PrivateKey>,
     private_nonce: Option<PrivateKey>,
+    extra: Option<Vec<u8>>,
 }
 
 impl CoinbaseBuilder {
@@ -75,6 +78,7 @@ impl CoinbaseBuilder {
             fees: None,
             spend_key: None,
             private_nonce: None,
+            extra: None,
         }
     }
 
@@ -102,6 +106,13 @@ impl CoinbaseBuilder {
         self
     }
 
+    /// An operator-defined tag (e.g. a pool name) that is committed to in the coinbase kernel. The size is limited by
+    /// the consensus rules.
+    pub fn with_extra(mut self, extra: Vec<u8>) -> Self {
+        self.extra = Some(extra);
+        self
+    }
+
     /// Try and construct a Coinbase Transaction. The block reward is taken from the emission curve for the current
     /// block height. The other parameters (keys, nonces etc.) are provided by the caller. Other data is
     /// automatically set: Coinbase transactions have an offset of zero, no fees, the `COINBASE_OUTPUT` flags are set
@@ -119,11 +130,18 @@ impl CoinbaseBuilder {
         let nonce = self.private_nonce.ok_or_else(|| CoinbaseBuildError::MissingNonce)?;
         let public_nonce = PublicKey::from_secret_key(&nonce);
         let key = self.spend_key.ok_or_else(|| CoinbaseBuildError::MissingSpendKey)?;
+        let extra = self.extra.filter(|extra| !extra.is_empty());
+        if extra.as_ref().map(Vec::len).unwrap_or(0) > rules.consensus_constants().coinbase_max_extra_size() {
+            return Err(CoinbaseBuildError::ExtraDataTooLarge);
+        }
         let output_features =
             OutputFeatures::create_coinbase(height + rules.consensus_constants().coinbase_lock_height());
         let excess = self.factories.commitment.commit_value(&key, 0);
         let kernel_features = KernelFeatures::create_coinbase();
-        let metadata = TransactionMetadata::default();
+        let metadata = TransactionMetadata {
+            meta_info: extra.clone(),
+            ..Default::default()
+        };
         let challenge = build_challenge(&public_nonce, &metadata);
         let sig = Signature::sign(key.clone(), nonce, &challenge)
             .map_err(|_| CoinbaseBuildError::BuildError("Challenge could not be represented as a scalar".into()))?;
@@ -131,12 +149,16 @@ impl CoinbaseBuilder {
         let output = unblinded_output
             .as_transaction_output(&self.factories)
             .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
-        let kernel = KernelBuilder::new()
+        let mut kernel = KernelBuilder::new()
             .with_fee(0 * uT)
             .with_features(kernel_features)
             .with_lock_height(0)
             .with_excess(&excess)
-            .with_signature(&sig)
+            .with_signature(&sig);
+        if let Some(extra) = extra {
+            kernel = kernel.with_meta_info(extra);
+        }
+        let kernel = kernel
             .build()
             .map_err(|e| CoinbaseBuildError::BuildError(e.to_string()))?;
 
@@ -218,4 +240,36 @@ mod test {
         assert!(utxo.verify_range_proof(&factories.range_proof).unwrap());
         assert!(utxo.features.flags.contains(OutputFlags::COINBASE_OUTPUT));
     }
+
+    #[test]
+    fn coinbase_with_extra() {
+        let p = TestParams::new();
+        let (builder, rules, factories) = get_builder();
+        let builder = builder
+            .with_block_height(42)
+            .with_fees(0 * uT)
+            .with_nonce(p.nonce.clone())
+            .with_spend_key(p.spend_key.clone())
+            .with_extra(b"my pool".to_vec());
+        let (tx, _) = builder.build(rules.clone()).unwrap();
+        let kernel = &tx.body.kernels()[0];
+        assert_eq!(kernel.meta_info, Some(b"my pool".to_vec()));
+        assert!(kernel.verify_signature().is_ok());
+        let block_reward = rules.emission_schedule().block_reward(42);
+        assert!(tx.validate_internal_consistency(&factories, Some(block_reward)).is_ok());
+    }
+
+    #[test]
+    fn extra_too_large() {
+        let p = TestParams::new();
+        let (builder, rules, _) = get_builder();
+        let extra = vec![7u8; rules.consensus_constants().coinbase_max_extra_size() + 1];
+        let builder = builder
+            .with_block_height(42)
+            .with_fees(0 * uT)
+            .with_nonce(p.nonce)
+            .with_spend_key(p.spend_key)
+            .with_extra(extra);
+        assert_eq!(builder.build(rules).unwrap_err(), CoinbaseBuildError::ExtraDataTooLarge);
+    }
 }
@@ -0,0 +1,220 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Hashrate and solution metrics for the internal miner.
+//!
+//! Every update is published to the default Prometheus registry and kept in a [MinerStats] snapshot so that the base
+//! node `get-network-status` command can show the same figures that operators graph.
+
+use lazy_static::lazy_static;
+use prometheus::{register_gauge, register_gauge_vec, register_int_counter, Gauge, GaugeVec, IntCounter};
+use std::{
+    fmt::{Display, Error, Formatter},
+    sync::{Arc, PoisonError, RwLock},
+    time::{Duration, Instant},
+};
+
+lazy_static! {
+    static ref THREAD_HASHRATE: GaugeVec = register_gauge_vec!(
+        "tari_miner_thread_hashrate_mhs",
+        "Hashrate of each mining thread in MH/s",
+        &["thread"]
+    )
+    .expect("Miner thread hashrate metric registered more than once");
+    static ref HASHRATE: Gauge = register_gauge!(
+        "tari_miner_hashrate_mhs",
+        "Combined hashrate of all mining threads in MH/s"
+    )
+    .expect("Miner hashrate metric registered more than once");
+    static ref TEMPLATE_AGE: Gauge = register_gauge!(
+        "tari_miner_template_age_seconds",
+        "Time since the miner received the block template it is working on"
+    )
+    .expect("Miner template age metric registered more than once");
+    static ref TEMPLATES: IntCounter = register_int_counter!(
+        "tari_miner_templates_total",
+        "Number of block templates the miner has started working on"
+    )
+    .expect("Miner template metric registered more than once");
+    static ref SOLUTIONS_FOUND: IntCounter = register_int_counter!(
+        "tari_miner_solutions_found_total",
+        "Number of headers found that meet the target difficulty"
+    )
+    .expect("Miner solutions found metric registered more than once");
+    static ref SOLUTIONS_REJECTED: IntCounter = register_int_counter!(
+        "tari_miner_solutions_rejected_total",
+        "Number of solved blocks that could not be submitted to the base node"
+    )
+    .expect("Miner solutions rejected metric registered more than once");
+}
+
+/// A point in time snapshot of the miner's performance.
+#[derive(Clone, Debug, Default)]
+pub struct MinerStats {
+    /// The last measured hashrate of each mining thread in MH/s
+    pub thread_hashrates: Vec<f64>,
+    /// When the template currently being mined was received, if the miner is working on one
+    pub template_received: Option<Instant>,
+    /// The height of the template currently being mined
+    pub template_height: u64,
+    pub templates: u64,
+    pub solutions_found: u64,
+    pub solutions_rejected: u64,
+}
+
+impl MinerStats {
+    /// The combined hashrate of all mining threads in MH/s
+    pub fn hashrate(&self) -> f64 {
+        self.thread_hashrates.iter().sum()
+    }
+
+    /// The time since the current template was received
+    pub fn template_age(&self) -> Option<Duration> {
+        self.template_received.map(|received| received.elapsed())
+    }
+}
+
+impl Display for MinerStats {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(
+            f,
+            "{:.6} MH/s over {} thread(s), ",
+            self.hashrate(),
+            self.thread_hashrates.len()
+        )?;
+        match self.template_age() {
+            Some(age) => write!(f, "mining height {} for {}s, ", self.template_height, age.as_secs())?,
+            None => write!(f, "idle, ")?,
+        }
+        write!(
+            f,
+            "{} template(s), {} solution(s) found, {} rejected",
+            self.templates, self.solutions_found, self.solutions_rejected
+        )
+    }
+}
+
+/// Shared recorder for miner metrics. Clones record into the same [MinerStats] so that mining threads, the miner and
+/// the UI all see the same figures.
+#[derive(Clone, Default)]
+pub struct MinerMetrics {
+    stats: Arc<RwLock<MinerStats>>,
+}
+
+impl MinerMetrics {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns a snapshot of the current stats
+    pub fn stats(&self) -> MinerStats {
+        self.stats.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Records that the miner has started working on a new template with the given number of threads
+    pub fn record_new_template(&self, height: u64, threads: usize) {
+        let mut stats = self.stats.write().unwrap_or_else(PoisonError::into_inner);
+        stats.template_received = Some(Instant::now());
+        stats.template_height = height;
+        stats.templates += 1;
+        stats.thread_hashrates.resize(threads, 0.0);
+        TEMPLATES.inc();
+        TEMPLATE_AGE.set(0.0);
+    }
+
+    /// Records the hashrate measured by a single mining thread, in MH/s
+    pub fn record_thread_hashrate(&self, thread: usize, hashrate: f64) {
+        let mut stats = self.stats.write().unwrap_or_else(PoisonError::into_inner);
+        if thread >= stats.thread_hashrates.len() {
+            stats.thread_hashrates.resize(thread + 1, 0.0);
+        }
+        stats.thread_hashrates[thread] = hashrate;
+        THREAD_HASHRATE.with_label_values(&[&thread.to_string()]).set(hashrate);
+        HASHRATE.set(stats.hashrate());
+        if let Some(age) = stats.template_age() {
+            TEMPLATE_AGE.set(age.as_secs_f64());
+        }
+    }
+
+    /// Records that a mining thread found a header meeting the target difficulty
+    pub fn record_solution_found(&self) {
+        self.stats
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .solutions_found += 1;
+        SOLUTIONS_FOUND.inc();
+    }
+
+    /// Records that a solved block could not be submitted to the base node
+    pub fn record_solution_rejected(&self) {
+        self.stats
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .solutions_rejected += 1;
+        SOLUTIONS_REJECTED.inc();
+    }
+
+    /// Records that the miner is no longer working on a template, resetting the hashrate gauges to zero
+    pub fn record_idle(&self) {
+        let mut stats = self.stats.write().unwrap_or_else(PoisonError::into_inner);
+        if stats.template_received.take().is_none() {
+            return;
+        }
+        for (thread, rate) in stats.thread_hashrates.iter_mut().enumerate() {
+            *rate = 0.0;
+            THREAD_HASHRATE.with_label_values(&[&thread.to_string()]).set(0.0);
+        }
+        HASHRATE.set(0.0);
+        TEMPLATE_AGE.set(0.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn records_stats() {
+        let metrics = MinerMetrics::new();
+        assert!(metrics.stats().template_age().is_none());
+
+        metrics.record_new_template(10, 2);
+        metrics.record_thread_hashrate(0, 1.5);
+        metrics.record_thread_hashrate(1, 2.5);
+        metrics.record_solution_found();
+        metrics.record_solution_rejected();
+        let stats = metrics.clone().stats();
+        assert_eq!(stats.thread_hashrates, vec![1.5, 2.5]);
+        assert!((stats.hashrate() - 4.0).abs() < std::f64::EPSILON);
+        assert_eq!(stats.template_height, 10);
+        assert!(stats.template_age().is_some());
+        assert_eq!(stats.templates, 1);
+        assert_eq!(stats.solutions_found, 1);
+        assert_eq!(stats.solutions_rejected, 1);
+
+        metrics.record_idle();
+        let stats = metrics.stats();
+        assert!(stats.template_age().is_none());
+        assert!(stats.hashrate().abs() < std::f64::EPSILON);
+        assert_eq!(stats.solutions_found, 1);
+    }
+}
@@ -28,7 +28,7 @@ use crate::{
     blocks::{Block, BlockHeader, NewBlockTemplate},
     chain_storage::BlockAddResult,
     consensus::ConsensusManager,
-    mining::{blake_miner::CpuBlakePow, error::MinerError, CoinbaseBuilder},
+    mining::{blake_miner::CpuBlakePow, error::MinerError, CoinbaseBuilder, MinerMetrics},
     proof_of_work::{Difficulty, PowAlgorithm},
     transactions::{
         transaction::UnblindedOutput,
@@ -63,6 +63,8 @@ pub struct Miner {
     state_change_event_rx: Option<Subscriber<StateEvent>>,
     threads: usize,
     enabled: Arc<AtomicBool>,
+    metrics: MinerMetrics,
+    coinbase_extra: Vec<u8>,
 }
 
 impl Miner {
@@ -84,9 +86,17 @@ impl Miner {
             state_change_event_rx: None,
             threads,
             enabled: Arc::new(AtomicBool::new(false)),
+            metrics: MinerMetrics::new(),
+            coinbase_extra: Vec::new(),
         }
     }
 
+    /// Sets the extra data that is embedded in the kernel of every coinbase this miner creates, e.g. to identify the
+    /// mining operator. An empty tag is not included in the kernel.
+    pub fn set_coinbase_extra(&mut self, extra: Vec<u8>) {
+        self.coinbase_extra = extra;
+    }
+
     /// This function instantiates a new channel and returns the receiver so that the miner can send out a unblinded
     /// output. This output is only sent if the miner successfully mines a block
     pub fn get_utxo_receiver_channel(&mut self) -> Receiver<UnblindedOutput> {
@@ -109,6 +119,11 @@ impl Miner {
         self.enabled.clone()
     }
 
+    /// Returns the recorder this miner publishes its hashrate, template and solution metrics to.
+    pub fn metrics(&self) -> MinerMetrics {
+        self.metrics.clone()
+    }
+
     /// Mine blocks asynchronously.
     ///
     /// On the first iteration, the thread will loop around until `received_new_block_flag` is true. This flag is set
@@ -151,14 +166,16 @@ impl Miner {
         let mut block = block.unwrap();
         debug!(target: LOG_TARGET, "Miner got new block to mine.");
         let difficulty = self.get_req_difficulty().await?;
+        self.metrics.record_new_template(block.header.height, self.threads);
         let (tx, mut rx): (Sender<Option<BlockHeader>>, Receiver<Option<BlockHeader>>) = mpsc::channel(self.threads);
-        for _ in 0..self.threads {
+        for thread_index in 0..self.threads {
             let stop_mining_flag = self.stop_mining_flag.clone();
             let header = block.header.clone();
             let mut tx_channel = tx.clone();
+            let metrics = self.metrics.clone();
             trace!("spawning mining thread");
             spawn_blocking(move || {
-                let result = CpuBlakePow::mine(difficulty, header, stop_mining_flag);
+                let result = CpuBlakePow::mine(difficulty, header, stop_mining_flag, thread_index, metrics);
                 // send back what the miner found, None will be sent if the miner did not find a nonce
                 if let Err(e) = tx_channel.try_send(result) {
                     warn!(target: LOG_TARGET, "Could not return mining result: {}", e);
@@ -181,6 +198,7 @@ impl Miner {
                     })
                     .is_err()
                 {
+                    self.metrics.record_solution_rejected();
                     break;
                 };
                 let _ = self
@@ -200,6 +218,7 @@ impl Miner {
 
     // This is just a helper function to get around the rust borrow checker
     async fn not_mining(self) -> Result<Miner, MinerError> {
+        self.metrics.record_idle();
         Ok(self)
     }
 
@@ -215,6 +234,7 @@ impl Miner {
             .expect("Miner does not have access to state event stream")
             .fuse();
         let mut kill_signal = self.kill_signal.clone();
+        let metrics = self.metrics.clone();
 
         pin_mut!(block_event);
         pin_mut!(state_event);
@@ -271,6 +291,7 @@ impl Miner {
             };
             self = mining_future.await.expect("Miner crashed").expect("Miner crashed");
         }
+        metrics.record_idle();
         debug!(target: LOG_TARGET, "Mining thread stopped.");
     }
 
@@ -339,10 +360,12 @@ impl Miner {
             .with_block_height(block.header.height)
             .with_fees(fees)
             .with_nonce(r)
-            .with_spend_key(key);
-        let (tx, unblinded_output) = builder
-            .build(self.consensus.clone())
-            .expect("invalid constructed coinbase");
+            .with_spend_key(key)
+            .with_extra(self.coinbase_extra.clone());
+        let (tx, unblinded_output) = builder.build(self.consensus.clone()).map_err(|e| {
+            error!(target: LOG_TARGET, "Could not build the coinbase transaction: {}", e);
+            MinerError::CoinbaseError
+        })?;
         block.body.add_output(tx.body.outputs()[0].clone());
         block.body.add_kernel(tx.body.kernels()[0].clone());
         Ok(unblinded_output)
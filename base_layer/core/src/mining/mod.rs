@@ -23,7 +23,9 @@
 mod blake_miner;
 mod coinbase_builder;
 mod error;
+mod metrics;
 mod miner;
 
 pub use coinbase_builder::CoinbaseBuilder;
+pub use metrics::{MinerMetrics, MinerStats};
 pub use miner::Miner;
@@ -29,12 +29,13 @@ use crate::{
         difficulty::DifficultyAdjustment,
         lwma_diff::LinearWeightedMovingAverage,
         Difficulty,
+        DifficultyLimits,
         PowAlgorithm,
         ProofOfWork,
     },
 };
 use log::*;
-use std::{cmp, collections::VecDeque};
+use std::collections::VecDeque;
 use tari_crypto::tari_utilities::{epoch_time::EpochTime, hash::Hashable};
 
 pub const LOG_TARGET: &str = "c::pow::diff_adj_manager::diff_adj_storage";
@@ -59,6 +60,7 @@ pub struct DiffAdjStorage {
     difficulty_max_block_interval: u64,
     median_timestamp_count: usize,
     min_pow_difficulty: Difficulty,
+    difficulty_limits: DifficultyLimits,
 }
 
 impl DiffAdjStorage {
@@ -83,6 +85,7 @@ impl DiffAdjStorage {
             median_timestamp_count: consensus_constants.get_median_timestamp_count(),
             diff_target_block_interval: consensus_constants.get_diff_target_block_interval(),
             min_pow_difficulty: consensus_constants.min_pow_difficulty(),
+            difficulty_limits: consensus_constants.difficulty_limits(),
             difficulty_max_block_interval: consensus_constants.get_difficulty_max_block_interval(),
         }
     }
@@ -167,10 +170,17 @@ impl DiffAdjStorage {
             target: LOG_TARGET,
             "Getting target difficulty at height:{} for PoW:{}", height, pow_algo
         );
-        Ok(match pow_algo {
-            PowAlgorithm::Monero => self.monero_lwma.get_difficulty(),
-            PowAlgorithm::Blake => cmp::max(self.min_pow_difficulty, self.blake_lwma.get_difficulty()),
-        })
+        Ok(self.clamped_target_difficulty(pow_algo))
+    }
+
+    // Returns the target difficulty of the LWMA for the PoW algorithm, bounded by the network difficulty limits.
+    fn clamped_target_difficulty(&self, pow_algo: PowAlgorithm) -> Difficulty {
+        let lwma = match pow_algo {
+            PowAlgorithm::Monero => &self.monero_lwma,
+            PowAlgorithm::Blake => &self.blake_lwma,
+        };
+        self.difficulty_limits
+            .clamp(lwma.last_target_difficulty(), lwma.get_difficulty())
     }
 
     /// Returns the median timestamp of the past 11 blocks at the chain tip.
@@ -228,16 +238,10 @@ impl DiffAdjStorage {
             target: LOG_TARGET,
             "Adding timestamp {} for {}", timestamp, pow.pow_algo
         );
+        let target_difficulty = self.clamped_target_difficulty(pow.pow_algo);
         match pow.pow_algo {
-            PowAlgorithm::Monero => {
-                let target_difficulty = self.monero_lwma.get_difficulty();
-                self.monero_lwma.add(timestamp, target_difficulty)?
-            },
-
-            PowAlgorithm::Blake => {
-                let target_difficulty = cmp::max(self.min_pow_difficulty, self.blake_lwma.get_difficulty());
-                self.blake_lwma.add(timestamp, target_difficulty)?
-            },
+            PowAlgorithm::Monero => self.monero_lwma.add(timestamp, target_difficulty)?,
+            PowAlgorithm::Blake => self.blake_lwma.add(timestamp, target_difficulty)?,
         }
         Ok(())
     }
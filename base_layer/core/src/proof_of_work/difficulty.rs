@@ -24,7 +24,7 @@ use crate::proof_of_work::error::DifficultyAdjustmentError;
 use bitflags::_core::ops::Div;
 use newtype_ops::newtype_ops;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{cmp, fmt};
 use tari_crypto::tari_utilities::epoch_time::EpochTime;
 
 /// Minimum difficulty, enforced in diff retargetting
@@ -95,6 +95,44 @@ impl From<Difficulty> for u64 {
     }
 }
 
+/// Per-network sanity limits that are applied to the target difficulty calculated by the difficulty adjustment
+/// algorithms. The adjustment factor limits how far the target difficulty can move up or down in a single block, so
+/// that a short burst of hashrate cannot push the difficulty out of reach of the remaining miners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultyLimits {
+    pub min: Difficulty,
+    pub max: Difficulty,
+    pub max_adjustment_factor: u64,
+}
+
+impl DifficultyLimits {
+    pub fn new(min: Difficulty, max: Difficulty, max_adjustment_factor: u64) -> Self {
+        Self {
+            min,
+            max,
+            max_adjustment_factor,
+        }
+    }
+
+    /// Bounds the target difficulty to within `max_adjustment_factor` of the previous target difficulty, if there is
+    /// one, and then to the [min, max] range.
+    pub fn clamp(&self, previous_target: Option<Difficulty>, target: Difficulty) -> Difficulty {
+        let mut target = target.as_u64();
+        if let Some(previous) = previous_target {
+            let factor = cmp::max(self.max_adjustment_factor, 1);
+            let previous = previous.as_u64();
+            target = cmp::min(target, previous.saturating_mul(factor));
+            target = cmp::max(target, previous / factor);
+        }
+        Difficulty(cmp::min(cmp::max(target, self.min.as_u64()), self.max.as_u64()))
+    }
+
+    /// Returns true if the target difficulty lies in the [min, max] range.
+    pub fn contains(&self, target: Difficulty) -> bool {
+        target >= self.min && target <= self.max
+    }
+}
+
 /// General difficulty adjustment algorithm trait. The key method is `get_difficulty`, which returns the target
 /// difficulty given a set of historical achieved difficulties; supplied through the `add` method.
 pub trait DifficultyAdjustment {
@@ -112,7 +150,7 @@ pub trait DifficultyAdjustment {
 
 #[cfg(test)]
 mod test {
-    use crate::proof_of_work::difficulty::Difficulty;
+    use crate::proof_of_work::difficulty::{Difficulty, DifficultyLimits};
 
     #[test]
     fn add_difficulty() {
@@ -123,4 +161,27 @@ mod test {
         assert_eq!(Difficulty::default() + Difficulty::from(42), Difficulty::from(43));
         assert_eq!(&Difficulty::from(15) + &Difficulty::from(5), Difficulty::from(20));
     }
+
+    #[test]
+    fn clamp_difficulty() {
+        let limits = DifficultyLimits::new(Difficulty::from(100), Difficulty::from(10_000), 2);
+        assert_eq!(limits.clamp(None, Difficulty::from(1)), Difficulty::from(100));
+        assert_eq!(limits.clamp(None, Difficulty::from(50_000)), Difficulty::from(10_000));
+        assert_eq!(limits.clamp(None, Difficulty::from(500)), Difficulty::from(500));
+        // A single block can at most double or halve the target difficulty
+        assert_eq!(
+            limits.clamp(Some(Difficulty::from(1_000)), Difficulty::from(9_000)),
+            Difficulty::from(2_000)
+        );
+        assert_eq!(
+            limits.clamp(Some(Difficulty::from(1_000)), Difficulty::from(100)),
+            Difficulty::from(500)
+        );
+        assert_eq!(
+            limits.clamp(Some(Difficulty::from(8_000)), Difficulty::from(20_000)),
+            Difficulty::from(10_000)
+        );
+        assert!(limits.contains(Difficulty::from(100)));
+        assert!(!limits.contains(Difficulty::from(10_001)));
+    }
 }
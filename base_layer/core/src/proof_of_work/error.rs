@@ -28,6 +28,8 @@ pub enum PowError {
     InvalidProofOfWork,
     // Target difficulty not achieved
     AchievedDifficultyTooLow,
+    // Target difficulty is outside of the network difficulty limits
+    TargetDifficultyOutOfBounds,
 }
 
 #[derive(Debug, Error, Clone, PartialEq)]
@@ -42,6 +42,11 @@ impl LinearWeightedMovingAverage {
         }
     }
 
+    /// Returns the most recently added target difficulty, if any.
+    pub fn last_target_difficulty(&self) -> Option<Difficulty> {
+        self.target_difficulties.back().cloned()
+    }
+
     fn calculate(&self) -> Difficulty {
         let timestamps = &self.timestamps;
         if timestamps.len() <= 1 {
@@ -135,6 +140,7 @@ impl DifficultyAdjustment for LinearWeightedMovingAverage {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::proof_of_work::test_vectors::DifficultyTestVectors;
 
     #[test]
     fn lwma_zero_len() {
@@ -217,4 +223,20 @@ mod test {
         let _ = dif.add(979.into(), 148.into());
         assert_eq!(dif.get_difficulty(), 175.into());
     }
+
+    #[test]
+    fn lwma_golden_vectors() {
+        let vectors: DifficultyTestVectors =
+            serde_json::from_str(include_str!("../../tests/data/lwma_diff_vectors.json")).unwrap();
+        assert_eq!(vectors.algorithm, "lwma");
+        assert!(!vectors.cases.is_empty());
+        for case in &vectors.cases {
+            assert_eq!(
+                case.calculate_lwma(),
+                case.expected,
+                "Test vector '{}' failed",
+                case.name
+            );
+        }
+    }
 }
@@ -33,10 +33,11 @@ mod proof_of_work;
 pub use blake_pow::test as blake_test;
 
 pub mod lwma_diff;
+pub mod test_vectors;
 
 pub use blake_pow::{blake_difficulty, blake_difficulty_with_hash};
 pub use diff_adj_manager::{DiffAdjManager, DiffAdjManagerError};
-pub use difficulty::{Difficulty, DifficultyAdjustment};
+pub use difficulty::{Difficulty, DifficultyAdjustment, DifficultyLimits};
 pub use error::{DifficultyAdjustmentError, PowError};
 pub use monero_rx::monero_difficulty;
 pub use proof_of_work::{PowAlgorithm, ProofOfWork};
@@ -0,0 +1,191 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Test vectors for the difficulty adjustment algorithms. Each test case is a sequence of blocks along with the
+//! target difficulty calculated after every block is added. The cases cover the boundary conditions of the algorithm:
+//! an underfull window, identical and decreasing timestamps, solve times longer than the maximum block time and a
+//! block timestamp at the future time limit.
+//!
+//! The golden file `tests/data/lwma_diff_vectors.json` is produced by the `gen_diff_test_vectors` example and checked
+//! by the `lwma_diff` tests, so that a refactor of the algorithm can be verified to produce bit-for-bit identical
+//! difficulties. The file must only be regenerated when the algorithm is intentionally changed.
+
+use crate::proof_of_work::{lwma_diff::LinearWeightedMovingAverage, DifficultyAdjustment};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyTestVectors {
+    /// The difficulty adjustment algorithm that the vectors apply to
+    pub algorithm: String,
+    pub cases: Vec<DifficultyTestCase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyTestBlock {
+    pub timestamp: u64,
+    pub difficulty: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyTestCase {
+    pub name: String,
+    pub block_window: usize,
+    pub target_time: u64,
+    pub initial_difficulty: u64,
+    pub max_block_time: u64,
+    pub blocks: Vec<DifficultyTestBlock>,
+    /// The target difficulty after each block has been added
+    pub expected: Vec<u64>,
+}
+
+impl DifficultyTestCase {
+    fn new(
+        name: &str,
+        block_window: usize,
+        target_time: u64,
+        initial_difficulty: u64,
+        max_block_time: u64,
+        blocks: Vec<(u64, u64)>,
+    ) -> Self
+    {
+        let mut case = Self {
+            name: name.to_string(),
+            block_window,
+            target_time,
+            initial_difficulty,
+            max_block_time,
+            blocks: blocks
+                .into_iter()
+                .map(|(timestamp, difficulty)| DifficultyTestBlock { timestamp, difficulty })
+                .collect(),
+            expected: Vec::new(),
+        };
+        case.expected = case.calculate_lwma();
+        case
+    }
+
+    fn lwma(&self) -> LinearWeightedMovingAverage {
+        LinearWeightedMovingAverage::new(
+            self.block_window,
+            self.target_time,
+            self.initial_difficulty.into(),
+            self.max_block_time,
+        )
+    }
+
+    /// Adds the blocks of this case to the LWMA algorithm, returning the target difficulty after each block
+    pub fn calculate_lwma(&self) -> Vec<u64> {
+        let mut lwma = self.lwma();
+        self.blocks
+            .iter()
+            .map(|block| {
+                lwma.add(block.timestamp.into(), block.difficulty.into())
+                    .expect("LWMA does not reject blocks");
+                lwma.get_difficulty().as_u64()
+            })
+            .collect()
+    }
+}
+
+/// Returns the LWMA test vectors, with the expected difficulties calculated by the current implementation
+pub fn lwma_test_vectors() -> DifficultyTestVectors {
+    let mut cases = Vec::new();
+
+    let blocks = (1..=10).map(|i| (120 * i, 1000)).collect();
+    cases.push(DifficultyTestCase::new("window_underfull", 90, 120, 1, 720, blocks));
+
+    let blocks = (1..=20).map(|i| (60 * i, 100)).collect();
+    cases.push(DifficultyTestCase::new("window_rolling_steady", 5, 60, 1, 360, blocks));
+
+    let timestamps = [60, 120, 180, 240, 300, 350, 380, 445, 515, 615, 975, 976, 977, 978, 979];
+    let difficulties = [100, 100, 100, 100, 100, 105, 128, 123, 116, 94, 39, 46, 55, 75, 148];
+    let blocks = timestamps.iter().copied().zip(difficulties.iter().copied()).collect();
+    cases.push(DifficultyTestCase::new("reference_series", 5, 60, 1, 360, blocks));
+
+    let mut blocks = (1..=5).map(|i| (120 * i, 500)).collect::<Vec<_>>();
+    blocks.extend(vec![(600, 500); 8]);
+    cases.push(DifficultyTestCase::new("identical_timestamps", 10, 120, 1, 720, blocks));
+
+    let mut blocks = (1..=11).map(|i| (120 * i, 500)).collect::<Vec<_>>();
+    let mut timestamp = 120 * 11;
+    for _ in 0..6 {
+        timestamp -= 1;
+        blocks.push((timestamp, 500));
+    }
+    cases.push(DifficultyTestCase::new(
+        "decreasing_timestamps",
+        10,
+        120,
+        1,
+        720,
+        blocks,
+    ));
+
+    let mut blocks = (1..=6).map(|i| (60 * i, 100)).collect::<Vec<_>>();
+    let mut timestamp = 60 * 6 + 100_000;
+    blocks.push((timestamp, 100));
+    for _ in 0..3 {
+        timestamp += 60;
+        blocks.push((timestamp, 100));
+    }
+    cases.push(DifficultyTestCase::new("max_block_time_clamp", 5, 60, 1, 360, blocks));
+
+    // A block timestamp at the future time limit (target_time * block_window / 20) followed by honest timestamps
+    let mut blocks = (1..=20).map(|i| (120 * i, 1000)).collect::<Vec<_>>();
+    blocks.push((120 * 21 + 540, 1000));
+    blocks.extend((22..=31).map(|i| (120 * i, 1000)));
+    cases.push(DifficultyTestCase::new("max_future_drift", 90, 120, 1, 720, blocks));
+
+    let mut timestamp = 0;
+    let blocks = (0..100)
+        .map(|i| {
+            timestamp += if i % 2 == 0 { 60 } else { 180 };
+            (timestamp, 1_000_000_000_000_000)
+        })
+        .collect();
+    cases.push(DifficultyTestCase::new("high_difficulty", 90, 120, 1, 720, blocks));
+
+    // Pseudo-random solve times, with each block mined at the target difficulty calculated for it
+    let mut case = DifficultyTestCase::new("variable_solve_times", 90, 120, 1000, 720, Vec::new());
+    let mut lwma = case.lwma();
+    let (mut x, mut timestamp) = (1u64, 0);
+    for _ in 0..200 {
+        x = x
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        timestamp += 1 + (x >> 33) % 240;
+        let difficulty = lwma.get_difficulty();
+        lwma.add(timestamp.into(), difficulty)
+            .expect("LWMA does not reject blocks");
+        case.blocks.push(DifficultyTestBlock {
+            timestamp,
+            difficulty: difficulty.as_u64(),
+        });
+    }
+    case.expected = case.calculate_lwma();
+    cases.push(case);
+
+    DifficultyTestVectors {
+        algorithm: "lwma".to_string(),
+        cases,
+    }
+}
@@ -176,10 +176,13 @@ impl AggregateBody {
     /// will be added to the public key used in the signature verification.
     pub fn verify_kernel_signatures(&self) -> Result<(), TransactionError> {
         trace!(target: LOG_TARGET, "Checking kernel signatures",);
-        for kernel in self.kernels.iter() {
+        for (i, kernel) in self.kernels.iter().enumerate() {
             kernel.verify_signature().or_else(|e| {
                 warn!(target: LOG_TARGET, "Kernel ({}) signature failed {:?}.", kernel, e);
-                Err(e)
+                match e {
+                    TransactionError::InvalidSignatureError => Err(TransactionError::InvalidKernelSignature(i)),
+                    e => Err(e),
+                }
             })?;
         }
         Ok(())
@@ -248,9 +251,7 @@ impl AggregateBody {
         let sum_io = self.sum_commitments(kernel_sum.fees.into(), factory);
 
         if kernel_sum.sum != sum_io {
-            return Err(TransactionError::ValidationError(
-                "Sum of inputs and outputs did not equal sum of kernels with fees".into(),
-            ));
+            return Err(TransactionError::KernelSumMismatch);
         }
 
         Ok(())
@@ -260,9 +261,7 @@ impl AggregateBody {
         trace!(target: LOG_TARGET, "Checking range proofs");
         for o in &self.outputs {
             if !o.verify_range_proof(&range_proof_service)? {
-                return Err(TransactionError::ValidationError(
-                    "Range proof could not be verified".into(),
-                ));
+                return Err(TransactionError::InvalidRangeProof(o.commitment.clone()));
             }
         }
         Ok(())
@@ -322,7 +322,7 @@ pub fn spend_utxos(schema: TransactionSchema) -> (Transaction, Vec<UnblindedOutp
     let change = stx_protocol.get_change_amount().unwrap();
     let change_output = UnblindedOutput {
         value: change,
-        spending_key: test_params.change_key.clone(),
+        spending_key: test_params.change_key.clone().into(),
         features: schema.features,
     };
     outputs.push(change_output);
@@ -0,0 +1,190 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Canonical (proto3) JSON mapping for the generated protobuf types. The build script derives `serde` implementations
+//! for the `tari.types` and `tari.core` packages, and uses the field helpers in this module wherever the default serde
+//! representation of a field differs from the proto3 JSON mapping.
+
+use serde::{de, de::DeserializeOwned, Deserializer, Serialize, Serializer};
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+/// Serialize a generated protobuf type to canonical JSON
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    serde_json::to_string(value)
+}
+
+/// Deserialize a generated protobuf type from JSON. Fields which are missing take their protobuf default value.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(json)
+}
+
+/// `bytes` fields are represented as a base64 string
+pub mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s: String = de::Deserialize::deserialize(deserializer)?;
+        base64::decode(&s).map_err(de::Error::custom)
+    }
+}
+
+/// `repeated bytes` fields are represented as an array of base64 strings
+pub mod repeated_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(items: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(items.iter().map(base64::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error> {
+        let items: Vec<String> = de::Deserialize::deserialize(deserializer)?;
+        items
+            .iter()
+            .map(|s| base64::decode(s).map_err(de::Error::custom))
+            .collect()
+    }
+}
+
+/// 64-bit integers are represented as a decimal string. Both strings and numbers are accepted when deserializing.
+struct IntVisitor<T>(PhantomData<T>);
+
+impl<'de, T> de::Visitor<'de> for IntVisitor<T>
+where
+    T: FromStr + std::convert::TryFrom<u64> + std::convert::TryFrom<i64>,
+    <T as FromStr>::Err: fmt::Display,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an integer or a string containing an integer")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        T::try_from(v).map_err(|_| E::custom("integer out of range"))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        T::try_from(v).map_err(|_| E::custom("integer out of range"))
+    }
+}
+
+pub mod uint64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        deserializer.deserialize_any(IntVisitor(PhantomData))
+    }
+}
+
+pub mod int64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        deserializer.deserialize_any(IntVisitor(PhantomData))
+    }
+}
+
+/// `google.protobuf.Timestamp` fields are represented as an RFC 3339 string
+pub mod timestamp {
+    use super::*;
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use prost_types::Timestamp;
+
+    pub fn serialize<S: Serializer>(timestamp: &Option<Timestamp>, serializer: S) -> Result<S::Ok, S::Error> {
+        match timestamp {
+            Some(timestamp) => {
+                let naive = NaiveDateTime::from_timestamp_opt(timestamp.seconds, timestamp.nanos as u32)
+                    .ok_or_else(|| serde::ser::Error::custom("timestamp out of range"))?;
+                serializer.serialize_some(&DateTime::<Utc>::from_utc(naive, Utc).to_rfc3339())
+            },
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Timestamp>, D::Error> {
+        let s: Option<String> = de::Deserialize::deserialize(deserializer)?;
+        s.map(|s| {
+            let datetime = DateTime::parse_from_rfc3339(&s).map_err(de::Error::custom)?;
+            Ok(Timestamp {
+                seconds: datetime.timestamp(),
+                nanos: datetime.timestamp_subsec_nanos() as i32,
+            })
+        })
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transactions::proto::types;
+
+    #[test]
+    fn round_trip() {
+        let signature = types::Signature {
+            public_nonce: vec![1, 2, 3],
+            signature: vec![4, 5, 6],
+        };
+        let json = to_json(&signature).unwrap();
+        assert_eq!(json, r#"{"publicNonce":"AQID","signature":"BAUG"}"#);
+        let decoded: types::Signature = from_json(&json).unwrap();
+        assert_eq!(decoded, signature);
+    }
+
+    #[test]
+    fn missing_fields_default() {
+        let decoded: types::Signature = from_json(r#"{"signature":"BAUG"}"#).unwrap();
+        assert!(decoded.public_nonce.is_empty());
+        assert_eq!(decoded.signature, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn u64_accepts_string_and_number() {
+        #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug)]
+        struct Value {
+            #[serde(with = "super::uint64")]
+            value: u64,
+        }
+        let value = Value {
+            value: u64::max_value(),
+        };
+        assert_eq!(to_json(&value).unwrap(), r#"{"value":"18446744073709551615"}"#);
+        assert_eq!(from_json::<Value>(r#"{"value":"12"}"#).unwrap(), Value { value: 12 });
+        assert_eq!(from_json::<Value>(r#"{"value":12}"#).unwrap(), Value { value: 12 });
+    }
+}
@@ -164,6 +164,7 @@ impl TryFrom<proto::OutputFeatures> for OutputFeatures {
             flags: OutputFlags::from_bits(features.flags as u8)
                 .ok_or_else(|| "Invalid or unrecognised output flags".to_string())?,
             maturity: features.maturity,
+            extensions: OutputFeatures::extensions_from_bytes(&features.extensions).map_err(|err| err.to_string())?,
         })
     }
 }
@@ -173,6 +174,7 @@ impl From<OutputFeatures> for proto::OutputFeatures {
         Self {
             flags: features.flags.bits() as u32,
             maturity: features.maturity,
+            extensions: features.extensions_to_bytes(),
         }
     }
 }
@@ -35,8 +35,11 @@ use crate::transactions::{
         HashDigest,
         HashOutput,
         MessageHash,
+        PrivateKey,
+        PublicKey,
         RangeProof,
         RangeProofService,
+        RewindData,
         Signature,
     },
 };
@@ -45,21 +48,32 @@ use digest::Input;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::{max, min, Ordering},
+    collections::BTreeMap,
+    convert::TryFrom,
     fmt::{Display, Formatter},
     hash::{Hash, Hasher},
     ops::Add,
 };
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
-    range_proof::{RangeProofError, RangeProofService as RangeProofServiceTrait},
+    range_proof::{
+        FullRewindResult,
+        RangeProofError,
+        RangeProofService as RangeProofServiceTrait,
+        RewindResult,
+        RewindableRangeProofService,
+    },
     tari_utilities::{hex::Hex, message_format::MessageFormat, ByteArray, Hashable},
 };
+use tari_secret::Secret;
 
 // These are set fairly arbitrarily at the moment. We'll need to do some modelling / testing to tune these values.
 pub const MAX_TRANSACTION_INPUTS: usize = 500;
 pub const MAX_TRANSACTION_OUTPUTS: usize = 100;
 pub const MAX_TRANSACTION_RECIPIENTS: usize = 15;
 pub const MINIMUM_TRANSACTION_FEE: MicroTari = MicroTari(100);
+/// The size in bytes of the tag and length prefix of each tag-length-value output feature record
+pub const OUTPUT_FEATURE_RECORD_HEADER_SIZE: usize = 3;
 
 //--------------------------------------        Output features   --------------------------------------------------//
 
@@ -87,19 +101,95 @@ pub struct OutputFeatures {
     /// the maturity of the specific UTXO. This is the min lock height at which an UTXO can be spend. Coinbase UTXO
     /// require a min maturity of the Coinbase_lock_height, this should be checked on receiving new blocks.
     pub maturity: u64,
+    /// Additional feature records keyed by their tag. This version of the software does not interpret any of them,
+    /// but they are preserved so that outputs created by newer versions round-trip unchanged. Which tags are allowed
+    /// on the chain is decided by the consensus constants.
+    #[serde(default)]
+    pub extensions: BTreeMap<u8, Vec<u8>>,
 }
 
 impl OutputFeatures {
+    /// The serialization used when hashing outputs. The flags and maturity are followed by the tag-length-value
+    /// encoded extensions, so outputs without extensions hash exactly as they did before extensions existed.
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buf = Vec::new();
-        bincode::serialize_into(&mut buf, self).unwrap(); // this should not fail
+        bincode::serialize_into(&mut buf, &self.flags).unwrap(); // this should not fail
+        bincode::serialize_into(&mut buf, &self.maturity).unwrap(); // this should not fail
+        buf.extend(self.extensions_to_bytes());
+        buf
+    }
+
+    /// Encode the extensions as a sequence of tag-length-value records: a one byte tag, a two byte little-endian
+    /// length and the value. Records are ordered by tag.
+    pub fn extensions_to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.extensions_size());
+        for (tag, value) in &self.extensions {
+            buf.push(*tag);
+            // Values are limited to u16::MAX bytes when they are decoded or added
+            buf.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            buf.extend_from_slice(value);
+        }
         buf
     }
 
+    /// Decode tag-length-value extension records. Unknown tags are accepted; the encoding must be canonical, i.e.
+    /// tags must be strictly increasing and the records must exactly fill the buffer.
+    pub fn extensions_from_bytes(bytes: &[u8]) -> Result<BTreeMap<u8, Vec<u8>>, OutputFeaturesError> {
+        let mut extensions = BTreeMap::new();
+        let mut last_tag = None;
+        let mut remaining = bytes;
+        while !remaining.is_empty() {
+            if remaining.len() < OUTPUT_FEATURE_RECORD_HEADER_SIZE {
+                return Err(OutputFeaturesError::TruncatedRecord);
+            }
+            let tag = remaining[0];
+            let len = u16::from_le_bytes([remaining[1], remaining[2]]) as usize;
+            remaining = &remaining[OUTPUT_FEATURE_RECORD_HEADER_SIZE..];
+            if remaining.len() < len {
+                return Err(OutputFeaturesError::TruncatedRecord);
+            }
+            if last_tag.map(|last| tag <= last).unwrap_or(false) {
+                return Err(OutputFeaturesError::NonCanonicalEncoding);
+            }
+            extensions.insert(tag, remaining[..len].to_vec());
+            last_tag = Some(tag);
+            remaining = &remaining[len..];
+        }
+        Ok(extensions)
+    }
+
+    /// Add an extension record, replacing any existing record with the same tag
+    pub fn set_extension(&mut self, tag: u8, value: Vec<u8>) -> Result<(), OutputFeaturesError> {
+        u16::try_from(value.len()).map_err(|_| OutputFeaturesError::ExtensionTooLarge)?;
+        self.extensions.insert(tag, value);
+        Ok(())
+    }
+
+    /// The size in bytes of the encoded extensions
+    pub fn extensions_size(&self) -> usize {
+        self.extensions
+            .values()
+            .map(|value| OUTPUT_FEATURE_RECORD_HEADER_SIZE + value.len())
+            .sum()
+    }
+
+    /// Check the extensions against the rules set by the consensus constants: every tag must be permitted and the
+    /// encoded extensions must not exceed the maximum size.
+    pub fn validate_extensions(&self, permitted_tags: &[u8], max_size: usize) -> Result<(), OutputFeaturesError> {
+        if self.extensions.keys().any(|tag| !permitted_tags.contains(tag)) {
+            return Err(OutputFeaturesError::UnsupportedExtension);
+        }
+        if self.extensions_size() > max_size {
+            return Err(OutputFeaturesError::ExtensionTooLarge);
+        }
+        Ok(())
+    }
+
     pub fn create_coinbase(maturity_height: u64) -> OutputFeatures {
         OutputFeatures {
             flags: OutputFlags::COINBASE_OUTPUT,
             maturity: maturity_height,
+            ..OutputFeatures::default()
         }
     }
 
@@ -117,6 +207,7 @@ impl Default for OutputFeatures {
         OutputFeatures {
             flags: OutputFlags::empty(),
             maturity: 0,
+            extensions: BTreeMap::new(),
         }
     }
 }
@@ -141,6 +232,18 @@ bitflags! {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Error, Deserialize, Serialize)]
+pub enum OutputFeaturesError {
+    // An extension record extends past the end of the encoded extensions
+    TruncatedRecord,
+    // Extension records are not in strictly increasing tag order
+    NonCanonicalEncoding,
+    // The output carries an extension that the consensus rules do not permit
+    UnsupportedExtension,
+    // The extensions are larger than the consensus rules or the encoding permit
+    ExtensionTooLarge,
+}
+
 //----------------------------------------     TransactionError   ----------------------------------------------------//
 
 #[derive(Clone, Debug, PartialEq, Error, Deserialize, Serialize)]
@@ -154,16 +257,25 @@ pub enum TransactionError {
     NoSignatureError,
     // A range proof construction or verification has produced an error
     RangeProofError(RangeProofError),
+    // The sum of the inputs and outputs does not equal the sum of the kernel excesses and fees
+    KernelSumMismatch,
+    // The range proof of the output with this commitment could not be verified
+    #[error(no_from, non_std)]
+    InvalidRangeProof(Commitment),
+    // The signature of the kernel at this index could not be verified
+    #[error(no_from, non_std)]
+    InvalidKernelSignature(usize),
 }
 
 //-----------------------------------------     UnblindedOutput   ----------------------------------------------------//
 
 /// An unblinded output is one where the value and spending key (blinding factor) are known. This can be used to
-/// build both inputs and outputs (every input comes from an output)
+/// build both inputs and outputs (every input comes from an output). The spending key is zeroized when the output is
+/// dropped.
 #[derive(Debug, Clone)]
 pub struct UnblindedOutput {
     pub value: MicroTari,
-    pub spending_key: BlindingFactor,
+    pub spending_key: Secret<BlindingFactor>,
     pub features: OutputFeatures,
 }
 
@@ -172,7 +284,7 @@ impl UnblindedOutput {
     pub fn new(value: MicroTari, spending_key: BlindingFactor, features: Option<OutputFeatures>) -> UnblindedOutput {
         UnblindedOutput {
             value,
-            spending_key,
+            spending_key: Secret::new(spending_key),
             features: features.unwrap_or_default(),
         }
     }
@@ -184,16 +296,37 @@ impl UnblindedOutput {
     }
 
     pub fn as_transaction_output(&self, factories: &CryptoFactories) -> Result<TransactionOutput, TransactionError> {
+        let proof = factories
+            .range_proof
+            .construct_proof(&self.spending_key, self.value.into())?;
+        self.with_proof(factories, &proof)
+    }
+
+    /// Commits an UnblindedOutput into a TransactionOutput with a range proof that can be rewound with the given
+    /// rewind keys to recover the value and proof message
+    pub fn as_rewindable_transaction_output(
+        &self,
+        factories: &CryptoFactories,
+        rewind_data: &RewindData,
+    ) -> Result<TransactionOutput, TransactionError>
+    {
+        let proof = factories.range_proof.construct_proof_with_rewind_key(
+            &self.spending_key,
+            self.value.into(),
+            &rewind_data.rewind_key,
+            &rewind_data.rewind_blinding_key,
+            &rewind_data.proof_message,
+        )?;
+        self.with_proof(factories, &proof)
+    }
+
+    fn with_proof(&self, factories: &CryptoFactories, proof: &[u8]) -> Result<TransactionOutput, TransactionError> {
         let commitment = factories.commitment.commit(&self.spending_key, &self.value.into());
         let output = TransactionOutput {
             features: self.features.clone(),
             commitment,
-            proof: RangeProof::from_bytes(
-                &factories
-                    .range_proof
-                    .construct_proof(&self.spending_key, self.value.into())?,
-            )
-            .map_err(|_| TransactionError::RangeProofError(RangeProofError::ProofConstructionError))?,
+            proof: RangeProof::from_bytes(proof)
+                .map_err(|_| TransactionError::RangeProofError(RangeProofError::ProofConstructionError))?,
         };
         // A range proof can be constructed for an invalid value so we should confirm that the proof can be verified.
         if !output.verify_range_proof(&factories.range_proof)? {
@@ -336,6 +469,41 @@ impl TransactionOutput {
         Ok(prover.verify(&self.proof.to_vec(), &self.commitment))
     }
 
+    /// Attempt to rewind the range proof to recover the committed value and the proof message. This succeeds for any
+    /// range proof, so the result is only meaningful if the proof was constructed with the matching rewind keys; the
+    /// caller must check the recovered value against the commitment or the message against an expected value.
+    pub fn rewind_range_proof_value_only(
+        &self,
+        prover: &RangeProofService,
+        rewind_public_key: &PublicKey,
+        rewind_blinding_public_key: &PublicKey,
+    ) -> Result<RewindResult, TransactionError>
+    {
+        Ok(prover.rewind_proof_value_only(
+            &self.proof.to_vec(),
+            &self.commitment,
+            rewind_public_key,
+            rewind_blinding_public_key,
+        )?)
+    }
+
+    /// Attempt to fully rewind the range proof to recover the committed value, the proof message and the blinding
+    /// factor. This fails if the proof was not constructed with the given rewind keys.
+    pub fn full_rewind_range_proof(
+        &self,
+        prover: &RangeProofService,
+        rewind_key: &PrivateKey,
+        rewind_blinding_key: &PrivateKey,
+    ) -> Result<FullRewindResult<PrivateKey>, TransactionError>
+    {
+        Ok(prover.rewind_proof_commitment_data(
+            &self.proof.to_vec(),
+            &self.commitment,
+            rewind_key,
+            rewind_blinding_key,
+        )?)
+    }
+
     /// This will check if the input and the output is the same commitment by looking at the commitment and features.
     /// This will ignore the output rangeproof
     pub fn is_equal_to(&self, output: &TransactionInput) -> bool {
@@ -507,8 +675,8 @@ impl TransactionKernel {
         let m = TransactionMetadata {
             lock_height: self.lock_height,
             fee: self.fee,
-            meta_info: None,
-            linked_kernel: None,
+            meta_info: self.meta_info.clone(),
+            linked_kernel: self.linked_kernel.clone(),
         };
         let c = build_challenge(r, &m);
         if self.excess_sig.verify_challenge(excess, &c) {
@@ -781,12 +949,15 @@ mod test {
             helpers::{create_test_kernel, create_tx, spend_utxos},
             tari_amount::T,
             transaction::OutputFeatures,
-            types::{BlindingFactor, PrivateKey, PublicKey, RangeProof},
+            types::{BlindingFactor, PrivateKey, PublicKey, RangeProof, REWIND_USER_MESSAGE_LENGTH},
         },
         txn_schema,
     };
     use rand::{self, rngs::OsRng};
-    use tari_crypto::{keys::SecretKey as SecretKeyTrait, ristretto::pedersen::PedersenCommitmentFactory};
+    use tari_crypto::{
+        keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait},
+        ristretto::pedersen::PedersenCommitmentFactory,
+    };
 
     #[test]
     fn unblinded_input() {
@@ -798,6 +969,47 @@ mod test {
         assert!(input.opened_by(&i, &factory));
     }
 
+    #[test]
+    fn rewind_range_proof() {
+        let factories = CryptoFactories::default();
+        let rewind_data = RewindData {
+            rewind_key: PrivateKey::random(&mut OsRng),
+            rewind_blinding_key: PrivateKey::random(&mut OsRng),
+            proof_message: [7u8; REWIND_USER_MESSAGE_LENGTH],
+        };
+        let k = BlindingFactor::random(&mut OsRng);
+        let uo = UnblindedOutput::new(1234.into(), k.clone(), None);
+        let output = uo.as_rewindable_transaction_output(&factories, &rewind_data).unwrap();
+        assert!(output.verify_range_proof(&factories.range_proof).unwrap());
+
+        let rewind_public_key = PublicKey::from_secret_key(&rewind_data.rewind_key);
+        let rewind_blinding_public_key = PublicKey::from_secret_key(&rewind_data.rewind_blinding_key);
+        let result = output
+            .rewind_range_proof_value_only(&factories.range_proof, &rewind_public_key, &rewind_blinding_public_key)
+            .unwrap();
+        assert_eq!(result.committed_value, 1234);
+        assert_eq!(result.proof_message, rewind_data.proof_message);
+
+        let result = output
+            .full_rewind_range_proof(
+                &factories.range_proof,
+                &rewind_data.rewind_key,
+                &rewind_data.rewind_blinding_key,
+            )
+            .unwrap();
+        assert_eq!(result.committed_value, 1234);
+        assert_eq!(result.blinding_factor, k);
+
+        // The wrong keys do not recover the blinding factor
+        assert!(output
+            .full_rewind_range_proof(
+                &factories.range_proof,
+                &PrivateKey::random(&mut OsRng),
+                &rewind_data.rewind_blinding_key,
+            )
+            .is_err());
+    }
+
     #[test]
     fn with_maturity() {
         let features = OutputFeatures::with_maturity(42);
@@ -805,6 +1017,50 @@ mod test {
         assert_eq!(features.flags, OutputFlags::empty());
     }
 
+    #[test]
+    fn output_feature_extensions() {
+        let features = OutputFeatures::create_coinbase(60);
+        // Outputs without extensions hash the same as they did before extensions existed
+        assert_eq!(features.to_bytes(), vec![1, 60, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut features = OutputFeatures::with_maturity(42);
+        features.set_extension(7, vec![1, 2, 3]).unwrap();
+        features.set_extension(3, vec![]).unwrap();
+        let bytes = features.extensions_to_bytes();
+        assert_eq!(bytes, vec![3, 0, 0, 7, 3, 0, 1, 2, 3]);
+        assert_eq!(features.extensions_size(), bytes.len());
+        // Unknown extensions survive a round-trip
+        assert_eq!(
+            OutputFeatures::extensions_from_bytes(&bytes).unwrap(),
+            features.extensions
+        );
+        assert_ne!(features.to_bytes(), OutputFeatures::with_maturity(42).to_bytes());
+
+        assert_eq!(
+            OutputFeatures::extensions_from_bytes(&bytes[..bytes.len() - 1]),
+            Err(OutputFeaturesError::TruncatedRecord)
+        );
+        assert_eq!(
+            OutputFeatures::extensions_from_bytes(&[7, 0, 0, 3, 0, 0]),
+            Err(OutputFeaturesError::NonCanonicalEncoding)
+        );
+        assert_eq!(
+            features.set_extension(1, vec![0; 0x1_0000]),
+            Err(OutputFeaturesError::ExtensionTooLarge)
+        );
+
+        assert!(OutputFeatures::default().validate_extensions(&[], 0).is_ok());
+        assert!(features.validate_extensions(&[3, 7], bytes.len()).is_ok());
+        assert_eq!(
+            features.validate_extensions(&[7], bytes.len()),
+            Err(OutputFeaturesError::UnsupportedExtension)
+        );
+        assert_eq!(
+            features.validate_extensions(&[3, 7], bytes.len() - 1),
+            Err(OutputFeaturesError::ExtensionTooLarge)
+        );
+    }
+
     #[test]
     fn range_proof_verification() {
         let factories = CryptoFactories::new(32);
@@ -27,7 +27,7 @@ use crate::transactions::{
         single_receiver::SingleReceiverTransactionProtocol,
         TransactionProtocolError,
     },
-    types::{CryptoFactories, MessageHash, PrivateKey, PublicKey, Signature},
+    types::{CryptoFactories, MessageHash, PrivateKey, PublicKey, RewindData, Signature},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -94,13 +94,39 @@ impl ReceiverTransactionProtocol {
         let state = match info {
             TransactionSenderMessage::None => RecipientState::Failed(TransactionProtocolError::InvalidStateError),
             TransactionSenderMessage::Single(v) => {
-                ReceiverTransactionProtocol::single_round(nonce, spending_key, features, &v, factories)
+                ReceiverTransactionProtocol::single_round(nonce, spending_key, features, &v, factories, None)
             },
             TransactionSenderMessage::Multiple => Self::multi_round(),
         };
         ReceiverTransactionProtocol { state }
     }
 
+    /// As for `new`, but the recipient's output is given a range proof that can be rewound with the given rewind keys
+    /// to recover its value and proof message
+    pub fn new_with_rewindable_output(
+        info: TransactionSenderMessage,
+        nonce: PrivateKey,
+        spending_key: PrivateKey,
+        features: OutputFeatures,
+        factories: &CryptoFactories,
+        rewind_data: &RewindData,
+    ) -> ReceiverTransactionProtocol
+    {
+        let state = match info {
+            TransactionSenderMessage::None => RecipientState::Failed(TransactionProtocolError::InvalidStateError),
+            TransactionSenderMessage::Single(v) => ReceiverTransactionProtocol::single_round(
+                nonce,
+                spending_key,
+                features,
+                &v,
+                factories,
+                Some(rewind_data),
+            ),
+            TransactionSenderMessage::Multiple => Self::multi_round(),
+        };
+        ReceiverTransactionProtocol { state }
+    }
+
     /// Returns true if the recipient protocol is finalised, and the signature data is ready to be sent to the sender.
     pub fn is_finalized(&self) -> bool {
         match self.state {
@@ -140,9 +166,15 @@ impl ReceiverTransactionProtocol {
         features: OutputFeatures,
         data: &SD,
         factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
     ) -> RecipientState
     {
-        let signer = SingleReceiverTransactionProtocol::create(data, nonce, key, features, factories);
+        let signer = match rewind_data {
+            Some(rewind_data) => {
+                SingleReceiverTransactionProtocol::create_rewindable(data, nonce, key, features, factories, rewind_data)
+            },
+            None => SingleReceiverTransactionProtocol::create(data, nonce, key, features, factories),
+        };
         match signer {
             Ok(signed_data) => RecipientState::Finalized(Box::new(signed_data)),
             Err(e) => RecipientState::Failed(e),
@@ -256,16 +256,27 @@ impl SenderTransactionProtocol {
     pub fn build_single_round_message(&mut self) -> Result<SingleRoundSenderData, TPE> {
         match &self.state {
             SenderState::SingleRoundMessageReady(info) => {
-                let result = SingleRoundSenderData {
+                let result = self.get_single_round_message()?;
+                self.state = SenderState::CollectingSingleSignature(info.clone());
+                Ok(result)
+            },
+            _ => Err(TPE::InvalidStateError),
+        }
+    }
+
+    /// Return the sender's message for the single-round protocol without changing State. This allows the message to be
+    /// sent again while the recipient's signature is still being collected.
+    pub fn get_single_round_message(&self) -> Result<SingleRoundSenderData, TPE> {
+        match &self.state {
+            SenderState::SingleRoundMessageReady(info) | SenderState::CollectingSingleSignature(info) => {
+                Ok(SingleRoundSenderData {
                     tx_id: info.ids[0],
-                    amount: self.get_total_amount().unwrap(),
+                    amount: self.get_total_amount()?,
                     public_nonce: info.public_nonce.clone(),
                     public_excess: info.public_excess.clone(),
                     metadata: info.metadata.clone(),
                     message: info.message.clone(),
-                };
-                self.state = SenderState::CollectingSingleSignature(info.clone());
-                Ok(result)
+                })
             },
             _ => Err(TPE::InvalidStateError),
         }
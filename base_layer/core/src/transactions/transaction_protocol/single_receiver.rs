@@ -28,12 +28,12 @@ use crate::transactions::{
         sender::SingleRoundSenderData as SD,
         TransactionProtocolError as TPE,
     },
-    types::{CryptoFactories, PrivateKey as SK, PublicKey, RangeProof, Signature},
+    types::{CryptoFactories, PrivateKey as SK, PublicKey, RangeProof, RewindData, Signature},
 };
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
     keys::PublicKey as PK,
-    range_proof::{RangeProofError, RangeProofService as RPS},
+    range_proof::{RangeProofError, RangeProofService as RPS, RewindableRangeProofService},
     tari_utilities::byte_array::ByteArray,
 };
 
@@ -53,9 +53,54 @@ impl SingleReceiverTransactionProtocol {
         features: OutputFeatures,
         factories: &CryptoFactories,
     ) -> Result<RD, TPE>
+    {
+        SingleReceiverTransactionProtocol::create_with_rewind_data(
+            sender_info,
+            nonce,
+            spending_key,
+            features,
+            factories,
+            None,
+        )
+    }
+
+    /// As for `create`, but the receiver's output is given a range proof that can be rewound with the given rewind keys
+    pub fn create_rewindable(
+        sender_info: &SD,
+        nonce: SK,
+        spending_key: SK,
+        features: OutputFeatures,
+        factories: &CryptoFactories,
+        rewind_data: &RewindData,
+    ) -> Result<RD, TPE>
+    {
+        SingleReceiverTransactionProtocol::create_with_rewind_data(
+            sender_info,
+            nonce,
+            spending_key,
+            features,
+            factories,
+            Some(rewind_data),
+        )
+    }
+
+    fn create_with_rewind_data(
+        sender_info: &SD,
+        nonce: SK,
+        spending_key: SK,
+        features: OutputFeatures,
+        factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
+    ) -> Result<RD, TPE>
     {
         SingleReceiverTransactionProtocol::validate_sender_data(sender_info)?;
-        let output = SingleReceiverTransactionProtocol::build_output(sender_info, &spending_key, features, factories)?;
+        let output = SingleReceiverTransactionProtocol::build_output(
+            sender_info,
+            &spending_key,
+            features,
+            factories,
+            rewind_data,
+        )?;
         let public_nonce = PublicKey::from_secret_key(&nonce);
         let public_spending_key = PublicKey::from_secret_key(&spending_key);
         let e = build_challenge(&(&sender_info.public_nonce + &public_nonce), &sender_info.metadata);
@@ -82,14 +127,24 @@ impl SingleReceiverTransactionProtocol {
         spending_key: &SK,
         features: OutputFeatures,
         factories: &CryptoFactories,
+        rewind_data: Option<&RewindData>,
     ) -> Result<TransactionOutput, TPE>
     {
         let commitment = factories
             .commitment
             .commit_value(&spending_key, sender_info.amount.into());
-        let proof = factories
-            .range_proof
-            .construct_proof(&spending_key, sender_info.amount.into())?;
+        let proof = match rewind_data {
+            Some(rewind_data) => factories.range_proof.construct_proof_with_rewind_key(
+                &spending_key,
+                sender_info.amount.into(),
+                &rewind_data.rewind_key,
+                &rewind_data.rewind_blinding_key,
+                &rewind_data.proof_message,
+            )?,
+            None => factories
+                .range_proof
+                .construct_proof(&spending_key, sender_info.amount.into())?,
+        };
         Ok(TransactionOutput::new(
             features,
             commitment,
@@ -111,7 +166,7 @@ mod test {
             TransactionMetadata,
             TransactionProtocolError,
         },
-        types::{CryptoFactories, PrivateKey, PublicKey},
+        types::{CryptoFactories, PrivateKey, PublicKey, RewindData, REWIND_USER_MESSAGE_LENGTH},
     };
     use rand::rngs::OsRng;
     use tari_crypto::{
@@ -181,4 +236,40 @@ mod test {
         );
         assert!(out.features.flags.is_empty(), "Output features flags have changed");
     }
+
+    #[test]
+    fn rewindable_output() {
+        let factories = CryptoFactories::default();
+        let (_xs, pub_xs) = PublicKey::random_keypair(&mut OsRng);
+        let (_rs, pub_rs) = PublicKey::random_keypair(&mut OsRng);
+        let (r, k, of) = generate_output_parms();
+        let rewind_data = RewindData {
+            rewind_key: PrivateKey::random(&mut OsRng),
+            rewind_blinding_key: PrivateKey::random(&mut OsRng),
+            proof_message: [1u8; REWIND_USER_MESSAGE_LENGTH],
+        };
+        let info = SingleRoundSenderData {
+            tx_id: 500,
+            amount: MicroTari(1500),
+            public_excess: pub_xs,
+            public_nonce: pub_rs,
+            metadata: TransactionMetadata::default(),
+            message: "".to_string(),
+        };
+        let prot =
+            SingleReceiverTransactionProtocol::create_rewindable(&info, r, k.clone(), of, &factories, &rewind_data)
+                .unwrap();
+        let out = &prot.output;
+        assert!(out.verify_range_proof(&factories.range_proof).unwrap());
+        let result = out
+            .full_rewind_range_proof(
+                &factories.range_proof,
+                &rewind_data.rewind_key,
+                &rewind_data.rewind_blinding_key,
+            )
+            .unwrap();
+        assert_eq!(result.committed_value, 1500);
+        assert_eq!(result.blinding_factor, k);
+        assert_eq!(result.proof_message, rewind_data.proof_message);
+    }
 }
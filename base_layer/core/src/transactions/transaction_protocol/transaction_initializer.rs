@@ -35,7 +35,7 @@ use crate::transactions::{
         sender::{calculate_tx_id, RawTransactionInfo, SenderState, SenderTransactionProtocol},
         TransactionMetadata,
     },
-    types::{BlindingFactor, CryptoFactories, PrivateKey, PublicKey},
+    types::{BlindingFactor, CryptoFactories, PrivateKey, PublicKey, RewindData},
 };
 use digest::Digest;
 use std::{
@@ -61,7 +61,10 @@ pub struct SenderTransactionInitializer {
     inputs: Vec<TransactionInput>,
     unblinded_inputs: Vec<UnblindedOutput>,
     outputs: Vec<UnblindedOutput>,
+    output_rewind_data: Vec<Option<RewindData>>,
     change_secret: Option<BlindingFactor>,
+    change_rewind_data: Option<RewindData>,
+    dust_threshold: MicroTari,
     offset: Option<BlindingFactor>,
     excess_blinding_factor: BlindingFactor,
     private_nonce: Option<PrivateKey>,
@@ -89,7 +92,10 @@ impl SenderTransactionInitializer {
             inputs: Vec::new(),
             unblinded_inputs: Vec::new(),
             outputs: Vec::new(),
+            output_rewind_data: Vec::new(),
             change_secret: None,
+            change_rewind_data: None,
+            dust_threshold: MicroTari(0),
             offset: None,
             private_nonce: None,
             excess_blinding_factor: BlindingFactor::default(),
@@ -126,15 +132,26 @@ impl SenderTransactionInitializer {
     /// was first set as an output. We don't check that the input and commitments match at this point.
     pub fn with_input(&mut self, utxo: TransactionInput, input: UnblindedOutput) -> &mut Self {
         self.inputs.push(utxo);
-        self.excess_blinding_factor = &self.excess_blinding_factor - &input.spending_key;
+        self.excess_blinding_factor = &self.excess_blinding_factor - input.spending_key.reveal();
         self.unblinded_inputs.push(input);
         self
     }
 
     /// Adds an output to the transaction. This can be called multiple times
     pub fn with_output(&mut self, output: UnblindedOutput) -> &mut Self {
-        self.excess_blinding_factor = &self.excess_blinding_factor + &output.spending_key;
+        self.add_output(output, None)
+    }
+
+    /// Adds an output whose range proof can be rewound with the given rewind keys to recover its value and proof
+    /// message. This can be called multiple times
+    pub fn with_rewindable_output(&mut self, output: UnblindedOutput, rewind_data: RewindData) -> &mut Self {
+        self.add_output(output, Some(rewind_data))
+    }
+
+    fn add_output(&mut self, output: UnblindedOutput, rewind_data: Option<RewindData>) -> &mut Self {
+        self.excess_blinding_factor = &self.excess_blinding_factor + output.spending_key.reveal();
         self.outputs.push(output);
+        self.output_rewind_data.push(rewind_data);
         self
     }
 
@@ -145,6 +162,19 @@ impl SenderTransactionInitializer {
         self
     }
 
+    /// Provide the rewind data for the change output, if one is created, so that its range proof can be rewound
+    pub fn with_change_rewind_data(&mut self, rewind_data: RewindData) -> &mut Self {
+        self.change_rewind_data = Some(rewind_data);
+        self
+    }
+
+    /// Set the dust threshold. A change output worth less than this is not created; the change is added to the fee
+    /// instead. The default threshold is zero, i.e. any change that can pay for its own output is kept.
+    pub fn with_dust_threshold(&mut self, dust_threshold: MicroTari) -> &mut Self {
+        self.dust_threshold = dust_threshold;
+        self
+    }
+
     /// Provide the private nonce that will be used for the sender's partial signature for the transaction.
     pub fn with_private_nonce(&mut self, nonce: PrivateKey) -> &mut Self {
         self.private_nonce = Some(nonce);
@@ -159,7 +189,7 @@ impl SenderTransactionInitializer {
 
     /// Tries to make a change output with the given transaction parameters and add it to the set of outputs. The total
     /// fee, including the additional change output (if any) is returned along with the amount of change.
-    /// The change output **always has default output features**.
+    /// The change output **always has default output features**. Change below the dust threshold is added to the fee.
     fn add_change_if_required(&mut self) -> Result<(MicroTari, MicroTari), String> {
         // The number of outputs excluding a possible residual change output
         let num_outputs = self.outputs.len() + self.num_recipients;
@@ -184,13 +214,16 @@ impl SenderTransactionInitializer {
                     // output and go without a change output
                     None => Ok((fee_without_change + v, MicroTari(0))),
                     Some(MicroTari(0)) => Ok((fee_without_change + v, MicroTari(0))),
+                    // The change would be dust, so sweep it into the fee rather than creating an unspendable output
+                    Some(change) if change < self.dust_threshold => Ok((fee_without_change + v, MicroTari(0))),
                     Some(v) => {
                         let change_key = self
                             .change_secret
                             .as_ref()
                             .ok_or_else(|| "Change spending key was not provided")?;
                         let change_key = change_key.clone();
-                        self.with_output(UnblindedOutput::new(v, change_key, None));
+                        let rewind_data = self.change_rewind_data.clone();
+                        self.add_output(UnblindedOutput::new(v, change_key, None), rewind_data);
                         Ok((fee_with_change, v))
                     },
                 }
@@ -250,7 +283,11 @@ impl SenderTransactionInitializer {
         let outputs = match self
             .outputs
             .iter()
-            .map(|o| o.as_transaction_output(factories))
+            .zip(self.output_rewind_data.iter())
+            .map(|(o, rewind_data)| match rewind_data {
+                Some(rewind_data) => o.as_rewindable_transaction_output(factories, rewind_data),
+                None => o.as_transaction_output(factories),
+            })
             .collect::<Result<Vec<TransactionOutput>, _>>()
         {
             Ok(o) => o,
@@ -322,10 +359,10 @@ mod test {
             transaction_initializer::SenderTransactionInitializer,
             TransactionProtocolError,
         },
-        types::CryptoFactories,
+        types::{CryptoFactories, PrivateKey, RewindData, REWIND_USER_MESSAGE_LENGTH},
     };
     use rand::rngs::OsRng;
-    use tari_crypto::common::Blake256;
+    use tari_crypto::{common::Blake256, keys::SecretKey};
 
     /// One input, 2 outputs
     #[test]
@@ -442,6 +479,88 @@ mod test {
         }
     }
 
+    /// Change that could pay for its own output but is below the dust threshold is added to the fee
+    #[test]
+    fn dust_change_is_added_to_fee() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let expected_fee = MicroTari::from(BASE_COST + (WEIGHT_PER_INPUT + 1 * WEIGHT_PER_OUTPUT) * 20); // 101
+        let build = |dust_threshold: MicroTari| {
+            let (utxo, input) = make_input(&mut OsRng, MicroTari(2000), &factories.commitment);
+            // 200 µT is left over, which is 120 µT of change after paying for the change output
+            let output = UnblindedOutput::new(
+                MicroTari(2000) - expected_fee - MicroTari(200),
+                p.spend_key.clone(),
+                None,
+            );
+            let mut builder = SenderTransactionInitializer::new(0);
+            builder
+                .with_lock_height(0)
+                .with_offset(p.offset.clone())
+                .with_private_nonce(p.nonce.clone())
+                .with_output(output)
+                .with_input(utxo, input)
+                .with_change_secret(p.change_key.clone())
+                .with_dust_threshold(dust_threshold)
+                .with_fee_per_gram(MicroTari(20));
+            match builder.build::<Blake256>(&factories).unwrap().state {
+                SenderState::Finalizing(info) => info,
+                _ => panic!("There were no recipients, so we should be finalizing"),
+            }
+        };
+
+        let info = build(MicroTari(0));
+        assert_eq!(info.change, MicroTari(120), "Change");
+        assert_eq!(info.outputs.len(), 2, "There should be a change output");
+
+        let info = build(MicroTari(121));
+        assert_eq!(info.change, MicroTari(0), "Change");
+        assert_eq!(info.metadata.fee, expected_fee + MicroTari(200), "Fee");
+        assert_eq!(info.outputs.len(), 1, "There should be no change output");
+    }
+
+    #[test]
+    fn rewindable_change_output() {
+        let factories = CryptoFactories::default();
+        let p = TestParams::new();
+        let rewind_data = RewindData {
+            rewind_key: PrivateKey::random(&mut OsRng),
+            rewind_blinding_key: PrivateKey::random(&mut OsRng),
+            proof_message: [5u8; REWIND_USER_MESSAGE_LENGTH],
+        };
+        let (utxo, input) = make_input(&mut OsRng, MicroTari(2000), &factories.commitment);
+        let mut builder = SenderTransactionInitializer::new(0);
+        builder
+            .with_lock_height(0)
+            .with_offset(p.offset)
+            .with_private_nonce(p.nonce)
+            .with_output(UnblindedOutput::new(MicroTari(500), p.spend_key, None))
+            .with_input(utxo, input)
+            .with_change_secret(p.change_key.clone())
+            .with_change_rewind_data(rewind_data.clone())
+            .with_fee_per_gram(MicroTari(20));
+        let info = match builder.build::<Blake256>(&factories).unwrap().state {
+            SenderState::Finalizing(info) => info,
+            _ => panic!("There were no recipients, so we should be finalizing"),
+        };
+        assert_eq!(info.outputs.len(), 2, "There should be a change output");
+        let rewound = info
+            .outputs
+            .iter()
+            .filter_map(|o| {
+                o.full_rewind_range_proof(
+                    &factories.range_proof,
+                    &rewind_data.rewind_key,
+                    &rewind_data.rewind_blinding_key,
+                )
+                .ok()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rewound.len(), 1, "Only the change output should be rewindable");
+        assert_eq!(rewound[0].committed_value, u64::from(info.change));
+        assert_eq!(rewound[0].blinding_factor, p.change_key);
+    }
+
     #[test]
     fn too_many_inputs() {
         // Create some inputs
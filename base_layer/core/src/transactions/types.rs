@@ -22,6 +22,7 @@
 
 use crate::transactions::bullet_rangeproofs::BulletRangeProof;
 use std::sync::Arc;
+pub use tari_crypto::range_proof::{FullRewindResult, RewindResult, REWIND_USER_MESSAGE_LENGTH};
 use tari_crypto::{
     common::Blake256,
     ristretto::{
@@ -71,6 +72,16 @@ pub type HashOutput = Vec<u8>;
 
 pub const MAX_RANGE_PROOF_RANGE: usize = 64; // 2^64
 
+/// The keys and message used to construct a rewindable range proof. Anyone holding the rewind keys can later recover
+/// the committed value and the proof message from the range proof on the blockchain, and with the rewind blinding key
+/// also the blinding factor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RewindData {
+    pub rewind_key: PrivateKey,
+    pub rewind_blinding_key: PrivateKey,
+    pub proof_message: [u8; REWIND_USER_MESSAGE_LENGTH],
+}
+
 /// A convenience struct wrapping cryptographic factories that are used through-out the rest of the code base
 /// Uses Arcs internally so calling clone on this is cheap, no need to wrap this in an Arc
 pub struct CryptoFactories {
@@ -32,6 +32,7 @@ use crate::{
     transactions::{transaction::OutputFlags, types::CryptoFactories},
     validation::{
         helpers::{check_achieved_difficulty, check_median_timestamp},
+        timings::{time_stage, ValidationStage},
         StatelessValidation,
         Validation,
         ValidationError,
@@ -59,15 +60,18 @@ impl StatelessBlockValidator {
 impl StatelessValidation<Block> for StatelessBlockValidator {
     /// The consensus checks that are done (in order of cheapest to verify to most expensive):
     /// 1. Is there precisely one Coinbase output and is it correctly defined?
+    /// 1. Are the output feature extensions permitted?
     /// 1. Is the accounting correct?
     /// 1. Are all inputs allowed to be spent (Are the feature flags satisfied)
     fn validate(&self, block: &Block) -> Result<(), ValidationError> {
-        check_coinbase_output(block, &self.consensus_constants)?;
-        check_block_weight(block, &self.consensus_constants)?;
-        // Check that the inputs are are allowed to be spent
-        block.check_stxo_rules().map_err(BlockValidationError::from)?;
-        check_cut_through(block)?;
-        Ok(())
+        time_stage(ValidationStage::Stateless, || {
+            check_coinbase_output(block, &self.consensus_constants)?;
+            check_block_weight(block, &self.consensus_constants)?;
+            check_output_features(block, &self.consensus_constants)?;
+            // Check that the inputs are are allowed to be spent
+            block.check_stxo_rules().map_err(BlockValidationError::from)?;
+            check_cut_through(block)
+        })
     }
 }
 
@@ -100,18 +104,27 @@ impl<B: BlockchainBackend> Validation<Block, B> for FullConsensusValidator {
             block.header.height,
             block.hash().to_hex()
         );
-        check_coinbase_output(block, &self.rules.consensus_constants())?;
-        check_block_weight(block, &self.rules.consensus_constants())?;
-        check_cut_through(block)?;
-        block.check_stxo_rules().map_err(BlockValidationError::from)?;
-        check_accounting_balance(block, self.rules.clone(), &self.factories)?;
-        check_inputs_are_utxos(block, db)?;
-        check_mmr_roots(block, db)?;
-        check_timestamp_ftl(&block.header, &self.rules)?;
+        time_stage(ValidationStage::Stateless, || -> Result<(), ValidationError> {
+            check_coinbase_output(block, &self.rules.consensus_constants())?;
+            check_block_weight(block, &self.rules.consensus_constants())?;
+            check_output_features(block, &self.rules.consensus_constants())?;
+            check_cut_through(block)?;
+            block.check_stxo_rules().map_err(BlockValidationError::from)?;
+            Ok(())
+        })?;
+        time_stage(ValidationStage::Transactions, || {
+            check_accounting_balance(block, self.rules.clone(), &self.factories)
+        })?;
+        time_stage(ValidationStage::Inputs, || check_inputs_are_utxos(block, db))?;
+        time_stage(ValidationStage::MmrRoots, || check_mmr_roots(block, db))?;
         let tip_height = metadata.height_of_longest_chain.unwrap_or(0);
-        check_median_timestamp(db, &block.header, tip_height, self.rules.clone())?;
-        check_achieved_difficulty(db, &block.header, tip_height, self.rules.clone())?;
-        Ok(())
+        time_stage(ValidationStage::Header, || {
+            check_timestamp_ftl(&block.header, &self.rules)?;
+            check_median_timestamp(db, &block.header, tip_height, self.rules.clone())
+        })?;
+        time_stage(ValidationStage::ProofOfWork, || {
+            check_achieved_difficulty(db, &block.header, tip_height, self.rules.clone())
+        })
     }
 }
 
@@ -150,11 +163,18 @@ fn check_block_weight(block: &Block, consensus_constants: &ConsensusConstants) -
         block.hash().to_hex()
     );
     // The genesis block has a larger weight than other blocks may have so we have to exclude it here
-    if block.body.calculate_weight() <= consensus_constants.get_max_block_transaction_weight() ||
-        block.header.height == 0
-    {
+    let max_weight = consensus_constants.get_max_block_transaction_weight(block.header.height);
+    let weight = block.body.calculate_weight();
+    if weight <= max_weight || block.header.height == 0 {
         Ok(())
     } else {
+        warn!(
+            target: LOG_TARGET,
+            "Block #{} has weight {}, which exceeds the maximum block weight of {}",
+            block.header.height,
+            weight,
+            max_weight
+        );
         Err(BlockValidationError::BlockTooLarge).map_err(ValidationError::from)
     }
 }
@@ -170,6 +190,17 @@ fn check_coinbase_output(block: &Block, consensus_constants: &ConsensusConstants
         .map_err(ValidationError::from)
 }
 
+fn check_output_features(block: &Block, consensus_constants: &ConsensusConstants) -> Result<(), ValidationError> {
+    trace!(
+        target: LOG_TARGET,
+        "Checking output features on block with hash {}",
+        block.hash().to_hex()
+    );
+    block
+        .check_output_features(consensus_constants)
+        .map_err(ValidationError::from)
+}
+
 /// This function checks that all inputs in the blocks are valid UTXO's to be spend
 fn check_inputs_are_utxos<B: BlockchainBackend>(block: &Block, db: &B) -> Result<(), ValidationError> {
     trace!(target: LOG_TARGET, "Checking input UXTOs exist",);
@@ -22,7 +22,8 @@
 
 use crate::{
     blocks::{blockheader::BlockHeaderValidationError, BlockValidationError},
-    transactions::transaction::TransactionError,
+    mempool::ValidationComponent,
+    transactions::{transaction::TransactionError, types::Commitment},
 };
 use derive_error::Error;
 
@@ -32,8 +33,9 @@ pub enum ValidationError {
     BlockError(BlockValidationError),
     // Contains kernels or inputs that are not yet spendable
     MaturityError,
-    // Contains unknown inputs
-    UnknownInputs,
+    // Contains an input, with this commitment, that is not in the UTXO set
+    #[error(no_from, non_std)]
+    UnknownInput(Commitment),
     // The transaction has some transaction error
     TransactionError(TransactionError),
     /// Custom error with string message
@@ -45,3 +47,80 @@ pub enum ValidationError {
     // commitments.
     InvalidAccountingBalance,
 }
+
+impl ValidationError {
+    /// A stable numeric code for the cause of the failure, so that clients can act on the cause without parsing error
+    /// messages. Codes are never reused or renumbered:
+    /// - 1-99: validator errors
+    /// - 100-199: block header errors
+    /// - 200-299: block errors
+    /// - 300-399: transaction errors
+    pub fn code(&self) -> u32 {
+        match self {
+            ValidationError::CustomError(_) => 1,
+            ValidationError::NoDatabaseConfigured => 2,
+            ValidationError::BlockHeaderError(e) => block_header_error_code(e),
+            ValidationError::BlockError(e) => block_error_code(e),
+            ValidationError::InvalidAccountingBalance => 220,
+            ValidationError::TransactionError(e) => transaction_error_code(e),
+            ValidationError::MaturityError => 320,
+            ValidationError::UnknownInput(_) => 321,
+        }
+    }
+
+    /// The kernel, input or output that caused the failure, if the failure can be attributed to one
+    pub fn component(&self) -> Option<ValidationComponent> {
+        match self {
+            ValidationError::UnknownInput(c) => Some(ValidationComponent::Input(c.clone())),
+            ValidationError::TransactionError(e) => transaction_error_component(e),
+            ValidationError::BlockError(BlockValidationError::TransactionError(e)) => transaction_error_component(e),
+            _ => None,
+        }
+    }
+}
+
+fn block_header_error_code(err: &BlockHeaderValidationError) -> u32 {
+    match err {
+        BlockHeaderValidationError::ChainedGenesisBlockHeader => 101,
+        BlockHeaderValidationError::IncorrectGenesisBlockHeader => 102,
+        BlockHeaderValidationError::InvalidChaining => 103,
+        BlockHeaderValidationError::InvalidTimestamp => 104,
+        BlockHeaderValidationError::InvalidTimestampFutureTimeLimit => 105,
+        BlockHeaderValidationError::ProofOfWorkError(_) => 106,
+        BlockHeaderValidationError::MismatchedMmrRoots => 107,
+    }
+}
+
+fn block_error_code(err: &BlockValidationError) -> u32 {
+    match err {
+        BlockValidationError::TransactionError(e) => transaction_error_code(e),
+        BlockValidationError::InvalidKernel => 201,
+        BlockValidationError::InvalidInput => 202,
+        BlockValidationError::InputMaturity => 203,
+        BlockValidationError::InvalidCoinbase => 204,
+        BlockValidationError::MismatchedMmrRoots => 205,
+        BlockValidationError::NoCutThrough => 206,
+        BlockValidationError::BlockTooLarge => 207,
+        BlockValidationError::InvalidOutputFeatures(_) => 208,
+    }
+}
+
+fn transaction_error_code(err: &TransactionError) -> u32 {
+    match err {
+        TransactionError::ValidationError(_) => 300,
+        TransactionError::KernelSumMismatch => 301,
+        TransactionError::InvalidKernelSignature(_) => 302,
+        TransactionError::InvalidSignatureError => 303,
+        TransactionError::NoSignatureError => 304,
+        TransactionError::InvalidRangeProof(_) => 305,
+        TransactionError::RangeProofError(_) => 306,
+    }
+}
+
+fn transaction_error_component(err: &TransactionError) -> Option<ValidationComponent> {
+    match err {
+        TransactionError::InvalidKernelSignature(i) => Some(ValidationComponent::Kernel(*i)),
+        TransactionError::InvalidRangeProof(c) => Some(ValidationComponent::Output(c.clone())),
+        _ => None,
+    }
+}
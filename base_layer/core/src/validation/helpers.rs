@@ -67,7 +67,8 @@ pub fn check_median_timestamp<B: BlockchainBackend>(
     Ok(())
 }
 
-/// Calculates the achieved and target difficulties at the specified height and compares them.
+/// Calculates the achieved and target difficulties at the specified height and compares them. The target difficulty
+/// must also lie within the network difficulty limits.
 pub fn check_achieved_difficulty<B: BlockchainBackend>(
     db: &B,
     block_header: &BlockHeader,
@@ -93,6 +94,17 @@ pub fn check_achieved_difficulty<B: BlockchainBackend>(
                     PowError::InvalidProofOfWork,
                 ))
             })?;
+        if !rules.consensus_constants().difficulty_limits().contains(target) {
+            warn!(
+                target: LOG_TARGET,
+                "Target difficulty {} for {} is outside of the network difficulty limits",
+                target,
+                block_header.hash().to_hex()
+            );
+            return Err(ValidationError::BlockHeaderError(
+                BlockHeaderValidationError::ProofOfWorkError(PowError::TargetDifficultyOutOfBounds),
+            ));
+        }
     }
     if achieved < target {
         warn!(
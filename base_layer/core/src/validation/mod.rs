@@ -24,6 +24,8 @@
 //! transaction, or other validation tasks. Validators implement the [Validation] trait and can be chained together
 //! in a [ValidationPipeline] object to carry out complex validation routines.
 //!
+//! The time taken by each stage of block validation is recorded by the [timings] module.
+//!
 //! This module also defines a mock [MockValidator] that is useful for testing components that require validation
 //! without having to bring in all sorts of blockchain and communications paraphernalia.
 
@@ -33,6 +35,7 @@ mod traits;
 
 pub mod block_validators;
 pub mod mocks;
+pub mod timings;
 pub use error::ValidationError;
 pub use traits::{StatelessValidation, StatelessValidator, Validation, Validator};
 pub mod transaction_validators;
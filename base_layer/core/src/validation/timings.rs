@@ -0,0 +1,231 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Per-stage timings of block validation.
+//!
+//! Every stage that a block passes through on its way into the database is run in a tracing span, and its duration
+//! is observed in the `tari_base_node_block_validation_duration_seconds` histogram, labelled by stage. While a
+//! [BlockValidationTimer] is active on the current thread the stage durations are also collected into a breakdown
+//! for that block, which is logged when the block takes longer than the configured threshold to be added. This makes
+//! it possible to see which stage is responsible when a node is slow to sync.
+
+use crate::blocks::Block;
+use lazy_static::lazy_static;
+use log::*;
+use prometheus::{register_histogram_vec, HistogramVec};
+use std::{
+    cell::RefCell,
+    fmt::{Display, Error, Formatter},
+    time::{Duration, Instant},
+};
+use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+use tracing::debug_span;
+
+const LOG_TARGET: &str = "c::val::timings";
+
+lazy_static! {
+    static ref VALIDATION_DURATION: HistogramVec = register_histogram_vec!(
+        "tari_base_node_block_validation_duration_seconds",
+        "Time taken by each stage of validating and storing a block",
+        &["stage"]
+    )
+    .expect("Block validation metrics registered more than once");
+}
+
+thread_local! {
+    static CURRENT_TIMINGS: RefCell<Option<BlockValidationTimings>> = RefCell::new(None);
+}
+
+/// The stages of adding a block to the database
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationStage {
+    /// Checks that do not need the chain state: the coinbase, block weight, output features, cut-through and spend
+    /// rules
+    Stateless,
+    /// Checks that the inputs are in the UTXO set
+    Inputs,
+    /// Checks the accounting balance, which includes verifying the range proofs and kernel signatures
+    Transactions,
+    /// Applies the block to the MMRs to check the MMR roots in the header
+    MmrRoots,
+    /// Checks the header timestamp against the future time limit and the median timestamp
+    Header,
+    /// Checks the achieved difficulty of the proof of work
+    ProofOfWork,
+    /// Writes the block to the database
+    Commit,
+}
+
+impl ValidationStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValidationStage::Stateless => "stateless",
+            ValidationStage::Inputs => "inputs",
+            ValidationStage::Transactions => "transactions",
+            ValidationStage::MmrRoots => "mmr_roots",
+            ValidationStage::Header => "header",
+            ValidationStage::ProofOfWork => "proof_of_work",
+            ValidationStage::Commit => "commit",
+        }
+    }
+}
+
+/// The time spent in each stage while adding a single block. Stages that run more than once, such as the commits of
+/// a reorg, are summed.
+#[derive(Clone, Debug, Default)]
+pub struct BlockValidationTimings {
+    stages: Vec<(ValidationStage, Duration)>,
+}
+
+impl BlockValidationTimings {
+    pub fn add(&mut self, stage: ValidationStage, duration: Duration) {
+        match self.stages.iter_mut().find(|(s, _)| *s == stage) {
+            Some((_, total)) => *total += duration,
+            None => self.stages.push((stage, duration)),
+        }
+    }
+
+    /// The time spent in `stage`, zero if the block did not reach it
+    pub fn get(&self, stage: ValidationStage) -> Duration {
+        self.stages
+            .iter()
+            .find(|(s, _)| *s == stage)
+            .map(|(_, d)| *d)
+            .unwrap_or_default()
+    }
+
+    /// The time spent in all stages
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|(_, d)| *d).sum()
+    }
+}
+
+impl Display for BlockValidationTimings {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        for (stage, duration) in &self.stages {
+            write!(f, "{}: {}ms, ", stage.as_str(), duration.as_millis())?;
+        }
+        write!(f, "total: {}ms", self.total().as_millis())
+    }
+}
+
+/// Run `f` as the given stage of block validation, recording the time it takes
+pub fn time_stage<F, R>(stage: ValidationStage, f: F) -> R
+where F: FnOnce() -> R {
+    let span = debug_span!("block_validation_stage", stage = stage.as_str());
+    let _enter = span.enter();
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    VALIDATION_DURATION
+        .with_label_values(&[stage.as_str()])
+        .observe(elapsed.as_secs_f64());
+    CURRENT_TIMINGS.with(|timings| {
+        if let Some(timings) = timings.borrow_mut().as_mut() {
+            timings.add(stage, elapsed);
+        }
+    });
+    result
+}
+
+/// Collects the stage timings of a block added on the current thread until it is dropped. The breakdown is logged as
+/// a warning if adding the block took longer than `log_threshold`, otherwise at trace level.
+pub struct BlockValidationTimer {
+    height: u64,
+    hash: String,
+    start: Instant,
+    log_threshold: Option<Duration>,
+}
+
+impl BlockValidationTimer {
+    pub fn start(block: &Block, log_threshold: Option<Duration>) -> Self {
+        CURRENT_TIMINGS.with(|timings| *timings.borrow_mut() = Some(BlockValidationTimings::default()));
+        Self {
+            height: block.header.height,
+            hash: block.hash().to_hex(),
+            start: Instant::now(),
+            log_threshold,
+        }
+    }
+
+    /// The stage timings collected so far
+    pub fn timings(&self) -> BlockValidationTimings {
+        CURRENT_TIMINGS.with(|timings| timings.borrow().clone().unwrap_or_default())
+    }
+}
+
+impl Drop for BlockValidationTimer {
+    fn drop(&mut self) {
+        let timings = CURRENT_TIMINGS.with(|timings| timings.borrow_mut().take().unwrap_or_default());
+        let elapsed = self.start.elapsed();
+        match self.log_threshold {
+            Some(threshold) if elapsed > threshold => warn!(
+                target: LOG_TARGET,
+                "Adding block {} at height {} took {}ms ({})",
+                self.hash,
+                self.height,
+                elapsed.as_millis(),
+                timings
+            ),
+            _ => trace!(
+                target: LOG_TARGET,
+                "Adding block {} at height {} took {}ms ({})",
+                self.hash,
+                self.height,
+                elapsed.as_millis(),
+                timings
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{blocks::BlockHeader, transactions::aggregated_body::AggregateBody};
+
+    #[test]
+    fn records_stage_timings() {
+        let histogram = VALIDATION_DURATION.with_label_values(&[ValidationStage::Commit.as_str()]);
+        let count = histogram.get_sample_count();
+        // Stages outside of a timer are only recorded in the histogram. Other tests may add blocks concurrently.
+        assert_eq!(time_stage(ValidationStage::Commit, || 1), 1);
+        assert!(histogram.get_sample_count() >= count + 1);
+
+        let block = Block {
+            header: BlockHeader::new(0),
+            body: AggregateBody::empty(),
+        };
+        let timer = BlockValidationTimer::start(&block, None);
+        time_stage(ValidationStage::Header, || std::thread::sleep(Duration::from_millis(5)));
+        time_stage(ValidationStage::Commit, || ());
+        time_stage(ValidationStage::Commit, || ());
+        let timings = timer.timings();
+        assert!(timings.get(ValidationStage::Header) >= Duration::from_millis(5));
+        assert_eq!(timings.get(ValidationStage::ProofOfWork), Duration::from_millis(0));
+        assert!(timings.total() >= timings.get(ValidationStage::Header));
+        assert_eq!(timings.stages.len(), 2);
+        assert!(histogram.get_sample_count() >= count + 3);
+        drop(timer);
+        assert!(CURRENT_TIMINGS.with(|timings| timings.borrow().is_none()));
+    }
+}
@@ -129,7 +129,7 @@ fn verify_inputs<B: BlockchainBackend>(tx: &Transaction, db: &B) -> Result<(), V
                 target: LOG_TARGET,
                 "Transaction validation failed due to unknown input: {}", input
             );
-            return Err(ValidationError::UnknownInputs);
+            return Err(ValidationError::UnknownInput(input.commitment.clone()));
         }
     }
     Ok(())
@@ -20,12 +20,21 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[allow(dead_code)]
+mod helpers;
+
+use helpers::block_builders::create_coinbase_with_builder;
 use tari_core::{
+    blocks::{BlockHeader, BlockValidationError},
     chain_storage::{BlockchainDatabase, MemoryDatabase, Validators},
-    consensus::{ConsensusManagerBuilder, Network},
+    consensus::{ConsensusConstantsBuilder, ConsensusManagerBuilder, Network},
     proof_of_work::DiffAdjManager,
     transactions::types::{CryptoFactories, HashDigest},
-    validation::block_validators::{FullConsensusValidator, StatelessBlockValidator},
+    validation::{
+        block_validators::{FullConsensusValidator, StatelessBlockValidator},
+        StatelessValidation,
+        ValidationError,
+    },
 };
 
 #[test]
@@ -45,3 +54,28 @@ fn test_genesis_block() {
     let result = db.add_block(block);
     assert!(result.is_ok());
 }
+
+#[test]
+fn coinbase_extra_size_is_limited() {
+    let factories = CryptoFactories::default();
+    let network = Network::LocalNet;
+    let rules = ConsensusManagerBuilder::new(network).build();
+    let genesis = rules.get_genesis_block();
+    let (utxo, kernel, _) = create_coinbase_with_builder(&factories, &rules, 1, b"tari pool".to_vec());
+    let block = BlockHeader::from_previous(&genesis.header)
+        .into_builder()
+        .with_coinbase_utxo(utxo, kernel)
+        .build();
+
+    let validator = StatelessBlockValidator::new(&rules.consensus_constants());
+    assert!(validator.validate(&block).is_ok());
+
+    let constants = ConsensusConstantsBuilder::new(network)
+        .with_coinbase_max_extra_size(4)
+        .build();
+    let validator = StatelessBlockValidator::new(&constants);
+    assert_eq!(
+        validator.validate(&block),
+        Err(ValidationError::BlockError(BlockValidationError::InvalidCoinbase))
+    );
+}
@@ -22,6 +22,7 @@
 
 use croaring::Bitmap;
 use tari_core::{
+    base_node::BlockFilter,
     blocks::BlockHeader,
     chain_storage::{
         create_lmdb_database,
@@ -78,18 +79,6 @@ fn insert_contains_delete_and_fetch_header<T: BlockchainBackend>(mut db: T) {
     assert_eq!(db.contains(&DbKey::BlockHash(hash)), Ok(false));
 }
 
-#[test]
-fn memory_insert_contains_delete_and_fetch_header() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    insert_contains_delete_and_fetch_header(db);
-}
-
-#[test]
-fn lmdb_insert_contains_delete_and_fetch_header() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    insert_contains_delete_and_fetch_header(db);
-}
-
 fn insert_contains_delete_and_fetch_utxo<T: BlockchainBackend>(mut db: T) {
     let factories = CryptoFactories::default();
     let (utxo, _) = create_utxo(MicroTari(10_000), &factories, None);
@@ -112,18 +101,6 @@ fn insert_contains_delete_and_fetch_utxo<T: BlockchainBackend>(mut db: T) {
     assert_eq!(db.contains(&DbKey::UnspentOutput(hash)), Ok(false));
 }
 
-#[test]
-fn memory_insert_contains_delete_and_fetch_utxo() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    insert_contains_delete_and_fetch_utxo(db);
-}
-
-#[test]
-fn lmdb_insert_contains_delete_and_fetch_utxo() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    insert_contains_delete_and_fetch_utxo(db);
-}
-
 fn insert_contains_delete_and_fetch_kernel<T: BlockchainBackend>(mut db: T) {
     let kernel = create_test_kernel(5.into(), 0);
     let hash = kernel.hash();
@@ -147,18 +124,6 @@ fn insert_contains_delete_and_fetch_kernel<T: BlockchainBackend>(mut db: T) {
     assert_eq!(db.contains(&DbKey::TransactionKernel(hash)), Ok(false));
 }
 
-#[test]
-fn memory_insert_contains_delete_and_fetch_kernel() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    insert_contains_delete_and_fetch_kernel(db);
-}
-
-#[test]
-fn lmdb_insert_contains_delete_and_fetch_kernel() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    insert_contains_delete_and_fetch_kernel(db);
-}
-
 fn insert_contains_delete_and_fetch_orphan<T: BlockchainBackend>(mut db: T, consensus_constants: &ConsensusConstants) {
     let txs = vec![
         (tx!(1000.into(), fee: 20.into(), inputs: 2, outputs: 1)).0,
@@ -185,22 +150,6 @@ fn insert_contains_delete_and_fetch_orphan<T: BlockchainBackend>(mut db: T, cons
     assert_eq!(db.contains(&DbKey::OrphanBlock(hash)), Ok(false));
 }
 
-#[test]
-fn memory_insert_contains_delete_and_fetch_orphan() {
-    let network = Network::LocalNet;
-    let consensus_constants = network.create_consensus_constants();
-    let db = MemoryDatabase::<HashDigest>::default();
-    insert_contains_delete_and_fetch_orphan(db, &consensus_constants);
-}
-
-#[test]
-fn lmdb_insert_contains_delete_and_fetch_orphan() {
-    let network = Network::LocalNet;
-    let consensus_constants = network.create_consensus_constants();
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    insert_contains_delete_and_fetch_orphan(db, &consensus_constants);
-}
-
 fn spend_utxo_and_unspend_stxo<T: BlockchainBackend>(mut db: T) {
     let factories = CryptoFactories::default();
     let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
@@ -250,18 +199,6 @@ fn spend_utxo_and_unspend_stxo<T: BlockchainBackend>(mut db: T) {
     assert_eq!(db.contains(&DbKey::SpentOutput(hash2)), Ok(false));
 }
 
-#[test]
-fn memory_spend_utxo_and_unspend_stxo() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    spend_utxo_and_unspend_stxo(db);
-}
-
-#[test]
-fn lmdb_spend_utxo_and_unspend_stxo() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    spend_utxo_and_unspend_stxo(db);
-}
-
 fn insert_fetch_metadata<T: BlockchainBackend>(mut db: T) {
     assert!(db.fetch(&DbKey::Metadata(MetadataKey::ChainHeight)).unwrap().is_none());
     assert!(db
@@ -329,18 +266,6 @@ fn insert_fetch_metadata<T: BlockchainBackend>(mut db: T) {
     }
 }
 
-#[test]
-fn memory_insert_fetch_metadata() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    insert_fetch_metadata(db);
-}
-
-#[test]
-fn lmdb_insert_fetch_metadata() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    insert_fetch_metadata(db);
-}
-
 fn fetch_mmr_root_and_proof_for_utxo_and_rp<T: BlockchainBackend>(mut db: T) {
     // This is the zero-length MMR of a mutable MMR with Blake256 as hasher
     assert_eq!(
@@ -404,18 +329,6 @@ fn fetch_mmr_root_and_proof_for_utxo_and_rp<T: BlockchainBackend>(mut db: T) {
     assert!(proof3.verify_leaf::<HashDigest>(&mmr_only_root, &rp_hash3, 2).is_ok());
 }
 
-#[test]
-fn memory_fetch_mmr_root_and_proof_for_utxo_and_rp() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    fetch_mmr_root_and_proof_for_utxo_and_rp(db);
-}
-
-#[test]
-fn lmdb_fetch_mmr_root_and_proof_for_utxo_and_rp() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    fetch_mmr_root_and_proof_for_utxo_and_rp(db);
-}
-
 fn fetch_mmr_root_and_proof_for_kernel<T: BlockchainBackend>(mut db: T) {
     // This is the zero-length MMR of a mutable MMR with Blake256 as hasher
     assert_eq!(
@@ -454,18 +367,6 @@ fn fetch_mmr_root_and_proof_for_kernel<T: BlockchainBackend>(mut db: T) {
     assert!(proof3.verify_leaf::<HashDigest>(&mmr_only_root, &hash3, 2).is_ok());
 }
 
-#[test]
-fn memory_fetch_mmr_root_and_proof_for_kernel() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    fetch_mmr_root_and_proof_for_kernel(db);
-}
-
-#[test]
-fn lmdb_fetch_mmr_root_and_proof_for_kernel() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    fetch_mmr_root_and_proof_for_kernel(db);
-}
-
 fn fetch_future_mmr_root_for_utxo_and_rp<T: BlockchainBackend>(mut db: T) {
     let factories = CryptoFactories::default();
 
@@ -505,18 +406,6 @@ fn fetch_future_mmr_root_for_utxo_and_rp<T: BlockchainBackend>(mut db: T) {
     assert_eq!(rp_future_root, db.fetch_mmr_root(MmrTree::RangeProof).unwrap().to_hex());
 }
 
-#[test]
-fn memory_fetch_future_mmr_root_for_utxo_and_rp() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    fetch_future_mmr_root_for_utxo_and_rp(db);
-}
-
-#[test]
-fn lmdb_fetch_future_mmr_root_for_utxo_and_rp() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    fetch_future_mmr_root_for_utxo_and_rp(db);
-}
-
 fn fetch_future_mmr_root_for_for_kernel<T: BlockchainBackend>(mut db: T) {
     let kernel1 = create_test_kernel(100.into(), 0);
     let kernel2 = create_test_kernel(200.into(), 1);
@@ -544,18 +433,6 @@ fn fetch_future_mmr_root_for_for_kernel<T: BlockchainBackend>(mut db: T) {
     assert_eq!(future_root, db.fetch_mmr_root(MmrTree::Kernel).unwrap().to_hex());
 }
 
-#[test]
-fn memory_fetch_future_mmr_root_for_for_kernel() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    fetch_future_mmr_root_for_for_kernel(db);
-}
-
-#[test]
-fn lmdb_fetch_future_mmr_root_for_for_kernel() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    fetch_future_mmr_root_for_for_kernel(db);
-}
-
 fn commit_block_and_create_fetch_checkpoint_and_rewind_mmr<T: BlockchainBackend>(mut db: T) {
     let factories = CryptoFactories::default();
     let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
@@ -641,18 +518,6 @@ fn commit_block_and_create_fetch_checkpoint_and_rewind_mmr<T: BlockchainBackend>
     assert_eq!(db.contains(&DbKey::BlockHeader(1)), Ok(false));
 }
 
-#[test]
-fn memory_commit_block_and_create_fetch_checkpoint_and_rewind_mmr() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    commit_block_and_create_fetch_checkpoint_and_rewind_mmr(db);
-}
-
-#[test]
-fn lmdb_commit_block_and_create_fetch_checkpoint_and_rewind_mmr() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    commit_block_and_create_fetch_checkpoint_and_rewind_mmr(db);
-}
-
 // TODO: Test Needed: fetch_mmr_node
 
 fn for_each_orphan<T: BlockchainBackend>(mut db: T, consensus_constants: &ConsensusConstants) {
@@ -702,22 +567,6 @@ fn for_each_orphan<T: BlockchainBackend>(mut db: T, consensus_constants: &Consen
     assert!(orphan1_found & orphan2_found & orphan3_found);
 }
 
-#[test]
-fn memory_for_each_orphan() {
-    let network = Network::LocalNet;
-    let consensus_constants = network.create_consensus_constants();
-    let db = MemoryDatabase::<HashDigest>::default();
-    for_each_orphan(db, &consensus_constants);
-}
-
-#[test]
-fn lmdb_for_each_orphan() {
-    let network = Network::LocalNet;
-    let consensus_constants = network.create_consensus_constants();
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    for_each_orphan(db, &consensus_constants);
-}
-
 fn for_each_kernel<T: BlockchainBackend>(mut db: T) {
     let kernel1 = create_test_kernel(100.into(), 0);
     let kernel2 = create_test_kernel(200.into(), 1);
@@ -753,18 +602,6 @@ fn for_each_kernel<T: BlockchainBackend>(mut db: T) {
     assert!(kernel1_found & kernel2_found & kernel3_found);
 }
 
-#[test]
-fn memory_for_each_kernel() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    for_each_kernel(db);
-}
-
-#[test]
-fn lmdb_for_each_kernel() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    for_each_kernel(db);
-}
-
 fn for_each_header<T: BlockchainBackend>(mut db: T) {
     let header1 = BlockHeader::new(0);
     let header2 = BlockHeader::from_previous(&header1);
@@ -800,18 +637,6 @@ fn for_each_header<T: BlockchainBackend>(mut db: T) {
     assert!(header1_found & header2_found & header3_found);
 }
 
-#[test]
-fn memory_for_each_header() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    for_each_header(db);
-}
-
-#[test]
-fn lmdb_for_each_header() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    for_each_header(db);
-}
-
 fn for_each_utxo<T: BlockchainBackend>(mut db: T) {
     let factories = CryptoFactories::default();
     let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
@@ -848,18 +673,6 @@ fn for_each_utxo<T: BlockchainBackend>(mut db: T) {
     assert!(utxo1_found & utxo2_found & utxo3_found);
 }
 
-#[test]
-fn memory_for_each_utxo() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    for_each_utxo(db);
-}
-
-#[test]
-fn lmdb_for_each_utxo() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    for_each_utxo(db);
-}
-
 #[test]
 fn lmdb_backend_restore() {
     let factories = CryptoFactories::default();
@@ -998,6 +811,103 @@ fn lmdb_mmr_reset_and_commit() {
     assert!(db.fetch_checkpoint(MmrTree::RangeProof, 1).is_err());
 }
 
+#[test]
+fn lmdb_block_write_is_atomic() {
+    let factories = CryptoFactories::default();
+    let mut db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
+
+    let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
+    let (utxo2, _) = create_utxo(MicroTari(15_000), &factories, None);
+    let kernel = create_test_kernel(100.into(), 0);
+    let mut header = BlockHeader::new(0);
+    header.height = 1;
+    let utxo_hash1 = utxo1.hash();
+    let kernel_hash = kernel.hash();
+    let header_hash = header.hash();
+
+    // The last operation fails, none of the block's changes may be persisted
+    let mut txn = DbTransaction::new();
+    txn.insert_header(header.clone());
+    txn.insert_utxo(utxo1, true);
+    txn.insert_kernel(kernel, true);
+    txn.commit_block();
+    txn.set_chain_metadata(1, header_hash.clone(), 1.into());
+    txn.spend_utxo(utxo2.hash());
+    assert!(db.write(txn).is_err());
+
+    assert_eq!(db.contains(&DbKey::BlockHeader(1)), Ok(false));
+    assert_eq!(db.contains(&DbKey::BlockHash(header_hash.clone())), Ok(false));
+    assert_eq!(db.contains(&DbKey::UnspentOutput(utxo_hash1.clone())), Ok(false));
+    assert_eq!(db.contains(&DbKey::TransactionKernel(kernel_hash.clone())), Ok(false));
+    assert_eq!(db.contains(&DbKey::Metadata(MetadataKey::ChainHeight)), Ok(false));
+    assert!(db.fetch_checkpoint(MmrTree::Utxo, 0).is_err());
+    assert!(db.fetch_checkpoint(MmrTree::Kernel, 0).is_err());
+    assert!(db.fetch_checkpoint(MmrTree::RangeProof, 0).is_err());
+
+    // Outputs created earlier in the same transaction can be spent, as reads see the uncommitted changes
+    let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
+    let utxo_hash1 = utxo1.hash();
+    let mut txn = DbTransaction::new();
+    txn.insert_header(header);
+    txn.insert_utxo(utxo1, true);
+    txn.commit_block();
+    txn.spend_utxo(utxo_hash1.clone());
+    txn.commit_block();
+    txn.set_chain_metadata(1, header_hash.clone(), 1.into());
+    assert!(db.write(txn).is_ok());
+
+    assert_eq!(db.contains(&DbKey::BlockHash(header_hash)), Ok(true));
+    assert_eq!(db.contains(&DbKey::UnspentOutput(utxo_hash1.clone())), Ok(false));
+    assert_eq!(db.contains(&DbKey::SpentOutput(utxo_hash1.clone())), Ok(true));
+    assert_eq!(db.contains(&DbKey::Metadata(MetadataKey::ChainHeight)), Ok(true));
+    assert_eq!(
+        db.fetch_checkpoint(MmrTree::Utxo, 0).unwrap().nodes_added()[0],
+        utxo_hash1
+    );
+    assert!(db
+        .fetch_checkpoint(MmrTree::Utxo, 1)
+        .unwrap()
+        .nodes_deleted()
+        .contains(0));
+}
+
+#[test]
+fn lmdb_group_commit() {
+    let factories = CryptoFactories::default();
+    let path = create_temporary_data_path();
+    let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
+    let (utxo2, _) = create_utxo(MicroTari(15_000), &factories, None);
+    let utxo_hash1 = utxo1.hash();
+    let utxo_hash2 = utxo2.hash();
+    {
+        let mut db = create_lmdb_database(&path, MmrCacheConfig::default()).unwrap();
+        assert!(db.begin_group_commit().is_ok());
+        let mut txn = DbTransaction::new();
+        txn.insert_utxo(utxo1, true);
+        txn.commit_block();
+        assert!(db.write(txn).is_ok());
+        let mut txn = DbTransaction::new();
+        txn.insert_utxo(utxo2, true);
+        txn.commit_block();
+        assert!(db.write(txn).is_ok());
+        // Committed transactions are visible before the group ends
+        assert_eq!(db.contains(&DbKey::UnspentOutput(utxo_hash1.clone())), Ok(true));
+        assert!(db.end_group_commit().is_ok());
+    }
+    // Restore backend storage
+    let db = create_lmdb_database(&path, MmrCacheConfig::default()).unwrap();
+    assert_eq!(db.contains(&DbKey::UnspentOutput(utxo_hash1.clone())), Ok(true));
+    assert_eq!(db.contains(&DbKey::UnspentOutput(utxo_hash2.clone())), Ok(true));
+    assert_eq!(
+        db.fetch_checkpoint(MmrTree::Utxo, 0).unwrap().nodes_added()[0],
+        utxo_hash1
+    );
+    assert_eq!(
+        db.fetch_checkpoint(MmrTree::Utxo, 1).unwrap().nodes_added()[0],
+        utxo_hash2
+    );
+}
+
 fn fetch_checkpoint<T: BlockchainBackend>(mut db: T) {
     let factories = CryptoFactories::default();
     let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
@@ -1076,20 +986,6 @@ fn fetch_checkpoint<T: BlockchainBackend>(mut db: T) {
     assert!(rp_cp2.unwrap().nodes_added().contains(&rp_hash3));
 }
 
-#[test]
-fn memory_fetch_checkpoint() {
-    let mmr_cache_config = MmrCacheConfig { rewind_hist_len: 1 };
-    let db = MemoryDatabase::<HashDigest>::new(mmr_cache_config);
-    fetch_checkpoint(db);
-}
-
-#[test]
-fn lmdb_fetch_checkpoint() {
-    let mmr_cache_config = MmrCacheConfig { rewind_hist_len: 1 };
-    let db = create_lmdb_database(&create_temporary_data_path(), mmr_cache_config).unwrap();
-    fetch_checkpoint(db);
-}
-
 fn duplicate_utxo<T: BlockchainBackend>(mut db: T) {
     let factories = CryptoFactories::default();
     let (utxo1, _) = create_utxo(MicroTari(10_000), &factories, None);
@@ -1115,18 +1011,6 @@ fn duplicate_utxo<T: BlockchainBackend>(mut db: T) {
     }
 }
 
-#[test]
-fn memory_duplicate_utxo() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    duplicate_utxo(db);
-}
-
-#[test]
-fn lmdb_duplicate_utxo() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    duplicate_utxo(db);
-}
-
 fn fetch_last_header<T: BlockchainBackend>(mut db: T) {
     let mut header0 = BlockHeader::new(0);
     header0.height = 0;
@@ -1148,14 +1032,144 @@ fn fetch_last_header<T: BlockchainBackend>(mut db: T) {
     assert_eq!(db.fetch_last_header(), Ok(Some(header2)));
 }
 
-#[test]
-fn memory_fetch_last_header() {
-    let db = MemoryDatabase::<HashDigest>::default();
-    fetch_last_header(db);
+fn insert_contains_delete_and_fetch_block_filter<T: BlockchainBackend>(mut db: T) {
+    let filter = BlockFilter::new(vec![1u8; 32], 7, vec![b"output one".to_vec(), b"output two".to_vec()]);
+    assert_eq!(db.contains(&DbKey::BlockFilter(7)), Ok(false));
+
+    let mut txn = DbTransaction::new();
+    txn.insert_block_filter(filter.clone());
+    assert!(db.write(txn).is_ok());
+    assert_eq!(db.contains(&DbKey::BlockFilter(7)), Ok(true));
+    if let Some(DbValue::BlockFilter(retrieved_filter)) = db.fetch(&DbKey::BlockFilter(7)).unwrap() {
+        assert_eq!(*retrieved_filter, filter);
+        assert!(retrieved_filter.matches(b"output one"));
+    } else {
+        assert!(false);
+    }
+
+    let mut txn = DbTransaction::new();
+    txn.delete(DbKey::BlockFilter(7));
+    assert!(db.write(txn).is_ok());
+    assert_eq!(db.contains(&DbKey::BlockFilter(7)), Ok(false));
 }
 
-#[test]
-fn lmdb_fetch_last_header() {
-    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
-    fetch_last_header(db);
+/// Generates the conformance suite that every `BlockchainBackend` implementation must pass. Adding a backend only
+/// requires another invocation with a module name and a constructor that accepts the `MmrCacheConfig` to use.
+macro_rules! backend_conformance_tests {
+    ($backend:ident, $create_db:expr) => {
+        mod $backend {
+            use super::*;
+
+            fn create_db(mmr_cache_config: MmrCacheConfig) -> impl BlockchainBackend {
+                ($create_db)(mmr_cache_config)
+            }
+
+            #[test]
+            fn insert_contains_delete_and_fetch_header() {
+                super::insert_contains_delete_and_fetch_header(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn insert_contains_delete_and_fetch_utxo() {
+                super::insert_contains_delete_and_fetch_utxo(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn insert_contains_delete_and_fetch_kernel() {
+                super::insert_contains_delete_and_fetch_kernel(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn insert_contains_delete_and_fetch_orphan() {
+                let consensus_constants = Network::LocalNet.create_consensus_constants();
+                super::insert_contains_delete_and_fetch_orphan(
+                    create_db(MmrCacheConfig::default()),
+                    &consensus_constants,
+                );
+            }
+
+            #[test]
+            fn insert_contains_delete_and_fetch_block_filter() {
+                super::insert_contains_delete_and_fetch_block_filter(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn spend_utxo_and_unspend_stxo() {
+                super::spend_utxo_and_unspend_stxo(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn insert_fetch_metadata() {
+                super::insert_fetch_metadata(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn fetch_mmr_root_and_proof_for_utxo_and_rp() {
+                super::fetch_mmr_root_and_proof_for_utxo_and_rp(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn fetch_mmr_root_and_proof_for_kernel() {
+                super::fetch_mmr_root_and_proof_for_kernel(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn fetch_future_mmr_root_for_utxo_and_rp() {
+                super::fetch_future_mmr_root_for_utxo_and_rp(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn fetch_future_mmr_root_for_for_kernel() {
+                super::fetch_future_mmr_root_for_for_kernel(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn commit_block_and_create_fetch_checkpoint_and_rewind_mmr() {
+                super::commit_block_and_create_fetch_checkpoint_and_rewind_mmr(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn for_each_orphan() {
+                let consensus_constants = Network::LocalNet.create_consensus_constants();
+                super::for_each_orphan(create_db(MmrCacheConfig::default()), &consensus_constants);
+            }
+
+            #[test]
+            fn for_each_kernel() {
+                super::for_each_kernel(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn for_each_header() {
+                super::for_each_header(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn for_each_utxo() {
+                super::for_each_utxo(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn fetch_checkpoint() {
+                super::fetch_checkpoint(create_db(MmrCacheConfig { rewind_hist_len: 1 }));
+            }
+
+            #[test]
+            fn duplicate_utxo() {
+                super::duplicate_utxo(create_db(MmrCacheConfig::default()));
+            }
+
+            #[test]
+            fn fetch_last_header() {
+                super::fetch_last_header(create_db(MmrCacheConfig::default()));
+            }
+        }
+    };
 }
+
+backend_conformance_tests!(memory, |mmr_cache_config: MmrCacheConfig| {
+    MemoryDatabase::<HashDigest>::new(mmr_cache_config)
+});
+backend_conformance_tests!(lmdb, |mmr_cache_config: MmrCacheConfig| {
+    create_lmdb_database(&create_temporary_data_path(), mmr_cache_config).unwrap()
+});
@@ -58,7 +58,7 @@ use tari_core::{
     txn_schema,
     validation::{block_validators::StatelessBlockValidator, mocks::MockValidator},
 };
-use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+use tari_crypto::tari_utilities::{hex::Hex, ByteArray, Hashable};
 use tari_mmr::{MmrCacheConfig, MutableMmr};
 use tari_test_utils::paths::create_temporary_data_path;
 
@@ -424,6 +424,47 @@ fn add_multiple_blocks() {
     assert_eq!(metadata.best_block.unwrap(), hash);
 }
 
+#[test]
+fn add_blocks_with_group_commit() {
+    let network = Network::LocalNet;
+    let consensus_manager = ConsensusManagerBuilder::new(network).build();
+    let store = create_mem_db(&consensus_manager);
+    let block0 = store.fetch_block(0).unwrap().block().clone();
+    let block1 = append_block(
+        &store,
+        &block0,
+        vec![],
+        &consensus_manager.consensus_constants(),
+        1.into(),
+    )
+    .unwrap();
+    let block2 = append_block(
+        &store,
+        &block1,
+        vec![],
+        &consensus_manager.consensus_constants(),
+        1.into(),
+    )
+    .unwrap();
+
+    let validators = Validators::new(MockValidator::new(true), MockValidator::new(true));
+    let db = create_lmdb_database(&create_temporary_data_path(), MmrCacheConfig::default()).unwrap();
+    let store = BlockchainDatabase::new(db, &consensus_manager, validators).unwrap();
+    let results = store
+        .add_blocks(vec![block1.clone(), block2.clone(), block2.clone()])
+        .unwrap();
+    assert_eq!(results, vec![
+        Ok(BlockAddResult::Ok),
+        Ok(BlockAddResult::Ok),
+        Ok(BlockAddResult::BlockExists)
+    ]);
+    let metadata = store.get_metadata().unwrap();
+    assert_eq!(metadata.height_of_longest_chain, Some(2));
+    assert_eq!(metadata.best_block, Some(block2.hash()));
+    assert_eq!(store.fetch_block(1).unwrap().block().hash(), block1.hash());
+    assert_eq!(store.fetch_block(2).unwrap().block().hash(), block2.hash());
+}
+
 #[test]
 fn test_checkpoints() {
     let network = Network::LocalNet;
@@ -527,6 +568,76 @@ fn rewind_to_height() {
     assert_eq!(mmr, mmr_check);
 }
 
+#[test]
+fn store_and_fetch_block_filters() {
+    let network = Network::LocalNet;
+    let (mut db, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let schema = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![6 * T, 3 * T])];
+    assert_eq!(
+        generate_new_block(
+            &mut db,
+            &mut blocks,
+            &mut outputs,
+            schema,
+            &consensus_manager.consensus_constants(),
+        ),
+        Ok(BlockAddResult::Ok)
+    );
+
+    let filter = db.fetch_block_filter(1).unwrap();
+    assert_eq!(filter.height, 1);
+    assert_eq!(filter.block_hash, blocks[1].hash());
+    let body = &blocks[1].body;
+    assert!(body.outputs().iter().all(|o| filter.matches(o.commitment.as_bytes())));
+    assert!(body.inputs().iter().all(|i| filter.matches(i.commitment.as_bytes())));
+    assert!(body.kernels().iter().all(|k| filter.matches(k.excess.as_bytes())));
+    let genesis_filter = db.fetch_block_filter(0).unwrap();
+    assert!(blocks[0]
+        .body
+        .outputs()
+        .iter()
+        .all(|o| genesis_filter.matches(o.commitment.as_bytes())));
+
+    // Filters of rewound blocks are removed
+    assert!(db.rewind_to_height(0).is_ok());
+    assert!(db.fetch_block_filter(1).is_err());
+}
+
+#[test]
+fn search_kernels_by_excess_sig() {
+    let network = Network::LocalNet;
+    let (mut db, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let schema = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![6 * T, 3 * T])];
+    assert_eq!(
+        generate_new_block(
+            &mut db,
+            &mut blocks,
+            &mut outputs,
+            schema,
+            &consensus_manager.consensus_constants(),
+        ),
+        Ok(BlockAddResult::Ok)
+    );
+    assert_eq!(
+        generate_new_block(
+            &mut db,
+            &mut blocks,
+            &mut outputs,
+            vec![],
+            &consensus_manager.consensus_constants(),
+        ),
+        Ok(BlockAddResult::Ok)
+    );
+
+    let kernel = blocks[1].body.kernels()[0].clone();
+    let unknown_kernel = create_test_kernel(5.into(), 0);
+    let results = db
+        .search_kernels(vec![unknown_kernel.excess_sig, kernel.excess_sig.clone()])
+        .unwrap();
+    assert_eq!(results, vec![(kernel, blocks[1].header.clone())]);
+    assert!(db.search_kernels(vec![]).unwrap().is_empty());
+}
+
 #[test]
 fn handle_tip_reorg() {
     // GB --> A1 --> A2(Low PoW)      [Main Chain]
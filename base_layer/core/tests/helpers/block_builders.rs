@@ -26,6 +26,7 @@ use tari_core::{
     blocks::{Block, BlockHeader, NewBlockTemplate},
     chain_storage::{BlockAddResult, BlockchainBackend, BlockchainDatabase, ChainStorageError},
     consensus::{ConsensusConstants, ConsensusManager, ConsensusManagerBuilder, Network},
+    mining::CoinbaseBuilder,
     proof_of_work::Difficulty,
     transactions::{
         helpers::{
@@ -45,14 +46,15 @@ use tari_core::{
             TransactionOutput,
             UnblindedOutput,
         },
-        types::{Commitment, CryptoFactories, HashDigest, HashOutput, PublicKey},
+        types::{Commitment, CryptoFactories, HashDigest, HashOutput, PrivateKey, PublicKey},
     },
 };
 use tari_crypto::{
-    keys::PublicKey as PublicKeyTrait,
+    keys::{PublicKey as PublicKeyTrait, SecretKey},
     tari_utilities::{hash::Hashable, hex::Hex},
 };
 use tari_mmr::MutableMmr;
+use tari_test_utils::random::with_test_rng;
 
 const MAINNET: Network = Network::MainNet;
 
@@ -77,6 +79,26 @@ pub fn create_coinbase(
     (utxo, kernel, output)
 }
 
+/// Create a valid coinbase for the given height, carrying the block reward from the emission schedule and the optional
+/// extra data in its kernel.
+pub fn create_coinbase_with_builder(
+    factories: &CryptoFactories,
+    rules: &ConsensusManager,
+    height: u64,
+    extra: Vec<u8>,
+) -> (TransactionOutput, TransactionKernel, UnblindedOutput)
+{
+    let (tx, output) = CoinbaseBuilder::new(factories.clone())
+        .with_block_height(height)
+        .with_fees(0.into())
+        .with_nonce(PrivateKey::random(&mut OsRng))
+        .with_spend_key(PrivateKey::random(&mut OsRng))
+        .with_extra(extra)
+        .build(rules.clone())
+        .unwrap();
+    (tx.body.outputs()[0].clone(), tx.body.kernels()[0].clone(), output)
+}
+
 fn genesis_template(
     factories: &CryptoFactories,
     coinbase_value: MicroTari,
@@ -225,7 +247,7 @@ pub fn append_block<B: BlockchainBackend>(
 {
     let template = chain_block(prev_block, txns, consensus_constants);
     let mut block = db.calculate_mmr_roots(template)?;
-    block.header.nonce = OsRng.next_u64();
+    block.header.nonce = with_test_rng(|rng| rng.next_u64());
     find_header_with_achieved_difficulty(&mut block.header, achieved_difficulty);
     db.add_block(block.clone())?;
     Ok(block)
@@ -340,7 +362,7 @@ pub fn generate_block_with_achieved_difficulty<B: BlockchainBackend>(
 {
     let template = chain_block(&blocks.last().unwrap(), transactions, consensus_constants);
     let mut new_block = db.calculate_mmr_roots(template)?;
-    new_block.header.nonce = OsRng.next_u64();
+    new_block.header.nonce = with_test_rng(|rng| rng.next_u64());
     find_header_with_achieved_difficulty(&mut new_block.header, achieved_difficulty);
     let result = db.add_block(new_block.clone());
     if let Ok(BlockAddResult::Ok) = result {
@@ -30,6 +30,7 @@ use tari_core::{
     proof_of_work::Difficulty,
 };
 use tari_crypto::{common::Blake256, tari_utilities::ByteArray};
+use tari_service_framework::reply_channel;
 
 /// Create a mock Chain Metadata stream.
 ///
@@ -47,7 +48,8 @@ impl MockChainMetadata {
     }
 
     pub fn chain_metadata_handle(&self) -> ChainMetadataHandle {
-        ChainMetadataHandle::new(self.subscriber.clone())
+        let (sender, _) = reply_channel::unbounded();
+        ChainMetadataHandle::new(sender, self.subscriber.clone())
     }
 
     pub fn subscriber(&self) -> Subscriber<ChainMetadataEvent> {
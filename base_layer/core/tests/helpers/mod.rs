@@ -6,5 +6,6 @@
 pub mod block_builders;
 pub mod chain_metadata;
 pub mod event_stream;
+pub mod network;
 pub mod nodes;
 pub mod sample_blockchains;
@@ -0,0 +1,286 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::helpers::{
+    block_builders::chain_block,
+    nodes::{random_node_identity, BaseNodeBuilder, NodeInterfaces},
+};
+use futures::future;
+use std::time::{Duration, Instant};
+use tari_core::{
+    base_node::service::BaseNodeServiceConfig,
+    blocks::Block,
+    chain_storage::ChainMetadata,
+    consensus::{ConsensusManager, ConsensusManagerBuilder, Network},
+    mempool::MempoolServiceConfig,
+};
+use tari_mmr::MmrCacheConfig;
+use tari_p2p::services::liveness::LivenessConfig;
+use tari_test_utils::async_assert_eventually;
+use tokio::runtime::Runtime;
+
+/// The TestNetworkBuilder spins up a number of fully connected in-process base nodes that share the same consensus
+/// rules. Every node uses a memory database and the in-memory transport.
+pub struct TestNetworkBuilder {
+    network: Network,
+    num_nodes: usize,
+    base_node_service_config: BaseNodeServiceConfig,
+    mmr_cache_config: MmrCacheConfig,
+    mempool_service_config: MempoolServiceConfig,
+    liveness_service_config: LivenessConfig,
+    consensus_manager: Option<ConsensusManager>,
+}
+
+impl TestNetworkBuilder {
+    /// Create a new TestNetworkBuilder for a network of two nodes
+    pub fn new(network: Network) -> Self {
+        Self {
+            network,
+            num_nodes: 2,
+            base_node_service_config: BaseNodeServiceConfig::default(),
+            mmr_cache_config: MmrCacheConfig { rewind_hist_len: 10 },
+            mempool_service_config: MempoolServiceConfig::default(),
+            liveness_service_config: LivenessConfig::default(),
+            consensus_manager: None,
+        }
+    }
+
+    /// Set the number of base nodes in the network.
+    pub fn with_num_nodes(mut self, num_nodes: usize) -> Self {
+        self.num_nodes = num_nodes;
+        self
+    }
+
+    /// Set the configuration of the base node service used by every node.
+    pub fn with_base_node_service_config(mut self, config: BaseNodeServiceConfig) -> Self {
+        self.base_node_service_config = config;
+        self
+    }
+
+    /// Set the MMR cache configuration used by every node.
+    pub fn with_mmr_cache_config(mut self, config: MmrCacheConfig) -> Self {
+        self.mmr_cache_config = config;
+        self
+    }
+
+    /// Set the configuration of the mempool service used by every node.
+    pub fn with_mempool_service_config(mut self, config: MempoolServiceConfig) -> Self {
+        self.mempool_service_config = config;
+        self
+    }
+
+    /// Set the configuration of the liveness service used by every node.
+    pub fn with_liveness_service_config(mut self, config: LivenessConfig) -> Self {
+        self.liveness_service_config = config;
+        self
+    }
+
+    /// Set the consensus manager shared by every node. If not specified the default rules for the network are used.
+    pub fn with_consensus_manager(mut self, consensus_manager: ConsensusManager) -> Self {
+        self.consensus_manager = Some(consensus_manager);
+        self
+    }
+
+    /// Start all the base nodes and wait until every node is connected to every other node.
+    pub fn build(self, runtime: &mut Runtime, data_path: &str) -> TestNetwork {
+        let node_identities = (0..self.num_nodes).map(|_| random_node_identity()).collect::<Vec<_>>();
+        let mut consensus_manager = self
+            .consensus_manager
+            .unwrap_or(ConsensusManagerBuilder::new(self.network).build());
+
+        let mut nodes = Vec::with_capacity(self.num_nodes);
+        for (i, node_identity) in node_identities.iter().enumerate() {
+            // Each node only knows about the nodes started after it, which avoids simultaneous dials between a pair
+            let (node, rules) = BaseNodeBuilder::new(self.network)
+                .with_node_identity(node_identity.clone())
+                .with_peers(node_identities[i + 1..].to_vec())
+                .with_base_node_service_config(self.base_node_service_config)
+                .with_mmr_cache_config(self.mmr_cache_config)
+                .with_mempool_service_config(self.mempool_service_config)
+                .with_liveness_service_config(self.liveness_service_config)
+                .with_consensus_manager(consensus_manager)
+                .start(runtime, data_path);
+            consensus_manager = rules;
+            nodes.push(node);
+        }
+
+        let network = TestNetwork {
+            nodes,
+            consensus_manager,
+        };
+        runtime.block_on(network.connect_all());
+        network
+    }
+}
+
+/// A running network of base nodes created by the [TestNetworkBuilder].
+pub struct TestNetwork {
+    pub nodes: Vec<NodeInterfaces>,
+    pub consensus_manager: ConsensusManager,
+}
+
+impl TestNetwork {
+    /// Returns the node at the given index
+    pub fn node(&self, index: usize) -> &NodeInterfaces {
+        &self.nodes[index]
+    }
+
+    /// Returns a mutable reference to the node at the given index
+    pub fn node_mut(&mut self, index: usize) -> &mut NodeInterfaces {
+        &mut self.nodes[index]
+    }
+
+    /// Mine `num_blocks` empty blocks on top of the tip of the given node. The blocks are submitted through the local
+    /// node interface so that they are propagated to the rest of the network.
+    pub async fn mine_blocks(&mut self, index: usize, num_blocks: usize) -> Vec<Block> {
+        let constants = self.consensus_manager.consensus_constants();
+        let node = &mut self.nodes[index];
+        let mut blocks = Vec::with_capacity(num_blocks);
+        for _ in 0..num_blocks {
+            let height = node.blockchain_db.get_height().unwrap().unwrap_or(0);
+            let tip = node.blockchain_db.fetch_block(height).unwrap().block().clone();
+            let block = node
+                .blockchain_db
+                .calculate_mmr_roots(chain_block(&tip, vec![], &constants))
+                .unwrap();
+            node.local_nci.submit_block(block.clone()).await.unwrap();
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    /// Split the network into the given groups of node indexes. Nodes in different groups ban each other and any
+    /// existing connections between them are closed. Nodes that do not appear in any group are left untouched.
+    pub async fn partition(&self, groups: &[&[usize]]) {
+        for (i, group) in groups.iter().enumerate() {
+            for other_group in groups.iter().skip(i + 1) {
+                for a in group.iter() {
+                    for b in other_group.iter() {
+                        self.set_link_banned(*a, *b, true).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove all partitions and reconnect every node to every other node.
+    pub async fn heal(&self) {
+        for a in 0..self.nodes.len() {
+            for b in a + 1..self.nodes.len() {
+                self.set_link_banned(a, b, false).await;
+            }
+        }
+        self.connect_all().await;
+    }
+
+    /// Returns the chain metadata of every node in the network.
+    pub async fn chain_metadata(&mut self) -> Vec<ChainMetadata> {
+        future::join_all(self.nodes.iter_mut().map(|node| node.local_nci.get_metadata()))
+            .await
+            .into_iter()
+            .map(|result| result.unwrap())
+            .collect()
+    }
+
+    /// Wait until all the nodes in the network agree on the best block. Returns false if the nodes have not converged
+    /// within the given timeout.
+    pub async fn await_convergence(&mut self, timeout: Duration) -> bool {
+        let indexes = (0..self.nodes.len()).collect::<Vec<_>>();
+        self.await_convergence_of(&indexes, timeout).await
+    }
+
+    /// Wait until the given subset of nodes agree on the best block. Returns false if the nodes have not converged
+    /// within the given timeout.
+    pub async fn await_convergence_of(&mut self, indexes: &[usize], timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let metadata = self.chain_metadata().await;
+            let mut best_blocks = indexes.iter().map(|i| &metadata[*i].best_block);
+            let first = best_blocks.next();
+            if best_blocks.all(|best_block| Some(best_block) == first) {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::delay_for(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Shut down the comms stacks of all the nodes.
+    pub async fn shutdown(self) {
+        future::join_all(self.nodes.into_iter().map(|node| node.comms.shutdown())).await;
+    }
+
+    async fn connect_all(&self) {
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut conn_man = node.comms.connection_manager();
+            for peer in &self.nodes[i + 1..] {
+                let _ = conn_man.dial_peer(peer.node_identity.node_id().clone()).await;
+            }
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            for peer in &self.nodes[i + 1..] {
+                async_assert_eventually!(
+                    peer.comms
+                        .connection_manager()
+                        .get_active_connection(node.node_identity.node_id().clone())
+                        .await
+                        .unwrap()
+                        .is_some(),
+                    expect = true,
+                    max_attempts = 20,
+                    interval = Duration::from_millis(1000)
+                );
+            }
+        }
+    }
+
+    async fn set_link_banned(&self, a: usize, b: usize, is_banned: bool) {
+        let (node_a, node_b) = (&self.nodes[a], &self.nodes[b]);
+        node_a
+            .comms
+            .peer_manager()
+            .set_banned(node_b.node_identity.public_key(), is_banned)
+            .await
+            .unwrap();
+        node_b
+            .comms
+            .peer_manager()
+            .set_banned(node_a.node_identity.public_key(), is_banned)
+            .await
+            .unwrap();
+        if is_banned {
+            let _ = node_a
+                .comms
+                .connection_manager()
+                .disconnect_peer(node_b.node_identity.node_id().clone())
+                .await;
+            let _ = node_b
+                .comms
+                .connection_manager()
+                .disconnect_peer(node_a.node_identity.node_id().clone())
+                .await;
+        }
+    }
+}
@@ -46,14 +46,17 @@ use tari_core::{
         MempoolConfig,
         MempoolServiceConfig,
         MempoolServiceError,
+        MempoolTxFilter,
         MempoolValidators,
+        TxSortOrder,
         TxStorageResponse,
+        ValidationComponent,
     },
     proof_of_work::Difficulty,
     transactions::{
         helpers::{schema_to_transaction, spend_utxos},
         proto,
-        tari_amount::{uT, T},
+        tari_amount::{uT, MicroTari, T},
         transaction::{OutputFeatures, Transaction},
         types::CryptoFactories,
     },
@@ -222,6 +225,158 @@ fn test_insert_and_process_published_block() {
     assert_eq!(stats.total_weight, 36);
 }
 
+#[test]
+fn test_validate_transaction() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = MempoolValidators::new(TxInputAndMaturityValidator {}, TxInputAndMaturityValidator {});
+    let mempool = Mempool::new(store.clone(), MempoolConfig::default(), mempool_validator);
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![2 * T, 2 * T, 2 * T, 2 * T]
+    )];
+    generate_new_block(
+        &mut store,
+        &mut blocks,
+        &mut outputs,
+        txs,
+        &consensus_manager.consensus_constants(),
+    )
+    .unwrap();
+
+    let tx1 = txn_schema!(from: vec![outputs[1][0].clone()], to: vec![1*T], fee: 20*uT);
+    let tx1 = Arc::new(spend_utxos(tx1).0);
+    let report = mempool.validate_transaction(tx1.clone()).unwrap();
+    assert!(report.is_accepted());
+    assert_eq!(report.storage, TxStorageResponse::UnconfirmedPool);
+    assert_eq!(report.fee, 20 * uT);
+    assert_eq!(report.weight, tx1.calculate_weight());
+    assert_eq!(report.validation_error, None);
+    assert_eq!(report.relay_rejection, None);
+    assert!(report.conflicts.is_empty());
+    // Validating does not store the transaction
+    assert_eq!(
+        mempool
+            .has_tx_with_excess_sig(tx1.body.kernels()[0].excess_sig.clone())
+            .unwrap(),
+        TxStorageResponse::NotStored
+    );
+
+    mempool.insert(tx1.clone()).unwrap();
+    let double_spend = txn_schema!(from: vec![outputs[1][0].clone()], to: vec![1*T], fee: 25*uT);
+    let double_spend = Arc::new(spend_utxos(double_spend).0);
+    let report = mempool.validate_transaction(double_spend).unwrap();
+    assert_eq!(report.conflicts, vec![tx1.body.kernels()[0].excess_sig.clone()]);
+
+    let time_locked = txn_schema!(
+        from: vec![outputs[1][1].clone()],
+        to: vec![1*T],
+        fee: 20*uT,
+        lock: 4,
+        OutputFeatures::default()
+    );
+    let time_locked = Arc::new(spend_utxos(time_locked).0);
+    let report = mempool.validate_transaction(time_locked).unwrap();
+    assert_eq!(report.storage, TxStorageResponse::PendingPool);
+    assert!(report.has_maturity_issue());
+    assert_eq!(report.validation_error.unwrap().code, 320);
+
+    let (orphan, _, _) = tx!(1*T, fee: 100*uT);
+    let orphan_input = orphan.body.inputs()[0].commitment.clone();
+    let report = mempool.validate_transaction(Arc::new(orphan)).unwrap();
+    assert_eq!(report.storage, TxStorageResponse::OrphanPool);
+    let rejection = report.validation_error.unwrap();
+    assert_eq!(rejection.code, 321);
+    assert_eq!(rejection.component, Some(ValidationComponent::Input(orphan_input)));
+
+    assert_eq!(mempool.stats().unwrap().total_txs, 1);
+}
+
+#[test]
+fn test_list_and_get_transactions() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = MempoolValidators::new(TxInputAndMaturityValidator {}, TxInputAndMaturityValidator {});
+    let mempool = Mempool::new(store.clone(), MempoolConfig::default(), mempool_validator);
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![2 * T, 2 * T, 2 * T, 2 * T]
+    )];
+    generate_new_block(
+        &mut store,
+        &mut blocks,
+        &mut outputs,
+        txs,
+        &consensus_manager.consensus_constants(),
+    )
+    .unwrap();
+
+    let low_fee = txn_schema!(from: vec![outputs[1][0].clone()], to: vec![1*T], fee: 20*uT);
+    let low_fee = Arc::new(spend_utxos(low_fee).0);
+    let high_fee = txn_schema!(from: vec![outputs[1][1].clone()], to: vec![1*T], fee: 500*uT);
+    let high_fee = Arc::new(spend_utxos(high_fee).0);
+    let time_locked = txn_schema!(
+        from: vec![outputs[1][2].clone()],
+        to: vec![1*T],
+        fee: 100*uT,
+        lock: 4,
+        OutputFeatures::default()
+    );
+    let time_locked = Arc::new(spend_utxos(time_locked).0);
+    mempool.insert(low_fee.clone()).unwrap();
+    mempool.insert(high_fee.clone()).unwrap();
+    mempool.insert(time_locked.clone()).unwrap();
+
+    let txs = mempool.list_transactions(MempoolTxFilter::default()).unwrap();
+    assert_eq!(txs.len(), 3);
+    assert_eq!(txs[0].excess_sig, high_fee.body.kernels()[0].excess_sig);
+    assert_eq!(txs[0].fee, 500 * uT);
+    assert_eq!(txs[0].storage, TxStorageResponse::UnconfirmedPool);
+    assert_eq!(txs[2].excess_sig, low_fee.body.kernels()[0].excess_sig);
+    assert!(txs.iter().all(|tx| tx.transaction.is_none()));
+    assert!(txs.iter().any(|tx| tx.storage == TxStorageResponse::PendingPool));
+
+    let filter = MempoolTxFilter {
+        min_fee_per_gram: MicroTari(high_fee.calculate_ave_fee_per_gram().floor() as u64),
+        sort: TxSortOrder::Age,
+        include_transactions: true,
+        ..Default::default()
+    };
+    let txs = mempool.list_transactions(filter).unwrap();
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].transaction, Some(high_fee.deref().clone()));
+
+    let filter = MempoolTxFilter {
+        limit: Some(2),
+        ..Default::default()
+    };
+    let page = mempool.list_transactions(filter).unwrap();
+    assert_eq!(page.len(), 2);
+    assert_eq!(page[0].excess_sig, high_fee.body.kernels()[0].excess_sig);
+    let filter = MempoolTxFilter {
+        after: Some(page[1].excess_sig.clone()),
+        limit: Some(2),
+        ..Default::default()
+    };
+    let page = mempool.list_transactions(filter).unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].excess_sig, low_fee.body.kernels()[0].excess_sig);
+
+    let tx = mempool
+        .get_transaction(time_locked.body.kernels()[0].excess_sig.clone())
+        .unwrap()
+        .unwrap();
+    assert_eq!(tx.storage, TxStorageResponse::PendingPool);
+    assert_eq!(tx.transaction, Some(time_locked.deref().clone()));
+    let (unknown, _, _) = tx!(1*T, fee: 100*uT);
+    assert_eq!(
+        mempool
+            .get_transaction(unknown.body.kernels()[0].excess_sig.clone())
+            .unwrap(),
+        None
+    );
+}
+
 #[test]
 fn test_retrieve() {
     let network = Network::LocalNet;
@@ -33,6 +33,7 @@ use helpers::{
         generate_block,
     },
     event_stream::event_stream_next,
+    network::TestNetworkBuilder,
     nodes::{
         create_network_with_2_base_nodes,
         create_network_with_2_base_nodes_with_config,
@@ -582,6 +583,7 @@ fn service_request_timeout() {
     let base_node_service_config = BaseNodeServiceConfig {
         request_timeout: Duration::from_millis(1),
         desired_response_fraction: BASE_NODE_SERVICE_DESIRED_RESPONSE_FRACTION,
+        ..Default::default()
     };
     let temp_dir = TempDir::new(string(8).as_str()).unwrap();
     let (mut alice_node, bob_node, _consensus_manager) = create_network_with_2_base_nodes_with_config(
@@ -742,3 +744,54 @@ fn local_submit_block() {
         node.comms.shutdown().await;
     });
 }
+
+#[test]
+fn test_network_mined_blocks_converge() {
+    let mut runtime = Runtime::new().unwrap();
+    let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+    let mut network = TestNetworkBuilder::new(Network::LocalNet)
+        .with_num_nodes(3)
+        .build(&mut runtime, temp_dir.path().to_str().unwrap());
+
+    runtime.block_on(async {
+        let blocks = network.mine_blocks(0, 2).await;
+        assert!(network.await_convergence(Duration::from_secs(20)).await);
+        for metadata in network.chain_metadata().await {
+            assert_eq!(metadata.height_of_longest_chain, Some(2));
+            assert_eq!(metadata.best_block, Some(blocks[1].hash()));
+        }
+
+        network.shutdown().await;
+    });
+}
+
+#[test]
+fn test_network_partition() {
+    let mut runtime = Runtime::new().unwrap();
+    let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+    let mut network = TestNetworkBuilder::new(Network::LocalNet)
+        .with_num_nodes(3)
+        .build(&mut runtime, temp_dir.path().to_str().unwrap());
+
+    runtime.block_on(async {
+        network.partition(&[&[0], &[1, 2]]).await;
+        network.mine_blocks(1, 1).await;
+        assert!(network.await_convergence_of(&[1, 2], Duration::from_secs(20)).await);
+        assert!(!network.await_convergence(Duration::from_secs(2)).await);
+        assert_eq!(network.node(0).blockchain_db.get_height(), Ok(Some(0)));
+
+        // Healing the partition reconnects the isolated node
+        network.heal().await;
+        let node_id = network.node(1).node_identity.node_id().clone();
+        let conn = network
+            .node(0)
+            .comms
+            .connection_manager()
+            .get_active_connection(node_id)
+            .await
+            .unwrap();
+        assert!(conn.is_some());
+
+        network.shutdown().await;
+    });
+}
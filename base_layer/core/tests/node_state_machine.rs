@@ -29,7 +29,7 @@ use helpers::{
         append_block,
         chain_block,
         chain_block_with_coinbase,
-        create_coinbase,
+        create_coinbase_with_builder,
         create_genesis_block,
         find_header_with_achieved_difficulty,
     },
@@ -42,16 +42,18 @@ use helpers::{
     },
 };
 use rand::{rngs::OsRng, RngCore};
-use std::{thread, time::Duration};
+use std::{sync::atomic::Ordering, thread, time::Duration};
 use tari_core::{
     base_node::{
         chain_metadata_service::PeerChainMetadata,
         service::BaseNodeServiceConfig,
         states::{
+            BaseNodeState,
             BestChainMetadataBlockSyncInfo,
             BlockSyncConfig,
             ListeningInfo,
             StateEvent,
+            SyncPhase,
             SyncStatus,
             SyncStatus::Lagging,
         },
@@ -92,7 +94,6 @@ fn test_listening_lagging() {
         MempoolServiceConfig::default(),
         LivenessConfig {
             enable_auto_join: false,
-            enable_auto_stored_message_request: false,
             auto_ping_interval: Some(Duration::from_millis(100)),
             refresh_neighbours_interval: Duration::from_secs(60),
         },
@@ -246,6 +247,8 @@ fn test_block_sync() {
         shutdown.to_signal(),
     );
 
+    let sync_progress = alice_state_machine.get_sync_progress_handle();
+
     runtime.block_on(async {
         let alice_db = &alice_node.blockchain_db;
         let bob_db = &bob_node.blockchain_db;
@@ -273,6 +276,98 @@ fn test_block_sync() {
             assert_eq!(alice_db.fetch_block(height), bob_db.fetch_block(height));
         }
 
+        let progress = sync_progress.get_progress();
+        assert_eq!(progress.phase, SyncPhase::DownloadingBlocks);
+        assert_eq!(progress.blocks_done, 5);
+        assert_eq!(progress.blocks_total, 5);
+
+        alice_node.comms.shutdown().await;
+        bob_node.comms.shutdown().await;
+    });
+}
+
+#[test]
+fn test_paused_block_sync() {
+    let mut runtime = Runtime::new().unwrap();
+    let factories = CryptoFactories::default();
+    let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+    let network = Network::LocalNet;
+    let consensus_constants = ConsensusConstantsBuilder::new(network)
+        .with_emission_amounts(100_000_000.into(), 0.999, 100.into())
+        .build();
+    let (mut prev_block, _) = create_genesis_block(&factories, &consensus_constants);
+    let consensus_manager = ConsensusManagerBuilder::new(network)
+        .with_consensus_constants(consensus_constants)
+        .with_block(prev_block.clone())
+        .build();
+    let (alice_node, bob_node, consensus_manager) = create_network_with_2_base_nodes_with_config(
+        &mut runtime,
+        BaseNodeServiceConfig::default(),
+        MmrCacheConfig::default(),
+        MempoolServiceConfig::default(),
+        LivenessConfig::default(),
+        consensus_manager,
+        temp_dir.path().to_str().unwrap(),
+    );
+    let shutdown = Shutdown::new();
+    let mut alice_state_machine = BaseNodeStateMachine::new(
+        &alice_node.blockchain_db,
+        &alice_node.outbound_nci,
+        alice_node.comms.peer_manager(),
+        alice_node.comms.connection_manager(),
+        alice_node.chain_metadata_handle.get_event_stream(),
+        BaseNodeStateMachineConfig::default(),
+        shutdown.to_signal(),
+    );
+    let pause_flag = alice_state_machine.get_block_sync_pause_flag();
+    pause_flag.store(true, Ordering::Relaxed);
+
+    runtime.block_on(async {
+        let alice_db = &alice_node.blockchain_db;
+        let bob_db = &bob_node.blockchain_db;
+        for _ in 1..4 {
+            prev_block = append_block(
+                bob_db,
+                &prev_block,
+                vec![],
+                &consensus_manager.consensus_constants(),
+                1.into(),
+            )
+            .unwrap();
+        }
+
+        let network_tip = bob_db.get_metadata().unwrap();
+        let sync_peers = vec![bob_node.node_identity.node_id().clone()];
+
+        // A paused node stays in the listening state when it falls behind
+        let state = alice_state_machine.transition(
+            BaseNodeState::Listening(ListeningInfo),
+            StateEvent::FallenBehind(Lagging(network_tip.clone(), sync_peers.clone())),
+        );
+        assert_eq!(state, BaseNodeState::Listening(ListeningInfo));
+
+        // A block sync in progress is abandoned without downloading any blocks
+        let state_event = BestChainMetadataBlockSyncInfo {}
+            .next_event(&mut alice_state_machine, &network_tip, &mut sync_peers.clone())
+            .await;
+        assert_eq!(state_event, StateEvent::BlockSyncFailure);
+        assert_eq!(alice_db.get_height(), Ok(Some(0)));
+
+        pause_flag.store(false, Ordering::Relaxed);
+        let state = alice_state_machine.transition(
+            BaseNodeState::Listening(ListeningInfo),
+            StateEvent::FallenBehind(Lagging(network_tip.clone(), sync_peers.clone())),
+        );
+        match state {
+            BaseNodeState::BlockSync(_, _, _) => assert!(true),
+            _ => assert!(false),
+        }
+        let state_event = BestChainMetadataBlockSyncInfo {}
+            .next_event(&mut alice_state_machine, &network_tip, &mut sync_peers.clone())
+            .await;
+        assert_eq!(state_event, StateEvent::BlocksSynchronized);
+        assert_eq!(alice_db.get_height(), bob_db.get_height());
+
         alice_node.comms.shutdown().await;
         bob_node.comms.shutdown().await;
     });
@@ -670,12 +765,8 @@ fn test_sync_peer_banning() {
         let bob_db = &bob_node.blockchain_db;
         let bob_public_key = &bob_node.node_identity.public_key();
         for height in 1..=2 {
-            let coinbase_value = consensus_manager.emission_schedule().block_reward(height);
-            let (coinbase_utxo, coinbase_kernel, _) = create_coinbase(
-                &factories,
-                coinbase_value,
-                height + consensus_manager.consensus_constants().coinbase_lock_height(),
-            );
+            let (coinbase_utxo, coinbase_kernel, _) =
+                create_coinbase_with_builder(&factories, &consensus_manager, height, Vec::new());
             let template = chain_block_with_coinbase(
                 &prev_block,
                 vec![],
@@ -698,12 +789,8 @@ fn test_sync_peer_banning() {
         // Alice fork
         let mut alice_prev_block = prev_block.clone();
         for height in 3..=4 {
-            let coinbase_value = consensus_manager.emission_schedule().block_reward(height);
-            let (coinbase_utxo, coinbase_kernel, _) = create_coinbase(
-                &factories,
-                coinbase_value,
-                height + consensus_manager.consensus_constants().coinbase_lock_height(),
-            );
+            let (coinbase_utxo, coinbase_kernel, _) =
+                create_coinbase_with_builder(&factories, &consensus_manager, height, Vec::new());
             let template = chain_block_with_coinbase(
                 &alice_prev_block,
                 vec![],
@@ -129,6 +129,7 @@ fn wallet_base_node_integration_test() {
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        peer_allow_list: None,
     };
     let alice_wallet_config = WalletConfig {
         comms_config: alice_comms_config,
@@ -138,6 +139,8 @@ fn wallet_base_node_integration_test() {
             base_node_mined_timeout: Duration::from_secs(1),
             ..Default::default()
         }),
+        output_manager_service_config: None,
+        locale_config: None,
     };
     let alice_runtime = create_runtime();
     let mut alice_wallet = Wallet::new(
@@ -179,11 +182,14 @@ fn wallet_base_node_integration_test() {
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        peer_allow_list: None,
     };
     let bob_wallet_config = WalletConfig {
         comms_config: bob_comms_config,
         factories: factories.clone(),
         transaction_service_config: None,
+        output_manager_service_config: None,
+        locale_config: None,
     };
     let bob_runtime = create_runtime();
     let mut bob_wallet = Wallet::new(
@@ -31,6 +31,8 @@ use tari_crypto::{
     keys::SecretKey,
     tari_utilities::{byte_array::ByteArrayError, hex::Hex},
 };
+use tari_secret::Secret;
+use zeroize::Zeroize;
 
 #[derive(Debug, Error, PartialEq)]
 pub enum KeyManagerError {
@@ -50,7 +52,7 @@ where K: SecretKey
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct KeyManager<K: SecretKey, D: Digest> {
-    pub master_key: K,
+    pub master_key: Secret<K>,
     pub branch_seed: String,
     pub primary_key_index: usize,
     digest_type: PhantomData<D>,
@@ -64,7 +66,7 @@ where
     /// Creates a new KeyManager with a new randomly selected master_key
     pub fn new<R: CryptoRng + Rng>(rng: &mut R) -> KeyManager<K, D> {
         KeyManager {
-            master_key: SecretKey::random(rng),
+            master_key: Secret::new(SecretKey::random(rng)),
             branch_seed: "".to_string(),
             primary_key_index: 0,
             digest_type: PhantomData,
@@ -72,7 +74,7 @@ where
     }
 
     /// Constructs a KeyManager from known parts
-    pub fn from(master_key: K, branch_seed: String, primary_key_index: usize) -> KeyManager<K, D> {
+    pub fn from(master_key: Secret<K>, branch_seed: String, primary_key_index: usize) -> KeyManager<K, D> {
         KeyManager {
             master_key,
             branch_seed,
@@ -88,9 +90,14 @@ where
         primary_key_index: usize,
     ) -> Result<KeyManager<K, D>, KeyManagerError>
     {
-        match K::from_bytes(D::digest(&seed_phrase.into_bytes()).as_slice()) {
+        let mut seed_phrase = seed_phrase.into_bytes();
+        let mut digest = D::digest(&seed_phrase);
+        seed_phrase.zeroize();
+        let master_key = K::from_bytes(digest.as_slice());
+        digest.as_mut_slice().zeroize();
+        match master_key {
             Ok(master_key) => Ok(KeyManager {
-                master_key,
+                master_key: Secret::new(master_key),
                 branch_seed,
                 primary_key_index,
                 digest_type: PhantomData,
@@ -109,7 +116,7 @@ where
     {
         match K::from_mnemonic(mnemonic_seq) {
             Ok(master_key) => Ok(KeyManager {
-                master_key,
+                master_key: Secret::new(master_key),
                 branch_seed,
                 primary_key_index,
                 digest_type: PhantomData,
@@ -118,13 +125,15 @@ where
         }
     }
 
-    /// Derive a new private key from master key: derived_key=SHA256(master_key||branch_seed||index)
+    /// Derive a new private key from master key: derived_key=SHA256(master_key||branch_seed||index). The intermediate
+    /// buffers holding the master key and the digest are scrubbed before returning.
     pub fn derive_key(&self, key_index: usize) -> Result<DerivedKey<K>, ByteArrayError> {
-        let concatenated = format!("{}{}", self.master_key.to_hex(), key_index.to_string());
-        match K::from_bytes(D::digest(&concatenated.into_bytes()).as_slice()) {
-            Ok(k) => Ok(DerivedKey { k, key_index }),
-            Err(e) => Err(e),
-        }
+        let mut concatenated = format!("{}{}", self.master_key.to_hex(), key_index.to_string());
+        let mut digest = D::digest(concatenated.as_bytes());
+        concatenated.zeroize();
+        let k = K::from_bytes(digest.as_slice());
+        digest.as_mut_slice().zeroize();
+        Ok(DerivedKey { k: k?, key_index })
     }
 
     /// Generate next deterministic private key derived from master key
@@ -185,6 +185,7 @@ mod pingpong {
             allow_test_addresses: true,
             listener_liveness_whitelist_cidrs: Vec::new(),
             listener_liveness_max_sessions: 0,
+            peer_allow_list: None,
         };
 
         let (comms, dht) = rt.block_on(initialize_comms(comms_config, publisher)).unwrap();
@@ -202,7 +203,6 @@ mod pingpong {
                     .add_initializer(LivenessInitializer::new(
                         LivenessConfig {
                             auto_ping_interval: None, // Some(Duration::from_secs(5)),
-                            enable_auto_stored_message_request: false,
                             enable_auto_join: true,
                             ..Default::default()
                         },
@@ -88,6 +88,7 @@ impl<TSink> InboundDomainConnector<TSink> {
         let DecryptedDhtMessage {
             source_peer,
             dht_header,
+            reply_block,
             ..
         } = inbound_message;
 
@@ -96,6 +97,7 @@ impl<TSink> InboundDomainConnector<TSink> {
             source_peer: Clone::clone(&*source_peer),
             dht_header,
             body: msg_bytes,
+            reply_block,
         };
 
         Ok(peer_message)
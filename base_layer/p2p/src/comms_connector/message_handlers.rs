@@ -0,0 +1,313 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Registration of handlers for application-defined message types.
+//!
+//! Message types from [CUSTOM_MESSAGE_TYPE_START] upwards are never assigned to a `TariMessageType`, so applications
+//! built on the Tari network (asset layers, chat experiments etc.) can use them for their own messages. An application
+//! registers a handler for a range of these types with a [MessageHandlerRegistry] and passes the registry to
+//! [pubsub_connector_with_handlers](super::pubsub_connector_with_handlers). Messages of a registered type are decoded
+//! and sent to the handler instead of the domain subscriptions. Outbound messages of a custom type are sent as usual
+//! with an `OutboundDomainMessage` that uses the `i32` message type.
+
+use super::peer_message::PeerMessage;
+use crate::domain_message::DomainMessage;
+use derive_error::Error;
+use futures::channel::mpsc;
+use log::*;
+use std::{ops::RangeInclusive, sync::Arc};
+use tari_comms_dht::envelope::DhtMessageFlags;
+
+const LOG_TARGET: &str = "comms::middleware::message_handlers";
+
+/// The first message type available to applications. All message types below this are reserved for `TariMessageType`.
+pub const CUSTOM_MESSAGE_TYPE_START: i32 = 256;
+
+#[derive(Debug, Error)]
+pub enum MessageHandlerError {
+    /// The message type range includes types reserved for `TariMessageType`
+    ReservedMessageType,
+    /// The message type range is empty
+    EmptyMessageTypeRange,
+    #[error(msg_embedded, no_from, non_std)]
+    OverlappingMessageTypes(String),
+}
+
+/// Filters applied to messages before they are decoded for a handler
+#[derive(Debug, Clone)]
+pub struct MessageHandlerOptions {
+    /// Discard messages that were not encrypted for this node
+    pub require_encryption: bool,
+    /// Discard messages that were forwarded by another peer instead of being sent by the originator
+    pub reject_forwarded: bool,
+    /// Discard messages with a body larger than this many bytes
+    pub max_body_size: Option<usize>,
+    /// The number of decoded messages buffered for the handler. Messages received while the buffer is full are
+    /// discarded so that a slow handler cannot hold up the domain subscriptions.
+    pub buffer_size: usize,
+}
+
+impl Default for MessageHandlerOptions {
+    fn default() -> Self {
+        Self {
+            require_encryption: false,
+            reject_forwarded: false,
+            max_body_size: None,
+            buffer_size: 100,
+        }
+    }
+}
+
+type DispatchFn = Box<dyn FnMut(Arc<PeerMessage>) + Send>;
+
+struct RegisteredHandler {
+    message_types: RangeInclusive<i32>,
+    options: MessageHandlerOptions,
+    dispatch: DispatchFn,
+}
+
+impl RegisteredHandler {
+    /// Returns the reason the message is rejected by the handler options, if it is
+    fn rejection_reason(&self, message: &PeerMessage) -> Option<&'static str> {
+        if self.options.require_encryption && !message.dht_header.flags.contains(DhtMessageFlags::ENCRYPTED) {
+            return Some("message was not encrypted");
+        }
+        if self.options.reject_forwarded && message.origin_public_key() != &message.source_peer.public_key {
+            return Some("message was forwarded");
+        }
+        if let Some(max_body_size) = self.options.max_body_size {
+            if message.body.len() > max_body_size {
+                return Some("message body is too large");
+            }
+        }
+        None
+    }
+}
+
+/// The handlers registered for application-defined message types
+#[derive(Default)]
+pub struct MessageHandlerRegistry {
+    handlers: Vec<RegisteredHandler>,
+}
+
+impl MessageHandlerRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Register a handler for a range of message types. Message bodies are converted with the given decoder, messages
+    /// which fail to decode are discarded. Returns the receiver for the decoded messages.
+    pub fn register<T, D>(
+        &mut self,
+        message_types: RangeInclusive<i32>,
+        options: MessageHandlerOptions,
+        decoder: D,
+    ) -> Result<mpsc::Receiver<DomainMessage<T>>, MessageHandlerError>
+    where
+        T: Send + 'static,
+        D: Fn(&[u8]) -> Result<T, String> + Send + 'static,
+    {
+        if message_types.start() > message_types.end() {
+            return Err(MessageHandlerError::EmptyMessageTypeRange);
+        }
+        if *message_types.start() < CUSTOM_MESSAGE_TYPE_START {
+            return Err(MessageHandlerError::ReservedMessageType);
+        }
+        if let Some(existing) = self
+            .handlers
+            .iter()
+            .find(|h| h.message_types.start() <= message_types.end() && message_types.start() <= h.message_types.end())
+        {
+            return Err(MessageHandlerError::OverlappingMessageTypes(format!(
+                "Message types {:?} overlap the registered message types {:?}",
+                message_types, existing.message_types
+            )));
+        }
+
+        let (mut sender, receiver) = mpsc::channel(options.buffer_size);
+        let dispatch = move |message: Arc<PeerMessage>| {
+            let inner = match decoder(&message.body) {
+                Ok(inner) => inner,
+                Err(err) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Discarding message of type {} from peer '{}' because it could not be decoded: {}",
+                        message.message_header.message_type,
+                        message.source_peer.node_id.short_str(),
+                        err
+                    );
+                    return;
+                },
+            };
+            let domain_message = DomainMessage {
+                source_peer: message.source_peer.clone(),
+                dht_header: message.dht_header.clone(),
+                reply_block: message.reply_block.clone(),
+                inner,
+            };
+            if let Err(err) = sender.try_send(domain_message) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Discarding message of type {} because the handler is not accepting messages: {}",
+                    message.message_header.message_type,
+                    err
+                );
+            }
+        };
+
+        self.handlers.push(RegisteredHandler {
+            message_types,
+            options,
+            dispatch: Box::new(dispatch),
+        });
+        Ok(receiver)
+    }
+
+    /// Register a handler for a range of message types with protobuf encoded bodies
+    pub fn register_proto<T>(
+        &mut self,
+        message_types: RangeInclusive<i32>,
+        options: MessageHandlerOptions,
+    ) -> Result<mpsc::Receiver<DomainMessage<T>>, MessageHandlerError>
+    where
+        T: prost::Message + Default + Send + 'static,
+    {
+        self.register(message_types, options, |body| {
+            T::decode(body).map_err(|err| err.to_string())
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// Pass the message to the handler registered for its message type. Returns false if there is no handler for the
+    /// message type, in which case the message should be published to the domain subscriptions.
+    pub(crate) fn dispatch(&mut self, message: Arc<PeerMessage>) -> bool {
+        let message_type = message.message_header.message_type;
+        let handler = match self
+            .handlers
+            .iter_mut()
+            .find(|h| h.message_types.contains(&message_type))
+        {
+            Some(handler) => handler,
+            None => return false,
+        };
+        match handler.rejection_reason(&message) {
+            Some(reason) => debug!(
+                target: LOG_TARGET,
+                "Discarding message of type {} from peer '{}' because the {}",
+                message_type,
+                message.source_peer.node_id.short_str(),
+                reason
+            ),
+            None => (handler.dispatch)(message),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        proto::liveness::PingPongMessage,
+        test_utils::{make_dht_header, make_node_identity},
+    };
+    use futures::StreamExt;
+    use prost::Message;
+    use tari_comms::peer_manager::{Peer, PeerFeatures, PeerFlags};
+    use tari_comms_dht::domain_message::MessageHeader;
+
+    fn make_peer_message(message_type: i32, body: Vec<u8>, flags: DhtMessageFlags) -> Arc<PeerMessage> {
+        let node_identity = make_node_identity();
+        let peer = Peer::new(
+            node_identity.public_key().clone(),
+            node_identity.node_id().clone(),
+            Vec::<tari_comms::multiaddr::Multiaddr>::new().into(),
+            PeerFlags::empty(),
+            PeerFeatures::COMMUNICATION_NODE,
+            &[],
+        );
+        Arc::new(PeerMessage::new(
+            make_dht_header(&node_identity, &body, flags),
+            peer,
+            MessageHeader::new(message_type),
+            body,
+        ))
+    }
+
+    fn encode(nonce: u64) -> Vec<u8> {
+        let msg = PingPongMessage {
+            nonce,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        msg.encode(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn register_rejects_invalid_ranges() {
+        let mut registry = MessageHandlerRegistry::new();
+        match registry.register_proto::<PingPongMessage>(1..=300, Default::default()) {
+            Err(MessageHandlerError::ReservedMessageType) => {},
+            _ => panic!("Expected a reserved message type error"),
+        }
+
+        registry
+            .register_proto::<PingPongMessage>(300..=310, Default::default())
+            .unwrap();
+        match registry.register_proto::<PingPongMessage>(310..=320, Default::default()) {
+            Err(MessageHandlerError::OverlappingMessageTypes(_)) => {},
+            _ => panic!("Expected an overlapping message types error"),
+        }
+        registry
+            .register_proto::<PingPongMessage>(311..=320, Default::default())
+            .unwrap();
+        assert!(!registry.is_empty());
+    }
+
+    #[tokio_macros::test_basic]
+    async fn dispatch_decodes_and_filters_messages() {
+        let mut registry = MessageHandlerRegistry::new();
+        let handler = registry
+            .register_proto::<PingPongMessage>(300..=310, MessageHandlerOptions {
+                require_encryption: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Not a custom message type
+        assert!(!registry.dispatch(make_peer_message(1, encode(1), DhtMessageFlags::ENCRYPTED)));
+        // Rejected by the handler options, but still handled
+        assert!(registry.dispatch(make_peer_message(305, encode(2), DhtMessageFlags::NONE)));
+        // Fails to decode
+        assert!(registry.dispatch(make_peer_message(305, vec![0xff; 3], DhtMessageFlags::ENCRYPTED)));
+        assert!(registry.dispatch(make_peer_message(310, encode(3), DhtMessageFlags::ENCRYPTED)));
+
+        drop(registry);
+        let received = handler.collect::<Vec<_>>().await;
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].inner().nonce, 3);
+    }
+}
@@ -21,11 +21,13 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 mod inbound_connector;
+mod message_handlers;
 mod peer_message;
 mod pubsub;
 
 pub use self::{
     inbound_connector::InboundDomainConnector,
+    message_handlers::{MessageHandlerError, MessageHandlerOptions, MessageHandlerRegistry, CUSTOM_MESSAGE_TYPE_START},
     peer_message::PeerMessage,
-    pubsub::{pubsub_connector, PubsubDomainConnector, SubscriptionFactory},
+    pubsub::{pubsub_connector, pubsub_connector_with_handlers, PubsubDomainConnector, SubscriptionFactory},
 };
@@ -22,7 +22,10 @@
 
 use log::*;
 use tari_comms::{peer_manager::Peer, types::CommsPublicKey};
-use tari_comms_dht::{domain_message::MessageHeader, envelope::DhtMessageHeader};
+use tari_comms_dht::{
+    domain_message::MessageHeader,
+    envelope::{DhtMessageHeader, ReplyBlock},
+};
 
 const LOG_TARGET: &str = "comms::dht::requests::inbound";
 
@@ -36,6 +39,8 @@ pub struct PeerMessage {
     pub message_header: MessageHeader,
     /// Serialized message data
     pub body: Vec<u8>,
+    /// The single-use reply block included by the sender of an anonymous message
+    pub reply_block: Option<ReplyBlock>,
 }
 
 impl PeerMessage {
@@ -45,6 +50,7 @@ impl PeerMessage {
             message_header,
             dht_header,
             source_peer,
+            reply_block: None,
         }
     }
 
@@ -20,9 +20,9 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::peer_message::PeerMessage;
+use super::{message_handlers::MessageHandlerRegistry, peer_message::PeerMessage};
 use crate::{comms_connector::InboundDomainConnector, tari_message::TariMessageType};
-use futures::{channel::mpsc, FutureExt, SinkExt, StreamExt};
+use futures::{channel::mpsc, future, FutureExt, SinkExt, StreamExt};
 use log::*;
 use std::sync::Arc;
 use tari_pubsub::{pubsub_channel, TopicPayload, TopicSubscriptionFactory};
@@ -36,11 +36,24 @@ pub type SubscriptionFactory = TopicSubscriptionFactory<TariMessageType, Arc<Pee
 
 /// Connects `InboundDomainConnector` to a `tari_pubsub::TopicPublisher` through a buffered channel
 pub fn pubsub_connector(executor: Handle, buf_size: usize) -> (PubsubDomainConnector, SubscriptionFactory) {
+    pubsub_connector_with_handlers(executor, buf_size, MessageHandlerRegistry::new())
+}
+
+/// Connects `InboundDomainConnector` to a `tari_pubsub::TopicPublisher` through a buffered channel. Messages with a
+/// message type registered in `handlers` are passed to their handler instead of the `TopicPublisher`.
+pub fn pubsub_connector_with_handlers(
+    executor: Handle,
+    buf_size: usize,
+    mut handlers: MessageHandlerRegistry,
+) -> (PubsubDomainConnector, SubscriptionFactory)
+{
     let (publisher, subscription_factory) = pubsub_channel(buf_size);
     let (sender, receiver) = mpsc::channel(buf_size);
 
     // Spawn a task which forwards messages from the pubsub service to the TopicPublisher
     let forwarder = receiver
+        // Pass application-defined message types to their registered handler
+        .filter(move |msg: &Arc<PeerMessage>| future::ready(!handlers.dispatch(msg.clone())))
         // Map DomainMessage into a TopicPayload
         .map(|msg: Arc<PeerMessage>| {
             TariMessageType::from_i32(msg.message_header.message_type)
@@ -22,7 +22,7 @@
 
 use std::convert::{From, TryFrom};
 use tari_comms::{peer_manager::Peer, types::CommsPublicKey};
-use tari_comms_dht::envelope::DhtMessageHeader;
+use tari_comms_dht::envelope::{DhtMessageHeader, ReplyBlock};
 
 /// Wrapper around a received message. Provides source peer and origin information
 #[derive(Debug, Clone)]
@@ -32,6 +32,9 @@ pub struct DomainMessage<T> {
     /// This DHT header of this message. If `DhtMessageHeader::origin_public_key` is different from the
     /// `source_peer.public_key`, this message was forwarded.
     pub dht_header: DhtMessageHeader,
+    /// The single-use reply block included by the sender of an anonymous message. This can be used to reply to the
+    /// sender using `OutboundMessageRequester::send_reply`.
+    pub reply_block: Option<ReplyBlock>,
     /// The domain-level message
     pub inner: T,
 }
@@ -88,6 +91,7 @@ impl<T> DomainMessage<T> {
         DomainMessage {
             source_peer: self.source_peer,
             dht_header: self.dht_header,
+            reply_block: self.reply_block,
             inner,
         }
     }
@@ -103,6 +107,7 @@ impl<T> DomainMessage<T> {
         Ok(DomainMessage {
             source_peer: self.source_peer,
             dht_header: self.dht_header,
+            reply_block: self.reply_block,
             inner,
         })
     }
@@ -0,0 +1,376 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+//! # Identity files
+//!
+//! Node identities are stored as versioned JSON documents. The secret key can optionally be encrypted with a key
+//! derived from a passphrase, and every file carries a MAC over its contents so that a corrupted or tampered file is
+//! rejected when it is loaded, rather than silently producing a different identity. Without a passphrase the MAC only
+//! protects against corruption.
+//!
+//! Identity files written before the format was versioned contain a plain JSON encoded `NodeIdentity`. These are
+//! upgraded to the current format, encrypted if a passphrase is given, when they are loaded.
+//!
+//! Buffers that hold the secret key or key material derived from the passphrase are zeroized once they are no longer
+//! needed.
+
+use derive_error::Error;
+use digest::Digest;
+use log::*;
+use rand::{rngs::OsRng, RngCore};
+use serde_derive::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+use tari_comms::{
+    multiaddr::Multiaddr,
+    peer_manager::{NodeIdentity, NodeIdentityError, PeerFeatures},
+    types::{CommsPublicKey, CommsSecretKey},
+};
+use tari_crypto::{
+    common::Blake256,
+    tari_utilities::{
+        ciphers::{chacha20::ChaCha20, cipher::Cipher},
+        hex::{from_hex, to_hex, Hex},
+        ByteArray,
+    },
+};
+use zeroize::{Zeroize, Zeroizing};
+
+const LOG_TARGET: &str = "p2p::identity_file";
+
+/// The identity file format version written by this version of the software
+pub const IDENTITY_FILE_VERSION: u32 = 1;
+
+const CIPHER_KEY_DOMAIN: &[u8] = b"com.tari.identity_file.cipher_key";
+const MAC_KEY_DOMAIN: &[u8] = b"com.tari.identity_file.mac_key";
+const SALT_SIZE: usize = 16;
+
+#[derive(Debug, Error)]
+pub enum IdentityFileError {
+    IoError(io::Error),
+    JsonError(serde_json::Error),
+    NodeIdentityError(NodeIdentityError),
+    /// The identity file was written by a newer version of the software
+    #[error(no_from, non_std)]
+    UnsupportedVersion(u32),
+    /// The secret key is encrypted, but no passphrase was given
+    PassphraseRequired,
+    /// The MAC does not match, either the passphrase is incorrect or the file is corrupted
+    IntegrityCheckFailed,
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidField(String),
+}
+
+/// The on-disk representation of a node identity
+#[derive(Debug, Serialize, Deserialize)]
+struct IdentityFile {
+    version: u32,
+    public_key: String,
+    features: PeerFeatures,
+    public_address: String,
+    /// The hex encoded secret key, or the hex encoded cipher text of the secret key if it is encrypted
+    secret_key: String,
+    /// The hex encoded salt used to derive the passphrase keys, only present if the secret key is encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    mac: String,
+}
+
+impl Drop for IdentityFile {
+    fn drop(&mut self) {
+        self.secret_key.zeroize();
+    }
+}
+
+impl IdentityFile {
+    fn is_encrypted(&self) -> bool {
+        self.salt.is_some()
+    }
+
+    fn calculate_mac(&self, mac_key: &[u8]) -> String {
+        let digest = Blake256::new()
+            .chain(MAC_KEY_DOMAIN)
+            .chain(mac_key)
+            .chain(&self.version.to_le_bytes())
+            .chain(self.public_key.as_bytes())
+            .chain(&self.features.bits().to_le_bytes())
+            .chain(self.public_address.as_bytes())
+            .chain(self.secret_key.as_bytes())
+            .chain(self.salt.as_ref().map(String::as_bytes).unwrap_or_default())
+            .result();
+        to_hex(&digest)
+    }
+}
+
+/// The cipher and MAC keys derived from a passphrase
+struct PassphraseKeys {
+    cipher_key: Zeroizing<Vec<u8>>,
+    mac_key: Zeroizing<Vec<u8>>,
+}
+
+impl PassphraseKeys {
+    fn derive(passphrase: &str, salt: &[u8]) -> Result<Self, IdentityFileError> {
+        let master_key = argon2::hash_raw(passphrase.as_bytes(), salt, &argon2::Config::default())
+            .map(Zeroizing::new)
+            .map_err(|e| IdentityFileError::InvalidField(format!("Passphrase key derivation failed: {}", e)))?;
+        Ok(Self {
+            cipher_key: Zeroizing::new(
+                Blake256::new()
+                    .chain(CIPHER_KEY_DOMAIN)
+                    .chain(&*master_key)
+                    .result()
+                    .to_vec(),
+            ),
+            mac_key: Zeroizing::new(
+                Blake256::new()
+                    .chain(MAC_KEY_DOMAIN)
+                    .chain(&*master_key)
+                    .result()
+                    .to_vec(),
+            ),
+        })
+    }
+}
+
+/// Save the node identity to `path` in the current identity file format. The secret key is encrypted if a passphrase
+/// is given.
+pub fn save_identity_file<P: AsRef<Path>>(
+    path: P,
+    identity: &NodeIdentity,
+    passphrase: Option<&str>,
+) -> Result<(), IdentityFileError>
+{
+    let mut file = IdentityFile {
+        version: IDENTITY_FILE_VERSION,
+        public_key: identity.public_key().to_hex(),
+        features: identity.features(),
+        public_address: identity.public_address().to_string(),
+        secret_key: String::new(),
+        salt: None,
+        mac: String::new(),
+    };
+    let mac_key = match passphrase {
+        Some(passphrase) => {
+            let mut salt = [0u8; SALT_SIZE];
+            OsRng.fill_bytes(&mut salt);
+            let keys = PassphraseKeys::derive(passphrase, &salt)?;
+            let plain_text = Zeroizing::new(identity.secret_key().to_vec());
+            let cipher_text = ChaCha20::seal_with_integral_nonce(&*plain_text, &keys.cipher_key)
+                .map_err(|e| IdentityFileError::InvalidField(format!("Secret key encryption failed: {:?}", e)))?;
+            file.secret_key = to_hex(&cipher_text);
+            file.salt = Some(to_hex(&salt));
+            keys.mac_key
+        },
+        None => {
+            file.secret_key = identity.secret_key().to_hex();
+            Zeroizing::new(Vec::new())
+        },
+    };
+    file.mac = file.calculate_mac(&mac_key);
+
+    if let Some(parent) = path.as_ref().parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    let contents = Zeroizing::new(serde_json::to_string_pretty(&file)?);
+    fs::write(path.as_ref(), contents.as_bytes())?;
+    Ok(())
+}
+
+/// Load the node identity stored at `path`, decrypting the secret key with the passphrase if it is encrypted. Files
+/// in the legacy format, and unencrypted files when a passphrase is given, are rewritten in the current format.
+pub fn load_identity_file<P: AsRef<Path>>(
+    path: P,
+    passphrase: Option<&str>,
+) -> Result<NodeIdentity, IdentityFileError>
+{
+    let contents = Zeroizing::new(fs::read_to_string(path.as_ref())?);
+    let value = serde_json::from_str::<serde_json::Value>(&contents)?;
+    if value.get("version").is_none() {
+        let identity = serde_json::from_str::<NodeIdentity>(&contents)?;
+        info!(
+            target: LOG_TARGET,
+            "Upgrading identity file '{}' to version {}",
+            path.as_ref().display(),
+            IDENTITY_FILE_VERSION
+        );
+        save_identity_file(path, &identity, passphrase)?;
+        return Ok(identity);
+    }
+
+    let file = serde_json::from_value::<IdentityFile>(value)?;
+    if file.version > IDENTITY_FILE_VERSION {
+        return Err(IdentityFileError::UnsupportedVersion(file.version));
+    }
+    let secret_key = match &file.salt {
+        Some(salt) => {
+            let passphrase = passphrase.ok_or(IdentityFileError::PassphraseRequired)?;
+            let salt = from_hex(salt).map_err(|e| IdentityFileError::InvalidField(format!("Invalid salt: {}", e)))?;
+            let keys = PassphraseKeys::derive(passphrase, &salt)?;
+            if file.calculate_mac(&keys.mac_key) != file.mac {
+                return Err(IdentityFileError::IntegrityCheckFailed);
+            }
+            let cipher_text = from_hex(&file.secret_key)
+                .map_err(|e| IdentityFileError::InvalidField(format!("Invalid secret key: {}", e)))?;
+            let plain_text: Zeroizing<Vec<u8>> = ChaCha20::open_with_integral_nonce(&cipher_text, &keys.cipher_key)
+                .map(Zeroizing::new)
+                .map_err(|_| IdentityFileError::IntegrityCheckFailed)?;
+            CommsSecretKey::from_bytes(&plain_text)
+                .map_err(|e| IdentityFileError::InvalidField(format!("Invalid secret key: {}", e)))?
+        },
+        None => {
+            if file.calculate_mac(&[]) != file.mac {
+                return Err(IdentityFileError::IntegrityCheckFailed);
+            }
+            CommsSecretKey::from_hex(&file.secret_key)
+                .map_err(|e| IdentityFileError::InvalidField(format!("Invalid secret key: {}", e)))?
+        },
+    };
+    let public_address = file
+        .public_address
+        .parse::<Multiaddr>()
+        .map_err(|e| IdentityFileError::InvalidField(format!("Invalid public address: {}", e)))?;
+    let identity = NodeIdentity::new(secret_key, public_address, file.features)?;
+    let public_key = CommsPublicKey::from_hex(&file.public_key)
+        .map_err(|e| IdentityFileError::InvalidField(format!("Invalid public key: {}", e)))?;
+    if identity.public_key() != &public_key {
+        return Err(IdentityFileError::IntegrityCheckFailed);
+    }
+
+    if passphrase.is_some() && !file.is_encrypted() {
+        info!(
+            target: LOG_TARGET,
+            "Encrypting the secret key in identity file '{}'",
+            path.as_ref().display()
+        );
+        save_identity_file(path, &identity, passphrase)?;
+    }
+    Ok(identity)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn random_identity() -> NodeIdentity {
+        NodeIdentity::random(
+            &mut OsRng,
+            "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+            PeerFeatures::COMMUNICATION_NODE,
+        )
+        .unwrap()
+    }
+
+    fn read_file(path: &Path) -> IdentityFile {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn assert_same_identity(a: &NodeIdentity, b: &NodeIdentity) {
+        assert_eq!(a.secret_key(), b.secret_key());
+        assert_eq!(a.node_id(), b.node_id());
+        assert_eq!(a.features(), b.features());
+        assert_eq!(a.public_address(), b.public_address());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = TempDir::new("identity_file").unwrap();
+        let path = dir.path().join("node_id.json");
+        let identity = random_identity();
+        save_identity_file(&path, &identity, None).unwrap();
+        assert!(!read_file(&path).is_encrypted());
+
+        let loaded = load_identity_file(&path, None).unwrap();
+        assert_same_identity(&loaded, &identity);
+    }
+
+    #[test]
+    fn save_and_load_encrypted() {
+        let dir = TempDir::new("identity_file").unwrap();
+        let path = dir.path().join("node_id.json");
+        let identity = random_identity();
+        save_identity_file(&path, &identity, Some("correct horse")).unwrap();
+        let file = read_file(&path);
+        assert!(file.is_encrypted());
+        assert_ne!(file.secret_key, identity.secret_key().to_hex());
+
+        let loaded = load_identity_file(&path, Some("correct horse")).unwrap();
+        assert_same_identity(&loaded, &identity);
+
+        match load_identity_file(&path, None) {
+            Err(IdentityFileError::PassphraseRequired) => {},
+            r => panic!("Unexpected result {:?}", r),
+        }
+        match load_identity_file(&path, Some("battery staple")) {
+            Err(IdentityFileError::IntegrityCheckFailed) => {},
+            r => panic!("Unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn tampered_file_rejected() {
+        let dir = TempDir::new("identity_file").unwrap();
+        let path = dir.path().join("node_id.json");
+        let identity = random_identity();
+        save_identity_file(&path, &identity, None).unwrap();
+
+        let mut file = read_file(&path);
+        file.public_address = "/ip4/10.0.0.1/tcp/9000".to_string();
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        match load_identity_file(&path, None) {
+            Err(IdentityFileError::IntegrityCheckFailed) => {},
+            r => panic!("Unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn legacy_file_upgraded() {
+        let dir = TempDir::new("identity_file").unwrap();
+        let path = dir.path().join("node_id.json");
+        let identity = random_identity();
+        fs::write(&path, serde_json::to_string(&identity).unwrap()).unwrap();
+
+        let loaded = load_identity_file(&path, Some("correct horse")).unwrap();
+        assert_same_identity(&loaded, &identity);
+        let file = read_file(&path);
+        assert_eq!(file.version, IDENTITY_FILE_VERSION);
+        assert!(file.is_encrypted());
+
+        let loaded = load_identity_file(&path, Some("correct horse")).unwrap();
+        assert_same_identity(&loaded, &identity);
+    }
+
+    #[test]
+    fn unsupported_version_rejected() {
+        let dir = TempDir::new("identity_file").unwrap();
+        let path = dir.path().join("node_id.json");
+        save_identity_file(&path, &random_identity(), None).unwrap();
+
+        let mut file = read_file(&path);
+        file.version = IDENTITY_FILE_VERSION + 1;
+        fs::write(&path, serde_json::to_string(&file).unwrap()).unwrap();
+        match load_identity_file(&path, None) {
+            Err(IdentityFileError::UnsupportedVersion(v)) => assert_eq!(v, IDENTITY_FILE_VERSION + 1),
+            r => panic!("Unexpected result {:?}", r),
+        }
+    }
+}
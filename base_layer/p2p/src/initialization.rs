@@ -31,6 +31,7 @@ use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use std::{error::Error, iter, path::PathBuf, sync::Arc, time::Duration};
 use tari_comms::{
     backoff::ConstantBackoff,
+    connection_manager::PeerAllowList,
     peer_manager::NodeIdentity,
     pipeline,
     pipeline::SinkService,
@@ -82,6 +83,8 @@ pub struct CommsConfig {
     pub listener_liveness_max_sessions: usize,
     /// CIDR for addresses allowed to enter into liveness check mode on the listener.
     pub listener_liveness_whitelist_cidrs: Vec<String>,
+    /// If set, only peers in the allow list are dialed and only connections from peers in the allow list are accepted.
+    pub peer_allow_list: Option<PeerAllowList>,
 }
 
 /// Initialize Tari Comms configured for tests
@@ -279,12 +282,15 @@ where
     let listener_liveness_whitelist_cidrs = parse_cidrs(&config.listener_liveness_whitelist_cidrs)
         .map_err(CommsInitializationError::InvalidLivenessCidrs)?;
 
-    let comms = builder
+    let mut builder = builder
         .with_listener_liveness_max_sessions(config.listener_liveness_max_sessions)
         .with_listener_liveness_whitelist_cidrs(listener_liveness_whitelist_cidrs)
         .with_dial_backoff(ConstantBackoff::new(Duration::from_millis(500)))
-        .with_peer_storage(peer_database)
-        .build()?;
+        .with_peer_storage(peer_database);
+    if let Some(allow_list) = config.peer_allow_list {
+        builder = builder.with_peer_allow_list(allow_list);
+    }
+    let comms = builder.build()?;
 
     // Create outbound channel
     let (outbound_tx, outbound_rx) = mpsc::channel(config.outbound_buffer_size);
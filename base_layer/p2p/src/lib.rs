@@ -32,6 +32,7 @@ mod test_utils;
 
 pub mod comms_connector;
 pub mod domain_message;
+pub mod identity_file;
 pub mod initialization;
 pub mod peer;
 pub mod proto;
@@ -20,6 +20,9 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[path = "tari.p2p.blocklist.rs"]
+pub(crate) mod blocklist;
+
 #[path = "tari.p2p.liveness.rs"]
 pub(crate) mod liveness;
 
@@ -0,0 +1,30 @@
+/// A signed blocklist feed published by an operator-chosen source
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlocklistFeed {
+    /// Encoded `BlocklistEntries`. The signature is over these bytes.
+    #[prost(bytes, tag = "1")]
+    pub entries: std::vec::Vec<u8>,
+    /// Schnorr signature of the entries by the feed publisher
+    #[prost(bytes, tag = "2")]
+    pub signature: std::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlocklistEntries {
+    /// Unix timestamp (seconds) at which this feed was issued. Feeds older than the last applied feed are ignored.
+    #[prost(uint64, tag = "1")]
+    pub issued_at: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub entries: ::std::vec::Vec<BlocklistEntry>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BlocklistEntry {
+    /// The node ID to ban
+    #[prost(bytes, tag = "1")]
+    pub node_id: std::vec::Vec<u8>,
+    /// The number of seconds from `issued_at` that the ban applies
+    #[prost(uint64, tag = "2")]
+    pub ban_duration_secs: u64,
+    /// A human-readable reason for the ban
+    #[prost(string, tag = "3")]
+    pub reason: std::string::String,
+}
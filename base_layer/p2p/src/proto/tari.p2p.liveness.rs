@@ -28,4 +28,7 @@ pub enum MetadataKey {
     None = 0,
     /// The value for this key contains chain metadata
     ChainMetadata = 1,
+    /// The value for this key contains the Unix timestamp in milliseconds at which the pong was sent, encoded as a
+    /// little-endian i64
+    Timestamp = 2,
 }
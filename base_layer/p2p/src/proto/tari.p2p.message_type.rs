@@ -17,6 +17,12 @@ pub enum TariMessageType {
     MempoolResponse = 72,
     /// -- DAN Messages --
     TransactionFinalized = 73,
+    ConfirmationWatchRequest = 74,
+    ConfirmationNotification = 75,
+    VersionHandshakeRequest = 76,
+    VersionHandshakeResponse = 77,
+    HeaderSubscriptionRequest = 78,
+    HeaderChainUpdate = 79,
     // -- Extended --
     Text = 225,
     TextAck = 226,
@@ -0,0 +1,49 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{path::PathBuf, time::Duration};
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
+
+/// Configuration for the blocklist feed service
+#[derive(Debug, Clone)]
+pub struct BlocklistConfig {
+    /// Path to the signed blocklist feed. The operator is responsible for keeping this file in sync with their chosen
+    /// feed source.
+    pub feed_path: PathBuf,
+    /// The public key of the feed publisher. Feeds which are not signed by this key are rejected.
+    pub feed_public_key: CommsPublicKey,
+    /// The interval at which the feed is reloaded and bans are reconciled (default: 5mins)
+    pub refresh_interval: Duration,
+    /// Node IDs which are never banned by the feed, even if they appear in it (default: empty)
+    pub allowlist: Vec<NodeId>,
+}
+
+impl BlocklistConfig {
+    pub fn new(feed_path: PathBuf, feed_public_key: CommsPublicKey) -> Self {
+        Self {
+            feed_path,
+            feed_public_key,
+            refresh_interval: Duration::from_secs(5 * 60),
+            allowlist: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::error::BlocklistError;
+use crate::proto::blocklist as proto;
+use prost::Message;
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey, utils::signature};
+use tari_crypto::tari_utilities::ByteArray;
+
+/// A single ban taken from a verified blocklist feed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlocklistEntry {
+    pub node_id: NodeId,
+    /// Unix timestamp (seconds) after which this ban no longer applies
+    pub expires_at: u64,
+    pub reason: String,
+}
+
+/// A blocklist feed which has been verified against the feed publisher's public key
+#[derive(Debug, Clone, Default)]
+pub struct Blocklist {
+    pub issued_at: u64,
+    pub entries: Vec<BlocklistEntry>,
+}
+
+impl Blocklist {
+    /// Decode a signed `BlocklistFeed` and verify it was signed by `public_key`. Entries with invalid node IDs are
+    /// discarded.
+    pub fn from_signed_bytes(bytes: &[u8], public_key: &CommsPublicKey) -> Result<Self, BlocklistError> {
+        let feed = proto::BlocklistFeed::decode(bytes)?;
+        if !signature::verify(public_key, &feed.signature, &feed.entries)? {
+            return Err(BlocklistError::InvalidFeedSignature);
+        }
+
+        let decoded = proto::BlocklistEntries::decode(feed.entries.as_slice())?;
+        let issued_at = decoded.issued_at;
+        let entries = decoded
+            .entries
+            .into_iter()
+            .filter_map(|entry| {
+                let node_id = NodeId::from_bytes(&entry.node_id).ok()?;
+                Some(BlocklistEntry {
+                    node_id,
+                    expires_at: issued_at.saturating_add(entry.ban_duration_secs),
+                    reason: entry.reason,
+                })
+            })
+            .collect();
+
+        Ok(Self { issued_at, entries })
+    }
+
+    /// Returns the entries which have not expired at the given unix timestamp
+    pub fn active_entries(&self, now: u64) -> impl Iterator<Item = &BlocklistEntry> {
+        self.entries.iter().filter(move |entry| entry.expires_at > now)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_comms::types::CommsSecretKey;
+    use tari_crypto::{keys::PublicKey, tari_utilities::message_format::MessageFormat};
+
+    pub fn make_feed(secret_key: &CommsSecretKey, issued_at: u64, entries: Vec<proto::BlocklistEntry>) -> Vec<u8> {
+        let mut entries_buf = Vec::new();
+        proto::BlocklistEntries { issued_at, entries }
+            .encode(&mut entries_buf)
+            .unwrap();
+        let signature = signature::sign(&mut OsRng, secret_key.clone(), &entries_buf)
+            .unwrap()
+            .to_binary()
+            .unwrap();
+        let mut buf = Vec::new();
+        proto::BlocklistFeed {
+            entries: entries_buf,
+            signature,
+        }
+        .encode(&mut buf)
+        .unwrap();
+        buf
+    }
+
+    pub fn make_entry(node_id: &NodeId, ban_duration_secs: u64) -> proto::BlocklistEntry {
+        proto::BlocklistEntry {
+            node_id: node_id.to_vec(),
+            ban_duration_secs,
+            reason: "spam".to_string(),
+        }
+    }
+
+    #[test]
+    fn from_signed_bytes() {
+        let (sk, pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (_, other_pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let node_id = NodeId::from_key(&other_pk).unwrap();
+        let feed = make_feed(&sk, 1000, vec![make_entry(&node_id, 60), proto::BlocklistEntry {
+            node_id: vec![1, 2, 3],
+            ban_duration_secs: 60,
+            reason: "invalid".to_string(),
+        }]);
+
+        let blocklist = Blocklist::from_signed_bytes(&feed, &pk).unwrap();
+        assert_eq!(blocklist.issued_at, 1000);
+        assert_eq!(blocklist.entries.len(), 1);
+        assert_eq!(blocklist.entries[0].node_id, node_id);
+        assert_eq!(blocklist.entries[0].expires_at, 1060);
+
+        let err = Blocklist::from_signed_bytes(&feed, &other_pk).unwrap_err();
+        match err {
+            BlocklistError::InvalidFeedSignature => {},
+            err => panic!("Unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn active_entries() {
+        let (sk, pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let node_id1 = NodeId::from_key(&CommsPublicKey::random_keypair(&mut OsRng).1).unwrap();
+        let node_id2 = NodeId::from_key(&CommsPublicKey::random_keypair(&mut OsRng).1).unwrap();
+        let feed = make_feed(&sk, 1000, vec![make_entry(&node_id1, 10), make_entry(&node_id2, 100)]);
+
+        let blocklist = Blocklist::from_signed_bytes(&feed, &pk).unwrap();
+        let active = blocklist.active_entries(1050).collect::<Vec<_>>();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].node_id, node_id2);
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Blocklist Service
+//!
+//! This service consumes a signed blocklist feed from an operator-chosen source and applies temporary bans to the
+//! peers it lists.
+//!
+//! The feed is read from a local file which the operator keeps in sync with their chosen source. A feed is only
+//! applied if it is signed by the configured feed public key and is not older than the last applied feed. Node IDs
+//! in the local allowlist are never banned, and bans which were not applied by this service are never lifted by it.
+
+mod config;
+pub mod error;
+mod feed;
+mod service;
+
+use self::service::BlocklistService;
+use futures::{future, Future};
+use log::*;
+use std::sync::Arc;
+use tari_comms::PeerManager;
+use tari_service_framework::{handles::ServiceHandlesFuture, ServiceInitializationError, ServiceInitializer};
+use tari_shutdown::ShutdownSignal;
+use tokio::runtime;
+
+// Public exports
+pub use self::config::BlocklistConfig;
+
+const LOG_TARGET: &str = "p2p::services::blocklist";
+
+/// Initializer for the Blocklist service
+pub struct BlocklistInitializer {
+    config: Option<BlocklistConfig>,
+    peer_manager: Arc<PeerManager>,
+}
+
+impl BlocklistInitializer {
+    pub fn new(config: BlocklistConfig, peer_manager: Arc<PeerManager>) -> Self {
+        Self {
+            config: Some(config),
+            peer_manager,
+        }
+    }
+}
+
+impl ServiceInitializer for BlocklistInitializer {
+    type Future = impl Future<Output = Result<(), ServiceInitializationError>>;
+
+    fn initialize(
+        &mut self,
+        executor: runtime::Handle,
+        _: ServiceHandlesFuture,
+        shutdown: ShutdownSignal,
+    ) -> Self::Future
+    {
+        let config = self
+            .config
+            .take()
+            .expect("Blocklist service initialized more than once.");
+
+        let service = BlocklistService::new(config, self.peer_manager.clone(), shutdown);
+        executor.spawn(async move {
+            service.run().await;
+            debug!(target: LOG_TARGET, "Blocklist service has shut down");
+        });
+
+        future::ready(Ok(()))
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{config::BlocklistConfig, error::BlocklistError, feed::Blocklist, LOG_TARGET};
+use chrono::Utc;
+use futures::StreamExt;
+use log::*;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    sync::Arc,
+};
+use tari_comms::{peer_manager::NodeId, PeerManager};
+use tari_shutdown::ShutdownSignal;
+use tokio::{task, time};
+
+/// Service which periodically loads a signed blocklist feed and applies temporary bans via the peer manager.
+///
+/// Only bans applied by this service are ever lifted by it. Peers which were banned by other means are left alone,
+/// and peers in the configured allowlist are never banned.
+pub struct BlocklistService {
+    config: BlocklistConfig,
+    peer_manager: Arc<PeerManager>,
+    blocklist: Blocklist,
+    applied_bans: HashSet<NodeId>,
+    shutdown_signal: Option<ShutdownSignal>,
+}
+
+impl BlocklistService {
+    pub fn new(config: BlocklistConfig, peer_manager: Arc<PeerManager>, shutdown_signal: ShutdownSignal) -> Self {
+        Self {
+            config,
+            peer_manager,
+            blocklist: Blocklist::default(),
+            applied_bans: HashSet::new(),
+            shutdown_signal: Some(shutdown_signal),
+        }
+    }
+
+    pub async fn run(mut self) {
+        let mut refresh_tick = time::interval(self.config.refresh_interval).fuse();
+
+        let mut shutdown_signal = self
+            .shutdown_signal
+            .take()
+            .expect("Blocklist service initialized without shutdown signal");
+
+        loop {
+            futures::select! {
+                _ = refresh_tick.select_next_some() => {
+                    if let Err(err) = self.refresh().await {
+                        error!(target: LOG_TARGET, "Failed to apply blocklist: {:?}", err);
+                    }
+                },
+                _ = shutdown_signal => {
+                    info!(target: LOG_TARGET, "Blocklist service shutting down because the shutdown signal was received");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn refresh(&mut self) -> Result<(), BlocklistError> {
+        match self.load_feed().await {
+            Ok(blocklist) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Loaded blocklist feed issued at {} containing {} entries",
+                    blocklist.issued_at,
+                    blocklist.entries.len()
+                );
+                self.blocklist = blocklist;
+            },
+            // The last valid feed continues to be applied so that its bans still expire on time
+            Err(err) => {
+                warn!(target: LOG_TARGET, "Unable to load blocklist feed: {:?}", err);
+            },
+        }
+
+        self.apply_bans(Utc::now().timestamp() as u64).await
+    }
+
+    async fn load_feed(&self) -> Result<Blocklist, BlocklistError> {
+        let path = self.config.feed_path.clone();
+        let bytes = task::spawn_blocking(move || fs::read(path))
+            .await
+            .map_err(|err| BlocklistError::FeedReadFailed(err.to_string()))?
+            .map_err(|err| BlocklistError::FeedReadFailed(err.to_string()))?;
+
+        let blocklist = Blocklist::from_signed_bytes(&bytes, &self.config.feed_public_key)?;
+        if blocklist.issued_at < self.blocklist.issued_at {
+            return Err(BlocklistError::StaleFeed);
+        }
+
+        Ok(blocklist)
+    }
+
+    async fn apply_bans(&mut self, now: u64) -> Result<(), BlocklistError> {
+        let allowlist = &self.config.allowlist;
+        let active = self
+            .blocklist
+            .active_entries(now)
+            .filter(|entry| !allowlist.contains(&entry.node_id))
+            .map(|entry| (entry.node_id.clone(), entry.reason.clone()))
+            .collect::<HashMap<_, _>>();
+
+        // Lift bans which have expired, been removed from the feed or been added to the allowlist
+        let lifted = self
+            .applied_bans
+            .iter()
+            .filter(|node_id| !active.contains_key(node_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        for node_id in lifted {
+            self.applied_bans.remove(&node_id);
+            match self.peer_manager.find_by_node_id(&node_id).await {
+                Ok(peer) => {
+                    self.peer_manager.set_banned(&peer.public_key, false).await?;
+                    info!(target: LOG_TARGET, "Lifted blocklist ban for peer '{}'", node_id);
+                },
+                Err(err) if err.is_peer_not_found() => {},
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        for (node_id, reason) in active {
+            if self.applied_bans.contains(&node_id) {
+                continue;
+            }
+
+            match self.peer_manager.find_by_node_id(&node_id).await {
+                // Peers which are already banned were banned locally, so the feed does not take ownership of the ban
+                Ok(peer) if peer.is_banned() => {},
+                Ok(peer) => {
+                    self.peer_manager.set_banned(&peer.public_key, true).await?;
+                    self.applied_bans.insert(node_id.clone());
+                    info!(
+                        target: LOG_TARGET,
+                        "Banned peer '{}' from blocklist feed (reason: {})", node_id, reason
+                    );
+                },
+                // Unknown peers are banned on a later refresh if they are added before the ban expires
+                Err(err) if err.is_peer_not_found() => {},
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        services::blocklist::feed::test::{make_entry, make_feed},
+        test_utils::{make_node_identity, make_peer_manager},
+    };
+    use rand::rngs::OsRng;
+    use tari_comms::{
+        multiaddr::Multiaddr,
+        peer_manager::{NodeIdentity, Peer, PeerFeatures, PeerFlags},
+        types::CommsPublicKey,
+    };
+    use tari_crypto::keys::PublicKey;
+    use tari_shutdown::Shutdown;
+    use tari_test_utils::paths::create_temporary_data_path;
+
+    async fn add_peer(peer_manager: &PeerManager, node_identity: &NodeIdentity, flags: PeerFlags) {
+        peer_manager
+            .add_peer(Peer::new(
+                node_identity.public_key().clone(),
+                node_identity.node_id().clone(),
+                Vec::<Multiaddr>::new().into(),
+                flags,
+                PeerFeatures::COMMUNICATION_NODE,
+                &[],
+            ))
+            .await
+            .unwrap();
+    }
+
+    async fn is_banned(peer_manager: &PeerManager, node_identity: &NodeIdentity) -> bool {
+        peer_manager
+            .find_by_node_id(node_identity.node_id())
+            .await
+            .unwrap()
+            .is_banned()
+    }
+
+    #[tokio_macros::test_basic]
+    async fn refresh_applies_and_lifts_bans() {
+        let (sk, pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let feed_path = create_temporary_data_path().join("blocklist.dat");
+        let peer_manager = make_peer_manager();
+
+        let spammer = make_node_identity();
+        let allowed = make_node_identity();
+        let locally_banned = make_node_identity();
+        add_peer(&peer_manager, &spammer, PeerFlags::empty()).await;
+        add_peer(&peer_manager, &allowed, PeerFlags::empty()).await;
+        add_peer(&peer_manager, &locally_banned, PeerFlags::BANNED).await;
+
+        let mut config = BlocklistConfig::new(feed_path.clone(), pk);
+        config.allowlist = vec![allowed.node_id().clone()];
+        let shutdown = Shutdown::new();
+        let mut service = BlocklistService::new(config, peer_manager.clone(), shutdown.to_signal());
+
+        let now = Utc::now().timestamp() as u64;
+        fs::write(
+            &feed_path,
+            make_feed(&sk, now, vec![
+                make_entry(spammer.node_id(), 3600),
+                make_entry(allowed.node_id(), 3600),
+                make_entry(locally_banned.node_id(), 3600),
+            ]),
+        )
+        .unwrap();
+        service.refresh().await.unwrap();
+
+        assert!(is_banned(&peer_manager, &spammer).await);
+        assert!(!is_banned(&peer_manager, &allowed).await);
+        assert!(is_banned(&peer_manager, &locally_banned).await);
+
+        // Stale feeds are ignored
+        fs::write(&feed_path, make_feed(&sk, now - 1, vec![])).unwrap();
+        service.refresh().await.unwrap();
+        assert!(is_banned(&peer_manager, &spammer).await);
+
+        // Only bans applied by the feed are lifted once they are removed from the feed
+        fs::write(&feed_path, make_feed(&sk, now + 1, vec![])).unwrap();
+        service.refresh().await.unwrap();
+        assert!(!is_banned(&peer_manager, &spammer).await);
+        assert!(is_banned(&peer_manager, &locally_banned).await);
+    }
+}
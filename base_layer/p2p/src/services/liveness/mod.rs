@@ -138,6 +138,7 @@ impl ServiceInitializer for LivenessInitializer {
 
         // Register handle before waiting for handles to be ready
         handles_fut.register(liveness_handle);
+        let ready_signal = handles_fut.ready_signal::<LivenessHandle>();
 
         // Create a stream which receives PingPong messages from comms
         let ping_stream = self.ping_stream();
@@ -148,7 +149,8 @@ impl ServiceInitializer for LivenessInitializer {
             let handles = handles_fut.await;
 
             let outbound_handle = handles
-                .get_handle::<OutboundMessageRequester>()
+                .wait_for_handle::<OutboundMessageRequester>()
+                .await
                 .expect("Liveness service requires CommsOutbound service handle");
 
             if config.enable_auto_join {
@@ -165,25 +167,6 @@ impl ServiceInitializer for LivenessInitializer {
                 }
             }
 
-            if config.enable_auto_stored_message_request {
-                // TODO: Record when store message request was last requested
-                //       and request messages from after that time
-                match dht_requester.send_request_stored_messages().await {
-                    Ok(_) => {
-                        trace!(
-                            target: LOG_TARGET,
-                            "Stored message request has been sent to closest peers",
-                        );
-                    },
-                    Err(err) => {
-                        error!(
-                            target: LOG_TARGET,
-                            "Failed to send stored message on startup because '{}'", err
-                        );
-                    },
-                }
-            }
-
             let state = LivenessState::new();
 
             let service = LivenessService::new(
@@ -196,6 +179,7 @@ impl ServiceInitializer for LivenessInitializer {
                 publisher,
                 shutdown,
             );
+            ready_signal.set_ready();
             service.run().await;
             debug!(target: LOG_TARGET, "Liveness service has shut down");
         });
@@ -31,9 +31,11 @@ use super::{
 };
 use crate::{
     domain_message::DomainMessage,
+    proto::liveness::MetadataKey,
     services::liveness::{neighbours::Neighbours, LivenessEvent, PongEvent},
     tari_message::TariMessageType,
 };
+use chrono::Utc;
 use futures::{pin_mut, stream::StreamExt, task::Context, SinkExt, Stream};
 use log::*;
 use std::{pin::Pin, task::Poll, time::Instant};
@@ -45,7 +47,7 @@ use tari_comms::{
 use tari_comms_dht::{
     broadcast_strategy::BroadcastStrategy,
     domain_message::OutboundDomainMessage,
-    outbound::{DhtOutboundError, OutboundEncryption, OutboundMessageRequester},
+    outbound::{DhtMessagePriority, DhtOutboundError, OutboundEncryption, OutboundMessageRequester},
     DhtRequester,
 };
 use tari_service_framework::RequestContext;
@@ -225,7 +227,12 @@ where
     }
 
     async fn send_pong(&mut self, nonce: u64, dest: CommsPublicKey) -> Result<(), LivenessError> {
-        let msg = PingPongMessage::pong_with_metadata(nonce, self.state.pong_metadata().clone());
+        let mut metadata = self.state.pong_metadata().clone();
+        metadata.insert(
+            MetadataKey::Timestamp,
+            Utc::now().timestamp_millis().to_le_bytes().to_vec(),
+        );
+        let msg = PingPongMessage::pong_with_metadata(nonce, metadata);
         self.oms_handle
             .send_direct(
                 dest,
@@ -306,10 +313,12 @@ where
         for peer in peers {
             let msg = PingPongMessage::ping();
             self.state.add_inflight_ping(msg.nonce, &peer.node_id);
+            // Neighbour pings are background traffic and should not hold up dials to more important peers
             self.oms_handle
-                .send_direct(
+                .send_direct_with_priority(
                     peer.public_key.clone(),
                     OutboundEncryption::None,
+                    DhtMessagePriority::Low,
                     OutboundDomainMessage::new(TariMessageType::PingPong, msg),
                 )
                 .await?;
@@ -394,10 +403,7 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{
-        proto::liveness::MetadataKey,
-        services::liveness::{handle::LivenessHandle, state::Metadata},
-    };
+    use crate::services::liveness::{handle::LivenessHandle, state::Metadata};
     use futures::{channel::mpsc, stream};
     use rand::rngs::OsRng;
     use std::time::Duration;
@@ -521,6 +527,7 @@ mod test {
                 Default::default(),
             ),
             source_peer,
+            reply_block: None,
             inner,
         }
     }
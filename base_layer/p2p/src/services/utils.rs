@@ -43,6 +43,7 @@ where T: prost::Message + Default {
     Ok(DomainMessage {
         source_peer: serialized.source_peer.clone(),
         dht_header: serialized.dht_header.clone(),
+        reply_block: serialized.reply_block.clone(),
         inner: serialized.decode_message()?,
     })
 }
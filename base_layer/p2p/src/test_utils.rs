@@ -23,8 +23,10 @@
 use rand::rngs::OsRng;
 use std::sync::Arc;
 use tari_comms::{
+    message::MessageTag,
     multiaddr::Multiaddr,
-    peer_manager::{NodeIdentity, Peer, PeerFeatures, PeerFlags},
+    peer_manager::{NodeIdentity, Peer, PeerFeatures, PeerFlags, PeerManager},
+    types::CommsDatabase,
     utils::signature,
 };
 use tari_comms_dht::{
@@ -32,6 +34,8 @@ use tari_comms_dht::{
     inbound::DhtInboundMessage,
 };
 use tari_crypto::tari_utilities::message_format::MessageFormat;
+use tari_storage::lmdb_store::LMDBBuilder;
+use tari_test_utils::{paths::create_temporary_data_path, random};
 
 macro_rules! unwrap_oms_send_msg {
     ($var:expr, reply_value=$reply_value:expr) => {
@@ -75,6 +79,8 @@ pub fn make_dht_header(node_identity: &NodeIdentity, message: &Vec<u8>, flags: D
         message_type: DhtMessageType::None,
         network: Network::LocalTest,
         flags,
+        ephemeral_public_key: None,
+        wrapped_keys: Vec::new(),
     }
 }
 
@@ -85,6 +91,7 @@ pub fn make_dht_inbound_message(
 ) -> DhtInboundMessage
 {
     DhtInboundMessage::new(
+        MessageTag::new(),
         make_dht_header(node_identity, &message, flags),
         Arc::new(Peer::new(
             node_identity.public_key().clone(),
@@ -97,3 +104,21 @@ pub fn make_dht_inbound_message(
         message,
     )
 }
+
+pub fn make_peer_manager() -> Arc<PeerManager> {
+    let database_name = random::string(8);
+    let path = create_temporary_data_path();
+    let datastore = LMDBBuilder::new()
+        .set_path(path.to_str().unwrap())
+        .set_environment_size(50)
+        .set_max_number_of_databases(1)
+        .add_database(&database_name, lmdb_zero::db::CREATE)
+        .build()
+        .unwrap();
+
+    let peer_database = datastore.get_handle(&database_name).unwrap();
+
+    PeerManager::new(CommsDatabase::new(Arc::new(peer_database)))
+        .map(Arc::new)
+        .unwrap()
+}
@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::ServiceHandles;
-use crate::handles::LazyService;
+use crate::handles::{LazyService, ReadySignal};
 use futures::{
     task::{AtomicWaker, Context},
     Future,
@@ -113,6 +113,12 @@ impl ServiceHandlesFuture {
         self.handles.register(handle);
     }
 
+    /// Register the service with handle type `H` as starting. The returned signal must be set once the service is
+    /// running. Services that depend on this service wait for the signal using `ServiceHandles::wait_for_handle`.
+    pub fn ready_signal<H: 'static>(&self) -> ReadySignal {
+        self.handles.readiness().register::<H>()
+    }
+
     /// Retrieve a handle and downcast it to return type and return a copy, otherwise None is returned
     pub fn get_handle<H>(&self) -> Option<H>
     where H: Clone + 'static {
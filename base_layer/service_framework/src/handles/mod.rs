@@ -22,13 +22,18 @@
 
 mod future;
 mod lazy_service;
+use crate::initializer::ServiceInitializationError;
 use std::{
-    any::{Any, TypeId},
+    any::{type_name, Any, TypeId},
     collections::HashMap,
     sync::Mutex,
 };
 
-pub use self::{future::ServiceHandlesFuture, lazy_service::LazyService};
+pub use self::{
+    future::ServiceHandlesFuture,
+    lazy_service::LazyService,
+    readiness::{ReadySignal, ServiceNotReady, ServiceReadiness},
+};
 pub(crate) use future::handle_notifier_pair;
 
 /// This macro unlocks a Mutex or RwLock. If the lock is
@@ -49,10 +54,13 @@ macro_rules! acquire_lock {
     };
 }
 
+mod readiness;
+
 /// Simple collection for named handles
 #[derive(Default)]
 pub struct ServiceHandles {
     handles: Mutex<HashMap<TypeId, Box<dyn Any + Sync + Send>>>,
+    readiness: ServiceReadiness,
 }
 
 impl ServiceHandles {
@@ -60,6 +68,7 @@ impl ServiceHandles {
     pub fn new() -> Self {
         Self {
             handles: Default::default(),
+            readiness: ServiceReadiness::new(),
         }
     }
 
@@ -85,11 +94,30 @@ impl ServiceHandles {
             .and_then(|b| b.downcast_ref::<H>())
             .map(Clone::clone)
     }
+
+    /// Returns the readiness signals of the services
+    pub fn readiness(&self) -> &ServiceReadiness {
+        &self.readiness
+    }
+
+    /// Wait for the service with handle type `H` to be ready and return the handle. This is used by services to start
+    /// after the services they depend on. An error is returned if the handle is not registered or the service failed
+    /// to start.
+    pub async fn wait_for_handle<H>(&self) -> Result<H, ServiceInitializationError>
+    where H: Clone + 'static {
+        let handle = self
+            .get_handle::<H>()
+            .ok_or_else(|| ServiceInitializationError::HandleNotRegistered(type_name::<H>().to_string()))?;
+        self.readiness.wait_ready::<H>().await?;
+        Ok(handle)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use futures::executor::block_on;
+    use tari_test_utils::unpack_enum;
 
     #[test]
     fn service_handles_insert_get() {
@@ -101,4 +129,22 @@ mod test {
         assert!(handles.get_handle::<()>().is_none());
         assert!(handles.get_handle::<usize>().is_none());
     }
+
+    #[test]
+    fn service_handles_wait_for_handle() {
+        #[derive(Clone)]
+        struct TestHandle;
+        let handles = ServiceHandles::new();
+        let err = block_on(handles.wait_for_handle::<TestHandle>()).unwrap_err();
+        unpack_enum!(ServiceInitializationError::HandleNotRegistered(_s) = err);
+
+        handles.register(TestHandle);
+        // Services without a ready signal are always ready
+        block_on(handles.wait_for_handle::<TestHandle>()).unwrap();
+
+        let ready_signal = handles.readiness().register::<TestHandle>();
+        drop(ready_signal);
+        let err = block_on(handles.wait_for_handle::<TestHandle>()).unwrap_err();
+        unpack_enum!(ServiceInitializationError::ServiceNotReady(_e) = err);
+    }
 }
@@ -0,0 +1,157 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use futures::{
+    channel::{oneshot, oneshot::Canceled},
+    future::{join_all, FutureExt, Shared},
+};
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        Mutex,
+    },
+};
+
+type ReadyFuture = Shared<oneshot::Receiver<()>>;
+
+#[derive(Clone)]
+struct ReadyState {
+    name: &'static str,
+    is_ready: Arc<AtomicBool>,
+    ready_fut: ReadyFuture,
+}
+
+/// Signals that a service has started. A service obtains a `ReadySignal` during initialization and calls `set_ready`
+/// once it has acquired the handles it depends on and is about to run. If the signal is dropped without being set, the
+/// service is considered to have failed to start.
+pub struct ReadySignal {
+    is_ready: Arc<AtomicBool>,
+    sender: oneshot::Sender<()>,
+}
+
+impl ReadySignal {
+    /// Notify everything waiting on this service that it is ready
+    pub fn set_ready(self) {
+        self.is_ready.store(true, Ordering::SeqCst);
+        // The receiver is held by `ServiceReadiness` and is never dropped before the signal
+        let _ = self.sender.send(());
+    }
+}
+
+/// Error returned when waiting for a service that failed to start
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceNotReady(pub &'static str);
+
+impl fmt::Display for ServiceNotReady {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Service '{}' did not become ready", self.0)
+    }
+}
+
+impl std::error::Error for ServiceNotReady {}
+
+/// Collection of readiness signals, keyed by the type of the handle for the service
+#[derive(Clone, Default)]
+pub struct ServiceReadiness {
+    services: Arc<Mutex<HashMap<TypeId, ReadyState>>>,
+}
+
+impl ServiceReadiness {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Register the service with handle type `H` as starting and return the signal used to mark it as ready
+    pub fn register<H: 'static>(&self) -> ReadySignal {
+        let (sender, receiver) = oneshot::channel();
+        let is_ready = Arc::new(AtomicBool::new(false));
+        acquire_lock!(self.services).insert(TypeId::of::<H>(), ReadyState {
+            name: type_name::<H>(),
+            is_ready: Arc::clone(&is_ready),
+            ready_fut: receiver.shared(),
+        });
+        ReadySignal { is_ready, sender }
+    }
+
+    /// Returns true if the service for handle type `H` has signalled that it is ready. Services which do not use a
+    /// ready signal are always ready.
+    pub fn is_ready<H: 'static>(&self) -> bool {
+        acquire_lock!(self.services)
+            .get(&TypeId::of::<H>())
+            .map(|state| state.is_ready.load(Ordering::SeqCst))
+            .unwrap_or(true)
+    }
+
+    /// Wait until the service for handle type `H` is ready
+    pub async fn wait_ready<H: 'static>(&self) -> Result<(), ServiceNotReady> {
+        let ready = acquire_lock!(self.services).get(&TypeId::of::<H>()).cloned();
+        match ready {
+            Some(ReadyState { name, ready_fut, .. }) => ready_fut.await.map_err(|_: Canceled| ServiceNotReady(name)),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait until all registered services are ready. An error is returned for the first service that failed to start.
+    pub async fn wait_all_ready(&self) -> Result<(), ServiceNotReady> {
+        let all_ready = acquire_lock!(self.services).values().cloned().collect::<Vec<_>>();
+        let results = join_all(all_ready.into_iter().map(|state| {
+            let name = state.name;
+            state
+                .ready_fut
+                .map(move |result| result.map_err(|_| ServiceNotReady(name)))
+        }))
+        .await;
+        results.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::executor::block_on;
+
+    struct HandleA;
+    struct HandleB;
+
+    #[test]
+    fn wait_ready() {
+        let readiness = ServiceReadiness::new();
+        assert!(readiness.is_ready::<HandleA>());
+        let signal_a = readiness.register::<HandleA>();
+        let signal_b = readiness.register::<HandleB>();
+        assert_eq!(readiness.is_ready::<HandleA>(), false);
+
+        let mut wait_a = readiness.wait_ready::<HandleA>().boxed();
+        assert!((&mut wait_a).now_or_never().is_none());
+        signal_a.set_ready();
+        block_on(wait_a).unwrap();
+        assert!(readiness.is_ready::<HandleA>());
+
+        drop(signal_b);
+        let err = block_on(readiness.wait_all_ready()).unwrap_err();
+        assert_eq!(err, ServiceNotReady(type_name::<HandleB>()));
+    }
+}
@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::handles::ServiceHandlesFuture;
+use crate::handles::{ServiceHandlesFuture, ServiceNotReady};
 use derive_error::Error;
 use futures::{Future, FutureExt};
 use std::pin::Pin;
@@ -33,6 +33,10 @@ pub enum ServiceInitializationError {
     // Specialized errors should be added and used if appropriate.
     #[error(msg_embedded, non_std, no_from)]
     Failed(String),
+    /// A handle required by the service has not been registered
+    #[error(msg_embedded, non_std, no_from)]
+    HandleNotRegistered(String),
+    ServiceNotReady(ServiceNotReady),
 }
 
 /// Implementors of this trait will initialize a service
@@ -22,6 +22,12 @@
 //! Handles are simply a way to communicate with their corresponding service. Typically, a [SenderService] would
 //! be used for this purpose but a handle can be implemented in any way the implementor sees fit.
 //!
+//! ## Readiness
+//!
+//! A service can register a [ReadySignal] for its handle type with `ServiceHandlesFuture::ready_signal` and set it
+//! once it is running. Services which depend on it use `ServiceHandles::wait_for_handle` to start only once the
+//! dependency is ready, and the application can wait for the whole stack with `ServiceReadiness::wait_all_ready`.
+//!
 //! ## `reply_channel`
 //!
 //! This provides for query messages to be sent to services along with a "reply channel" for the service to send back
@@ -62,6 +68,7 @@
 //! [StackBuilder]: ./stack/struct.StackBuilder.html
 //! [ServiceHandlesFuture]: ./handles/future/struct.ServiceHandlesFuture.html
 //! [SenderService]: ./reply_channel/struct.SenderService.html
+//! [ReadySignal]: ./handles/struct.ReadySignal.html
 
 // Used to eliminate the need for boxing futures in many cases.
 // Tracking issue: https://github.com/rust-lang/rust/issues/63063
@@ -103,7 +103,10 @@ mod test {
     use super::*;
     use crate::{handles::ServiceHandlesFuture, initializer::ServiceInitializer};
     use futures::{executor::block_on, future, Future};
-    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
     use tari_shutdown::Shutdown;
     use tokio::runtime::Runtime;
     use tower::service_fn;
@@ -195,4 +198,54 @@ mod test {
 
         assert_eq!(shared_state.load(Ordering::SeqCst), 1);
     }
+
+    #[derive(Clone)]
+    struct DependentServiceHandle;
+
+    #[test]
+    fn service_stack_dependency_ordered_startup() {
+        let rt = Runtime::new().unwrap();
+        let shutdown = Shutdown::new();
+        let started = Arc::new(Mutex::new(Vec::new()));
+
+        // Added first, but must not start until the DummyServiceHandle service is ready
+        let started_dependent = Arc::clone(&started);
+        let dependent_initializer = move |executor: runtime::Handle, handles_fut: ServiceHandlesFuture, _| {
+            handles_fut.register(DependentServiceHandle);
+            let ready_signal = handles_fut.ready_signal::<DependentServiceHandle>();
+            let started = Arc::clone(&started_dependent);
+            executor.spawn(async move {
+                let handles = handles_fut.await;
+                let dep = handles.wait_for_handle::<DummyServiceHandle>().await.unwrap();
+                started.lock().unwrap().push(dep.0);
+                ready_signal.set_ready();
+            });
+            future::ok(())
+        };
+
+        let started_dep = Arc::clone(&started);
+        let dep_initializer = move |executor: runtime::Handle, handles_fut: ServiceHandlesFuture, _| {
+            handles_fut.register(DummyServiceHandle(1));
+            let ready_signal = handles_fut.ready_signal::<DummyServiceHandle>();
+            let started = Arc::clone(&started_dep);
+            executor.spawn(async move {
+                let _ = handles_fut.await;
+                started.lock().unwrap().push(0);
+                ready_signal.set_ready();
+            });
+            future::ok(())
+        };
+
+        let handles = block_on(
+            StackBuilder::new(rt.handle().clone(), shutdown.to_signal())
+                .add_initializer(dependent_initializer)
+                .add_initializer(dep_initializer)
+                .finish(),
+        )
+        .unwrap();
+
+        block_on(handles.readiness().wait_all_ready()).unwrap();
+        assert!(handles.readiness().is_ready::<DependentServiceHandle>());
+        assert_eq!(*started.lock().unwrap(), vec![0, 1]);
+    }
 }
@@ -0,0 +1,5 @@
+CREATE TABLE output_migration_plans (
+    id INTEGER PRIMARY KEY,
+    plan TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
@@ -0,0 +1,32 @@
+PRAGMA foreign_keys=off;
+
+ALTER TABLE outbound_transactions RENAME TO outbound_transactions_old;
+CREATE TABLE outbound_transactions (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    destination_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    fee INTEGER NOT NULL,
+    sender_protocol TEXT NOT NULL,
+    message TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
+INSERT INTO outbound_transactions (tx_id, destination_public_key, amount, fee, sender_protocol, message, timestamp)
+    SELECT tx_id, destination_public_key, amount, fee, sender_protocol, message, timestamp
+    FROM outbound_transactions_old;
+DROP TABLE outbound_transactions_old;
+
+ALTER TABLE inbound_transactions RENAME TO inbound_transactions_old;
+CREATE TABLE inbound_transactions (
+    tx_id INTEGER PRIMARY KEY NOT NULL,
+    source_public_key BLOB NOT NULL,
+    amount INTEGER NOT NULL,
+    receiver_protocol TEXT NOT NULL,
+    message TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
+INSERT INTO inbound_transactions (tx_id, source_public_key, amount, receiver_protocol, message, timestamp)
+    SELECT tx_id, source_public_key, amount, receiver_protocol, message, timestamp
+    FROM inbound_transactions_old;
+DROP TABLE inbound_transactions_old;
+
+PRAGMA foreign_keys=on;
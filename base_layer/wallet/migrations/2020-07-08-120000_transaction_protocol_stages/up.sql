@@ -0,0 +1,3 @@
+-- Transactions persisted before protocol stages were tracked had already sent their message to the counterparty
+ALTER TABLE outbound_transactions ADD COLUMN protocol_stage INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE inbound_transactions ADD COLUMN protocol_stage INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,14 @@
+CREATE TABLE invoices (
+    invoice_id TEXT PRIMARY KEY NOT NULL,
+    spending_key BLOB NOT NULL,
+    key_index INTEGER NOT NULL,
+    tx_id INTEGER NULL,
+    amount INTEGER NULL,
+    timestamp DATETIME NOT NULL
+);
+
+CREATE TABLE spend_locks (
+    id INTEGER PRIMARY KEY,
+    passphrase_hash TEXT NOT NULL,
+    timestamp DATETIME NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE outbound_transactions ADD COLUMN retry_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE inbound_transactions ADD COLUMN retry_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+CREATE TABLE balance_audit_log (
+    id INTEGER PRIMARY KEY,
+    event INTEGER NOT NULL,
+    commitment BLOB NOT NULL,
+    value INTEGER NOT NULL,
+    tx_id INTEGER NULL,
+    timestamp DATETIME NOT NULL
+);
@@ -0,0 +1,62 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    contacts_service::error::ContactsServiceError,
+    output_manager_service::error::OutputManagerError,
+    transaction_service::error::TransactionServiceError,
+};
+use derive_error::Error;
+use std::io;
+use tokio::task::JoinError;
+
+#[derive(Debug, Error)]
+pub enum WalletBackupError {
+    IoError(io::Error),
+    SerdeJsonError(serde_json::Error),
+    OutputManagerError(OutputManagerError),
+    TransactionServiceError(TransactionServiceError),
+    ContactsServiceError(ContactsServiceError),
+    /// The backup was written by a newer version of the software
+    #[error(no_from, non_std)]
+    UnsupportedVersion(u32),
+    /// The MAC does not match, either the passphrase is incorrect or the backup is corrupted
+    IntegrityCheckFailed,
+    /// An incremental backup does not follow on from the previous backup in its chain
+    BrokenChain,
+    #[error(msg_embedded, no_from, non_std)]
+    EncryptionError(String),
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidBackup(String),
+    #[error(msg_embedded, no_from, non_std)]
+    InvalidTarget(String),
+    #[error(msg_embedded, no_from, non_std)]
+    TargetCommandFailed(String),
+    #[error(msg_embedded, no_from, non_std)]
+    BlockingTaskSpawnError(String),
+}
+
+impl From<JoinError> for WalletBackupError {
+    fn from(err: JoinError) -> Self {
+        WalletBackupError::BlockingTaskSpawnError(err.to_string())
+    }
+}
@@ -0,0 +1,536 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! The wallet export format.
+//!
+//! A [WalletExport] holds the wallet's master key seed words, its spent and unspent outputs, completed transactions
+//! and contacts. An export is either full, or incremental in which case it only holds the records that were added or
+//! changed since the previous export and the keys of the records that were removed.
+//!
+//! Exports are stored as a JSON envelope holding the ChaCha20 encrypted export and a MAC over the envelope. The cipher
+//! and MAC keys are derived from a passphrase and a random salt.
+
+use crate::{
+    backup::error::WalletBackupError,
+    contacts_service::{handle::ContactsServiceHandle, storage::database::Contact},
+    output_manager_service::{handle::OutputManagerHandle, TxId},
+    transaction_service::{handle::TransactionServiceHandle, storage::database::CompletedTransaction},
+};
+use chrono::{NaiveDateTime, Utc};
+use digest::Digest;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::{OutputFeatures, UnblindedOutput},
+    types::PrivateKey,
+};
+use tari_crypto::{
+    common::Blake256,
+    tari_utilities::{
+        ciphers::{chacha20::ChaCha20, cipher::Cipher},
+        hex::{from_hex, to_hex, Hex},
+    },
+};
+use zeroize::{Zeroize, Zeroizing};
+
+/// The export format version written by this version of the software
+pub const WALLET_EXPORT_VERSION: u32 = 1;
+
+const CIPHER_KEY_DOMAIN: &[u8] = b"com.tari.wallet.backup.cipher_key";
+const MAC_KEY_DOMAIN: &[u8] = b"com.tari.wallet.backup.mac_key";
+const SALT_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ExportedOutputStatus {
+    Unspent,
+    Spent,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExportedOutput {
+    pub value: MicroTari,
+    /// The hex encoded spending key, which also identifies the output between exports
+    pub spending_key: String,
+    pub features: OutputFeatures,
+    pub status: ExportedOutputStatus,
+}
+
+impl ExportedOutput {
+    pub fn new(output: &UnblindedOutput, status: ExportedOutputStatus) -> Self {
+        Self {
+            value: output.value,
+            spending_key: output.spending_key.reveal().to_hex(),
+            features: output.features.clone(),
+            status,
+        }
+    }
+
+    pub fn to_unblinded_output(&self) -> Result<UnblindedOutput, WalletBackupError> {
+        let spending_key = PrivateKey::from_hex(&self.spending_key)
+            .map_err(|e| WalletBackupError::InvalidBackup(format!("Invalid spending key: {}", e)))?;
+        Ok(UnblindedOutput::new(
+            self.value,
+            spending_key,
+            Some(self.features.clone()),
+        ))
+    }
+}
+
+impl Drop for ExportedOutput {
+    fn drop(&mut self) {
+        self.spending_key.zeroize();
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExportedContact {
+    pub alias: String,
+    /// The hex encoded public key, which also identifies the contact between exports
+    pub public_key: String,
+}
+
+impl ExportedContact {
+    pub fn to_contact(&self) -> Result<Contact, WalletBackupError> {
+        let public_key = CommsPublicKey::from_hex(&self.public_key)
+            .map_err(|e| WalletBackupError::InvalidBackup(format!("Invalid contact public key: {}", e)))?;
+        Ok(Contact {
+            alias: self.alias.clone(),
+            public_key,
+        })
+    }
+}
+
+impl From<&Contact> for ExportedContact {
+    fn from(contact: &Contact) -> Self {
+        Self {
+            alias: contact.alias.clone(),
+            public_key: contact.public_key.to_hex(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WalletExport {
+    pub version: u32,
+    pub created_at: NaiveDateTime,
+    /// The creation time of the export this export follows on from, `None` for a full export
+    pub previous: Option<NaiveDateTime>,
+    /// The seed words of the master key. An incremental export only contains them if the master key changed.
+    pub seed_words: Vec<String>,
+    pub outputs: Vec<ExportedOutput>,
+    pub transactions: Vec<CompletedTransaction>,
+    pub contacts: Vec<ExportedContact>,
+    /// The spending keys of the outputs removed since the previous export
+    #[serde(default)]
+    pub removed_outputs: Vec<String>,
+    #[serde(default)]
+    pub removed_transactions: Vec<TxId>,
+    /// The public keys of the contacts removed since the previous export
+    #[serde(default)]
+    pub removed_contacts: Vec<String>,
+}
+
+impl WalletExport {
+    /// Creates a full export
+    pub fn new(
+        seed_words: Vec<String>,
+        outputs: Vec<ExportedOutput>,
+        transactions: Vec<CompletedTransaction>,
+        contacts: Vec<ExportedContact>,
+    ) -> Self
+    {
+        Self {
+            version: WALLET_EXPORT_VERSION,
+            created_at: Utc::now().naive_utc(),
+            previous: None,
+            seed_words,
+            outputs,
+            transactions,
+            contacts,
+            removed_outputs: Vec::new(),
+            removed_transactions: Vec::new(),
+            removed_contacts: Vec::new(),
+        }
+    }
+
+    /// Creates a full export of the current state of the wallet services
+    pub async fn from_wallet(
+        output_manager: &mut OutputManagerHandle,
+        transaction_service: &mut TransactionServiceHandle,
+        contacts_service: &mut ContactsServiceHandle,
+    ) -> Result<Self, WalletBackupError>
+    {
+        let seed_words = output_manager.get_seed_words().await?;
+        let mut outputs = output_manager
+            .get_unspent_outputs()
+            .await?
+            .iter()
+            .map(|output| ExportedOutput::new(output, ExportedOutputStatus::Unspent))
+            .collect::<Vec<_>>();
+        outputs.extend(
+            output_manager
+                .get_spent_outputs()
+                .await?
+                .iter()
+                .map(|output| ExportedOutput::new(output, ExportedOutputStatus::Spent)),
+        );
+        let mut transactions = transaction_service
+            .get_completed_transactions()
+            .await?
+            .into_iter()
+            .map(|(_, tx)| tx)
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|tx| tx.tx_id);
+        let contacts = contacts_service
+            .get_contacts()
+            .await?
+            .iter()
+            .map(ExportedContact::from)
+            .collect();
+
+        Ok(Self::new(seed_words, outputs, transactions, contacts))
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.previous.is_none()
+    }
+
+    /// Returns true if this is an incremental export without any changes
+    pub fn is_empty(&self) -> bool {
+        !self.is_full() &&
+            self.seed_words.is_empty() &&
+            self.outputs.is_empty() &&
+            self.transactions.is_empty() &&
+            self.contacts.is_empty() &&
+            self.removed_outputs.is_empty() &&
+            self.removed_transactions.is_empty() &&
+            self.removed_contacts.is_empty()
+    }
+
+    /// Returns an incremental export of the changes between the full export `previous` and this full export
+    pub fn changes_since(&self, previous: &WalletExport) -> WalletExport {
+        let previous_outputs = previous
+            .outputs
+            .iter()
+            .map(|o| (o.spending_key.as_str(), o))
+            .collect::<HashMap<_, _>>();
+        let previous_transactions = previous
+            .transactions
+            .iter()
+            .map(|tx| (tx.tx_id, tx))
+            .collect::<HashMap<_, _>>();
+        let previous_contacts = previous
+            .contacts
+            .iter()
+            .map(|c| (c.public_key.as_str(), c))
+            .collect::<HashMap<_, _>>();
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|o| o.spending_key.as_str())
+            .collect::<HashSet<_>>();
+        let transactions = self.transactions.iter().map(|tx| tx.tx_id).collect::<HashSet<_>>();
+        let contacts = self
+            .contacts
+            .iter()
+            .map(|c| c.public_key.as_str())
+            .collect::<HashSet<_>>();
+
+        WalletExport {
+            version: WALLET_EXPORT_VERSION,
+            created_at: self.created_at,
+            previous: Some(previous.created_at),
+            seed_words: if self.seed_words == previous.seed_words {
+                Vec::new()
+            } else {
+                self.seed_words.clone()
+            },
+            outputs: self
+                .outputs
+                .iter()
+                .filter(|o| previous_outputs.get(o.spending_key.as_str()) != Some(o))
+                .cloned()
+                .collect(),
+            transactions: self
+                .transactions
+                .iter()
+                .filter(|tx| previous_transactions.get(&tx.tx_id) != Some(tx))
+                .cloned()
+                .collect(),
+            contacts: self
+                .contacts
+                .iter()
+                .filter(|c| previous_contacts.get(c.public_key.as_str()) != Some(c))
+                .cloned()
+                .collect(),
+            removed_outputs: previous_outputs
+                .keys()
+                .filter(|key| !outputs.contains(*key))
+                .map(|key| key.to_string())
+                .collect(),
+            removed_transactions: previous_transactions
+                .keys()
+                .filter(|tx_id| !transactions.contains(*tx_id))
+                .cloned()
+                .collect(),
+            removed_contacts: previous_contacts
+                .keys()
+                .filter(|key| !contacts.contains(*key))
+                .map(|key| key.to_string())
+                .collect(),
+        }
+    }
+
+    /// Applies the incremental export `changes`, which must follow on from this export
+    pub fn apply(&mut self, changes: &WalletExport) -> Result<(), WalletBackupError> {
+        if changes.previous != Some(self.created_at) {
+            return Err(WalletBackupError::BrokenChain);
+        }
+        if !changes.seed_words.is_empty() {
+            self.seed_words = changes.seed_words.clone();
+        }
+
+        let replaced = changes
+            .outputs
+            .iter()
+            .map(|o| o.spending_key.as_str())
+            .chain(changes.removed_outputs.iter().map(String::as_str))
+            .collect::<HashSet<_>>();
+        self.outputs.retain(|o| !replaced.contains(o.spending_key.as_str()));
+        self.outputs.extend(changes.outputs.iter().cloned());
+
+        let replaced = changes
+            .transactions
+            .iter()
+            .map(|tx| tx.tx_id)
+            .chain(changes.removed_transactions.iter().cloned())
+            .collect::<HashSet<_>>();
+        self.transactions.retain(|tx| !replaced.contains(&tx.tx_id));
+        self.transactions.extend(changes.transactions.iter().cloned());
+        self.transactions.sort_by_key(|tx| tx.tx_id);
+
+        let replaced = changes
+            .contacts
+            .iter()
+            .map(|c| c.public_key.as_str())
+            .chain(changes.removed_contacts.iter().map(String::as_str))
+            .collect::<HashSet<_>>();
+        self.contacts.retain(|c| !replaced.contains(c.public_key.as_str()));
+        self.contacts.extend(changes.contacts.iter().cloned());
+
+        self.created_at = changes.created_at;
+        Ok(())
+    }
+
+    /// Restores the full wallet state from a chain of exports, which must start with a full export and be in the order
+    /// they were created
+    pub fn from_chain<I: IntoIterator<Item = WalletExport>>(chain: I) -> Result<WalletExport, WalletBackupError> {
+        let mut chain = chain.into_iter();
+        let mut state = chain.next().filter(WalletExport::is_full).ok_or_else(|| {
+            WalletBackupError::InvalidBackup("A backup chain must start with a full backup".to_string())
+        })?;
+        for changes in chain {
+            state.apply(&changes)?;
+        }
+        Ok(state)
+    }
+}
+
+impl Drop for WalletExport {
+    fn drop(&mut self) {
+        self.seed_words.iter_mut().for_each(Zeroize::zeroize);
+    }
+}
+
+/// The stored representation of an encrypted export
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedExport {
+    version: u32,
+    salt: String,
+    cipher_text: String,
+    mac: String,
+}
+
+impl EncryptedExport {
+    fn calculate_mac(&self, mac_key: &[u8]) -> String {
+        let digest = Blake256::new()
+            .chain(MAC_KEY_DOMAIN)
+            .chain(mac_key)
+            .chain(&self.version.to_le_bytes())
+            .chain(self.salt.as_bytes())
+            .chain(self.cipher_text.as_bytes())
+            .result();
+        to_hex(&digest)
+    }
+}
+
+/// The cipher and MAC keys derived from a passphrase
+struct PassphraseKeys {
+    cipher_key: Zeroizing<Vec<u8>>,
+    mac_key: Zeroizing<Vec<u8>>,
+}
+
+impl PassphraseKeys {
+    fn derive(passphrase: &str, salt: &[u8]) -> Result<Self, WalletBackupError> {
+        let master_key = argon2::hash_raw(passphrase.as_bytes(), salt, &argon2::Config::default())
+            .map(Zeroizing::new)
+            .map_err(|e| WalletBackupError::EncryptionError(format!("Passphrase key derivation failed: {}", e)))?;
+        Ok(Self {
+            cipher_key: Zeroizing::new(
+                Blake256::new()
+                    .chain(CIPHER_KEY_DOMAIN)
+                    .chain(&*master_key)
+                    .result()
+                    .to_vec(),
+            ),
+            mac_key: Zeroizing::new(
+                Blake256::new()
+                    .chain(MAC_KEY_DOMAIN)
+                    .chain(&*master_key)
+                    .result()
+                    .to_vec(),
+            ),
+        })
+    }
+}
+
+/// Serializes and encrypts the export with a key derived from the passphrase
+pub fn encrypt_export(export: &WalletExport, passphrase: &str) -> Result<Vec<u8>, WalletBackupError> {
+    let mut salt = [0u8; SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+    let keys = PassphraseKeys::derive(passphrase, &salt)?;
+    let plain_text = Zeroizing::new(serde_json::to_vec(export)?);
+    let cipher_text = ChaCha20::seal_with_integral_nonce(&*plain_text, &keys.cipher_key)
+        .map_err(|e| WalletBackupError::EncryptionError(format!("Backup encryption failed: {:?}", e)))?;
+    let mut encrypted = EncryptedExport {
+        version: WALLET_EXPORT_VERSION,
+        salt: to_hex(&salt),
+        cipher_text: to_hex(&cipher_text),
+        mac: String::new(),
+    };
+    encrypted.mac = encrypted.calculate_mac(&keys.mac_key);
+    Ok(serde_json::to_vec_pretty(&encrypted)?)
+}
+
+/// Verifies and decrypts an export produced by `encrypt_export`
+pub fn decrypt_export(contents: &[u8], passphrase: &str) -> Result<WalletExport, WalletBackupError> {
+    let encrypted = serde_json::from_slice::<EncryptedExport>(contents)?;
+    if encrypted.version > WALLET_EXPORT_VERSION {
+        return Err(WalletBackupError::UnsupportedVersion(encrypted.version));
+    }
+    let salt = from_hex(&encrypted.salt).map_err(|e| WalletBackupError::InvalidBackup(format!("Invalid salt: {}", e)))?;
+    let keys = PassphraseKeys::derive(passphrase, &salt)?;
+    if encrypted.calculate_mac(&keys.mac_key) != encrypted.mac {
+        return Err(WalletBackupError::IntegrityCheckFailed);
+    }
+    let cipher_text = from_hex(&encrypted.cipher_text)
+        .map_err(|e| WalletBackupError::InvalidBackup(format!("Invalid cipher text: {}", e)))?;
+    let plain_text = ChaCha20::open_with_integral_nonce(&cipher_text, &keys.cipher_key)
+        .map(Zeroizing::new)
+        .map_err(|e| WalletBackupError::EncryptionError(format!("Backup decryption failed: {:?}", e)))?;
+    let export = serde_json::from_slice::<WalletExport>(&plain_text)?;
+    if export.version > WALLET_EXPORT_VERSION {
+        return Err(WalletBackupError::UnsupportedVersion(export.version));
+    }
+    Ok(export)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_crypto::keys::{PublicKey, SecretKey};
+
+    fn random_output(value: u64) -> ExportedOutput {
+        let output = UnblindedOutput::new(MicroTari::from(value), PrivateKey::random(&mut OsRng), None);
+        ExportedOutput::new(&output, ExportedOutputStatus::Unspent)
+    }
+
+    fn random_contact(alias: &str) -> ExportedContact {
+        let (_, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        ExportedContact {
+            alias: alias.to_string(),
+            public_key: public_key.to_hex(),
+        }
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let export = WalletExport::new(
+            vec!["abandon".to_string(); 24],
+            vec![random_output(100)],
+            Vec::new(),
+            vec![random_contact("alice")],
+        );
+        let contents = encrypt_export(&export, "backup passphrase").unwrap();
+        assert_eq!(decrypt_export(&contents, "backup passphrase").unwrap(), export);
+        match decrypt_export(&contents, "wrong passphrase") {
+            Err(WalletBackupError::IntegrityCheckFailed) => {},
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn incremental_chain() {
+        let spent = random_output(100);
+        let removed = random_output(200);
+        let bob = random_contact("bob");
+        let full = WalletExport::new(
+            vec!["abandon".to_string(); 24],
+            vec![spent.clone(), removed.clone()],
+            Vec::new(),
+            vec![bob.clone()],
+        );
+
+        let mut current = full.clone();
+        current.created_at = full.created_at + chrono::Duration::seconds(1);
+        current.outputs[0].status = ExportedOutputStatus::Spent;
+        current.outputs.remove(1);
+        current.outputs.push(random_output(300));
+        current.contacts.push(random_contact("carol"));
+
+        let changes = current.changes_since(&full);
+        assert!(!changes.is_full());
+        assert!(changes.seed_words.is_empty());
+        assert_eq!(changes.outputs.len(), 2);
+        assert_eq!(changes.removed_outputs, vec![removed.spending_key.clone()]);
+        assert_eq!(changes.contacts.len(), 1);
+        assert!(current.changes_since(&current).is_empty());
+
+        let mut restored = WalletExport::from_chain(vec![full.clone(), changes.clone()]).unwrap();
+        restored.outputs.sort_by(|a, b| a.spending_key.cmp(&b.spending_key));
+        current.outputs.sort_by(|a, b| a.spending_key.cmp(&b.spending_key));
+        assert_eq!(restored.outputs, current.outputs);
+        assert_eq!(restored.contacts.len(), 2);
+        assert_eq!(restored.created_at, current.created_at);
+
+        // Changes can only be applied to the export they follow on from
+        let mut state = full;
+        state.created_at = state.created_at - chrono::Duration::seconds(1);
+        match state.apply(&changes) {
+            Err(WalletBackupError::BrokenChain) => {},
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    backup::{
+        error::WalletBackupError,
+        export::{encrypt_export, WalletExport},
+        target::{backup_file_name, expired_backups, BackupTarget},
+    },
+    contacts_service::handle::ContactsServiceHandle,
+    output_manager_service::handle::OutputManagerHandle,
+    transaction_service::handle::TransactionServiceHandle,
+};
+use futures::StreamExt;
+use log::*;
+use std::time::Duration;
+use tari_broadcast_channel::Publisher;
+use tari_shutdown::ShutdownSignal;
+use tokio::{task, time};
+use zeroize::{Zeroize, Zeroizing};
+
+const LOG_TARGET: &str = "wallet::backup::scheduler";
+
+#[derive(Clone)]
+pub struct WalletBackupConfig {
+    /// How often a backup is made
+    pub interval: Duration,
+    /// The number of backup chains kept on the target. Zero keeps every backup.
+    pub retention: usize,
+    /// The number of incremental backups made after each full backup
+    pub incremental_backups: usize,
+    pub target: BackupTarget,
+    /// The passphrase the backups are encrypted with
+    pub passphrase: String,
+}
+
+impl WalletBackupConfig {
+    pub fn new(target: BackupTarget, passphrase: String) -> Self {
+        Self {
+            interval: Duration::from_secs(60 * 60),
+            retention: 7,
+            incremental_backups: 23,
+            target,
+            passphrase,
+        }
+    }
+}
+
+impl Drop for WalletBackupConfig {
+    fn drop(&mut self) {
+        self.passphrase.zeroize();
+    }
+}
+
+/// Events published by the backup scheduler
+#[derive(Clone, Debug, PartialEq)]
+pub enum WalletBackupEvent {
+    /// A backup with the given name was written to the target
+    BackupCompleted {
+        name: String,
+        full: bool,
+    },
+    /// Nothing changed since the previous backup, so no backup was written
+    BackupSkipped,
+    BackupFailed(String),
+}
+
+/// Makes a backup of the wallet each interval. The first backup after the scheduler starts is a full backup.
+pub struct WalletBackupScheduler {
+    config: WalletBackupConfig,
+    output_manager: OutputManagerHandle,
+    transaction_service: TransactionServiceHandle,
+    contacts_service: ContactsServiceHandle,
+    event_publisher: Publisher<WalletBackupEvent>,
+    shutdown_signal: Option<ShutdownSignal>,
+    /// The wallet state at the last backup, the next backup is a full backup if this is `None`
+    last_backup: Option<WalletExport>,
+    incremental_backups: usize,
+}
+
+impl WalletBackupScheduler {
+    pub fn new(
+        config: WalletBackupConfig,
+        output_manager: OutputManagerHandle,
+        transaction_service: TransactionServiceHandle,
+        contacts_service: ContactsServiceHandle,
+        event_publisher: Publisher<WalletBackupEvent>,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self
+    {
+        Self {
+            config,
+            output_manager,
+            transaction_service,
+            contacts_service,
+            event_publisher,
+            shutdown_signal: Some(shutdown_signal),
+            last_backup: None,
+            incremental_backups: 0,
+        }
+    }
+
+    pub async fn run(mut self) {
+        info!(
+            target: LOG_TARGET,
+            "Wallet backup scheduler started. Backing up to {:?} every {:.0?}", self.config.target, self.config.interval
+        );
+        let mut shutdown = self
+            .shutdown_signal
+            .take()
+            .expect("WalletBackupScheduler initialized without a shutdown");
+        let mut interval = time::interval(self.config.interval).fuse();
+        loop {
+            futures::select! {
+                _ = interval.select_next_some() => {
+                    let event = match self.backup().await {
+                        Ok(Some((name, full))) => {
+                            info!(target: LOG_TARGET, "Wallet backup '{}' completed", name);
+                            WalletBackupEvent::BackupCompleted { name, full }
+                        },
+                        Ok(None) => {
+                            debug!(target: LOG_TARGET, "Wallet unchanged since the last backup, skipping backup");
+                            WalletBackupEvent::BackupSkipped
+                        },
+                        Err(err) => {
+                            error!(target: LOG_TARGET, "Wallet backup failed: {}", err);
+                            WalletBackupEvent::BackupFailed(err.to_string())
+                        },
+                    };
+                    let _ = self.event_publisher.send(event).await;
+                },
+                _ = shutdown => {
+                    info!(target: LOG_TARGET, "Wallet backup scheduler shutting down because it received the shutdown signal");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Makes a backup of the current wallet state. Returns the name of the backup and whether it is a full backup, or
+    /// `None` if nothing changed since the last backup. Backup chains outside the retention count are removed from the
+    /// target after each full backup.
+    pub async fn backup(&mut self) -> Result<Option<(String, bool)>, WalletBackupError> {
+        let state = WalletExport::from_wallet(
+            &mut self.output_manager,
+            &mut self.transaction_service,
+            &mut self.contacts_service,
+        )
+        .await?;
+        let export = match self.last_backup.as_ref() {
+            Some(previous) if self.incremental_backups < self.config.incremental_backups => {
+                let changes = state.changes_since(previous);
+                if changes.is_empty() {
+                    return Ok(None);
+                }
+                changes
+            },
+            _ => state.clone(),
+        };
+        let full = export.is_full();
+        let name = backup_file_name(&export.created_at, full);
+
+        let target = self.config.target.clone();
+        let passphrase = Zeroizing::new(self.config.passphrase.clone());
+        let retention = self.config.retention;
+        let backup_name = name.clone();
+        task::spawn_blocking(move || -> Result<(), WalletBackupError> {
+            let contents = encrypt_export(&export, &passphrase)?;
+            target.put(&backup_name, &contents)?;
+            if full {
+                remove_expired_backups(&target, retention);
+            }
+            Ok(())
+        })
+        .await??;
+
+        self.last_backup = Some(state);
+        if full {
+            self.incremental_backups = 0;
+        } else {
+            self.incremental_backups += 1;
+        }
+        Ok(Some((name, full)))
+    }
+}
+
+fn remove_expired_backups(target: &BackupTarget, retention: usize) {
+    let names = match target.list() {
+        Ok(names) => names,
+        Err(err) => {
+            warn!(target: LOG_TARGET, "Could not list wallet backups to remove: {}", err);
+            return;
+        },
+    };
+    for name in expired_backups(&names, retention) {
+        match target.remove(&name) {
+            Ok(_) => debug!(target: LOG_TARGET, "Removed expired wallet backup '{}'", name),
+            Err(err) => warn!(target: LOG_TARGET, "Could not remove expired wallet backup '{}': {}", name, err),
+        }
+    }
+}
@@ -0,0 +1,359 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Backup targets.
+//!
+//! Backups are written to a local directory, an SFTP server or an S3-compatible bucket. Remote targets are accessed
+//! with the `sftp` client and the AWS CLI respectively, which must be installed and able to authenticate with the
+//! target non-interactively, e.g. using an SSH key or the AWS credentials file.
+
+use crate::backup::error::WalletBackupError;
+use chrono::NaiveDateTime;
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
+use std::{
+    fs,
+    io::Write,
+    iter,
+    path::PathBuf,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+const BACKUP_FILE_PREFIX: &str = "wallet_backup_";
+const FULL_BACKUP_SUFFIX: &str = "_full.bak";
+const INCREMENTAL_BACKUP_SUFFIX: &str = "_incremental.bak";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BackupTarget {
+    /// A local directory
+    Directory(PathBuf),
+    /// A directory on an SFTP server. `host` is given as `[user@]host`.
+    Sftp {
+        host: String,
+        port: Option<u16>,
+        path: String,
+    },
+    /// A bucket or bucket prefix given as `s3://bucket[/prefix]`. The endpoint of an S3-compatible service can be
+    /// given, otherwise AWS S3 is used.
+    S3 { url: String, endpoint: Option<String> },
+}
+
+impl BackupTarget {
+    /// Sets the endpoint of an S3 target. Other targets are returned unchanged.
+    pub fn with_s3_endpoint(self, endpoint: Option<String>) -> Self {
+        match self {
+            BackupTarget::S3 { url, .. } => BackupTarget::S3 { url, endpoint },
+            target => target,
+        }
+    }
+
+    /// Writes a backup to the target, replacing a backup with the same name
+    pub fn put(&self, name: &str, contents: &[u8]) -> Result<(), WalletBackupError> {
+        match self {
+            BackupTarget::Directory(path) => {
+                fs::create_dir_all(path)?;
+                let temp_path = path.join(format!("{}.tmp", name));
+                fs::write(&temp_path, contents)?;
+                fs::rename(&temp_path, path.join(name))?;
+                Ok(())
+            },
+            BackupTarget::Sftp { path, .. } => {
+                let temp_path = std::env::temp_dir().join(format!("{}{}", name, random_suffix()));
+                fs::write(&temp_path, contents)?;
+                let result = self.run_sftp(&format!("put {} {}/{}", temp_path.display(), path, name));
+                let _ = fs::remove_file(&temp_path);
+                result.map(|_| ())
+            },
+            BackupTarget::S3 { url, endpoint } => {
+                let mut command = aws_command(endpoint);
+                command.args(&["s3", "cp", "-", &format!("{}/{}", url, name)]);
+                run_command(command, contents).map(|_| ())
+            },
+        }
+    }
+
+    /// Lists the names of the backups on the target in the order they were created
+    pub fn list(&self) -> Result<Vec<String>, WalletBackupError> {
+        let mut names = match self {
+            BackupTarget::Directory(path) => {
+                if !path.exists() {
+                    return Ok(Vec::new());
+                }
+                fs::read_dir(path)?
+                    .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                    .collect::<Vec<_>>()
+            },
+            BackupTarget::Sftp { path, .. } => {
+                let output = self.run_sftp(&format!("ls -1 {}", path))?;
+                output
+                    .lines()
+                    .filter(|line| !line.starts_with("sftp>"))
+                    .filter_map(|line| line.trim().rsplit('/').next().map(ToString::to_string))
+                    .collect()
+            },
+            BackupTarget::S3 { url, endpoint } => {
+                let mut command = aws_command(endpoint);
+                command.args(&["s3", "ls", &format!("{}/", url)]);
+                let output = run_command(command, &[])?;
+                // Each line is `<date> <time> <size> <name>`
+                output
+                    .lines()
+                    .filter_map(|line| line.split_whitespace().last().map(ToString::to_string))
+                    .collect()
+            },
+        };
+        names.retain(|name| is_backup_file(name));
+        names.sort();
+        Ok(names)
+    }
+
+    /// Removes a backup from the target
+    pub fn remove(&self, name: &str) -> Result<(), WalletBackupError> {
+        match self {
+            BackupTarget::Directory(path) => Ok(fs::remove_file(path.join(name))?),
+            BackupTarget::Sftp { path, .. } => self.run_sftp(&format!("rm {}/{}", path, name)).map(|_| ()),
+            BackupTarget::S3 { url, endpoint } => {
+                let mut command = aws_command(endpoint);
+                command.args(&["s3", "rm", &format!("{}/{}", url, name)]);
+                run_command(command, &[]).map(|_| ())
+            },
+        }
+    }
+
+    fn run_sftp(&self, batch_command: &str) -> Result<String, WalletBackupError> {
+        match self {
+            BackupTarget::Sftp { host, port, .. } => {
+                let mut command = Command::new("sftp");
+                command.args(&["-q", "-b", "-"]);
+                if let Some(port) = port {
+                    command.args(&["-P", &port.to_string()]);
+                }
+                command.arg(host);
+                run_command(command, format!("{}\n", batch_command).as_bytes())
+            },
+            _ => Err(WalletBackupError::InvalidTarget("Not an SFTP target".to_string())),
+        }
+    }
+}
+
+impl FromStr for BackupTarget {
+    type Err = WalletBackupError;
+
+    /// Parses `sftp://[user@]host[:port]/path`, `s3://bucket[/prefix]` or a local directory path
+    fn from_str(target: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = strip_prefix(target, "sftp://") {
+            let (authority, path) = match rest.find('/') {
+                Some(index) => (&rest[..index], &rest[index..]),
+                None => (rest, "."),
+            };
+            let (host, port) = match authority.rfind(':') {
+                Some(index) => {
+                    let port = authority[index + 1..]
+                        .parse::<u16>()
+                        .map_err(|e| WalletBackupError::InvalidTarget(format!("Invalid SFTP port: {}", e)))?;
+                    (&authority[..index], Some(port))
+                },
+                None => (authority, None),
+            };
+            if host.is_empty() {
+                return Err(WalletBackupError::InvalidTarget("The SFTP host is missing".to_string()));
+            }
+            return Ok(BackupTarget::Sftp {
+                host: host.to_string(),
+                port,
+                path: path.trim_end_matches('/').to_string(),
+            });
+        }
+        if let Some(rest) = strip_prefix(target, "s3://") {
+            if rest.trim_matches('/').is_empty() {
+                return Err(WalletBackupError::InvalidTarget("The S3 bucket is missing".to_string()));
+            }
+            return Ok(BackupTarget::S3 {
+                url: target.trim_end_matches('/').to_string(),
+                endpoint: None,
+            });
+        }
+        let path = strip_prefix(target, "file://").unwrap_or(target);
+        if path.is_empty() {
+            return Err(WalletBackupError::InvalidTarget(
+                "The backup directory is missing".to_string(),
+            ));
+        }
+        Ok(BackupTarget::Directory(PathBuf::from(path)))
+    }
+}
+
+/// Returns the name of the backup created at `created_at`
+pub fn backup_file_name(created_at: &NaiveDateTime, full: bool) -> String {
+    format!(
+        "{}{}{}",
+        BACKUP_FILE_PREFIX,
+        created_at.format("%Y%m%d%H%M%S%3f"),
+        if full {
+            FULL_BACKUP_SUFFIX
+        } else {
+            INCREMENTAL_BACKUP_SUFFIX
+        }
+    )
+}
+
+pub fn is_full_backup(name: &str) -> bool {
+    name.starts_with(BACKUP_FILE_PREFIX) && name.ends_with(FULL_BACKUP_SUFFIX)
+}
+
+pub fn is_backup_file(name: &str) -> bool {
+    name.starts_with(BACKUP_FILE_PREFIX) && (name.ends_with(FULL_BACKUP_SUFFIX) || name.ends_with(INCREMENTAL_BACKUP_SUFFIX))
+}
+
+/// Returns the backups, from a list in the order they were created, that are older than the `retention` most recent
+/// backup chains. A chain is a full backup and the incremental backups that follow it. A retention of zero keeps
+/// every backup.
+pub fn expired_backups(names: &[String], retention: usize) -> Vec<String> {
+    let full_backups = names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| is_full_backup(name))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if retention == 0 || full_backups.len() <= retention {
+        return Vec::new();
+    }
+    names[..full_backups[full_backups.len() - retention]].to_vec()
+}
+
+fn strip_prefix<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    if s.starts_with(prefix) {
+        Some(&s[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+fn random_suffix() -> String {
+    iter::repeat(()).map(|_| OsRng.sample(Alphanumeric)).take(8).collect()
+}
+
+fn aws_command(endpoint: &Option<String>) -> Command {
+    let mut command = Command::new("aws");
+    if let Some(endpoint) = endpoint {
+        command.args(&["--endpoint-url", endpoint]);
+    }
+    command
+}
+
+/// Runs the command, writing `input` to its stdin, and returns its stdout
+fn run_command(mut command: Command, input: &[u8]) -> Result<String, WalletBackupError> {
+    let program = format!("{:?}", command);
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| WalletBackupError::TargetCommandFailed(format!("Could not run {}: {}", program, e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(WalletBackupError::TargetCommandFailed(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+    use tempdir::TempDir;
+
+    #[test]
+    fn parse_targets() {
+        assert_eq!(
+            "sftp://backup@example.com:2222/var/backups/"
+                .parse::<BackupTarget>()
+                .unwrap(),
+            BackupTarget::Sftp {
+                host: "backup@example.com".to_string(),
+                port: Some(2222),
+                path: "/var/backups".to_string(),
+            }
+        );
+        assert_eq!(
+            "s3://bucket/wallet/"
+                .parse::<BackupTarget>()
+                .unwrap()
+                .with_s3_endpoint(Some("https://s3.example.com".to_string())),
+            BackupTarget::S3 {
+                url: "s3://bucket/wallet".to_string(),
+                endpoint: Some("https://s3.example.com".to_string()),
+            }
+        );
+        assert_eq!(
+            "/home/tari/backups".parse::<BackupTarget>().unwrap(),
+            BackupTarget::Directory(PathBuf::from("/home/tari/backups"))
+        );
+        assert!("sftp://:22/backups".parse::<BackupTarget>().is_err());
+        assert!("s3://".parse::<BackupTarget>().is_err());
+    }
+
+    #[test]
+    fn expire_old_chains() {
+        let names = [
+            "a_full",
+            "b_incremental",
+            "c_full",
+            "d_incremental",
+            "e_incremental",
+            "f_full",
+        ]
+        .iter()
+        .map(|name| format!("{}{}.bak", BACKUP_FILE_PREFIX, name))
+        .collect::<Vec<_>>();
+        assert_eq!(expired_backups(&names, 2), names[..2].to_vec());
+        assert_eq!(expired_backups(&names, 1), names[..5].to_vec());
+        assert!(expired_backups(&names, 3).is_empty());
+        assert!(expired_backups(&names, 0).is_empty());
+    }
+
+    #[test]
+    fn directory_target() {
+        let temp_dir = TempDir::new("wallet_backups").unwrap();
+        let target = BackupTarget::Directory(temp_dir.path().join("backups"));
+        assert!(target.list().unwrap().is_empty());
+
+        let now = Utc::now().naive_utc();
+        let full = backup_file_name(&now, true);
+        let incremental = backup_file_name(&(now + chrono::Duration::seconds(1)), false);
+        target.put(&incremental, b"incremental").unwrap();
+        target.put(&full, b"full").unwrap();
+        fs::write(temp_dir.path().join("backups").join("unrelated.txt"), b"").unwrap();
+        assert_eq!(target.list().unwrap(), vec![full.clone(), incremental.clone()]);
+
+        target.remove(&full).unwrap();
+        assert_eq!(target.list().unwrap(), vec![incremental]);
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::handle::OutputManagerHandle,
+    transaction_service::handle::TransactionServiceHandle,
+};
+use futures::StreamExt;
+use log::*;
+use std::sync::{Arc, RwLock};
+use tari_comms::{
+    connectivity::{ConnectivityEvent, ConnectivityRequester, DisconnectReason},
+    peer_manager::NodeId,
+    types::CommsPublicKey,
+};
+use tari_shutdown::ShutdownSignal;
+
+const LOG_TARGET: &str = "wallet::base_node_failover";
+
+/// The list of base nodes known to the wallet. The first base node in the list is the one currently in use.
+pub type BaseNodePeers = Arc<RwLock<Vec<CommsPublicKey>>>;
+
+/// Listens for connectivity events and switches the wallet services over to the next known base node when the
+/// connection to the current base node is lost, cannot be established or the base node is banned.
+pub struct BaseNodeFailover {
+    base_node_peers: BaseNodePeers,
+    connectivity: ConnectivityRequester,
+    transaction_service: TransactionServiceHandle,
+    output_manager_service: OutputManagerHandle,
+    shutdown_signal: Option<ShutdownSignal>,
+}
+
+impl BaseNodeFailover {
+    pub fn new(
+        base_node_peers: BaseNodePeers,
+        connectivity: ConnectivityRequester,
+        transaction_service: TransactionServiceHandle,
+        output_manager_service: OutputManagerHandle,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self
+    {
+        Self {
+            base_node_peers,
+            connectivity,
+            transaction_service,
+            output_manager_service,
+            shutdown_signal: Some(shutdown_signal),
+        }
+    }
+
+    pub async fn run(mut self) {
+        let mut shutdown = self
+            .shutdown_signal
+            .take()
+            .expect("BaseNodeFailover initialized without a shutdown");
+        let mut connectivity_events = self.connectivity.subscribe_event_stream().fuse();
+
+        loop {
+            futures::select! {
+                event = connectivity_events.select_next_some() => {
+                    match event {
+                        Ok(event) => self.handle_connectivity_event(&event).await,
+                        Err(err) => {
+                            warn!(target: LOG_TARGET, "Error receiving connectivity event: {:?}", err);
+                        },
+                    }
+                },
+
+                _ = shutdown => {
+                    info!(target: LOG_TARGET, "Base node failover shutting down because it received the shutdown signal");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn handle_connectivity_event(&mut self, event: &ConnectivityEvent) {
+        use ConnectivityEvent::*;
+        let node_id = match event {
+            PeerDisconnected { node_id, reason } if *reason != DisconnectReason::Requested => node_id,
+            ConnectFailed(node_id, _) | Banned(node_id) => node_id,
+            _ => return,
+        };
+
+        let next_base_node = match self.failover_from(node_id) {
+            Some(public_key) => public_key,
+            None => return,
+        };
+
+        info!(
+            target: LOG_TARGET,
+            "Lost connectivity to base node '{}' ({}). Switching to base node '{}'.",
+            node_id.short_str(),
+            event,
+            next_base_node
+        );
+        if let Err(err) = self
+            .transaction_service
+            .set_base_node_public_key(next_base_node.clone())
+            .await
+        {
+            error!(
+                target: LOG_TARGET,
+                "Failed to set base node for the transaction service because '{:?}'", err
+            );
+        }
+        if let Err(err) = self
+            .output_manager_service
+            .set_base_node_public_key(next_base_node)
+            .await
+        {
+            error!(
+                target: LOG_TARGET,
+                "Failed to set base node for the output manager service because '{:?}'", err
+            );
+        }
+    }
+
+    /// If the given node is the current base node and another base node is known, the current base node is moved to
+    /// the back of the list and the public key of the new current base node is returned.
+    fn failover_from(&self, node_id: &NodeId) -> Option<CommsPublicKey> {
+        let mut base_node_peers = acquire_write_lock!(self.base_node_peers);
+        if base_node_peers.len() < 2 {
+            return None;
+        }
+        let current_node_id = NodeId::from_key(&base_node_peers[0]).ok()?;
+        if &current_node_id != node_id {
+            return None;
+        }
+        base_node_peers.rotate_left(1);
+        Some(base_node_peers[0].clone())
+    }
+}
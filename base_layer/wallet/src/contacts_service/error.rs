@@ -24,6 +24,7 @@ use crate::contacts_service::storage::database::DbKey;
 use derive_error::Error;
 use diesel::result::Error as DieselError;
 use tari_service_framework::reply_channel::TransportChannelError;
+use tokio::task::JoinError;
 
 #[derive(Debug, Error, PartialEq)]
 pub enum ContactsServiceError {
@@ -55,3 +56,9 @@ pub enum ContactsServiceStorageError {
     #[error(msg_embedded, non_std, no_from)]
     BlockingTaskSpawnError(String),
 }
+
+impl From<JoinError> for ContactsServiceStorageError {
+    fn from(err: JoinError) -> Self {
+        ContactsServiceStorageError::BlockingTaskSpawnError(err.to_string())
+    }
+}
@@ -75,6 +75,7 @@ where T: ContactsBackend + 'static
 
         // Register handle before waiting for handles to be ready
         handles_fut.register(contacts_handle);
+        let ready_signal = handles_fut.ready_signal::<ContactsServiceHandle>();
 
         let backend = self
             .backend
@@ -83,6 +84,7 @@ where T: ContactsBackend + 'static
 
         executor.spawn(async move {
             let service = ContactsService::new(receiver, ContactsDatabase::new(backend)).start();
+            ready_signal.set_ready();
 
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
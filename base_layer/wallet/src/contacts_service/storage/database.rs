@@ -20,12 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::contacts_service::error::ContactsServiceStorageError;
+use crate::{contacts_service::error::ContactsServiceStorageError, storage::blocking_adapter::BlockingAdapter};
+use futures::future::BoxFuture;
 use log::*;
-use std::{
-    fmt::{Display, Error, Formatter},
-    sync::Arc,
-};
+use std::fmt::{Display, Error, Formatter};
 use tari_comms::types::CommsPublicKey;
 
 const LOG_TARGET: &str = "wallet::contacts_service::database";
@@ -44,6 +42,25 @@ pub trait ContactsBackend: Send + Sync {
     fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, ContactsServiceStorageError>;
 }
 
+/// The async counterpart of `ContactsBackend` used by the `ContactsDatabase`. It is implemented for a `BlockingAdapter`
+/// over any synchronous `ContactsBackend`.
+pub trait AsyncContactsBackend: Send + Sync {
+    fn fetch(&self, key: DbKey) -> BoxFuture<'static, Result<Option<DbValue>, ContactsServiceStorageError>>;
+    fn write(&self, op: WriteOperation) -> BoxFuture<'static, Result<Option<DbValue>, ContactsServiceStorageError>>;
+}
+
+impl<T> AsyncContactsBackend for BlockingAdapter<T>
+where T: ContactsBackend + 'static
+{
+    fn fetch(&self, key: DbKey) -> BoxFuture<'static, Result<Option<DbValue>, ContactsServiceStorageError>> {
+        self.run(move |db| db.fetch(&key))
+    }
+
+    fn write(&self, op: WriteOperation) -> BoxFuture<'static, Result<Option<DbValue>, ContactsServiceStorageError>> {
+        self.run(move |db| db.write(op))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DbKey {
     Contact(CommsPublicKey),
@@ -80,64 +97,55 @@ macro_rules! fetch {
 pub struct ContactsDatabase<T>
 where T: ContactsBackend
 {
-    db: Arc<T>,
+    db: BlockingAdapter<T>,
 }
 
 impl<T> ContactsDatabase<T>
 where T: ContactsBackend + 'static
 {
     pub fn new(db: T) -> Self {
-        Self { db: Arc::new(db) }
+        Self {
+            db: BlockingAdapter::new(db),
+        }
     }
 
     pub async fn get_contact(&self, pub_key: CommsPublicKey) -> Result<Contact, ContactsServiceStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || fetch!(db_clone, pub_key.clone(), Contact))
-            .await
-            .or_else(|err| Err(ContactsServiceStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.run(move |db| fetch!(db, pub_key.clone(), Contact)).await
     }
 
     pub async fn get_contacts(&self) -> Result<Vec<Contact>, ContactsServiceStorageError> {
-        let db_clone = self.db.clone();
-
-        let c = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::Contacts) {
-            Ok(None) => log_error(
-                DbKey::Contacts,
-                ContactsServiceStorageError::UnexpectedResult("Could not retrieve contacts".to_string()),
-            ),
-            Ok(Some(DbValue::Contacts(c))) => Ok(c),
-            Ok(Some(other)) => unexpected_result(DbKey::Contacts, other),
-            Err(e) => log_error(DbKey::Contacts, e),
-        })
-        .await
-        .or_else(|err| Err(ContactsServiceStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let c = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::Contacts) {
+                Ok(None) => log_error(
+                    DbKey::Contacts,
+                    ContactsServiceStorageError::UnexpectedResult("Could not retrieve contacts".to_string()),
+                ),
+                Ok(Some(DbValue::Contacts(c))) => Ok(c),
+                Ok(Some(other)) => unexpected_result(DbKey::Contacts, other),
+                Err(e) => log_error(DbKey::Contacts, e),
+            })
+            .await?;
         Ok(c)
     }
 
     pub async fn upsert_contact(&self, contact: Contact) -> Result<(), ContactsServiceStorageError> {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Upsert(DbKeyValuePair::Contact(
+        self.db
+            .write(WriteOperation::Upsert(DbKeyValuePair::Contact(
                 contact.public_key.clone(),
                 contact,
             )))
-        })
-        .await
-        .or_else(|err| Err(ContactsServiceStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        .await?;
         Ok(())
     }
 
     pub async fn remove_contact(&self, pub_key: CommsPublicKey) -> Result<Contact, ContactsServiceStorageError> {
-        let db_clone = self.db.clone();
         let pub_key_clone = pub_key.clone();
-        let result =
-            tokio::task::spawn_blocking(move || db_clone.write(WriteOperation::Remove(DbKey::Contact(pub_key_clone))))
-                .await
-                .or_else(|err| Err(ContactsServiceStorageError::BlockingTaskSpawnError(err.to_string())))
-                .and_then(|inner_result| inner_result)?
-                .ok_or_else(|| ContactsServiceStorageError::ValueNotFound(DbKey::Contact(pub_key.clone())))?;
+        let result = self
+            .db
+            .write(WriteOperation::Remove(DbKey::Contact(pub_key_clone)))
+            .await?
+            .ok_or_else(|| ContactsServiceStorageError::ValueNotFound(DbKey::Contact(pub_key.clone())))?;
 
         match result {
             DbValue::Contact(c) => Ok(*c),
@@ -20,11 +20,13 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::contacts_service::{
-    error::ContactsServiceStorageError,
-    storage::database::{Contact, ContactsBackend, DbKey, DbKeyValuePair, DbValue, WriteOperation},
+use crate::{
+    contacts_service::{
+        error::ContactsServiceStorageError,
+        storage::database::{Contact, ContactsBackend, DbKey, DbKeyValuePair, DbValue, WriteOperation},
+    },
+    storage::memory_store::MemoryStore,
 };
-use std::sync::{Arc, RwLock};
 
 #[derive(Default)]
 pub struct InnerDatabase {
@@ -39,52 +41,55 @@ impl InnerDatabase {
 
 #[derive(Default)]
 pub struct ContactsServiceMemoryDatabase {
-    db: Arc<RwLock<InnerDatabase>>,
+    db: MemoryStore<InnerDatabase>,
 }
 
 impl ContactsServiceMemoryDatabase {
     pub fn new() -> Self {
         Self {
-            db: Arc::new(RwLock::new(InnerDatabase::new())),
+            db: MemoryStore::new(InnerDatabase::new()),
         }
     }
 }
 
 impl ContactsBackend for ContactsServiceMemoryDatabase {
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, ContactsServiceStorageError> {
-        let db = acquire_read_lock!(self.db);
-        let result = match key {
-            DbKey::Contact(pk) => db
-                .contacts
-                .iter()
-                .find(|v| &v.public_key == pk)
-                .map(|c| DbValue::Contact(Box::new(c.clone()))),
-            DbKey::Contacts => Some(DbValue::Contacts(db.contacts.clone())),
-        };
+        let key = key.clone();
+        self.db.run(move |db| {
+            let result = match &key {
+                DbKey::Contact(pk) => db
+                    .contacts
+                    .iter()
+                    .find(|v| &v.public_key == pk)
+                    .map(|c| DbValue::Contact(Box::new(c.clone()))),
+                DbKey::Contacts => Some(DbValue::Contacts(db.contacts.clone())),
+            };
 
-        Ok(result)
+            Ok(result)
+        })
     }
 
     fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, ContactsServiceStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-        match op {
-            WriteOperation::Upsert(kvp) => match kvp {
-                DbKeyValuePair::Contact(pk, c) => match db.contacts.iter_mut().find(|i| i.public_key == pk) {
-                    None => db.contacts.push(c),
-                    Some(existing_contact) => existing_contact.alias = c.alias,
+        self.db.run(move |db| {
+            match op {
+                WriteOperation::Upsert(kvp) => match kvp {
+                    DbKeyValuePair::Contact(pk, c) => match db.contacts.iter_mut().find(|i| i.public_key == pk) {
+                        None => db.contacts.push(c),
+                        Some(existing_contact) => existing_contact.alias = c.alias,
+                    },
                 },
-            },
-            WriteOperation::Remove(k) => match k {
-                DbKey::Contact(pk) => match db.contacts.iter().position(|c| c.public_key == pk) {
-                    None => return Err(ContactsServiceStorageError::ValueNotFound(DbKey::Contact(pk))),
-                    Some(pos) => return Ok(Some(DbValue::Contact(Box::new(db.contacts.remove(pos))))),
+                WriteOperation::Remove(k) => match k {
+                    DbKey::Contact(pk) => match db.contacts.iter().position(|c| c.public_key == pk) {
+                        None => return Err(ContactsServiceStorageError::ValueNotFound(DbKey::Contact(pk))),
+                        Some(pos) => return Ok(Some(DbValue::Contact(Box::new(db.contacts.remove(pos))))),
+                    },
+                    DbKey::Contacts => {
+                        return Err(ContactsServiceStorageError::OperationNotSupported);
+                    },
                 },
-                DbKey::Contacts => {
-                    return Err(ContactsServiceStorageError::OperationNotSupported);
-                },
-            },
-        }
+            }
 
-        Ok(None)
+            Ok(None)
+        })
     }
 }
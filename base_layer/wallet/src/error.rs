@@ -32,6 +32,7 @@ use log::SetLoggerError;
 use serde_json::Error as SerdeJsonError;
 use tari_comms::{multiaddr, peer_manager::PeerManagerError};
 use tari_p2p::{initialization::CommsInitializationError, services::liveness::error::LivenessError};
+use tokio::task::JoinError;
 
 #[derive(Debug, Error)]
 pub enum WalletError {
@@ -44,6 +45,21 @@ pub enum WalletError {
     SetLoggerError(SetLoggerError),
     ContactsServiceError(ContactsServiceError),
     LivenessServiceError(LivenessError),
+    LocalizationError(LocalizationError),
+}
+
+#[derive(Debug, Error)]
+pub enum LocalizationError {
+    /// The locale is not a valid language tag
+    #[error(non_std, no_from)]
+    InvalidLocale(String),
+    /// No message catalogue was found for the locale
+    #[error(non_std, no_from)]
+    LocaleNotFound(String),
+    /// The message catalogue could not be read
+    IoError(std::io::Error),
+    /// The message catalogue is not a valid JSON map of message ids to messages
+    SerdeJsonError(SerdeJsonError),
 }
 
 #[derive(Debug, Error)]
@@ -73,3 +89,9 @@ pub enum WalletStorageError {
     /// The storage path was invalid unicode or not supported by the host OS
     InvalidUnicodePath,
 }
+
+impl From<JoinError> for WalletStorageError {
+    fn from(err: JoinError) -> Self {
+        WalletStorageError::BlockingTaskSpawnError(err.to_string())
+    }
+}
@@ -4,9 +4,13 @@
 
 #[macro_use]
 mod macros;
+pub mod backup;
+pub mod base_node_failover;
 pub mod contacts_service;
 pub mod error;
+pub mod localization;
 pub mod output_manager_service;
+pub mod signer;
 pub mod storage;
 pub mod transaction_service;
 pub mod types;
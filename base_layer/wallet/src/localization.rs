@@ -0,0 +1,186 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! User-facing wallet strings are looked up in a [MessageCatalogue] by a stable message id, e.g.
+//! `transaction_status.mined`, so that wallet UIs and FFI clients can show them in the user's language instead of
+//! hard-coding English descriptions of the wallet's enums. The English messages are built in. Other locales are loaded
+//! from a `<locale>.json` file in the catalogue directory, which contains a map of message ids to translated messages.
+//! A message that is missing from a translation falls back to English.
+
+use crate::{error::LocalizationError, transaction_service::storage::database::TransactionStatus};
+use std::{collections::HashMap, fs, path::PathBuf};
+
+/// The locale of the built in messages
+pub const DEFAULT_LOCALE: &str = "en";
+
+const ENGLISH_MESSAGES: &[(&str, &str)] = &[
+    (
+        "transaction_status.completed",
+        "Completed, waiting to be broadcast to the network",
+    ),
+    ("transaction_status.broadcast", "Broadcast, waiting to be mined"),
+    ("transaction_status.mined", "Mined"),
+    ("transaction_status.imported", "Imported"),
+    (
+        "transaction_status.pending",
+        "Pending, waiting for the other party to respond",
+    ),
+    ("transaction_status.cancelled", "Cancelled"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocaleConfig {
+    /// The language tag of the locale, e.g. `en` or `pt-BR`
+    pub locale: String,
+    /// The directory containing the `<locale>.json` message catalogues. Only the built in English messages are
+    /// available if this is `None`.
+    pub catalogue_dir: Option<PathBuf>,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            locale: DEFAULT_LOCALE.to_string(),
+            catalogue_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageCatalogue {
+    locale: String,
+    messages: HashMap<String, String>,
+}
+
+impl Default for MessageCatalogue {
+    /// The built in English catalogue
+    fn default() -> Self {
+        Self {
+            locale: DEFAULT_LOCALE.to_string(),
+            messages: ENGLISH_MESSAGES
+                .iter()
+                .map(|(id, message)| (id.to_string(), message.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl MessageCatalogue {
+    /// Load the message catalogue of the configured locale. A catalogue file is only required for locales other than
+    /// the default English locale.
+    pub fn load(config: &LocaleConfig) -> Result<Self, LocalizationError> {
+        let locale = config.locale.as_str();
+        // The locale is used as a file name so only language tag characters are allowed
+        if locale.is_empty() ||
+            !locale
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(LocalizationError::InvalidLocale(locale.to_string()));
+        }
+
+        let mut catalogue = Self::default();
+        let path = config
+            .catalogue_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", locale)))
+            .filter(|path| path.exists());
+        match path {
+            Some(path) => {
+                let translations: HashMap<String, String> = serde_json::from_slice(&fs::read(path)?)?;
+                catalogue.messages.extend(translations);
+            },
+            None if locale == DEFAULT_LOCALE => {},
+            None => return Err(LocalizationError::LocaleNotFound(locale.to_string())),
+        }
+        catalogue.locale = locale.to_string();
+        Ok(catalogue)
+    }
+
+    /// The locale of this catalogue
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Returns the message with the given id, or `None` if the id is unknown
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.messages.get(id).map(String::as_str)
+    }
+
+    /// Returns the description of a transaction status
+    pub fn transaction_status(&self, status: &TransactionStatus) -> &str {
+        let id = status.message_id();
+        self.get(id).unwrap_or(id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn translations_fall_back_to_english() {
+        let dir = TempDir::new("localization").unwrap();
+        fs::write(
+            dir.path().join("es.json"),
+            r#"{"transaction_status.mined": "Minada", "error.101": "Fondos insuficientes"}"#,
+        )
+        .unwrap();
+
+        let english = MessageCatalogue::load(&LocaleConfig::default()).unwrap();
+        assert_eq!(english.locale(), "en");
+        assert_eq!(english.transaction_status(&TransactionStatus::Mined), "Mined");
+        assert_eq!(english.get("error.101"), None);
+
+        let spanish = MessageCatalogue::load(&LocaleConfig {
+            locale: "es".to_string(),
+            catalogue_dir: Some(dir.path().to_path_buf()),
+        })
+        .unwrap();
+        assert_eq!(spanish.locale(), "es");
+        assert_eq!(spanish.transaction_status(&TransactionStatus::Mined), "Minada");
+        assert_eq!(spanish.get("error.101"), Some("Fondos insuficientes"));
+        assert_eq!(spanish.transaction_status(&TransactionStatus::Cancelled), "Cancelled");
+    }
+
+    #[test]
+    fn unknown_or_invalid_locale() {
+        let dir = TempDir::new("localization").unwrap();
+        let config = LocaleConfig {
+            locale: "fr".to_string(),
+            catalogue_dir: Some(dir.path().to_path_buf()),
+        };
+        match MessageCatalogue::load(&config) {
+            Err(LocalizationError::LocaleNotFound(locale)) => assert_eq!(locale, "fr"),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        let config = LocaleConfig {
+            locale: "../fr".to_string(),
+            catalogue_dir: Some(dir.path().to_path_buf()),
+        };
+        match MessageCatalogue::load(&config) {
+            Err(LocalizationError::InvalidLocale(_)) => {},
+            r => panic!("Unexpected result {:?}", r),
+        }
+    }
+}
@@ -20,17 +20,40 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::output_manager_service::recovery::DEFAULT_RECOVERY_BYTE;
 use std::time::Duration;
+use tari_common::retry::RetryPolicy;
+use tari_core::transactions::{tari_amount::MicroTari, transaction::MINIMUM_TRANSACTION_FEE};
 
 #[derive(Clone)]
 pub struct OutputManagerServiceConfig {
     pub base_node_query_timeout: Duration,
+    // Every consecutive unanswered UTXO query waits this policy's backoff delay longer than `base_node_query_timeout`
+    // before it times out and is re-sent. Queries are no longer re-sent once the maximum number of attempts is
+    // reached.
+    pub base_node_query_retry_policy: RetryPolicy,
+    // Outputs worth less than this are dust. The wallet refuses to send or split into dust amounts and adds dust
+    // change to the transaction fee instead of creating a change output. The default is the minimum transaction
+    // fee, as an output worth less than that can never pay for its own spend.
+    pub dust_threshold: MicroTari,
+    // Written into the rewindable range proof of every output the wallet creates. Recovery only picks up outputs
+    // with the same recovery byte, so wallets sharing seed words can keep their outputs apart by using different
+    // values.
+    pub recovery_byte: u8,
+    // How long spending stays unlocked after the spend passphrase is entered when the wallet is in receive-only mode
+    pub spend_unlock_period: Duration,
 }
 
 impl Default for OutputManagerServiceConfig {
     fn default() -> Self {
         Self {
             base_node_query_timeout: Duration::from_secs(30),
+            base_node_query_retry_policy: RetryPolicy::exponential(Duration::from_secs(10))
+                .with_max_delay(Duration::from_secs(5 * 60))
+                .with_jitter(0.25),
+            dust_threshold: MINIMUM_TRANSACTION_FEE,
+            recovery_byte: DEFAULT_RECOVERY_BYTE,
+            spend_unlock_period: Duration::from_secs(5 * 60),
         }
     }
 }
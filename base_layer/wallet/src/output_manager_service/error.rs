@@ -29,6 +29,7 @@ use tari_crypto::tari_utilities::ByteArrayError;
 use tari_key_manager::{key_manager::KeyManagerError, mnemonic::MnemonicError};
 use tari_service_framework::reply_channel::TransportChannelError;
 use time::OutOfRangeError;
+use tokio::task::JoinError;
 
 #[derive(Debug, Error)]
 pub enum OutputManagerError {
@@ -41,6 +42,7 @@ pub enum OutputManagerError {
     OutputManagerStorageError(OutputManagerStorageError),
     MnemonicError(MnemonicError),
     KeyManagerError(KeyManagerError),
+    KeyManagerBackendError(KeyManagerBackendError),
     TransactionError(TransactionError),
     DhtOutboundError(DhtOutboundError),
     #[error(msg_embedded, no_from, non_std)]
@@ -49,6 +51,8 @@ pub enum OutputManagerError {
     IncompleteTransaction,
     /// Not enough funds to fulfil transaction
     NotEnoughFunds,
+    /// The amount is below the dust threshold and the output would be too small to spend economically
+    AmountBelowDustThreshold,
     /// Output already exists
     DuplicateOutput,
     /// Error sending a message to the public API
@@ -66,6 +70,41 @@ pub enum OutputManagerError {
     NoBaseNodeKeysProvided,
     /// An error occured sending an event out on the event stream
     EventStreamError,
+    /// The outputs of the previous master key rotation have not all been migrated yet
+    KeyRotationInProgress,
+    /// The master key cannot be rotated while transactions are pending
+    TransactionsPending,
+    /// The master key has not been rotated so there are no outputs to migrate
+    NoOutputMigrationPlan,
+    /// The previous output migration transaction has not been confirmed or cancelled yet
+    MigrationBatchPending,
+    /// The outputs that remain to be migrated are encumbered by pending transactions
+    MigrationOutputsEncumbered,
+    /// An invoice with this id already exists
+    DuplicateInvoice,
+    /// No invoice with this id exists
+    InvoiceNotFound,
+    /// The invoice has already been paid by another transaction
+    InvoiceAlreadyPaid,
+    /// The wallet is in receive-only mode and spending has not been unlocked
+    SpendingLocked,
+    /// The passphrase does not unlock spending
+    InvalidSpendPassphrase,
+    /// The wallet is already in receive-only mode
+    ReceiveOnlyModeEnabled,
+    /// The wallet is not in receive-only mode
+    ReceiveOnlyModeNotEnabled,
+    #[error(msg_embedded, no_from, non_std)]
+    PassphraseHashError(String),
+}
+
+#[derive(Debug, Error)]
+pub enum KeyManagerBackendError {
+    ByteArrayError(ByteArrayError),
+    /// The key manager backend does not support this operation
+    OperationNotSupported,
+    #[error(msg_embedded, no_from, non_std)]
+    BackendError(String),
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -78,6 +117,8 @@ pub enum OutputManagerStorageError {
     UnexpectedResult(String),
     /// If an pending transaction does not exist to be confirmed
     PendingTransactionNotFound,
+    /// If a confirmed transaction does not exist to be reverted
+    ConfirmedTransactionNotFound,
     /// This write operation is not supported for provided DbKey
     OperationNotSupported,
     /// Could not find all values specified for batch operation
@@ -97,3 +138,9 @@ pub enum OutputManagerStorageError {
     #[error(msg_embedded, non_std, no_from)]
     BlockingTaskSpawnError(String),
 }
+
+impl From<JoinError> for OutputManagerStorageError {
+    fn from(err: JoinError) -> Self {
+        OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())
+    }
+}
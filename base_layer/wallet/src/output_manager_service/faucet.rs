@@ -0,0 +1,132 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Parsing of outputs that were created out-of-band, such as the testnet faucet's `keys.json` file or the genesis
+//! block generator's output file, so that they can be imported into the wallet.
+
+use crate::output_manager_service::error::OutputManagerError;
+use serde::Deserialize;
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::{OutputFeatures, UnblindedOutput},
+    types::{Commitment, CommitmentFactory, PrivateKey},
+};
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, tari_utilities::hex::Hex};
+
+/// A single output as written by the faucet. The genesis block generator uses `spending_key` for the key and adds the
+/// maturity; the commitment is optional.
+#[derive(Debug, Deserialize)]
+struct FaucetOutput {
+    #[serde(alias = "spending_key")]
+    key: String,
+    value: u64,
+    #[serde(default)]
+    maturity: u64,
+    #[serde(default)]
+    commitment: Option<String>,
+}
+
+/// Parse a file of out-of-band outputs. Both a JSON array of outputs and one JSON output per line, as written by the
+/// faucet, are accepted. If an output includes its commitment, it is checked against the spending key and value.
+pub fn parse_faucet_outputs(
+    contents: &str,
+    factory: &CommitmentFactory,
+) -> Result<Vec<UnblindedOutput>, OutputManagerError>
+{
+    let outputs = if contents.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<FaucetOutput>>(contents)
+            .map_err(|e| OutputManagerError::ConversionError(format!("Invalid outputs file: {}", e)))?
+    } else {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str::<FaucetOutput>(line).map_err(|e| {
+                    OutputManagerError::ConversionError(format!("Invalid output on line {}: {}", i + 1, e))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    outputs
+        .into_iter()
+        .map(|output| {
+            let spending_key = PrivateKey::from_hex(&output.key)
+                .map_err(|e| OutputManagerError::ConversionError(format!("Invalid spending key: {}", e)))?;
+            if let Some(commitment) = output.commitment {
+                let commitment = Commitment::from_hex(&commitment)
+                    .map_err(|e| OutputManagerError::ConversionError(format!("Invalid commitment: {}", e)))?;
+                if !factory.open_value(&spending_key, output.value, &commitment) {
+                    return Err(OutputManagerError::ConversionError(format!(
+                        "The commitment {} does not match the spending key and value",
+                        commitment.to_hex()
+                    )));
+                }
+            }
+            Ok(UnblindedOutput::new(
+                MicroTari::from(output.value),
+                spending_key,
+                Some(OutputFeatures::with_maturity(output.maturity)),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::SecretKey;
+
+    #[test]
+    fn parse_faucet_and_genesis_formats() {
+        let factory = CommitmentFactory::default();
+        let key = PrivateKey::random(&mut OsRng);
+        let commitment = factory.commit_value(&key, 5000);
+
+        let faucet = format!(
+            "{{\"key\":\"{}\",\"value\":5000,\"commitment\":\"{}\",\"proof\":\"00\"}}\n\n",
+            key.to_hex(),
+            commitment.to_hex()
+        );
+        let outputs = parse_faucet_outputs(&faucet, &factory).unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].value, MicroTari::from(5000));
+        assert_eq!(outputs[0].spending_key, key);
+
+        let genesis = format!(
+            "[{{\"value\":5000,\"spending_key\":\"{}\",\"maturity\":60}}]",
+            key.to_hex()
+        );
+        let outputs = parse_faucet_outputs(&genesis, &factory).unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].features.maturity, 60);
+
+        let wrong_value = format!(
+            "{{\"key\":\"{}\",\"value\":4000,\"commitment\":\"{}\"}}",
+            key.to_hex(),
+            commitment.to_hex()
+        );
+        assert!(parse_faucet_outputs(&wrong_value, &factory).is_err());
+    }
+}
@@ -22,8 +22,9 @@
 
 use crate::output_manager_service::{
     error::OutputManagerError,
-    service::Balance,
-    storage::database::PendingTransactionOutputs,
+    service::{Balance, ImportedOutputs},
+    storage::database::{BalanceAuditEntry, Invoice, OutputMigrationPlan, PendingTransactionOutputs},
+    TxId,
 };
 use futures::{stream::Fuse, StreamExt};
 use std::{collections::HashMap, fmt, time::Duration};
@@ -31,10 +32,11 @@ use tari_broadcast_channel::Subscriber;
 use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
-    transaction::{TransactionInput, TransactionOutput, UnblindedOutput},
-    types::PrivateKey,
+    transaction::{Transaction, TransactionInput, TransactionOutput, UnblindedOutput},
+    types::{PrivateKey, PublicKey, RewindData},
     SenderTransactionProtocol,
 };
+use tari_secret::Secret;
 use tari_service_framework::reply_channel::SenderService;
 use tower::Service;
 
@@ -43,12 +45,15 @@ use tower::Service;
 pub enum OutputManagerRequest {
     GetBalance,
     AddOutput(UnblindedOutput),
+    ImportOutputs(Vec<UnblindedOutput>),
+    RecoverOutputs(Vec<TransactionOutput>),
     GetRecipientKey((u64, MicroTari)),
     GetCoinbaseKey((u64, MicroTari, u64)),
     ConfirmPendingTransaction(u64),
     ConfirmTransaction((u64, Vec<TransactionInput>, Vec<TransactionOutput>)),
     PrepareToSendTransaction((MicroTari, MicroTari, Option<u64>, String)),
     CancelTransaction(u64),
+    RevertTransaction(u64),
     TimeoutTransactions(Duration),
     GetPendingTransactions,
     GetSpentOutputs,
@@ -57,6 +62,18 @@ pub enum OutputManagerRequest {
     GetSeedWords,
     SetBaseNodePublicKey(CommsPublicKey),
     SyncWithBaseNode,
+    CreateCoinSplit((MicroTari, usize, MicroTari, Option<u64>)),
+    RotateMasterKey((MicroTari, usize)),
+    GetOutputMigrationPlan,
+    MigrateOutputs,
+    CreateInvoice(String),
+    GetInvoiceRecipientKey((String, u64, MicroTari)),
+    GetInvoices,
+    EnableReceiveOnlyMode(Secret<String>),
+    DisableReceiveOnlyMode(Secret<String>),
+    UnlockSpending(Secret<String>),
+    LockSpending,
+    GetBalanceAuditLog(Option<TxId>),
 }
 
 impl fmt::Display for OutputManagerRequest {
@@ -64,6 +81,8 @@ impl fmt::Display for OutputManagerRequest {
         match self {
             Self::GetBalance => f.write_str("GetBalance"),
             Self::AddOutput(v) => f.write_str(&format!("AddOutput ({})", v.value)),
+            Self::ImportOutputs(v) => f.write_str(&format!("ImportOutputs ({} outputs)", v.len())),
+            Self::RecoverOutputs(v) => f.write_str(&format!("RecoverOutputs ({} outputs)", v.len())),
             Self::GetRecipientKey(v) => f.write_str(&format!("GetRecipientKey ({})", v.0)),
             Self::GetCoinbaseKey(v) => f.write_str(&format!("GetCoinbaseKey ({})", v.0)),
             Self::ConfirmTransaction(v) => f.write_str(&format!("ConfirmTransaction ({})", v.0)),
@@ -72,6 +91,7 @@ impl fmt::Display for OutputManagerRequest {
                 f.write_str(&format!("PrepareToSendTransaction ({})", msg))
             },
             Self::CancelTransaction(v) => f.write_str(&format!("CancelTransaction ({})", v)),
+            Self::RevertTransaction(v) => f.write_str(&format!("RevertTransaction ({})", v)),
             Self::TimeoutTransactions(d) => f.write_str(&format!("TimeoutTransactions ({}s)", d.as_secs())),
             Self::GetPendingTransactions => f.write_str("GetPendingTransactions"),
             Self::GetSpentOutputs => f.write_str("GetSpentOutputs"),
@@ -80,6 +100,19 @@ impl fmt::Display for OutputManagerRequest {
             Self::GetSeedWords => f.write_str("GetSeedWords"),
             Self::SetBaseNodePublicKey(k) => f.write_str(&format!("SetBaseNodePublicKey ({})", k)),
             Self::SyncWithBaseNode => f.write_str("SyncWithBaseNode"),
+            Self::CreateCoinSplit(v) => f.write_str(&format!("CreateCoinSplit ({} x {})", v.1, v.0)),
+            Self::RotateMasterKey(v) => f.write_str(&format!("RotateMasterKey (batches of {})", v.1)),
+            Self::GetOutputMigrationPlan => f.write_str("GetOutputMigrationPlan"),
+            Self::MigrateOutputs => f.write_str("MigrateOutputs"),
+            Self::CreateInvoice(id) => f.write_str(&format!("CreateInvoice ({})", id)),
+            Self::GetInvoiceRecipientKey(v) => f.write_str(&format!("GetInvoiceRecipientKey ({}, {})", v.0, v.1)),
+            Self::GetInvoices => f.write_str("GetInvoices"),
+            Self::EnableReceiveOnlyMode(_) => f.write_str("EnableReceiveOnlyMode"),
+            Self::DisableReceiveOnlyMode(_) => f.write_str("DisableReceiveOnlyMode"),
+            Self::UnlockSpending(_) => f.write_str("UnlockSpending"),
+            Self::LockSpending => f.write_str("LockSpending"),
+            Self::GetBalanceAuditLog(Some(tx_id)) => f.write_str(&format!("GetBalanceAuditLog ({})", tx_id)),
+            Self::GetBalanceAuditLog(None) => f.write_str("GetBalanceAuditLog"),
         }
     }
 }
@@ -88,12 +121,16 @@ impl fmt::Display for OutputManagerRequest {
 pub enum OutputManagerResponse {
     Balance(Balance),
     OutputAdded,
+    OutputsImported(ImportedOutputs),
+    OutputsRecovered(ImportedOutputs),
     RecipientKeyGenerated(PrivateKey),
+    RecipientKeyAndRewindDataGenerated((PrivateKey, RewindData)),
     OutputConfirmed,
     PendingTransactionConfirmed,
     TransactionConfirmed,
     TransactionToSend(SenderTransactionProtocol),
     TransactionCancelled,
+    TransactionReverted,
     TransactionsTimedOut,
     PendingTransactions(HashMap<u64, PendingTransactionOutputs>),
     SpentOutputs(Vec<UnblindedOutput>),
@@ -102,6 +139,17 @@ pub enum OutputManagerResponse {
     SeedWords(Vec<String>),
     BaseNodePublicKeySet,
     StartedBaseNodeSync(u64),
+    Transaction((TxId, Transaction, MicroTari, MicroTari)),
+    MasterKeyRotated(OutputMigrationPlan),
+    OutputMigrationPlan(Option<OutputMigrationPlan>),
+    MigrationTransaction(Option<(TxId, Transaction, MicroTari, MicroTari)>),
+    InvoiceCreated(PublicKey),
+    Invoices(Vec<Invoice>),
+    ReceiveOnlyModeEnabled,
+    ReceiveOnlyModeDisabled,
+    SpendingUnlocked,
+    SpendingLocked,
+    BalanceAuditLog(Vec<BalanceAuditEntry>),
 }
 
 /// Events that can be published on the Text Message Service Event Stream
@@ -109,6 +157,9 @@ pub enum OutputManagerResponse {
 pub enum OutputManagerEvent {
     BaseNodeSyncRequestTimedOut(u64),
     ReceiveBaseNodeResponse(u64),
+    /// The outputs of a confirmed transaction were returned to pending because it was removed from the chain by a
+    /// reorg
+    TransactionReverted(u64),
     Error(String),
 }
 
@@ -138,6 +189,34 @@ impl OutputManagerHandle {
         }
     }
 
+    pub async fn import_outputs(
+        &mut self,
+        outputs: Vec<UnblindedOutput>,
+    ) -> Result<ImportedOutputs, OutputManagerError>
+    {
+        match self.handle.call(OutputManagerRequest::ImportOutputs(outputs)).await?? {
+            OutputManagerResponse::OutputsImported(r) => Ok(r),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Scan the given transaction outputs for outputs created by this wallet, using the rewindable range proofs of the
+    /// wallet's outputs, and import the ones found
+    pub async fn recover_outputs(
+        &mut self,
+        outputs: Vec<TransactionOutput>,
+    ) -> Result<ImportedOutputs, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::RecoverOutputs(outputs))
+            .await??
+        {
+            OutputManagerResponse::OutputsRecovered(r) => Ok(r),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_balance(&mut self) -> Result<Balance, OutputManagerError> {
         match self.handle.call(OutputManagerRequest::GetBalance).await?? {
             OutputManagerResponse::Balance(b) => Ok(b),
@@ -149,14 +228,14 @@ impl OutputManagerHandle {
         &mut self,
         tx_id: u64,
         amount: MicroTari,
-    ) -> Result<PrivateKey, OutputManagerError>
+    ) -> Result<(PrivateKey, RewindData), OutputManagerError>
     {
         match self
             .handle
             .call(OutputManagerRequest::GetRecipientKey((tx_id, amount)))
             .await??
         {
-            OutputManagerResponse::RecipientKeyGenerated(k) => Ok(k),
+            OutputManagerResponse::RecipientKeyAndRewindDataGenerated(k) => Ok(k),
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
@@ -244,6 +323,18 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Move the outputs of a confirmed transaction back to pending after it was removed from the chain by a reorg
+    pub async fn revert_transaction(&mut self, tx_id: u64) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::RevertTransaction(tx_id))
+            .await??
+        {
+            OutputManagerResponse::TransactionReverted => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn timeout_transactions(&mut self, period: Duration) -> Result<(), OutputManagerError> {
         match self
             .handle
@@ -309,4 +400,168 @@ impl OutputManagerHandle {
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
+
+    /// Create a transaction that splits the wallet's funds into `split_count` outputs of `amount_per_split` each. The
+    /// returned tuple contains the TxId, the finalized transaction, the fee and the total amount that was split.
+    pub async fn create_coin_split(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+    ) -> Result<(TxId, Transaction, MicroTari, MicroTari), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateCoinSplit((
+                amount_per_split,
+                split_count,
+                fee_per_gram,
+                lock_height,
+            )))
+            .await??
+        {
+            OutputManagerResponse::Transaction(ct) => Ok(ct),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Replace the wallet's master key with a new random key and plan the migration of the wallet's outputs to keys
+    /// derived from it. The outputs are migrated in batches of up to `batch_size` outputs with `migrate_outputs`.
+    pub async fn rotate_master_key(
+        &mut self,
+        fee_per_gram: MicroTari,
+        batch_size: usize,
+    ) -> Result<OutputMigrationPlan, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::RotateMasterKey((fee_per_gram, batch_size)))
+            .await??
+        {
+            OutputManagerResponse::MasterKeyRotated(plan) => Ok(plan),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Get the progress of the output migration following the last master key rotation, if the key was ever rotated
+    pub async fn get_output_migration_plan(&mut self) -> Result<Option<OutputMigrationPlan>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetOutputMigrationPlan).await?? {
+            OutputManagerResponse::OutputMigrationPlan(plan) => Ok(plan),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a transaction that migrates the next batch of outputs to the current master key. The returned tuple
+    /// contains the TxId, the finalized transaction, the fee and the amount migrated, or None once the migration is
+    /// complete.
+    pub async fn migrate_outputs(
+        &mut self,
+    ) -> Result<Option<(TxId, Transaction, MicroTari, MicroTari)>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::MigrateOutputs).await?? {
+            OutputManagerResponse::MigrationTransaction(tx) => Ok(tx),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create an invoice with the given id. The invoice is paid to a spending key derived for it alone, whose public
+    /// key is returned. A payment is matched to the invoice when its memo is the invoice id.
+    pub async fn create_invoice(&mut self, invoice_id: String) -> Result<PublicKey, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateInvoice(invoice_id))
+            .await??
+        {
+            OutputManagerResponse::InvoiceCreated(pk) => Ok(pk),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Get the spending key of an unpaid invoice to accept a transaction that pays it. The invoice is marked as paid
+    /// by the transaction.
+    pub async fn get_invoice_recipient_spending_key(
+        &mut self,
+        invoice_id: String,
+        tx_id: u64,
+        amount: MicroTari,
+    ) -> Result<(PrivateKey, RewindData), OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetInvoiceRecipientKey((
+                invoice_id, tx_id, amount,
+            )))
+            .await??
+        {
+            OutputManagerResponse::RecipientKeyAndRewindDataGenerated(k) => Ok(k),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_invoices(&mut self) -> Result<Vec<Invoice>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetInvoices).await?? {
+            OutputManagerResponse::Invoices(i) => Ok(i),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Put the wallet in receive-only mode. The wallet keeps receiving payments but refuses to spend its outputs or
+    /// reveal its seed words until it is unlocked with the given passphrase.
+    pub async fn enable_receive_only_mode(&mut self, passphrase: String) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::EnableReceiveOnlyMode(Secret::new(passphrase)))
+            .await??
+        {
+            OutputManagerResponse::ReceiveOnlyModeEnabled => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn disable_receive_only_mode(&mut self, passphrase: String) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::DisableReceiveOnlyMode(Secret::new(passphrase)))
+            .await??
+        {
+            OutputManagerResponse::ReceiveOnlyModeDisabled => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Allow a wallet in receive-only mode to spend for the configured spend unlock period
+    pub async fn unlock_spending(&mut self, passphrase: String) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::UnlockSpending(Secret::new(passphrase)))
+            .await??
+        {
+            OutputManagerResponse::SpendingUnlocked => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn lock_spending(&mut self) -> Result<(), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::LockSpending).await?? {
+            OutputManagerResponse::SpendingLocked => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Fetch the log of balance-affecting changes to the wallet's outputs, optionally only the changes caused by the
+    /// given transaction
+    pub async fn get_balance_audit_log(
+        &mut self,
+        tx_id: Option<TxId>,
+    ) -> Result<Vec<BalanceAuditEntry>, OutputManagerError>
+    {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetBalanceAuditLog(tx_id))
+            .await??
+        {
+            OutputManagerResponse::BalanceAuditLog(entries) => Ok(entries),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
 }
@@ -0,0 +1,86 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Key manager backends
+//!
+//! The output manager does not derive spending keys itself. It keeps track of which key indices have been handed out
+//! in its storage backend and asks a [KeyManagerBackend] for the spending key at the next index. [SoftwareKeyManager]
+//! derives the keys in memory from the wallet's master seed, which is the default. Custody integrations such as a
+//! hardware device or a remote signing service implement the trait to derive the keys themselves.
+//!
+//! The rewind keys that let the wallet recognise its own outputs on the blockchain are always derived from the master
+//! seed in the storage backend, so output recovery works the same way with any key manager backend.
+
+use crate::{output_manager_service::error::KeyManagerBackendError, types::KeyDigest};
+use tari_core::transactions::types::PrivateKey;
+use tari_key_manager::key_manager::KeyManager;
+use tari_secret::Secret;
+
+pub trait KeyManagerBackend: Send + Sync {
+    /// Derive the spending key at `key_index`. The same index must always yield the same key.
+    fn derive_key(&self, key_index: usize) -> Result<PrivateKey, KeyManagerBackendError>;
+
+    /// The master key the spending keys are derived from. Backends that do not reveal their master key return None,
+    /// in which case seed words and master key rotation are not available.
+    fn master_key(&self) -> Option<PrivateKey>;
+}
+
+/// A [KeyManagerBackend] that derives spending keys from a master seed held in memory
+pub struct SoftwareKeyManager {
+    key_manager: KeyManager<PrivateKey, KeyDigest>,
+}
+
+impl SoftwareKeyManager {
+    pub fn new(master_seed: Secret<PrivateKey>, branch_seed: String) -> Self {
+        Self {
+            key_manager: KeyManager::from(master_seed, branch_seed, 0),
+        }
+    }
+}
+
+impl KeyManagerBackend for SoftwareKeyManager {
+    fn derive_key(&self, key_index: usize) -> Result<PrivateKey, KeyManagerBackendError> {
+        Ok(self.key_manager.derive_key(key_index)?.k)
+    }
+
+    fn master_key(&self) -> Option<PrivateKey> {
+        Some(self.key_manager.master_key.reveal().clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn derives_the_same_keys_as_the_key_manager() {
+        let mut key_manager = KeyManager::<PrivateKey, KeyDigest>::new(&mut OsRng);
+        let backend = SoftwareKeyManager::new(key_manager.master_key.clone(), key_manager.branch_seed.clone());
+        for _ in 0..3 {
+            let key = key_manager.next_key().unwrap();
+            assert_eq!(backend.derive_key(key.key_index).unwrap(), key.k);
+        }
+        assert_eq!(backend.master_key().as_ref(), Some(key_manager.master_key.reveal()));
+        assert_ne!(backend.derive_key(1).unwrap(), PrivateKey::default());
+    }
+}
@@ -24,6 +24,7 @@ use crate::output_manager_service::{handle::OutputManagerHandle, service::Output
 
 use crate::output_manager_service::{
     config::OutputManagerServiceConfig,
+    key_manager_backend::KeyManagerBackend,
     storage::database::{OutputManagerBackend, OutputManagerDatabase},
 };
 use futures::{future, Future, Stream, StreamExt};
@@ -50,7 +51,10 @@ use tokio::runtime;
 
 pub mod config;
 pub mod error;
+pub mod faucet;
 pub mod handle;
+pub mod key_manager_backend;
+pub mod recovery;
 #[allow(unused_assignments)]
 pub mod service;
 pub mod storage;
@@ -66,6 +70,7 @@ where T: OutputManagerBackend
     subscription_factory: Arc<TopicSubscriptionFactory<TariMessageType, Arc<PeerMessage>>>,
     backend: Option<T>,
     factories: CryptoFactories,
+    key_manager_backend: Option<Arc<dyn KeyManagerBackend>>,
 }
 
 impl<T> OutputManagerServiceInitializer<T>
@@ -83,9 +88,16 @@ where T: OutputManagerBackend
             subscription_factory,
             backend: Some(backend),
             factories,
+            key_manager_backend: None,
         }
     }
 
+    /// Derive spending keys with the given backend, e.g. a hardware device, instead of the wallet's master seed
+    pub fn with_key_manager_backend(mut self, key_manager_backend: Arc<dyn KeyManagerBackend>) -> Self {
+        self.key_manager_backend = Some(key_manager_backend);
+        self
+    }
+
     fn base_node_response_stream(&self) -> impl Stream<Item = DomainMessage<BaseNodeProto::BaseNodeServiceResponse>> {
         self.subscription_factory
             .get_subscription(TariMessageType::BaseNodeResponse)
@@ -115,6 +127,7 @@ where T: OutputManagerBackend + 'static
 
         // Register handle before waiting for handles to be ready
         handles_fut.register(oms_handle);
+        let ready_signal = handles_fut.ready_signal::<OutputManagerHandle>();
 
         let backend = self
             .backend
@@ -122,15 +135,17 @@ where T: OutputManagerBackend + 'static
             .expect("Cannot start Output Manager Service without setting a storage backend");
         let factories = self.factories.clone();
         let config = self.config.clone();
+        let key_manager_backend = self.key_manager_backend.clone();
 
         executor.spawn(async move {
             let handles = handles_fut.await;
 
             let outbound_message_service = handles
-                .get_handle::<OutboundMessageRequester>()
+                .wait_for_handle::<OutboundMessageRequester>()
+                .await
                 .expect("OMS handle required for Output Manager Service");
 
-            let service = OutputManagerService::new(
+            let mut service = OutputManagerService::new(
                 config,
                 outbound_message_service,
                 receiver,
@@ -140,8 +155,12 @@ where T: OutputManagerBackend + 'static
                 factories,
             )
             .await
-            .expect("Could not initialize Output Manager Service")
-            .start();
+            .expect("Could not initialize Output Manager Service");
+            if let Some(key_manager_backend) = key_manager_backend {
+                service = service.with_key_manager_backend(key_manager_backend);
+            }
+            let service = service.start();
+            ready_signal.set_ready();
 
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
@@ -0,0 +1,191 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Output recovery
+//!
+//! Outputs created by the wallet carry range proofs that can be rewound with a pair of rewind keys derived from the
+//! wallet's master key. Rewinding a proof reveals the committed value and a short proof message without needing the
+//! output's spending key. The wallet writes a recovery byte and the key manager index of the spending key into the
+//! proof message, so a wallet restored from its seed words can recognise its own outputs among the outputs on the
+//! blockchain and re-derive their spending keys directly, instead of trying every key index against every output.
+//!
+//! The proof message layout is `[recovery byte][key index as u64, little endian][zero padding]`.
+
+use crate::{output_manager_service::key_manager_backend::KeyManagerBackend, types::KeyDigest};
+use digest::Digest;
+use std::convert::TryInto;
+use tari_core::transactions::{
+    transaction::{TransactionOutput, UnblindedOutput},
+    types::{CryptoFactories, PrivateKey, PublicKey, RewindData, REWIND_USER_MESSAGE_LENGTH},
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::PublicKey as PublicKeyTrait,
+    tari_utilities::{ByteArray, ByteArrayError},
+};
+
+/// The recovery byte used when none is configured
+pub const DEFAULT_RECOVERY_BYTE: u8 = 0;
+
+const REWIND_KEY_LABEL: &str = "wallet_output_rewind_key";
+const REWIND_BLINDING_KEY_LABEL: &str = "wallet_output_rewind_blinding_key";
+const KEY_INDEX_LENGTH: usize = 8;
+
+/// The keys used to construct and rewind the range proofs of the wallet's outputs. They are derived from the master
+/// key so that they can be recreated from the seed words.
+#[derive(Clone)]
+pub struct RewindKeys {
+    rewind_key: PrivateKey,
+    rewind_blinding_key: PrivateKey,
+    rewind_public_key: PublicKey,
+    rewind_blinding_public_key: PublicKey,
+}
+
+impl RewindKeys {
+    pub fn from_master_key(master_key: &PrivateKey) -> Result<Self, ByteArrayError> {
+        let rewind_key = derive_rewind_key(master_key, REWIND_KEY_LABEL)?;
+        let rewind_blinding_key = derive_rewind_key(master_key, REWIND_BLINDING_KEY_LABEL)?;
+        Ok(Self {
+            rewind_public_key: PublicKey::from_secret_key(&rewind_key),
+            rewind_blinding_public_key: PublicKey::from_secret_key(&rewind_blinding_key),
+            rewind_key,
+            rewind_blinding_key,
+        })
+    }
+
+    /// The rewind data for an output whose spending key has the given key manager index
+    pub fn rewind_data(&self, recovery_byte: u8, key_index: usize) -> RewindData {
+        RewindData {
+            rewind_key: self.rewind_key.clone(),
+            rewind_blinding_key: self.rewind_blinding_key.clone(),
+            proof_message: encode_proof_message(recovery_byte, key_index),
+        }
+    }
+}
+
+fn derive_rewind_key(master_key: &PrivateKey, label: &str) -> Result<PrivateKey, ByteArrayError> {
+    let digest = KeyDigest::new()
+        .chain(label.as_bytes())
+        .chain(master_key.as_bytes())
+        .result();
+    PrivateKey::from_bytes(digest.as_slice())
+}
+
+pub fn encode_proof_message(recovery_byte: u8, key_index: usize) -> [u8; REWIND_USER_MESSAGE_LENGTH] {
+    let mut message = [0u8; REWIND_USER_MESSAGE_LENGTH];
+    message[0] = recovery_byte;
+    message[1..=KEY_INDEX_LENGTH].copy_from_slice(&(key_index as u64).to_le_bytes());
+    message
+}
+
+/// Returns the key index in the proof message, or None if the message was not written by a wallet using this recovery
+/// byte. Rewinding a proof with the wrong keys yields a random message, which is rejected here in all but a negligible
+/// number of cases because the padding must be zero.
+pub fn decode_proof_message(recovery_byte: u8, message: &[u8; REWIND_USER_MESSAGE_LENGTH]) -> Option<usize> {
+    if message[0] != recovery_byte || message[KEY_INDEX_LENGTH + 1..].iter().any(|b| *b != 0) {
+        return None;
+    }
+    let key_index = u64::from_le_bytes(message[1..=KEY_INDEX_LENGTH].try_into().ok()?);
+    key_index.try_into().ok()
+}
+
+/// Try to recover an output created by this wallet from its transaction output. The range proof is rewound to find
+/// the value and the key index, and the spending key derived at that index must open the output's commitment.
+/// Returns the unblinded output and the key index of its spending key.
+pub fn recover_output(
+    output: &TransactionOutput,
+    rewind_keys: &RewindKeys,
+    recovery_byte: u8,
+    key_manager: &dyn KeyManagerBackend,
+    factories: &CryptoFactories,
+) -> Option<(UnblindedOutput, usize)>
+{
+    let rewound = output
+        .rewind_range_proof_value_only(
+            &factories.range_proof,
+            &rewind_keys.rewind_public_key,
+            &rewind_keys.rewind_blinding_public_key,
+        )
+        .ok()?;
+    let key_index = decode_proof_message(recovery_byte, &rewound.proof_message)?;
+    let key = key_manager.derive_key(key_index).ok()?;
+    if !factories
+        .commitment
+        .open_value(&key, rewound.committed_value, &output.commitment)
+    {
+        return None;
+    }
+    Some((
+        UnblindedOutput::new(rewound.committed_value.into(), key, Some(output.features.clone())),
+        key_index,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::output_manager_service::key_manager_backend::SoftwareKeyManager;
+    use rand::rngs::OsRng;
+    use tari_core::transactions::tari_amount::MicroTari;
+    use tari_crypto::keys::SecretKey;
+    use tari_key_manager::key_manager::KeyManager;
+
+    #[test]
+    fn proof_message_round_trip() {
+        let message = encode_proof_message(42, 123_456);
+        assert_eq!(decode_proof_message(42, &message), Some(123_456));
+        assert_eq!(decode_proof_message(43, &message), None);
+
+        let mut message = message;
+        message[REWIND_USER_MESSAGE_LENGTH - 1] = 1;
+        assert_eq!(decode_proof_message(42, &message), None);
+    }
+
+    #[test]
+    fn recover_wallet_output() {
+        let factories = CryptoFactories::default();
+        let mut key_manager = KeyManager::<PrivateKey, KeyDigest>::new(&mut OsRng);
+        let rewind_keys = RewindKeys::from_master_key(&key_manager.master_key).unwrap();
+        let key = key_manager.next_key().unwrap();
+        let output = UnblindedOutput::new(MicroTari(5000), key.k.clone(), None);
+        let tx_output = output
+            .as_rewindable_transaction_output(&factories, &rewind_keys.rewind_data(7, key.key_index))
+            .unwrap();
+
+        let backend = SoftwareKeyManager::new(key_manager.master_key.clone(), key_manager.branch_seed.clone());
+
+        let (recovered, key_index) = recover_output(&tx_output, &rewind_keys, 7, &backend, &factories).unwrap();
+        assert_eq!(key_index, key.key_index);
+        assert_eq!(recovered, output);
+
+        // A different recovery byte or a different wallet does not recover the output
+        assert!(recover_output(&tx_output, &rewind_keys, 8, &backend, &factories).is_none());
+        let other_wallet = KeyManager::<PrivateKey, KeyDigest>::new(&mut OsRng);
+        let other_keys = RewindKeys::from_master_key(&other_wallet.master_key).unwrap();
+        let other_backend = SoftwareKeyManager::new(other_wallet.master_key.clone(), other_wallet.branch_seed);
+        assert!(recover_output(&tx_output, &other_keys, 7, &other_backend, &factories).is_none());
+
+        // Outputs without a rewindable proof are not recovered
+        let tx_output = output.as_transaction_output(&factories).unwrap();
+        assert!(recover_output(&tx_output, &rewind_keys, 7, &backend, &factories).is_none());
+    }
+}
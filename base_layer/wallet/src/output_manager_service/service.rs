@@ -23,23 +23,40 @@
 use crate::{
     output_manager_service::{
         config::OutputManagerServiceConfig,
-        error::OutputManagerError,
+        error::{KeyManagerBackendError, OutputManagerError, OutputManagerStorageError},
         handle::{OutputManagerEvent, OutputManagerRequest, OutputManagerResponse},
-        storage::database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase, PendingTransactionOutputs},
+        key_manager_backend::{KeyManagerBackend, SoftwareKeyManager},
+        recovery::{recover_output, RewindKeys},
+        storage::database::{
+            Invoice,
+            KeyManagerState,
+            OutputManagerBackend,
+            OutputManagerDatabase,
+            OutputMigrationPlan,
+            PendingTransactionOutputs,
+        },
         TxId,
     },
-    types::{HashDigest, KeyDigest},
+    types::HashDigest,
     util::futures::StateDelay,
 };
+use chrono::Utc;
 use futures::{future::BoxFuture, pin_mut, stream::FuturesUnordered, FutureExt, SinkExt, Stream, StreamExt};
 use log::*;
 use rand::{rngs::OsRng, RngCore};
-use std::{cmp::Ordering, collections::HashMap, convert::TryFrom, fmt, sync::Mutex, time::Duration};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tari_broadcast_channel::Publisher;
 use tari_comms::types::CommsPublicKey;
 use tari_comms_dht::{
     domain_message::OutboundDomainMessage,
-    outbound::{OutboundEncryption, OutboundMessageRequester},
+    outbound::{DhtMessagePriority, OutboundEncryption, OutboundMessageRequester},
 };
 use tari_core::{
     base_node::proto::{
@@ -50,19 +67,27 @@ use tari_core::{
         },
     },
     transactions::{
-        fee::Fee,
+        fee::{Fee, WEIGHT_PER_INPUT},
         tari_amount::MicroTari,
-        transaction::{OutputFeatures, TransactionInput, TransactionOutput, UnblindedOutput},
-        types::{CryptoFactories, PrivateKey},
+        transaction::{
+            KernelFeatures,
+            OutputFeatures,
+            Transaction,
+            TransactionInput,
+            TransactionOutput,
+            UnblindedOutput,
+        },
+        types::{CryptoFactories, PrivateKey, PublicKey, RewindData},
         SenderTransactionProtocol,
     },
 };
-use tari_crypto::{keys::SecretKey as SecretKeyTrait, tari_utilities::hash::Hashable};
-use tari_key_manager::{
-    key_manager::KeyManager,
-    mnemonic::{from_secret_key, MnemonicLanguage},
+use tari_crypto::{
+    keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait},
+    tari_utilities::hash::Hashable,
 };
+use tari_key_manager::mnemonic::{from_secret_key, MnemonicLanguage};
 use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
+use tari_secret::Secret;
 use tari_service_framework::reply_channel;
 
 const LOG_TARGET: &str = "wallet::output_manager_service";
@@ -75,7 +100,8 @@ pub struct OutputManagerService<TBackend, BNResponseStream>
 where TBackend: OutputManagerBackend + 'static
 {
     config: OutputManagerServiceConfig,
-    key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
+    key_manager: Arc<dyn KeyManagerBackend>,
+    rewind_keys: RewindKeys,
     db: OutputManagerDatabase<TBackend>,
     outbound_message_service: OutboundMessageRequester,
     request_stream:
@@ -84,7 +110,11 @@ where TBackend: OutputManagerBackend + 'static
     factories: CryptoFactories,
     base_node_public_key: Option<CommsPublicKey>,
     pending_utxo_query_keys: HashMap<u64, Vec<Vec<u8>>>,
+    utxo_query_attempts: usize,
     event_publisher: Publisher<OutputManagerEvent>,
+    // The hash of the spend passphrase while the wallet is in receive-only mode
+    spend_lock: Option<String>,
+    spending_unlocked_until: Option<Instant>,
 }
 
 impl<TBackend, BNResponseStream> OutputManagerService<TBackend, BNResponseStream>
@@ -109,7 +139,7 @@ where
         let key_manager_state = match db.get_key_manager_state().await? {
             None => {
                 let starting_state = KeyManagerState {
-                    master_seed: PrivateKey::random(&mut OsRng),
+                    master_seed: Secret::new(PrivateKey::random(&mut OsRng)),
                     branch_seed: "".to_string(),
                     primary_key_index: 0,
                 };
@@ -123,13 +153,16 @@ where
         // Pending Transactions.
         db.clear_short_term_encumberances().await?;
 
+        let rewind_keys = RewindKeys::from_master_key(&key_manager_state.master_seed)?;
+        let spend_lock = db.get_spend_lock().await?;
+
         Ok(OutputManagerService {
             config,
             outbound_message_service,
-            key_manager: Mutex::new(KeyManager::<PrivateKey, KeyDigest>::from(
+            rewind_keys,
+            key_manager: Arc::new(SoftwareKeyManager::new(
                 key_manager_state.master_seed,
                 key_manager_state.branch_seed,
-                key_manager_state.primary_key_index,
             )),
             db,
             request_stream: Some(request_stream),
@@ -137,10 +170,20 @@ where
             factories,
             base_node_public_key: None,
             pending_utxo_query_keys: HashMap::new(),
+            utxo_query_attempts: 0,
             event_publisher,
+            spend_lock,
+            spending_unlocked_until: None,
         })
     }
 
+    /// Derive spending keys with the given backend instead of the wallet's master seed. The key indices that have been
+    /// used are still tracked by the storage backend.
+    pub fn with_key_manager_backend(mut self, key_manager: Arc<dyn KeyManagerBackend>) -> Self {
+        self.key_manager = key_manager;
+        self
+    }
+
     pub async fn start(mut self) -> Result<(), OutputManagerError> {
         let request_stream = self
             .request_stream
@@ -219,11 +262,19 @@ where
             OutputManagerRequest::AddOutput(uo) => {
                 self.add_output(uo).await.map(|_| OutputManagerResponse::OutputAdded)
             },
+            OutputManagerRequest::ImportOutputs(outputs) => self
+                .import_outputs(outputs, utxo_query_timeout_futures)
+                .await
+                .map(OutputManagerResponse::OutputsImported),
             OutputManagerRequest::GetBalance => self.get_balance().await.map(OutputManagerResponse::Balance),
+            OutputManagerRequest::RecoverOutputs(outputs) => self
+                .recover_outputs(outputs, utxo_query_timeout_futures)
+                .await
+                .map(OutputManagerResponse::OutputsRecovered),
             OutputManagerRequest::GetRecipientKey((tx_id, amount)) => self
                 .get_recipient_spending_key(tx_id, amount)
                 .await
-                .map(OutputManagerResponse::RecipientKeyGenerated),
+                .map(OutputManagerResponse::RecipientKeyAndRewindDataGenerated),
             OutputManagerRequest::PrepareToSendTransaction((amount, fee_per_gram, lock_height, message)) => self
                 .prepare_transaction_to_send(amount, fee_per_gram, lock_height, message)
                 .await
@@ -240,6 +291,10 @@ where
                 .cancel_transaction(tx_id)
                 .await
                 .map(|_| OutputManagerResponse::TransactionCancelled),
+            OutputManagerRequest::RevertTransaction(tx_id) => self
+                .revert_transaction(tx_id)
+                .await
+                .map(|_| OutputManagerResponse::TransactionReverted),
             OutputManagerRequest::TimeoutTransactions(period) => self
                 .timeout_pending_transactions(period)
                 .await
@@ -265,14 +320,59 @@ where
                 .set_base_node_public_key(pk, utxo_query_timeout_futures)
                 .await
                 .map(|_| OutputManagerResponse::BaseNodePublicKeySet),
-            OutputManagerRequest::SyncWithBaseNode => self
-                .query_unspent_outputs_status(utxo_query_timeout_futures)
-                .await
-                .map(OutputManagerResponse::StartedBaseNodeSync),
+            OutputManagerRequest::SyncWithBaseNode => {
+                self.utxo_query_attempts = 0;
+                self.query_unspent_outputs_status(utxo_query_timeout_futures)
+                    .await
+                    .map(OutputManagerResponse::StartedBaseNodeSync)
+            },
             OutputManagerRequest::GetInvalidOutputs => self
                 .fetch_invalid_outputs()
                 .await
                 .map(OutputManagerResponse::InvalidOutputs),
+            OutputManagerRequest::CreateCoinSplit((amount_per_split, split_count, fee_per_gram, lock_height)) => self
+                .create_coin_split(amount_per_split, split_count, fee_per_gram, lock_height)
+                .await
+                .map(OutputManagerResponse::Transaction),
+            OutputManagerRequest::RotateMasterKey((fee_per_gram, batch_size)) => self
+                .rotate_master_key(fee_per_gram, batch_size)
+                .await
+                .map(OutputManagerResponse::MasterKeyRotated),
+            OutputManagerRequest::GetOutputMigrationPlan => self
+                .get_output_migration_plan()
+                .await
+                .map(OutputManagerResponse::OutputMigrationPlan),
+            OutputManagerRequest::MigrateOutputs => self
+                .migrate_outputs()
+                .await
+                .map(OutputManagerResponse::MigrationTransaction),
+            OutputManagerRequest::CreateInvoice(invoice_id) => self
+                .create_invoice(invoice_id)
+                .await
+                .map(OutputManagerResponse::InvoiceCreated),
+            OutputManagerRequest::GetInvoiceRecipientKey((invoice_id, tx_id, amount)) => self
+                .get_invoice_recipient_spending_key(invoice_id, tx_id, amount)
+                .await
+                .map(OutputManagerResponse::RecipientKeyAndRewindDataGenerated),
+            OutputManagerRequest::GetInvoices => Ok(OutputManagerResponse::Invoices(self.db.get_invoices().await?)),
+            OutputManagerRequest::EnableReceiveOnlyMode(passphrase) => self
+                .enable_receive_only_mode(&passphrase)
+                .await
+                .map(|_| OutputManagerResponse::ReceiveOnlyModeEnabled),
+            OutputManagerRequest::DisableReceiveOnlyMode(passphrase) => self
+                .disable_receive_only_mode(&passphrase)
+                .await
+                .map(|_| OutputManagerResponse::ReceiveOnlyModeDisabled),
+            OutputManagerRequest::UnlockSpending(passphrase) => self
+                .unlock_spending(&passphrase)
+                .map(|_| OutputManagerResponse::SpendingUnlocked),
+            OutputManagerRequest::LockSpending => {
+                self.spending_unlocked_until = None;
+                Ok(OutputManagerResponse::SpendingLocked)
+            },
+            OutputManagerRequest::GetBalanceAuditLog(tx_id) => Ok(OutputManagerResponse::BalanceAuditLog(
+                self.db.get_balance_audit_log(tx_id).await?,
+            )),
         }
     }
 
@@ -303,6 +403,7 @@ where
             },
             Some(qh) => qh,
         };
+        self.utxo_query_attempts = 0;
 
         trace!(
             target: LOG_TARGET,
@@ -360,9 +461,17 @@ where
     {
         if self.pending_utxo_query_keys.remove(&query_key).is_some() {
             error!(target: LOG_TARGET, "UTXO Query {} timed out", query_key);
-            self.query_unspent_outputs_status(utxo_query_timeout_futures).await?;
-            // TODO Remove this once this bug is fixed
-            trace!(target: LOG_TARGET, "Finished queueing new Base Node query timeout");
+            let retry_policy = self.config.base_node_query_retry_policy;
+            if retry_policy.can_retry(self.utxo_query_attempts) {
+                self.query_unspent_outputs_status(utxo_query_timeout_futures).await?;
+                // TODO Remove this once this bug is fixed
+                trace!(target: LOG_TARGET, "Finished queueing new Base Node query timeout");
+            } else {
+                warn!(
+                    target: LOG_TARGET,
+                    "No more UTXO queries will be sent after {} unanswered attempts", self.utxo_query_attempts
+                );
+            }
             self.event_publisher
                 .send(OutputManagerEvent::BaseNodeSyncRequestTimedOut(query_key))
                 .await
@@ -377,11 +486,26 @@ where
         &mut self,
         utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
     ) -> Result<u64, OutputManagerError>
+    {
+        if self.base_node_public_key.is_none() {
+            return Err(OutputManagerError::NoBaseNodeKeysProvided);
+        }
+        let unspent_outputs: Vec<UnblindedOutput> = self.db.get_unspent_outputs().await?;
+        self.query_outputs_status(&unspent_outputs, utxo_query_timeout_futures)
+            .await
+    }
+
+    /// Send a query to the base node for the given outputs. Any of the outputs that are not in the base node's UTXO set
+    /// are invalidated when the response is received.
+    async fn query_outputs_status(
+        &mut self,
+        unspent_outputs: &[UnblindedOutput],
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<u64, OutputManagerError>
     {
         match self.base_node_public_key.as_ref() {
             None => Err(OutputManagerError::NoBaseNodeKeysProvided),
             Some(pk) => {
-                let unspent_outputs: Vec<UnblindedOutput> = self.db.get_unspent_outputs().await?;
                 let mut output_hashes = Vec::new();
                 for uo in unspent_outputs.iter() {
                     let hash = uo.as_transaction_output(&self.factories)?.hash();
@@ -400,16 +524,23 @@ where
                 // TODO Remove this once this bug is fixed
                 trace!(target: LOG_TARGET, "About to attempt to send query to base node");
                 self.outbound_message_service
-                    .send_direct(
+                    .send_direct_with_priority(
                         pk.clone(),
                         OutboundEncryption::EncryptForPeer,
+                        DhtMessagePriority::High,
                         OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
                     )
                     .await?;
                 // TODO Remove this once this bug is fixed
                 trace!(target: LOG_TARGET, "Query sent to Base Node");
                 self.pending_utxo_query_keys.insert(request_key, output_hashes);
-                let state_timeout = StateDelay::new(self.config.base_node_query_timeout, request_key);
+                // Back off from a base node which has not answered the previous queries
+                self.utxo_query_attempts += 1;
+                let backoff = self
+                    .config
+                    .base_node_query_retry_policy
+                    .jittered_delay_for_attempt(self.utxo_query_attempts);
+                let state_timeout = StateDelay::new(self.config.base_node_query_timeout + backoff, request_key);
                 utxo_query_timeout_futures.push(state_timeout.delay().boxed());
                 debug!(
                     target: LOG_TARGET,
@@ -425,6 +556,110 @@ where
         Ok(self.db.add_unspent_output(output).await?)
     }
 
+    /// Import a batch of outputs that were created outside of this wallet, e.g. by a faucet or another wallet. Outputs
+    /// that the wallet already knows about are skipped, as are outputs for which a valid range proof cannot be
+    /// constructed. The imported outputs are added to the unspent outputs and, if a base node is set, a query is sent
+    /// to it to check that they exist and are unspent. Imported outputs that are not in the base node's UTXO set are
+    /// invalidated when the response arrives.
+    pub async fn import_outputs(
+        &mut self,
+        outputs: Vec<UnblindedOutput>,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<ImportedOutputs, OutputManagerError>
+    {
+        let mut known_outputs = self.db.get_unspent_outputs().await?;
+        known_outputs.extend(self.db.fetch_spent_outputs().await?);
+        known_outputs.extend(self.db.get_invalid_outputs().await?);
+        for (_, pending) in self.db.fetch_all_pending_transaction_outputs().await? {
+            known_outputs.extend(pending.outputs_to_be_spent);
+            known_outputs.extend(pending.outputs_to_be_received);
+        }
+
+        let mut result = ImportedOutputs::default();
+        for output in outputs {
+            if known_outputs
+                .iter()
+                .chain(result.imported.iter())
+                .any(|o| o.spending_key == output.spending_key)
+            {
+                result.duplicates += 1;
+                continue;
+            }
+            if let Err(e) = output.as_transaction_output(&self.factories) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Rejecting imported output with value {}: {:?}", output.value, e
+                );
+                result.rejected += 1;
+                continue;
+            }
+            match self.db.add_unspent_output(output.clone()).await {
+                Ok(_) => result.imported.push(output),
+                Err(OutputManagerStorageError::DuplicateOutput) => result.duplicates += 1,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        info!(
+            target: LOG_TARGET,
+            "Imported {} outputs ({} duplicates, {} rejected)",
+            result.imported.len(),
+            result.duplicates,
+            result.rejected
+        );
+
+        if !result.imported.is_empty() && self.base_node_public_key.is_some() {
+            self.utxo_query_attempts = 0;
+            let request_key = self
+                .query_outputs_status(&result.imported, utxo_query_timeout_futures)
+                .await?;
+            result.validation_request_key = Some(request_key);
+        }
+        Ok(result)
+    }
+
+    /// Scan transaction outputs, e.g. from the blockchain, for outputs created by this wallet and import them. An
+    /// output is recognised by rewinding its range proof with the wallet's rewind keys, which reveals its value and
+    /// the key manager index of its spending key. The key manager index is moved past the highest index recovered so
+    /// that recovered keys are not handed out again.
+    pub async fn recover_outputs(
+        &mut self,
+        outputs: Vec<TransactionOutput>,
+        utxo_query_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, u64>>,
+    ) -> Result<ImportedOutputs, OutputManagerError>
+    {
+        let mut recovered = Vec::new();
+        let mut highest_key_index = None;
+        for output in outputs.iter() {
+            if let Some((uo, key_index)) = recover_output(
+                output,
+                &self.rewind_keys,
+                self.config.recovery_byte,
+                &*self.key_manager,
+                &self.factories,
+            ) {
+                highest_key_index = highest_key_index.max(Some(key_index));
+                recovered.push(uo);
+            }
+        }
+        debug!(
+            target: LOG_TARGET,
+            "Recovered {} of {} scanned outputs",
+            recovered.len(),
+            outputs.len()
+        );
+
+        if let Some(key_index) = highest_key_index {
+            if let Some(mut state) = self.db.get_key_manager_state().await? {
+                if key_index > state.primary_key_index {
+                    state.primary_key_index = key_index;
+                    self.db.set_key_manager_state(state).await?;
+                }
+            }
+        }
+
+        self.import_outputs(recovered, utxo_query_timeout_futures).await
+    }
+
     pub async fn get_balance(&self) -> Result<Balance, OutputManagerError> {
         let balance = self.db.get_balance().await?;
         trace!(target: LOG_TARGET, "Balance: {:?}", balance);
@@ -436,20 +671,14 @@ where
         &mut self,
         tx_id: TxId,
         amount: MicroTari,
-    ) -> Result<PrivateKey, OutputManagerError>
+    ) -> Result<(PrivateKey, RewindData), OutputManagerError>
     {
-        let mut key = PrivateKey::default();
-        {
-            let mut km = acquire_lock!(self.key_manager);
-            key = km.next_key()?.k;
-        }
-
-        self.db.increment_key_index().await?;
+        let (key, rewind_data) = self.next_key_with_rewind_data().await?;
         self.db
             .accept_incoming_pending_transaction(tx_id, amount, key.clone(), OutputFeatures::default())
             .await?;
 
-        Ok(key)
+        Ok((key, rewind_data))
     }
 
     /// Request a spending key to be used to accept a coinbase output to be mined with the specified maturity height
@@ -464,14 +693,8 @@ where
         maturity_height: u64,
     ) -> Result<PrivateKey, OutputManagerError>
     {
-        let mut key = PrivateKey::default();
-
-        {
-            let mut km = acquire_lock!(self.key_manager);
-            key = km.next_key()?.k;
-        }
-
-        self.db.increment_key_index().await?;
+        let key_index = self.db.increment_key_index().await?;
+        let key = self.key_manager.derive_key(key_index)?;
         self.db
             .accept_incoming_pending_transaction(
                 tx_id,
@@ -484,6 +707,120 @@ where
         Ok(key)
     }
 
+    /// Create an invoice that is paid to a spending key derived for it alone and return the public key of that key
+    pub async fn create_invoice(&mut self, invoice_id: String) -> Result<PublicKey, OutputManagerError> {
+        if self.db.get_invoice(invoice_id.clone()).await?.is_some() {
+            return Err(OutputManagerError::DuplicateInvoice);
+        }
+        let key_index = self.db.increment_key_index().await?;
+        let key = self.key_manager.derive_key(key_index)?;
+        let public_key = PublicKey::from_secret_key(&key);
+        self.db
+            .set_invoice(Invoice {
+                invoice_id,
+                spending_key: Secret::new(key),
+                key_index,
+                tx_id: None,
+                amount: None,
+                timestamp: Utc::now().naive_utc(),
+            })
+            .await?;
+
+        Ok(public_key)
+    }
+
+    /// Request the spending key of an unpaid invoice to accept a transaction from a sender that pays the invoice
+    pub async fn get_invoice_recipient_spending_key(
+        &mut self,
+        invoice_id: String,
+        tx_id: TxId,
+        amount: MicroTari,
+    ) -> Result<(PrivateKey, RewindData), OutputManagerError>
+    {
+        let mut invoice = self
+            .db
+            .get_invoice(invoice_id)
+            .await?
+            .ok_or_else(|| OutputManagerError::InvoiceNotFound)?;
+        if invoice.is_paid() {
+            return Err(OutputManagerError::InvoiceAlreadyPaid);
+        }
+        let key = invoice.spending_key.reveal().clone();
+        let rewind_data = self
+            .rewind_keys
+            .rewind_data(self.config.recovery_byte, invoice.key_index);
+        self.db
+            .accept_incoming_pending_transaction(tx_id, amount, key.clone(), OutputFeatures::default())
+            .await?;
+        invoice.tx_id = Some(tx_id);
+        invoice.amount = Some(amount);
+        self.db.set_invoice(invoice).await?;
+
+        Ok((key, rewind_data))
+    }
+
+    /// Put the wallet in receive-only mode, in which it refuses to spend its outputs unless spending has been
+    /// unlocked with the given passphrase. Only a hash of the passphrase is stored.
+    pub async fn enable_receive_only_mode(&mut self, passphrase: &Secret<String>) -> Result<(), OutputManagerError> {
+        if self.spend_lock.is_some() {
+            return Err(OutputManagerError::ReceiveOnlyModeEnabled);
+        }
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let passphrase_hash = argon2::hash_encoded(passphrase.as_bytes(), &salt, &argon2::Config::default())
+            .map_err(|e| OutputManagerError::PassphraseHashError(e.to_string()))?;
+        self.db.set_spend_lock(passphrase_hash.clone()).await?;
+        self.spend_lock = Some(passphrase_hash);
+        self.spending_unlocked_until = None;
+
+        Ok(())
+    }
+
+    pub async fn disable_receive_only_mode(&mut self, passphrase: &Secret<String>) -> Result<(), OutputManagerError> {
+        self.verify_spend_passphrase(passphrase)?;
+        self.db.clear_spend_lock().await?;
+        self.spend_lock = None;
+        self.spending_unlocked_until = None;
+
+        Ok(())
+    }
+
+    /// Allow a wallet in receive-only mode to spend its outputs for the configured spend unlock period
+    pub fn unlock_spending(&mut self, passphrase: &Secret<String>) -> Result<(), OutputManagerError> {
+        self.verify_spend_passphrase(passphrase)?;
+        self.spending_unlocked_until = Some(Instant::now() + self.config.spend_unlock_period);
+        info!(
+            target: LOG_TARGET,
+            "Spending unlocked for {}s",
+            self.config.spend_unlock_period.as_secs()
+        );
+
+        Ok(())
+    }
+
+    fn verify_spend_passphrase(&self, passphrase: &Secret<String>) -> Result<(), OutputManagerError> {
+        let passphrase_hash = self
+            .spend_lock
+            .as_ref()
+            .ok_or_else(|| OutputManagerError::ReceiveOnlyModeNotEnabled)?;
+        match argon2::verify_encoded(passphrase_hash, passphrase.as_bytes()) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(OutputManagerError::InvalidSpendPassphrase),
+            Err(e) => Err(OutputManagerError::PassphraseHashError(e.to_string())),
+        }
+    }
+
+    /// Outputs can always be spent unless the wallet is in receive-only mode and spending has not been unlocked
+    fn check_spending_unlocked(&self) -> Result<(), OutputManagerError> {
+        if self.spend_lock.is_none() {
+            return Ok(());
+        }
+        match self.spending_unlocked_until {
+            Some(until) if Instant::now() < until => Ok(()),
+            _ => Err(OutputManagerError::SpendingLocked),
+        }
+    }
+
     /// Confirm the reception of an expected transaction output. This will be called by the Transaction Service when it
     /// detects the output on the blockchain
     pub async fn confirm_received_transaction_output(
@@ -521,8 +858,12 @@ where
         message: String,
     ) -> Result<SenderTransactionProtocol, OutputManagerError>
     {
+        self.check_spending_unlocked()?;
+        if amount < self.config.dust_threshold {
+            return Err(OutputManagerError::AmountBelowDustThreshold);
+        }
         let outputs = self
-            .select_outputs(amount, fee_per_gram, UTXOSelectionStrategy::MaturityThenSmallest)
+            .select_outputs(amount, fee_per_gram, 1, UTXOSelectionStrategy::MaturityThenSmallest)
             .await?;
         let total = outputs.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
 
@@ -536,6 +877,7 @@ where
             .with_offset(offset.clone())
             .with_private_nonce(nonce.clone())
             .with_amount(0, amount)
+            .with_dust_threshold(self.config.dust_threshold)
             .with_message(message);
 
         for uo in outputs.iter() {
@@ -550,28 +892,24 @@ where
         // If the input values > the amount to be sent + fees_without_change then we will need to include a change
         // output
         if total > amount + fee_without_change {
-            let mut key = PrivateKey::default();
-            {
-                let mut km = acquire_lock!(self.key_manager);
-                key = km.next_key()?.k;
-            }
-            self.db.increment_key_index().await?;
+            let (key, rewind_data) = self.next_key_with_rewind_data().await?;
             change_key = Some(key.clone());
-            builder.with_change_secret(key);
+            builder.with_change_secret(key).with_change_rewind_data(rewind_data);
         }
 
         let stp = builder
             .build::<HashDigest>(&self.factories)
             .map_err(|e| OutputManagerError::BuildError(e.message))?;
 
-        // If a change output was created add it to the pending_outputs list.
+        // If a change output was created add it to the pending_outputs list. Dust change is added to the fee so no
+        // change output is created for it.
         let change_output = match change_key {
-            Some(key) => Some(UnblindedOutput {
-                value: stp.get_amount_to_self()?,
-                spending_key: key,
+            Some(key) if stp.get_change_amount()? > MicroTari::from(0) => vec![UnblindedOutput {
+                value: stp.get_change_amount()?,
+                spending_key: Secret::new(key),
                 features: OutputFeatures::default(),
-            }),
-            None => None,
+            }],
+            _ => Vec::new(),
         };
 
         // The Transaction Protocol built successfully so we will pull the unspent outputs out of the unspent list and
@@ -583,6 +921,323 @@ where
         Ok(stp)
     }
 
+    /// Build a transaction that spends enough of the wallet's outputs to create `split_count` new outputs of
+    /// `amount_per_split` each (plus change if required). The outputs all belong to this wallet so the transaction is
+    /// finalized immediately and its outputs are fully encumbered until it is confirmed on the blockchain.
+    pub async fn create_coin_split(
+        &mut self,
+        amount_per_split: MicroTari,
+        split_count: usize,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+    ) -> Result<(TxId, Transaction, MicroTari, MicroTari), OutputManagerError>
+    {
+        self.check_spending_unlocked()?;
+        if split_count == 0 {
+            return Err(OutputManagerError::BuildError(
+                "The split count must be greater than zero".to_string(),
+            ));
+        }
+        if amount_per_split < self.config.dust_threshold {
+            return Err(OutputManagerError::AmountBelowDustThreshold);
+        }
+        let total_split_amount = amount_per_split * split_count as u64;
+        let inputs = self
+            .select_outputs(
+                total_split_amount,
+                fee_per_gram,
+                split_count,
+                UTXOSelectionStrategy::MaturityThenSmallest,
+            )
+            .await?;
+
+        let offset = PrivateKey::random(&mut OsRng);
+        let nonce = PrivateKey::random(&mut OsRng);
+
+        let mut builder = SenderTransactionProtocol::builder(0);
+        builder
+            .with_lock_height(lock_height.unwrap_or(0))
+            .with_fee_per_gram(fee_per_gram)
+            .with_offset(offset)
+            .with_private_nonce(nonce)
+            .with_dust_threshold(self.config.dust_threshold)
+            .with_message("Coin split".to_string());
+
+        for uo in inputs.iter() {
+            builder.with_input(
+                uo.as_transaction_input(&self.factories.commitment, uo.clone().features),
+                uo.clone(),
+            );
+        }
+
+        let mut outputs = Vec::with_capacity(split_count + 1);
+        for _ in 0..split_count {
+            let (key, rewind_data) = self.next_key_with_rewind_data().await?;
+            let output = UnblindedOutput::new(amount_per_split, key, None);
+            builder.with_rewindable_output(output.clone(), rewind_data);
+            outputs.push(output);
+        }
+
+        let (change_key, change_rewind_data) = self.next_key_with_rewind_data().await?;
+        builder
+            .with_change_secret(change_key.clone())
+            .with_change_rewind_data(change_rewind_data);
+
+        let mut stp = builder
+            .build::<HashDigest>(&self.factories)
+            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+
+        let change = stp.get_change_amount()?;
+        if change > MicroTari::from(0) {
+            outputs.push(UnblindedOutput::new(change, change_key, None));
+        }
+        let fee = stp.get_fee_amount()?;
+
+        if !stp.finalize(KernelFeatures::empty(), &self.factories)? {
+            return Err(OutputManagerError::BuildError(format!(
+                "Coin split transaction could not be finalized: {:?}",
+                stp.failure_reason()
+            )));
+        }
+        let tx = stp.take_transaction()?;
+
+        let tx_id = OsRng.next_u64();
+        self.db.encumber_outputs(tx_id, inputs, outputs).await?;
+        self.db.confirm_encumbered_outputs(tx_id).await?;
+
+        Ok((tx_id, tx, fee, total_split_amount))
+    }
+
+    /// Rotate the wallet's master key, for users who fear it has been compromised. The current master key is replaced
+    /// by a new random key, so keys derived from now on are unrelated to the old one, and a plan is stored to migrate
+    /// the wallet's current outputs to keys derived from the new master key over time using `migrate_outputs`. The
+    /// seed words change with the master key and have to be backed up again.
+    pub async fn rotate_master_key(
+        &mut self,
+        fee_per_gram: MicroTari,
+        batch_size: usize,
+    ) -> Result<OutputMigrationPlan, OutputManagerError>
+    {
+        if batch_size == 0 {
+            return Err(OutputManagerError::BuildError(
+                "The migration batch size must be greater than zero".to_string(),
+            ));
+        }
+        // The master key of an external key manager backend is not managed by the wallet
+        if self.key_manager.master_key().is_none() {
+            return Err(KeyManagerBackendError::OperationNotSupported.into());
+        }
+        if let Some(plan) = self.db.get_output_migration_plan().await? {
+            if !plan.is_complete() {
+                return Err(OutputManagerError::KeyRotationInProgress);
+            }
+        }
+        // Outputs that are still to be received or returned as change by pending transactions are owned by the old
+        // master key but could not be included in the migration plan
+        if !self.db.fetch_all_pending_transaction_outputs().await?.is_empty() {
+            return Err(OutputManagerError::TransactionsPending);
+        }
+
+        let state = KeyManagerState {
+            master_seed: Secret::new(PrivateKey::random(&mut OsRng)),
+            branch_seed: "".to_string(),
+            primary_key_index: 0,
+        };
+        self.db.set_key_manager_state(state.clone()).await?;
+        self.rewind_keys = RewindKeys::from_master_key(&state.master_seed)?;
+        self.key_manager = Arc::new(SoftwareKeyManager::new(state.master_seed, state.branch_seed));
+
+        let plan = OutputMigrationPlan {
+            fee_per_gram,
+            batch_size,
+            outputs_to_migrate: self
+                .db
+                .fetch_sorted_unspent_outputs()
+                .await?
+                .into_iter()
+                .map(|o| o.spending_key.reveal().clone())
+                .collect(),
+            migration_tx_ids: Vec::new(),
+            outputs_migrated: 0,
+            amount_migrated: MicroTari::from(0),
+            outputs_skipped: 0,
+            started: Utc::now().naive_utc(),
+        };
+        self.db.set_output_migration_plan(plan.clone()).await?;
+        info!(
+            target: LOG_TARGET,
+            "Master key rotated, {} output(s) will be migrated to the new key",
+            plan.outputs_to_migrate.len()
+        );
+
+        Ok(plan)
+    }
+
+    /// Fetch the plan for migrating outputs after the last master key rotation, updated with the current state of the
+    /// wallet's outputs
+    pub async fn get_output_migration_plan(&mut self) -> Result<Option<OutputMigrationPlan>, OutputManagerError> {
+        match self.db.get_output_migration_plan().await? {
+            None => Ok(None),
+            Some(mut plan) => {
+                self.update_output_migration_plan(&mut plan).await?;
+                self.db.set_output_migration_plan(plan.clone()).await?;
+                Ok(Some(plan))
+            },
+        }
+    }
+
+    /// Build the next batch of the output migration started by `rotate_master_key`. Up to `batch_size` of the largest
+    /// remaining outputs are spent to a single output with a key derived from the new master key. Outputs worth less
+    /// than the fee required to spend them are left behind. Only one batch is in flight at a time, so the next batch
+    /// can only be built once the previous one has been confirmed or cancelled. The returned tuple contains the TxId,
+    /// the finalized transaction, the fee and the amount migrated, or None if there is nothing left to migrate.
+    pub async fn migrate_outputs(
+        &mut self,
+    ) -> Result<Option<(TxId, Transaction, MicroTari, MicroTari)>, OutputManagerError> {
+        let mut plan = self
+            .db
+            .get_output_migration_plan()
+            .await?
+            .ok_or_else(|| OutputManagerError::NoOutputMigrationPlan)?;
+        let pending_transactions = self.db.fetch_all_pending_transaction_outputs().await?;
+        if plan
+            .migration_tx_ids
+            .iter()
+            .any(|tx_id| pending_transactions.contains_key(tx_id))
+        {
+            return Err(OutputManagerError::MigrationBatchPending);
+        }
+
+        let mut inputs = self.update_output_migration_plan(&mut plan).await?;
+        // Migrate the largest outputs first as they have the most value at risk
+        inputs.sort_by(|a, b| b.value.cmp(&a.value));
+        inputs.truncate(plan.batch_size);
+        if inputs.is_empty() {
+            self.db.set_output_migration_plan(plan.clone()).await?;
+            return if plan.is_complete() {
+                Ok(None)
+            } else {
+                Err(OutputManagerError::MigrationOutputsEncumbered)
+            };
+        }
+
+        let total = inputs.iter().map(|o| o.value).sum::<MicroTari>();
+        let fee = Fee::calculate_with_minimum(plan.fee_per_gram, inputs.len(), 1);
+        if total < fee + self.config.dust_threshold {
+            // Even the largest remaining outputs do not cover the fee to migrate them, so neither will the rest
+            plan.outputs_skipped += plan.outputs_to_migrate.len();
+            plan.outputs_to_migrate.clear();
+            self.db.set_output_migration_plan(plan).await?;
+            return Ok(None);
+        }
+        let amount = total - fee;
+
+        let offset = PrivateKey::random(&mut OsRng);
+        let nonce = PrivateKey::random(&mut OsRng);
+        let mut builder = SenderTransactionProtocol::builder(0);
+        builder
+            .with_lock_height(0)
+            .with_fee_per_gram(plan.fee_per_gram)
+            .with_offset(offset)
+            .with_private_nonce(nonce)
+            .with_dust_threshold(self.config.dust_threshold)
+            .with_message("Output migration".to_string());
+
+        for uo in inputs.iter() {
+            builder.with_input(
+                uo.as_transaction_input(&self.factories.commitment, uo.clone().features),
+                uo.clone(),
+            );
+        }
+
+        let (key, rewind_data) = self.next_key_with_rewind_data().await?;
+        let mut outputs = vec![UnblindedOutput::new(amount, key, None)];
+        builder.with_rewindable_output(outputs[0].clone(), rewind_data);
+
+        // The minimum fee can leave a little change over when the calculated fee is lower
+        let (change_key, change_rewind_data) = self.next_key_with_rewind_data().await?;
+        builder
+            .with_change_secret(change_key.clone())
+            .with_change_rewind_data(change_rewind_data);
+
+        let mut stp = builder
+            .build::<HashDigest>(&self.factories)
+            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+
+        let change = stp.get_change_amount()?;
+        if change > MicroTari::from(0) {
+            outputs.push(UnblindedOutput::new(change, change_key, None));
+        }
+        let fee = stp.get_fee_amount()?;
+
+        if !stp.finalize(KernelFeatures::empty(), &self.factories)? {
+            return Err(OutputManagerError::BuildError(format!(
+                "Output migration transaction could not be finalized: {:?}",
+                stp.failure_reason()
+            )));
+        }
+        let tx = stp.take_transaction()?;
+
+        let tx_id = OsRng.next_u64();
+        self.db.encumber_outputs(tx_id, inputs.clone(), outputs).await?;
+        self.db.confirm_encumbered_outputs(tx_id).await?;
+
+        plan.migration_tx_ids.push(tx_id);
+        self.db.set_output_migration_plan(plan).await?;
+        info!(
+            target: LOG_TARGET,
+            "Built output migration transaction (TxId: {}) spending {} output(s) worth {}",
+            tx_id,
+            inputs.len(),
+            total
+        );
+
+        Ok(Some((tx_id, tx, fee, amount)))
+    }
+
+    /// Bring the migration plan up to date with the wallet's outputs and return the unspent outputs that still have to
+    /// be migrated. Outputs that have been spent, whether by a migration batch or a regular transaction, no longer need
+    /// to be migrated. Outputs encumbered by a pending transaction stay in the plan in case that transaction is
+    /// cancelled.
+    async fn update_output_migration_plan(
+        &mut self,
+        plan: &mut OutputMigrationPlan,
+    ) -> Result<Vec<UnblindedOutput>, OutputManagerError>
+    {
+        let unspent_outputs = self.db.fetch_sorted_unspent_outputs().await?;
+        let spent_outputs = self.db.fetch_spent_outputs().await?;
+        let pending_transactions = self.db.fetch_all_pending_transaction_outputs().await?;
+        // An output is only worth migrating if it is worth more than the fee its input adds to a transaction
+        let input_fee = MicroTari::from(WEIGHT_PER_INPUT * u64::from(plan.fee_per_gram));
+
+        let mut to_migrate = Vec::new();
+        let mut remaining = Vec::with_capacity(plan.outputs_to_migrate.len());
+        for key in plan.outputs_to_migrate.drain(..) {
+            if let Some(output) = unspent_outputs.iter().find(|o| o.spending_key == key) {
+                if output.value <= input_fee {
+                    plan.outputs_skipped += 1;
+                } else {
+                    to_migrate.push(output.clone());
+                    remaining.push(key);
+                }
+            } else if pending_transactions
+                .values()
+                .any(|p| p.outputs_to_be_spent.iter().any(|o| o.spending_key == key))
+            {
+                remaining.push(key);
+            } else if let Some(output) = spent_outputs.iter().find(|o| o.spending_key == key) {
+                plan.outputs_migrated += 1;
+                plan.amount_migrated += output.value;
+            } else {
+                // The output was invalidated and can no longer be spent
+                plan.outputs_skipped += 1;
+            }
+        }
+        plan.outputs_to_migrate = remaining;
+
+        Ok(to_migrate)
+    }
+
     /// Confirm that a transaction has finished being negotiated between parties so the short-term encumberance can be
     /// made official
     pub async fn confirm_encumberance(&mut self, tx_id: u64) -> Result<(), OutputManagerError> {
@@ -641,17 +1296,43 @@ where
         Ok(self.db.cancel_pending_transaction_outputs(tx_id).await?)
     }
 
+    /// Return the outputs of a confirmed transaction that was removed from the chain by a reorg to pending. Outputs it
+    /// received are no longer spendable and outputs it spent are no longer counted as spent until the transaction is
+    /// confirmed again. If it is cancelled instead the spent outputs are returned to the unspent pool.
+    pub async fn revert_transaction(&mut self, tx_id: u64) -> Result<(), OutputManagerError> {
+        self.db.revert_confirmed_transaction_outputs(tx_id).await?;
+        warn!(
+            target: LOG_TARGET,
+            "Outputs of TxId: {} returned to pending after the transaction was removed from the chain by a reorg", tx_id
+        );
+
+        self.event_publisher
+            .send(OutputManagerEvent::TransactionReverted(tx_id))
+            .await
+            .map_err(|_| OutputManagerError::EventStreamError)?;
+        Ok(())
+    }
+
     /// Go through the pending transaction and if any have existed longer than the specified duration, cancel them
     pub async fn timeout_pending_transactions(&mut self, period: Duration) -> Result<(), OutputManagerError> {
         Ok(self.db.timeout_pending_transaction_outputs(period).await?)
     }
 
+    /// Derive the next spending key along with the rewind data for an output that uses it
+    async fn next_key_with_rewind_data(&mut self) -> Result<(PrivateKey, RewindData), OutputManagerError> {
+        let key_index = self.db.increment_key_index().await?;
+        let key = self.key_manager.derive_key(key_index)?;
+        let rewind_data = self.rewind_keys.rewind_data(self.config.recovery_byte, key_index);
+        Ok((key, rewind_data))
+    }
+
     /// Select which outputs to use to send a transaction of the specified amount. Use the specified selection strategy
     /// to choose the outputs
     async fn select_outputs(
         &mut self,
         amount: MicroTari,
         fee_per_gram: MicroTari,
+        output_count: usize,
         strategy: UTXOSelectionStrategy,
     ) -> Result<Vec<UnblindedOutput>, OutputManagerError>
     {
@@ -680,9 +1361,9 @@ where
         for o in uo.iter() {
             outputs.push(o.clone());
             total += o.value;
-            // I am assuming that the only output will be the payment output and change if required
-            fee_without_change = Fee::calculate(fee_per_gram, outputs.len(), 1);
-            fee_with_change = Fee::calculate(fee_per_gram, outputs.len(), 2);
+            // The outputs are the payment output(s) and change if required
+            fee_without_change = Fee::calculate(fee_per_gram, outputs.len(), output_count);
+            fee_with_change = Fee::calculate(fee_per_gram, outputs.len(), output_count + 1);
 
             if total == amount + fee_without_change || total >= amount + fee_with_change {
                 break;
@@ -734,10 +1415,11 @@ where
 
     /// Return the Seed words for the current Master Key set in the Key Manager
     pub fn get_seed_words(&self) -> Result<Vec<String>, OutputManagerError> {
-        Ok(from_secret_key(
-            &acquire_lock!(self.key_manager).master_key,
-            &MnemonicLanguage::English,
-        )?)
+        let master_key = self
+            .key_manager
+            .master_key()
+            .ok_or(KeyManagerBackendError::OperationNotSupported)?;
+        Ok(from_secret_key(&master_key, &MnemonicLanguage::English)?)
     }
 }
 
@@ -754,7 +1436,8 @@ pub enum UTXOSelectionStrategy {
 /// This struct holds the detailed balance of the Output Manager Service.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Balance {
-    /// The current balance that is available to spend
+    /// The current balance that is available to spend. This includes funds received in broadcast transactions that
+    /// were made available under the transaction service's zero-conf policy.
     pub available_balance: MicroTari,
     /// The current balance of funds that are due to be received but have not yet been confirmed
     pub pending_incoming_balance: MicroTari,
@@ -762,6 +1445,27 @@ pub struct Balance {
     pub pending_outgoing_balance: MicroTari,
 }
 
+/// The outcome of importing a batch of outputs
+#[derive(Clone, Debug, Default)]
+pub struct ImportedOutputs {
+    /// The outputs that were added to the wallet
+    pub imported: Vec<UnblindedOutput>,
+    /// The number of outputs skipped because the wallet already knows about them
+    pub duplicates: usize,
+    /// The number of outputs rejected because they are not valid
+    pub rejected: usize,
+    /// The request key of the base node query that checks the imported outputs exist and are unspent, if a base node
+    /// is set
+    pub validation_request_key: Option<u64>,
+}
+
+impl ImportedOutputs {
+    /// The total value of the imported outputs
+    pub fn imported_value(&self) -> MicroTari {
+        self.imported.iter().fold(MicroTari::from(0), |acc, o| acc + o.value)
+    }
+}
+
 impl fmt::Display for Balance {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Available balance: {}", self.available_balance)?;
@@ -20,20 +20,26 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{error::OutputManagerStorageError, service::Balance, TxId};
+use crate::{
+    output_manager_service::{error::OutputManagerStorageError, service::Balance, TxId},
+    storage::blocking_adapter::BlockingAdapter,
+};
 use chrono::{NaiveDateTime, Utc};
+use futures::future::BoxFuture;
 use log::*;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     fmt::{Display, Error, Formatter},
-    sync::Arc,
     time::Duration,
 };
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::{OutputFeatures, UnblindedOutput},
-    types::{BlindingFactor, PrivateKey},
+    types::{BlindingFactor, Commitment, CommitmentFactory, PrivateKey},
 };
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, tari_utilities::hex::Hex};
+use tari_secret::Secret;
 
 const LOG_TARGET: &str = "wallet::output_manager_service::database";
 
@@ -50,6 +56,11 @@ pub trait OutputManagerBackend: Send + Sync {
     /// `outputs_to_be_received` from a `PendingTransactionOutputs` record into the `unspent_outputs` and
     /// `spent_outputs` collections.
     fn confirm_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
+    /// This method is called when the block containing a confirmed transaction is removed from the chain by a reorg.
+    /// It must move the outputs received by the transaction out of the `unspent_outputs` collection and the outputs
+    /// spent by the transaction out of the `spent_outputs` collection and back into a `PendingTransactionOutputs`
+    /// record. Received outputs that have since been spent by another transaction are left as they are.
+    fn revert_confirmed_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
     /// This method encumbers the specified outputs into a `PendingTransactionOutputs` record. This is a short term
     /// encumberance in case the app is closed or crashes before transaction neogtiation is complete. These will be
     /// cleared on startup of the service.
@@ -57,7 +68,7 @@ pub trait OutputManagerBackend: Send + Sync {
         &self,
         tx_id: TxId,
         outputs_to_send: &[UnblindedOutput],
-        change_output: Option<UnblindedOutput>,
+        outputs_to_receive: &[UnblindedOutput],
     ) -> Result<(), OutputManagerStorageError>;
     /// This method confirms that a transaction negotiation is complete and outputs can be fully encumbered. This
     /// reserves these outputs until the transaction is confirmed or cancelled
@@ -71,14 +82,104 @@ pub trait OutputManagerBackend: Send + Sync {
     /// This method must run through all the `PendingTransactionOutputs` and test if any have existed for longer that
     /// the specified duration. If they have they should be cancelled.
     fn timeout_pending_transactions(&self, period: Duration) -> Result<(), OutputManagerStorageError>;
-    /// This method will increment the currently stored key index for the key manager config. Increment this after eac
-    /// key is generated
-    fn increment_key_index(&self) -> Result<(), OutputManagerStorageError>;
+    /// This method will increment the currently stored key index for the key manager config and return the new index.
+    /// The key at the returned index is the next key to be generated.
+    fn increment_key_index(&self) -> Result<usize, OutputManagerStorageError>;
     /// If an unspent output is detected as invalid (i.e. not available on the blockchain) then it should be moved to
     /// the invalid outputs collection
     fn invalidate_unspent_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError>;
 }
 
+/// The async counterpart of `OutputManagerBackend` used by the `OutputManagerDatabase`. Arguments are taken by value so
+/// that the returned futures are `'static` and can be driven on any executor. It is implemented for a
+/// `BlockingAdapter` over any synchronous `OutputManagerBackend`.
+pub trait AsyncOutputManagerBackend: Send + Sync {
+    fn fetch(&self, key: DbKey) -> BoxFuture<'static, Result<Option<DbValue>, OutputManagerStorageError>>;
+    fn write(&self, op: WriteOperation) -> BoxFuture<'static, Result<Option<DbValue>, OutputManagerStorageError>>;
+    fn confirm_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>;
+    fn revert_confirmed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>;
+    fn short_term_encumber_outputs(
+        &self,
+        tx_id: TxId,
+        outputs_to_send: Vec<UnblindedOutput>,
+        outputs_to_receive: Vec<UnblindedOutput>,
+    ) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>;
+    fn confirm_encumbered_outputs(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>;
+    fn clear_short_term_encumberances(&self) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>;
+    fn cancel_pending_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>;
+    fn timeout_pending_transactions(
+        &self,
+        period: Duration,
+    ) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>;
+    fn increment_key_index(&self) -> BoxFuture<'static, Result<usize, OutputManagerStorageError>>;
+    fn invalidate_unspent_output(
+        &self,
+        output: UnblindedOutput,
+    ) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>;
+}
+
+impl<T> AsyncOutputManagerBackend for BlockingAdapter<T>
+where T: OutputManagerBackend + 'static
+{
+    fn fetch(&self, key: DbKey) -> BoxFuture<'static, Result<Option<DbValue>, OutputManagerStorageError>> {
+        self.run(move |db| db.fetch(&key))
+    }
+
+    fn write(&self, op: WriteOperation) -> BoxFuture<'static, Result<Option<DbValue>, OutputManagerStorageError>> {
+        self.run(move |db| db.write(op))
+    }
+
+    fn confirm_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), OutputManagerStorageError>> {
+        self.run(move |db| db.confirm_transaction(tx_id))
+    }
+
+    fn revert_confirmed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), OutputManagerStorageError>> {
+        self.run(move |db| db.revert_confirmed_transaction(tx_id))
+    }
+
+    fn short_term_encumber_outputs(
+        &self,
+        tx_id: TxId,
+        outputs_to_send: Vec<UnblindedOutput>,
+        outputs_to_receive: Vec<UnblindedOutput>,
+    ) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>
+    {
+        self.run(move |db| db.short_term_encumber_outputs(tx_id, &outputs_to_send, &outputs_to_receive))
+    }
+
+    fn confirm_encumbered_outputs(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), OutputManagerStorageError>> {
+        self.run(move |db| db.confirm_encumbered_outputs(tx_id))
+    }
+
+    fn clear_short_term_encumberances(&self) -> BoxFuture<'static, Result<(), OutputManagerStorageError>> {
+        self.run(|db| db.clear_short_term_encumberances())
+    }
+
+    fn cancel_pending_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), OutputManagerStorageError>> {
+        self.run(move |db| db.cancel_pending_transaction(tx_id))
+    }
+
+    fn timeout_pending_transactions(
+        &self,
+        period: Duration,
+    ) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>
+    {
+        self.run(move |db| db.timeout_pending_transactions(period))
+    }
+
+    fn increment_key_index(&self) -> BoxFuture<'static, Result<usize, OutputManagerStorageError>> {
+        self.run(|db| db.increment_key_index())
+    }
+
+    fn invalidate_unspent_output(
+        &self,
+        output: UnblindedOutput,
+    ) -> BoxFuture<'static, Result<(), OutputManagerStorageError>>
+    {
+        self.run(move |db| db.invalidate_unspent_output(&output))
+    }
+}
+
 /// Holds the outputs that have been selected for a given pending transaction waiting for confirmation
 #[derive(Debug, Clone, PartialEq)]
 pub struct PendingTransactionOutputs {
@@ -91,11 +192,158 @@ pub struct PendingTransactionOutputs {
 /// Holds the state of the KeyManager being used by the Output Manager Service
 #[derive(Clone, Debug, PartialEq)]
 pub struct KeyManagerState {
-    pub master_seed: PrivateKey,
+    pub master_seed: Secret<PrivateKey>,
     pub branch_seed: String,
     pub primary_key_index: usize,
 }
 
+/// Tracks the migration of the outputs the wallet owned when its master key was rotated. The outputs are migrated in
+/// batches, each of which is a self-spend to a single output with a spending key derived from the new master key.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutputMigrationPlan {
+    pub fee_per_gram: MicroTari,
+    pub batch_size: usize,
+    /// The spending keys of the outputs that have not been migrated yet
+    pub outputs_to_migrate: Vec<PrivateKey>,
+    /// The transactions that were created to migrate outputs, in the order they were created
+    pub migration_tx_ids: Vec<TxId>,
+    /// The number and value of outputs of the old master key that have been spent, either by a migration batch or by
+    /// a regular transaction
+    pub outputs_migrated: usize,
+    pub amount_migrated: MicroTari,
+    /// The number of outputs that were left behind because they are worth less than the fee required to spend them
+    /// or were found to be invalid
+    pub outputs_skipped: usize,
+    pub started: NaiveDateTime,
+}
+
+/// The ways an output can change the wallet balance. Each one is recorded in the balance audit log.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BalanceAuditEvent {
+    /// An output was added to the unspent outputs directly, e.g. an imported, recovered or coinbase output
+    OutputAdded,
+    /// An output was removed from storage
+    OutputRemoved,
+    /// An unspent output was reserved to be spent by a pending transaction
+    OutputEncumbered,
+    /// A pending transaction is expected to create this output for the wallet
+    OutputExpected,
+    /// A pending transaction was cancelled or timed out and the output it reserved is unspent again
+    OutputReleased,
+    /// A pending transaction was cancelled or timed out and the output it was expected to create was discarded
+    ExpectedOutputDiscarded,
+    /// The transaction that creates the output was confirmed
+    OutputReceived,
+    /// The transaction that spends the output was confirmed
+    OutputSpent,
+    /// The output was not found on the blockchain
+    OutputInvalidated,
+    /// The transaction that received or spent the output was removed from the chain by a reorg, so the output is
+    /// pending again
+    OutputReorged,
+}
+
+impl Display for BalanceAuditEvent {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let event = match self {
+            BalanceAuditEvent::OutputAdded => "Output added",
+            BalanceAuditEvent::OutputRemoved => "Output removed",
+            BalanceAuditEvent::OutputEncumbered => "Output encumbered",
+            BalanceAuditEvent::OutputExpected => "Output expected",
+            BalanceAuditEvent::OutputReleased => "Output released",
+            BalanceAuditEvent::ExpectedOutputDiscarded => "Expected output discarded",
+            BalanceAuditEvent::OutputReceived => "Output received",
+            BalanceAuditEvent::OutputSpent => "Output spent",
+            BalanceAuditEvent::OutputInvalidated => "Output invalidated",
+            BalanceAuditEvent::OutputReorged => "Output reorged",
+        };
+        f.write_str(event)
+    }
+}
+
+/// An entry in the append-only log of balance-affecting changes to the wallet's outputs. Outputs are identified by
+/// their commitment so that the log can be shared to investigate a discrepancy without revealing spending keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalanceAuditEntry {
+    /// The position of the entry in the log, assigned by the backend when the entry is stored
+    pub id: u64,
+    pub event: BalanceAuditEvent,
+    pub commitment: Commitment,
+    pub value: MicroTari,
+    /// The transaction that caused the change, if there is one
+    pub tx_id: Option<TxId>,
+    pub timestamp: NaiveDateTime,
+}
+
+impl BalanceAuditEntry {
+    pub fn new(event: BalanceAuditEvent, output: &UnblindedOutput, tx_id: Option<TxId>) -> Self {
+        Self {
+            id: 0,
+            event,
+            commitment: CommitmentFactory::default().commit_value(&output.spending_key, u64::from(output.value)),
+            value: output.value,
+            tx_id,
+            timestamp: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl Display for BalanceAuditEntry {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(
+            f,
+            "{} {}: {} ({})",
+            self.timestamp,
+            self.event,
+            self.value,
+            self.commitment.to_hex()
+        )?;
+        if let Some(tx_id) = self.tx_id {
+            write!(f, " TxId: {}", tx_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// A payment request issued by the wallet. Every invoice is paid to its own spending key, derived when the invoice is
+/// created, so that a payment can be matched to the invoice it settles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Invoice {
+    pub invoice_id: String,
+    pub spending_key: Secret<PrivateKey>,
+    pub key_index: usize,
+    /// The transaction that paid the invoice and the amount it paid, once the payment has been accepted
+    pub tx_id: Option<TxId>,
+    pub amount: Option<MicroTari>,
+    pub timestamp: NaiveDateTime,
+}
+
+impl Invoice {
+    pub fn is_paid(&self) -> bool {
+        self.tx_id.is_some()
+    }
+}
+
+impl OutputMigrationPlan {
+    pub fn is_complete(&self) -> bool {
+        self.outputs_to_migrate.is_empty()
+    }
+}
+
+impl Display for OutputMigrationPlan {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(
+            f,
+            "{} output(s) ({}) migrated in {} transaction(s), {} remaining, {} skipped as uneconomical",
+            self.outputs_migrated,
+            self.amount_migrated,
+            self.migration_tx_ids.len(),
+            self.outputs_to_migrate.len(),
+            self.outputs_skipped
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DbKey {
     SpentOutput(BlindingFactor),
@@ -106,6 +354,12 @@ pub enum DbKey {
     AllPendingTransactionOutputs,
     KeyManagerState,
     InvalidOutputs,
+    OutputMigrationPlan,
+    Invoice(String),
+    Invoices,
+    SpendLock,
+    /// The balance audit log entries, optionally only those linked to the given transaction
+    BalanceAuditLog(Option<TxId>),
 }
 
 #[derive(Debug)]
@@ -118,6 +372,11 @@ pub enum DbValue {
     InvalidOutputs(Vec<UnblindedOutput>),
     AllPendingTransactionOutputs(HashMap<TxId, PendingTransactionOutputs>),
     KeyManagerState(KeyManagerState),
+    OutputMigrationPlan(Box<OutputMigrationPlan>),
+    Invoice(Box<Invoice>),
+    Invoices(Vec<Invoice>),
+    SpendLock(String),
+    BalanceAuditLog(Vec<BalanceAuditEntry>),
 }
 
 pub enum DbKeyValuePair {
@@ -125,6 +384,9 @@ pub enum DbKeyValuePair {
     UnspentOutput(BlindingFactor, Box<UnblindedOutput>),
     PendingTransactionOutputs(TxId, Box<PendingTransactionOutputs>),
     KeyManagerState(KeyManagerState),
+    OutputMigrationPlan(Box<OutputMigrationPlan>),
+    Invoice(String, Box<Invoice>),
+    SpendLock(String),
 }
 
 pub enum WriteOperation {
@@ -150,83 +412,178 @@ macro_rules! fetch {
 pub struct OutputManagerDatabase<T>
 where T: OutputManagerBackend + 'static
 {
-    db: Arc<T>,
+    db: BlockingAdapter<T>,
 }
 
 impl<T> OutputManagerDatabase<T>
 where T: OutputManagerBackend + 'static
 {
     pub fn new(db: T) -> Self {
-        Self { db: Arc::new(db) }
+        Self {
+            db: BlockingAdapter::new(db),
+        }
     }
 
     pub async fn get_key_manager_state(&self) -> Result<Option<KeyManagerState>, OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::KeyManagerState) {
-            Ok(None) => Ok(None),
-            Ok(Some(DbValue::KeyManagerState(c))) => Ok(Some(c)),
-            Ok(Some(other)) => unexpected_result(DbKey::KeyManagerState, other),
-            Err(e) => log_error(DbKey::KeyManagerState, e),
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-        .and_then(|inner_result| inner_result)
+        self.db
+            .run(move |db| match db.fetch(&DbKey::KeyManagerState) {
+                Ok(None) => Ok(None),
+                Ok(Some(DbValue::KeyManagerState(c))) => Ok(Some(c)),
+                Ok(Some(other)) => unexpected_result(DbKey::KeyManagerState, other),
+                Err(e) => log_error(DbKey::KeyManagerState, e),
+            })
+            .await
     }
 
     pub async fn set_key_manager_state(&self, state: KeyManagerState) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::KeyManagerState(state)))
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::KeyManagerState(state)))
+            .await?;
 
         Ok(())
     }
 
-    pub async fn increment_key_index(&self) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.increment_key_index())
+    pub async fn get_output_migration_plan(&self) -> Result<Option<OutputMigrationPlan>, OutputManagerStorageError> {
+        self.db
+            .run(move |db| match db.fetch(&DbKey::OutputMigrationPlan) {
+                Ok(None) => Ok(None),
+                Ok(Some(DbValue::OutputMigrationPlan(p))) => Ok(Some(*p)),
+                Ok(Some(other)) => unexpected_result(DbKey::OutputMigrationPlan, other),
+                Err(e) => log_error(DbKey::OutputMigrationPlan, e),
+            })
+            .await
+    }
+
+    pub async fn set_output_migration_plan(&self, plan: OutputMigrationPlan) -> Result<(), OutputManagerStorageError> {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::OutputMigrationPlan(Box::new(
+                plan,
+            ))))
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store an invoice, replacing the invoice with the same id if there is one
+    pub async fn set_invoice(&self, invoice: Invoice) -> Result<(), OutputManagerStorageError> {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::Invoice(
+                invoice.invoice_id.clone(),
+                Box::new(invoice),
+            )))
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_invoice(&self, invoice_id: String) -> Result<Option<Invoice>, OutputManagerStorageError> {
+        self.db
+            .run(move |db| {
+                let key = DbKey::Invoice(invoice_id);
+                match db.fetch(&key) {
+                    Ok(None) => Ok(None),
+                    Ok(Some(DbValue::Invoice(i))) => Ok(Some(*i)),
+                    Ok(Some(other)) => unexpected_result(key, other),
+                    Err(e) => log_error(key, e),
+                }
+            })
+            .await
+    }
+
+    pub async fn get_invoices(&self) -> Result<Vec<Invoice>, OutputManagerStorageError> {
+        self.db
+            .run(move |db| match db.fetch(&DbKey::Invoices) {
+                Ok(None) => log_error(
+                    DbKey::Invoices,
+                    OutputManagerStorageError::UnexpectedResult("Could not retrieve invoices".to_string()),
+                ),
+                Ok(Some(DbValue::Invoices(i))) => Ok(i),
+                Ok(Some(other)) => unexpected_result(DbKey::Invoices, other),
+                Err(e) => log_error(DbKey::Invoices, e),
+            })
+            .await
+    }
+
+    /// The balance-affecting changes to the wallet's outputs in the order they happened, optionally only those linked
+    /// to the given transaction
+    pub async fn get_balance_audit_log(
+        &self,
+        tx_id: Option<TxId>,
+    ) -> Result<Vec<BalanceAuditEntry>, OutputManagerStorageError>
+    {
+        self.db
+            .run(move |db| {
+                let key = DbKey::BalanceAuditLog(tx_id);
+                match db.fetch(&key) {
+                    Ok(None) => log_error(
+                        key,
+                        OutputManagerStorageError::UnexpectedResult("Could not retrieve balance audit log".to_string()),
+                    ),
+                    Ok(Some(DbValue::BalanceAuditLog(entries))) => Ok(entries),
+                    Ok(Some(other)) => unexpected_result(key, other),
+                    Err(e) => log_error(key, e),
+                }
+            })
+            .await
+    }
+
+    /// The hash of the passphrase that unlocks spending when the wallet is in receive-only mode
+    pub async fn get_spend_lock(&self) -> Result<Option<String>, OutputManagerStorageError> {
+        self.db
+            .run(move |db| match db.fetch(&DbKey::SpendLock) {
+                Ok(None) => Ok(None),
+                Ok(Some(DbValue::SpendLock(h))) => Ok(Some(h)),
+                Ok(Some(other)) => unexpected_result(DbKey::SpendLock, other),
+                Err(e) => log_error(DbKey::SpendLock, e),
+            })
             .await
-            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+    }
+
+    pub async fn set_spend_lock(&self, passphrase_hash: String) -> Result<(), OutputManagerStorageError> {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::SpendLock(passphrase_hash)))
+            .await?;
+
         Ok(())
     }
 
+    pub async fn clear_spend_lock(&self) -> Result<(), OutputManagerStorageError> {
+        self.db.write(WriteOperation::Remove(DbKey::SpendLock)).await?;
+
+        Ok(())
+    }
+
+    /// Reserve the next key index and return it
+    pub async fn increment_key_index(&self) -> Result<usize, OutputManagerStorageError> {
+        self.db.increment_key_index().await
+    }
+
     pub async fn add_unspent_output(&self, output: UnblindedOutput) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::UnspentOutput(
-                output.spending_key.clone(),
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::UnspentOutput(
+                output.spending_key.reveal().clone(),
                 Box::new(output),
             )))
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        .await?;
 
         Ok(())
     }
 
     pub async fn get_balance(&self) -> Result<Balance, OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        let db_clone2 = self.db.clone();
-
-        let pending_txs = tokio::task::spawn_blocking(move || {
-            db_clone.fetch(&DbKey::AllPendingTransactionOutputs)?.ok_or_else(|| {
+        let pending_txs = self
+            .db
+            .fetch(DbKey::AllPendingTransactionOutputs)
+            .await?
+            .ok_or_else(|| {
                 OutputManagerStorageError::UnexpectedResult(
                     "Pending Transaction Outputs cannot be retrieved".to_string(),
                 )
-            })
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+            })?;
+
+        let unspent_outputs = self.db.fetch(DbKey::UnspentOutputs).await?.ok_or_else(|| {
+            OutputManagerStorageError::UnexpectedResult("Unspent Outputs cannot be retrieved".to_string())
+        })?;
 
-        let unspent_outputs = tokio::task::spawn_blocking(move || {
-            db_clone2.fetch(&DbKey::UnspentOutputs)?.ok_or_else(|| {
-                OutputManagerStorageError::UnexpectedResult("Unspent Outputs cannot be retrieved".to_string())
-            })
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
         if let DbValue::UnspentOutputs(uo) = unspent_outputs {
             if let DbValue::AllPendingTransactionOutputs(pto) = pending_txs {
                 let available_balance = uo.iter().fold(MicroTari::from(0), |acc, x| acc + x.value);
@@ -262,15 +619,12 @@ where T: OutputManagerBackend + 'static
         pending_transaction_outputs: PendingTransactionOutputs,
     ) -> Result<(), OutputManagerStorageError>
     {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PendingTransactionOutputs(
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::PendingTransactionOutputs(
                 pending_transaction_outputs.tx_id,
                 Box::new(pending_transaction_outputs),
             )))
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        .await?;
 
         Ok(())
     }
@@ -280,22 +634,23 @@ where T: OutputManagerBackend + 'static
         tx_id: TxId,
     ) -> Result<PendingTransactionOutputs, OutputManagerStorageError>
     {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || fetch!(db_clone, tx_id, PendingTransactionOutputs))
+        self.db
+            .run(move |db| fetch!(db, tx_id, PendingTransactionOutputs))
             .await
-            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
     }
 
     /// This method is called when a pending transaction is confirmed. It moves the `outputs_to_be_spent` and
     /// `outputs_to_be_received` from a `PendingTransactionOutputs` record into the `unspent_outputs` and
     /// `spent_outputs` collections.
     pub async fn confirm_pending_transaction_outputs(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.confirm_transaction(tx_id))
-            .await
-            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.confirm_transaction(tx_id).await
+    }
+
+    /// This method is called when a confirmed transaction is removed from the chain by a reorg. It moves the outputs
+    /// it received and spent back into a `PendingTransactionOutputs` record, so that they are confirmed again if the
+    /// transaction is mined again or released if the transaction is cancelled.
+    pub async fn revert_confirmed_transaction_outputs(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
+        self.db.revert_confirmed_transaction(tx_id).await
     }
 
     /// This method accepts and stores a pending inbound transaction and creates the `output_to_be_received` from the
@@ -308,182 +663,153 @@ where T: OutputManagerBackend + 'static
         output_features: OutputFeatures,
     ) -> Result<(), OutputManagerStorageError>
     {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PendingTransactionOutputs(
-                tx_id,
-                Box::new(PendingTransactionOutputs {
+        self.db
+            .run(move |db| {
+                db.write(WriteOperation::Insert(DbKeyValuePair::PendingTransactionOutputs(
                     tx_id,
-                    outputs_to_be_spent: Vec::new(),
-                    outputs_to_be_received: vec![UnblindedOutput {
-                        value: amount,
-                        spending_key: spending_key.clone(),
-                        features: output_features,
-                    }],
-                    timestamp: Utc::now().naive_utc(),
-                }),
-            )))
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+                    Box::new(PendingTransactionOutputs {
+                        tx_id,
+                        outputs_to_be_spent: Vec::new(),
+                        outputs_to_be_received: vec![UnblindedOutput {
+                            value: amount,
+                            spending_key: Secret::new(spending_key),
+                            features: output_features,
+                        }],
+                        timestamp: Utc::now().naive_utc(),
+                    }),
+                )))
+            })
+            .await?;
         Ok(())
     }
 
     /// This method is called when a transaction is built to be sent. It will encumber unspent outputs against a pending
-    /// transaction in the short term.
+    /// transaction in the short term. The `outputs_to_receive` are the change (or other outputs to self) produced by the
+    /// transaction.
     pub async fn encumber_outputs(
         &self,
         tx_id: TxId,
         outputs_to_send: Vec<UnblindedOutput>,
-        change_output: Option<UnblindedOutput>,
+        outputs_to_receive: Vec<UnblindedOutput>,
     ) -> Result<(), OutputManagerStorageError>
     {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.short_term_encumber_outputs(tx_id, &outputs_to_send, change_output)
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-        .and_then(|inner_result| inner_result)
+        self.db
+            .short_term_encumber_outputs(tx_id, outputs_to_send, outputs_to_receive)
+            .await
     }
 
     /// This method is called when a transaction is finished being negotiated. This will fully encumber the outputs
     /// against a pending transaction.
     pub async fn confirm_encumbered_outputs(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.confirm_encumbered_outputs(tx_id))
-            .await
-            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.confirm_encumbered_outputs(tx_id).await
     }
 
     /// Clear all pending transaction encumberances marked as short term. These are the result of an unfinished
     /// transaction negotiation
     pub async fn clear_short_term_encumberances(&self) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.clear_short_term_encumberances())
-            .await
-            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.clear_short_term_encumberances().await
     }
 
     /// When a pending transaction is cancelled the encumbered outputs are moved back to the `unspent_outputs`
     /// collection.
     pub async fn cancel_pending_transaction_outputs(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.cancel_pending_transaction(tx_id))
-            .await
-            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.cancel_pending_transaction(tx_id).await
     }
 
     /// This method is check all pending transactions to see if any are older that the provided duration. If they are
     /// they will be cancelled.
     pub async fn timeout_pending_transaction_outputs(&self, period: Duration) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.timeout_pending_transactions(period))
-            .await
-            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.timeout_pending_transactions(period).await
     }
 
     pub async fn fetch_sorted_unspent_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-
-        let mut uo = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::UnspentOutputs) {
-            Ok(None) => log_error(
-                DbKey::UnspentOutputs,
-                OutputManagerStorageError::UnexpectedResult("Could not retrieve unspent outputs".to_string()),
-            ),
-            Ok(Some(DbValue::UnspentOutputs(uo))) => Ok(uo),
-            Ok(Some(other)) => unexpected_result(DbKey::UnspentOutputs, other),
-            Err(e) => log_error(DbKey::UnspentOutputs, e),
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let mut uo = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::UnspentOutputs) {
+                Ok(None) => log_error(
+                    DbKey::UnspentOutputs,
+                    OutputManagerStorageError::UnexpectedResult("Could not retrieve unspent outputs".to_string()),
+                ),
+                Ok(Some(DbValue::UnspentOutputs(uo))) => Ok(uo),
+                Ok(Some(other)) => unexpected_result(DbKey::UnspentOutputs, other),
+                Err(e) => log_error(DbKey::UnspentOutputs, e),
+            })
+            .await?;
 
         uo.sort();
         Ok(uo)
     }
 
     pub async fn fetch_spent_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-
-        let uo = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::SpentOutputs) {
-            Ok(None) => log_error(
-                DbKey::UnspentOutputs,
-                OutputManagerStorageError::UnexpectedResult("Could not retrieve spent outputs".to_string()),
-            ),
-            Ok(Some(DbValue::SpentOutputs(uo))) => Ok(uo),
-            Ok(Some(other)) => unexpected_result(DbKey::SpentOutputs, other),
-            Err(e) => log_error(DbKey::SpentOutputs, e),
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let uo = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::SpentOutputs) {
+                Ok(None) => log_error(
+                    DbKey::UnspentOutputs,
+                    OutputManagerStorageError::UnexpectedResult("Could not retrieve spent outputs".to_string()),
+                ),
+                Ok(Some(DbValue::SpentOutputs(uo))) => Ok(uo),
+                Ok(Some(other)) => unexpected_result(DbKey::SpentOutputs, other),
+                Err(e) => log_error(DbKey::SpentOutputs, e),
+            })
+            .await?;
         Ok(uo)
     }
 
     pub async fn fetch_all_pending_transaction_outputs(
         &self,
     ) -> Result<HashMap<u64, PendingTransactionOutputs>, OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-
-        let uo = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::AllPendingTransactionOutputs) {
-            Ok(None) => log_error(
-                DbKey::AllPendingTransactionOutputs,
-                OutputManagerStorageError::UnexpectedResult(
-                    "Could not retrieve pending transaction outputs".to_string(),
+        let uo = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::AllPendingTransactionOutputs) {
+                Ok(None) => log_error(
+                    DbKey::AllPendingTransactionOutputs,
+                    OutputManagerStorageError::UnexpectedResult(
+                        "Could not retrieve pending transaction outputs".to_string(),
+                    ),
                 ),
-            ),
-            Ok(Some(DbValue::AllPendingTransactionOutputs(pt))) => Ok(pt),
-            Ok(Some(other)) => unexpected_result(DbKey::AllPendingTransactionOutputs, other),
-            Err(e) => log_error(DbKey::AllPendingTransactionOutputs, e),
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+                Ok(Some(DbValue::AllPendingTransactionOutputs(pt))) => Ok(pt),
+                Ok(Some(other)) => unexpected_result(DbKey::AllPendingTransactionOutputs, other),
+                Err(e) => log_error(DbKey::AllPendingTransactionOutputs, e),
+            })
+            .await?;
         Ok(uo)
     }
 
     pub async fn get_unspent_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-
-        let uo = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::UnspentOutputs) {
-            Ok(None) => log_error(
-                DbKey::UnspentOutputs,
-                OutputManagerStorageError::UnexpectedResult("Could not retrieve unspent outputs".to_string()),
-            ),
-            Ok(Some(DbValue::UnspentOutputs(uo))) => Ok(uo),
-            Ok(Some(other)) => unexpected_result(DbKey::UnspentOutputs, other),
-            Err(e) => log_error(DbKey::UnspentOutputs, e),
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let uo = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::UnspentOutputs) {
+                Ok(None) => log_error(
+                    DbKey::UnspentOutputs,
+                    OutputManagerStorageError::UnexpectedResult("Could not retrieve unspent outputs".to_string()),
+                ),
+                Ok(Some(DbValue::UnspentOutputs(uo))) => Ok(uo),
+                Ok(Some(other)) => unexpected_result(DbKey::UnspentOutputs, other),
+                Err(e) => log_error(DbKey::UnspentOutputs, e),
+            })
+            .await?;
         Ok(uo)
     }
 
     pub async fn get_invalid_outputs(&self) -> Result<Vec<UnblindedOutput>, OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-
-        let uo = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::InvalidOutputs) {
-            Ok(None) => log_error(
-                DbKey::InvalidOutputs,
-                OutputManagerStorageError::UnexpectedResult("Could not retrieve invalid outputs".to_string()),
-            ),
-            Ok(Some(DbValue::InvalidOutputs(uo))) => Ok(uo),
-            Ok(Some(other)) => unexpected_result(DbKey::InvalidOutputs, other),
-            Err(e) => log_error(DbKey::InvalidOutputs, e),
-        })
-        .await
-        .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let uo = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::InvalidOutputs) {
+                Ok(None) => log_error(
+                    DbKey::InvalidOutputs,
+                    OutputManagerStorageError::UnexpectedResult("Could not retrieve invalid outputs".to_string()),
+                ),
+                Ok(Some(DbValue::InvalidOutputs(uo))) => Ok(uo),
+                Ok(Some(other)) => unexpected_result(DbKey::InvalidOutputs, other),
+                Err(e) => log_error(DbKey::InvalidOutputs, e),
+            })
+            .await?;
         Ok(uo)
     }
 
     pub async fn invalidate_output(&self, output: UnblindedOutput) -> Result<(), OutputManagerStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.invalidate_unspent_output(&output))
-            .await
-            .or_else(|err| Err(OutputManagerStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.invalidate_unspent_output(output).await
     }
 }
 
@@ -506,6 +832,11 @@ impl Display for DbKey {
             DbKey::AllPendingTransactionOutputs => f.write_str(&"All Pending Transaction Outputs".to_string()),
             DbKey::KeyManagerState => f.write_str(&"Key Manager State".to_string()),
             DbKey::InvalidOutputs => f.write_str(&"Invalid Outputs Key"),
+            DbKey::OutputMigrationPlan => f.write_str(&"Output Migration Plan"),
+            DbKey::Invoice(id) => f.write_str(&format!("Invoice: {}", id)),
+            DbKey::Invoices => f.write_str(&"Invoices"),
+            DbKey::SpendLock => f.write_str(&"Spend Lock"),
+            DbKey::BalanceAuditLog(_) => f.write_str(&"Balance Audit Log"),
         }
     }
 }
@@ -521,6 +852,11 @@ impl Display for DbValue {
             DbValue::AllPendingTransactionOutputs(_) => f.write_str("All Pending Transaction Outputs"),
             DbValue::KeyManagerState(_) => f.write_str("Key Manager State"),
             DbValue::InvalidOutputs(_) => f.write_str("Invalid Outputs"),
+            DbValue::OutputMigrationPlan(_) => f.write_str("Output Migration Plan"),
+            DbValue::Invoice(_) => f.write_str("Invoice"),
+            DbValue::Invoices(_) => f.write_str("Invoices"),
+            DbValue::SpendLock(_) => f.write_str("Spend Lock"),
+            DbValue::BalanceAuditLog(_) => f.write_str("Balance Audit Log"),
         }
     }
 }
@@ -20,25 +20,28 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{
-    error::OutputManagerStorageError,
-    storage::database::{
-        DbKey,
-        DbKeyValuePair,
-        DbValue,
-        KeyManagerState,
-        OutputManagerBackend,
-        PendingTransactionOutputs,
-        WriteOperation,
+use crate::{
+    output_manager_service::{
+        error::OutputManagerStorageError,
+        storage::database::{
+            BalanceAuditEntry,
+            BalanceAuditEvent,
+            DbKey,
+            DbKeyValuePair,
+            DbValue,
+            Invoice,
+            KeyManagerState,
+            OutputManagerBackend,
+            OutputMigrationPlan,
+            PendingTransactionOutputs,
+            WriteOperation,
+        },
+        TxId,
     },
-    TxId,
+    storage::memory_store::MemoryStore,
 };
 use chrono::{Duration as ChronoDuration, Utc};
-use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
-    time::Duration,
-};
+use std::{collections::HashMap, time::Duration};
 use tari_core::transactions::transaction::UnblindedOutput;
 
 /// This structure is an In-Memory database backend that implements the `OutputManagerBackend` trait and provides all
@@ -50,7 +53,12 @@ pub struct InnerDatabase {
     invalid_outputs: Vec<UnblindedOutput>,
     pending_transactions: HashMap<TxId, PendingTransactionOutputs>,
     short_term_pending_transactions: HashMap<TxId, PendingTransactionOutputs>,
+    confirmed_transactions: HashMap<TxId, PendingTransactionOutputs>,
     key_manager_state: Option<KeyManagerState>,
+    output_migration_plan: Option<OutputMigrationPlan>,
+    invoices: HashMap<String, Invoice>,
+    spend_lock: Option<String>,
+    balance_audit_log: Vec<BalanceAuditEntry>,
 }
 
 impl InnerDatabase {
@@ -61,272 +69,378 @@ impl InnerDatabase {
             invalid_outputs: Vec::new(),
             pending_transactions: HashMap::new(),
             short_term_pending_transactions: Default::default(),
+            confirmed_transactions: HashMap::new(),
             key_manager_state: None,
+            output_migration_plan: None,
+            invoices: HashMap::new(),
+            spend_lock: None,
+            balance_audit_log: Vec::new(),
         }
     }
+
+    /// Append an entry to the balance audit log
+    fn record(&mut self, event: BalanceAuditEvent, output: &UnblindedOutput, tx_id: Option<TxId>) {
+        let mut entry = BalanceAuditEntry::new(event, output, tx_id);
+        entry.id = self.balance_audit_log.len() as u64 + 1;
+        self.balance_audit_log.push(entry);
+    }
+
+    /// Remove a pending transaction and return the outputs it was going to spend to the unspent pool
+    fn cancel_pending_transaction(&mut self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
+        let mut pending_tx = self.pending_transactions.remove(&tx_id);
+
+        if pending_tx.is_none() {
+            pending_tx = self.short_term_pending_transactions.remove(&tx_id);
+        }
+
+        let mut pending_tx = pending_tx
+            .ok_or_else(|| OutputManagerStorageError::ValueNotFound(DbKey::PendingTransactionOutputs(tx_id)))?;
+
+        for o in pending_tx.outputs_to_be_spent.drain(..) {
+            self.record(BalanceAuditEvent::OutputReleased, &o, Some(tx_id));
+            self.unspent_outputs.push(o);
+        }
+        for o in pending_tx.outputs_to_be_received.iter() {
+            self.record(BalanceAuditEvent::ExpectedOutputDiscarded, o, Some(tx_id));
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Clone, Default)]
 pub struct OutputManagerMemoryDatabase {
-    db: Arc<RwLock<InnerDatabase>>,
+    db: MemoryStore<InnerDatabase>,
 }
 
 impl OutputManagerMemoryDatabase {
     pub fn new() -> Self {
         Self {
-            db: Arc::new(RwLock::new(InnerDatabase::new())),
+            db: MemoryStore::new(InnerDatabase::new()),
         }
     }
 }
 
 impl OutputManagerBackend for OutputManagerMemoryDatabase {
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, OutputManagerStorageError> {
-        let db = acquire_read_lock!(self.db);
-        let result = match key {
-            DbKey::SpentOutput(k) => db
-                .spent_outputs
-                .iter()
-                .find(|v| &v.spending_key == k)
-                .map(|v| DbValue::SpentOutput(Box::new(v.clone()))),
-            DbKey::UnspentOutput(k) => db
-                .unspent_outputs
-                .iter()
-                .find(|v| &v.spending_key == k)
-                .map(|v| DbValue::UnspentOutput(Box::new(v.clone()))),
-            DbKey::PendingTransactionOutputs(tx_id) => {
-                let mut result = db.pending_transactions.get(tx_id);
-                if result.is_none() {
-                    result = db.short_term_pending_transactions.get(&tx_id);
-                }
-                result.map(|v| DbValue::PendingTransactionOutputs(Box::new(v.clone())))
-            },
-            DbKey::UnspentOutputs => Some(DbValue::UnspentOutputs(db.unspent_outputs.clone())),
-            DbKey::SpentOutputs => Some(DbValue::SpentOutputs(db.spent_outputs.clone())),
-            DbKey::AllPendingTransactionOutputs => {
-                let mut pending_tx_outputs = db.pending_transactions.clone();
-                for (k, v) in db.short_term_pending_transactions.iter() {
-                    pending_tx_outputs.insert(k.clone(), v.clone());
-                }
-                Some(DbValue::AllPendingTransactionOutputs(pending_tx_outputs))
-            },
-            DbKey::KeyManagerState => db
-                .key_manager_state
-                .as_ref()
-                .map(|km| DbValue::KeyManagerState(km.clone())),
-            DbKey::InvalidOutputs => Some(DbValue::InvalidOutputs(db.invalid_outputs.clone())),
-        };
-
-        Ok(result)
-    }
-
-    fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, OutputManagerStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-        match op {
-            WriteOperation::Insert(kvp) => match kvp {
-                DbKeyValuePair::SpentOutput(k, o) => {
-                    if db.spent_outputs.iter().any(|v| v.spending_key == k) ||
-                        db.unspent_outputs.iter().any(|v| v.spending_key == k)
-                    {
-                        return Err(OutputManagerStorageError::DuplicateOutput);
+        let key = key.clone();
+        self.db.run(move |db| {
+            let result = match &key {
+                DbKey::SpentOutput(k) => db
+                    .spent_outputs
+                    .iter()
+                    .find(|v| &v.spending_key == k)
+                    .map(|v| DbValue::SpentOutput(Box::new(v.clone()))),
+                DbKey::UnspentOutput(k) => db
+                    .unspent_outputs
+                    .iter()
+                    .find(|v| &v.spending_key == k)
+                    .map(|v| DbValue::UnspentOutput(Box::new(v.clone()))),
+                DbKey::PendingTransactionOutputs(tx_id) => {
+                    let mut result = db.pending_transactions.get(tx_id);
+                    if result.is_none() {
+                        result = db.short_term_pending_transactions.get(&tx_id);
                     }
-                    db.spent_outputs.push(*o);
+                    result.map(|v| DbValue::PendingTransactionOutputs(Box::new(v.clone())))
                 },
-                DbKeyValuePair::UnspentOutput(k, o) => {
-                    if db.unspent_outputs.iter().any(|v| v.spending_key == k) ||
-                        db.spent_outputs.iter().any(|v| v.spending_key == k)
-                    {
-                        return Err(OutputManagerStorageError::DuplicateOutput);
+                DbKey::UnspentOutputs => Some(DbValue::UnspentOutputs(db.unspent_outputs.clone())),
+                DbKey::SpentOutputs => Some(DbValue::SpentOutputs(db.spent_outputs.clone())),
+                DbKey::AllPendingTransactionOutputs => {
+                    let mut pending_tx_outputs = db.pending_transactions.clone();
+                    for (k, v) in db.short_term_pending_transactions.iter() {
+                        pending_tx_outputs.insert(k.clone(), v.clone());
                     }
-                    db.unspent_outputs.push(*o);
+                    Some(DbValue::AllPendingTransactionOutputs(pending_tx_outputs))
                 },
-                DbKeyValuePair::PendingTransactionOutputs(t, p) => {
-                    db.pending_transactions.insert(t, *p);
+                DbKey::KeyManagerState => db
+                    .key_manager_state
+                    .as_ref()
+                    .map(|km| DbValue::KeyManagerState(km.clone())),
+                DbKey::InvalidOutputs => Some(DbValue::InvalidOutputs(db.invalid_outputs.clone())),
+                DbKey::OutputMigrationPlan => db
+                    .output_migration_plan
+                    .as_ref()
+                    .map(|p| DbValue::OutputMigrationPlan(Box::new(p.clone()))),
+                DbKey::Invoice(id) => db.invoices.get(id).map(|i| DbValue::Invoice(Box::new(i.clone()))),
+                DbKey::Invoices => {
+                    let mut invoices = db.invoices.values().cloned().collect::<Vec<_>>();
+                    invoices.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+                    Some(DbValue::Invoices(invoices))
                 },
-                DbKeyValuePair::KeyManagerState(km) => db.key_manager_state = Some(km),
-            },
-            WriteOperation::Remove(k) => match k {
-                DbKey::SpentOutput(k) => match db.spent_outputs.iter().position(|v| v.spending_key == k) {
-                    None => return Err(OutputManagerStorageError::ValueNotFound(DbKey::SpentOutput(k))),
-                    Some(pos) => {
-                        return Ok(Some(DbValue::SpentOutput(Box::new(db.spent_outputs.remove(pos)))));
+                DbKey::SpendLock => db.spend_lock.as_ref().map(|h| DbValue::SpendLock(h.clone())),
+                DbKey::BalanceAuditLog(tx_id) => Some(DbValue::BalanceAuditLog(
+                    db.balance_audit_log
+                        .iter()
+                        .filter(|e| tx_id.is_none() || e.tx_id == *tx_id)
+                        .cloned()
+                        .collect(),
+                )),
+            };
+
+            Ok(result)
+        })
+    }
+
+    fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, OutputManagerStorageError> {
+        self.db.run(move |db| {
+            match op {
+                WriteOperation::Insert(kvp) => match kvp {
+                    DbKeyValuePair::SpentOutput(k, o) => {
+                        if db.spent_outputs.iter().any(|v| v.spending_key == k) ||
+                            db.unspent_outputs.iter().any(|v| v.spending_key == k)
+                        {
+                            return Err(OutputManagerStorageError::DuplicateOutput);
+                        }
+                        db.record(BalanceAuditEvent::OutputSpent, &o, None);
+                        db.spent_outputs.push(*o);
                     },
-                },
-                DbKey::UnspentOutput(k) => match db.unspent_outputs.iter().position(|v| v.spending_key == k) {
-                    None => return Err(OutputManagerStorageError::ValueNotFound(DbKey::UnspentOutput(k))),
-                    Some(pos) => {
-                        return Ok(Some(DbValue::UnspentOutput(Box::new(db.unspent_outputs.remove(pos)))));
+                    DbKeyValuePair::UnspentOutput(k, o) => {
+                        if db.unspent_outputs.iter().any(|v| v.spending_key == k) ||
+                            db.spent_outputs.iter().any(|v| v.spending_key == k)
+                        {
+                            return Err(OutputManagerStorageError::DuplicateOutput);
+                        }
+                        db.record(BalanceAuditEvent::OutputAdded, &o, None);
+                        db.unspent_outputs.push(*o);
+                    },
+                    DbKeyValuePair::PendingTransactionOutputs(t, p) => {
+                        for o in p.outputs_to_be_spent.iter() {
+                            db.record(BalanceAuditEvent::OutputEncumbered, o, Some(t));
+                        }
+                        for o in p.outputs_to_be_received.iter() {
+                            db.record(BalanceAuditEvent::OutputExpected, o, Some(t));
+                        }
+                        db.pending_transactions.insert(t, *p);
                     },
+                    DbKeyValuePair::KeyManagerState(km) => db.key_manager_state = Some(km),
+                    DbKeyValuePair::OutputMigrationPlan(p) => db.output_migration_plan = Some(*p),
+                    DbKeyValuePair::Invoice(id, i) => {
+                        db.invoices.insert(id, *i);
+                    },
+                    DbKeyValuePair::SpendLock(h) => db.spend_lock = Some(h),
                 },
-                DbKey::PendingTransactionOutputs(tx_id) => {
-                    if let Some(p) = db.pending_transactions.remove(&tx_id) {
-                        return Ok(Some(DbValue::PendingTransactionOutputs(Box::new(p))));
-                    } else {
-                        return Err(OutputManagerStorageError::ValueNotFound(
-                            DbKey::PendingTransactionOutputs(tx_id),
-                        ));
-                    }
+                WriteOperation::Remove(k) => match k {
+                    DbKey::SpentOutput(k) => match db.spent_outputs.iter().position(|v| v.spending_key == k) {
+                        None => return Err(OutputManagerStorageError::ValueNotFound(DbKey::SpentOutput(k))),
+                        Some(pos) => {
+                            let output = db.spent_outputs.remove(pos);
+                            db.record(BalanceAuditEvent::OutputRemoved, &output, None);
+                            return Ok(Some(DbValue::SpentOutput(Box::new(output))));
+                        },
+                    },
+                    DbKey::UnspentOutput(k) => match db.unspent_outputs.iter().position(|v| v.spending_key == k) {
+                        None => return Err(OutputManagerStorageError::ValueNotFound(DbKey::UnspentOutput(k))),
+                        Some(pos) => {
+                            let output = db.unspent_outputs.remove(pos);
+                            db.record(BalanceAuditEvent::OutputRemoved, &output, None);
+                            return Ok(Some(DbValue::UnspentOutput(Box::new(output))));
+                        },
+                    },
+                    DbKey::PendingTransactionOutputs(tx_id) => {
+                        if let Some(p) = db.pending_transactions.remove(&tx_id) {
+                            return Ok(Some(DbValue::PendingTransactionOutputs(Box::new(p))));
+                        } else {
+                            return Err(OutputManagerStorageError::ValueNotFound(
+                                DbKey::PendingTransactionOutputs(tx_id),
+                            ));
+                        }
+                    },
+                    DbKey::UnspentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                    DbKey::SpentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                    DbKey::AllPendingTransactionOutputs => {
+                        return Err(OutputManagerStorageError::OperationNotSupported)
+                    },
+                    DbKey::KeyManagerState => return Err(OutputManagerStorageError::OperationNotSupported),
+                    DbKey::InvalidOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
+                    DbKey::OutputMigrationPlan => return Err(OutputManagerStorageError::OperationNotSupported),
+                    DbKey::Invoice(_) => return Err(OutputManagerStorageError::OperationNotSupported),
+                    DbKey::Invoices => return Err(OutputManagerStorageError::OperationNotSupported),
+                    DbKey::BalanceAuditLog(_) => return Err(OutputManagerStorageError::OperationNotSupported),
+                    DbKey::SpendLock => {
+                        if let Some(h) = db.spend_lock.take() {
+                            return Ok(Some(DbValue::SpendLock(h)));
+                        }
+                    },
                 },
-                DbKey::UnspentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
-                DbKey::SpentOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
-                DbKey::AllPendingTransactionOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
-                DbKey::KeyManagerState => return Err(OutputManagerStorageError::OperationNotSupported),
-                DbKey::InvalidOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
-            },
-        }
-        Ok(None)
+            }
+            Ok(None)
+        })
     }
 
     fn confirm_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
-        let mut db = acquire_write_lock!(self.db);
+        self.db.run(move |db| {
+            let mut pending_tx = db.pending_transactions.remove(&tx_id);
+            if pending_tx.is_none() {
+                pending_tx = db.short_term_pending_transactions.remove(&tx_id);
+            }
 
-        let mut pending_tx = db.pending_transactions.remove(&tx_id);
-        if pending_tx.is_none() {
-            pending_tx = db.short_term_pending_transactions.remove(&tx_id);
-        }
+            let mut pending_tx = pending_tx
+                .ok_or_else(|| OutputManagerStorageError::ValueNotFound(DbKey::PendingTransactionOutputs(tx_id)))?;
+            // Keep a record of the outputs in case the transaction is reverted by a reorg
+            db.confirmed_transactions.insert(tx_id, pending_tx.clone());
 
-        let mut pending_tx = pending_tx
-            .ok_or_else(|| OutputManagerStorageError::ValueNotFound(DbKey::PendingTransactionOutputs(tx_id)))?;
+            // Add Spent outputs
+            for o in pending_tx.outputs_to_be_spent.drain(..) {
+                db.record(BalanceAuditEvent::OutputSpent, &o, Some(tx_id));
+                db.spent_outputs.push(o)
+            }
 
-        // Add Spent outputs
-        for o in pending_tx.outputs_to_be_spent.drain(..) {
-            db.spent_outputs.push(o)
-        }
+            // Add Unspent outputs
+            for o in pending_tx.outputs_to_be_received.drain(..) {
+                db.record(BalanceAuditEvent::OutputReceived, &o, Some(tx_id));
+                db.unspent_outputs.push(o);
+            }
 
-        // Add Unspent outputs
-        for o in pending_tx.outputs_to_be_received.drain(..) {
-            db.unspent_outputs.push(o);
-        }
+            Ok(())
+        })
+    }
 
-        Ok(())
+    fn revert_confirmed_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
+        self.db.run(move |db| {
+            let mut confirmed_tx = db
+                .confirmed_transactions
+                .remove(&tx_id)
+                .ok_or(OutputManagerStorageError::ConfirmedTransactionNotFound)?;
+
+            // Received outputs which have since been spent by another transaction are left as they are
+            confirmed_tx
+                .outputs_to_be_received
+                .retain(|o| db.unspent_outputs.iter().any(|uo| uo.spending_key == o.spending_key));
+            for o in confirmed_tx.outputs_to_be_received.iter() {
+                db.unspent_outputs.retain(|uo| uo.spending_key != o.spending_key);
+                db.record(BalanceAuditEvent::OutputReorged, o, Some(tx_id));
+            }
+            for o in confirmed_tx.outputs_to_be_spent.iter() {
+                db.spent_outputs.retain(|so| so.spending_key != o.spending_key);
+                db.record(BalanceAuditEvent::OutputReorged, o, Some(tx_id));
+            }
+
+            confirmed_tx.timestamp = Utc::now().naive_utc();
+            db.pending_transactions.insert(tx_id, confirmed_tx);
+
+            Ok(())
+        })
     }
 
     fn short_term_encumber_outputs(
         &self,
         tx_id: TxId,
         outputs_to_send: &[UnblindedOutput],
-        change_output: Option<UnblindedOutput>,
+        outputs_to_receive: &[UnblindedOutput],
     ) -> Result<(), OutputManagerStorageError>
     {
-        let mut db = acquire_write_lock!(self.db);
-        let mut outputs_to_be_spent = Vec::new();
-        for i in outputs_to_send {
-            if let Some(pos) = db.unspent_outputs.iter().position(|v| v.spending_key == i.spending_key) {
-                outputs_to_be_spent.push(db.unspent_outputs.remove(pos));
-            } else {
+        let outputs_to_send = outputs_to_send.to_vec();
+        let outputs_to_receive = outputs_to_receive.to_vec();
+        self.db.run(move |db| {
+            // Check that every output is available before any are removed, so a failed encumberance changes nothing
+            if !outputs_to_send
+                .iter()
+                .all(|i| db.unspent_outputs.iter().any(|v| v.spending_key == i.spending_key))
+            {
                 return Err(OutputManagerStorageError::ValuesNotFound);
             }
-        }
-
-        let mut pending_transaction = PendingTransactionOutputs {
-            tx_id,
-            outputs_to_be_spent,
-            outputs_to_be_received: Vec::new(),
-            timestamp: Utc::now().naive_utc(),
-        };
+            let mut outputs_to_be_spent = Vec::new();
+            for i in outputs_to_send {
+                if let Some(pos) = db.unspent_outputs.iter().position(|v| v.spending_key == i.spending_key) {
+                    let output = db.unspent_outputs.remove(pos);
+                    db.record(BalanceAuditEvent::OutputEncumbered, &output, Some(tx_id));
+                    outputs_to_be_spent.push(output);
+                }
+            }
+            for o in outputs_to_receive.iter() {
+                db.record(BalanceAuditEvent::OutputExpected, o, Some(tx_id));
+            }
 
-        if let Some(co) = change_output {
-            pending_transaction.outputs_to_be_received.push(co);
-        }
+            let pending_transaction = PendingTransactionOutputs {
+                tx_id,
+                outputs_to_be_spent,
+                outputs_to_be_received: outputs_to_receive,
+                timestamp: Utc::now().naive_utc(),
+            };
 
-        db.short_term_pending_transactions.insert(tx_id, pending_transaction);
+            db.short_term_pending_transactions.insert(tx_id, pending_transaction);
 
-        Ok(())
+            Ok(())
+        })
     }
 
     fn confirm_encumbered_outputs(&self, tx_id: u64) -> Result<(), OutputManagerStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-
-        let pending_tx = db
-            .short_term_pending_transactions
-            .remove(&tx_id)
-            .ok_or_else(|| OutputManagerStorageError::ValueNotFound(DbKey::PendingTransactionOutputs(tx_id)))?;
+        self.db.run(move |db| {
+            let pending_tx = db
+                .short_term_pending_transactions
+                .remove(&tx_id)
+                .ok_or_else(|| OutputManagerStorageError::ValueNotFound(DbKey::PendingTransactionOutputs(tx_id)))?;
 
-        let _ = db.pending_transactions.insert(pending_tx.tx_id, pending_tx);
+            let _ = db.pending_transactions.insert(pending_tx.tx_id, pending_tx);
 
-        Ok(())
+            Ok(())
+        })
     }
 
     fn clear_short_term_encumberances(&self) -> Result<(), OutputManagerStorageError> {
-        let db = acquire_write_lock!(self.db);
+        self.db.run(|db| {
+            let short_term_encumberances = db.short_term_pending_transactions.keys().cloned().collect::<Vec<_>>();
 
-        let short_term_encumberances = db.short_term_pending_transactions.clone();
-
-        drop(db);
-
-        for tx_id in short_term_encumberances.keys() {
-            self.cancel_pending_transaction(tx_id.clone())?;
-        }
-        Ok(())
+            for tx_id in short_term_encumberances {
+                db.cancel_pending_transaction(tx_id)?;
+            }
+            Ok(())
+        })
     }
 
     fn cancel_pending_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-        let mut pending_tx = db.pending_transactions.remove(&tx_id);
-
-        if pending_tx.is_none() {
-            pending_tx = db.short_term_pending_transactions.remove(&tx_id);
-        }
-
-        let mut pending_tx = pending_tx
-            .ok_or_else(|| OutputManagerStorageError::ValueNotFound(DbKey::PendingTransactionOutputs(tx_id)))?;
-
-        for o in pending_tx.outputs_to_be_spent.drain(..) {
-            db.unspent_outputs.push(o);
-        }
-
-        Ok(())
+        self.db.run(move |db| db.cancel_pending_transaction(tx_id))
     }
 
     fn timeout_pending_transactions(&self, period: Duration) -> Result<(), OutputManagerStorageError> {
-        let db = acquire_write_lock!(self.db);
-        let mut transactions_to_be_cancelled = Vec::new();
+        self.db.run(move |db| {
+            let mut transactions_to_be_cancelled = Vec::new();
 
-        for (tx_id, pt) in db.pending_transactions.iter() {
-            if pt.timestamp + ChronoDuration::from_std(period)? < Utc::now().naive_utc() {
-                transactions_to_be_cancelled.push(tx_id.clone());
+            for (tx_id, pt) in db.pending_transactions.iter() {
+                if pt.timestamp + ChronoDuration::from_std(period)? < Utc::now().naive_utc() {
+                    transactions_to_be_cancelled.push(tx_id.clone());
+                }
             }
-        }
-        for (tx_id, pt) in db.short_term_pending_transactions.iter() {
-            if pt.timestamp + ChronoDuration::from_std(period)? < Utc::now().naive_utc() {
-                transactions_to_be_cancelled.push(tx_id.clone());
+            for (tx_id, pt) in db.short_term_pending_transactions.iter() {
+                if pt.timestamp + ChronoDuration::from_std(period)? < Utc::now().naive_utc() {
+                    transactions_to_be_cancelled.push(tx_id.clone());
+                }
             }
-        }
 
-        drop(db);
-        for t in transactions_to_be_cancelled {
-            self.cancel_pending_transaction(t.clone())?;
-        }
+            for t in transactions_to_be_cancelled {
+                db.cancel_pending_transaction(t)?;
+            }
 
-        Ok(())
+            Ok(())
+        })
     }
 
     fn invalidate_unspent_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-        match db
-            .unspent_outputs
-            .iter()
-            .position(|v| v.spending_key == output.spending_key)
-        {
-            Some(pos) => {
-                let output = db.unspent_outputs.remove(pos);
-                db.invalid_outputs.push(output);
-            },
-            None => return Err(OutputManagerStorageError::ValuesNotFound),
-        }
-        Ok(())
+        let output = output.clone();
+        self.db.run(move |db| {
+            match db
+                .unspent_outputs
+                .iter()
+                .position(|v| v.spending_key == output.spending_key)
+            {
+                Some(pos) => {
+                    let output = db.unspent_outputs.remove(pos);
+                    db.record(BalanceAuditEvent::OutputInvalidated, &output, None);
+                    db.invalid_outputs.push(output);
+                },
+                None => return Err(OutputManagerStorageError::ValuesNotFound),
+            }
+            Ok(())
+        })
     }
 
-    fn increment_key_index(&self) -> Result<(), OutputManagerStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-
-        if db.key_manager_state.is_none() {
-            return Err(OutputManagerStorageError::KeyManagerNotInitialized);
-        }
-        db.key_manager_state = db.key_manager_state.clone().map(|mut state| {
-            state.primary_key_index += 1;
-            state
-        });
-
-        Ok(())
+    fn increment_key_index(&self) -> Result<usize, OutputManagerStorageError> {
+        self.db.run(move |db| match db.key_manager_state.as_mut() {
+            Some(state) => {
+                state.primary_key_index += 1;
+                Ok(state.primary_key_index)
+            },
+            None => Err(OutputManagerStorageError::KeyManagerNotInitialized),
+        })
     }
 }
@@ -24,17 +24,29 @@ use crate::{
     output_manager_service::{
         error::OutputManagerStorageError,
         storage::database::{
+            BalanceAuditEntry,
+            BalanceAuditEvent,
             DbKey,
             DbKeyValuePair,
             DbValue,
+            Invoice,
             KeyManagerState,
             OutputManagerBackend,
+            OutputMigrationPlan,
             PendingTransactionOutputs,
             WriteOperation,
         },
         TxId,
     },
-    schema::{key_manager_states, outputs, pending_transaction_outputs},
+    schema::{
+        balance_audit_log,
+        invoices,
+        key_manager_states,
+        output_migration_plans,
+        outputs,
+        pending_transaction_outputs,
+        spend_locks,
+    },
 };
 use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
 #[cfg(test)]
@@ -49,9 +61,10 @@ use std::{
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::{OutputFeatures, OutputFlags, UnblindedOutput},
-    types::PrivateKey,
+    types::{Commitment, PrivateKey},
 };
 use tari_crypto::tari_utilities::ByteArray;
+use tari_secret::Secret;
 
 /// A Sqlite backend for the Output Manager Service. The Backend is accessed via a connection pool to the Sqlite file.
 #[derive(Clone)]
@@ -137,6 +150,30 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     .map(|o| UnblindedOutput::try_from(o.clone()))
                     .collect::<Result<Vec<_>, _>>()?,
             )),
+            DbKey::OutputMigrationPlan => match OutputMigrationPlanSql::get_plan(&(*conn))? {
+                None => None,
+                Some(p) => {
+                    let plan = OutputMigrationPlan::try_from(p)?;
+                    Some(DbValue::OutputMigrationPlan(Box::new(plan)))
+                },
+            },
+            DbKey::Invoice(id) => match InvoiceSql::find(id, &(*conn))? {
+                None => None,
+                Some(i) => Some(DbValue::Invoice(Box::new(Invoice::try_from(i)?))),
+            },
+            DbKey::Invoices => Some(DbValue::Invoices(
+                InvoiceSql::index(&(*conn))?
+                    .into_iter()
+                    .map(Invoice::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::SpendLock => SpendLockSql::get(&(*conn))?.map(|l| DbValue::SpendLock(l.passphrase_hash)),
+            DbKey::BalanceAuditLog(tx_id) => Some(DbValue::BalanceAuditLog(
+                BalanceAuditEntrySql::index(*tx_id, &(*conn))?
+                    .into_iter()
+                    .map(BalanceAuditEntry::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
         };
 
         Ok(result)
@@ -151,12 +188,14 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     if OutputSql::find(&k.to_vec(), &(*conn)).is_ok() {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
+                    BalanceAuditEntrySql::record(BalanceAuditEvent::OutputSpent, &o, None, &(*conn))?;
                     OutputSql::new(*o, OutputStatus::Spent, None).commit(&(*conn))?
                 },
                 DbKeyValuePair::UnspentOutput(k, o) => {
                     if OutputSql::find(&k.to_vec(), &(*conn)).is_ok() {
                         return Err(OutputManagerStorageError::DuplicateOutput);
                     }
+                    BalanceAuditEntrySql::record(BalanceAuditEvent::OutputAdded, &o, None, &(*conn))?;
                     OutputSql::new(*o, OutputStatus::Unspent, None).commit(&(*conn))?
                 },
                 DbKeyValuePair::PendingTransactionOutputs(tx_id, p) => {
@@ -165,20 +204,27 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                     }
                     PendingTransactionOutputSql::new(p.tx_id, true, p.timestamp).commit(&(*conn))?;
                     for o in p.outputs_to_be_spent {
+                        BalanceAuditEntrySql::record(BalanceAuditEvent::OutputEncumbered, &o, Some(p.tx_id), &(*conn))?;
                         OutputSql::new(o.clone(), OutputStatus::EncumberedToBeSpent, Some(p.tx_id)).commit(&(*conn))?;
                     }
                     for o in p.outputs_to_be_received {
+                        BalanceAuditEntrySql::record(BalanceAuditEvent::OutputExpected, &o, Some(p.tx_id), &(*conn))?;
                         OutputSql::new(o.clone(), OutputStatus::EncumberedToBeReceived, Some(p.tx_id))
                             .commit(&(*conn))?;
                     }
                 },
                 DbKeyValuePair::KeyManagerState(km) => KeyManagerStateSql::set_state(km, &(*conn))?,
+                DbKeyValuePair::OutputMigrationPlan(p) => OutputMigrationPlanSql::set_plan(*p, &(*conn))?,
+                DbKeyValuePair::Invoice(_, i) => InvoiceSql::from(*i).set(&(*conn))?,
+                DbKeyValuePair::SpendLock(h) => SpendLockSql::set(h, &(*conn))?,
             },
             WriteOperation::Remove(k) => match k {
                 DbKey::SpentOutput(s) => match OutputSql::find_status(&s.to_vec(), OutputStatus::Spent, &(*conn)) {
                     Ok(o) => {
                         o.delete(&(*conn))?;
-                        return Ok(Some(DbValue::SpentOutput(Box::new(UnblindedOutput::try_from(o)?))));
+                        let output = UnblindedOutput::try_from(o)?;
+                        BalanceAuditEntrySql::record(BalanceAuditEvent::OutputRemoved, &output, None, &(*conn))?;
+                        return Ok(Some(DbValue::SpentOutput(Box::new(output))));
                     },
                     Err(e) => {
                         match e {
@@ -190,7 +236,9 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 DbKey::UnspentOutput(k) => match OutputSql::find_status(&k.to_vec(), OutputStatus::Unspent, &(*conn)) {
                     Ok(o) => {
                         o.delete(&(*conn))?;
-                        return Ok(Some(DbValue::UnspentOutput(Box::new(UnblindedOutput::try_from(o)?))));
+                        let output = UnblindedOutput::try_from(o)?;
+                        BalanceAuditEntrySql::record(BalanceAuditEvent::OutputRemoved, &output, None, &(*conn))?;
+                        return Ok(Some(DbValue::UnspentOutput(Box::new(output))));
                     },
                     Err(e) => {
                         match e {
@@ -219,6 +267,16 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 DbKey::AllPendingTransactionOutputs => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::KeyManagerState => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::InvalidOutputs => {},
+                DbKey::OutputMigrationPlan => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::Invoice(_) => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::Invoices => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::BalanceAuditLog(_) => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::SpendLock => {
+                    if let Some(l) = SpendLockSql::get(&(*conn))? {
+                        diesel::delete(spend_locks::table).execute(&(*conn))?;
+                        return Ok(Some(DbValue::SpendLock(l.passphrase_hash)));
+                    }
+                },
             },
         }
 
@@ -241,6 +299,13 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                             },
                             &(*conn),
                         )?;
+                        let output = UnblindedOutput::try_from(o)?;
+                        BalanceAuditEntrySql::record(
+                            BalanceAuditEvent::OutputReceived,
+                            &output,
+                            Some(tx_id),
+                            &(*conn),
+                        )?;
                     } else if o.status == (OutputStatus::EncumberedToBeSpent as i32) {
                         o.update(
                             UpdateOutput {
@@ -249,6 +314,8 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                             },
                             &(*conn),
                         )?;
+                        let output = UnblindedOutput::try_from(o)?;
+                        BalanceAuditEntrySql::record(BalanceAuditEvent::OutputSpent, &output, Some(tx_id), &(*conn))?;
                     }
                 }
 
@@ -265,11 +332,41 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         Ok(())
     }
 
+    fn revert_confirmed_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
+        let conn = acquire_lock!(self.database_connection);
+
+        // The outputs keep the tx_id of the last transaction that received or spent them after it is confirmed
+        let outputs = OutputSql::find_by_tx_id_and_confirmed(tx_id, &(*conn))?;
+        if outputs.is_empty() {
+            return Err(OutputManagerStorageError::ConfirmedTransactionNotFound);
+        }
+
+        PendingTransactionOutputSql::new(tx_id, false, Utc::now().naive_utc()).commit(&(*conn))?;
+        for o in outputs {
+            let status = if o.status == (OutputStatus::Unspent as i32) {
+                OutputStatus::EncumberedToBeReceived
+            } else {
+                OutputStatus::EncumberedToBeSpent
+            };
+            o.update(
+                UpdateOutput {
+                    status: Some(status),
+                    tx_id: None,
+                },
+                &(*conn),
+            )?;
+            let output = UnblindedOutput::try_from(o)?;
+            BalanceAuditEntrySql::record(BalanceAuditEvent::OutputReorged, &output, Some(tx_id), &(*conn))?;
+        }
+
+        Ok(())
+    }
+
     fn short_term_encumber_outputs(
         &self,
         tx_id: u64,
         outputs_to_send: &[UnblindedOutput],
-        change_output: Option<UnblindedOutput>,
+        outputs_to_receive: &[UnblindedOutput],
     ) -> Result<(), OutputManagerStorageError>
     {
         let conn = acquire_lock!(self.database_connection);
@@ -293,10 +390,13 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 },
                 &(*conn),
             )?;
+            let output = UnblindedOutput::try_from(o)?;
+            BalanceAuditEntrySql::record(BalanceAuditEvent::OutputEncumbered, &output, Some(tx_id), &(*conn))?;
         }
 
-        if let Some(co) = change_output {
-            OutputSql::new(co, OutputStatus::EncumberedToBeReceived, Some(tx_id)).commit(&(*conn))?;
+        for co in outputs_to_receive {
+            BalanceAuditEntrySql::record(BalanceAuditEvent::OutputExpected, co, Some(tx_id), &(*conn))?;
+            OutputSql::new(co.clone(), OutputStatus::EncumberedToBeReceived, Some(tx_id)).commit(&(*conn))?;
         }
 
         Ok(())
@@ -347,6 +447,13 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 for o in outputs {
                     if o.status == (OutputStatus::EncumberedToBeReceived as i32) {
                         o.delete(&(*conn))?;
+                        let output = UnblindedOutput::try_from(o)?;
+                        BalanceAuditEntrySql::record(
+                            BalanceAuditEvent::ExpectedOutputDiscarded,
+                            &output,
+                            Some(tx_id),
+                            &(*conn),
+                        )?;
                     } else if o.status == (OutputStatus::EncumberedToBeSpent as i32) {
                         o.update(
                             UpdateOutput {
@@ -356,6 +463,13 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                             &(*conn),
                         )?;
                         o.update_null(NullOutputSql { tx_id: None }, &(*conn))?;
+                        let output = UnblindedOutput::try_from(o)?;
+                        BalanceAuditEntrySql::record(
+                            BalanceAuditEvent::OutputReleased,
+                            &output,
+                            Some(tx_id),
+                            &(*conn),
+                        )?;
                     }
                 }
 
@@ -390,12 +504,10 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         Ok(())
     }
 
-    fn increment_key_index(&self) -> Result<(), OutputManagerStorageError> {
+    fn increment_key_index(&self) -> Result<usize, OutputManagerStorageError> {
         let conn = acquire_lock!(self.database_connection);
 
-        KeyManagerStateSql::increment_index(&(*conn))?;
-
-        Ok(())
+        KeyManagerStateSql::increment_index(&(*conn))
     }
 
     fn invalidate_unspent_output(&self, output: &UnblindedOutput) -> Result<(), OutputManagerStorageError> {
@@ -408,6 +520,8 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             },
             &(*conn),
         )?;
+        let output = UnblindedOutput::try_from(output)?;
+        BalanceAuditEntrySql::record(BalanceAuditEvent::OutputInvalidated, &output, None, &(*conn))?;
 
         Ok(())
     }
@@ -535,6 +649,22 @@ impl OutputSql {
             .load(conn)?)
     }
 
+    /// Find outputs via tx_id that were received or spent by a confirmed transaction
+    pub fn find_by_tx_id_and_confirmed(
+        tx_id: TxId,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<OutputSql>, OutputManagerStorageError>
+    {
+        Ok(outputs::table
+            .filter(outputs::tx_id.eq(Some(tx_id as i64)))
+            .filter(
+                outputs::status
+                    .eq(OutputStatus::Unspent as i32)
+                    .or(outputs::status.eq(OutputStatus::Spent as i32)),
+            )
+            .load(conn)?)
+    }
+
     /// Find a particular Output, if it exists and is in the specified Spent state
     pub fn find_status(
         spending_key: &[u8],
@@ -607,11 +737,13 @@ impl TryFrom<OutputSql> for UnblindedOutput {
         Ok(Self {
             value: MicroTari::from(o.value as u64),
             spending_key: PrivateKey::from_vec(&o.spending_key)
+                .map(Secret::new)
                 .map_err(|_| OutputManagerStorageError::ConversionError)?,
             features: OutputFeatures {
                 flags: OutputFlags::from_bits(o.flags as u8)
                     .ok_or_else(|| OutputManagerStorageError::ConversionError)?,
                 maturity: o.maturity as u64,
+                ..Default::default()
             },
         })
     }
@@ -779,6 +911,7 @@ impl TryFrom<KeyManagerStateSql> for KeyManagerState {
     fn try_from(km: KeyManagerStateSql) -> Result<Self, Self::Error> {
         Ok(Self {
             master_seed: PrivateKey::from_vec(&km.master_seed)
+                .map(Secret::new)
                 .map_err(|_| OutputManagerStorageError::ConversionError)?,
             branch_seed: km.branch_seed,
             primary_key_index: km.primary_key_index as usize,
@@ -852,7 +985,7 @@ impl KeyManagerStateSql {
 }
 
 struct KeyManagerStateUpdate {
-    master_seed: Option<PrivateKey>,
+    master_seed: Option<Secret<PrivateKey>>,
     branch_seed: Option<String>,
     primary_key_index: Option<usize>,
 }
@@ -875,11 +1008,249 @@ impl From<KeyManagerStateUpdate> for KeyManagerStateUpdateSql {
     }
 }
 
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "output_migration_plans"]
+struct OutputMigrationPlanSql {
+    id: Option<i64>,
+    plan: String,
+    timestamp: NaiveDateTime,
+}
+
+impl TryFrom<OutputMigrationPlan> for OutputMigrationPlanSql {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(p: OutputMigrationPlan) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: None,
+            plan: serde_json::to_string(&p).map_err(|_| OutputManagerStorageError::ConversionError)?,
+            timestamp: Utc::now().naive_utc(),
+        })
+    }
+}
+
+impl TryFrom<OutputMigrationPlanSql> for OutputMigrationPlan {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(p: OutputMigrationPlanSql) -> Result<Self, Self::Error> {
+        serde_json::from_str(&p.plan).map_err(|_| OutputManagerStorageError::ConversionError)
+    }
+}
+
+impl OutputMigrationPlanSql {
+    pub fn get_plan(conn: &SqliteConnection) -> Result<Option<OutputMigrationPlanSql>, OutputManagerStorageError> {
+        Ok(output_migration_plans::table
+            .first::<OutputMigrationPlanSql>(conn)
+            .optional()?)
+    }
+
+    /// There is only ever one migration plan, so storing a plan replaces the existing one
+    pub fn set_plan(plan: OutputMigrationPlan, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        let plan = OutputMigrationPlanSql::try_from(plan)?;
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            diesel::delete(output_migration_plans::table).execute(conn)?;
+            diesel::insert_into(output_migration_plans::table)
+                .values(plan)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "invoices"]
+struct InvoiceSql {
+    invoice_id: String,
+    spending_key: Vec<u8>,
+    key_index: i64,
+    tx_id: Option<i64>,
+    amount: Option<i64>,
+    timestamp: NaiveDateTime,
+}
+
+impl From<Invoice> for InvoiceSql {
+    fn from(i: Invoice) -> Self {
+        Self {
+            invoice_id: i.invoice_id,
+            spending_key: i.spending_key.to_vec(),
+            key_index: i.key_index as i64,
+            tx_id: i.tx_id.map(|t| t as i64),
+            amount: i.amount.map(|a| u64::from(a) as i64),
+            timestamp: i.timestamp,
+        }
+    }
+}
+
+impl TryFrom<InvoiceSql> for Invoice {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(i: InvoiceSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            invoice_id: i.invoice_id,
+            spending_key: PrivateKey::from_vec(&i.spending_key)
+                .map(Secret::new)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            key_index: i.key_index as usize,
+            tx_id: i.tx_id.map(|t| t as u64),
+            amount: i.amount.map(|a| MicroTari::from(a as u64)),
+            timestamp: i.timestamp,
+        })
+    }
+}
+
+impl InvoiceSql {
+    pub fn find(invoice_id: &str, conn: &SqliteConnection) -> Result<Option<InvoiceSql>, OutputManagerStorageError> {
+        Ok(invoices::table
+            .filter(invoices::invoice_id.eq(invoice_id))
+            .first::<InvoiceSql>(conn)
+            .optional()?)
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<InvoiceSql>, OutputManagerStorageError> {
+        Ok(invoices::table
+            .order(invoices::timestamp.asc())
+            .load::<InvoiceSql>(conn)?)
+    }
+
+    /// Insert the invoice or replace the stored invoice with the same id
+    pub fn set(self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::replace_into(invoices::table).values(self).execute(conn)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "spend_locks"]
+struct SpendLockSql {
+    id: Option<i64>,
+    passphrase_hash: String,
+    timestamp: NaiveDateTime,
+}
+
+impl SpendLockSql {
+    pub fn get(conn: &SqliteConnection) -> Result<Option<SpendLockSql>, OutputManagerStorageError> {
+        Ok(spend_locks::table.first::<SpendLockSql>(conn).optional()?)
+    }
+
+    /// There is only ever one spend lock, so setting a lock replaces the existing one
+    pub fn set(passphrase_hash: String, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        let lock = SpendLockSql {
+            id: None,
+            passphrase_hash,
+            timestamp: Utc::now().naive_utc(),
+        };
+        conn.transaction::<_, OutputManagerStorageError, _>(|| {
+            diesel::delete(spend_locks::table).execute(conn)?;
+            diesel::insert_into(spend_locks::table).values(lock).execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl TryFrom<i32> for BalanceAuditEvent {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BalanceAuditEvent::OutputAdded),
+            1 => Ok(BalanceAuditEvent::OutputRemoved),
+            2 => Ok(BalanceAuditEvent::OutputEncumbered),
+            3 => Ok(BalanceAuditEvent::OutputExpected),
+            4 => Ok(BalanceAuditEvent::OutputReleased),
+            5 => Ok(BalanceAuditEvent::ExpectedOutputDiscarded),
+            6 => Ok(BalanceAuditEvent::OutputReceived),
+            7 => Ok(BalanceAuditEvent::OutputSpent),
+            8 => Ok(BalanceAuditEvent::OutputInvalidated),
+            9 => Ok(BalanceAuditEvent::OutputReorged),
+            _ => Err(OutputManagerStorageError::ConversionError),
+        }
+    }
+}
+
+/// This struct represents a BalanceAuditEntry in the Sql database. Entries are only ever inserted, the id is assigned
+/// by Sqlite on insert.
+#[derive(Clone, Debug, Queryable, Insertable)]
+#[table_name = "balance_audit_log"]
+struct BalanceAuditEntrySql {
+    id: Option<i64>,
+    event: i32,
+    commitment: Vec<u8>,
+    value: i64,
+    tx_id: Option<i64>,
+    timestamp: NaiveDateTime,
+}
+
+impl From<BalanceAuditEntry> for BalanceAuditEntrySql {
+    fn from(e: BalanceAuditEntry) -> Self {
+        Self {
+            id: None,
+            event: e.event as i32,
+            commitment: e.commitment.to_vec(),
+            value: u64::from(e.value) as i64,
+            tx_id: e.tx_id.map(|t| t as i64),
+            timestamp: e.timestamp,
+        }
+    }
+}
+
+impl TryFrom<BalanceAuditEntrySql> for BalanceAuditEntry {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(e: BalanceAuditEntrySql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: e.id.unwrap_or_default() as u64,
+            event: BalanceAuditEvent::try_from(e.event)?,
+            commitment: Commitment::from_bytes(&e.commitment)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            value: MicroTari::from(e.value as u64),
+            tx_id: e.tx_id.map(|t| t as u64),
+            timestamp: e.timestamp,
+        })
+    }
+}
+
+impl BalanceAuditEntrySql {
+    /// Append an entry for the output to the balance audit log
+    pub fn record(
+        event: BalanceAuditEvent,
+        output: &UnblindedOutput,
+        tx_id: Option<TxId>,
+        conn: &SqliteConnection,
+    ) -> Result<(), OutputManagerStorageError>
+    {
+        let entry = BalanceAuditEntrySql::from(BalanceAuditEntry::new(event, output, tx_id));
+        diesel::insert_into(balance_audit_log::table)
+            .values(entry)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Return the entries in the order they were recorded, optionally only those linked to the given transaction
+    pub fn index(
+        tx_id: Option<TxId>,
+        conn: &SqliteConnection,
+    ) -> Result<Vec<BalanceAuditEntrySql>, OutputManagerStorageError> {
+        let mut query = balance_audit_log::table.into_boxed();
+        if let Some(tx_id) = tx_id {
+            query = query.filter(balance_audit_log::tx_id.eq(tx_id as i64));
+        }
+        Ok(query
+            .order(balance_audit_log::id.asc())
+            .load::<BalanceAuditEntrySql>(conn)?)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::output_manager_service::storage::{
-        database::KeyManagerState,
-        sqlite_db::{KeyManagerStateSql, OutputSql, OutputStatus, PendingTransactionOutputSql, UpdateOutput},
+        database::{KeyManagerState, OutputMigrationPlan},
+        sqlite_db::{
+            KeyManagerStateSql,
+            OutputMigrationPlanSql,
+            OutputSql,
+            OutputStatus,
+            PendingTransactionOutputSql,
+            UpdateOutput,
+        },
     };
     use chrono::{Duration as ChronoDuration, Utc};
     use diesel::{Connection, SqliteConnection};
@@ -1047,7 +1418,7 @@ mod test {
         assert!(KeyManagerStateSql::get_state(&conn).is_err());
 
         let state1 = KeyManagerState {
-            master_seed: PrivateKey::random(&mut OsRng),
+            master_seed: Secret::new(PrivateKey::random(&mut OsRng)),
             branch_seed: random_string(8),
             primary_key_index: 0,
         };
@@ -1059,7 +1430,7 @@ mod test {
         assert_eq!(state1, KeyManagerState::try_from(state1_read).unwrap());
 
         let state2 = KeyManagerState {
-            master_seed: PrivateKey::random(&mut OsRng),
+            master_seed: Secret::new(PrivateKey::random(&mut OsRng)),
             branch_seed: random_string(8),
             primary_key_index: 0,
         };
@@ -1077,4 +1448,41 @@ mod test {
 
         assert_eq!(state3_read.primary_key_index, 2);
     }
+
+    #[test]
+    fn test_output_migration_plan_crud() {
+        let db_name = format!("{}.sqlite3", random_string(8).as_str());
+        let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        embed_migrations!("./migrations");
+        let conn = SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+
+        embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+
+        assert!(OutputMigrationPlanSql::get_plan(&conn).unwrap().is_none());
+
+        let mut plan = OutputMigrationPlan {
+            fee_per_gram: MicroTari::from(25),
+            batch_size: 10,
+            outputs_to_migrate: vec![PrivateKey::random(&mut OsRng), PrivateKey::random(&mut OsRng)],
+            migration_tx_ids: Vec::new(),
+            outputs_migrated: 0,
+            amount_migrated: MicroTari::from(0),
+            outputs_skipped: 0,
+            started: Utc::now().naive_utc(),
+        };
+        OutputMigrationPlanSql::set_plan(plan.clone(), &conn).unwrap();
+        let plan_read = OutputMigrationPlanSql::get_plan(&conn).unwrap().unwrap();
+        assert_eq!(plan, OutputMigrationPlan::try_from(plan_read).unwrap());
+
+        plan.outputs_to_migrate.remove(0);
+        plan.migration_tx_ids.push(1);
+        plan.outputs_migrated = 1;
+        plan.amount_migrated = MicroTari::from(1000);
+        OutputMigrationPlanSql::set_plan(plan.clone(), &conn).unwrap();
+        let plan_read = OutputMigrationPlanSql::get_plan(&conn).unwrap().unwrap();
+        assert_eq!(plan, OutputMigrationPlan::try_from(plan_read).unwrap());
+    }
 }
@@ -1,3 +1,14 @@
+table! {
+    balance_audit_log (id) {
+        id -> Nullable<BigInt>,
+        event -> Integer,
+        commitment -> Binary,
+        value -> BigInt,
+        tx_id -> Nullable<BigInt>,
+        timestamp -> Timestamp,
+    }
+}
+
 table! {
     coinbase_transactions (tx_id) {
         tx_id -> BigInt,
@@ -36,6 +47,19 @@ table! {
         receiver_protocol -> Text,
         message -> Text,
         timestamp -> Timestamp,
+        protocol_stage -> Integer,
+        retry_count -> Integer,
+    }
+}
+
+table! {
+    invoices (invoice_id) {
+        invoice_id -> Text,
+        spending_key -> Binary,
+        key_index -> BigInt,
+        tx_id -> Nullable<BigInt>,
+        amount -> Nullable<BigInt>,
+        timestamp -> Timestamp,
     }
 }
 
@@ -58,6 +82,16 @@ table! {
         sender_protocol -> Text,
         message -> Text,
         timestamp -> Timestamp,
+        protocol_stage -> Integer,
+        retry_count -> Integer,
+    }
+}
+
+table! {
+    output_migration_plans (id) {
+        id -> Nullable<BigInt>,
+        plan -> Text,
+        timestamp -> Timestamp,
     }
 }
 
@@ -87,14 +121,26 @@ table! {
     }
 }
 
+table! {
+    spend_locks (id) {
+        id -> Nullable<BigInt>,
+        passphrase_hash -> Text,
+        timestamp -> Timestamp,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(
+    balance_audit_log,
     coinbase_transactions,
     completed_transactions,
     contacts,
     inbound_transactions,
+    invoices,
     key_manager_states,
     outbound_transactions,
+    output_migration_plans,
     outputs,
     peers,
     pending_transaction_outputs,
+    spend_locks,
 );
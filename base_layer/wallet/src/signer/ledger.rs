@@ -0,0 +1,232 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Prototype [TransactionSigner] for a Ledger device. Each request is encoded as an ISO 7816-4 APDU and passed to a
+//! [LedgerTransport], which is responsible for the USB/HID framing to the device. The device app is expected to
+//! derive spending keys and keep secret nonces on the device, and to return only public keys, public nonces and the
+//! `s` part of signatures.
+
+use crate::signer::{SignerError, TransactionSigner};
+use std::convert::TryFrom;
+use tari_core::transactions::types::{PrivateKey, PublicKey, Signature};
+use tari_crypto::tari_utilities::ByteArray;
+
+/// APDU class byte used by the Tari Ledger app
+pub const LEDGER_CLA: u8 = 0x80;
+pub const INS_GET_PUBLIC_KEY: u8 = 0x01;
+pub const INS_GET_NONCE: u8 = 0x02;
+pub const INS_SIGN: u8 = 0x03;
+/// Status word returned by the device on success
+pub const SW_OK: u16 = 0x9000;
+
+const KEY_SIZE: usize = 32;
+const MAX_APDU_DATA: usize = 255;
+
+/// Sends raw APDU commands to a Ledger device and returns the raw response, including the trailing status word.
+pub trait LedgerTransport {
+    fn exchange(&mut self, command: &[u8]) -> Result<Vec<u8>, SignerError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApduCommand {
+    pub cla: u8,
+    pub ins: u8,
+    pub p1: u8,
+    pub p2: u8,
+    pub data: Vec<u8>,
+}
+
+impl ApduCommand {
+    pub fn new(ins: u8, data: Vec<u8>) -> Self {
+        Self {
+            cla: LEDGER_CLA,
+            ins,
+            p1: 0,
+            p2: 0,
+            data,
+        }
+    }
+
+    /// Serialize the command as a short APDU: CLA INS P1 P2 Lc DATA
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SignerError> {
+        if self.data.len() > MAX_APDU_DATA {
+            return Err(SignerError::ChallengeTooLong);
+        }
+        let mut buf = Vec::with_capacity(5 + self.data.len());
+        buf.extend_from_slice(&[self.cla, self.ins, self.p1, self.p2, self.data.len() as u8]);
+        buf.extend_from_slice(&self.data);
+        Ok(buf)
+    }
+
+    /// Parse a short APDU produced by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SignerError> {
+        if bytes.len() < 5 || bytes.len() != 5 + bytes[4] as usize {
+            return Err(SignerError::InvalidDeviceResponse);
+        }
+        Ok(Self {
+            cla: bytes[0],
+            ins: bytes[1],
+            p1: bytes[2],
+            p2: bytes[3],
+            data: bytes[5..].to_vec(),
+        })
+    }
+}
+
+pub struct LedgerSigner<T> {
+    transport: T,
+}
+
+impl<T> LedgerSigner<T>
+where T: LedgerTransport
+{
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    fn send(&mut self, command: ApduCommand) -> Result<Vec<u8>, SignerError> {
+        let response = self.transport.exchange(&command.to_bytes()?)?;
+        if response.len() < 2 {
+            return Err(SignerError::InvalidDeviceResponse);
+        }
+        let (body, sw) = response.split_at(response.len() - 2);
+        let sw = u16::from_be_bytes([sw[0], sw[1]]);
+        if sw != SW_OK {
+            return Err(SignerError::DeviceError(sw));
+        }
+        Ok(body.to_vec())
+    }
+
+    fn send_for_key(&mut self, command: ApduCommand) -> Result<Vec<u8>, SignerError> {
+        let body = self.send(command)?;
+        if body.len() != KEY_SIZE {
+            return Err(SignerError::InvalidDeviceResponse);
+        }
+        Ok(body)
+    }
+}
+
+fn encode_key_index(key_index: usize) -> Result<[u8; 4], SignerError> {
+    u32::try_from(key_index)
+        .map(u32::to_be_bytes)
+        .map_err(|_| SignerError::TransportError("Key index does not fit in 32 bits".to_string()))
+}
+
+impl<T> TransactionSigner for LedgerSigner<T>
+where T: LedgerTransport
+{
+    fn get_public_key(&mut self, key_index: usize) -> Result<PublicKey, SignerError> {
+        let data = encode_key_index(key_index)?.to_vec();
+        let body = self.send_for_key(ApduCommand::new(INS_GET_PUBLIC_KEY, data))?;
+        Ok(PublicKey::from_bytes(&body)?)
+    }
+
+    fn generate_nonce(&mut self) -> Result<PublicKey, SignerError> {
+        let body = self.send_for_key(ApduCommand::new(INS_GET_NONCE, Vec::new()))?;
+        Ok(PublicKey::from_bytes(&body)?)
+    }
+
+    fn sign(&mut self, key_index: usize, public_nonce: &PublicKey, challenge: &[u8]) -> Result<Signature, SignerError> {
+        let mut data = encode_key_index(key_index)?.to_vec();
+        data.extend_from_slice(public_nonce.as_bytes());
+        data.extend_from_slice(challenge);
+        let body = self.send_for_key(ApduCommand::new(INS_SIGN, data))?;
+        let s = PrivateKey::from_bytes(&body)?;
+        Ok(Signature::new(public_nonce.clone(), s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{signer::SoftwareSigner, types::KeyDigest};
+    use rand::rngs::OsRng;
+    use tari_key_manager::key_manager::KeyManager;
+    use tari_test_utils::unpack_enum;
+
+    /// Emulates the device app by decoding APDUs and answering them with a software signer
+    struct MockDevice {
+        signer: SoftwareSigner,
+    }
+
+    impl MockDevice {
+        fn handle(&mut self, command: ApduCommand) -> Result<Vec<u8>, SignerError> {
+            let index = |data: &[u8]| u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+            match command.ins {
+                INS_GET_PUBLIC_KEY => Ok(self.signer.get_public_key(index(&command.data))?.as_bytes().to_vec()),
+                INS_GET_NONCE => Ok(self.signer.generate_nonce()?.as_bytes().to_vec()),
+                INS_SIGN => {
+                    let nonce = PublicKey::from_bytes(&command.data[4..4 + KEY_SIZE])?;
+                    let challenge = &command.data[4 + KEY_SIZE..];
+                    let sig = self.signer.sign(index(&command.data), &nonce, challenge)?;
+                    Ok(sig.get_signature().as_bytes().to_vec())
+                },
+                _ => Err(SignerError::DeviceError(0x6D00)),
+            }
+        }
+    }
+
+    impl LedgerTransport for MockDevice {
+        fn exchange(&mut self, command: &[u8]) -> Result<Vec<u8>, SignerError> {
+            let command = ApduCommand::from_bytes(command)?;
+            assert_eq!(command.cla, LEDGER_CLA);
+            let (mut body, sw) = match self.handle(command) {
+                Ok(body) => (body, SW_OK),
+                Err(SignerError::DeviceError(sw)) => (Vec::new(), sw),
+                Err(_) => (Vec::new(), 0x6A80),
+            };
+            body.extend_from_slice(&sw.to_be_bytes());
+            Ok(body)
+        }
+    }
+
+    #[test]
+    fn apdu_roundtrip() {
+        let command = ApduCommand::new(INS_SIGN, vec![1, 2, 3]);
+        let bytes = command.to_bytes().unwrap();
+        assert_eq!(bytes, vec![LEDGER_CLA, INS_SIGN, 0, 0, 3, 1, 2, 3]);
+        assert_eq!(ApduCommand::from_bytes(&bytes).unwrap(), command);
+
+        let err = ApduCommand::new(INS_SIGN, vec![0; 256]).to_bytes().unwrap_err();
+        unpack_enum!(SignerError::ChallengeTooLong = err);
+    }
+
+    #[test]
+    fn sign_with_device() {
+        let key_manager = KeyManager::<PrivateKey, KeyDigest>::new(&mut OsRng);
+        let mut signer = LedgerSigner::new(MockDevice {
+            signer: SoftwareSigner::new(key_manager),
+        });
+
+        let public_key = signer.get_public_key(7).unwrap();
+        let public_nonce = signer.generate_nonce().unwrap();
+        let signature = signer.sign(7, &public_nonce, b"challenge").unwrap();
+        assert!(signature.verify_challenge(&public_key, b"challenge"));
+
+        // The device refuses to reuse a nonce
+        let err = signer.sign(7, &public_nonce, b"challenge").unwrap_err();
+        match err {
+            SignerError::DeviceError(sw) => assert_eq!(sw, 0x6A80),
+            e => panic!("Unexpected error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Transaction Signer
+//!
+//! The [TransactionSigner] trait separates the use of spending keys from where they are stored. The wallet asks a
+//! signer for public keys, single-use public nonces and signatures over a challenge, and never needs the secret keys
+//! themselves. [SoftwareSigner] keeps the keys in memory and derives them with the wallet key manager, while
+//! [LedgerSigner] forwards every request to a hardware device over APDU so that spend keys never leave it.
+
+mod error;
+mod ledger;
+mod software;
+
+pub use error::SignerError;
+pub use ledger::{ApduCommand, LedgerSigner, LedgerTransport};
+pub use software::SoftwareSigner;
+
+use tari_core::transactions::types::{PublicKey, Signature};
+
+pub trait TransactionSigner {
+    /// Return the public key for the spending key at `key_index`
+    fn get_public_key(&mut self, key_index: usize) -> Result<PublicKey, SignerError>;
+
+    /// Generate a new single-use signing nonce. Only the public nonce is returned, the secret nonce stays with the
+    /// signer until it is consumed by a call to `sign`.
+    fn generate_nonce(&mut self) -> Result<PublicKey, SignerError>;
+
+    /// Sign `challenge` with the spending key at `key_index` using the secret nonce that belongs to `public_nonce`.
+    /// The nonce is discarded afterwards, so signing twice with the same nonce will fail.
+    fn sign(&mut self, key_index: usize, public_nonce: &PublicKey, challenge: &[u8]) -> Result<Signature, SignerError>;
+}
@@ -0,0 +1,104 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    signer::{SignerError, TransactionSigner},
+    types::KeyDigest,
+};
+use rand::rngs::OsRng;
+use std::collections::HashMap;
+use tari_core::transactions::types::{PrivateKey, PublicKey, Signature};
+use tari_crypto::{
+    keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait},
+    tari_utilities::ByteArray,
+};
+use tari_key_manager::key_manager::KeyManager;
+use tari_secret::Secret;
+
+/// A [TransactionSigner] that derives spending keys in memory from the wallet key manager. Outstanding nonces are
+/// zeroized once they have been used.
+pub struct SoftwareSigner {
+    key_manager: KeyManager<PrivateKey, KeyDigest>,
+    nonces: HashMap<Vec<u8>, Secret<PrivateKey>>,
+}
+
+impl SoftwareSigner {
+    pub fn new(key_manager: KeyManager<PrivateKey, KeyDigest>) -> Self {
+        Self {
+            key_manager,
+            nonces: HashMap::new(),
+        }
+    }
+
+    fn derive_key(&self, key_index: usize) -> Result<PrivateKey, SignerError> {
+        Ok(self.key_manager.derive_key(key_index)?.k)
+    }
+}
+
+impl TransactionSigner for SoftwareSigner {
+    fn get_public_key(&mut self, key_index: usize) -> Result<PublicKey, SignerError> {
+        let key = self.derive_key(key_index)?;
+        Ok(PublicKey::from_secret_key(&key))
+    }
+
+    fn generate_nonce(&mut self) -> Result<PublicKey, SignerError> {
+        let nonce = PrivateKey::random(&mut OsRng);
+        let public_nonce = PublicKey::from_secret_key(&nonce);
+        self.nonces.insert(public_nonce.as_bytes().to_vec(), Secret::new(nonce));
+        Ok(public_nonce)
+    }
+
+    fn sign(&mut self, key_index: usize, public_nonce: &PublicKey, challenge: &[u8]) -> Result<Signature, SignerError> {
+        let nonce = self
+            .nonces
+            .remove(public_nonce.as_bytes())
+            .ok_or(SignerError::UnknownNonce)?;
+        let key = self.derive_key(key_index)?;
+        Ok(Signature::sign(key, nonce.reveal().clone(), challenge)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signer::SignerError;
+    use tari_test_utils::unpack_enum;
+
+    #[test]
+    fn sign_and_verify() {
+        let key_manager = KeyManager::<PrivateKey, KeyDigest>::new(&mut OsRng);
+        let expected_key = key_manager.derive_key(3).unwrap().k;
+        let mut signer = SoftwareSigner::new(key_manager);
+
+        let public_key = signer.get_public_key(3).unwrap();
+        assert_eq!(public_key, PublicKey::from_secret_key(&expected_key));
+
+        let public_nonce = signer.generate_nonce().unwrap();
+        let signature = signer.sign(3, &public_nonce, b"challenge").unwrap();
+        assert_eq!(signature.get_public_nonce(), &public_nonce);
+        assert!(signature.verify_challenge(&public_key, b"challenge"));
+
+        // A nonce can only be used once
+        let err = signer.sign(3, &public_nonce, b"challenge").unwrap_err();
+        unpack_enum!(SignerError::UnknownNonce = err);
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+//! Adapts a synchronous storage backend for use from async service code. The SQLite and in-memory backends are
+//! synchronous and every call into them may block on disk IO or on the connection lock. The `BlockingAdapter` runs
+//! each operation on tokio's blocking thread pool, so that the services' executor threads are not stalled while a
+//! backend operation is in progress. The async backend traits (e.g. `AsyncOutputManagerBackend`) are implemented for
+//! `BlockingAdapter<T>` for every synchronous backend `T`.
+
+use futures::{future::BoxFuture, FutureExt};
+use std::sync::Arc;
+use tokio::task::JoinError;
+
+pub struct BlockingAdapter<T> {
+    backend: Arc<T>,
+}
+
+impl<T> BlockingAdapter<T>
+where T: Send + Sync + 'static
+{
+    pub fn new(backend: T) -> Self {
+        Self {
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Run `f` against the backend on the blocking thread pool. A failure to run the blocking task is converted into
+    /// the backend's error type.
+    pub fn run<F, R, E>(&self, f: F) -> BoxFuture<'static, Result<R, E>>
+    where
+        F: FnOnce(&T) -> Result<R, E> + Send + 'static,
+        R: Send + 'static,
+        E: From<JoinError> + Send + 'static,
+    {
+        let backend = self.backend.clone();
+        tokio::task::spawn_blocking(move || f(&*backend))
+            .map(|result| result.map_err(E::from).and_then(|inner_result| inner_result))
+            .boxed()
+    }
+}
+
+impl<T> Clone for BlockingAdapter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            backend: self.backend.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Join,
+        Backend,
+    }
+
+    impl From<JoinError> for TestError {
+        fn from(_: JoinError) -> Self {
+            TestError::Join
+        }
+    }
+
+    #[tokio_macros::test]
+    async fn run_on_blocking_pool() {
+        let adapter = BlockingAdapter::new(Mutex::new(1u32));
+        let value = adapter
+            .run(|db| {
+                let mut lock = db.lock().unwrap();
+                *lock += 1;
+                Ok::<_, TestError>(*lock)
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, 2);
+
+        let err = adapter.run(|_| Err::<(), _>(TestError::Backend)).await.unwrap_err();
+        assert_eq!(err, TestError::Backend);
+
+        let err = adapter
+            .clone()
+            .run(|_| -> Result<(), TestError> { panic!() })
+            .await
+            .unwrap_err();
+        assert_eq!(err, TestError::Join);
+    }
+}
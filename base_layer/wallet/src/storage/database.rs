@@ -20,12 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::error::WalletStorageError;
+use crate::{error::WalletStorageError, storage::blocking_adapter::BlockingAdapter};
+use futures::future::BoxFuture;
 use log::*;
-use std::{
-    fmt::{Display, Error, Formatter},
-    sync::Arc,
-};
+use std::fmt::{Display, Error, Formatter};
 use tari_comms::{peer_manager::Peer, types::CommsPublicKey};
 
 const LOG_TARGET: &str = "wallet::database";
@@ -38,6 +36,25 @@ pub trait WalletBackend: Send + Sync {
     fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, WalletStorageError>;
 }
 
+/// The async counterpart of `WalletBackend` used by the `WalletDatabase`. It is implemented for a `BlockingAdapter`
+/// over any synchronous `WalletBackend`.
+pub trait AsyncWalletBackend: Send + Sync {
+    fn fetch(&self, key: DbKey) -> BoxFuture<'static, Result<Option<DbValue>, WalletStorageError>>;
+    fn write(&self, op: WriteOperation) -> BoxFuture<'static, Result<Option<DbValue>, WalletStorageError>>;
+}
+
+impl<T> AsyncWalletBackend for BlockingAdapter<T>
+where T: WalletBackend + 'static
+{
+    fn fetch(&self, key: DbKey) -> BoxFuture<'static, Result<Option<DbValue>, WalletStorageError>> {
+        self.run(move |db| db.fetch(&key))
+    }
+
+    fn write(&self, op: WriteOperation) -> BoxFuture<'static, Result<Option<DbValue>, WalletStorageError>> {
+        self.run(move |db| db.write(op))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum DbKey {
     Peer(CommsPublicKey),
@@ -74,73 +91,62 @@ macro_rules! fetch {
 pub struct WalletDatabase<T>
 where T: WalletBackend + 'static
 {
-    db: Arc<T>,
+    db: BlockingAdapter<T>,
 }
 
 impl<T> WalletDatabase<T>
 where T: WalletBackend + 'static
 {
     pub fn new(db: T) -> Self {
-        Self { db: Arc::new(db) }
+        Self {
+            db: BlockingAdapter::new(db),
+        }
     }
 
     pub async fn get_peer(&self, pub_key: CommsPublicKey) -> Result<Peer, WalletStorageError> {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || fetch!(db_clone, pub_key.clone(), Peer))
-            .await
-            .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.run(move |db| fetch!(db, pub_key.clone(), Peer)).await
     }
 
     pub async fn get_peers(&self) -> Result<Vec<Peer>, WalletStorageError> {
-        let db_clone = self.db.clone();
-
-        let c = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::Peers) {
-            Ok(None) => log_error(
-                DbKey::Peers,
-                WalletStorageError::UnexpectedResult("Could not retrieve peers".to_string()),
-            ),
-            Ok(Some(DbValue::Peers(c))) => Ok(c),
-            Ok(Some(other)) => unexpected_result(DbKey::Peers, other),
-            Err(e) => log_error(DbKey::Peers, e),
-        })
-        .await
-        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let c = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::Peers) {
+                Ok(None) => log_error(
+                    DbKey::Peers,
+                    WalletStorageError::UnexpectedResult("Could not retrieve peers".to_string()),
+                ),
+                Ok(Some(DbValue::Peers(c))) => Ok(c),
+                Ok(Some(other)) => unexpected_result(DbKey::Peers, other),
+                Err(e) => log_error(DbKey::Peers, e),
+            })
+            .await?;
         Ok(c)
     }
 
     pub async fn save_peer(&self, peer: Peer) -> Result<(), WalletStorageError> {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::Peer(
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::Peer(
                 peer.public_key.clone(),
                 peer,
             )))
-        })
-        .await
-        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        .await?;
         Ok(())
     }
 
     pub async fn remove_peer(&self, pub_key: CommsPublicKey) -> Result<Peer, WalletStorageError> {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            match db_clone
-                .write(WriteOperation::Remove(DbKey::Peer(pub_key.clone())))?
-                .ok_or_else(|| WalletStorageError::ValueNotFound(DbKey::Peer(pub_key.clone())))?
-            {
-                DbValue::Peer(c) => Ok(*c),
-                DbValue::Peers(_) => Err(WalletStorageError::UnexpectedResult(
-                    "Incorrect response from backend.".to_string(),
-                )),
-            }
-        })
-        .await
-        .or_else(|err| Err(WalletStorageError::BlockingTaskSpawnError(err.to_string())))
-        .and_then(|inner_result| inner_result)
+        self.db
+            .run(move |db| {
+                match db
+                    .write(WriteOperation::Remove(DbKey::Peer(pub_key.clone())))?
+                    .ok_or_else(|| WalletStorageError::ValueNotFound(DbKey::Peer(pub_key.clone())))?
+                {
+                    DbValue::Peer(c) => Ok(*c),
+                    DbValue::Peers(_) => Err(WalletStorageError::UnexpectedResult(
+                        "Incorrect response from backend.".to_string(),
+                    )),
+                }
+            })
+            .await
     }
 }
 
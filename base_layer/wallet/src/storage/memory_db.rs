@@ -22,9 +22,11 @@
 
 use crate::{
     error::WalletStorageError,
-    storage::database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
+    storage::{
+        database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
+        memory_store::MemoryStore,
+    },
 };
-use std::sync::{Arc, RwLock};
 use tari_comms::peer_manager::Peer;
 
 #[derive(Default)]
@@ -39,13 +41,13 @@ impl InnerDatabase {
 }
 
 pub struct WalletMemoryDatabase {
-    db: Arc<RwLock<InnerDatabase>>,
+    db: MemoryStore<InnerDatabase>,
 }
 
 impl WalletMemoryDatabase {
     pub fn new() -> Self {
         Self {
-            db: Arc::new(RwLock::new(InnerDatabase::new())),
+            db: MemoryStore::new(InnerDatabase::new()),
         }
     }
 }
@@ -58,41 +60,44 @@ impl Default for WalletMemoryDatabase {
 
 impl WalletBackend for WalletMemoryDatabase {
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, WalletStorageError> {
-        let db = acquire_read_lock!(self.db);
-        let result = match key {
-            DbKey::Peer(pk) => db
-                .peers
-                .iter()
-                .find(|v| &v.public_key == pk)
-                .map(|p| DbValue::Peer(Box::new(p.clone()))),
-            DbKey::Peers => Some(DbValue::Peers(db.peers.clone())),
-        };
+        let key = key.clone();
+        self.db.run(move |db| {
+            let result = match &key {
+                DbKey::Peer(pk) => db
+                    .peers
+                    .iter()
+                    .find(|v| &v.public_key == pk)
+                    .map(|p| DbValue::Peer(Box::new(p.clone()))),
+                DbKey::Peers => Some(DbValue::Peers(db.peers.clone())),
+            };
 
-        Ok(result)
+            Ok(result)
+        })
     }
 
     fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, WalletStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-        match op {
-            WriteOperation::Insert(kvp) => match kvp {
-                DbKeyValuePair::Peer(pk, p) => {
-                    if db.peers.iter().any(|p| p.public_key == pk) {
-                        return Err(WalletStorageError::DuplicateContact);
-                    }
-                    db.peers.push(p)
+        self.db.run(move |db| {
+            match op {
+                WriteOperation::Insert(kvp) => match kvp {
+                    DbKeyValuePair::Peer(pk, p) => {
+                        if db.peers.iter().any(|p| p.public_key == pk) {
+                            return Err(WalletStorageError::DuplicateContact);
+                        }
+                        db.peers.push(p)
+                    },
                 },
-            },
-            WriteOperation::Remove(k) => match k {
-                DbKey::Peer(pk) => match db.peers.iter().position(|p| p.public_key == pk) {
-                    None => return Err(WalletStorageError::ValueNotFound(DbKey::Peer(pk))),
-                    Some(pos) => return Ok(Some(DbValue::Peer(Box::new(db.peers.remove(pos))))),
+                WriteOperation::Remove(k) => match k {
+                    DbKey::Peer(pk) => match db.peers.iter().position(|p| p.public_key == pk) {
+                        None => return Err(WalletStorageError::ValueNotFound(DbKey::Peer(pk))),
+                        Some(pos) => return Ok(Some(DbValue::Peer(Box::new(db.peers.remove(pos))))),
+                    },
+                    DbKey::Peers => {
+                        return Err(WalletStorageError::OperationNotSupported);
+                    },
                 },
-                DbKey::Peers => {
-                    return Err(WalletStorageError::OperationNotSupported);
-                },
-            },
-        }
+            }
 
-        Ok(None)
+            Ok(None)
+        })
     }
 }
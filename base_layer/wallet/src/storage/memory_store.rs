@@ -0,0 +1,149 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A single-writer store for the in-memory storage backends. The state is owned by a dedicated thread and every
+//! operation is sent to that thread as a closure, so operations never contend on a lock and each one is applied
+//! atomically with respect to all others. A read-modify-write such as encumbering a set of outputs can therefore be
+//! written as a single operation without holding a lock guard across calls.
+//!
+//! `run` waits for the operation to complete, so like every synchronous backend call it should not be made from an
+//! executor thread. The services reach the backends through the `BlockingAdapter`, which makes these calls on tokio's
+//! blocking thread pool.
+
+use crossbeam_channel::{self as channel, Sender};
+use log::*;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    thread,
+};
+
+const LOG_TARGET: &str = "wallet::storage::memory_store";
+
+type Operation<T> = Box<dyn FnOnce(&mut T) + Send>;
+
+pub struct MemoryStore<T> {
+    sender: Sender<Operation<T>>,
+}
+
+impl<T> MemoryStore<T>
+where T: Send + 'static
+{
+    /// Start the thread that owns `state`. The thread exits once every clone of the store has been dropped.
+    pub fn new(state: T) -> Self {
+        let (sender, receiver) = channel::unbounded::<Operation<T>>();
+        thread::spawn(move || {
+            let mut state = state;
+            for operation in receiver {
+                operation(&mut state);
+            }
+        });
+        Self { sender }
+    }
+
+    /// Apply `f` to the state and return its result. A panic in `f` is propagated to the caller; the store keeps
+    /// running with the state as `f` left it.
+    pub fn run<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (reply_sender, reply_receiver) = channel::bounded(1);
+        let operation: Operation<T> = Box::new(move |state| {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(state)));
+            let _ = reply_sender.send(result);
+        });
+        self.sender
+            .send(operation)
+            .expect("The memory store thread only exits once the store is dropped");
+        match reply_receiver
+            .recv()
+            .expect("The memory store thread always replies to an operation")
+        {
+            Ok(result) => result,
+            Err(panic) => {
+                warn!(target: LOG_TARGET, "A memory store operation panicked");
+                panic::resume_unwind(panic)
+            },
+        }
+    }
+}
+
+impl<T> Clone for MemoryStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T> Default for MemoryStore<T>
+where T: Default + Send + 'static
+{
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{Arc, Barrier};
+
+    #[test]
+    fn operations_are_applied_atomically() {
+        let store = MemoryStore::new(Vec::<usize>::new());
+        let barrier = Arc::new(Barrier::new(8));
+        let handles = (0..8)
+            .map(|i| {
+                let store = store.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    for j in 0..100 {
+                        // Each operation reads and then writes, which would interleave without a single writer
+                        store.run(move |v| {
+                            let len = v.len();
+                            v.push(i * 100 + j);
+                            assert_eq!(v.len(), len + 1);
+                        });
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut values = store.run(|v| v.clone());
+        values.sort();
+        assert_eq!(values, (0..800).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn panic_is_propagated() {
+        let store = MemoryStore::new(1u32);
+        let s = store.clone();
+        let result = thread::spawn(move || s.run(|_| -> u32 { panic!("operation failed") })).join();
+        assert!(result.is_err());
+        assert_eq!(store.run(|v| *v), 1);
+    }
+}
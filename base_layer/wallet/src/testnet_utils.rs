@@ -140,12 +140,15 @@ pub fn create_wallet(
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        peer_allow_list: None,
     };
 
     let config = WalletConfig {
         comms_config,
         factories,
         transaction_service_config: None,
+        output_manager_service_config: None,
+        locale_config: None,
     };
 
     Wallet::new(
@@ -21,6 +21,8 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::time::Duration;
+use tari_common::retry::RetryPolicy;
+use tari_core::transactions::tari_amount::MicroTari;
 
 #[derive(Clone)]
 pub struct TransactionServiceConfig {
@@ -30,6 +32,21 @@ pub struct TransactionServiceConfig {
     pub mempool_broadcast_timeout: Duration,
     pub initial_base_node_mined_timeout: Duration,
     pub base_node_mined_timeout: Duration,
+    // Every consecutive unanswered Transaction Mined? request for a transaction waits this policy's backoff delay
+    // longer than `base_node_mined_timeout` before it times out and is re-sent. Requests are no longer re-sent once
+    // the maximum number of attempts is reached.
+    pub base_node_query_retry_policy: RetryPolicy,
+    // The number of confirmations a transaction requires before it is marked as mined. The base node pushes
+    // confirmation notifications until this number is reached and reports a reorg of the containing block before then.
+    pub num_confirmations_required: u64,
+    // Inbound transactions for less than this amount are dropped without being stored
+    pub min_inbound_amount: MicroTari,
+    // The maximum number of inbound transactions accepted from a single peer within `inbound_throttle_period`.
+    // Transactions over this limit are dropped and count towards banning the peer.
+    pub max_inbound_transactions_per_peer: usize,
+    pub inbound_throttle_period: Duration,
+    // When funds received in an inbound transaction become available to display and spend
+    pub zero_conf_policy: ZeroConfPolicy,
 }
 
 impl Default for TransactionServiceConfig {
@@ -39,6 +56,53 @@ impl Default for TransactionServiceConfig {
             mempool_broadcast_timeout: Duration::from_secs(30),
             initial_base_node_mined_timeout: Duration::from_secs(5),
             base_node_mined_timeout: Duration::from_secs(30),
+            base_node_query_retry_policy: RetryPolicy::exponential(Duration::from_secs(10))
+                .with_max_delay(Duration::from_secs(5 * 60))
+                .with_jitter(0.25),
+            num_confirmations_required: 3,
+            min_inbound_amount: MicroTari::from(0),
+            max_inbound_transactions_per_peer: 10,
+            inbound_throttle_period: Duration::from_secs(60),
+            zero_conf_policy: ZeroConfPolicy::Never,
         }
     }
 }
+
+/// Governs when the funds received in an inbound transaction are counted as available and may be spent. Until then
+/// they are reported as pending incoming funds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ZeroConfPolicy {
+    /// Received funds only become available once the transaction has been mined
+    Never,
+    /// Received funds become available as soon as a base node accepts the transaction into its mempool, provided the
+    /// transaction pays at least `min_fee`. They are returned to pending if the transaction leaves the mempool without
+    /// being mined.
+    MempoolAccepted { min_fee: MicroTari },
+}
+
+impl ZeroConfPolicy {
+    /// Returns true if an inbound transaction paying `fee` may be made available once it is accepted into the mempool
+    pub fn accepts(&self, fee: MicroTari) -> bool {
+        match self {
+            ZeroConfPolicy::Never => false,
+            ZeroConfPolicy::MempoolAccepted { min_fee } => fee >= *min_fee,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ZeroConfPolicy;
+    use tari_core::transactions::tari_amount::MicroTari;
+
+    #[test]
+    fn zero_conf_policy_accepts() {
+        assert!(!ZeroConfPolicy::Never.accepts(MicroTari::from(1_000_000)));
+        let policy = ZeroConfPolicy::MempoolAccepted {
+            min_fee: MicroTari::from(100),
+        };
+        assert!(!policy.accepts(MicroTari::from(99)));
+        assert!(policy.accepts(MicroTari::from(100)));
+        assert!(policy.accepts(MicroTari::from(101)));
+    }
+}
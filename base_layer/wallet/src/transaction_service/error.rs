@@ -29,9 +29,13 @@ use diesel::result::Error as DieselError;
 use serde_json::Error as SerdeJsonError;
 use tari_comms::peer_manager::node_id::NodeIdError;
 use tari_comms_dht::outbound::DhtOutboundError;
-use tari_core::transactions::{transaction::TransactionError, transaction_protocol::TransactionProtocolError};
+use tari_core::{
+    base_node::protocol_version::ProtocolVersionError,
+    transactions::{transaction::TransactionError, transaction_protocol::TransactionProtocolError},
+};
 use tari_service_framework::reply_channel::TransportChannelError;
 use time::OutOfRangeError;
+use tokio::task::JoinError;
 
 #[derive(Debug, Error)]
 pub enum TransactionServiceError {
@@ -58,6 +62,8 @@ pub enum TransactionServiceError {
     /// The Source Public Key on the received transaction does not match the transaction with the same TX_ID in the
     /// database
     InvalidSourcePublicKey,
+    /// The confirmation notification was not signed by the base node
+    InvalidConfirmationSignature,
     /// The transaction does not contain the receivers output
     ReceiverOutputNotFound,
     /// Outbound Service send failed
@@ -73,6 +79,15 @@ pub enum TransactionServiceError {
     InvalidCompletedTransaction,
     /// No Base Node public keys are provided for Base chain broadcast and monitoring
     NoBaseNodeKeysProvided,
+    /// The transaction memo could not be encrypted
+    MemoEncryptionError,
+    /// The sender has exceeded the number of inbound transactions accepted from a single peer within the throttle
+    /// period
+    InboundTransactionThrottled,
+    /// The amount of an inbound transaction is below the configured minimum
+    InboundAmountBelowMinimum,
+    /// The transaction is not a pending inbound or outbound transaction
+    PendingTransactionNotFound,
     DhtOutboundError(DhtOutboundError),
     OutputManagerError(OutputManagerError),
     TransportChannelError(TransportChannelError),
@@ -86,6 +101,7 @@ pub enum TransactionServiceError {
     #[error(msg_embedded, no_from, non_std)]
     ConversionError(String),
     NodeIdError(NodeIdError),
+    ProtocolVersionError(ProtocolVersionError),
 }
 
 #[derive(Debug, Error)]
@@ -102,6 +118,8 @@ pub enum TransactionStorageError {
     ValuesNotFound,
     /// Transaction is already present in the database
     TransactionAlreadyExists,
+    /// The transaction protocol cannot move from its stored stage to the requested stage
+    InvalidProtocolTransition,
     OutOfRangeError(OutOfRangeError),
     /// Error converting a type
     ConversionError,
@@ -114,3 +132,9 @@ pub enum TransactionStorageError {
     #[error(msg_embedded, non_std, no_from)]
     BlockingTaskSpawnError(String),
 }
+
+impl From<JoinError> for TransactionStorageError {
+    fn from(err: JoinError) -> Self {
+        TransactionStorageError::BlockingTaskSpawnError(err.to_string())
+    }
+}
@@ -32,7 +32,10 @@ use futures::{stream::Fuse, StreamExt};
 use std::{collections::HashMap, fmt};
 use tari_broadcast_channel::Subscriber;
 use tari_comms::types::CommsPublicKey;
-use tari_core::transactions::{tari_amount::MicroTari, transaction::Transaction};
+use tari_core::{
+    base_node::protocol_version::NegotiatedCapabilities,
+    transactions::{tari_amount::MicroTari, transaction::Transaction},
+};
 use tari_service_framework::reply_channel::SenderService;
 use tower::Service;
 
@@ -43,11 +46,15 @@ pub enum TransactionServiceRequest {
     GetPendingOutboundTransactions,
     GetCompletedTransactions,
     SetBaseNodePublicKey(CommsPublicKey),
+    GetBaseNodeCapabilities,
     SendTransaction((CommsPublicKey, MicroTari, MicroTari, String)),
     RequestCoinbaseSpendingKey((MicroTari, u64)),
     CompleteCoinbaseTransaction((TxId, Transaction)),
     CancelPendingCoinbaseTransaction(TxId),
     ImportUtxo(MicroTari, CommsPublicKey, String),
+    SubmitTransaction((TxId, Transaction, MicroTari, MicroTari, String)),
+    CancelTransaction(TxId),
+    RetryTransaction(TxId),
     #[cfg(feature = "test_harness")]
     CompletePendingOutboundTransaction(CompletedTransaction),
     #[cfg(feature = "test_harness")]
@@ -67,6 +74,7 @@ impl fmt::Display for TransactionServiceRequest {
             Self::GetPendingOutboundTransactions => f.write_str("GetPendingOutboundTransactions"),
             Self::GetCompletedTransactions => f.write_str("GetCompletedTransactions"),
             Self::SetBaseNodePublicKey(k) => f.write_str(&format!("SetBaseNodePublicKey ({})", k)),
+            Self::GetBaseNodeCapabilities => f.write_str("GetBaseNodeCapabilities"),
             Self::SendTransaction((k, v, _, msg)) => {
                 f.write_str(&format!("SendTransaction (to {}, {}, {})", k, v, msg))
             },
@@ -78,6 +86,9 @@ impl fmt::Display for TransactionServiceRequest {
                 f.write_str(&format!("CancelPendingCoinbaseTransaction ({}) ", id))
             },
             Self::ImportUtxo(v, k, msg) => f.write_str(&format!("ImportUtxo (from {}, {}, {})", k, v, msg)),
+            Self::SubmitTransaction((id, _, _, _, msg)) => f.write_str(&format!("SubmitTransaction ({}, {})", id, msg)),
+            Self::CancelTransaction(id) => f.write_str(&format!("CancelTransaction ({})", id)),
+            Self::RetryTransaction(id) => f.write_str(&format!("RetryTransaction ({})", id)),
             #[cfg(feature = "test_harness")]
             Self::CompletePendingOutboundTransaction(tx) => {
                 f.write_str(&format!("CompletePendingOutboundTransaction ({})", tx.tx_id))
@@ -107,7 +118,11 @@ pub enum TransactionServiceResponse {
     CompletedCoinbaseTransactionReceived,
     CoinbaseTransactionCancelled,
     BaseNodePublicKeySet,
+    BaseNodeCapabilities(Option<NegotiatedCapabilities>),
     UtxoImported(TxId),
+    TransactionSubmitted,
+    TransactionCancelled,
+    TransactionRetried,
     #[cfg(feature = "test_harness")]
     CompletedPendingTransaction,
     #[cfg(feature = "test_harness")]
@@ -131,7 +146,17 @@ pub enum TransactionEvent {
     TransactionSendDiscoveryComplete(TxId, bool),
     TransactionBroadcast(TxId),
     TransactionMined(TxId),
+    /// The number of confirmations of a broadcast transaction, zero if the block containing it was removed by a reorg
+    TransactionConfirmations(TxId, u64),
+    /// A mined transaction was removed from the chain by a reorg and has returned to the broadcast state
+    TransactionReorged(TxId),
+    /// The funds received in a broadcast transaction were made available under the zero-conf policy
+    ZeroConfTransactionAccepted(TxId),
     TransactionMinedRequestTimedOut(TxId),
+    /// A pending transaction was cancelled before its negotiation completed
+    TransactionCancelled(TxId),
+    /// The base node refused the version handshake or is older than the minimum compatible version
+    BaseNodeIncompatible(CommsPublicKey),
     Error(String),
 }
 
@@ -282,6 +307,21 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Returns the capabilities negotiated with the current base node, or None if the version handshake has not
+    /// completed
+    pub async fn get_base_node_capabilities(
+        &mut self,
+    ) -> Result<Option<NegotiatedCapabilities>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetBaseNodeCapabilities)
+            .await??
+        {
+            TransactionServiceResponse::BaseNodeCapabilities(capabilities) => Ok(capabilities),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn import_utxo(
         &mut self,
         amount: MicroTari,
@@ -303,6 +343,54 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Submit a transaction that was completed outside of the transaction negotiation protocol (e.g. a coin split) so
+    /// that it is recorded, broadcast to the base node and monitored until it is mined.
+    pub async fn submit_transaction(
+        &mut self,
+        tx_id: TxId,
+        tx: Transaction,
+        fee: MicroTari,
+        amount: MicroTari,
+        message: String,
+    ) -> Result<(), TransactionServiceError>
+    {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SubmitTransaction((
+                tx_id, tx, fee, amount, message,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSubmitted => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Cancel a pending inbound or outbound transaction whose negotiation has not completed, releasing the outputs it
+    /// encumbered
+    pub async fn cancel_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CancelTransaction(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::TransactionCancelled => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send the current message of a pending transaction's negotiation to the counterparty again
+    pub async fn retry_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::RetryTransaction(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::TransactionRetried => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     #[cfg(feature = "test_harness")]
     pub async fn test_complete_pending_transaction(
         &mut self,
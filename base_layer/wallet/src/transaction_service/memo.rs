@@ -0,0 +1,151 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Transaction memos are encrypted end-to-end using a key derived from the Diffie-Hellman shared secret between the
+//! sender and the receiver of a transaction. The sender encrypts the memo before it is sent, and both parties store
+//! the encrypted memo. It is only decrypted when a transaction is returned to a client of the transaction service.
+
+use crate::transaction_service::{
+    error::TransactionServiceError,
+    storage::database::{CompletedTransaction, InboundTransaction, OutboundTransaction},
+};
+use blake2::Digest;
+use std::sync::Arc;
+use tari_comms::{
+    peer_manager::NodeIdentity,
+    types::{CommsPublicKey, CommsSecretKey},
+};
+use tari_crypto::{
+    common::Blake256,
+    keys::DiffieHellmanSharedSecret,
+    tari_utilities::{
+        ciphers::{chacha20::ChaCha20, cipher::Cipher},
+        hex::{from_hex, to_hex},
+        ByteArray,
+    },
+};
+
+const MEMO_KEY_DOMAIN: &[u8] = b"com.tari.wallet.transaction_memo";
+
+/// Derive the key used to encrypt memos for transactions with the given counterparty. Both parties derive the same
+/// key from their own secret key and the public key of the other party.
+pub fn memo_cipher_key(secret_key: &CommsSecretKey, counterparty_public_key: &CommsPublicKey) -> Vec<u8> {
+    let shared_secret = CommsPublicKey::shared_secret(secret_key, counterparty_public_key);
+    Blake256::new()
+        .chain(MEMO_KEY_DOMAIN)
+        .chain(shared_secret.as_bytes())
+        .result()
+        .to_vec()
+}
+
+/// Encrypt a memo with the given key. The cipher text is hex encoded so that it can be stored in place of the plain
+/// text memo. An empty memo is left empty.
+pub fn encrypt_memo(cipher_key: &[u8], memo: &str) -> Result<String, TransactionServiceError> {
+    if memo.is_empty() {
+        return Ok(String::new());
+    }
+    let cipher_text = ChaCha20::seal_with_integral_nonce(&memo.as_bytes().to_vec(), cipher_key)
+        .map_err(|_| TransactionServiceError::MemoEncryptionError)?;
+    Ok(to_hex(&cipher_text))
+}
+
+/// Decrypt a memo produced by `encrypt_memo`. Memos which are not valid cipher texts, such as memos stored or
+/// received before memo encryption was introduced, are returned as is.
+pub fn decrypt_memo(cipher_key: &[u8], encrypted_memo: &str) -> String {
+    from_hex(encrypted_memo)
+        .ok()
+        .and_then(|cipher_text| ChaCha20::open_with_integral_nonce(&cipher_text, cipher_key).ok())
+        .and_then(|plain_text| String::from_utf8(plain_text).ok())
+        .unwrap_or_else(|| encrypted_memo.to_string())
+}
+
+/// Encrypts and decrypts transaction memos on behalf of a wallet node identity
+#[derive(Clone)]
+pub struct MemoCipher {
+    node_identity: Arc<NodeIdentity>,
+}
+
+impl MemoCipher {
+    pub fn new(node_identity: Arc<NodeIdentity>) -> Self {
+        Self { node_identity }
+    }
+
+    /// Encrypt a memo for a transaction with the given counterparty
+    pub fn encrypt(&self, counterparty: &CommsPublicKey, memo: &str) -> Result<String, TransactionServiceError> {
+        encrypt_memo(&self.cipher_key(counterparty), memo)
+    }
+
+    /// Decrypt a memo of a transaction with the given counterparty
+    pub fn decrypt(&self, counterparty: &CommsPublicKey, encrypted_memo: &str) -> String {
+        decrypt_memo(&self.cipher_key(counterparty), encrypted_memo)
+    }
+
+    pub fn decrypt_inbound(&self, tx: &mut InboundTransaction) {
+        tx.message = self.decrypt(&tx.source_public_key, &tx.message);
+    }
+
+    pub fn decrypt_outbound(&self, tx: &mut OutboundTransaction) {
+        tx.message = self.decrypt(&tx.destination_public_key, &tx.message);
+    }
+
+    pub fn decrypt_completed(&self, tx: &mut CompletedTransaction) {
+        let counterparty = if &tx.source_public_key == self.node_identity.public_key() {
+            &tx.destination_public_key
+        } else {
+            &tx.source_public_key
+        };
+        tx.message = self.decrypt(counterparty, &tx.message);
+    }
+
+    fn cipher_key(&self, counterparty: &CommsPublicKey) -> Vec<u8> {
+        memo_cipher_key(self.node_identity.secret_key(), counterparty)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::PublicKey;
+
+    #[test]
+    fn memo_encryption() {
+        let (alice_sk, alice_pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (bob_sk, bob_pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (eve_sk, _) = CommsPublicKey::random_keypair(&mut OsRng);
+
+        let alice_key = memo_cipher_key(&alice_sk, &bob_pk);
+        let bob_key = memo_cipher_key(&bob_sk, &alice_pk);
+        assert_eq!(alice_key, bob_key);
+
+        let memo = "Payment for the ☕";
+        let encrypted = encrypt_memo(&alice_key, memo).unwrap();
+        assert_ne!(encrypted, memo);
+        assert_eq!(decrypt_memo(&bob_key, &encrypted), memo);
+
+        let eve_key = memo_cipher_key(&eve_sk, &alice_pk);
+        assert_ne!(decrypt_memo(&eve_key, &encrypted), memo);
+
+        assert_eq!(encrypt_memo(&alice_key, "").unwrap(), "");
+        assert_eq!(decrypt_memo(&bob_key, "Yo!"), "Yo!");
+    }
+}
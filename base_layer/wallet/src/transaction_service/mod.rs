@@ -23,8 +23,10 @@
 pub mod config;
 pub mod error;
 pub mod handle;
+pub mod memo;
 pub mod service;
 pub mod storage;
+pub mod throttle;
 
 use crate::{
     output_manager_service::handle::OutputManagerHandle,
@@ -39,7 +41,11 @@ use futures::{future, Future, Stream, StreamExt};
 use log::*;
 use std::sync::Arc;
 use tari_broadcast_channel::bounded;
-use tari_comms::{peer_manager::NodeIdentity, protocol::messaging::MessagingEventReceiver};
+use tari_comms::{
+    connectivity::ConnectivityRequester,
+    peer_manager::NodeIdentity,
+    protocol::messaging::MessagingEventReceiver,
+};
 use tari_comms_dht::outbound::OutboundMessageRequester;
 use tari_core::{
     base_node::proto::base_node as BaseNodeProto,
@@ -72,6 +78,7 @@ where T: TransactionBackend
     message_event_receiver: Option<MessagingEventReceiver>,
     backend: Option<T>,
     node_identity: Arc<NodeIdentity>,
+    connectivity: ConnectivityRequester,
     factories: CryptoFactories,
 }
 
@@ -84,6 +91,7 @@ where T: TransactionBackend
         message_event_receiver: MessagingEventReceiver,
         backend: T,
         node_identity: Arc<NodeIdentity>,
+        connectivity: ConnectivityRequester,
         factories: CryptoFactories,
     ) -> Self
     {
@@ -93,6 +101,7 @@ where T: TransactionBackend
             message_event_receiver: Some(message_event_receiver),
             backend: Some(backend),
             node_identity,
+            connectivity,
             factories,
         }
     }
@@ -132,6 +141,22 @@ where T: TransactionBackend
             .map(map_decode::<BaseNodeProto::BaseNodeServiceResponse>)
             .filter_map(ok_or_skip_result)
     }
+
+    fn confirmation_notification_stream(
+        &self,
+    ) -> impl Stream<Item = DomainMessage<BaseNodeProto::ConfirmationNotification>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::ConfirmationNotification)
+            .map(map_decode::<BaseNodeProto::ConfirmationNotification>)
+            .filter_map(ok_or_skip_result)
+    }
+
+    fn version_handshake_stream(&self) -> impl Stream<Item = DomainMessage<BaseNodeProto::VersionHandshake>> {
+        self.subscription_factory
+            .get_subscription(TariMessageType::VersionHandshakeResponse)
+            .map(map_decode::<BaseNodeProto::VersionHandshake>)
+            .filter_map(ok_or_skip_result)
+    }
 }
 
 impl<T> ServiceInitializer for TransactionServiceInitializer<T>
@@ -152,6 +177,8 @@ where T: TransactionBackend + Clone + 'static
         let transaction_finalized_stream = self.transaction_finalized_stream();
         let mempool_response_stream = self.mempool_response_stream();
         let base_node_response_stream = self.base_node_response_stream();
+        let confirmation_notification_stream = self.confirmation_notification_stream();
+        let version_handshake_stream = self.version_handshake_stream();
 
         let (publisher, subscriber) = bounded(100);
 
@@ -159,6 +186,7 @@ where T: TransactionBackend + Clone + 'static
 
         // Register handle before waiting for handles to be ready
         handles_fut.register(transaction_handle);
+        let ready_signal = handles_fut.ready_signal::<TransactionServiceHandle>();
 
         let backend = self
             .backend
@@ -171,6 +199,7 @@ where T: TransactionBackend + Clone + 'static
             .expect("Cannot start Transaction Service without providing an Message Event Receiver");
 
         let node_identity = self.node_identity.clone();
+        let connectivity = self.connectivity.clone();
         let factories = self.factories.clone();
         let config = self.config.clone();
 
@@ -178,10 +207,12 @@ where T: TransactionBackend + Clone + 'static
             let handles = handles_fut.await;
 
             let outbound_message_service = handles
-                .get_handle::<OutboundMessageRequester>()
+                .wait_for_handle::<OutboundMessageRequester>()
+                .await
                 .expect("OMS handle required for TransactionService");
             let output_manager_service = handles
-                .get_handle::<OutputManagerHandle>()
+                .wait_for_handle::<OutputManagerHandle>()
+                .await
                 .expect("Output Manager Service handle required for TransactionService");
 
             let service = TransactionService::new(
@@ -193,14 +224,18 @@ where T: TransactionBackend + Clone + 'static
                 transaction_finalized_stream,
                 mempool_response_stream,
                 base_node_response_stream,
+                confirmation_notification_stream,
+                version_handshake_stream,
                 output_manager_service,
                 outbound_message_service,
                 message_event_receiver,
                 publisher,
                 node_identity,
+                connectivity,
                 factories,
             )
             .start();
+            ready_signal.set_ready();
             futures::pin_mut!(service);
             future::select(service, shutdown).await;
             info!(target: LOG_TARGET, "Transaction Service shutdown");
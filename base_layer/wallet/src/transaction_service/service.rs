@@ -47,24 +47,36 @@ use tari_crypto::{
 };
 
 use tari_comms::{
+    connectivity::ConnectivityRequester,
     message::MessageTag,
-    peer_manager::NodeIdentity,
+    peer_manager::{NodeId, NodeIdentity},
     protocol::messaging::{MessagingEvent, MessagingEventReceiver},
     types::CommsPublicKey,
+    utils::signature,
 };
 use tari_comms_dht::{
     domain_message::OutboundDomainMessage,
-    outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageResponse},
+    outbound::{
+        DhtMessagePriority,
+        OutboundEncryption,
+        OutboundMessageRequester,
+        SendMessageParams,
+        SendMessageResponse,
+    },
 };
 #[cfg(feature = "test_harness")]
 use tari_core::transactions::{tari_amount::uT, types::BlindingFactor};
 use tari_core::{
-    base_node::proto::{
-        base_node as BaseNodeProto,
-        base_node::{
-            base_node_service_request::Request as BaseNodeRequestProto,
-            base_node_service_response::Response as BaseNodeResponseProto,
+    base_node::{
+        proto::{
+            base_node as BaseNodeProto,
+            base_node::{
+                base_node_service_request::Request as BaseNodeRequestProto,
+                base_node_service_response::Response as BaseNodeResponseProto,
+                confirmation_notification::Watched,
+            },
         },
+        protocol_version::{NegotiatedCapabilities, NodeVersion, ProtocolFeatures, MIN_COMPATIBLE_VERSION},
     },
     mempool::{
         proto::mempool as MempoolProto,
@@ -87,26 +99,37 @@ use tari_p2p::{domain_message::DomainMessage, tari_message::TariMessageType};
 use tari_service_framework::{reply_channel, reply_channel::Receiver};
 
 use crate::{
-    output_manager_service::{handle::OutputManagerHandle, TxId},
+    output_manager_service::{error::OutputManagerError, handle::OutputManagerHandle, TxId},
     transaction_service::{
         config::TransactionServiceConfig,
         error::TransactionServiceError,
         handle::{TransactionEvent, TransactionServiceRequest, TransactionServiceResponse},
+        memo::MemoCipher,
         storage::database::{
             CompletedTransaction,
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            ReceiverProtocolStage,
+            SenderProtocolStage,
             TransactionBackend,
             TransactionDatabase,
             TransactionStatus,
         },
+        throttle::InboundThrottle,
     },
     util::futures::StateDelay,
 };
 
 const LOG_TARGET: &str = "wallet::transaction_service::service";
 
+/// The ban score added to a peer each time one of its inbound transactions is dropped as spam. The peer is banned by
+/// the connectivity manager once its accumulated score reaches `BAN_SCORE_THRESHOLD`.
+const INBOUND_SPAM_BAN_SCORE: u32 = 10;
+
+/// The protocol features that the wallet uses if its base node supports them
+const WALLET_PROTOCOL_FEATURES: ProtocolFeatures = ProtocolFeatures::CONFIRMATION_NOTIFICATIONS;
+
 /// Contains the generated TxId and SpendingKey for a Pending Coinbase transaction
 #[derive(Debug)]
 pub struct PendingCoinbaseSpendingKey {
@@ -130,8 +153,16 @@ pub struct PendingCoinbaseSpendingKey {
 /// `pending_inbound_transactions` - List of transaction protocols that have been received and responded to.
 /// `completed_transaction` - List of sent transactions that have been responded to and are completed.
 
-pub struct TransactionService<TTxStream, TTxReplyStream, TTxFinalizedStream, MReplyStream, BNResponseStream, TBackend>
-where TBackend: TransactionBackend + Clone + 'static
+pub struct TransactionService<
+    TTxStream,
+    TTxReplyStream,
+    TTxFinalizedStream,
+    MReplyStream,
+    BNResponseStream,
+    BNConfirmationStream,
+    BNHandshakeStream,
+    TBackend,
+> where TBackend: TransactionBackend + Clone + 'static
 {
     config: TransactionServiceConfig,
     db: TransactionDatabase<TBackend>,
@@ -143,26 +174,55 @@ where TBackend: TransactionBackend + Clone + 'static
     transaction_finalized_stream: Option<TTxFinalizedStream>,
     mempool_response_stream: Option<MReplyStream>,
     base_node_response_stream: Option<BNResponseStream>,
+    confirmation_notification_stream: Option<BNConfirmationStream>,
+    version_handshake_stream: Option<BNHandshakeStream>,
     request_stream: Option<
         reply_channel::Receiver<TransactionServiceRequest, Result<TransactionServiceResponse, TransactionServiceError>>,
     >,
     event_publisher: Publisher<TransactionEvent>,
     node_identity: Arc<NodeIdentity>,
+    memo_cipher: MemoCipher,
+    connectivity: ConnectivityRequester,
+    inbound_throttle: InboundThrottle,
     factories: CryptoFactories,
     base_node_public_key: Option<CommsPublicKey>,
     pending_outbound_message_results: HashMap<MessageTag, OutboundTransaction>,
     pending_transaction_mined_queries: HashMap<TxId, TransactionMinedRequestResult>,
+    mined_request_attempts: HashMap<TxId, usize>,
+    watched_excess_sigs: HashMap<Vec<u8>, TxId>,
+    base_node_pushes_confirmations: bool,
+    base_node_capabilities: Option<NegotiatedCapabilities>,
 }
 
 #[allow(clippy::too_many_arguments)]
-impl<TTxStream, TTxReplyStream, TTxFinalizedStream, MReplyStream, BNResponseStream, TBackend>
-    TransactionService<TTxStream, TTxReplyStream, TTxFinalizedStream, MReplyStream, BNResponseStream, TBackend>
+impl<
+        TTxStream,
+        TTxReplyStream,
+        TTxFinalizedStream,
+        MReplyStream,
+        BNResponseStream,
+        BNConfirmationStream,
+        BNHandshakeStream,
+        TBackend,
+    >
+    TransactionService<
+        TTxStream,
+        TTxReplyStream,
+        TTxFinalizedStream,
+        MReplyStream,
+        BNResponseStream,
+        BNConfirmationStream,
+        BNHandshakeStream,
+        TBackend,
+    >
 where
     TTxStream: Stream<Item = DomainMessage<proto::TransactionSenderMessage>>,
     TTxReplyStream: Stream<Item = DomainMessage<proto::RecipientSignedMessage>>,
     TTxFinalizedStream: Stream<Item = DomainMessage<proto::TransactionFinalizedMessage>>,
     MReplyStream: Stream<Item = DomainMessage<MempoolProto::MempoolServiceResponse>>,
     BNResponseStream: Stream<Item = DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>,
+    BNConfirmationStream: Stream<Item = DomainMessage<BaseNodeProto::ConfirmationNotification>>,
+    BNHandshakeStream: Stream<Item = DomainMessage<BaseNodeProto::VersionHandshake>>,
     TBackend: TransactionBackend + Clone + 'static,
 {
     pub fn new(
@@ -177,14 +237,19 @@ where
         transaction_finalized_stream: TTxFinalizedStream,
         mempool_response_stream: MReplyStream,
         base_node_response_stream: BNResponseStream,
+        confirmation_notification_stream: BNConfirmationStream,
+        version_handshake_stream: BNHandshakeStream,
         output_manager_service: OutputManagerHandle,
         outbound_message_service: OutboundMessageRequester,
         message_event_receiver: MessagingEventReceiver,
         event_publisher: Publisher<TransactionEvent>,
         node_identity: Arc<NodeIdentity>,
+        connectivity: ConnectivityRequester,
         factories: CryptoFactories,
     ) -> Self
     {
+        let inbound_throttle =
+            InboundThrottle::new(config.max_inbound_transactions_per_peer, config.inbound_throttle_period);
         TransactionService {
             config,
             db,
@@ -196,13 +261,22 @@ where
             transaction_finalized_stream: Some(transaction_finalized_stream),
             mempool_response_stream: Some(mempool_response_stream),
             base_node_response_stream: Some(base_node_response_stream),
+            confirmation_notification_stream: Some(confirmation_notification_stream),
+            version_handshake_stream: Some(version_handshake_stream),
             request_stream: Some(request_stream),
             event_publisher,
+            memo_cipher: MemoCipher::new(node_identity.clone()),
             node_identity,
+            connectivity,
+            inbound_throttle,
             factories,
             base_node_public_key: None,
             pending_outbound_message_results: HashMap::new(),
             pending_transaction_mined_queries: HashMap::new(),
+            mined_request_attempts: HashMap::new(),
+            watched_excess_sigs: HashMap::new(),
+            base_node_pushes_confirmations: false,
+            base_node_capabilities: None,
         }
     }
 
@@ -244,6 +318,18 @@ where
             .expect("Transaction Service initialized without base_node_response_stream")
             .fuse();
         pin_mut!(base_node_response_stream);
+        let confirmation_notification_stream = self
+            .confirmation_notification_stream
+            .take()
+            .expect("Transaction Service initialized without confirmation_notification_stream")
+            .fuse();
+        pin_mut!(confirmation_notification_stream);
+        let version_handshake_stream = self
+            .version_handshake_stream
+            .take()
+            .expect("Transaction Service initialized without version_handshake_stream")
+            .fuse();
+        pin_mut!(version_handshake_stream);
         let message_event_receiver = self
             .message_event_receiver
             .take()
@@ -258,6 +344,9 @@ where
         let mut broadcast_timeout_futures: FuturesUnordered<BoxFuture<'static, TxId>> = FuturesUnordered::new();
         let mut mined_request_timeout_futures: FuturesUnordered<BoxFuture<'static, TxId>> = FuturesUnordered::new();
 
+        self.resume_pending_protocols(&mut discovery_process_futures, &mut broadcast_timeout_futures)
+            .await;
+
         loop {
             futures::select! {
                 //Incoming request
@@ -341,6 +430,24 @@ where
                         Err(resp)
                     });
                 }
+                // Incoming messages from the Comms layer
+                msg = confirmation_notification_stream.select_next_some() => {
+                    trace!(target: LOG_TARGET, "Handling Confirmation Notification");
+                    let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
+                    let _ = self.handle_confirmation_notification(origin_public_key.clone(), inner_msg).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error handling confirmation notification from {}: {:?} for NodeID: {}", origin_public_key, resp, self.node_identity.node_id().short_str());
+                        Err(resp)
+                    });
+                }
+                // Incoming messages from the Comms layer
+                msg = version_handshake_stream.select_next_some() => {
+                    trace!(target: LOG_TARGET, "Handling Version Handshake");
+                    let (origin_public_key, inner_msg) = msg.into_origin_and_inner();
+                    let _ = self.handle_version_handshake(origin_public_key.clone(), inner_msg).await.or_else(|resp| {
+                        error!(target: LOG_TARGET, "Error handling version handshake from {}: {:?} for NodeID: {}", origin_public_key, resp, self.node_identity.node_id().short_str());
+                        Err(resp)
+                    });
+                }
                 response = discovery_process_futures.select_next_some() => {
                     trace!(target: LOG_TARGET, "Handling Discovery Process Completion");
                     match response {
@@ -351,19 +458,38 @@ where
                                 outbound_tx.tx_id,
                                 message_tag,
                             );
-                            self.db
-                                .add_pending_outbound_transaction(outbound_tx.tx_id, outbound_tx.clone())
-                                .await?;
-                            self.pending_outbound_message_results.insert(message_tag.clone(), outbound_tx);
+                            // A resent sender message leaves the protocol in the stage it has already reached
+                            if outbound_tx.stage == SenderProtocolStage::Queued {
+                                let tx_id = outbound_tx.tx_id;
+                                match self.db.advance_outbound_transaction(outbound_tx, SenderProtocolStage::WaitingForReply).await {
+                                    Ok(outbound_tx) => {
+                                        self.pending_outbound_message_results.insert(message_tag.clone(), outbound_tx);
+                                    },
+                                    Err(e) => error!(target: LOG_TARGET, "Could not advance protocol of TX_ID: {} after discovery: {:?}", tx_id, e),
+                                }
+                            }
                         },
                         Err(TransactionServiceError::DiscoveryProcessFailed(tx_id)) => {
-                            if let Err(e) = self.output_manager_service.cancel_transaction(tx_id).await {
-                                error!(target: LOG_TARGET, "Failed to Cancel TX_ID: {} after failed sending attempt", tx_id);
+                            let is_resend = self.db
+                                .get_pending_outbound_transaction(tx_id)
+                                .await
+                                .map(|tx| tx.stage != SenderProtocolStage::Queued)
+                                .unwrap_or(false);
+                            if is_resend {
+                                // The transaction was sent before, so it stays pending and can be retried again
+                                warn!(target: LOG_TARGET, "Discovery failed while resending sender message for TX_ID: {}", tx_id);
+                            } else {
+                                if let Err(e) = self.db.remove_pending_outbound_transaction(tx_id).await {
+                                    error!(target: LOG_TARGET, "Failed to remove pending transaction TX_ID: {} after failed discovery: {:?}", tx_id, e);
+                                }
+                                if let Err(e) = self.output_manager_service.cancel_transaction(tx_id).await {
+                                    error!(target: LOG_TARGET, "Failed to Cancel TX_ID: {} after failed sending attempt", tx_id);
+                                }
+                                error!(target: LOG_TARGET, "Discovery and Send failed for TX_ID: {}", tx_id);
+                                let _ = self.event_publisher
+                                    .send(TransactionEvent::TransactionSendDiscoveryComplete(tx_id, false))
+                                    .await;
                             }
-                            error!(target: LOG_TARGET, "Discovery and Send failed for TX_ID: {}", tx_id);
-                            let _ = self.event_publisher
-                                .send(TransactionEvent::TransactionSendDiscoveryComplete(tx_id, false))
-                                .await;
                         }
                         Err(e) => error!(target: LOG_TARGET, "Discovery and Send failed with Error: {:?}", e),
                     }
@@ -446,10 +572,25 @@ where
                 .set_base_node_public_key(public_key, broadcast_timeout_futures, mined_request_timeout_futures)
                 .await
                 .map(|_| TransactionServiceResponse::BaseNodePublicKeySet),
+            TransactionServiceRequest::GetBaseNodeCapabilities => Ok(TransactionServiceResponse::BaseNodeCapabilities(
+                self.base_node_capabilities,
+            )),
             TransactionServiceRequest::ImportUtxo(value, source_public_key, message) => self
                 .add_utxo_import_transaction(value, source_public_key, message)
                 .await
                 .map(TransactionServiceResponse::UtxoImported),
+            TransactionServiceRequest::SubmitTransaction((tx_id, tx, fee, amount, message)) => self
+                .submit_transaction(tx_id, tx, fee, amount, message, broadcast_timeout_futures)
+                .await
+                .map(|_| TransactionServiceResponse::TransactionSubmitted),
+            TransactionServiceRequest::CancelTransaction(tx_id) => self
+                .cancel_transaction(tx_id)
+                .await
+                .map(|_| TransactionServiceResponse::TransactionCancelled),
+            TransactionServiceRequest::RetryTransaction(tx_id) => self
+                .retry_transaction(tx_id, discovery_process_futures, broadcast_timeout_futures)
+                .await
+                .map(|_| TransactionServiceResponse::TransactionRetried),
             #[cfg(feature = "test_harness")]
             TransactionServiceRequest::CompletePendingOutboundTransaction(completed_transaction) => {
                 self.complete_pending_outbound_transaction(completed_transaction)
@@ -551,6 +692,9 @@ where
         >,
     ) -> Result<(), TransactionServiceError>
     {
+        // The memo is encrypted for the recipient before it is added to the sender protocol so that it is never sent
+        // or stored in plain text
+        let message = self.memo_cipher.encrypt(&dest_pubkey, &message)?;
         let mut sender_protocol = self
             .output_manager_service
             .prepare_transaction_to_send(amount, fee_per_gram, None, message.clone())
@@ -562,6 +706,42 @@ where
 
         let msg = sender_protocol.build_single_round_message()?;
         let tx_id = msg.tx_id;
+
+        // The protocol is persisted before the sender message is handed to the comms layer so that the message can be
+        // sent again if the wallet stops before it goes out
+        let outbound_tx = OutboundTransaction {
+            tx_id,
+            destination_public_key: dest_pubkey,
+            amount,
+            fee: sender_protocol.get_fee_amount()?,
+            sender_protocol,
+            stage: SenderProtocolStage::Queued,
+            status: TransactionStatus::Pending,
+            message,
+            timestamp: Utc::now().naive_utc(),
+            retry_count: 0,
+        };
+        self.db
+            .add_pending_outbound_transaction(tx_id, outbound_tx.clone())
+            .await?;
+
+        self.send_sender_message(outbound_tx, discovery_process_futures).await
+    }
+
+    /// Send the sender message of an outbound transaction in the `Queued` or `WaitingForReply` stage to its recipient.
+    /// Once the message has been queued for sending a `Queued` transaction's protocol moves to the `WaitingForReply`
+    /// stage.
+    async fn send_sender_message(
+        &mut self,
+        outbound_tx: OutboundTransaction,
+        discovery_process_futures: &mut FuturesUnordered<
+            BoxFuture<'static, Result<(MessageTag, OutboundTransaction), TransactionServiceError>>,
+        >,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let tx_id = outbound_tx.tx_id;
+        let dest_pubkey = outbound_tx.destination_public_key.clone();
+        let msg = outbound_tx.sender_protocol.get_single_round_message()?;
         let proto_message = proto::TransactionSenderMessage::single(msg.into());
 
         match self
@@ -586,21 +766,16 @@ where
                         tags[0],
                     );
 
-                    let outbound_tx = OutboundTransaction {
-                        tx_id,
-                        destination_public_key: dest_pubkey.clone(),
-                        amount,
-                        fee: sender_protocol.get_fee_amount()?,
-                        sender_protocol,
-                        status: TransactionStatus::Pending,
-                        message,
-                        timestamp: Utc::now().naive_utc(),
-                    };
-                    self.db
-                        .add_pending_outbound_transaction(outbound_tx.tx_id, outbound_tx.clone())
-                        .await?;
-                    self.pending_outbound_message_results
-                        .insert(tags[0].clone(), outbound_tx);
+                    // Only the first send moves the protocol on and decides whether its outputs are kept, a resent
+                    // sender message leaves the transaction as it is
+                    if outbound_tx.stage == SenderProtocolStage::Queued {
+                        let outbound_tx = self
+                            .db
+                            .advance_outbound_transaction(outbound_tx, SenderProtocolStage::WaitingForReply)
+                            .await?;
+                        self.pending_outbound_message_results
+                            .insert(tags[0].clone(), outbound_tx);
+                    }
                 },
                 _ => error!(
                     target: LOG_TARGET,
@@ -612,18 +787,6 @@ where
                 // The sending of the message resulted in a long running Discovery process being performed by the Comms
                 // layer. This can take minutes so we will spawn a task to wait for the result and then act
                 // appropriately on it
-                let tx_id_clone = tx_id;
-                let outbound_tx_clone = OutboundTransaction {
-                    tx_id,
-                    destination_public_key: dest_pubkey.clone(),
-                    amount,
-                    fee: sender_protocol.get_fee_amount()?,
-                    sender_protocol: sender_protocol.clone(),
-                    status: TransactionStatus::Pending,
-                    message: message.clone(),
-                    timestamp: Utc::now().naive_utc(),
-                };
-
                 info!(
                     target: LOG_TARGET,
                     "Send Transaction request for TxID: {:?} to recipient with public_key {} requires that a \
@@ -632,9 +795,8 @@ where
                     dest_pubkey
                 );
 
-                let discovery_future = async move {
-                    transaction_send_discovery_process_completion(r, tx_id_clone, outbound_tx_clone).await
-                };
+                let discovery_future =
+                    async move { transaction_send_discovery_process_completion(r, tx_id, outbound_tx).await };
                 discovery_process_futures.push(discovery_future.boxed());
 
                 return Err(TransactionServiceError::OutboundSendDiscoveryInProgress(tx_id));
@@ -678,27 +840,41 @@ where
         outbound_tx
             .sender_protocol
             .finalize(KernelFeatures::empty(), &self.factories)?;
-        let tx = outbound_tx.sender_protocol.get_transaction()?;
 
-        let completed_transaction = CompletedTransaction {
-            tx_id,
-            source_public_key: self.node_identity.public_key().clone(),
-            destination_public_key: outbound_tx.destination_public_key,
-            amount: outbound_tx.amount,
-            fee: outbound_tx.fee,
-            transaction: tx.clone(),
-            status: TransactionStatus::Completed,
-            message: outbound_tx.message.clone(),
-            timestamp: Utc::now().naive_utc(),
-        };
-        self.db
-            .complete_outbound_transaction(tx_id.clone(), completed_transaction.clone())
+        // The finalized protocol is persisted before the finalized transaction is sent so that it reaches the recipient
+        // even if the wallet stops before this reply has been fully handled
+        let outbound_tx = self
+            .db
+            .advance_outbound_transaction(outbound_tx, SenderProtocolStage::Finalized)
             .await?;
         info!(
             target: LOG_TARGET,
             "Transaction Recipient Reply for TX_ID = {} received", tx_id,
         );
 
+        self.complete_finalized_outbound_transaction(outbound_tx, source_pubkey, broadcast_timeout_futures)
+            .await?;
+
+        self.event_publisher
+            .send(TransactionEvent::ReceivedTransactionReply(tx_id))
+            .await
+            .map_err(|_| TransactionServiceError::EventStreamError)?;
+
+        Ok(())
+    }
+
+    /// Send the finalized transaction of an outbound transaction in the `Finalized` stage to its recipient, then move
+    /// it to the completed transactions and broadcast it to the mempool.
+    async fn complete_finalized_outbound_transaction(
+        &mut self,
+        outbound_tx: OutboundTransaction,
+        recipient_pubkey: CommsPublicKey,
+        broadcast_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, TxId>>,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let tx_id = outbound_tx.tx_id;
+        let tx = outbound_tx.sender_protocol.get_transaction()?.clone();
+
         let finalized_transaction_message = proto::TransactionFinalizedMessage {
             tx_id,
             transaction: Some(tx.clone().into()),
@@ -706,12 +882,27 @@ where
 
         self.outbound_message_service
             .send_direct(
-                source_pubkey.clone(),
+                recipient_pubkey,
                 OutboundEncryption::EncryptForPeer,
                 OutboundDomainMessage::new(TariMessageType::TransactionFinalized, finalized_transaction_message),
             )
             .await?;
 
+        let completed_transaction = CompletedTransaction {
+            tx_id,
+            source_public_key: self.node_identity.public_key().clone(),
+            destination_public_key: outbound_tx.destination_public_key,
+            amount: outbound_tx.amount,
+            fee: outbound_tx.fee,
+            transaction: tx,
+            status: TransactionStatus::Completed,
+            message: outbound_tx.message,
+            timestamp: Utc::now().naive_utc(),
+        };
+        self.db
+            .complete_outbound_transaction(tx_id, completed_transaction)
+            .await?;
+
         // Logging this error here instead of propogating it up to the select! catchall which generates the Error Event.
         let _ = self
             .broadcast_completed_transaction_to_mempool(
@@ -728,11 +919,6 @@ where
                 e
             });
 
-        self.event_publisher
-            .send(TransactionEvent::ReceivedTransactionReply(tx_id))
-            .await
-            .map_err(|_| TransactionServiceError::EventStreamError)?;
-
         Ok(())
     }
 
@@ -746,6 +932,11 @@ where
         sender_message: proto::TransactionSenderMessage,
     ) -> Result<(), TransactionServiceError>
     {
+        if !self.inbound_throttle.check(&source_pubkey) {
+            self.report_inbound_spam(&source_pubkey).await;
+            return Err(TransactionServiceError::InboundTransactionThrottled);
+        }
+
         let sender_message: TransactionSenderMessage = sender_message
             .try_into()
             .map_err(TransactionServiceError::InvalidMessageError)?;
@@ -753,19 +944,43 @@ where
         // Currently we will only reply to a Single sender transaction protocol
         if let TransactionSenderMessage::Single(data) = sender_message.clone() {
             let amount = data.amount;
+            if amount < self.config.min_inbound_amount {
+                self.report_inbound_spam(&source_pubkey).await;
+                return Err(TransactionServiceError::InboundAmountBelowMinimum);
+            }
 
-            let spending_key = self
-                .output_manager_service
-                .get_recipient_spending_key(data.tx_id, data.amount)
-                .await?;
+            // A payment whose memo is the id of an unpaid invoice is received with the key derived for that invoice
+            let memo = self.memo_cipher.decrypt(&source_pubkey, &data.message);
+            let invoice_key = if memo.is_empty() {
+                None
+            } else {
+                match self
+                    .output_manager_service
+                    .get_invoice_recipient_spending_key(memo, data.tx_id, data.amount)
+                    .await
+                {
+                    Ok(k) => Some(k),
+                    Err(OutputManagerError::InvoiceNotFound) => None,
+                    Err(e) => return Err(e.into()),
+                }
+            };
+            let (spending_key, rewind_data) = match invoice_key {
+                Some(k) => k,
+                None => {
+                    self.output_manager_service
+                        .get_recipient_spending_key(data.tx_id, data.amount)
+                        .await?
+                },
+            };
             let nonce = PrivateKey::random(&mut OsRng);
 
-            let rtp = ReceiverTransactionProtocol::new(
+            let rtp = ReceiverTransactionProtocol::new_with_rewindable_output(
                 sender_message,
                 nonce,
                 spending_key,
                 OutputFeatures::default(),
                 &self.factories,
+                &rewind_data,
             );
             let recipient_reply = rtp.get_signed_data()?.clone();
 
@@ -775,37 +990,33 @@ where
                 return Err(TransactionServiceError::RepeatedMessageError);
             }
 
+            // The protocol is persisted before the reply is handed to the comms layer so that the reply can be sent
+            // again if the wallet stops before it goes out
             let tx_id = recipient_reply.tx_id;
-            let proto_message: proto::RecipientSignedMessage = recipient_reply.into();
-            self.outbound_message_service
-                .send_direct(
-                    source_pubkey.clone(),
-                    OutboundEncryption::EncryptForPeer,
-                    OutboundDomainMessage::new(TariMessageType::ReceiverPartialTransactionReply, proto_message),
-                )
-                .await?;
-
-            // Otherwise add it to our pending transaction list and return reply
             let inbound_transaction = InboundTransaction {
                 tx_id,
                 source_public_key: source_pubkey.clone(),
                 amount,
-                receiver_protocol: rtp.clone(),
+                receiver_protocol: rtp,
+                stage: ReceiverProtocolStage::ReplyPending,
                 status: TransactionStatus::Pending,
                 message: data.message.clone(),
                 timestamp: Utc::now().naive_utc(),
+                retry_count: 0,
             };
             self.db
                 .add_pending_inbound_transaction(tx_id, inbound_transaction.clone())
                 .await?;
 
+            self.send_recipient_reply(inbound_transaction).await?;
+
             info!(
                 target: LOG_TARGET,
                 "Transaction with TX_ID = {} received from {}. Reply Sent", tx_id, source_pubkey,
             );
             info!(
                 target: LOG_TARGET,
-                "Transaction (TX_ID: {}) - Amount: {} - Message: {}", tx_id, amount, data.message
+                "Transaction (TX_ID: {}) - Amount: {}", tx_id, amount
             );
 
             self.event_publisher
@@ -816,6 +1027,195 @@ where
         Ok(())
     }
 
+    /// Send the reply of a pending inbound transaction to its sender. Once the reply has been queued for sending a
+    /// `ReplyPending` transaction's protocol moves to the `WaitingForFinalization` stage.
+    async fn send_recipient_reply(&mut self, inbound_tx: InboundTransaction) -> Result<(), TransactionServiceError> {
+        let recipient_reply = inbound_tx.receiver_protocol.get_signed_data()?.clone();
+        let proto_message: proto::RecipientSignedMessage = recipient_reply.into();
+        self.outbound_message_service
+            .send_message(
+                SendMessageParams::new()
+                    .direct_public_key(inbound_tx.source_public_key.clone())
+                    .with_encryption(OutboundEncryption::EncryptForPeer)
+                    .with_discovery(true)
+                    .with_priority(DhtMessagePriority::High)
+                    .finish(),
+                OutboundDomainMessage::new(TariMessageType::ReceiverPartialTransactionReply, proto_message),
+            )
+            .await?;
+
+        if inbound_tx.stage == ReceiverProtocolStage::ReplyPending {
+            self.db
+                .advance_inbound_transaction(inbound_tx, ReceiverProtocolStage::WaitingForFinalization)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Resume the transaction negotiations that were interrupted after their protocol was persisted but before the
+    /// resulting message was sent to the counterparty, e.g. because the wallet stopped in between.
+    async fn resume_pending_protocols(
+        &mut self,
+        discovery_process_futures: &mut FuturesUnordered<
+            BoxFuture<'static, Result<(MessageTag, OutboundTransaction), TransactionServiceError>>,
+        >,
+        broadcast_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, TxId>>,
+    )
+    {
+        let outbound_txs = match self.db.get_pending_outbound_transactions().await {
+            Ok(txs) => txs,
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not load pending outbound transactions: {:?}", e);
+                HashMap::new()
+            },
+        };
+        for (tx_id, outbound_tx) in outbound_txs {
+            let result = match outbound_tx.stage {
+                SenderProtocolStage::Queued => {
+                    info!(target: LOG_TARGET, "Resending sender message for TX_ID: {}", tx_id);
+                    self.send_sender_message(outbound_tx, discovery_process_futures).await
+                },
+                SenderProtocolStage::Finalized => {
+                    info!(target: LOG_TARGET, "Resending finalized transaction for TX_ID: {}", tx_id);
+                    let recipient_pubkey = outbound_tx.destination_public_key.clone();
+                    self.complete_finalized_outbound_transaction(
+                        outbound_tx,
+                        recipient_pubkey,
+                        broadcast_timeout_futures,
+                    )
+                    .await
+                },
+                SenderProtocolStage::WaitingForReply => Ok(()),
+            };
+            match result {
+                Ok(_) | Err(TransactionServiceError::OutboundSendDiscoveryInProgress(_)) => {},
+                Err(e) => error!(
+                    target: LOG_TARGET,
+                    "Could not resume protocol of outbound transaction TX_ID: {}: {:?}", tx_id, e
+                ),
+            }
+        }
+
+        let inbound_txs = match self.db.get_pending_inbound_transactions().await {
+            Ok(txs) => txs,
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not load pending inbound transactions: {:?}", e);
+                HashMap::new()
+            },
+        };
+        for (tx_id, inbound_tx) in inbound_txs {
+            if inbound_tx.stage != ReceiverProtocolStage::ReplyPending {
+                continue;
+            }
+            info!(target: LOG_TARGET, "Resending reply for TX_ID: {}", tx_id);
+            if let Err(e) = self.send_recipient_reply(inbound_tx).await {
+                error!(
+                    target: LOG_TARGET,
+                    "Could not resume protocol of inbound transaction TX_ID: {}: {:?}", tx_id, e
+                );
+            }
+        }
+    }
+
+    /// Cancel a pending transaction whose negotiation has not completed. Its protocol is removed from the database and
+    /// the outputs it encumbered are released.
+    pub async fn cancel_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        if self.db.get_pending_outbound_transactions().await?.contains_key(&tx_id) {
+            self.db.remove_pending_outbound_transaction(tx_id).await?;
+            // The result of a send that is still in flight no longer decides anything for this transaction
+            self.pending_outbound_message_results.retain(|_, tx| tx.tx_id != tx_id);
+        } else if self.db.get_pending_inbound_transactions().await?.contains_key(&tx_id) {
+            self.db.remove_pending_inbound_transaction(tx_id).await?;
+        } else {
+            return Err(TransactionServiceError::PendingTransactionNotFound);
+        }
+
+        self.output_manager_service.cancel_transaction(tx_id).await?;
+        info!(target: LOG_TARGET, "Pending transaction TX_ID: {} cancelled", tx_id);
+
+        let _ = self
+            .event_publisher
+            .send(TransactionEvent::TransactionCancelled(tx_id))
+            .await;
+
+        Ok(())
+    }
+
+    /// Send the current message of a pending transaction's negotiation to the counterparty again, e.g. because the
+    /// counterparty was offline when it was first sent. The protocol stays in the stage it has reached.
+    pub async fn retry_transaction(
+        &mut self,
+        tx_id: TxId,
+        discovery_process_futures: &mut FuturesUnordered<
+            BoxFuture<'static, Result<(MessageTag, OutboundTransaction), TransactionServiceError>>,
+        >,
+        broadcast_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, TxId>>,
+    ) -> Result<(), TransactionServiceError>
+    {
+        if self.db.get_pending_outbound_transactions().await?.contains_key(&tx_id) {
+            let outbound_tx = self.db.increment_outbound_retry_count(tx_id).await?;
+            info!(
+                target: LOG_TARGET,
+                "Retrying outbound transaction TX_ID: {} in stage {:?} (retry {})",
+                tx_id,
+                outbound_tx.stage,
+                outbound_tx.retry_count
+            );
+            match outbound_tx.stage {
+                SenderProtocolStage::Queued | SenderProtocolStage::WaitingForReply => {
+                    match self.send_sender_message(outbound_tx, discovery_process_futures).await {
+                        Ok(_) | Err(TransactionServiceError::OutboundSendDiscoveryInProgress(_)) => Ok(()),
+                        Err(e) => Err(e),
+                    }
+                },
+                SenderProtocolStage::Finalized => {
+                    let recipient_pubkey = outbound_tx.destination_public_key.clone();
+                    self.complete_finalized_outbound_transaction(
+                        outbound_tx,
+                        recipient_pubkey,
+                        broadcast_timeout_futures,
+                    )
+                    .await
+                },
+            }
+        } else if self.db.get_pending_inbound_transactions().await?.contains_key(&tx_id) {
+            let inbound_tx = self.db.increment_inbound_retry_count(tx_id).await?;
+            info!(
+                target: LOG_TARGET,
+                "Retrying inbound transaction TX_ID: {} in stage {:?} (retry {})",
+                tx_id,
+                inbound_tx.stage,
+                inbound_tx.retry_count
+            );
+            self.send_recipient_reply(inbound_tx).await
+        } else {
+            Err(TransactionServiceError::PendingTransactionNotFound)
+        }
+    }
+
+    /// Add to the ban score of a peer whose inbound transaction was dropped as spam
+    async fn report_inbound_spam(&mut self, source_pubkey: &CommsPublicKey) {
+        let node_id = match NodeId::from_key(source_pubkey) {
+            Ok(node_id) => node_id,
+            Err(_) => return,
+        };
+        match self
+            .connectivity
+            .add_peer_ban_score(node_id, INBOUND_SPAM_BAN_SCORE)
+            .await
+        {
+            Ok(true) => warn!(
+                target: LOG_TARGET,
+                "Banned peer {} for flooding the wallet with inbound transactions", source_pubkey
+            ),
+            Ok(false) => {},
+            Err(err) => warn!(
+                target: LOG_TARGET,
+                "Failed to update the ban score of peer {}: {:?}", source_pubkey, err
+            ),
+        }
+    }
+
     /// Accept a new transaction from a sender by handling a public SenderMessage. The reply is generated and sent.
     /// # Arguments
     /// 'source_pubkey' - The pubkey from which the message was sent and to which the reply will be sent.
@@ -999,6 +1399,9 @@ where
             return Err(TransactionServiceError::InvalidCompletedTransaction);
         }
 
+        let message = self
+            .memo_cipher
+            .encrypt(self.node_identity.public_key(), "Coinbase Transaction")?;
         self.db
             .complete_coinbase_transaction(tx_id, CompletedTransaction {
                 tx_id,
@@ -1008,7 +1411,7 @@ where
                 fee: MicroTari::from(0),
                 transaction: completed_transaction,
                 status: TransactionStatus::Completed,
-                message: "Coinbase Transaction".to_string(),
+                message,
                 timestamp: Utc::now().naive_utc(),
             })
             .await?;
@@ -1040,19 +1443,31 @@ where
     pub async fn get_pending_inbound_transactions(
         &self,
     ) -> Result<HashMap<u64, InboundTransaction>, TransactionServiceError> {
-        Ok(self.db.get_pending_inbound_transactions().await?)
+        let mut transactions = self.db.get_pending_inbound_transactions().await?;
+        transactions
+            .values_mut()
+            .for_each(|tx| self.memo_cipher.decrypt_inbound(tx));
+        Ok(transactions)
     }
 
     pub async fn get_pending_outbound_transactions(
         &self,
     ) -> Result<HashMap<u64, OutboundTransaction>, TransactionServiceError> {
-        Ok(self.db.get_pending_outbound_transactions().await?)
+        let mut transactions = self.db.get_pending_outbound_transactions().await?;
+        transactions
+            .values_mut()
+            .for_each(|tx| self.memo_cipher.decrypt_outbound(tx));
+        Ok(transactions)
     }
 
     pub async fn get_completed_transactions(
         &self,
     ) -> Result<HashMap<u64, CompletedTransaction>, TransactionServiceError> {
-        Ok(self.db.get_completed_transactions().await?)
+        let mut transactions = self.db.get_completed_transactions().await?;
+        transactions
+            .values_mut()
+            .for_each(|tx| self.memo_cipher.decrypt_completed(tx));
+        Ok(transactions)
     }
 
     /// Add a base node public key to the list that will be used to broadcast transactions and monitor the base chain
@@ -1067,7 +1482,20 @@ where
     {
         let startup_broadcast = self.base_node_public_key.is_none();
 
-        self.base_node_public_key = Some(base_node_public_key);
+        let is_new_base_node = self.base_node_public_key.as_ref() != Some(&base_node_public_key);
+        if is_new_base_node {
+            // Until the new base node pushes a confirmation notification it is polled for mined transactions
+            self.base_node_pushes_confirmations = false;
+            self.base_node_capabilities = None;
+        }
+        self.base_node_public_key = Some(base_node_public_key.clone());
+
+        if is_new_base_node {
+            let _ = self.send_version_handshake(base_node_public_key).await.or_else(|resp| {
+                error!(target: LOG_TARGET, "Error sending version handshake to base node: {:?}", resp);
+                Err(resp)
+            });
+        }
 
         if startup_broadcast {
             let _ = self
@@ -1132,9 +1560,10 @@ where
                     )),
                 };
                 self.outbound_message_service
-                    .send_direct(
+                    .send_direct_with_priority(
                         pk.clone(),
                         OutboundEncryption::EncryptForPeer,
+                        DhtMessagePriority::High,
                         OutboundDomainMessage::new(TariMessageType::MempoolRequest, mempool_request),
                     )
                     .await?;
@@ -1213,7 +1642,18 @@ where
     {
         let response = MempoolServiceResponse::try_from(response).unwrap();
         let tx_id = response.request_key;
-        match response.response {
+        // A rejected transaction is handled as one that was not stored, the rejection only adds the reason
+        let mempool_response = match response.response {
+            MempoolResponse::TxRejected(rejection) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Mempool rejected transaction TxId: {}: {}", tx_id, rejection
+                );
+                MempoolResponse::TxStorage(TxStorageResponse::NotStored)
+            },
+            r => r,
+        };
+        match mempool_response {
             MempoolResponse::Stats(_) => {
                 return Err(TransactionServiceError::InvalidMessageError(
                     "Mempool Response of invalid type".to_string(),
@@ -1224,6 +1664,21 @@ where
                     "Mempool Response of invalid type".to_string(),
                 ))
             },
+            MempoolResponse::TxValidationReport(_) => {
+                return Err(TransactionServiceError::InvalidMessageError(
+                    "Mempool Response of invalid type".to_string(),
+                ))
+            },
+            MempoolResponse::Transactions(_) => {
+                return Err(TransactionServiceError::InvalidMessageError(
+                    "Mempool Response of invalid type".to_string(),
+                ))
+            },
+            MempoolResponse::Transaction(_) | MempoolResponse::TxRejected(_) => {
+                return Err(TransactionServiceError::InvalidMessageError(
+                    "Mempool Response of invalid type".to_string(),
+                ))
+            },
             MempoolResponse::TxStorage(ts) => {
                 let completed_tx = self.db.get_completed_transaction(response.request_key.clone()).await?;
 
@@ -1290,6 +1745,10 @@ where
                                 .send(TransactionEvent::TransactionBroadcast(tx_id))
                                 .await
                                 .map_err(|_| TransactionServiceError::EventStreamError)?;
+
+                            if self.is_zero_conf_transaction(&completed_tx) {
+                                self.accept_zero_conf_transaction(&completed_tx).await?;
+                            }
                         },
                     },
                     TransactionStatus::Broadcast => {
@@ -1297,16 +1756,24 @@ where
                             target: LOG_TARGET,
                             "Mempool query for transaction Tx_ID: {} returned {:?}", completed_tx.tx_id, ts
                         );
+                        let is_stored = match ts {
+                            TxStorageResponse::NotStored => false,
+                            _ => true,
+                        };
                         if let Some(result) = self.pending_transaction_mined_queries.get_mut(&completed_tx.tx_id) {
-                            match ts {
-                                TxStorageResponse::NotStored => result.mempool_response = Some(false),
-                                _ => result.mempool_response = Some(true),
-                            }
+                            result.mempool_response = Some(is_stored);
                             debug!(target: LOG_TARGET, "Current Mempool/Mined state {:?}", result);
                             if result.is_complete() {
                                 self.handle_transaction_mined_request_result(completed_tx.tx_id).await;
                             }
                         }
+                        // The outputs were not requested from a base node that pushes confirmations. The transaction
+                        // left the mempool, so check that it was mined before it is cancelled.
+                        if !is_stored && self.base_node_pushes_confirmations {
+                            if let Some(pk) = self.base_node_public_key.clone() {
+                                self.send_transaction_outputs_request(pk, &completed_tx).await?;
+                            }
+                        }
                     },
                     _ => (),
                 }
@@ -1335,18 +1802,22 @@ where
         match self.base_node_public_key.clone() {
             None => return Err(TransactionServiceError::NoBaseNodeKeysProvided),
             Some(pk) => {
-                let mut hashes = Vec::new();
-                for o in completed_tx.transaction.body.outputs() {
-                    hashes.push(o.hash());
-                }
-
                 info!(
                     target: LOG_TARGET,
                     "Sending Transaction Mined? request for TxId: {} to Base Node with {} outputs",
                     tx_id,
-                    hashes.len(),
+                    completed_tx.transaction.body.outputs().len(),
                 );
 
+                // Ask the base node to push a notification when the transaction is mined, unless the version
+                // handshake showed that it does not support confirmation notifications
+                let supports_notifications = self
+                    .base_node_capabilities
+                    .map_or(true, |c| c.supports(ProtocolFeatures::CONFIRMATION_NOTIFICATIONS));
+                if supports_notifications {
+                    self.send_confirmation_watch_request(pk.clone(), &completed_tx).await?;
+                }
+
                 // Send a request to the mempool to find the state of the Tx there
                 let tx_excess_sig = completed_tx.transaction.body.kernels()[0].excess_sig.clone();
                 let mempool_request = MempoolProto::MempoolServiceRequest {
@@ -1356,28 +1827,26 @@ where
                     )),
                 };
                 self.outbound_message_service
-                    .send_direct(
+                    .send_direct_with_priority(
                         pk.clone(),
                         OutboundEncryption::EncryptForPeer,
+                        DhtMessagePriority::High,
                         OutboundDomainMessage::new(TariMessageType::MempoolRequest, mempool_request),
                     )
                     .await?;
 
-                // Ask the base node if the outputs are in the chain
-                let request = BaseNodeRequestProto::FetchUtxos(BaseNodeProto::HashOutputs { outputs: hashes });
-                let service_request = BaseNodeProto::BaseNodeServiceRequest {
-                    request_key: tx_id,
-                    request: Some(request),
-                };
-                self.outbound_message_service
-                    .send_direct(
-                        pk.clone(),
-                        OutboundEncryption::EncryptForPeer,
-                        OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
-                    )
-                    .await?;
-                // Start Timeout
-                let state_timeout = StateDelay::new(timeout, completed_tx.tx_id);
+                // Base nodes that push confirmation notifications do not have to be polled for the outputs
+                if !self.base_node_pushes_confirmations {
+                    self.send_transaction_outputs_request(pk, &completed_tx).await?;
+                }
+                // Start Timeout, backing off from a base node which has not answered the previous requests
+                let attempt = self.mined_request_attempts.entry(tx_id).or_insert(0);
+                *attempt += 1;
+                let backoff = self
+                    .config
+                    .base_node_query_retry_policy
+                    .jittered_delay_for_attempt(*attempt);
+                let state_timeout = StateDelay::new(timeout + backoff, completed_tx.tx_id);
                 let _ = self
                     .pending_transaction_mined_queries
                     .insert(tx_id, TransactionMinedRequestResult::default());
@@ -1387,6 +1856,71 @@ where
         Ok(())
     }
 
+    /// Ask the Base Node if the outputs of the specified transaction are in the chain
+    async fn send_transaction_outputs_request(
+        &mut self,
+        base_node_public_key: CommsPublicKey,
+        completed_tx: &CompletedTransaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let hashes = completed_tx
+            .transaction
+            .body
+            .outputs()
+            .iter()
+            .map(|o| o.hash())
+            .collect();
+        let request = BaseNodeRequestProto::FetchUtxos(BaseNodeProto::HashOutputs { outputs: hashes });
+        let service_request = BaseNodeProto::BaseNodeServiceRequest {
+            request_key: completed_tx.tx_id,
+            request: Some(request),
+        };
+        self.outbound_message_service
+            .send_direct_with_priority(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                DhtMessagePriority::High,
+                OutboundDomainMessage::new(TariMessageType::BaseNodeRequest, service_request),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Register the kernels of the specified transaction with the Base Node. The Base Node will push a
+    /// ConfirmationNotification when they are mined and for every confirmation until the required number of
+    /// confirmations is reached.
+    async fn send_confirmation_watch_request(
+        &mut self,
+        base_node_public_key: CommsPublicKey,
+        completed_tx: &CompletedTransaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let request = BaseNodeProto::ConfirmationWatchRequest {
+            excess_sigs: completed_tx
+                .transaction
+                .body
+                .kernels()
+                .iter()
+                .map(|k| k.excess_sig.clone().into())
+                .collect(),
+            commitments: Vec::new(),
+            required_confirmations: self.config.num_confirmations_required,
+        };
+        for excess_sig in request.excess_sigs.iter() {
+            self.watched_excess_sigs
+                .insert(Watched::ExcessSig(excess_sig.clone()).to_bytes(), completed_tx.tx_id);
+        }
+        self.outbound_message_service
+            .send_direct_with_priority(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                DhtMessagePriority::High,
+                OutboundDomainMessage::new(TariMessageType::ConfirmationWatchRequest, request),
+            )
+            .await?;
+        Ok(())
+    }
+
     /// Handle the timeout of a pending transaction mined? request. This will check if the transaction's status has
     /// been updated by received BaseNodeRepsonse during the course of this timeout. If it has not been updated the
     /// transaction is broadcast again
@@ -1404,6 +1938,17 @@ where
                 "Transaction Mined? request timed out for TX_ID: {}", tx_id
             );
 
+            let attempts = self.mined_request_attempts.get(&tx_id).cloned().unwrap_or(0);
+            if !self.config.base_node_query_retry_policy.can_retry(attempts) {
+                warn!(
+                    target: LOG_TARGET,
+                    "No more Transaction Mined? requests will be sent for TX_ID: {} after {} unanswered attempts",
+                    tx_id,
+                    attempts
+                );
+                return Ok(());
+            }
+
             self.send_transaction_mined_request(
                 tx_id,
                 self.config.base_node_mined_timeout,
@@ -1423,6 +1968,7 @@ where
     /// Handle the result of receiving all the stages needed to complete a Transaction Mined request
     pub async fn handle_transaction_mined_request_result(&mut self, tx_id: TxId) {
         if let Some(result) = self.pending_transaction_mined_queries.remove(&tx_id) {
+            self.mined_request_attempts.remove(&tx_id);
             // If the transaction is not in mempool AND not mined then the Tx was reorged out and will never appear
             // in the chain and should be cancelled
             if result.mempool_response == Some(false) && result.chain_response == Some(false) {
@@ -1430,6 +1976,25 @@ where
                     target: LOG_TARGET,
                     "Transaction (TxId: {}) has left the Mempool while not being Mined. It will be cancelled.", tx_id,
                 );
+                // Outputs made available under the zero-conf policy are returned to pending so they can be cancelled
+                if let Ok(completed_tx) = self.db.get_completed_transaction(tx_id).await {
+                    let is_zero_conf = completed_tx.status == TransactionStatus::Broadcast &&
+                        self.is_zero_conf_transaction(&completed_tx);
+                    if is_zero_conf {
+                        let _ = self
+                            .output_manager_service
+                            .revert_transaction(tx_id)
+                            .await
+                            .map_err(|e| {
+                                error!(
+                                    target: LOG_TARGET,
+                                    "Failed to return zero-conf outputs for TX_ID: {} to pending with error {:?}",
+                                    tx_id,
+                                    e
+                                );
+                            });
+                    }
+                }
                 let _ = self
                     .output_manager_service
                     .cancel_transaction(tx_id)
@@ -1517,27 +2082,18 @@ where
                             .iter()
                             .any(|item| item == &transaction_output);
                 }
-                // If all outputs are present then mark this transaction as mined.
-                if check {
-                    self.output_manager_service
-                        .confirm_transaction(
-                            tx_id.clone(),
-                            completed_tx.transaction.body.inputs().clone(),
-                            completed_tx.transaction.body.outputs().clone(),
-                        )
-                        .await?;
-
-                    self.db.mine_completed_transaction(tx_id).await?;
-
-                    self.event_publisher
-                        .send(TransactionEvent::TransactionMined(tx_id))
-                        .await
-                        .map_err(|_| TransactionServiceError::EventStreamError)?;
-
-                    info!(
-                        target: LOG_TARGET,
-                        "Transaction (TxId: {:?}) detected as mined on the Base Layer", tx_id
-                    );
+                // If all outputs are present then mark this transaction as mined. Base nodes that push confirmation
+                // notifications report the number of confirmations, so the transaction is only marked as mined once
+                // it has the required confirmations.
+                if check && self.base_node_pushes_confirmations {
+                    if let Some(result) = self.pending_transaction_mined_queries.get_mut(&completed_tx.tx_id) {
+                        result.chain_response = Some(true);
+                        if result.is_complete() {
+                            self.handle_transaction_mined_request_result(completed_tx.tx_id).await;
+                        }
+                    }
+                } else if check {
+                    self.mark_transaction_mined(completed_tx).await?;
                 }
             }
         } else {
@@ -1550,6 +2106,245 @@ where
         Ok(())
     }
 
+    /// Send this wallet's version and protocol features to the Base Node. The Base Node replies with its own, after
+    /// which the capabilities supported by both are used.
+    async fn send_version_handshake(
+        &mut self,
+        base_node_public_key: CommsPublicKey,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let version = env!("CARGO_PKG_VERSION").parse::<NodeVersion>()?;
+        let request = BaseNodeProto::VersionHandshake::new(OsRng.next_u64(), version, WALLET_PROTOCOL_FEATURES);
+        self.outbound_message_service
+            .send_direct_with_priority(
+                base_node_public_key,
+                OutboundEncryption::EncryptForPeer,
+                DhtMessagePriority::High,
+                OutboundDomainMessage::new(TariMessageType::VersionHandshakeRequest, request),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Handle the version handshake response of the base node. If the base node is incompatible it is only polled, and
+    /// a `BaseNodeIncompatible` event is published so that a different base node can be selected.
+    pub async fn handle_version_handshake(
+        &mut self,
+        source_public_key: CommsPublicKey,
+        handshake: BaseNodeProto::VersionHandshake,
+    ) -> Result<(), TransactionServiceError>
+    {
+        if self.base_node_public_key.as_ref() != Some(&source_public_key) {
+            return Err(TransactionServiceError::InvalidSourcePublicKey);
+        }
+
+        match handshake.negotiate(WALLET_PROTOCOL_FEATURES, MIN_COMPATIBLE_VERSION) {
+            Ok(capabilities) => {
+                info!(
+                    target: LOG_TARGET,
+                    "Version handshake with base node {} negotiated {}", source_public_key, capabilities
+                );
+                self.base_node_pushes_confirmations =
+                    capabilities.supports(ProtocolFeatures::CONFIRMATION_NOTIFICATIONS);
+                self.base_node_capabilities = Some(capabilities);
+            },
+            Err(err) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Base node {} (version '{}') is not compatible with this wallet: {}",
+                    source_public_key,
+                    handshake.version,
+                    err
+                );
+                self.base_node_pushes_confirmations = false;
+                self.base_node_capabilities = None;
+                self.event_publisher
+                    .send(TransactionEvent::BaseNodeIncompatible(source_public_key))
+                    .await
+                    .map_err(|_| TransactionServiceError::EventStreamError)?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Handle a confirmation notification pushed by the base node for a watched transaction kernel
+    pub async fn handle_confirmation_notification(
+        &mut self,
+        source_public_key: CommsPublicKey,
+        notification: BaseNodeProto::ConfirmationNotification,
+    ) -> Result<(), TransactionServiceError>
+    {
+        if self.base_node_public_key.as_ref() != Some(&source_public_key) {
+            return Err(TransactionServiceError::InvalidSourcePublicKey);
+        }
+        if !signature::verify(
+            &source_public_key,
+            &notification.signature,
+            notification.signature_body(),
+        )
+        .unwrap_or(false)
+        {
+            return Err(TransactionServiceError::InvalidConfirmationSignature);
+        }
+
+        let key = notification.watched.as_ref().map(Watched::to_bytes).unwrap_or_default();
+        let tx_id = match self.watched_excess_sigs.get(&key) {
+            Some(tx_id) => *tx_id,
+            None => {
+                debug!(target: LOG_TARGET, "Confirmation notification received for an unwatched kernel");
+                return Ok(());
+            },
+        };
+        self.base_node_pushes_confirmations = true;
+
+        info!(
+            target: LOG_TARGET,
+            "Base node reports TxId: {} with {} confirmation(s) in block {} (height {})",
+            tx_id,
+            notification.confirmations,
+            notification.block_hash.to_hex(),
+            notification.height,
+        );
+        let completed_tx = self.db.get_completed_transaction(tx_id).await?;
+        let is_unmined =
+            completed_tx.status == TransactionStatus::Broadcast || completed_tx.status == TransactionStatus::Completed;
+        if notification.confirmations == 0 {
+            // The block containing the transaction was removed by a reorg. The transaction is not marked as mined
+            // until it has the required confirmations, so it remains Broadcast and is monitored until it is mined again
+            // or has left the mempool.
+            warn!(
+                target: LOG_TARGET,
+                "Transaction (TxId: {}) was removed from the chain by a reorg of block {} (height {})",
+                tx_id,
+                notification.block_hash.to_hex(),
+                notification.height,
+            );
+            if let Some(result) = self.pending_transaction_mined_queries.get_mut(&tx_id) {
+                result.chain_response = None;
+            }
+            if completed_tx.status == TransactionStatus::Mined {
+                self.revert_mined_transaction(tx_id).await?;
+            }
+        } else if is_unmined && notification.confirmations >= self.config.num_confirmations_required {
+            self.pending_transaction_mined_queries.remove(&tx_id);
+            self.mined_request_attempts.remove(&tx_id);
+            self.mark_transaction_mined(completed_tx).await?;
+        } else if completed_tx.status == TransactionStatus::Completed {
+            // The transaction is in a block, so it has been broadcast
+            self.db.broadcast_completed_transaction(tx_id).await?;
+            if self.is_zero_conf_transaction(&completed_tx) {
+                self.accept_zero_conf_transaction(&completed_tx).await?;
+            }
+        }
+
+        self.event_publisher
+            .send(TransactionEvent::TransactionConfirmations(
+                tx_id,
+                notification.confirmations,
+            ))
+            .await
+            .map_err(|_| TransactionServiceError::EventStreamError)?;
+
+        if notification.confirmations >= self.config.num_confirmations_required {
+            self.watched_excess_sigs
+                .retain(|_, watched_tx_id| *watched_tx_id != tx_id);
+        }
+
+        Ok(())
+    }
+
+    /// Confirm the inputs and outputs of a completed transaction with the Output Manager and mark it as mined
+    async fn mark_transaction_mined(
+        &mut self,
+        completed_tx: CompletedTransaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let tx_id = completed_tx.tx_id;
+        // The outputs of a zero-conf transaction were already confirmed when it was broadcast
+        if completed_tx.status != TransactionStatus::Broadcast || !self.is_zero_conf_transaction(&completed_tx) {
+            self.output_manager_service
+                .confirm_transaction(
+                    tx_id,
+                    completed_tx.transaction.body.inputs().clone(),
+                    completed_tx.transaction.body.outputs().clone(),
+                )
+                .await?;
+        }
+
+        self.db.mine_completed_transaction(tx_id).await?;
+
+        self.event_publisher
+            .send(TransactionEvent::TransactionMined(tx_id))
+            .await
+            .map_err(|_| TransactionServiceError::EventStreamError)?;
+
+        info!(
+            target: LOG_TARGET,
+            "Transaction (TxId: {:?}) detected as mined on the Base Layer", tx_id
+        );
+        Ok(())
+    }
+
+    /// Returns true if the funds received in this transaction are made available once it has been accepted into the
+    /// mempool rather than once it is mined, which is decided by the configured `ZeroConfPolicy` and the fee it pays.
+    /// Only inbound transactions from another wallet qualify.
+    fn is_zero_conf_transaction(&self, completed_tx: &CompletedTransaction) -> bool {
+        completed_tx.destination_public_key == *self.node_identity.public_key() &&
+            completed_tx.source_public_key != completed_tx.destination_public_key &&
+            self.config.zero_conf_policy.accepts(completed_tx.fee)
+    }
+
+    /// Confirm the outputs received in a broadcast zero-conf transaction with the Output Manager so that they are
+    /// available to spend before the transaction is mined
+    async fn accept_zero_conf_transaction(
+        &mut self,
+        completed_tx: &CompletedTransaction,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let tx_id = completed_tx.tx_id;
+        self.output_manager_service
+            .confirm_transaction(
+                tx_id,
+                completed_tx.transaction.body.inputs().clone(),
+                completed_tx.transaction.body.outputs().clone(),
+            )
+            .await?;
+
+        self.event_publisher
+            .send(TransactionEvent::ZeroConfTransactionAccepted(tx_id))
+            .await
+            .map_err(|_| TransactionServiceError::EventStreamError)?;
+
+        info!(
+            target: LOG_TARGET,
+            "Funds received in Transaction (TxId: {}) made available under the zero-conf policy", tx_id
+        );
+        Ok(())
+    }
+
+    /// Return a mined transaction that was removed from the chain by a reorg to the broadcast state and move its inputs
+    /// and outputs back to pending with the Output Manager. It is marked as mined again if it is included in another
+    /// block, or cancelled if it leaves the mempool without being mined. The outputs of a zero-conf transaction stay
+    /// available, as they would be for a broadcast transaction.
+    async fn revert_mined_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        let completed_tx = self.db.get_completed_transaction(tx_id).await?;
+        if !self.is_zero_conf_transaction(&completed_tx) {
+            self.output_manager_service.revert_transaction(tx_id).await?;
+        }
+        self.db.unmine_completed_transaction(tx_id).await?;
+
+        self.event_publisher
+            .send(TransactionEvent::TransactionReorged(tx_id))
+            .await
+            .map_err(|_| TransactionServiceError::EventStreamError)?;
+
+        warn!(
+            target: LOG_TARGET,
+            "Mined transaction (TxId: {}) was reverted to Broadcast after a reorg", tx_id
+        );
+        Ok(())
+    }
+
     /// Go through all completed transactions that have  been broadcast and start querying the base_node to see if they
     /// have been mined
     async fn monitor_all_completed_transactions_for_mining(
@@ -1587,6 +2382,7 @@ where
     ) -> Result<TxId, TransactionServiceError>
     {
         let tx_id = OsRng.next_u64();
+        let message = self.memo_cipher.encrypt(&source_public_key, &message)?;
         self.db
             .add_utxo_import_transaction(
                 tx_id.clone(),
@@ -1599,6 +2395,54 @@ where
         Ok(tx_id)
     }
 
+    /// Record a transaction that was completed outside of the transaction negotiation protocol, such as a coin split
+    /// that only pays to this wallet, and broadcast it to the base node mempool.
+    pub async fn submit_transaction(
+        &mut self,
+        tx_id: TxId,
+        tx: Transaction,
+        fee: MicroTari,
+        amount: MicroTari,
+        message: String,
+        broadcast_timeout_futures: &mut FuturesUnordered<BoxFuture<'static, TxId>>,
+    ) -> Result<(), TransactionServiceError>
+    {
+        let message = self.memo_cipher.encrypt(self.node_identity.public_key(), &message)?;
+        self.db
+            .insert_completed_transaction(tx_id, CompletedTransaction {
+                tx_id,
+                source_public_key: self.node_identity.public_key().clone(),
+                destination_public_key: self.node_identity.public_key().clone(),
+                amount,
+                fee,
+                transaction: tx,
+                status: TransactionStatus::Completed,
+                message,
+                timestamp: Utc::now().naive_utc(),
+            })
+            .await?;
+        info!(target: LOG_TARGET, "Transaction (TxId: {}) submitted", tx_id);
+
+        // Logging this error here instead of propogating it up, the transaction will be broadcast again when a base
+        // node is set.
+        let _ = self
+            .broadcast_completed_transaction_to_mempool(
+                tx_id,
+                self.config.initial_mempool_broadcast_timeout,
+                broadcast_timeout_futures,
+            )
+            .await
+            .map_err(|e| {
+                error!(
+                    target: LOG_TARGET,
+                    "Error broadcasting submitted transaction to mempool: {:?}", e
+                );
+                e
+            });
+
+        Ok(())
+    }
+
     /// This function is only available for testing by the client of LibWallet. It simulates a receiver accepting and
     /// replying to a Pending Outbound Transaction. This results in that transaction being "completed" and it's status
     /// set to `Broadcast` which indicated it is in a base_layer mempool.
@@ -1727,17 +2571,18 @@ where
             .try_into()
             .map_err(TransactionServiceError::InvalidMessageError)?;
 
-        let spending_key = self
+        let (spending_key, rewind_data) = self
             .output_manager_service
             .get_recipient_spending_key(tx_id.clone(), amount.clone())
             .await?;
         let nonce = PrivateKey::random(&mut OsRng);
-        let rtp = ReceiverTransactionProtocol::new(
+        let rtp = ReceiverTransactionProtocol::new_with_rewindable_output(
             sender_message,
             nonce,
             spending_key.clone(),
             OutputFeatures::default(),
             &self.factories,
+            &rewind_data,
         );
 
         let inbound_transaction = InboundTransaction {
@@ -1745,9 +2590,11 @@ where
             source_public_key,
             amount,
             receiver_protocol: rtp,
+            stage: ReceiverProtocolStage::WaitingForFinalization,
             status: TransactionStatus::Pending,
             message: "".to_string(),
             timestamp: Utc::now().naive_utc(),
+            retry_count: 0,
         };
 
         self.db
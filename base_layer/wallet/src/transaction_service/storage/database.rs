@@ -20,15 +20,19 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{output_manager_service::TxId, transaction_service::error::TransactionStorageError};
+use crate::{
+    output_manager_service::TxId,
+    storage::blocking_adapter::BlockingAdapter,
+    transaction_service::error::TransactionStorageError,
+};
 use chrono::{NaiveDateTime, Utc};
+use futures::future::BoxFuture;
 use log::*;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     convert::TryFrom,
     fmt::{Display, Error, Formatter},
-    sync::Arc,
 };
 use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
@@ -79,8 +83,23 @@ pub trait TransactionBackend: Send + Sync {
     fn broadcast_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError>;
     /// Indicated that a completed transaction has been detected as mined on the base layer
     fn mine_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError>;
+    /// Indicated that a mined transaction was removed from the base layer by a reorg, returning it to the broadcast
+    /// state
+    fn unmine_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError>;
     /// Cancel Completed transaction, this will update the transaction status
     fn cancel_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError>;
+    /// Replace the stored protocol state and protocol stage of the `OutboundTransaction` with the provided `TxId`
+    fn update_pending_outbound_transaction(
+        &self,
+        tx_id: TxId,
+        outbound_transaction: OutboundTransaction,
+    ) -> Result<(), TransactionStorageError>;
+    /// Replace the stored protocol state and protocol stage of the `InboundTransaction` with the provided `TxId`
+    fn update_pending_inbound_transaction(
+        &self,
+        tx_id: TxId,
+        inbound_transaction: InboundTransaction,
+    ) -> Result<(), TransactionStorageError>;
     /// Update a completed transactions timestamp for use in test data generation
     #[cfg(feature = "test_harness")]
     fn update_completed_transaction_timestamp(
@@ -90,6 +109,126 @@ pub trait TransactionBackend: Send + Sync {
     ) -> Result<(), TransactionStorageError>;
 }
 
+/// The async counterpart of `TransactionBackend` used by the `TransactionDatabase`. Arguments are taken by value so
+/// that the returned futures are `'static`. It is implemented for a `BlockingAdapter` over any synchronous
+/// `TransactionBackend`.
+pub trait AsyncTransactionBackend: Send + Sync {
+    fn fetch(&self, key: DbKey) -> BoxFuture<'static, Result<Option<DbValue>, TransactionStorageError>>;
+    fn contains(&self, key: DbKey) -> BoxFuture<'static, Result<bool, TransactionStorageError>>;
+    fn write(&self, op: WriteOperation) -> BoxFuture<'static, Result<Option<DbValue>, TransactionStorageError>>;
+    fn transaction_exists(&self, tx_id: TxId) -> BoxFuture<'static, Result<bool, TransactionStorageError>>;
+    fn complete_outbound_transaction(
+        &self,
+        tx_id: TxId,
+        completed_transaction: CompletedTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+    fn complete_inbound_transaction(
+        &self,
+        tx_id: TxId,
+        completed_transaction: CompletedTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+    fn complete_coinbase_transaction(
+        &self,
+        tx_id: TxId,
+        completed_transaction: CompletedTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+    fn broadcast_completed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+    fn mine_completed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+    fn unmine_completed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+    fn cancel_completed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+    fn update_pending_outbound_transaction(
+        &self,
+        tx_id: TxId,
+        outbound_transaction: OutboundTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+    fn update_pending_inbound_transaction(
+        &self,
+        tx_id: TxId,
+        inbound_transaction: InboundTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>;
+}
+
+impl<T> AsyncTransactionBackend for BlockingAdapter<T>
+where T: TransactionBackend + 'static
+{
+    fn fetch(&self, key: DbKey) -> BoxFuture<'static, Result<Option<DbValue>, TransactionStorageError>> {
+        self.run(move |db| db.fetch(&key))
+    }
+
+    fn contains(&self, key: DbKey) -> BoxFuture<'static, Result<bool, TransactionStorageError>> {
+        self.run(move |db| db.contains(&key))
+    }
+
+    fn write(&self, op: WriteOperation) -> BoxFuture<'static, Result<Option<DbValue>, TransactionStorageError>> {
+        self.run(move |db| db.write(op))
+    }
+
+    fn transaction_exists(&self, tx_id: TxId) -> BoxFuture<'static, Result<bool, TransactionStorageError>> {
+        self.run(move |db| db.transaction_exists(tx_id))
+    }
+
+    fn complete_outbound_transaction(
+        &self,
+        tx_id: TxId,
+        completed_transaction: CompletedTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>
+    {
+        self.run(move |db| db.complete_outbound_transaction(tx_id, completed_transaction))
+    }
+
+    fn complete_inbound_transaction(
+        &self,
+        tx_id: TxId,
+        completed_transaction: CompletedTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>
+    {
+        self.run(move |db| db.complete_inbound_transaction(tx_id, completed_transaction))
+    }
+
+    fn complete_coinbase_transaction(
+        &self,
+        tx_id: TxId,
+        completed_transaction: CompletedTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>
+    {
+        self.run(move |db| db.complete_coinbase_transaction(tx_id, completed_transaction))
+    }
+
+    fn broadcast_completed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), TransactionStorageError>> {
+        self.run(move |db| db.broadcast_completed_transaction(tx_id))
+    }
+
+    fn mine_completed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), TransactionStorageError>> {
+        self.run(move |db| db.mine_completed_transaction(tx_id))
+    }
+
+    fn unmine_completed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), TransactionStorageError>> {
+        self.run(move |db| db.unmine_completed_transaction(tx_id))
+    }
+
+    fn cancel_completed_transaction(&self, tx_id: TxId) -> BoxFuture<'static, Result<(), TransactionStorageError>> {
+        self.run(move |db| db.cancel_completed_transaction(tx_id))
+    }
+
+    fn update_pending_outbound_transaction(
+        &self,
+        tx_id: TxId,
+        outbound_transaction: OutboundTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>
+    {
+        self.run(move |db| db.update_pending_outbound_transaction(tx_id, outbound_transaction))
+    }
+
+    fn update_pending_inbound_transaction(
+        &self,
+        tx_id: TxId,
+        inbound_transaction: InboundTransaction,
+    ) -> BoxFuture<'static, Result<(), TransactionStorageError>>
+    {
+        self.run(move |db| db.update_pending_inbound_transaction(tx_id, inbound_transaction))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TransactionStatus {
     /// This transaction has been completed between the parties but has not been broadcast to the base layer network.
@@ -107,6 +246,20 @@ pub enum TransactionStatus {
     Cancelled,
 }
 
+impl TransactionStatus {
+    /// The id of the description of this status in the wallet's `MessageCatalogue`
+    pub fn message_id(&self) -> &'static str {
+        match self {
+            TransactionStatus::Completed => "transaction_status.completed",
+            TransactionStatus::Broadcast => "transaction_status.broadcast",
+            TransactionStatus::Mined => "transaction_status.mined",
+            TransactionStatus::Imported => "transaction_status.imported",
+            TransactionStatus::Pending => "transaction_status.pending",
+            TransactionStatus::Cancelled => "transaction_status.cancelled",
+        }
+    }
+}
+
 impl TryFrom<i32> for TransactionStatus {
     type Error = TransactionStorageError;
 
@@ -129,15 +282,86 @@ impl Default for TransactionStatus {
     }
 }
 
+/// The stages of the sender's side of a transaction negotiation. The stage is persisted along with the sender protocol
+/// after every transition so that a negotiation interrupted by a restart resumes where it left off.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SenderProtocolStage {
+    /// The sender message has been built but has not been handed to the comms layer yet
+    Queued,
+    /// The sender message has been sent and the recipient's reply is awaited
+    WaitingForReply,
+    /// The recipient's reply has been applied but the finalized transaction has not been sent to the recipient yet
+    Finalized,
+}
+
+impl SenderProtocolStage {
+    /// Returns true if a sender protocol at this stage may move to the `next` stage
+    pub fn can_transition_to(self, next: SenderProtocolStage) -> bool {
+        match (self, next) {
+            (SenderProtocolStage::Queued, SenderProtocolStage::WaitingForReply) |
+            (SenderProtocolStage::Queued, SenderProtocolStage::Finalized) |
+            (SenderProtocolStage::WaitingForReply, SenderProtocolStage::Finalized) => true,
+            _ => false,
+        }
+    }
+}
+
+impl TryFrom<i32> for SenderProtocolStage {
+    type Error = TransactionStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(SenderProtocolStage::Queued),
+            1 => Ok(SenderProtocolStage::WaitingForReply),
+            2 => Ok(SenderProtocolStage::Finalized),
+            _ => Err(TransactionStorageError::ConversionError),
+        }
+    }
+}
+
+/// The stages of the recipient's side of a transaction negotiation. The stage is persisted along with the receiver
+/// protocol after every transition so that a negotiation interrupted by a restart resumes where it left off.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReceiverProtocolStage {
+    /// The reply has been generated but has not been handed to the comms layer yet
+    ReplyPending,
+    /// The reply has been sent and the finalized transaction is awaited
+    WaitingForFinalization,
+}
+
+impl ReceiverProtocolStage {
+    /// Returns true if a receiver protocol at this stage may move to the `next` stage
+    pub fn can_transition_to(self, next: ReceiverProtocolStage) -> bool {
+        match (self, next) {
+            (ReceiverProtocolStage::ReplyPending, ReceiverProtocolStage::WaitingForFinalization) => true,
+            _ => false,
+        }
+    }
+}
+
+impl TryFrom<i32> for ReceiverProtocolStage {
+    type Error = TransactionStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ReceiverProtocolStage::ReplyPending),
+            1 => Ok(ReceiverProtocolStage::WaitingForFinalization),
+            _ => Err(TransactionStorageError::ConversionError),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct InboundTransaction {
     pub tx_id: TxId,
     pub source_public_key: CommsPublicKey,
     pub amount: MicroTari,
     pub receiver_protocol: ReceiverTransactionProtocol,
+    pub stage: ReceiverProtocolStage,
     pub status: TransactionStatus,
     pub message: String,
     pub timestamp: NaiveDateTime,
+    pub retry_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -147,9 +371,11 @@ pub struct OutboundTransaction {
     pub amount: MicroTari,
     pub fee: MicroTari,
     pub sender_protocol: SenderTransactionProtocol,
+    pub stage: SenderProtocolStage,
     pub status: TransactionStatus,
     pub message: String,
     pub timestamp: NaiveDateTime,
+    pub retry_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -216,9 +442,11 @@ impl From<CompletedTransaction> for InboundTransaction {
             source_public_key: ct.source_public_key,
             amount: ct.amount,
             receiver_protocol: ReceiverTransactionProtocol::new_placeholder(),
+            stage: ReceiverProtocolStage::WaitingForFinalization,
             status: ct.status,
             message: ct.message,
             timestamp: ct.timestamp,
+            retry_count: 0,
         }
     }
 }
@@ -231,9 +459,11 @@ impl From<CompletedTransaction> for OutboundTransaction {
             amount: ct.amount,
             fee: ct.fee,
             sender_protocol: SenderTransactionProtocol::new_placeholder(),
+            stage: SenderProtocolStage::Finalized,
             status: ct.status,
             message: ct.message,
             timestamp: ct.timestamp,
+            retry_count: 0,
         }
     }
 }
@@ -257,14 +487,16 @@ macro_rules! fetch {
 pub struct TransactionDatabase<T>
 where T: TransactionBackend + 'static
 {
-    db: Arc<T>,
+    db: BlockingAdapter<T>,
 }
 
 impl<T> TransactionDatabase<T>
 where T: TransactionBackend + 'static
 {
     pub fn new(db: T) -> Self {
-        Self { db: Arc::new(db) }
+        Self {
+            db: BlockingAdapter::new(db),
+        }
     }
 
     pub async fn add_pending_inbound_transaction(
@@ -273,15 +505,12 @@ where T: TransactionBackend + 'static
         inbound_tx: InboundTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PendingInboundTransaction(
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::PendingInboundTransaction(
                 tx_id,
                 Box::new(inbound_tx),
             )))
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        .await?;
 
         Ok(())
     }
@@ -292,54 +521,127 @@ where T: TransactionBackend + 'static
         outbound_tx: OutboundTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PendingOutboundTransaction(
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::PendingOutboundTransaction(
                 tx_id,
                 Box::new(outbound_tx),
             )))
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        .await?;
         Ok(())
     }
 
     pub async fn remove_pending_outbound_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Remove(DbKey::PendingOutboundTransaction(tx_id)))
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        self.db
+            .write(WriteOperation::Remove(DbKey::PendingOutboundTransaction(tx_id)))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_pending_inbound_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        self.db
+            .write(WriteOperation::Remove(DbKey::PendingInboundTransaction(tx_id)))
+            .await?;
         Ok(())
     }
 
+    /// Record that the current message of a pending outbound transaction's negotiation is being sent again and return
+    /// the updated transaction
+    pub async fn increment_outbound_retry_count(
+        &self,
+        tx_id: TxId,
+    ) -> Result<OutboundTransaction, TransactionStorageError>
+    {
+        self.db
+            .run(move |db| {
+                let current: OutboundTransaction = fetch!(db, tx_id, PendingOutboundTransaction)?;
+                let retry_count = current.retry_count + 1;
+                let updated_tx = OutboundTransaction { retry_count, ..current };
+                db.update_pending_outbound_transaction(tx_id, updated_tx.clone())?;
+                Ok(updated_tx)
+            })
+            .await
+    }
+
+    /// Record that the current message of a pending inbound transaction's negotiation is being sent again and return
+    /// the updated transaction
+    pub async fn increment_inbound_retry_count(
+        &self,
+        tx_id: TxId,
+    ) -> Result<InboundTransaction, TransactionStorageError> {
+        self.db
+            .run(move |db| {
+                let current: InboundTransaction = fetch!(db, tx_id, PendingInboundTransaction)?;
+                let retry_count = current.retry_count + 1;
+                let updated_tx = InboundTransaction { retry_count, ..current };
+                db.update_pending_inbound_transaction(tx_id, updated_tx.clone())?;
+                Ok(updated_tx)
+            })
+            .await
+    }
+
+    /// Persist the transition of a pending outbound transaction's protocol to the provided stage, along with its
+    /// updated protocol state. Transitions that do not follow from the stored stage are rejected.
+    pub async fn advance_outbound_transaction(
+        &self,
+        outbound_tx: OutboundTransaction,
+        stage: SenderProtocolStage,
+    ) -> Result<OutboundTransaction, TransactionStorageError>
+    {
+        self.db
+            .run(move |db| {
+                let tx_id = outbound_tx.tx_id;
+                let current: OutboundTransaction = fetch!(db, tx_id, PendingOutboundTransaction)?;
+                if !current.stage.can_transition_to(stage) {
+                    return Err(TransactionStorageError::InvalidProtocolTransition);
+                }
+                let updated_tx = OutboundTransaction { stage, ..outbound_tx };
+                db.update_pending_outbound_transaction(tx_id, updated_tx.clone())?;
+                Ok(updated_tx)
+            })
+            .await
+    }
+
+    /// Persist the transition of a pending inbound transaction's protocol to the provided stage, along with its
+    /// updated protocol state. Transitions that do not follow from the stored stage are rejected.
+    pub async fn advance_inbound_transaction(
+        &self,
+        inbound_tx: InboundTransaction,
+        stage: ReceiverProtocolStage,
+    ) -> Result<InboundTransaction, TransactionStorageError>
+    {
+        self.db
+            .run(move |db| {
+                let tx_id = inbound_tx.tx_id;
+                let current: InboundTransaction = fetch!(db, tx_id, PendingInboundTransaction)?;
+                if !current.stage.can_transition_to(stage) {
+                    return Err(TransactionStorageError::InvalidProtocolTransition);
+                }
+                let updated_tx = InboundTransaction { stage, ..inbound_tx };
+                db.update_pending_inbound_transaction(tx_id, updated_tx.clone())?;
+                Ok(updated_tx)
+            })
+            .await
+    }
+
     pub async fn add_pending_coinbase_transaction(
         &self,
         tx_id: TxId,
         coinbase_tx: PendingCoinbaseTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PendingCoinbaseTransaction(
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::PendingCoinbaseTransaction(
                 tx_id,
                 Box::new(coinbase_tx),
             )))
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        .await?;
         Ok(())
     }
 
     /// Check if a transaction with the specified TxId exists in any of the collections
     pub async fn transaction_exists(&self, tx_id: TxId) -> Result<bool, TransactionStorageError> {
-        let db_clone = self.db.clone();
         let tx_id_clone = tx_id;
-        tokio::task::spawn_blocking(move || db_clone.transaction_exists(tx_id_clone))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.transaction_exists(tx_id_clone).await
     }
 
     pub async fn get_pending_outbound_transaction(
@@ -347,10 +649,10 @@ where T: TransactionBackend + 'static
         tx_id: TxId,
     ) -> Result<OutboundTransaction, TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-        let result = tokio::task::spawn_blocking(move || fetch!(db_clone, tx_id, PendingOutboundTransaction))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let result = self
+            .db
+            .run(move |db| fetch!(db, tx_id, PendingOutboundTransaction))
+            .await?;
         Ok(result)
     }
 
@@ -359,11 +661,10 @@ where T: TransactionBackend + 'static
         tx_id: TxId,
     ) -> Result<InboundTransaction, TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-
-        let result = tokio::task::spawn_blocking(move || fetch!(db_clone, tx_id, PendingInboundTransaction))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let result = self
+            .db
+            .run(move |db| fetch!(db, tx_id, PendingInboundTransaction))
+            .await?;
 
         Ok(result)
     }
@@ -373,11 +674,10 @@ where T: TransactionBackend + 'static
         tx_id: TxId,
     ) -> Result<PendingCoinbaseTransaction, TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-
-        let result = tokio::task::spawn_blocking(move || fetch!(db_clone, tx_id, PendingCoinbaseTransaction))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let result = self
+            .db
+            .run(move |db| fetch!(db, tx_id, PendingCoinbaseTransaction))
+            .await?;
 
         Ok(result)
     }
@@ -387,96 +687,105 @@ where T: TransactionBackend + 'static
         tx_id: TxId,
     ) -> Result<CompletedTransaction, TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-
-        let result = tokio::task::spawn_blocking(move || fetch!(db_clone, tx_id, CompletedTransaction))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let result = self.db.run(move |db| fetch!(db, tx_id, CompletedTransaction)).await?;
         Ok(result)
     }
 
     pub async fn get_pending_inbound_transactions(
         &self,
     ) -> Result<HashMap<TxId, InboundTransaction>, TransactionStorageError> {
-        let db_clone = self.db.clone();
-
-        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::PendingInboundTransactions) {
-            Ok(None) => log_error(
-                DbKey::PendingInboundTransactions,
-                TransactionStorageError::UnexpectedResult(
-                    "Could not retrieve pending inbound transactions".to_string(),
+        let t = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::PendingInboundTransactions) {
+                Ok(None) => log_error(
+                    DbKey::PendingInboundTransactions,
+                    TransactionStorageError::UnexpectedResult(
+                        "Could not retrieve pending inbound transactions".to_string(),
+                    ),
                 ),
-            ),
-            Ok(Some(DbValue::PendingInboundTransactions(pt))) => Ok(pt),
-            Ok(Some(other)) => unexpected_result(DbKey::PendingInboundTransactions, other),
-            Err(e) => log_error(DbKey::PendingInboundTransactions, e),
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+                Ok(Some(DbValue::PendingInboundTransactions(pt))) => Ok(pt),
+                Ok(Some(other)) => unexpected_result(DbKey::PendingInboundTransactions, other),
+                Err(e) => log_error(DbKey::PendingInboundTransactions, e),
+            })
+            .await?;
         Ok(t)
     }
 
     pub async fn get_pending_outbound_transactions(
         &self,
     ) -> Result<HashMap<TxId, OutboundTransaction>, TransactionStorageError> {
-        let db_clone = self.db.clone();
-
-        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::PendingOutboundTransactions) {
-            Ok(None) => log_error(
-                DbKey::PendingOutboundTransactions,
-                TransactionStorageError::UnexpectedResult(
-                    "Could not retrieve pending outbound transactions".to_string(),
+        let t = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::PendingOutboundTransactions) {
+                Ok(None) => log_error(
+                    DbKey::PendingOutboundTransactions,
+                    TransactionStorageError::UnexpectedResult(
+                        "Could not retrieve pending outbound transactions".to_string(),
+                    ),
                 ),
-            ),
-            Ok(Some(DbValue::PendingOutboundTransactions(pt))) => Ok(pt),
-            Ok(Some(other)) => unexpected_result(DbKey::PendingOutboundTransactions, other),
-            Err(e) => log_error(DbKey::PendingOutboundTransactions, e),
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+                Ok(Some(DbValue::PendingOutboundTransactions(pt))) => Ok(pt),
+                Ok(Some(other)) => unexpected_result(DbKey::PendingOutboundTransactions, other),
+                Err(e) => log_error(DbKey::PendingOutboundTransactions, e),
+            })
+            .await?;
         Ok(t)
     }
 
     pub async fn get_pending_coinbase_transactions(
         &self,
     ) -> Result<HashMap<TxId, PendingCoinbaseTransaction>, TransactionStorageError> {
-        let db_clone = self.db.clone();
-
-        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::PendingCoinbaseTransactions) {
-            Ok(None) => log_error(
-                DbKey::PendingCoinbaseTransactions,
-                TransactionStorageError::UnexpectedResult(
-                    "Could not retrieve pending coinbase transactions".to_string(),
+        let t = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::PendingCoinbaseTransactions) {
+                Ok(None) => log_error(
+                    DbKey::PendingCoinbaseTransactions,
+                    TransactionStorageError::UnexpectedResult(
+                        "Could not retrieve pending coinbase transactions".to_string(),
+                    ),
                 ),
-            ),
-            Ok(Some(DbValue::PendingCoinbaseTransactions(pt))) => Ok(pt),
-            Ok(Some(other)) => unexpected_result(DbKey::PendingCoinbaseTransactions, other),
-            Err(e) => log_error(DbKey::PendingCoinbaseTransactions, e),
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+                Ok(Some(DbValue::PendingCoinbaseTransactions(pt))) => Ok(pt),
+                Ok(Some(other)) => unexpected_result(DbKey::PendingCoinbaseTransactions, other),
+                Err(e) => log_error(DbKey::PendingCoinbaseTransactions, e),
+            })
+            .await?;
         Ok(t)
     }
 
     pub async fn get_completed_transactions(
         &self,
     ) -> Result<HashMap<TxId, CompletedTransaction>, TransactionStorageError> {
-        let db_clone = self.db.clone();
-
-        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::CompletedTransactions) {
-            Ok(None) => log_error(
-                DbKey::CompletedTransactions,
-                TransactionStorageError::UnexpectedResult("Could not retrieve completed transactions".to_string()),
-            ),
-            Ok(Some(DbValue::CompletedTransactions(pt))) => Ok(pt),
-            Ok(Some(other)) => unexpected_result(DbKey::CompletedTransactions, other),
-            Err(e) => log_error(DbKey::CompletedTransactions, e),
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        let t = self
+            .db
+            .run(move |db| match db.fetch(&DbKey::CompletedTransactions) {
+                Ok(None) => log_error(
+                    DbKey::CompletedTransactions,
+                    TransactionStorageError::UnexpectedResult("Could not retrieve completed transactions".to_string()),
+                ),
+                Ok(Some(DbValue::CompletedTransactions(pt))) => Ok(pt),
+                Ok(Some(other)) => unexpected_result(DbKey::CompletedTransactions, other),
+                Err(e) => log_error(DbKey::CompletedTransactions, e),
+            })
+            .await?;
         Ok(t)
     }
 
+    /// This method inserts a transaction that was completed without being negotiated directly into the
+    /// `CompleteTransaction` collection.
+    pub async fn insert_completed_transaction(
+        &self,
+        tx_id: TxId,
+        transaction: CompletedTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::CompletedTransaction(
+                tx_id,
+                Box::new(transaction),
+            )))
+        .await?;
+        Ok(())
+    }
+
     /// This method moves a `PendingOutboundTransaction` to the `CompleteTransaction` collection.
     pub async fn complete_outbound_transaction(
         &self,
@@ -484,12 +793,7 @@ where T: TransactionBackend + 'static
         transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || db_clone.complete_outbound_transaction(tx_id, transaction))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.complete_outbound_transaction(tx_id, transaction).await
     }
 
     /// This method moves a `PendingInboundTransaction` to the `CompleteTransaction` collection.
@@ -499,12 +803,7 @@ where T: TransactionBackend + 'static
         transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || db_clone.complete_inbound_transaction(tx_id, transaction))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.complete_inbound_transaction(tx_id, transaction).await
     }
 
     /// This method moves a `PendingCoinbaseTransaction` to the `CompleteTransaction` collection.
@@ -514,51 +813,34 @@ where T: TransactionBackend + 'static
         transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || db_clone.complete_coinbase_transaction(tx_id, transaction))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.complete_coinbase_transaction(tx_id, transaction).await
     }
 
     pub async fn cancel_coinbase_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Remove(DbKey::PendingCoinbaseTransaction(tx_id)))
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        self.db
+            .write(WriteOperation::Remove(DbKey::PendingCoinbaseTransaction(tx_id)))
+            .await?;
         Ok(())
     }
 
     pub async fn cancel_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || db_clone.cancel_completed_transaction(tx_id))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        self.db.cancel_completed_transaction(tx_id).await?;
         Ok(())
     }
 
     /// Indicated that the specified completed transaction has been broadcast into the mempool
     pub async fn broadcast_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let db_clone = self.db.clone();
-
-        tokio::task::spawn_blocking(move || db_clone.broadcast_completed_transaction(tx_id))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+        self.db.broadcast_completed_transaction(tx_id).await
     }
 
     /// Indicated that the specified completed transaction has been detected as mined on the base layer
     pub async fn mine_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let db_clone = self.db.clone();
+        self.db.mine_completed_transaction(tx_id).await
+    }
 
-        tokio::task::spawn_blocking(move || db_clone.mine_completed_transaction(tx_id))
-            .await
-            .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))
-            .and_then(|inner_result| inner_result)
+    /// Indicated that the specified mined transaction was removed from the base layer by a reorg
+    pub async fn unmine_completed_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        self.db.unmine_completed_transaction(tx_id).await
     }
 
     #[allow(clippy::erasing_op)] // this is for 0 * uT
@@ -583,15 +865,12 @@ where T: TransactionBackend + 'static
             timestamp: Utc::now().naive_utc(),
         };
 
-        let db_clone = self.db.clone();
-        tokio::task::spawn_blocking(move || {
-            db_clone.write(WriteOperation::Insert(DbKeyValuePair::CompletedTransaction(
+        self.db
+            .write(WriteOperation::Insert(DbKeyValuePair::CompletedTransaction(
                 tx_id,
                 Box::new(transaction),
             )))
-        })
-        .await
-        .or_else(|err| Err(TransactionStorageError::BlockingTaskSpawnError(err.to_string())))??;
+        .await?;
         Ok(())
     }
 }
@@ -22,6 +22,7 @@
 
 use crate::{
     output_manager_service::TxId,
+    storage::memory_store::MemoryStore,
     transaction_service::{
         error::TransactionStorageError,
         storage::database::{
@@ -40,10 +41,7 @@ use crate::{
 };
 #[cfg(feature = "test_harness")]
 use chrono::NaiveDateTime;
-use std::{
-    collections::HashMap,
-    sync::{Arc, RwLock},
-};
+use std::collections::HashMap;
 
 #[derive(Default)]
 struct InnerDatabase {
@@ -66,164 +64,169 @@ impl InnerDatabase {
 
 #[derive(Clone, Default)]
 pub struct TransactionMemoryDatabase {
-    db: Arc<RwLock<InnerDatabase>>,
+    db: MemoryStore<InnerDatabase>,
 }
 
 impl TransactionMemoryDatabase {
     pub fn new() -> Self {
         Self {
-            db: Arc::new(RwLock::new(InnerDatabase::new())),
+            db: MemoryStore::new(InnerDatabase::new()),
         }
     }
 }
 
 impl TransactionBackend for TransactionMemoryDatabase {
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, TransactionStorageError> {
-        let db = acquire_read_lock!(self.db);
-        let result = match key {
-            DbKey::PendingOutboundTransaction(t) => db
-                .pending_outbound_transactions
-                .get(t)
-                .map(|v| DbValue::PendingOutboundTransaction(Box::new(v.clone()))),
-            DbKey::PendingInboundTransaction(t) => db
-                .pending_inbound_transactions
-                .get(t)
-                .map(|v| DbValue::PendingInboundTransaction(Box::new(v.clone()))),
-            DbKey::CompletedTransaction(t) => {
-                let mut result = None;
-                if let Some(v) = db.completed_transactions.get(t) {
-                    if v.status != TransactionStatus::Cancelled {
-                        result = Some(DbValue::CompletedTransaction(Box::new(v.clone())));
+        let key = key.clone();
+        self.db.run(move |db| {
+            let result = match &key {
+                DbKey::PendingOutboundTransaction(t) => db
+                    .pending_outbound_transactions
+                    .get(t)
+                    .map(|v| DbValue::PendingOutboundTransaction(Box::new(v.clone()))),
+                DbKey::PendingInboundTransaction(t) => db
+                    .pending_inbound_transactions
+                    .get(t)
+                    .map(|v| DbValue::PendingInboundTransaction(Box::new(v.clone()))),
+                DbKey::CompletedTransaction(t) => {
+                    let mut result = None;
+                    if let Some(v) = db.completed_transactions.get(t) {
+                        if v.status != TransactionStatus::Cancelled {
+                            result = Some(DbValue::CompletedTransaction(Box::new(v.clone())));
+                        }
                     }
-                }
-                result
-            },
-            DbKey::PendingCoinbaseTransaction(t) => db
-                .pending_coinbase_transactions
-                .get(t)
-                .map(|v| DbValue::PendingCoinbaseTransaction(Box::new(v.clone()))),
-            DbKey::PendingOutboundTransactions => Some(DbValue::PendingOutboundTransactions(
-                db.pending_outbound_transactions.clone(),
-            )),
-            DbKey::PendingInboundTransactions => Some(DbValue::PendingInboundTransactions(
-                db.pending_inbound_transactions.clone(),
-            )),
-            DbKey::PendingCoinbaseTransactions => Some(DbValue::PendingCoinbaseTransactions(
-                db.pending_coinbase_transactions.clone(),
-            )),
-            DbKey::CompletedTransactions => {
-                // Filter out cancelled transactions
-                let mut result = HashMap::new();
-                for (k, v) in db.completed_transactions.iter() {
-                    if v.status != TransactionStatus::Cancelled {
-                        result.insert(k.clone(), v.clone());
+                    result
+                },
+                DbKey::PendingCoinbaseTransaction(t) => db
+                    .pending_coinbase_transactions
+                    .get(t)
+                    .map(|v| DbValue::PendingCoinbaseTransaction(Box::new(v.clone()))),
+                DbKey::PendingOutboundTransactions => Some(DbValue::PendingOutboundTransactions(
+                    db.pending_outbound_transactions.clone(),
+                )),
+                DbKey::PendingInboundTransactions => Some(DbValue::PendingInboundTransactions(
+                    db.pending_inbound_transactions.clone(),
+                )),
+                DbKey::PendingCoinbaseTransactions => Some(DbValue::PendingCoinbaseTransactions(
+                    db.pending_coinbase_transactions.clone(),
+                )),
+                DbKey::CompletedTransactions => {
+                    // Filter out cancelled transactions
+                    let mut result = HashMap::new();
+                    for (k, v) in db.completed_transactions.iter() {
+                        if v.status != TransactionStatus::Cancelled {
+                            result.insert(k.clone(), v.clone());
+                        }
                     }
-                }
-                Some(DbValue::CompletedTransactions(result))
-            },
-        };
+                    Some(DbValue::CompletedTransactions(result))
+                },
+            };
 
-        Ok(result)
+            Ok(result)
+        })
     }
 
     fn contains(&self, key: &DbKey) -> Result<bool, TransactionStorageError> {
-        let db = acquire_read_lock!(self.db);
-        let result = match key {
-            DbKey::PendingOutboundTransaction(k) => db.pending_outbound_transactions.contains_key(k),
-            DbKey::PendingInboundTransaction(k) => db.pending_inbound_transactions.contains_key(k),
-            DbKey::CompletedTransaction(k) => db.completed_transactions.contains_key(k),
-            DbKey::PendingCoinbaseTransaction(k) => db.pending_coinbase_transactions.contains_key(k),
-            DbKey::PendingOutboundTransactions => false,
-            DbKey::PendingInboundTransactions => false,
-            DbKey::CompletedTransactions => false,
-            DbKey::PendingCoinbaseTransactions => false,
-        };
-
-        Ok(result)
+        let key = key.clone();
+        self.db.run(move |db| {
+            let result = match &key {
+                DbKey::PendingOutboundTransaction(k) => db.pending_outbound_transactions.contains_key(k),
+                DbKey::PendingInboundTransaction(k) => db.pending_inbound_transactions.contains_key(k),
+                DbKey::CompletedTransaction(k) => db.completed_transactions.contains_key(k),
+                DbKey::PendingCoinbaseTransaction(k) => db.pending_coinbase_transactions.contains_key(k),
+                DbKey::PendingOutboundTransactions => false,
+                DbKey::PendingInboundTransactions => false,
+                DbKey::CompletedTransactions => false,
+                DbKey::PendingCoinbaseTransactions => false,
+            };
+
+            Ok(result)
+        })
     }
 
     fn write(&self, op: WriteOperation) -> Result<Option<DbValue>, TransactionStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-        match op {
-            WriteOperation::Insert(kvp) => match kvp {
-                DbKeyValuePair::PendingOutboundTransaction(k, v) => {
-                    if db.pending_outbound_transactions.contains_key(&k) {
-                        return Err(TransactionStorageError::DuplicateOutput);
-                    }
-                    db.pending_outbound_transactions.insert(k, *v);
-                },
-                DbKeyValuePair::PendingInboundTransaction(k, v) => {
-                    if db.pending_inbound_transactions.contains_key(&k) {
-                        return Err(TransactionStorageError::DuplicateOutput);
-                    }
-                    db.pending_inbound_transactions.insert(k, *v);
-                },
-                DbKeyValuePair::PendingCoinbaseTransaction(k, v) => {
-                    if db.pending_coinbase_transactions.contains_key(&k) {
-                        return Err(TransactionStorageError::DuplicateOutput);
-                    }
-                    db.pending_coinbase_transactions.insert(k, *v);
-                },
-
-                DbKeyValuePair::CompletedTransaction(k, v) => {
-                    if db.completed_transactions.contains_key(&k) {
-                        return Err(TransactionStorageError::DuplicateOutput);
-                    }
-                    db.completed_transactions.insert(k, *v);
+        self.db.run(move |db| {
+            match op {
+                WriteOperation::Insert(kvp) => match kvp {
+                    DbKeyValuePair::PendingOutboundTransaction(k, v) => {
+                        if db.pending_outbound_transactions.contains_key(&k) {
+                            return Err(TransactionStorageError::DuplicateOutput);
+                        }
+                        db.pending_outbound_transactions.insert(k, *v);
+                    },
+                    DbKeyValuePair::PendingInboundTransaction(k, v) => {
+                        if db.pending_inbound_transactions.contains_key(&k) {
+                            return Err(TransactionStorageError::DuplicateOutput);
+                        }
+                        db.pending_inbound_transactions.insert(k, *v);
+                    },
+                    DbKeyValuePair::PendingCoinbaseTransaction(k, v) => {
+                        if db.pending_coinbase_transactions.contains_key(&k) {
+                            return Err(TransactionStorageError::DuplicateOutput);
+                        }
+                        db.pending_coinbase_transactions.insert(k, *v);
+                    },
+
+                    DbKeyValuePair::CompletedTransaction(k, v) => {
+                        if db.completed_transactions.contains_key(&k) {
+                            return Err(TransactionStorageError::DuplicateOutput);
+                        }
+                        db.completed_transactions.insert(k, *v);
+                    },
                 },
-            },
-            WriteOperation::Remove(k) => match k {
-                DbKey::PendingOutboundTransaction(k) => {
-                    if let Some(p) = db.pending_outbound_transactions.remove(&k) {
-                        return Ok(Some(DbValue::PendingOutboundTransaction(Box::new(p))));
-                    } else {
-                        return Err(TransactionStorageError::ValueNotFound(
-                            DbKey::PendingOutboundTransaction(k),
-                        ));
-                    }
-                },
-                DbKey::PendingInboundTransaction(k) => {
-                    if let Some(p) = db.pending_inbound_transactions.remove(&k) {
-                        return Ok(Some(DbValue::PendingInboundTransaction(Box::new(p))));
-                    } else {
-                        return Err(TransactionStorageError::ValueNotFound(
-                            DbKey::PendingInboundTransaction(k),
-                        ));
-                    }
-                },
-                DbKey::PendingCoinbaseTransaction(k) => {
-                    if let Some(p) = db.pending_coinbase_transactions.remove(&k) {
-                        return Ok(Some(DbValue::PendingCoinbaseTransaction(Box::new(p))));
-                    } else {
-                        return Err(TransactionStorageError::ValueNotFound(
-                            DbKey::PendingCoinbaseTransaction(k),
-                        ));
-                    }
-                },
-                DbKey::CompletedTransaction(k) => {
-                    if let Some(p) = db.completed_transactions.remove(&k) {
-                        return Ok(Some(DbValue::CompletedTransaction(Box::new(p))));
-                    } else {
-                        return Err(TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(k)));
-                    }
+                WriteOperation::Remove(k) => match k {
+                    DbKey::PendingOutboundTransaction(k) => {
+                        if let Some(p) = db.pending_outbound_transactions.remove(&k) {
+                            return Ok(Some(DbValue::PendingOutboundTransaction(Box::new(p))));
+                        } else {
+                            return Err(TransactionStorageError::ValueNotFound(
+                                DbKey::PendingOutboundTransaction(k),
+                            ));
+                        }
+                    },
+                    DbKey::PendingInboundTransaction(k) => {
+                        if let Some(p) = db.pending_inbound_transactions.remove(&k) {
+                            return Ok(Some(DbValue::PendingInboundTransaction(Box::new(p))));
+                        } else {
+                            return Err(TransactionStorageError::ValueNotFound(
+                                DbKey::PendingInboundTransaction(k),
+                            ));
+                        }
+                    },
+                    DbKey::PendingCoinbaseTransaction(k) => {
+                        if let Some(p) = db.pending_coinbase_transactions.remove(&k) {
+                            return Ok(Some(DbValue::PendingCoinbaseTransaction(Box::new(p))));
+                        } else {
+                            return Err(TransactionStorageError::ValueNotFound(
+                                DbKey::PendingCoinbaseTransaction(k),
+                            ));
+                        }
+                    },
+                    DbKey::CompletedTransaction(k) => {
+                        if let Some(p) = db.completed_transactions.remove(&k) {
+                            return Ok(Some(DbValue::CompletedTransaction(Box::new(p))));
+                        } else {
+                            return Err(TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(k)));
+                        }
+                    },
+                    DbKey::PendingInboundTransactions => return Err(TransactionStorageError::OperationNotSupported),
+                    DbKey::PendingOutboundTransactions => return Err(TransactionStorageError::OperationNotSupported),
+                    DbKey::CompletedTransactions => return Err(TransactionStorageError::OperationNotSupported),
+                    DbKey::PendingCoinbaseTransactions => return Err(TransactionStorageError::OperationNotSupported),
                 },
-                DbKey::PendingInboundTransactions => return Err(TransactionStorageError::OperationNotSupported),
-                DbKey::PendingOutboundTransactions => return Err(TransactionStorageError::OperationNotSupported),
-                DbKey::CompletedTransactions => return Err(TransactionStorageError::OperationNotSupported),
-                DbKey::PendingCoinbaseTransactions => return Err(TransactionStorageError::OperationNotSupported),
-            },
-        }
+            }
 
-        Ok(None)
+            Ok(None)
+        })
     }
 
     fn transaction_exists(&self, tx_id: u64) -> Result<bool, TransactionStorageError> {
-        let db = acquire_read_lock!(self.db);
-
-        Ok(db.pending_outbound_transactions.contains_key(&tx_id) ||
-            db.pending_inbound_transactions.contains_key(&tx_id) ||
-            db.pending_coinbase_transactions.contains_key(&tx_id) ||
-            db.completed_transactions.contains_key(&tx_id))
+        self.db.run(move |db| {
+            Ok(db.pending_outbound_transactions.contains_key(&tx_id) ||
+                db.pending_inbound_transactions.contains_key(&tx_id) ||
+                db.pending_coinbase_transactions.contains_key(&tx_id) ||
+                db.completed_transactions.contains_key(&tx_id))
+        })
     }
 
     fn complete_outbound_transaction(
@@ -232,20 +235,20 @@ impl TransactionBackend for TransactionMemoryDatabase {
         transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let mut db = acquire_write_lock!(self.db);
-
-        if db.completed_transactions.contains_key(&tx_id) {
-            return Err(TransactionStorageError::TransactionAlreadyExists);
-        }
+        self.db.run(move |db| {
+            if db.completed_transactions.contains_key(&tx_id) {
+                return Err(TransactionStorageError::TransactionAlreadyExists);
+            }
 
-        let _ = db
-            .pending_outbound_transactions
-            .remove(&tx_id)
-            .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::PendingOutboundTransaction(tx_id)))?;
+            let _ = db
+                .pending_outbound_transactions
+                .remove(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::PendingOutboundTransaction(tx_id)))?;
 
-        db.completed_transactions.insert(tx_id, transaction);
+            db.completed_transactions.insert(tx_id, transaction);
 
-        Ok(())
+            Ok(())
+        })
     }
 
     fn complete_inbound_transaction(
@@ -254,18 +257,18 @@ impl TransactionBackend for TransactionMemoryDatabase {
         transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let mut db = acquire_write_lock!(self.db);
-
-        if db.completed_transactions.contains_key(&tx_id) {
-            return Err(TransactionStorageError::TransactionAlreadyExists);
-        }
-        let _ = db
-            .pending_inbound_transactions
-            .remove(&tx_id)
-            .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::PendingInboundTransaction(tx_id)))?;
+        self.db.run(move |db| {
+            if db.completed_transactions.contains_key(&tx_id) {
+                return Err(TransactionStorageError::TransactionAlreadyExists);
+            }
+            let _ = db
+                .pending_inbound_transactions
+                .remove(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::PendingInboundTransaction(tx_id)))?;
 
-        db.completed_transactions.insert(tx_id, transaction);
-        Ok(())
+            db.completed_transactions.insert(tx_id, transaction);
+            Ok(())
+        })
     }
 
     fn complete_coinbase_transaction(
@@ -274,65 +277,114 @@ impl TransactionBackend for TransactionMemoryDatabase {
         completed_transaction: CompletedTransaction,
     ) -> Result<(), TransactionStorageError>
     {
-        let mut db = acquire_write_lock!(self.db);
-
-        if db.completed_transactions.contains_key(&tx_id) {
-            return Err(TransactionStorageError::TransactionAlreadyExists);
-        }
-        let _ = db
-            .pending_coinbase_transactions
-            .remove(&tx_id)
-            .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::PendingCoinbaseTransaction(tx_id)))?;
+        self.db.run(move |db| {
+            if db.completed_transactions.contains_key(&tx_id) {
+                return Err(TransactionStorageError::TransactionAlreadyExists);
+            }
+            let _ = db
+                .pending_coinbase_transactions
+                .remove(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::PendingCoinbaseTransaction(tx_id)))?;
 
-        db.completed_transactions.insert(tx_id, completed_transaction);
-        Ok(())
+            db.completed_transactions.insert(tx_id, completed_transaction);
+            Ok(())
+        })
     }
 
     fn broadcast_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-
-        let mut completed_tx = db
-            .completed_transactions
-            .get_mut(&tx_id)
-            .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(tx_id)))?;
-
-        if completed_tx.status == TransactionStatus::Completed {
-            completed_tx.status = TransactionStatus::Broadcast;
-        }
-
-        Ok(())
+        self.db.run(move |db| {
+            let mut completed_tx = db
+                .completed_transactions
+                .get_mut(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(tx_id)))?;
+
+            if completed_tx.status == TransactionStatus::Completed {
+                completed_tx.status = TransactionStatus::Broadcast;
+            }
+
+            Ok(())
+        })
     }
 
     fn mine_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let mut db = acquire_write_lock!(self.db);
-
-        let mut completed_tx = db
-            .completed_transactions
-            .get_mut(&tx_id)
-            .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(tx_id)))?;
+        self.db.run(move |db| {
+            let mut completed_tx = db
+                .completed_transactions
+                .get_mut(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(tx_id)))?;
+
+            if completed_tx.status == TransactionStatus::Cancelled {
+                return Err(TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(
+                    tx_id,
+                )));
+            }
+
+            completed_tx.status = TransactionStatus::Mined;
+
+            Ok(())
+        })
+    }
 
-        if completed_tx.status == TransactionStatus::Cancelled {
-            return Err(TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(
-                tx_id,
-            )));
-        }
+    fn unmine_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        self.db.run(move |db| {
+            let mut completed_tx = db
+                .completed_transactions
+                .get_mut(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(tx_id)))?;
 
-        completed_tx.status = TransactionStatus::Mined;
+            if completed_tx.status == TransactionStatus::Mined {
+                completed_tx.status = TransactionStatus::Broadcast;
+            }
 
-        Ok(())
+            Ok(())
+        })
     }
 
     fn cancel_completed_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
-        let mut db = acquire_write_lock!(self.db);
+        self.db.run(move |db| {
+            let mut completed_tx = db
+                .completed_transactions
+                .get_mut(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(tx_id)))?;
+
+            completed_tx.status = TransactionStatus::Cancelled;
+
+            Ok(())
+        })
+    }
 
-        let mut completed_tx = db
-            .completed_transactions
-            .get_mut(&tx_id)
-            .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(tx_id)))?;
+    fn update_pending_outbound_transaction(
+        &self,
+        tx_id: TxId,
+        outbound_transaction: OutboundTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        self.db.run(move |db| {
+            let outbound_tx = db
+                .pending_outbound_transactions
+                .get_mut(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::PendingOutboundTransaction(tx_id)))?;
+            *outbound_tx = outbound_transaction;
+
+            Ok(())
+        })
+    }
 
-        completed_tx.status = TransactionStatus::Cancelled;
+    fn update_pending_inbound_transaction(
+        &self,
+        tx_id: TxId,
+        inbound_transaction: InboundTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        self.db.run(move |db| {
+            let inbound_tx = db
+                .pending_inbound_transactions
+                .get_mut(&tx_id)
+                .ok_or_else(|| TransactionStorageError::ValueNotFound(DbKey::PendingInboundTransaction(tx_id)))?;
+            *inbound_tx = inbound_transaction;
 
-        Ok(())
+            Ok(())
+        })
     }
 
     #[cfg(feature = "test_harness")]
@@ -342,12 +394,12 @@ impl TransactionBackend for TransactionMemoryDatabase {
         timestamp: NaiveDateTime,
     ) -> Result<(), TransactionStorageError>
     {
-        let mut db = acquire_write_lock!(self.db);
-
-        if let Some(tx) = db.completed_transactions.get_mut(&tx_id) {
-            tx.timestamp = timestamp;
-        }
+        self.db.run(move |db| {
+            if let Some(tx) = db.completed_transactions.get_mut(&tx_id) {
+                tx.timestamp = timestamp;
+            }
 
-        Ok(())
+            Ok(())
+        })
     }
 }
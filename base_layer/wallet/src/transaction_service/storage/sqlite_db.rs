@@ -33,6 +33,8 @@ use crate::{
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            ReceiverProtocolStage,
+            SenderProtocolStage,
             TransactionBackend,
             TransactionStatus,
             WriteOperation,
@@ -397,6 +399,31 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         Ok(())
     }
 
+    fn unmine_completed_transaction(&self, tx_id: u64) -> Result<(), TransactionStorageError> {
+        let conn = acquire_lock!(self.database_connection);
+
+        match CompletedTransactionSql::find(tx_id, &(*conn)) {
+            Ok(v) => {
+                if TransactionStatus::try_from(v.status)? == TransactionStatus::Mined {
+                    let _ = v.update(
+                        UpdateCompletedTransaction {
+                            status: Some(TransactionStatus::Broadcast),
+                            timestamp: None,
+                        },
+                        &(*conn),
+                    )?;
+                }
+            },
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                return Err(TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(
+                    tx_id,
+                )))
+            },
+            Err(e) => return Err(e),
+        };
+        Ok(())
+    }
+
     fn cancel_completed_transaction(&self, tx_id: u64) -> Result<(), TransactionStorageError> {
         let conn = acquire_lock!(self.database_connection);
         match CompletedTransactionSql::find(tx_id, &(*conn)) {
@@ -413,6 +440,48 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         Ok(())
     }
 
+    fn update_pending_outbound_transaction(
+        &self,
+        tx_id: u64,
+        outbound_transaction: OutboundTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let conn = acquire_lock!(self.database_connection);
+        match OutboundTransactionSql::find(tx_id, &(*conn)) {
+            Ok(v) => {
+                v.update(UpdateOutboundTransactionSql::try_from(outbound_transaction)?, &(*conn))?;
+            },
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                return Err(TransactionStorageError::ValueNotFound(
+                    DbKey::PendingOutboundTransaction(tx_id),
+                ));
+            },
+            Err(e) => return Err(e),
+        };
+        Ok(())
+    }
+
+    fn update_pending_inbound_transaction(
+        &self,
+        tx_id: u64,
+        inbound_transaction: InboundTransaction,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let conn = acquire_lock!(self.database_connection);
+        match InboundTransactionSql::find(tx_id, &(*conn)) {
+            Ok(v) => {
+                v.update(UpdateInboundTransactionSql::try_from(inbound_transaction)?, &(*conn))?;
+            },
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                return Err(TransactionStorageError::ValueNotFound(
+                    DbKey::PendingInboundTransaction(tx_id),
+                ));
+            },
+            Err(e) => return Err(e),
+        };
+        Ok(())
+    }
+
     #[cfg(feature = "test_harness")]
     fn update_completed_transaction_timestamp(
         &self,
@@ -445,6 +514,8 @@ struct InboundTransactionSql {
     receiver_protocol: String,
     message: String,
     timestamp: NaiveDateTime,
+    protocol_stage: i32,
+    retry_count: i32,
 }
 
 impl InboundTransactionSql {
@@ -476,6 +547,26 @@ impl InboundTransactionSql {
 
         Ok(())
     }
+
+    pub fn update(
+        &self,
+        update: UpdateInboundTransactionSql,
+        conn: &SqliteConnection,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let num_updated =
+            diesel::update(inbound_transactions::table.filter(inbound_transactions::tx_id.eq(&self.tx_id)))
+                .set(update)
+                .execute(conn)?;
+
+        if num_updated == 0 {
+            return Err(TransactionStorageError::UnexpectedResult(
+                "Database update error".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl TryFrom<InboundTransaction> for InboundTransactionSql {
@@ -489,6 +580,8 @@ impl TryFrom<InboundTransaction> for InboundTransactionSql {
             receiver_protocol: serde_json::to_string(&i.receiver_protocol)?,
             message: i.message,
             timestamp: i.timestamp,
+            protocol_stage: i.stage as i32,
+            retry_count: i.retry_count as i32,
         })
     }
 }
@@ -503,9 +596,11 @@ impl TryFrom<InboundTransactionSql> for InboundTransaction {
                 .map_err(|_| TransactionStorageError::ConversionError)?,
             amount: MicroTari::from(i.amount as u64),
             receiver_protocol: serde_json::from_str(&i.receiver_protocol)?,
+            stage: ReceiverProtocolStage::try_from(i.protocol_stage)?,
             status: TransactionStatus::Pending,
             message: i.message,
             timestamp: i.timestamp,
+            retry_count: i.retry_count as u32,
         })
     }
 }
@@ -521,6 +616,8 @@ struct OutboundTransactionSql {
     sender_protocol: String,
     message: String,
     timestamp: NaiveDateTime,
+    protocol_stage: i32,
+    retry_count: i32,
 }
 
 impl OutboundTransactionSql {
@@ -552,6 +649,26 @@ impl OutboundTransactionSql {
 
         Ok(())
     }
+
+    pub fn update(
+        &self,
+        update: UpdateOutboundTransactionSql,
+        conn: &SqliteConnection,
+    ) -> Result<(), TransactionStorageError>
+    {
+        let num_updated =
+            diesel::update(outbound_transactions::table.filter(outbound_transactions::tx_id.eq(&self.tx_id)))
+                .set(update)
+                .execute(conn)?;
+
+        if num_updated == 0 {
+            return Err(TransactionStorageError::UnexpectedResult(
+                "Database update error".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl TryFrom<OutboundTransaction> for OutboundTransactionSql {
@@ -566,6 +683,8 @@ impl TryFrom<OutboundTransaction> for OutboundTransactionSql {
             sender_protocol: serde_json::to_string(&i.sender_protocol)?,
             message: i.message,
             timestamp: i.timestamp,
+            protocol_stage: i.stage as i32,
+            retry_count: i.retry_count as i32,
         })
     }
 }
@@ -581,9 +700,55 @@ impl TryFrom<OutboundTransactionSql> for OutboundTransaction {
             amount: MicroTari::from(i.amount as u64),
             fee: MicroTari::from(i.fee as u64),
             sender_protocol: serde_json::from_str(&i.sender_protocol)?,
+            stage: SenderProtocolStage::try_from(i.protocol_stage)?,
             status: TransactionStatus::Pending,
             message: i.message,
             timestamp: i.timestamp,
+            retry_count: i.retry_count as u32,
+        })
+    }
+}
+
+/// The fields of an Outbound Transaction that change as its protocol advances
+#[derive(AsChangeset)]
+#[table_name = "outbound_transactions"]
+pub struct UpdateOutboundTransactionSql {
+    sender_protocol: String,
+    protocol_stage: i32,
+    timestamp: NaiveDateTime,
+    retry_count: i32,
+}
+
+impl TryFrom<OutboundTransaction> for UpdateOutboundTransactionSql {
+    type Error = TransactionStorageError;
+
+    fn try_from(o: OutboundTransaction) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sender_protocol: serde_json::to_string(&o.sender_protocol)?,
+            protocol_stage: o.stage as i32,
+            timestamp: o.timestamp,
+            retry_count: o.retry_count as i32,
+        })
+    }
+}
+
+/// The fields of an Inbound Transaction that change as its protocol advances
+#[derive(AsChangeset)]
+#[table_name = "inbound_transactions"]
+pub struct UpdateInboundTransactionSql {
+    receiver_protocol: String,
+    protocol_stage: i32,
+    retry_count: i32,
+}
+
+impl TryFrom<InboundTransaction> for UpdateInboundTransactionSql {
+    type Error = TransactionStorageError;
+
+    fn try_from(i: InboundTransaction) -> Result<Self, Self::Error> {
+        Ok(Self {
+            receiver_protocol: serde_json::to_string(&i.receiver_protocol)?,
+            protocol_stage: i.stage as i32,
+            retry_count: i.retry_count as i32,
         })
     }
 }
@@ -814,6 +979,8 @@ mod test {
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            ReceiverProtocolStage,
+            SenderProtocolStage,
             TransactionStatus,
         },
         sqlite_db::{
@@ -881,9 +1048,11 @@ mod test {
             amount,
             fee: stp.clone().get_fee_amount().unwrap(),
             sender_protocol: stp.clone(),
+            stage: SenderProtocolStage::WaitingForReply,
             status: TransactionStatus::Pending,
             message: "Yo!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            retry_count: 0,
         };
 
         let outbound_tx2 = OutboundTransactionSql::try_from(OutboundTransaction {
@@ -892,10 +1061,12 @@ mod test {
             amount,
             fee: stp.clone().get_fee_amount().unwrap(),
             sender_protocol: stp.clone(),
+            stage: SenderProtocolStage::WaitingForReply,
             status: TransactionStatus::Pending,
 
             message: "Hey!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            retry_count: 0,
         })
         .unwrap();
 
@@ -929,18 +1100,22 @@ mod test {
             source_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
             amount,
             receiver_protocol: rtp.clone(),
+            stage: ReceiverProtocolStage::ReplyPending,
             status: TransactionStatus::Pending,
             message: "Yo!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            retry_count: 0,
         };
         let inbound_tx2 = InboundTransaction {
             tx_id: 3,
             source_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
             amount,
             receiver_protocol: rtp.clone(),
+            stage: ReceiverProtocolStage::ReplyPending,
             status: TransactionStatus::Pending,
             message: "Hey!".to_string(),
             timestamp: Utc::now().naive_utc(),
+            retry_count: 0,
         };
 
         InboundTransactionSql::try_from(inbound_tx1.clone())
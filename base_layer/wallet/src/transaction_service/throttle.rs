@@ -0,0 +1,97 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Per-sender throttling of inbound transactions. A peer that sends more transactions than the wallet is willing to
+//! accept within the throttle period has its requests dropped so that it cannot bloat the wallet's storage.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+use tari_comms::types::CommsPublicKey;
+
+pub struct InboundThrottle {
+    max_per_period: usize,
+    period: Duration,
+    senders: HashMap<CommsPublicKey, VecDeque<Instant>>,
+}
+
+impl InboundThrottle {
+    pub fn new(max_per_period: usize, period: Duration) -> Self {
+        Self {
+            max_per_period,
+            period,
+            senders: HashMap::new(),
+        }
+    }
+
+    /// Record an inbound transaction from `sender` at the current time. Returns false if the sender has already
+    /// reached its limit for the throttle period, in which case the transaction is not recorded and should be dropped.
+    pub fn check(&mut self, sender: &CommsPublicKey) -> bool {
+        self.check_at(sender, Instant::now())
+    }
+
+    fn check_at(&mut self, sender: &CommsPublicKey, now: Instant) -> bool {
+        let period = self.period;
+        // Forget senders that have not sent anything within the period so that the map does not grow unbounded
+        self.senders.retain(|_, received| {
+            while received.front().filter(|t| now.duration_since(**t) >= period).is_some() {
+                received.pop_front();
+            }
+            !received.is_empty()
+        });
+
+        let received = self.senders.entry(sender.clone()).or_insert_with(VecDeque::new);
+        if received.len() >= self.max_per_period {
+            return false;
+        }
+        received.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::PublicKey;
+
+    #[test]
+    fn throttles_per_sender() {
+        let (_, alice) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (_, bob) = CommsPublicKey::random_keypair(&mut OsRng);
+        let mut throttle = InboundThrottle::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert!(throttle.check_at(&alice, start));
+        assert!(throttle.check_at(&alice, start + Duration::from_secs(1)));
+        assert!(!throttle.check_at(&alice, start + Duration::from_secs(2)));
+        assert!(throttle.check_at(&bob, start + Duration::from_secs(2)));
+
+        // The first transaction from alice falls out of the window
+        assert!(throttle.check_at(&alice, start + Duration::from_secs(60)));
+        assert!(!throttle.check_at(&alice, start + Duration::from_secs(60)));
+
+        assert!(throttle.check_at(&alice, start + Duration::from_secs(200)));
+        assert_eq!(throttle.senders.len(), 1);
+    }
+}
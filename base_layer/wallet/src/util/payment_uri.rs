@@ -0,0 +1,264 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Payment URIs
+//! A payment URI is the canonical way for a wallet to share its receive address, e.g. as the payload of a QR code, so
+//! that all frontends can read each other's payment requests. It has the form
+//!
+//! ```text
+//! tari://<network>/<public key hex>?amount=<amount in µT>&memo=<percent encoded message>
+//! ```
+//!
+//! The `amount` and `memo` parameters are optional. Unknown parameters are ignored so that new parameters can be added
+//! without breaking older wallets. When parsing, the public key may also be given as an emoji id.
+//!
+//! # Example
+//!
+//! ```
+//! use tari_common::Network;
+//! use tari_wallet::util::payment_uri::PaymentUri;
+//!
+//! let key = "70350e09c474809209824c6e6888707b7dd09959aa227343b5106382b856f73a";
+//! let uri = format!("tari://rincewind/{}?amount=1000&memo=Hi%20there", key)
+//!     .parse::<PaymentUri>()
+//!     .unwrap();
+//! assert_eq!(uri.network, Network::Rincewind);
+//! assert_eq!(uri.amount, Some(1000.into()));
+//! assert_eq!(uri.memo.as_deref(), Some("Hi there"));
+//! ```
+
+use crate::util::emoji::EmojiId;
+use derive_error::Error;
+use std::{
+    fmt::{Display, Error as FmtError, Formatter},
+    str::FromStr,
+};
+use tari_common::Network;
+use tari_core::transactions::{tari_amount::MicroTari, types::PublicKey};
+use tari_crypto::tari_utilities::hex::Hex;
+
+pub const PAYMENT_URI_SCHEME: &str = "tari://";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PaymentUriError {
+    /// The URI does not start with `tari://`
+    InvalidScheme,
+    /// The network is not one of the known networks
+    #[error(non_std, no_from)]
+    InvalidNetwork(String),
+    /// The public key is neither a valid hex public key nor a valid emoji id
+    InvalidPublicKey,
+    /// The amount is not a whole number of µT
+    InvalidAmount,
+    /// A parameter is not correctly percent encoded UTF-8
+    InvalidEncoding,
+}
+
+/// A request to pay the holder of `public_key` on `network`, optionally for a specific amount and with a message
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentUri {
+    pub network: Network,
+    pub public_key: PublicKey,
+    pub amount: Option<MicroTari>,
+    pub memo: Option<String>,
+}
+
+impl PaymentUri {
+    pub fn new(network: Network, public_key: PublicKey) -> Self {
+        Self {
+            network,
+            public_key,
+            amount: None,
+            memo: None,
+        }
+    }
+
+    pub fn with_amount(mut self, amount: MicroTari) -> Self {
+        self.amount = Some(amount);
+        self
+    }
+
+    /// Sets the message that the payment should be sent with. An empty memo is treated as no memo.
+    pub fn with_memo<T: Into<String>>(mut self, memo: T) -> Self {
+        let memo = memo.into();
+        self.memo = if memo.is_empty() { None } else { Some(memo) };
+        self
+    }
+
+    /// Returns true if the string looks like a payment URI, so that it can be told apart from a public key or emoji id
+    pub fn is_payment_uri(s: &str) -> bool {
+        s.starts_with(PAYMENT_URI_SCHEME)
+    }
+}
+
+impl Display for PaymentUri {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        write!(f, "{}{}/{}", PAYMENT_URI_SCHEME, self.network, self.public_key.to_hex())?;
+        let mut separator = '?';
+        if let Some(amount) = self.amount {
+            write!(f, "{}amount={}", separator, u64::from(amount))?;
+            separator = '&';
+        }
+        if let Some(memo) = self.memo.as_ref() {
+            write!(f, "{}memo={}", separator, percent_encode(memo))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PaymentUri {
+    type Err = PaymentUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !PaymentUri::is_payment_uri(s) {
+            return Err(PaymentUriError::InvalidScheme);
+        }
+        let s = &s[PAYMENT_URI_SCHEME.len()..];
+        let (path, query) = match s.find('?') {
+            Some(i) => (&s[..i], Some(&s[i + 1..])),
+            None => (s, None),
+        };
+        let mut segments = path.splitn(2, '/');
+        let network = segments.next().unwrap_or_default();
+        let network = Network::from_str(network).map_err(|_| PaymentUriError::InvalidNetwork(network.to_string()))?;
+        let key = percent_decode(segments.next().unwrap_or_default().trim_end_matches('/'))?;
+        let public_key = PublicKey::from_hex(&key)
+            .or_else(|_| EmojiId::str_to_pubkey(&key))
+            .map_err(|_| PaymentUriError::InvalidPublicKey)?;
+
+        let mut uri = PaymentUri::new(network, public_key);
+        for param in query.unwrap_or_default().split('&').filter(|p| !p.is_empty()) {
+            let mut parts = param.splitn(2, '=');
+            let name = parts.next().unwrap_or_default();
+            let value = percent_decode(parts.next().unwrap_or_default())?;
+            match name {
+                "amount" => {
+                    let amount = value.parse::<u64>().map_err(|_| PaymentUriError::InvalidAmount)?;
+                    uri = uri.with_amount(amount.into());
+                },
+                "memo" => uri = uri.with_memo(value),
+                // Ignore parameters added by newer versions
+                _ => {},
+            }
+        }
+        Ok(uri)
+    }
+}
+
+// Percent encode everything except the unreserved characters of RFC 3986
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(b as char),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str) -> Result<String, PaymentUriError> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = s.get(i + 1..i + 3).ok_or(PaymentUriError::InvalidEncoding)?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| PaymentUriError::InvalidEncoding)?);
+                i += 3;
+            },
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            },
+            b => {
+                decoded.push(b);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| PaymentUriError::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::PublicKey as PublicKeyTrait;
+
+    #[test]
+    fn encode_and_parse() {
+        let (_, public_key) = PublicKey::random_keypair(&mut OsRng);
+        let uri = PaymentUri::new(Network::Rincewind, public_key.clone());
+        let encoded = uri.to_string();
+        assert_eq!(encoded, format!("tari://rincewind/{}", public_key.to_hex()));
+        assert_eq!(encoded.parse::<PaymentUri>().unwrap(), uri);
+
+        let uri = uri.with_amount(12_345.into()).with_memo("Invoice #42: 🍕 & 🍺");
+        let encoded = uri.to_string();
+        assert_eq!(
+            encoded,
+            format!(
+                "tari://rincewind/{}?amount=12345&memo=Invoice%20%2342%3A%20%F0%9F%8D%95%20%26%20%F0%9F%8D%BA",
+                public_key.to_hex()
+            )
+        );
+        assert_eq!(encoded.parse::<PaymentUri>().unwrap(), uri);
+
+        // Emoji ids, unknown parameters and parameters in any order are accepted
+        let emoji = EmojiId::from_pubkey(&public_key);
+        let parsed = format!("tari://localnet/{}?foo=bar&memo=Hi+there&amount=5", emoji.as_str())
+            .parse::<PaymentUri>()
+            .unwrap();
+        assert_eq!(parsed.network, Network::LocalNet);
+        assert_eq!(parsed.public_key, public_key);
+        assert_eq!(parsed.amount, Some(5.into()));
+        assert_eq!(parsed.memo.as_deref(), Some("Hi there"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let (_, public_key) = PublicKey::random_keypair(&mut OsRng);
+        let key = public_key.to_hex();
+        assert_eq!(
+            format!("bitcoin://rincewind/{}", key).parse::<PaymentUri>(),
+            Err(PaymentUriError::InvalidScheme)
+        );
+        assert_eq!(
+            format!("tari://moonnet/{}", key).parse::<PaymentUri>(),
+            Err(PaymentUriError::InvalidNetwork("moonnet".to_string()))
+        );
+        assert_eq!(
+            "tari://rincewind/abc".parse::<PaymentUri>(),
+            Err(PaymentUriError::InvalidPublicKey)
+        );
+        assert_eq!(
+            format!("tari://rincewind/{}?amount=1.5", key).parse::<PaymentUri>(),
+            Err(PaymentUriError::InvalidAmount)
+        );
+        assert_eq!(
+            format!("tari://rincewind/{}?memo=%F0%9F", key).parse::<PaymentUri>(),
+            Err(PaymentUriError::InvalidEncoding)
+        );
+    }
+}
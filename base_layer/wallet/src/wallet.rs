@@ -21,8 +21,11 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    backup::{WalletBackupConfig, WalletBackupEvent, WalletBackupScheduler},
+    base_node_failover::{BaseNodeFailover, BaseNodePeers},
     contacts_service::{handle::ContactsServiceHandle, storage::database::ContactsBackend, ContactsServiceInitializer},
     error::WalletError,
+    localization::{LocaleConfig, MessageCatalogue},
     output_manager_service::{
         config::OutputManagerServiceConfig,
         handle::OutputManagerHandle,
@@ -40,11 +43,18 @@ use crate::{
 };
 use blake2::Digest;
 use log::*;
-use std::{marker::PhantomData, sync::Arc, time::Duration};
+use rand::rngs::OsRng;
+use std::{
+    marker::PhantomData,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tari_broadcast_channel::{bounded, Subscriber};
 use tari_comms::{
     multiaddr::Multiaddr,
     peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
     types::CommsPublicKey,
+    utils::signature,
     CommsNode,
 };
 use tari_comms_dht::Dht;
@@ -77,6 +87,8 @@ pub struct WalletConfig {
     pub comms_config: CommsConfig,
     pub factories: CryptoFactories,
     pub transaction_service_config: Option<TransactionServiceConfig>,
+    pub output_manager_service_config: Option<OutputManagerServiceConfig>,
+    pub locale_config: Option<LocaleConfig>,
 }
 
 /// A structure containing the config and services that a Wallet application will require. This struct will start up all
@@ -94,9 +106,12 @@ where
     pub output_manager_service: OutputManagerHandle,
     pub transaction_service: TransactionServiceHandle,
     pub contacts_service: ContactsServiceHandle,
+    pub base_node_peers: BaseNodePeers,
     pub db: WalletDatabase<T>,
     pub runtime: Runtime,
     pub factories: CryptoFactories,
+    /// The catalogue of user-facing messages in the configured locale
+    pub message_catalogue: MessageCatalogue,
     #[cfg(feature = "test_harness")]
     pub transaction_backend: U,
     _u: PhantomData<U>,
@@ -127,6 +142,19 @@ where
         let transaction_backend_handle = transaction_backend.clone();
 
         let factories = config.factories;
+        // An unavailable locale should not stop the wallet from starting, so fall back to the built in messages
+        let message_catalogue = match config.locale_config {
+            Some(locale_config) => MessageCatalogue::load(&locale_config).unwrap_or_else(|err| {
+                warn!(
+                    target: LOG_TARGET,
+                    "Using the default locale because the '{}' locale could not be loaded: {:?}",
+                    locale_config.locale,
+                    err
+                );
+                MessageCatalogue::default()
+            }),
+            None => MessageCatalogue::default(),
+        };
         let (publisher, subscription_factory) = pubsub_connector(
             runtime.handle().clone(),
             config.comms_config.max_concurrent_inbound_tasks,
@@ -141,14 +169,13 @@ where
                 LivenessConfig {
                     auto_ping_interval: Some(Duration::from_secs(30)),
                     enable_auto_join: true,
-                    enable_auto_stored_message_request: true,
                     refresh_neighbours_interval: Default::default(),
                 },
                 Arc::clone(&subscription_factory),
                 dht.dht_requester(),
             ))
             .add_initializer(OutputManagerServiceInitializer::new(
-                OutputManagerServiceConfig::default(),
+                config.output_manager_service_config.unwrap_or_default(),
                 subscription_factory.clone(),
                 output_manager_backend,
                 factories.clone(),
@@ -159,12 +186,16 @@ where
                 comms.subscribe_messaging_events(),
                 transaction_backend,
                 comms.node_identity(),
+                comms.connectivity(),
                 factories.clone(),
             ))
             .add_initializer(ContactsServiceInitializer::new(contacts_backend))
             .finish();
 
         let handles = runtime.block_on(fut).expect("Service initialization failed");
+        runtime
+            .block_on(handles.readiness().wait_all_ready())
+            .expect("Wallet services failed to start");
 
         let mut output_manager_handle = handles
             .get_handle::<OutputManagerHandle>()
@@ -179,10 +210,22 @@ where
             .get_handle::<ContactsServiceHandle>()
             .expect("Could not get Contacts Service Handle");
 
-        for p in base_node_peers {
+        if let Some(p) = base_node_peers.first() {
             runtime.block_on(transaction_service_handle.set_base_node_public_key(p.public_key.clone()))?;
             runtime.block_on(output_manager_handle.set_base_node_public_key(p.public_key.clone()))?;
         }
+        let base_node_peers = Arc::new(RwLock::new(
+            base_node_peers.into_iter().map(|p| p.public_key).collect::<Vec<_>>(),
+        ));
+
+        let base_node_failover = BaseNodeFailover::new(
+            base_node_peers.clone(),
+            comms.connectivity(),
+            transaction_service_handle.clone(),
+            output_manager_handle.clone(),
+            comms.shutdown_signal(),
+        );
+        runtime.spawn(base_node_failover.run());
 
         Ok(Wallet {
             comms,
@@ -191,9 +234,11 @@ where
             output_manager_service: output_manager_handle,
             transaction_service: transaction_service_handle,
             contacts_service: contacts_handle,
+            base_node_peers,
             db,
             runtime,
             factories,
+            message_catalogue,
             #[cfg(feature = "test_harness")]
             transaction_backend: transaction_backend_handle,
             _u: PhantomData,
@@ -208,6 +253,29 @@ where
         self.runtime.block_on(self.comms.shutdown());
     }
 
+    /// Start making automatic backups of the wallet as configured. An event is published on the returned stream for
+    /// every scheduled backup.
+    pub fn start_backup_scheduler(&mut self, config: WalletBackupConfig) -> Subscriber<WalletBackupEvent> {
+        let (publisher, subscriber) = bounded(100);
+        let scheduler = WalletBackupScheduler::new(
+            config,
+            self.output_manager_service.clone(),
+            self.transaction_service.clone(),
+            self.contacts_service.clone(),
+            publisher,
+            self.comms.shutdown_signal(),
+        );
+        self.runtime.spawn(scheduler.run());
+        subscriber
+    }
+
+    /// Change the locale of the wallet's user-facing messages. The current locale is kept if the catalogue of the new
+    /// locale cannot be loaded.
+    pub fn set_locale(&mut self, config: &LocaleConfig) -> Result<(), WalletError> {
+        self.message_catalogue = MessageCatalogue::load(config)?;
+        Ok(())
+    }
+
     /// This function will set the base_node that the wallet uses to broadcast transactions and monitor the blockchain
     /// state
     pub fn set_base_node_peer(&mut self, public_key: CommsPublicKey, net_address: String) -> Result<(), WalletError> {
@@ -230,12 +298,47 @@ where
 
         self.runtime
             .block_on(self.comms.peer_manager().add_peer(peer.clone()))?;
-        self.runtime.block_on(
-            self.transaction_service
-                .set_base_node_public_key(peer.public_key.clone()),
-        )?;
+        *acquire_write_lock!(self.base_node_peers) = vec![peer.public_key.clone()];
+        self.set_current_base_node(peer.public_key)
+    }
+
+    /// Add a base node that the wallet will fail over to if connectivity to the current base node is lost. If no base
+    /// node has been set yet, the given base node becomes the current base node.
+    pub fn add_base_node_peer(&mut self, public_key: CommsPublicKey, net_address: String) -> Result<(), WalletError> {
+        let address = net_address.parse::<Multiaddr>()?;
+        let peer = Peer::new(
+            public_key.clone(),
+            NodeId::from_key(&public_key).unwrap(),
+            vec![address].into(),
+            PeerFlags::empty(),
+            PeerFeatures::COMMUNICATION_NODE,
+            &[],
+        );
+
+        self.runtime.block_on(self.db.save_peer(peer.clone()))?;
+        self.runtime
+            .block_on(self.comms.peer_manager().add_peer(peer.clone()))?;
+
+        let is_first = {
+            let mut base_node_peers = acquire_write_lock!(self.base_node_peers);
+            if base_node_peers.contains(&public_key) {
+                return Ok(());
+            }
+            base_node_peers.push(public_key.clone());
+            base_node_peers.len() == 1
+        };
+        if is_first {
+            self.set_current_base_node(public_key)?;
+        }
+
+        Ok(())
+    }
+
+    fn set_current_base_node(&mut self, public_key: CommsPublicKey) -> Result<(), WalletError> {
         self.runtime
-            .block_on(self.output_manager_service.set_base_node_public_key(peer.public_key))?;
+            .block_on(self.transaction_service.set_base_node_public_key(public_key.clone()))?;
+        self.runtime
+            .block_on(self.output_manager_service.set_base_node_public_key(public_key))?;
 
         Ok(())
     }
@@ -285,6 +388,17 @@ where
         RistrettoSchnorr::sign(secret, nonce, challenge.clone().as_slice())
     }
 
+    /// Sign a message with the wallet's identity key to prove ownership of it out-of-band. The signature can be checked
+    /// with `tari_comms::utils::signature::verify_message`.
+    pub fn sign_message_with_identity(
+        &self,
+        message: &str,
+    ) -> Result<SchnorrSignature<RistrettoPublicKey, RistrettoSecretKey>, SchnorrSignatureError>
+    {
+        let secret_key = self.comms.node_identity().secret_key().clone();
+        signature::sign_message(&mut OsRng, secret_key, message)
+    }
+
     pub fn verify_message_signature(
         &mut self,
         public_key: RistrettoPublicKey,
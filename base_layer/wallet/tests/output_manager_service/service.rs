@@ -49,13 +49,13 @@ use tari_core::{
         tari_amount::{uT, MicroTari},
         transaction::{KernelFeatures, OutputFeatures, Transaction, TransactionOutput, UnblindedOutput},
         transaction_protocol::single_receiver::SingleReceiverTransactionProtocol,
-        types::{CryptoFactories, PrivateKey, RangeProof},
+        types::{CryptoFactories, PrivateKey, PublicKey, RangeProof},
         SenderTransactionProtocol,
     },
 };
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
-    keys::SecretKey,
+    keys::{PublicKey as PublicKeyTrait, SecretKey},
     range_proof::RangeProofService,
     tari_utilities::ByteArray,
 };
@@ -102,6 +102,7 @@ pub fn setup_output_manager_service<T: OutputManagerBackend + 'static>(
         .block_on(OutputManagerService::new(
             OutputManagerServiceConfig {
                 base_node_query_timeout: Duration::from_secs(3),
+                ..Default::default()
             },
             outbound_message_requester.clone(),
             oms_request_receiver,
@@ -341,6 +342,147 @@ fn send_no_change_sqlite_db() {
     send_no_change(OutputManagerSqliteDatabase::new(connection));
 }
 
+fn coin_split<T: OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let value = 10_000;
+    runtime
+        .block_on(oms.add_output(UnblindedOutput::new(
+            MicroTari::from(value),
+            PrivateKey::random(&mut OsRng),
+            None,
+        )))
+        .unwrap();
+
+    let fee_per_gram = MicroTari::from(20);
+    let (tx_id, tx, fee, amount) = runtime
+        .block_on(oms.create_coin_split(MicroTari::from(1000), 3, fee_per_gram, None))
+        .unwrap();
+
+    assert_eq!(amount, MicroTari::from(3000));
+    assert_eq!(fee, Fee::calculate(fee_per_gram, 1, 4));
+    assert_eq!(tx.body.inputs().len(), 1);
+    // Three split outputs and the change
+    assert_eq!(tx.body.outputs().len(), 4);
+    tx.validate_internal_consistency(&factories, None).unwrap();
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(0));
+    assert_eq!(balance.pending_outgoing_balance, MicroTari::from(value));
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(value) - fee);
+
+    runtime
+        .block_on(oms.confirm_transaction(tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 0);
+    assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 4);
+    assert_eq!(
+        runtime.block_on(oms.get_balance()).unwrap().available_balance,
+        MicroTari::from(value) - fee
+    );
+
+    match runtime.block_on(oms.create_coin_split(MicroTari::from(10_000), 2, fee_per_gram, None)) {
+        Err(OutputManagerError::NotEnoughFunds) => {},
+        _ => panic!("Expected NotEnoughFunds"),
+    }
+}
+
+#[test]
+fn coin_split_memory_db() {
+    coin_split(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn coin_split_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    coin_split(OutputManagerSqliteDatabase::new(connection));
+}
+
+fn rotate_master_key_and_migrate_outputs<T: OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
+
+    for value in &[10_000, 5_000, 20] {
+        runtime
+            .block_on(oms.add_output(UnblindedOutput::new(
+                MicroTari::from(*value),
+                PrivateKey::random(&mut OsRng),
+                None,
+            )))
+            .unwrap();
+    }
+
+    match runtime.block_on(oms.migrate_outputs()) {
+        Err(OutputManagerError::NoOutputMigrationPlan) => {},
+        _ => panic!("Expected NoOutputMigrationPlan"),
+    }
+
+    let seed_words = runtime.block_on(oms.get_seed_words()).unwrap();
+    let fee_per_gram = MicroTari::from(20);
+    let plan = runtime.block_on(oms.rotate_master_key(fee_per_gram, 2)).unwrap();
+    assert_eq!(plan.outputs_to_migrate.len(), 3);
+    assert_ne!(runtime.block_on(oms.get_seed_words()).unwrap(), seed_words);
+
+    // The two largest outputs are migrated first
+    let (tx_id, tx, fee, amount) = runtime.block_on(oms.migrate_outputs()).unwrap().unwrap();
+    assert_eq!(fee, Fee::calculate_with_minimum(fee_per_gram, 2, 1));
+    assert_eq!(amount, MicroTari::from(15_000) - fee);
+    assert_eq!(tx.body.inputs().len(), 2);
+    assert_eq!(tx.body.outputs().len(), 1);
+    tx.validate_internal_consistency(&factories, None).unwrap();
+
+    match runtime.block_on(oms.migrate_outputs()) {
+        Err(OutputManagerError::MigrationBatchPending) => {},
+        _ => panic!("Expected MigrationBatchPending"),
+    }
+    match runtime.block_on(oms.rotate_master_key(fee_per_gram, 2)) {
+        Err(OutputManagerError::KeyRotationInProgress) => {},
+        _ => panic!("Expected KeyRotationInProgress"),
+    }
+
+    runtime
+        .block_on(oms.confirm_transaction(tx_id, tx.body.inputs().clone(), tx.body.outputs().clone()))
+        .unwrap();
+
+    // The remaining output is worth less than the fee to spend it and is left behind
+    let plan = runtime.block_on(oms.get_output_migration_plan()).unwrap().unwrap();
+    assert!(plan.is_complete());
+    assert_eq!(plan.outputs_migrated, 2);
+    assert_eq!(plan.amount_migrated, MicroTari::from(15_000));
+    assert_eq!(plan.outputs_skipped, 1);
+    assert_eq!(plan.migration_tx_ids, vec![tx_id]);
+    assert!(runtime.block_on(oms.migrate_outputs()).unwrap().is_none());
+}
+
+#[test]
+fn rotate_master_key_and_migrate_outputs_memory_db() {
+    rotate_master_key_and_migrate_outputs(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn rotate_master_key_and_migrate_outputs_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    rotate_master_key_and_migrate_outputs(OutputManagerSqliteDatabase::new(connection));
+}
+
 fn send_not_enough_for_change<T: OutputManagerBackend + 'static>(backend: T) {
     let mut runtime = Runtime::new().unwrap();
 
@@ -386,6 +528,55 @@ fn send_not_enough_for_change_sqlite_db() {
     send_not_enough_for_change(OutputManagerSqliteDatabase::new(connection));
 }
 
+fn send_dust<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let fee_per_gram = MicroTari::from(20);
+    let amount = MicroTari::from(1000);
+    // Leave 50 µT of change after paying for the change output, which is below the default dust threshold
+    let value = amount + Fee::calculate(fee_per_gram, 1, 2) + MicroTari::from(50);
+    runtime
+        .block_on(oms.add_output(UnblindedOutput::new(value, PrivateKey::random(&mut OsRng), None)))
+        .unwrap();
+
+    match runtime.block_on(oms.prepare_transaction_to_send(MicroTari::from(50), fee_per_gram, None, "".to_string())) {
+        Err(OutputManagerError::AmountBelowDustThreshold) => assert!(true),
+        _ => assert!(false),
+    }
+    match runtime.block_on(oms.create_coin_split(MicroTari::from(50), 2, fee_per_gram, None)) {
+        Err(OutputManagerError::AmountBelowDustThreshold) => assert!(true),
+        _ => assert!(false),
+    }
+
+    let stp = runtime
+        .block_on(oms.prepare_transaction_to_send(amount, fee_per_gram, None, "".to_string()))
+        .unwrap();
+    assert_eq!(stp.get_change_amount().unwrap(), MicroTari::from(0));
+    assert_eq!(stp.get_fee_amount().unwrap(), value - amount);
+
+    let balance = runtime.block_on(oms.get_balance()).unwrap();
+    assert_eq!(balance.pending_outgoing_balance, value);
+    assert_eq!(balance.pending_incoming_balance, MicroTari::from(0));
+}
+
+#[test]
+fn send_dust_memory_db() {
+    send_dust(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn send_dust_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    send_dust(OutputManagerSqliteDatabase::new(connection));
+}
+
 fn receiving_and_confirmation<T: OutputManagerBackend + 'static>(backend: T) {
     let factories = CryptoFactories::default();
 
@@ -394,7 +585,7 @@ fn receiving_and_confirmation<T: OutputManagerBackend + 'static>(backend: T) {
     let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
 
     let value = MicroTari::from(5000);
-    let recv_key = runtime.block_on(oms.get_recipient_spending_key(1, value)).unwrap();
+    let (recv_key, _) = runtime.block_on(oms.get_recipient_spending_key(1, value)).unwrap();
     assert_eq!(runtime.block_on(oms.get_unspent_outputs()).unwrap().len(), 0);
     assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 1);
 
@@ -430,6 +621,103 @@ fn receiving_and_confirmation_sqlite_db() {
     receiving_and_confirmation(OutputManagerSqliteDatabase::new(connection));
 }
 
+fn invoices_and_receive_only_mode<T: OutputManagerBackend + 'static>(backend: T) {
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
+
+    let invoice_pk = runtime.block_on(oms.create_invoice("INV-1".to_string())).unwrap();
+    let other_pk = runtime.block_on(oms.create_invoice("INV-2".to_string())).unwrap();
+    assert_ne!(invoice_pk, other_pk);
+    match runtime.block_on(oms.create_invoice("INV-1".to_string())) {
+        Err(OutputManagerError::DuplicateInvoice) => {},
+        _ => panic!("Expected DuplicateInvoice"),
+    }
+
+    let value = MicroTari::from(5000);
+    let (recv_key, _) = runtime
+        .block_on(oms.get_invoice_recipient_spending_key("INV-1".to_string(), 1, value))
+        .unwrap();
+    assert_eq!(PublicKey::from_secret_key(&recv_key), invoice_pk);
+    assert_eq!(runtime.block_on(oms.get_pending_transactions()).unwrap().len(), 1);
+    match runtime.block_on(oms.get_invoice_recipient_spending_key("INV-1".to_string(), 2, value)) {
+        Err(OutputManagerError::InvoiceAlreadyPaid) => {},
+        _ => panic!("Expected InvoiceAlreadyPaid"),
+    }
+    match runtime.block_on(oms.get_invoice_recipient_spending_key("INV-3".to_string(), 2, value)) {
+        Err(OutputManagerError::InvoiceNotFound) => {},
+        _ => panic!("Expected InvoiceNotFound"),
+    }
+
+    let invoices = runtime.block_on(oms.get_invoices()).unwrap();
+    assert_eq!(invoices.len(), 2);
+    let paid = invoices.iter().find(|i| i.invoice_id == "INV-1").unwrap();
+    assert_eq!(paid.tx_id, Some(1));
+    assert_eq!(paid.amount, Some(value));
+    assert!(!invoices.iter().find(|i| i.invoice_id == "INV-2").unwrap().is_paid());
+
+    runtime
+        .block_on(oms.add_output(UnblindedOutput::new(
+            MicroTari::from(10_000),
+            PrivateKey::random(&mut OsRng),
+            None,
+        )))
+        .unwrap();
+    runtime
+        .block_on(oms.enable_receive_only_mode("till passphrase".to_string()))
+        .unwrap();
+    match runtime.block_on(oms.prepare_transaction_to_send(
+        MicroTari::from(1000),
+        MicroTari::from(20),
+        None,
+        "".to_string(),
+    )) {
+        Err(OutputManagerError::SpendingLocked) => {},
+        _ => panic!("Expected SpendingLocked"),
+    }
+    match runtime.block_on(oms.unlock_spending("wrong passphrase".to_string())) {
+        Err(OutputManagerError::InvalidSpendPassphrase) => {},
+        _ => panic!("Expected InvalidSpendPassphrase"),
+    }
+
+    runtime
+        .block_on(oms.unlock_spending("till passphrase".to_string()))
+        .unwrap();
+    runtime
+        .block_on(oms.create_coin_split(MicroTari::from(1000), 2, MicroTari::from(20), None))
+        .unwrap();
+
+    runtime.block_on(oms.lock_spending()).unwrap();
+    match runtime.block_on(oms.create_coin_split(MicroTari::from(1000), 2, MicroTari::from(20), None)) {
+        Err(OutputManagerError::SpendingLocked) => {},
+        _ => panic!("Expected SpendingLocked"),
+    }
+
+    runtime
+        .block_on(oms.disable_receive_only_mode("till passphrase".to_string()))
+        .unwrap();
+    match runtime.block_on(oms.unlock_spending("till passphrase".to_string())) {
+        Err(OutputManagerError::ReceiveOnlyModeNotEnabled) => {},
+        _ => panic!("Expected ReceiveOnlyModeNotEnabled"),
+    }
+}
+
+#[test]
+fn invoices_and_receive_only_mode_memory_db() {
+    invoices_and_receive_only_mode(OutputManagerMemoryDatabase::new());
+}
+
+#[test]
+fn invoices_and_receive_only_mode_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let db_tempdir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+    let db_path = format!("{}/{}", db_folder, db_name);
+    let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+    invoices_and_receive_only_mode(OutputManagerSqliteDatabase::new(connection));
+}
+
 fn cancel_transaction<T: OutputManagerBackend + 'static>(backend: T) {
     let factories = CryptoFactories::default();
 
@@ -563,7 +851,7 @@ fn test_get_balance<T: OutputManagerBackend + 'static>(backend: T) {
     let change_val = stp.get_change_amount().unwrap();
 
     let recv_value = MicroTari::from(1500);
-    let _recv_key = runtime.block_on(oms.get_recipient_spending_key(1, recv_value)).unwrap();
+    let (_recv_key, _) = runtime.block_on(oms.get_recipient_spending_key(1, recv_value)).unwrap();
 
     let balance = runtime.block_on(oms.get_balance()).unwrap();
 
@@ -596,7 +884,7 @@ fn test_confirming_received_output<T: OutputManagerBackend + 'static>(backend: T
     let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend);
 
     let value = MicroTari::from(5000);
-    let recv_key = runtime.block_on(oms.get_recipient_spending_key(1, value)).unwrap();
+    let (recv_key, _) = runtime.block_on(oms.get_recipient_spending_key(1, value)).unwrap();
     let commitment = factories.commitment.commit(&recv_key, &value.into());
 
     let rr = factories.range_proof.construct_proof(&recv_key, value.into()).unwrap();
@@ -805,6 +1093,145 @@ fn test_startup_utxo_scan() {
     assert_eq!(invalid_txs.len(), 3);
 }
 
+#[test]
+fn test_import_outputs() {
+    let factories = CryptoFactories::default();
+
+    let mut runtime = Runtime::new().unwrap();
+
+    let (mut oms, outbound_service, _shutdown, mut base_node_response_sender) =
+        setup_output_manager_service(&mut runtime, OutputManagerMemoryDatabase::new());
+    let output1 = UnblindedOutput::new(MicroTari::from(500), PrivateKey::random(&mut OsRng), None);
+    runtime.block_on(oms.add_output(output1.clone())).unwrap();
+
+    let result = runtime.block_on(oms.import_outputs(vec![output1.clone()])).unwrap();
+    assert!(result.imported.is_empty());
+    assert_eq!(result.duplicates, 1);
+    assert_eq!(result.validation_request_key, None);
+
+    let base_node_identity = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/58217".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .unwrap();
+    runtime
+        .block_on(oms.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+    let _ = outbound_service.pop_call().unwrap();
+
+    let output2 = UnblindedOutput::new(MicroTari::from(800), PrivateKey::random(&mut OsRng), None);
+    let output3 = UnblindedOutput::new(MicroTari::from(900), PrivateKey::random(&mut OsRng), None);
+    let result = runtime
+        .block_on(oms.import_outputs(vec![output1.clone(), output2.clone(), output3.clone(), output2.clone()]))
+        .unwrap();
+    assert_eq!(result.imported, vec![output2.clone(), output3.clone()]);
+    assert_eq!(result.imported_value(), MicroTari::from(1700));
+    assert_eq!(result.duplicates, 2);
+    assert_eq!(result.rejected, 0);
+
+    let call = outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(&mut call.1.as_slice()).unwrap();
+    let bn_request: BaseNodeProto::BaseNodeServiceRequest = envelope_body
+        .decode_part::<BaseNodeProto::BaseNodeServiceRequest>(1)
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.validation_request_key, Some(bn_request.request_key));
+
+    let unspent_outputs = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs.len(), 3);
+
+    // Only output2 exists on the chain, so output3 should be invalidated
+    let base_node_response = BaseNodeProto::BaseNodeServiceResponse {
+        request_key: bn_request.request_key,
+        response: Some(BaseNodeResponseProto::TransactionOutputs(
+            BaseNodeProto::TransactionOutputs {
+                outputs: vec![output2.clone().as_transaction_output(&factories).unwrap().into()].into(),
+            },
+        )),
+    };
+    runtime
+        .block_on(base_node_response_sender.send(create_dummy_message(
+            base_node_response,
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+
+    let result_stream = runtime.block_on(async {
+        collect_stream!(
+            oms.get_event_stream_fused().map(|i| (*i).clone()),
+            take = 1,
+            timeout = Duration::from_secs(60)
+        )
+    });
+    assert!(result_stream.iter().any(|item| {
+        if let OutputManagerEvent::ReceiveBaseNodeResponse(_) = item {
+            true
+        } else {
+            false
+        }
+    }));
+
+    let invalid_outputs = runtime.block_on(oms.get_invalid_outputs()).unwrap();
+    assert_eq!(invalid_outputs, vec![output3]);
+    let unspent_outputs = runtime.block_on(oms.get_unspent_outputs()).unwrap();
+    assert_eq!(unspent_outputs.len(), 2);
+}
+
+#[test]
+fn test_recover_outputs() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let backend = OutputManagerMemoryDatabase::new();
+    let (mut oms, _, _shutdown, _) = setup_output_manager_service(&mut runtime, backend.clone());
+    runtime
+        .block_on(oms.add_output(UnblindedOutput::new(
+            MicroTari::from(10_000),
+            PrivateKey::random(&mut OsRng),
+            None,
+        )))
+        .unwrap();
+    let (_, tx, fee, _) = runtime
+        .block_on(oms.create_coin_split(MicroTari::from(1000), 3, MicroTari::from(20), None))
+        .unwrap();
+
+    // Restore a second wallet with the same master key, as if from the seed words
+    let mut key_manager_state = match backend.fetch(&DbKey::KeyManagerState).unwrap().unwrap() {
+        DbValue::KeyManagerState(km) => km,
+        _ => panic!("No Key Manager set"),
+    };
+    assert_eq!(key_manager_state.primary_key_index, 4);
+    key_manager_state.primary_key_index = 0;
+    let restored_backend = OutputManagerMemoryDatabase::new();
+    runtime
+        .block_on(OutputManagerDatabase::new(restored_backend.clone()).set_key_manager_state(key_manager_state))
+        .unwrap();
+    let (mut restored_oms, _, _shutdown2, _) = setup_output_manager_service(&mut runtime, restored_backend.clone());
+
+    // An output that was not created by the wallet is ignored
+    let mut outputs = tx.body.outputs().clone();
+    let foreign_output = UnblindedOutput::new(MicroTari::from(2000), PrivateKey::random(&mut OsRng), None);
+    outputs.push(foreign_output.as_transaction_output(&factories).unwrap());
+    let result = runtime.block_on(restored_oms.recover_outputs(outputs.clone())).unwrap();
+    assert_eq!(result.imported.len(), 4);
+    assert_eq!(result.imported_value(), MicroTari::from(10_000) - fee);
+    assert_eq!(
+        runtime.block_on(restored_oms.get_balance()).unwrap().available_balance,
+        MicroTari::from(10_000) - fee
+    );
+
+    // The key manager moves past the recovered keys
+    match restored_backend.fetch(&DbKey::KeyManagerState).unwrap().unwrap() {
+        DbValue::KeyManagerState(km) => assert_eq!(km.primary_key_index, 4),
+        _ => panic!("No Key Manager set"),
+    }
+
+    let result = runtime.block_on(restored_oms.recover_outputs(outputs)).unwrap();
+    assert!(result.imported.is_empty());
+    assert_eq!(result.duplicates, 4);
+}
+
 fn sending_transaction_with_short_term_clear<T: Clone + OutputManagerBackend + 'static>(backend: T) {
     let factories = CryptoFactories::default();
     let mut runtime = Runtime::new().unwrap();
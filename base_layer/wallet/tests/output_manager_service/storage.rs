@@ -23,18 +23,29 @@
 use crate::support::utils::{make_input, random_string};
 use chrono::{Duration as ChronoDuration, Utc};
 use rand::{rngs::OsRng, RngCore};
-use std::time::Duration;
+use std::{collections::HashSet, thread, time::Duration};
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::OutputFeatures,
     types::{CryptoFactories, PrivateKey},
 };
-use tari_crypto::keys::SecretKey;
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey, tari_utilities::ByteArray};
+use tari_secret::Secret;
 use tari_wallet::{
     output_manager_service::{
         service::Balance,
         storage::{
-            database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase, PendingTransactionOutputs},
+            database::{
+                BalanceAuditEvent,
+                DbKey,
+                DbKeyValuePair,
+                DbValue,
+                KeyManagerState,
+                OutputManagerBackend,
+                OutputManagerDatabase,
+                PendingTransactionOutputs,
+                WriteOperation,
+            },
             memory_db::OutputManagerMemoryDatabase,
             sqlite_db::OutputManagerSqliteDatabase,
         },
@@ -176,7 +187,7 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
     let outputs_to_encumber = vec![outputs[0].clone(), outputs[1].clone()];
     let total_encumbered = outputs[0].clone().value + outputs[1].clone().value;
     runtime
-        .block_on(db.encumber_outputs(2, outputs_to_encumber, Some(uo_change.clone())))
+        .block_on(db.encumber_outputs(2, outputs_to_encumber, vec![uo_change.clone()]))
         .unwrap();
     runtime.block_on(db.confirm_encumbered_outputs(2)).unwrap();
 
@@ -200,7 +211,7 @@ pub fn test_db_backend<T: OutputManagerBackend + 'static>(backend: T) {
         .block_on(db.accept_incoming_pending_transaction(
             5,
             uo_incoming.value,
-            uo_incoming.spending_key.clone(),
+            uo_incoming.spending_key.reveal().clone(),
             OutputFeatures::default(),
         ))
         .unwrap();
@@ -308,7 +319,7 @@ pub fn test_key_manager_crud<T: OutputManagerBackend + 'static>(backend: T) {
     assert!(runtime.block_on(db.increment_key_index()).is_err());
 
     let state1 = KeyManagerState {
-        master_seed: PrivateKey::random(&mut OsRng),
+        master_seed: Secret::new(PrivateKey::random(&mut OsRng)),
         branch_seed: "blah".to_string(),
         primary_key_index: 0,
     };
@@ -319,7 +330,7 @@ pub fn test_key_manager_crud<T: OutputManagerBackend + 'static>(backend: T) {
     assert_eq!(state1, read_state1);
 
     let state2 = KeyManagerState {
-        master_seed: PrivateKey::random(&mut OsRng),
+        master_seed: Secret::new(PrivateKey::random(&mut OsRng)),
         branch_seed: "blah2".to_string(),
         primary_key_index: 0,
     };
@@ -329,8 +340,8 @@ pub fn test_key_manager_crud<T: OutputManagerBackend + 'static>(backend: T) {
     let read_state2 = runtime.block_on(db.get_key_manager_state()).unwrap().unwrap();
     assert_eq!(state2, read_state2);
 
-    runtime.block_on(db.increment_key_index()).unwrap();
-    runtime.block_on(db.increment_key_index()).unwrap();
+    assert_eq!(runtime.block_on(db.increment_key_index()).unwrap(), 1);
+    assert_eq!(runtime.block_on(db.increment_key_index()).unwrap(), 2);
 
     let read_state3 = runtime.block_on(db.get_key_manager_state()).unwrap().unwrap();
     assert_eq!(read_state3.primary_key_index, 2);
@@ -373,11 +384,9 @@ pub async fn test_short_term_encumberance<T: OutputManagerBackend + 'static>(bac
     let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(50), &factories.commitment);
     pending_tx.outputs_to_be_received.push(uo);
 
-    db.encumber_outputs(
-        pending_tx.tx_id,
-        pending_tx.outputs_to_be_spent.clone(),
-        Some(pending_tx.outputs_to_be_received[0].clone()),
-    )
+    db.encumber_outputs(pending_tx.tx_id, pending_tx.outputs_to_be_spent.clone(), vec![
+        pending_tx.outputs_to_be_received[0].clone(),
+    ])
     .await
     .unwrap();
 
@@ -389,11 +398,9 @@ pub async fn test_short_term_encumberance<T: OutputManagerBackend + 'static>(bac
     let balance = db.get_balance().await.unwrap();
     assert_eq!(available_balance, balance.available_balance);
 
-    db.encumber_outputs(
-        pending_tx.tx_id,
-        pending_tx.outputs_to_be_spent.clone(),
-        Some(pending_tx.outputs_to_be_received[0].clone()),
-    )
+    db.encumber_outputs(pending_tx.tx_id, pending_tx.outputs_to_be_spent.clone(), vec![
+        pending_tx.outputs_to_be_received[0].clone(),
+    ])
     .await
     .unwrap();
 
@@ -405,11 +412,9 @@ pub async fn test_short_term_encumberance<T: OutputManagerBackend + 'static>(bac
 
     db.cancel_pending_transaction_outputs(pending_tx.tx_id).await.unwrap();
 
-    db.encumber_outputs(
-        pending_tx.tx_id,
-        pending_tx.outputs_to_be_spent.clone(),
-        Some(pending_tx.outputs_to_be_received[0].clone()),
-    )
+    db.encumber_outputs(pending_tx.tx_id, pending_tx.outputs_to_be_spent.clone(), vec![
+        pending_tx.outputs_to_be_received[0].clone(),
+    ])
     .await
     .unwrap();
 
@@ -433,3 +438,220 @@ pub async fn test_short_term_encumberance_sqlite_db() {
 
     test_short_term_encumberance(OutputManagerSqliteDatabase::new(connection)).await;
 }
+
+pub async fn test_revert_confirmed_transaction<T: OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+
+    let db = OutputManagerDatabase::new(backend);
+
+    let mut pending_tx = PendingTransactionOutputs {
+        tx_id: OsRng.next_u64(),
+        outputs_to_be_spent: vec![],
+        outputs_to_be_received: vec![],
+        timestamp: Utc::now().naive_utc(),
+    };
+    let mut spent_value = MicroTari(0);
+    for i in 1..3 {
+        let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(1000 * i), &factories.commitment);
+        spent_value += uo.value;
+        db.add_unspent_output(uo.clone()).await.unwrap();
+        pending_tx.outputs_to_be_spent.push(uo);
+    }
+    let (_ti, change) = make_input(&mut OsRng, MicroTari::from(500), &factories.commitment);
+    pending_tx.outputs_to_be_received.push(change.clone());
+
+    db.encumber_outputs(pending_tx.tx_id, pending_tx.outputs_to_be_spent.clone(), vec![
+        change.clone()
+    ])
+    .await
+    .unwrap();
+    db.confirm_encumbered_outputs(pending_tx.tx_id).await.unwrap();
+    db.confirm_pending_transaction_outputs(pending_tx.tx_id).await.unwrap();
+    assert_eq!(db.fetch_spent_outputs().await.unwrap().len(), 2);
+    assert_eq!(db.get_balance().await.unwrap().available_balance, change.value);
+
+    // The transaction is removed from the chain by a reorg
+    db.revert_confirmed_transaction_outputs(pending_tx.tx_id).await.unwrap();
+    assert!(db.fetch_spent_outputs().await.unwrap().is_empty());
+    let balance = db.get_balance().await.unwrap();
+    assert_eq!(balance.available_balance, MicroTari(0));
+    assert_eq!(balance.pending_incoming_balance, change.value);
+    assert_eq!(balance.pending_outgoing_balance, spent_value);
+    assert!(db.fetch_pending_transaction_outputs(pending_tx.tx_id).await.is_ok());
+    assert!(db.revert_confirmed_transaction_outputs(pending_tx.tx_id).await.is_err());
+
+    // Mined again
+    db.confirm_pending_transaction_outputs(pending_tx.tx_id).await.unwrap();
+    assert_eq!(db.get_balance().await.unwrap().available_balance, change.value);
+
+    // Reorged out again and then cancelled, so the spent outputs are unspent again
+    db.revert_confirmed_transaction_outputs(pending_tx.tx_id).await.unwrap();
+    db.cancel_pending_transaction_outputs(pending_tx.tx_id).await.unwrap();
+    let balance = db.get_balance().await.unwrap();
+    assert_eq!(balance.available_balance, spent_value);
+    assert_eq!(balance.pending_incoming_balance, MicroTari(0));
+}
+
+#[tokio_macros::test]
+pub async fn test_revert_confirmed_transaction_memory_db() {
+    test_revert_confirmed_transaction(OutputManagerMemoryDatabase::new()).await;
+}
+
+#[tokio_macros::test]
+pub async fn test_revert_confirmed_transaction_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    let connection = run_migration_and_create_sqlite_connection(&format!("{}/{}", db_folder, db_name)).unwrap();
+
+    test_revert_confirmed_transaction(OutputManagerSqliteDatabase::new(connection)).await;
+}
+
+pub async fn test_balance_audit_log<T: OutputManagerBackend + 'static>(backend: T) {
+    let factories = CryptoFactories::default();
+
+    let db = OutputManagerDatabase::new(backend);
+
+    let tx_id = OsRng.next_u64();
+    let mut outputs_to_spend = Vec::new();
+    for i in 1..3 {
+        let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(1000 * i), &factories.commitment);
+        db.add_unspent_output(uo.clone()).await.unwrap();
+        outputs_to_spend.push(uo);
+    }
+    let (_ti, change) = make_input(&mut OsRng, MicroTari::from(500), &factories.commitment);
+
+    db.encumber_outputs(tx_id, outputs_to_spend.clone(), vec![change.clone()])
+        .await
+        .unwrap();
+    db.confirm_encumbered_outputs(tx_id).await.unwrap();
+    db.confirm_pending_transaction_outputs(tx_id).await.unwrap();
+    db.revert_confirmed_transaction_outputs(tx_id).await.unwrap();
+    db.cancel_pending_transaction_outputs(tx_id).await.unwrap();
+
+    let log = db.get_balance_audit_log(None).await.unwrap();
+    assert_eq!(log.len(), 14);
+    assert!(log.windows(2).all(|w| w[0].id < w[1].id));
+    assert!(log[..2]
+        .iter()
+        .all(|e| e.event == BalanceAuditEvent::OutputAdded && e.tx_id.is_none()));
+
+    let tx_log = db.get_balance_audit_log(Some(tx_id)).await.unwrap();
+    assert_eq!(tx_log.len(), 12);
+    assert!(tx_log.iter().all(|e| e.tx_id == Some(tx_id)));
+    let count = |event| tx_log.iter().filter(|e| e.event == event).count();
+    assert_eq!(count(BalanceAuditEvent::OutputEncumbered), 2);
+    assert_eq!(count(BalanceAuditEvent::OutputExpected), 1);
+    assert_eq!(count(BalanceAuditEvent::OutputSpent), 2);
+    assert_eq!(count(BalanceAuditEvent::OutputReceived), 1);
+    assert_eq!(count(BalanceAuditEvent::OutputReorged), 3);
+    assert_eq!(count(BalanceAuditEvent::OutputReleased), 2);
+    assert_eq!(count(BalanceAuditEvent::ExpectedOutputDiscarded), 1);
+
+    let received = tx_log
+        .iter()
+        .find(|e| e.event == BalanceAuditEvent::OutputReceived)
+        .unwrap();
+    assert_eq!(received.value, change.value);
+    assert_eq!(
+        received.commitment,
+        factories
+            .commitment
+            .commit_value(&change.spending_key, u64::from(change.value))
+    );
+
+    db.invalidate_output(outputs_to_spend[0].clone()).await.unwrap();
+    let log = db.get_balance_audit_log(None).await.unwrap();
+    assert_eq!(log.last().unwrap().event, BalanceAuditEvent::OutputInvalidated);
+    assert!(db.get_balance_audit_log(Some(tx_id + 1)).await.unwrap().is_empty());
+}
+
+#[tokio_macros::test]
+pub async fn test_balance_audit_log_memory_db() {
+    test_balance_audit_log(OutputManagerMemoryDatabase::new()).await;
+}
+
+#[tokio_macros::test]
+pub async fn test_balance_audit_log_sqlite_db() {
+    let db_name = format!("{}.sqlite3", random_string(8).as_str());
+    let temp_dir = TempDir::new(random_string(8).as_str()).unwrap();
+    let db_folder = temp_dir.path().to_str().unwrap().to_string();
+    let connection = run_migration_and_create_sqlite_connection(&format!("{}/{}", db_folder, db_name)).unwrap();
+
+    test_balance_audit_log(OutputManagerSqliteDatabase::new(connection)).await;
+}
+
+#[test]
+pub fn test_concurrent_encumberance_memory_db() {
+    let factories = CryptoFactories::default();
+    let backend = OutputManagerMemoryDatabase::new();
+
+    let mut keys = HashSet::new();
+    for _ in 0..10 {
+        let (_ti, uo) = make_input(&mut OsRng, MicroTari::from(1000), &factories.commitment);
+        keys.insert(uo.spending_key.to_vec());
+        backend
+            .write(WriteOperation::Insert(DbKeyValuePair::UnspentOutput(
+                uo.spending_key.clone(),
+                Box::new(uo),
+            )))
+            .unwrap();
+    }
+
+    // Threads race to encumber overlapping outputs while others cancel, confirm and clear the encumberances
+    let handles = (0..8u64)
+        .map(|t| {
+            let backend = backend.clone();
+            thread::spawn(move || {
+                for i in 0..50u64 {
+                    let tx_id = t * 1000 + i;
+                    if t == 0 && i % 10 == 0 {
+                        backend.clear_short_term_encumberances().unwrap();
+                        continue;
+                    }
+                    let unspent = match backend.fetch(&DbKey::UnspentOutputs).unwrap() {
+                        Some(DbValue::UnspentOutputs(uo)) => uo,
+                        _ => panic!("Unexpected value"),
+                    };
+                    if unspent.len() < 2 {
+                        continue;
+                    }
+                    let start = (OsRng.next_u64() as usize) % (unspent.len() - 1);
+                    let outputs = &unspent[start..start + 2];
+                    if backend.short_term_encumber_outputs(tx_id, outputs, &[]).is_err() {
+                        // Another thread encumbered one of the outputs first
+                        continue;
+                    }
+                    // The encumberance may already have been cleared by another thread
+                    match OsRng.next_u64() % 3 {
+                        0 => {
+                            let _ = backend.confirm_encumbered_outputs(tx_id);
+                        },
+                        1 => {
+                            let _ = backend.cancel_pending_transaction(tx_id);
+                        },
+                        _ => (),
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // Every output is either unspent or encumbered by exactly one pending transaction
+    let mut found = Vec::new();
+    if let Some(DbValue::UnspentOutputs(uo)) = backend.fetch(&DbKey::UnspentOutputs).unwrap() {
+        found.extend(uo.into_iter().map(|o| o.spending_key.to_vec()));
+    }
+    if let Some(DbValue::AllPendingTransactionOutputs(pto)) =
+        backend.fetch(&DbKey::AllPendingTransactionOutputs).unwrap()
+    {
+        for p in pto.values() {
+            found.extend(p.outputs_to_be_spent.iter().map(|o| o.spending_key.to_vec()));
+        }
+    }
+    assert_eq!(found.len(), keys.len());
+    assert_eq!(found.into_iter().collect::<HashSet<_>>(), keys);
+}
@@ -83,8 +83,11 @@ pub fn create_dummy_message<T>(inner: T, public_key: &CommsPublicKey) -> DomainM
             flags: Default::default(),
             network: Network::LocalTest,
             destination: Default::default(),
+            ephemeral_public_key: None,
+            wrapped_keys: Vec::new(),
         },
         source_peer: peer_source,
+        reply_block: None,
         inner,
     }
 }
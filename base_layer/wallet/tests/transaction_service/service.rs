@@ -41,16 +41,24 @@ use std::{
 };
 use tari_broadcast_channel::bounded;
 use tari_comms::{
+    connectivity::ConnectivityRequester,
     message::EnvelopeBody,
     peer_manager::{NodeIdentity, PeerFeatures},
     protocol::messaging::MessagingEventSender,
+    utils::signature,
     CommsNode,
 };
 use tari_comms_dht::outbound::mock::{create_outbound_service_mock, OutboundServiceMockState};
 use tari_core::{
-    base_node::proto::{
-        base_node as BaseNodeProto,
-        base_node::base_node_service_response::Response as BaseNodeResponseProto,
+    base_node::{
+        proto::{
+            base_node as BaseNodeProto,
+            base_node::{
+                base_node_service_response::Response as BaseNodeResponseProto,
+                confirmation_notification::Watched,
+            },
+        },
+        protocol_version::{NodeVersion, ProtocolFeatures, MIN_COMPATIBLE_VERSION},
     },
     mempool::{
         proto::mempool as MempoolProto,
@@ -62,13 +70,15 @@ use tari_core::{
         tari_amount::*,
         transaction::{KernelBuilder, KernelFeatures, OutputFeatures, Transaction, TransactionOutput},
         transaction_protocol::{proto, recipient::RecipientSignedMessage, sender::TransactionSenderMessage},
-        types::{CryptoFactories, PrivateKey, PublicKey, RangeProof, Signature},
+        types::{CryptoFactories, HashDigest, PrivateKey, PublicKey, RangeProof, Signature},
         ReceiverTransactionProtocol,
+        SenderTransactionProtocol,
     },
 };
 use tari_crypto::{
     commitment::HomomorphicCommitmentFactory,
     keys::{PublicKey as PK, SecretKey as SK},
+    tari_utilities::message_format::MessageFormat,
 };
 use tari_p2p::{
     comms_connector::pubsub_connector,
@@ -94,7 +104,13 @@ use tari_wallet::{
         storage::{
             database::{
                 CompletedTransaction,
+                DbKey,
                 DbKeyValuePair,
+                DbValue,
+                InboundTransaction,
+                OutboundTransaction,
+                ReceiverProtocolStage,
+                SenderProtocolStage,
                 TransactionBackend,
                 TransactionDatabase,
                 TransactionStatus,
@@ -161,6 +177,7 @@ pub fn setup_transaction_service<T: TransactionBackend + Clone + 'static>(
             comms.subscribe_messaging_events(),
             backend,
             comms.node_identity().clone(),
+            comms.connectivity(),
             factories.clone(),
         ))
         .finish();
@@ -189,6 +206,8 @@ pub fn setup_transaction_service_no_comms<T: TransactionBackend + Clone + 'stati
     Sender<DomainMessage<proto::TransactionFinalizedMessage>>,
     Sender<DomainMessage<MempoolProto::MempoolServiceResponse>>,
     Sender<DomainMessage<BaseNodeProto::BaseNodeServiceResponse>>,
+    Sender<DomainMessage<BaseNodeProto::ConfirmationNotification>>,
+    Sender<DomainMessage<BaseNodeProto::VersionHandshake>>,
     MessagingEventSender,
 )
 {
@@ -219,11 +238,15 @@ pub fn setup_transaction_service_no_comms<T: TransactionBackend + Clone + 'stati
     let (tx_finalized_sender, tx_finalized_receiver) = mpsc::channel(20);
     let (mempool_response_sender, mempool_response_receiver) = mpsc::channel(20);
     let (base_node_response_sender, base_node_response_receiver) = mpsc::channel(20);
+    let (confirmation_notification_sender, confirmation_notification_receiver) = mpsc::channel(20);
+    let (version_handshake_sender, version_handshake_receiver) = mpsc::channel(20);
 
     let outbound_mock_state = mock_outbound_service.get_state();
     runtime.spawn(mock_outbound_service.run());
 
     let (message_event_publisher, message_event_subscriber) = broadcast::channel(30);
+    let (connectivity_tx, _) = mpsc::channel(20);
+    let (connectivity_event_tx, _) = broadcast::channel(20);
 
     let ts_service = TransactionService::new(
         TransactionServiceConfig {
@@ -238,6 +261,8 @@ pub fn setup_transaction_service_no_comms<T: TransactionBackend + Clone + 'stati
         tx_finalized_receiver,
         mempool_response_receiver,
         base_node_response_receiver,
+        confirmation_notification_receiver,
+        version_handshake_receiver,
         output_manager_service_handle.clone(),
         outbound_message_requester.clone(),
         message_event_subscriber,
@@ -245,6 +270,7 @@ pub fn setup_transaction_service_no_comms<T: TransactionBackend + Clone + 'stati
         Arc::new(
             NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap(),
         ),
+        ConnectivityRequester::new(connectivity_tx, connectivity_event_tx),
         factories.clone(),
     );
     runtime.spawn(async move { output_manager_service.start().await.unwrap() });
@@ -258,6 +284,8 @@ pub fn setup_transaction_service_no_comms<T: TransactionBackend + Clone + 'stati
         tx_finalized_sender,
         mempool_response_sender,
         base_node_response_sender,
+        confirmation_notification_sender,
+        version_handshake_sender,
         message_event_publisher,
     )
 }
@@ -307,6 +335,7 @@ fn manage_single_transaction<T: TransactionBackend + Clone + 'static>(
 
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
+    let bob_db = TransactionDatabase::new(bob_backend.clone());
     let (mut bob_ts, mut bob_oms, bob_comms) = setup_transaction_service(
         &mut runtime,
         bob_node_identity.clone(),
@@ -370,11 +399,17 @@ fn manage_single_transaction<T: TransactionBackend + Clone + 'static>(
         })
         .unwrap();
 
+    // The memo is stored encrypted and only decrypted when the transaction is returned by the service
+    let stored_tx = runtime.block_on(bob_db.get_completed_transaction(tx_id)).unwrap();
+    assert!(!stored_tx.message.is_empty());
+    assert_ne!(stored_tx.message, message);
+
     let mut bob_completed_tx = runtime.block_on(bob_ts.get_completed_transactions()).unwrap();
 
     match bob_completed_tx.remove(&tx_id) {
         None => assert!(false, "Completed transaction could not be found"),
         Some(tx) => {
+            assert_eq!(tx.message, message);
             runtime
                 .block_on(bob_oms.confirm_transaction(tx_id, vec![], tx.transaction.body.outputs().clone()))
                 .unwrap();
@@ -681,8 +716,10 @@ fn test_sending_repeated_tx_ids<T: TransactionBackend + Clone + 'static>(alice_b
         _,
         _,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), alice_backend, None);
-    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _) =
+    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories.clone(), bob_backend, None);
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
@@ -771,6 +808,8 @@ fn test_accepting_unknown_tx_id_and_malformed_reply<T: TransactionBackend + Clon
         _,
         _,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), alice_backend, None);
 
     let alice_event_stream = alice_ts.get_event_stream_fused();
@@ -864,6 +903,8 @@ fn finalize_tx_with_nonexistent_txid<T: TransactionBackend + Clone + 'static>(al
         _,
         _,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), alice_backend, None);
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
@@ -922,12 +963,14 @@ fn finalize_tx_with_incorrect_pubkey<T: TransactionBackend + Clone + 'static>(al
         _,
         _,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), alice_backend, None);
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
-    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _) =
+    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories.clone(), bob_backend, None);
 
     let (_utxo, uo) = make_input(&mut OsRng, MicroTari(250000), &factories.commitment);
@@ -1026,12 +1069,14 @@ fn finalize_tx_with_missing_output<T: TransactionBackend + Clone + 'static>(alic
         _,
         _,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), alice_backend, None);
     let alice_event_stream = alice_ts.get_event_stream_fused();
 
     let bob_node_identity =
         NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
-    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _) =
+    let (_bob_ts, mut bob_output_manager, _bob_outbound_service, _bob_tx_sender, _bob_tx_ack_sender, _, _, _, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories.clone(), bob_backend, None);
 
     let (_utxo, uo) = make_input(&mut OsRng, MicroTari(250000), &factories.commitment);
@@ -1286,6 +1331,8 @@ fn test_coinbase<T: TransactionBackend + Clone + 'static>(backend: T) {
         _,
         _,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), backend, None);
 
     let balance = runtime.block_on(alice_output_manager.get_balance()).unwrap();
@@ -1414,6 +1461,8 @@ fn transaction_mempool_broadcast() {
         mut alice_mempool_response_sender,
         _,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), TransactionMemoryDatabase::new(), None);
 
     runtime
@@ -1430,6 +1479,8 @@ fn transaction_mempool_broadcast() {
         _,
         _,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), TransactionMemoryDatabase::new(), None);
 
     runtime
@@ -1580,6 +1631,9 @@ fn transaction_mempool_broadcast() {
         MempoolRequest::GetState => assert!(false, "Invalid Mempool Service Request variant"),
         MempoolRequest::GetTxStateWithExcessSig(_) => assert!(false, "Invalid Mempool Service Request variant"),
         MempoolRequest::SubmitTransaction(tx) => assert_eq!(tx, alice_completed_tx.transaction),
+        MempoolRequest::ValidateTransaction(_) => assert!(false, "Invalid Mempool Service Request variant"),
+        MempoolRequest::ListTransactions(_) => assert!(false, "Invalid Mempool Service Request variant"),
+        MempoolRequest::GetTransaction(_) => assert!(false, "Invalid Mempool Service Request variant"),
     }
 
     let mempool_response = MempoolProto::MempoolServiceResponse {
@@ -1681,7 +1735,7 @@ fn broadcast_all_completed_transactions_on_startup() {
     )))
     .unwrap();
 
-    let (mut alice_ts, _, _, _, _, _, _, _, _) =
+    let (mut alice_ts, _, _, _, _, _, _, _, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories.clone(), db, None);
 
     runtime
@@ -1719,6 +1773,159 @@ fn broadcast_all_completed_transactions_on_startup() {
     });
 }
 
+#[test]
+fn resume_interrupted_protocols_on_startup() {
+    let mut runtime = Runtime::new().unwrap();
+    let factories = CryptoFactories::default();
+
+    let db = TransactionMemoryDatabase::new();
+
+    let amount = MicroTari::from(10_000);
+    let (_utxo, input) = make_input(&mut OsRng, MicroTari::from(100_000), &factories.commitment);
+    let mut builder = SenderTransactionProtocol::builder(1);
+    builder
+        .with_lock_height(0)
+        .with_fee_per_gram(MicroTari::from(20))
+        .with_offset(PrivateKey::random(&mut OsRng))
+        .with_private_nonce(PrivateKey::random(&mut OsRng))
+        .with_amount(0, amount)
+        .with_message("Yo!".to_string())
+        .with_input(
+            input.as_transaction_input(&factories.commitment, OutputFeatures::default()),
+            input.clone(),
+        )
+        .with_change_secret(PrivateKey::random(&mut OsRng));
+    let mut stp = builder.build::<HashDigest>(&factories).unwrap();
+    let sender_message = stp.build_single_round_message().unwrap();
+    let outbound_tx_id = sender_message.tx_id;
+    let inbound_tx_id = outbound_tx_id + 1;
+
+    let rtp = ReceiverTransactionProtocol::new(
+        TransactionSenderMessage::Single(Box::new(sender_message)),
+        PrivateKey::random(&mut OsRng),
+        PrivateKey::random(&mut OsRng),
+        OutputFeatures::default(),
+        &factories,
+    );
+
+    // The wallet stopped after persisting both protocols but before their messages were sent
+    let outbound_tx = OutboundTransaction {
+        tx_id: outbound_tx_id,
+        destination_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+        amount,
+        fee: stp.get_fee_amount().unwrap(),
+        sender_protocol: stp,
+        stage: SenderProtocolStage::Queued,
+        status: TransactionStatus::Pending,
+        message: "Yo!".to_string(),
+        timestamp: Utc::now().naive_utc(),
+        retry_count: 0,
+    };
+    let inbound_tx = InboundTransaction {
+        tx_id: inbound_tx_id,
+        source_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+        amount,
+        receiver_protocol: rtp,
+        stage: ReceiverProtocolStage::ReplyPending,
+        status: TransactionStatus::Pending,
+        message: "Yo!".to_string(),
+        timestamp: Utc::now().naive_utc(),
+        retry_count: 0,
+    };
+    db.write(WriteOperation::Insert(DbKeyValuePair::PendingOutboundTransaction(
+        outbound_tx_id,
+        Box::new(outbound_tx),
+    )))
+    .unwrap();
+    db.write(WriteOperation::Insert(DbKeyValuePair::PendingInboundTransaction(
+        inbound_tx_id,
+        Box::new(inbound_tx),
+    )))
+    .unwrap();
+
+    let (_alice_ts, _, alice_outbound_service, _, _, _, _, _, _, _, _) =
+        setup_transaction_service_no_comms(&mut runtime, factories.clone(), db.clone(), None);
+
+    alice_outbound_service
+        .wait_call_count(2, Duration::from_secs(60))
+        .expect("Interrupted protocol messages should be resent on startup");
+
+    let mut resumed = false;
+    for _ in 0..50 {
+        let outbound_stage = match db.fetch(&DbKey::PendingOutboundTransaction(outbound_tx_id)).unwrap() {
+            Some(DbValue::PendingOutboundTransaction(tx)) => tx.stage,
+            _ => panic!("Outbound transaction should still be pending"),
+        };
+        let inbound_stage = match db.fetch(&DbKey::PendingInboundTransaction(inbound_tx_id)).unwrap() {
+            Some(DbValue::PendingInboundTransaction(tx)) => tx.stage,
+            _ => panic!("Inbound transaction should still be pending"),
+        };
+        if outbound_stage == SenderProtocolStage::WaitingForReply &&
+            inbound_stage == ReceiverProtocolStage::WaitingForFinalization
+        {
+            resumed = true;
+            break;
+        }
+        runtime.block_on(delay_for(Duration::from_millis(100)));
+    }
+    assert!(resumed, "Resumed protocols should have moved to their next stage");
+}
+
+#[test]
+fn retry_and_cancel_pending_transaction() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let bob_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+
+    let (mut alice_ts, mut alice_output_manager, alice_outbound_service, _, _, _, _, _, _, _, _) =
+        setup_transaction_service_no_comms(&mut runtime, factories.clone(), TransactionMemoryDatabase::new(), None);
+
+    let (_utxo, uo) = make_input(&mut OsRng, 250000 * uT, &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+    let initial_balance = runtime.block_on(alice_output_manager.get_balance()).unwrap();
+
+    runtime
+        .block_on(alice_ts.send_transaction(
+            bob_node_identity.public_key().clone(),
+            10000 * uT,
+            100 * uT,
+            "Testing Message".to_string(),
+        ))
+        .unwrap();
+    let _ = alice_outbound_service.pop_call().unwrap();
+
+    let pending = runtime.block_on(alice_ts.get_pending_outbound_transactions()).unwrap();
+    let tx_id = *pending.keys().next().unwrap();
+    assert_eq!(pending[&tx_id].stage, SenderProtocolStage::WaitingForReply);
+    assert_eq!(pending[&tx_id].retry_count, 0);
+
+    // Resending the sender message leaves the protocol waiting for the reply
+    runtime.block_on(alice_ts.retry_transaction(tx_id)).unwrap();
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(60))
+        .expect("The sender message should be resent");
+    let pending = runtime.block_on(alice_ts.get_pending_outbound_transactions()).unwrap();
+    assert_eq!(pending[&tx_id].stage, SenderProtocolStage::WaitingForReply);
+    assert_eq!(pending[&tx_id].retry_count, 1);
+
+    runtime.block_on(alice_ts.cancel_transaction(tx_id)).unwrap();
+    assert!(runtime
+        .block_on(alice_ts.get_pending_outbound_transactions())
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        runtime.block_on(alice_output_manager.get_balance()).unwrap(),
+        initial_balance
+    );
+
+    match runtime.block_on(alice_ts.retry_transaction(tx_id)) {
+        Err(TransactionServiceError::PendingTransactionNotFound) => {},
+        r => panic!("Unexpected result retrying a cancelled transaction: {:?}", r),
+    }
+}
+
 #[test]
 fn transaction_base_node_monitoring() {
     let factories = CryptoFactories::default();
@@ -1743,9 +1950,11 @@ fn transaction_base_node_monitoring() {
         mut alice_mempool_response_sender,
         mut alice_base_node_response_sender,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), TransactionMemoryDatabase::new(), None);
 
-    let (mut bob_ts, _, bob_outbound_service, mut bob_tx_sender, _, _, _, _, _) =
+    let (mut bob_ts, _, bob_outbound_service, mut bob_tx_sender, _, _, _, _, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories.clone(), TransactionMemoryDatabase::new(), None);
 
     runtime
@@ -2108,6 +2317,313 @@ fn transaction_base_node_monitoring() {
     );
 }
 
+#[test]
+fn transaction_base_node_confirmation_notification() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let alice_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+
+    let bob_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+
+    let base_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        _,
+        mut alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        mut alice_confirmation_notification_sender,
+        _,
+        _,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), TransactionMemoryDatabase::new(), None);
+
+    let (mut bob_ts, _, bob_outbound_service, mut bob_tx_sender, _, _, _, _, _, _, _) =
+        setup_transaction_service_no_comms(&mut runtime, factories.clone(), TransactionMemoryDatabase::new(), None);
+
+    runtime
+        .block_on(bob_ts.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    let (_utxo, uo) = make_input(&mut OsRng, 250000 * uT, &factories.commitment);
+    runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+
+    runtime
+        .block_on(alice_ts.send_transaction(
+            bob_node_identity.public_key().clone(),
+            10000 * uT,
+            100 * uT,
+            "Testing Message".to_string(),
+        ))
+        .unwrap();
+
+    let call = alice_outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(&mut call.1.as_slice()).unwrap();
+    let tx_sender_msg = envelope_body
+        .decode_part::<proto::TransactionSenderMessage>(1)
+        .unwrap()
+        .unwrap();
+
+    runtime
+        .block_on(bob_tx_sender.send(create_dummy_message(tx_sender_msg, alice_node_identity.public_key())))
+        .unwrap();
+
+    let _result_stream = runtime.block_on(async {
+        collect_stream!(
+            bob_ts.get_event_stream_fused(),
+            take = 1,
+            timeout = Duration::from_secs(20)
+        )
+    });
+    let call = bob_outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(&mut call.1.as_slice()).unwrap();
+    let tx_reply_msg = envelope_body
+        .decode_part::<proto::RecipientSignedMessage>(1)
+        .unwrap()
+        .unwrap();
+
+    runtime
+        .block_on(alice_tx_ack_sender.send(create_dummy_message(tx_reply_msg, bob_node_identity.public_key())))
+        .unwrap();
+
+    let _result_stream = runtime.block_on(async {
+        collect_stream!(
+            alice_ts.get_event_stream_fused().map(|i| (*i).clone()),
+            take = 1,
+            timeout = Duration::from_secs(60)
+        )
+    });
+
+    let (tx_id, completed_tx) = runtime
+        .block_on(alice_ts.get_completed_transactions())
+        .unwrap()
+        .into_iter()
+        .next()
+        .expect("Transaction must be in collection");
+    assert_eq!(completed_tx.status, TransactionStatus::Completed);
+
+    // Setting the base node starts monitoring the completed transaction, which registers its kernel with the base node
+    runtime
+        .block_on(alice_ts.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    let mut notification = BaseNodeProto::ConfirmationNotification {
+        watched: Some(Watched::ExcessSig(
+            completed_tx.transaction.body.kernels()[0].excess_sig.clone().into(),
+        )),
+        block_hash: vec![1u8; 32],
+        height: 10,
+        confirmations: 1,
+        signature: Vec::new(),
+    };
+
+    // A notification which is not signed by the base node is rejected
+    notification.signature = signature::sign(
+        &mut OsRng,
+        bob_node_identity.secret_key().clone(),
+        notification.signature_body(),
+    )
+    .unwrap()
+    .to_binary()
+    .unwrap();
+    runtime
+        .block_on(alice_confirmation_notification_sender.send(create_dummy_message(
+            notification.clone(),
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+
+    let sign_notification = |mut notification: BaseNodeProto::ConfirmationNotification| {
+        notification.signature = signature::sign(
+            &mut OsRng,
+            base_node_identity.secret_key().clone(),
+            notification.signature_body(),
+        )
+        .unwrap()
+        .to_binary()
+        .unwrap();
+        notification
+    };
+
+    // The transaction is not marked as mined before it has the required number of confirmations and is reset when
+    // the block containing it is reorged out
+    let mut alice_event_stream = alice_ts.get_event_stream_fused();
+    for confirmations in &[1, 0] {
+        runtime
+            .block_on(alice_confirmation_notification_sender.send(create_dummy_message(
+                sign_notification(BaseNodeProto::ConfirmationNotification {
+                    confirmations: *confirmations,
+                    ..notification.clone()
+                }),
+                base_node_identity.public_key(),
+            )))
+            .unwrap();
+    }
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(20)).fuse();
+        let mut confirmations_received = Vec::new();
+        let mut mined = false;
+        loop {
+            futures::select! {
+                event = alice_event_stream.select_next_some() => {
+                    match &*event {
+                        TransactionEvent::TransactionConfirmations(id, confirmations) if *id == tx_id => {
+                            confirmations_received.push(*confirmations);
+                            if *confirmations == 0 {
+                                break;
+                            }
+                        },
+                        TransactionEvent::TransactionMined(id) if *id == tx_id => mined = true,
+                        _ => (),
+                    }
+                },
+                () = delay => {
+                    log::error!("This select loop timed out");
+                    break;
+                },
+            }
+        }
+        assert_eq!(confirmations_received, vec![1, 0]);
+        assert!(!mined);
+    });
+
+    let alice_completed_tx = runtime
+        .block_on(alice_ts.get_completed_transactions())
+        .unwrap()
+        .remove(&tx_id)
+        .expect("Transaction must be in collection");
+    assert_eq!(alice_completed_tx.status, TransactionStatus::Broadcast);
+
+    // The transaction is mined again and reaches the required number of confirmations
+    runtime
+        .block_on(alice_confirmation_notification_sender.send(create_dummy_message(
+            sign_notification(BaseNodeProto::ConfirmationNotification {
+                block_hash: vec![2u8; 32],
+                height: 11,
+                confirmations: 3,
+                ..notification
+            }),
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(20)).fuse();
+        let mut mined = false;
+        loop {
+            futures::select! {
+                event = alice_event_stream.select_next_some() => {
+                    if let TransactionEvent::TransactionMined(id) = &*event {
+                        if *id == tx_id {
+                            mined = true;
+                            break;
+                        }
+                    }
+                },
+                () = delay => {
+                    log::error!("This select loop timed out");
+                    break;
+                },
+            }
+        }
+        assert!(mined);
+    });
+
+    let alice_completed_tx = runtime
+        .block_on(alice_ts.get_completed_transactions())
+        .unwrap()
+        .remove(&tx_id)
+        .expect("Transaction must be in collection");
+    assert_eq!(alice_completed_tx.status, TransactionStatus::Mined);
+}
+
+#[test]
+fn base_node_version_handshake() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let base_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE).unwrap();
+
+    let (mut alice_ts, _, alice_outbound_service, _, _, _, _, _, _, mut alice_version_handshake_sender, _) =
+        setup_transaction_service_no_comms(&mut runtime, factories.clone(), TransactionMemoryDatabase::new(), None);
+    let mut alice_event_stream = alice_ts.get_event_stream_fused();
+
+    runtime
+        .block_on(alice_ts.set_base_node_public_key(base_node_identity.public_key().clone()))
+        .unwrap();
+
+    alice_outbound_service
+        .wait_call_count(1, Duration::from_secs(10))
+        .unwrap();
+    let (_, body) = alice_outbound_service.pop_call().unwrap();
+    let envelope_body = EnvelopeBody::decode(&mut body.as_slice()).unwrap();
+    let request = envelope_body
+        .decode_part::<BaseNodeProto::VersionHandshake>(1)
+        .unwrap()
+        .unwrap();
+    assert!(!request.rejected);
+    assert!(
+        ProtocolFeatures::from_bits_truncate(request.features).contains(ProtocolFeatures::CONFIRMATION_NOTIFICATIONS)
+    );
+    assert_eq!(runtime.block_on(alice_ts.get_base_node_capabilities()).unwrap(), None);
+
+    // An older base node without confirmation notifications is still used, but is polled for mined transactions
+    runtime
+        .block_on(alice_version_handshake_sender.send(create_dummy_message(
+            BaseNodeProto::VersionHandshake::new(request.request_key, MIN_COMPATIBLE_VERSION, ProtocolFeatures::NONE),
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+    let capabilities = runtime.block_on(async {
+        for _ in 0..20 {
+            if let Some(capabilities) = alice_ts.get_base_node_capabilities().await.unwrap() {
+                return Some(capabilities);
+            }
+            delay_for(Duration::from_millis(100)).await;
+        }
+        None
+    });
+    let capabilities = capabilities.expect("Version handshake was not completed");
+    assert_eq!(capabilities.peer_version, MIN_COMPATIBLE_VERSION);
+    assert!(!capabilities.supports(ProtocolFeatures::CONFIRMATION_NOTIFICATIONS));
+
+    // A base node which refuses the handshake is reported as incompatible
+    runtime
+        .block_on(alice_version_handshake_sender.send(create_dummy_message(
+            BaseNodeProto::VersionHandshake::rejection(request.request_key, NodeVersion::new(0, 0, 1)),
+            base_node_identity.public_key(),
+        )))
+        .unwrap();
+    runtime.block_on(async {
+        let mut delay = delay_for(Duration::from_secs(20)).fuse();
+        let mut incompatible = false;
+        loop {
+            futures::select! {
+                event = alice_event_stream.select_next_some() => {
+                    if let TransactionEvent::BaseNodeIncompatible(pk) = &*event {
+                        assert_eq!(pk, base_node_identity.public_key());
+                        incompatible = true;
+                        break;
+                    }
+                },
+                () = delay => {
+                    log::error!("This select loop timed out");
+                    break;
+                },
+            }
+        }
+        assert!(incompatible);
+    });
+    assert_eq!(runtime.block_on(alice_ts.get_base_node_capabilities()).unwrap(), None);
+}
+
 #[test]
 fn query_all_completed_transactions_on_startup() {
     let mut runtime = Runtime::new().unwrap();
@@ -2165,7 +2681,7 @@ fn query_all_completed_transactions_on_startup() {
     )))
     .unwrap();
 
-    let (mut alice_ts, _, _, _, _, _, _, _, _) =
+    let (mut alice_ts, _, _, _, _, _, _, _, _, _, _) =
         setup_transaction_service_no_comms(&mut runtime, factories.clone(), db, None);
 
     runtime
@@ -2329,6 +2845,8 @@ fn transaction_cancellation_when_not_in_mempool() {
         mut alice_mempool_response_sender,
         mut alice_base_node_response_sender,
         _,
+        _,
+        _,
     ) = setup_transaction_service_no_comms(
         &mut runtime,
         factories.clone(),
@@ -2336,7 +2854,7 @@ fn transaction_cancellation_when_not_in_mempool() {
         Some(Duration::from_secs(20)),
     );
 
-    let (mut bob_ts, _, bob_outbound_service, mut bob_tx_sender, _, _, _, _, _) = setup_transaction_service_no_comms(
+    let (mut bob_ts, _, bob_outbound_service, mut bob_tx_sender, _, _, _, _, _, _, _) = setup_transaction_service_no_comms(
         &mut runtime,
         factories.clone(),
         TransactionMemoryDatabase::new(),
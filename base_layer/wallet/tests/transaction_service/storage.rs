@@ -43,6 +43,8 @@ use tari_wallet::{
             InboundTransaction,
             OutboundTransaction,
             PendingCoinbaseTransaction,
+            ReceiverProtocolStage,
+            SenderProtocolStage,
             TransactionBackend,
             TransactionDatabase,
             TransactionStatus,
@@ -88,9 +90,11 @@ pub fn test_db_backend<T: TransactionBackend + 'static>(backend: T) {
             amount: amounts[i].clone(),
             fee: stp.clone().get_fee_amount().unwrap(),
             sender_protocol: stp.clone(),
+            stage: SenderProtocolStage::Queued,
             status: TransactionStatus::Pending,
             message: messages[i].clone(),
             timestamp: Utc::now().naive_utc(),
+            retry_count: 0,
         });
         assert!(
             !runtime.block_on(db.transaction_exists((i + 10) as u64)).unwrap(),
@@ -120,6 +124,32 @@ pub fn test_db_backend<T: TransactionBackend + 'static>(backend: T) {
         );
     }
 
+    let advanced_outbound_tx = runtime
+        .block_on(db.advance_outbound_transaction(outbound_txs[0].clone(), SenderProtocolStage::WaitingForReply))
+        .unwrap();
+    assert_eq!(advanced_outbound_tx.stage, SenderProtocolStage::WaitingForReply);
+    assert_eq!(
+        runtime
+            .block_on(db.get_pending_outbound_transaction(outbound_txs[0].tx_id))
+            .unwrap(),
+        advanced_outbound_tx
+    );
+    assert!(runtime
+        .block_on(db.advance_outbound_transaction(outbound_txs[0].clone(), SenderProtocolStage::Queued))
+        .is_err());
+
+    let retried_outbound_tx = runtime
+        .block_on(db.increment_outbound_retry_count(outbound_txs[0].tx_id))
+        .unwrap();
+    assert_eq!(retried_outbound_tx.retry_count, 1);
+    assert_eq!(retried_outbound_tx.stage, SenderProtocolStage::WaitingForReply);
+    assert_eq!(
+        runtime
+            .block_on(db.get_pending_outbound_transaction(outbound_txs[0].tx_id))
+            .unwrap(),
+        retried_outbound_tx
+    );
+
     let rtp = ReceiverTransactionProtocol::new(
         TransactionSenderMessage::Single(Box::new(stp.clone().build_single_round_message().unwrap())),
         PrivateKey::random(&mut OsRng),
@@ -136,9 +166,11 @@ pub fn test_db_backend<T: TransactionBackend + 'static>(backend: T) {
             source_public_key: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
             amount: amounts[i].clone(),
             receiver_protocol: rtp.clone(),
+            stage: ReceiverProtocolStage::ReplyPending,
             status: TransactionStatus::Pending,
             message: messages[i].clone(),
             timestamp: Utc::now().naive_utc(),
+            retry_count: 0,
         });
         assert!(
             !runtime.block_on(db.transaction_exists(i as u64)).unwrap(),
@@ -162,6 +194,30 @@ pub fn test_db_backend<T: TransactionBackend + 'static>(backend: T) {
         );
     }
 
+    let advanced_inbound_tx = runtime
+        .block_on(db.advance_inbound_transaction(inbound_txs[0].clone(), ReceiverProtocolStage::WaitingForFinalization))
+        .unwrap();
+    assert_eq!(
+        runtime
+            .block_on(db.get_pending_inbound_transaction(inbound_txs[0].tx_id))
+            .unwrap(),
+        advanced_inbound_tx
+    );
+    assert!(runtime
+        .block_on(db.advance_inbound_transaction(inbound_txs[0].clone(), ReceiverProtocolStage::ReplyPending))
+        .is_err());
+
+    let retried_inbound_tx = runtime
+        .block_on(db.increment_inbound_retry_count(inbound_txs[0].tx_id))
+        .unwrap();
+    assert_eq!(retried_inbound_tx.retry_count, 1);
+    assert_eq!(
+        runtime
+            .block_on(db.get_pending_inbound_transaction(inbound_txs[0].tx_id))
+            .unwrap(),
+        retried_inbound_tx
+    );
+
     let mut coinbases = Vec::new();
     for i in 0..messages.len() {
         coinbases.push(PendingCoinbaseTransaction {
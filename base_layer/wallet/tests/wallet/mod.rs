@@ -99,6 +99,7 @@ fn test_wallet() {
             allow_test_addresses: true,
             listener_liveness_whitelist_cidrs: Vec::new(),
             listener_liveness_max_sessions: 0,
+            peer_allow_list: None,
         };
         let comms_config2 = CommsConfig {
             node_identity: Arc::new(bob_identity.clone()),
@@ -114,16 +115,21 @@ fn test_wallet() {
             allow_test_addresses: true,
             listener_liveness_whitelist_cidrs: Vec::new(),
             listener_liveness_max_sessions: 0,
+            peer_allow_list: None,
         };
         let config1 = WalletConfig {
             comms_config: comms_config1,
             factories: factories.clone(),
             transaction_service_config: None,
+            output_manager_service_config: None,
+            locale_config: None,
         };
         let config2 = WalletConfig {
             comms_config: comms_config2,
             factories: factories.clone(),
             transaction_service_config: None,
+            output_manager_service_config: None,
+            locale_config: None,
         };
         let runtime_node1 = Runtime::new().unwrap();
         let runtime_node2 = Runtime::new().unwrap();
@@ -248,11 +254,14 @@ fn test_import_utxo() {
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        peer_allow_list: None,
     };
     let config = WalletConfig {
         comms_config,
         factories: factories.clone(),
         transaction_service_config: None,
+        output_manager_service_config: None,
+        locale_config: None,
     };
     let runtime_node = Runtime::new().unwrap();
     let mut alice_wallet = Wallet::new(
@@ -318,12 +327,15 @@ fn test_data_generation() {
         allow_test_addresses: true,
         listener_liveness_whitelist_cidrs: Vec::new(),
         listener_liveness_max_sessions: 0,
+        peer_allow_list: None,
     };
 
     let config = WalletConfig {
         comms_config,
         factories,
         transaction_service_config: None,
+        output_manager_service_config: None,
+        locale_config: None,
     };
 
     let transaction_backend = TransactionMemoryDatabase::new();
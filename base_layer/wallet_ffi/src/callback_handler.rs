@@ -56,6 +56,7 @@ use tari_wallet::{
     output_manager_service::{handle::OutputManagerEvent, TxId},
     transaction_service::{
         handle::TransactionEvent,
+        memo::MemoCipher,
         storage::database::{CompletedTransaction, InboundTransaction, TransactionBackend, TransactionDatabase},
     },
 };
@@ -73,6 +74,7 @@ where TBackend: TransactionBackend + 'static
     callback_discovery_process_complete: unsafe extern "C" fn(TxId, bool),
     callback_base_node_sync_complete: unsafe extern "C" fn(TxId, bool),
     db: TransactionDatabase<TBackend>,
+    memo_cipher: MemoCipher,
     transaction_service_event_stream: Fuse<Subscriber<TransactionEvent>>,
     output_manager_service_event_stream: Fuse<Subscriber<OutputManagerEvent>>,
     shutdown_signal: Option<ShutdownSignal>,
@@ -84,6 +86,7 @@ where TBackend: TransactionBackend + 'static
 {
     pub fn new(
         db: TransactionDatabase<TBackend>,
+        memo_cipher: MemoCipher,
         transaction_service_event_stream: Fuse<Subscriber<TransactionEvent>>,
         output_manager_service_event_stream: Fuse<Subscriber<OutputManagerEvent>>,
         shutdown_signal: ShutdownSignal,
@@ -134,6 +137,7 @@ where TBackend: TransactionBackend + 'static
             callback_discovery_process_complete,
             callback_base_node_sync_complete,
             db,
+            memo_cipher,
             transaction_service_event_stream,
             output_manager_service_event_stream,
             shutdown_signal: Some(shutdown_signal),
@@ -211,7 +215,8 @@ where TBackend: TransactionBackend + 'static
 
     async fn receive_transaction_event(&mut self, tx_id: TxId) {
         match self.db.get_pending_inbound_transaction(tx_id).await {
-            Ok(tx) => {
+            Ok(mut tx) => {
+                self.memo_cipher.decrypt_inbound(&mut tx);
                 debug!(
                     target: LOG_TARGET,
                     "Calling Received Transaction callback function for TxId: {}", tx_id
@@ -230,7 +235,8 @@ where TBackend: TransactionBackend + 'static
 
     async fn receive_transaction_reply_event(&mut self, tx_id: TxId) {
         match self.db.get_completed_transaction(tx_id).await {
-            Ok(tx) => {
+            Ok(mut tx) => {
+                self.memo_cipher.decrypt_completed(&mut tx);
                 debug!(
                     target: LOG_TARGET,
                     "Calling Received Transaction Reply callback function for TxId: {}", tx_id
@@ -246,7 +252,8 @@ where TBackend: TransactionBackend + 'static
 
     async fn receive_finalized_transaction_event(&mut self, tx_id: TxId) {
         match self.db.get_completed_transaction(tx_id).await {
-            Ok(tx) => {
+            Ok(mut tx) => {
+                self.memo_cipher.decrypt_completed(&mut tx);
                 debug!(
                     target: LOG_TARGET,
                     "Calling Received Finalized Transaction callback function for TxId: {}", tx_id
@@ -272,7 +279,8 @@ where TBackend: TransactionBackend + 'static
 
     async fn receive_transaction_broadcast_event(&mut self, tx_id: TxId) {
         match self.db.get_completed_transaction(tx_id).await {
-            Ok(tx) => {
+            Ok(mut tx) => {
+                self.memo_cipher.decrypt_completed(&mut tx);
                 debug!(
                     target: LOG_TARGET,
                     "Calling Received Transaction Broadcast callback function for TxId: {}", tx_id
@@ -288,7 +296,8 @@ where TBackend: TransactionBackend + 'static
 
     async fn receive_transaction_mined_event(&mut self, tx_id: TxId) {
         match self.db.get_completed_transaction(tx_id).await {
-            Ok(tx) => {
+            Ok(mut tx) => {
+                self.memo_cipher.decrypt_completed(&mut tx);
                 debug!(
                     target: LOG_TARGET,
                     "Calling Received Transaction Mined callback function for TxId: {}", tx_id
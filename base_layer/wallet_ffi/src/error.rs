@@ -31,7 +31,7 @@ use tari_crypto::{
 };
 use tari_wallet::{
     contacts_service::error::{ContactsServiceError, ContactsServiceStorageError},
-    error::WalletError,
+    error::{LocalizationError, WalletError},
     output_manager_service::error::{OutputManagerError, OutputManagerStorageError},
     transaction_service::error::{TransactionServiceError, TransactionStorageError},
 };
@@ -55,6 +55,14 @@ pub enum InterfaceError {
     DeserializationError(String),
     /// Emoji ID is invalid
     InvalidEmojiId,
+    /// The value does not correspond to a transaction status
+    InvalidTransactionStatus,
+    /// An error has occurred when configuring logging
+    #[error(non_std, no_from)]
+    LoggingError(String),
+    /// The payment URI or one of its parts is invalid
+    #[error(non_std, no_from)]
+    InvalidPaymentUri(String),
 }
 
 /// This struct is meant to hold an error for use by FFI client applications. The error has an integer code and string
@@ -93,6 +101,18 @@ impl From<InterfaceError> for LibWalletError {
                 code: 6,
                 message: format!("{:?}", v),
             },
+            InterfaceError::InvalidTransactionStatus => Self {
+                code: 7,
+                message: format!("{:?}", v),
+            },
+            InterfaceError::LoggingError(_) => Self {
+                code: 8,
+                message: format!("{:?}", v),
+            },
+            InterfaceError::InvalidPaymentUri(_) => Self {
+                code: 9,
+                message: format!("{:?}", v),
+            },
         }
     }
 }
@@ -224,6 +244,19 @@ impl From<WalletError> for LibWalletError {
                 code: 404,
                 message: format!("{:?}", w),
             },
+            // Localization errors
+            WalletError::LocalizationError(LocalizationError::InvalidLocale(_)) => Self {
+                code: 1001,
+                message: format!("{:?}", w),
+            },
+            WalletError::LocalizationError(LocalizationError::LocaleNotFound(_)) => Self {
+                code: 1002,
+                message: format!("{:?}", w),
+            },
+            WalletError::LocalizationError(_) => Self {
+                code: 1003,
+                message: format!("{:?}", w),
+            },
             // This is the catch all error code. Any error that is not explicitly mapped above will be given this code
             _ => Self {
                 code: 999,
@@ -344,3 +377,47 @@ impl From<SchnorrSignatureError> for LibWalletError {
         }
     }
 }
+
+/// Returns the English explanation of a LibWalletError code. Translations are looked up in the wallet's message
+/// catalogue under the id `error.<code>`, with this explanation as the fallback.
+pub fn error_description(code: i32) -> &'static str {
+    match code {
+        0 => "No error",
+        1 => "A required value was not provided",
+        2 => "The requested item could not be allocated",
+        3 => "The requested position is out of range",
+        4 => "The wallet runtime could not be started",
+        5 => "The provided data could not be read",
+        6 => "The emoji ID is not valid",
+        7 => "The value is not a valid transaction status",
+        101 | 113 => "There are not enough funds available to send this transaction",
+        102 => "The transaction is incomplete",
+        103 | 112 => "This output already exists in the wallet",
+        104 | 108 => "The requested output could not be found",
+        105 => "This output has already been spent",
+        106 => "The pending transaction could not be found",
+        109 => "No base node has been set for the wallet",
+        110 | 401 => "The contact could not be found",
+        111 | 204 => "The transaction could not be found",
+        201 => "The transaction is not in a valid state for this operation",
+        202 => "The transaction could not be negotiated with the other party",
+        203 => "This message has already been received",
+        206 => "The wallet's outputs could not be updated for this transaction",
+        207 => "The transaction is not valid",
+        210 => {
+            "The recipient is still being searched for on the network, the transaction will be sent once they are found"
+        },
+        301 | 801..=810 => "The network address is not valid",
+        403 => "This operation is not supported",
+        404 => "The value could not be converted",
+        501 => "The hex string has an incorrect length",
+        503 => "The hex string contains an invalid character",
+        601 => "The value has an incorrect length",
+        701..=704 => "The node identity is not valid",
+        901 => "The signature challenge is not valid",
+        1001 => "The language setting is not valid",
+        1002 => "Messages are not available in this language",
+        1003 => "The messages for this language could not be read",
+        _ => "An unexpected error occurred",
+    }
+}
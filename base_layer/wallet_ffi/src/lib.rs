@@ -50,31 +50,30 @@
 //! `Contact`.
 //!
 //! To send a transaction:
-//! 1.  Call the `send_transaction(dest_public_key, amount, fee_per_gram, message)` function which will result in a
-//!     `PendingOutboundTransaction` being produced and transmitted to the recipient and the funds becoming
-//!     encumbered and appearing in the `PendingOutgoingBalance` and any change will appear in the
-//!     `PendingIncomingBalance`.
-//! 2.  Wait until the recipient replies to the sent transaction which will result in the `PendingOutboundTransaction`
-//!     becoming a `CompletedTransaction` with the `Completed` status. This means that the transaction has been
-//!     negotiated between the parties and is now ready to be broadcast to the Base Layer. The funds are still
-//!     encumbered as pending because the transaction has not been mined yet.
-//! 3.  The finalized `CompletedTransaction' will be sent back to the the receiver so that they have a copy.
-//! 4.  The wallet will broadcast the `CompletedTransaction` to a Base Node to be added to the mempool. its status will
-//!     from `Completed` to `Broadcast.
-//! 5.  Wait until the transaction is mined. The `CompleteTransaction` status will then move from `Broadcast` to `Mined`
-//!     and the pending funds will be spent and received.
+//! 1. Call the `send_transaction(dest_public_key, amount, fee_per_gram, message)` function which will result in a
+//!    `PendingOutboundTransaction` being produced and transmitted to the recipient and the funds becoming encumbered
+//!    and appearing in the `PendingOutgoingBalance` and any change will appear in the `PendingIncomingBalance`.
+//! 2. Wait until the recipient replies to the sent transaction which will result in the `PendingOutboundTransaction`
+//!    becoming a `CompletedTransaction` with the `Completed` status. This means that the transaction has been
+//!    negotiated between the parties and is now ready to be broadcast to the Base Layer. The funds are still encumbered
+//!    as pending because the transaction has not been mined yet.
+//! 3. The finalized `CompletedTransaction' will be sent back to the the receiver so that they have a copy.
+//! 4. The wallet will broadcast the `CompletedTransaction` to a Base Node to be added to the mempool. its status will
+//!    from `Completed` to `Broadcast.
+//! 5. Wait until the transaction is mined. The `CompleteTransaction` status will then move from `Broadcast` to `Mined`
+//!    and the pending funds will be spent and received.
 //!
 //! ## Receive a Transaction
-//! 1.  When a transaction is received it will appear as an `InboundTransaction` and the amount to be received will
-//!     appear as a `PendingIncomingBalance`. The wallet backend will be listening for these transactions and will
-//!     immediately reply to the sending wallet.
-//! 2.  The sender will send back the finalized `CompletedTransaction`
-//! 3.  This wallet will also broadcast the `CompletedTransaction` to a Base Node to be added to the mempool, its status
-//!     will move from `Completed` to `Broadcast`. This is done so that the Receiver can be sure the finalized
-//!     transaction is broadcast.
-//! 6.  This wallet will then monitor the Base Layer to see when the transaction is mined which means the
-//!     `CompletedTransaction` status will become `Mined` and the funds will then move from the `PendingIncomingBalance`
-//!     to the `AvailableBalance`.
+//! 1. When a transaction is received it will appear as an `InboundTransaction` and the amount to be received will
+//!    appear as a `PendingIncomingBalance`. The wallet backend will be listening for these transactions and will
+//!    immediately reply to the sending wallet.
+//! 2. The sender will send back the finalized `CompletedTransaction`
+//! 3. This wallet will also broadcast the `CompletedTransaction` to a Base Node to be added to the mempool, its status
+//!    will move from `Completed` to `Broadcast`. This is done so that the Receiver can be sure the finalized
+//!    transaction is broadcast.
+//! 6. This wallet will then monitor the Base Layer to see when the transaction is mined which means the
+//!    `CompletedTransaction` status will become `Mined` and the funds will then move from the `PendingIncomingBalance`
+//!    to the `AvailableBalance`.
 //!
 //! ## Using the test functions
 //! The above two flows both require a second wallet for this wallet to interact with. Because we do not yet have a live
@@ -82,32 +81,31 @@
 //! second wallets role in these flows. The following will describe how to use these functions to produce the flows.
 //!
 //! ### Send Transaction with test functions
-//! 1.  Send Transaction as above to produce a `PendingOutboundTransaction`.
-//! 2.  Call the `complete_sent_transaction(...)` function with the tx_id of the sent transaction to simulate a reply.
-//!     This will move the `PendingOutboundTransaction` to become a `CompletedTransaction` with the `Completed` status.
-//! 3.  Call the 'broadcast_transaction(...)` function with the tx_id of the sent transaction and its status will move
-//!     from 'Completed' to 'Broadcast' which means it has been broadcast to the Base Layer Mempool but not mined yet.
-//!     from 'Completed' to 'Broadcast' which means it has been broadcast to the Base Layer Mempool but not mined yet.
-//! 4.  Call the `mined_transaction(...)` function with the tx_id of the sent transaction which will change
-//!     the status of the `CompletedTransaction` from `Broadcast` to `Mined`. The pending funds will also become
-//!     finalized as spent and available funds respectively.
+//! 1. Send Transaction as above to produce a `PendingOutboundTransaction`.
+//! 2. Call the `complete_sent_transaction(...)` function with the tx_id of the sent transaction to simulate a reply.
+//!    This will move the `PendingOutboundTransaction` to become a `CompletedTransaction` with the `Completed` status.
+//! 3. Call the 'broadcast_transaction(...)` function with the tx_id of the sent transaction and its status will move
+//!    from 'Completed' to 'Broadcast' which means it has been broadcast to the Base Layer Mempool but not mined yet.
+//!    from 'Completed' to 'Broadcast' which means it has been broadcast to the Base Layer Mempool but not mined yet.
+//! 4. Call the `mined_transaction(...)` function with the tx_id of the sent transaction which will change the status of
+//!    the `CompletedTransaction` from `Broadcast` to `Mined`. The pending funds will also become finalized as spent and
+//!    available funds respectively.
 //!
 //! ### Receive Transaction with test functions
 //! Under normal operation another wallet would initiate a Receive Transaction flow by sending you a transaction. We
 //! will use the `receive_test_transaction(...)` function to initiate the flow:
 //!
-//! 1.  Calling `receive_test_transaction(...)` will produce an `InboundTransaction`, the amount of the transaction will
-//!     appear under the `PendingIncomingBalance`.
-//! 2.  To simulate detecting the `InboundTransaction` being broadcast to the Base Layer Mempool call
-//!     `broadcast_transaction(...)` function. This will change the `InboundTransaction` to a
-//!     `CompletedTransaction` with the `Broadcast` status. The funds will still reflect in the pending balance.
-//! 3.  Call the `mined_transaction(...)` function with the tx_id of the received transaction which will
-//!     change the status of the `CompletedTransaction` from    `Broadcast` to `Mined`. The pending funds will also
-//!     become finalized as spent and available funds respectively
+//! 1. Calling `receive_test_transaction(...)` will produce an `InboundTransaction`, the amount of the transaction will
+//!    appear under the `PendingIncomingBalance`.
+//! 2. To simulate detecting the `InboundTransaction` being broadcast to the Base Layer Mempool call
+//!    `broadcast_transaction(...)` function. This will change the `InboundTransaction` to a `CompletedTransaction` with
+//!    the `Broadcast` status. The funds will still reflect in the pending balance.
+//! 3. Call the `mined_transaction(...)` function with the tx_id of the received transaction which will change the
+//!    status of the `CompletedTransaction` from    `Broadcast` to `Mined`. The pending funds will also become finalized
+//!    as spent and available funds respectively
 
 #![recursion_limit = "512"]
 
-#[cfg(test)]
 #[macro_use]
 extern crate lazy_static;
 
@@ -116,26 +114,25 @@ extern crate tari_wallet;
 
 mod callback_handler;
 mod error;
+mod logging;
 
 use crate::{callback_handler::CallbackHandler, error::InterfaceError};
 use core::ptr;
 use error::LibWalletError;
 use libc::{c_char, c_int, c_longlong, c_uchar, c_uint, c_ulonglong, c_ushort};
-use log::{LevelFilter, *};
-use log4rs::{
-    append::file::FileAppender,
-    config::{Appender, Config, Root},
-    encode::pattern::PatternEncoder,
-};
+use log::*;
 use rand::rngs::OsRng;
 use std::{
     boxed::Box,
+    convert::TryFrom,
     ffi::{CStr, CString},
     path::PathBuf,
     slice,
+    str::FromStr,
     sync::Arc,
     time::Duration,
 };
+use tari_common::Network;
 use tari_comms::{
     multiaddr::Multiaddr,
     peer_manager::{NodeIdentity, PeerFeatures},
@@ -153,6 +150,7 @@ use tari_utilities::{hex, hex::Hex, message_format::MessageFormat};
 use tari_wallet::{
     contacts_service::storage::{database::Contact, sqlite_db::ContactsServiceSqliteDatabase},
     error::WalletError,
+    localization::LocaleConfig,
     output_manager_service::storage::sqlite_db::OutputManagerSqliteDatabase,
     storage::{connection_manager::run_migration_and_create_sqlite_connection, sqlite_db::WalletSqliteDatabase},
     testnet_utils::{
@@ -164,11 +162,14 @@ use tari_wallet::{
         mine_transaction,
         receive_test_transaction,
     },
-    transaction_service::storage::{
-        database::{InboundTransaction, OutboundTransaction, TransactionDatabase, TransactionStatus},
-        sqlite_db::TransactionServiceSqliteDatabase,
+    transaction_service::{
+        memo::MemoCipher,
+        storage::{
+            database::{InboundTransaction, OutboundTransaction, TransactionDatabase, TransactionStatus},
+            sqlite_db::TransactionServiceSqliteDatabase,
+        },
     },
-    util::emoji::EmojiId,
+    util::{emoji::EmojiId, payment_uri::PaymentUri},
     wallet::WalletConfig,
 };
 use tokio::runtime::Runtime;
@@ -186,6 +187,7 @@ pub type TariTransportType = tari_p2p::transport::TransportType;
 pub type TariPublicKey = tari_comms::types::CommsPublicKey;
 pub type TariPrivateKey = tari_comms::types::CommsSecretKey;
 pub type TariCommsConfig = tari_p2p::initialization::CommsConfig;
+pub type TariPaymentUri = tari_wallet::util::payment_uri::PaymentUri;
 
 pub struct TariContacts(Vec<TariContact>);
 
@@ -547,6 +549,244 @@ pub unsafe extern "C" fn emoji_id_to_public_key(emoji: *const c_char, error_out:
 
 /// -------------------------------------------------------------------------------------------- ///
 
+/// -------------------------------- Payment URI ----------------------------------------------- ///
+
+/// Creates a payment URI (`tari://<network>/<public key>?amount=&memo=`) that can be shared, e.g. as a QR code, to
+/// request a payment to the given public key
+///
+/// ## Arguments
+/// `network` - The name of the network, e.g. "rincewind"
+/// `public_key` - The pointer to the TariPublicKey that should be paid
+/// `amount` - The amount in MicroTari to request, zero if no amount is requested
+/// `memo` - The message the payment should be sent with, may be null if no message is requested
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns a pointer to a char array. Note that it returns empty if there was an error
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with the string to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn payment_uri_create(
+    network: *const c_char,
+    public_key: *mut TariPublicKey,
+    amount: c_ulonglong,
+    memo: *const c_char,
+    error_out: *mut c_int,
+) -> *mut c_char
+{
+    let mut error = 0;
+    let mut result = CString::new("").unwrap();
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if network.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("network".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return CString::into_raw(result);
+    }
+    if public_key.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("public_key".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return CString::into_raw(result);
+    }
+
+    let network = match CStr::from_ptr(network)
+        .to_str()
+        .map_err(|e| e.to_string())
+        .and_then(|n| Network::from_str(n).map_err(|e| e.to_string()))
+    {
+        Ok(n) => n,
+        Err(e) => {
+            error = LibWalletError::from(InterfaceError::InvalidPaymentUri(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return CString::into_raw(result);
+        },
+    };
+    let mut uri = PaymentUri::new(network, (*public_key).clone());
+    if amount > 0 {
+        uri = uri.with_amount(MicroTari::from(amount));
+    }
+    if !memo.is_null() {
+        match CStr::from_ptr(memo).to_str() {
+            Ok(m) => uri = uri.with_memo(m),
+            Err(e) => {
+                error = LibWalletError::from(InterfaceError::InvalidPaymentUri(e.to_string())).code;
+                ptr::swap(error_out, &mut error as *mut c_int);
+                return CString::into_raw(result);
+            },
+        }
+    }
+    result = CString::new(uri.to_string()).unwrap();
+    CString::into_raw(result)
+}
+
+/// Parses a payment URI
+///
+/// ## Arguments
+/// `uri` - The pointer to a char array holding the payment URI
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPaymentUri` - Returns a pointer to a TariPaymentUri. Note that it returns null on error.
+///
+/// # Safety
+/// The ```payment_uri_destroy``` method must be called when finished with a TariPaymentUri to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn payment_uri_from_string(uri: *const c_char, error_out: *mut c_int) -> *mut TariPaymentUri {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if uri.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("uri".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+
+    match CStr::from_ptr(uri)
+        .to_str()
+        .map_err(|e| e.to_string())
+        .and_then(|u| u.parse::<PaymentUri>().map_err(|e| e.to_string()))
+    {
+        Ok(uri) => Box::into_raw(Box::new(uri)),
+        Err(e) => {
+            error = LibWalletError::from(InterfaceError::InvalidPaymentUri(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            ptr::null_mut()
+        },
+    }
+}
+
+/// Gets the network name of a TariPaymentUri
+///
+/// ## Arguments
+/// `uri` - The pointer to a TariPaymentUri
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns a pointer to a char array. Note that it returns empty if uri is null
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with the string to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn payment_uri_get_network(uri: *mut TariPaymentUri, error_out: *mut c_int) -> *mut c_char {
+    let mut error = 0;
+    let mut result = CString::new("").unwrap();
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if uri.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("uri".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return CString::into_raw(result);
+    }
+    result = CString::new((*uri).network.to_string()).unwrap();
+    CString::into_raw(result)
+}
+
+/// Gets the public key that a TariPaymentUri requests a payment to
+///
+/// ## Arguments
+/// `uri` - The pointer to a TariPaymentUri
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut TariPublicKey` - Returns a pointer to a TariPublicKey. Note that it returns null if uri is null
+///
+/// # Safety
+/// The ```public_key_destroy``` method must be called when finished with a TariPublicKey to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn payment_uri_get_public_key(
+    uri: *mut TariPaymentUri,
+    error_out: *mut c_int,
+) -> *mut TariPublicKey
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if uri.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("uri".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new((*uri).public_key.clone()))
+}
+
+/// Gets the amount that a TariPaymentUri requests
+///
+/// ## Arguments
+/// `uri` - The pointer to a TariPaymentUri
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ulonglong` - Returns the amount in MicroTari, note that it will be zero if no amount is requested or uri is null
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn payment_uri_get_amount(uri: *mut TariPaymentUri, error_out: *mut c_int) -> c_ulonglong {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if uri.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("uri".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+    (*uri).amount.map(u64::from).unwrap_or(0) as c_ulonglong
+}
+
+/// Gets the message that a TariPaymentUri requests the payment to be sent with
+///
+/// ## Arguments
+/// `uri` - The pointer to a TariPaymentUri
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns a pointer to a char array. Note that it returns empty if there is no message or uri is null
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with the string to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn payment_uri_get_memo(uri: *mut TariPaymentUri, error_out: *mut c_int) -> *mut c_char {
+    let mut error = 0;
+    let mut result = CString::new("").unwrap();
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if uri.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("uri".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return CString::into_raw(result);
+    }
+    if let Some(memo) = (*uri).memo.as_ref() {
+        match CString::new(memo.as_str()) {
+            Ok(m) => result = m,
+            Err(e) => {
+                error = LibWalletError::from(InterfaceError::InvalidPaymentUri(e.to_string())).code;
+                ptr::swap(error_out, &mut error as *mut c_int);
+            },
+        }
+    }
+    CString::into_raw(result)
+}
+
+/// Frees memory for a TariPaymentUri
+///
+/// ## Arguments
+/// `uri` - The pointer to a TariPaymentUri
+///
+/// ## Returns
+/// `()` - Does not return a value, equivalent to void in C
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn payment_uri_destroy(uri: *mut TariPaymentUri) {
+    if !uri.is_null() {
+        Box::from_raw(uri);
+    }
+}
+
+/// -------------------------------------------------------------------------------------------- ///
+
 /// -------------------------------- Private Key ----------------------------------------------- ///
 
 /// Creates a TariPrivateKey from a ByteVector
@@ -2139,6 +2379,7 @@ pub unsafe extern "C" fn comms_config_create(
                         allow_test_addresses: true,
                         listener_liveness_whitelist_cidrs: Vec::new(),
                         listener_liveness_max_sessions: 0,
+                        peer_allow_list: None,
                     };
 
                     Box::into_raw(Box::new(config))
@@ -2176,6 +2417,42 @@ pub unsafe extern "C" fn comms_config_destroy(wc: *mut TariCommsConfig) {
 
 /// ---------------------------------------------------------------------------------------------- ///
 
+/// ------------------------------------- Logging ------------------------------------------------///
+
+/// Sets the callback that log records will be forwarded to. This can be used instead of, or in addition to, the
+/// `log_path` passed to `wallet_create` and can be called before or after the wallet is created.
+///
+/// ## Arguments
+/// `callback_log` - The callback function pointer matching the function signature. This will be called with the level
+/// (1 = Error, 2 = Warn, 3 = Info, 4 = Debug, 5 = Trace), target and message of every log record. The strings are only
+/// valid for the duration of the call and must not be freed. Pass a *null* pointer to stop forwarding log records.
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns true if the callback was set, false if logging could not be configured
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn log_set_callback(
+    callback_log: Option<unsafe extern "C" fn(c_int, *const c_char, *const c_char)>,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    match logging::set_log_callback(callback_log) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(InterfaceError::LoggingError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// ---------------------------------------------------------------------------------------------- ///
+
 /// ------------------------------------- Wallet -------------------------------------------------///
 
 /// Creates a TariWallet
@@ -2240,21 +2517,12 @@ pub unsafe extern "C" fn wallet_create(
     let factories = CryptoFactories::default();
     let w;
 
-    if let Some(path) = logging_path_string {
-        let logfile = FileAppender::builder()
-            .encoder(Box::new(PatternEncoder::new(
-                "{d(%Y-%m-%d %H:%M:%S.%f)} [{t}] {l:5} {m}{n}",
-            )))
-            .append(false)
-            .build(path.as_str())
-            .unwrap();
-
-        let lconfig = Config::builder()
-            .appender(Appender::builder().build("logfile", Box::new(logfile)))
-            .build(Root::builder().appender("logfile").build(LevelFilter::Debug))
-            .unwrap();
-
-        log4rs::init_config(lconfig).expect("Should be able to start logging");
+    if logging_path_string.is_some() {
+        if let Err(e) = logging::set_log_path(logging_path_string) {
+            error = LibWalletError::from(InterfaceError::LoggingError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return ptr::null_mut();
+        }
         debug!(target: LOG_TARGET, "Logging started");
     }
 
@@ -2284,6 +2552,8 @@ pub unsafe extern "C" fn wallet_create(
                     comms_config: (*config).clone(),
                     factories,
                     transaction_service_config: None,
+                    output_manager_service_config: None,
+                    locale_config: None,
                 },
                 runtime,
                 wallet_backend,
@@ -2297,6 +2567,7 @@ pub unsafe extern "C" fn wallet_create(
                     // Start Callback Handler
                     let callback_handler = CallbackHandler::new(
                         TransactionDatabase::new(transaction_backend),
+                        MemoCipher::new(w.comms.node_identity()),
                         w.transaction_service.get_event_stream_fused(),
                         w.output_manager_service.get_event_stream_fused(),
                         w.comms.shutdown_signal(),
@@ -3605,6 +3876,157 @@ pub unsafe extern "C" fn wallet_sync_with_base_node(wallet: *mut TariWallet, err
     }
 }
 
+/// Sets the locale of the wallet's user-facing messages. English messages are built in, messages in other locales are
+/// loaded from the `<locale>.json` file in `catalogue_dir`.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `locale` - The pointer to a char array containing the language tag of the locale, e.g. "en" or "pt-BR"
+/// `catalogue_dir` - The pointer to a char array containing the path of the message catalogue directory, may be null
+/// if only the built in English messages are used
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not, the current locale is kept if the new locale could not be loaded
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn wallet_set_locale(
+    wallet: *mut TariWallet,
+    locale: *const c_char,
+    catalogue_dir: *const c_char,
+    error_out: *mut c_int,
+) -> bool
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if locale.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("locale".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+    let locale_string = match CStr::from_ptr(locale).to_str() {
+        Ok(v) => v.to_owned(),
+        Err(_) => {
+            error = LibWalletError::from(InterfaceError::NullError("locale".to_string())).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return false;
+        },
+    };
+
+    let catalogue_dir = if catalogue_dir.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(catalogue_dir).to_str() {
+            Ok(v) => Some(PathBuf::from(v)),
+            Err(_) => {
+                error = LibWalletError::from(InterfaceError::NullError("catalogue_dir".to_string())).code;
+                ptr::swap(error_out, &mut error as *mut c_int);
+                return false;
+            },
+        }
+    };
+
+    match (*wallet).set_locale(&LocaleConfig {
+        locale: locale_string,
+        catalogue_dir,
+    }) {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Gets the description of a transaction status in the wallet's locale
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `status` - The transaction status as returned by `completed_transaction_get_status`
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns the pointer to the char array, note that it will return a pointer to an empty char array if
+/// wallet is null or the status is not valid
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with the string to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_transaction_status_description(
+    wallet: *mut TariWallet,
+    status: c_int,
+    error_out: *mut c_int,
+) -> *mut c_char
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut result = CString::new("").unwrap();
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return result.into_raw();
+    }
+
+    match TransactionStatus::try_from(status) {
+        Ok(status) => {
+            result = CString::new((*wallet).message_catalogue.transaction_status(&status)).unwrap_or(result);
+        },
+        Err(_) => {
+            error = LibWalletError::from(InterfaceError::InvalidTransactionStatus).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+        },
+    }
+    result.into_raw()
+}
+
+/// Gets the explanation of an error code in the wallet's locale, suitable for showing to the user
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `code` - The error code
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `*mut c_char` - Returns the pointer to the char array, note that it will return a pointer to an empty char array if
+/// wallet is null
+///
+/// # Safety
+/// The ```string_destroy``` method must be called when finished with the string to prevent a memory leak
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_error_description(
+    wallet: *mut TariWallet,
+    code: c_int,
+    error_out: *mut c_int,
+) -> *mut c_char
+{
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    let mut result = CString::new("").unwrap();
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return result.into_raw();
+    }
+
+    let description = (*wallet)
+        .message_catalogue
+        .get(&format!("error.{}", code))
+        .unwrap_or_else(|| error::error_description(code));
+    result = CString::new(description).unwrap_or(result);
+    result.into_raw()
+}
+
 /// Frees memory for a TariWallet
 ///
 /// ## Arguments
@@ -3677,6 +4099,15 @@ mod test {
             let c = Mutex::new(CallbackState::new());
             c
         };
+        static ref LOG_RECORDS_FFI: Mutex<Vec<(c_int, String, String)>> = Mutex::new(Vec::new());
+    }
+
+    unsafe extern "C" fn log_callback(level: c_int, target: *const c_char, message: *const c_char) {
+        assert_eq!(target.is_null(), false);
+        assert_eq!(message.is_null(), false);
+        let target = CStr::from_ptr(target).to_str().unwrap().to_string();
+        let message = CStr::from_ptr(message).to_str().unwrap().to_string();
+        LOG_RECORDS_FFI.lock().unwrap().push((level, target, message));
     }
 
     unsafe extern "C" fn received_tx_callback(tx: *mut TariPendingInboundTransaction) {
@@ -3990,6 +4421,47 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_payment_uri() {
+        unsafe {
+            let mut error = 0;
+            let error_ptr = &mut error as *mut c_int;
+            let private_key = private_key_generate();
+            let public_key = public_key_from_private_key(private_key, error_ptr);
+            let network = CString::new("rincewind").unwrap();
+            let memo = CString::new("Invoice 42").unwrap();
+            let uri_str = payment_uri_create(network.as_ptr(), public_key, 1000, memo.as_ptr(), error_ptr);
+            assert_eq!(error, 0);
+            assert!(CStr::from_ptr(uri_str)
+                .to_str()
+                .unwrap()
+                .ends_with("?amount=1000&memo=Invoice%2042"));
+
+            let uri = payment_uri_from_string(uri_str, error_ptr);
+            assert_eq!(error, 0);
+            let uri_network = payment_uri_get_network(uri, error_ptr);
+            assert_eq!(CStr::from_ptr(uri_network).to_str().unwrap(), "rincewind");
+            let uri_public_key = payment_uri_get_public_key(uri, error_ptr);
+            assert_eq!((*uri_public_key), (*public_key));
+            assert_eq!(payment_uri_get_amount(uri, error_ptr), 1000);
+            let uri_memo = payment_uri_get_memo(uri, error_ptr);
+            assert_eq!(CStr::from_ptr(uri_memo).to_str().unwrap(), "Invoice 42");
+            assert_eq!(error, 0);
+
+            let invalid = CString::new("tari://moonnet/abc").unwrap();
+            assert!(payment_uri_from_string(invalid.as_ptr(), error_ptr).is_null());
+            assert_eq!(error, 9);
+
+            string_destroy(uri_str);
+            string_destroy(uri_network);
+            string_destroy(uri_memo);
+            payment_uri_destroy(uri);
+            public_key_destroy(uri_public_key);
+            public_key_destroy(public_key);
+            private_key_destroy(private_key);
+        }
+    }
+
     #[test]
     fn test_contact_dont_panic() {
         unsafe {
@@ -4039,6 +4511,29 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_log_callback() {
+        unsafe {
+            let mut error = 0;
+            let error_ptr = &mut error as *mut c_int;
+            assert!(log_set_callback(Some(log_callback), error_ptr));
+            assert_eq!(error, 0);
+            info!(target: "wallet_ffi::test", "Forwarded to the callback");
+            assert!(log_set_callback(None, error_ptr));
+            assert_eq!(error, 0);
+            info!(target: "wallet_ffi::test", "Not forwarded to the callback");
+
+            let records = LOG_RECORDS_FFI.lock().unwrap();
+            let forwarded = records
+                .iter()
+                .filter(|(_, target, _)| target == "wallet_ffi::test")
+                .collect::<Vec<_>>();
+            assert_eq!(forwarded.len(), 1);
+            assert_eq!(forwarded[0].0, 3);
+            assert_eq!(forwarded[0].2, "Forwarded to the callback");
+        }
+    }
+
     #[test]
     fn test_wallet_ffi() {
         unsafe {
@@ -0,0 +1,136 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Wallet Logging
+//! Log records from the Rust side of the library can be written to a log file, forwarded to a callback provided by
+//! the client application, or both. Mobile sandboxes often do not allow the library to write to arbitrary paths, so
+//! the callback allows the client application to handle the records itself.
+//!
+//! The log file and the callback can be set in any order, before or after the wallet is created. The logging
+//! configuration is rebuilt every time one of them changes.
+//!
+//! ## Callback
+//! `callback_log(level, target, message)` - This will be called for every log record at `Debug` level or above.
+//! `level` is 1 for Error, 2 for Warn, 3 for Info, 4 for Debug and 5 for Trace. `target` is the module the record
+//! came from and `message` is the formatted log message. The strings are only valid for the duration of the call and
+//! must not be freed by the client application.
+
+use libc::{c_char, c_int};
+use log::{LevelFilter, Record};
+use log4rs::{
+    append::{file::FileAppender, Append},
+    config::{Appender, Config, Root},
+    encode::pattern::PatternEncoder,
+    Handle,
+};
+use std::{error::Error, ffi::CString, fmt, sync::Mutex};
+
+pub type LogCallback = unsafe extern "C" fn(c_int, *const c_char, *const c_char);
+
+const LOG_PATTERN: &str = "{d(%Y-%m-%d %H:%M:%S.%f)} [{t}] {l:5} {m}{n}";
+
+lazy_static! {
+    static ref LOGGING: Mutex<LoggingState> = Mutex::new(LoggingState::default());
+}
+
+#[derive(Default)]
+struct LoggingState {
+    handle: Option<Handle>,
+    log_path: Option<String>,
+    callback: Option<LogCallback>,
+}
+
+/// Write log records to the file at the given path, in addition to the log callback if one is set
+pub fn set_log_path(path: Option<String>) -> Result<(), String> {
+    let mut state = acquire_state();
+    state.log_path = path;
+    apply(&mut state)
+}
+
+/// Forward log records to the given callback, in addition to the log file if one is set. Passing `None` stops
+/// forwarding records.
+pub fn set_log_callback(callback: Option<LogCallback>) -> Result<(), String> {
+    let mut state = acquire_state();
+    state.callback = callback;
+    apply(&mut state)
+}
+
+fn acquire_state() -> std::sync::MutexGuard<'static, LoggingState> {
+    // Logging state is always left consistent, so a panic while the lock was held does not invalidate it
+    LOGGING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn apply(state: &mut LoggingState) -> Result<(), String> {
+    let mut config = Config::builder();
+    let mut root = Root::builder();
+    if let Some(path) = state.log_path.as_ref() {
+        let logfile = FileAppender::builder()
+            .encoder(Box::new(PatternEncoder::new(LOG_PATTERN)))
+            .append(false)
+            .build(path)
+            .map_err(|e| e.to_string())?;
+        config = config.appender(Appender::builder().build("logfile", Box::new(logfile)));
+        root = root.appender("logfile");
+    }
+    if let Some(callback) = state.callback {
+        config = config.appender(Appender::builder().build("callback", Box::new(CallbackAppender { callback })));
+        root = root.appender("callback");
+    }
+    let config = config
+        .build(root.build(LevelFilter::Debug))
+        .map_err(|e| e.to_string())?;
+
+    match state.handle.as_ref() {
+        Some(handle) => handle.set_config(config),
+        None => state.handle = Some(log4rs::init_config(config).map_err(|e| e.to_string())?),
+    }
+    Ok(())
+}
+
+/// A log4rs appender which passes every record to a callback provided by the client application
+struct CallbackAppender {
+    callback: LogCallback,
+}
+
+impl fmt::Debug for CallbackAppender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackAppender").finish()
+    }
+}
+
+impl Append for CallbackAppender {
+    fn append(&self, record: &Record) -> Result<(), Box<dyn Error + Sync + Send>> {
+        let target = to_c_string(record.target());
+        let message = to_c_string(&record.args().to_string());
+        unsafe {
+            (self.callback)(record.level() as c_int, target.as_ptr(), message.as_ptr());
+        }
+        Ok(())
+    }
+
+    fn flush(&self) {}
+}
+
+fn to_c_string(s: &str) -> CString {
+    // Interior nul bytes cannot be represented in a C string
+    CString::new(s.replace('\0', "")).expect("nul bytes were removed")
+}
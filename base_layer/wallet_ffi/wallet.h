@@ -71,6 +71,8 @@ struct TariPendingInboundTransaction;
 
 struct TariTransportType;
 
+struct TariPaymentUri;
+
 /// -------------------------------- Transport Types ----------------------------------------------- ///
 
 // Creates a memory transport type
@@ -140,6 +142,29 @@ char *public_key_to_emoji_id(struct TariPublicKey *pk, int* error_out);
 // Converts a char array in emoji format to a public key
 struct TariPublicKey *emoji_id_to_public_key(const char *emoji,  int* error_out);
 
+/// -------------------------------- TariPaymentUri ----------------------------------------------- ///
+
+// Creates a payment URI (tari://<network>/<public key>?amount=&memo=), amount may be 0 and memo may be null
+char *payment_uri_create(const char *network, struct TariPublicKey *public_key, unsigned long long amount, const char *memo, int* error_out);
+
+// Parses a payment URI
+struct TariPaymentUri *payment_uri_from_string(const char *uri, int* error_out);
+
+// Gets the network name of a TariPaymentUri
+char *payment_uri_get_network(struct TariPaymentUri *uri, int* error_out);
+
+// Gets the public key that a TariPaymentUri requests a payment to
+struct TariPublicKey *payment_uri_get_public_key(struct TariPaymentUri *uri, int* error_out);
+
+// Gets the amount in MicroTari that a TariPaymentUri requests, 0 if there is no amount
+unsigned long long payment_uri_get_amount(struct TariPaymentUri *uri, int* error_out);
+
+// Gets the message that a TariPaymentUri requests the payment to be sent with, empty if there is no message
+char *payment_uri_get_memo(struct TariPaymentUri *uri, int* error_out);
+
+// Frees memory for a TariPaymentUri
+void payment_uri_destroy(struct TariPaymentUri *uri);
+
 /// -------------------------------- TariPrivateKey ----------------------------------------------- ///
 
 // Creates a TariPrivateKey from a ByteVector
@@ -329,6 +354,11 @@ struct TariCommsConfig *comms_config_create(char *public_address,
 // Frees memory for a TariCommsConfig
 void comms_config_destroy(struct TariCommsConfig *wc);
 
+/// -------------------------------- Logging ----------------------------------------------- //
+
+// Sets the callback that log records (level, target, message) are forwarded to, pass null to stop forwarding
+bool log_set_callback(void (*callback_log)(int, const char*, const char*), int* error_out);
+
 /// -------------------------------- TariWallet ----------------------------------------------- //
 
 // Creates a TariWallet
@@ -423,6 +453,15 @@ bool wallet_test_mine_transaction(struct TariWallet *wallet, unsigned long long
 // Simulates a TariPendingInboundtransaction being received
 bool wallet_test_receive_transaction(struct TariWallet *wallet,int* error_out);
 
+// Sets the locale of the wallet's user-facing messages, catalogue_dir may be null if only English messages are used
+bool wallet_set_locale(struct TariWallet *wallet, const char *locale, const char *catalogue_dir, int* error_out);
+
+// Gets the description of a transaction status in the wallet's locale
+char *wallet_get_transaction_status_description(struct TariWallet *wallet, int status, int* error_out);
+
+// Gets the explanation of an error code in the wallet's locale
+char *wallet_get_error_description(struct TariWallet *wallet, int code, int* error_out);
+
 // Frees memory for a TariWallet
 void wallet_destroy(struct TariWallet *wallet);
 
@@ -0,0 +1,219 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A builder for [GlobalConfig] that starts from the same defaults as the configuration file, so that applications and
+//! tests can create a configuration in code without writing a `config.toml`.
+//!
+//! ```edition2018
+//! # use tari_common::{GlobalConfig, Network};
+//! # use std::path::PathBuf;
+//! let config = GlobalConfig::builder()
+//!     .network(Network::LocalNet)
+//!     .data_dir(PathBuf::from("/tmp/tari/localnet"))
+//!     .core_threads(2)
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(config.network, Network::LocalNet);
+//! assert_eq!(config.core_threads, 2);
+//! ```
+//!
+//! Unlike [GlobalConfig::convert_from], environment variables are not read.
+
+use crate::{
+    configuration::{config_string, convert_node_config},
+    default_config,
+    dir_utils,
+    ConfigBootstrap,
+    ConfigurationError,
+    GlobalConfig,
+    Network,
+};
+use config::Value;
+use multiaddr::Multiaddr;
+use std::path::{Path, PathBuf};
+
+/// Builds a [GlobalConfig]. Settings that are not given keep the defaults of [default_config].
+pub struct GlobalConfigBuilder {
+    base_path: PathBuf,
+    network: Network,
+    /// Settings of the `[base_node.<network>]` section, keyed without the section prefix
+    node_settings: Vec<(String, Value)>,
+    /// Settings keyed with their full path, e.g. `common.message_cache_size`
+    settings: Vec<(String, Value)>,
+}
+
+impl Default for GlobalConfigBuilder {
+    fn default() -> Self {
+        Self {
+            base_path: dir_utils::default_path("", None),
+            network: Network::MainNet,
+            node_settings: Vec::new(),
+            settings: Vec::new(),
+        }
+    }
+}
+
+impl GlobalConfigBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The network whose `[base_node.<network>]` section the node settings are read from
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// The base directory the default data, identity and database paths are created in
+    pub fn base_path<P: AsRef<Path>>(mut self, base_path: P) -> Self {
+        self.base_path = base_path.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn data_dir<P: AsRef<Path>>(self, data_dir: P) -> Self {
+        self.node_setting("data_dir", path_value(data_dir))
+    }
+
+    /// Either `lmdb` or `memory`
+    pub fn db_type(self, db_type: &str) -> Self {
+        self.node_setting("db_type", db_type)
+    }
+
+    pub fn core_threads(self, core_threads: usize) -> Self {
+        self.node_setting("core_threads", core_threads as i64)
+    }
+
+    pub fn blocking_threads(self, blocking_threads: usize) -> Self {
+        self.node_setting("blocking_threads", blocking_threads as i64)
+    }
+
+    pub fn identity_file<P: AsRef<Path>>(self, identity_file: P) -> Self {
+        self.node_setting("identity_file", path_value(identity_file))
+    }
+
+    pub fn wallet_identity_file<P: AsRef<Path>>(self, wallet_identity_file: P) -> Self {
+        self.node_setting("wallet_identity_file", path_value(wallet_identity_file))
+    }
+
+    pub fn public_address(self, public_address: &Multiaddr) -> Self {
+        self.node_setting("public_address", public_address.to_string())
+    }
+
+    pub fn peer_seeds(self, peer_seeds: Vec<String>) -> Self {
+        self.node_setting("peer_seeds", peer_seeds)
+    }
+
+    /// Use the TCP transport, listening on the given address
+    pub fn tcp_transport(self, listener_address: &Multiaddr) -> Self {
+        self.node_setting("transport", "tcp")
+            .node_setting("tcp_listener_address", listener_address.to_string())
+    }
+
+    pub fn enable_mining(self, enable_mining: bool) -> Self {
+        self.node_setting("enable_mining", enable_mining)
+    }
+
+    /// Set any other key of the `[base_node.<network>]` section, e.g. `node_setting("num_mining_threads", 2)`
+    pub fn node_setting<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> Self {
+        self.node_settings.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set a key outside of the `[base_node.<network>]` section, e.g. `setting("common.message_cache_size", 20)`
+    pub fn setting<K: Into<String>, V: Into<Value>>(mut self, key: K, value: V) -> Self {
+        self.settings.push((key.into(), value.into()));
+        self
+    }
+
+    /// Validate the settings and create the configuration
+    pub fn build(self) -> Result<GlobalConfig, ConfigurationError> {
+        let bootstrap = ConfigBootstrap {
+            base_path: self.base_path.clone(),
+            config: self.base_path.join(crate::DEFAULT_CONFIG),
+            log_config: self.base_path.join(crate::DEFAULT_LOG_CONFIG),
+            log_format: Default::default(),
+            network: Some(self.network.clone()),
+            secrets_file: self.base_path.join(crate::DEFAULT_SECRETS_FILE),
+            secrets: Default::default(),
+        };
+        let mut cfg = default_config(&bootstrap);
+        let network = self.network.to_string();
+        let node_settings = self
+            .node_settings
+            .into_iter()
+            .map(|(key, value)| (config_string(&network, &key), value));
+        for (key, value) in self.settings.into_iter().chain(node_settings) {
+            cfg.set(&key, value)
+                .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+        }
+        cfg.set("base_node.network", network)
+            .map_err(|e| ConfigurationError::new("base_node.network", &e.to_string()))?;
+        convert_node_config(self.network, cfg)
+    }
+}
+
+fn path_value<P: AsRef<Path>>(path: P) -> String {
+    path.as_ref().to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CommsTransport, DatabaseType};
+
+    #[test]
+    fn build_overrides_the_defaults() {
+        let config = GlobalConfigBuilder::new()
+            .network(Network::LocalNet)
+            .base_path("/tmp/tari_test")
+            .data_dir("/tmp/tari_test/data")
+            .db_type("memory")
+            .tcp_transport(&"/ip4/127.0.0.1/tcp/18189".parse().unwrap())
+            .node_setting("num_mining_threads", 3i64)
+            .build()
+            .unwrap();
+        assert_eq!(config.network, Network::LocalNet);
+        assert_eq!(config.data_dir, PathBuf::from("/tmp/tari_test/data"));
+        assert_eq!(config.peer_db_path, PathBuf::from("/tmp/tari_test/data/peer_db"));
+        assert!(matches_memory(&config.db_type));
+        assert_eq!(config.num_mining_threads, 3);
+        match config.comms_transport {
+            CommsTransport::Tcp { listener_address, .. } => {
+                assert_eq!(listener_address.to_string(), "/ip4/127.0.0.1/tcp/18189")
+            },
+            transport => panic!("Unexpected transport: {:?}", transport),
+        }
+    }
+
+    #[test]
+    fn build_reports_invalid_settings() {
+        let err = GlobalConfigBuilder::new().db_type("flatfile").build().unwrap_err();
+        assert!(err.to_string().contains("base_node.mainnet.db_type"));
+    }
+
+    fn matches_memory(db_type: &DatabaseType) -> bool {
+        match db_type {
+            DatabaseType::Memory => true,
+            DatabaseType::LMDB(_) => false,
+        }
+    }
+}
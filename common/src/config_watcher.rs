@@ -0,0 +1,182 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Watches the configuration file and broadcasts changes to running services.
+//!
+//! The [ConfigWatcher] polls the configuration file and, whenever its contents change, reloads it into a new
+//! [GlobalConfig] and publishes a [ConfigUpdate] containing the previous and the new configuration. Services
+//! [subscribe](ConfigWatcher::subscribe) to the updates and apply only the subset of settings that are safe to change
+//! while they are running; everything else still needs a restart. If the changed file cannot be loaded, the error is
+//! logged and the running configuration is kept.
+//!
+//! Log levels are not handled here. log4rs reloads its own configuration file (see `refresh_rate` in `log4rs.yml`).
+
+use crate::{load_configuration, ConfigBootstrap, GlobalConfig};
+use log::*;
+use std::{fs, sync::Arc, time::Duration};
+use tokio::{sync::broadcast, time};
+
+const LOG_TARGET: &str = "common::config_watcher";
+/// The number of updates a slow subscriber can fall behind before it starts missing them
+const UPDATE_CHANNEL_SIZE: usize = 10;
+
+/// A change to the configuration file
+#[derive(Clone, Debug)]
+pub struct ConfigUpdate {
+    pub previous: Arc<GlobalConfig>,
+    pub current: Arc<GlobalConfig>,
+}
+
+impl ConfigUpdate {
+    /// Returns true if the value selected by `field` differs between the previous and the current configuration
+    pub fn changed<T, F>(&self, field: F) -> bool
+    where
+        T: PartialEq,
+        F: Fn(&GlobalConfig) -> T,
+    {
+        field(&self.previous) != field(&self.current)
+    }
+}
+
+pub type ConfigUpdateReceiver = broadcast::Receiver<ConfigUpdate>;
+
+pub struct ConfigWatcher {
+    bootstrap: ConfigBootstrap,
+    poll_interval: Duration,
+    current: Arc<GlobalConfig>,
+    last_contents: Option<Vec<u8>>,
+    sender: broadcast::Sender<ConfigUpdate>,
+}
+
+impl ConfigWatcher {
+    /// Create a watcher for the configuration file in `bootstrap`. `config` is the configuration that is currently
+    /// in use and should have been loaded from that file.
+    pub fn new(bootstrap: ConfigBootstrap, config: GlobalConfig, poll_interval: Duration) -> Self {
+        let last_contents = fs::read(&bootstrap.config).ok();
+        let (sender, _) = broadcast::channel(UPDATE_CHANNEL_SIZE);
+        Self {
+            bootstrap,
+            poll_interval,
+            current: Arc::new(config),
+            last_contents,
+            sender,
+        }
+    }
+
+    /// Subscribe to configuration updates
+    pub fn subscribe(&self) -> ConfigUpdateReceiver {
+        self.sender.subscribe()
+    }
+
+    /// The configuration that is currently in use
+    pub fn current(&self) -> Arc<GlobalConfig> {
+        self.current.clone()
+    }
+
+    /// Check the configuration file once. Returns the update if the file has changed and loaded successfully, or None
+    /// if it is unchanged. A file that fails to load is only reported once, until it changes again.
+    pub fn poll(&mut self) -> Result<Option<ConfigUpdate>, String> {
+        let contents = fs::read(&self.bootstrap.config).ok();
+        if contents.is_none() || contents == self.last_contents {
+            return Ok(None);
+        }
+        self.last_contents = contents;
+
+        let config = load_configuration(&self.bootstrap)?;
+        // Loading upgrades files written for older versions, which is not a change to report again
+        self.last_contents = fs::read(&self.bootstrap.config).ok();
+        let config = GlobalConfig::convert_from(config).map_err(|err| err.to_string())?;
+        let update = ConfigUpdate {
+            previous: self.current.clone(),
+            current: Arc::new(config),
+        };
+        self.current = update.current.clone();
+        Ok(Some(update))
+    }
+
+    /// Poll the configuration file until every subscriber has gone away
+    pub async fn run(mut self) {
+        info!(
+            target: LOG_TARGET,
+            "Watching '{}' for configuration changes every {:.0?}",
+            self.bootstrap.config.to_string_lossy(),
+            self.poll_interval
+        );
+        let mut interval = time::interval(self.poll_interval);
+        loop {
+            interval.tick().await;
+            match self.poll() {
+                Ok(Some(update)) => {
+                    info!(target: LOG_TARGET, "Configuration file changed. Applying live settings.");
+                    if self.sender.send(update).is_err() {
+                        debug!(target: LOG_TARGET, "No subscribers left. Config watcher stopping.");
+                        break;
+                    }
+                },
+                Ok(None) => {},
+                Err(err) => warn!(
+                    target: LOG_TARGET,
+                    "The changed configuration file could not be loaded and will be ignored. {}", err
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_test_utils::random::string;
+    use tempdir::TempDir;
+
+    #[test]
+    fn poll_reports_changes_to_the_config_file() {
+        let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+        let bootstrap = ConfigBootstrap {
+            base_path: temp_dir.path().to_path_buf(),
+            config: temp_dir.path().join("config.toml"),
+            log_config: temp_dir.path().join("log4rs.yml"),
+            log_format: Default::default(),
+            network: None,
+            secrets_file: temp_dir.path().join("secrets.enc"),
+            secrets: Default::default(),
+        };
+        fs::write(&bootstrap.config, "[common]\n").unwrap();
+        let config = GlobalConfig::convert_from(load_configuration(&bootstrap).unwrap()).unwrap();
+        let mut watcher = ConfigWatcher::new(bootstrap, config, Duration::from_secs(1));
+        assert!(watcher.poll().unwrap().is_none());
+
+        let config_file = watcher.bootstrap.config.clone();
+        fs::write(&config_file, "[base_node.mainnet]\nmin_free_disk_space_mb = 2048\n").unwrap();
+        let update = watcher.poll().unwrap().unwrap();
+        assert!(update.changed(|c| c.min_free_disk_space_mb));
+        assert!(!update.changed(|c| c.network));
+        assert_eq!(watcher.current().min_free_disk_space_mb, Some(2048));
+        assert!(watcher.poll().unwrap().is_none());
+
+        // A broken file is reported once and the running configuration is kept
+        fs::write(&config_file, "[base_node.mainnet\nmin_free_disk_space_mb = 512\n").unwrap();
+        assert!(watcher.poll().is_err());
+        assert!(watcher.poll().unwrap().is_none());
+        assert_eq!(watcher.current().min_free_disk_space_mb, Some(2048));
+    }
+}
@@ -21,8 +21,8 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //
 
-use crate::{dir_utils::default_subdir, ConfigBootstrap};
-use config::{Config, Environment};
+use crate::{dir_utils::default_subdir, secrets, ConfigBootstrap, GlobalConfigBuilder};
+use config::{Config, Environment, FileFormat};
 use log::*;
 use multiaddr::{Multiaddr, Protocol};
 use std::{
@@ -31,7 +31,8 @@ use std::{
     fmt::{Display, Formatter, Result as FormatResult},
     fs,
     net::IpAddr,
-    num::{NonZeroU16, TryFromIntError},
+    num::{NonZeroU16, ParseIntError, TryFromIntError},
+    ops::Range,
     path::{Path, PathBuf},
     str::FromStr,
 };
@@ -47,6 +48,9 @@ pub fn load_configuration(bootstrap: &ConfigBootstrap) -> Result<Config, String>
         bootstrap.config.to_str().unwrap_or("[??]")
     );
     let mut cfg = default_config(bootstrap);
+    // Upgrade files written by older versions, so that renamed settings are not silently replaced by their defaults
+    migrate_config_file(&bootstrap.config)
+        .map_err(|e| format!("There was an error upgrading the configuration file. {}", e))?;
     // Load the configuration file
     let filename = bootstrap
         .config
@@ -56,13 +60,24 @@ pub fn load_configuration(bootstrap: &ConfigBootstrap) -> Result<Config, String>
     match cfg.merge(config_file) {
         Ok(_) => {
             info!(target: LOG_TARGET, "Configuration file loaded.");
-            Ok(cfg)
         },
-        Err(e) => Err(format!(
-            "There was an error loading the configuration file. {}",
-            e.to_string()
-        )),
+        Err(e) => {
+            return Err(format!(
+                "There was an error loading the configuration file. {}",
+                e.to_string()
+            ))
+        },
+    }
+    // A network given on the command line takes precedence over the one in the configuration file
+    if let Some(network) = &bootstrap.network {
+        info!(target: LOG_TARGET, "Using the [base_node.{}] configuration section", network);
+        cfg.set("base_node.network", network.to_string())
+            .map_err(|e| format!("Could not select the {} network. {}", network, e.to_string()))?;
     }
+    // Sensitive settings may refer to secrets that are kept outside of the configuration file
+    secrets::resolve_config_secrets(&mut cfg, &bootstrap.secrets)
+        .map_err(|(key, e)| format!("Could not resolve the secret for {}. {}", key, e))?;
+    Ok(cfg)
 }
 
 /// Installs a new configuration file template, copied from `rincewind-simple.toml` to the given path.
@@ -76,6 +91,7 @@ pub fn install_default_config_file(path: &Path) -> Result<(), std::io::Error> {
 pub enum Network {
     MainNet,
     Rincewind,
+    LocalNet,
 }
 
 impl FromStr for Network {
@@ -85,6 +101,7 @@ impl FromStr for Network {
         match value.to_lowercase().as_str() {
             "rincewind" => Ok(Self::Rincewind),
             "mainnet" => Ok(Self::MainNet),
+            "localnet" => Ok(Self::LocalNet),
             invalid => Err(ConfigurationError::new(
                 "network",
                 &format!("Invalid network option: {}", invalid),
@@ -98,6 +115,7 @@ impl Display for Network {
         let msg = match self {
             Self::MainNet => "mainnet",
             Self::Rincewind => "rincewind",
+            Self::LocalNet => "localnet",
         };
         f.write_str(msg)
     }
@@ -143,6 +161,7 @@ pub trait ConfigExtractor {
     where Self: Sized;
 }
 //---------------------------------------------      Database type        ------------------------------------------//
+/// The blockchain database backend, selected with the `db_type` config setting. LMDB is used when it is not set.
 #[derive(Debug)]
 pub enum DatabaseType {
     LMDB(PathBuf),
@@ -191,6 +210,8 @@ impl FromStr for TorControlAuthentication {
 pub enum SocksAuthentication {
     None,
     UsernamePassword(String, String),
+    /// Use distinct credentials for each destination, so that tor isolates every peer connection on its own circuit
+    Isolated,
 }
 
 impl FromStr for SocksAuthentication {
@@ -200,6 +221,7 @@ impl FromStr for SocksAuthentication {
         let (auth_type, maybe_value) = parse_key_value(s, '=');
         match auth_type.as_str() {
             "none" => Ok(SocksAuthentication::None),
+            "isolated" => Ok(SocksAuthentication::Isolated),
             "username_password" => {
                 let (username, password) = maybe_value
                     .and_then(|value| {
@@ -239,6 +261,8 @@ pub enum CommsTransport {
         forward_address: Multiaddr,
         auth: TorControlAuthentication,
         onion_port: NonZeroU16,
+        /// Authentication to use for the tor SOCKS5 proxy
+        socks_auth: SocksAuthentication,
     },
     /// Use a SOCKS5 proxy transport. This transport recognises any addresses supported by the proxy.
     Socks5 {
@@ -261,20 +285,75 @@ pub struct GlobalConfig {
     pub core_threads: usize,
     pub blocking_threads: usize,
     pub identity_file: PathBuf,
+    pub identity_passphrase: Option<String>,
     pub public_address: Multiaddr,
+    pub public_address_check_url: Option<String>,
+    pub public_address_check_interval: u64,
+    pub resource_check_interval: u64,
+    pub min_free_disk_space_mb: Option<u64>,
+    pub critical_free_disk_space_mb: Option<u64>,
+    pub max_memory_usage_mb: Option<u64>,
     pub peer_seeds: Vec<String>,
     pub peer_db_path: PathBuf,
+    pub blocklist_feed_path: Option<PathBuf>,
+    pub blocklist_feed_public_key: Option<String>,
+    pub blocklist_allowlist: Vec<String>,
+    pub peer_allow_list_only: bool,
+    pub peer_allow_list: Vec<String>,
+    pub peer_allow_list_file: Option<PathBuf>,
+    pub dht_join_startup_delay: Option<u64>,
+    pub dht_join_min_interval: Option<u64>,
+    pub dht_join_reannounce_interval: Option<u64>,
     pub block_sync_strategy: String,
+    pub header_verification_threads: Option<usize>,
+    pub block_validation_log_threshold_ms: Option<u64>,
+    pub max_headers_per_response: Option<usize>,
+    pub max_blocks_per_response: Option<usize>,
+    pub max_kernels_per_response: Option<usize>,
+    pub max_utxos_per_response: Option<usize>,
+    pub mempool_max_list_page_size: Option<usize>,
+    pub relay_min_fee_per_gram: Option<u64>,
+    pub relay_max_tx_weight: Option<u64>,
+    pub relay_non_standard_features: Option<bool>,
     pub enable_mining: bool,
     pub num_mining_threads: usize,
+    pub coinbase_extra: Option<String>,
     pub tor_identity_file: PathBuf,
     pub wallet_db_file: PathBuf,
     pub wallet_identity_file: PathBuf,
     pub wallet_tor_identity_file: PathBuf,
     pub wallet_peer_db_path: PathBuf,
+    pub wallet_num_confirmations_required: Option<u64>,
+    pub wallet_dust_threshold: Option<u64>,
+    pub wallet_output_recovery_byte: Option<u8>,
+    pub wallet_min_inbound_amount: Option<u64>,
+    pub wallet_max_inbound_transactions_per_peer: Option<usize>,
+    pub wallet_inbound_throttle_period: Option<u64>,
+    pub wallet_zero_conf_min_fee: Option<u64>,
+    pub wallet_backup_target: Option<String>,
+    pub wallet_backup_s3_endpoint: Option<String>,
+    pub wallet_backup_passphrase: Option<String>,
+    pub wallet_backup_interval: Option<u64>,
+    pub wallet_backup_retention: Option<usize>,
+    pub wallet_backup_incremental_count: Option<usize>,
+    pub wallet_spend_unlock_period: Option<u64>,
+    pub wallet_locale: Option<String>,
+    pub wallet_locale_dir: Option<PathBuf>,
+    pub notification_webhook_urls: Vec<String>,
+    pub notification_hook_command: Option<PathBuf>,
+    pub notification_events: Vec<String>,
+    pub notification_reorg_depth_threshold: Option<u64>,
+    pub notification_min_peer_count: Option<usize>,
+    pub genesis_block_file: Option<PathBuf>,
+    pub max_block_weight_schedule: Option<Vec<(u64, u64)>>,
 }
 
 impl GlobalConfig {
+    /// Create a configuration in code, starting from the defaults. See [GlobalConfigBuilder].
+    pub fn builder() -> GlobalConfigBuilder {
+        GlobalConfigBuilder::new()
+    }
+
     pub fn convert_from(mut cfg: Config) -> Result<Self, ConfigurationError> {
         let network = cfg
             .get_str("base_node.network")
@@ -289,14 +368,14 @@ impl GlobalConfig {
     }
 }
 
-fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, ConfigurationError> {
+pub(crate) fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, ConfigurationError> {
     let net_str = network.to_string().to_lowercase();
 
     let key = config_string(&net_str, "db_type");
     let db_type = cfg
         .get_str(&key)
         .map(|s| s.to_lowercase())
-        .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+        .unwrap_or_else(|_| "lmdb".to_string());
 
     let key = config_string(&net_str, "data_dir");
     let data_dir: PathBuf = cfg
@@ -308,8 +387,8 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         "memory" => Ok(DatabaseType::Memory),
         "lmdb" => Ok(DatabaseType::LMDB(data_dir.join("db"))),
         invalid_opt => Err(ConfigurationError::new(
-            "base_node.db_type",
-            &format!("Invalid option: {}", invalid_opt),
+            &config_string(&net_str, "db_type"),
+            &format!("Invalid option: {}. Supported backends are: lmdb, memory", invalid_opt),
         )),
     }?;
 
@@ -331,6 +410,10 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
         .into();
 
+    // Passphrase used to encrypt the secret keys in the node and wallet identity files (optional)
+    let key = config_string(&net_str, "identity_passphrase");
+    let identity_passphrase = cfg.get_str(&key).ok();
+
     // Wallet identity path
     let key = config_string(&net_str, "wallet_identity_file");
     let wallet_identity_file = cfg
@@ -364,6 +447,22 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
                 .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
         })?;
 
+    // Public address change detection (optional)
+    let key = config_string(&net_str, "public_address_check_url");
+    let public_address_check_url = cfg.get_str(&key).ok();
+    let key = config_string(&net_str, "public_address_check_interval");
+    let public_address_check_interval = cfg.get_int(&key).map(|v| v as u64).unwrap_or(300);
+
+    // Resource monitoring (optional)
+    let key = config_string(&net_str, "resource_check_interval");
+    let resource_check_interval = cfg.get_int(&key).map(|v| v as u64).unwrap_or(30);
+    let key = config_string(&net_str, "min_free_disk_space_mb");
+    let min_free_disk_space_mb = cfg.get_int(&key).ok().map(|v| v as u64);
+    let key = config_string(&net_str, "critical_free_disk_space_mb");
+    let critical_free_disk_space_mb = cfg.get_int(&key).ok().map(|v| v as u64);
+    let key = config_string(&net_str, "max_memory_usage_mb");
+    let max_memory_usage_mb = cfg.get_int(&key).ok().map(|v| v as u64);
+
     // Peer seeds
     let key = config_string(&net_str, "peer_seeds");
     let peer_seeds = cfg
@@ -373,12 +472,93 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
 
     // Peer DB path
     let peer_db_path = data_dir.join("peer_db");
+
+    // Blocklist feed (optional)
+    let key = config_string(&net_str, "blocklist_feed_path");
+    let blocklist_feed_path = cfg.get_str(&key).ok().map(PathBuf::from);
+    let key = config_string(&net_str, "blocklist_feed_public_key");
+    let blocklist_feed_public_key = cfg.get_str(&key).ok();
+    let key = config_string(&net_str, "blocklist_allowlist");
+    let blocklist_allowlist = cfg
+        .get_array(&key)
+        .map(|values| values.into_iter().filter_map(|v| v.into_str().ok()).collect())
+        .unwrap_or_default();
+
+    // Peer allow list for private networks (optional)
+    let peer_allow_list_only = cfg.get_bool("comms.allow_list_only").unwrap_or(false);
+    let peer_allow_list = cfg
+        .get_array("comms.allow_list")
+        .map(|values| values.into_iter().filter_map(|v| v.into_str().ok()).collect())
+        .unwrap_or_default();
+    let peer_allow_list_file = cfg.get_str("comms.allow_list_file").ok().map(PathBuf::from);
+
+    // DHT join/announce policy (optional)
+    let dht_join_startup_delay = cfg.get_int("comms.dht_join_startup_delay").ok().map(|v| v as u64);
+    let dht_join_min_interval = cfg.get_int("comms.dht_join_min_interval").ok().map(|v| v as u64);
+    let dht_join_reannounce_interval = cfg.get_int("comms.dht_join_reannounce_interval").ok().map(|v| v as u64);
     let wallet_peer_db_path = data_dir.join("wallet_peer_db");
 
+    // Custom genesis block (optional, localnet only)
+    let key = config_string(&net_str, "genesis_block_file");
+    let genesis_block_file = cfg.get_str(&key).ok().map(PathBuf::from);
+
+    // Maximum block weight schedule as [height, weight] pairs (optional, testnets only)
+    let key = config_string(&net_str, "max_block_weight_schedule");
+    let max_block_weight_schedule = match cfg.get_array(&key) {
+        Ok(entries) => Some(
+            entries
+                .into_iter()
+                .map(|entry| {
+                    let pair = entry.into_array().ok().and_then(|pair| match pair.as_slice() {
+                        [height, weight] => Some((
+                            height.clone().into_int().ok()? as u64,
+                            weight.clone().into_int().ok()? as u64,
+                        )),
+                        _ => None,
+                    });
+                    pair.ok_or_else(|| ConfigurationError::new(&key, "Entries must be [height, weight] pairs"))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Err(_) => None,
+    };
+
     let key = config_string(&net_str, "block_sync_strategy");
     let block_sync_strategy = cfg
         .get_str(&key)
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?;
+    let key = config_string(&net_str, "header_verification_threads");
+    let header_verification_threads = cfg.get_int(&key).ok().map(|v| v as usize);
+    // Log the validation timings of blocks slower than this (optional)
+    let key = config_string(&net_str, "block_validation_log_threshold_ms");
+    let block_validation_log_threshold_ms = cfg.get_int(&key).ok().map(|v| v as u64);
+
+    // Limits on the number of items returned for a single request (optional)
+    let key = config_string(&net_str, "max_headers_per_response");
+    let max_headers_per_response = cfg.get_int(&key).ok().map(|v| v as usize);
+    let key = config_string(&net_str, "max_blocks_per_response");
+    let max_blocks_per_response = cfg.get_int(&key).ok().map(|v| v as usize);
+    let key = config_string(&net_str, "max_kernels_per_response");
+    let max_kernels_per_response = cfg.get_int(&key).ok().map(|v| v as usize);
+    let key = config_string(&net_str, "max_utxos_per_response");
+    let max_utxos_per_response = cfg.get_int(&key).ok().map(|v| v as usize);
+    let mempool_max_list_page_size = cfg
+        .get_int(&format!("mempool.{}.max_list_page_size", net_str))
+        .ok()
+        .map(|v| v as usize);
+
+    // Relay policy (optional, the mempool defaults are used for missing values)
+    let relay_min_fee_per_gram = cfg
+        .get_int(&format!("relay.{}.min_fee_per_gram", net_str))
+        .ok()
+        .map(|v| v as u64);
+    let relay_max_tx_weight = cfg
+        .get_int(&format!("relay.{}.max_tx_weight", net_str))
+        .ok()
+        .map(|v| v as u64);
+    let relay_non_standard_features = cfg
+        .get_bool(&format!("relay.{}.relay_non_standard_features", net_str))
+        .ok();
 
     // set base node mining
     let key = config_string(&net_str, "enable_mining");
@@ -391,6 +571,9 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         .get_int(&key)
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))? as usize;
 
+    // The extra data the miner tags its coinbase kernels with (optional)
+    let coinbase_extra = cfg.get_str(&config_string(&net_str, "coinbase_extra")).ok();
+
     // set wallet_file
     let key = "wallet.wallet_file".to_string();
     let wallet_db_file = cfg
@@ -398,6 +581,53 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         .map_err(|e| ConfigurationError::new(&key, &e.to_string()))?
         .into();
 
+    // The number of confirmations a wallet transaction requires before it is marked as mined (optional)
+    let wallet_num_confirmations_required = cfg
+        .get_int("wallet.transaction_num_confirmations_required")
+        .ok()
+        .map(|v| v as u64);
+    // The dust threshold in µT (optional)
+    let wallet_dust_threshold = cfg.get_int("wallet.dust_threshold").ok().map(|v| v as u64);
+    // The byte written into the rewindable range proofs of the wallet's outputs (optional)
+    let wallet_output_recovery_byte = cfg.get_int("wallet.output_recovery_byte").ok().map(|v| v as u8);
+    // Inbound transaction spam protection (optional)
+    let wallet_min_inbound_amount = cfg.get_int("wallet.min_inbound_amount").ok().map(|v| v as u64);
+    let wallet_max_inbound_transactions_per_peer = cfg
+        .get_int("wallet.max_inbound_transactions_per_peer")
+        .ok()
+        .map(|v| v as usize);
+    let wallet_inbound_throttle_period = cfg.get_int("wallet.inbound_throttle_period").ok().map(|v| v as u64);
+    // The minimum fee in µT for received funds to be available before they are mined (optional)
+    let wallet_zero_conf_min_fee = cfg.get_int("wallet.zero_conf_min_fee").ok().map(|v| v as u64);
+    // Automatic wallet backups (optional)
+    let wallet_backup_target = cfg.get_str("wallet.backup_target").ok();
+    let wallet_backup_s3_endpoint = cfg.get_str("wallet.backup_s3_endpoint").ok();
+    let wallet_backup_passphrase = cfg.get_str("wallet.backup_passphrase").ok();
+    let wallet_backup_interval = cfg.get_int("wallet.backup_interval").ok().map(|v| v as u64);
+    let wallet_backup_retention = cfg.get_int("wallet.backup_retention").ok().map(|v| v as usize);
+    let wallet_backup_incremental_count = cfg.get_int("wallet.backup_incremental_count").ok().map(|v| v as usize);
+    // How long spending stays unlocked in receive-only mode (optional)
+    let wallet_spend_unlock_period = cfg.get_int("wallet.spend_unlock_period").ok().map(|v| v as u64);
+    // The language of user-facing wallet messages (optional)
+    let wallet_locale = cfg.get_str("wallet.locale").ok();
+    let wallet_locale_dir = cfg.get_str("wallet.locale_dir").ok().map(PathBuf::from);
+
+    // Operator notifications (optional)
+    let notification_webhook_urls = cfg
+        .get_array("notifications.webhook_urls")
+        .map(|values| values.into_iter().filter_map(|v| v.into_str().ok()).collect())
+        .unwrap_or_default();
+    let notification_hook_command = cfg.get_str("notifications.hook_command").ok().map(PathBuf::from);
+    let notification_events = cfg
+        .get_array("notifications.events")
+        .map(|values| values.into_iter().filter_map(|v| v.into_str().ok()).collect())
+        .unwrap_or_default();
+    let notification_reorg_depth_threshold = cfg
+        .get_int("notifications.reorg_depth_threshold")
+        .ok()
+        .map(|v| v as u64);
+    let notification_min_peer_count = cfg.get_int("notifications.min_peer_count").ok().map(|v| v as usize);
+
     let key = "common.liveness_max_sessions";
     let liveness_max_sessions = cfg
         .get_int(key)
@@ -422,17 +652,67 @@ fn convert_node_config(network: Network, cfg: Config) -> Result<GlobalConfig, Co
         core_threads,
         blocking_threads,
         identity_file,
+        identity_passphrase,
         public_address,
+        public_address_check_url,
+        public_address_check_interval,
+        resource_check_interval,
+        min_free_disk_space_mb,
+        critical_free_disk_space_mb,
+        max_memory_usage_mb,
         peer_seeds,
         peer_db_path,
+        blocklist_feed_path,
+        blocklist_feed_public_key,
+        blocklist_allowlist,
+        peer_allow_list_only,
+        peer_allow_list,
+        peer_allow_list_file,
+        dht_join_startup_delay,
+        dht_join_min_interval,
+        dht_join_reannounce_interval,
         block_sync_strategy,
+        header_verification_threads,
+        block_validation_log_threshold_ms,
+        max_headers_per_response,
+        max_blocks_per_response,
+        max_kernels_per_response,
+        max_utxos_per_response,
+        mempool_max_list_page_size,
+        relay_min_fee_per_gram,
+        relay_max_tx_weight,
+        relay_non_standard_features,
         enable_mining,
         num_mining_threads,
+        coinbase_extra,
         tor_identity_file,
         wallet_identity_file,
         wallet_db_file,
         wallet_tor_identity_file,
         wallet_peer_db_path,
+        wallet_num_confirmations_required,
+        wallet_dust_threshold,
+        wallet_output_recovery_byte,
+        wallet_min_inbound_amount,
+        wallet_max_inbound_transactions_per_peer,
+        wallet_inbound_throttle_period,
+        wallet_zero_conf_min_fee,
+        wallet_backup_target,
+        wallet_backup_s3_endpoint,
+        wallet_backup_passphrase,
+        wallet_backup_interval,
+        wallet_backup_retention,
+        wallet_backup_incremental_count,
+        wallet_spend_unlock_period,
+        wallet_locale,
+        wallet_locale_dir,
+        notification_webhook_urls,
+        notification_hook_command,
+        notification_events,
+        notification_reorg_depth_threshold,
+        notification_min_peer_count,
+        genesis_block_file,
+        max_block_weight_schedule,
     })
 }
 
@@ -493,12 +773,21 @@ fn network_transport_config(cfg: &Config, network: &str) -> Result<CommsTranspor
                 None => None,
             };
 
+            let key = config_string(network, "tor_socks_auth");
+            let socks_auth = match get_conf_str(&key).ok() {
+                Some(auth_str) => auth_str
+                    .parse()
+                    .map_err(|err: String| ConfigurationError::new(&key, &err))?,
+                None => SocksAuthentication::None,
+            };
+
             Ok(CommsTransport::TorHiddenService {
                 control_server_address,
                 auth,
                 socks_address_override,
                 forward_address,
                 onion_port,
+                socks_auth,
             })
         },
         "socks5" => {
@@ -527,10 +816,509 @@ fn network_transport_config(cfg: &Config, network: &str) -> Result<CommsTranspor
     }
 }
 
-fn config_string(network: &str, key: &str) -> String {
+pub(crate) fn config_string(network: &str, key: &str) -> String {
     format!("base_node.{}.{}", network, key)
 }
 
+//-------------------------------------      Configuration validation      ---------------------------------------//
+
+/// Run the same conversion as [GlobalConfig::convert_from] along with checks that span several settings, and report
+/// every problem found instead of stopping at the first one.
+pub fn validate_configuration(mut cfg: Config) -> Result<GlobalConfig, Vec<ConfigurationError>> {
+    cfg.merge(Environment::with_prefix("tari"))
+        .map_err(|e| vec![ConfigurationError::new("environment variable", &e.to_string())])?;
+    let network = cfg
+        .get_str("base_node.network")
+        .map_err(|e| ConfigurationError::new("base_node.network", &e.to_string()))
+        .and_then(|network| network.parse::<Network>())
+        .map_err(|e| vec![e])?;
+
+    let mut errors = validate_transport(&cfg, &network.to_string());
+    let global_config = match GlobalConfig::convert_from(cfg) {
+        Ok(global_config) => Some(global_config),
+        Err(e) => {
+            // The transport errors above are more complete than the first one hit during conversion
+            if errors.iter().all(|err| err.field != e.field) {
+                errors.push(e);
+            }
+            None
+        },
+    };
+    if let Some(global_config) = &global_config {
+        errors.extend(validate_paths(global_config, &network.to_string()));
+    }
+
+    match global_config {
+        Some(global_config) if errors.is_empty() => Ok(global_config),
+        _ => Err(errors),
+    }
+}
+
+/// Checks that all the settings needed by the selected transport are present and can be parsed
+fn validate_transport(cfg: &Config, network: &str) -> Vec<ConfigurationError> {
+    let transport_key = config_string(network, "transport");
+    let transport = match cfg.get_str(&transport_key) {
+        Ok(transport) => transport.to_lowercase(),
+        Err(e) => return vec![ConfigurationError::new(&transport_key, &e.to_string())],
+    };
+    let (address_keys, auth_key): (&[&str], Option<&str>) = match transport.as_str() {
+        "tcp" => (&["tcp_listener_address"], None),
+        "tor" => (
+            &["tor_control_address", "tor_forward_address"],
+            Some("tor_control_auth"),
+        ),
+        "socks5" => (
+            &["socks5_proxy_address", "socks5_listener_address"],
+            Some("socks5_auth"),
+        ),
+        t => {
+            return vec![ConfigurationError::new(
+                &transport_key,
+                &format!("Invalid transport type '{}'", t),
+            )]
+        },
+    };
+
+    let mut errors = Vec::new();
+    for key in address_keys.iter().map(|key| config_string(network, key)) {
+        if let Err(e) = cfg
+            .get_str(&key)
+            .map_err(|e| e.to_string())
+            .and_then(|addr| addr.parse::<Multiaddr>().map(|_| ()).map_err(|e| e.to_string()))
+        {
+            errors.push(ConfigurationError::new(&key, &e));
+        }
+    }
+    if let Some(key) = auth_key.map(|key| config_string(network, key)) {
+        let auth = cfg.get_str(&key).map_err(|e| e.to_string());
+        let auth = if transport == "tor" {
+            auth.and_then(|auth| auth.parse::<TorControlAuthentication>().map(|_| ()))
+        } else {
+            auth.and_then(|auth| auth.parse::<SocksAuthentication>().map(|_| ()))
+        };
+        if let Err(e) = auth {
+            errors.push(ConfigurationError::new(&key, &e));
+        }
+    }
+    if transport == "tor" {
+        let key = config_string(network, "tor_onion_port");
+        if let Err(e) = cfg.get::<NonZeroU16>(&key) {
+            errors.push(ConfigurationError::new(&key, &e.to_string()));
+        }
+    }
+    errors
+}
+
+/// Checks that the node will be able to write to its data directories and identity files
+fn validate_paths(global_config: &GlobalConfig, network: &str) -> Vec<ConfigurationError> {
+    let dirs = vec![
+        ("data_dir", Some(global_config.data_dir.as_path())),
+        ("peer_db_path", Some(global_config.peer_db_path.as_path())),
+        ("identity_file", global_config.identity_file.parent()),
+        ("tor_identity_file", global_config.tor_identity_file.parent()),
+    ];
+    dirs.into_iter()
+        .filter_map(|(key, dir)| dir.map(|dir| (key, dir)))
+        .filter_map(|(key, dir)| {
+            check_writable(dir)
+                .err()
+                .map(|e| ConfigurationError::new(&config_string(network, key), &e))
+        })
+        .collect()
+}
+
+/// A directory is writable if a file can be created in it, or, if it does not exist yet, in its closest existing
+/// ancestor
+fn check_writable(dir: &Path) -> Result<(), String> {
+    let existing = match dir.ancestors().find(|path| path.exists()) {
+        Some(existing) => existing,
+        None => return Err(format!("No part of {} exists", dir.display())),
+    };
+    if !existing.is_dir() {
+        return Err(format!("{} is not a directory", existing.display()));
+    }
+    let probe = existing.join(".tari_write_check");
+    fs::write(&probe, b"")
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|e| format!("{} is not writable: {}", existing.display(), e))
+}
+
+//-------------------------------------      Configuration migrations      ---------------------------------------//
+
+/// The configuration file layout version written by this version of the software. When a setting is renamed or
+/// moved, bump this and add a `ConfigMigration` to `CONFIG_MIGRATIONS` so that existing files are upgraded.
+pub const CONFIG_VERSION: u32 = 1;
+const CONFIG_VERSION_KEY: &str = "config_version";
+
+/// A change to the layout of the configuration file
+#[derive(Debug, Clone, Copy)]
+pub enum ConfigChange {
+    /// Rename a section and its sub-sections, e.g. `[base_node.testnet]` to `[base_node.rincewind]`. If the new
+    /// section already exists, the keys it does not have yet are moved into it.
+    RenameSection { from: &'static str, to: &'static str },
+    /// Rename or move a single key, given by its full path, e.g. `common.peer_database` to `comms.peer_database`
+    MoveKey { from: &'static str, to: &'static str },
+}
+
+/// The changes that upgrade a configuration file to `version` from the version before it
+struct ConfigMigration {
+    version: u32,
+    changes: &'static [ConfigChange],
+}
+
+const CONFIG_MIGRATIONS: &[ConfigMigration] = &[ConfigMigration {
+    version: 1,
+    // The test network sections were only ever read as `rincewind`
+    changes: &[
+        ConfigChange::RenameSection {
+            from: "base_node.testnet",
+            to: "base_node.rincewind",
+        },
+        ConfigChange::RenameSection {
+            from: "mempool.testnet",
+            to: "mempool.rincewind",
+        },
+        ConfigChange::RenameSection {
+            from: "relay.testnet",
+            to: "relay.rincewind",
+        },
+    ],
+}];
+
+/// Upgrade the configuration file at `path` to the current [CONFIG_VERSION], if it was written for an older version.
+/// The original file is kept next to it as `<file name>.v<version>.bak`, and its path is returned. Files without a
+/// `config_version` key are version 0.
+pub fn migrate_config_file(path: &Path) -> Result<Option<PathBuf>, ConfigurationError> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path).map_err(|e| ConfigurationError::new(CONFIG_VERSION_KEY, &e.to_string()))?;
+    // Files that cannot be parsed are left alone, loading them reports the error
+    if Config::new()
+        .merge(config::File::from_str(&contents, FileFormat::Toml))
+        .is_err()
+    {
+        return Ok(None);
+    }
+    let mut document = ConfigDocument::parse(&contents);
+    let version = document.version()?;
+    if version > CONFIG_VERSION {
+        return Err(ConfigurationError::new(
+            CONFIG_VERSION_KEY,
+            &format!(
+                "The configuration file is for a newer version of Tari (version {}, this version supports up to {})",
+                version, CONFIG_VERSION
+            ),
+        ));
+    }
+    if version == CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    for migration in CONFIG_MIGRATIONS.iter().filter(|m| m.version > version) {
+        for change in migration.changes {
+            document.apply(change);
+        }
+    }
+    document.set_version(CONFIG_VERSION);
+
+    let mut backup_name = path.file_name().unwrap_or_default().to_os_string();
+    backup_name.push(format!(".v{}.bak", version));
+    let backup = path.with_file_name(backup_name);
+    fs::copy(path, &backup)
+        .and_then(|_| fs::write(path, document.to_string()))
+        .map_err(|e| ConfigurationError::new(CONFIG_VERSION_KEY, &format!("Could not upgrade the file. {}", e)))?;
+    info!(
+        target: LOG_TARGET,
+        "Upgraded the configuration file from version {} to {}. The original file was saved as {:?}",
+        version,
+        CONFIG_VERSION,
+        backup
+    );
+    Ok(Some(backup))
+}
+
+/// A configuration file split into sections, keeping the comments and formatting of every line so that it can be
+/// migrated in place
+struct ConfigDocument {
+    /// The first section holds the top level keys and has an empty name. Every other section starts with its header.
+    sections: Vec<ConfigSection>,
+    trailing_newline: bool,
+}
+
+struct ConfigSection {
+    name: String,
+    lines: Vec<String>,
+}
+
+impl ConfigSection {
+    fn first_entry_line(&self) -> usize {
+        if self.name.is_empty() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// The line ranges of the keys in the section. Values spanning several lines, e.g. arrays, are included.
+    fn entries(&self) -> Vec<(String, Range<usize>)> {
+        let mut entries = Vec::new();
+        let mut i = self.first_entry_line();
+        while i < self.lines.len() {
+            match entry_key(&self.lines[i]) {
+                Some(key) => {
+                    let start = i;
+                    let mut depth = bracket_depth_change(&self.lines[i]);
+                    i += 1;
+                    while depth > 0 && i < self.lines.len() {
+                        depth += bracket_depth_change(&self.lines[i]);
+                        i += 1;
+                    }
+                    entries.push((key.to_string(), start..i));
+                },
+                None => i += 1,
+            }
+        }
+        entries
+    }
+
+    fn find_entry(&self, key: &str) -> Option<Range<usize>> {
+        self.entries()
+            .into_iter()
+            .find(|(entry, _)| entry == key)
+            .map(|(_, range)| range)
+    }
+}
+
+impl ConfigDocument {
+    fn parse(contents: &str) -> Self {
+        let mut sections = vec![ConfigSection {
+            name: String::new(),
+            lines: Vec::new(),
+        }];
+        let mut depth = 0;
+        for line in contents.lines() {
+            if depth <= 0 {
+                if let Some(name) = section_name(line) {
+                    sections.push(ConfigSection {
+                        name: name.to_string(),
+                        lines: vec![line.to_string()],
+                    });
+                    continue;
+                }
+                depth = 0;
+            }
+            depth += bracket_depth_change(line);
+            sections
+                .last_mut()
+                .expect("there is always a top level section")
+                .lines
+                .push(line.to_string());
+        }
+        Self {
+            sections,
+            trailing_newline: contents.ends_with('\n'),
+        }
+    }
+
+    fn find_section(&self, name: &str) -> Option<usize> {
+        self.sections.iter().position(|section| section.name == name)
+    }
+
+    fn version(&self) -> Result<u32, ConfigurationError> {
+        let top = &self.sections[0];
+        match top.find_entry(CONFIG_VERSION_KEY) {
+            Some(range) => entry_value(&top.lines[range.start])
+                .parse()
+                .map_err(|e: ParseIntError| ConfigurationError::new(CONFIG_VERSION_KEY, &e.to_string())),
+            None => Ok(0),
+        }
+    }
+
+    fn set_version(&mut self, version: u32) {
+        let line = format!("{} = {}", CONFIG_VERSION_KEY, version);
+        let top = &mut self.sections[0];
+        match top.find_entry(CONFIG_VERSION_KEY) {
+            Some(range) => {
+                top.lines.splice(range, Some(line));
+            },
+            None => {
+                top.lines.insert(0, line);
+                top.lines.insert(1, String::new());
+            },
+        }
+    }
+
+    fn apply(&mut self, change: &ConfigChange) {
+        match *change {
+            ConfigChange::RenameSection { from, to } => self.rename_section(from, to),
+            ConfigChange::MoveKey { from, to } => {
+                let (from_section, from_key) = split_key(from);
+                let (to_section, to_key) = split_key(to);
+                self.move_key(from_section, from_key, to_section, to_key);
+            },
+        }
+    }
+
+    fn rename_section(&mut self, from: &str, to: &str) {
+        let prefix = format!("{}.", from);
+        let names = self
+            .sections
+            .iter()
+            .map(|section| section.name.clone())
+            .filter(|name| name == from || name.starts_with(&prefix))
+            .collect::<Vec<_>>();
+        for name in names {
+            let new_name = format!("{}{}", to, &name[from.len()..]);
+            let index = self.find_section(&name).expect("section exists");
+            if self.find_section(&new_name).is_none() {
+                let section = &mut self.sections[index];
+                section.lines[0] = section.lines[0].replacen(&name, &new_name, 1);
+                section.name = new_name;
+                continue;
+            }
+            let keys = self.sections[index]
+                .entries()
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>();
+            for key in keys {
+                self.move_key(&name, &key, &new_name, &key);
+            }
+            warn!(
+                target: LOG_TARGET,
+                "Merged configuration section [{}] into [{}]. Comments in [{}] were not kept.", name, new_name, name
+            );
+            let index = self.find_section(&name).expect("section exists");
+            self.sections.remove(index);
+        }
+    }
+
+    fn move_key(&mut self, from_section: &str, from_key: &str, to_section: &str, to_key: &str) {
+        let mut lines = match self.find_section(from_section).and_then(|index| {
+            let section = &mut self.sections[index];
+            section
+                .find_entry(from_key)
+                .map(|range| section.lines.drain(range).collect::<Vec<_>>())
+        }) {
+            Some(lines) => lines,
+            None => return,
+        };
+
+        let index = match self.find_section(to_section) {
+            Some(index) => index,
+            None => {
+                if let Some(last) = self.sections.last_mut() {
+                    last.lines.push(String::new());
+                }
+                self.sections.push(ConfigSection {
+                    name: to_section.to_string(),
+                    lines: vec![format!("[{}]", to_section)],
+                });
+                self.sections.len() - 1
+            },
+        };
+        let section = &mut self.sections[index];
+        if section.find_entry(to_key).is_some() {
+            warn!(
+                target: LOG_TARGET,
+                "Dropped configuration setting {}.{} because {}.{} is already set",
+                from_section,
+                from_key,
+                to_section,
+                to_key
+            );
+            return;
+        }
+        lines[0] = lines[0].replacen(from_key, to_key, 1);
+        let insert_at = section
+            .entries()
+            .last()
+            .map_or_else(|| section.first_entry_line(), |(_, range)| range.end);
+        section.lines.splice(insert_at..insert_at, lines);
+    }
+}
+
+impl Display for ConfigDocument {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        let lines = self
+            .sections
+            .iter()
+            .flat_map(|section| section.lines.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        f.write_str(&lines.join("\n"))?;
+        if self.trailing_newline {
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the name of the section if the line is a section header, e.g. `[base_node.mainnet]`
+fn section_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if !line.starts_with('[') {
+        return None;
+    }
+    let name = line.trim_start_matches('[');
+    name.find(']').map(|end| name[..end].trim())
+}
+
+/// Returns the key if the line sets a (bare) key, e.g. `db_type = "lmdb"`
+fn entry_key(line: &str) -> Option<&str> {
+    let mut parts = line.splitn(2, '=');
+    let key = parts.next()?.trim();
+    parts.next()?;
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+/// Returns the value of a `key = value` line, without quotes or a trailing comment
+fn entry_value(line: &str) -> &str {
+    let value = line.splitn(2, '=').nth(1).unwrap_or_default();
+    value.split('#').next().unwrap_or_default().trim().trim_matches('"')
+}
+
+/// The number of array brackets a line opens, less the number it closes. Brackets in strings and comments are ignored.
+fn bracket_depth_change(line: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in line.chars() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && q == '"' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            },
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '#' => break,
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {},
+            },
+        }
+    }
+    depth
+}
+
+/// Split a full key path into its section and key, e.g. `base_node.mainnet.db_type` into `base_node.mainnet` and
+/// `db_type`. Top level keys have an empty section.
+fn split_key(path: &str) -> (&str, &str) {
+    match path.rfind('.') {
+        Some(i) => (&path[..i], &path[i + 1..]),
+        None => ("", path),
+    }
+}
+
 //-------------------------------------      Configuration file defaults      --------------------------------------//
 
 /// Generate the global Tari configuration instance.
@@ -655,6 +1443,48 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
     cfg.set_default("base_node.rincewind.enable_mining", false).unwrap();
     cfg.set_default("base_node.rincewind.num_mining_threads", 1).unwrap();
 
+    //---------------------------------- Localnet Defaults --------------------------------------------//
+
+    cfg.set_default("base_node.localnet.db_type", "lmdb").unwrap();
+    cfg.set_default("base_node.localnet.peer_seeds", Vec::<String>::new())
+        .unwrap();
+    cfg.set_default("base_node.localnet.block_sync_strategy", "ViaBestChainMetadata")
+        .unwrap();
+    cfg.set_default("base_node.localnet.blocking_threads", 4).unwrap();
+    cfg.set_default("base_node.localnet.core_threads", 4).unwrap();
+    cfg.set_default(
+        "base_node.localnet.data_dir",
+        default_subdir("localnet/", Some(&bootstrap.base_path)),
+    )
+    .unwrap();
+    cfg.set_default(
+        "base_node.localnet.tor_identity_file",
+        default_subdir("localnet/tor.json", Some(&bootstrap.base_path)),
+    )
+    .unwrap();
+    cfg.set_default(
+        "base_node.localnet.wallet_identity_file",
+        default_subdir("localnet/wallet-identity.json", Some(&bootstrap.base_path)),
+    )
+    .unwrap();
+    cfg.set_default(
+        "base_node.localnet.wallet_tor_identity_file",
+        default_subdir("localnet/wallet-tor.json", Some(&bootstrap.base_path)),
+    )
+    .unwrap();
+    cfg.set_default(
+        "base_node.localnet.identity_file",
+        default_subdir("localnet/node_id.json", Some(&bootstrap.base_path)),
+    )
+    .unwrap();
+    cfg.set_default(
+        "base_node.localnet.public_address",
+        format!("{}/tcp/18289", local_ip_addr),
+    )
+    .unwrap();
+    cfg.set_default("base_node.localnet.enable_mining", false).unwrap();
+    cfg.set_default("base_node.localnet.num_mining_threads", 1).unwrap();
+
     set_transport_defaults(&mut cfg);
 
     cfg
@@ -698,6 +1528,12 @@ fn set_transport_defaults(cfg: &mut Config) {
     cfg.set_default("base_node.rincewind.socks5_listener_address", "/ip4/0.0.0.0/tcp/18199")
         .unwrap();
     cfg.set_default("base_node.rincewind.socks5_auth", "none").unwrap();
+
+    // localnet
+    // Default transport for localnet is tcp
+    cfg.set_default("base_node.localnet.transport", "tcp").unwrap();
+    cfg.set_default("base_node.localnet.tcp_listener_address", "/ip4/0.0.0.0/tcp/18289")
+        .unwrap();
 }
 
 fn get_local_ip() -> Option<Multiaddr> {
@@ -747,11 +1583,118 @@ impl Error for ConfigurationError {}
 
 #[cfg(test)]
 mod test {
-    use crate::ConfigurationError;
+    use super::{ConfigChange, ConfigDocument};
+    use crate::{
+        default_config,
+        migrate_config_file,
+        validate_configuration,
+        ConfigBootstrap,
+        ConfigurationError,
+        CONFIG_VERSION,
+    };
+    use config::{Config, File};
+    use std::fs;
+    use tari_test_utils::random::string;
+    use tempdir::TempDir;
 
     #[test]
     fn configuration_error() {
         let e = ConfigurationError::new("test", "is a string");
         assert_eq!(e.to_string(), "Invalid value for test: is a string");
     }
+
+    #[test]
+    fn validate_configuration_reports_all_transport_errors() {
+        let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+        let bootstrap = ConfigBootstrap {
+            base_path: temp_dir.path().to_path_buf(),
+            config: temp_dir.path().join("config.toml"),
+            log_config: temp_dir.path().join("log4rs.yml"),
+            log_format: Default::default(),
+            network: None,
+            secrets_file: temp_dir.path().join("secrets.enc"),
+            secrets: Default::default(),
+        };
+        let mut cfg = default_config(&bootstrap);
+        cfg.set("base_node.mainnet.transport", "tor").unwrap();
+        cfg.set("base_node.mainnet.tor_control_address", "not an address")
+            .unwrap();
+        cfg.set("base_node.mainnet.tor_control_auth", "hunter2").unwrap();
+        cfg.set("base_node.mainnet.tor_forward_address", "").unwrap();
+
+        let errors = validate_configuration(cfg).unwrap_err();
+        assert_eq!(errors.len(), 3);
+        let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n");
+        assert!(errors.contains("base_node.mainnet.tor_control_address"));
+        assert!(errors.contains("base_node.mainnet.tor_control_auth"));
+        assert!(errors.contains("base_node.mainnet.tor_forward_address"));
+    }
+
+    #[test]
+    fn migrate_config_file_upgrades_old_files() {
+        let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let old_config = r#"# An old configuration file
+[common]
+message_cache_size = 20
+
+[base_node]
+network = "rincewind"
+
+[base_node.testnet]
+# The database backend
+db_type = "memory"
+peer_seeds = [
+  "a::/ip4/1.2.3.4/tcp/18141", # [first]
+  "b::/ip4/1.2.3.5/tcp/18141"
+]
+
+[base_node.rincewind]
+db_type = "lmdb"
+
+[mempool.testnet]
+orphan_tx_ttl = 60
+"#;
+        fs::write(&path, old_config).unwrap();
+
+        let backup = migrate_config_file(&path).unwrap().unwrap();
+        assert_eq!(backup, temp_dir.path().join("config.toml.v0.bak"));
+        assert_eq!(fs::read_to_string(&backup).unwrap(), old_config);
+
+        let mut cfg = Config::new();
+        cfg.merge(File::from(path.clone())).unwrap();
+        assert_eq!(cfg.get_int("config_version").unwrap(), i64::from(CONFIG_VERSION));
+        assert_eq!(cfg.get_int("common.message_cache_size").unwrap(), 20);
+        // Settings already in the new section are kept
+        assert_eq!(cfg.get_str("base_node.rincewind.db_type").unwrap(), "lmdb");
+        assert_eq!(cfg.get_array("base_node.rincewind.peer_seeds").unwrap().len(), 2);
+        assert!(cfg.get_str("base_node.testnet.db_type").is_err());
+        assert_eq!(cfg.get_int("mempool.rincewind.orphan_tx_ttl").unwrap(), 60);
+
+        // The file is only upgraded once
+        assert!(migrate_config_file(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn migrate_config_file_rejects_newer_versions() {
+        let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, format!("config_version = {}\n", CONFIG_VERSION + 1)).unwrap();
+        assert!(migrate_config_file(&path).is_err());
+    }
+
+    #[test]
+    fn move_key_keeps_comments() {
+        let mut document =
+            ConfigDocument::parse("[common]\n# Where peers are stored\npeer_database = \"peers\" # default\n");
+        document.apply(&ConfigChange::MoveKey {
+            from: "common.peer_database",
+            to: "comms.peer_db_path",
+        });
+        document.set_version(1);
+        assert_eq!(
+            document.to_string(),
+            "config_version = 1\n\n[common]\n# Where peers are stored\n\n[comms]\npeer_db_path = \"peers\" # default\n"
+        );
+    }
 }
@@ -20,7 +20,17 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::path::PathBuf;
+use crate::{DEFAULT_CONFIG, DEFAULT_LOG_CONFIG};
+use std::{
+    env,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Set this environment variable to `1` or `true` to keep Tari files in the platform's standard directories instead of
+/// `~/.tari`
+pub const PLATFORM_DIRS_ENV_VAR: &str = "TARI_USE_PLATFORM_DIRS";
 
 /// Create the default data directory (`~/.tari` on OSx and Linux, for example) if it doesn't already exist
 pub fn create_data_directory(base_dir: Option<&PathBuf>) -> Result<(), std::io::Error> {
@@ -28,12 +38,51 @@ pub fn create_data_directory(base_dir: Option<&PathBuf>) -> Result<(), std::io::
 
     if !home.exists() {
         println!("Creating {:?}", home);
-        std::fs::create_dir(home)
+        std::fs::create_dir_all(home)
     } else {
         Ok(())
     }
 }
 
+/// Returns true if the platform's standard directories should be used instead of `~/.tari`. This is opt-in with the
+/// `TARI_USE_PLATFORM_DIRS` environment variable.
+pub fn use_platform_dirs() -> bool {
+    env::var(PLATFORM_DIRS_ENV_VAR)
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// The `~/.tari` directory that is used unless platform directories are enabled
+pub fn legacy_data_dir() -> PathBuf {
+    let mut home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+    home.push(".tari");
+    home
+}
+
+/// The directory Tari data is kept in when no base path is given. With platform directories enabled this is
+/// `$XDG_DATA_HOME/tari` (usually `~/.local/share/tari`) on Linux, `~/Library/Application Support/tari` on macOS and
+/// `%APPDATA%\tari` on Windows.
+pub fn default_data_dir() -> PathBuf {
+    if use_platform_dirs() {
+        if let Some(dir) = dirs::data_dir() {
+            return dir.join("tari");
+        }
+    }
+    legacy_data_dir()
+}
+
+/// The directory the configuration files are kept in when no base path is given. With platform directories enabled
+/// this is `$XDG_CONFIG_HOME/tari` (usually `~/.config/tari`) on Linux. On macOS and Windows it is the same as the data
+/// directory.
+pub fn default_config_dir() -> PathBuf {
+    if use_platform_dirs() {
+        if let Some(dir) = dirs::config_dir() {
+            return dir.join("tari");
+        }
+    }
+    legacy_data_dir()
+}
+
 /// A convenience function for creating subfolders inside the `~/.tari` default data directory
 ///
 /// # Panics
@@ -45,11 +94,140 @@ pub fn default_subdir(path: &str, base_dir: Option<&PathBuf>) -> String {
 }
 
 pub fn default_path(filename: &str, base_path: Option<&PathBuf>) -> PathBuf {
-    let mut home = base_path.cloned().unwrap_or_else(|| {
-        let mut home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        home.push(".tari");
-        home
-    });
+    let mut home = base_path.cloned().unwrap_or_else(default_data_dir);
+    home.push(filename);
+    home
+}
+
+/// The path of a configuration file. Configuration files are kept in the base path if one is given, otherwise in the
+/// default configuration directory.
+pub fn default_config_path(filename: &str, base_path: Option<&PathBuf>) -> PathBuf {
+    let mut home = base_path.cloned().unwrap_or_else(default_config_dir);
     home.push(filename);
     home
 }
+
+/// How an existing `~/.tari` directory is migrated to the platform directories
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LegacyDirMigration {
+    /// Move the directory, and the configuration files into the configuration directory
+    Move,
+    /// Leave the directory where it is and link the platform directories to it
+    Symlink,
+}
+
+/// Returns the `~/.tari` directory if platform directories are enabled, it exists and it has not been migrated yet
+pub fn find_unmigrated_legacy_dir() -> Option<PathBuf> {
+    if !use_platform_dirs() {
+        return None;
+    }
+    let legacy_dir = legacy_data_dir();
+    let data_dir = default_data_dir();
+    if legacy_dir.is_dir() && legacy_dir != data_dir && !data_dir.exists() {
+        Some(legacy_dir)
+    } else {
+        None
+    }
+}
+
+/// Migrate the `legacy_dir` to the `data_dir`. If the `config_dir` is a different directory, the configuration files
+/// are moved (or linked) there too.
+pub fn migrate_legacy_dir(
+    legacy_dir: &Path,
+    data_dir: &Path,
+    config_dir: &Path,
+    migration: LegacyDirMigration,
+) -> Result<(), io::Error>
+{
+    if data_dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", data_dir.to_string_lossy()),
+        ));
+    }
+    if let Some(parent) = data_dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let separate_config_dir = config_dir != data_dir && !config_dir.exists();
+
+    match migration {
+        LegacyDirMigration::Move => {
+            fs::rename(legacy_dir, data_dir)?;
+            if separate_config_dir {
+                fs::create_dir_all(config_dir)?;
+                for filename in &[DEFAULT_CONFIG, DEFAULT_LOG_CONFIG] {
+                    let file = data_dir.join(filename);
+                    if file.exists() {
+                        fs::rename(file, config_dir.join(filename))?;
+                    }
+                }
+            }
+        },
+        LegacyDirMigration::Symlink => {
+            symlink_dir(legacy_dir, data_dir)?;
+            if separate_config_dir {
+                if let Some(parent) = config_dir.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                symlink_dir(legacy_dir, config_dir)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink_dir(original: &Path, link: &Path) -> Result<(), io::Error> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink_dir(original: &Path, link: &Path) -> Result<(), io::Error> {
+    std::os::windows::fs::symlink_dir(original, link)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn make_legacy_dir(root: &Path) -> PathBuf {
+        let legacy_dir = root.join(".tari");
+        fs::create_dir_all(legacy_dir.join("rincewind")).unwrap();
+        fs::write(legacy_dir.join(DEFAULT_CONFIG), "").unwrap();
+        fs::write(legacy_dir.join(DEFAULT_LOG_CONFIG), "").unwrap();
+        legacy_dir
+    }
+
+    #[test]
+    fn migrate_legacy_dir_by_moving() {
+        let root = TempDir::new("legacy_dir_move").unwrap();
+        let legacy_dir = make_legacy_dir(root.path());
+        let data_dir = root.path().join("share/tari");
+        let config_dir = root.path().join("config/tari");
+
+        migrate_legacy_dir(&legacy_dir, &data_dir, &config_dir, LegacyDirMigration::Move).unwrap();
+        assert!(!legacy_dir.exists());
+        assert!(data_dir.join("rincewind").is_dir());
+        assert!(!data_dir.join(DEFAULT_CONFIG).exists());
+        assert!(config_dir.join(DEFAULT_CONFIG).is_file());
+        assert!(config_dir.join(DEFAULT_LOG_CONFIG).is_file());
+
+        // The data directory now exists, so a second migration is refused
+        let legacy_dir = make_legacy_dir(root.path());
+        assert!(migrate_legacy_dir(&legacy_dir, &data_dir, &config_dir, LegacyDirMigration::Move).is_err());
+    }
+
+    #[test]
+    fn migrate_legacy_dir_by_symlinking() {
+        let root = TempDir::new("legacy_dir_symlink").unwrap();
+        let legacy_dir = make_legacy_dir(root.path());
+        let data_dir = root.path().join("share/tari");
+        let config_dir = root.path().join("config/tari");
+
+        migrate_legacy_dir(&legacy_dir, &data_dir, &config_dir, LegacyDirMigration::Symlink).unwrap();
+        assert!(legacy_dir.join("rincewind").is_dir());
+        assert!(data_dir.join("rincewind").is_dir());
+        assert!(config_dir.join(DEFAULT_CONFIG).is_file());
+    }
+}
@@ -49,20 +49,30 @@
 //! ```
 
 use clap::ArgMatches;
+use secrets::SecretStore;
 use std::path::{Path, PathBuf};
 
+mod config_builder;
+mod config_watcher;
 mod configuration;
 #[macro_use]
 mod logging;
 
 pub mod protobuf_build;
+pub mod retry;
+pub mod secrets;
 
 pub mod dir_utils;
+pub use config_builder::GlobalConfigBuilder;
+pub use config_watcher::{ConfigUpdate, ConfigUpdateReceiver, ConfigWatcher};
 pub use configuration::{
     default_config,
     install_default_config_file,
     load_configuration,
+    migrate_config_file,
+    validate_configuration,
     CommsTransport,
+    ConfigChange,
     ConfigExtractor,
     ConfigurationError,
     DatabaseType,
@@ -70,13 +80,16 @@ pub use configuration::{
     Network,
     SocksAuthentication,
     TorControlAuthentication,
+    CONFIG_VERSION,
 };
-pub use logging::initialize_logging;
+pub use logging::{initialize_logging, LogFormat};
 use std::io;
 pub const DEFAULT_CONFIG: &str = "config.toml";
 pub const DEFAULT_LOG_CONFIG: &str = "log4rs.yml";
+pub const DEFAULT_SECRETS_FILE: &str = "secrets.enc";
 
 /// A minimal parsed configuration object that's used to bootstrap the main Configuration.
+#[derive(Clone)]
 pub struct ConfigBootstrap {
     pub base_path: PathBuf,
     pub config: PathBuf,
@@ -85,23 +98,43 @@ pub struct ConfigBootstrap {
     ///   2. from the `TARI_LOG_CONFIGURATION` environment variable,
     ///   3. from a default value, usually `~/.tari/log4rs.yml` (or OS equivalent).
     pub log_config: PathBuf,
+    /// The format log lines are written in. It is set using the following precedence set:
+    ///   1. from the `--log_format` command-line parameter,
+    ///   2. from the `TARI_LOG_FORMAT` environment variable,
+    ///   3. plain text, as given by the patterns in the log configuration file.
+    pub log_format: LogFormat,
+    /// The network selected with `--network`. When set, it overrides `base_node.network` in the configuration file so
+    /// that only the matching `[base_node.<network>]` section is used.
+    pub network: Option<Network>,
+    /// The path of the encrypted secrets file, usually `~/.tari/secrets.enc`
+    pub secrets_file: PathBuf,
+    /// The unlocked secrets file, used to resolve `secret:` references in the configuration file. It is locked (empty)
+    /// if there is no secrets file.
+    pub secrets: SecretStore,
 }
 
 impl Default for ConfigBootstrap {
     fn default() -> Self {
         ConfigBootstrap {
             base_path: dir_utils::default_path("", None),
-            config: dir_utils::default_path(DEFAULT_CONFIG, None),
-            log_config: dir_utils::default_path(DEFAULT_LOG_CONFIG, None),
+            config: dir_utils::default_config_path(DEFAULT_CONFIG, None),
+            log_config: dir_utils::default_config_path(DEFAULT_LOG_CONFIG, None),
+            log_format: LogFormat::default(),
+            network: None,
+            secrets_file: dir_utils::default_config_path(DEFAULT_SECRETS_FILE, None),
+            secrets: SecretStore::default(),
         }
     }
 }
 
 pub fn bootstrap_config_from_cli(matches: &ArgMatches) -> ConfigBootstrap {
-    let base_path = matches
-        .value_of("base_dir")
-        .map(PathBuf::from)
-        .unwrap_or_else(|| dir_utils::default_path("", None));
+    let base_dir = matches.value_of("base_dir").map(PathBuf::from);
+    if base_dir.is_none() {
+        if let Some(legacy_dir) = dir_utils::find_unmigrated_legacy_dir() {
+            offer_legacy_dir_migration(&legacy_dir);
+        }
+    }
+    let base_path = base_dir.clone().unwrap_or_else(|| dir_utils::default_path("", None));
 
     // Create the tari data directory
     if let Err(e) = dir_utils::create_data_directory(Some(&base_path)) {
@@ -115,13 +148,34 @@ pub fn bootstrap_config_from_cli(matches: &ArgMatches) -> ConfigBootstrap {
     let config = matches
         .value_of("config")
         .map(PathBuf::from)
-        .unwrap_or_else(|| dir_utils::default_path(DEFAULT_CONFIG, Some(&base_path)));
+        .unwrap_or_else(|| dir_utils::default_config_path(DEFAULT_CONFIG, base_dir.as_ref()));
 
     let log_config = matches
         .value_of("log_config")
         .map(PathBuf::from)
-        .or_else(|| Some(base_path.clone().join(DEFAULT_LOG_CONFIG)));
+        .or_else(|| Some(dir_utils::default_config_path(DEFAULT_LOG_CONFIG, base_dir.as_ref())));
     let log_config = logging::get_log_configuration_path(log_config);
+    let log_format = match logging::get_log_format(matches.value_of("log_format")) {
+        Ok(log_format) => log_format,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(1);
+        },
+    };
+
+    let network = match matches.value_of("network").map(str::parse::<Network>).transpose() {
+        Ok(network) => network,
+        Err(e) => {
+            println!("{}. Use one of mainnet, rincewind or localnet.", e);
+            std::process::exit(1);
+        },
+    };
+
+    if let Some(config_dir) = config.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if let Err(e) = std::fs::create_dir_all(config_dir) {
+            println!("We couldn't create the configuration directory {:?}: {}", config_dir, e);
+        }
+    }
 
     if !config.exists() {
         let install = if !matches.is_present("init") {
@@ -152,10 +206,31 @@ pub fn bootstrap_config_from_cli(matches: &ArgMatches) -> ConfigBootstrap {
             install_configuration(&log_config, logging::install_default_logfile_config);
         }
     }
+
+    let secrets_file = matches
+        .value_of("secrets_file")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| dir_utils::default_config_path(DEFAULT_SECRETS_FILE, base_dir.as_ref()));
+    let secrets = if secrets_file.exists() {
+        match secrets::unlock_secrets_file(&secrets_file) {
+            Ok(secrets) => secrets,
+            Err(e) => {
+                println!("We couldn't unlock the secrets file {:?}: {}", secrets_file, e);
+                std::process::exit(1);
+            },
+        }
+    } else {
+        SecretStore::default()
+    };
+
     ConfigBootstrap {
         base_path,
         config,
         log_config,
+        log_format,
+        network,
+        secrets_file,
+        secrets,
     }
 }
 
@@ -167,6 +242,33 @@ fn prompt(question: &str) -> bool {
     input == "y" || input.is_empty()
 }
 
+/// Ask whether the `~/.tari` directory should be moved or linked to the platform directories
+fn offer_legacy_dir_migration(legacy_dir: &Path) {
+    let data_dir = dir_utils::default_data_dir();
+    let config_dir = dir_utils::default_config_dir();
+    println!(
+        "Tari is set to use the platform directories, but existing Tari data was found in {:?}.\nWould you like to \
+         move it to {:?} (m), link {:?} to it (s) or leave it where it is (N)?",
+        legacy_dir, data_dir, data_dir
+    );
+    let mut input = "".to_string();
+    if io::stdin().read_line(&mut input).is_err() {
+        return;
+    }
+    let migration = match input.trim().to_lowercase().as_str() {
+        "m" => dir_utils::LegacyDirMigration::Move,
+        "s" => dir_utils::LegacyDirMigration::Symlink,
+        _ => return,
+    };
+    match dir_utils::migrate_legacy_dir(legacy_dir, &data_dir, &config_dir, migration) {
+        Ok(_) => println!("Migrated {:?} to {:?}", legacy_dir, data_dir),
+        Err(e) => {
+            println!("We could not migrate {:?}: {}", legacy_dir, e);
+            std::process::exit(1);
+        },
+    }
+}
+
 pub fn install_configuration<F>(path: &Path, installer: F)
 where F: Fn(&Path) -> Result<(), std::io::Error> {
     if let Err(e) = installer(path) {
@@ -180,7 +282,7 @@ where F: Fn(&Path) -> Result<(), std::io::Error> {
 
 #[cfg(test)]
 mod test {
-    use crate::{bootstrap_config_from_cli, dir_utils, dir_utils::default_subdir, load_configuration};
+    use crate::{bootstrap_config_from_cli, dir_utils, dir_utils::default_subdir, load_configuration, Network};
     use clap::clap_app;
     use tari_test_utils::random::string;
     use tempdir::TempDir;
@@ -202,6 +304,7 @@ mod test {
             (@arg log_config: -l --log_config +takes_value "A path to the logfile configuration (log4rs.yml))")
             (@arg init: --init "Create a default configuration file if it doesn't exist")
             (@arg create_id: --create_id "Create and save new node identity if one doesn't exist ")
+            (@arg network: --network +takes_value "The network to run on (mainnet, rincewind or localnet)")
         )
         .get_matches_from(vec![
             "",
@@ -209,6 +312,8 @@ mod test {
             default_subdir("", Some(dir)).as_str(),
             "--init",
             "--create_id",
+            "--network",
+            "localnet",
         ]);
 
         // Load bootstrap
@@ -227,6 +332,8 @@ mod test {
         assert!(config_exists);
         assert!(log_config_exists);
         assert!(&cfg.is_ok());
+        assert_eq!(bootstrap.network, Some(Network::LocalNet));
+        assert_eq!(cfg.unwrap().get_str("base_node.network").unwrap(), "localnet");
     }
 
     #[test]
@@ -21,12 +21,57 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 //
 
+use log4rs::{
+    encode::{json::JsonEncoder, Encode},
+    file::{Deserialize, Deserializers},
+};
 use std::{
+    collections::BTreeMap,
     env,
+    error::Error,
+    fmt::{Display, Error as FormatError, Formatter},
     fs,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
+/// The format log lines are written in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// The patterns given in the log4rs configuration file
+    Text,
+    /// One JSON object per line with the timestamp, level, target, message and source location of the record. The
+    /// appenders in the log4rs configuration file are kept, only their encoders are replaced.
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Text
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            invalid => Err(format!("Invalid log format: {}. Use one of text or json", invalid)),
+        }
+    }
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FormatError> {
+        match self {
+            LogFormat::Text => f.write_str("text"),
+            LogFormat::Json => f.write_str("json"),
+        }
+    }
+}
+
 /// Determine the path to a log configuration file using the following precedence rules:
 /// 1. Use the provided path (usually pulled from a CLI argument)
 /// 2. Use the value in the `TARI_LOG_CONFIGURATION` envar
@@ -50,19 +95,57 @@ pub fn get_log_configuration_path(cli_path: Option<PathBuf>) -> PathBuf {
         .unwrap()
 }
 
-/// Set up application-level logging using the Log4rs configuration file specified in
-pub fn initialize_logging(config_file: &Path) -> bool {
+/// Determine the log format using the following precedence rules:
+/// 1. Use the provided value (usually pulled from a CLI argument)
+/// 2. Use the value in the `TARI_LOG_FORMAT` envar
+/// 3. Plain text
+pub fn get_log_format(cli_format: Option<&str>) -> Result<LogFormat, String> {
+    match cli_format {
+        Some(format) => format.parse(),
+        None => match env::var("TARI_LOG_FORMAT") {
+            Ok(format) if !format.is_empty() => format.parse(),
+            _ => Ok(LogFormat::default()),
+        },
+    }
+}
+
+/// Set up application-level logging using the Log4rs configuration file specified in `config_file`, writing log lines
+/// in the given `format`
+pub fn initialize_logging(config_file: &Path, format: LogFormat) -> bool {
     println!(
-        "Initializing logging according to {:?}",
+        "Initializing {} logging according to {:?}",
+        format,
         config_file.to_str().unwrap_or("[??]")
     );
-    if let Err(e) = log4rs::init_file(config_file, Default::default()) {
+    let mut deserializers = Deserializers::default();
+    if format == LogFormat::Json {
+        // Encoders without an explicit kind are pattern encoders, so this covers every appender in the sample file
+        deserializers.insert("pattern", JsonInPlaceOfPatternDeserializer);
+    }
+    if let Err(e) = log4rs::init_file(config_file, deserializers) {
         println!("We couldn't load a logging configuration file. {}", e.to_string());
         return false;
     }
     true
 }
 
+/// Builds a JSON encoder wherever the log4rs configuration file asks for a pattern encoder. The pattern is ignored.
+struct JsonInPlaceOfPatternDeserializer;
+
+impl Deserialize for JsonInPlaceOfPatternDeserializer {
+    type Config = BTreeMap<String, String>;
+    type Trait = dyn Encode;
+
+    fn deserialize(
+        &self,
+        _config: Self::Config,
+        _deserializers: &Deserializers,
+    ) -> Result<Box<dyn Encode>, Box<dyn Error + Sync + Send>>
+    {
+        Ok(Box::new(JsonEncoder::new()))
+    }
+}
+
 /// Installs a new default logfile configuration, copied from `log4rs-sample.yml` to the given path.
 pub fn install_default_logfile_config(path: &Path) -> Result<(), std::io::Error> {
     let source = include_str!("../logging/log4rs-sample.yml");
@@ -129,7 +212,10 @@ mod test {
     #[cfg(not(target_os = "windows"))]
     pub const PATH_SEPARATOR: &str = "/";
 
-    use crate::{dir_utils, logging::get_log_configuration_path};
+    use crate::{
+        dir_utils,
+        logging::{get_log_configuration_path, get_log_format, LogFormat},
+    };
     use std::{env, path::PathBuf};
 
     #[test]
@@ -157,6 +243,13 @@ mod test {
         env::set_var("TARI_LOG_CONFIGURATION", "");
     }
 
+    #[test]
+    fn get_log_format_from_cli() {
+        assert_eq!(get_log_format(Some("JSON")), Ok(LogFormat::Json));
+        assert_eq!(get_log_format(Some("text")), Ok(LogFormat::Text));
+        assert!(get_log_format(Some("xml")).is_err());
+    }
+
     #[test]
     fn log_if_error() {
         let err = Result::<(), _>::Err("What a shame");
@@ -1,6 +1,6 @@
 use sha2::{Digest, Sha256};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
     fmt::Display,
     fs,
     fs::File,
@@ -9,6 +9,9 @@ use std::{
     process::Command,
 };
 
+/// Name of the file in `OUT_DIR` which records the hash of each generated package
+const MANIFEST_FILE_NAME: &str = "proto_build.manifest";
+
 /// Runs rustfmt on the generated files - this is lifted from tonic-build
 fn fmt<P>(out_dir: P)
 where P: AsRef<Path> + Display {
@@ -50,23 +53,302 @@ fn walk_files<P: AsRef<Path>>(search_path: P, search_ext: &str) -> Vec<PathBuf>
     protos
 }
 
+fn hash_file_contents<P: AsRef<Path>>(file_path: P) -> Result<Vec<u8>, String> {
+    let mut file = File::open(file_path.as_ref())
+        .map_err(|err| format!("Failed to open '{}': {}", file_path.as_ref().display(), err))?;
+    let mut file_hash = Sha256::default();
+    io::copy(&mut file, &mut file_hash).map_err(|err| format!("Failed to hash file: '{}'", err))?;
+    Ok(file_hash.result().to_vec())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Proto packages which are shared between crates. Including a shared package adds its directory to the include
+/// paths, so that a crate can import those files without knowing where they live in the workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtoPackage {
+    /// `tari.types` and `tari.transaction_protocol` types (types.proto, transaction.proto)
+    CoreTypes,
+    /// `tari.core` block types (block.proto)
+    CoreBlocks,
+    /// `tari.dht.envelope` (envelope.proto)
+    DhtEnvelope,
+}
+
+impl ProtoPackage {
+    /// The directory containing the package's .proto files
+    pub fn path(&self) -> PathBuf {
+        let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        match self {
+            ProtoPackage::CoreTypes => workspace_root.join("base_layer/core/src/transactions/proto"),
+            ProtoPackage::CoreBlocks => workspace_root.join("base_layer/core/src/proto"),
+            ProtoPackage::DhtEnvelope => workspace_root.join("comms/dht/src/proto"),
+        }
+    }
+}
+
+/// A parsed .proto file
+#[derive(Debug, Clone)]
+struct ProtoFile {
+    package: Option<String>,
+    imports: Vec<PathBuf>,
+    declarations: Vec<ProtoDeclaration>,
+    fields: Vec<ProtoField>,
+}
+
+/// A type declared in a .proto file, identified by its fully-qualified path (e.g. `.tari.core.BlockHeader`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtoDeclaration {
+    Message(String),
+    Enum(String),
+    Oneof(String),
+}
+
+/// A (non-oneof) message field, identified by its fully-qualified path (e.g. `.tari.core.BlockHeader.height`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoField {
+    pub path: String,
+    pub type_name: String,
+    pub is_repeated: bool,
+}
+
+/// Splits the contents of a .proto file into statements, each terminated by `;`, `{` or `}`. Comments are removed.
+fn proto_statements(contents: &str) -> Vec<(String, char)> {
+    let uncommented = contents
+        .lines()
+        .map(|line| line.splitn(2, "//").next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    for c in uncommented.chars() {
+        match c {
+            ';' | '{' | '}' => {
+                statements.push((current.split_whitespace().collect::<Vec<_>>().join(" "), c));
+                current.clear();
+            },
+            c => current.push(c),
+        }
+    }
+    statements
+}
+
+/// The import graph of a set of .proto files and everything they (transitively) import from the include paths.
+/// Imports which cannot be resolved against the include paths (e.g. `google/protobuf/*.proto`, which are bundled with
+/// protoc) are left out of the graph.
+#[derive(Debug, Default)]
+pub struct ProtoDependencyGraph {
+    files: BTreeMap<PathBuf, ProtoFile>,
+}
+
+impl ProtoDependencyGraph {
+    /// Parse the given .proto files and all the files they import
+    pub fn build<P: AsRef<Path>>(protos: &[PathBuf], include_paths: &[P]) -> Result<Self, String> {
+        let mut graph = Self::default();
+        let mut queue = protos.iter().cloned().collect::<VecDeque<_>>();
+        while let Some(path) = queue.pop_front() {
+            if graph.files.contains_key(&path) {
+                continue;
+            }
+            let contents =
+                fs::read_to_string(&path).map_err(|err| format!("Failed to read '{}': {}", path.display(), err))?;
+            let (mut file, import_names) = Self::parse(&contents);
+            file.imports = import_names
+                .iter()
+                .filter_map(|name| {
+                    include_paths
+                        .iter()
+                        .map(|include_path| include_path.as_ref().join(name))
+                        .find(|p| p.is_file())
+                })
+                .collect::<Vec<_>>();
+            queue.extend(file.imports.iter().cloned());
+            graph.files.insert(path, file);
+        }
+        Ok(graph)
+    }
+
+    /// Parses a .proto file, returning the file (with unresolved imports) and the imported file names
+    fn parse(contents: &str) -> (ProtoFile, Vec<String>) {
+        enum Scope {
+            Message(String),
+            Enum,
+            Oneof,
+            Other,
+        }
+
+        let statements = proto_statements(contents);
+        let package = statements
+            .iter()
+            .find(|(stmt, _)| stmt.starts_with("package "))
+            .map(|(stmt, _)| stmt["package ".len()..].trim().to_string());
+        let package_path = package.as_ref().map(|p| format!(".{}", p)).unwrap_or_default();
+
+        let mut imports = Vec::new();
+        let mut declarations = Vec::new();
+        let mut fields = Vec::new();
+        let mut scopes = Vec::<Scope>::new();
+        for (stmt, terminator) in statements {
+            let words = stmt.split(' ').collect::<Vec<_>>();
+            let parent_path = scopes
+                .iter()
+                .rev()
+                .find_map(|scope| match scope {
+                    Scope::Message(path) => Some(path.clone()),
+                    _ => None,
+                })
+                .unwrap_or_else(|| package_path.clone());
+            match terminator {
+                '{' => {
+                    let scope = match words.as_slice() {
+                        ["message", name] => {
+                            let path = format!("{}.{}", parent_path, name);
+                            declarations.push(ProtoDeclaration::Message(path.clone()));
+                            Scope::Message(path)
+                        },
+                        ["enum", name] => {
+                            declarations.push(ProtoDeclaration::Enum(format!("{}.{}", parent_path, name)));
+                            Scope::Enum
+                        },
+                        ["oneof", name] => {
+                            declarations.push(ProtoDeclaration::Oneof(format!("{}.{}", parent_path, name)));
+                            Scope::Oneof
+                        },
+                        _ => Scope::Other,
+                    };
+                    scopes.push(scope);
+                },
+                '}' => {
+                    scopes.pop();
+                },
+                _ => match scopes.last() {
+                    None if words[0] == "import" => {
+                        imports.push(words[words.len() - 1].trim_matches('"').to_string());
+                    },
+                    Some(Scope::Message(message_path)) => {
+                        let is_repeated = words[0] == "repeated";
+                        let field_words = if is_repeated { &words[1..] } else { &words[..] };
+                        if field_words.len() >= 3 &&
+                            field_words[2] == "=" &&
+                            field_words[0] != "option" &&
+                            !field_words[0].starts_with("map<")
+                        {
+                            fields.push(ProtoField {
+                                path: format!("{}.{}", message_path, field_words[1]),
+                                type_name: field_words[0].to_string(),
+                                is_repeated,
+                            });
+                        }
+                    },
+                    _ => {},
+                },
+            }
+        }
+
+        let file = ProtoFile {
+            package,
+            imports: Vec::new(),
+            declarations,
+            fields,
+        };
+        (file, imports)
+    }
+
+    /// Returns every file in the graph
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+
+    /// Returns the given files and all the files they transitively import
+    pub fn with_dependencies(&self, paths: &[PathBuf]) -> BTreeSet<PathBuf> {
+        let mut deps = BTreeSet::new();
+        let mut stack = paths.to_vec();
+        while let Some(path) = stack.pop() {
+            if !deps.insert(path.clone()) {
+                continue;
+            }
+            if let Some(file) = self.files.get(&path) {
+                stack.extend(file.imports.iter().cloned());
+            }
+        }
+        deps
+    }
+
+    /// Groups the given files by their proto package. Files without a package declaration are grouped under `_`,
+    /// which is the name prost uses for the generated file.
+    pub fn packages(&self, paths: &[PathBuf]) -> BTreeMap<String, Vec<PathBuf>> {
+        paths.iter().fold(BTreeMap::new(), |mut packages, path| {
+            let package = self
+                .files
+                .get(path)
+                .and_then(|f| f.package.clone())
+                .unwrap_or_else(|| "_".to_string());
+            packages.entry(package).or_insert_with(Vec::new).push(path.clone());
+            packages
+        })
+    }
+
+    /// Returns a hash over the contents of the given files and all the files they transitively import
+    pub fn hash(&self, paths: &[PathBuf]) -> Result<String, String> {
+        let mut hasher = Sha256::default();
+        for path in self.with_dependencies(paths) {
+            hasher.input(path.to_string_lossy().as_bytes());
+            hasher.input(&hash_file_contents(&path)?);
+        }
+        Ok(to_hex(&hasher.result()))
+    }
+}
+
+fn read_manifest<P: AsRef<Path>>(path: P) -> HashMap<String, String> {
+    fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| {
+                    let mut parts = line.splitn(2, ' ');
+                    Some((parts.next()?.to_string(), parts.next()?.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn write_manifest<P: AsRef<Path>>(path: P, manifest: &HashMap<String, String>) -> Result<(), String> {
+    let contents = manifest
+        .iter()
+        .map(|(package, hash)| format!("{} {}\n", package, hash))
+        .collect::<String>();
+    fs::write(path, contents).map_err(|err| format!("Failed to write proto manifest: {}", err))
+}
+
 #[derive(Default)]
 pub struct ProtoCompiler {
     out_dir: Option<PathBuf>,
-    type_attributes: HashMap<&'static str, &'static str>,
-    field_attributes: HashMap<&'static str, &'static str>,
+    type_attributes: Vec<(String, String)>,
+    field_attributes: Vec<(String, String)>,
     proto_paths: Vec<PathBuf>,
     include_paths: Vec<PathBuf>,
+    serde: Option<SerdeOptions>,
+}
+
+/// Packages for which serde implementations are derived, and the module providing the field helpers
+#[derive(Debug, Clone)]
+struct SerdeOptions {
+    packages: Vec<String>,
+    helpers_path: String,
 }
 
 impl ProtoCompiler {
     pub fn new() -> Self {
         Self {
             out_dir: None,
-            type_attributes: HashMap::new(),
-            field_attributes: HashMap::new(),
+            type_attributes: Vec::new(),
+            field_attributes: Vec::new(),
             proto_paths: Vec::new(),
             include_paths: Vec::new(),
+            serde: None,
         }
     }
 
@@ -77,15 +359,98 @@ impl ProtoCompiler {
     }
 
     pub fn add_type_attribute(&mut self, path: &'static str, attr: &'static str) -> &mut Self {
-        self.type_attributes.insert(path, attr);
+        self.type_attributes.push((path.to_string(), attr.to_string()));
         self
     }
 
     pub fn add_field_attribute(&mut self, path: &'static str, attr: &'static str) -> &mut Self {
-        self.field_attributes.insert(path, attr);
+        self.field_attributes.push((path.to_string(), attr.to_string()));
         self
     }
 
+    /// Derive `serde::Serialize` and `serde::Deserialize` for every message, enum and oneof in the given proto
+    /// packages, using the proto3 JSON field names (lowerCamelCase). Fields whose default serde representation
+    /// differs from the canonical JSON mapping use `#[serde(with = "...")]` helpers from the `helpers_path` module,
+    /// which must provide the following submodules:
+    /// - `bytes` for `bytes` fields (base64 string)
+    /// - `repeated_bytes` for `repeated bytes` fields (array of base64 strings)
+    /// - `uint64` and `int64` for 64-bit integer fields (decimal string)
+    /// - `timestamp` for `google.protobuf.Timestamp` fields (RFC 3339 string)
+    ///
+    /// Fields within a `oneof` use the default serde representation of their type.
+    pub fn derive_serde(&mut self, packages: &[&str], helpers_path: &str) -> &mut Self {
+        self.serde = Some(SerdeOptions {
+            packages: packages.iter().map(|p| p.to_string()).collect(),
+            helpers_path: helpers_path.to_string(),
+        });
+        self
+    }
+
+    /// Returns the type and field attributes which implement the `derive_serde` option
+    fn serde_attributes(
+        &self,
+        graph: &ProtoDependencyGraph,
+    ) -> Result<(Vec<(String, String)>, Vec<(String, String)>), String>
+    {
+        let options = match &self.serde {
+            Some(options) => options,
+            None => return Ok((Vec::new(), Vec::new())),
+        };
+
+        let derive = "#[derive(serde::Serialize, serde::Deserialize)]";
+        let mut type_attributes = Vec::new();
+        let mut field_attributes = Vec::new();
+        for file in graph.files.values() {
+            let is_included = file.package.as_ref().map_or(false, |p| options.packages.contains(p));
+            if !is_included {
+                continue;
+            }
+
+            for declaration in &file.declarations {
+                match declaration {
+                    ProtoDeclaration::Message(path) => {
+                        type_attributes.push((
+                            path.clone(),
+                            format!("{}\n#[serde(default, rename_all = \"camelCase\")]", derive),
+                        ));
+                    },
+                    ProtoDeclaration::Enum(path) => {
+                        type_attributes.push((path.clone(), derive.to_string()));
+                    },
+                    ProtoDeclaration::Oneof(path) => {
+                        type_attributes.push((
+                            path.clone(),
+                            format!("{}\n#[serde(rename_all = \"camelCase\")]", derive),
+                        ));
+                    },
+                }
+            }
+
+            for field in &file.fields {
+                let helper = match (field.type_name.as_str(), field.is_repeated) {
+                    ("bytes", false) => "bytes",
+                    ("bytes", true) => "repeated_bytes",
+                    ("uint64", false) | ("fixed64", false) => "uint64",
+                    ("int64", false) | ("sint64", false) | ("sfixed64", false) => "int64",
+                    ("google.protobuf.Timestamp", false) => "timestamp",
+                    (type_name, _) if type_name.starts_with("google.protobuf.") && !type_name.ends_with("Value") => {
+                        return Err(format!(
+                            "Field '{}' of type '{}' is not supported by derive_serde",
+                            field.path, type_name
+                        ));
+                    },
+                    _ => continue,
+                };
+                field_attributes.push((
+                    field.path.clone(),
+                    format!("#[serde(with = \"{}::{}\")]", options.helpers_path, helper),
+                ));
+            }
+        }
+
+        Ok((type_attributes, field_attributes))
+    }
+
     pub fn proto_paths<P: AsRef<Path>>(&mut self, proto_paths: &[P]) -> &mut Self {
         self.proto_paths
             .extend(proto_paths.iter().map(|p| p.as_ref().to_path_buf()));
@@ -98,11 +463,10 @@ impl ProtoCompiler {
         self
     }
 
-    fn hash_file_contents<P: AsRef<Path>>(&self, file_path: P) -> Result<Vec<u8>, String> {
-        let mut file = File::open(file_path).unwrap();
-        let mut file_hash = Sha256::default();
-        io::copy(&mut file, &mut file_hash).map_err(|err| format!("Failed to hash file: '{}'", err))?;
-        Ok(file_hash.result().to_vec())
+    /// Make the given shared proto packages available to `import` statements
+    pub fn include_packages(&mut self, packages: &[ProtoPackage]) -> &mut Self {
+        self.include_paths.extend(packages.iter().map(ProtoPackage::path));
+        self
     }
 
     fn compare_and_move<P: AsRef<Path>>(&self, tmp_out_dir: P, out_dir: P) {
@@ -110,8 +474,8 @@ impl ProtoCompiler {
         for tmp_file in tmp_files {
             let target_file = out_dir.as_ref().join(tmp_file.file_name().unwrap());
             if target_file.exists() {
-                let tmp_hash = self.hash_file_contents(&tmp_file).unwrap();
-                let target_hash = self.hash_file_contents(&target_file).unwrap();
+                let tmp_hash = hash_file_contents(&tmp_file).unwrap();
+                let target_hash = hash_file_contents(&target_file).unwrap();
                 if tmp_hash != target_hash {
                     fs::rename(tmp_file, target_file).unwrap();
                 }
@@ -133,28 +497,64 @@ impl ProtoCompiler {
             protos
         });
 
-        let mut config = prost_build::Config::new();
-
-        for (k, v) in &self.type_attributes {
-            config.type_attribute(k, v);
+        let graph = ProtoDependencyGraph::build(&protos, &self.include_paths)?;
+        for path in graph.files() {
+            println!("cargo:rerun-if-changed={}", path.display());
         }
 
-        for (k, v) in &self.field_attributes {
-            config.field_attribute(k, v);
-        }
+        let (serde_type_attributes, serde_field_attributes) = self.serde_attributes(&graph)?;
+        let type_attributes = self.type_attributes.iter().chain(serde_type_attributes.iter());
+        let field_attributes = self.field_attributes.iter().chain(serde_field_attributes.iter());
+
+        // Attributes change the generated code, so they are part of each package hash
+        let attributes_hash = type_attributes
+            .clone()
+            .chain(field_attributes.clone())
+            .fold(Sha256::default(), |hasher, (path, attr)| hasher.chain(path).chain(attr))
+            .result();
+        let attributes_hash = to_hex(&attributes_hash);
 
         let out_dir = self
             .out_dir
             .take()
             .unwrap_or_else(|| PathBuf::from(std::env::var("OUT_DIR").unwrap()));
 
+        // Only regenerate the packages whose files (or any file they import) have changed since the last build, or
+        // whose generated code is missing
+        let manifest_path = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join(MANIFEST_FILE_NAME);
+        let previous_manifest = read_manifest(&manifest_path);
+        let mut manifest = HashMap::new();
+        let mut changed = Vec::new();
+        for (package, files) in graph.packages(&protos) {
+            let hash = format!("{}{}", graph.hash(&files)?, attributes_hash);
+            let is_generated = out_dir.join(format!("{}.rs", package)).exists();
+            if !is_generated || previous_manifest.get(&package) != Some(&hash) {
+                changed.extend(files);
+            }
+            manifest.insert(package, hash);
+        }
+
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        let mut config = prost_build::Config::new();
+
+        for (k, v) in type_attributes {
+            config.type_attribute(k, v);
+        }
+
+        for (k, v) in field_attributes {
+            config.field_attribute(k, v);
+        }
+
         let tmp_out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("tmp_protos");
         fs::create_dir_all(&tmp_out_dir)
             .map_err(|err| format!("Failed to create temporary out dir because '{}'", err))?;
 
         config.out_dir(tmp_out_dir.clone());
 
-        config.compile_protos(&protos, &self.include_paths).map_err(|err| {
+        config.compile_protos(&changed, &self.include_paths).map_err(|err| {
             // Side effect - print the error to stderr
             eprintln!("\n{}", err);
             format!("{}", err)
@@ -166,6 +566,103 @@ impl ProtoCompiler {
 
         fs::remove_dir_all(&tmp_out_dir).map_err(|err| format!("Failed to remove temporary dir: {}", err))?;
 
+        write_manifest(&manifest_path, &manifest)?;
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn parse() {
+        let (file, imports) = ProtoDependencyGraph::parse(
+            r#"
+syntax = "proto3";
+
+import "types.proto";
+import public "block.proto";
+
+package tari.core;
+
+// A message; with a comment {
+message Header {
+    option deprecated = true;
+    uint64 height = 1;
+    repeated bytes hashes = 2; // trailing comment
+    map<string, bytes> extra = 3;
+    message Inner {
+        google.protobuf.Timestamp timestamp = 1;
+    }
+    oneof pow {
+        bytes monero = 4;
+    }
+    enum Algo {
+        BLAKE = 0;
+    }
+}
+"#,
+        );
+        assert_eq!(file.package.unwrap(), "tari.core");
+        assert_eq!(imports, vec!["types.proto", "block.proto"]);
+        assert_eq!(file.declarations, vec![
+            ProtoDeclaration::Message(".tari.core.Header".to_string()),
+            ProtoDeclaration::Message(".tari.core.Header.Inner".to_string()),
+            ProtoDeclaration::Oneof(".tari.core.Header.pow".to_string()),
+            ProtoDeclaration::Enum(".tari.core.Header.Algo".to_string()),
+        ]);
+        assert_eq!(file.fields, vec![
+            ProtoField {
+                path: ".tari.core.Header.height".to_string(),
+                type_name: "uint64".to_string(),
+                is_repeated: false,
+            },
+            ProtoField {
+                path: ".tari.core.Header.hashes".to_string(),
+                type_name: "bytes".to_string(),
+                is_repeated: true,
+            },
+            ProtoField {
+                path: ".tari.core.Header.Inner.timestamp".to_string(),
+                type_name: "google.protobuf.Timestamp".to_string(),
+                is_repeated: false,
+            },
+        ]);
+    }
+
+    #[test]
+    fn dependency_graph() {
+        let shared_dir = TempDir::new("shared").unwrap();
+        let crate_dir = TempDir::new("crate").unwrap();
+        let types = shared_dir.path().join("types.proto");
+        fs::write(&types, "package tari.types;").unwrap();
+        let a = crate_dir.path().join("a.proto");
+        fs::write(
+            &a,
+            "import \"types.proto\";\nimport \"google/protobuf/timestamp.proto\";\npackage tari.a;",
+        )
+        .unwrap();
+        let b = crate_dir.path().join("b.proto");
+        fs::write(&b, "import \"a.proto\";\npackage tari.a;").unwrap();
+
+        let protos = vec![a.clone(), b.clone()];
+        let graph = ProtoDependencyGraph::build(&protos, &[crate_dir.path(), shared_dir.path()]).unwrap();
+        assert_eq!(graph.files().count(), 3);
+        let deps = graph.with_dependencies(&[b.clone()]);
+        assert!(deps.contains(&a));
+        assert!(deps.contains(&types));
+
+        let packages = graph.packages(&protos);
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages["tari.a"], protos);
+
+        // Changing an imported file in another directory changes the hash of the importing package
+        let hash = graph.hash(&protos).unwrap();
+        assert_eq!(graph.hash(&protos).unwrap(), hash);
+        fs::write(&types, "package tari.types;\nmessage Foo {}").unwrap();
+        assert_ne!(graph.hash(&protos).unwrap(), hash);
+    }
+}
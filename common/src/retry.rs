@@ -0,0 +1,324 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Retry policies
+//!
+//! A [RetryPolicy] describes how often an operation that talks to a remote peer is attempted and how long to wait
+//! between attempts. Delays are either fixed or grow exponentially (up to a maximum delay), and can be randomised by a
+//! jitter fraction so that many nodes retrying against the same peer do not do so in lock-step.
+//!
+//! ```edition2018
+//! # use tari_common::retry::RetryPolicy;
+//! # use std::time::Duration;
+//! let policy = RetryPolicy::exponential(Duration::from_millis(100))
+//!     .with_max_delay(Duration::from_secs(1))
+//!     .with_max_attempts(5);
+//! assert_eq!(policy.delay_for_attempt(1), Duration::from_millis(0));
+//! assert_eq!(policy.delay_for_attempt(2), Duration::from_millis(100));
+//! assert_eq!(policy.delay_for_attempt(3), Duration::from_millis(200));
+//! assert_eq!(policy.delay_for_attempt(5), Duration::from_millis(800));
+//! assert_eq!(policy.delay_for_attempt(6), Duration::from_secs(1));
+//! ```
+
+use rand::Rng;
+use std::{future::Future, time::Duration};
+use tokio::time;
+
+/// The default multiplier applied to the delay after every failed attempt of an exponential policy
+pub const DEFAULT_BACKOFF_FACTOR: u32 = 2;
+/// The default upper bound of the delay between attempts
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backoff {
+    Fixed(Duration),
+    Exponential { initial: Duration, factor: u32 },
+}
+
+/// Describes how many times an operation is attempted and how long to wait between attempts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    backoff: Backoff,
+    max_attempts: Option<usize>,
+    max_delay: Duration,
+    jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Wait the same `delay` between every attempt.
+    pub fn fixed(delay: Duration) -> Self {
+        Self::new(Backoff::Fixed(delay))
+    }
+
+    /// Wait `initial` before the first retry, multiplying the delay by the backoff factor (default 2) for every
+    /// retry after that.
+    pub fn exponential(initial: Duration) -> Self {
+        Self::new(Backoff::Exponential {
+            initial,
+            factor: DEFAULT_BACKOFF_FACTOR,
+        })
+    }
+
+    /// Attempt the operation once and never retry it.
+    pub fn no_retry() -> Self {
+        Self::fixed(Duration::from_millis(0)).with_max_attempts(1)
+    }
+
+    fn new(backoff: Backoff) -> Self {
+        Self {
+            backoff,
+            max_attempts: None,
+            max_delay: DEFAULT_MAX_DELAY,
+            jitter: 0.0,
+        }
+    }
+
+    /// Set the multiplier of an exponential policy. This has no effect on a fixed policy.
+    pub fn with_factor(mut self, factor: u32) -> Self {
+        if let Backoff::Exponential { initial, .. } = self.backoff {
+            self.backoff = Backoff::Exponential {
+                initial,
+                factor: factor.max(1),
+            };
+        }
+        self
+    }
+
+    /// Set the maximum number of attempts, including the first. A policy without a maximum retries indefinitely.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts.max(1));
+        self
+    }
+
+    /// Set the upper bound of the delay between attempts.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Shorten every delay by a random amount of up to `fraction` of its value. The fraction is clamped to [0, 1].
+    pub fn with_jitter(mut self, fraction: f64) -> Self {
+        self.jitter = if fraction.is_finite() { fraction.max(0.0).min(1.0) } else { 0.0 };
+        self
+    }
+
+    /// The maximum number of attempts, or `None` if the operation is retried indefinitely
+    pub fn max_attempts(&self) -> Option<usize> {
+        self.max_attempts
+    }
+
+    /// Returns true if another attempt may be made after `attempt` attempts have failed
+    pub fn can_retry(&self, attempt: usize) -> bool {
+        self.max_attempts.map_or(true, |max| attempt < max)
+    }
+
+    /// The delay, without jitter, before the given (1-based) attempt. The first attempt is never delayed.
+    pub fn delay_for_attempt(&self, attempt: usize) -> Duration {
+        if attempt <= 1 {
+            return Duration::from_millis(0);
+        }
+        let delay = match self.backoff {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, factor } => {
+                let exponent = (attempt - 2).min(u32::max_value() as usize) as u32;
+                factor
+                    .checked_pow(exponent)
+                    .and_then(|multiplier| initial.checked_mul(multiplier))
+                    .unwrap_or(self.max_delay)
+            },
+        };
+        delay.min(self.max_delay)
+    }
+
+    /// The delay before the given (1-based) attempt, randomised by the jitter fraction of this policy
+    pub fn jittered_delay_for_attempt(&self, attempt: usize) -> Duration {
+        let delay = self.delay_for_attempt(attempt);
+        if self.jitter <= 0.0 || delay == Duration::from_millis(0) {
+            return delay;
+        }
+        let scale = rand::thread_rng().gen_range(1.0 - self.jitter, 1.0);
+        delay.mul_f64(scale)
+    }
+
+    /// Returns an iterator-like tracker of attempts which waits for the backoff delay before yielding each retry.
+    pub fn attempts(&self) -> Attempts {
+        Attempts {
+            policy: *self,
+            attempt: 0,
+        }
+    }
+
+    /// Call `operation` until it succeeds or the maximum number of attempts is reached, returning the last error.
+    /// The operation is passed the (1-based) attempt number.
+    pub async fn retry<F, Fut, T, E>(&self, operation: F) -> Result<T, E>
+    where
+        F: FnMut(usize) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.retry_if(operation, |_| true).await
+    }
+
+    /// Call `operation` until it succeeds, `should_retry` returns false for the error or the maximum number of
+    /// attempts is reached, returning the last error. The operation is passed the (1-based) attempt number.
+    pub async fn retry_if<F, Fut, T, E, P>(&self, mut operation: F, mut should_retry: P) -> Result<T, E>
+    where
+        F: FnMut(usize) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        P: FnMut(&E) -> bool,
+    {
+        let mut attempts = self.attempts();
+        loop {
+            attempts.wait().await;
+            let attempt = attempts.current();
+            match operation(attempt).await {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    if !should_retry(&err) || !self.can_retry(attempt) {
+                        return Err(err);
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Tracks the attempts made under a [RetryPolicy].
+pub struct Attempts {
+    policy: RetryPolicy,
+    attempt: usize,
+}
+
+impl Attempts {
+    /// Wait for the backoff delay and return the (1-based) number of the next attempt, or `None` if the maximum
+    /// number of attempts has been made. The first attempt is returned immediately.
+    pub async fn next(&mut self) -> Option<usize> {
+        if !self.policy.can_retry(self.attempt) {
+            return None;
+        }
+        self.wait().await;
+        Some(self.attempt)
+    }
+
+    /// The number of attempts made so far
+    pub fn current(&self) -> usize {
+        self.attempt
+    }
+
+    async fn wait(&mut self) {
+        self.attempt += 1;
+        let delay = self.policy.jittered_delay_for_attempt(self.attempt);
+        if delay > Duration::from_millis(0) {
+            time::delay_for(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn fixed_delay() {
+        let policy = RetryPolicy::fixed(Duration::from_millis(50));
+        assert_eq!(policy.delay_for_attempt(1), Duration::from_millis(0));
+        assert_eq!(policy.delay_for_attempt(2), Duration::from_millis(50));
+        assert_eq!(policy.delay_for_attempt(10), Duration::from_millis(50));
+        assert_eq!(policy.max_attempts(), None);
+        assert!(policy.can_retry(1000));
+    }
+
+    #[test]
+    fn exponential_delay() {
+        let policy = RetryPolicy::exponential(Duration::from_millis(10))
+            .with_factor(3)
+            .with_max_delay(Duration::from_secs(1));
+        assert_eq!(policy.delay_for_attempt(2), Duration::from_millis(10));
+        assert_eq!(policy.delay_for_attempt(3), Duration::from_millis(30));
+        assert_eq!(policy.delay_for_attempt(4), Duration::from_millis(90));
+        assert_eq!(policy.delay_for_attempt(7), Duration::from_secs(1));
+        assert_eq!(policy.delay_for_attempt(usize::max_value()), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn jittered_delay() {
+        let policy = RetryPolicy::fixed(Duration::from_millis(100)).with_jitter(0.5);
+        for _ in 0..100 {
+            let delay = policy.jittered_delay_for_attempt(2);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+        assert_eq!(policy.jittered_delay_for_attempt(1), Duration::from_millis(0));
+    }
+
+    #[tokio_macros::test_basic]
+    async fn retry_until_max_attempts() {
+        let policy = RetryPolicy::fixed(Duration::from_millis(1)).with_max_attempts(3);
+        let calls = Cell::new(0);
+        let result: Result<(), usize> = policy
+            .retry(|attempt| {
+                calls.set(calls.get() + 1);
+                async move { Err(attempt) }
+            })
+            .await;
+        assert_eq!(result, Err(3));
+        assert_eq!(calls.get(), 3);
+
+        let result = policy
+            .retry(|attempt| async move { if attempt < 2 { Err(()) } else { Ok(attempt) } })
+            .await;
+        assert_eq!(result, Ok(2));
+    }
+
+    #[tokio_macros::test_basic]
+    async fn retry_if_predicate() {
+        let policy = RetryPolicy::fixed(Duration::from_millis(1)).with_max_attempts(5);
+        let calls = Cell::new(0);
+        let result: Result<(), &str> = policy
+            .retry_if(
+                |_| {
+                    calls.set(calls.get() + 1);
+                    async { Err("fatal") }
+                },
+                |err| *err != "fatal",
+            )
+            .await;
+        assert_eq!(result, Err("fatal"));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[tokio_macros::test_basic]
+    async fn attempts() {
+        let mut attempts = RetryPolicy::no_retry().attempts();
+        assert_eq!(attempts.next().await, Some(1));
+        assert_eq!(attempts.next().await, None);
+
+        let mut attempts = RetryPolicy::fixed(Duration::from_millis(1))
+            .with_max_attempts(3)
+            .attempts();
+        let mut made = Vec::new();
+        while let Some(attempt) = attempts.next().await {
+            made.push(attempt);
+        }
+        assert_eq!(made, vec![1, 2, 3]);
+        assert_eq!(attempts.current(), 3);
+    }
+}
@@ -0,0 +1,538 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Secrets
+//!
+//! Sensitive settings, such as tor control passwords and SOCKS credentials, do not have to be written to the
+//! configuration file in plain text. Instead, the value of a sensitive setting can be a reference to where the secret
+//! is kept:
+//!
+//! * `env:NAME` - the value of the environment variable `NAME`
+//! * `file:/path/to/file` - the contents of the file, with trailing new lines removed
+//! * `keyring:service/user` or `keyring:user` - the password stored in the system keyring. The service is `tari` if it
+//!   is not given. This requires the `keyring` feature.
+//! * `secret:name` - an entry in the encrypted secrets file
+//!
+//! Values without one of these prefixes are used as they are. The reference replaces the whole value, e.g.
+//! `tor_control_auth = "env:TARI_TOR_CONTROL_AUTH"` with `TARI_TOR_CONTROL_AUTH=password=xxxxxx` set.
+//!
+//! The encrypted secrets file (`secrets.enc` next to the configuration file by default) is unlocked with a passphrase
+//! when the application starts. The passphrase is read from the `TARI_SECRETS_PASSPHRASE` environment variable, or
+//! asked for if that is not set.
+
+use config::Config;
+use digest::Digest;
+use log::*;
+use rand::{rngs::OsRng, RngCore};
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fmt::{Display, Formatter, Result as FormatResult},
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+use tari_crypto::{
+    common::Blake256,
+    tari_utilities::{
+        ciphers::{chacha20::ChaCha20, cipher::Cipher},
+        hex::{from_hex, to_hex},
+    },
+};
+use zeroize::Zeroizing;
+
+const LOG_TARGET: &str = "common::secrets";
+
+/// The environment variable the secrets file passphrase is read from
+pub const SECRETS_PASSPHRASE_ENV_VAR: &str = "TARI_SECRETS_PASSPHRASE";
+/// The secrets file format version written by this version of the software
+pub const SECRETS_FILE_VERSION: u32 = 1;
+
+/// The settings in each `[base_node.<network>]` section that may hold secret references
+const SENSITIVE_NETWORK_KEYS: &[&str] = &[
+    "identity_passphrase",
+    "tcp_tor_socks_auth",
+    "tor_control_auth",
+    "tor_socks_auth",
+    "socks5_auth",
+];
+/// The settings outside of the network sections that may hold secret references
+const SENSITIVE_KEYS: &[&str] = &["wallet.backup_passphrase"];
+const NETWORKS: &[&str] = &["mainnet", "rincewind", "localnet"];
+
+const DEFAULT_KEYRING_SERVICE: &str = "tari";
+const FILE_HEADER: &str = "tari_secrets";
+const CIPHER_KEY_DOMAIN: &[u8] = b"com.tari.secrets_file.cipher_key";
+const MAC_KEY_DOMAIN: &[u8] = b"com.tari.secrets_file.mac_key";
+const SALT_SIZE: usize = 16;
+
+#[derive(Debug)]
+pub enum SecretError {
+    IoError(io::Error),
+    /// The environment variable named in an `env:` reference is not set
+    EnvVarNotSet(String),
+    /// The system keyring could not provide the password for a `keyring:` reference
+    KeyringError(String),
+    /// A `secret:` reference was used, but no secrets file has been unlocked
+    SecretsFileLocked(String),
+    /// The unlocked secrets file does not contain the secret
+    SecretNotFound(String),
+    /// The secrets file was written by a newer version of the software
+    UnsupportedVersion(u32),
+    /// The MAC does not match, either the passphrase is incorrect or the file is corrupted
+    IntegrityCheckFailed,
+    InvalidSecretsFile(String),
+    InvalidSecretName(String),
+}
+
+impl Display for SecretError {
+    fn fmt(&self, f: &mut Formatter) -> FormatResult {
+        match self {
+            SecretError::IoError(e) => write!(f, "{}", e),
+            SecretError::EnvVarNotSet(name) => write!(f, "Environment variable '{}' is not set", name),
+            SecretError::KeyringError(e) => write!(f, "Could not read the secret from the keyring: {}", e),
+            SecretError::SecretsFileLocked(name) => {
+                write!(
+                    f,
+                    "Secret '{}' is kept in the secrets file, which has not been unlocked",
+                    name
+                )
+            },
+            SecretError::SecretNotFound(name) => write!(f, "Secret '{}' is not in the secrets file", name),
+            SecretError::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "The secrets file was written by a newer version of Tari (version {})",
+                    v
+                )
+            },
+            SecretError::IntegrityCheckFailed => {
+                f.write_str("The secrets file passphrase is incorrect or the file is corrupted")
+            },
+            SecretError::InvalidSecretsFile(e) => write!(f, "Invalid secrets file: {}", e),
+            SecretError::InvalidSecretName(name) => write!(
+                f,
+                "Invalid secret name '{}'. Names may not be empty or contain '=' or new lines",
+                name
+            ),
+        }
+    }
+}
+
+impl Error for SecretError {}
+
+impl From<io::Error> for SecretError {
+    fn from(e: io::Error) -> Self {
+        SecretError::IoError(e)
+    }
+}
+
+/// Where the value of a sensitive setting comes from
+#[derive(Clone, Debug, PartialEq)]
+pub enum SecretRef {
+    /// The setting holds the secret itself
+    Plain(String),
+    Env(String),
+    File(PathBuf),
+    Keyring {
+        service: String,
+        user: String,
+    },
+    SecretsFile(String),
+}
+
+impl SecretRef {
+    pub fn parse(value: &str) -> Self {
+        let mut parts = value.splitn(2, ':');
+        let scheme = parts.next().expect("splitn always emits at least one part");
+        match (scheme, parts.next()) {
+            ("env", Some(name)) => SecretRef::Env(name.to_string()),
+            ("file", Some(path)) => SecretRef::File(PathBuf::from(path)),
+            ("keyring", Some(entry)) => {
+                let mut parts = entry.rsplitn(2, '/');
+                let user = parts
+                    .next()
+                    .expect("rsplitn always emits at least one part")
+                    .to_string();
+                let service = parts.next().unwrap_or(DEFAULT_KEYRING_SERVICE).to_string();
+                SecretRef::Keyring { service, user }
+            },
+            ("secret", Some(name)) => SecretRef::SecretsFile(name.to_string()),
+            _ => SecretRef::Plain(value.to_string()),
+        }
+    }
+
+    /// Returns true if the setting holds the secret itself rather than a reference to it
+    pub fn is_plain(&self) -> bool {
+        match self {
+            SecretRef::Plain(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The cipher and MAC keys derived from the secrets file passphrase
+#[derive(Clone)]
+struct SecretsFileKeys {
+    salt: Vec<u8>,
+    cipher_key: Zeroizing<Vec<u8>>,
+    mac_key: Zeroizing<Vec<u8>>,
+}
+
+impl SecretsFileKeys {
+    fn derive(passphrase: &str, salt: Vec<u8>) -> Result<Self, SecretError> {
+        let master_key = argon2::hash_raw(passphrase.as_bytes(), &salt, &argon2::Config::default())
+            .map(Zeroizing::new)
+            .map_err(|e| SecretError::InvalidSecretsFile(format!("Passphrase key derivation failed: {}", e)))?;
+        let derive_key =
+            |domain: &[u8]| Zeroizing::new(Blake256::new().chain(domain).chain(&*master_key).result().to_vec());
+        Ok(Self {
+            cipher_key: derive_key(CIPHER_KEY_DOMAIN),
+            mac_key: derive_key(MAC_KEY_DOMAIN),
+            salt,
+        })
+    }
+
+    fn calculate_mac(&self, cipher_text: &[u8]) -> String {
+        let digest = Blake256::new()
+            .chain(MAC_KEY_DOMAIN)
+            .chain(&*self.mac_key)
+            .chain(&SECRETS_FILE_VERSION.to_le_bytes())
+            .chain(&self.salt)
+            .chain(cipher_text)
+            .result();
+        to_hex(&digest)
+    }
+}
+
+/// Resolves secret references, holding the contents of the encrypted secrets file once it is unlocked
+#[derive(Clone, Default)]
+pub struct SecretStore {
+    secrets: HashMap<String, Zeroizing<String>>,
+    /// The path of the unlocked secrets file and the keys derived from its passphrase
+    file: Option<(PathBuf, SecretsFileKeys)>,
+}
+
+impl SecretStore {
+    /// Unlock the secrets file at `path` with the passphrase. If the file does not exist yet, an empty store is
+    /// returned that is written to `path` when it is saved.
+    pub fn open<P: AsRef<Path>>(path: P, passphrase: &str) -> Result<Self, SecretError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            let mut salt = vec![0u8; SALT_SIZE];
+            OsRng.fill_bytes(&mut salt);
+            return Ok(Self {
+                secrets: HashMap::new(),
+                file: Some((path.to_path_buf(), SecretsFileKeys::derive(passphrase, salt)?)),
+            });
+        }
+
+        let contents = Zeroizing::new(fs::read_to_string(path)?);
+        let mut fields = HashMap::new();
+        for line in contents.lines() {
+            let mut parts = line.splitn(2, ' ');
+            if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                fields.insert(key, value.trim());
+            }
+        }
+        let field = |name: &str| {
+            fields
+                .get(name)
+                .ok_or_else(|| SecretError::InvalidSecretsFile(format!("The '{}' field is missing", name)))
+        };
+        let version = field(FILE_HEADER)?
+            .parse::<u32>()
+            .map_err(|e| SecretError::InvalidSecretsFile(format!("Invalid version: {}", e)))?;
+        if version > SECRETS_FILE_VERSION {
+            return Err(SecretError::UnsupportedVersion(version));
+        }
+        let salt =
+            from_hex(field("salt")?).map_err(|e| SecretError::InvalidSecretsFile(format!("Invalid salt: {}", e)))?;
+        let cipher_text =
+            from_hex(field("data")?).map_err(|e| SecretError::InvalidSecretsFile(format!("Invalid data: {}", e)))?;
+        let keys = SecretsFileKeys::derive(passphrase, salt)?;
+        if keys.calculate_mac(&cipher_text) != *field("mac")? {
+            return Err(SecretError::IntegrityCheckFailed);
+        }
+        let plain_text: Zeroizing<Vec<u8>> = ChaCha20::open_with_integral_nonce(&cipher_text, &keys.cipher_key)
+            .map(Zeroizing::new)
+            .map_err(|_| SecretError::IntegrityCheckFailed)?;
+        let plain_text = std::str::from_utf8(&plain_text)
+            .map_err(|_| SecretError::InvalidSecretsFile("The secrets are not valid UTF-8".to_string()))?;
+
+        let mut secrets = HashMap::new();
+        for line in plain_text.lines() {
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().expect("splitn always emits at least one part");
+            let value = parts
+                .next()
+                .and_then(|value| from_hex(value).ok())
+                .and_then(|value| String::from_utf8(value).ok())
+                .ok_or_else(|| SecretError::InvalidSecretsFile(format!("Invalid value for secret '{}'", name)))?;
+            secrets.insert(name.to_string(), Zeroizing::new(value));
+        }
+        debug!(target: LOG_TARGET, "Unlocked {} secret(s) from {:?}", secrets.len(), path);
+
+        Ok(Self {
+            secrets,
+            file: Some((path.to_path_buf(), keys)),
+        })
+    }
+
+    /// Returns true if a secrets file has been unlocked
+    pub fn is_unlocked(&self) -> bool {
+        self.file.is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(|value| value.as_str())
+    }
+
+    /// The names of the secrets in the store, in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.secrets.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Add or replace a secret. The store must be saved for the change to be written to the secrets file.
+    pub fn insert<S: Into<String>>(&mut self, name: &str, value: S) -> Result<(), SecretError> {
+        if name.is_empty() || name.contains('=') || name.contains('\n') || name.contains('\r') {
+            return Err(SecretError::InvalidSecretName(name.to_string()));
+        }
+        self.secrets.insert(name.to_string(), Zeroizing::new(value.into()));
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.secrets.remove(name).is_some()
+    }
+
+    /// Encrypt the secrets and write them to the secrets file the store was opened from
+    pub fn save(&self) -> Result<(), SecretError> {
+        let (path, keys) = self
+            .file
+            .as_ref()
+            .ok_or_else(|| SecretError::InvalidSecretsFile("No secrets file has been opened".to_string()))?;
+        let mut plain_text = Zeroizing::new(String::new());
+        for name in self.names() {
+            plain_text.push_str(&format!("{}={}\n", name, to_hex(self.secrets[name].as_bytes())));
+        }
+        let cipher_text = ChaCha20::seal_with_integral_nonce(plain_text.as_bytes(), &keys.cipher_key)
+            .map_err(|e| SecretError::InvalidSecretsFile(format!("Encryption failed: {:?}", e)))?;
+        let contents = format!(
+            "{} {}\nsalt {}\nmac {}\ndata {}\n",
+            FILE_HEADER,
+            SECRETS_FILE_VERSION,
+            to_hex(&keys.salt),
+            keys.calculate_mac(&cipher_text),
+            to_hex(&cipher_text)
+        );
+        if let Some(parent) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Returns the secret for a setting value. Values that are not secret references are returned as they are.
+    pub fn resolve(&self, value: &str) -> Result<String, SecretError> {
+        match SecretRef::parse(value) {
+            SecretRef::Plain(value) => Ok(value),
+            SecretRef::Env(name) => env::var(&name).map_err(|_| SecretError::EnvVarNotSet(name)),
+            SecretRef::File(path) => Ok(fs::read_to_string(path)?
+                .trim_end_matches(&['\r', '\n'][..])
+                .to_string()),
+            SecretRef::Keyring { service, user } => read_keyring(&service, &user),
+            SecretRef::SecretsFile(name) => {
+                if !self.is_unlocked() {
+                    return Err(SecretError::SecretsFileLocked(name));
+                }
+                self.get(&name)
+                    .map(ToString::to_string)
+                    .ok_or_else(|| SecretError::SecretNotFound(name))
+            },
+        }
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn read_keyring(service: &str, user: &str) -> Result<String, SecretError> {
+    keyring::Keyring::new(service, user)
+        .get_password()
+        .map_err(|e| SecretError::KeyringError(e.to_string()))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn read_keyring(_service: &str, _user: &str) -> Result<String, SecretError> {
+    Err(SecretError::KeyringError(
+        "This build of Tari does not include keyring support".to_string(),
+    ))
+}
+
+/// Replace the secret references in the sensitive settings of `cfg` with the secrets they refer to. The error
+/// contains the setting that could not be resolved.
+pub fn resolve_config_secrets(cfg: &mut Config, store: &SecretStore) -> Result<(), (String, SecretError)> {
+    let keys = NETWORKS
+        .iter()
+        .flat_map(|network| {
+            SENSITIVE_NETWORK_KEYS
+                .iter()
+                .map(move |key| format!("base_node.{}.{}", network, key))
+        })
+        .chain(SENSITIVE_KEYS.iter().map(ToString::to_string));
+    for key in keys {
+        let value = match cfg.get_str(&key) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        if SecretRef::parse(&value).is_plain() {
+            continue;
+        }
+        let secret = store.resolve(&value).map_err(|e| (key.clone(), e))?;
+        cfg.set(&key, secret)
+            .map_err(|e| (key.clone(), SecretError::InvalidSecretsFile(e.to_string())))?;
+    }
+    Ok(())
+}
+
+/// Unlock the secrets file at `path` with the passphrase from the `TARI_SECRETS_PASSPHRASE` environment variable,
+/// asking for it on the terminal if the variable is not set
+pub fn unlock_secrets_file(path: &Path) -> Result<SecretStore, SecretError> {
+    let passphrase = match env::var(SECRETS_PASSPHRASE_ENV_VAR) {
+        Ok(passphrase) => Zeroizing::new(passphrase),
+        Err(_) => Zeroizing::new(rpassword::read_password_from_tty(Some(&format!(
+            "Enter the passphrase for the secrets file {:?}: ",
+            path
+        )))?),
+    };
+    SecretStore::open(path, &passphrase)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_test_utils::random::string;
+    use tempdir::TempDir;
+
+    #[test]
+    fn parse_secret_refs() {
+        assert_eq!(
+            SecretRef::parse("password=abc"),
+            SecretRef::Plain("password=abc".to_string())
+        );
+        assert_eq!(SecretRef::parse("env:TOR_AUTH"), SecretRef::Env("TOR_AUTH".to_string()));
+        assert_eq!(
+            SecretRef::parse("file:/run/secrets/tor"),
+            SecretRef::File(PathBuf::from("/run/secrets/tor"))
+        );
+        assert_eq!(SecretRef::parse("keyring:tor_control"), SecretRef::Keyring {
+            service: "tari".to_string(),
+            user: "tor_control".to_string()
+        });
+        assert_eq!(SecretRef::parse("keyring:my/app/tor_control"), SecretRef::Keyring {
+            service: "my/app".to_string(),
+            user: "tor_control".to_string()
+        });
+        assert_eq!(
+            SecretRef::parse("secret:socks"),
+            SecretRef::SecretsFile("socks".to_string())
+        );
+    }
+
+    #[test]
+    fn secrets_file_round_trip() {
+        let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+        let path = temp_dir.path().join("secrets.enc");
+        let mut store = SecretStore::open(&path, "correct horse").unwrap();
+        assert!(store.is_unlocked());
+        store.insert("tor", "password=hunter2").unwrap();
+        store
+            .insert("socks", "username_password=me:secret\nwith a new line")
+            .unwrap();
+        assert!(store.insert("bad=name", "value").is_err());
+        store.save().unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("hunter2"));
+
+        let store = SecretStore::open(&path, "correct horse").unwrap();
+        assert_eq!(store.names(), vec!["socks", "tor"]);
+        assert_eq!(store.resolve("secret:tor").unwrap(), "password=hunter2");
+        assert_eq!(
+            store.get("socks").unwrap(),
+            "username_password=me:secret\nwith a new line"
+        );
+        match store.resolve("secret:missing") {
+            Err(SecretError::SecretNotFound(name)) => assert_eq!(name, "missing"),
+            r => panic!("Unexpected result {:?}", r),
+        }
+
+        match SecretStore::open(&path, "battery staple") {
+            Err(SecretError::IntegrityCheckFailed) => {},
+            Err(e) => panic!("Unexpected error {}", e),
+            Ok(_) => panic!("The secrets file was unlocked with the wrong passphrase"),
+        }
+    }
+
+    #[test]
+    fn resolve_config_secrets_replaces_references() {
+        let temp_dir = TempDir::new(string(8).as_str()).unwrap();
+        let secret_file = temp_dir.path().join("tor_password");
+        fs::write(&secret_file, "password=from_file\n").unwrap();
+        env::set_var("TARI_TEST_SOCKS5_AUTH", "username_password=user:from_env");
+
+        let mut cfg = Config::new();
+        cfg.set(
+            "base_node.mainnet.tor_control_auth",
+            format!("file:{}", secret_file.to_str().unwrap()),
+        )
+        .unwrap();
+        cfg.set("base_node.mainnet.socks5_auth", "env:TARI_TEST_SOCKS5_AUTH")
+            .unwrap();
+        cfg.set("base_node.rincewind.tor_control_auth", "none").unwrap();
+        cfg.set("wallet.backup_passphrase", "secret:backup").unwrap();
+
+        // The secrets file has not been unlocked
+        let (key, err) = resolve_config_secrets(&mut cfg, &SecretStore::default()).unwrap_err();
+        assert_eq!(key, "wallet.backup_passphrase");
+        match err {
+            SecretError::SecretsFileLocked(name) => assert_eq!(name, "backup"),
+            e => panic!("Unexpected error {}", e),
+        }
+
+        let mut store = SecretStore::open(temp_dir.path().join("secrets.enc"), "passphrase").unwrap();
+        store.insert("backup", "backup passphrase").unwrap();
+        resolve_config_secrets(&mut cfg, &store).unwrap();
+        assert_eq!(
+            cfg.get_str("base_node.mainnet.tor_control_auth").unwrap(),
+            "password=from_file"
+        );
+        assert_eq!(
+            cfg.get_str("base_node.mainnet.socks5_auth").unwrap(),
+            "username_password=user:from_env"
+        );
+        assert_eq!(cfg.get_str("base_node.rincewind.tor_control_auth").unwrap(), "none");
+        assert_eq!(cfg.get_str("wallet.backup_passphrase").unwrap(), "backup passphrase");
+    }
+}
@@ -22,7 +22,7 @@
 
 //! Actor for DHT functionality.
 //!
-//! The DhtActor is responsible for sending a join request on startup
+//! The DhtActor is responsible for sending join requests, periodically re-announcing the node
 //! and furnishing [DhtRequest]s.
 //!
 //! [DhtRequest]: ./enum.DhtRequest.html
@@ -32,6 +32,7 @@ use crate::{
     discovery::DhtDiscoveryError,
     outbound::{OutboundMessageRequester, SendMessageParams},
     proto::{dht::JoinMessage, envelope::DhtMessageType, store_forward::StoredMessagesRequest},
+    routing_table::{RoutingTable, RoutingTableError, RoutingTableStatus},
     DhtConfig,
 };
 use chrono::{DateTime, Utc};
@@ -39,15 +40,22 @@ use derive_error::Error;
 use futures::{
     channel::{mpsc, mpsc::SendError, oneshot},
     future,
-    future::BoxFuture,
+    future::{BoxFuture, Fuse as FutureFuse},
     stream::{Fuse, FuturesUnordered},
     FutureExt,
     SinkExt,
     StreamExt,
 };
 use log::*;
-use std::{fmt, fmt::Display, sync::Arc};
+use rand::Rng;
+use std::{
+    fmt,
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use tari_comms::{
+    multiaddr::Multiaddr,
     peer_manager::{
         NodeId,
         NodeIdentity,
@@ -63,6 +71,7 @@ use tari_comms::{
 use tari_crypto::tari_utilities::ByteArray;
 use tari_shutdown::ShutdownSignal;
 use tari_storage::IterationResult;
+use tokio::time;
 use ttl_cache::TtlCache;
 
 const LOG_TARGET: &str = "comms::dht::actor";
@@ -80,6 +89,7 @@ pub enum DhtActorError {
     SendFailed(String),
     DiscoveryError(DhtDiscoveryError),
     BlockingJoinError(tokio::task::JoinError),
+    RoutingTableError(RoutingTableError),
 }
 
 impl From<SendError> for DhtActorError {
@@ -106,6 +116,8 @@ pub enum DhtRequest {
     MsgHashCacheInsert(Vec<u8>, oneshot::Sender<bool>),
     /// Fetch selected peers according to the broadcast strategy
     SelectPeers(BroadcastStrategy, oneshot::Sender<Vec<Peer>>),
+    /// Summarise the occupancy of the routing table buckets
+    GetRoutingTableStatus(oneshot::Sender<RoutingTableStatus>),
 }
 
 impl Display for DhtRequest {
@@ -115,6 +127,7 @@ impl Display for DhtRequest {
             DhtRequest::SendRequestStoredMessages(d) => f.write_str(&format!("SendRequestStoredMessages ({:?})", d)),
             DhtRequest::MsgHashCacheInsert(_, _) => f.write_str("MsgHashCacheInsert"),
             DhtRequest::SelectPeers(s, _) => f.write_str(&format!("SelectPeers (Strategy={})", s)),
+            DhtRequest::GetRoutingTableStatus(_) => f.write_str("GetRoutingTableStatus"),
         }
     }
 }
@@ -156,6 +169,41 @@ impl DhtRequester {
             .await
             .map_err(Into::into)
     }
+
+    /// Request messages which were stored by neighbouring peers since the given date time
+    pub async fn send_request_stored_messages_since(&mut self, since: DateTime<Utc>) -> Result<(), DhtActorError> {
+        self.sender
+            .send(DhtRequest::SendRequestStoredMessages(Some(since)))
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn get_routing_table_status(&mut self) -> Result<RoutingTableStatus, DhtActorError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender.send(DhtRequest::GetRoutingTableStatus(reply_tx)).await?;
+        reply_rx.await.map_err(|_| DhtActorError::ReplyCanceled)
+    }
+}
+
+/// The public address and features the node last announced in a join message
+struct JoinAnnouncement {
+    requested_at: Instant,
+    address: Multiaddr,
+    features: PeerFeatures,
+}
+
+impl JoinAnnouncement {
+    fn new(node_identity: &NodeIdentity) -> Self {
+        Self {
+            requested_at: Instant::now(),
+            address: node_identity.public_address(),
+            features: node_identity.features(),
+        }
+    }
+
+    fn announces_same_as(&self, other: &JoinAnnouncement) -> bool {
+        self.address == other.address && self.features == other.features
+    }
 }
 
 pub struct DhtActor<'a> {
@@ -166,7 +214,9 @@ pub struct DhtActor<'a> {
     shutdown_signal: Option<ShutdownSignal>,
     request_rx: Fuse<mpsc::Receiver<DhtRequest>>,
     msg_hash_cache: TtlCache<Vec<u8>, ()>,
+    routing_table: Arc<Mutex<RoutingTable>>,
     pending_jobs: FuturesUnordered<BoxFuture<'a, Result<(), DhtActorError>>>,
+    last_join: Option<JoinAnnouncement>,
 }
 
 impl<'a> DhtActor<'a> {
@@ -181,6 +231,11 @@ impl<'a> DhtActor<'a> {
     {
         Self {
             msg_hash_cache: TtlCache::new(config.msg_hash_cache_capacity),
+            routing_table: Arc::new(Mutex::new(RoutingTable::new(
+                node_identity.node_id().clone(),
+                config.routing_table_capacity,
+                config.routing_table_half_life,
+            ))),
             config,
             outbound_requester,
             peer_manager,
@@ -188,6 +243,7 @@ impl<'a> DhtActor<'a> {
             shutdown_signal: Some(shutdown_signal),
             request_rx: request_rx.fuse(),
             pending_jobs: FuturesUnordered::new(),
+            last_join: None,
         }
     }
 
@@ -198,6 +254,12 @@ impl<'a> DhtActor<'a> {
             .expect("DhtActor initialized without shutdown_signal")
             .fuse();
 
+        if let Err(err) = self.bootstrap_routing_table().await {
+            warn!(target: LOG_TARGET, "Failed to bootstrap from the routing table: {}", err);
+        }
+        let mut next_refresh = time::delay_for(self.config.routing_table_refresh_interval).fuse();
+        let mut next_reannounce = Self::reannounce_delay(&self.config);
+
         loop {
             futures::select! {
                 request = self.request_rx.select_next_some() => {
@@ -217,8 +279,35 @@ impl<'a> DhtActor<'a> {
                     }
                 },
 
+                _ = next_refresh => {
+                    self.pending_jobs.push(Box::pin(Self::refresh_routing_table(
+                        self.config.clone(),
+                        Arc::clone(&self.node_identity),
+                        Arc::clone(&self.peer_manager),
+                        Arc::clone(&self.routing_table),
+                    )));
+                    next_refresh = time::delay_for(self.config.routing_table_refresh_interval).fuse();
+                },
+
+                _ = next_reannounce => {
+                    debug!(target: LOG_TARGET, "Periodically re-announcing this node to the network");
+                    let job = self.join_handler();
+                    self.pending_jobs.push(job);
+                    next_reannounce = Self::reannounce_delay(&self.config);
+                },
+
                 _ = shutdown_signal => {
                     info!(target: LOG_TARGET, "DhtActor is shutting down because it received a shutdown signal.");
+                    let result = Self::refresh_routing_table(
+                        self.config.clone(),
+                        Arc::clone(&self.node_identity),
+                        Arc::clone(&self.peer_manager),
+                        Arc::clone(&self.routing_table),
+                    )
+                    .await;
+                    if let Err(err) = result {
+                        warn!(target: LOG_TARGET, "Failed to save the routing table on shutdown: {}", err);
+                    }
                     break;
                 },
                 complete => {
@@ -232,15 +321,7 @@ impl<'a> DhtActor<'a> {
     fn request_handler(&mut self, request: DhtRequest) -> BoxFuture<'a, Result<(), DhtActorError>> {
         use DhtRequest::*;
         match request {
-            SendJoin => {
-                let node_identity = Arc::clone(&self.node_identity);
-                let outbound_requester = self.outbound_requester.clone();
-                Box::pin(Self::send_join(
-                    node_identity,
-                    outbound_requester,
-                    self.config.num_neighbouring_nodes,
-                ))
-            },
+            SendJoin => self.join_handler(),
             MsgHashCacheInsert(hash, reply_tx) => {
                 // No locks needed here. Downside is this isn't really async, however this should be
                 // fine as it is very quick
@@ -275,9 +356,141 @@ impl<'a> DhtActor<'a> {
                     maybe_since,
                 ))
             },
+            GetRoutingTableStatus(reply_tx) => {
+                let peer_manager = Arc::clone(&self.peer_manager);
+                let routing_table = Arc::clone(&self.routing_table);
+                Box::pin(async move {
+                    let peers = peer_manager.all().await?;
+                    let status = acquire_lock!(routing_table).status(&peers, Utc::now());
+                    reply_tx.send(status).map_err(|_| DhtActorError::ReplyCanceled)
+                })
+            },
         }
     }
 
+    /// Sends a join message, unless this node already announced the same public address and features within the
+    /// `join_min_interval`. The first join message is delayed by a random period of up to `join_startup_delay`.
+    fn join_handler(&mut self) -> BoxFuture<'a, Result<(), DhtActorError>> {
+        let announcement = JoinAnnouncement::new(&self.node_identity);
+        let delay = match self.last_join.as_ref() {
+            Some(last_join)
+                if last_join.announces_same_as(&announcement) &&
+                    last_join.requested_at.elapsed() < self.config.join_min_interval =>
+            {
+                debug!(
+                    target: LOG_TARGET,
+                    "Suppressing join request because this node was announced {:.0?} ago",
+                    last_join.requested_at.elapsed()
+                );
+                return Box::pin(future::ready(Ok(())));
+            },
+            Some(_) => Duration::from_secs(0),
+            None => random_delay(self.config.join_startup_delay),
+        };
+        self.last_join = Some(announcement);
+
+        let node_identity = Arc::clone(&self.node_identity);
+        let outbound_requester = self.outbound_requester.clone();
+        let num_neighbouring_nodes = self.config.num_neighbouring_nodes;
+        Box::pin(async move {
+            if delay > Duration::from_secs(0) {
+                debug!(target: LOG_TARGET, "Delaying join message by {:.0?}", delay);
+                time::delay_for(delay).await;
+            }
+            Self::send_join(node_identity, outbound_requester, num_neighbouring_nodes).await
+        })
+    }
+
+    /// Returns a future that resolves when the node is next due to re-announce itself, or never if periodic
+    /// re-announcing is disabled
+    fn reannounce_delay(config: &DhtConfig) -> FutureFuse<BoxFuture<'static, ()>> {
+        match config.join_reannounce_interval {
+            Some(interval) => time::delay_for(jittered(interval, config.join_reannounce_jitter))
+                .boxed()
+                .fuse(),
+            None => future::pending().boxed().fuse(),
+        }
+    }
+
+    /// Loads the persisted routing table (if any) and makes its fresh entries available to the peer manager, so that
+    /// the node is able to rejoin its neighbourhood immediately. Peers which are unknown to the peer manager are
+    /// added and the offline flag is cleared for known peers that were recently seen.
+    async fn bootstrap_routing_table(&self) -> Result<(), DhtActorError> {
+        let path = match self.config.routing_table_path.as_ref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let table = RoutingTable::load(
+            path,
+            self.node_identity.node_id().clone(),
+            self.config.routing_table_capacity,
+            self.config.routing_table_half_life,
+        )?;
+
+        let mut num_added = 0;
+        let mut num_restored = 0;
+        for entry in table.closest(table.len()) {
+            match self.peer_manager.find_by_node_id(&entry.node_id).await {
+                Ok(mut peer) => {
+                    if peer.is_offline() && !peer.is_banned() {
+                        peer.set_offline(false);
+                        self.peer_manager.add_peer(peer).await?;
+                        num_restored += 1;
+                    }
+                },
+                Err(PeerManagerError::PeerNotFoundError) => {
+                    self.peer_manager.add_peer(entry.to_peer()).await?;
+                    num_added += 1;
+                },
+                Err(PeerManagerError::BannedPeer) => {},
+                Err(err) => return Err(err.into()),
+            }
+        }
+        info!(
+            target: LOG_TARGET,
+            "Bootstrapped from {} routing table entries ({} peer(s) added, {} peer(s) no longer marked offline)",
+            table.len(),
+            num_added,
+            num_restored
+        );
+
+        *acquire_lock!(self.routing_table) = table;
+        Ok(())
+    }
+
+    /// Records the closest recently seen propagation peers in the routing table and persists it
+    async fn refresh_routing_table(
+        config: DhtConfig,
+        node_identity: Arc<NodeIdentity>,
+        peer_manager: Arc<PeerManager>,
+        routing_table: Arc<Mutex<RoutingTable>>,
+    ) -> Result<(), DhtActorError>
+    {
+        let query = PeerQuery::new()
+            .select_where(|peer| {
+                !peer.is_banned() &&
+                    !peer.is_offline() &&
+                    peer.last_seen().is_some() &&
+                    peer.features.contains(PeerFeatures::MESSAGE_PROPAGATION)
+            })
+            .sort_by(PeerQuerySortBy::DistanceFrom(node_identity.node_id()))
+            .limit(config.routing_table_capacity);
+        let peers = peer_manager.perform_query(query).await?;
+
+        let mut table = acquire_lock!(routing_table);
+        table.update_from_peers(peers, Utc::now());
+        if let Some(path) = config.routing_table_path.as_ref() {
+            table.save(path)?;
+            trace!(
+                target: LOG_TARGET,
+                "Saved {} routing table entries to '{}'",
+                table.len(),
+                path.display()
+            );
+        }
+        Ok(())
+    }
+
     async fn send_join(
         node_identity: Arc<NodeIdentity>,
         mut outbound_requester: OutboundMessageRequester,
@@ -361,6 +574,16 @@ impl<'a> DhtActor<'a> {
                     .map(|peer| peer.map(|p| vec![p]).unwrap_or_default())
                     .map_err(Into::into)
             },
+            DirectPublicKeys(public_keys) => {
+                // Send to each known peer matching one of the given public keys
+                let mut peers = Vec::with_capacity(public_keys.len());
+                for public_key in &public_keys {
+                    if let Some(peer) = peer_manager.direct_identity_public_key(public_key).await? {
+                        peers.push(peer);
+                    }
+                }
+                Ok(peers)
+            },
             Flood => {
                 // Send to all known peers
                 peer_manager.flood_peers().await.map_err(Into::into)
@@ -535,6 +758,23 @@ impl<'a> DhtActor<'a> {
     }
 }
 
+/// A random duration of up to `max`
+fn random_delay(max: Duration) -> Duration {
+    if max == Duration::from_secs(0) {
+        return max;
+    }
+    max.mul_f64(rand::thread_rng().gen_range(0.0, 1.0))
+}
+
+/// Randomly lengthen or shorten the interval by up to `fraction` of its value. The fraction is clamped to [0, 1].
+fn jittered(interval: Duration, fraction: f64) -> Duration {
+    let fraction = if fraction.is_finite() { fraction.max(0.0).min(1.0) } else { 0.0 };
+    if fraction <= 0.0 {
+        return interval;
+    }
+    interval.mul_f64(rand::thread_rng().gen_range(1.0 - fraction, 1.0 + fraction))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -559,7 +799,7 @@ mod test {
         let outbound_requester = OutboundMessageRequester::new(out_tx);
         let shutdown = Shutdown::new();
         let actor = DhtActor::new(
-            Default::default(),
+            DhtConfig::default_local_test(),
             node_identity,
             peer_manager,
             outbound_requester,
@@ -574,6 +814,55 @@ mod test {
         assert_eq!(params.dht_message_type, DhtMessageType::Join);
     }
 
+    #[tokio_macros::test_basic]
+    async fn suppress_duplicate_join_requests() {
+        let node_identity = make_node_identity();
+        let peer_manager = make_peer_manager();
+        let (out_tx, mut out_rx) = mpsc::channel(1);
+        let (actor_tx, actor_rx) = mpsc::channel(1);
+        let mut requester = DhtRequester::new(actor_tx);
+        let outbound_requester = OutboundMessageRequester::new(out_tx);
+        let shutdown = Shutdown::new();
+        let actor = DhtActor::new(
+            DhtConfig::default_local_test(),
+            Arc::clone(&node_identity),
+            peer_manager,
+            outbound_requester,
+            actor_rx,
+            shutdown.to_signal(),
+        );
+
+        runtime::Handle::current().spawn(actor.run());
+
+        requester.send_join().await.unwrap();
+        let (params, _) = unwrap_oms_send_msg!(out_rx.next().await.unwrap());
+        assert_eq!(params.dht_message_type, DhtMessageType::Join);
+
+        // The same announcement within the minimum interval is suppressed
+        requester.send_join().await.unwrap();
+        assert!(time::timeout(Duration::from_millis(100), out_rx.next()).await.is_err());
+
+        // A changed public address is announced straight away
+        node_identity
+            .set_public_address("/ip4/127.0.0.1/tcp/9999".parse().unwrap())
+            .unwrap();
+        requester.send_join().await.unwrap();
+        let (params, _) = unwrap_oms_send_msg!(out_rx.next().await.unwrap());
+        assert_eq!(params.dht_message_type, DhtMessageType::Join);
+    }
+
+    #[test]
+    fn jittered_interval() {
+        let interval = Duration::from_secs(100);
+        assert_eq!(jittered(interval, 0.0), interval);
+        for _ in 0..100 {
+            let delay = jittered(interval, 0.25);
+            assert!(delay >= Duration::from_secs(75) && delay <= Duration::from_secs(125));
+            assert!(random_delay(interval) <= interval);
+        }
+        assert_eq!(random_delay(Duration::from_secs(0)), Duration::from_secs(0));
+    }
+
     #[tokio_macros::test_basic]
     async fn insert_message_signature() {
         let node_identity = make_node_identity();
@@ -683,4 +972,45 @@ mod test {
 
         assert_eq!(peers.len(), 1);
     }
+
+    #[tokio_macros::test_basic]
+    async fn get_routing_table_status() {
+        let node_identity = make_node_identity();
+        let peer_manager = make_peer_manager();
+        peer_manager
+            .add_peer({
+                let node_identity = make_node_identity();
+                Peer::new(
+                    node_identity.public_key().clone(),
+                    node_identity.node_id().clone(),
+                    MultiaddressesWithStats::new(vec![]),
+                    PeerFlags::OFFLINE,
+                    PeerFeatures::COMMUNICATION_NODE,
+                    &[],
+                )
+            })
+            .await
+            .unwrap();
+        let (out_tx, _) = mpsc::channel(1);
+        let (actor_tx, actor_rx) = mpsc::channel(1);
+        let mut requester = DhtRequester::new(actor_tx);
+        let outbound_requester = OutboundMessageRequester::new(out_tx);
+        let shutdown = Shutdown::new();
+        let actor = DhtActor::new(
+            Default::default(),
+            node_identity,
+            peer_manager,
+            outbound_requester,
+            actor_rx,
+            shutdown.to_signal(),
+        );
+
+        runtime::Handle::current().spawn(actor.run());
+
+        let status = requester.get_routing_table_status().await.unwrap();
+        assert_eq!(status.num_cached, 0);
+        assert_eq!(status.buckets.len(), 1);
+        assert_eq!(status.buckets[0].num_peers, 1);
+        assert_eq!(status.buckets[0].num_online, 0);
+    }
 }
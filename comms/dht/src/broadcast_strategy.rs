@@ -40,6 +40,8 @@ pub enum BroadcastStrategy {
     DirectNodeId(Box<NodeId>),
     /// Send to a particular peer matching the given Public Key
     DirectPublicKey(Box<CommsPublicKey>),
+    /// Send to each known peer matching one of the given Public Keys
+    DirectPublicKeys(Vec<CommsPublicKey>),
     /// Send to all known peers
     Flood,
     /// Send to a random set of peers of size n that are Communication Nodes
@@ -57,6 +59,7 @@ impl fmt::Display for BroadcastStrategy {
         use BroadcastStrategy::*;
         match self {
             DirectPublicKey(pk) => write!(f, "DirectPublicKey({})", pk),
+            DirectPublicKeys(pks) => write!(f, "DirectPublicKeys({} keys)", pks.len()),
             DirectNodeId(node_id) => write!(f, "DirectNodeId({})", node_id),
             Flood => write!(f, "Flood"),
             Closest(request) => write!(f, "Closest({})", request.n),
@@ -75,7 +78,7 @@ impl BroadcastStrategy {
     pub fn is_direct(&self) -> bool {
         use BroadcastStrategy::*;
         match self {
-            DirectNodeId(_) | DirectPublicKey(_) => true,
+            DirectNodeId(_) | DirectPublicKey(_) | DirectPublicKeys(_) => true,
             _ => false,
         }
     }
@@ -113,6 +116,7 @@ mod test {
     fn is_direct() {
         assert!(BroadcastStrategy::DirectPublicKey(Box::new(CommsPublicKey::default())).is_direct());
         assert!(BroadcastStrategy::DirectNodeId(Box::new(NodeId::default())).is_direct());
+        assert!(BroadcastStrategy::DirectPublicKeys(vec![CommsPublicKey::default()]).is_direct());
         assert_eq!(
             BroadcastStrategy::Neighbours(Default::default(), Default::default()).is_direct(),
             false
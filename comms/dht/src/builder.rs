@@ -23,9 +23,11 @@
 use crate::{outbound::DhtOutboundRequest, Dht, DhtConfig};
 use futures::channel::mpsc;
 use std::{sync::Arc, time::Duration};
+use tari_common::retry::RetryPolicy;
 use tari_comms::{
     connection_manager::ConnectionManagerRequester,
     peer_manager::{NodeIdentity, PeerManager},
+    utils::clock::{ClockRef, MonotonicClock},
 };
 use tari_shutdown::ShutdownSignal;
 
@@ -36,6 +38,7 @@ pub struct DhtBuilder {
     outbound_tx: mpsc::Sender<DhtOutboundRequest>,
     connection_manager: ConnectionManagerRequester,
     shutdown_signal: ShutdownSignal,
+    clock: ClockRef,
 }
 
 impl DhtBuilder {
@@ -57,6 +60,7 @@ impl DhtBuilder {
             outbound_tx,
             connection_manager,
             shutdown_signal,
+            clock: MonotonicClock::shared(),
         }
     }
 
@@ -100,6 +104,18 @@ impl DhtBuilder {
         self
     }
 
+    pub fn with_discovery_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.config.discovery_retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the clock used to timestamp and expire stored messages. Defaults to a monotonic clock which is unaffected by
+    /// adjustments to the system time.
+    pub fn with_clock(mut self, clock: ClockRef) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Build a Dht object.
     ///
     /// Will panic if an executor is not given AND not in a tokio runtime context
@@ -111,6 +127,7 @@ impl DhtBuilder {
             self.outbound_tx,
             self.connection_manager,
             self.shutdown_signal,
+            self.clock,
         )
     }
 }
@@ -0,0 +1,70 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use derive_error::Error;
+
+/// The maximum size of a decompressed envelope body. This prevents a small compressed body from being decompressed
+/// into an excessively large one.
+pub const MAX_DECOMPRESSED_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum CompressionError {
+    SnapError(snap::Error),
+    /// The decompressed body would exceed the maximum allowed size
+    DecompressedBodyTooLarge,
+}
+
+/// Compress the given bytes using snappy
+pub fn compress(body: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    snap::raw::Encoder::new().compress_vec(body).map_err(Into::into)
+}
+
+/// Decompress snappy compressed bytes. An error is returned if the decompressed body would be larger than
+/// `MAX_DECOMPRESSED_BODY_SIZE`.
+pub fn decompress(body: &[u8]) -> Result<Vec<u8>, CompressionError> {
+    if snap::raw::decompress_len(body)? > MAX_DECOMPRESSED_BODY_SIZE {
+        return Err(CompressionError::DecompressedBodyTooLarge);
+    }
+    snap::raw::Decoder::new().decompress_vec(body).map_err(Into::into)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compress_decompress() {
+        let body = b"compress me ".repeat(100);
+        let compressed = compress(&body).unwrap();
+        assert!(compressed.len() < body.len());
+        assert_eq!(decompress(&compressed).unwrap(), body);
+    }
+
+    #[test]
+    fn decompress_too_large() {
+        let compressed = compress(&vec![0u8; MAX_DECOMPRESSED_BODY_SIZE + 1]).unwrap();
+        match decompress(&compressed) {
+            Err(CompressionError::DecompressedBodyTooLarge) => {},
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+}
@@ -21,7 +21,8 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::envelope::Network;
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
+use tari_common::retry::RetryPolicy;
 
 /// The default maximum number of messages that can be stored using the Store-and-forward middleware
 pub const SAF_MSG_CACHE_STORAGE_CAPACITY: usize = 10_000;
@@ -73,6 +74,63 @@ pub struct DhtConfig {
     /// The duration to wait for a peer discovery to complete before giving up.
     /// Default: 2 minutes
     pub discovery_request_timeout: Duration,
+    /// The policy used to retry peer discoveries which time out.
+    /// Default: 2 attempts, 1 second backoff with jitter
+    pub discovery_retry_policy: RetryPolicy,
+    /// Set to true to automatically request stored messages from neighbouring store and forward nodes on startup, on
+    /// connection to a store and forward node and periodically thereafter.
+    /// Default: true
+    pub saf_auto_request: bool,
+    /// The interval between periodic requests for stored messages. This interval is increased (up to
+    /// `saf_auto_request_max_interval`) while no new messages are returned.
+    /// Default: 10 minutes
+    pub saf_auto_request_interval: Duration,
+    /// The maximum interval between periodic requests for stored messages.
+    /// Default: 2 hours
+    pub saf_auto_request_max_interval: Duration,
+    /// The difference between this node's clock and a peer's clock that is tolerated when requesting and expiring
+    /// stored messages, which are timestamped by the clock of the peer that stored them.
+    /// Default: 10 minutes
+    pub saf_max_clock_skew: Duration,
+    /// The maximum number of reply block keys, for anonymous messages sent from this node, to retain while waiting
+    /// for a reply.
+    /// Default: 1000
+    pub reply_key_cache_capacity: usize,
+    /// The time-to-live for reply block keys. Replies received after this period cannot be decrypted.
+    /// Default: 1 hour
+    pub reply_key_ttl: Duration,
+    /// Envelope bodies larger than this number of bytes are compressed when sent to peers which support
+    /// compression. Set to None to disable compression of outbound messages.
+    /// Default: 1024 bytes
+    pub compression_threshold: Option<usize>,
+    /// The file in which the routing table of recently seen neighbourhood peers is persisted across restarts. If
+    /// None, the routing table is kept in memory only.
+    /// Default: None
+    pub routing_table_path: Option<PathBuf>,
+    /// The maximum number of neighbourhood peers recorded in the routing table.
+    /// Default: 50
+    pub routing_table_capacity: usize,
+    /// The freshness of a routing table entry halves every `routing_table_half_life` since the peer was last seen.
+    /// Default: 24 hours
+    pub routing_table_half_life: Duration,
+    /// The interval between routing table refreshes from the peer manager.
+    /// Default: 5 minutes
+    pub routing_table_refresh_interval: Duration,
+    /// The first join message is delayed by a random period of up to `join_startup_delay`, so that many nodes which
+    /// restart at the same time (e.g. after a release) do not all announce themselves at once.
+    /// Default: 30 seconds
+    pub join_startup_delay: Duration,
+    /// Join requests within this period of the previous join are suppressed, unless the node's public address or
+    /// features have changed since then.
+    /// Default: 10 minutes
+    pub join_min_interval: Duration,
+    /// The interval at which the node periodically re-announces itself to the network. Set to None to only join on
+    /// request.
+    /// Default: 12 hours
+    pub join_reannounce_interval: Option<Duration>,
+    /// Each re-announce interval is randomly lengthened or shortened by up to this fraction of its value.
+    /// Default: 0.25
+    pub join_reannounce_jitter: f64,
     /// The active Network. Default: TestNet
     pub network: Network,
 }
@@ -92,6 +150,9 @@ impl DhtConfig {
     pub fn default_local_test() -> Self {
         Self {
             network: Network::LocalTest,
+            saf_auto_request: false,
+            join_startup_delay: Duration::from_secs(0),
+            join_reannounce_interval: None,
             ..Default::default()
         }
     }
@@ -112,6 +173,24 @@ impl Default for DhtConfig {
             broadcast_cooldown_max_attempts: 3,
             broadcast_cooldown_period: Duration::from_secs(60 * 30),
             discovery_request_timeout: Duration::from_secs(2 * 60),
+            discovery_retry_policy: RetryPolicy::exponential(Duration::from_secs(1))
+                .with_max_attempts(2)
+                .with_jitter(0.25),
+            saf_auto_request: true,
+            saf_auto_request_interval: Duration::from_secs(10 * 60),
+            saf_auto_request_max_interval: Duration::from_secs(2 * 60 * 60),
+            saf_max_clock_skew: Duration::from_secs(10 * 60),
+            reply_key_cache_capacity: 1000,
+            reply_key_ttl: Duration::from_secs(60 * 60),
+            compression_threshold: Some(1024),
+            routing_table_path: None,
+            routing_table_capacity: 50,
+            routing_table_half_life: Duration::from_secs(24 * 60 * 60),
+            routing_table_refresh_interval: Duration::from_secs(5 * 60),
+            join_startup_delay: Duration::from_secs(30),
+            join_min_interval: Duration::from_secs(10 * 60),
+            join_reannounce_interval: Some(Duration::from_secs(12 * 60 * 60)),
+            join_reannounce_jitter: 0.25,
             network: Network::TestNet,
         }
     }
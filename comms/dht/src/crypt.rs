@@ -20,7 +20,8 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use tari_comms::types::CommsPublicKey;
+use rand::rngs::OsRng;
+use tari_comms::types::{CommsPublicKey, CommsSecretKey};
 use tari_crypto::{
     keys::{DiffieHellmanSharedSecret, PublicKey},
     tari_utilities::{
@@ -45,6 +46,34 @@ pub fn encrypt(cipher_key: &CommsPublicKey, plain_text: &[u8]) -> Result<Vec<u8>
     ChaCha20::seal_with_integral_nonce(&plain_text.to_vec(), cipher_key.as_bytes())
 }
 
+/// Encrypts the plain text once using a random message key and wraps that key for each recipient using a shared
+/// secret derived from the given secret key and the recipient public key. The wrapped keys are returned in the same
+/// order as the recipients.
+pub fn encrypt_for_many(
+    secret_key: &CommsSecretKey,
+    recipients: &[CommsPublicKey],
+    plain_text: &[u8],
+) -> Result<(Vec<u8>, Vec<Vec<u8>>), CipherError>
+{
+    let (_, message_key) = CommsPublicKey::random_keypair(&mut OsRng);
+    let cipher_text = encrypt(&message_key, plain_text)?;
+    let wrapped_keys = recipients
+        .iter()
+        .map(|public_key| {
+            let shared_secret = generate_ecdh_secret(secret_key, public_key);
+            encrypt(&shared_secret, message_key.as_bytes())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((cipher_text, wrapped_keys))
+}
+
+/// Unwraps a message key produced by `encrypt_for_many`. A key will usually be returned even if the shared secret
+/// does not belong to the recipient of the wrapped key, so the caller must check that the decrypted body is valid.
+pub fn unwrap_key(shared_secret: &CommsPublicKey, wrapped_key: &[u8]) -> Option<CommsPublicKey> {
+    let message_key = decrypt(shared_secret, wrapped_key).ok()?;
+    CommsPublicKey::from_bytes(&message_key).ok()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -69,4 +98,20 @@ mod test {
         let secret_msg = "Last enemy position 0830h AJ 9863".as_bytes().to_vec();
         assert_eq!(plain_text, secret_msg);
     }
+
+    #[test]
+    fn encrypt_for_many_unwrap_key() {
+        let (sender_sk, sender_pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (recipient1_sk, recipient1_pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (_, recipient2_pk) = CommsPublicKey::random_keypair(&mut OsRng);
+        let plain_text = "Last enemy position 0830h AJ 9863".as_bytes().to_vec();
+
+        let (cipher_text, wrapped_keys) =
+            encrypt_for_many(&sender_sk, &[recipient1_pk, recipient2_pk], &plain_text).unwrap();
+        assert_eq!(wrapped_keys.len(), 2);
+
+        let shared_secret = generate_ecdh_secret(&recipient1_sk, &sender_pk);
+        let message_key = unwrap_key(&shared_secret, &wrapped_keys[0]).unwrap();
+        assert_eq!(decrypt(&message_key, &cipher_text).unwrap(), plain_text);
+    }
 }
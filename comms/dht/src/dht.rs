@@ -30,7 +30,9 @@ use crate::{
     outbound,
     outbound::DhtOutboundRequest,
     proto::envelope::DhtMessageType,
+    reply_keys::ReplyKeyStore,
     store_forward,
+    store_forward::{SafRetrievalService, StoredMessagesReceived},
     tower_filter,
     DhtConfig,
 };
@@ -42,6 +44,7 @@ use tari_comms::{
     message::{InboundMessage, OutboundMessage},
     peer_manager::{NodeIdentity, PeerFeatures, PeerManager},
     pipeline::PipelineError,
+    utils::clock::ClockRef,
 };
 use tari_shutdown::ShutdownSignal;
 use tokio::task;
@@ -64,6 +67,12 @@ pub struct Dht {
     discovery_sender: mpsc::Sender<DhtDiscoveryRequest>,
     /// Connection manager actor requester
     connection_manager: ConnectionManagerRequester,
+    /// Sender for notifying the SAF retrieval service of received stored messages
+    saf_response_tx: mpsc::Sender<StoredMessagesReceived>,
+    /// Secret keys for reply blocks sent with anonymous messages
+    reply_keys: Arc<ReplyKeyStore>,
+    /// Clock used to timestamp and expire stored messages
+    clock: ClockRef,
 }
 
 impl Dht {
@@ -74,10 +83,16 @@ impl Dht {
         outbound_tx: mpsc::Sender<DhtOutboundRequest>,
         connection_manager: ConnectionManagerRequester,
         shutdown_signal: ShutdownSignal,
+        clock: ClockRef,
     ) -> Self
     {
         let (dht_sender, dht_receiver) = mpsc::channel(20);
         let (discovery_sender, discovery_receiver) = mpsc::channel(20);
+        let (saf_response_tx, saf_response_rx) = mpsc::channel(20);
+        let reply_keys = Arc::new(ReplyKeyStore::new(
+            config.reply_key_cache_capacity,
+            config.reply_key_ttl,
+        ));
 
         let dht = Self {
             node_identity,
@@ -87,10 +102,14 @@ impl Dht {
             dht_sender,
             connection_manager,
             discovery_sender,
+            saf_response_tx,
+            reply_keys,
+            clock,
         };
 
         task::spawn(dht.actor(dht_receiver, shutdown_signal.clone()).run());
-        task::spawn(dht.discovery_service(discovery_receiver, shutdown_signal).run());
+        task::spawn(dht.discovery_service(discovery_receiver, shutdown_signal.clone()).run());
+        task::spawn(dht.saf_retrieval_service(saf_response_rx, shutdown_signal).run());
 
         dht
     }
@@ -130,6 +149,23 @@ impl Dht {
         )
     }
 
+    /// Create the store and forward retrieval service
+    fn saf_retrieval_service(
+        &self,
+        saf_response_rx: mpsc::Receiver<StoredMessagesReceived>,
+        shutdown_signal: ShutdownSignal,
+    ) -> SafRetrievalService
+    {
+        SafRetrievalService::new(
+            self.config.clone(),
+            self.dht_requester(),
+            self.connection_manager.clone(),
+            Arc::clone(&self.peer_manager),
+            saf_response_rx,
+            shutdown_signal,
+        )
+    }
+
     /// Return a new OutboundMessageRequester connected to the receiver
     pub fn outbound_requester(&self) -> OutboundMessageRequester {
         OutboundMessageRequester::new(self.outbound_tx.clone())
@@ -142,7 +178,11 @@ impl Dht {
 
     /// Returns a requester for the DhtDiscoveryService associated with this instance
     pub fn discovery_service_requester(&self) -> DhtDiscoveryRequester {
-        DhtDiscoveryRequester::new(self.discovery_sender.clone(), self.config.discovery_request_timeout)
+        DhtDiscoveryRequester::new(
+            self.discovery_sender.clone(),
+            self.config.discovery_request_timeout,
+            self.config.discovery_retry_policy,
+        )
     }
 
     /// Returns an the full DHT stack as a `tower::layer::Layer`. This can be composed with
@@ -163,8 +203,9 @@ impl Dht {
         S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError> + Clone + Send + Sync + 'static,
         S::Future: Send,
     {
-        let saf_storage = Arc::new(store_forward::SafStorage::new(
+        let saf_storage = Arc::new(store_forward::SafStorage::with_clock(
             self.config.saf_msg_cache_storage_capacity,
+            Arc::clone(&self.clock),
         ));
         let builder = ServiceBuilder::new()
             .layer(inbound::DeserializeLayer::new())
@@ -183,7 +224,10 @@ impl Dht {
             .layer(MessageLoggingLayer::new("Inbound message: "));
 
         builder
-            .layer(inbound::DecryptionLayer::new(Arc::clone(&self.node_identity)))
+            .layer(inbound::DecryptionLayer::new(
+                Arc::clone(&self.node_identity),
+                Arc::clone(&self.reply_keys),
+            ))
             .layer(store_forward::ForwardLayer::new(
                 Arc::clone(&self.peer_manager),
                 self.outbound_requester(),
@@ -201,6 +245,7 @@ impl Dht {
                 Arc::clone(&self.node_identity),
                 Arc::clone(&self.peer_manager),
                 self.outbound_requester(),
+                self.saf_response_tx.clone(),
             ))
             .layer(inbound::DhtHandlerLayer::new(
                 self.config.clone(),
@@ -238,8 +283,15 @@ impl Dht {
                 self.config.network,
             ))
             .layer(MessageLoggingLayer::new("Outbound message: "))
-            .layer(outbound::EncryptionLayer::new(Arc::clone(&self.node_identity)))
-            .layer(outbound::SerializeLayer::new(Arc::clone(&self.node_identity)))
+            .layer(outbound::EncryptionLayer::new(
+                Arc::clone(&self.node_identity),
+                Arc::clone(&self.reply_keys),
+            ))
+            .layer(outbound::PriorityLayer::new())
+            .layer(outbound::SerializeLayer::new(
+                Arc::clone(&self.node_identity),
+                self.config.compression_threshold,
+            ))
             .into_inner()
     }
 
@@ -29,6 +29,7 @@ use std::{
     fmt::{Display, Error, Formatter},
     time::Duration,
 };
+use tari_common::retry::RetryPolicy;
 use tari_comms::{
     peer_manager::{NodeId, Peer},
     types::CommsPublicKey,
@@ -91,16 +92,24 @@ impl Display for DhtDiscoveryRequest {
 pub struct DhtDiscoveryRequester {
     sender: mpsc::Sender<DhtDiscoveryRequest>,
     discovery_timeout: Duration,
+    retry_policy: RetryPolicy,
 }
 
 impl DhtDiscoveryRequester {
-    pub fn new(sender: mpsc::Sender<DhtDiscoveryRequest>, discovery_timeout: Duration) -> Self {
+    pub fn new(
+        sender: mpsc::Sender<DhtDiscoveryRequest>,
+        discovery_timeout: Duration,
+        retry_policy: RetryPolicy,
+    ) -> Self
+    {
         Self {
             sender,
             discovery_timeout,
+            retry_policy,
         }
     }
 
+    /// Discover a peer, retrying discoveries which time out according to the retry policy of this requester.
     pub async fn discover_peer(
         &mut self,
         dest_public_key: Box<CommsPublicKey>,
@@ -108,12 +117,25 @@ impl DhtDiscoveryRequester {
         destination: NodeDestination,
     ) -> Result<Peer, DhtDiscoveryError>
     {
+        let requester = self.clone();
+        self.retry_policy
+            .retry_if(
+                |_| {
+                    let mut requester = requester.clone();
+                    let request = DiscoverPeerRequest {
+                        dest_public_key: dest_public_key.clone(),
+                        dest_node_id: dest_node_id.clone(),
+                        destination: destination.clone(),
+                    };
+                    async move { requester.send_discover_peer_request(request).await }
+                },
+                DhtDiscoveryError::is_timeout,
+            )
+            .await
+    }
+
+    async fn send_discover_peer_request(&mut self, request: DiscoverPeerRequest) -> Result<Peer, DhtDiscoveryError> {
         let (reply_tx, reply_rx) = oneshot::channel();
-        let request = DiscoverPeerRequest {
-            dest_public_key,
-            dest_node_id,
-            destination,
-        };
         self.sender
             .send(DhtDiscoveryRequest::DiscoverPeer(Box::new((request, reply_tx))))
             .await?;
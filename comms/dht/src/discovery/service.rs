@@ -439,6 +439,7 @@ mod test {
         test_utils::{make_node_identity, make_peer_manager},
     };
     use std::time::Duration;
+    use tari_common::retry::RetryPolicy;
     use tari_comms::test_utils::mocks::create_connection_manager_mock;
     use tari_shutdown::Shutdown;
     use tari_test_utils::runtime;
@@ -455,7 +456,7 @@ mod test {
             let (connection_manager, _) = create_connection_manager_mock(1);
             let (sender, receiver) = mpsc::channel(10);
             // Requester which timeout instantly
-            let mut requester = DhtDiscoveryRequester::new(sender, Duration::from_millis(1));
+            let mut requester = DhtDiscoveryRequester::new(sender, Duration::from_millis(1), RetryPolicy::no_retry());
             let mut shutdown = Shutdown::new();
 
             let service = DhtDiscoveryService::new(
@@ -20,7 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{consts::DHT_ENVELOPE_HEADER_VERSION, proto::envelope::DhtOrigin};
+use crate::{
+    consts::DHT_ENVELOPE_HEADER_VERSION,
+    proto::envelope::{DhtOrigin, DhtReplyBlock},
+};
 use bitflags::bitflags;
 use derive_error::Error;
 use serde::{Deserialize, Serialize};
@@ -49,6 +52,10 @@ pub enum DhtMessageError {
     InvalidMessageFlags,
     /// Header was omitted from the message
     HeaderOmitted,
+    /// Invalid ephemeral public key
+    InvalidEphemeralPublicKey,
+    /// Invalid reply block
+    InvalidReplyBlock,
 }
 
 impl fmt::Display for DhtMessageType {
@@ -66,6 +73,11 @@ bitflags! {
         const NONE = 0x00;
         /// Set if the message is encrypted
         const ENCRYPTED = 0x01;
+        /// Set if the message was sent anonymously. The origin is omitted and the body is encrypted using the
+        /// ephemeral public key in the header.
+        const ANONYMOUS = 0x02;
+        /// Set if the envelope body is compressed
+        const COMPRESSED = 0x04;
     }
 }
 
@@ -113,6 +125,46 @@ impl From<DhtMessageOrigin> for DhtOrigin {
     }
 }
 
+/// A single-use reply block included in the encrypted body of an anonymous message. This allows the recipient to
+/// reply to the sender without learning the sender's public key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ReplyBlock {
+    /// Single-use public key which replies should be encrypted for and addressed to
+    pub public_key: CommsPublicKey,
+    /// Approximate network region of the sender. Replies are sent towards this region.
+    pub node_id: NodeId,
+}
+
+impl fmt::Debug for ReplyBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReplyBlock")
+            .field("public_key", &self.public_key.to_hex())
+            .field("node_id", &self.node_id.to_hex())
+            .finish()
+    }
+}
+
+impl TryFrom<DhtReplyBlock> for ReplyBlock {
+    type Error = DhtMessageError;
+
+    fn try_from(value: DhtReplyBlock) -> Result<Self, Self::Error> {
+        Ok(Self {
+            public_key: CommsPublicKey::from_bytes(&value.public_key)
+                .map_err(|_| DhtMessageError::InvalidReplyBlock)?,
+            node_id: NodeId::from_bytes(&value.node_id).map_err(|_| DhtMessageError::InvalidReplyBlock)?,
+        })
+    }
+}
+
+impl From<ReplyBlock> for DhtReplyBlock {
+    fn from(value: ReplyBlock) -> Self {
+        Self {
+            public_key: value.public_key.to_vec(),
+            node_id: value.node_id.to_vec(),
+        }
+    }
+}
+
 /// This struct mirrors the protobuf version of DhtHeader but is more ergonomic to work with.
 /// It is preferable to not to expose the generated prost structs publicly.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -125,6 +177,10 @@ pub struct DhtMessageHeader {
     pub message_type: DhtMessageType,
     pub network: Network,
     pub flags: DhtMessageFlags,
+    /// Ephemeral public key used to encrypt the body of an anonymous message
+    pub ephemeral_public_key: Option<CommsPublicKey>,
+    /// Message keys wrapped for each recipient of a body that has been encrypted once for multiple recipients
+    pub wrapped_keys: Vec<Vec<u8>>,
 }
 
 impl DhtMessageHeader {
@@ -143,6 +199,8 @@ impl DhtMessageHeader {
             message_type,
             network,
             flags,
+            ephemeral_public_key: None,
+            wrapped_keys: Vec::new(),
         }
     }
 }
@@ -173,6 +231,15 @@ impl TryFrom<DhtHeader> for DhtMessageHeader {
             None => None,
         };
 
+        let ephemeral_public_key = if header.ephemeral_public_key.is_empty() {
+            None
+        } else {
+            Some(
+                CommsPublicKey::from_bytes(&header.ephemeral_public_key)
+                    .map_err(|_| DhtMessageError::InvalidEphemeralPublicKey)?,
+            )
+        };
+
         Ok(Self {
             version: header.version,
             destination,
@@ -181,6 +248,8 @@ impl TryFrom<DhtHeader> for DhtMessageHeader {
                 .ok_or_else(|| DhtMessageError::InvalidMessageType)?,
             network: Network::from_i32(header.network).ok_or_else(|| DhtMessageError::InvalidNetwork)?,
             flags: DhtMessageFlags::from_bits(header.flags).ok_or_else(|| DhtMessageError::InvalidMessageFlags)?,
+            ephemeral_public_key,
+            wrapped_keys: header.wrapped_keys,
         })
     }
 }
@@ -205,6 +274,8 @@ impl From<DhtMessageHeader> for DhtHeader {
             message_type: header.message_type as i32,
             network: header.network as i32,
             flags: header.flags.bits(),
+            ephemeral_public_key: header.ephemeral_public_key.map(|pk| pk.to_vec()).unwrap_or_default(),
+            wrapped_keys: header.wrapped_keys,
         }
     }
 }
@@ -22,14 +22,21 @@
 
 use crate::{
     crypt,
-    envelope::DhtMessageFlags,
-    inbound::message::{DecryptedDhtMessage, DhtInboundMessage},
+    envelope::{DhtMessageFlags, ReplyBlock},
+    inbound::{
+        error::DhtInboundError,
+        message::{DecryptedDhtMessage, DhtInboundMessage},
+    },
+    metrics,
+    proto::envelope::DhtAnonymousBody,
+    reply_keys::ReplyKeyStore,
 };
 use futures::{task::Context, Future};
 use log::*;
-use prost::Message;
-use std::{sync::Arc, task::Poll};
-use tari_comms::{message::EnvelopeBody, peer_manager::NodeIdentity, pipeline::PipelineError};
+use prost::{DecodeError, Message};
+use std::{convert::TryInto, sync::Arc, task::Poll};
+use tari_comms::{message::EnvelopeBody, peer_manager::NodeIdentity, pipeline::PipelineError, types::CommsPublicKey};
+use tari_crypto::tari_utilities::hex::Hex;
 use tower::{layer::Layer, Service, ServiceExt};
 
 const LOG_TARGET: &str = "comms::middleware::decryption";
@@ -37,11 +44,15 @@ const LOG_TARGET: &str = "comms::middleware::decryption";
 /// This layer is responsible for attempting to decrypt inbound messages.
 pub struct DecryptionLayer {
     node_identity: Arc<NodeIdentity>,
+    reply_keys: Arc<ReplyKeyStore>,
 }
 
 impl DecryptionLayer {
-    pub fn new(node_identity: Arc<NodeIdentity>) -> Self {
-        Self { node_identity }
+    pub fn new(node_identity: Arc<NodeIdentity>, reply_keys: Arc<ReplyKeyStore>) -> Self {
+        Self {
+            node_identity,
+            reply_keys,
+        }
     }
 }
 
@@ -49,7 +60,7 @@ impl<S> Layer<S> for DecryptionLayer {
     type Service = DecryptionService<S>;
 
     fn layer(&self, service: S) -> Self::Service {
-        DecryptionService::new(service, Arc::clone(&self.node_identity))
+        DecryptionService::new(service, Arc::clone(&self.node_identity), Arc::clone(&self.reply_keys))
     }
 }
 
@@ -57,14 +68,16 @@ impl<S> Layer<S> for DecryptionLayer {
 #[derive(Clone)]
 pub struct DecryptionService<S> {
     node_identity: Arc<NodeIdentity>,
+    reply_keys: Arc<ReplyKeyStore>,
     inner: S,
 }
 
 impl<S> DecryptionService<S> {
-    pub fn new(service: S, node_identity: Arc<NodeIdentity>) -> Self {
+    pub fn new(service: S, node_identity: Arc<NodeIdentity>, reply_keys: Arc<ReplyKeyStore>) -> Self {
         Self {
             inner: service,
             node_identity,
+            reply_keys,
         }
     }
 }
@@ -82,7 +95,17 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError> + Cl
     }
 
     fn call(&mut self, msg: DhtInboundMessage) -> Self::Future {
-        Self::handle_message(self.inner.clone(), Arc::clone(&self.node_identity), msg)
+        let tag = msg.tag;
+        metrics::instrument(
+            "decrypt",
+            tag,
+            Self::handle_message(
+                self.inner.clone(),
+                Arc::clone(&self.node_identity),
+                Arc::clone(&self.reply_keys),
+                msg,
+            ),
+        )
     }
 }
 
@@ -92,6 +115,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
     async fn handle_message(
         next_service: S,
         node_identity: Arc<NodeIdentity>,
+        reply_keys: Arc<ReplyKeyStore>,
         message: DhtInboundMessage,
     ) -> Result<(), PipelineError>
     {
@@ -100,6 +124,10 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
             return Self::success_not_encrypted(next_service, message).await;
         }
 
+        if dht_header.flags.contains(DhtMessageFlags::ANONYMOUS) {
+            return Self::handle_anonymous_message(next_service, &node_identity, message).await;
+        }
+
         let origin = dht_header
             .origin
             .as_ref()
@@ -108,58 +136,132 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
 
         debug!(target: LOG_TARGET, "Attempting to decrypt message");
         let shared_secret = crypt::generate_ecdh_secret(node_identity.secret_key(), &origin.public_key);
-        match crypt::decrypt(&shared_secret, &message.body) {
-            Ok(decrypted) => Self::decryption_succeeded(next_service, &node_identity, message, &decrypted).await,
+        let result = if dht_header.wrapped_keys.is_empty() {
+            Self::attempt_decrypt(&shared_secret, &message.body)
+        } else {
+            Self::attempt_decrypt_multi_recipient(&shared_secret, &dht_header.wrapped_keys, &message.body)
+        };
+        match result {
+            Ok(body) => {
+                debug!(target: LOG_TARGET, "Message successfully decrypted");
+                let msg = DecryptedDhtMessage::succeeded(body, message);
+                next_service.oneshot(msg).await
+            },
             Err(err) => {
+                if let Some(body) = Self::attempt_decrypt_reply(&reply_keys, &message) {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Message successfully decrypted as a reply to an anonymous message"
+                    );
+                    let msg = DecryptedDhtMessage::succeeded(body, message);
+                    return next_service.oneshot(msg).await;
+                }
                 debug!(target: LOG_TARGET, "Unable to decrypt message: {}", err);
                 Self::decryption_failed(next_service, &node_identity, message).await
             },
         }
     }
 
-    async fn decryption_succeeded(
+    async fn handle_anonymous_message(
         next_service: S,
         node_identity: &NodeIdentity,
         message: DhtInboundMessage,
-        decrypted: &[u8],
     ) -> Result<(), PipelineError>
     {
-        // Deserialization into an EnvelopeBody is done here to determine if the
-        // decryption produced valid bytes or not.
-        let result = EnvelopeBody::decode(decrypted).and_then(|body| {
-            // Check if we received a body length of zero
-            //
-            // In addition to a peer sending a zero-length EnvelopeBody, decoding can erroneously succeed
-            // if the decrypted bytes happen to be valid protobuf encoding. This is very possible and
-            // the decrypt_inbound_fail test below _will_ sporadically fail without the following check.
-            // This is because proto3 will set fields to their default value if they don't exist in a valid encoding.
-            //
-            // For the parts of EnvelopeBody to be erroneously populated with bytes, all of these
-            // conditions would have to be true:
-            // 1. field type == 2 (length-delimited)
-            // 2. field number == 1
-            // 3. the subsequent byte(s) would have to be varint-encoded length which does not overflow
-            // 4. the rest of the bytes would have to be valid protobuf encoding
-            //
-            // The chance of this happening is extremely negligible.
-            if body.is_empty() {
-                return Err(prost::DecodeError::new("EnvelopeBody has no parts"));
-            }
-            Ok(body)
-        });
-        match result {
-            Ok(deserialized) => {
-                debug!(target: LOG_TARGET, "Message successfully decrypted");
-                let msg = DecryptedDhtMessage::succeeded(deserialized, message);
+        let ephemeral_public_key = message
+            .dht_header
+            .ephemeral_public_key
+            .as_ref()
+            // TODO: #banheuristics - this should not have been sent/propagated
+            .ok_or_else(|| "Ephemeral public key is required for anonymous messages")?;
+
+        debug!(target: LOG_TARGET, "Attempting to decrypt anonymous message");
+        let shared_secret = crypt::generate_ecdh_secret(node_identity.secret_key(), ephemeral_public_key);
+        match Self::attempt_decrypt_anonymous(&shared_secret, &message.body) {
+            Ok((body, reply_block)) => {
+                debug!(target: LOG_TARGET, "Anonymous message successfully decrypted");
+                let msg = DecryptedDhtMessage::succeeded(body, message).with_reply_block(reply_block);
                 next_service.oneshot(msg).await
             },
             Err(err) => {
-                debug!(target: LOG_TARGET, "Unable to deserialize message: {}", err);
-                Self::decryption_failed(next_service, &node_identity, message).await
+                debug!(target: LOG_TARGET, "Unable to decrypt anonymous message: {}", err);
+                Self::decryption_failed(next_service, node_identity, message).await
             },
         }
     }
 
+    /// Attempt to decrypt a message which is addressed to the public key of a reply block that was sent from this
+    /// node. The reply key is removed once the reply has been decrypted, as reply blocks may only be used once.
+    fn attempt_decrypt_reply(reply_keys: &ReplyKeyStore, message: &DhtInboundMessage) -> Option<EnvelopeBody> {
+        let reply_public_key = message.dht_header.destination.public_key()?;
+        let origin = message.dht_header.origin.as_ref()?;
+        let reply_secret_key = reply_keys.get(reply_public_key)?;
+        let shared_secret = crypt::generate_ecdh_secret(&reply_secret_key, &origin.public_key);
+        let body = Self::attempt_decrypt(&shared_secret, &message.body).ok()?;
+        reply_keys.remove(reply_public_key);
+        Some(body)
+    }
+
+    fn attempt_decrypt_anonymous(
+        shared_secret: &CommsPublicKey,
+        body: &[u8],
+    ) -> Result<(EnvelopeBody, Option<ReplyBlock>), DhtInboundError>
+    {
+        let decrypted = crypt::decrypt(shared_secret, body)?;
+        let anonymous_body = DhtAnonymousBody::decode(decrypted.as_slice())?;
+        let body = Self::decode_envelope_body(&anonymous_body.body)?;
+        // An invalid reply block does not invalidate the message, however it will not be possible to reply to it
+        let reply_block = anonymous_body
+            .reply_block
+            .and_then(|reply_block| reply_block.try_into().ok());
+        Ok((body, reply_block))
+    }
+
+    /// Attempt to decrypt a body which was encrypted once for multiple recipients. Each wrapped message key is tried in
+    /// turn until one of them decrypts a valid body.
+    fn attempt_decrypt_multi_recipient(
+        shared_secret: &CommsPublicKey,
+        wrapped_keys: &[Vec<u8>],
+        body: &[u8],
+    ) -> Result<EnvelopeBody, DhtInboundError>
+    {
+        wrapped_keys
+            .iter()
+            .filter_map(|wrapped_key| crypt::unwrap_key(shared_secret, wrapped_key))
+            .find_map(|message_key| Self::attempt_decrypt(&message_key, body).ok())
+            .ok_or_else(|| DhtInboundError::NoMatchingWrappedKey)
+    }
+
+    fn attempt_decrypt(shared_secret: &CommsPublicKey, body: &[u8]) -> Result<EnvelopeBody, DhtInboundError> {
+        let decrypted = crypt::decrypt(shared_secret, body)?;
+        Self::decode_envelope_body(&decrypted)
+    }
+
+    fn decode_envelope_body(bytes: &[u8]) -> Result<EnvelopeBody, DhtInboundError> {
+        // Deserialization into an EnvelopeBody is done here to determine if the
+        // decryption produced valid bytes or not.
+        let body = EnvelopeBody::decode(bytes)?;
+        // Check if we received a body length of zero
+        //
+        // In addition to a peer sending a zero-length EnvelopeBody, decoding can erroneously succeed
+        // if the decrypted bytes happen to be valid protobuf encoding. This is very possible and
+        // the decrypt_inbound_fail test below _will_ sporadically fail without the following check.
+        // This is because proto3 will set fields to their default value if they don't exist in a valid encoding.
+        //
+        // For the parts of EnvelopeBody to be erroneously populated with bytes, all of these
+        // conditions would have to be true:
+        // 1. field type == 2 (length-delimited)
+        // 2. field number == 1
+        // 3. the subsequent byte(s) would have to be varint-encoded length which does not overflow
+        // 4. the rest of the bytes would have to be valid protobuf encoding
+        //
+        // The chance of this happening is extremely negligible.
+        if body.is_empty() {
+            return Err(DecodeError::new("EnvelopeBody has no parts").into());
+        }
+        Ok(body)
+    }
+
     async fn success_not_encrypted(next_service: S, message: DhtInboundMessage) -> Result<(), PipelineError> {
         match EnvelopeBody::decode(message.body.as_slice()) {
             Ok(deserialized) => {
@@ -195,7 +297,12 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
             warn!(
                 target: LOG_TARGET,
                 "Received message from peer '{}' that is destined for that peer. Discarding message",
-                message.dht_header.origin.as_ref().expect("already checked").public_key
+                message
+                    .dht_header
+                    .origin
+                    .as_ref()
+                    .map(|o| o.public_key.to_hex())
+                    .unwrap_or_else(|| "<anonymous>".to_string())
             );
             return Err(
                 "Message rejected because this node could not decrypt a message that was addressed to it".into(),
@@ -211,18 +318,20 @@ mod test {
     use super::*;
     use crate::{
         envelope::DhtMessageFlags,
-        test_utils::{make_dht_inbound_message, make_node_identity, service_fn},
+        test_utils::{make_dht_inbound_message, make_node_identity, make_reply_key_store, service_fn},
     };
     use futures::{executor::block_on, future};
+    use rand::rngs::OsRng;
     use std::sync::Mutex;
     use tari_comms::{message::MessageExt, wrap_in_envelope_body};
+    use tari_crypto::keys::PublicKey;
     use tari_test_utils::counter_context;
 
     #[test]
     fn poll_ready() {
         let inner = service_fn(|_: DecryptedDhtMessage| future::ready(Result::<(), PipelineError>::Ok(())));
         let node_identity = make_node_identity();
-        let mut service = DecryptionService::new(inner, node_identity);
+        let mut service = DecryptionService::new(inner, node_identity, make_reply_key_store());
 
         counter_context!(cx, counter);
 
@@ -239,7 +348,7 @@ mod test {
             future::ready(Result::<(), PipelineError>::Ok(()))
         });
         let node_identity = make_node_identity();
-        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity));
+        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity), make_reply_key_store());
 
         let plain_text_msg = wrap_in_envelope_body!(Vec::new()).unwrap();
         let secret_key = crypt::generate_ecdh_secret(node_identity.secret_key(), node_identity.public_key());
@@ -252,6 +361,34 @@ mod test {
         assert_eq!(decrypted.decryption_result.unwrap(), plain_text_msg);
     }
 
+    #[test]
+    fn decrypt_inbound_multi_recipient() {
+        let result = Mutex::new(None);
+        let inner = service_fn(|msg: DecryptedDhtMessage| {
+            *result.lock().unwrap() = Some(msg);
+            future::ready(Result::<(), PipelineError>::Ok(()))
+        });
+        let node_identity = make_node_identity();
+        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity), make_reply_key_store());
+
+        let sender = make_node_identity();
+        let other_recipient = make_node_identity();
+        let plain_text_msg = wrap_in_envelope_body!(Vec::new()).unwrap();
+        let (encrypted, wrapped_keys) = crypt::encrypt_for_many(
+            sender.secret_key(),
+            &[other_recipient.public_key().clone(), node_identity.public_key().clone()],
+            &plain_text_msg.to_encoded_bytes().unwrap(),
+        )
+        .unwrap();
+        let mut inbound_msg = make_dht_inbound_message(&sender, encrypted, DhtMessageFlags::ENCRYPTED);
+        inbound_msg.dht_header.wrapped_keys = wrapped_keys;
+
+        block_on(service.call(inbound_msg)).unwrap();
+        let decrypted = result.lock().unwrap().take().unwrap();
+        assert_eq!(decrypted.decryption_succeeded(), true);
+        assert_eq!(decrypted.decryption_result.unwrap(), plain_text_msg);
+    }
+
     #[test]
     fn decrypt_inbound_fail() {
         let result = Mutex::new(None);
@@ -260,7 +397,7 @@ mod test {
             future::ready(Result::<(), PipelineError>::Ok(()))
         });
         let node_identity = make_node_identity();
-        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity));
+        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity), make_reply_key_store());
 
         let nonsense = "Cannot Decrypt this".as_bytes().to_vec();
         let inbound_msg = make_dht_inbound_message(&node_identity, nonsense.clone(), DhtMessageFlags::ENCRYPTED);
@@ -280,7 +417,7 @@ mod test {
             future::ready(Result::<(), PipelineError>::Ok(()))
         });
         let node_identity = make_node_identity();
-        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity));
+        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity), make_reply_key_store());
 
         let nonsense = "Cannot Decrypt this".as_bytes().to_vec();
         let mut inbound_msg = make_dht_inbound_message(&node_identity, nonsense.clone(), DhtMessageFlags::ENCRYPTED);
@@ -290,4 +427,66 @@ mod test {
         assert!(err.to_string().starts_with("Message rejected"),);
         assert!(result.lock().unwrap().is_none());
     }
+
+    #[test]
+    fn decrypt_inbound_anonymous() {
+        let result = Mutex::new(None);
+        let inner = service_fn(|msg: DecryptedDhtMessage| {
+            *result.lock().unwrap() = Some(msg);
+            future::ready(Result::<(), PipelineError>::Ok(()))
+        });
+        let node_identity = make_node_identity();
+        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity), make_reply_key_store());
+
+        let sender = make_node_identity();
+        let reply_block = make_reply_key_store().create_reply_block(sender.node_id());
+        let plain_text_msg = wrap_in_envelope_body!(Vec::new()).unwrap();
+        let anonymous_body = DhtAnonymousBody {
+            body: plain_text_msg.to_encoded_bytes().unwrap(),
+            reply_block: Some(reply_block.clone().into()),
+        };
+        let (ephemeral_secret_key, ephemeral_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let shared_secret = crypt::generate_ecdh_secret(&ephemeral_secret_key, node_identity.public_key());
+        let encrypted = crypt::encrypt(&shared_secret, &anonymous_body.to_encoded_bytes().unwrap()).unwrap();
+        let mut inbound_msg = make_dht_inbound_message(
+            &sender,
+            encrypted,
+            DhtMessageFlags::ENCRYPTED | DhtMessageFlags::ANONYMOUS,
+        );
+        inbound_msg.dht_header.origin = None;
+        inbound_msg.dht_header.ephemeral_public_key = Some(ephemeral_public_key);
+
+        block_on(service.call(inbound_msg)).unwrap();
+        let decrypted = result.lock().unwrap().take().unwrap();
+        assert_eq!(decrypted.decryption_succeeded(), true);
+        assert_eq!(decrypted.reply_block.unwrap(), reply_block);
+        assert_eq!(decrypted.decryption_result.unwrap(), plain_text_msg);
+    }
+
+    #[test]
+    fn decrypt_inbound_reply() {
+        let result = Mutex::new(None);
+        let inner = service_fn(|msg: DecryptedDhtMessage| {
+            *result.lock().unwrap() = Some(msg);
+            future::ready(Result::<(), PipelineError>::Ok(()))
+        });
+        let node_identity = make_node_identity();
+        let reply_keys = make_reply_key_store();
+        let mut service = DecryptionService::new(inner, Arc::clone(&node_identity), Arc::clone(&reply_keys));
+
+        let reply_block = reply_keys.create_reply_block(node_identity.node_id());
+        let replier = make_node_identity();
+        let plain_text_msg = wrap_in_envelope_body!(Vec::new()).unwrap();
+        let shared_secret = crypt::generate_ecdh_secret(replier.secret_key(), &reply_block.public_key);
+        let encrypted = crypt::encrypt(&shared_secret, &plain_text_msg.to_encoded_bytes().unwrap()).unwrap();
+        let mut inbound_msg = make_dht_inbound_message(&replier, encrypted, DhtMessageFlags::ENCRYPTED);
+        inbound_msg.dht_header.destination = reply_block.public_key.clone().into();
+
+        block_on(service.call(inbound_msg)).unwrap();
+        let decrypted = result.lock().unwrap().take().unwrap();
+        assert_eq!(decrypted.decryption_succeeded(), true);
+        assert_eq!(decrypted.decryption_result.unwrap(), plain_text_msg);
+        // Reply blocks are single-use
+        assert!(reply_keys.get(&reply_block.public_key).is_none());
+    }
 }
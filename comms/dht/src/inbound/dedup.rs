@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{actor::DhtRequester, inbound::DhtInboundMessage};
+use crate::{actor::DhtRequester, inbound::DhtInboundMessage, metrics};
 use digest::Input;
 use futures::{task::Context, Future};
 use log::*;
@@ -63,7 +63,12 @@ where S: Service<DhtInboundMessage, Response = (), Error = PipelineError> + Clon
     }
 
     fn call(&mut self, msg: DhtInboundMessage) -> Self::Future {
-        Self::process_message(self.next_service.clone(), self.dht_requester.clone(), msg)
+        let tag = msg.tag;
+        metrics::instrument(
+            "dedup",
+            tag,
+            Self::process_message(self.next_service.clone(), self.dht_requester.clone(), msg),
+        )
     }
 }
 
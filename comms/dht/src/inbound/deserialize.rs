@@ -20,7 +20,12 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{inbound::DhtInboundMessage, proto::envelope::DhtEnvelope};
+use crate::{
+    compression::{self, CompressionError},
+    envelope::DhtMessageFlags,
+    inbound::DhtInboundMessage,
+    proto::envelope::DhtEnvelope,
+};
 use futures::{task::Context, Future};
 use log::*;
 use prost::Message;
@@ -32,8 +37,8 @@ const LOG_TARGET: &str = "comms::dht::deserialize";
 
 /// # DHT Deserialization middleware
 ///
-/// Takes in an `InboundMessage` and deserializes the body into a [DhtEnvelope].
-/// The `next_service` is called with a constructed [DhtInboundMessage] which contains
+/// Takes in an `InboundMessage` and deserializes the body into a [DhtEnvelope], decompressing the envelope body if
+/// it is compressed. The `next_service` is called with a constructed [DhtInboundMessage] which contains
 /// the relevant comms-level and dht-level information.
 #[derive(Clone)]
 pub struct DhtDeserializeMiddleware<S> {
@@ -70,11 +75,22 @@ where S: Service<DhtInboundMessage, Response = (), Error = PipelineError>
         trace!(target: LOG_TARGET, "Deserializing InboundMessage");
 
         let InboundMessage {
-            source_peer, mut body, ..
+            tag,
+            source_peer,
+            mut body,
+            ..
         } = message;
 
         match DhtEnvelope::decode(&mut body) {
-            Ok(dht_envelope) => {
+            Ok(mut dht_envelope) => {
+                if let Err(err) = Self::decompress_body(&mut dht_envelope) {
+                    error!(
+                        target: LOG_TARGET,
+                        "Failed to decompress message body from NodeId {}: {:?}", source_peer.node_id, err
+                    );
+                    return Err(PipelineError::from_debug(err));
+                }
+
                 trace!(target: LOG_TARGET, "Deserialization succeeded. Checking signatures");
                 if dht_envelope.has_origin() {
                     if dht_envelope.is_origin_signature_valid() {
@@ -92,6 +108,7 @@ where S: Service<DhtInboundMessage, Response = (), Error = PipelineError>
                 }
 
                 let inbound_msg = DhtInboundMessage::new(
+                    tag,
                     dht_envelope.header.try_into().map_err(PipelineError::from_debug)?,
                     source_peer,
                     dht_envelope.body,
@@ -105,6 +122,20 @@ where S: Service<DhtInboundMessage, Response = (), Error = PipelineError>
             },
         }
     }
+
+    /// Decompress the envelope body if the COMPRESSED flag is set. The flag is cleared, as the body is passed on
+    /// uncompressed.
+    fn decompress_body(dht_envelope: &mut DhtEnvelope) -> Result<(), CompressionError> {
+        if let Some(header) = dht_envelope.header.as_mut() {
+            let mut flags = DhtMessageFlags::from_bits_truncate(header.flags);
+            if flags.contains(DhtMessageFlags::COMPRESSED) {
+                dht_envelope.body = compression::decompress(&dht_envelope.body)?;
+                flags.remove(DhtMessageFlags::COMPRESSED);
+                header.flags = flags.bits();
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Default)]
@@ -156,4 +187,26 @@ mod test {
         assert_eq!(msg.body, b"A".to_vec());
         assert_eq!(msg.dht_header, dht_envelope.header.unwrap().try_into().unwrap());
     }
+
+    #[test]
+    fn deserialize_compressed() {
+        let spy = service_spy();
+        let mut deserialize = DeserializeLayer::new().layer(spy.to_service::<PipelineError>());
+
+        let node_identity = make_node_identity();
+        let body = b"compress me ".repeat(10);
+        // The origin signature is over the uncompressed body
+        let mut dht_envelope = make_dht_envelope(&node_identity, body.clone(), DhtMessageFlags::COMPRESSED);
+        dht_envelope.body = compression::compress(&body).unwrap();
+        block_on(deserialize.call(make_comms_inbound_message(
+            &node_identity,
+            dht_envelope.to_encoded_bytes().unwrap().into(),
+            MessageFlags::empty(),
+        )))
+        .unwrap();
+
+        let msg = spy.pop_request().unwrap();
+        assert_eq!(msg.body, body);
+        assert!(!msg.dht_header.flags.contains(DhtMessageFlags::COMPRESSED));
+    }
 }
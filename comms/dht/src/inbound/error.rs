@@ -24,6 +24,7 @@ use crate::{discovery::DhtDiscoveryError, outbound::DhtOutboundError};
 use derive_error::Error;
 use prost::DecodeError;
 use tari_comms::{message::MessageError, peer_manager::PeerManagerError};
+use tari_crypto::tari_utilities::ciphers::cipher::CipherError;
 
 #[derive(Debug, Error)]
 pub enum DhtInboundError {
@@ -42,6 +43,9 @@ pub enum DhtInboundError {
     /// One or more NetAddress in the join message were invalid
     InvalidJoinNetAddresses,
     DhtDiscoveryError(DhtDiscoveryError),
+    CipherError(CipherError),
+    /// None of the wrapped message keys could be used to decrypt the message
+    NoMatchingWrappedKey,
     #[error(msg_embedded, no_from, non_std)]
     OriginRequired(String),
 }
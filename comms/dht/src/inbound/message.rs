@@ -20,23 +20,32 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{consts::DHT_ENVELOPE_HEADER_VERSION, envelope::DhtMessageHeader};
+use crate::{
+    consts::DHT_ENVELOPE_HEADER_VERSION,
+    envelope::{DhtMessageHeader, ReplyBlock},
+};
 use std::{
     fmt::{Display, Error, Formatter},
     sync::Arc,
 };
-use tari_comms::{message::EnvelopeBody, peer_manager::Peer, types::CommsPublicKey};
+use tari_comms::{
+    message::{EnvelopeBody, MessageTag},
+    peer_manager::Peer,
+    types::CommsPublicKey,
+};
 
 #[derive(Debug, Clone)]
 pub struct DhtInboundMessage {
+    pub tag: MessageTag,
     pub version: u32,
     pub source_peer: Arc<Peer>,
     pub dht_header: DhtMessageHeader,
     pub body: Vec<u8>,
 }
 impl DhtInboundMessage {
-    pub fn new(dht_header: DhtMessageHeader, source_peer: Arc<Peer>, body: Vec<u8>) -> Self {
+    pub fn new(tag: MessageTag, dht_header: DhtMessageHeader, source_peer: Arc<Peer>, body: Vec<u8>) -> Self {
         Self {
+            tag,
             version: DHT_ENVELOPE_HEADER_VERSION,
             dht_header,
             source_peer,
@@ -49,7 +58,8 @@ impl Display for DhtInboundMessage {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         write!(
             f,
-            "\n---- DhtInboundMessage ---- \nSize: {} byte(s)\nType: {}\nPeer: {}\nHeader: {}\n----",
+            "\n---- DhtInboundMessage ---- \nTag: {}\nSize: {} byte(s)\nType: {}\nPeer: {}\nHeader: {}\n----",
+            self.tag,
             self.body.len(),
             self.dht_header.message_type,
             self.source_peer,
@@ -61,33 +71,45 @@ impl Display for DhtInboundMessage {
 /// Represents a decrypted InboundMessage.
 #[derive(Debug, Clone)]
 pub struct DecryptedDhtMessage {
+    pub tag: MessageTag,
     pub version: u32,
     /// The _connected_ peer which sent or forwarded this message. This may not be the peer
     /// which created this message.
     pub source_peer: Arc<Peer>,
     pub dht_header: DhtMessageHeader,
     pub decryption_result: Result<EnvelopeBody, Vec<u8>>,
+    /// The single-use reply block included by the sender of an anonymous message
+    pub reply_block: Option<ReplyBlock>,
 }
 
 impl DecryptedDhtMessage {
     pub fn succeeded(decrypted_message: EnvelopeBody, message: DhtInboundMessage) -> Self {
         Self {
+            tag: message.tag,
             version: message.version,
             source_peer: message.source_peer,
             dht_header: message.dht_header,
             decryption_result: Ok(decrypted_message),
+            reply_block: None,
         }
     }
 
     pub fn failed(message: DhtInboundMessage) -> Self {
         Self {
+            tag: message.tag,
             version: message.version,
             source_peer: message.source_peer,
             dht_header: message.dht_header,
             decryption_result: Err(message.body),
+            reply_block: None,
         }
     }
 
+    pub fn with_reply_block(mut self, reply_block: Option<ReplyBlock>) -> Self {
+        self.reply_block = reply_block;
+        self
+    }
+
     pub fn fail(&self) -> Option<&Vec<u8>> {
         self.decryption_result.as_ref().err()
     }
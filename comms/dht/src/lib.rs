@@ -48,7 +48,10 @@
 //! * `BroadcastMiddleware` produces multiple outbound messages according on the `BroadcastStrategy` from the received
 //!   `DhtOutboundRequest` message. The `next_service` is called for each resulting message.
 //! * `EncryptionMiddleware` encrypts the body of a message if `DhtMessagheFlags::ENCRYPTED` is given. The result is
-//!   passed onto the `next_service`.
+//!   passed onto the `next_service`. Anonymous messages are encrypted using an ephemeral key and include a single-use
+//!   `ReplyBlock` which allows the recipient to reply without learning the sender's public key.
+//! * `PriorityMiddleware` queues messages by `DhtMessagePriority` so that high priority messages (e.g. block
+//!   propagation) are passed on before bulk traffic when the `next_service` is not ready.
 //! * `SerializeMiddleware` wraps the body in a `DhtEnvelope`, serializes the result, constructs an `OutboundMessage`
 //!   and calls `next_service`. Typically, `next_service` will be a `SinkMiddleware` which send the message to the comms
 //!   OMS.
@@ -123,6 +126,7 @@ pub use builder::DhtBuilder;
 mod config;
 pub use config::DhtConfig;
 
+mod compression;
 mod consts;
 mod crypt;
 
@@ -133,9 +137,16 @@ mod discovery;
 pub use discovery::DhtDiscoveryRequester;
 
 mod logging_middleware;
+mod metrics;
 mod proto;
 mod tower_filter;
 
+mod reply_keys;
+pub use reply_keys::ReplyKeyStore;
+
+mod routing_table;
+pub use routing_table::{BucketStatus, RoutingTableError, RoutingTableStatus};
+
 pub mod broadcast_strategy;
 pub mod domain_message;
 pub mod envelope;
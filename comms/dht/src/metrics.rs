@@ -0,0 +1,73 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Metrics and tracing for the DHT middleware.
+//!
+//! Each middleware records the time taken to handle a message in the `tari_comms_dht_middleware_duration_seconds`
+//! histogram, which is registered in the default prometheus registry. The recorded duration includes the time taken
+//! by subsequent middleware, so the difference between adjacent middleware is the time spent in each. Message
+//! handling is also wrapped in a tracing span carrying the message tag, so that log events can be correlated with
+//! the message being handled.
+
+use futures::Future;
+use lazy_static::lazy_static;
+use prometheus::{register_histogram_vec, HistogramVec};
+use tari_comms::message::MessageTag;
+use tracing::debug_span;
+use tracing_futures::Instrument;
+
+lazy_static! {
+    static ref MIDDLEWARE_DURATION: HistogramVec = register_histogram_vec!(
+        "tari_comms_dht_middleware_duration_seconds",
+        "Time taken by a DHT middleware to handle a message, including subsequent middleware",
+        &["middleware"]
+    )
+    .expect("DHT middleware metrics registered more than once");
+}
+
+/// Instrument a middleware future with a tracing span and record the time taken for it to resolve
+pub fn instrument<F>(middleware: &'static str, tag: MessageTag, fut: F) -> impl Future<Output = F::Output>
+where F: Future {
+    let timer = MIDDLEWARE_DURATION.with_label_values(&[middleware]).start_timer();
+    let span = debug_span!("dht_middleware", middleware, %tag);
+    async move {
+        let output = fut.await;
+        timer.observe_duration();
+        output
+    }
+    .instrument(span)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{executor::block_on, future};
+
+    #[test]
+    fn instrument_records_duration() {
+        let histogram = MIDDLEWARE_DURATION.with_label_values(&["test"]);
+        let count = histogram.get_sample_count();
+        let output = block_on(instrument("test", MessageTag::new(), future::ready(123)));
+        assert_eq!(output, 123);
+        assert_eq!(histogram.get_sample_count(), count + 1);
+    }
+}
@@ -24,11 +24,13 @@ use super::{error::DhtOutboundError, message::DhtOutboundRequest};
 use crate::{
     actor::DhtRequester,
     broadcast_strategy::BroadcastStrategy,
+    crypt,
     discovery::DhtDiscoveryRequester,
     envelope::{DhtMessageFlags, DhtMessageHeader, DhtMessageOrigin, NodeDestination},
     outbound::{
         message::{DhtOutboundMessage, OutboundEncryption},
         message_params::FinalSendMessageParams,
+        DhtMessagePriority,
         SendMessageResponse,
     },
     proto::envelope::{DhtMessageType, Network},
@@ -240,9 +242,23 @@ where S: Service<DhtOutboundMessage, Response = (), Error = PipelineError>
             encryption,
             is_discovery_enabled,
             force_origin,
+            is_anonymous,
             dht_header,
+            priority,
         } = params;
 
+        if is_anonymous && !encryption.is_encrypt() {
+            let _ = reply_tx.send(SendMessageResponse::Failed);
+            return Err(DhtOutboundError::AnonymousMessageNotEncrypted);
+        }
+
+        if is_anonymous {
+            if let OutboundEncryption::EncryptForMany(_) = encryption {
+                let _ = reply_tx.send(SendMessageResponse::Failed);
+                return Err(DhtOutboundError::AnonymousMessageMultipleRecipients);
+            }
+        }
+
         match self.select_peers(broadcast_strategy.clone()).await {
             Ok(mut peers) => {
                 if reply_tx.is_canceled() {
@@ -298,6 +314,8 @@ where S: Service<DhtOutboundMessage, Response = (), Error = PipelineError>
                         dht_header,
                         dht_message_flags,
                         force_origin,
+                        is_anonymous,
+                        priority,
                         body,
                     )
                     .await
@@ -398,16 +416,22 @@ where S: Service<DhtOutboundMessage, Response = (), Error = PipelineError>
         custom_header: Option<DhtMessageHeader>,
         extra_flags: DhtMessageFlags,
         force_origin: bool,
+        is_anonymous: bool,
+        priority: DhtMessagePriority,
         body: Vec<u8>,
     ) -> Result<Vec<DhtOutboundMessage>, DhtOutboundError>
     {
-        let dht_flags = encryption.flags() | extra_flags;
+        let mut dht_flags = encryption.flags() | extra_flags;
+        if is_anonymous {
+            dht_flags |= DhtMessageFlags::ANONYMOUS;
+        }
 
         // Create a DHT header
-        let dht_header = custom_header
+        let mut dht_header = custom_header
             .or_else(|| {
-                // The origin is specified if encryption is turned on, otherwise it is not
-                let origin = if force_origin || encryption.is_encrypt() {
+                // The origin is specified if encryption is turned on, otherwise it is not. Anonymous messages never
+                // include the origin.
+                let origin = if !is_anonymous && (force_origin || encryption.is_encrypt()) {
                     Some(DhtMessageOrigin {
                         // Origin public key used to identify the origin and verify the signature
                         public_key: self.node_identity.public_key().clone(),
@@ -430,6 +454,18 @@ where S: Service<DhtOutboundMessage, Response = (), Error = PipelineError>
             })
             .expect("always Some");
 
+        // A body for multiple recipients is encrypted once here, rather than for each peer in the encryption
+        // middleware
+        let body = match encryption {
+            OutboundEncryption::EncryptForMany(ref recipients) => {
+                let (cipher_text, wrapped_keys) =
+                    crypt::encrypt_for_many(self.node_identity.secret_key(), recipients, &body)?;
+                dht_header.wrapped_keys = wrapped_keys;
+                cipher_text
+            },
+            _ => body,
+        };
+
         // Construct a MessageEnvelope for each recipient
         let messages = selected_peers
             .into_iter()
@@ -441,6 +477,7 @@ where S: Service<DhtOutboundMessage, Response = (), Error = PipelineError>
                     MessageFlags::NONE,
                     body.clone(),
                 )
+                .with_priority(priority)
             })
             .collect::<Vec<_>>();
 
@@ -456,6 +493,7 @@ mod test {
         test_utils::{
             create_dht_actor_mock,
             create_dht_discovery_mock,
+            make_node_identity,
             make_peer,
             service_spy,
             DhtDiscoveryMockState,
@@ -643,4 +681,115 @@ mod test {
         assert_eq!(tags.len(), 1);
         assert_eq!(spy.call_count(), 1);
     }
+
+    #[test]
+    fn send_message_anonymous() {
+        let mut rt = Runtime::new().unwrap();
+
+        let node_identity = make_node_identity();
+        let (dht_requester, mut dht_mock) = create_dht_actor_mock(10);
+        let (dht_discover_requester, _) = create_dht_discovery_mock(10, Duration::from_secs(10));
+
+        let mock_state = DhtMockState::new();
+        mock_state.set_select_peers_response(vec![make_peer()]);
+        dht_mock.set_shared_state(mock_state);
+        rt.spawn(dht_mock.run());
+
+        let spy = service_spy();
+        let mut service = BroadcastMiddleware::new(
+            spy.to_service::<PipelineError>(),
+            node_identity,
+            dht_requester,
+            dht_discover_requester,
+            Network::LocalTest,
+        );
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        rt.block_on(service.call(DhtOutboundRequest::SendMessage(
+            Box::new(SendMessageParams::new().flood().anonymous().finish()),
+            "custom_msg".as_bytes().to_vec(),
+            reply_tx,
+        )))
+        .unwrap_err();
+        unpack_enum!(SendMessageResponse::Failed = rt.block_on(reply_rx).unwrap());
+        assert_eq!(spy.call_count(), 0);
+
+        let (reply_tx, _reply_rx) = oneshot::channel();
+        rt.block_on(
+            service.call(DhtOutboundRequest::SendMessage(
+                Box::new(
+                    SendMessageParams::new()
+                        .flood()
+                        .with_encryption(OutboundEncryption::EncryptForPeer)
+                        .force_origin()
+                        .anonymous()
+                        .finish(),
+                ),
+                "custom_msg".as_bytes().to_vec(),
+                reply_tx,
+            )),
+        )
+        .unwrap();
+
+        let msg = spy.pop_request().unwrap();
+        assert!(msg.dht_header.origin.is_none());
+        assert!(msg
+            .dht_header
+            .flags
+            .contains(DhtMessageFlags::ENCRYPTED | DhtMessageFlags::ANONYMOUS));
+    }
+
+    #[test]
+    fn send_message_encrypt_for_many() {
+        let mut rt = Runtime::new().unwrap();
+
+        let node_identity = make_node_identity();
+        let (dht_requester, mut dht_mock) = create_dht_actor_mock(10);
+        let (dht_discover_requester, _) = create_dht_discovery_mock(10, Duration::from_secs(10));
+
+        let peers = vec![make_peer(), make_peer()];
+        let mock_state = DhtMockState::new();
+        mock_state.set_select_peers_response(peers.clone());
+        dht_mock.set_shared_state(mock_state);
+        rt.spawn(dht_mock.run());
+
+        let spy = service_spy();
+        let mut service = BroadcastMiddleware::new(
+            spy.to_service::<PipelineError>(),
+            Arc::clone(&node_identity),
+            dht_requester,
+            dht_discover_requester,
+            Network::LocalTest,
+        );
+
+        let public_keys = peers.iter().map(|p| p.public_key.clone()).collect::<Vec<_>>();
+        let (reply_tx, _reply_rx) = oneshot::channel();
+        rt.block_on(
+            service.call(DhtOutboundRequest::SendMessage(
+                Box::new(
+                    SendMessageParams::new()
+                        .direct_public_keys(public_keys.clone())
+                        .with_encryption(OutboundEncryption::EncryptForMany(public_keys))
+                        .finish(),
+                ),
+                "custom_msg".as_bytes().to_vec(),
+                reply_tx,
+            )),
+        )
+        .unwrap();
+
+        assert_eq!(spy.call_count(), 2);
+        let requests = spy.take_requests();
+        // The body is encrypted once and shared by every message
+        assert_ne!(requests[0].body, "custom_msg".as_bytes().to_vec());
+        assert_eq!(requests[0].body, requests[1].body);
+        for msg in requests {
+            assert_eq!(msg.dht_header.wrapped_keys.len(), 2);
+            assert!(msg.dht_header.flags.contains(DhtMessageFlags::ENCRYPTED));
+            assert_eq!(
+                msg.dht_header.origin.as_ref().unwrap().public_key,
+                *node_identity.public_key()
+            );
+        }
+    }
 }
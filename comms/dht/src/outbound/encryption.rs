@@ -22,12 +22,18 @@
 
 use crate::{
     crypt,
+    envelope::DhtMessageFlags,
+    metrics,
     outbound::message::{DhtOutboundMessage, OutboundEncryption},
+    proto::envelope::DhtAnonymousBody,
+    reply_keys::ReplyKeyStore,
 };
 use futures::{task::Context, Future};
 use log::*;
-use std::{sync::Arc, task::Poll};
-use tari_comms::{peer_manager::NodeIdentity, pipeline::PipelineError};
+use rand::rngs::OsRng;
+use std::{mem, sync::Arc, task::Poll};
+use tari_comms::{message::MessageExt, peer_manager::NodeIdentity, pipeline::PipelineError, types::CommsPublicKey};
+use tari_crypto::keys::PublicKey;
 use tower::{layer::Layer, Service, ServiceExt};
 
 const LOG_TARGET: &str = "comms::middleware::encryption";
@@ -35,11 +41,15 @@ const LOG_TARGET: &str = "comms::middleware::encryption";
 /// This layer is responsible for attempting to decrypt inbound messages.
 pub struct EncryptionLayer {
     node_identity: Arc<NodeIdentity>,
+    reply_keys: Arc<ReplyKeyStore>,
 }
 
 impl EncryptionLayer {
-    pub fn new(node_identity: Arc<NodeIdentity>) -> Self {
-        Self { node_identity }
+    pub fn new(node_identity: Arc<NodeIdentity>, reply_keys: Arc<ReplyKeyStore>) -> Self {
+        Self {
+            node_identity,
+            reply_keys,
+        }
     }
 }
 
@@ -47,7 +57,7 @@ impl<S> Layer<S> for EncryptionLayer {
     type Service = EncryptionService<S>;
 
     fn layer(&self, service: S) -> Self::Service {
-        EncryptionService::new(service, Arc::clone(&self.node_identity))
+        EncryptionService::new(service, Arc::clone(&self.node_identity), Arc::clone(&self.reply_keys))
     }
 }
 
@@ -55,14 +65,16 @@ impl<S> Layer<S> for EncryptionLayer {
 #[derive(Clone)]
 pub struct EncryptionService<S> {
     node_identity: Arc<NodeIdentity>,
+    reply_keys: Arc<ReplyKeyStore>,
     inner: S,
 }
 
 impl<S> EncryptionService<S> {
-    pub fn new(service: S, node_identity: Arc<NodeIdentity>) -> Self {
+    pub fn new(service: S, node_identity: Arc<NodeIdentity>, reply_keys: Arc<ReplyKeyStore>) -> Self {
         Self {
             inner: service,
             node_identity,
+            reply_keys,
         }
     }
 }
@@ -80,7 +92,17 @@ where S: Service<DhtOutboundMessage, Response = (), Error = PipelineError> + Clo
     }
 
     fn call(&mut self, msg: DhtOutboundMessage) -> Self::Future {
-        Self::handle_message(self.inner.clone(), Arc::clone(&self.node_identity), msg)
+        let tag = msg.tag;
+        metrics::instrument(
+            "encrypt",
+            tag,
+            Self::handle_message(
+                self.inner.clone(),
+                Arc::clone(&self.node_identity),
+                Arc::clone(&self.reply_keys),
+                msg,
+            ),
+        )
     }
 }
 
@@ -90,42 +112,82 @@ where S: Service<DhtOutboundMessage, Response = (), Error = PipelineError>
     async fn handle_message(
         next_service: S,
         node_identity: Arc<NodeIdentity>,
+        reply_keys: Arc<ReplyKeyStore>,
         mut message: DhtOutboundMessage,
     ) -> Result<(), PipelineError>
     {
         trace!(target: LOG_TARGET, "DHT Message flags: {:?}", message.dht_header.flags);
-        match &message.encryption {
+        let public_key = match &message.encryption {
             OutboundEncryption::EncryptFor(public_key) => {
                 debug!(target: LOG_TARGET, "Encrypting message for {}", public_key);
-                let shared_secret = crypt::generate_ecdh_secret(node_identity.secret_key(), &**public_key);
-                message.body = crypt::encrypt(&shared_secret, &message.body).map_err(PipelineError::from_debug)?;
+                (**public_key).clone()
             },
             OutboundEncryption::EncryptForPeer => {
                 debug!(
                     target: LOG_TARGET,
                     "Encrypting message for peer with public key {}", message.destination_peer.public_key
                 );
-                let shared_secret =
-                    crypt::generate_ecdh_secret(node_identity.secret_key(), &message.destination_peer.public_key);
-                message.body = crypt::encrypt(&shared_secret, &message.body).map_err(PipelineError::from_debug)?
+                message.destination_peer.public_key.clone()
+            },
+            OutboundEncryption::EncryptForMany(_) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Message body is already encrypted for {} recipient(s)",
+                    message.dht_header.wrapped_keys.len()
+                );
+                return next_service.oneshot(message).await;
             },
             OutboundEncryption::None => {
                 debug!(target: LOG_TARGET, "Encryption not requested for message");
+                return next_service.oneshot(message).await;
             },
         };
 
+        if message.dht_header.flags.contains(DhtMessageFlags::ANONYMOUS) {
+            Self::encrypt_anonymous(&node_identity, &reply_keys, &public_key, &mut message)?;
+        } else {
+            let shared_secret = crypt::generate_ecdh_secret(node_identity.secret_key(), &public_key);
+            message.body = crypt::encrypt(&shared_secret, &message.body).map_err(PipelineError::from_debug)?;
+        }
+
         next_service.oneshot(message).await
     }
+
+    /// Includes a new single-use reply block in the body and encrypts it using an ephemeral key. The ephemeral public
+    /// key is set in the header so that the recipient can decrypt the message without learning the public key of this
+    /// node.
+    fn encrypt_anonymous(
+        node_identity: &NodeIdentity,
+        reply_keys: &ReplyKeyStore,
+        public_key: &CommsPublicKey,
+        message: &mut DhtOutboundMessage,
+    ) -> Result<(), PipelineError>
+    {
+        let reply_block = reply_keys.create_reply_block(node_identity.node_id());
+        let anonymous_body = DhtAnonymousBody {
+            body: mem::take(&mut message.body),
+            reply_block: Some(reply_block.into()),
+        };
+        let plain_text = anonymous_body.to_encoded_bytes().map_err(PipelineError::from_debug)?;
+
+        let (ephemeral_secret_key, ephemeral_public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let shared_secret = crypt::generate_ecdh_secret(&ephemeral_secret_key, public_key);
+        message.body = crypt::encrypt(&shared_secret, &plain_text).map_err(PipelineError::from_debug)?;
+        message.dht_header.ephemeral_public_key = Some(ephemeral_public_key);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{
-        envelope::DhtMessageFlags,
-        test_utils::{make_dht_header, make_node_identity, service_spy},
+        envelope::{DhtMessageFlags, ReplyBlock},
+        test_utils::{make_dht_header, make_node_identity, make_reply_key_store, service_spy},
     };
     use futures::executor::block_on;
+    use prost::Message;
+    use std::convert::TryInto;
     use tari_comms::{
         message::MessageFlags,
         net_address::MultiaddressesWithStats,
@@ -138,7 +200,8 @@ mod test {
     fn no_encryption() {
         let spy = service_spy();
         let node_identity = make_node_identity();
-        let mut encryption = EncryptionLayer::new(Arc::clone(&node_identity)).layer(spy.to_service::<PipelineError>());
+        let mut encryption = EncryptionLayer::new(Arc::clone(&node_identity), make_reply_key_store())
+            .layer(spy.to_service::<PipelineError>());
 
         panic_context!(cx);
         assert!(encryption.poll_ready(&mut cx).is_ready());
@@ -169,7 +232,8 @@ mod test {
     fn encryption() {
         let spy = service_spy();
         let node_identity = make_node_identity();
-        let mut encryption = EncryptionLayer::new(Arc::clone(&node_identity)).layer(spy.to_service::<PipelineError>());
+        let mut encryption = EncryptionLayer::new(Arc::clone(&node_identity), make_reply_key_store())
+            .layer(spy.to_service::<PipelineError>());
 
         panic_context!(cx);
         assert!(encryption.poll_ready(&mut cx).is_ready());
@@ -195,4 +259,46 @@ mod test {
         assert_ne!(msg.body, body);
         assert_eq!(msg.destination_peer.node_id, NodeId::default());
     }
+
+    #[test]
+    fn encryption_anonymous() {
+        let spy = service_spy();
+        let node_identity = make_node_identity();
+        let reply_keys = make_reply_key_store();
+        let mut encryption = EncryptionLayer::new(Arc::clone(&node_identity), Arc::clone(&reply_keys))
+            .layer(spy.to_service::<PipelineError>());
+
+        let recipient = make_node_identity();
+        let body = b"A".to_vec();
+        let mut dht_header = make_dht_header(&node_identity, &body, DhtMessageFlags::ENCRYPTED);
+        dht_header.flags |= DhtMessageFlags::ANONYMOUS;
+        dht_header.origin = None;
+        let msg = DhtOutboundMessage::new(
+            Peer::new(
+                recipient.public_key().clone(),
+                recipient.node_id().clone(),
+                MultiaddressesWithStats::new(vec![]),
+                PeerFlags::empty(),
+                PeerFeatures::COMMUNICATION_NODE,
+                &[],
+            ),
+            dht_header,
+            OutboundEncryption::EncryptForPeer,
+            MessageFlags::empty(),
+            body.clone(),
+        );
+        block_on(encryption.call(msg)).unwrap();
+
+        let msg = spy.pop_request().unwrap();
+        assert!(msg.dht_header.origin.is_none());
+        let ephemeral_public_key = msg.dht_header.ephemeral_public_key.unwrap();
+        assert_ne!(&ephemeral_public_key, node_identity.public_key());
+
+        let shared_secret = crypt::generate_ecdh_secret(recipient.secret_key(), &ephemeral_public_key);
+        let decrypted = crypt::decrypt(&shared_secret, &msg.body).unwrap();
+        let anonymous_body = DhtAnonymousBody::decode(decrypted.as_slice()).unwrap();
+        assert_eq!(anonymous_body.body, body);
+        let reply_block: ReplyBlock = anonymous_body.reply_block.unwrap().try_into().unwrap();
+        assert!(reply_keys.get(&reply_block.public_key).is_some());
+    }
 }
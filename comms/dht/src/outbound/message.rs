@@ -22,7 +22,7 @@
 
 use crate::{
     envelope::{DhtMessageFlags, DhtMessageHeader},
-    outbound::message_params::FinalSendMessageParams,
+    outbound::{message_params::FinalSendMessageParams, priority::DhtMessagePriority},
 };
 use futures::channel::oneshot;
 use std::{fmt, fmt::Display};
@@ -45,13 +45,18 @@ pub enum OutboundEncryption {
     /// public key. Each message sent according to the broadcast strategy will be encrypted for
     /// the destination peer.
     EncryptForPeer,
+    /// Message body should be encrypted once using a random message key, which is wrapped for each of the given
+    /// public keys. The wrapped keys are included in the DHT header.
+    EncryptForMany(Vec<CommsPublicKey>),
 }
 
 impl OutboundEncryption {
     /// Return the correct DHT flags for the encryption setting
     pub fn flags(&self) -> DhtMessageFlags {
         match self {
-            OutboundEncryption::EncryptFor(_) | OutboundEncryption::EncryptForPeer => DhtMessageFlags::ENCRYPTED,
+            OutboundEncryption::EncryptFor(_) |
+            OutboundEncryption::EncryptForPeer |
+            OutboundEncryption::EncryptForMany(_) => DhtMessageFlags::ENCRYPTED,
             _ => DhtMessageFlags::NONE,
         }
     }
@@ -61,7 +66,7 @@ impl OutboundEncryption {
         use OutboundEncryption::*;
         match self {
             None => false,
-            EncryptFor(_) | EncryptForPeer => true,
+            EncryptFor(_) | EncryptForPeer | EncryptForMany(_) => true,
         }
     }
 }
@@ -72,6 +77,7 @@ impl Display for OutboundEncryption {
             OutboundEncryption::None => write!(f, "None"),
             OutboundEncryption::EncryptFor(ref key) => write!(f, "EncryptFor:{}", key.to_hex()),
             OutboundEncryption::EncryptForPeer => write!(f, "EncryptForPeer"),
+            OutboundEncryption::EncryptForMany(ref keys) => write!(f, "EncryptForMany({} keys)", keys.len()),
         }
     }
 }
@@ -150,6 +156,7 @@ pub struct DhtOutboundMessage {
     pub dht_header: DhtMessageHeader,
     pub comms_flags: MessageFlags,
     pub encryption: OutboundEncryption,
+    pub priority: DhtMessagePriority,
     pub body: Vec<u8>,
 }
 
@@ -169,9 +176,16 @@ impl DhtOutboundMessage {
             dht_header,
             encryption,
             comms_flags,
+            priority: Default::default(),
             body,
         }
     }
+
+    /// Set the priority of this message
+    pub fn with_priority(mut self, priority: DhtMessagePriority) -> Self {
+        self.priority = priority;
+        self
+    }
 }
 
 impl fmt::Display for DhtOutboundMessage {
@@ -179,13 +193,14 @@ impl fmt::Display for DhtOutboundMessage {
         write!(
             f,
             "\n---- DhtOutboundMessage ---- \nSize: {} byte(s)\nType: {}\nPeer: {}\nHeader: {} \nFlags: \
-             {:?}\nEncryption: {}\n{}\n----",
+             {:?}\nEncryption: {}\nPriority: {}\n{}\n----",
             self.body.len(),
             self.dht_header.message_type,
             self.destination_peer,
             self.dht_header,
             self.dht_header.flags,
             self.encryption,
+            self.priority,
             self.tag
         )
     }
@@ -23,7 +23,7 @@
 use crate::{
     broadcast_strategy::{BroadcastClosestRequest, BroadcastStrategy},
     envelope::{DhtMessageFlags, DhtMessageHeader, NodeDestination},
-    outbound::OutboundEncryption,
+    outbound::{DhtMessagePriority, OutboundEncryption},
     proto::envelope::DhtMessageType,
 };
 use std::{fmt, fmt::Display};
@@ -63,9 +63,11 @@ pub struct FinalSendMessageParams {
     pub encryption: OutboundEncryption,
     pub is_discovery_enabled: bool,
     pub force_origin: bool,
+    pub is_anonymous: bool,
     pub dht_message_type: DhtMessageType,
     pub dht_message_flags: DhtMessageFlags,
     pub dht_header: Option<DhtMessageHeader>,
+    pub priority: DhtMessagePriority,
 }
 
 impl Default for FinalSendMessageParams {
@@ -77,8 +79,10 @@ impl Default for FinalSendMessageParams {
             dht_message_type: Default::default(),
             dht_message_flags: Default::default(),
             force_origin: false,
+            is_anonymous: false,
             is_discovery_enabled: true,
             dht_header: None,
+            priority: Default::default(),
         }
     }
 }
@@ -87,8 +91,8 @@ impl Display for FinalSendMessageParams {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
             f,
-            "BroadcastStrategy: {}, Destination: {}",
-            self.broadcast_strategy, self.destination
+            "BroadcastStrategy: {}, Destination: {}, Priority: {}",
+            self.broadcast_strategy, self.destination, self.priority
         )
     }
 }
@@ -104,6 +108,12 @@ impl SendMessageParams {
         self
     }
 
+    /// Set broadcast_strategy to DirectPublicKeys
+    pub fn direct_public_keys(&mut self, public_keys: Vec<CommsPublicKey>) -> &mut Self {
+        self.params_mut().broadcast_strategy = BroadcastStrategy::DirectPublicKeys(public_keys);
+        self
+    }
+
     /// Set broadcast_strategy to DirectNodeId
     pub fn direct_node_id(&mut self, node_id: NodeId) -> &mut Self {
         self.params_mut().broadcast_strategy = BroadcastStrategy::DirectNodeId(Box::new(node_id));
@@ -189,6 +199,13 @@ impl SendMessageParams {
         self
     }
 
+    /// Set the priority of the message. Higher priority messages are sent before lower priority messages when the
+    /// outbound pipeline is under load.
+    pub fn with_priority(&mut self, priority: DhtMessagePriority) -> &mut Self {
+        self.params_mut().priority = priority;
+        self
+    }
+
     /// Force the message origin to be included in the message. The origin is usually not included in messages without
     /// encryption, however this setting will force the message origin and signature to be included.
     pub fn force_origin(&mut self) -> &mut Self {
@@ -196,6 +213,14 @@ impl SendMessageParams {
         self
     }
 
+    /// Send the message anonymously. The origin is omitted from the message and the body is encrypted using an
+    /// ephemeral key. A single-use reply block is included so that the recipient is able to reply without learning
+    /// the public key of this node. Encryption must be set for anonymous messages.
+    pub fn anonymous(&mut self) -> &mut Self {
+        self.params_mut().is_anonymous = true;
+        self
+    }
+
     /// Return the final SendMessageParams
     pub fn finish(&mut self) -> FinalSendMessageParams {
         self.params.take().expect("cannot be None")
@@ -25,6 +25,7 @@ mod encryption;
 mod error;
 pub(crate) mod message;
 mod message_params;
+mod priority;
 mod requester;
 mod serialize;
 
@@ -37,6 +38,7 @@ pub use self::{
     error::DhtOutboundError,
     message::{DhtOutboundRequest, OutboundEncryption, SendMessageResponse},
     message_params::SendMessageParams,
+    priority::{DhtMessagePriority, PriorityLayer},
     requester::OutboundMessageRequester,
     serialize::SerializeLayer,
 };
@@ -0,0 +1,254 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::outbound::message::DhtOutboundMessage;
+use futures::{task::Context, Future};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fmt,
+    sync::{Arc, Mutex},
+    task::Poll,
+};
+use tari_comms::{connection_manager::DialPriority, pipeline::PipelineError};
+use tower::{layer::Layer, Service, ServiceExt};
+
+/// The priority of an outbound message. When the outbound pipeline is under load, messages with a higher priority
+/// are passed on to the serialize middleware (and ultimately comms) before lower priority messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DhtMessagePriority {
+    /// Bulk traffic which can tolerate delays e.g. store and forward responses and forwarded messages
+    Low,
+    /// The default priority
+    Normal,
+    /// Time-sensitive traffic e.g. block propagation and direct replies to wallets
+    High,
+}
+
+impl Default for DhtMessagePriority {
+    fn default() -> Self {
+        DhtMessagePriority::Normal
+    }
+}
+
+impl From<DhtMessagePriority> for DialPriority {
+    fn from(priority: DhtMessagePriority) -> Self {
+        match priority {
+            DhtMessagePriority::Low => DialPriority::Low,
+            DhtMessagePriority::Normal => DialPriority::Normal,
+            DhtMessagePriority::High => DialPriority::High,
+        }
+    }
+}
+
+impl fmt::Display for DhtMessagePriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Layer which produces a `PriorityMiddleware`
+#[derive(Default)]
+pub struct PriorityLayer;
+
+impl PriorityLayer {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for PriorityLayer {
+    type Service = PriorityMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        PriorityMiddleware::new(service)
+    }
+}
+
+/// Middleware which queues outbound messages according to their `DhtMessagePriority`.
+///
+/// Every call pushes the message onto a queue which is shared between all clones of this middleware and then waits
+/// for the next service to be ready. Once it is ready, the highest priority message waiting in the queue (which is not
+/// necessarily the message given to this call) is passed on. Messages of equal priority are passed on in the order in
+/// which they were received.
+#[derive(Clone)]
+pub struct PriorityMiddleware<S> {
+    next_service: S,
+    queue: Arc<Mutex<PriorityQueue>>,
+}
+
+impl<S> PriorityMiddleware<S> {
+    pub fn new(service: S) -> Self {
+        Self {
+            next_service: service,
+            queue: Arc::new(Mutex::new(PriorityQueue::new())),
+        }
+    }
+}
+
+impl<S> Service<DhtOutboundMessage> for PriorityMiddleware<S>
+where S: Service<DhtOutboundMessage, Response = (), Error = PipelineError> + Clone + 'static
+{
+    type Error = PipelineError;
+    type Response = ();
+
+    type Future = impl Future<Output = Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, msg: DhtOutboundMessage) -> Self::Future {
+        acquire_lock!(self.queue).push(msg);
+        let queue = Arc::clone(&self.queue);
+        let mut next_service = self.next_service.clone();
+        async move {
+            let next_service = next_service.ready_and().await?;
+            // If another call has already taken the last message, there is nothing left for this call to do
+            let next_msg = acquire_lock!(queue).pop();
+            match next_msg {
+                Some(msg) => next_service.call(msg).await,
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+/// A queue of outbound messages ordered by priority and then by arrival order
+struct PriorityQueue {
+    heap: BinaryHeap<PrioritisedMessage>,
+    next_seq: u64,
+}
+
+impl PriorityQueue {
+    fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            next_seq: 0,
+        }
+    }
+
+    fn push(&mut self, message: DhtOutboundMessage) {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        self.heap.push(PrioritisedMessage { seq, message });
+    }
+
+    fn pop(&mut self) -> Option<DhtOutboundMessage> {
+        self.heap.pop().map(|m| m.message)
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+}
+
+struct PrioritisedMessage {
+    seq: u64,
+    message: DhtOutboundMessage,
+}
+
+impl PartialEq for PrioritisedMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.message.priority == other.message.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for PrioritisedMessage {}
+
+impl PartialOrd for PrioritisedMessage {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PrioritisedMessage {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap: higher priority first, then the lowest sequence number (oldest) first
+        self.message
+            .priority
+            .cmp(&other.message.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        envelope::DhtMessageFlags,
+        outbound::OutboundEncryption,
+        test_utils::{make_dht_header, make_node_identity, make_peer, service_spy},
+    };
+    use futures::executor::block_on;
+    use tari_comms::message::MessageFlags;
+    use tari_test_utils::panic_context;
+
+    fn make_message(priority: DhtMessagePriority, body: &[u8]) -> DhtOutboundMessage {
+        let node_identity = make_node_identity();
+        DhtOutboundMessage::new(
+            make_peer(),
+            make_dht_header(&node_identity, body, DhtMessageFlags::empty()),
+            OutboundEncryption::None,
+            MessageFlags::empty(),
+            body.to_vec(),
+        )
+        .with_priority(priority)
+    }
+
+    #[test]
+    fn queue_ordering() {
+        let mut queue = PriorityQueue::new();
+        queue.push(make_message(DhtMessagePriority::Low, b"low"));
+        queue.push(make_message(DhtMessagePriority::Normal, b"normal1"));
+        queue.push(make_message(DhtMessagePriority::High, b"high"));
+        queue.push(make_message(DhtMessagePriority::Normal, b"normal2"));
+        assert_eq!(queue.len(), 4);
+
+        let bodies = (0..4).map(|_| queue.pop().unwrap().body).collect::<Vec<_>>();
+        assert_eq!(bodies, vec![
+            b"high".to_vec(),
+            b"normal1".to_vec(),
+            b"normal2".to_vec(),
+            b"low".to_vec()
+        ]);
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn passes_messages_through() {
+        let spy = service_spy();
+        let mut service = PriorityLayer::new().layer(spy.to_service::<PipelineError>());
+
+        panic_context!(cx);
+        assert!(service.poll_ready(&mut cx).is_ready());
+
+        let msg = make_message(DhtMessagePriority::High, b"A");
+        block_on(service.call(msg)).unwrap();
+
+        let msg = spy.pop_request().unwrap();
+        assert_eq!(msg.body, b"A".to_vec());
+        assert_eq!(msg.priority, DhtMessagePriority::High);
+        assert_eq!(acquire_lock!(service.queue).len(), 0);
+    }
+}
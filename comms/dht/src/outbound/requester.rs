@@ -22,20 +22,28 @@
 
 use super::message::DhtOutboundRequest;
 use crate::{
+    config::DEFAULT_NUM_NEIGHBOURING_NODES,
     domain_message::OutboundDomainMessage,
-    envelope::NodeDestination,
+    envelope::{NodeDestination, ReplyBlock},
     outbound::{
         message::{OutboundEncryption, SendMessageResponse},
         message_params::{FinalSendMessageParams, SendMessageParams},
+        DhtMessagePriority,
         DhtOutboundError,
     },
 };
 use futures::{
     channel::{mpsc, oneshot},
-    SinkExt,
+    future,
+    task::{Context, Poll},
 };
 use log::*;
-use tari_comms::{message::MessageExt, peer_manager::NodeId, types::CommsPublicKey, wrap_in_envelope_body};
+use tari_comms::{
+    message::MessageExt,
+    peer_manager::{NodeId, PeerFeatures},
+    types::CommsPublicKey,
+    wrap_in_envelope_body,
+};
 
 const LOG_TARGET: &str = "comms::dht::requests::outbound";
 
@@ -49,6 +57,17 @@ impl OutboundMessageRequester {
         Self { sender }
     }
 
+    /// Returns `Poll::Ready(Ok(()))` once the outbound queue has capacity for another message from this requester.
+    /// Once ready, the next call to `try_send_raw` will not fail with `DhtOutboundError::QueueFull`.
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), DhtOutboundError>> {
+        self.sender.poll_ready(cx).map_err(Into::into)
+    }
+
+    /// Wait until the outbound queue has capacity for another message from this requester
+    pub async fn ready(&mut self) -> Result<(), DhtOutboundError> {
+        future::poll_fn(|cx| self.poll_ready(cx)).await
+    }
+
     /// Send directly to a peer.
     pub async fn send_direct<T>(
         &mut self,
@@ -56,6 +75,22 @@ impl OutboundMessageRequester {
         encryption: OutboundEncryption,
         message: OutboundDomainMessage<T>,
     ) -> Result<SendMessageResponse, DhtOutboundError>
+    where
+        T: prost::Message,
+    {
+        self.send_direct_with_priority(dest_public_key, encryption, DhtMessagePriority::Normal, message)
+            .await
+    }
+
+    /// Send directly to a peer with the given priority. The priority also determines how urgently the peer is dialed
+    /// if there is no connection to it.
+    pub async fn send_direct_with_priority<T>(
+        &mut self,
+        dest_public_key: CommsPublicKey,
+        encryption: OutboundEncryption,
+        priority: DhtMessagePriority,
+        message: OutboundDomainMessage<T>,
+    ) -> Result<SendMessageResponse, DhtOutboundError>
     where
         T: prost::Message,
     {
@@ -64,6 +99,7 @@ impl OutboundMessageRequester {
                 .direct_public_key(dest_public_key)
                 .with_encryption(encryption)
                 .with_discovery(true)
+                .with_priority(priority)
                 .finish(),
             message,
         )
@@ -91,6 +127,26 @@ impl OutboundMessageRequester {
         .await
     }
 
+    /// Send directly to each of the given peers. The message body is encrypted once and the message key is wrapped
+    /// for each recipient, rather than encrypting the full body for each peer.
+    pub async fn send_direct_encrypted_many<T>(
+        &mut self,
+        dest_public_keys: Vec<CommsPublicKey>,
+        message: OutboundDomainMessage<T>,
+    ) -> Result<SendMessageResponse, DhtOutboundError>
+    where
+        T: prost::Message,
+    {
+        self.send_message(
+            SendMessageParams::new()
+                .direct_public_keys(dest_public_keys.clone())
+                .with_encryption(OutboundEncryption::EncryptForMany(dest_public_keys))
+                .finish(),
+            message,
+        )
+        .await
+    }
+
     /// Send to a pre-configured number of closest peers.
     ///
     /// Each message is destined for each peer.
@@ -178,6 +234,33 @@ impl OutboundMessageRequester {
         .await
     }
 
+    /// Reply to an anonymous message using the single-use `ReplyBlock` included in that message. The reply is
+    /// encrypted for and addressed to the reply block public key and sent towards the network region of the sender.
+    pub async fn send_reply<T>(
+        &mut self,
+        reply_block: ReplyBlock,
+        message: OutboundDomainMessage<T>,
+    ) -> Result<SendMessageResponse, DhtOutboundError>
+    where
+        T: prost::Message,
+    {
+        let ReplyBlock { public_key, node_id } = reply_block;
+        self.send_message(
+            SendMessageParams::new()
+                .closest(
+                    node_id,
+                    DEFAULT_NUM_NEIGHBOURING_NODES,
+                    Vec::new(),
+                    PeerFeatures::MESSAGE_PROPAGATION,
+                )
+                .with_destination(public_key.clone().into())
+                .with_encryption(OutboundEncryption::EncryptFor(Box::new(public_key)))
+                .finish(),
+            message,
+        )
+        .await
+    }
+
     /// Send a message with custom parameters
     pub async fn send_message<T>(
         &mut self,
@@ -215,17 +298,35 @@ impl OutboundMessageRequester {
         self.send_raw(params, body).await
     }
 
-    /// Send a raw message
+    /// Send a raw message, waiting for the outbound queue to have capacity
     pub async fn send_raw(
         &mut self,
         params: FinalSendMessageParams,
         body: Vec<u8>,
     ) -> Result<SendMessageResponse, DhtOutboundError>
+    {
+        self.ready().await?;
+        self.try_send_raw(params, body).await
+    }
+
+    /// Send a raw message without waiting for the outbound queue to have capacity. If the queue is full,
+    /// `DhtOutboundError::QueueFull` is returned and the message is discarded.
+    pub async fn try_send_raw(
+        &mut self,
+        params: FinalSendMessageParams,
+        body: Vec<u8>,
+    ) -> Result<SendMessageResponse, DhtOutboundError>
     {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.sender
-            .send(DhtOutboundRequest::SendMessage(Box::new(params), body, reply_tx))
-            .await?;
+            .try_send(DhtOutboundRequest::SendMessage(Box::new(params), body, reply_tx))
+            .map_err(|err| {
+                if err.is_full() {
+                    DhtOutboundError::QueueFull
+                } else {
+                    err.into_send_error().into()
+                }
+            })?;
 
         reply_rx
             .await
@@ -237,3 +338,32 @@ impl OutboundMessageRequester {
         self.sender.clone()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{task::noop_waker_ref, FutureExt};
+    use tari_test_utils::unpack_enum;
+
+    #[test]
+    fn try_send_raw_queue_full() {
+        let (tx, _rx) = mpsc::channel(0);
+        let mut requester = OutboundMessageRequester::new(tx);
+
+        // Each sender is guaranteed a single slot, so the first message is queued and waits for a reply
+        let result = requester
+            .try_send_raw(SendMessageParams::new().flood().finish(), vec![])
+            .now_or_never();
+        assert!(result.is_none());
+
+        let err = requester
+            .try_send_raw(SendMessageParams::new().flood().finish(), vec![])
+            .now_or_never()
+            .unwrap()
+            .unwrap_err();
+        unpack_enum!(DhtOutboundError::QueueFull = err);
+
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert!(requester.poll_ready(&mut cx).is_pending());
+    }
+}
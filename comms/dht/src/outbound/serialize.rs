@@ -20,14 +20,20 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{outbound::message::DhtOutboundMessage, proto::envelope::DhtEnvelope};
+use crate::{
+    compression,
+    envelope::DhtMessageFlags,
+    metrics,
+    outbound::message::DhtOutboundMessage,
+    proto::envelope::DhtEnvelope,
+};
 use futures::{task::Context, Future};
 use log::*;
 use rand::rngs::OsRng;
 use std::{sync::Arc, task::Poll};
 use tari_comms::{
     message::{MessageExt, OutboundMessage},
-    peer_manager::NodeIdentity,
+    peer_manager::{NodeIdentity, PeerFeatures},
     pipeline::PipelineError,
     utils::signature,
     Bytes,
@@ -41,13 +47,15 @@ const LOG_TARGET: &str = "comms::dht::serialize";
 pub struct SerializeMiddleware<S> {
     inner: S,
     node_identity: Arc<NodeIdentity>,
+    compression_threshold: Option<usize>,
 }
 
 impl<S> SerializeMiddleware<S> {
-    pub fn new(service: S, node_identity: Arc<NodeIdentity>) -> Self {
+    pub fn new(service: S, node_identity: Arc<NodeIdentity>, compression_threshold: Option<usize>) -> Self {
         Self {
             inner: service,
             node_identity,
+            compression_threshold,
         }
     }
 }
@@ -60,12 +68,24 @@ where S: Service<OutboundMessage, Response = (), Error = PipelineError> + Clone
 
     type Future = impl Future<Output = Result<Self::Response, Self::Error>>;
 
-    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Readiness of the next service (usually a sink to comms) is passed up so that the priority queue can hold
+        // back lower priority messages while the sink is full
+        self.inner.poll_ready(cx)
     }
 
     fn call(&mut self, msg: DhtOutboundMessage) -> Self::Future {
-        Self::serialize(self.inner.clone(), Arc::clone(&self.node_identity), msg)
+        let tag = msg.tag;
+        metrics::instrument(
+            "serialize",
+            tag,
+            Self::serialize(
+                self.inner.clone(),
+                Arc::clone(&self.node_identity),
+                self.compression_threshold,
+                msg,
+            ),
+        )
     }
 }
 
@@ -75,6 +95,7 @@ where S: Service<OutboundMessage, Response = (), Error = PipelineError>
     pub async fn serialize(
         next_service: S,
         node_identity: Arc<NodeIdentity>,
+        compression_threshold: Option<usize>,
         message: DhtOutboundMessage,
     ) -> Result<(), PipelineError>
     {
@@ -82,9 +103,10 @@ where S: Service<OutboundMessage, Response = (), Error = PipelineError>
 
         let DhtOutboundMessage {
             mut dht_header,
-            body,
+            mut body,
             destination_peer,
             comms_flags,
+            priority,
             ..
         } = message;
 
@@ -117,28 +139,51 @@ where S: Service<OutboundMessage, Response = (), Error = PipelineError>
             }
         }
 
+        // The body is compressed after signing, so the origin signature is always over the uncompressed body
+        let should_compress = compression_threshold
+            .map(|threshold| body.len() > threshold)
+            .unwrap_or(false) &&
+            destination_peer.features.contains(PeerFeatures::COMPRESSION);
+        if should_compress {
+            let compressed = compression::compress(&body).map_err(PipelineError::from_debug)?;
+            // Bodies which do not compress well (e.g. encrypted bodies) are sent uncompressed
+            if compressed.len() < body.len() {
+                trace!(
+                    target: LOG_TARGET,
+                    "Compressed message {:?} body from {} to {} bytes",
+                    message.tag,
+                    body.len(),
+                    compressed.len()
+                );
+                body = compressed;
+                dht_header.flags.insert(DhtMessageFlags::COMPRESSED);
+            }
+        }
+
         let envelope = DhtEnvelope::new(dht_header.into(), body);
 
         let body = Bytes::from(envelope.to_encoded_bytes().map_err(PipelineError::from_debug)?);
 
         next_service
-            .oneshot(OutboundMessage::with_tag(
-                message.tag,
-                destination_peer.node_id,
-                comms_flags,
-                body,
-            ))
+            .oneshot(
+                OutboundMessage::with_tag(message.tag, destination_peer.node_id, comms_flags, body)
+                    .with_dial_priority(priority.into()),
+            )
             .await
     }
 }
 
 pub struct SerializeLayer {
     node_identity: Arc<NodeIdentity>,
+    compression_threshold: Option<usize>,
 }
 
 impl SerializeLayer {
-    pub fn new(node_identity: Arc<NodeIdentity>) -> Self {
-        Self { node_identity }
+    pub fn new(node_identity: Arc<NodeIdentity>, compression_threshold: Option<usize>) -> Self {
+        Self {
+            node_identity,
+            compression_threshold,
+        }
     }
 }
 
@@ -146,7 +191,7 @@ impl<S> Layer<S> for SerializeLayer {
     type Service = SerializeMiddleware<S>;
 
     fn layer(&self, service: S) -> Self::Service {
-        SerializeMiddleware::new(service, Arc::clone(&self.node_identity))
+        SerializeMiddleware::new(service, Arc::clone(&self.node_identity), self.compression_threshold)
     }
 }
 
@@ -155,12 +200,13 @@ mod test {
     use super::*;
     use crate::{
         envelope::DhtMessageFlags,
-        outbound::OutboundEncryption,
+        outbound::{DhtMessagePriority, OutboundEncryption},
         test_utils::{make_dht_header, make_node_identity, service_spy},
     };
     use futures::executor::block_on;
     use prost::Message;
     use tari_comms::{
+        connection_manager::DialPriority,
         message::MessageFlags,
         net_address::MultiaddressesWithStats,
         peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
@@ -172,7 +218,7 @@ mod test {
     fn serialize() {
         let spy = service_spy();
         let node_identity = make_node_identity();
-        let mut serialize = SerializeLayer::new(Arc::clone(&node_identity)).layer(spy.to_service::<PipelineError>());
+        let mut serialize = SerializeLayer::new(Arc::clone(&node_identity), None).layer(spy.to_service::<PipelineError>());
 
         panic_context!(cx);
 
@@ -191,12 +237,55 @@ mod test {
             OutboundEncryption::None,
             MessageFlags::empty(),
             body,
-        );
+        )
+        .with_priority(DhtMessagePriority::High);
         block_on(serialize.call(msg)).unwrap();
 
         let mut msg = spy.pop_request().unwrap();
         let dht_envelope = DhtEnvelope::decode(&mut msg.body).unwrap();
         assert_eq!(dht_envelope.body, b"A".to_vec());
         assert_eq!(msg.peer_node_id, NodeId::default());
+        assert_eq!(msg.dial_priority, DialPriority::High);
+    }
+
+    #[test]
+    fn serialize_compressed() {
+        let spy = service_spy();
+        let node_identity = make_node_identity();
+        let mut serialize =
+            SerializeLayer::new(Arc::clone(&node_identity), Some(10)).layer(spy.to_service::<PipelineError>());
+
+        let body = b"compress me ".repeat(10);
+        let make_msg = |features| {
+            DhtOutboundMessage::new(
+                Peer::new(
+                    CommsPublicKey::default(),
+                    NodeId::default(),
+                    MultiaddressesWithStats::new(vec![]),
+                    PeerFlags::empty(),
+                    features,
+                    &[],
+                ),
+                make_dht_header(&node_identity, &body, DhtMessageFlags::empty()),
+                OutboundEncryption::None,
+                MessageFlags::empty(),
+                body.clone(),
+            )
+        };
+
+        block_on(serialize.call(make_msg(PeerFeatures::COMMUNICATION_NODE))).unwrap();
+        let mut msg = spy.pop_request().unwrap();
+        let dht_envelope = DhtEnvelope::decode(&mut msg.body).unwrap();
+        let flags = DhtMessageFlags::from_bits_truncate(dht_envelope.header.unwrap().flags);
+        assert!(flags.contains(DhtMessageFlags::COMPRESSED));
+        assert_eq!(compression::decompress(&dht_envelope.body).unwrap(), body);
+
+        // Peers which do not support compression receive the uncompressed body
+        block_on(serialize.call(make_msg(PeerFeatures::COMMUNICATION_CLIENT))).unwrap();
+        let mut msg = spy.pop_request().unwrap();
+        let dht_envelope = DhtEnvelope::decode(&mut msg.body).unwrap();
+        let flags = DhtMessageFlags::from_bits_truncate(dht_envelope.header.unwrap().flags);
+        assert!(!flags.contains(DhtMessageFlags::COMPRESSED));
+        assert_eq!(dht_envelope.body, body);
     }
 }
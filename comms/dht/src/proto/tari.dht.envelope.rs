@@ -15,6 +15,14 @@ pub struct DhtHeader {
     pub network: i32,
     #[prost(uint32, tag = "8")]
     pub flags: u32,
+    /// Ephemeral public key used to encrypt the body of an anonymous message. This must be specified if the ANONYMOUS
+    /// flag is set, in which case the origin is omitted.
+    #[prost(bytes, tag = "9")]
+    pub ephemeral_public_key: std::vec::Vec<u8>,
+    /// Message keys for a body that has been encrypted once for multiple recipients. Each key is encrypted using a
+    /// shared secret derived from the origin public key and the public key of one of the recipients.
+    #[prost(bytes, repeated, tag = "10")]
+    pub wrapped_keys: ::std::vec::Vec<std::vec::Vec<u8>>,
     #[prost(oneof = "dht_header::Destination", tags = "2, 3, 4")]
     pub destination: ::std::option::Option<dht_header::Destination>,
 }
@@ -47,6 +55,26 @@ pub struct DhtOrigin {
     #[prost(bytes, tag = "2")]
     pub signature: std::vec::Vec<u8>,
 }
+/// A single-use reply block which allows the recipient of an anonymous message to reply to the sender without
+/// learning the sender's public key.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DhtReplyBlock {
+    /// Single-use public key which replies should be encrypted for and addressed to
+    #[prost(bytes, tag = "1")]
+    pub public_key: std::vec::Vec<u8>,
+    /// Approximate network region of the sender
+    #[prost(bytes, tag = "2")]
+    pub node_id: std::vec::Vec<u8>,
+}
+/// The plain text body of an anonymous message. This is encrypted before being sent.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DhtAnonymousBody {
+    /// The serialized EnvelopeBody
+    #[prost(bytes, tag = "1")]
+    pub body: std::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub reply_block: ::std::option::Option<DhtReplyBlock>,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum DhtMessageType {
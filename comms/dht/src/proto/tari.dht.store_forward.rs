@@ -23,4 +23,7 @@ pub struct StoredMessage {
 pub struct StoredMessagesResponse {
     #[prost(message, repeated, tag = "1")]
     pub messages: ::std::vec::Vec<StoredMessage>,
+    /// The time at which the response was sent according to the clock of the responding node
+    #[prost(message, optional, tag = "2")]
+    pub responded_at: ::std::option::Option<::prost_types::Timestamp>,
 }
@@ -0,0 +1,103 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::envelope::ReplyBlock;
+use rand::{rngs::OsRng, RngCore};
+use std::{sync::Mutex, time::Duration};
+use tari_comms::{
+    peer_manager::NodeId,
+    types::{CommsPublicKey, CommsSecretKey},
+};
+use tari_crypto::{keys::PublicKey, tari_utilities::ByteArray};
+use ttl_cache::TtlCache;
+
+/// The number of leading node id bytes included in a reply block. The remaining bytes are randomised so that the
+/// reply block only discloses the approximate network region of the sender.
+const REPLY_BLOCK_NODE_ID_PREFIX_LEN: usize = 2;
+
+/// Holds the secret keys of reply blocks sent with anonymous messages until a reply is received or the key expires.
+pub struct ReplyKeyStore {
+    keys: Mutex<TtlCache<Vec<u8>, CommsSecretKey>>,
+    ttl: Duration,
+}
+
+impl ReplyKeyStore {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            keys: Mutex::new(TtlCache::new(capacity)),
+            ttl,
+        }
+    }
+
+    /// Create a new single-use reply block for a message sent from the given node id. The secret key for the reply
+    /// block is retained by this store.
+    pub fn create_reply_block(&self, node_id: &NodeId) -> ReplyBlock {
+        let (secret_key, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        acquire_lock!(self.keys).insert(public_key.to_vec(), secret_key, self.ttl);
+        ReplyBlock {
+            public_key,
+            node_id: obscure_node_id(node_id),
+        }
+    }
+
+    /// Returns the secret key for the given reply block public key, if it exists and has not expired
+    pub fn get(&self, public_key: &CommsPublicKey) -> Option<CommsSecretKey> {
+        acquire_lock!(self.keys).get(&public_key.to_vec()).cloned()
+    }
+
+    /// Remove the secret key for the given reply block public key. This should be called once a reply has been
+    /// received as reply blocks may only be used once.
+    pub fn remove(&self, public_key: &CommsPublicKey) -> Option<CommsSecretKey> {
+        acquire_lock!(self.keys).remove(&public_key.to_vec())
+    }
+}
+
+/// Keeps the leading bytes of the node id and randomises the rest
+fn obscure_node_id(node_id: &NodeId) -> NodeId {
+    let mut bytes = node_id.to_vec();
+    OsRng.fill_bytes(&mut bytes[REPLY_BLOCK_NODE_ID_PREFIX_LEN..]);
+    NodeId::from_bytes(&bytes).expect("node id bytes have the correct length")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::make_node_identity;
+
+    #[test]
+    fn create_reply_block() {
+        let node_identity = make_node_identity();
+        let store = ReplyKeyStore::new(10, Duration::from_secs(60));
+        let reply_block = store.create_reply_block(node_identity.node_id());
+
+        assert_eq!(
+            reply_block.node_id.as_bytes()[..REPLY_BLOCK_NODE_ID_PREFIX_LEN],
+            node_identity.node_id().as_bytes()[..REPLY_BLOCK_NODE_ID_PREFIX_LEN]
+        );
+
+        let secret_key = store.get(&reply_block.public_key).unwrap();
+        assert_eq!(CommsPublicKey::from_secret_key(&secret_key), reply_block.public_key);
+
+        assert!(store.remove(&reply_block.public_key).is_some());
+        assert!(store.get(&reply_block.public_key).is_none());
+    }
+}
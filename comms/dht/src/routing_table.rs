@@ -0,0 +1,368 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+//! # Routing table
+//!
+//! The DHT keeps a record of the peers in its neighbourhood that it has recently been in contact with. The record is
+//! persisted so that a restarted node can rejoin its neighbourhood straight away, rather than rebuilding its view of
+//! the network from the seed nodes. The freshness of each entry decays with the time since the peer was last seen and
+//! entries that are no longer fresh are dropped.
+
+use chrono::{DateTime, Utc};
+use derive_error::Error;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs,
+    io,
+    path::Path,
+    time::Duration,
+};
+use tari_comms::{
+    multiaddr::Multiaddr,
+    peer_manager::{node_id::deserialize_node_id_from_hex, NodeId, Peer, PeerFeatures, PeerFlags},
+    types::CommsPublicKey,
+};
+use tari_crypto::tari_utilities::hex::serialize_to_hex;
+
+/// Entries with a freshness below this value are dropped from the routing table
+pub const MIN_FRESHNESS: f64 = 0.05;
+
+#[derive(Debug, Error)]
+pub enum RoutingTableError {
+    /// Failed to read or write the routing table file
+    IoError(io::Error),
+    /// The routing table file could not be serialized or deserialized
+    JsonError(serde_json::Error),
+}
+
+/// A neighbourhood peer recorded in the routing table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingTableEntry {
+    #[serde(serialize_with = "serialize_to_hex")]
+    #[serde(deserialize_with = "deserialize_node_id_from_hex")]
+    pub node_id: NodeId,
+    pub public_key: CommsPublicKey,
+    pub addresses: Vec<Multiaddr>,
+    pub features: PeerFeatures,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl RoutingTableEntry {
+    /// The freshness of the entry between 0 and 1, which halves every `half_life` since the peer was last seen
+    pub fn freshness(&self, now: DateTime<Utc>, half_life: Duration) -> f64 {
+        let age = now.signed_duration_since(self.last_seen).to_std().unwrap_or_default();
+        0.5f64.powf(age.as_secs_f64() / half_life.as_secs_f64())
+    }
+
+    /// Create a peer from the entry, used to add peers to the peer manager that it no longer knows about
+    pub fn to_peer(&self) -> Peer {
+        Peer::new(
+            self.public_key.clone(),
+            self.node_id.clone(),
+            self.addresses.clone().into(),
+            PeerFlags::empty(),
+            self.features,
+            &[],
+        )
+    }
+}
+
+/// The DHT's record of recently seen neighbourhood peers
+pub struct RoutingTable {
+    node_id: NodeId,
+    capacity: usize,
+    half_life: Duration,
+    entries: HashMap<NodeId, RoutingTableEntry>,
+}
+
+impl RoutingTable {
+    pub fn new(node_id: NodeId, capacity: usize, half_life: Duration) -> Self {
+        Self {
+            node_id,
+            capacity,
+            half_life,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Load the routing table from `path`, dropping entries which are no longer fresh. A missing file results in an
+    /// empty routing table.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        node_id: NodeId,
+        capacity: usize,
+        half_life: Duration,
+    ) -> Result<Self, RoutingTableError>
+    {
+        let mut table = Self::new(node_id, capacity, half_life);
+        if !path.as_ref().exists() {
+            return Ok(table);
+        }
+        let entries = serde_json::from_str::<Vec<RoutingTableEntry>>(&fs::read_to_string(path)?)?;
+        table.update(entries, Utc::now());
+        Ok(table)
+    }
+
+    /// Save the routing table to `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RoutingTableError> {
+        if let Some(parent) = path.as_ref().parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let entries = self.entries.values().collect::<Vec<_>>();
+        fs::write(path, serde_json::to_string_pretty(&entries)?)?;
+        Ok(())
+    }
+
+    /// Record the peers, which have been seen at least once, in the routing table. Only the `capacity` freshest
+    /// entries that are closest to this node are kept.
+    pub fn update_from_peers(&mut self, peers: Vec<Peer>, now: DateTime<Utc>) {
+        let entries = peers
+            .into_iter()
+            .filter_map(|peer| {
+                let last_seen = peer.last_seen()?;
+                Some(RoutingTableEntry {
+                    addresses: peer.addresses.addresses.iter().map(|a| a.address.clone()).collect(),
+                    node_id: peer.node_id,
+                    public_key: peer.public_key,
+                    features: peer.features,
+                    last_seen,
+                })
+            })
+            .collect();
+        self.update(entries, now);
+    }
+
+    fn update(&mut self, entries: Vec<RoutingTableEntry>, now: DateTime<Utc>) {
+        for entry in entries {
+            let is_newer = self
+                .entries
+                .get(&entry.node_id)
+                .map(|existing| existing.last_seen < entry.last_seen)
+                .unwrap_or(true);
+            if is_newer {
+                self.entries.insert(entry.node_id.clone(), entry);
+            }
+        }
+
+        let half_life = self.half_life;
+        self.entries
+            .retain(|_, entry| entry.freshness(now, half_life) >= MIN_FRESHNESS);
+        if self.entries.len() > self.capacity {
+            let keep = self
+                .closest(self.capacity)
+                .into_iter()
+                .map(|entry| entry.node_id.clone())
+                .collect::<Vec<_>>();
+            self.entries.retain(|node_id, _| keep.contains(node_id));
+        }
+    }
+
+    /// Returns up to `n` entries, closest to this node first
+    pub fn closest(&self, n: usize) -> Vec<&RoutingTableEntry> {
+        let mut entries = self.entries.values().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| self.node_id.distance(&entry.node_id));
+        entries.truncate(n);
+        entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Summarise the routing table entries and the given known peers by bucket
+    pub fn status(&self, known_peers: &[Peer], now: DateTime<Utc>) -> RoutingTableStatus {
+        let mut buckets = BTreeMap::new();
+        for peer in known_peers {
+            let index = self.node_id.distance(&peer.node_id).get_bucket_index();
+            let bucket = buckets.entry(index).or_insert_with(|| BucketStatus::new(index));
+            bucket.num_peers += 1;
+            if !peer.is_offline() && !peer.is_banned() {
+                bucket.num_online += 1;
+            }
+        }
+        for entry in self.entries.values() {
+            let index = self.node_id.distance(&entry.node_id).get_bucket_index();
+            buckets
+                .entry(index)
+                .or_insert_with(|| BucketStatus::new(index))
+                .num_cached += 1;
+        }
+        let total_freshness = self
+            .entries
+            .values()
+            .map(|entry| entry.freshness(now, self.half_life))
+            .sum::<f64>();
+
+        RoutingTableStatus {
+            buckets: buckets.into_iter().map(|(_, bucket)| bucket).collect(),
+            num_cached: self.entries.len(),
+            mean_freshness: if self.entries.is_empty() {
+                0.0
+            } else {
+                total_freshness / self.entries.len() as f64
+            },
+        }
+    }
+}
+
+/// The number of peers at a given distance from this node
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketStatus {
+    /// The number of leading zero bits shared by node ids in this bucket and the node id of this node
+    pub index: u32,
+    /// Peers known to the peer manager
+    pub num_peers: usize,
+    /// Known peers that are neither offline nor banned
+    pub num_online: usize,
+    /// Peers recorded in the routing table
+    pub num_cached: usize,
+}
+
+impl BucketStatus {
+    fn new(index: u32) -> Self {
+        Self {
+            index,
+            num_peers: 0,
+            num_online: 0,
+            num_cached: 0,
+        }
+    }
+}
+
+/// The occupancy of the non-empty routing table buckets
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoutingTableStatus {
+    pub buckets: Vec<BucketStatus>,
+    pub num_cached: usize,
+    pub mean_freshness: f64,
+}
+
+impl fmt::Display for RoutingTableStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>6} {:>6} {:>6} {:>6}", "Bucket", "Peers", "Online", "Cached")?;
+        for bucket in &self.buckets {
+            writeln!(
+                f,
+                "{:>6} {:>6} {:>6} {:>6}",
+                bucket.index, bucket.num_peers, bucket.num_online, bucket.num_cached
+            )?;
+        }
+        write!(
+            f,
+            "{} neighbour(s) cached, mean freshness {:.2}",
+            self.num_cached, self.mean_freshness
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::make_node_identity;
+    use chrono::Duration as ChronoDuration;
+    use tempdir::TempDir;
+
+    const HALF_LIFE: Duration = Duration::from_secs(60 * 60);
+
+    fn make_entry(last_seen: DateTime<Utc>) -> RoutingTableEntry {
+        let node_identity = make_node_identity();
+        RoutingTableEntry {
+            node_id: node_identity.node_id().clone(),
+            public_key: node_identity.public_key().clone(),
+            addresses: vec![node_identity.public_address()],
+            features: node_identity.features(),
+            last_seen,
+        }
+    }
+
+    #[test]
+    fn freshness_decays() {
+        let now = Utc::now();
+        let entry = make_entry(now);
+        assert!((entry.freshness(now, HALF_LIFE) - 1.0).abs() < 1e-9);
+        let later = now + ChronoDuration::hours(2);
+        assert!((entry.freshness(later, HALF_LIFE) - 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn update_drops_stale_and_distant_entries() {
+        let node_identity = make_node_identity();
+        let now = Utc::now();
+        let mut table = RoutingTable::new(node_identity.node_id().clone(), 2, HALF_LIFE);
+        let stale = make_entry(now - ChronoDuration::hours(10));
+        let fresh = (0..3).map(|_| make_entry(now)).collect::<Vec<_>>();
+        let mut entries = fresh.clone();
+        entries.push(stale);
+        table.update(entries, now);
+
+        assert_eq!(table.len(), 2);
+        let mut expected = fresh.iter().collect::<Vec<_>>();
+        expected.sort_by_key(|entry| node_identity.node_id().distance(&entry.node_id));
+        expected.truncate(2);
+        assert_eq!(table.closest(2), expected);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = TempDir::new("routing_table").unwrap();
+        let path = dir.path().join("routing_table.json");
+        let node_identity = make_node_identity();
+        let now = Utc::now();
+        let mut table = RoutingTable::new(node_identity.node_id().clone(), 10, HALF_LIFE);
+        let entry = make_entry(now);
+        table.update(vec![entry.clone(), make_entry(now - ChronoDuration::hours(3))], now);
+        table.save(&path).unwrap();
+
+        // With a shorter half life the older entry is no longer fresh
+        let loaded = RoutingTable::load(&path, node_identity.node_id().clone(), 10, Duration::from_secs(40 * 60)).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded.closest(1), vec![&entry]);
+
+        let missing = RoutingTable::load(dir.path().join("missing.json"), NodeId::new(), 10, HALF_LIFE).unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn status() {
+        let node_identity = make_node_identity();
+        let now = Utc::now();
+        let mut table = RoutingTable::new(node_identity.node_id().clone(), 10, HALF_LIFE);
+        let entry = make_entry(now);
+        let mut offline = make_entry(now).to_peer();
+        offline.set_offline(true);
+        table.update(vec![entry.clone()], now);
+
+        let status = table.status(&[entry.to_peer(), offline], now);
+        assert_eq!(status.num_cached, 1);
+        assert!((status.mean_freshness - 1.0).abs() < 1e-9);
+        assert_eq!(status.buckets.iter().map(|b| b.num_peers).sum::<usize>(), 2);
+        assert_eq!(status.buckets.iter().map(|b| b.num_online).sum::<usize>(), 1);
+        assert_eq!(status.buckets.iter().map(|b| b.num_cached).sum::<usize>(), 1);
+    }
+}
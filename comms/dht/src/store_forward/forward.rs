@@ -23,7 +23,8 @@
 use crate::{
     envelope::{DhtMessageHeader, NodeDestination},
     inbound::DecryptedDhtMessage,
-    outbound::{OutboundMessageRequester, SendMessageParams},
+    metrics,
+    outbound::{DhtMessagePriority, OutboundMessageRequester, SendMessageParams},
     proto::envelope::DhtMessageType,
     store_forward::error::StoreAndForwardError,
 };
@@ -100,12 +101,13 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError> + Cl
     }
 
     fn call(&mut self, msg: DecryptedDhtMessage) -> Self::Future {
-        Forwarder::new(
+        let tag = msg.tag;
+        let forwarder = Forwarder::new(
             self.next_service.clone(),
             Arc::clone(&self.peer_manager),
             self.outbound_service.clone(),
-        )
-        .handle(msg)
+        );
+        metrics::instrument("forward", tag, forwarder.handle(msg))
     }
 }
 
@@ -176,7 +178,9 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         }
         let mut message_params = self.get_send_params(&dht_header, excluded_peers).await?;
 
-        message_params.with_dht_header(dht_header.clone());
+        message_params
+            .with_dht_header(dht_header.clone())
+            .with_priority(DhtMessagePriority::Low);
 
         self.outbound_service.send_raw(message_params.finish(), body).await?;
 
@@ -24,7 +24,7 @@ use crate::{
     envelope::DhtMessageHeader,
     proto::store_forward::{StoredMessage, StoredMessagesRequest, StoredMessagesResponse},
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use prost_types::Timestamp;
 
 /// Utility function that converts a `chrono::DateTime` to a `prost::Timestamp`
@@ -35,6 +35,13 @@ pub(crate) fn datetime_to_timestamp(datetime: DateTime<Utc>) -> Timestamp {
     }
 }
 
+/// Utility function that converts a `prost::Timestamp` to a `chrono::DateTime`. None is returned if the timestamp is
+/// out of range.
+pub(crate) fn timestamp_to_datetime(timestamp: &Timestamp) -> Option<DateTime<Utc>> {
+    NaiveDateTime::from_timestamp_opt(timestamp.seconds, timestamp.nanos as u32)
+        .map(|naive| DateTime::from_utc(naive, Utc))
+}
+
 impl StoredMessagesRequest {
     pub fn since(since: DateTime<Utc>) -> Self {
         Self {
@@ -66,6 +73,9 @@ impl StoredMessagesResponse {
 
 impl From<Vec<StoredMessage>> for StoredMessagesResponse {
     fn from(messages: Vec<StoredMessage>) -> Self {
-        Self { messages }
+        Self {
+            messages,
+            responded_at: None,
+        }
     }
 }
@@ -23,6 +23,7 @@
 mod error;
 mod forward;
 mod message;
+mod retrieval;
 mod saf_handler;
 mod state;
 mod store;
@@ -30,6 +31,7 @@ mod store;
 pub use self::{
     error::StoreAndForwardError,
     forward::ForwardLayer,
+    retrieval::{SafRetrievalService, StoredMessagesReceived},
     saf_handler::MessageHandlerLayer,
     state::SafStorage,
     store::StoreLayer,
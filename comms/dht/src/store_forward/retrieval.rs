@@ -0,0 +1,329 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{actor::DhtRequester, DhtConfig};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use futures::{channel::mpsc, FutureExt, StreamExt};
+use log::*;
+use std::{
+    cmp,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tari_comms::{
+    connection_manager::ConnectionManagerRequester,
+    peer_manager::{NodeId, PeerFeatures, PeerManager},
+    ConnectionManagerEvent,
+};
+use tari_shutdown::ShutdownSignal;
+use tokio::time;
+
+const LOG_TARGET: &str = "comms::dht::store_forward::retrieval";
+
+/// The minimum time between stored message requests triggered by new peer connections. This prevents a flood of
+/// requests when many connections are established at once (e.g. on startup).
+const MIN_REQUEST_INTERVAL_ON_CONNECT: Duration = Duration::from_secs(30);
+
+/// Sent by the store and forward message handler to the `SafRetrievalService` when stored messages are received from
+/// a peer.
+#[derive(Debug, Clone)]
+pub struct StoredMessagesReceived {
+    /// The number of messages which were new to this node (i.e. not duplicates) and were successfully processed
+    pub num_new_messages: usize,
+    /// The most recent `stored_at` timestamp of the returned messages, adjusted to this node's clock
+    pub latest_stored_at: Option<DateTime<Utc>>,
+}
+
+/// Requests stored messages from neighbouring store and forward nodes on startup, whenever a connection to a store
+/// and forward node is established and periodically thereafter.
+///
+/// A cursor is kept of the most recent message received so that each request only asks for messages stored since
+/// then. If a request returns no new messages, the interval between periodic requests is doubled (up to
+/// `DhtConfig::saf_auto_request_max_interval`) and is reset once new messages are received.
+pub struct SafRetrievalService {
+    config: DhtConfig,
+    dht_requester: DhtRequester,
+    connection_manager: ConnectionManagerRequester,
+    peer_manager: Arc<PeerManager>,
+    response_rx: Option<mpsc::Receiver<StoredMessagesReceived>>,
+    shutdown_signal: Option<ShutdownSignal>,
+    cursor: Option<DateTime<Utc>>,
+    current_interval: Duration,
+    last_request_at: Option<Instant>,
+    is_awaiting_first_response: bool,
+}
+
+impl SafRetrievalService {
+    pub fn new(
+        config: DhtConfig,
+        dht_requester: DhtRequester,
+        connection_manager: ConnectionManagerRequester,
+        peer_manager: Arc<PeerManager>,
+        response_rx: mpsc::Receiver<StoredMessagesReceived>,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self
+    {
+        Self {
+            current_interval: config.saf_auto_request_interval,
+            config,
+            dht_requester,
+            connection_manager,
+            peer_manager,
+            response_rx: Some(response_rx),
+            shutdown_signal: Some(shutdown_signal),
+            cursor: None,
+            last_request_at: None,
+            is_awaiting_first_response: false,
+        }
+    }
+
+    pub async fn run(mut self) {
+        if !self.config.saf_auto_request {
+            info!(
+                target: LOG_TARGET,
+                "Automatic stored message requests are disabled. SafRetrievalService will not run."
+            );
+            return;
+        }
+
+        let mut shutdown_signal = self
+            .shutdown_signal
+            .take()
+            .expect("SafRetrievalService initialized without shutdown_signal")
+            .fuse();
+
+        let mut response_rx = self
+            .response_rx
+            .take()
+            .expect("SafRetrievalService initialized without response_rx")
+            .fuse();
+
+        let mut connection_events = self.connection_manager.get_event_subscription().fuse();
+
+        self.request_stored_messages().await;
+        let mut next_request = time::delay_for(self.current_interval).fuse();
+
+        loop {
+            futures::select! {
+                response = response_rx.select_next_some() => {
+                    self.handle_stored_messages_received(response);
+                },
+
+                event = connection_events.select_next_some() => {
+                    if let Ok(event) = event {
+                        if let ConnectionManagerEvent::PeerConnected(conn) = &*event {
+                            self.handle_peer_connected(conn.peer_node_id()).await;
+                        }
+                    }
+                },
+
+                _ = next_request => {
+                    self.request_stored_messages().await;
+                    next_request = time::delay_for(self.current_interval).fuse();
+                },
+
+                _ = shutdown_signal => {
+                    info!(target: LOG_TARGET, "SafRetrievalService is shutting down because the shutdown signal was received");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn handle_peer_connected(&mut self, node_id: &NodeId) {
+        let is_recent = self
+            .last_request_at
+            .map(|at| at.elapsed() < MIN_REQUEST_INTERVAL_ON_CONNECT)
+            .unwrap_or(false);
+        if is_recent {
+            return;
+        }
+
+        match self.peer_manager.find_by_node_id(node_id).await {
+            Ok(peer) if peer.features.contains(PeerFeatures::DHT_STORE_FORWARD) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Requesting stored messages because a connection was established to store and forward node '{}'",
+                    node_id.short_str()
+                );
+                self.request_stored_messages().await;
+            },
+            Ok(_) => {},
+            Err(err) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Unable to find newly connected peer '{}' because '{:?}'",
+                    node_id.short_str(),
+                    err
+                );
+            },
+        }
+    }
+
+    async fn request_stored_messages(&mut self) {
+        let result = match self.request_since() {
+            Some(since) => {
+                trace!(target: LOG_TARGET, "Requesting stored messages since {}", since);
+                self.dht_requester.send_request_stored_messages_since(since).await
+            },
+            None => {
+                trace!(target: LOG_TARGET, "Requesting all stored messages");
+                self.dht_requester.send_request_stored_messages().await
+            },
+        };
+
+        match result {
+            Ok(_) => {
+                self.last_request_at = Some(Instant::now());
+                self.is_awaiting_first_response = true;
+            },
+            Err(err) => {
+                error!(
+                    target: LOG_TARGET,
+                    "Failed to send request for stored messages because '{}'", err
+                );
+            },
+        }
+    }
+
+    /// The time from which stored messages are requested. The cursor is in this node's time while peers compare it to
+    /// their own clocks, so it is moved back by the tolerated clock skew. Messages received again because of this
+    /// overlap are discarded as duplicates.
+    fn request_since(&self) -> Option<DateTime<Utc>> {
+        self.cursor.map(|cursor| {
+            ChronoDuration::from_std(self.config.saf_max_clock_skew)
+                .ok()
+                .and_then(|skew| cursor.checked_sub_signed(skew))
+                .unwrap_or(cursor)
+        })
+    }
+
+    fn handle_stored_messages_received(&mut self, response: StoredMessagesReceived) {
+        let StoredMessagesReceived {
+            num_new_messages,
+            latest_stored_at,
+        } = response;
+
+        if let Some(latest) = latest_stored_at {
+            self.cursor = Some(self.cursor.map(|c| cmp::max(c, latest)).unwrap_or(latest));
+        }
+
+        if num_new_messages > 0 {
+            self.current_interval = self.config.saf_auto_request_interval;
+        } else if self.is_awaiting_first_response {
+            // Only back off once per request, even though many peers may respond to it
+            self.current_interval = cmp::min(self.current_interval * 2, self.config.saf_auto_request_max_interval);
+        }
+        self.is_awaiting_first_response = false;
+
+        debug!(
+            target: LOG_TARGET,
+            "Received {} new stored message(s). Next request in {:.0?} (cursor = {:?})",
+            num_new_messages,
+            self.current_interval,
+            self.cursor
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{create_dht_actor_mock, make_peer_manager};
+    use chrono::Duration as ChronoDuration;
+    use tari_comms::test_utils::mocks::create_connection_manager_mock;
+    use tari_shutdown::Shutdown;
+
+    fn create_service() -> SafRetrievalService {
+        let (dht_requester, _) = create_dht_actor_mock(1);
+        let (connection_manager, _) = create_connection_manager_mock(1);
+        let (_, response_rx) = mpsc::channel(1);
+        let shutdown = Shutdown::new();
+        SafRetrievalService::new(
+            DhtConfig::default_local_test(),
+            dht_requester,
+            connection_manager,
+            make_peer_manager(),
+            response_rx,
+            shutdown.to_signal(),
+        )
+    }
+
+    #[test]
+    fn cursor_advances() {
+        let mut service = create_service();
+        let earlier = Utc::now() - ChronoDuration::hours(1);
+        let later = Utc::now();
+
+        service.handle_stored_messages_received(StoredMessagesReceived {
+            num_new_messages: 1,
+            latest_stored_at: Some(later),
+        });
+        assert_eq!(service.cursor, Some(later));
+
+        // The cursor never moves backwards
+        service.handle_stored_messages_received(StoredMessagesReceived {
+            num_new_messages: 1,
+            latest_stored_at: Some(earlier),
+        });
+        assert_eq!(service.cursor, Some(later));
+
+        // Peers are asked for messages from before the cursor to allow for the skew between their clocks and ours
+        let skew = ChronoDuration::from_std(service.config.saf_max_clock_skew).unwrap();
+        assert_eq!(service.request_since(), Some(later - skew));
+    }
+
+    #[test]
+    fn backoff_when_nothing_new() {
+        let mut service = create_service();
+        let base_interval = service.config.saf_auto_request_interval;
+
+        service.is_awaiting_first_response = true;
+        service.handle_stored_messages_received(StoredMessagesReceived {
+            num_new_messages: 0,
+            latest_stored_at: None,
+        });
+        assert_eq!(service.current_interval, base_interval * 2);
+
+        // Further responses to the same request do not increase the backoff
+        service.handle_stored_messages_received(StoredMessagesReceived {
+            num_new_messages: 0,
+            latest_stored_at: None,
+        });
+        assert_eq!(service.current_interval, base_interval * 2);
+
+        for _ in 0..20 {
+            service.is_awaiting_first_response = true;
+            service.handle_stored_messages_received(StoredMessagesReceived {
+                num_new_messages: 0,
+                latest_stored_at: None,
+            });
+        }
+        assert_eq!(service.current_interval, service.config.saf_auto_request_max_interval);
+
+        service.handle_stored_messages_received(StoredMessagesReceived {
+            num_new_messages: 3,
+            latest_stored_at: None,
+        });
+        assert_eq!(service.current_interval, base_interval);
+    }
+}
@@ -21,7 +21,13 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::middleware::MessageHandlerMiddleware;
-use crate::{actor::DhtRequester, config::DhtConfig, outbound::OutboundMessageRequester, store_forward::SafStorage};
+use crate::{
+    actor::DhtRequester,
+    config::DhtConfig,
+    outbound::OutboundMessageRequester,
+    store_forward::{SafStorage, StoredMessagesReceived},
+};
+use futures::channel::mpsc;
 use std::sync::Arc;
 use tari_comms::peer_manager::{NodeIdentity, PeerManager};
 use tower::layer::Layer;
@@ -33,6 +39,7 @@ pub struct MessageHandlerLayer {
     peer_manager: Arc<PeerManager>,
     node_identity: Arc<NodeIdentity>,
     outbound_service: OutboundMessageRequester,
+    saf_response_tx: mpsc::Sender<StoredMessagesReceived>,
 }
 
 impl MessageHandlerLayer {
@@ -43,6 +50,7 @@ impl MessageHandlerLayer {
         node_identity: Arc<NodeIdentity>,
         peer_manager: Arc<PeerManager>,
         outbound_service: OutboundMessageRequester,
+        saf_response_tx: mpsc::Sender<StoredMessagesReceived>,
     ) -> Self
     {
         Self {
@@ -52,6 +60,7 @@ impl MessageHandlerLayer {
             node_identity,
             peer_manager,
             outbound_service,
+            saf_response_tx,
         }
     }
 }
@@ -68,6 +77,7 @@ impl<S> Layer<S> for MessageHandlerLayer {
             Arc::clone(&self.node_identity),
             Arc::clone(&self.peer_manager),
             self.outbound_service.clone(),
+            self.saf_response_tx.clone(),
         )
     }
 }
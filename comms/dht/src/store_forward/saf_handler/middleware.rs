@@ -25,10 +25,11 @@ use crate::{
     actor::DhtRequester,
     config::DhtConfig,
     inbound::DecryptedDhtMessage,
+    metrics,
     outbound::OutboundMessageRequester,
-    store_forward::SafStorage,
+    store_forward::{SafStorage, StoredMessagesReceived},
 };
-use futures::{task::Context, Future};
+use futures::{channel::mpsc, task::Context, Future};
 use std::{sync::Arc, task::Poll};
 use tari_comms::{
     peer_manager::{NodeIdentity, PeerManager},
@@ -45,9 +46,11 @@ pub struct MessageHandlerMiddleware<S> {
     peer_manager: Arc<PeerManager>,
     node_identity: Arc<NodeIdentity>,
     outbound_service: OutboundMessageRequester,
+    saf_response_tx: mpsc::Sender<StoredMessagesReceived>,
 }
 
 impl<S> MessageHandlerMiddleware<S> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: DhtConfig,
         next_service: S,
@@ -56,6 +59,7 @@ impl<S> MessageHandlerMiddleware<S> {
         node_identity: Arc<NodeIdentity>,
         peer_manager: Arc<PeerManager>,
         outbound_service: OutboundMessageRequester,
+        saf_response_tx: mpsc::Sender<StoredMessagesReceived>,
     ) -> Self
     {
         Self {
@@ -66,6 +70,7 @@ impl<S> MessageHandlerMiddleware<S> {
             node_identity,
             peer_manager,
             outbound_service,
+            saf_response_tx,
         }
     }
 }
@@ -83,7 +88,8 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError> + Cl
     }
 
     fn call(&mut self, message: DecryptedDhtMessage) -> Self::Future {
-        MessageHandlerTask::new(
+        let tag = message.tag;
+        let task = MessageHandlerTask::new(
             self.config.clone(),
             self.next_service.clone(),
             Arc::clone(&self.store),
@@ -91,8 +97,9 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError> + Cl
             Arc::clone(&self.peer_manager),
             self.outbound_service.clone(),
             Arc::clone(&self.node_identity),
+            self.saf_response_tx.clone(),
             message,
-        )
-        .run()
+        );
+        metrics::instrument("saf_handler", tag, task.run())
     }
 }
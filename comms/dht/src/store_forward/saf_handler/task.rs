@@ -26,20 +26,26 @@ use crate::{
     crypt,
     envelope::{Destination, DhtMessageFlags, DhtMessageHeader, DhtMessageOrigin, NodeDestination},
     inbound::{DecryptedDhtMessage, DhtInboundMessage},
-    outbound::{OutboundMessageRequester, SendMessageParams},
+    outbound::{DhtMessagePriority, OutboundMessageRequester, SendMessageParams},
     proto::{
         envelope::DhtMessageType,
         store_forward::{StoredMessage, StoredMessagesRequest, StoredMessagesResponse},
     },
-    store_forward::{error::StoreAndForwardError, SafStorage},
+    store_forward::{
+        error::StoreAndForwardError,
+        message::{datetime_to_timestamp, timestamp_to_datetime},
+        SafStorage,
+        StoredMessagesReceived,
+    },
 };
+use chrono::Duration as ChronoDuration;
 use digest::Digest;
-use futures::{future, stream, Future, StreamExt};
+use futures::{channel::mpsc, future, stream, Future, SinkExt, StreamExt};
 use log::*;
 use prost::Message;
 use std::{convert::TryInto, sync::Arc};
 use tari_comms::{
-    message::EnvelopeBody,
+    message::{EnvelopeBody, MessageTag},
     peer_manager::{NodeIdentity, Peer, PeerManager, PeerManagerError},
     pipeline::PipelineError,
     types::Challenge,
@@ -59,6 +65,7 @@ pub struct MessageHandlerTask<S> {
     node_identity: Arc<NodeIdentity>,
     message: Option<DecryptedDhtMessage>,
     store: Arc<SafStorage>,
+    saf_response_tx: mpsc::Sender<StoredMessagesReceived>,
 }
 
 impl<S> MessageHandlerTask<S>
@@ -73,6 +80,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         peer_manager: Arc<PeerManager>,
         outbound_service: OutboundMessageRequester,
         node_identity: Arc<NodeIdentity>,
+        saf_response_tx: mpsc::Sender<StoredMessagesReceived>,
         message: DecryptedDhtMessage,
     ) -> Self
     {
@@ -84,6 +92,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
             peer_manager,
             outbound_service,
             node_identity,
+            saf_response_tx,
             message: Some(message),
         }
     }
@@ -158,14 +167,13 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         }
 
         // Compile a set of stored messages for the requesting peer
-        let messages = self.store.with_lock(|mut store| {
+        let messages = self.store.with_messages(|store| {
             store
-                .iter()
                 // All messages within start_time (if specified)
-                .filter(|(_, msg)| {
+                .filter(|msg| {
                     retrieve_msgs.since.as_ref().map(|since| msg.stored_at.as_ref().map(|s| since.seconds <= s.seconds).unwrap_or( false)).unwrap_or( true)
                 })
-                .filter(|(_, msg)|{
+                .filter(|msg|{
                     if msg.dht_header.is_none() {
                         warn!(target: LOG_TARGET, "Message was stored without a header. This should never happen!");
                         return false;
@@ -184,12 +192,12 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
                     }
                 })
                 .take(self.config.saf_max_returned_messages)
-                .map(|(_, msg)| msg)
                 .cloned()
                 .collect::<Vec<_>>()
         });
 
-        let stored_messages: StoredMessagesResponse = messages.into();
+        let mut stored_messages: StoredMessagesResponse = messages.into();
+        stored_messages.responded_at = Some(datetime_to_timestamp(self.store.now()));
 
         trace!(
             target: LOG_TARGET,
@@ -201,6 +209,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
                 SendMessageParams::new()
                     .direct_public_key(message.source_peer.public_key.clone())
                     .with_dht_message_type(DhtMessageType::SafStoredMessages)
+                    .with_priority(DhtMessagePriority::Low)
                     .finish(),
                 stored_messages,
             )
@@ -219,7 +228,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         let msg = message
             .success()
             .expect("already checked that this message decrypted successfully");
-        let response = msg
+        let mut response = msg
             .decode_part::<StoredMessagesResponse>(0)?
             .ok_or_else(|| StoreAndForwardError::InvalidEnvelopeBody)?;
         let source_peer = Arc::new(message.source_peer);
@@ -230,13 +239,55 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
             response.messages().len()
         );
 
+        // The stored_at timestamps were set by the peer's clock. They are moved onto this node's clock using the skew
+        // observed from the time at which the peer sent the response.
+        let now = self.store.now();
+        let clock_skew = response
+            .responded_at
+            .as_ref()
+            .and_then(timestamp_to_datetime)
+            .map(|responded_at| responded_at - now);
+        if let Some(skew) = clock_skew {
+            self.record_clock_skew(&source_peer, skew).await;
+        }
+        let clock_skew = clock_skew.unwrap_or_else(ChronoDuration::zero);
+        let local_stored_at = |msg: &StoredMessage| {
+            msg.stored_at
+                .as_ref()
+                .and_then(timestamp_to_datetime)
+                .and_then(|stored_at| stored_at.checked_sub_signed(clock_skew))
+        };
+
+        // Messages that have outlived the longest storage period (plus the tolerated skew) should already have been
+        // expired by the peer
+        let oldest_unexpired =
+            ChronoDuration::from_std(self.config.saf_high_priority_msg_storage_ttl + self.config.saf_max_clock_skew)
+                .ok()
+                .and_then(|max_age| now.checked_sub_signed(max_age));
+        if let Some(oldest_unexpired) = oldest_unexpired {
+            let num_received = response.messages.len();
+            response
+                .messages
+                .retain(|msg| local_stored_at(msg).map(|t| t >= oldest_unexpired).unwrap_or(true));
+            if response.messages.len() < num_received {
+                debug!(
+                    target: LOG_TARGET,
+                    "Discarded {} expired stored message(s) from peer '{}'",
+                    num_received - response.messages.len(),
+                    source_peer.node_id.short_str()
+                );
+            }
+        }
+
+        let latest_stored_at = response.messages.iter().filter_map(local_stored_at).max();
+
         let tasks = response
             .messages
             .into_iter()
             // Map to futures which process the stored message
             .map(|msg| self.process_incoming_stored_message(Arc::clone(&source_peer), msg));
 
-        let successful_msgs_iter = future::join_all(tasks)
+        let successful_msgs = future::join_all(tasks)
             .await
             .into_iter()
             .map(|result| {
@@ -290,10 +341,23 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
                 result
             })
             .filter(Result::is_ok)
-            .map(Result::unwrap);
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        // Let the retrieval service know so that it can advance its cursor
+        let notification = StoredMessagesReceived {
+            num_new_messages: successful_msgs.len(),
+            latest_stored_at,
+        };
+        if let Err(err) = self.saf_response_tx.send(notification).await {
+            debug!(
+                target: LOG_TARGET,
+                "Unable to notify SAF retrieval service of received stored messages because '{}'", err
+            );
+        }
 
         self.next_service
-            .call_all(stream::iter(successful_msgs_iter))
+            .call_all(stream::iter(successful_msgs))
             .unordered()
             .for_each(|service_result| {
                 if let Err(err) = service_result {
@@ -306,6 +370,32 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         Ok(())
     }
 
+    async fn record_clock_skew(&self, source_peer: &Peer, skew: ChronoDuration) {
+        let tolerance =
+            ChronoDuration::from_std(self.config.saf_max_clock_skew).unwrap_or_else(|_| ChronoDuration::max_value());
+        if skew > tolerance || skew < -tolerance {
+            warn!(
+                target: LOG_TARGET,
+                "Clock of peer '{}' differs from this node's clock by {}s, which is more than the tolerated {}s",
+                source_peer.node_id.short_str(),
+                skew.num_seconds(),
+                tolerance.num_seconds()
+            );
+        }
+        if let Err(err) = self
+            .peer_manager
+            .set_observed_clock_skew(&source_peer.node_id, skew)
+            .await
+        {
+            debug!(
+                target: LOG_TARGET,
+                "Unable to record clock skew of peer '{}' because '{}'",
+                source_peer.node_id.short_str(),
+                err
+            );
+        }
+    }
+
     fn process_incoming_stored_message(
         &self,
         source_peer: Arc<Peer>,
@@ -355,7 +445,12 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
             let decrypted_body =
                 Self::maybe_decrypt_and_deserialize(&node_identity, origin, dht_flags, &message.encrypted_body)?;
 
-            let inbound_msg = DhtInboundMessage::new(dht_header, Arc::clone(&source_peer), message.encrypted_body);
+            let inbound_msg = DhtInboundMessage::new(
+                MessageTag::new(),
+                dht_header,
+                Arc::clone(&source_peer),
+                message.encrypted_body,
+            );
 
             Ok(DecryptedDhtMessage::succeeded(decrypted_body, inbound_msg))
         }
@@ -494,6 +589,7 @@ mod test {
 
         let (tx, _) = mpsc::channel(1);
         let dht_requester = DhtRequester::new(tx);
+        let (saf_response_tx, _) = mpsc::channel(1);
 
         let task = MessageHandlerTask::new(
             Default::default(),
@@ -503,6 +599,7 @@ mod test {
             peer_manager,
             OutboundMessageRequester::new(oms_tx),
             node_identity,
+            saf_response_tx,
             message,
         );
 
@@ -572,6 +669,7 @@ mod test {
         let mut message = DecryptedDhtMessage::succeeded(
             wrap_in_envelope_body!(StoredMessagesResponse {
                 messages: vec![msg1.clone(), msg2, msg_clear],
+                responded_at: None,
             })
             .unwrap(),
             make_dht_inbound_message(&node_identity, vec![], DhtMessageFlags::ENCRYPTED),
@@ -582,6 +680,7 @@ mod test {
         let mock_state = DhtMockState::new();
         mock.set_shared_state(mock_state.clone());
         rt_handle.spawn(mock.run());
+        let (saf_response_tx, mut saf_response_rx) = mpsc::channel(1);
 
         let task = MessageHandlerTask::new(
             Default::default(),
@@ -591,6 +690,7 @@ mod test {
             peer_manager,
             OutboundMessageRequester::new(oms_tx),
             node_identity,
+            saf_response_tx,
             message,
         );
 
@@ -607,5 +707,88 @@ mod test {
         assert!(msgs.contains(&b"B".to_vec()));
         assert!(msgs.contains(&b"Clear".to_vec()));
         assert_eq!(mock_state.call_count(), msgs.len());
+
+        let notification = saf_response_rx.next().await.unwrap();
+        assert_eq!(notification.num_new_messages, 3);
+        assert!(notification.latest_stored_at.is_some());
+    }
+
+    #[tokio_macros::test_basic]
+    async fn receive_stored_messages_from_skewed_peer() {
+        let rt_handle = Handle::current();
+        let spy = service_spy();
+        let storage = Arc::new(SafStorage::new(10));
+        let peer_manager = make_peer_manager();
+        let (oms_tx, _) = mpsc::channel(1);
+        let node_identity = make_node_identity();
+
+        // The responding peer's clock is an hour ahead of this node's clock
+        let now = Utc::now();
+        let skew = chrono::Duration::hours(1);
+        let make_stored_msg = |body: &[u8], age: chrono::Duration| {
+            let body = wrap_in_envelope_body!(body.to_vec())
+                .unwrap()
+                .to_encoded_bytes()
+                .unwrap();
+            let header = make_dht_inbound_message(&node_identity, body.clone(), DhtMessageFlags::empty()).dht_header;
+            let mut msg = StoredMessage::new(0, header, body);
+            msg.stored_at = Some(datetime_to_timestamp(now + skew - age));
+            msg
+        };
+        let recent_msg = make_stored_msg(b"Recent", chrono::Duration::seconds(10));
+        let expired_msg = make_stored_msg(b"Expired", chrono::Duration::days(2));
+
+        let mut message = DecryptedDhtMessage::succeeded(
+            wrap_in_envelope_body!(StoredMessagesResponse {
+                messages: vec![recent_msg, expired_msg],
+                responded_at: Some(datetime_to_timestamp(now + skew)),
+            })
+            .unwrap(),
+            make_dht_inbound_message(&node_identity, vec![], DhtMessageFlags::ENCRYPTED),
+        );
+        message.dht_header.message_type = DhtMessageType::SafStoredMessages;
+        let source_node_id = message.source_peer.node_id.clone();
+        peer_manager
+            .add_peer(Clone::clone(&*message.source_peer))
+            .await
+            .unwrap();
+
+        let (dht_requester, mut mock) = create_dht_actor_mock(1);
+        mock.set_shared_state(DhtMockState::new());
+        rt_handle.spawn(mock.run());
+        let (saf_response_tx, mut saf_response_rx) = mpsc::channel(1);
+
+        let task = MessageHandlerTask::new(
+            Default::default(),
+            spy.to_service::<PipelineError>(),
+            storage,
+            dht_requester,
+            Arc::clone(&peer_manager),
+            OutboundMessageRequester::new(oms_tx),
+            Arc::clone(&node_identity),
+            saf_response_tx,
+            message,
+        );
+
+        task.run().await.unwrap();
+        let requests = spy.take_requests();
+        assert_eq!(requests.len(), 1);
+        let body = requests[0]
+            .success()
+            .unwrap()
+            .decode_part::<Vec<u8>>(0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(body, b"Recent".to_vec());
+
+        // The cursor is kept in this node's time
+        let notification = saf_response_rx.next().await.unwrap();
+        let latest_stored_at = notification.latest_stored_at.unwrap();
+        let drift = latest_stored_at - (now - chrono::Duration::seconds(10));
+        assert!(drift.num_seconds().abs() <= 5);
+
+        let peer = peer_manager.find_by_node_id(&source_node_id).await.unwrap();
+        let observed_skew = peer.connection_stats.observed_clock_skew_secs.unwrap();
+        assert!((observed_skew - skew.num_seconds()).abs() <= 5);
     }
 }
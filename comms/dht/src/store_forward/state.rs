@@ -20,37 +20,113 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::proto::store_forward::StoredMessage;
-use std::{
-    sync::{RwLock, RwLockWriteGuard},
-    time::Duration,
-};
+use crate::{proto::store_forward::StoredMessage, store_forward::message::datetime_to_timestamp};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::{sync::RwLock, time::Duration};
+use tari_comms::utils::clock::{ClockRef, MonotonicClock};
 use ttl_cache::TtlCache;
 
 pub type SignatureBytes = Vec<u8>;
 
+struct SafEntry {
+    message: StoredMessage,
+    expires_at: DateTime<Utc>,
+}
+
 pub struct SafStorage {
-    message_cache: RwLock<TtlCache<SignatureBytes, StoredMessage>>,
+    message_cache: RwLock<TtlCache<SignatureBytes, SafEntry>>,
+    clock: ClockRef,
 }
 
 impl SafStorage {
     pub fn new(cache_capacity: usize) -> Self {
+        Self::with_clock(cache_capacity, MonotonicClock::shared())
+    }
+
+    /// Create a new SafStorage which uses the given clock to timestamp and expire messages
+    pub fn with_clock(cache_capacity: usize, clock: ClockRef) -> Self {
         Self {
             message_cache: RwLock::new(TtlCache::new(cache_capacity)),
+            clock,
         }
     }
 
-    pub fn insert(&self, key: SignatureBytes, message: StoredMessage, ttl: Duration) -> Option<StoredMessage> {
-        acquire_write_lock!(self.message_cache).insert(key, message, ttl)
+    pub fn insert(&self, key: SignatureBytes, mut message: StoredMessage, ttl: Duration) -> Option<StoredMessage> {
+        let now = self.clock.now();
+        message.stored_at = Some(datetime_to_timestamp(now));
+        let expires_at = ChronoDuration::from_std(ttl)
+            .ok()
+            .and_then(|ttl| now.checked_add_signed(ttl))
+            .unwrap_or_else(|| chrono::MAX_DATE.and_hms(0, 0, 0));
+        let entry = SafEntry { message, expires_at };
+        acquire_write_lock!(self.message_cache)
+            .insert(key, entry, ttl)
+            .map(|entry| entry.message)
+    }
+
+    /// Calls the given function with an iterator over all messages which have not yet expired according to the
+    /// storage clock.
+    pub fn with_messages<F, T>(&self, f: F) -> T
+    where F: FnOnce(&mut dyn Iterator<Item = &StoredMessage>) -> T {
+        let now = self.clock.now();
+        let mut lock = acquire_write_lock!(self.message_cache);
+        let mut iter = lock
+            .iter()
+            .filter(|(_, entry)| entry.expires_at > now)
+            .map(|(_, entry)| &entry.message);
+        f(&mut iter)
+    }
+
+    /// Returns the current time according to the storage clock
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
     }
 
-    pub fn with_lock<F, T>(&self, f: F) -> T
-    where F: FnOnce(RwLockWriteGuard<TtlCache<SignatureBytes, StoredMessage>>) -> T {
-        f(acquire_write_lock!(self.message_cache))
+    /// Returns the number of messages which have not yet expired
+    pub fn len(&self) -> usize {
+        self.with_messages(|iter| iter.count())
+    }
+
+    /// Returns true if there are no unexpired messages in storage
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     #[cfg(test)]
     pub fn remove(&self, key: &SignatureBytes) -> Option<StoredMessage> {
-        acquire_write_lock!(self.message_cache).remove(key)
+        acquire_write_lock!(self.message_cache)
+            .remove(key)
+            .map(|entry| entry.message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        envelope::DhtMessageFlags,
+        test_utils::{make_dht_inbound_message, make_node_identity},
+    };
+    use std::sync::Arc;
+    use tari_comms::utils::clock::ManualClock;
+
+    #[test]
+    fn messages_expire_by_clock() {
+        let clock = ManualClock::starting_now();
+        let storage = SafStorage::with_clock(10, Arc::new(clock.clone()));
+        let node_identity = make_node_identity();
+        let inbound_msg = make_dht_inbound_message(&node_identity, vec![], DhtMessageFlags::empty());
+        storage.insert(
+            vec![0],
+            StoredMessage::new(0, inbound_msg.dht_header, vec![]),
+            Duration::from_secs(60 * 60),
+        );
+        assert_eq!(storage.len(), 1);
+
+        clock.advance(Duration::from_secs(60 * 60 - 1));
+        assert_eq!(storage.len(), 1);
+
+        clock.advance(Duration::from_secs(1));
+        assert!(storage.is_empty());
     }
 }
@@ -23,6 +23,7 @@
 use crate::{
     envelope::{DhtMessageFlags, NodeDestination},
     inbound::DecryptedDhtMessage,
+    metrics,
     proto::store_forward::StoredMessage,
     store_forward::{error::StoreAndForwardError, state::SafStorage},
     DhtConfig,
@@ -120,14 +121,15 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError> + Cl
     }
 
     fn call(&mut self, msg: DecryptedDhtMessage) -> Self::Future {
-        StoreTask::new(
+        let tag = msg.tag;
+        let task = StoreTask::new(
             self.next_service.clone(),
             self.config.clone(),
             Arc::clone(&self.peer_manager),
             Arc::clone(&self.node_identity),
             Arc::clone(&self.storage),
-        )
-        .handle(msg)
+        );
+        metrics::instrument("saf_store", tag, task.handle(msg))
     }
 }
 
@@ -184,6 +186,14 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
                 self.next_service.oneshot(message).await?;
             },
             None => {
+                if message.dht_header.flags.contains(DhtMessageFlags::ANONYMOUS) {
+                    // Anonymous messages have no origin signature with which to identify the stored message
+                    debug!(
+                        target: LOG_TARGET,
+                        "Decryption failed for anonymous message. Anonymous messages are not stored."
+                    );
+                    return Ok(());
+                }
                 if message.dht_header.origin.is_none() {
                     // TODO: #banheuristic
                     warn!(
@@ -299,9 +309,7 @@ mod test {
         let msg = DecryptedDhtMessage::succeeded(wrap_in_envelope_body!(Vec::new()).unwrap(), inbound_msg);
         service.call(msg).await.unwrap();
         assert!(spy.is_called());
-        storage.with_lock(|mut lock| {
-            assert_eq!(lock.iter().count(), 0);
-        });
+        assert_eq!(storage.len(), 0);
     }
 
     #[tokio_macros::test_basic]
@@ -318,9 +326,7 @@ mod test {
         let msg = DecryptedDhtMessage::succeeded(wrap_in_envelope_body!(Vec::new()).unwrap(), inbound_msg);
         service.call(msg).await.unwrap();
         assert!(spy.is_called());
-        storage.with_lock(|mut lock| {
-            assert_eq!(lock.iter().count(), 1);
-        });
+        assert_eq!(storage.len(), 1);
     }
 
     #[tokio_macros::test_basic]
@@ -337,9 +343,7 @@ mod test {
         let msg = DecryptedDhtMessage::succeeded(wrap_in_envelope_body!(b"secret".to_vec()).unwrap(), inbound_msg);
         service.call(msg).await.unwrap();
         assert!(spy.is_called());
-        storage.with_lock(|mut lock| {
-            assert_eq!(lock.iter().count(), 0);
-        });
+        assert_eq!(storage.len(), 0);
     }
 
     #[tokio_macros::test_basic]
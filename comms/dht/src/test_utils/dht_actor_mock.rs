@@ -106,6 +106,9 @@ impl DhtActorMock {
                 reply_tx.send(lock.clone()).unwrap();
             },
             SendRequestStoredMessages(_) => {},
+            GetRoutingTableStatus(reply_tx) => {
+                reply_tx.send(Default::default()).unwrap();
+            },
         }
     }
 }
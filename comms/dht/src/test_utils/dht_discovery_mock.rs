@@ -34,6 +34,7 @@ use std::{
     },
     time::Duration,
 };
+use tari_common::retry::RetryPolicy;
 use tari_comms::peer_manager::Peer;
 
 const LOG_TARGET: &str = "comms::dht::discovery_mock";
@@ -41,7 +42,7 @@ const LOG_TARGET: &str = "comms::dht::discovery_mock";
 pub fn create_dht_discovery_mock(buf_size: usize, timeout: Duration) -> (DhtDiscoveryRequester, DhtDiscoveryMock) {
     let (tx, rx) = mpsc::channel(buf_size);
     (
-        DhtDiscoveryRequester::new(tx, timeout),
+        DhtDiscoveryRequester::new(tx, timeout, RetryPolicy::no_retry()),
         DhtDiscoveryMock::new(rx.fuse()),
     )
 }
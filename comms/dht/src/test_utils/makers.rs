@@ -23,11 +23,12 @@ use crate::{
     envelope::{DhtMessageFlags, DhtMessageHeader, DhtMessageOrigin, NodeDestination},
     inbound::DhtInboundMessage,
     proto::envelope::{DhtEnvelope, DhtMessageType, Network},
+    reply_keys::ReplyKeyStore,
 };
 use rand::rngs::OsRng;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tari_comms::{
-    message::{InboundMessage, MessageEnvelopeHeader, MessageFlags},
+    message::{InboundMessage, MessageEnvelopeHeader, MessageFlags, MessageTag},
     multiaddr::Multiaddr,
     peer_manager::{NodeIdentity, Peer, PeerFeatures, PeerFlags, PeerManager},
     types::CommsDatabase,
@@ -49,6 +50,10 @@ pub fn make_node_identity() -> Arc<NodeIdentity> {
     )
 }
 
+pub fn make_reply_key_store() -> Arc<ReplyKeyStore> {
+    Arc::new(ReplyKeyStore::new(10, Duration::from_secs(60)))
+}
+
 pub fn make_peer() -> Peer {
     let node_identity = make_node_identity();
     Peer::new(
@@ -105,6 +110,8 @@ pub fn make_dht_header(node_identity: &NodeIdentity, message: &Vec<u8>, flags: D
         message_type: DhtMessageType::None,
         network: Network::LocalTest,
         flags,
+        ephemeral_public_key: None,
+        wrapped_keys: Vec::new(),
     }
 }
 
@@ -115,6 +122,7 @@ pub fn make_dht_inbound_message(
 ) -> DhtInboundMessage
 {
     DhtInboundMessage::new(
+        MessageTag::new(),
         make_dht_header(node_identity, &body, flags),
         Arc::new(Peer::new(
             node_identity.public_key().clone(),
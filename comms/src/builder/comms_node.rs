@@ -25,6 +25,7 @@ use crate::{
     backoff::BoxedBackoff,
     bounded_executor::BoundedExecutor,
     connection_manager::{ConnectionManager, ConnectionManagerEvent, ConnectionManagerRequester},
+    connectivity::{ConnectivityManager, ConnectivityRequester},
     message::InboundMessage,
     multiaddr::Multiaddr,
     peer_manager::{NodeIdentity, PeerManager},
@@ -53,6 +54,8 @@ pub struct BuiltCommsNode<
     pub connection_manager: ConnectionManager<TTransport, BoxedBackoff>,
     pub connection_manager_requester: ConnectionManagerRequester,
     pub connection_manager_event_tx: broadcast::Sender<Arc<ConnectionManagerEvent>>,
+    pub connectivity_manager: ConnectivityManager,
+    pub connectivity_requester: ConnectivityRequester,
     pub messaging_pipeline: Option<pipeline::Config<TInPipe, TOutPipe, TOutReq>>,
     pub node_identity: Arc<NodeIdentity>,
     pub messaging: MessagingProtocol,
@@ -94,6 +97,8 @@ where
             connection_manager: self.connection_manager,
             connection_manager_requester: self.connection_manager_requester,
             connection_manager_event_tx: self.connection_manager_event_tx,
+            connectivity_manager: self.connectivity_manager,
+            connectivity_requester: self.connectivity_requester,
             node_identity: self.node_identity,
             messaging: self.messaging,
             messaging_event_tx: self.messaging_event_tx,
@@ -129,6 +134,8 @@ where
             connection_manager,
             connection_manager_requester,
             connection_manager_event_tx,
+            connectivity_manager,
+            connectivity_requester,
             messaging_pipeline,
             messaging_request_tx,
             inbound_message_rx,
@@ -163,6 +170,7 @@ where
 
         let executor = runtime::current_executor();
         executor.spawn(connection_manager.run());
+        executor.spawn(connectivity_manager.run());
 
         // Spawn messaging protocol
         let messaging_signal = messaging.complete_signal();
@@ -183,6 +191,7 @@ where
             shutdown,
             connection_manager_event_tx,
             connection_manager_requester,
+            connectivity_requester,
             listening_addr,
             node_identity,
             peer_manager,
@@ -212,6 +221,12 @@ where
         self.connection_manager_requester.clone()
     }
 
+    /// Return an owned copy of a ConnectivityRequester. Used to query peer connectivity and subscribe to
+    /// connectivity events.
+    pub fn connectivity(&self) -> ConnectivityRequester {
+        self.connectivity_requester.clone()
+    }
+
     /// Returns a new `ShutdownSignal`
     pub fn shutdown_signal(&self) -> ShutdownSignal {
         self.shutdown.to_signal()
@@ -230,6 +245,8 @@ pub struct CommsNode {
     connection_manager_event_tx: broadcast::Sender<Arc<ConnectionManagerEvent>>,
     /// Requester object for the ConnectionManager
     connection_manager_requester: ConnectionManagerRequester,
+    /// Requester object for the ConnectivityManager
+    connectivity_requester: ConnectivityRequester,
     /// Node identity for this node
     node_identity: Arc<NodeIdentity>,
     /// Shared PeerManager instance
@@ -280,6 +297,12 @@ impl CommsNode {
         self.connection_manager_requester.clone()
     }
 
+    /// Return an owned copy of a ConnectivityRequester. Used to query peer connectivity and subscribe to
+    /// connectivity events.
+    pub fn connectivity(&self) -> ConnectivityRequester {
+        self.connectivity_requester.clone()
+    }
+
     /// Returns a new `ShutdownSignal`
     pub fn shutdown_signal(&self) -> ShutdownSignal {
         self.shutdown.to_signal()
@@ -29,6 +29,11 @@ pub const CONNECTION_MANAGER_REQUEST_BUFFER_SIZE: usize = 10;
 /// Connection manager events buffer size. The size should allow more than enough "time" for slow subscribers to read
 /// the events while not being wasteful.
 pub const CONNECTION_MANAGER_EVENTS_BUFFER_SIZE: usize = 30;
+/// Buffer size for actor requests to the connectivity manager.
+pub const CONNECTIVITY_MANAGER_REQUEST_BUFFER_SIZE: usize = 10;
+/// Connectivity events buffer size. Connectivity events are published at most a few times per peer connection, so a
+/// small buffer is sufficient.
+pub const CONNECTIVITY_MANAGER_EVENTS_BUFFER_SIZE: usize = 30;
 /// Buffer size notifications that a peer wants to speak /tari/messaging. This buffer is used for all peers, but a low
 /// value is ok because this events happen once (or less) per connecting peer. For e.g. a value of 10 would allow 10
 /// peers to concurrently request to speak /tari/messaging.
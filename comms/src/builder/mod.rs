@@ -49,7 +49,9 @@ use crate::{
         ConnectionManagerEvent,
         ConnectionManagerRequest,
         ConnectionManagerRequester,
+        PeerAllowList,
     },
+    connectivity::{ConnectivityManager, ConnectivityRequester},
     message::InboundMessage,
     multiaddr::Multiaddr,
     noise::NoiseConfig,
@@ -61,7 +63,7 @@ use crate::{
 };
 use futures::{channel::mpsc, AsyncRead, AsyncWrite};
 use log::*;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tari_shutdown::Shutdown;
 use tokio::{runtime, sync::broadcast};
 
@@ -162,12 +164,32 @@ where
         self
     }
 
+    /// Only dial and accept connections from peers in the given allow list, e.g. for a private network.
+    pub fn with_peer_allow_list(mut self, allow_list: PeerAllowList) -> Self {
+        self.connection_manager_config.peer_allow_list = Some(allow_list);
+        self
+    }
+
     /// The number of dial attempts to make before giving up.
     pub fn with_max_dial_attempts(mut self, max_dial_attempts: usize) -> Self {
         self.connection_manager_config.max_dial_attempts = max_dial_attempts;
         self
     }
 
+    /// The maximum number of outbound dials that are in progress at the same time. Further dials are queued by
+    /// priority.
+    pub fn with_max_concurrent_dials(mut self, max_concurrent_dials: usize) -> Self {
+        self.connection_manager_config.max_concurrent_dials = max_concurrent_dials;
+        self
+    }
+
+    /// The period of time for which an address that failed to connect is not dialed again, unless the dial is high
+    /// priority.
+    pub fn with_dial_address_cooldown(mut self, cooldown: Duration) -> Self {
+        self.connection_manager_config.dial_address_cooldown = cooldown;
+        self
+    }
+
     /// Set the peer storage database to use.
     pub fn with_peer_storage(mut self, peer_storage: CommsDatabase) -> Self {
         self.peer_storage = Some(peer_storage);
@@ -283,8 +305,11 @@ where
     ) -> ConnectionManager<TTransport, BoxedBackoff>
     {
         let backoff = self.dial_backoff.take().expect("always set");
-        let noise_config = NoiseConfig::new(Arc::clone(&node_identity));
         let config = self.connection_manager_config.clone();
+        let mut noise_config = NoiseConfig::new(Arc::clone(&node_identity));
+        if let Some(allow_list) = config.peer_allow_list.clone() {
+            noise_config = noise_config.with_allow_list(allow_list);
+        }
 
         ConnectionManager::new(
             config,
@@ -338,10 +363,24 @@ where
             connection_manager_event_tx.clone(),
         );
 
+        //---------------------------------- ConnectivityManager --------------------------------------------//
+        let (connectivity_tx, connectivity_rx) = mpsc::channel(consts::CONNECTIVITY_MANAGER_REQUEST_BUFFER_SIZE);
+        let (connectivity_event_tx, _) = broadcast::channel(consts::CONNECTIVITY_MANAGER_EVENTS_BUFFER_SIZE);
+        let connectivity_requester = ConnectivityRequester::new(connectivity_tx, connectivity_event_tx.clone());
+        let connectivity_manager = ConnectivityManager::new(
+            connectivity_rx,
+            connection_manager_requester.clone(),
+            peer_manager.clone(),
+            connectivity_event_tx,
+            self.shutdown.to_signal(),
+        );
+
         Ok(BuiltCommsNode {
             connection_manager,
             connection_manager_requester,
             connection_manager_event_tx,
+            connectivity_manager,
+            connectivity_requester,
             messaging_request_tx,
             messaging_pipeline: None,
             messaging,
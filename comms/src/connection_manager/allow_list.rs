@@ -0,0 +1,194 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! An allow list of peer public keys for private networks. When the allow list is enabled, the node only accepts
+//! connections from, and only dials, peers whose public keys are in the list. Connections from other peers are dropped
+//! as soon as the noise handshake has authenticated their public key.
+//!
+//! Public keys can be given directly and/or read from a file containing one hex public key per line. Blank lines and
+//! lines starting with `#` are ignored. The file is reloaded whenever its modification time changes, so peers can be
+//! added and removed without restarting the node.
+
+use crate::types::CommsPublicKey;
+use log::*;
+use std::{
+    collections::HashSet,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::SystemTime,
+};
+use tari_crypto::tari_utilities::hex::Hex;
+
+const LOG_TARGET: &str = "comms::connection_manager::allow_list";
+
+#[derive(Debug, Clone, Default)]
+pub struct PeerAllowList {
+    inner: Arc<RwLock<AllowListInner>>,
+}
+
+#[derive(Debug, Default)]
+struct AllowListInner {
+    public_keys: HashSet<CommsPublicKey>,
+    file: Option<AllowListFile>,
+}
+
+#[derive(Debug)]
+struct AllowListFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    public_keys: HashSet<CommsPublicKey>,
+}
+
+impl PeerAllowList {
+    /// Create an allow list containing the given public keys
+    pub fn new<I: IntoIterator<Item = CommsPublicKey>>(public_keys: I) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(AllowListInner {
+                public_keys: public_keys.into_iter().collect(),
+                file: None,
+            })),
+        }
+    }
+
+    /// Also allow the public keys listed in the given file. The file is read immediately and reloaded whenever it
+    /// changes.
+    pub fn with_file<P: Into<PathBuf>>(self, path: P) -> Result<Self, io::Error> {
+        let path = path.into();
+        let (public_keys, modified) = read_allow_list_file(&path)?;
+        {
+            let mut inner = acquire_lock!(self.inner, write);
+            inner.file = Some(AllowListFile {
+                path,
+                modified,
+                public_keys,
+            });
+        }
+        Ok(self)
+    }
+
+    /// Returns true if the peer with the given public key is allowed to connect
+    pub fn is_allowed(&self, public_key: &CommsPublicKey) -> bool {
+        self.reload_if_modified();
+        let inner = acquire_read_lock!(self.inner);
+        inner.public_keys.contains(public_key) ||
+            inner
+                .file
+                .as_ref()
+                .map(|f| f.public_keys.contains(public_key))
+                .unwrap_or(false)
+    }
+
+    /// Re-read the allow list file, if any. If the file cannot be read the previously loaded public keys are kept.
+    pub fn reload(&self) -> Result<(), io::Error> {
+        let mut inner = acquire_lock!(self.inner, write);
+        if let Some(file) = inner.file.as_mut() {
+            let (public_keys, modified) = read_allow_list_file(&file.path)?;
+            debug!(
+                target: LOG_TARGET,
+                "Loaded {} public key(s) from allow list file '{}'",
+                public_keys.len(),
+                file.path.display()
+            );
+            file.public_keys = public_keys;
+            file.modified = modified;
+        }
+        Ok(())
+    }
+
+    fn reload_if_modified(&self) {
+        let is_modified = {
+            let inner = acquire_read_lock!(self.inner);
+            match inner.file.as_ref() {
+                Some(file) => fs::metadata(&file.path).and_then(|m| m.modified()).ok() != file.modified,
+                None => false,
+            }
+        };
+        if is_modified {
+            if let Err(err) = self.reload() {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to reload the allow list file. The previous allow list will be used. {}", err
+                );
+            }
+        }
+    }
+}
+
+fn read_allow_list_file(path: &Path) -> Result<(HashSet<CommsPublicKey>, Option<SystemTime>), io::Error> {
+    let modified = fs::metadata(path)?.modified().ok();
+    let contents = fs::read_to_string(path)?;
+    let public_keys = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match CommsPublicKey::from_hex(line) {
+            Ok(pk) => Some(pk),
+            Err(err) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Ignoring invalid public key '{}' in allow list file '{}': {:?}",
+                    line,
+                    path.display(),
+                    err
+                );
+                None
+            },
+        })
+        .collect();
+    Ok((public_keys, modified))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+    use tari_crypto::keys::PublicKey;
+    use tempdir::TempDir;
+
+    #[test]
+    fn allow_list() {
+        let (_, pk1) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (_, pk2) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (_, pk3) = CommsPublicKey::random_keypair(&mut OsRng);
+
+        let dir = TempDir::new("allow_list").unwrap();
+        let path = dir.path().join("allow_list.txt");
+        fs::write(&path, format!("# Consortium members\n\n{}\nnot-a-key\n", pk2.to_hex())).unwrap();
+
+        let allow_list = PeerAllowList::new(vec![pk1.clone()]).with_file(&path).unwrap();
+        assert!(allow_list.is_allowed(&pk1));
+        assert!(allow_list.is_allowed(&pk2));
+        assert!(!allow_list.is_allowed(&pk3));
+
+        fs::write(&path, pk3.to_hex()).unwrap();
+        allow_list.reload().unwrap();
+        assert!(allow_list.is_allowed(&pk1));
+        assert!(!allow_list.is_allowed(&pk2));
+        assert!(allow_list.is_allowed(&pk3));
+
+        // The previous keys are kept if the file goes missing
+        fs::remove_file(&path).unwrap();
+        assert!(allow_list.is_allowed(&pk3));
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    connection_manager::{error::ConnectionManagerError, peer_connection::PeerConnection, types::DialPriority},
+    peer_manager::{NodeId, Peer},
+};
+use futures::channel::oneshot;
+use std::{cmp::Ordering, collections::BinaryHeap, mem};
+
+type DialReply = oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>;
+
+/// Dials that are waiting for a free dial slot, ordered by priority and then by arrival order
+#[derive(Default)]
+pub struct DialQueue {
+    heap: BinaryHeap<QueuedDial>,
+    next_seq: u64,
+}
+
+impl DialQueue {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, peer: Box<Peer>, priority: DialPriority, reply_tx: DialReply) {
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        self.heap.push(QueuedDial {
+            seq,
+            priority,
+            peer,
+            reply_tx,
+        });
+    }
+
+    /// Remove and return the highest priority dial
+    pub fn pop(&mut self) -> Option<(Box<Peer>, DialPriority, DialReply)> {
+        self.heap.pop().map(|d| (d.peer, d.priority, d.reply_tx))
+    }
+
+    pub fn contains(&self, node_id: &NodeId) -> bool {
+        self.heap.iter().any(|d| d.peer.node_id == *node_id)
+    }
+
+    /// Raise the priority of the queued dial for the given peer if the given priority is higher
+    pub fn raise_priority(&mut self, node_id: &NodeId, priority: DialPriority) {
+        let needs_update = self
+            .heap
+            .iter()
+            .any(|d| d.peer.node_id == *node_id && d.priority < priority);
+        if needs_update {
+            let mut dials = mem::take(&mut self.heap).into_vec();
+            dials
+                .iter_mut()
+                .filter(|d| d.peer.node_id == *node_id)
+                .for_each(|d| d.priority = priority);
+            self.heap = dials.into();
+        }
+    }
+
+    /// Remove the queued dial for the given peer, returning its reply channel
+    pub fn remove(&mut self, node_id: &NodeId) -> Option<DialReply> {
+        if !self.contains(node_id) {
+            return None;
+        }
+        let (removed, remaining) = mem::take(&mut self.heap)
+            .into_vec()
+            .into_iter()
+            .partition::<Vec<_>, _>(|d| d.peer.node_id == *node_id);
+        self.heap = remaining.into();
+        removed.into_iter().next().map(|d| d.reply_tx)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Remove all queued dials, returning their reply channels
+    pub fn drain(&mut self) -> impl Iterator<Item = DialReply> {
+        mem::take(&mut self.heap).into_iter().map(|d| d.reply_tx)
+    }
+}
+
+struct QueuedDial {
+    seq: u64,
+    priority: DialPriority,
+    peer: Box<Peer>,
+    reply_tx: DialReply,
+}
+
+impl PartialEq for QueuedDial {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for QueuedDial {}
+
+impl PartialOrd for QueuedDial {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedDial {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap: higher priority first, then the lowest sequence number (oldest) first
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{peer_manager::PeerFeatures, test_utils::node_identity::build_node_identity};
+
+    fn make_peer() -> Box<Peer> {
+        let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+        Box::new(node_identity.to_peer())
+    }
+
+    fn push(queue: &mut DialQueue, priority: DialPriority) -> NodeId {
+        let peer = make_peer();
+        let node_id = peer.node_id.clone();
+        let (reply_tx, _) = oneshot::channel();
+        queue.push(peer, priority, reply_tx);
+        node_id
+    }
+
+    #[test]
+    fn queue_ordering() {
+        let mut queue = DialQueue::new();
+        let low = push(&mut queue, DialPriority::Low);
+        let normal1 = push(&mut queue, DialPriority::Normal);
+        let high = push(&mut queue, DialPriority::High);
+        let normal2 = push(&mut queue, DialPriority::Normal);
+        assert_eq!(queue.len(), 4);
+
+        let order = (0..4).map(|_| queue.pop().unwrap().0.node_id).collect::<Vec<_>>();
+        assert_eq!(order, vec![high, normal1, normal2, low]);
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn raise_priority_and_remove() {
+        let mut queue = DialQueue::new();
+        let low = push(&mut queue, DialPriority::Low);
+        let normal = push(&mut queue, DialPriority::Normal);
+
+        queue.raise_priority(&low, DialPriority::High);
+        // Lowering the priority has no effect
+        queue.raise_priority(&normal, DialPriority::Low);
+
+        assert!(queue.remove(&normal).is_some());
+        assert!(!queue.contains(&normal));
+        assert!(queue.remove(&normal).is_none());
+
+        let (peer, priority, _) = queue.pop().unwrap();
+        assert_eq!(peer.node_id, low);
+        assert_eq!(priority, DialPriority::High);
+        assert!(queue.pop().is_none());
+    }
+}
@@ -22,6 +22,7 @@
 
 use crate::{
     connection_manager::{error::ConnectionManagerError, peer_connection::PeerConnection},
+    multiaddr::Multiaddr,
     peer_manager::Peer,
 };
 use futures::channel::oneshot;
@@ -37,6 +38,8 @@ pub struct DialState {
     cancel_signal: ShutdownSignal,
     /// Reply channel for a connection result
     pub reply_tx: oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>,
+    /// Addresses that failed to connect during this dial
+    failed_addresses: Vec<Multiaddr>,
 }
 
 impl DialState {
@@ -52,6 +55,7 @@ impl DialState {
             attempts: 0,
             reply_tx,
             cancel_signal,
+            failed_addresses: Vec::new(),
         }
     }
 
@@ -69,4 +73,17 @@ impl DialState {
     pub fn num_attempts(&self) -> usize {
         self.attempts
     }
+
+    /// Record an address that failed to connect
+    pub fn add_failed_address(&mut self, address: Multiaddr) -> &mut Self {
+        if !self.failed_addresses.contains(&address) {
+            self.failed_addresses.push(address);
+        }
+        self
+    }
+
+    /// Take the addresses that failed to connect during this dial
+    pub fn take_failed_addresses(&mut self) -> Vec<Multiaddr> {
+        std::mem::take(&mut self.failed_addresses)
+    }
 }
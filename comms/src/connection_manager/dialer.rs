@@ -20,11 +20,16 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::{error::ConnectionManagerError, peer_connection::PeerConnection, types::ConnectionDirection};
+use super::{
+    error::ConnectionManagerError,
+    peer_connection::PeerConnection,
+    types::{ConnectionDirection, DialPriority},
+};
 use crate::{
     backoff::Backoff,
     connection_manager::{
         common,
+        dial_queue::DialQueue,
         dial_state::DialState,
         manager::{ConnectionManagerConfig, ConnectionManagerEvent},
         peer_connection,
@@ -52,7 +57,11 @@ use futures::{
     StreamExt,
 };
 use log::*;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
 use tari_crypto::tari_utilities::hex::Hex;
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::time;
@@ -67,6 +76,7 @@ type DialFuturesUnordered =
 pub(crate) enum DialerRequest {
     Dial(
         Box<Peer>,
+        DialPriority,
         oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>,
     ),
     CancelPendingDial(NodeId),
@@ -85,6 +95,8 @@ pub struct Dialer<TTransport, TBackoff> {
     shutdown: Option<ShutdownSignal>,
     pending_dial_requests: HashMap<NodeId, Vec<oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>>>,
     supported_protocols: Vec<ProtocolId>,
+    dial_queue: DialQueue,
+    address_cooldowns: HashMap<Multiaddr, Instant>,
 }
 
 impl<TTransport, TBackoff> Dialer<TTransport, TBackoff>
@@ -120,6 +132,8 @@ where
             shutdown: Some(shutdown),
             pending_dial_requests: Default::default(),
             supported_protocols,
+            dial_queue: DialQueue::new(),
+            address_cooldowns: Default::default(),
         }
     }
 
@@ -135,6 +149,7 @@ where
                 request = self.request_rx.select_next_some() => self.handle_request(&mut pending_dials, request),
                 (dial_state, dial_result) = pending_dials.select_next_some() => {
                     self.handle_dial_result(dial_state, dial_result).await;
+                    self.start_queued_dials(&mut pending_dials);
                 }
                 _ = shutdown => {
                     info!(target: LOG_TARGET, "Connection dialer shutting down because the shutdown signal was received");
@@ -149,13 +164,17 @@ where
         use DialerRequest::*;
         trace!(target: LOG_TARGET, "Connection dialer got request: {:?}", request);
         match request {
-            Dial(peer, reply_tx) => {
-                self.handle_dial_peer_request(pending_dials, peer, reply_tx);
+            Dial(peer, priority, reply_tx) => {
+                self.handle_dial_peer_request(pending_dials, peer, priority, reply_tx);
             },
             CancelPendingDial(peer_id) => {
                 if let Some(mut s) = self.cancel_signals.remove(&peer_id) {
                     let _ = s.trigger();
                 }
+                if let Some(reply_tx) = self.dial_queue.remove(&peer_id) {
+                    let _ = reply_tx.send(Err(ConnectionManagerError::DialCancelled));
+                    self.reply_to_pending_requests(&peer_id, Err(ConnectionManagerError::DialCancelled));
+                }
             },
         }
     }
@@ -177,15 +196,58 @@ where
                 signal.trigger(),
                 "Shutdown trigger failed",
             );
-        })
+        });
+        self.dial_queue.drain().for_each(|reply_tx| {
+            let _ = reply_tx.send(Err(ConnectionManagerError::DialCancelled));
+        });
+    }
+
+    /// Start queued dials, highest priority first, until the concurrent dial limit is reached
+    fn start_queued_dials(&mut self, pending_dials: &mut DialFuturesUnordered) {
+        while self.cancel_signals.len() < self.config.max_concurrent_dials {
+            match self.dial_queue.pop() {
+                Some((peer, priority, reply_tx)) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Starting queued {} priority dial to peer '{}' ({} dial(s) still queued)",
+                        priority,
+                        peer.node_id.short_str(),
+                        self.dial_queue.len()
+                    );
+                    self.start_dial(pending_dials, peer, priority, reply_tx);
+                },
+                None => break,
+            }
+        }
+    }
+
+    /// Record the addresses that failed to connect so that they are not dialed again until the cooldown has elapsed
+    fn add_address_cooldowns(&mut self, addresses: Vec<Multiaddr>) {
+        let until = Instant::now() + self.config.dial_address_cooldown;
+        for address in addresses {
+            self.address_cooldowns.insert(address, until);
+        }
+    }
+
+    /// Returns the addresses of the peer which are in their failure cooldown period
+    fn cooling_down_addresses(&mut self, peer: &Peer) -> HashSet<Multiaddr> {
+        let now = Instant::now();
+        self.address_cooldowns.retain(|_, until| *until > now);
+        peer.addresses
+            .address_iter()
+            .filter(|addr| self.address_cooldowns.contains_key(addr))
+            .cloned()
+            .collect()
     }
 
     async fn handle_dial_result(
         &mut self,
-        dial_state: DialState,
+        mut dial_state: DialState,
         dial_result: Result<PeerConnection, ConnectionManagerError>,
     )
     {
+        let failed_addresses = dial_state.take_failed_addresses();
+        self.add_address_cooldowns(failed_addresses);
         let DialState { peer, reply_tx, .. } = dial_state;
 
         let node_id = peer.node_id.clone();
@@ -258,6 +320,7 @@ where
         &mut self,
         pending_dials: &mut DialFuturesUnordered,
         peer: Box<Peer>,
+        priority: DialPriority,
         reply_tx: oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>,
     )
     {
@@ -267,6 +330,75 @@ where
             return;
         }
 
+        if self.dial_queue.contains(&peer.node_id) {
+            self.dial_queue.raise_priority(&peer.node_id, priority);
+            let entry = self.pending_dial_requests.entry(peer.node_id).or_insert_with(Vec::new);
+            entry.push(reply_tx);
+            return;
+        }
+
+        if let Some(allow_list) = self.config.peer_allow_list.as_ref() {
+            if !allow_list.is_allowed(&peer.public_key) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Not dialing peer '{}' because it is not in the allow list",
+                    peer.node_id.short_str()
+                );
+                log_if_error_fmt!(
+                    target: LOG_TARGET,
+                    reply_tx.send(Err(ConnectionManagerError::PeerNotAllowed)),
+                    "Failed to send dial result reply for peer '{}'",
+                    peer.node_id.short_str()
+                );
+                return;
+            }
+        }
+
+        if self.cancel_signals.len() >= self.config.max_concurrent_dials {
+            debug!(
+                target: LOG_TARGET,
+                "Queuing {} priority dial to peer '{}' because {} dial(s) are in progress",
+                priority,
+                peer.node_id.short_str(),
+                self.cancel_signals.len()
+            );
+            self.dial_queue.push(peer, priority, reply_tx);
+            return;
+        }
+
+        self.start_dial(pending_dials, peer, priority, reply_tx);
+    }
+
+    fn start_dial(
+        &mut self,
+        pending_dials: &mut DialFuturesUnordered,
+        peer: Box<Peer>,
+        priority: DialPriority,
+        reply_tx: oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>,
+    )
+    {
+        // High priority dials try every address, even those that failed recently
+        let skip_addresses = if priority == DialPriority::High {
+            HashSet::new()
+        } else {
+            self.cooling_down_addresses(&peer)
+        };
+        if !skip_addresses.is_empty() && skip_addresses.len() == peer.addresses.len() {
+            debug!(
+                target: LOG_TARGET,
+                "Not dialing peer '{}' because all of its addresses failed to connect recently",
+                peer.node_id.short_str()
+            );
+            log_if_error_fmt!(
+                target: LOG_TARGET,
+                reply_tx.send(Err(ConnectionManagerError::DialAddressesCoolingDown)),
+                "Failed to send dial result reply for peer '{}'",
+                peer.node_id.short_str()
+            );
+            self.reply_to_pending_requests(&peer.node_id, Err(ConnectionManagerError::DialAddressesCoolingDown));
+            return;
+        }
+
         let transport = self.transport.clone();
         let dial_cancel = Shutdown::new();
         let cancel_signal = dial_cancel.to_signal();
@@ -284,8 +416,15 @@ where
         let allow_test_addresses = self.config.allow_test_addresses;
 
         let dial_fut = async move {
-            let (dial_state, dial_result) =
-                Self::dial_peer_with_retry(dial_state, noise_config, transport, backoff, max_attempts).await;
+            let (dial_state, dial_result) = Self::dial_peer_with_retry(
+                dial_state,
+                noise_config,
+                transport,
+                backoff,
+                max_attempts,
+                skip_addresses,
+            )
+            .await;
 
             let cancel_signal = dial_state.get_cancel_signal();
 
@@ -410,6 +549,7 @@ where
         transport: TTransport,
         backoff: Arc<TBackoff>,
         max_attempts: usize,
+        skip_addresses: HashSet<Multiaddr>,
     ) -> (DialState, DialResult<TTransport::Output>)
     {
         // Container for dial state
@@ -433,7 +573,7 @@ where
             futures::select! {
                 _ = delay => {
                     debug!(target: LOG_TARGET, "[Attempt {}] Connecting to peer '{}'", current_state.num_attempts(), current_state.peer.node_id.short_str());
-                    match Self::dial_peer(current_state, &noise_config, &current_transport).await {
+                    match Self::dial_peer(current_state, &noise_config, &current_transport, &skip_addresses).await {
                         (state, Ok((socket, addr))) => {
                             debug!(target: LOG_TARGET, "Dial succeeded for peer '{}' after {} attempt(s)", state.peer.node_id.short_str(), state.num_attempts());
                             break (state, Ok((socket, addr)));
@@ -460,22 +600,33 @@ where
         }
     }
 
-    /// Attempts to dial a peer sequentially on all addresses.
+    /// Attempts to dial a peer sequentially on all addresses, except those in `skip_addresses`.
     /// Returns ownership of the given `DialState` and a success or failure result for the dial,
     /// or None if the dial was cancelled inflight
     async fn dial_peer(
-        dial_state: DialState,
+        mut dial_state: DialState,
         noise_config: &NoiseConfig,
         transport: &TTransport,
+        skip_addresses: &HashSet<Multiaddr>,
     ) -> (
         DialState,
         Result<(NoiseSocket<TTransport::Output>, Multiaddr), ConnectionManagerError>,
     )
     {
-        let mut addr_iter = dial_state.peer.addresses.address_iter();
+        let peer = dial_state.peer.clone();
+        let mut addr_iter = peer.addresses.address_iter();
         let cancel_signal = dial_state.get_cancel_signal();
         loop {
             let result = match addr_iter.next() {
+                Some(address) if skip_addresses.contains(address) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Skipping address '{}' for peer '{}' because it failed to connect recently",
+                        address,
+                        dial_state.peer.node_id.short_str()
+                    );
+                    continue;
+                },
                 Some(address) => {
                     debug!(
                         target: LOG_TARGET,
@@ -518,6 +669,7 @@ where
                                 dial_state.peer.node_id.short_str(),
                                 err,
                             );
+                            dial_state.add_failed_address(address.clone());
                             // Try the next address
                             continue;
                         },
@@ -541,8 +693,6 @@ where
                 None => Err(ConnectionManagerError::DialConnectFailedAllAddresses),
             };
 
-            drop(addr_iter);
-
             break (dial_state, result);
         }
     }
@@ -69,6 +69,8 @@ pub enum ConnectionManagerError {
     PeerIdentityInvalidNodeId,
     /// Peer is banned, denying connection
     PeerBanned,
+    /// Peer is not in the allow list, denying connection
+    PeerNotAllowed,
     /// Unable to parse any of the network addresses offered by the connecting peer
     PeerIdentityNoValidAddresses,
     IdentityProtocolError(IdentityProtocolError),
@@ -76,6 +78,8 @@ pub enum ConnectionManagerError {
     DialCancelled,
     /// The peer is offline and will not be dialed
     PeerOffline,
+    /// All of the peer's addresses failed to connect recently and will not be dialed until their cooldown has elapsed
+    DialAddressesCoolingDown,
     #[error(msg_embedded, no_from, non_std)]
     InvalidMultiaddr(String),
     /// Failed to send wire format byte
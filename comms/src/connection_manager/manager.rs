@@ -21,12 +21,13 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::{
+    allow_list::PeerAllowList,
     dialer::{Dialer, DialerRequest},
     error::ConnectionManagerError,
     listener::PeerListener,
     peer_connection::{ConnId, PeerConnection},
     requester::ConnectionManagerRequest,
-    types::ConnectionDirection,
+    types::{ConnectionDirection, DialPriority},
 };
 use crate::{
     backoff::Backoff,
@@ -109,6 +110,12 @@ pub struct ConnectionManagerConfig {
     pub listener_address: Multiaddr,
     /// The number of dial attempts to make before giving up. Default: 3
     pub max_dial_attempts: usize,
+    /// The maximum number of outbound dials that are in progress at the same time. Further dials are queued by
+    /// priority until a dial completes. Default: 10
+    pub max_concurrent_dials: usize,
+    /// The period of time for which an address that failed to connect is not dialed again, unless the dial is high
+    /// priority. Default: 60s
+    pub dial_address_cooldown: Duration,
     /// The maximum number of connection tasks that will be spawned at the same time. Once this limit is reached, peers
     /// attempting to connect will have to wait for another connection attempt to complete. Default: 20
     pub max_simultaneous_inbound_connects: usize,
@@ -123,6 +130,9 @@ pub struct ConnectionManagerConfig {
     pub liveness_max_sessions: usize,
     /// CIDR blocks that whitelist liveness checks. Default: Localhost only (127.0.0.1/32)
     pub liveness_cidr_whitelist: Vec<cidr::AnyIpCidr>,
+    /// If set, only peers in the allow list are dialed and only connections from peers in the allow list are
+    /// accepted. Default: None
+    pub peer_allow_list: Option<PeerAllowList>,
 }
 
 impl Default for ConnectionManagerConfig {
@@ -132,6 +142,8 @@ impl Default for ConnectionManagerConfig {
                 .parse()
                 .expect("DEFAULT_LISTENER_ADDRESS is malformed"),
             max_dial_attempts: 3,
+            max_concurrent_dials: 10,
+            dial_address_cooldown: Duration::from_secs(60),
             max_simultaneous_inbound_connects: 20,
             disconnect_linger: Duration::from_secs(3),
             #[cfg(not(test))]
@@ -142,6 +154,7 @@ impl Default for ConnectionManagerConfig {
             liveness_max_sessions: 0,
             time_to_first_byte: Duration::from_secs(7),
             liveness_cidr_whitelist: vec![cidr::AnyIpCidr::V4("127.0.0.1/32".parse().unwrap())],
+            peer_allow_list: None,
         }
     }
 }
@@ -315,7 +328,7 @@ where
         use ConnectionManagerRequest::*;
         trace!(target: LOG_TARGET, "Connection manager got request: {:?}", request);
         match request {
-            DialPeer(node_id, is_forced, reply_tx) => match self.get_active_connection(&node_id) {
+            DialPeer(node_id, is_forced, priority, reply_tx) => match self.get_active_connection(&node_id) {
                 Some(conn) => {
                     debug!(target: LOG_TARGET, "[{}] Found existing active connection", conn);
                     log_if_error_fmt!(
@@ -333,7 +346,7 @@ where
                         self.node_identity.node_id().short_str(),
                         node_id.short_str()
                     );
-                    self.dial_peer(node_id, reply_tx, is_forced).await
+                    self.dial_peer(node_id, reply_tx, is_forced, priority).await
                 },
             },
             NotifyListening(reply_tx) => match self.listener_address.as_ref() {
@@ -568,6 +581,7 @@ where
         node_id: NodeId,
         reply_tx: oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>,
         force_dial: bool,
+        priority: DialPriority,
     )
     {
         match self.peer_manager.find_by_node_id(&node_id).await {
@@ -586,12 +600,14 @@ where
                     return;
                 }
 
-                if let Err(err) = self.dialer_tx.try_send(DialerRequest::Dial(Box::new(peer), reply_tx)) {
+                if let Err(err) = self
+                    .dialer_tx
+                    .try_send(DialerRequest::Dial(Box::new(peer), priority, reply_tx)) {
                     error!(target: LOG_TARGET, "Failed to send request to dialer because '{}'", err);
                     // TODO: If the channel is full - we'll fail to dial. This function should block until the dial
                     //       request channel has cleared
 
-                    if let DialerRequest::Dial(_, reply_tx) = err.into_inner() {
+                    if let DialerRequest::Dial(_, _, reply_tx) = err.into_inner() {
                         log_if_error_fmt!(
                             target: LOG_TARGET,
                             reply_tx.send(Err(ConnectionManagerError::EstablisherChannelError)),
@@ -20,6 +20,10 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+mod allow_list;
+pub use allow_list::PeerAllowList;
+
+mod dial_queue;
 mod dial_state;
 mod dialer;
 mod listener;
@@ -28,7 +32,7 @@ mod common;
 pub use common::validate_peer_addresses;
 
 mod types;
-pub use types::ConnectionDirection;
+pub use types::{ConnectionDirection, DialPriority};
 
 mod requester;
 pub use requester::{ConnectionManagerRequest, ConnectionManagerRequester};
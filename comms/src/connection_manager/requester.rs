@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::{error::ConnectionManagerError, peer_connection::PeerConnection};
+use super::{error::ConnectionManagerError, peer_connection::PeerConnection, types::DialPriority};
 use crate::{connection_manager::manager::ConnectionManagerEvent, multiaddr::Multiaddr, peer_manager::NodeId};
 use futures::{
     channel::{mpsc, oneshot},
@@ -36,9 +36,11 @@ pub enum ConnectionManagerRequest {
     /// Parameters:
     /// 1. Node Id to dial
     /// 1. If true, attempt to dial the peer even if we recently failed to dial them and they are considered offline
+    /// 1. The priority of the dial
     DialPeer(
         NodeId,
         bool,
+        DialPriority,
         oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>,
     ),
     /// Register a oneshot to get triggered when the node is listening, or has failed to listen
@@ -111,23 +113,36 @@ impl ConnectionManagerRequester {
 
     /// Attempt to connect to a remote peer
     pub async fn dial_peer(&mut self, node_id: NodeId) -> Result<PeerConnection, ConnectionManagerError> {
-        self.send_dial_peer(node_id, false).await
+        self.send_dial_peer(node_id, false, DialPriority::Normal).await
+    }
+
+    /// Attempt to connect to a remote peer with the given priority
+    pub async fn dial_peer_with_priority(
+        &mut self,
+        node_id: NodeId,
+        priority: DialPriority,
+    ) -> Result<PeerConnection, ConnectionManagerError>
+    {
+        self.send_dial_peer(node_id, false, priority).await
     }
 
     /// Attempt to connect to a remote peer, even if we failed to contact the peer recently
     pub async fn dial_peer_forced(&mut self, node_id: NodeId) -> Result<PeerConnection, ConnectionManagerError> {
-        self.send_dial_peer(node_id, true).await
+        self.send_dial_peer(node_id, true, DialPriority::Normal).await
     }
 
     async fn send_dial_peer(
         &mut self,
         node_id: NodeId,
         is_forced: bool,
+        priority: DialPriority,
     ) -> Result<PeerConnection, ConnectionManagerError>
     {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.sender
-            .send(ConnectionManagerRequest::DialPeer(node_id, is_forced, reply_tx))
+            .send(ConnectionManagerRequest::DialPeer(
+                node_id, is_forced, priority, reply_tx,
+            ))
             .await
             .map_err(|_| ConnectionManagerError::SendToActorFailed)?;
         reply_rx
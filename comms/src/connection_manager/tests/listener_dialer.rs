@@ -27,6 +27,8 @@ use crate::{
         listener::PeerListener,
         manager::ConnectionManagerEvent,
         ConnectionManagerConfig,
+        ConnectionManagerError,
+        DialPriority,
     },
     noise::NoiseConfig,
     peer_manager::{Peer, PeerFeatures, PeerFlags},
@@ -148,7 +150,7 @@ async fn smoke() {
 
     let (reply_tx, reply_rx) = oneshot::channel();
     request_tx
-        .send(DialerRequest::Dial(Box::new(peer), reply_tx))
+        .send(DialerRequest::Dial(Box::new(peer), DialPriority::Normal, reply_tx))
         .await
         .unwrap();
 
@@ -193,3 +195,69 @@ async fn smoke() {
     timeout(Duration::from_secs(5), listener_fut).await.unwrap().unwrap();
     timeout(Duration::from_secs(5), dialer_fut).await.unwrap().unwrap();
 }
+
+#[tokio_macros::test_basic]
+async fn dial_address_cooldown() {
+    let rt_handle = Handle::current();
+    let (event_tx, _event_rx) = mpsc::channel(10);
+    let mut shutdown = Shutdown::new();
+
+    let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let noise_config = NoiseConfig::new(node_identity.clone());
+    let (mut request_tx, request_rx) = mpsc::channel(1);
+    let dialer = Dialer::new(
+        ConnectionManagerConfig {
+            max_dial_attempts: 0,
+            dial_address_cooldown: Duration::from_secs(60),
+            ..Default::default()
+        },
+        node_identity,
+        build_peer_manager().into(),
+        MemoryTransport,
+        noise_config,
+        ConstantBackoff::new(Duration::from_millis(0)),
+        request_rx,
+        event_tx,
+        vec![],
+        shutdown.to_signal(),
+    );
+
+    let dialer_fut = rt_handle.spawn(dialer.run());
+
+    // Nothing is listening on this address
+    let unreachable = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let mut peer = Peer::new(
+        unreachable.public_key().clone(),
+        unreachable.node_id().clone(),
+        vec!["/memory/1".parse().unwrap()].into(),
+        PeerFlags::empty(),
+        PeerFeatures::COMMUNICATION_NODE,
+        &[],
+    );
+    peer.set_id_for_test(1);
+
+    let dial = |priority: DialPriority| {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let request = DialerRequest::Dial(Box::new(peer.clone()), priority, reply_tx);
+        let mut request_tx = request_tx.clone();
+        async move {
+            request_tx.send(request).await.unwrap();
+            reply_rx.await.unwrap()
+        }
+    };
+
+    let err = dial(DialPriority::Normal).await.unwrap_err();
+    unpack_enum!(ConnectionManagerError::ConnectFailedMaximumAttemptsReached = err);
+
+    // The only address failed, so it is not dialed again until the cooldown has elapsed
+    let err = dial(DialPriority::Low).await.unwrap_err();
+    unpack_enum!(ConnectionManagerError::DialAddressesCoolingDown = err);
+
+    // High priority dials ignore the cooldown
+    let err = dial(DialPriority::High).await.unwrap_err();
+    unpack_enum!(ConnectionManagerError::ConnectFailedMaximumAttemptsReached = err);
+
+    request_tx.close_channel();
+    shutdown.trigger().unwrap();
+    timeout(Duration::from_secs(5), dialer_fut).await.unwrap().unwrap();
+}
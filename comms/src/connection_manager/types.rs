@@ -52,3 +52,28 @@ impl fmt::Display for ConnectionDirection {
         write!(f, "{:?}", self)
     }
 }
+
+/// The priority of an outbound dial. When the number of concurrent dials is at its limit, queued dials with a higher
+/// priority are started first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DialPriority {
+    /// Background dials e.g. refreshing neighbouring peers
+    Low,
+    /// The default priority
+    Normal,
+    /// Dials which the node is waiting on e.g. sync peers and a wallet's base node. High priority dials also ignore
+    /// the address cooldown after failed dials.
+    High,
+}
+
+impl Default for DialPriority {
+    fn default() -> Self {
+        DialPriority::Normal
+    }
+}
+
+impl fmt::Display for DialPriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
@@ -0,0 +1,286 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{
+    error::ConnectivityError,
+    requester::{
+        ConnectivityEvent,
+        ConnectivityEventSender,
+        ConnectivityRequest,
+        ConnectivityStatus,
+        DisconnectReason,
+    },
+};
+use crate::{
+    connection_manager::{ConnectionManagerEvent, ConnectionManagerRequester},
+    peer_manager::NodeId,
+    PeerManager,
+};
+use futures::{
+    channel::{mpsc, oneshot},
+    stream::Fuse,
+    StreamExt,
+};
+use log::*;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use tari_shutdown::ShutdownSignal;
+use tokio::sync::broadcast;
+
+const LOG_TARGET: &str = "comms::connectivity::manager";
+
+/// The misbehaviour score at which a peer is banned
+pub const BAN_SCORE_THRESHOLD: u32 = 100;
+
+/// The ConnectivityManager keeps track of the peers this node is connected to and translates low-level connection
+/// manager events into `ConnectivityEvent`s which are published to applications. It also answers connectivity queries
+/// and allows callers to wait until the node is online.
+pub struct ConnectivityManager {
+    request_rx: Fuse<mpsc::Receiver<ConnectivityRequest>>,
+    connection_manager: ConnectionManagerRequester,
+    connection_manager_events: Fuse<broadcast::Receiver<Arc<ConnectionManagerEvent>>>,
+    peer_manager: Arc<PeerManager>,
+    event_tx: ConnectivityEventSender,
+    connected: HashSet<NodeId>,
+    ban_scores: HashMap<NodeId, u32>,
+    online_waiters: Vec<(usize, oneshot::Sender<()>)>,
+    shutdown_signal: Option<ShutdownSignal>,
+}
+
+impl ConnectivityManager {
+    pub fn new(
+        request_rx: mpsc::Receiver<ConnectivityRequest>,
+        connection_manager: ConnectionManagerRequester,
+        peer_manager: Arc<PeerManager>,
+        event_tx: ConnectivityEventSender,
+        shutdown_signal: ShutdownSignal,
+    ) -> Self
+    {
+        let connection_manager_events = connection_manager.get_event_subscription().fuse();
+        Self {
+            request_rx: request_rx.fuse(),
+            connection_manager,
+            connection_manager_events,
+            peer_manager,
+            event_tx,
+            connected: HashSet::new(),
+            ban_scores: HashMap::new(),
+            online_waiters: Vec::new(),
+            shutdown_signal: Some(shutdown_signal),
+        }
+    }
+
+    pub async fn run(mut self) {
+        let mut shutdown = self
+            .shutdown_signal
+            .take()
+            .expect("ConnectivityManager initialized without a shutdown");
+
+        debug!(target: LOG_TARGET, "Connectivity manager started");
+        loop {
+            futures::select! {
+                event = self.connection_manager_events.select_next_some() => {
+                    match event {
+                        Ok(event) => self.handle_connection_manager_event(&event),
+                        Err(broadcast::RecvError::Lagged(n)) => {
+                            warn!(
+                                target: LOG_TARGET,
+                                "Connectivity manager lagged behind by {} connection manager event(s). Resyncing \
+                                 connected peers.",
+                                n
+                            );
+                            self.resync_connected_peers().await;
+                        },
+                        Err(broadcast::RecvError::Closed) => {
+                            warn!(target: LOG_TARGET, "Connection manager event stream closed");
+                        },
+                    }
+                },
+
+                request = self.request_rx.select_next_some() => {
+                    self.handle_request(request).await;
+                },
+
+                _ = shutdown => {
+                    info!(target: LOG_TARGET, "ConnectivityManager is shutting down because it received the shutdown signal");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn handle_request(&mut self, request: ConnectivityRequest) {
+        use ConnectivityRequest::*;
+        trace!(target: LOG_TARGET, "Connectivity manager got request: {:?}", request);
+        match request {
+            GetConnectivityStatus(reply_tx) => {
+                let _ = reply_tx.send(self.status());
+            },
+            GetConnectedPeers(reply_tx) => {
+                let _ = reply_tx.send(self.connected.iter().cloned().collect());
+            },
+            WaitOnline(min_connected_peers, reply_tx) => {
+                if self.connected.len() >= min_connected_peers {
+                    let _ = reply_tx.send(());
+                } else {
+                    self.online_waiters.push((min_connected_peers, reply_tx));
+                }
+            },
+            DisconnectPeer(node_id, reply_tx) => {
+                let result = self.disconnect_peer(node_id, DisconnectReason::Requested).await;
+                let _ = reply_tx.send(result);
+            },
+            BanPeer(node_id, reply_tx) => {
+                let result = self.ban_peer(node_id).await;
+                let _ = reply_tx.send(result);
+            },
+            AddPeerBanScore(node_id, score, reply_tx) => {
+                let result = self.add_peer_ban_score(node_id, score).await;
+                let _ = reply_tx.send(result);
+            },
+        }
+    }
+
+    fn handle_connection_manager_event(&mut self, event: &ConnectionManagerEvent) {
+        use ConnectionManagerEvent::*;
+        trace!(target: LOG_TARGET, "Received connection manager event '{}'", event);
+        match event {
+            PeerConnected(conn) => {
+                let node_id = conn.peer_node_id().clone();
+                if self.connected.insert(node_id.clone()) {
+                    self.publish_event(ConnectivityEvent::PeerConnected(node_id));
+                    self.notify_online_waiters();
+                }
+            },
+            PeerDisconnected(node_id) => {
+                if self.connected.remove(node_id) {
+                    self.publish_event(ConnectivityEvent::PeerDisconnected {
+                        node_id: (**node_id).clone(),
+                        reason: DisconnectReason::ConnectionClosed,
+                    });
+                }
+            },
+            PeerConnectFailed(node_id, err) => {
+                self.publish_event(ConnectivityEvent::ConnectFailed((**node_id).clone(), err.clone()));
+            },
+            _ => {},
+        }
+    }
+
+    async fn disconnect_peer(&mut self, node_id: NodeId, reason: DisconnectReason) -> Result<(), ConnectivityError> {
+        self.connection_manager.disconnect_peer(node_id.clone()).await??;
+        // The connection manager does not publish a PeerDisconnected event for requested disconnects
+        if self.connected.remove(&node_id) {
+            self.publish_event(ConnectivityEvent::PeerDisconnected { node_id, reason });
+        }
+        Ok(())
+    }
+
+    async fn add_peer_ban_score(&mut self, node_id: NodeId, score: u32) -> Result<bool, ConnectivityError> {
+        let total = self.ban_scores.entry(node_id.clone()).or_insert(0);
+        *total = total.saturating_add(score);
+        debug!(
+            target: LOG_TARGET,
+            "Ban score for peer '{}' is now {}",
+            node_id.short_str(),
+            *total
+        );
+        if *total < BAN_SCORE_THRESHOLD {
+            return Ok(false);
+        }
+
+        warn!(
+            target: LOG_TARGET,
+            "Peer '{}' reached the ban score threshold of {}",
+            node_id.short_str(),
+            BAN_SCORE_THRESHOLD
+        );
+        self.ban_peer(node_id).await?;
+        Ok(true)
+    }
+
+    async fn ban_peer(&mut self, node_id: NodeId) -> Result<(), ConnectivityError> {
+        self.ban_scores.remove(&node_id);
+        let peer = self.peer_manager.find_by_node_id(&node_id).await?;
+        self.peer_manager.set_banned(&peer.public_key, true).await?;
+        debug!(target: LOG_TARGET, "Banned peer '{}'", node_id.short_str());
+        self.disconnect_peer(node_id.clone(), DisconnectReason::Banned).await?;
+        self.publish_event(ConnectivityEvent::Banned(node_id));
+        Ok(())
+    }
+
+    async fn resync_connected_peers(&mut self) {
+        match self.connection_manager.get_active_connections().await {
+            Ok(conns) => {
+                let active = conns
+                    .iter()
+                    .map(|conn| conn.peer_node_id().clone())
+                    .collect::<HashSet<_>>();
+                let disconnected = self.connected.difference(&active).cloned().collect::<Vec<_>>();
+                let connected = active.difference(&self.connected).cloned().collect::<Vec<_>>();
+                self.connected = active;
+                for node_id in disconnected {
+                    self.publish_event(ConnectivityEvent::PeerDisconnected {
+                        node_id,
+                        reason: DisconnectReason::ConnectionClosed,
+                    });
+                }
+                for node_id in connected {
+                    self.publish_event(ConnectivityEvent::PeerConnected(node_id));
+                }
+                self.notify_online_waiters();
+            },
+            Err(err) => {
+                error!(
+                    target: LOG_TARGET,
+                    "Failed to resync connected peers because '{:?}'", err
+                );
+            },
+        }
+    }
+
+    fn notify_online_waiters(&mut self) {
+        let num_connected = self.connected.len();
+        let (ready, pending) = self
+            .online_waiters
+            .drain(..)
+            .partition::<Vec<_>, _>(|(min_peers, _)| num_connected >= *min_peers);
+        self.online_waiters = pending;
+        for (_, reply_tx) in ready {
+            let _ = reply_tx.send(());
+        }
+    }
+
+    fn status(&self) -> ConnectivityStatus {
+        match self.connected.len() {
+            0 => ConnectivityStatus::Offline,
+            n => ConnectivityStatus::Online(n),
+        }
+    }
+
+    fn publish_event(&mut self, event: ConnectivityEvent) {
+        // A send operation can only fail if there are no subscribers, so it is safe to ignore the error
+        let _ = self.event_tx.send(Arc::new(event));
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::error::ConnectivityError;
+use crate::{connection_manager::ConnectionManagerError, peer_manager::NodeId};
+use futures::{
+    channel::{mpsc, oneshot},
+    SinkExt,
+};
+use std::{fmt, sync::Arc, time::Duration};
+use tokio::{sync::broadcast, time};
+
+pub type ConnectivityEventSender = broadcast::Sender<Arc<ConnectivityEvent>>;
+pub type ConnectivityEventReceiver = broadcast::Receiver<Arc<ConnectivityEvent>>;
+
+/// The reason a peer connection was closed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisconnectReason {
+    /// The connection was closed by the remote peer or was lost
+    ConnectionClosed,
+    /// The connection was closed at the request of this node
+    Requested,
+    /// The peer was banned
+    Banned,
+}
+
+/// Peer connectivity events published by the [ConnectivityManager](super::ConnectivityManager)
+#[derive(Debug, Clone)]
+pub enum ConnectivityEvent {
+    PeerConnected(NodeId),
+    PeerDisconnected { node_id: NodeId, reason: DisconnectReason },
+    ConnectFailed(NodeId, ConnectionManagerError),
+    Banned(NodeId),
+}
+
+impl fmt::Display for ConnectivityEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        use ConnectivityEvent::*;
+        match self {
+            PeerConnected(node_id) => write!(f, "PeerConnected({})", node_id.short_str()),
+            PeerDisconnected { node_id, reason } => {
+                write!(f, "PeerDisconnected({}, {:?})", node_id.short_str(), reason)
+            },
+            ConnectFailed(node_id, err) => write!(f, "ConnectFailed({}, {:?})", node_id.short_str(), err),
+            Banned(node_id) => write!(f, "Banned({})", node_id.short_str()),
+        }
+    }
+}
+
+/// The current connectivity of this node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectivityStatus {
+    /// No peers are connected
+    Offline,
+    /// The given number of peers are connected
+    Online(usize),
+}
+
+impl ConnectivityStatus {
+    pub fn is_online(&self) -> bool {
+        self.num_connected_peers() > 0
+    }
+
+    pub fn num_connected_peers(&self) -> usize {
+        match self {
+            ConnectivityStatus::Offline => 0,
+            ConnectivityStatus::Online(n) => *n,
+        }
+    }
+}
+
+impl fmt::Display for ConnectivityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            ConnectivityStatus::Offline => write!(f, "Offline"),
+            ConnectivityStatus::Online(n) => write!(f, "Online ({} peer(s) connected)", n),
+        }
+    }
+}
+
+/// Requests which are handled by the ConnectivityManager
+#[derive(Debug)]
+pub enum ConnectivityRequest {
+    /// Retrieve the current connectivity status
+    GetConnectivityStatus(oneshot::Sender<ConnectivityStatus>),
+    /// Retrieve the node ids of all connected peers
+    GetConnectedPeers(oneshot::Sender<Vec<NodeId>>),
+    /// Register a oneshot to get triggered once at least the given number of peers are connected
+    WaitOnline(usize, oneshot::Sender<()>),
+    /// Disconnect a peer
+    DisconnectPeer(NodeId, oneshot::Sender<Result<(), ConnectivityError>>),
+    /// Ban a peer and disconnect it if it is connected
+    BanPeer(NodeId, oneshot::Sender<Result<(), ConnectivityError>>),
+    /// Add to the misbehaviour score of a peer, banning it if the score reaches the ban threshold
+    AddPeerBanScore(NodeId, u32, oneshot::Sender<Result<bool, ConnectivityError>>),
+}
+
+/// Responsible for constructing requests to the ConnectivityManager and subscribing to its events
+#[derive(Clone)]
+pub struct ConnectivityRequester {
+    sender: mpsc::Sender<ConnectivityRequest>,
+    event_tx: ConnectivityEventSender,
+}
+
+impl ConnectivityRequester {
+    /// Create a new ConnectivityRequester
+    pub fn new(sender: mpsc::Sender<ConnectivityRequest>, event_tx: ConnectivityEventSender) -> Self {
+        Self { sender, event_tx }
+    }
+
+    /// Returns a ConnectivityEvent stream. This will emit events sent _after_ this subscription was created.
+    pub fn subscribe_event_stream(&self) -> ConnectivityEventReceiver {
+        self.event_tx.subscribe()
+    }
+
+    /// Retrieve the current connectivity status
+    pub async fn get_connectivity_status(&mut self) -> Result<ConnectivityStatus, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(ConnectivityRequest::GetConnectivityStatus(reply_tx)).await?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorRequestCanceled)
+    }
+
+    /// Retrieve the node ids of all connected peers
+    pub async fn get_connected_peers(&mut self) -> Result<Vec<NodeId>, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(ConnectivityRequest::GetConnectedPeers(reply_tx)).await?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorRequestCanceled)
+    }
+
+    /// Wait until at least `min_connected_peers` peers are connected. An `OnlineWaitTimeout` error is returned if
+    /// that does not happen within the given timeout.
+    pub async fn wait_for_connectivity(
+        &mut self,
+        min_connected_peers: usize,
+        timeout: Duration,
+    ) -> Result<(), ConnectivityError>
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(ConnectivityRequest::WaitOnline(min_connected_peers, reply_tx))
+            .await?;
+        time::timeout(timeout, reply_rx)
+            .await
+            .map_err(|_| ConnectivityError::OnlineWaitTimeout)?
+            .map_err(|_| ConnectivityError::ActorRequestCanceled)
+    }
+
+    /// Disconnect a peer. A `PeerDisconnected` event is published if the peer was connected.
+    pub async fn disconnect_peer(&mut self, node_id: NodeId) -> Result<(), ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(ConnectivityRequest::DisconnectPeer(node_id, reply_tx))
+            .await?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorRequestCanceled)?
+    }
+
+    /// Ban a peer in the peer manager and disconnect it. A `Banned` event is published.
+    pub async fn ban_peer(&mut self, node_id: NodeId) -> Result<(), ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(ConnectivityRequest::BanPeer(node_id, reply_tx)).await?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorRequestCanceled)?
+    }
+
+    /// Add `score` to the misbehaviour score of a peer. Once the accumulated score reaches
+    /// [BAN_SCORE_THRESHOLD](super::BAN_SCORE_THRESHOLD) the peer is banned and `true` is returned.
+    pub async fn add_peer_ban_score(&mut self, node_id: NodeId, score: u32) -> Result<bool, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(ConnectivityRequest::AddPeerBanScore(node_id, score, reply_tx))
+            .await?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorRequestCanceled)?
+    }
+
+    async fn send(&mut self, request: ConnectivityRequest) -> Result<(), ConnectivityError> {
+        self.sender
+            .send(request)
+            .await
+            .map_err(|_| ConnectivityError::SendToActorFailed)
+    }
+}
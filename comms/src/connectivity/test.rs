@@ -0,0 +1,176 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{
+    error::ConnectivityError,
+    manager::{ConnectivityManager, BAN_SCORE_THRESHOLD},
+    requester::{ConnectivityEvent, ConnectivityRequester, ConnectivityStatus, DisconnectReason},
+};
+use crate::{
+    connection_manager::{ConnectionManagerError, ConnectionManagerEvent},
+    test_utils::{
+        factories::{self, TestFactory},
+        mocks::{create_connection_manager_mock, create_peer_connection_mock_pair, ConnectionManagerMockState},
+        node_id,
+        test_node::build_peer_manager,
+    },
+    PeerManager,
+};
+use futures::channel::mpsc;
+use std::{sync::Arc, time::Duration};
+use tari_shutdown::Shutdown;
+use tari_test_utils::{collect_stream, unpack_enum};
+use tokio::{runtime::Handle, sync::broadcast};
+use tokio_macros as runtime;
+
+fn setup_connectivity_manager() -> (
+    ConnectivityRequester,
+    ConnectionManagerMockState,
+    Arc<PeerManager>,
+    Shutdown,
+) {
+    let shutdown = Shutdown::new();
+    let rt_handle = Handle::current();
+
+    let (conn_man_requester, mock) = create_connection_manager_mock(10);
+    let mock_state = mock.get_shared_state();
+    rt_handle.spawn(mock.run());
+
+    let peer_manager = build_peer_manager();
+    let (request_tx, request_rx) = mpsc::channel(10);
+    let (event_tx, _) = broadcast::channel(10);
+    let connectivity_manager = ConnectivityManager::new(
+        request_rx,
+        conn_man_requester,
+        peer_manager.clone(),
+        event_tx.clone(),
+        shutdown.to_signal(),
+    );
+    rt_handle.spawn(connectivity_manager.run());
+
+    (
+        ConnectivityRequester::new(request_tx, event_tx),
+        mock_state,
+        peer_manager,
+        shutdown,
+    )
+}
+
+#[runtime::test_basic]
+async fn connectivity_events_and_status() {
+    let (mut connectivity, mut mock_state, peer_manager, _shutdown) = setup_connectivity_manager();
+    let mut events_rx = connectivity.subscribe_event_stream();
+
+    let status = connectivity.get_connectivity_status().await.unwrap();
+    assert_eq!(status, ConnectivityStatus::Offline);
+    let err = connectivity
+        .wait_for_connectivity(1, Duration::from_millis(10))
+        .await
+        .unwrap_err();
+    unpack_enum!(ConnectivityError::OnlineWaitTimeout = err);
+
+    let peer = factories::peer::create().build().unwrap();
+    peer_manager.add_peer(peer.clone()).await.unwrap();
+    let (conn, _, _, _) = create_peer_connection_mock_pair(1, peer.node_id.clone(), node_id::random()).await;
+    mock_state
+        .add_active_connection(peer.node_id.clone(), conn.clone())
+        .await;
+    mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+
+    let events = collect_stream!(&mut events_rx, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::PeerConnected(node_id) = &**events[0].as_ref().unwrap());
+    assert_eq!(node_id, &peer.node_id);
+
+    connectivity
+        .wait_for_connectivity(1, Duration::from_secs(10))
+        .await
+        .unwrap();
+    let status = connectivity.get_connectivity_status().await.unwrap();
+    assert_eq!(status, ConnectivityStatus::Online(1));
+    let connected_peers = connectivity.get_connected_peers().await.unwrap();
+    assert_eq!(connected_peers, vec![peer.node_id.clone()]);
+
+    let failed_node_id = node_id::random();
+    mock_state.publish_event(ConnectionManagerEvent::PeerConnectFailed(
+        Box::new(failed_node_id.clone()),
+        ConnectionManagerError::DialConnectFailedAllAddresses,
+    ));
+    let events = collect_stream!(&mut events_rx, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::ConnectFailed(node_id, _err) = &**events[0].as_ref().unwrap());
+    assert_eq!(node_id, &failed_node_id);
+
+    connectivity.ban_peer(peer.node_id.clone()).await.unwrap();
+    let events = collect_stream!(&mut events_rx, take = 2, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::PeerDisconnected { node_id, reason } = &**events[0].as_ref().unwrap());
+    assert_eq!(node_id, &peer.node_id);
+    assert_eq!(*reason, DisconnectReason::Banned);
+    unpack_enum!(ConnectivityEvent::Banned(node_id) = &**events[1].as_ref().unwrap());
+    assert_eq!(node_id, &peer.node_id);
+
+    assert!(peer_manager.find_by_node_id(&peer.node_id).await.unwrap().is_banned());
+    let status = connectivity.get_connectivity_status().await.unwrap();
+    assert_eq!(status, ConnectivityStatus::Offline);
+}
+
+#[runtime::test_basic]
+async fn connection_lost() {
+    let (mut connectivity, mut mock_state, _, _shutdown) = setup_connectivity_manager();
+    let mut events_rx = connectivity.subscribe_event_stream();
+
+    let peer_node_id = node_id::random();
+    let (conn, _, _, _) = create_peer_connection_mock_pair(1, peer_node_id.clone(), node_id::random()).await;
+    mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+    mock_state.publish_event(ConnectionManagerEvent::PeerDisconnected(Box::new(peer_node_id.clone())));
+
+    let events = collect_stream!(&mut events_rx, take = 2, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::PeerConnected(_node_id) = &**events[0].as_ref().unwrap());
+    unpack_enum!(ConnectivityEvent::PeerDisconnected { node_id, reason } = &**events[1].as_ref().unwrap());
+    assert_eq!(node_id, &peer_node_id);
+    assert_eq!(*reason, DisconnectReason::ConnectionClosed);
+
+    let status = connectivity.get_connectivity_status().await.unwrap();
+    assert_eq!(status, ConnectivityStatus::Offline);
+}
+
+#[runtime::test_basic]
+async fn ban_score_threshold() {
+    let (mut connectivity, _, peer_manager, _shutdown) = setup_connectivity_manager();
+    let mut events_rx = connectivity.subscribe_event_stream();
+
+    let peer = factories::peer::create().build().unwrap();
+    peer_manager.add_peer(peer.clone()).await.unwrap();
+
+    let is_banned = connectivity
+        .add_peer_ban_score(peer.node_id.clone(), BAN_SCORE_THRESHOLD - 1)
+        .await
+        .unwrap();
+    assert!(!is_banned);
+    assert!(!peer_manager.find_by_node_id(&peer.node_id).await.unwrap().is_banned());
+
+    let is_banned = connectivity.add_peer_ban_score(peer.node_id.clone(), 1).await.unwrap();
+    assert!(is_banned);
+    assert!(peer_manager.find_by_node_id(&peer.node_id).await.unwrap().is_banned());
+
+    let events = collect_stream!(&mut events_rx, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::Banned(node_id) = &**events[0].as_ref().unwrap());
+    assert_eq!(node_id, &peer.node_id);
+}
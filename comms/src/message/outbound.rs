@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    connection_manager::DialPriority,
     message::{MessageFlags, MessageTag},
     peer_manager::NodeId,
 };
@@ -38,6 +39,8 @@ pub struct OutboundMessage {
     pub peer_node_id: NodeId,
     pub flags: MessageFlags,
     pub body: Bytes,
+    /// The priority with which the peer is dialed if there is no connection to it
+    pub dial_priority: DialPriority,
 }
 
 impl OutboundMessage {
@@ -53,8 +56,15 @@ impl OutboundMessage {
             peer_node_id,
             flags,
             body,
+            dial_priority: Default::default(),
         }
     }
+
+    /// Set the priority with which the peer is dialed if there is no connection to it
+    pub fn with_dial_priority(mut self, dial_priority: DialPriority) -> Self {
+        self.dial_priority = dial_priority;
+        self
+    }
 }
 
 impl fmt::Display for OutboundMessage {
@@ -23,7 +23,7 @@
 // This file is heavily influenced by the Libra Noise protocol implementation.
 
 use crate::{
-    connection_manager::ConnectionDirection,
+    connection_manager::{ConnectionDirection, PeerAllowList},
     noise::{
         crypto_resolver::TariCryptoResolver,
         error::NoiseError,
@@ -46,6 +46,7 @@ pub(super) const NOISE_IX_PARAMETER: &str = "Noise_IX_25519_ChaChaPoly_BLAKE2b";
 pub struct NoiseConfig {
     node_identity: Arc<NodeIdentity>,
     parameters: NoiseParams,
+    allow_list: Option<PeerAllowList>,
 }
 
 impl NoiseConfig {
@@ -55,9 +56,16 @@ impl NoiseConfig {
         Self {
             node_identity,
             parameters,
+            allow_list: None,
         }
     }
 
+    /// Only complete the upgrade for peers whose public key is in the allow list
+    pub fn with_allow_list(mut self, allow_list: PeerAllowList) -> Self {
+        self.allow_list = Some(allow_list);
+        self
+    }
+
     /// Upgrades the given socket to using the noise protocol. The upgraded socket and the peer's static key
     /// is returned.
     pub async fn upgrade_socket<TSocket>(
@@ -88,6 +96,21 @@ impl NoiseConfig {
         let handshake = Handshake::new(socket, handshake_state);
         let socket = handshake.handshake_1rt().await.map_err(NoiseError::HandshakeFailed)?;
 
+        if let Some(allow_list) = self.allow_list.as_ref() {
+            match socket.get_remote_public_key() {
+                Some(pk) if allow_list.is_allowed(&pk) => {},
+                remote_public_key => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Rejecting {} connection from peer '{}' which is not in the allow list",
+                        direction,
+                        remote_public_key.map(|pk| pk.to_string()).unwrap_or_else(|| "<unknown>".to_string())
+                    );
+                    return Err(NoiseError::PeerNotAllowed);
+                },
+            }
+        }
+
         Ok(socket)
     }
 }
@@ -152,4 +175,39 @@ mod test {
             assert_eq!(read_buf, sample);
         });
     }
+
+    #[test]
+    fn upgrade_socket_rejects_peers_not_in_allow_list() {
+        let mut rt = Runtime::new().unwrap();
+
+        let node_identity1 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+        let node_identity2 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+        let node_identity3 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+        let config1 = NoiseConfig::new(node_identity1.clone())
+            .with_allow_list(PeerAllowList::new(vec![node_identity2.public_key().clone()]));
+        let config2 = NoiseConfig::new(node_identity2.clone());
+        let config3 = NoiseConfig::new(node_identity3.clone());
+
+        rt.block_on(async move {
+            let (in_socket, out_socket) = MemorySocket::new_pair();
+            let (socket_in, socket_out) = future::join(
+                config1.upgrade_socket(in_socket, ConnectionDirection::Inbound),
+                config2.upgrade_socket(out_socket, ConnectionDirection::Outbound),
+            )
+            .await;
+            assert!(socket_in.is_ok());
+            assert!(socket_out.is_ok());
+
+            let (in_socket, out_socket) = MemorySocket::new_pair();
+            let (socket_in, _) = future::join(
+                config1.upgrade_socket(in_socket, ConnectionDirection::Inbound),
+                config3.upgrade_socket(out_socket, ConnectionDirection::Outbound),
+            )
+            .await;
+            match socket_in {
+                Err(NoiseError::PeerNotAllowed) => {},
+                _ => panic!("Expected the peer to be rejected"),
+            }
+        });
+    }
 }
@@ -28,6 +28,8 @@ pub enum NoiseError {
     SnowError(snow::Error),
     #[error(no_from)]
     HandshakeFailed(io::Error),
+    /// The peer is not in the allow list
+    PeerNotAllowed,
 }
 
 impl NoiseError {
@@ -35,6 +37,7 @@ impl NoiseError {
         match self {
             NoiseError::SnowError(err) => format!("SnowError: {:?}", err),
             NoiseError::HandshakeFailed(err) => format!("HandshakeFailed: {:?}", err),
+            NoiseError::PeerNotAllowed => "PeerNotAllowed".to_string(),
         }
     }
 }
@@ -35,6 +35,9 @@ pub struct PeerConnectionStats {
     pub last_connected_at: Option<NaiveDateTime>,
     /// Represents the last connection attempt
     pub last_connection_attempt: LastConnectionAttempt,
+    /// The number of seconds the peer's clock was ahead of this node's clock (negative if behind) when it last sent
+    /// this node a timestamp, or None if the skew has never been observed
+    pub observed_clock_skew_secs: Option<i64>,
 }
 
 impl PeerConnectionStats {
@@ -56,6 +59,11 @@ impl PeerConnectionStats {
         };
     }
 
+    /// Records the difference between the peer's clock and this node's clock
+    pub fn set_observed_clock_skew(&mut self, skew: chrono::Duration) {
+        self.observed_clock_skew_secs = Some(skew.num_seconds());
+    }
+
     /// Returns true if a successful connection has ever been recorded, otherwise false
     pub fn has_ever_connected(&self) -> bool {
         self.last_connected_at.is_some()
@@ -103,6 +111,10 @@ impl fmt::Display for PeerConnectionStats {
             },
         }
 
+        if let Some(skew) = self.observed_clock_skew_secs {
+            write!(f, " Clock skew {:+}s.", skew)?;
+        }
+
         Ok(())
     }
 }
@@ -182,5 +194,10 @@ mod test {
         assert_eq!(state.has_ever_connected(), false);
         state.set_connection_success();
         assert_eq!(state.has_ever_connected(), true);
+
+        assert!(state.observed_clock_skew_secs.is_none());
+        state.set_observed_clock_skew(chrono::Duration::seconds(-90));
+        assert_eq!(state.observed_clock_skew_secs, Some(-90));
+        assert!(state.to_string().ends_with("Clock skew -90s."));
     }
 }
@@ -38,6 +38,17 @@ use multiaddr::Multiaddr;
 use tari_storage::IterationResult;
 use tokio::sync::RwLock;
 
+/// The outcome of [PeerManager::import_peers]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PeerImportSummary {
+    /// The number of peers that were not known and have been added
+    pub added: usize,
+    /// The number of known peers that have been updated
+    pub updated: usize,
+    /// The number of peers that were skipped because their node id does not belong to their public key
+    pub skipped: usize,
+}
+
 /// The PeerManager consist of a routing table of previously discovered peers.
 /// It also provides functionality to add, find and delete peers. A subset of peers can also be requested from the
 /// routing table based on the selected Broadcast strategy.
@@ -84,6 +95,44 @@ impl PeerManager {
         )
     }
 
+    /// Merges peers exported from another peer database into this one. Unknown peers are added as they are, except
+    /// for the offline flag which only reflects the connectivity of the node they were exported from. For known
+    /// peers, the imported addresses are added to the existing addresses and an imported ban is applied. Local
+    /// connection stats are kept and local bans are never lifted.
+    pub async fn import_peers<I: IntoIterator<Item = Peer>>(
+        &self,
+        peers: I,
+    ) -> Result<PeerImportSummary, PeerManagerError>
+    {
+        let mut storage = self.peer_storage.write().await;
+        let mut summary = PeerImportSummary::default();
+        for mut peer in peers {
+            if NodeId::from_key(&peer.public_key).ok().as_ref() != Some(&peer.node_id) {
+                summary.skipped += 1;
+                continue;
+            }
+            match storage.find_by_public_key(&peer.public_key) {
+                Ok(mut existing) => {
+                    for address in &peer.addresses.addresses {
+                        existing.addresses.add_net_address(&address.address);
+                    }
+                    if peer.is_banned() {
+                        existing.set_banned(true);
+                    }
+                    storage.add_peer(existing)?;
+                    summary.updated += 1;
+                },
+                Err(PeerManagerError::PeerNotFoundError) => {
+                    peer.set_offline(false);
+                    storage.add_peer(peer)?;
+                    summary.added += 1;
+                },
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(summary)
+    }
+
     /// Set the last connection to this peer as a success
     pub async fn set_last_connect_success(&self, node_id: &NodeId) -> Result<(), PeerManagerError> {
         let mut storage = self.peer_storage.write().await;
@@ -117,6 +166,27 @@ impl PeerManager {
         )
     }
 
+    /// Record the difference between the clock of this peer and this node's clock
+    pub async fn set_observed_clock_skew(
+        &self,
+        node_id: &NodeId,
+        skew: chrono::Duration,
+    ) -> Result<(), PeerManagerError>
+    {
+        let mut storage = self.peer_storage.write().await;
+        let mut peer = storage.find_by_node_id(node_id)?;
+        peer.connection_stats.set_observed_clock_skew(skew);
+        storage.update_peer(
+            &peer.public_key,
+            None,
+            None,
+            None,
+            None,
+            Some(peer.connection_stats),
+            None,
+        )
+    }
+
     /// The peer with the specified public_key will be removed from the PeerManager
     pub async fn delete_peer(&self, node_id: &NodeId) -> Result<(), PeerManagerError> {
         self.peer_storage.write().await.delete_peer(node_id)
@@ -420,4 +490,50 @@ mod test {
             }
         }
     }
+
+    #[tokio_macros::test_basic]
+    async fn import_peers() {
+        let peer_manager = PeerManager::new(HashmapDatabase::new()).unwrap();
+        let known = create_test_peer(false);
+        peer_manager.add_peer(known.clone()).await.unwrap();
+        let locally_banned = create_test_peer(true);
+        peer_manager.add_peer(locally_banned.clone()).await.unwrap();
+
+        let mut imported_known = known.clone();
+        imported_known.addresses = MultiaddressesWithStats::from("/ip4/5.6.7.8/tcp/8000".parse::<Multiaddr>().unwrap());
+        imported_known.set_banned(true);
+        let mut imported_unbanned = locally_banned.clone();
+        imported_unbanned.set_banned(false);
+        let mut unknown = create_test_peer(false);
+        unknown.set_offline(true);
+        let mut invalid = create_test_peer(false);
+        invalid.node_id = NodeId::new();
+
+        let summary = peer_manager
+            .import_peers(vec![
+                imported_known,
+                imported_unbanned,
+                unknown.clone(),
+                invalid.clone(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(summary, PeerImportSummary {
+            added: 1,
+            updated: 2,
+            skipped: 1
+        });
+
+        let peer = peer_manager.find_by_public_key(&known.public_key).await.unwrap();
+        assert!(peer.is_banned());
+        assert_eq!(peer.addresses.addresses.len(), 2);
+        let peer = peer_manager
+            .find_by_public_key(&locally_banned.public_key)
+            .await
+            .unwrap();
+        assert!(peer.is_banned());
+        let peer = peer_manager.find_by_public_key(&unknown.public_key).await.unwrap();
+        assert!(!peer.is_offline());
+        assert!(!peer_manager.exists(&invalid.public_key).await);
+    }
 }
@@ -82,7 +82,7 @@ mod peer_id;
 pub use peer_id::PeerId;
 
 mod manager;
-pub use manager::PeerManager;
+pub use manager::{PeerImportSummary, PeerManager};
 
 mod peer_query;
 pub use peer_query::{PeerQuery, PeerQuerySortBy};
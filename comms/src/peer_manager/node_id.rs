@@ -71,6 +71,19 @@ impl NodeDistance {
     pub fn max_distance() -> NodeDistance {
         NodeDistance([255; NODE_ID_ARRAY_SIZE])
     }
+
+    /// Returns the number of leading zero bits of the distance. Peers are grouped into routing table buckets by this
+    /// index, peers in higher buckets are closer.
+    pub fn get_bucket_index(&self) -> u32 {
+        let mut index = 0;
+        for byte in self.0.iter() {
+            index += byte.leading_zeros();
+            if *byte != 0 {
+                break;
+            }
+        }
+        index
+    }
 }
 
 impl PartialEq for NodeDistance {
@@ -404,4 +417,14 @@ mod test {
 
         assert_eq!(nid1, nid2);
     }
+
+    #[test]
+    fn get_bucket_index() {
+        let node_id = NodeId::try_from(&[0u8; 13][..]).unwrap();
+        assert_eq!(node_id.distance(&node_id).get_bucket_index(), 104);
+        let other = NodeId::try_from(&[128u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0][..]).unwrap();
+        assert_eq!(node_id.distance(&other).get_bucket_index(), 0);
+        let other = NodeId::try_from(&[0u8, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1][..]).unwrap();
+        assert_eq!(node_id.distance(&other).get_bucket_index(), 22);
+    }
 }
@@ -39,6 +39,7 @@ use tari_crypto::{
     keys::{PublicKey, SecretKey},
     tari_utilities::hex::serialize_to_hex,
 };
+use tari_secret::Secret;
 
 #[derive(Debug, Error)]
 pub enum NodeIdentityError {
@@ -55,7 +56,7 @@ pub struct NodeIdentity {
     node_id: NodeId,
     public_key: CommsPublicKey,
     features: PeerFeatures,
-    secret_key: CommsSecretKey,
+    secret_key: Secret<CommsSecretKey>,
     public_address: RwLock<Multiaddr>,
 }
 
@@ -74,7 +75,7 @@ impl NodeIdentity {
             node_id,
             public_key,
             features,
-            secret_key,
+            secret_key: Secret::new(secret_key),
             public_address: RwLock::new(public_address),
         })
     }
@@ -96,7 +97,7 @@ impl NodeIdentity {
             node_id,
             public_key,
             features,
-            secret_key,
+            secret_key: Secret::new(secret_key),
             public_address: RwLock::new(public_address),
         })
     }
@@ -139,7 +140,7 @@ impl NodeIdentity {
 
     #[inline]
     pub fn secret_key(&self) -> &CommsSecretKey {
-        &self.secret_key
+        self.secret_key.reveal()
     }
 
     #[inline]
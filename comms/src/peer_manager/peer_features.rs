@@ -29,8 +29,11 @@ bitflags! {
         const NONE = 0b0000_0000;
         const MESSAGE_PROPAGATION = 0b0000_0001;
         const DHT_STORE_FORWARD = 0b0000_0010;
+        const COMPRESSION = 0b0000_0100;
 
-        const COMMUNICATION_NODE = Self::MESSAGE_PROPAGATION.bits | Self::DHT_STORE_FORWARD.bits;
+        const COMMUNICATION_NODE = Self::MESSAGE_PROPAGATION.bits
+            | Self::DHT_STORE_FORWARD.bits
+            | Self::COMPRESSION.bits;
         const COMMUNICATION_CLIENT = Self::NONE.bits;
     }
 }
@@ -36,6 +36,7 @@ type OutboundMessageSinkService = SinkService<mpsc::Sender<OutboundMessage>>;
 #[derive(Default)]
 pub struct Builder<TInSvc, TOutSvc, TOutReq> {
     max_concurrent_inbound_tasks: usize,
+    max_concurrent_outbound_tasks: usize,
     outbound_buffer_size: usize,
     inbound: Option<TInSvc>,
     outbound_rx: Option<mpsc::Receiver<TOutReq>>,
@@ -46,6 +47,7 @@ impl Builder<(), (), ()> {
     pub fn new() -> Self {
         Self {
             max_concurrent_inbound_tasks: DEFAULT_MAX_CONCURRENT_TASKS,
+            max_concurrent_outbound_tasks: DEFAULT_MAX_CONCURRENT_TASKS,
             outbound_buffer_size: DEFAULT_OUTBOUND_BUFFER_SIZE,
             inbound: None,
             outbound_rx: None,
@@ -60,6 +62,11 @@ impl<TInSvc, TOutSvc, TOutReq> Builder<TInSvc, TOutSvc, TOutReq> {
         self
     }
 
+    pub fn max_concurrent_outbound_tasks(mut self, max_tasks: usize) -> Self {
+        self.max_concurrent_outbound_tasks = max_tasks;
+        self
+    }
+
     pub fn outbound_buffer_size(mut self, buf_size: usize) -> Self {
         self.outbound_buffer_size = buf_size;
         self
@@ -76,6 +83,7 @@ impl<TInSvc, TOutSvc, TOutReq> Builder<TInSvc, TOutSvc, TOutReq> {
             outbound_pipeline_factory: Some(Box::new(factory)),
 
             max_concurrent_inbound_tasks: self.max_concurrent_inbound_tasks,
+            max_concurrent_outbound_tasks: self.max_concurrent_outbound_tasks,
             inbound: self.inbound,
             outbound_buffer_size: self.outbound_buffer_size,
         }
@@ -87,6 +95,7 @@ impl<TInSvc, TOutSvc, TOutReq> Builder<TInSvc, TOutSvc, TOutReq> {
             inbound: Some(inbound),
 
             max_concurrent_inbound_tasks: self.max_concurrent_inbound_tasks,
+            max_concurrent_outbound_tasks: self.max_concurrent_outbound_tasks,
             outbound_rx: self.outbound_rx,
             outbound_pipeline_factory: self.outbound_pipeline_factory,
             outbound_buffer_size: self.outbound_buffer_size,
@@ -118,6 +127,7 @@ where
             in_receiver,
             pipeline,
             out_receiver,
+            max_concurrent_tasks: self.max_concurrent_outbound_tasks,
         })
     }
 
@@ -147,6 +157,9 @@ pub struct OutboundPipelineConfig<TInStream, TPipeline> {
     pub out_receiver: mpsc::Receiver<OutboundMessage>,
     /// The pipeline (`tower::Service`) to run for each in_stream message
     pub pipeline: TPipeline,
+    /// The maximum number of pipeline tasks that may run concurrently. Once reached, no more messages are read from
+    /// the in_stream until a task completes.
+    pub max_concurrent_tasks: usize,
 }
 
 pub struct Config<TInSvc, TOutSvc, TOutReq> {
@@ -21,11 +21,12 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    bounded_executor::BoundedExecutor,
     message::OutboundMessage,
     pipeline::builder::OutboundPipelineConfig,
     protocol::messaging::MessagingRequest,
 };
-use futures::{channel::mpsc, future, future::Either, stream::FusedStream, SinkExt, Stream, StreamExt};
+use futures::{channel::mpsc, stream::FusedStream, SinkExt, Stream, StreamExt};
 use log::*;
 use std::fmt::Debug;
 use tokio::runtime;
@@ -34,7 +35,8 @@ use tower::{Service, ServiceExt};
 const LOG_TARGET: &str = "comms::pipeline::outbound";
 
 pub struct Outbound<TPipeline, TStream> {
-    /// Executor used to spawn a pipeline for each received item on the stream
+    /// Executor used to spawn a pipeline for each received item on the stream. At most
+    /// `OutboundPipelineConfig::max_concurrent_tasks` pipelines are spawned at a time.
     executor: runtime::Handle,
     /// Outbound pipeline configuration containing the pipeline and it's in and out streams
     config: OutboundPipelineConfig<TStream, TPipeline>,
@@ -63,55 +65,60 @@ where
         }
     }
 
-    pub async fn run(mut self) {
-        loop {
-            let either = future::select(self.config.in_receiver.next(), self.config.out_receiver.next()).await;
-            match either {
-                // Pipeline IN received a message. Spawn a new task for the pipeline
-                Either::Left((Some(msg), _)) => {
-                    let pipeline = self.config.pipeline.clone();
-                    self.executor.spawn(async move {
-                        if let Err(err) = pipeline.oneshot(msg).await {
-                            error!(target: LOG_TARGET, "Outbound pipeline returned an error: '{:?}'", err);
-                        }
-                    });
-                },
-                // Pipeline IN channel closed
-                Either::Left((None, _)) => {
-                    info!(
-                        target: LOG_TARGET,
-                        "Outbound pipeline is shutting down because the in channel closed"
-                    );
-                    break;
-                },
-                // Pipeline OUT received a message
-                Either::Right((Some(out_msg), _)) => {
-                    if self.messaging_request_tx.is_closed() {
-                        // MessagingRequest channel closed
-                        break;
+    pub async fn run(self) {
+        let OutboundPipelineConfig {
+            mut in_receiver,
+            pipeline,
+            out_receiver,
+            max_concurrent_tasks,
+        } = self.config;
+
+        // Messages leaving the pipeline are forwarded in their own task so that pipeline tasks waiting on a full out
+        // channel always make progress, even while no more pipeline tasks can be spawned
+        self.executor
+            .spawn(Self::forward_to_messaging(out_receiver, self.messaging_request_tx));
+
+        let executor = BoundedExecutor::new(self.executor, max_concurrent_tasks);
+        // Pipeline IN received a message. Spawn a new task for the pipeline once there is capacity to do so
+        while let Some(msg) = in_receiver.next().await {
+            let pipeline = pipeline.clone();
+            executor
+                .spawn(async move {
+                    if let Err(err) = pipeline.oneshot(msg).await {
+                        error!(target: LOG_TARGET, "Outbound pipeline returned an error: '{:?}'", err);
                     }
-                    self.send_messaging_request(out_msg).await;
-                },
-                // Pipeline OUT channel closed
-                Either::Right((None, _)) => {
-                    info!(
-                        target: LOG_TARGET,
-                        "Outbound pipeline is shutting down because the out channel closed"
-                    );
-                    break;
-                },
-            }
+                })
+                .await;
         }
+
+        info!(
+            target: LOG_TARGET,
+            "Outbound pipeline is shutting down because the in channel closed"
+        );
     }
 
-    async fn send_messaging_request(&mut self, out_msg: OutboundMessage) {
-        let msg_req = MessagingRequest::SendMessage(out_msg);
-        if let Err(err) = self.messaging_request_tx.send(msg_req).await {
-            error!(
-                target: LOG_TARGET,
-                "Failed to send OutboundMessage to Messaging protocol because '{}'", err
-            );
+    async fn forward_to_messaging(
+        mut out_receiver: mpsc::Receiver<OutboundMessage>,
+        mut messaging_request_tx: mpsc::Sender<MessagingRequest>,
+    )
+    {
+        while let Some(out_msg) = out_receiver.next().await {
+            let msg_req = MessagingRequest::SendMessage(out_msg);
+            if let Err(err) = messaging_request_tx.send(msg_req).await {
+                error!(
+                    target: LOG_TARGET,
+                    "Failed to send OutboundMessage to Messaging protocol because '{}'", err
+                );
+                if err.is_disconnected() {
+                    break;
+                }
+            }
         }
+
+        info!(
+            target: LOG_TARGET,
+            "Outbound pipeline is shutting down because the out channel closed"
+        );
     }
 }
 
@@ -146,6 +153,7 @@ mod test {
                 in_receiver: stream,
                 out_receiver: out_rx,
                 pipeline: SinkService::new(out_tx),
+                max_concurrent_tasks: 1,
             },
             msg_tx,
         );
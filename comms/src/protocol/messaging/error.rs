@@ -52,5 +52,7 @@ pub enum MessagingProtocolError {
     PeerDialFailed,
     /// Failure when sending on an outbound substream
     OutboundSubstreamFailure,
+    /// The outbound message queue for the peer is full
+    QueueFull,
     MessageError(MessageError),
 }
@@ -22,7 +22,13 @@
 
 use super::{error::MessagingProtocolError, MessagingEvent, MessagingProtocol, SendFailReason, MESSAGING_PROTOCOL};
 use crate::{
-    connection_manager::{ConnectionManagerError, ConnectionManagerRequester, NegotiatedSubstream, PeerConnection},
+    connection_manager::{
+        ConnectionManagerError,
+        ConnectionManagerRequester,
+        DialPriority,
+        NegotiatedSubstream,
+        PeerConnection,
+    },
     message::{Envelope, MessageExt, OutboundMessage},
     peer_manager::{NodeId, NodeIdentity},
     types::CommsSubstream,
@@ -37,9 +43,10 @@ const LOG_TARGET: &str = "comms::protocol::messaging::outbound";
 pub struct OutboundMessaging {
     conn_man_requester: ConnectionManagerRequester,
     node_identity: Arc<NodeIdentity>,
-    request_rx: mpsc::UnboundedReceiver<OutboundMessage>,
+    request_rx: mpsc::Receiver<OutboundMessage>,
     messaging_events_tx: mpsc::Sender<MessagingEvent>,
     peer_node_id: NodeId,
+    dial_priority: DialPriority,
 }
 
 impl OutboundMessaging {
@@ -47,8 +54,9 @@ impl OutboundMessaging {
         conn_man_requester: ConnectionManagerRequester,
         node_identity: Arc<NodeIdentity>,
         messaging_events_tx: mpsc::Sender<MessagingEvent>,
-        request_rx: mpsc::UnboundedReceiver<OutboundMessage>,
+        request_rx: mpsc::Receiver<OutboundMessage>,
         peer_node_id: NodeId,
+        dial_priority: DialPriority,
     ) -> Self
     {
         Self {
@@ -57,6 +65,7 @@ impl OutboundMessaging {
             request_rx,
             messaging_events_tx,
             peer_node_id,
+            dial_priority,
         }
     }
 
@@ -76,7 +85,11 @@ impl OutboundMessaging {
 
     async fn try_dial_peer(&mut self) -> Result<PeerConnection, MessagingProtocolError> {
         loop {
-            match self.conn_man_requester.dial_peer(self.peer_node_id.clone()).await {
+            match self
+                .conn_man_requester
+                .dial_peer_with_priority(self.peer_node_id.clone(), self.dial_priority)
+                .await
+            {
                 Ok(conn) => break Ok(conn),
                 Err(ConnectionManagerError::DialCancelled) => {
                     error!(
@@ -23,7 +23,7 @@
 use super::error::MessagingProtocolError;
 use crate::{
     compat::IoCompat,
-    connection_manager::{ConnectionManagerEvent, ConnectionManagerRequester},
+    connection_manager::{ConnectionManagerEvent, ConnectionManagerRequester, DialPriority},
     message::{InboundMessage, MessageTag, OutboundMessage},
     peer_manager::{NodeId, NodeIdentity, Peer, PeerManagerError},
     protocol::{
@@ -51,6 +51,9 @@ use tokio_util::codec::{Framed, LengthDelimitedCodec};
 const LOG_TARGET: &str = "comms::protocol::messaging";
 pub static MESSAGING_PROTOCOL: Bytes = Bytes::from_static(b"/tari/messaging/0.1.0");
 const INTERNAL_MESSAGING_EVENT_CHANNEL_SIZE: usize = 50;
+/// The maximum number of messages that may be queued for a single peer. Messages sent to a peer whose queue is full
+/// are discarded so that a slow peer cannot cause unbounded memory growth.
+pub(super) const PEER_MESSAGE_QUEUE_SIZE: usize = 50;
 
 pub type MessagingEventSender = broadcast::Sender<Arc<MessagingEvent>>;
 pub type MessagingEventReceiver = broadcast::Receiver<Arc<MessagingEvent>>;
@@ -75,6 +78,8 @@ pub enum SendFailReason {
     SubstreamOpenFailed,
     /// Failed to send on substream channel
     SubstreamSendFailed,
+    /// The outbound message queue for the peer is full
+    QueueFull,
 }
 
 #[derive(Clone, Debug)]
@@ -103,7 +108,7 @@ pub struct MessagingProtocol {
     node_identity: Arc<NodeIdentity>,
     peer_manager: Arc<PeerManager>,
     proto_notification: Fuse<mpsc::Receiver<ProtocolNotification<CommsSubstream>>>,
-    active_queues: HashMap<Box<NodeId>, mpsc::UnboundedSender<OutboundMessage>>,
+    active_queues: HashMap<Box<NodeId>, mpsc::Sender<OutboundMessage>>,
     request_rx: Fuse<mpsc::Receiver<MessagingRequest>>,
     messaging_events_tx: MessagingEventSender,
     inbound_message_tx: mpsc::Sender<InboundMessage>,
@@ -273,7 +278,7 @@ impl MessagingProtocol {
                 }
             },
             PeerConnectWillClose(_, node_id, direction) => {
-                if let Some(sender) = self.active_queues.remove(node_id) {
+                if let Some(mut sender) = self.active_queues.remove(node_id) {
                     sender.close_channel();
                     debug!(
                         target: LOG_TARGET,
@@ -321,6 +326,7 @@ impl MessagingProtocol {
                         self.connection_manager_requester.clone(),
                         self.internal_messaging_event_tx.clone(),
                         out_msg.peer_node_id.clone(),
+                        out_msg.dial_priority,
                     )
                     .await?;
                     break entry.insert(sender);
@@ -328,17 +334,33 @@ impl MessagingProtocol {
             }
         };
 
-        match sender.send(out_msg.clone()).await {
+        match sender.try_send(out_msg) {
             Ok(_) => Ok(()),
+            Err(err) if err.is_full() => {
+                let out_msg = err.into_inner();
+                warn!(
+                    target: LOG_TARGET,
+                    "Outbound message queue for peer '{}' is full. Discarding message '{}'.",
+                    out_msg.peer_node_id.short_str(),
+                    out_msg.tag
+                );
+                self.attempts.remove(&out_msg.tag);
+                let _ = self
+                    .messaging_events_tx
+                    .send(Arc::new(MessagingEvent::SendMessageFailed(
+                        out_msg,
+                        SendFailReason::QueueFull,
+                    )));
+                Err(MessagingProtocolError::QueueFull)
+            },
             Err(err) => {
                 debug!(
                     target: LOG_TARGET,
                     "Failed to send message on channel because '{:?}'", err
                 );
+                let out_msg = err.into_inner();
                 // Lazily remove Senders from the active queue if the MessagingProtocolHandler has shut down
-                if err.is_disconnected() {
-                    self.active_queues.remove(&out_msg.peer_node_id);
-                }
+                self.active_queues.remove(&out_msg.peer_node_id);
                 Err(MessagingProtocolError::MessageSendFailed(out_msg))
             },
         }
@@ -350,11 +372,20 @@ impl MessagingProtocol {
         conn_man_requester: ConnectionManagerRequester,
         events_tx: mpsc::Sender<MessagingEvent>,
         peer_node_id: NodeId,
-    ) -> Result<mpsc::UnboundedSender<OutboundMessage>, MessagingProtocolError>
+        dial_priority: DialPriority,
+    ) -> Result<mpsc::Sender<OutboundMessage>, MessagingProtocolError>
     {
-        let (msg_tx, msg_rx) = mpsc::unbounded();
+        let (msg_tx, msg_rx) = mpsc::channel(PEER_MESSAGE_QUEUE_SIZE);
         executor.spawn(
-            OutboundMessaging::new(conn_man_requester, our_node_identity, events_tx, msg_rx, peer_node_id).run(),
+            OutboundMessaging::new(
+                conn_man_requester,
+                our_node_identity,
+                events_tx,
+                msg_rx,
+                peer_node_id,
+                dial_priority,
+            )
+            .run(),
         );
         Ok(msg_tx)
     }
@@ -26,6 +26,7 @@ use super::protocol::{
     MessagingProtocol,
     MessagingRequest,
     MESSAGING_PROTOCOL,
+    PEER_MESSAGE_QUEUE_SIZE,
 };
 use crate::{
     message::{InboundMessage, MessageExt, MessageFlags, MessageTag, OutboundMessage},
@@ -211,6 +212,24 @@ async fn send_message_dial_failed() {
     assert!(calls.iter().all(|evt| evt.starts_with("DialPeer")));
 }
 
+#[runtime::test_basic]
+async fn send_message_peer_queue_full() {
+    let (_, _, _, _, mut request_tx, _, mut event_tx, _shutdown) = spawn_messaging_protocol().await;
+
+    let node_id = node_id::random();
+    // The requests are sent without yielding, so the outbound messaging task for the peer cannot drain its queue
+    // before it is full
+    for _ in 0..PEER_MESSAGE_QUEUE_SIZE + 10 {
+        let out_msg = OutboundMessage::new(node_id.clone(), MessageFlags::NONE, TEST_MSG1);
+        request_tx.send(MessagingRequest::SendMessage(out_msg)).await.unwrap();
+    }
+
+    let event = event_tx.next().await.unwrap().unwrap();
+    unpack_enum!(MessagingEvent::SendMessageFailed(out_msg, reason) = &*event);
+    unpack_enum!(SendFailReason::QueueFull = reason);
+    assert_eq!(out_msg.peer_node_id, node_id);
+}
+
 #[runtime::test_basic]
 async fn send_message_substream_bulk_failure() {
     const NUM_MSGS: usize = 10;
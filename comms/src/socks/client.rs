@@ -37,12 +37,16 @@ pub type Result<T> = std::result::Result<T, SocksError>;
 pub enum Authentication {
     None,
     Password(String, String),
+    /// Stream isolation: a distinct username/password is generated for every destination, so that tor uses a
+    /// separate circuit for each peer. This is resolved into `Password` credentials by the `SocksTransport` before
+    /// connecting and cannot be used with the `Socks5Client` directly.
+    Isolated,
 }
 
 impl Authentication {
     fn id(&self) -> u8 {
         match self {
-            Authentication::Password(_, _) => 0x02,
+            Authentication::Password(_, _) | Authentication::Isolated => 0x02,
             Authentication::None => 0x00,
         }
     }
@@ -135,6 +139,11 @@ where TSocket: AsyncRead + AsyncWrite + Unpin
                     ));
                 }
             },
+            Authentication::Isolated => {
+                return Err(SocksError::InvalidAuthValues(
+                    "isolated authentication must be resolved to credentials for a destination".to_string(),
+                ));
+            },
         }
         Ok(())
     }
@@ -308,7 +317,7 @@ where TSocket: AsyncRead + AsyncWrite + Unpin
                 self.buf[1..3].copy_from_slice(&[1, 0x00]);
                 self.len = 3;
             },
-            Authentication::Password { .. } => {
+            Authentication::Password { .. } | Authentication::Isolated => {
                 self.buf[1..4].copy_from_slice(&[2, 0x00, 0x02]);
                 self.len = 4;
             },
@@ -335,7 +344,7 @@ where TSocket: AsyncRead + AsyncWrite + Unpin
                 self.buf[(2 + username_len)] = password_len as u8;
                 self.buf[(3 + username_len)..self.len].copy_from_slice(password_bytes);
             },
-            Authentication::None => unreachable!(),
+            Authentication::None | Authentication::Isolated => unreachable!(),
         }
     }
 
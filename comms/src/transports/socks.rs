@@ -25,9 +25,13 @@ use crate::{
     socks,
     socks::Socks5Client,
     transports::{tcp::TcpTransport, TcpSocket, Transport},
+    types::Challenge,
 };
+use digest::Digest;
 use futures::{Future, FutureExt};
+use rand::{rngs::OsRng, RngCore};
 use std::{io, time::Duration};
+use tari_crypto::tari_utilities::hex::to_hex;
 
 /// SO_KEEPALIVE setting for the SOCKS TCP connection
 const SOCKS_SO_KEEPALIVE: Duration = Duration::from_millis(1500);
@@ -42,6 +46,9 @@ pub struct SocksConfig {
 pub struct SocksTransport {
     socks_config: SocksConfig,
     tcp_transport: TcpTransport,
+    /// Random per-transport salt used to derive isolated SOCKS credentials, so that the credentials for a destination
+    /// cannot be linked across restarts
+    isolation_salt: [u8; 16],
 }
 
 impl SocksTransport {
@@ -50,24 +57,44 @@ impl SocksTransport {
         tcp_transport.set_nodelay(true);
         tcp_transport.set_keepalive(Some(SOCKS_SO_KEEPALIVE));
 
+        let mut isolation_salt = [0u8; 16];
+        OsRng.fill_bytes(&mut isolation_salt);
+
         Self {
             socks_config,
             tcp_transport,
+            isolation_salt,
+        }
+    }
+
+    /// Returns the authentication to use when connecting to `dest_addr`. `Isolated` authentication is resolved into
+    /// credentials that are unique to the destination.
+    fn authentication_for(&self, dest_addr: &Multiaddr) -> socks::Authentication {
+        match self.socks_config.authentication {
+            socks::Authentication::Isolated => {
+                let digest = Challenge::new()
+                    .chain(&self.isolation_salt)
+                    .chain(dest_addr.as_ref())
+                    .result();
+                socks::Authentication::Password(to_hex(&digest[..16]), "tari".to_string())
+            },
+            ref auth => auth.clone(),
         }
     }
 
     async fn socks_connect(
         tcp: TcpTransport,
-        socks_config: SocksConfig,
+        proxy_address: Multiaddr,
+        authentication: socks::Authentication,
         dest_addr: Multiaddr,
     ) -> io::Result<TcpSocket>
     {
         // Create a new connection to the SOCKS proxy
-        let socks_conn = tcp.dial(socks_config.proxy_address)?.await?;
+        let socks_conn = tcp.dial(proxy_address)?.await?;
         let mut client = Socks5Client::new(socks_conn);
 
         client
-            .with_authentication(socks_config.authentication)
+            .with_authentication(authentication)
             .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
 
         client
@@ -92,7 +119,14 @@ impl Transport for SocksTransport {
     }
 
     fn dial(&self, addr: Multiaddr) -> Result<Self::DialFuture, Self::Error> {
-        Ok(Self::socks_connect(self.tcp_transport.clone(), self.socks_config.clone(), addr).boxed())
+        let authentication = self.authentication_for(&addr);
+        Ok(Self::socks_connect(
+            self.tcp_transport.clone(),
+            self.socks_config.proxy_address.clone(),
+            authentication,
+            addr,
+        )
+        .boxed())
     }
 }
 
@@ -100,6 +134,7 @@ impl Transport for SocksTransport {
 mod test {
     use super::*;
     use crate::socks::Authentication;
+    use tari_test_utils::unpack_enum;
 
     #[test]
     fn new() {
@@ -112,4 +147,31 @@ mod test {
         assert_eq!(transport.socks_config.proxy_address, proxy_address);
         assert_eq!(transport.socks_config.authentication, Authentication::None);
     }
+
+    #[test]
+    fn isolated_authentication() {
+        let transport = SocksTransport::new(SocksConfig {
+            proxy_address: "/ip4/127.0.0.1/tcp/9050".parse().unwrap(),
+            authentication: Authentication::Isolated,
+        });
+        let peer1 = "/ip4/1.2.3.4/tcp/18189".parse::<Multiaddr>().unwrap();
+        let peer2 = "/ip4/5.6.7.8/tcp/18189".parse::<Multiaddr>().unwrap();
+
+        let auth1 = transport.authentication_for(&peer1);
+        unpack_enum!(Authentication::Password(username, _password) = auth1.clone());
+        assert_eq!(username.len(), 32);
+        // Credentials are stable for a destination and distinct between destinations
+        assert_eq!(transport.authentication_for(&peer1), auth1);
+        assert_ne!(transport.authentication_for(&peer2), auth1);
+
+        // A different transport instance derives different credentials
+        let other = SocksTransport::new(transport.socks_config.clone());
+        assert_ne!(other.authentication_for(&peer1), auth1);
+
+        let transport = SocksTransport::new(SocksConfig {
+            proxy_address: "/ip4/127.0.0.1/tcp/9050".parse().unwrap(),
+            authentication: Authentication::None,
+        });
+        assert_eq!(transport.authentication_for(&peer1), Authentication::None);
+    }
 }
@@ -0,0 +1,162 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+/// Source of wall-clock time. Components that make decisions based on the current time (e.g. message expiry) should
+/// take a `Clock` so that tests can control the passage of time.
+pub trait Clock: Send + Sync {
+    /// Returns the current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Shared reference to a `Clock`
+pub type ClockRef = Arc<dyn Clock>;
+
+/// A `Clock` which returns the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    /// Returns a shared reference to the system clock
+    pub fn shared() -> ClockRef {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A `Clock` which reads the system time once and then advances with the monotonic clock, so that the times it returns
+/// never jump backwards or forwards when the system time is adjusted
+#[derive(Debug, Clone, Copy)]
+pub struct MonotonicClock {
+    origin: DateTime<Utc>,
+    started: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self {
+            origin: Utc::now(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns a shared reference to a new monotonic clock
+    pub fn shared() -> ClockRef {
+        Arc::new(Self::new())
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> DateTime<Utc> {
+        ChronoDuration::from_std(self.started.elapsed())
+            .ok()
+            .and_then(|elapsed| self.origin.checked_add_signed(elapsed))
+            .unwrap_or(self.origin)
+    }
+}
+
+/// A `Clock` which only moves when it is told to. Clones share the same time.
+#[derive(Clone)]
+pub struct ManualClock {
+    now: Arc<RwLock<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    /// Create a new ManualClock which starts at the given time
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(RwLock::new(start)),
+        }
+    }
+
+    /// Create a new ManualClock which starts at the current system time
+    pub fn starting_now() -> Self {
+        Self::new(Utc::now())
+    }
+
+    /// Move the clock forward by the given duration
+    pub fn advance(&self, duration: Duration) {
+        let duration = ChronoDuration::from_std(duration).expect("duration is out of range");
+        let mut now = acquire_lock!(self.now, write);
+        *now = *now + duration;
+    }
+
+    /// Set the clock to the given time
+    pub fn set(&self, time: DateTime<Utc>) {
+        *acquire_lock!(self.now, write) = time;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *acquire_read_lock!(self.now)
+    }
+}
+
+impl fmt::Debug for ManualClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ManualClock({})", self.now())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn manual_clock_advance() {
+        let start = Utc::now();
+        let clock = ManualClock::new(start);
+        assert_eq!(clock.now(), start);
+        let shared = clock.clone();
+        shared.advance(Duration::from_secs(10));
+        assert_eq!(clock.now(), start + ChronoDuration::seconds(10));
+        clock.set(start);
+        assert_eq!(shared.now(), start);
+    }
+
+    #[test]
+    fn monotonic_clock_never_goes_backwards() {
+        let clock = MonotonicClock::new();
+        let first = clock.now();
+        assert!((first - Utc::now()).num_seconds().abs() <= 1);
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(clock.now() > first);
+    }
+}
@@ -29,9 +29,15 @@ use rand::{CryptoRng, Rng};
 use tari_crypto::{
     keys::{PublicKey, SecretKey},
     signatures::{SchnorrSignature, SchnorrSignatureError},
-    tari_utilities::message_format::MessageFormat,
+    tari_utilities::{hex::Hex, message_format::MessageFormat, ByteArray},
 };
 
+type CommsSecretKey = <CommsPublicKey as PublicKey>::K;
+
+/// Domain separation tag for out-of-band message signatures. This ensures that a signature made to prove ownership of
+/// a key can never be valid for any other signed structure.
+const MESSAGE_SIGNING_DOMAIN: &[u8] = b"com.tari.comms.signed_message.v1";
+
 pub fn sign<R, B>(
     rng: &mut R,
     secret_key: <CommsPublicKey as PublicKey>::K,
@@ -54,3 +60,81 @@ where B: AsRef<[u8]> {
     let challenge = Challenge::new().chain(body).result().to_vec();
     Ok(signature.verify_challenge(public_key, &challenge))
 }
+
+/// Sign a human-readable message with the given key to prove ownership of it out-of-band. The challenge is
+/// domain-separated from all other signatures made with comms keys.
+pub fn sign_message<R>(
+    rng: &mut R,
+    secret_key: CommsSecretKey,
+    message: &str,
+) -> Result<SchnorrSignature<CommsPublicKey, CommsSecretKey>, SchnorrSignatureError>
+where
+    R: CryptoRng + Rng,
+{
+    let nonce = CommsSecretKey::random(rng);
+    SchnorrSignature::sign(secret_key, nonce, &message_challenge(message))
+}
+
+/// Verify a signature created by `sign_message`
+pub fn verify_message(
+    public_key: &CommsPublicKey,
+    signature: &SchnorrSignature<CommsPublicKey, CommsSecretKey>,
+    message: &str,
+) -> bool
+{
+    signature.verify_challenge(public_key, &message_challenge(message))
+}
+
+/// Encode a message signature as hex, the public nonce followed by the signature scalar
+pub fn encode_message_signature(signature: &SchnorrSignature<CommsPublicKey, CommsSecretKey>) -> String {
+    format!(
+        "{}{}",
+        signature.get_public_nonce().to_hex(),
+        signature.get_signature().to_hex()
+    )
+}
+
+/// Decode a message signature produced by `encode_message_signature`. Returns None if it is not valid.
+pub fn decode_message_signature(hex: &str) -> Option<SchnorrSignature<CommsPublicKey, CommsSecretKey>> {
+    let bytes = Vec::<u8>::from_hex(hex).ok()?;
+    if bytes.len() != 64 {
+        return None;
+    }
+    let public_nonce = CommsPublicKey::from_bytes(&bytes[..32]).ok()?;
+    let signature = CommsSecretKey::from_bytes(&bytes[32..]).ok()?;
+    Some(SchnorrSignature::new(public_nonce, signature))
+}
+
+fn message_challenge(message: &str) -> Vec<u8> {
+    Challenge::new()
+        .chain(MESSAGE_SIGNING_DOMAIN)
+        .chain(message.as_bytes())
+        .result()
+        .to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn sign_and_verify_message() {
+        let (secret_key, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let signature = sign_message(&mut OsRng, secret_key, "I own this node").unwrap();
+        assert!(verify_message(&public_key, &signature, "I own this node"));
+        assert!(!verify_message(&public_key, &signature, "I own that node"));
+
+        let decoded = decode_message_signature(&encode_message_signature(&signature)).unwrap();
+        assert!(verify_message(&public_key, &decoded, "I own this node"));
+        assert!(decode_message_signature("00").is_none());
+    }
+
+    #[test]
+    fn message_signature_is_domain_separated() {
+        let (secret_key, public_key) = CommsPublicKey::random_keypair(&mut OsRng);
+        let signature = sign_message(&mut OsRng, secret_key, "body").unwrap();
+        let signature = signature.to_binary().unwrap();
+        assert!(!verify(&public_key, &signature, "body").unwrap());
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Secret
+//!
+//! `Secret` wraps a secret key, such as a spending key, a key manager seed or a node identity secret, and overwrites
+//! it with the default (all zero) key when it is dropped. This keeps secret keys from lingering in freed memory, where
+//! they could end up in a core dump or swap file.
+//!
+//! The wrapped key is never printed: the `Debug` implementation is redacted and `Secret` does not implement
+//! `Display`. `Secret` dereferences to the wrapped key so that it can be used wherever a reference to the key is
+//! expected.
+//!
+//! Note that only the copy of the key owned by the `Secret` is scrubbed. Cloning the inner key, or passing it by value
+//! to a function that takes ownership of it, creates copies that are not covered.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    ops::Deref,
+    ptr,
+    sync::atomic::{self, Ordering},
+};
+use zeroize::Zeroize;
+
+/// A secret key that is zeroized when it is dropped. The key type's `Default` value must be the all zero key.
+pub struct Secret<K: Default>(K);
+
+impl<K: Default> Secret<K> {
+    pub fn new(key: K) -> Self {
+        Secret(key)
+    }
+
+    /// Returns a reference to the wrapped key
+    pub fn reveal(&self) -> &K {
+        &self.0
+    }
+}
+
+impl<K: Default> Zeroize for Secret<K> {
+    fn zeroize(&mut self) {
+        // A volatile write cannot be optimised away even though the key is about to be freed. The replaced key is not
+        // dropped, which is fine for the plain byte keys this is used for.
+        unsafe {
+            ptr::write_volatile(&mut self.0, K::default());
+        }
+        atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl<K: Default> Drop for Secret<K> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<K: Default> Deref for Secret<K> {
+    type Target = K;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<K: Default> From<K> for Secret<K> {
+    fn from(key: K) -> Self {
+        Secret(key)
+    }
+}
+
+impl<K: Default> Default for Secret<K> {
+    fn default() -> Self {
+        Secret(K::default())
+    }
+}
+
+impl<K: Default + Clone> Clone for Secret<K> {
+    fn clone(&self) -> Self {
+        Secret(self.0.clone())
+    }
+}
+
+impl<K: Default + PartialEq> PartialEq for Secret<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K: Default + PartialEq> PartialEq<K> for Secret<K> {
+    fn eq(&self, other: &K) -> bool {
+        &self.0 == other
+    }
+}
+
+impl<K: Default + Eq> Eq for Secret<K> {}
+
+impl<K: Default> fmt::Debug for Secret<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret(<redacted>)")
+    }
+}
+
+impl<K: Default + Serialize> Serialize for Secret<K> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, K: Default + Deserialize<'de>> Deserialize<'de> for Secret<K> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        K::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn zeroize() {
+        let mut secret = Secret::new([7u8; 32]);
+        assert_eq!(secret, [7u8; 32]);
+        secret.zeroize();
+        assert_eq!(*secret.reveal(), [0u8; 32]);
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let secret = Secret::new(123_456u64);
+        let output = format!("{:?}", secret);
+        assert_eq!(output, "Secret(<redacted>)");
+        assert!(!output.contains("123456"));
+    }
+
+    #[test]
+    fn serde_is_transparent() {
+        let secret = Secret::new([1u8, 2, 3]);
+        let json = serde_json::to_string(&secret).unwrap();
+        assert_eq!(json, "[1,2,3]");
+        let secret = serde_json::from_str::<Secret<[u8; 3]>>(&json).unwrap();
+        assert_eq!(secret, [1u8, 2, 3]);
+    }
+}
@@ -22,68 +22,242 @@
 
 use futures::{
     channel::oneshot,
-    future::{Fuse, Shared},
+    future::{self, Fuse, Shared},
     FutureExt,
 };
+use std::{
+    fmt,
+    mem,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+use tokio::time;
 
 /// Receiver end of a shutdown signal. Once received the consumer should shut down.
 pub type ShutdownSignal = Shared<Fuse<oneshot::Receiver<()>>>;
 
+/// Named phases of a shutdown. Phases are always triggered in order, and triggering a phase also triggers every phase
+/// before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShutdownPhase {
+    /// Stop accepting new work. The signal returned from `Shutdown::to_signal` resolves in this phase.
+    StopAcceptingWork = 0,
+    /// Complete or abandon work that is in progress
+    Drain = 1,
+    /// Flush any in-memory state to storage
+    FlushStorage = 2,
+    /// Release all remaining resources, including storage
+    Terminate = 3,
+}
+
+impl ShutdownPhase {
+    /// All shutdown phases in the order in which they are triggered
+    pub const ALL: [ShutdownPhase; 4] = [
+        ShutdownPhase::StopAcceptingWork,
+        ShutdownPhase::Drain,
+        ShutdownPhase::FlushStorage,
+        ShutdownPhase::Terminate,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for ShutdownPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+struct PhaseState {
+    trigger: Option<oneshot::Sender<()>>,
+    signal: ShutdownSignal,
+    guards: Vec<(String, oneshot::Receiver<()>)>,
+}
+
+impl PhaseState {
+    fn new() -> Self {
+        let (tx, rx) = oneshot::channel();
+        Self {
+            trigger: Some(tx),
+            signal: rx.fuse().shared(),
+            guards: Vec::new(),
+        }
+    }
+}
+
+/// Phase state shared between a `Shutdown`, its listeners and its parent
+struct Phases {
+    phases: Vec<PhaseState>,
+    children: Vec<Arc<Mutex<Phases>>>,
+    on_triggered: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl Phases {
+    fn new() -> Self {
+        Self {
+            phases: ShutdownPhase::ALL.iter().map(|_| PhaseState::new()).collect(),
+            children: Vec::new(),
+            on_triggered: None,
+        }
+    }
+
+    fn is_triggered(&self, phase: ShutdownPhase) -> bool {
+        self.phases[phase.index()].trigger.is_none()
+    }
+
+    fn trigger(&mut self, phase: ShutdownPhase) -> Result<(), ()> {
+        let mut result = Ok(());
+        for state in &mut self.phases[..=phase.index()] {
+            if let Some(trigger) = state.trigger.take() {
+                result = result.and(trigger.send(()));
+            }
+        }
+
+        if let Some(on_triggered) = self.on_triggered.take() {
+            on_triggered();
+        }
+
+        for child in &self.children {
+            result = result.and(lock(child).trigger(phase));
+        }
+
+        result
+    }
+
+    fn take_guards(&mut self, phase: ShutdownPhase) -> Vec<(String, oneshot::Receiver<()>)> {
+        let mut guards = mem::take(&mut self.phases[phase.index()].guards);
+        for child in &self.children {
+            guards.extend(lock(child).take_guards(phase));
+        }
+        guards
+    }
+}
+
+fn lock(phases: &Mutex<Phases>) -> MutexGuard<'_, Phases> {
+    // The phase state is always left consistent, so a poisoned lock can be safely recovered
+    phases.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 /// Trigger for shutdowns.
 ///
 /// Use `to_signal` to create a future which will resolve when `Shutdown` is triggered.
 /// Use `trigger` to signal. All signals will resolve.
 ///
+/// A shutdown proceeds through the `ShutdownPhase`s in order. `trigger` triggers all phases at once, while
+/// `trigger_in_phases` triggers each phase in turn and waits for all `PhaseGuard`s held for that phase to be released
+/// before moving on to the next. For example, the mempool can hold a `FlushStorage` guard to ensure that it has
+/// been flushed before the database is closed in the `Terminate` phase.
+///
+/// Child shutdowns created with `new_child` are triggered along with their parent, but may also be triggered on their
+/// own.
+///
 /// _Note_: This will trigger when dropped, so the `Shutdown` instance should be held as
 /// long as required by the application.
 pub struct Shutdown {
-    trigger: Option<oneshot::Sender<()>>,
-    signal: ShutdownSignal,
-    on_triggered: Option<Box<dyn FnOnce() + Send + Sync>>,
+    phases: Arc<Mutex<Phases>>,
 }
 
 impl Shutdown {
     /// Create a new Shutdown
     pub fn new() -> Self {
-        let (tx, rx) = oneshot::channel();
         Self {
-            trigger: Some(tx),
-            signal: rx.fuse().shared(),
-            on_triggered: None,
+            phases: Arc::new(Mutex::new(Phases::new())),
         }
     }
 
+    /// Create a child Shutdown which is triggered whenever this Shutdown is triggered. Triggering the child does not
+    /// trigger this Shutdown.
+    pub fn new_child(&mut self) -> Shutdown {
+        let child = Shutdown::new();
+        lock(&self.phases).children.push(Arc::clone(&child.phases));
+        child
+    }
+
     /// Set the on_triggered callback
     pub fn on_triggered<F>(&mut self, on_trigger: F) -> &mut Self
     where F: FnOnce() + Send + Sync + 'static {
-        self.on_triggered = Some(Box::new(on_trigger));
+        lock(&self.phases).on_triggered = Some(Box::new(on_trigger));
         self
     }
 
     /// Convert this into a ShutdownSignal without consuming the
     /// struct.
     pub fn to_signal(&self) -> ShutdownSignal {
-        self.signal.clone()
+        self.to_listener().signal(ShutdownPhase::StopAcceptingWork)
+    }
+
+    /// Returns a `ShutdownListener` which can be used to wait for, or hold up, particular shutdown phases
+    pub fn to_listener(&self) -> ShutdownListener {
+        ShutdownListener {
+            phases: Arc::clone(&self.phases),
+        }
     }
 
     /// Trigger any listening signals
     pub fn trigger(&mut self) -> Result<(), ()> {
-        match self.trigger.take() {
-            Some(trigger) => {
-                trigger.send(())?;
+        self.trigger_phase(ShutdownPhase::Terminate)
+    }
+
+    /// Trigger the given phase and all phases before it, without waiting for any `PhaseGuard`s to be released
+    pub fn trigger_phase(&mut self, phase: ShutdownPhase) -> Result<(), ()> {
+        lock(&self.phases).trigger(phase)
+    }
 
-                if let Some(on_triggered) = self.on_triggered.take() {
-                    on_triggered();
-                }
+    /// Trigger each phase in order. Before triggering the next phase, this waits for all `PhaseGuard`s held for the
+    /// current phase (including those held on child shutdowns) to be released.
+    pub async fn trigger_in_phases(&mut self) -> Result<(), ()> {
+        self.run_phases(None).await.map(|_| ())
+    }
 
-                Ok(())
-            },
-            None => Ok(()),
+    /// Trigger each phase in order as with `trigger_in_phases`, but stop waiting for `PhaseGuard`s once the timeout
+    /// has elapsed. Any remaining phases are triggered immediately after the timeout. The returned report lists the
+    /// names of the guards which were not released in time.
+    pub async fn trigger_with_timeout(&mut self, timeout: Duration) -> Result<ShutdownReport, ()> {
+        self.run_phases(Some(Instant::now() + timeout)).await
+    }
+
+    async fn run_phases(&mut self, deadline: Option<Instant>) -> Result<ShutdownReport, ()> {
+        let mut report = ShutdownReport::default();
+        for phase in ShutdownPhase::ALL.iter() {
+            let mut guards = {
+                let mut phases = lock(&self.phases);
+                phases.trigger(*phase)?;
+                phases.take_guards(*phase)
+            };
+
+            // A guard resolves once it has been released, whether or not it was explicitly released
+            let released = future::join_all(guards.iter_mut().map(|(_, guard)| guard));
+            match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    let _ = time::timeout(remaining, released).await;
+                },
+                None => {
+                    released.await;
+                },
+            }
+
+            report.stragglers.extend(
+                guards
+                    .into_iter()
+                    .filter_map(|(name, mut guard)| match guard.try_recv() {
+                        Ok(None) => Some((*phase, name)),
+                        _ => None,
+                    }),
+            );
         }
+        Ok(report)
     }
 
     pub fn is_triggered(&self) -> bool {
-        self.trigger.is_none()
+        self.is_phase_triggered(ShutdownPhase::StopAcceptingWork)
+    }
+
+    /// Returns true if the given phase has been triggered, otherwise false
+    pub fn is_phase_triggered(&self, phase: ShutdownPhase) -> bool {
+        lock(&self.phases).is_triggered(phase)
     }
 }
 
@@ -99,6 +273,84 @@ impl Default for Shutdown {
     }
 }
 
+/// Listener for the phases of a `Shutdown`. This can be cloned and moved into the components that need to take action
+/// in a particular phase.
+#[derive(Clone)]
+pub struct ShutdownListener {
+    phases: Arc<Mutex<Phases>>,
+}
+
+impl ShutdownListener {
+    /// Returns true if the `Shutdown` has been triggered, otherwise false
+    pub fn is_triggered(&self) -> bool {
+        lock(&self.phases).is_triggered(ShutdownPhase::StopAcceptingWork)
+    }
+
+    /// Returns a signal which resolves once the given phase has been triggered
+    pub fn signal(&self, phase: ShutdownPhase) -> ShutdownSignal {
+        lock(&self.phases).phases[phase.index()].signal.clone()
+    }
+
+    /// Wait until the given phase has been triggered
+    pub async fn wait_for_phase(&self, phase: ShutdownPhase) {
+        // The signal only resolves with an error if the Shutdown was dropped, in which case it was also triggered
+        let _ = self.signal(phase).await;
+    }
+
+    /// Returns a guard which holds up `Shutdown::trigger_in_phases` from proceeding past the given phase until it is
+    /// released. The guard must be acquired before the phase is triggered.
+    pub fn guard(&self, phase: ShutdownPhase) -> PhaseGuard {
+        self.named_guard(phase, "<unnamed>")
+    }
+
+    /// Returns a guard as with `guard`. The name is included in the `ShutdownReport` if the guard is not released
+    /// before the timeout given to `Shutdown::trigger_with_timeout`.
+    pub fn named_guard<T: Into<String>>(&self, phase: ShutdownPhase, name: T) -> PhaseGuard {
+        let (tx, rx) = oneshot::channel();
+        lock(&self.phases).phases[phase.index()].guards.push((name.into(), rx));
+        PhaseGuard { _release: tx }
+    }
+}
+
+/// Report returned from `Shutdown::trigger_with_timeout`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// The phase and name of each `PhaseGuard` which was not released in time
+    pub stragglers: Vec<(ShutdownPhase, String)>,
+}
+
+impl ShutdownReport {
+    /// Returns true if all guards were released in time, otherwise false
+    pub fn is_complete(&self) -> bool {
+        self.stragglers.is_empty()
+    }
+}
+
+impl fmt::Display for ShutdownReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_complete() {
+            return write!(f, "Shutdown completed in time");
+        }
+        write!(f, "Shutdown did not complete in time. Waiting on: ")?;
+        let stragglers = self
+            .stragglers
+            .iter()
+            .map(|(phase, name)| format!("{} ({})", name, phase))
+            .collect::<Vec<_>>();
+        write!(f, "{}", stragglers.join(", "))
+    }
+}
+
+/// Holds up a phased shutdown until dropped. See `ShutdownListener::guard`.
+pub struct PhaseGuard {
+    _release: oneshot::Sender<()>,
+}
+
+impl PhaseGuard {
+    /// Release the guard, allowing the shutdown to proceed to the next phase
+    pub fn release(self) {}
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -165,4 +417,86 @@ mod test {
         shutdown.trigger().unwrap();
         assert_eq!(spy.load(Ordering::SeqCst), true);
     }
+
+    #[test]
+    fn trigger_phase() {
+        let mut shutdown = Shutdown::new();
+        shutdown.trigger_phase(ShutdownPhase::Drain).unwrap();
+        assert_eq!(shutdown.is_triggered(), true);
+        assert_eq!(shutdown.is_phase_triggered(ShutdownPhase::StopAcceptingWork), true);
+        assert_eq!(shutdown.is_phase_triggered(ShutdownPhase::Drain), true);
+        assert_eq!(shutdown.is_phase_triggered(ShutdownPhase::FlushStorage), false);
+        assert_eq!(shutdown.is_phase_triggered(ShutdownPhase::Terminate), false);
+        assert!(shutdown.to_signal().now_or_never().is_some());
+    }
+
+    #[test]
+    fn child() {
+        let mut shutdown = Shutdown::new();
+        let mut child1 = shutdown.new_child();
+        let child2 = shutdown.new_child();
+        let listener1 = child1.to_listener();
+
+        child1.trigger().unwrap();
+        assert_eq!(child1.is_triggered(), true);
+        assert_eq!(listener1.is_triggered(), true);
+        assert_eq!(child2.is_triggered(), false);
+        assert_eq!(shutdown.is_triggered(), false);
+        assert_eq!(shutdown.to_listener().is_triggered(), false);
+
+        shutdown.trigger_phase(ShutdownPhase::FlushStorage).unwrap();
+        assert_eq!(child2.is_phase_triggered(ShutdownPhase::FlushStorage), true);
+        assert_eq!(child2.is_phase_triggered(ShutdownPhase::Terminate), false);
+    }
+
+    #[test]
+    fn trigger_in_phases() {
+        let mut rt = Runtime::new().unwrap();
+        let mut shutdown = Shutdown::new();
+        let child = shutdown.new_child();
+        let flushed = Arc::new(AtomicBool::new(false));
+
+        let listener = child.to_listener();
+        let guard = listener.guard(ShutdownPhase::FlushStorage);
+        let flushed_clone = Arc::clone(&flushed);
+        rt.spawn(async move {
+            listener.wait_for_phase(ShutdownPhase::FlushStorage).await;
+            flushed_clone.store(true, Ordering::SeqCst);
+            guard.release();
+        });
+
+        let listener = shutdown.to_listener();
+        let terminated = rt.spawn(async move {
+            listener.wait_for_phase(ShutdownPhase::Terminate).await;
+            flushed.load(Ordering::SeqCst)
+        });
+
+        rt.block_on(shutdown.trigger_in_phases()).unwrap();
+        // Storage was flushed before the terminate phase was triggered
+        assert_eq!(rt.block_on(terminated).unwrap(), true);
+    }
+
+    #[test]
+    fn trigger_with_timeout() {
+        let mut rt = Runtime::new().unwrap();
+        let mut shutdown = Shutdown::new();
+        let listener = shutdown.to_listener();
+        let _stuck_guard = listener.named_guard(ShutdownPhase::Drain, "stuck service");
+        let guard = listener.named_guard(ShutdownPhase::Drain, "well-behaved service");
+        rt.spawn(async move {
+            listener.wait_for_phase(ShutdownPhase::StopAcceptingWork).await;
+            guard.release();
+        });
+
+        let report = rt
+            .block_on(shutdown.trigger_with_timeout(Duration::from_millis(100)))
+            .unwrap();
+        assert_eq!(report.is_complete(), false);
+        assert_eq!(report.stragglers, vec![(
+            ShutdownPhase::Drain,
+            "stuck service".to_string()
+        )]);
+        // The remaining phases are triggered regardless
+        assert_eq!(shutdown.is_phase_triggered(ShutdownPhase::Terminate), true);
+    }
 }
@@ -15,3 +15,4 @@ pub mod random;
 #[macro_use]
 pub mod streams;
 pub mod runtime;
+pub mod time;
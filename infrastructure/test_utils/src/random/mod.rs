@@ -20,8 +20,37 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use rand::{distributions::Alphanumeric, thread_rng, Rng};
-use std::iter;
+use rand::{distributions::Alphanumeric, rngs::StdRng, thread_rng, Rng, SeedableRng};
+use std::{cell::RefCell, env, iter};
+
+/// Environment variable which, if set, is used as the seed for [with_test_rng]
+pub const TEST_SEED_ENV_VAR: &str = "TARI_TEST_SEED";
+
+thread_local! {
+    static TEST_RNG: RefCell<StdRng> = RefCell::new(seeded_rng(test_seed()));
+}
+
+/// Returns a deterministic RNG for the given seed
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Returns the seed given in the `TARI_TEST_SEED` environment variable, or a random seed if it is not set. The seed
+/// is printed so that a failing test can be reproduced.
+pub fn test_seed() -> u64 {
+    let seed = env::var(TEST_SEED_ENV_VAR)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| thread_rng().gen());
+    eprintln!("Using test RNG seed {} (set {} to reproduce)", seed, TEST_SEED_ENV_VAR);
+    seed
+}
+
+/// Calls the given function with this thread's test RNG. The RNG is seeded once per thread using [test_seed].
+pub fn with_test_rng<F, T>(f: F) -> T
+where F: FnOnce(&mut StdRng) -> T {
+    TEST_RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
 
 /// Generate a random alphanumeric string of the given size using the default `ThreadRng`.
 pub fn string(len: usize) -> String {
@@ -47,4 +76,12 @@ mod test {
         assert_ne!(sample, super::string(8));
         assert_eq!(sample.len(), 8);
     }
+
+    #[test]
+    fn seeded_rng() {
+        use rand::RngCore;
+        let mut rng1 = super::seeded_rng(123);
+        let mut rng2 = super::seeded_rng(123);
+        assert_eq!(rng1.next_u64(), rng2.next_u64());
+    }
 }
@@ -0,0 +1,63 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Helpers for tests which depend on the passage of time. Instead of sleeping, tests can run on a runtime with a
+//! paused clock and advance virtual time explicitly, so that timeouts fire deterministically.
+
+use std::time::Duration;
+use tokio::{runtime, runtime::Runtime, time};
+
+/// Create a single-threaded runtime whose timer is paused. Timers on this runtime only fire when virtual time is
+/// moved forward using [advance].
+pub fn create_paused_runtime() -> Runtime {
+    let rt = runtime::Builder::new()
+        .basic_scheduler()
+        .enable_time()
+        .build()
+        .expect("Could not create runtime");
+    rt.enter(time::pause);
+    rt
+}
+
+/// Move virtual time forward by the given duration, firing any timers which become due. This must be called within
+/// a paused runtime created by [create_paused_runtime].
+pub async fn advance(duration: Duration) {
+    time::advance(duration).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::FutureExt;
+
+    #[test]
+    fn advance_fires_timer() {
+        let mut rt = create_paused_runtime();
+        rt.block_on(async {
+            let mut delay = time::delay_for(Duration::from_secs(60 * 60)).fuse();
+            advance(Duration::from_secs(60 * 60 - 1)).await;
+            assert!((&mut delay).now_or_never().is_none());
+            advance(Duration::from_secs(1)).await;
+            assert!((&mut delay).now_or_never().is_some());
+        });
+    }
+}